@@ -0,0 +1,23 @@
+//! Benchmarks each stage of processing the documents of a corpus
+//!
+//! The corpus manifest is given by the `PDF_BENCH_CORPUS` environment variable:
+//!
+//! ```text
+//! PDF_BENCH_CORPUS=path/to/corpus.txt cargo bench --features bench
+//! ```
+
+use criterion::Criterion;
+use pdf::bench::{criterion_benchmarks, Corpus};
+
+fn main() {
+    let manifest = std::env::var("PDF_BENCH_CORPUS")
+        .expect("PDF_BENCH_CORPUS must be set to the path of a corpus manifest");
+
+    let corpus = Corpus::load(manifest).unwrap();
+
+    let mut criterion = Criterion::default().configure_from_args();
+
+    criterion_benchmarks(&mut criterion, &corpus).unwrap();
+
+    criterion.final_summary();
+}
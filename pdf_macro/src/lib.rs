@@ -1,10 +1,12 @@
 mod pdf_enum;
 mod pdf_obj;
+mod to_obj;
 mod util;
 
 use pdf_enum::pdf_enum_inner;
 use pdf_obj::pdf_obj_inner;
 use proc_macro::TokenStream;
+use to_obj::to_obj_inner;
 
 #[proc_macro_attribute]
 pub fn pdf_enum(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -15,3 +17,8 @@ pub fn pdf_enum(attr: TokenStream, item: TokenStream) -> TokenStream {
 pub fn pdf_obj(item: TokenStream) -> TokenStream {
     pdf_obj_inner(item)
 }
+
+#[proc_macro_derive(ToObj, attributes(field, obj_type))]
+pub fn to_obj(item: TokenStream) -> TokenStream {
+    to_obj_inner(item)
+}
@@ -65,7 +65,7 @@ pub fn pdf_enum_inner(attr: TokenStream, item: TokenStream) -> TokenStream {
     let field_attrs = variants.iter().map(|v| &v.attrs).collect::<Vec<_>>();
     let field_names = variants.iter().map(|v| &v.name).collect::<Vec<_>>();
     let field_values = variants.iter().map(|v| &v.value).collect::<Vec<_>>();
-    
+
     // temporary method impl during transition to proc macros
     let old_impl = if object_type != Ident::new("Integer", Span::call_site()) {
         quote!(impl #name {
@@ -78,6 +78,12 @@ pub fn pdf_enum_inner(attr: TokenStream, item: TokenStream) -> TokenStream {
                     })
                 })
             }
+
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    #(Self::#field_names => #field_values),*
+                }
+            }
         })
     } else {
         quote!(impl #name {
@@ -90,9 +96,21 @@ pub fn pdf_enum_inner(attr: TokenStream, item: TokenStream) -> TokenStream {
                     })
                 })
             }
+
+            pub fn as_integer(&self) -> i32 {
+                match self {
+                    #(Self::#field_names => #field_values),*
+                }
+            }
         })
     };
 
+    let to_obj_value = if object_type != Ident::new("Integer", Span::call_site()) {
+        quote!(crate::Object::Name(self.as_str().to_owned()))
+    } else {
+        quote!(crate::Object::Integer(self.as_integer()))
+    };
+
     let field = if object_type != Ident::new("Integer", Span::call_site()) {
         quote!(
             #(
@@ -126,6 +144,12 @@ pub fn pdf_enum_inner(attr: TokenStream, item: TokenStream) -> TokenStream {
             }
         }
 
+        impl<'a> crate::ToObj<'a> for #name {
+            fn to_obj(&self) -> crate::PdfResult<crate::Object<'a>> {
+                Ok(#to_obj_value)
+            }
+        }
+
         #old_impl        
     )
     .into()
@@ -38,16 +38,16 @@ fn field_getter(name: &Ident, ty: &Type, key: &LitStr, default: &Option<Expr>) -
     }
 }
 
-struct PdfDictObjField {
-    name: Ident,
-    ty: Type,
-    key: LitStr,
-    default: Option<Expr>,
+pub(crate) struct PdfDictObjField {
+    pub name: Ident,
+    pub ty: Type,
+    pub key: LitStr,
+    pub default: Option<Expr>,
 }
 
-struct HelperArgs {
-    key: LitStr,
-    default: Option<Expr>,
+pub(crate) struct HelperArgs {
+    pub key: LitStr,
+    pub default: Option<Expr>,
 }
 
 impl Parse for HelperArgs {
@@ -67,16 +67,23 @@ impl Parse for HelperArgs {
     }
 }
 
+/// The Type and Subtype given by the `obj_type` attribute, if any
+pub(crate) fn obj_type_args(input: &DeriveInput) -> Option<HelperArgs> {
+    Some(
+        input
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("obj_type"))?
+            .parse_args()
+            .unwrap(),
+    )
+}
+
 fn obj_type(input: &DeriveInput) -> Option<(TokenStream2, TokenStream2)> {
     let name = &input.ident;
     let generics = &input.generics;
 
-    let obj_type_args: HelperArgs = input
-        .attrs
-        .iter()
-        .find(|attr| attr.path().is_ident("obj_type"))?
-        .parse_args()
-        .unwrap();
+    let obj_type_args = obj_type_args(input)?;
 
     let obj_type_value: LitStr = obj_type_args.key;
     let obj_subtype_value = obj_type_args.default;
@@ -110,17 +117,10 @@ fn obj_type(input: &DeriveInput) -> Option<(TokenStream2, TokenStream2)> {
     Some((obj_type, obj_type_impl))
 }
 
-pub fn pdf_obj_inner(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as DeriveInput);
-
-    let (obj_type, obj_type_impl) = obj_type(&input)
-        .map(|(ty, imp)| (Some(ty), Some(imp)))
-        .unwrap_or((None, None));
-
-    let name = input.ident;
-    let mut generics = input.generics;
-
-    let fields = match input.data {
+/// The fields of a struct, with the keys and defaults given by their `field`
+/// attributes
+pub(crate) fn dict_fields(data: Data) -> Vec<PdfDictObjField> {
+    match data {
         Data::Struct(data_struct) => data_struct.fields.into_iter().map(|field| {
             let name = field.ident.unwrap();
             let ty = field.ty;
@@ -150,7 +150,20 @@ pub fn pdf_obj_inner(input: TokenStream) -> TokenStream {
         }),
         _ => todo!(),
     }
-    .collect::<Vec<PdfDictObjField>>();
+    .collect()
+}
+
+pub fn pdf_obj_inner(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let (obj_type, obj_type_impl) = obj_type(&input)
+        .map(|(ty, imp)| (Some(ty), Some(imp)))
+        .unwrap_or((None, None));
+
+    let name = input.ident;
+    let mut generics = input.generics;
+
+    let fields = dict_fields(input.data);
 
     let mut field_name = fields.iter().map(|v| &v.name).collect::<Vec<_>>();
     let field_type = fields.iter().map(|v| &v.ty).collect::<Vec<_>>();
@@ -0,0 +1,122 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, DeriveInput, Expr, LifetimeParam, LitStr, Type, TypePath,
+};
+
+use crate::{
+    pdf_obj::{dict_fields, obj_type_args},
+    util::extract_type_from_option,
+};
+
+fn field_setter(name: &Ident, ty: &Type, key: &LitStr, default: &Option<Expr>) -> TokenStream2 {
+    match ty {
+        Type::Path(TypePath { path, .. }) if path.segments.last().unwrap().ident == "Option" => {
+            let generic = extract_type_from_option(ty).unwrap();
+            quote!(
+                if let Some(value) = &self.#name {
+                    dict.insert(#key, <#generic as crate::ToObj<'to_obj>>::to_obj(value)?);
+                }
+            )
+        }
+        _ => {
+            if let Some(default) = default {
+                // values equal to the default are left out, as they would be
+                // read back the same either way
+                quote!(
+                    let value = <#ty as crate::ToObj<'to_obj>>::to_obj(&self.#name)?;
+
+                    if value != <#ty as crate::ToObj<'to_obj>>::to_obj(&(#default))? {
+                        dict.insert(#key, value);
+                    }
+                )
+            } else {
+                quote!(
+                    dict.insert(#key, <#ty as crate::ToObj<'to_obj>>::to_obj(&self.#name)?);
+                )
+            }
+        }
+    }
+}
+
+pub fn to_obj_inner(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let obj_type = obj_type_args(&input).map(|args| {
+        let obj_type_value = args.key;
+        let mut obj_type = quote!(
+            dict.insert("Type", crate::Object::Name(#obj_type_value.to_owned()));
+        );
+
+        if let Some(subtype) = args.default {
+            obj_type.extend(quote!(
+                dict.insert("Subtype", crate::Object::Name((#subtype).to_owned()));
+            ));
+        }
+
+        obj_type
+    });
+
+    let name = input.ident;
+    let mut generics = input.generics;
+
+    let fields = dict_fields(input.data);
+
+    let has_other = fields.iter().any(|field| field.name == "other");
+    let has_stream = fields.iter().any(|field| field.name == "stream");
+
+    let setters = fields
+        .iter()
+        .filter(|field| field.name != "other" && field.name != "stream")
+        .map(|field| field_setter(&field.name, &field.ty, &field.key, &field.default));
+
+    let other = has_other.then(|| {
+        quote!(for (key, value) in self.other.iter() {
+            dict.insert(key, value.clone());
+        })
+    });
+
+    // keys which were not read remain in the dictionary of the stream
+    let return_val = if has_stream {
+        quote!(
+            let mut stream = self.stream.clone();
+
+            for (key, value) in dict.entries() {
+                stream.dict.other.insert(key, value);
+            }
+
+            Ok(crate::Object::Stream(stream))
+        )
+    } else {
+        quote!(Ok(crate::Object::Dictionary(dict)))
+    };
+
+    let mut to_obj_lt: LifetimeParam = parse_quote!('to_obj);
+    for lt in generics.lifetimes_mut() {
+        lt.bounds.insert(0, parse_quote!('to_obj));
+        to_obj_lt.bounds.insert(0, lt.lifetime.clone());
+    }
+    let type_params = generics.type_params();
+    let lifetimes = generics.lifetimes();
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote!(
+        impl<#(#lifetimes,)* #(#type_params,)* #to_obj_lt> crate::ToObj<'to_obj> for #name #ty_generics #where_clause {
+            fn to_obj(&self) -> crate::PdfResult<crate::Object<'to_obj>> {
+                let mut dict = crate::objects::Dictionary::empty();
+
+                #obj_type
+
+                #(
+                    #setters
+                )*
+
+                #other
+
+                #return_val
+            }
+        }
+    )
+    .into()
+}
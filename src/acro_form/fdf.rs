@@ -0,0 +1,390 @@
+/*!
+ * The Forms Data Format (FDF), used to export the values of the fields of an
+ * interactive form to a separate file and to import them back into the same
+ * or another document
+ */
+
+use std::collections::HashMap;
+
+use crate::{
+    error::PdfResult,
+    objects::{decode_text_string, encode_text_string, Object, Reference},
+    tokenizer::Tokenizer,
+    Resolve,
+};
+
+use super::{field::field_value, Field, FieldType, FieldValue, FormFill};
+
+/// The field data of an FDF file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fdf {
+    /// The root fields, each of which may have descendants with their own
+    /// values
+    pub fields: Vec<FdfField>,
+}
+
+/// A field of an FDF file, which mirrors the hierarchy of the fields of the
+/// interactive form it was exported from
+#[derive(Debug, Clone, PartialEq)]
+pub struct FdfField {
+    /// The partial field name
+    pub name: String,
+
+    pub value: Option<FieldValue>,
+
+    pub kids: Vec<FdfField>,
+}
+
+impl Fdf {
+    /// The field/value tree of the given terminal fields, rebuilt from their
+    /// fully qualified names
+    ///
+    /// Fields which shall not be exported by a submit-form action and
+    /// pushbuttons, which have no value, are left out. The values of signature
+    /// fields refer to objects in the original document, so they are left out
+    /// too.
+    pub(crate) fn from_fields(fields: &[Field]) -> Self {
+        let mut roots = Vec::new();
+
+        for field in fields {
+            if field.is_no_export() || field.is_push_button() {
+                continue;
+            }
+
+            let value = match &field.value {
+                Some(FieldValue::Signature(..)) => None,
+                value => value.clone(),
+            };
+
+            let mut nodes = &mut roots;
+            let mut parts = field.name.split('.').peekable();
+
+            while let Some(part) = parts.next() {
+                let idx = match nodes.iter().position(|node: &FdfField| node.name == part) {
+                    Some(idx) => idx,
+                    None => {
+                        nodes.push(FdfField {
+                            name: part.to_owned(),
+                            value: None,
+                            kids: Vec::new(),
+                        });
+
+                        nodes.len() - 1
+                    }
+                };
+
+                if parts.peek().is_none() {
+                    nodes[idx].value = value;
+                    break;
+                }
+
+                nodes = &mut nodes[idx].kids;
+            }
+        }
+
+        Self { fields: roots }
+    }
+
+    /// Parse an FDF file
+    ///
+    /// FDF files rarely have a cross-reference table, so their objects are
+    /// found by scanning for `obj` keywords.
+    pub fn parse(bytes: &[u8]) -> PdfResult<Self> {
+        if !bytes.starts_with(b"%FDF-") {
+            anyhow::bail!("expected FDF header");
+        }
+
+        let mut objects = HashMap::new();
+        let mut tokenizer = Tokenizer::new(bytes);
+
+        while let Some(object) = tokenizer.next_indirect_object()? {
+            objects.insert(object.reference, object.object);
+        }
+
+        let trailer_offset = bytes
+            .windows(b"trailer".len())
+            .rposition(|window| window == b"trailer")
+            .ok_or_else(|| anyhow::anyhow!("FDF file has no trailer"))?;
+
+        let mut tokenizer = Tokenizer::new(bytes);
+        tokenizer.seek(trailer_offset + b"trailer".len());
+
+        let trailer = match tokenizer.next_object()? {
+            Some(trailer) => trailer.value,
+            None => anyhow::bail!("FDF file has no trailer dictionary"),
+        };
+
+        let resolver = &mut FdfObjects { objects };
+
+        let mut trailer = resolver.assert_dict(trailer)?;
+        let mut root = trailer.expect_dict("Root", resolver)?;
+        let mut fdf = root.expect_dict("FDF", resolver)?;
+
+        let fields = fdf
+            .get_arr("Fields", resolver)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|obj| FdfField::read(obj, resolver))
+            .collect::<PdfResult<Vec<FdfField>>>()?;
+
+        Ok(Self { fields })
+    }
+
+    /// Serialize as an FDF file
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out =
+            String::from("%FDF-1.2\n%\u{e2}\u{e3}\u{cf}\u{d3}\n1 0 obj\n<< /FDF << /Fields [");
+
+        for field in &self.fields {
+            out.push(' ');
+            field.write(&mut out);
+        }
+
+        out.push_str(" ] >> >>\nendobj\ntrailer\n<< /Root 1 0 R >>\n%%EOF\n");
+
+        // Strings hold each byte as a `char`
+        out.chars().map(|c| c as u8).collect()
+    }
+
+    /// The value of each field, keyed by its fully qualified name
+    pub fn values(&self) -> Vec<(String, FieldValue)> {
+        fn collect(
+            fields: &[FdfField],
+            parent: Option<&str>,
+            values: &mut Vec<(String, FieldValue)>,
+        ) {
+            for field in fields {
+                let name = match parent {
+                    Some(parent) => format!("{}.{}", parent, field.name),
+                    None => field.name.clone(),
+                };
+
+                if let Some(value) = &field.value {
+                    values.push((name.clone(), value.clone()));
+                }
+
+                collect(&field.kids, Some(&name), values);
+            }
+        }
+
+        let mut values = Vec::new();
+        collect(&self.fields, None, &mut values);
+        values
+    }
+
+    /// The values of this file as a [`FormFill`] for the given fields, which
+    /// determine how each value is filled in
+    pub(crate) fn form_fill(&self, fields: &[Field]) -> PdfResult<FormFill> {
+        let mut fill = FormFill::new();
+
+        for (name, value) in self.values() {
+            let field = fields
+                .iter()
+                .find(|field| field.name == name)
+                .ok_or_else(|| anyhow::anyhow!("no field named {:?}", name))?;
+
+            fill = match (field.field_type, value) {
+                // XFDF does not distinguish names from text
+                (Some(FieldType::Button), FieldValue::Name(state) | FieldValue::Text(state))
+                    if field.is_radio() =>
+                {
+                    if state == "Off" {
+                        fill
+                    } else {
+                        fill.select(name, state)
+                    }
+                }
+                (Some(FieldType::Button), FieldValue::Name(state) | FieldValue::Text(state)) => {
+                    fill.check(name, state != "Off")
+                }
+                (Some(FieldType::Text), FieldValue::Text(text)) => fill.text(name, text),
+                (Some(FieldType::Choice), FieldValue::Text(item)) => fill.select(name, item),
+                (Some(FieldType::Choice), FieldValue::Array(items)) => fill.select_all(name, items),
+                (_, value) => anyhow::bail!("cannot import {:?} into field {:?}", value, name),
+            };
+        }
+
+        Ok(fill)
+    }
+}
+
+impl FdfField {
+    fn read<'a>(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        let name = dict
+            .get_string("T", resolver)?
+            .as_deref()
+            .map(decode_text_string)
+            .unwrap_or_default();
+
+        let value = field_value(dict.get_object("V", resolver)?, resolver)?;
+
+        let kids = dict
+            .get_arr("Kids", resolver)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|obj| FdfField::read(obj, resolver))
+            .collect::<PdfResult<Vec<FdfField>>>()?;
+
+        Ok(Self { name, value, kids })
+    }
+
+    fn write(&self, out: &mut String) {
+        out.push_str("<< /T ");
+        write_string(&self.name, out);
+
+        match &self.value {
+            Some(FieldValue::Text(text)) => {
+                out.push_str(" /V ");
+                write_string(text, out);
+            }
+            Some(FieldValue::Name(name)) => {
+                out.push_str(" /V ");
+                write_name(name, out);
+            }
+            Some(FieldValue::Array(items)) => {
+                out.push_str(" /V [");
+
+                for item in items {
+                    out.push(' ');
+                    write_string(item, out);
+                }
+
+                out.push_str(" ]");
+            }
+            Some(FieldValue::Signature(..)) | None => {}
+        }
+
+        if !self.kids.is_empty() {
+            out.push_str(" /Kids [");
+
+            for kid in &self.kids {
+                out.push(' ');
+                kid.write(out);
+            }
+
+            out.push_str(" ]");
+        }
+
+        out.push_str(" >>");
+    }
+}
+
+/// Write a text string as a literal string, escaping its delimiters and line
+/// endings, which would otherwise be normalized
+fn write_string(s: &str, out: &mut String) {
+    out.push('(');
+
+    for c in encode_text_string(s).chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\r' => out.push_str("\\r"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+
+    out.push(')');
+}
+
+/// Write a name, escaping bytes which are not regular characters as a number
+/// sign followed by their hexadecimal code
+fn write_name(name: &str, out: &mut String) {
+    out.push('/');
+
+    for c in name.chars() {
+        match c {
+            '!'..='~'
+                if !matches!(
+                    c,
+                    '#' | '(' | ')' | '<' | '>' | '[' | ']' | '{' | '}' | '/' | '%'
+                ) =>
+            {
+                out.push(c)
+            }
+            c => out.push_str(&format!("#{:02X}", c as u32 as u8)),
+        }
+    }
+}
+
+/// The objects of an FDF file
+struct FdfObjects<'a> {
+    objects: HashMap<Reference, Object<'a>>,
+}
+
+impl<'a> Resolve<'a> for FdfObjects<'a> {
+    fn lex_object_from_reference(&mut self, reference: Reference) -> PdfResult<Object<'a>> {
+        Ok(self
+            .objects
+            .get(&reference)
+            .cloned()
+            .unwrap_or(Object::Null))
+    }
+
+    fn reference_exists(&mut self, reference: Reference) -> PdfResult<bool> {
+        Ok(self.objects.contains_key(&reference))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let fdf = Fdf {
+            fields: vec![
+                FdfField {
+                    name: "address".to_owned(),
+                    value: None,
+                    kids: vec![FdfField {
+                        name: "street".to_owned(),
+                        value: Some(FieldValue::Text("1 (Main) St\nFlat 2".to_owned())),
+                        kids: Vec::new(),
+                    }],
+                },
+                FdfField {
+                    name: "agree".to_owned(),
+                    value: Some(FieldValue::Name("Yes #1".to_owned())),
+                    kids: Vec::new(),
+                },
+                FdfField {
+                    name: "名前".to_owned(),
+                    value: Some(FieldValue::Array(vec!["a".to_owned(), "β".to_owned()])),
+                    kids: Vec::new(),
+                },
+            ],
+        };
+
+        let parsed = Fdf::parse(&fdf.to_bytes()).unwrap();
+
+        assert_eq!(parsed, fdf);
+        assert_eq!(
+            parsed.values()[0],
+            (
+                "address.street".to_owned(),
+                FieldValue::Text("1 (Main) St\nFlat 2".to_owned())
+            )
+        );
+    }
+
+    #[test]
+    fn parse_indirect_fields() {
+        let fdf = Fdf::parse(
+            b"%FDF-1.2\n1 0 obj\n<< /FDF << /Fields [2 0 R] >> >>\nendobj\n\
+              2 0 obj\n<< /T (name) /V 3 0 R >>\nendobj\n\
+              3 0 obj\n(value)\nendobj\n\
+              trailer\n<< /Root 1 0 R >>\n%%EOF\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            fdf.values(),
+            vec![("name".to_owned(), FieldValue::Text("value".to_owned()))]
+        );
+    }
+}
@@ -0,0 +1,448 @@
+use std::collections::HashSet;
+
+use crate::{
+    error::PdfResult,
+    filter::decode_stream,
+    objects::{decode_text_string, encode_text_string, Object, Reference},
+    Resolve, ToObj,
+};
+
+/// The type of a field, given by the FT entry of its field dictionary
+#[pdf_enum]
+pub enum FieldType {
+    /// Pushbuttons, check boxes, and radio buttons
+    Button = "Btn",
+
+    /// A box or space for text fill-in data typically entered from a keyboard
+    Text = "Tx",
+
+    /// A scrollable list box or combo box, containing several text items, one
+    /// or more of which shall be selected as the field value
+    Choice = "Ch",
+
+    /// A signature field, containing a digital signature
+    Signature = "Sig",
+}
+
+/// A terminal field of an interactive form, that is, a field with no
+/// descendants which are themselves fields
+///
+/// Inheritable attributes which are absent from the field dictionary are taken
+/// from the nearest ancestor in the field hierarchy that specifies them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    /// The fully qualified name of the field, formed from the partial names of
+    /// the field and each of its ancestors, separated by periods
+    pub name: String,
+
+    /// An alternate field name that shall be used in place of the actual field
+    /// name wherever the field shall be identified in the user interface
+    pub alternate_name: Option<String>,
+
+    pub field_type: Option<FieldType>,
+
+    /// A set of flags specifying various characteristics of the field, whose
+    /// meanings depend on the field type
+    pub flags: u32,
+
+    /// The field's value, whose format varies depending on the field type
+    pub value: Option<FieldValue>,
+
+    /// The default value to which the field reverts when a reset-form action
+    /// is executed
+    pub default_value: Option<FieldValue>,
+
+    /// The field dictionary
+    pub reference: Reference,
+
+    /// The widget annotations which display the field on a page
+    ///
+    /// A field with a single widget annotation may be merged with it into a
+    /// single dictionary, in which case this contains the field dictionary
+    /// itself.
+    pub widgets: Vec<Reference>,
+
+    /// The default appearance string of a variable text field, giving the
+    /// graphics state with which its text is laid out
+    pub(crate) default_appearance: Option<String>,
+
+    /// The justification of the text of a variable text field: 0 for left, 1
+    /// for centred, and 2 for right justified
+    pub(crate) quadding: Option<i32>,
+}
+
+impl Field {
+    const READ_ONLY: u32 = 1 << 0;
+    const REQUIRED: u32 = 1 << 1;
+    const NO_EXPORT: u32 = 1 << 2;
+    const MULTILINE: u32 = 1 << 12;
+    const RADIO: u32 = 1 << 15;
+    const PUSH_BUTTON: u32 = 1 << 16;
+    const COMBO: u32 = 1 << 17;
+    const MULTI_SELECT: u32 = 1 << 21;
+
+    /// If set, the user may not change the value of the field
+    pub fn is_read_only(&self) -> bool {
+        self.flags & Self::READ_ONLY != 0
+    }
+
+    /// If set, the field shall have a value at the time it is exported by a
+    /// submit-form action
+    pub fn is_required(&self) -> bool {
+        self.flags & Self::REQUIRED != 0
+    }
+
+    /// If set, the field shall not be exported by a submit-form action
+    pub fn is_no_export(&self) -> bool {
+        self.flags & Self::NO_EXPORT != 0
+    }
+
+    /// If set, a text field may contain multiple lines of text
+    pub fn is_multiline(&self) -> bool {
+        self.field_type == Some(FieldType::Text) && self.flags & Self::MULTILINE != 0
+    }
+
+    /// If set, a button field is a set of radio buttons, exactly one of which
+    /// shall be on at any time. Otherwise, it is a check box
+    pub fn is_radio(&self) -> bool {
+        self.field_type == Some(FieldType::Button) && self.flags & Self::RADIO != 0
+    }
+
+    /// If set, a button field is a pushbutton that does not retain a permanent
+    /// value
+    pub fn is_push_button(&self) -> bool {
+        self.field_type == Some(FieldType::Button) && self.flags & Self::PUSH_BUTTON != 0
+    }
+
+    /// If set, a choice field is a combo box. Otherwise, it is a list box
+    pub fn is_combo(&self) -> bool {
+        self.field_type == Some(FieldType::Choice) && self.flags & Self::COMBO != 0
+    }
+
+    /// If set, more than one of a choice field's option items may be selected
+    /// simultaneously
+    pub fn is_multi_select(&self) -> bool {
+        self.field_type == Some(FieldType::Choice) && self.flags & Self::MULTI_SELECT != 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    /// The text of a text field, or the selected item of a choice field
+    Text(String),
+
+    /// The appearance state of a check box or radio button, such as "Off"
+    Name(String),
+
+    /// The selected items of a choice field which allows multiple selection
+    Array(Vec<String>),
+
+    /// The signature dictionary of a signed signature field
+    Signature(Reference),
+}
+
+/// The attributes a field passes on to its descendants
+#[derive(Debug, Default, Clone)]
+struct InheritedAttributes<'a> {
+    /// The fully qualified name of the field
+    name: Option<String>,
+    field_type: Option<FieldType>,
+    flags: Option<u32>,
+    value: Option<Object<'a>>,
+    default_value: Option<Object<'a>>,
+    default_appearance: Option<String>,
+    quadding: Option<i32>,
+}
+
+/// A node of the field hierarchy, which is either a field or a widget
+/// annotation of its parent field
+#[derive(Debug)]
+struct FieldNode<'a> {
+    reference: Reference,
+
+    /// The partial field name
+    t: Option<String>,
+    tu: Option<String>,
+    ft: Option<FieldType>,
+    ff: Option<u32>,
+    v: Option<Object<'a>>,
+    dv: Option<Object<'a>>,
+    da: Option<String>,
+    q: Option<i32>,
+    kids: Vec<Reference>,
+
+    /// Whether the dictionary is (or is merged with) a widget annotation
+    is_widget: bool,
+}
+
+impl<'a> FieldNode<'a> {
+    fn read(reference: Reference, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(Object::Reference(reference))?;
+
+        Ok(Self {
+            reference,
+            t: dict.get_string("T", resolver)?,
+            tu: dict.get_string("TU", resolver)?,
+            ft: dict.get("FT", resolver)?,
+            ff: dict.get_unsigned_integer("Ff", resolver)?,
+            v: dict.get("V", resolver)?,
+            dv: dict.get("DV", resolver)?,
+            da: dict.get_string("DA", resolver)?,
+            q: dict.get_integer("Q", resolver)?,
+            kids: dict.get("Kids", resolver)?.unwrap_or_default(),
+            is_widget: dict.get_name("Subtype", resolver)?.as_deref() == Some("Widget"),
+        })
+    }
+
+    /// Whether this node is a widget annotation of its parent, rather than a
+    /// field in its own right
+    fn is_widget_only(&self) -> bool {
+        self.is_widget && self.t.is_none() && self.kids.is_empty()
+    }
+}
+
+/// Every terminal field in the hierarchies rooted at `roots`, in depth-first
+/// order
+///
+/// Nodes which have already been visited are skipped, so that malformed field
+/// hierarchies with cycles terminate.
+pub(crate) fn collect_fields<'a>(
+    roots: &[Reference],
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Vec<Field>> {
+    let mut fields = Vec::new();
+    let mut visited = HashSet::new();
+
+    for &root in roots {
+        if !visited.insert(root) {
+            continue;
+        }
+
+        let node = FieldNode::read(root, resolver)?;
+
+        collect(
+            node,
+            &InheritedAttributes::default(),
+            resolver,
+            &mut visited,
+            &mut fields,
+        )?;
+    }
+
+    Ok(fields)
+}
+
+fn collect<'a>(
+    node: FieldNode<'a>,
+    parent: &InheritedAttributes<'a>,
+    resolver: &mut dyn Resolve<'a>,
+    visited: &mut HashSet<Reference>,
+    fields: &mut Vec<Field>,
+) -> PdfResult<()> {
+    let name = match (&parent.name, node.t) {
+        (Some(parent), Some(t)) => Some(format!("{}.{}", parent, decode_text_string(&t))),
+        (None, Some(t)) => Some(decode_text_string(&t)),
+        (parent, None) => parent.clone(),
+    };
+
+    let inherited = InheritedAttributes {
+        name,
+        field_type: node.ft.or(parent.field_type),
+        flags: node.ff.or(parent.flags),
+        value: node.v.or_else(|| parent.value.clone()),
+        default_value: node.dv.or_else(|| parent.default_value.clone()),
+        default_appearance: node.da.or_else(|| parent.default_appearance.clone()),
+        quadding: node.q.or(parent.quadding),
+    };
+
+    let mut widgets = Vec::new();
+    let mut children = Vec::new();
+
+    for kid in node.kids {
+        if !visited.insert(kid) {
+            continue;
+        }
+
+        let kid = FieldNode::read(kid, resolver)?;
+
+        if kid.is_widget_only() {
+            widgets.push(kid.reference);
+        } else {
+            children.push(kid);
+        }
+    }
+
+    if !children.is_empty() {
+        for child in children {
+            collect(child, &inherited, resolver, visited, fields)?;
+        }
+
+        return Ok(());
+    }
+
+    if node.is_widget {
+        widgets.insert(0, node.reference);
+    }
+
+    fields.push(Field {
+        name: inherited.name.unwrap_or_default(),
+        alternate_name: node.tu.as_deref().map(decode_text_string),
+        field_type: inherited.field_type,
+        flags: inherited.flags.unwrap_or(0),
+        value: field_value(inherited.value, resolver)?,
+        default_value: field_value(inherited.default_value, resolver)?,
+        reference: node.reference,
+        widgets,
+        default_appearance: inherited.default_appearance,
+        quadding: inherited.quadding,
+    });
+
+    Ok(())
+}
+
+impl<'a> ToObj<'a> for FieldValue {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(match self {
+            FieldValue::Text(text) => Object::String(encode_text_string(text)),
+            FieldValue::Name(name) => Object::Name(name.clone()),
+            FieldValue::Array(items) => Object::Array(
+                items
+                    .iter()
+                    .map(|item| Object::String(encode_text_string(item)))
+                    .collect(),
+            ),
+            FieldValue::Signature(reference) => Object::Reference(*reference),
+        })
+    }
+}
+
+pub(super) fn field_value<'a>(
+    obj: Option<Object<'a>>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Option<FieldValue>> {
+    let obj = match obj {
+        Some(Object::Reference(reference)) => {
+            match resolver.resolve(Object::Reference(reference))? {
+                Object::Dictionary(..) => return Ok(Some(FieldValue::Signature(reference))),
+                obj => obj,
+            }
+        }
+        Some(obj) => obj,
+        None => return Ok(None),
+    };
+
+    Ok(match obj {
+        Object::Null => None,
+        Object::String(s) => Some(FieldValue::Text(decode_text_string(&s))),
+        Object::Name(name) => Some(FieldValue::Name(name)),
+        Object::Array(arr) => Some(FieldValue::Array(
+            arr.into_iter()
+                .map(|obj| Ok(decode_text_string(&resolver.assert_string(obj)?)))
+                .collect::<PdfResult<Vec<String>>>()?,
+        )),
+        // The value of a text field may be given by a text stream
+        Object::Stream(stream) => {
+            let text = decode_stream(&stream.stream, &stream.dict, resolver)?
+                .iter()
+                .map(|&b| b as char)
+                .collect::<String>();
+
+            Some(FieldValue::Text(decode_text_string(&text)))
+        }
+        obj => anyhow::bail!("unexpected field value {:?}", obj),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{tokenizer::Tokenizer, version::PdfVersion, writer::PdfWriter};
+
+    fn reference(object_number: usize) -> Reference {
+        Reference {
+            object_number,
+            generation: 0,
+        }
+    }
+
+    fn document(objects: &[&'static [u8]]) -> PdfWriter<'static> {
+        let mut writer = PdfWriter::new(PdfVersion::V1_7);
+
+        for obj in objects {
+            writer.add(Tokenizer::new(obj).next_object().unwrap().unwrap().value);
+        }
+
+        writer
+    }
+
+    #[test]
+    fn qualified_names_and_inherited_attributes() {
+        let mut writer = document(&[
+            b"<< /T (address) /FT /Tx /Ff 2 /DA (/Helv 0 Tf 0 g) /Kids [2 0 R 3 0 R] >>",
+            // a field merged with its widget annotation
+            b"<< /T (street) /Parent 1 0 R /Type /Annot /Subtype /Widget /V (1 Main St) \
+              /TU (Street address) >>",
+            // a field with two widget annotations, one of which lists the
+            // field's parent as a kid
+            b"<< /T (city) /Parent 1 0 R /Ff 4 /Q 1 /DV (Springfield) \
+              /Kids [4 0 R 5 0 R 1 0 R] >>",
+            b"<< /Parent 3 0 R /Type /Annot /Subtype /Widget >>",
+            b"<< /Parent 3 0 R /Type /Annot /Subtype /Widget >>",
+            b"<< /T (choice) /FT /Ch /Ff 2097152 /V [(a) (b)] >>",
+            b"<< /T (signature) /FT /Sig /V 8 0 R >>",
+            b"<< /Type /Sig /Filter /Adobe.PPKLite >>",
+        ]);
+
+        let fields = collect_fields(
+            &[reference(1), reference(6), reference(7), reference(1)],
+            &mut writer,
+        )
+        .unwrap();
+
+        let names = fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            names,
+            ["address.street", "address.city", "choice", "signature"]
+        );
+
+        assert_eq!(
+            fields[0],
+            Field {
+                name: "address.street".to_owned(),
+                alternate_name: Some("Street address".to_owned()),
+                field_type: Some(FieldType::Text),
+                flags: 2,
+                value: Some(FieldValue::Text("1 Main St".to_owned())),
+                default_value: None,
+                reference: reference(2),
+                widgets: vec![reference(2)],
+                default_appearance: Some("/Helv 0 Tf 0 g".to_owned()),
+                quadding: None,
+            }
+        );
+
+        let city = &fields[1];
+        assert!(city.is_no_export() && !city.is_required());
+        assert_eq!(city.field_type, Some(FieldType::Text));
+        assert_eq!(city.value, None);
+        assert_eq!(
+            city.default_value,
+            Some(FieldValue::Text("Springfield".to_owned()))
+        );
+        assert_eq!(city.widgets, [reference(4), reference(5)]);
+        assert_eq!(city.quadding, Some(1));
+
+        assert!(fields[2].is_multi_select());
+        assert_eq!(
+            fields[2].value,
+            Some(FieldValue::Array(vec!["a".to_owned(), "b".to_owned()]))
+        );
+
+        assert_eq!(fields[3].value, Some(FieldValue::Signature(reference(8))));
+        assert!(fields[3].widgets.is_empty());
+    }
+}
@@ -0,0 +1,966 @@
+/*!
+ * Filling in the fields of an interactive form, regenerating the appearance
+ * streams of their widget annotations so that the new values are displayed
+ * by viewers which do not construct appearances themselves
+ */
+
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    path::Path,
+};
+
+use crate::{
+    catalog::DocumentCatalog,
+    data_structures::{Matrix, Rectangle},
+    error::PdfResult,
+    font::{Font, Widths},
+    objects::{Dictionary, Object, Reference},
+    stream::Stream,
+    writer::IncrementalUpdate,
+    FromObj, Lexer, ParseOptions, Resolve, ToObj,
+};
+
+use super::{collect_fields, AcroForm, Field, FieldType, FieldValue, XfdfAnnotation};
+
+/// A set of values to fill into the fields of an interactive form, keyed by
+/// the fully qualified field name, and of markup annotations to add to its
+/// pages
+///
+/// The filled document is written as an incremental update, appending the
+/// changed field and widget annotation dictionaries, their new appearance
+/// streams, and the new annotations to the end of the original file. Existing bytes are never
+/// rewritten, so signatures covering other parts of the document remain valid.
+#[derive(Debug, Default, Clone)]
+pub struct FormFill {
+    values: Vec<(String, FillValue)>,
+    annotations: Vec<XfdfAnnotation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FillValue {
+    Text(String),
+    Checked(bool),
+
+    /// The export state of a radio button, or the selected items of a choice
+    /// field
+    Select(Vec<String>),
+}
+
+impl FormFill {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the text of a text field
+    pub fn text(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.values
+            .push((name.into(), FillValue::Text(value.into())));
+        self
+    }
+
+    /// Turn a check box on or off
+    pub fn check(mut self, name: impl Into<String>, checked: bool) -> Self {
+        self.values.push((name.into(), FillValue::Checked(checked)));
+        self
+    }
+
+    /// Turn on the radio button with the given export state, or select a
+    /// single item of a choice field
+    pub fn select(mut self, name: impl Into<String>, option: impl Into<String>) -> Self {
+        self.values
+            .push((name.into(), FillValue::Select(vec![option.into()])));
+        self
+    }
+
+    /// Select several items of a choice field which allows multiple selection
+    pub fn select_all(
+        mut self,
+        name: impl Into<String>,
+        options: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.values.push((
+            name.into(),
+            FillValue::Select(options.into_iter().map(Into::into).collect()),
+        ));
+        self
+    }
+
+    /// Add a markup annotation to the page it is on
+    pub fn annotation(mut self, annotation: XfdfAnnotation) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    /// Fill the form of the document at `path`, writing the changes as an
+    /// incremental update to the original file
+    ///
+    /// Every value is checked against its field before anything is written,
+    /// so a value for a field which does not exist, is read-only, or is of the
+    /// wrong type leaves the document unchanged, as does an annotation on a
+    /// page which does not exist.
+    pub fn apply(&self, path: impl AsRef<Path>) -> PdfResult<()> {
+        let file = std::fs::read(path.as_ref())?;
+
+        // Objects which are not changed are written back exactly as they were
+        let options = ParseOptions {
+            preserve_lexical_form: true,
+            preserve_key_order: true,
+            ..ParseOptions::default()
+        };
+
+        let (mut lexer, trailer) = Lexer::open(file, options)?;
+
+        let catalog = DocumentCatalog::from_obj(Object::Reference(trailer.root), &mut lexer)?;
+
+        let acro_form = match &catalog.acro_form {
+            Some(acro_form) => acro_form.get_ref(&mut lexer)?.into_owned(),
+            None => anyhow::bail!("document has no interactive form"),
+        };
+
+        let fields = collect_fields(&acro_form.fields, &mut lexer)?;
+
+        let updates = self
+            .values
+            .iter()
+            .map(|(name, value)| {
+                let field = fields
+                    .iter()
+                    .find(|field| &field.name == name)
+                    .ok_or_else(|| anyhow::anyhow!("no field named {:?}", name))?;
+
+                FieldUpdate::new(field, value, &acro_form, &mut lexer)
+            })
+            .collect::<PdfResult<Vec<FieldUpdate>>>()?;
+
+        // the strings of the field values would have to be encrypted
+        anyhow::ensure!(
+            trailer.encryption.is_none(),
+            "cannot fill the form of an encrypted document"
+        );
+
+        let mut catalog = lexer.assert_dict(Object::Reference(trailer.root))?;
+
+        // the form is found again as it was written, so that its entries
+        // other than NeedAppearances are kept exactly
+        let (form_reference, mut form) = match catalog.peek("AcroForm").cloned() {
+            Some(Object::Reference(reference)) => (
+                Some(reference),
+                lexer.assert_dict(Object::Reference(reference))?,
+            ),
+            Some(obj) => (None, lexer.assert_dict(obj)?),
+            None => anyhow::bail!("document has no interactive form"),
+        };
+
+        let resources = form.peek("DR").cloned();
+
+        let mut update = IncrementalUpdate::new(trailer.size);
+        let mut changed = HashMap::new();
+
+        for field_update in updates {
+            field_update.write(&mut update, &mut changed, resources.clone(), &mut lexer)?;
+        }
+
+        if !self.annotations.is_empty() {
+            let pages = match catalog.peek("Pages") {
+                Some(&Object::Reference(pages)) => {
+                    let mut references = Vec::new();
+                    page_references(pages, &mut HashSet::new(), &mut references, &mut lexer)?;
+                    references
+                }
+                _ => anyhow::bail!("expected the page tree to be an indirect object"),
+            };
+
+            add_annotations(
+                &self.annotations,
+                &pages,
+                &mut update,
+                &mut changed,
+                &mut lexer,
+            )?;
+        }
+
+        for (reference, dict) in changed {
+            update.insert(reference, Object::Dictionary(dict));
+        }
+
+        // every changed widget now has an appearance of its own
+        if form.remove("NeedAppearances").is_some() {
+            match form_reference {
+                Some(reference) => update.insert(reference, Object::Dictionary(form)),
+                None => {
+                    catalog.insert("AcroForm", Object::Dictionary(form));
+                    update.insert(trailer.root, Object::Dictionary(catalog));
+                }
+            }
+        }
+
+        let file = update.write(&lexer.file, IncrementalUpdate::trailer(&trailer)?)?;
+        std::fs::write(path, file)?;
+
+        Ok(())
+    }
+}
+
+/// The changes to a single field and its widget annotations
+#[derive(Debug)]
+struct FieldUpdate {
+    field: Reference,
+
+    /// The new value of the field's V entry
+    value: FieldValue,
+    widgets: Vec<WidgetUpdate>,
+}
+
+#[derive(Debug)]
+struct WidgetUpdate {
+    widget: Reference,
+
+    /// The new appearance state of a check box or radio button widget
+    state: Option<String>,
+
+    /// The new normal appearance of a variable text widget
+    appearance: Option<AppearanceStream>,
+}
+
+/// A form xobject to be used as the normal appearance of a widget annotation
+#[derive(Debug, Clone, PartialEq)]
+struct AppearanceStream {
+    bbox: Rectangle,
+    matrix: Matrix,
+    content: String,
+}
+
+impl FieldUpdate {
+    /// Add the new value of the field, and the new appearance streams of its
+    /// widgets, to `update`
+    ///
+    /// The changed field and widget dictionaries are collected in `changed`,
+    /// since a field with a single widget may share its dictionary with it.
+    /// Appearance streams use the default resources of the form.
+    fn write<'a>(
+        self,
+        update: &mut IncrementalUpdate<'a>,
+        changed: &mut HashMap<Reference, Dictionary<'a>>,
+        resources: Option<Object<'a>>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<()> {
+        let value = self.value.to_obj()?;
+        changed_dict(self.field, changed, resolver)?.insert("V", value);
+
+        for widget in self.widgets {
+            let appearance = match widget.appearance {
+                Some(appearance) => {
+                    Some(update.add(Object::Stream(appearance.to_stream(resources.clone())?)))
+                }
+                None => None,
+            };
+
+            let dict = changed_dict(widget.widget, changed, resolver)?;
+
+            if let Some(state) = widget.state {
+                dict.insert("AS", Object::Name(state));
+            }
+
+            if let Some(appearance) = appearance {
+                let mut ap = Dictionary::empty();
+                ap.insert("N", Object::Reference(appearance));
+
+                dict.insert("AP", Object::Dictionary(ap));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn new<'a>(
+        field: &Field,
+        value: &FillValue,
+        acro_form: &AcroForm<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        anyhow::ensure!(!field.is_read_only(), "field {:?} is read-only", field.name);
+
+        match (field.field_type, value) {
+            (Some(FieldType::Text), FillValue::Text(text)) => {
+                let lines = if field.is_multiline() {
+                    text.lines().collect()
+                } else {
+                    vec![text.as_str()]
+                };
+
+                Self::with_text_appearance(
+                    field,
+                    FieldValue::Text(text.clone()),
+                    &lines,
+                    acro_form,
+                    resolver,
+                )
+            }
+            (Some(FieldType::Button), FillValue::Checked(checked))
+                if !field.is_radio() && !field.is_push_button() =>
+            {
+                let mut on_state = None;
+                let mut widgets = Vec::new();
+
+                for &widget in &field.widgets {
+                    let state = widget_on_state(widget, resolver)?;
+                    on_state = on_state.or_else(|| state.clone());
+
+                    widgets.push(WidgetUpdate {
+                        widget,
+                        state: Some(match state {
+                            Some(state) if *checked => state,
+                            _ => "Off".to_owned(),
+                        }),
+                        appearance: None,
+                    });
+                }
+
+                let value = match on_state {
+                    Some(state) if *checked => state,
+                    _ if *checked => anyhow::bail!("check box {:?} has no on state", field.name),
+                    _ => "Off".to_owned(),
+                };
+
+                Ok(Self {
+                    field: field.reference,
+                    value: FieldValue::Name(value),
+                    widgets,
+                })
+            }
+            (Some(FieldType::Button), FillValue::Select(options))
+                if field.is_radio() && options.len() == 1 =>
+            {
+                let option = &options[0];
+                let mut found = false;
+                let mut widgets = Vec::new();
+
+                for &widget in &field.widgets {
+                    let is_on = widget_on_state(widget, resolver)?.as_ref() == Some(option);
+                    found |= is_on;
+
+                    widgets.push(WidgetUpdate {
+                        widget,
+                        state: Some(if is_on {
+                            option.clone()
+                        } else {
+                            "Off".to_owned()
+                        }),
+                        appearance: None,
+                    });
+                }
+
+                anyhow::ensure!(
+                    found,
+                    "radio button {:?} has no state {:?}",
+                    field.name,
+                    option
+                );
+
+                Ok(Self {
+                    field: field.reference,
+                    value: FieldValue::Name(option.clone()),
+                    widgets,
+                })
+            }
+            (Some(FieldType::Choice), FillValue::Select(options)) => {
+                anyhow::ensure!(
+                    options.len() == 1 || field.is_multi_select(),
+                    "choice field {:?} does not allow multiple selection",
+                    field.name
+                );
+
+                let value = match options.as_slice() {
+                    [option] => FieldValue::Text(option.clone()),
+                    options => FieldValue::Array(options.to_vec()),
+                };
+
+                // todo: list boxes should display every option, highlighting the
+                // selected items, rather than the selected items alone
+                let lines = options.iter().map(String::as_str).collect::<Vec<&str>>();
+
+                Self::with_text_appearance(field, value, &lines, acro_form, resolver)
+            }
+            (field_type, value) => anyhow::bail!(
+                "cannot set {:?} field {:?} to {:?}",
+                field_type,
+                field.name,
+                value
+            ),
+        }
+    }
+
+    /// An update setting the value of a variable text field and regenerating
+    /// the appearance of each of its widgets to display `lines`
+    fn with_text_appearance<'a>(
+        field: &Field,
+        value: FieldValue,
+        lines: &[&str],
+        acro_form: &AcroForm<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let da = field
+            .default_appearance
+            .as_deref()
+            .or(acro_form.da.as_deref())
+            .ok_or_else(|| anyhow::anyhow!("field {:?} has no default appearance", field.name))?;
+
+        let da = DefaultAppearance::parse(da)?;
+        let quadding = field.quadding.or(acro_form.q).unwrap_or(0);
+        let widths = default_font_widths(acro_form, &da.font, resolver)?;
+
+        let widgets = field
+            .widgets
+            .iter()
+            .map(|&widget| {
+                let (rect, rotation) = widget_geometry(widget, resolver)?;
+
+                Ok(WidgetUpdate {
+                    widget,
+                    state: None,
+                    appearance: Some(text_appearance(
+                        &da,
+                        rect,
+                        rotation,
+                        lines,
+                        quadding,
+                        widths.as_ref(),
+                    )),
+                })
+            })
+            .collect::<PdfResult<Vec<WidgetUpdate>>>()?;
+
+        Ok(Self {
+            field: field.reference,
+            value,
+            widgets,
+        })
+    }
+}
+
+/// The dictionary of the object `reference` as changed so far, read from the
+/// document the first time it is changed
+/// The page objects beneath the page tree node `node`, in order
+fn page_references<'a>(
+    node: Reference,
+    visited: &mut HashSet<Reference>,
+    pages: &mut Vec<Reference>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<()> {
+    anyhow::ensure!(visited.insert(node), "page tree contains a cycle");
+
+    let mut dict = resolver.assert_dict(Object::Reference(node))?;
+
+    let kids = match dict.get_arr("Kids", resolver)? {
+        Some(kids) => kids,
+        None => {
+            pages.push(node);
+            return Ok(());
+        }
+    };
+
+    for kid in kids {
+        match kid {
+            Object::Reference(kid) => page_references(kid, visited, pages, resolver)?,
+            kid => anyhow::bail!("expected page tree node reference, found {:?}", kid),
+        }
+    }
+
+    Ok(())
+}
+
+/// Add `annotations` to `update`, and append them to the Annots arrays of the
+/// `pages` they are on
+fn add_annotations<'a>(
+    annotations: &[XfdfAnnotation],
+    pages: &[Reference],
+    update: &mut IncrementalUpdate<'a>,
+    changed: &mut HashMap<Reference, Dictionary<'a>>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<()> {
+    let mut added: Vec<(Reference, Vec<Object<'a>>)> = Vec::new();
+
+    for annotation in annotations {
+        let page = match pages.get(annotation.page) {
+            Some(&page) => page,
+            None => anyhow::bail!(
+                "annotation on page {} of a document with {} pages",
+                annotation.page,
+                pages.len()
+            ),
+        };
+
+        let annotation =
+            Object::Reference(update.add(Object::Dictionary(annotation.to_dict(page))));
+
+        match added.iter_mut().find(|(added, _)| *added == page) {
+            Some((_, annotations)) => annotations.push(annotation),
+            None => added.push((page, vec![annotation])),
+        }
+    }
+
+    for (page, annotations) in added {
+        let dict = changed_dict(page, changed, resolver)?;
+
+        match dict.peek("Annots").cloned() {
+            // an indirect array may be shared, so it is replaced by the page's
+            // own array rather than changed in place
+            Some(annots) => {
+                let mut annots = resolver.assert_arr(annots)?;
+                annots.extend(annotations);
+                dict.insert("Annots", Object::Array(annots));
+            }
+            None => {
+                dict.insert("Annots", Object::Array(annotations));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn changed_dict<'b, 'a>(
+    reference: Reference,
+    changed: &'b mut HashMap<Reference, Dictionary<'a>>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<&'b mut Dictionary<'a>> {
+    Ok(match changed.entry(reference) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(resolver.assert_dict(Object::Reference(reference))?),
+    })
+}
+
+/// The name of the appearance state in which a check box or radio button
+/// widget is on, which is the key of its normal appearance subdictionary
+/// other than Off
+fn widget_on_state<'a>(
+    widget: Reference,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Option<String>> {
+    let mut dict = resolver.assert_dict(Object::Reference(widget))?;
+
+    let mut ap = match dict.get_dict("AP", resolver)? {
+        Some(ap) => ap,
+        None => return Ok(None),
+    };
+
+    let normal = match ap.get_dict("N", resolver)? {
+        Some(normal) => normal,
+        None => return Ok(None),
+    };
+
+    Ok(normal
+        .entries()
+        .map(|(state, _)| state)
+        .find(|state| state != "Off"))
+}
+
+/// The annotation rectangle of a widget, and its rotation from the MK entry
+fn widget_geometry<'a>(
+    widget: Reference,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<(Rectangle, i32)> {
+    let mut dict = resolver.assert_dict(Object::Reference(widget))?;
+
+    let rect = dict.expect::<Rectangle>("Rect", resolver)?;
+
+    let rotation = match dict.get_dict("MK", resolver)? {
+        Some(mut mk) => mk.get_integer("R", resolver)?.unwrap_or(0),
+        None => 0,
+    };
+
+    Ok((rect, rotation))
+}
+
+/// The glyph widths of the font named by a default appearance string, looked up
+/// in the default resources of the form
+fn default_font_widths<'a>(
+    acro_form: &AcroForm<'a>,
+    font: &str,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Option<Widths>> {
+    let mut fonts = match acro_form.dr.clone() {
+        Some(mut dr) => match dr.get_dict("Font", resolver)? {
+            Some(fonts) => fonts,
+            None => return Ok(None),
+        },
+        None => return Ok(None),
+    };
+
+    Ok(match fonts.get::<Font>(font, resolver)? {
+        Some(Font::Type1(font)) => font.base.widths,
+        Some(Font::MmType1(font)) => font.type1.base.widths,
+        Some(Font::TrueType(font)) => font.base.widths,
+        // todo: widths of composite fonts
+        _ => None,
+    })
+}
+
+/// A default appearance string, split into the font and size set by its Tf
+/// operator and the remaining operators, which set the text colour
+#[derive(Debug, Clone, PartialEq)]
+struct DefaultAppearance {
+    font: String,
+
+    /// The font size, or 0 if the text shall be sized to fit the widget
+    size: f32,
+    operators: String,
+}
+
+impl DefaultAppearance {
+    fn parse(da: &str) -> PdfResult<Self> {
+        let mut font = None;
+        let mut operators = Vec::new();
+        let mut operands = Vec::new();
+
+        for token in da.split_whitespace() {
+            let is_operator =
+                token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '\'' || c == '"');
+
+            if !is_operator {
+                operands.push(token);
+                continue;
+            }
+
+            if token == "Tf" {
+                match operands.as_slice() {
+                    [.., name, size] if name.starts_with('/') => {
+                        font = Some((name[1..].to_owned(), size.parse::<f32>()?));
+                    }
+                    _ => anyhow::bail!("invalid Tf operator in default appearance {:?}", da),
+                }
+            } else {
+                operators.extend(operands.iter().copied());
+                operators.push(token);
+            }
+
+            operands.clear();
+        }
+
+        let (font, size) =
+            font.ok_or_else(|| anyhow::anyhow!("default appearance {:?} sets no font", da))?;
+
+        Ok(Self {
+            font,
+            size,
+            operators: operators.join(" "),
+        })
+    }
+}
+
+/// The distance between the border of a widget and its text
+const PADDING: f32 = 2.0;
+
+/// The height of a line of text, relative to the font size
+const LEADING: f32 = 1.15;
+
+/// The size at which text is laid out when the default appearance asks for it
+/// to be sized to fit, but the widget is tall enough for larger text
+const MAX_AUTO_SIZE: f32 = 12.0;
+
+/// The normal appearance of a variable text widget displaying `lines`
+///
+/// Without glyph widths, each glyph is assumed to be half an em wide, which is
+/// only used to justify and auto-size the text.
+fn text_appearance(
+    da: &DefaultAppearance,
+    rect: Rectangle,
+    rotation: i32,
+    lines: &[&str],
+    quadding: i32,
+    widths: Option<&Widths>,
+) -> AppearanceStream {
+    // The appearance is laid out upright and rotated to fit the widget
+    let (width, height) = if rotation.rem_euclid(180) == 90 {
+        (rect.height(), rect.width())
+    } else {
+        (rect.width(), rect.height())
+    };
+
+    let text_width = |line: &str| -> f32 {
+        line.chars()
+            .map(|c| widths.map_or(0.5, |widths| widths.get(c as u32)))
+            .sum()
+    };
+
+    let line_count = lines.len().max(1) as f32;
+
+    let size = if da.size > 0.0 {
+        da.size
+    } else {
+        let widest = lines
+            .iter()
+            .map(|line| text_width(line))
+            .fold(0.0, f32::max);
+
+        let mut size = ((height - 2.0 * PADDING) / (line_count * LEADING)).min(MAX_AUTO_SIZE);
+
+        if widest > 0.0 {
+            size = size.min((width - 2.0 * PADDING) / widest);
+        }
+
+        size.max(1.0)
+    };
+
+    let mut content = String::from("/Tx BMC\nq\n");
+
+    content.push_str(&format!(
+        "{} {} {} {} re W n\nBT\n",
+        num(PADDING / 2.0),
+        num(PADDING / 2.0),
+        num(width - PADDING),
+        num(height - PADDING)
+    ));
+
+    if !da.operators.is_empty() {
+        content.push_str(&da.operators);
+        content.push('\n');
+    }
+
+    content.push_str(&format!("/{} {} Tf\n", da.font, num(size)));
+
+    // A single line is centred vertically, while multiple lines start at the top
+    let mut y = if lines.len() > 1 {
+        height - PADDING - size
+    } else {
+        (height - size) / 2.0 + 0.2 * size
+    };
+
+    let mut previous_x = 0.0;
+    let mut previous_y = 0.0;
+
+    for line in lines {
+        let line_width = text_width(line) * size;
+
+        let x = match quadding {
+            1 => (width - line_width) / 2.0,
+            2 => width - PADDING - line_width,
+            _ => PADDING,
+        };
+
+        content.push_str(&format!(
+            "{} {} Td\n({}) Tj\n",
+            num(x - previous_x),
+            num(y - previous_y),
+            escape_string(line)
+        ));
+
+        previous_x = x;
+        previous_y = y;
+        y -= size * LEADING;
+    }
+
+    content.push_str("ET\nQ\nEMC\n");
+
+    AppearanceStream {
+        bbox: Rectangle::new(0.0, 0.0, width, height),
+        matrix: Matrix::new_rotation(rotation as f32),
+        content,
+    }
+}
+
+impl AppearanceStream {
+    /// The appearance as a form XObject, using `resources` as its resource
+    /// dictionary
+    ///
+    /// The content is written unfiltered, so that the filled values can be
+    /// read from the file directly.
+    fn to_stream<'a>(&self, resources: Option<Object<'a>>) -> PdfResult<Stream<'a>> {
+        let mut dict = Dictionary::empty();
+        dict.insert("Type", Object::Name("XObject".to_owned()));
+        dict.insert("Subtype", Object::Name("Form".to_owned()));
+        dict.insert("BBox", self.bbox.to_obj()?);
+        dict.insert("Matrix", self.matrix.to_obj()?);
+
+        if let Some(resources) = resources {
+            dict.insert("Resources", resources);
+        }
+
+        Ok(Stream::new(self.content.clone().into_bytes(), dict))
+    }
+}
+
+/// Format a number for a content stream, rounded to two decimal places
+fn num(n: f32) -> String {
+    let n = (n * 100.0).round() / 100.0;
+
+    // Avoid writing negative zero
+    format!("{}", n + 0.0)
+}
+
+/// Escape the delimiters of a literal string
+///
+/// Characters which cannot be encoded in a single byte are replaced with a
+/// question mark.
+// todo: encode text using the encoding of the font
+fn escape_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if (c as u32) < 256 => escaped.push(c),
+            _ => escaped.push('?'),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        acro_form::Xfdf, tokenizer::Tokenizer, version::PdfVersion, writer::PdfWriter, Parser,
+    };
+
+    fn reference(object_number: usize) -> Reference {
+        Reference {
+            object_number,
+            generation: 0,
+        }
+    }
+
+    #[test]
+    fn parse_default_appearance() {
+        let da = DefaultAppearance::parse("0 0 1 rg /Helv 0 Tf").unwrap();
+
+        assert_eq!(da.font, "Helv");
+        assert_eq!(da.size, 0.0);
+        assert_eq!(da.operators, "0 0 1 rg");
+    }
+
+    #[test]
+    fn escapes_string_delimiters() {
+        assert_eq!(escape_string("a (b) \\c"), "a \\(b\\) \\\\c");
+    }
+
+    #[test]
+    fn right_justified_text() {
+        let da = DefaultAppearance::parse("/Helv 10 Tf 0 g").unwrap();
+        let rect = Rectangle::new(0.0, 0.0, 100.0, 20.0);
+
+        let appearance = text_appearance(&da, rect, 0, &["abcd"], 2, None);
+
+        assert!(appearance
+            .content
+            .contains("0 g\n/Helv 10 Tf\n78 7 Td\n(abcd) Tj\n"));
+        assert_eq!(appearance.bbox, rect);
+    }
+
+    /// A document with a single text field, named "name", saved to a file
+    /// with the given name
+    fn save_form(name: &str) -> std::path::PathBuf {
+        let objects: [&[u8]; 6] = [
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>",
+            b"<< /FT /Tx /T (name) /Type /Annot /Subtype /Widget /Rect [0 0 100 20] /P 5 0 R >>",
+            b"<< /Fields [2 0 R] /DA (/Helv 0 Tf 0 g) /DR << /Font << /Helv 1 0 R >> >> \
+              /NeedAppearances true >>",
+            b"<< /Type /Pages /Kids [5 0 R] /Count 1 >>",
+            b"<< /Type /Page /Parent 4 0 R /MediaBox [0 0 612 792] /Annots [2 0 R] >>",
+            b"<< /Type /Catalog /Pages 4 0 R /AcroForm 3 0 R >>",
+        ];
+
+        let mut writer = PdfWriter::new(PdfVersion::V1_7);
+
+        for obj in objects {
+            writer.add(Tokenizer::new(obj).next_object().unwrap().unwrap().value);
+        }
+
+        writer.set_root(reference(6));
+
+        let path = std::env::temp_dir().join(name);
+        writer.save(&path).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn fill_text_field() {
+        let path = save_form("pdf-form-fill.pdf");
+
+        FormFill::new()
+            .text("name", "Jane (Doe)")
+            .apply(&path)
+            .unwrap();
+
+        let file = std::fs::read(&path).unwrap();
+        let mut parser = Parser::from_bytes(file, ParseOptions::default()).unwrap();
+        let resolver = &mut parser.lexer;
+
+        let mut field = resolver
+            .assert_dict(Object::Reference(reference(2)))
+            .unwrap();
+
+        assert_eq!(
+            field.peek("V"),
+            Some(&Object::String("Jane (Doe)".to_owned()))
+        );
+
+        let mut ap = field.get_dict("AP", resolver).unwrap().unwrap();
+        let normal = ap.expect_object("N", resolver).unwrap();
+        let appearance = resolver.assert_stream(normal).unwrap();
+
+        assert!(String::from_utf8_lossy(&appearance.stream).contains("(Jane \\(Doe\\)) Tj"));
+        assert!(appearance.dict.other.peek("Resources").is_some());
+
+        let form = resolver
+            .assert_dict(Object::Reference(reference(3)))
+            .unwrap();
+        assert!(form.peek("NeedAppearances").is_none());
+    }
+
+    #[test]
+    fn import_xfdf_annotations() {
+        let path = save_form("pdf-form-fill-xfdf.pdf");
+
+        let xfdf = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+            <xfdf xmlns=\"http://ns.adobe.com/xfdf/\">\
+            <annots><text page=\"0\" rect=\"10,10,30,30\" name=\"n1\">\
+            <contents>note</contents></text></annots>\
+            <fields><field name=\"name\"><value>Jane</value></field></fields></xfdf>";
+
+        let file = std::fs::read(&path).unwrap();
+        let mut parser = Parser::from_bytes(file, ParseOptions::default()).unwrap();
+        let fill = parser.import_xfdf(xfdf).unwrap();
+
+        fill.apply(&path).unwrap();
+
+        let file = std::fs::read(&path).unwrap();
+        let mut parser = Parser::from_bytes(file, ParseOptions::default()).unwrap();
+        let exported = Xfdf::parse(&parser.export_xfdf().unwrap()).unwrap();
+
+        match exported.annotations.as_slice() {
+            [annotation] => {
+                assert_eq!(annotation.subtype, "Text");
+                assert_eq!(annotation.rect, [10.0, 10.0, 30.0, 30.0]);
+                assert_eq!(annotation.contents.as_deref(), Some("note"));
+                assert_eq!(annotation.name.as_deref(), Some("n1"));
+            }
+            annotations => panic!("unexpected annotations {:?}", annotations),
+        }
+
+        // the widget stays on the page along with the new annotation
+        let page = parser
+            .lexer
+            .assert_dict(Object::Reference(reference(5)))
+            .unwrap();
+        assert_eq!(
+            parser
+                .lexer
+                .assert_arr(page.peek("Annots").unwrap().clone())
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // annotations on pages which do not exist are refused
+        let mut annotation = exported.annotations[0].clone();
+        annotation.page = 1;
+
+        assert!(FormFill::new().annotation(annotation).apply(&path).is_err());
+    }
+}
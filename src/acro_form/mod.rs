@@ -0,0 +1,221 @@
+use crate::{
+    error::PdfResult,
+    objects::{Dictionary, Object, Reference},
+    FromObj, Parser, Resolve,
+};
+
+pub use self::{
+    fdf::{Fdf, FdfField},
+    field::{Field, FieldType, FieldValue},
+    fill::FormFill,
+    xfa::{Xfa, XfaPacket},
+    xfdf::{Xfdf, XfdfAnnotation},
+};
+pub(crate) use field::collect_fields;
+
+use xfa::XfaResource;
+
+mod fdf;
+mod field;
+mod fill;
+mod xfa;
+mod xfdf;
+
+#[derive(Debug, Clone, FromObj)]
+pub struct AcroForm<'a> {
+    /// An array of references to the document’s root fields (those with no
+    /// ancestors in the field hierarchy).
+    #[field("Fields")]
+    pub(crate) fields: Vec<Reference>,
+
+    /// A flag specifying whether to construct appearance streams and appearance
+    /// dictionaries for all widget annotations in the document
+    ///
+    /// Default value: false
+    #[field("NeedAppearances", default = false)]
+    need_appearances: bool,
+
+    /// A set of flags specifying various document-level characteristics related
+    /// to signature fields
+    ///
+    /// Default value: 0
+    #[field("SigFlags", default = SigFlags(0))]
+    sig_flags: SigFlags,
+
+    /// An array of indirect references to field dictionaries with calculation
+    /// actions, defining the calculation order in which their values will be
+    /// recalculated when the value of any field changes
+    #[field("CO")]
+    co: Option<Vec<Reference>>,
+
+    /// A resource dictionary containing default resources (such as fonts, patterns,
+    /// or colour spaces) that shall be used by form field appearance streams.
+    /// At a minimum, this dictionary shall contain a Font entry specifying the
+    /// resource name and font dictionary of the default font for displaying text.
+    #[field("DR")]
+    pub(crate) dr: Option<Dictionary<'a>>,
+
+    /// A document-wide default value for the DA attribute of variable text fields
+    #[field("DA")]
+    pub(crate) da: Option<String>,
+
+    /// A document-wide default value for the Q attribute of variable text fields
+    #[field("Q")]
+    pub(crate) q: Option<i32>,
+
+    /// A stream or array containing an XFA resource, whose format shall be
+    /// described by the Data Package (XDP) Specification.
+    ///
+    /// The value of this entry shall be either a stream representing the entire
+    /// contents of the XML Data Package or an array of text string and stream
+    /// pairs representing the individual packets comprising the XML Data Package.
+    #[field("XFA")]
+    pub(crate) xfa: Option<XfaResource<'a>>,
+}
+
+impl<'a> AcroForm<'a> {
+    /// The field/value tree of the form, as the field data of an FDF file
+    pub(crate) fn export_fdf(&self, resolver: &mut dyn Resolve<'a>) -> PdfResult<Fdf> {
+        Ok(Fdf::from_fields(&collect_fields(&self.fields, resolver)?))
+    }
+
+    /// The values of an FDF file, to be filled into the fields of the form
+    pub(crate) fn import_fdf(
+        &self,
+        bytes: &[u8],
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<FormFill> {
+        Fdf::parse(bytes)?.form_fill(&collect_fields(&self.fields, resolver)?)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SigFlags(u32);
+
+impl SigFlags {
+    /// If set, the document contains at least one signature field. This flag
+    /// allows a conforming reader to enable user interface items (such as menu
+    /// items or pushbuttons) related to signature processing without having to
+    /// scan the entire document for the presence of signature fields.
+    pub const SIGNATURES_EXIST: u8 = 0b01;
+
+    /// If set, the document contains signatures that may be invalidated if the
+    /// file is saved (written) in a way that alters its previous contents, as
+    /// opposed to an incremental update. Merely updating the file by appending
+    /// new information to the end of the previous version is safe. Conforming
+    /// readers may use this flag to inform a user requesting a full save that
+    /// signatures will be invalidated and require explicit confirmation before
+    /// continuing with the operation.
+    pub const APPEND_ONLY: u8 = 0b10;
+}
+
+impl<'a> FromObj<'a> for SigFlags {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        Ok(Self(u32::from_obj(obj, resolver)?))
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Every terminal field of the document's interactive form, with its fully
+    /// qualified name and current value
+    ///
+    /// Documents without an interactive form have no fields.
+    pub fn form_fields(&mut self) -> PdfResult<Vec<Field>> {
+        let acro_form = match &self.catalog.acro_form {
+            Some(acro_form) => acro_form.get_ref(&mut self.lexer)?.into_owned(),
+            None => return Ok(Vec::new()),
+        };
+
+        collect_fields(&acro_form.fields, &mut self.lexer)
+    }
+
+    /// Export the values of the document's interactive form as an FDF file
+    ///
+    /// Documents without an interactive form export an FDF file with no
+    /// fields.
+    pub fn export_fdf(&mut self) -> PdfResult<Vec<u8>> {
+        let fdf = match &self.catalog.acro_form {
+            Some(acro_form) => acro_form
+                .get_ref(&mut self.lexer)?
+                .into_owned()
+                .export_fdf(&mut self.lexer)?,
+            None => Fdf { fields: Vec::new() },
+        };
+
+        Ok(fdf.to_bytes())
+    }
+
+    /// Read the values of an FDF file into a [`FormFill`] for the document's
+    /// interactive form, which may then be applied to the document
+    pub fn import_fdf(&mut self, fdf: &[u8]) -> PdfResult<FormFill> {
+        let acro_form = match &self.catalog.acro_form {
+            Some(acro_form) => acro_form.get_ref(&mut self.lexer)?.into_owned(),
+            None => anyhow::bail!("document has no interactive form"),
+        };
+
+        acro_form.import_fdf(fdf, &mut self.lexer)
+    }
+
+    /// Export the values of the document's interactive form and its markup
+    /// annotations as an XFDF file
+    pub fn export_xfdf(&mut self) -> PdfResult<String> {
+        let fields = match &self.catalog.acro_form {
+            Some(acro_form) => {
+                acro_form
+                    .get_ref(&mut self.lexer)?
+                    .into_owned()
+                    .export_fdf(&mut self.lexer)?
+                    .fields
+            }
+            None => Vec::new(),
+        };
+
+        let mut annotations = Vec::new();
+
+        for (idx, page) in self.pages().iter().enumerate() {
+            for &annot in page.annots.iter().flatten() {
+                let dict = self.lexer.assert_dict(Object::Reference(annot))?;
+
+                if let Some(annotation) = XfdfAnnotation::from_dict(dict, idx, &mut self.lexer)? {
+                    annotations.push(annotation);
+                }
+            }
+        }
+
+        Ok(Xfdf {
+            fields,
+            annotations,
+        }
+        .to_xml())
+    }
+
+    /// Read the field values and annotations of an XFDF file into a
+    /// [`FormFill`] for the document's interactive form, which may then be
+    /// applied to the document
+    pub fn import_xfdf(&mut self, xfdf: &str) -> PdfResult<FormFill> {
+        let acro_form = match &self.catalog.acro_form {
+            Some(acro_form) => acro_form.get_ref(&mut self.lexer)?.into_owned(),
+            None => anyhow::bail!("document has no interactive form"),
+        };
+
+        let fields = collect_fields(&acro_form.fields, &mut self.lexer)?;
+
+        Xfdf::parse(xfdf)?.form_fill(&fields)
+    }
+
+    /// The XML Data Package of an XFA-based form, reassembled from its packets
+    ///
+    /// Documents without an interactive form, or whose form has no XFA
+    /// resource, have no XML Data Package.
+    pub fn xfa(&mut self) -> PdfResult<Option<Xfa>> {
+        let acro_form = match &self.catalog.acro_form {
+            Some(acro_form) => acro_form.get_ref(&mut self.lexer)?.into_owned(),
+            None => return Ok(None),
+        };
+
+        acro_form
+            .xfa
+            .map(|xfa| xfa.decode(&mut self.lexer))
+            .transpose()
+    }
+}
@@ -0,0 +1,343 @@
+use crate::{
+    error::PdfResult, filter::decode_stream, objects::Object, stream::Stream, FromObj, Resolve,
+};
+
+/// The XFA resource of an interactive form, as given by the XFA entry of the
+/// interactive form dictionary
+#[derive(Debug, Clone)]
+pub(crate) enum XfaResource<'a> {
+    /// A stream containing the entire XML Data Package
+    Stream(Stream<'a>),
+
+    /// The individual packets comprising the XML Data Package, each named by
+    /// a text string
+    Packets(Vec<(String, Stream<'a>)>),
+}
+
+impl<'a> FromObj<'a> for XfaResource<'a> {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        Ok(match resolver.resolve(obj)? {
+            Object::Stream(stream) => XfaResource::Stream(stream),
+            Object::Array(arr) => {
+                let mut packets = Vec::with_capacity(arr.len() / 2);
+                let mut arr = arr.into_iter();
+
+                while let Some(name) = arr.next() {
+                    let name = resolver.assert_string(name)?;
+                    let stream = match arr.next() {
+                        Some(obj) => resolver.assert_stream(obj)?,
+                        None => anyhow::bail!("XFA packet {:?} has no stream", name),
+                    };
+
+                    packets.push((name, stream));
+                }
+
+                XfaResource::Packets(packets)
+            }
+            obj => anyhow::bail!("expected XFA stream or array, found {:?}", obj),
+        })
+    }
+}
+
+impl<'a> XfaResource<'a> {
+    /// Decode the resource, reassembling its packets into a single XML Data
+    /// Package
+    pub(crate) fn decode(&self, resolver: &mut dyn Resolve<'a>) -> PdfResult<Xfa> {
+        let mut xdp = Vec::new();
+
+        match self {
+            XfaResource::Stream(stream) => {
+                xdp.extend_from_slice(&decode_stream(&stream.stream, &stream.dict, resolver)?);
+            }
+            XfaResource::Packets(packets) => {
+                for (_, stream) in packets {
+                    xdp.extend_from_slice(&decode_stream(&stream.stream, &stream.dict, resolver)?);
+                }
+            }
+        }
+
+        Ok(Xfa::from_xdp(String::from_utf8_lossy(&xdp).into_owned()))
+    }
+}
+
+/// The XML Data Package of an XFA-based form, split into the packets which are
+/// the children of its root xdp:xdp element
+#[derive(Debug, Clone, PartialEq)]
+pub struct Xfa {
+    xdp: String,
+    packets: Vec<XfaPacket>,
+}
+
+/// A single packet of an XML Data Package, such as the form template or its
+/// data
+#[derive(Debug, Clone, PartialEq)]
+pub struct XfaPacket {
+    /// The local name of the packet's element, such as template, datasets, or
+    /// config
+    pub name: String,
+
+    /// The XML of the packet's element
+    pub xml: String,
+}
+
+impl Xfa {
+    pub(crate) fn from_xdp(xdp: String) -> Self {
+        let packets = split_packets(&xdp);
+
+        Self { xdp, packets }
+    }
+
+    /// The entire XML Data Package
+    pub fn xdp(&self) -> &str {
+        &self.xdp
+    }
+
+    pub fn packets(&self) -> &[XfaPacket] {
+        &self.packets
+    }
+
+    /// The XML of the first packet with the given local name
+    pub fn packet(&self, name: &str) -> Option<&str> {
+        self.packets
+            .iter()
+            .find(|packet| packet.name == name)
+            .map(|packet| packet.xml.as_str())
+    }
+
+    /// The form template, describing the form's appearance and behaviour
+    pub fn template(&self) -> Option<&str> {
+        self.packet("template")
+    }
+
+    /// The data the form is bound to
+    pub fn datasets(&self) -> Option<&str> {
+        self.packet("datasets")
+    }
+
+    /// The configuration of the application processing the form
+    pub fn config(&self) -> Option<&str> {
+        self.packet("config")
+    }
+}
+
+/// A node of an XML document, as far as is needed to find the extent of
+/// elements
+#[derive(Debug)]
+enum XmlNode<'a> {
+    Start {
+        name: &'a str,
+        start: usize,
+        end: usize,
+        self_closing: bool,
+    },
+    End {
+        end: usize,
+    },
+
+    /// A comment, processing instruction, CDATA section, or document type
+    /// declaration
+    Other {
+        end: usize,
+    },
+}
+
+/// The next markup after `pos`, skipping character data
+fn next_node(xml: &str, pos: usize) -> Option<XmlNode<'_>> {
+    let start = pos + xml.get(pos..)?.find('<')?;
+    let rest = &xml[start..];
+
+    let skip_to = |terminator: &str| {
+        rest.find(terminator)
+            .map(|idx| start + idx + terminator.len())
+    };
+
+    if rest.starts_with("<!--") {
+        return Some(XmlNode::Other {
+            end: skip_to("-->")?,
+        });
+    }
+
+    if rest.starts_with("<![CDATA[") {
+        return Some(XmlNode::Other {
+            end: skip_to("]]>")?,
+        });
+    }
+
+    if rest.starts_with("<?") {
+        return Some(XmlNode::Other {
+            end: skip_to("?>")?,
+        });
+    }
+
+    if rest.starts_with("<!") {
+        return Some(XmlNode::Other {
+            end: tag_end(xml, start)?,
+        });
+    }
+
+    if rest.starts_with("</") {
+        return Some(XmlNode::End {
+            end: tag_end(xml, start)?,
+        });
+    }
+
+    let end = tag_end(xml, start)?;
+    let name = rest[1..]
+        .split(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+        .next()
+        .unwrap_or_default();
+
+    Some(XmlNode::Start {
+        name,
+        start,
+        end,
+        self_closing: xml[..end].ends_with("/>"),
+    })
+}
+
+/// The index just past the `>` closing the tag starting at `start`, ignoring
+/// any within quoted attribute values
+fn tag_end(xml: &str, start: usize) -> Option<usize> {
+    let mut quote = None;
+
+    for (idx, c) in xml[start..].char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(start + idx + 1),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// The children of the root element of `xdp`
+///
+/// Malformed XML ends the search, keeping the packets found so far.
+fn split_packets(xdp: &str) -> Vec<XfaPacket> {
+    let mut packets = Vec::new();
+
+    // skip the prolog
+    let mut pos = 0;
+    loop {
+        match next_node(xdp, pos) {
+            Some(XmlNode::Start {
+                end,
+                self_closing: false,
+                ..
+            }) => {
+                pos = end;
+                break;
+            }
+            Some(XmlNode::Other { end }) => pos = end,
+            _ => return packets,
+        }
+    }
+
+    loop {
+        match next_node(xdp, pos) {
+            Some(XmlNode::Start {
+                name,
+                start,
+                end,
+                self_closing,
+            }) => {
+                let end = if self_closing {
+                    end
+                } else {
+                    match element_end(xdp, end) {
+                        Some(end) => end,
+                        None => return packets,
+                    }
+                };
+
+                packets.push(XfaPacket {
+                    name: local_name(name).to_owned(),
+                    xml: xdp[start..end].to_owned(),
+                });
+
+                pos = end;
+            }
+            Some(XmlNode::Other { end }) => pos = end,
+            Some(XmlNode::End { .. }) | None => return packets,
+        }
+    }
+}
+
+/// The index just past the end tag of the element whose start tag ends at
+/// `pos`
+fn element_end(xml: &str, mut pos: usize) -> Option<usize> {
+    let mut depth = 1;
+
+    loop {
+        match next_node(xml, pos)? {
+            XmlNode::Start {
+                end, self_closing, ..
+            } => {
+                if !self_closing {
+                    depth += 1;
+                }
+
+                pos = end;
+            }
+            XmlNode::End { end } => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(end);
+                }
+
+                pos = end;
+            }
+            XmlNode::Other { end } => pos = end,
+        }
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_xdp_packets() {
+        let xdp = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- generated -->
+<xdp:xdp xmlns:xdp="http://ns.adobe.com/xdp/" timeStamp="a>b">
+<config xmlns="http://www.xfa.org/schema/xci/3.0/"><present><pdf/></present></config>
+<template xmlns="http://www.xfa.org/schema/xfa-template/3.3/"><subform name="form1"><subform><field name="x"/></subform><!-- </template> --></subform></template>
+<xfa:datasets xmlns:xfa="http://www.xfa.org/schema/xfa-data/1.0/"><xfa:data><![CDATA[</xfa:datasets>]]></xfa:data></xfa:datasets>
+<xfdf/>
+</xdp:xdp>"#;
+
+        let xfa = Xfa::from_xdp(xdp.to_owned());
+
+        assert_eq!(
+            xfa.packets()
+                .iter()
+                .map(|packet| packet.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["config", "template", "datasets", "xfdf"]
+        );
+        assert_eq!(
+            xfa.config(),
+            Some(
+                r#"<config xmlns="http://www.xfa.org/schema/xci/3.0/"><present><pdf/></present></config>"#
+            )
+        );
+        assert!(xfa
+            .template()
+            .unwrap()
+            .ends_with("--></subform></template>"));
+        assert!(xfa
+            .datasets()
+            .unwrap()
+            .ends_with("]]></xfa:data></xfa:datasets>"));
+        assert_eq!(xfa.packet("xfdf"), Some("<xfdf/>"));
+        assert_eq!(xfa.xdp(), xdp);
+    }
+}
@@ -0,0 +1,640 @@
+/*!
+ * XFDF, the XML representation of the field values and annotations of FDF,
+ * for interchange with applications which do not read binary FDF files
+ */
+
+use crate::{
+    error::PdfResult,
+    objects::{decode_text_string, encode_text_string, Dictionary, Object, Reference},
+    xml::{self, escape, XmlElement},
+    Resolve,
+};
+
+use super::{Fdf, FdfField, Field, FieldValue, FormFill};
+
+/// The field values and annotations of an XFDF file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Xfdf {
+    /// The root fields, each of which may have descendants with their own
+    /// values
+    pub fields: Vec<FdfField>,
+
+    pub annotations: Vec<XfdfAnnotation>,
+}
+
+/// A markup annotation of an XFDF file
+///
+/// Appearance streams and pop-up windows are not represented, so the
+/// annotation appears as its viewer draws it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XfdfAnnotation {
+    /// The annotation subtype, such as Text or Highlight
+    pub subtype: String,
+
+    /// The index of the page the annotation is on, starting from 0
+    pub page: usize,
+
+    /// The lower left and upper right corners of the annotation rectangle, in
+    /// default user space
+    pub rect: [f32; 4],
+
+    /// The text of the annotation
+    pub contents: Option<String>,
+
+    /// The name uniquely identifying the annotation among all the annotations
+    /// on its page
+    pub name: Option<String>,
+
+    /// The author of the annotation
+    pub title: Option<String>,
+
+    pub subject: Option<String>,
+
+    /// The date and time when the annotation was most recently modified, as a
+    /// PDF date string
+    pub date: Option<String>,
+
+    /// The colour of the annotation, as red, green, and blue components in
+    /// the range 0.0 to 1.0
+    pub color: Option<[f32; 3]>,
+
+    /// The annotation flags
+    pub flags: u32,
+
+    /// The QuadPoints of a text markup annotation, the Vertices of a polygon
+    /// or polyline annotation, or the L of a line annotation
+    pub coords: Vec<f32>,
+
+    /// The paths of an ink annotation
+    pub ink_list: Vec<Vec<f32>>,
+}
+
+/// The subtypes of the markup annotations represented in XFDF, whose element
+/// names are their lowercased names
+const MARKUP_SUBTYPES: &[&str] = &[
+    "Text",
+    "FreeText",
+    "Line",
+    "Square",
+    "Circle",
+    "Polygon",
+    "PolyLine",
+    "Highlight",
+    "Underline",
+    "Squiggly",
+    "StrikeOut",
+    "Stamp",
+    "Caret",
+    "Ink",
+    "FileAttachment",
+    "Sound",
+    "Redact",
+];
+
+/// The names of the annotation flags in the flags attribute, in the order of
+/// their bit positions
+const FLAG_NAMES: &[&str] = &[
+    "invisible",
+    "hidden",
+    "print",
+    "nozoom",
+    "norotate",
+    "noview",
+    "readonly",
+    "locked",
+    "togglenoview",
+    "lockedcontents",
+];
+
+impl Xfdf {
+    /// Parse an XFDF file
+    pub fn parse(xml: &str) -> PdfResult<Self> {
+        let root = xml::parse(xml)?;
+
+        if root.local_name() != "xfdf" {
+            anyhow::bail!("expected xfdf root element, found {:?}", root.name);
+        }
+
+        let fields = match root.element("fields") {
+            Some(fields) => fields
+                .elements()
+                .filter(|element| element.local_name() == "field")
+                .map(read_field)
+                .collect::<PdfResult<Vec<FdfField>>>()?,
+            None => Vec::new(),
+        };
+
+        let annotations = match root.element("annots") {
+            Some(annots) => annots
+                .elements()
+                .filter_map(|element| XfdfAnnotation::read(element).transpose())
+                .collect::<PdfResult<Vec<XfdfAnnotation>>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            fields,
+            annotations,
+        })
+    }
+
+    /// Serialize as an XFDF file
+    pub fn to_xml(&self) -> String {
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <xfdf xmlns=\"http://ns.adobe.com/xfdf/\" xml:space=\"preserve\">\n",
+        );
+
+        if !self.fields.is_empty() {
+            out.push_str("<fields>\n");
+
+            for field in &self.fields {
+                write_field(field, &mut out);
+            }
+
+            out.push_str("</fields>\n");
+        }
+
+        if !self.annotations.is_empty() {
+            out.push_str("<annots>\n");
+
+            for annotation in &self.annotations {
+                annotation.write(&mut out);
+            }
+
+            out.push_str("</annots>\n");
+        }
+
+        out.push_str("</xfdf>\n");
+
+        out
+    }
+
+    /// The field values and annotations of this file as a [`FormFill`] for
+    /// the given fields
+    pub(crate) fn form_fill(&self, fields: &[Field]) -> PdfResult<FormFill> {
+        let fill = Fdf {
+            fields: self.fields.clone(),
+        }
+        .form_fill(fields)?;
+
+        Ok(self
+            .annotations
+            .iter()
+            .cloned()
+            .fold(fill, FormFill::annotation))
+    }
+}
+
+/// The entry of an annotation dictionary holding the coordinates of an
+/// annotation of the given subtype, if any
+fn coords_key(subtype: &str) -> Option<&'static str> {
+    match subtype {
+        "Highlight" | "Underline" | "Squiggly" | "StrikeOut" => Some("QuadPoints"),
+        "Polygon" | "PolyLine" => Some("Vertices"),
+        "Line" => Some("L"),
+        _ => None,
+    }
+}
+
+/// XFDF does not distinguish names from text, so every value is read as text
+fn read_field(element: &XmlElement) -> PdfResult<FdfField> {
+    let name = match element.attribute("name") {
+        Some(name) => name.to_owned(),
+        None => anyhow::bail!("XFDF field has no name"),
+    };
+
+    let mut values = element
+        .elements()
+        .filter(|element| element.local_name() == "value")
+        .map(XmlElement::text)
+        .collect::<Vec<String>>();
+
+    let value = match values.len() {
+        0 => None,
+        1 => values.pop().map(FieldValue::Text),
+        _ => Some(FieldValue::Array(values)),
+    };
+
+    let kids = element
+        .elements()
+        .filter(|element| element.local_name() == "field")
+        .map(read_field)
+        .collect::<PdfResult<Vec<FdfField>>>()?;
+
+    Ok(FdfField { name, value, kids })
+}
+
+fn write_field(field: &FdfField, out: &mut String) {
+    out.push_str(&format!("<field name=\"{}\">\n", escape(&field.name)));
+
+    match &field.value {
+        Some(FieldValue::Text(value) | FieldValue::Name(value)) => {
+            out.push_str(&format!("<value>{}</value>\n", escape(value)));
+        }
+        Some(FieldValue::Array(values)) => {
+            for value in values {
+                out.push_str(&format!("<value>{}</value>\n", escape(value)));
+            }
+        }
+        Some(FieldValue::Signature(..)) | None => {}
+    }
+
+    for kid in &field.kids {
+        write_field(kid, out);
+    }
+
+    out.push_str("</field>\n");
+}
+
+impl XfdfAnnotation {
+    /// The annotation of the given annotation dictionary, or `None` if it is
+    /// not a markup annotation
+    pub(crate) fn from_dict<'a>(
+        mut dict: Dictionary<'a>,
+        page: usize,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Option<Self>> {
+        let subtype = dict.expect_name("Subtype", resolver)?;
+
+        if !MARKUP_SUBTYPES.contains(&subtype.as_str()) {
+            return Ok(None);
+        }
+
+        let rect = match dict.expect::<Vec<f32>>("Rect", resolver)?.as_slice() {
+            &[llx, lly, urx, ury] => [llx, lly, urx, ury],
+            rect => anyhow::bail!("expected rectangle, found {:?}", rect),
+        };
+
+        let mut text = |key: &str| -> PdfResult<Option<String>> {
+            Ok(dict
+                .get_string(key, resolver)?
+                .as_deref()
+                .map(decode_text_string))
+        };
+
+        let contents = text("Contents")?;
+        let name = text("NM")?;
+        let title = text("T")?;
+        let subject = text("Subj")?;
+
+        let date = dict.get_string("M", resolver)?;
+
+        let color = match dict.get::<Vec<f32>>("C", resolver)?.as_deref() {
+            Some(&[gray]) => Some([gray; 3]),
+            Some(&[r, g, b]) => Some([r, g, b]),
+            _ => None,
+        };
+
+        let flags = dict.get_unsigned_integer("F", resolver)?.unwrap_or(0);
+
+        let coords = match coords_key(&subtype) {
+            Some(key) => dict.get::<Vec<f32>>(key, resolver)?.unwrap_or_default(),
+            None => Vec::new(),
+        };
+
+        let ink_list = match subtype.as_str() {
+            "Ink" => dict
+                .get::<Vec<Vec<f32>>>("InkList", resolver)?
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        Ok(Some(Self {
+            subtype,
+            page,
+            rect,
+            contents,
+            name,
+            title,
+            subject,
+            date,
+            color,
+            flags,
+            coords,
+            ink_list,
+        }))
+    }
+
+    /// The annotation dictionary of this annotation, on the page object `page`
+    pub(crate) fn to_dict<'a>(&self, page: Reference) -> Dictionary<'a> {
+        let mut dict = Dictionary::empty();
+
+        let numbers =
+            |numbers: &[f32]| Object::Array(numbers.iter().copied().map(Object::Real).collect());
+
+        dict.insert("Type", Object::Name("Annot".to_owned()));
+        dict.insert("Subtype", Object::Name(self.subtype.clone()));
+        dict.insert("Rect", numbers(&self.rect));
+        dict.insert("P", Object::Reference(page));
+
+        for (key, text) in [
+            ("Contents", &self.contents),
+            ("NM", &self.name),
+            ("T", &self.title),
+            ("Subj", &self.subject),
+        ] {
+            if let Some(text) = text {
+                dict.insert(key, Object::String(encode_text_string(text)));
+            }
+        }
+
+        if let Some(date) = &self.date {
+            dict.insert("M", Object::String(date.clone()));
+        }
+
+        if let Some(color) = &self.color {
+            dict.insert("C", numbers(color));
+        }
+
+        if self.flags != 0 {
+            dict.insert("F", Object::Integer(self.flags as i32));
+        }
+
+        if let Some(key) = coords_key(&self.subtype) {
+            dict.insert(key, numbers(&self.coords));
+        }
+
+        if self.subtype == "Ink" {
+            dict.insert(
+                "InkList",
+                Object::Array(self.ink_list.iter().map(|path| numbers(path)).collect()),
+            );
+        }
+
+        dict
+    }
+
+    /// The annotation of an element of the annots element, or `None` if it is
+    /// not a markup annotation
+    fn read(element: &XmlElement) -> PdfResult<Option<Self>> {
+        let subtype = match MARKUP_SUBTYPES
+            .iter()
+            .find(|subtype| subtype.eq_ignore_ascii_case(element.local_name()))
+        {
+            Some(subtype) => (*subtype).to_owned(),
+            None => return Ok(None),
+        };
+
+        let page = match element.attribute("page") {
+            Some(page) => page.trim().parse::<usize>()?,
+            None => anyhow::bail!("XFDF annotation has no page"),
+        };
+
+        let rect = match parse_numbers(element.attribute("rect").unwrap_or_default())?.as_slice() {
+            &[llx, lly, urx, ury] => [llx, lly, urx, ury],
+            rect => anyhow::bail!("expected rectangle, found {:?}", rect),
+        };
+
+        let color = match element.attribute("color") {
+            Some(color) => Some(parse_color(color)?),
+            None => None,
+        };
+
+        let flags = element
+            .attribute("flags")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|flag| FLAG_NAMES.iter().position(|name| *name == flag.trim()))
+            .fold(0, |flags, bit| flags | 1 << bit);
+
+        let coords = match subtype.as_str() {
+            "Polygon" | "PolyLine" => parse_numbers(
+                &element
+                    .element("vertices")
+                    .map(XmlElement::text)
+                    .unwrap_or_default(),
+            )?,
+            "Line" => {
+                let mut coords = parse_numbers(element.attribute("start").unwrap_or_default())?;
+                coords.append(&mut parse_numbers(
+                    element.attribute("end").unwrap_or_default(),
+                )?);
+                coords
+            }
+            _ => parse_numbers(element.attribute("coords").unwrap_or_default())?,
+        };
+
+        let ink_list = match element.element("inklist") {
+            Some(ink_list) => ink_list
+                .elements()
+                .filter(|element| element.local_name() == "gesture")
+                .map(|gesture| parse_numbers(&gesture.text()))
+                .collect::<PdfResult<Vec<Vec<f32>>>>()?,
+            None => Vec::new(),
+        };
+
+        let attribute = |name: &str| element.attribute(name).map(str::to_owned);
+
+        Ok(Some(Self {
+            subtype,
+            page,
+            rect,
+            contents: element.element("contents").map(XmlElement::text),
+            name: attribute("name"),
+            title: attribute("title"),
+            subject: attribute("subject"),
+            date: attribute("date"),
+            color,
+            flags,
+            coords,
+            ink_list,
+        }))
+    }
+
+    fn write(&self, out: &mut String) {
+        let element = self.subtype.to_ascii_lowercase();
+
+        out.push_str(&format!(
+            "<{} page=\"{}\" rect=\"{}\"",
+            element,
+            self.page,
+            join_numbers(&self.rect, ",")
+        ));
+
+        let attributes = [
+            ("name", &self.name),
+            ("title", &self.title),
+            ("subject", &self.subject),
+            ("date", &self.date),
+        ];
+
+        for (key, value) in attributes {
+            if let Some(value) = value {
+                out.push_str(&format!(" {}=\"{}\"", key, escape(value)));
+            }
+        }
+
+        if let Some([r, g, b]) = self.color {
+            let component = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+            out.push_str(&format!(
+                " color=\"#{:02X}{:02X}{:02X}\"",
+                component(r),
+                component(g),
+                component(b)
+            ));
+        }
+
+        if self.flags != 0 {
+            let flags = FLAG_NAMES
+                .iter()
+                .enumerate()
+                .filter(|&(bit, _)| self.flags & 1 << bit != 0)
+                .map(|(_, name)| *name)
+                .collect::<Vec<&str>>();
+
+            out.push_str(&format!(" flags=\"{}\"", flags.join(",")));
+        }
+
+        let mut children = String::new();
+
+        match self.subtype.as_str() {
+            "Polygon" | "PolyLine" => {
+                children.push_str(&format!(
+                    "<vertices>{}</vertices>\n",
+                    join_points(&self.coords)
+                ));
+            }
+            "Line" => {
+                if let [x1, y1, x2, y2] = self.coords[..] {
+                    out.push_str(&format!(
+                        " start=\"{}\" end=\"{}\"",
+                        join_numbers(&[x1, y1], ","),
+                        join_numbers(&[x2, y2], ",")
+                    ));
+                }
+            }
+            _ if !self.coords.is_empty() => {
+                out.push_str(&format!(" coords=\"{}\"", join_numbers(&self.coords, ",")));
+            }
+            _ => {}
+        }
+
+        if !self.ink_list.is_empty() {
+            children.push_str("<inklist>\n");
+
+            for path in &self.ink_list {
+                children.push_str(&format!("<gesture>{}</gesture>\n", join_points(path)));
+            }
+
+            children.push_str("</inklist>\n");
+        }
+
+        if let Some(contents) = &self.contents {
+            children.push_str(&format!("<contents>{}</contents>\n", escape(contents)));
+        }
+
+        if children.is_empty() {
+            out.push_str("/>\n");
+        } else {
+            out.push_str(&format!(">\n{}</{}>\n", children, element));
+        }
+    }
+}
+
+/// Numbers separated by commas, semicolons, or whitespace
+fn parse_numbers(s: &str) -> PdfResult<Vec<f32>> {
+    s.split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .filter(|n| !n.is_empty())
+        .map(|n| Ok(n.parse::<f32>()?))
+        .collect()
+}
+
+fn join_numbers(numbers: &[f32], separator: &str) -> String {
+    numbers
+        .iter()
+        .map(f32::to_string)
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+/// Coordinates as `x,y` pairs separated by semicolons
+fn join_points(coords: &[f32]) -> String {
+    coords
+        .chunks(2)
+        .map(|point| join_numbers(point, ","))
+        .collect::<Vec<String>>()
+        .join(";")
+}
+
+/// A colour of the form `#RRGGBB`
+fn parse_color(color: &str) -> PdfResult<[f32; 3]> {
+    let hex = color.trim().trim_start_matches('#');
+
+    if hex.len() != 6 || !hex.is_ascii() {
+        anyhow::bail!("invalid XFDF color {:?}", color);
+    }
+
+    let component = |idx: usize| -> PdfResult<f32> {
+        Ok(u8::from_str_radix(&hex[idx..idx + 2], 16)? as f32 / 255.0)
+    };
+
+    Ok([component(0)?, component(2)?, component(4)?])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let xfdf = Xfdf {
+            fields: vec![FdfField {
+                name: "a & b".to_owned(),
+                value: None,
+                kids: vec![FdfField {
+                    name: "c".to_owned(),
+                    value: Some(FieldValue::Array(vec!["x".to_owned(), "<y>".to_owned()])),
+                    kids: Vec::new(),
+                }],
+            }],
+            annotations: vec![
+                XfdfAnnotation {
+                    subtype: "Highlight".to_owned(),
+                    page: 1,
+                    rect: [10.0, 20.0, 110.0, 32.5],
+                    contents: Some("check \"this\"".to_owned()),
+                    name: Some("h1".to_owned()),
+                    title: Some("Reviewer".to_owned()),
+                    subject: None,
+                    date: Some("D:20240101120000Z".to_owned()),
+                    color: Some([1.0, 1.0, 0.0]),
+                    flags: 4,
+                    coords: vec![10.0, 32.5, 110.0, 32.5, 10.0, 20.0, 110.0, 20.0],
+                    ink_list: Vec::new(),
+                },
+                XfdfAnnotation {
+                    subtype: "Ink".to_owned(),
+                    page: 0,
+                    rect: [0.0, 0.0, 50.0, 50.0],
+                    contents: None,
+                    name: None,
+                    title: None,
+                    subject: None,
+                    date: None,
+                    color: None,
+                    flags: 0,
+                    coords: Vec::new(),
+                    ink_list: vec![vec![1.0, 2.0, 3.0, 4.0], vec![5.0, 6.0]],
+                },
+                XfdfAnnotation {
+                    subtype: "Line".to_owned(),
+                    page: 0,
+                    rect: [0.0, 0.0, 50.0, 50.0],
+                    contents: None,
+                    name: None,
+                    title: None,
+                    subject: None,
+                    date: None,
+                    color: None,
+                    flags: 0,
+                    coords: vec![1.0, 2.0, 40.0, 45.0],
+                    ink_list: Vec::new(),
+                },
+            ],
+        };
+
+        assert_eq!(Xfdf::parse(&xfdf.to_xml()).unwrap(), xfdf);
+    }
+}
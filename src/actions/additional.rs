@@ -0,0 +1,280 @@
+use super::{ActionType, Actions};
+
+/// An additional-actions dictionary of a page object, defining actions that
+/// shall be performed when the page is opened or closed
+#[derive(Debug, FromObj)]
+pub struct PageAdditionalActions<'a> {
+    /// An action that shall be performed when the page is opened (for example,
+    /// when the user navigates to it from the next or previous page or by
+    /// means of a link annotation or outline item).
+    ///
+    /// This action is independent of any that may be defined by the
+    /// OpenAction entry in the document catalog and shall be executed after
+    /// such an action.
+    #[field("O")]
+    pub open: Option<Actions<'a>>,
+
+    /// An action that shall be performed when the page is closed (for example,
+    /// when the user navigates to the next or previous page or follows a link
+    /// annotation or an outline item).
+    ///
+    /// This action applies to the page being closed and shall be executed
+    /// before any other page is opened.
+    #[field("C")]
+    pub close: Option<Actions<'a>>,
+}
+
+/// The trigger events of page-level actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageEvent {
+    Open,
+    Close,
+}
+
+/// An action triggered by a page being opened or closed
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageAction {
+    /// The index of the page, starting from 0
+    pub page: usize,
+
+    pub event: PageEvent,
+
+    /// The type of the action and of each action that follows it
+    pub action_types: Vec<ActionType>,
+}
+
+impl<'a> PageAdditionalActions<'a> {
+    /// The actions of the page at index `page`, in the order of their trigger
+    /// events
+    pub(crate) fn page_actions(&self, page: usize) -> Vec<PageAction> {
+        [
+            (PageEvent::Open, &self.open),
+            (PageEvent::Close, &self.close),
+        ]
+        .into_iter()
+        .filter_map(|(event, actions)| {
+            Some(PageAction {
+                page,
+                event,
+                action_types: actions.as_ref()?.action_types(),
+            })
+        })
+        .collect()
+    }
+}
+
+/// An additional-actions dictionary of the document catalog, defining actions
+/// that shall be performed in response to events affecting the document as a
+/// whole
+#[derive(Debug, FromObj)]
+pub struct DocumentAdditionalActions<'a> {
+    /// A JavaScript action that shall be performed before closing a document
+    #[field("WC")]
+    will_close: Option<Actions<'a>>,
+
+    /// A JavaScript action that shall be performed before saving a document
+    #[field("WS")]
+    will_save: Option<Actions<'a>>,
+
+    /// A JavaScript action that shall be performed after saving a document
+    #[field("DS")]
+    did_save: Option<Actions<'a>>,
+
+    /// A JavaScript action that shall be performed before printing a document
+    #[field("WP")]
+    will_print: Option<Actions<'a>>,
+
+    /// A JavaScript action that shall be performed after printing a document
+    #[field("DP")]
+    did_print: Option<Actions<'a>>,
+}
+
+impl<'a> DocumentAdditionalActions<'a> {
+    /// The actions of the document, with their trigger events
+    pub(crate) fn actions(&self) -> Vec<(TriggerEvent, &Actions<'a>)> {
+        [
+            (TriggerEvent::WillClose, &self.will_close),
+            (TriggerEvent::WillSave, &self.will_save),
+            (TriggerEvent::DidSave, &self.did_save),
+            (TriggerEvent::WillPrint, &self.will_print),
+            (TriggerEvent::DidPrint, &self.did_print),
+        ]
+        .into_iter()
+        .filter_map(|(event, actions)| Some((event, actions.as_ref()?)))
+        .collect()
+    }
+}
+
+/// An additional-actions dictionary of an annotation or form field
+///
+/// A terminal field may be merged with its only widget annotation, in which
+/// case the dictionary holds the trigger events of both.
+#[derive(Debug, FromObj)]
+pub struct AnnotationAdditionalActions<'a> {
+    /// An action that shall be performed when the cursor enters the
+    /// annotation's active area
+    #[field("E")]
+    enter: Option<Actions<'a>>,
+
+    /// An action that shall be performed when the cursor exits the annotation's
+    /// active area
+    #[field("X")]
+    exit: Option<Actions<'a>>,
+
+    /// An action that shall be performed when the mouse button is pressed
+    /// inside the annotation's active area
+    #[field("D")]
+    down: Option<Actions<'a>>,
+
+    /// An action that shall be performed when the mouse button is released
+    /// inside the annotation's active area
+    #[field("U")]
+    up: Option<Actions<'a>>,
+
+    /// An action that shall be performed when the annotation receives the
+    /// input focus
+    #[field("Fo")]
+    focus: Option<Actions<'a>>,
+
+    /// An action that shall be performed when the annotation loses the input
+    /// focus
+    #[field("Bl")]
+    blur: Option<Actions<'a>>,
+
+    /// An action that shall be performed when the page containing the
+    /// annotation is opened
+    #[field("PO")]
+    page_open: Option<Actions<'a>>,
+
+    /// An action that shall be performed when the page containing the
+    /// annotation is closed
+    #[field("PC")]
+    page_close: Option<Actions<'a>>,
+
+    /// An action that shall be performed when the page containing the
+    /// annotation becomes visible
+    #[field("PV")]
+    page_visible: Option<Actions<'a>>,
+
+    /// An action that shall be performed when the page containing the
+    /// annotation is no longer visible in the user interface
+    #[field("PI")]
+    page_invisible: Option<Actions<'a>>,
+
+    /// A JavaScript action that shall be performed when the user modifies a
+    /// character in a text field or combo box or modifies the selection in a
+    /// scrollable list box
+    #[field("K")]
+    keystroke: Option<Actions<'a>>,
+
+    /// A JavaScript action that shall be performed before the field is
+    /// formatted to display its value
+    #[field("F")]
+    format: Option<Actions<'a>>,
+
+    /// A JavaScript action that shall be performed when the field's value is
+    /// changed
+    #[field("V")]
+    validate: Option<Actions<'a>>,
+
+    /// A JavaScript action that shall be performed to recalculate the value of
+    /// this field when that of another field changes
+    #[field("C")]
+    calculate: Option<Actions<'a>>,
+}
+
+impl<'a> AnnotationAdditionalActions<'a> {
+    /// The actions of the annotation, with their trigger events
+    pub(crate) fn annotation_actions(&self) -> Vec<(TriggerEvent, &Actions<'a>)> {
+        [
+            (TriggerEvent::Enter, &self.enter),
+            (TriggerEvent::Exit, &self.exit),
+            (TriggerEvent::Down, &self.down),
+            (TriggerEvent::Up, &self.up),
+            (TriggerEvent::Focus, &self.focus),
+            (TriggerEvent::Blur, &self.blur),
+            (TriggerEvent::PageOpen, &self.page_open),
+            (TriggerEvent::PageClose, &self.page_close),
+            (TriggerEvent::PageVisible, &self.page_visible),
+            (TriggerEvent::PageInvisible, &self.page_invisible),
+        ]
+        .into_iter()
+        .filter_map(|(event, actions)| Some((event, actions.as_ref()?)))
+        .collect()
+    }
+
+    /// The actions of the form field, with their trigger events
+    pub(crate) fn field_actions(&self) -> Vec<(TriggerEvent, &Actions<'a>)> {
+        [
+            (TriggerEvent::Keystroke, &self.keystroke),
+            (TriggerEvent::Format, &self.format),
+            (TriggerEvent::Validate, &self.validate),
+            (TriggerEvent::Calculate, &self.calculate),
+        ]
+        .into_iter()
+        .filter_map(|(event, actions)| Some((event, actions.as_ref()?)))
+        .collect()
+    }
+}
+
+/// The trigger events of the additional actions of the document, annotations
+/// and form fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerEvent {
+    WillClose,
+    WillSave,
+    DidSave,
+    WillPrint,
+    DidPrint,
+
+    Enter,
+    Exit,
+    Down,
+    Up,
+    Focus,
+    Blur,
+    PageOpen,
+    PageClose,
+    PageVisible,
+    PageInvisible,
+
+    Keystroke,
+    Format,
+    Validate,
+    Calculate,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{tokenizer::Tokenizer, FromObj};
+
+    use super::*;
+
+    #[test]
+    fn page_open_and_close_actions() {
+        let mut tokenizer = Tokenizer::new(
+            b"<< /O << /S /JavaScript /JS (app.fullScreen = true;) \
+                      /Next [<< /S /Named /N /FullScreen >>] >> \
+                 /C << /S /URI /URI (https://example.com) >> >>",
+        );
+
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+        let aa = PageAdditionalActions::from_obj(obj, &mut tokenizer).unwrap();
+
+        assert_eq!(
+            aa.page_actions(2),
+            vec![
+                PageAction {
+                    page: 2,
+                    event: PageEvent::Open,
+                    action_types: vec![ActionType::JavaScript, ActionType::Named],
+                },
+                PageAction {
+                    page: 2,
+                    event: PageEvent::Close,
+                    action_types: vec![ActionType::Uri],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{
+    assert_empty, error::PdfResult, file_specification::FileSpecification, objects::Object,
+    FromObj, Resolve,
+};
+
+use super::ActionTarget;
+
+/// A hide action hides or shows one or more annotations on the screen by
+/// setting or clearing their Hidden flags
+#[derive(Debug)]
+pub struct HideAction {
+    /// The annotations to hide or show, given by reference, and the fields
+    /// whose widget annotations are hidden or shown, given by their fully
+    /// qualified names
+    pub targets: Vec<ActionTarget>,
+
+    /// Whether to hide the annotations, rather than show them
+    ///
+    /// Default value: true
+    pub hide: bool,
+}
+
+impl<'a> FromObj<'a> for HideAction {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        dict.expect_type("Action", resolver, false)?;
+
+        let targets = match dict.expect_object("T", resolver)? {
+            Object::Array(targets) => targets
+                .into_iter()
+                .map(|target| ActionTarget::from_obj(target, resolver))
+                .collect::<PdfResult<Vec<ActionTarget>>>()?,
+            target => vec![ActionTarget::from_obj(target, resolver)?],
+        };
+        let hide = dict.get_bool("H", resolver)?.unwrap_or(true);
+
+        assert_empty(dict);
+
+        Ok(Self { targets, hide })
+    }
+}
+
+/// A submit-form action transmits the names and values of selected
+/// interactive form fields to a specified uniform resource locator
+#[derive(Debug, FromObj)]
+#[obj_type("Action")]
+pub struct SubmitFormAction<'a> {
+    /// A URL file specification giving the uniform resource locator of the
+    /// script at the web server that will process the submission
+    #[field("F")]
+    pub(crate) f: FileSpecification<'a>,
+
+    /// The fields to submit, or to exclude if the Include/Exclude flag is set
+    ///
+    /// If this entry is absent, all fields of the interactive form are
+    /// submitted.
+    #[field("Fields")]
+    pub fields: Option<Vec<ActionTarget>>,
+
+    /// A set of flags specifying various characteristics of the action
+    ///
+    /// Default value: 0
+    #[field("Flags", default = 0)]
+    pub flags: u32,
+
+    /// (PDF 2.0) The charset in which the data is encoded, such as utf-8
+    #[field("CharSet")]
+    pub char_set: Option<String>,
+}
+
+impl SubmitFormAction<'_> {
+    /// If set, the fields are excluded from the submission rather than
+    /// included
+    pub const INCLUDE_EXCLUDE: u32 = 1 << 0;
+
+    /// If set, fields without a value are submitted
+    pub const INCLUDE_NO_VALUE_FIELDS: u32 = 1 << 1;
+
+    /// If set, the values are submitted in HTML form format rather than FDF
+    pub const EXPORT_FORMAT: u32 = 1 << 2;
+
+    /// If set, the values are submitted with an HTTP GET request
+    pub const GET_METHOD: u32 = 1 << 3;
+
+    /// If set, the coordinates of the mouse click are submitted
+    pub const SUBMIT_COORDINATES: u32 = 1 << 4;
+
+    /// If set, the values are submitted as XFDF
+    pub const XFDF: u32 = 1 << 5;
+
+    /// If set, the entire document is submitted
+    pub const SUBMIT_PDF: u32 = 1 << 8;
+}
+
+/// A reset-form action resets selected interactive form fields to their
+/// default values
+#[derive(Debug, FromObj)]
+#[obj_type("Action")]
+pub struct ResetFormAction {
+    /// The fields to reset, or to exclude if the Include/Exclude flag is set
+    ///
+    /// If this entry is absent, all fields of the interactive form are reset.
+    #[field("Fields")]
+    pub fields: Option<Vec<ActionTarget>>,
+
+    /// A set of flags, of which only Include/Exclude is defined
+    ///
+    /// Default value: 0
+    #[field("Flags", default = 0)]
+    pub flags: u32,
+}
+
+/// An import-data action imports field values from a file into the document's
+/// interactive form
+#[derive(Debug, FromObj)]
+#[obj_type("Action")]
+pub struct ImportDataAction<'a> {
+    /// The FDF file from which to import the data
+    #[field("F")]
+    pub(crate) f: FileSpecification<'a>,
+}
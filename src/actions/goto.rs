@@ -1,21 +1,33 @@
-use crate::{destination::Destination, file_specification::FileSpecification};
+use crate::{
+    destination::Destination,
+    file_specification::FileSpecification,
+    objects::{Object, Reference},
+};
+
+use super::ActionTarget;
 
 /// A go-to action changes the view to a specified destination (page, location, and magnification factor)
 #[derive(Debug, FromObj)]
 #[obj_type("Action")]
-pub struct GoToAction {
+pub struct GoToAction<'a> {
     /// The destination to jump to
     #[field("D")]
-    d: Destination,
+    pub(crate) d: Destination,
+
+    /// (PDF 2.0) A structure destination to jump to, an array whose first
+    /// element is a structure element rather than a page
+    #[field("SD")]
+    sd: Option<Vec<Object<'a>>>,
 }
 
 /// A remote go-to action is similar to an ordinary go-to action but jumps to a destination in
 /// another PDF file instead of the current file
 #[derive(Debug, FromObj)]
+#[obj_type("Action")]
 pub struct GoToRemoteAction<'a> {
     /// The file in which the destination shall be located
     #[field("F")]
-    f: FileSpecification<'a>,
+    pub(crate) f: FileSpecification<'a>,
 
     /// The destination to jump to. If the value is an array defining an explicit destination, its
     /// first element shall be a page number within the remote document rather than an indirect reference
@@ -23,12 +35,90 @@ pub struct GoToRemoteAction<'a> {
     ///
     /// The first page shall be numbered 0.
     #[field("D")]
-    d: Destination,
+    pub(crate) d: Destination,
 
     /// A flag specifying whether to open the destination document in a new window.
     ///
     /// If this flag is false, the destination document replaces the current document in the same window.
     /// If this entry is absent, the conforming reader should behave in accordance with its preference
     #[field("NewWindow")]
-    new_window: Option<bool>,
+    pub new_window: Option<bool>,
+
+    /// (PDF 2.0) A structure destination to jump to
+    #[field("SD")]
+    sd: Option<Vec<Object<'a>>>,
+}
+
+/// An embedded go-to action is similar to a remote go-to action but allows jumping to or from a
+/// PDF file that is embedded in another PDF file
+#[derive(Debug, FromObj)]
+#[obj_type("Action")]
+pub struct GoToEmbeddedAction<'a> {
+    /// The root document of the target relative to the root document of the source. If this entry
+    /// is absent, the source and target share the same root document
+    #[field("F")]
+    pub(crate) f: Option<FileSpecification<'a>>,
+
+    /// The destination in the target to jump to
+    #[field("D")]
+    pub(crate) d: Destination,
+
+    /// A flag specifying whether to open the destination document in a new window
+    #[field("NewWindow")]
+    pub new_window: Option<bool>,
+
+    /// The path from the root document, or the document containing the action if F is absent, to
+    /// the target document
+    #[field("T")]
+    pub target: Option<EmbeddedTarget>,
+}
+
+/// A target dictionary, one step of the path from a document to an embedded document or to the
+/// document in which it is embedded
+#[derive(Debug, FromObj)]
+pub struct EmbeddedTarget {
+    #[field("R")]
+    pub relationship: TargetRelationship,
+
+    /// The name of the file in the EmbeddedFiles name tree, for a child which is embedded in the
+    /// document's name tree
+    #[field("N")]
+    pub name: Option<String>,
+
+    /// The page containing the file attachment annotation of a child, by its index or by a named
+    /// destination
+    #[field("P")]
+    pub page: Option<ActionTarget>,
+
+    /// The file attachment annotation of a child on the page given by `page`, by its index in the
+    /// Annots array or by its name
+    #[field("A")]
+    pub annotation: Option<ActionTarget>,
+
+    /// The next step of the path, if the target is not yet reached
+    #[field("T")]
+    pub target: Option<Box<EmbeddedTarget>>,
+}
+
+#[pdf_enum]
+pub enum TargetRelationship {
+    /// The target is the parent of the current document
+    Parent = "P",
+
+    /// The target is a child of the current document
+    Child = "C",
+}
+
+/// A 3D view action changes the view of a 3D annotation
+#[derive(Debug, FromObj)]
+#[obj_type("Action")]
+pub struct GoTo3DViewAction<'a> {
+    /// The 3D annotation whose view shall change
+    #[field("TA")]
+    pub annotation: Reference,
+
+    /// The view, either a 3D view dictionary, the index or name of one of the annotation's views,
+    /// or one of the names F, L, N, P and D for the first, last, next, previous and default view
+    #[field("V")]
+    pub(crate) view: Object<'a>,
 }
@@ -0,0 +1,147 @@
+use crate::{
+    assert_empty,
+    error::PdfResult,
+    filter::decode_stream,
+    objects::{decode_text_string, Object, Reference},
+    FromObj, Resolve,
+};
+
+use super::{additional::TriggerEvent, Actions, PageEvent};
+
+/// A JavaScript action causes a script to be compiled and executed by the
+/// JavaScript interpreter
+#[derive(Debug)]
+pub struct JavaScriptAction {
+    /// The script, decoded from a text string or text stream
+    script: String,
+}
+
+impl JavaScriptAction {
+    pub(crate) fn script(&self) -> &str {
+        &self.script
+    }
+}
+
+impl<'a> FromObj<'a> for JavaScriptAction {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        dict.expect_type("Action", resolver, false)?;
+
+        let script = decode_script(dict.expect_object("JS", resolver)?, resolver)?;
+
+        assert_empty(dict);
+
+        Ok(Self { script })
+    }
+}
+
+/// The script `obj`, a text string or text stream
+pub(super) fn decode_script<'a>(
+    obj: Object<'a>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<String> {
+    Ok(match resolver.resolve(obj)? {
+        Object::String(script) => decode_text_string(&script),
+        Object::Stream(stream) => {
+            let data = decode_stream(&stream.stream, &stream.dict, resolver)?;
+
+            decode_text_string(&data.iter().map(|&b| b as char).collect::<String>())
+        }
+        found => anyhow::bail!("expected JavaScript string or stream, found {:?}", found),
+    })
+}
+
+/// A script of a JavaScript action in the document, with where it is found
+#[derive(Debug, Clone, PartialEq)]
+pub struct Script {
+    pub location: ScriptLocation,
+
+    /// The position of the action among the actions performed in sequence,
+    /// through their Next entries, starting from 0
+    pub sequence: usize,
+
+    pub script: String,
+}
+
+impl Script {
+    /// The scripts of `actions` and the actions which follow it, all found at
+    /// `location`
+    pub(crate) fn from_actions(location: &ScriptLocation, actions: &Actions) -> Vec<Self> {
+        actions
+            .scripts()
+            .into_iter()
+            .map(|(sequence, script)| Self {
+                location: location.clone(),
+                sequence,
+                script: script.to_owned(),
+            })
+            .collect()
+    }
+}
+
+/// Where a JavaScript action is found, and when it is executed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptLocation {
+    /// A document-level script of the JavaScript name tree, executed when the
+    /// document is opened
+    Document { name: String },
+
+    /// The OpenAction of the document catalog
+    OpenAction,
+
+    /// The additional actions of the document catalog
+    DocumentEvent { event: TriggerEvent },
+
+    /// The additional actions of a page, by its index starting from 0
+    Page { page: usize, event: PageEvent },
+
+    /// The action of an annotation, executed when it is activated, or one of its
+    /// additional actions
+    Annotation {
+        page: usize,
+        annotation: Reference,
+        event: Option<TriggerEvent>,
+    },
+
+    /// The additional actions of a form field, by its fully qualified name
+    Field { name: String, event: TriggerEvent },
+
+    /// The action of an outline item, by its title
+    Outline { title: String },
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{tokenizer::Tokenizer, FromObj};
+
+    use super::*;
+
+    #[test]
+    fn scripts_of_action_sequence() {
+        let mut tokenizer = Tokenizer::new(
+            b"<< /S /JavaScript /JS (app.alert\\(1\\);) \
+                 /Next [<< /S /Named /N /NextPage >> \
+                        << /Type /Action /S /JavaScript /JS <FEFF0078> >>] >>",
+        );
+
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+        let actions = Actions::from_obj(obj, &mut tokenizer).unwrap();
+
+        assert_eq!(
+            Script::from_actions(&ScriptLocation::OpenAction, &actions),
+            vec![
+                Script {
+                    location: ScriptLocation::OpenAction,
+                    sequence: 0,
+                    script: "app.alert(1);".to_owned(),
+                },
+                Script {
+                    location: ScriptLocation::OpenAction,
+                    sequence: 2,
+                    script: "x".to_owned(),
+                },
+            ]
+        );
+    }
+}
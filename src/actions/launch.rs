@@ -0,0 +1,57 @@
+use crate::{file_specification::FileSpecification, objects::Object};
+
+/// A launch action launches an application or opens or prints a document
+#[derive(Debug, FromObj)]
+#[obj_type("Action")]
+pub struct LaunchAction<'a> {
+    /// The application that shall be launched or the document that shall be
+    /// opened or printed
+    ///
+    /// Required if none of the platform-specific entries is present
+    #[field("F")]
+    pub(crate) f: Option<FileSpecification<'a>>,
+
+    /// A dictionary containing Windows-specific launch parameters
+    #[field("Win")]
+    pub win: Option<WindowsLaunchParameters>,
+
+    /// Mac OS-specific launch parameters, whose form is not yet defined
+    #[field("Mac")]
+    mac: Option<Object<'a>>,
+
+    /// UNIX-specific launch parameters, whose form is not yet defined
+    #[field("Unix")]
+    unix: Option<Object<'a>>,
+
+    /// A flag specifying whether to open the destination document in a new
+    /// window
+    ///
+    /// This entry shall be ignored if the file designated by the F entry is not
+    /// a PDF document.
+    #[field("NewWindow")]
+    pub new_window: Option<bool>,
+}
+
+/// The Windows-specific parameters of a launch action
+#[derive(Debug, FromObj)]
+pub struct WindowsLaunchParameters {
+    /// The file name of the application that shall be launched or the document
+    /// that shall be opened or printed, in standard Windows pathname format
+    #[field("F")]
+    pub file_name: String,
+
+    /// The default directory in standard DOS syntax
+    #[field("D")]
+    pub directory: Option<String>,
+
+    /// The operation to perform, `open` or `print`, if F designates a document
+    ///
+    /// Default value: `open`
+    #[field("O")]
+    pub operation: Option<String>,
+
+    /// The parameters that shall be passed to the application designated by
+    /// F
+    #[field("P")]
+    pub parameters: Option<String>,
+}
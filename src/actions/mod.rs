@@ -1,13 +1,45 @@
+use std::collections::HashSet;
+
 use crate::{
+    acro_form::collect_fields,
+    catalog::OpenAction,
+    destination::Destination,
     error::{ParseError, PdfResult},
-    objects::{Object, ObjectType},
-    FromObj, Resolve,
+    objects::{decode_text_string, Object, ObjectType, Reference},
+    outline::OutlineItemDictionary,
+    FromObj, Parser, Resolve,
 };
 
-use self::goto::GoToRemoteAction;
-pub use self::{goto::GoToAction, uri::UriAction};
+pub use self::{
+    additional::{
+        AnnotationAdditionalActions, DocumentAdditionalActions, PageAction, PageAdditionalActions,
+        PageEvent, TriggerEvent,
+    },
+    form::{HideAction, ImportDataAction, ResetFormAction, SubmitFormAction},
+    goto::{
+        EmbeddedTarget, GoTo3DViewAction, GoToAction, GoToEmbeddedAction, GoToRemoteAction,
+        TargetRelationship,
+    },
+    javascript::{JavaScriptAction, Script, ScriptLocation},
+    launch::{LaunchAction, WindowsLaunchParameters},
+    multimedia::{MovieAction, MovieOperation, RenditionAction, SoundAction},
+    named::NamedAction,
+    optional_content::{OcgStateChange, SetOcgStateAction},
+    thread::ThreadAction,
+    transition::TransitionAction,
+    uri::UriAction,
+};
 
+mod additional;
+mod form;
 mod goto;
+mod javascript;
+mod launch;
+mod multimedia;
+mod named;
+mod optional_content;
+mod thread;
+mod transition;
 mod uri;
 
 #[derive(Debug)]
@@ -19,63 +51,254 @@ pub struct Actions<'a> {
     ///
     /// The value is either a single action dictionary or an array of action dictionaries
     /// that shall be performed in order
-    // todo: should this actually be Vec<Action>?
     next: Option<Vec<Self>>,
 }
 
+/// An action, by its type
 #[derive(Debug)]
-enum Action<'a> {
-    GoTo(GoToAction),
+pub enum Action<'a> {
+    GoTo(GoToAction<'a>),
     GoToRemote(GoToRemoteAction<'a>),
+    GoToEmbedded(GoToEmbeddedAction<'a>),
+    Launch(LaunchAction<'a>),
+    Thread(ThreadAction<'a>),
     Uri(UriAction),
+    Sound(SoundAction<'a>),
+    Movie(MovieAction),
+    Hide(HideAction),
+    Named(NamedAction),
+    SubmitForm(SubmitFormAction<'a>),
+    ResetForm(ResetFormAction),
+    ImportData(ImportDataAction<'a>),
+    JavaScript(JavaScriptAction),
+    SetOcgState(SetOcgStateAction),
+    Rendition(RenditionAction<'a>),
+    Transition(TransitionAction),
+    GoTo3DView(GoTo3DViewAction<'a>),
 }
 
 impl<'a> Actions<'a> {
     const TYPE: &'static str = "Action";
 
-    fn maybe_array(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Vec<Self>> {
-        Ok(match resolver.resolve(obj)? {
-            Object::Array(arr) => arr
-                .into_iter()
-                .map(|obj| Actions::from_obj(obj, resolver))
-                .collect::<PdfResult<Vec<Actions>>>()?,
-            obj @ Object::Dictionary(..) => vec![Actions::from_obj(obj, resolver)?],
-            _ => {
-                anyhow::bail!(ParseError::MismatchedObjectTypeAny {
-                    expected: &[ObjectType::Array, ObjectType::Dictionary],
-                });
-            }
-        })
+    pub fn action(&self) -> &Action<'a> {
+        &self.action
     }
-}
 
-impl<'a> FromObj<'a> for Actions<'a> {
-    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+    /// The actions performed after this one, each followed by its own
+    pub fn next(&self) -> &[Self] {
+        self.next.as_deref().unwrap_or_default()
+    }
+
+    /// This action and each action that follows it, in the order in which they
+    /// are performed
+    pub fn sequence(&self) -> Vec<&Action<'a>> {
+        let mut sequence = vec![&self.action];
+
+        for next in self.next() {
+            sequence.append(&mut next.sequence());
+        }
+
+        sequence
+    }
+
+    /// The destination of a go-to action, which is within this document
+    pub(crate) fn destination(&self) -> Option<&Destination> {
+        match &self.action {
+            Action::GoTo(action) => Some(&action.d),
+            _ => None,
+        }
+    }
+
+    /// The URI resolved by a URI action
+    pub(crate) fn uri(&self) -> Option<&str> {
+        match &self.action {
+            Action::Uri(action) => Some(action.uri()),
+            _ => None,
+        }
+    }
+
+    pub fn action_type(&self) -> ActionType {
+        self.action.action_type()
+    }
+
+    /// The type of this action and of each action that follows it, in the
+    /// order in which they are performed
+    pub fn action_types(&self) -> Vec<ActionType> {
+        self.sequence()
+            .into_iter()
+            .map(Action::action_type)
+            .collect()
+    }
+
+    /// The script of this action and of each action that follows it which is
+    /// a JavaScript action, or a rendition action with a script, with its
+    /// position in the order in which they are performed
+    pub(crate) fn scripts(&self) -> Vec<(usize, &str)> {
+        self.sequence()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(sequence, action)| match action {
+                Action::JavaScript(action) => Some((sequence, action.script())),
+                Action::Rendition(action) => Some((sequence, action.script.as_deref()?)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The action `obj` and those which follow it
+    ///
+    /// Actions already in `visited` end the sequence, so that malformed
+    /// documents whose Next entries form a cycle terminate.
+    fn from_obj_visited(
+        obj: Object<'a>,
+        resolver: &mut dyn Resolve<'a>,
+        visited: &mut HashSet<Reference>,
+    ) -> PdfResult<Self> {
+        if let Object::Reference(reference) = obj {
+            visited.insert(reference);
+        }
+
         let mut dict = resolver.assert_dict(obj)?;
 
         let action_type = ActionType::from_str(&dict.expect_name("S", resolver)?)?;
 
+        // the Next entry is checked against `visited` before it is resolved,
+        // since resolving an action which refers back to itself would recurse
         let next = dict
-            .get_object("Next", resolver)?
-            .map(|obj| Actions::maybe_array(obj, resolver))
+            .remove("Next")
+            .map(|obj| Actions::maybe_array(obj, resolver, visited))
             .transpose()?;
 
+        let obj = Object::Dictionary(dict);
+
         let action = match action_type {
-            ActionType::GoTo => {
-                Action::GoTo(GoToAction::from_obj(Object::Dictionary(dict), resolver)?)
+            ActionType::GoTo => Action::GoTo(GoToAction::from_obj(obj, resolver)?),
+            ActionType::GoToRemote => {
+                Action::GoToRemote(GoToRemoteAction::from_obj(obj, resolver)?)
+            }
+            ActionType::GoToEmbedded => {
+                Action::GoToEmbedded(GoToEmbeddedAction::from_obj(obj, resolver)?)
+            }
+            ActionType::Launch => Action::Launch(LaunchAction::from_obj(obj, resolver)?),
+            ActionType::Thread => Action::Thread(ThreadAction::from_obj(obj, resolver)?),
+            ActionType::Uri => Action::Uri(UriAction::from_obj(obj, resolver)?),
+            ActionType::Sound => Action::Sound(SoundAction::from_obj(obj, resolver)?),
+            ActionType::Movie => Action::Movie(MovieAction::from_obj(obj, resolver)?),
+            ActionType::Hide => Action::Hide(HideAction::from_obj(obj, resolver)?),
+            ActionType::Named => Action::Named(NamedAction::from_obj(obj, resolver)?),
+            ActionType::SubmitForm => {
+                Action::SubmitForm(SubmitFormAction::from_obj(obj, resolver)?)
+            }
+            ActionType::ResetForm => Action::ResetForm(ResetFormAction::from_obj(obj, resolver)?),
+            ActionType::ImportData => {
+                Action::ImportData(ImportDataAction::from_obj(obj, resolver)?)
             }
-            ActionType::GoToRemote => Action::GoToRemote(GoToRemoteAction::from_obj(
-                Object::Dictionary(dict),
-                resolver,
-            )?),
-            ActionType::Uri => {
-                Action::Uri(UriAction::from_obj(Object::Dictionary(dict), resolver)?)
+            ActionType::JavaScript => {
+                Action::JavaScript(JavaScriptAction::from_obj(obj, resolver)?)
+            }
+            ActionType::SetOptionalContentGroupState => {
+                Action::SetOcgState(SetOcgStateAction::from_obj(obj, resolver)?)
+            }
+            ActionType::Rendition => Action::Rendition(RenditionAction::from_obj(obj, resolver)?),
+            ActionType::Trans => Action::Transition(TransitionAction::from_obj(obj, resolver)?),
+            ActionType::GoTo3DView => {
+                Action::GoTo3DView(GoTo3DViewAction::from_obj(obj, resolver)?)
             }
-            _ => todo!(),
         };
 
         Ok(Self { action, next })
     }
+
+    fn maybe_array(
+        obj: Object<'a>,
+        resolver: &mut dyn Resolve<'a>,
+        visited: &mut HashSet<Reference>,
+    ) -> PdfResult<Vec<Self>> {
+        if let Object::Reference(reference) = obj {
+            if visited.contains(&reference) {
+                return Ok(Vec::new());
+            }
+        }
+
+        Ok(match resolver.resolve(obj.clone())? {
+            Object::Array(arr) => {
+                let mut actions = Vec::with_capacity(arr.len());
+
+                for obj in arr {
+                    match obj {
+                        Object::Reference(reference) if visited.contains(&reference) => {}
+                        obj => actions.push(Actions::from_obj_visited(obj, resolver, visited)?),
+                    }
+                }
+
+                actions
+            }
+            Object::Dictionary(..) => vec![Actions::from_obj_visited(obj, resolver, visited)?],
+            Object::Null => Vec::new(),
+            _ => {
+                anyhow::bail!(ParseError::MismatchedObjectTypeAny {
+                    expected: &[ObjectType::Array, ObjectType::Dictionary],
+                });
+            }
+        })
+    }
+}
+
+impl<'a> FromObj<'a> for Actions<'a> {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        Self::from_obj_visited(obj, resolver, &mut HashSet::new())
+    }
+}
+
+impl Action<'_> {
+    pub fn action_type(&self) -> ActionType {
+        match self {
+            Action::GoTo(..) => ActionType::GoTo,
+            Action::GoToRemote(..) => ActionType::GoToRemote,
+            Action::GoToEmbedded(..) => ActionType::GoToEmbedded,
+            Action::Launch(..) => ActionType::Launch,
+            Action::Thread(..) => ActionType::Thread,
+            Action::Uri(..) => ActionType::Uri,
+            Action::Sound(..) => ActionType::Sound,
+            Action::Movie(..) => ActionType::Movie,
+            Action::Hide(..) => ActionType::Hide,
+            Action::Named(..) => ActionType::Named,
+            Action::SubmitForm(..) => ActionType::SubmitForm,
+            Action::ResetForm(..) => ActionType::ResetForm,
+            Action::ImportData(..) => ActionType::ImportData,
+            Action::JavaScript(..) => ActionType::JavaScript,
+            Action::SetOcgState(..) => ActionType::SetOptionalContentGroupState,
+            Action::Rendition(..) => ActionType::Rendition,
+            Action::Transition(..) => ActionType::Trans,
+            Action::GoTo3DView(..) => ActionType::GoTo3DView,
+        }
+    }
+}
+
+/// An object targeted by an action, such as a field, annotation, thread or
+/// bead, given by reference, by its index, or by its name
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionTarget {
+    Reference(Reference),
+    Index(i32),
+    Name(String),
+}
+
+impl<'a> FromObj<'a> for ActionTarget {
+    fn from_obj(obj: Object<'a>, _resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        Ok(match obj {
+            Object::Reference(reference) => ActionTarget::Reference(reference),
+            Object::Integer(idx) => ActionTarget::Index(idx),
+            Object::String(name) => ActionTarget::Name(decode_text_string(&name)),
+            _ => anyhow::bail!(ParseError::MismatchedObjectTypeAny {
+                expected: &[
+                    ObjectType::Reference,
+                    ObjectType::Integer,
+                    ObjectType::String
+                ],
+            }),
+        })
+    }
 }
 
 #[pdf_enum]
@@ -136,3 +359,222 @@ pub enum ActionType {
     /// Set the current view of a 3D annotation
     GoTo3DView = "GoTo3DView",
 }
+
+impl<'a> Parser<'a> {
+    /// The actions performed when each page is opened or closed, such as the
+    /// scripts and named actions of kiosk and presentation documents
+    pub fn page_actions(&self) -> Vec<PageAction> {
+        self.pages()
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, page)| Some(page.aa.as_ref()?.page_actions(idx)))
+            .flatten()
+            .collect()
+    }
+
+    /// Every JavaScript action of the document, and script of a rendition
+    /// action, with where it is found
+    ///
+    /// Scripts are found in the JavaScript name tree, the OpenAction and
+    /// additional actions of the catalog and pages, the actions and additional
+    /// actions of annotations, the additional actions of form fields and the
+    /// actions of outline items. Actions which follow another through its Next
+    /// entry are included.
+    pub fn scripts(&mut self) -> PdfResult<Vec<Script>> {
+        let mut scripts = Vec::new();
+
+        let names = match &self.catalog.names {
+            Some(names) => Some(names.get_ref(&mut self.lexer)?.into_owned()),
+            None => None,
+        };
+
+        if let Some(java_script) = names.as_ref().and_then(|names| names.java_script.as_ref()) {
+            for (name, obj) in java_script.entries() {
+                let actions = Actions::from_obj(obj.clone(), &mut self.lexer)?;
+                let location = ScriptLocation::Document {
+                    name: decode_text_string(name),
+                };
+
+                scripts.append(&mut Script::from_actions(&location, &actions));
+            }
+        }
+
+        if let Some(OpenAction::Actions(actions)) = &self.catalog.open_action {
+            scripts.append(&mut Script::from_actions(
+                &ScriptLocation::OpenAction,
+                actions,
+            ));
+        }
+
+        for (event, actions) in self.catalog.aa.iter().flat_map(|aa| aa.actions()) {
+            let location = ScriptLocation::DocumentEvent { event };
+
+            scripts.append(&mut Script::from_actions(&location, actions));
+        }
+
+        for (page, page_object) in self.pages().iter().enumerate() {
+            if let Some(aa) = &page_object.aa {
+                for (event, actions) in [(PageEvent::Open, &aa.open), (PageEvent::Close, &aa.close)]
+                {
+                    if let Some(actions) = actions {
+                        let location = ScriptLocation::Page { page, event };
+
+                        scripts.append(&mut Script::from_actions(&location, actions));
+                    }
+                }
+            }
+
+            for &annotation in page_object.annots.iter().flatten() {
+                let mut dict = self.lexer.assert_dict(Object::Reference(annotation))?;
+
+                let a = dict.get::<Actions>("A", &mut self.lexer)?;
+                let aa = dict.get::<AnnotationAdditionalActions>("AA", &mut self.lexer)?;
+
+                let actions = a.iter().map(|a| (None, a)).chain(
+                    aa.iter()
+                        .flat_map(|aa| aa.annotation_actions())
+                        .map(|(event, actions)| (Some(event), actions)),
+                );
+
+                for (event, actions) in actions {
+                    let location = ScriptLocation::Annotation {
+                        page,
+                        annotation,
+                        event,
+                    };
+
+                    scripts.append(&mut Script::from_actions(&location, actions));
+                }
+            }
+        }
+
+        if let Some(acro_form) = &self.catalog.acro_form {
+            let acro_form = acro_form.get_ref(&mut self.lexer)?.into_owned();
+
+            for field in collect_fields(&acro_form.fields, &mut self.lexer)? {
+                let mut dict = self.lexer.assert_dict(Object::Reference(field.reference))?;
+                let aa = dict.get::<AnnotationAdditionalActions>("AA", &mut self.lexer)?;
+
+                for (event, actions) in aa.iter().flat_map(|aa| aa.field_actions()) {
+                    let location = ScriptLocation::Field {
+                        name: field.name.clone(),
+                        event,
+                    };
+
+                    scripts.append(&mut Script::from_actions(&location, actions));
+                }
+            }
+        }
+
+        if let Some(outline) = &self.catalog.outlines {
+            let outline = outline.get_ref(&mut self.lexer)?.into_owned();
+
+            let mut visited = HashSet::new();
+            let mut stack = vec![outline.first];
+
+            while let Some(next) = stack.pop() {
+                let reference = match next {
+                    Some(reference) if visited.insert(reference) => reference,
+                    _ => continue,
+                };
+
+                let obj = self.lexer.lex_object_from_reference(reference)?;
+                let item = OutlineItemDictionary::from_obj(obj, &mut self.lexer)?;
+
+                if let Some(actions) = &item.a {
+                    let location = ScriptLocation::Outline {
+                        title: decode_text_string(&item.title),
+                    };
+
+                    scripts.append(&mut Script::from_actions(&location, actions));
+                }
+
+                stack.push(item.next);
+                stack.push(item.first);
+            }
+        }
+
+        Ok(scripts)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{tokenizer::Tokenizer, version::PdfVersion, writer::PdfWriter};
+
+    use super::*;
+
+    fn reference(object_number: usize) -> Reference {
+        Reference {
+            object_number,
+            generation: 0,
+        }
+    }
+
+    #[test]
+    fn action_sequence() {
+        let mut tokenizer = Tokenizer::new(
+            b"<< /S /Hide /T [4 0 R (form.total)] /H false \
+                 /Next << /S /SetOCGState /State [/OFF 7 0 R /Toggle 8 0 R 9 0 R] \
+                          /Next << /Type /Action /S /ResetForm /Fields [(form.total)] >> >> >>",
+        );
+
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+        let actions = Actions::from_obj(obj, &mut tokenizer).unwrap();
+
+        assert_eq!(
+            actions.action_types(),
+            vec![
+                ActionType::Hide,
+                ActionType::SetOptionalContentGroupState,
+                ActionType::ResetForm
+            ]
+        );
+
+        match actions.sequence().as_slice() {
+            [Action::Hide(hide), Action::SetOcgState(state), Action::ResetForm(reset)] => {
+                assert_eq!(
+                    hide.targets,
+                    vec![
+                        ActionTarget::Reference(reference(4)),
+                        ActionTarget::Name("form.total".to_owned()),
+                    ]
+                );
+                assert!(!hide.hide);
+                assert_eq!(
+                    state.state,
+                    vec![
+                        (OcgStateChange::Off, reference(7)),
+                        (OcgStateChange::Toggle, reference(8)),
+                        (OcgStateChange::Toggle, reference(9)),
+                    ]
+                );
+                assert_eq!(reset.flags, 0);
+            }
+            sequence => panic!("unexpected actions {:?}", sequence),
+        }
+    }
+
+    #[test]
+    fn cyclic_next_chain() {
+        let mut writer = PdfWriter::new(PdfVersion::V1_7);
+
+        for (object_number, dict) in [
+            (5, &b"<< /S /Named /N /NextPage /Next 5 0 R >>"[..]),
+            (6, b"<< /S /Named /N /PrevPage /Next [ 7 0 R 6 0 R ] >>"),
+            (7, b"<< /S /Named /N /FirstPage /Next 6 0 R >>"),
+        ] {
+            let obj = Tokenizer::new(dict).next_object().unwrap().unwrap().value;
+            writer.insert(reference(object_number), obj);
+        }
+
+        let actions = Actions::from_obj(Object::Reference(reference(5)), &mut writer).unwrap();
+        assert_eq!(actions.action_types(), vec![ActionType::Named]);
+
+        let actions = Actions::from_obj(Object::Reference(reference(6)), &mut writer).unwrap();
+        assert_eq!(
+            actions.action_types(),
+            vec![ActionType::Named, ActionType::Named]
+        );
+    }
+}
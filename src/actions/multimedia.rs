@@ -0,0 +1,127 @@
+use crate::{
+    assert_empty,
+    error::PdfResult,
+    objects::{Dictionary, Object, Reference},
+    stream::Stream,
+    FromObj, Resolve,
+};
+
+use super::javascript::decode_script;
+
+/// A sound action plays a sound through the computer's speakers
+///
+/// Deprecated in PDF 2.0, in favour of rendition actions.
+#[derive(Debug, FromObj)]
+#[obj_type("Action")]
+pub struct SoundAction<'a> {
+    /// A sound object defining the sound that shall be played
+    #[field("Sound")]
+    pub(crate) sound: Stream<'a>,
+
+    /// The volume at which to play the sound, in the range -1.0 to 1.0
+    ///
+    /// Default value: 1.0
+    #[field("Volume", default = 1.0)]
+    pub volume: f32,
+
+    /// Whether to play the sound synchronously or asynchronously
+    ///
+    /// Default value: false
+    #[field("Synchronous", default = false)]
+    pub is_synchronous: bool,
+
+    /// Whether to repeat the sound indefinitely
+    ///
+    /// Default value: false
+    #[field("Repeat", default = false)]
+    pub repeat: bool,
+
+    /// Whether to mix this sound with any other sound already playing, rather
+    /// than stopping it
+    ///
+    /// Default value: false
+    #[field("Mix", default = false)]
+    pub mix: bool,
+}
+
+/// A movie action plays a movie in a floating window or within the
+/// annotation rectangle of a movie annotation
+///
+/// Deprecated in PDF 2.0, in favour of rendition actions.
+#[derive(Debug, FromObj)]
+#[obj_type("Action")]
+pub struct MovieAction {
+    /// The movie annotation identifying the movie that shall be played
+    #[field("Annotation")]
+    pub annotation: Option<Reference>,
+
+    /// The title of a movie annotation identifying the movie that shall be
+    /// played, if Annotation is absent
+    #[field("T")]
+    pub title: Option<String>,
+
+    /// The operation that shall be performed on the movie
+    ///
+    /// Default value: Play
+    #[field("Operation", default = MovieOperation::Play)]
+    pub operation: MovieOperation,
+}
+
+#[pdf_enum]
+pub enum MovieOperation {
+    /// Start playing the movie, or restart it if it is already playing
+    Play = "Play",
+
+    Stop = "Stop",
+
+    /// Pause a playing movie
+    Pause = "Pause",
+
+    /// Resume a paused movie
+    Resume = "Resume",
+}
+
+/// A rendition action controls the playing of multimedia content
+#[derive(Debug)]
+pub struct RenditionAction<'a> {
+    /// The rendition object to play, required when `operation` is 0 or 4
+    pub(crate) rendition: Option<Dictionary<'a>>,
+
+    /// The screen annotation with which the rendition is associated
+    pub annotation: Option<Reference>,
+
+    /// The operation to perform when the action is triggered: 0 to play the
+    /// rendition, 1 to stop, 2 to pause, 3 to resume, and 4 to play it or
+    /// resume it if it is paused
+    ///
+    /// Required if no script is present.
+    pub operation: Option<i32>,
+
+    /// A script executed in place of the operation
+    pub script: Option<String>,
+}
+
+impl<'a> FromObj<'a> for RenditionAction<'a> {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        dict.expect_type("Action", resolver, false)?;
+
+        let rendition = dict.get_dict("R", resolver)?;
+        let annotation = dict.get_reference("AN")?;
+        let operation = dict.get_integer("OP", resolver)?;
+        let script = dict
+            .get_object("JS", resolver)?
+            .map(|script| decode_script(script, resolver))
+            .transpose()?;
+
+        assert_empty(dict);
+
+        Ok(Self {
+            rendition,
+            annotation,
+            operation,
+            script,
+        })
+    }
+}
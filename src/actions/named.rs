@@ -0,0 +1,20 @@
+use crate::objects::Name;
+
+/// A named action executes an action predefined by the conforming reader
+#[derive(Debug, FromObj)]
+#[obj_type("Action")]
+pub struct NamedAction {
+    /// The name of the action that shall be performed
+    ///
+    /// Conforming readers may support additional, nonstandard named actions.
+    #[field("N")]
+    n: Name,
+}
+
+impl NamedAction {
+    /// The name of the action, such as NextPage, PrevPage, FirstPage or
+    /// LastPage
+    pub fn name(&self) -> &str {
+        &self.n.0
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{
+    assert_empty,
+    error::PdfResult,
+    objects::{Object, Reference},
+    FromObj, Resolve,
+};
+
+/// A set-OCG-state action sets the state of one or more optional content
+/// groups
+#[derive(Debug)]
+pub struct SetOcgStateAction {
+    /// The optional content groups whose state is set, with how, in the order
+    /// in which they are applied
+    pub state: Vec<(OcgStateChange, Reference)>,
+
+    /// Whether radio-button state relationships between optional content
+    /// groups are preserved when the states are applied
+    ///
+    /// Default value: true
+    pub preserve_radio_buttons: bool,
+}
+
+impl<'a> FromObj<'a> for SetOcgStateAction {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        dict.expect_type("Action", resolver, false)?;
+
+        // each name applies to the groups which follow it, until the next name
+        let mut change = None;
+        let mut state = Vec::new();
+
+        for obj in dict.expect_arr("State", resolver)? {
+            match obj {
+                Object::Name(name) => change = Some(OcgStateChange::from_str(&name)?),
+                Object::Reference(group) => match change {
+                    Some(change) => state.push((change, group)),
+                    None => anyhow::bail!("optional content group {:?} precedes any state", group),
+                },
+                found => anyhow::bail!(
+                    "expected state or optional content group, found {:?}",
+                    found
+                ),
+            }
+        }
+
+        let preserve_radio_buttons = dict.get_bool("PreserveRB", resolver)?.unwrap_or(true);
+
+        assert_empty(dict);
+
+        Ok(Self {
+            state,
+            preserve_radio_buttons,
+        })
+    }
+}
+
+#[pdf_enum]
+pub enum OcgStateChange {
+    On = "ON",
+    Off = "OFF",
+    Toggle = "Toggle",
+}
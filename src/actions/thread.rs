@@ -0,0 +1,25 @@
+use crate::file_specification::FileSpecification;
+
+use super::ActionTarget;
+
+/// A thread action jumps to a specified bead on an article thread, in the
+/// current or a different file
+#[derive(Debug, FromObj)]
+#[obj_type("Action")]
+pub struct ThreadAction<'a> {
+    /// The file containing the thread, if it is not the current file
+    #[field("F")]
+    pub(crate) f: Option<FileSpecification<'a>>,
+
+    /// The thread, either its thread dictionary, its index in the Threads array
+    /// of the catalog, or its title
+    #[field("D")]
+    pub thread: ActionTarget,
+
+    /// The bead to jump to, either its bead dictionary or its index in the
+    /// thread
+    ///
+    /// Default value: the first bead of the thread
+    #[field("B")]
+    pub bead: Option<ActionTarget>,
+}
@@ -0,0 +1,11 @@
+use crate::transition::Transition;
+
+/// A transition action controls drawing during a sequence of actions, using
+/// a transition dictionary to update the display
+#[derive(Debug, FromObj)]
+#[obj_type("Action")]
+pub struct TransitionAction {
+    /// The transition to use for the update of the display
+    #[field("Trans")]
+    pub transition: Transition,
+}
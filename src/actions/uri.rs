@@ -1,5 +1,6 @@
 /// A URI action causes a URI to be resolved
 #[derive(Debug, FromObj)]
+#[obj_type("Action")]
 pub struct UriAction {
     /// The uniform resource identifier to resolve, encoded in 7-bit ASCII
     #[field("URI")]
@@ -16,3 +17,9 @@ pub struct UriAction {
     #[field("IsMap", default = false)]
     is_map: bool,
 }
+
+impl UriAction {
+    pub(crate) fn uri(&self) -> &str {
+        &self.uri
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use crate::{
+    data_structures::{Matrix, Rectangle},
+    error::PdfResult,
+    objects::{Dictionary, Object},
+    xobject::{FormXObject, XObject},
+    FromObj, Resolve,
+};
+
+/// An appearance dictionary, specifying how an annotation shall be presented
+/// visually on the page in each of its appearances
+#[derive(Debug, Clone, FromObj)]
+pub(crate) struct AppearanceDictionary<'a> {
+    /// The annotation's normal appearance, used when the annotation is not
+    /// interacting with the user
+    #[field("N")]
+    pub normal: AppearanceEntry<'a>,
+
+    /// The annotation's rollover appearance, used when the user moves the cursor
+    /// into the annotation's active area without pressing the mouse button
+    ///
+    /// Default value: the value of the N entry
+    #[field("R")]
+    rollover: Option<AppearanceEntry<'a>>,
+
+    /// The annotation's down appearance, used when the mouse button is pressed
+    /// or held down within the annotation's active area
+    ///
+    /// Default value: the value of the N entry
+    #[field("D")]
+    down: Option<AppearanceEntry<'a>>,
+}
+
+/// A single appearance stream, or an appearance subdictionary mapping each of
+/// the annotation's appearance states to an appearance stream
+#[derive(Debug, Clone)]
+pub(crate) enum AppearanceEntry<'a> {
+    Stream(FormXObject<'a>),
+    States(HashMap<String, FormXObject<'a>>),
+}
+
+impl<'a> FromObj<'a> for AppearanceEntry<'a> {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let obj = resolver.resolve(obj)?;
+
+        Ok(match obj {
+            Object::Stream(..) => AppearanceEntry::Stream(appearance_stream(obj, resolver)?),
+            obj => AppearanceEntry::States(
+                resolver
+                    .assert_dict(obj)?
+                    .entries()
+                    .map(|(state, obj)| Ok((state, appearance_stream(obj, resolver)?)))
+                    .collect::<PdfResult<HashMap<String, FormXObject>>>()?,
+            ),
+        })
+    }
+}
+
+fn appearance_stream<'a>(
+    obj: Object<'a>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<FormXObject<'a>> {
+    match XObject::from_obj(obj, resolver)? {
+        XObject::Form(form) => Ok(form),
+        xobject => anyhow::bail!("expected form xobject appearance, found {:?}", xobject),
+    }
+}
+
+impl<'a> AppearanceEntry<'a> {
+    /// The appearance stream for the given appearance state
+    ///
+    /// An appearance subdictionary has no appearance without a state.
+    pub fn get(&self, state: Option<&str>) -> Option<&FormXObject<'a>> {
+        match self {
+            AppearanceEntry::Stream(form) => Some(form),
+            AppearanceEntry::States(states) => states.get(state?),
+        }
+    }
+}
+
+/// An appearance characteristics dictionary, used in constructing a dynamic
+/// appearance stream specifying the appearance of a widget annotation
+#[derive(Debug, Clone, FromObj)]
+pub(crate) struct AppearanceCharacteristics<'a> {
+    /// The number of degrees by which the widget annotation is rotated
+    /// counterclockwise relative to the page. The value shall be a multiple of
+    /// 90.
+    ///
+    /// Default value: 0
+    #[field("R", default = 0)]
+    pub r: i32,
+
+    /// An array of numbers in the range 0.0 to 1.0 specifying the colour of
+    /// the widget annotation's border. The number of array elements determines
+    /// the colour space in which the colour is defined
+    #[field("BC")]
+    bc: Option<Vec<f32>>,
+
+    /// An array of numbers in the range 0.0 to 1.0 specifying the colour of
+    /// the widget annotation's background
+    #[field("BG")]
+    bg: Option<Vec<f32>>,
+
+    /// The widget annotation's normal caption, which shall be displayed when
+    /// it is not interacting with the user
+    #[field("CA")]
+    ca: Option<String>,
+
+    /// The widget annotation's rollover caption, which shall be displayed when
+    /// the user rolls the cursor into its active area without pressing the
+    /// mouse button
+    #[field("RC")]
+    rc: Option<String>,
+
+    /// The widget annotation's alternate (down) caption, which shall be
+    /// displayed when the mouse button is pressed within its active area
+    #[field("AC")]
+    ac: Option<String>,
+
+    /// A code indicating where to position the text of the widget annotation's
+    /// caption relative to its icon
+    ///
+    /// Default value: 0 (caption only, no icon)
+    #[field("TP", default = 0)]
+    tp: i32,
+
+    // todo: the icons (I, RI, IX) and icon fit dictionary (IF) of pushbuttons
+    #[field]
+    other: Dictionary<'a>,
+}
+
+/// The matrix mapping the form coordinate space of an appearance stream onto
+/// the annotation rectangle, in default user space
+///
+/// The bounding box of the appearance is transformed by its form matrix, which
+/// may rotate or skew it, and the smallest upright rectangle enclosing the
+/// result is then scaled and translated to fit `rect`. The form matrix itself
+/// is not included, since it is concatenated when the form is painted.
+///
+/// Returns `None` if the transformed bounding box has no area, in which case
+/// the appearance cannot be mapped and is not painted.
+pub(crate) fn appearance_to_rect(
+    rect: Rectangle,
+    bbox: Rectangle,
+    form_matrix: Matrix,
+) -> Option<Matrix> {
+    let (rect_x, rect_y, rect_width, rect_height) = extent(rect, Matrix::identity());
+    let (bbox_x, bbox_y, bbox_width, bbox_height) = extent(bbox, form_matrix);
+
+    if bbox_width == 0.0 || bbox_height == 0.0 {
+        return None;
+    }
+
+    Some(
+        Matrix::new_translation(-bbox_x, -bbox_y)
+            * Matrix::new_scale(rect_width / bbox_width, rect_height / bbox_height)
+            * Matrix::new_translation(rect_x, rect_y),
+    )
+}
+
+/// The lower left corner, width, and height of the smallest upright rectangle
+/// enclosing `rect` after it has been transformed by `matrix`
+fn extent(rect: Rectangle, matrix: Matrix) -> (f32, f32, f32, f32) {
+    let corners = rect.corners().map(|corner| matrix * corner);
+
+    let min_x = corners.iter().map(|p| p.x).fold(f32::INFINITY, f32::min);
+    let min_y = corners.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+    let max_x = corners
+        .iter()
+        .map(|p| p.x)
+        .fold(f32::NEG_INFINITY, f32::max);
+    let max_y = corners
+        .iter()
+        .map(|p| p.y)
+        .fold(f32::NEG_INFINITY, f32::max);
+
+    (min_x, min_y, max_x - min_x, max_y - min_y)
+}
+
+/// The matrix which holds an annotation upright and at a fixed size as the page
+/// is rotated and zoomed, pivoting about the upper left corner of its
+/// rectangle
+///
+/// `rotation` is the clockwise rotation of the page in degrees, and `zoom` is
+/// the magnification at which the page is displayed. Either adjustment is
+/// skipped if the corresponding annotation flag is not set.
+pub(crate) fn fixed_orientation(
+    rect: Rectangle,
+    rotation: Option<i32>,
+    zoom: Option<f32>,
+) -> Matrix {
+    let (x, y, _, height) = extent(rect, Matrix::identity());
+    let (pivot_x, pivot_y) = (x, y + height);
+
+    let mut matrix = Matrix::new_translation(-pivot_x, -pivot_y);
+
+    if let Some(zoom) = zoom {
+        matrix *= Matrix::new_scale(1.0 / zoom, 1.0 / zoom);
+    }
+
+    // Counteract the clockwise page rotation by rotating counterclockwise
+    if let Some(rotation) = rotation {
+        matrix *= Matrix::new_rotation(rotation as f32);
+    }
+
+    matrix * Matrix::new_translation(pivot_x, pivot_y)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::geometry::Point;
+
+    use super::*;
+
+    fn assert_point_eq(a: Point, b: Point) {
+        assert!(
+            (a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4,
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn bbox_maps_onto_rect() {
+        let rect = Rectangle::new(100.0, 200.0, 150.0, 220.0);
+        let bbox = Rectangle::new(0.0, 0.0, 100.0, 40.0);
+
+        let matrix = appearance_to_rect(rect, bbox, Matrix::identity()).unwrap();
+
+        assert_point_eq(matrix * Point::new(0.0, 0.0), Point::new(100.0, 200.0));
+        assert_point_eq(matrix * Point::new(100.0, 40.0), Point::new(150.0, 220.0));
+    }
+
+    #[test]
+    fn rotated_bbox_maps_onto_rect() {
+        // A 90 degree rotation, as written for widgets with an MK rotation of 90
+        let form_matrix = Matrix::new(0.0, 1.0, -1.0, 0.0, 20.0, 0.0);
+        let rect = Rectangle::new(0.0, 0.0, 20.0, 100.0);
+        let bbox = Rectangle::new(0.0, 0.0, 100.0, 20.0);
+
+        let matrix = form_matrix * appearance_to_rect(rect, bbox, form_matrix).unwrap();
+
+        assert_point_eq(matrix * Point::new(0.0, 0.0), Point::new(20.0, 0.0));
+        assert_point_eq(matrix * Point::new(100.0, 20.0), Point::new(0.0, 100.0));
+    }
+
+    #[test]
+    fn skewed_bbox_fits_within_rect() {
+        let form_matrix = Matrix::new(1.0, 0.0, 0.5, 1.0, 0.0, 0.0);
+        let rect = Rectangle::new(0.0, 0.0, 30.0, 20.0);
+        let bbox = Rectangle::new(0.0, 0.0, 20.0, 20.0);
+
+        let matrix = form_matrix * appearance_to_rect(rect, bbox, form_matrix).unwrap();
+
+        assert_point_eq(matrix * Point::new(0.0, 0.0), Point::new(0.0, 0.0));
+        assert_point_eq(matrix * Point::new(20.0, 20.0), Point::new(30.0, 20.0));
+    }
+
+    #[test]
+    fn no_rotate_pivots_about_upper_left() {
+        let rect = Rectangle::new(10.0, 10.0, 30.0, 20.0);
+
+        let matrix = fixed_orientation(rect, Some(90), None);
+
+        assert_point_eq(matrix * Point::new(10.0, 20.0), Point::new(10.0, 20.0));
+        assert_point_eq(matrix * Point::new(30.0, 20.0), Point::new(10.0, 40.0));
+    }
+}
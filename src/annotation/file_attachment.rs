@@ -0,0 +1,96 @@
+use crate::{
+    error::PdfResult, file_specification::FileSpecification, objects::Dictionary, stream::Stream,
+    Resolve,
+};
+
+/// A file attachment annotation contains a reference to a file, which typically
+/// shall be embedded in the PDF file
+#[derive(Debug)]
+pub(crate) struct FileAttachmentAnnotation<'a> {
+    /// The file associated with this annotation
+    fs: FileSpecification<'a>,
+
+    /// The name of an icon that shall be used in displaying the annotation
+    ///
+    /// Default value: PushPin
+    name: FileAttachmentIcon,
+}
+
+impl<'a> FileAttachmentAnnotation<'a> {
+    pub fn from_dict(dict: &mut Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let fs = dict.expect("FS", resolver)?;
+        let name = dict
+            .get_name("Name", resolver)?
+            .map(FileAttachmentIcon::from_str)
+            .unwrap_or_default();
+
+        Ok(Self { fs, name })
+    }
+}
+
+#[derive(Debug, Default)]
+enum FileAttachmentIcon {
+    Graph,
+    #[default]
+    PushPin,
+    Paperclip,
+    Tag,
+    Other(String),
+}
+
+impl FileAttachmentIcon {
+    pub fn from_str(s: String) -> Self {
+        match s.as_ref() {
+            "Graph" | "GraphPushPin" => Self::Graph,
+            "PushPin" => Self::PushPin,
+            "Paperclip" | "PaperclipTag" => Self::Paperclip,
+            "Tag" => Self::Tag,
+            _ => Self::Other(s),
+        }
+    }
+}
+
+/// A sound annotation is analogous to a text annotation except that instead of a
+/// text note, it contains sound recorded from the computer's microphone or
+/// imported from a file
+#[derive(Debug)]
+pub(crate) struct SoundAnnotation<'a> {
+    /// A sound object defining the sound that shall be played when the annotation
+    /// is activated
+    sound: Stream<'a>,
+
+    /// The name of an icon that shall be used in displaying the annotation
+    ///
+    /// Default value: Speaker
+    name: SoundIcon,
+}
+
+impl<'a> SoundAnnotation<'a> {
+    pub fn from_dict(dict: &mut Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let sound = dict.expect("Sound", resolver)?;
+        let name = dict
+            .get_name("Name", resolver)?
+            .map(SoundIcon::from_str)
+            .unwrap_or_default();
+
+        Ok(Self { sound, name })
+    }
+}
+
+#[derive(Debug, Default)]
+enum SoundIcon {
+    #[default]
+    Speaker,
+    Mic,
+    Other(String),
+}
+
+impl SoundIcon {
+    pub fn from_str(s: String) -> Self {
+        match s.as_ref() {
+            "Speaker" => Self::Speaker,
+            "Mic" => Self::Mic,
+            _ => Self::Other(s),
+        }
+    }
+}
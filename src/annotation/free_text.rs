@@ -0,0 +1,82 @@
+use crate::{error::PdfResult, objects::Dictionary, Resolve};
+
+use super::{line::LineEnding, BorderEffect, BorderStyle};
+
+/// A free text annotation displays text directly on the page. Unlike an ordinary
+/// text annotation, a free text annotation has no open or closed state; instead of
+/// being displayed in a pop-up window, the text shall be always visible.
+#[derive(Debug)]
+pub(crate) struct FreeTextAnnotation {
+    /// The default appearance string that shall be used in formatting the text
+    da: String,
+
+    /// The justification that shall be used in displaying the annotation's text
+    ///
+    /// Default value: left-justified
+    q: Justification,
+
+    /// A default style string
+    ds: Option<String>,
+
+    /// An array of four or six numbers specifying a callout line attached to the
+    /// free text annotation. Six numbers [x1 y1 x2 y2 x3 y3] represent the starting,
+    /// knee point, and ending coordinates of the line in default user space. Four
+    /// numbers [x1 y1 x2 y2] represent the starting and ending coordinates of the line.
+    cl: Option<Vec<f32>>,
+
+    /// A border style dictionary specifying the line width and dash pattern that
+    /// shall be used in drawing the annotation's border
+    bs: Option<BorderStyle>,
+
+    /// A border effect dictionary used in conjunction with the border style
+    /// dictionary specified by the BS entry
+    be: Option<BorderEffect>,
+
+    /// A set of four numbers describing the numerical differences between two
+    /// rectangles: the Rect entry of the annotation and a rectangle contained
+    /// within that rectangle, in which the text is displayed
+    rd: Option<[f32; 4]>,
+
+    /// The line ending style that shall be used in drawing the callout line
+    /// specified in CL
+    ///
+    /// Default value: None
+    le: LineEnding,
+}
+
+impl FreeTextAnnotation {
+    pub fn from_dict<'a>(
+        dict: &mut Dictionary<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let da = dict.expect_string("DA", resolver)?;
+        let q = dict.get("Q", resolver)?.unwrap_or_default();
+        let ds = dict.get_string("DS", resolver)?;
+        let cl = dict.get("CL", resolver)?;
+        let bs = dict.get("BS", resolver)?;
+        let be = dict.get("BE", resolver)?;
+        let rd = dict.get("RD", resolver)?;
+        let le = dict.get("LE", resolver)?.unwrap_or_default();
+
+        Ok(Self {
+            da,
+            q,
+            ds,
+            cl,
+            bs,
+            be,
+            rd,
+            le,
+        })
+    }
+}
+
+/// The form of quadding (justification) that shall be used in displaying text
+#[pdf_enum(Integer)]
+#[derive(Default)]
+pub enum Justification {
+    #[default]
+    Left = 0,
+    Centered = 1,
+    Right = 2,
+}
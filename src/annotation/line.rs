@@ -0,0 +1,248 @@
+use crate::{
+    error::PdfResult,
+    measure::Measure,
+    objects::{Dictionary, Object},
+    FromObj, Resolve,
+};
+
+use super::{BorderEffect, BorderStyle};
+
+/// A line annotation displays a single straight line on the page. When opened,
+/// it shall display a pop-up window containing the text of the associated note.
+#[derive(Debug)]
+pub(crate) struct LineAnnotation {
+    /// An array of four numbers, [x1 y1 x2 y2], specifying the starting and ending
+    /// coordinates of the line in default user space
+    l: [f32; 4],
+
+    /// A border style dictionary specifying the width and dash pattern that shall
+    /// be used in drawing the line
+    bs: Option<BorderStyle>,
+
+    /// The line ending styles that shall be used in drawing the line, the first
+    /// at its start and the second at its end
+    ///
+    /// Default value: [/None /None]
+    le: [LineEnding; 2],
+
+    /// The interior colour with which to fill the annotation's line endings
+    ic: Option<Vec<f32>>,
+
+    /// The length of leader lines in default user space that extend from each
+    /// endpoint of the line perpendicular to the line itself. A positive value
+    /// shall mean that the leader lines appear in the direction that is clockwise
+    /// when traversing the line from its starting point to its ending point
+    ///
+    /// Default value: 0 (no leader lines)
+    ll: f32,
+
+    /// A non-negative number that shall represent the length of leader line
+    /// extensions that extend from the line proper 180 degrees from the leader lines
+    ///
+    /// Default value: 0 (no leader line extensions)
+    lle: f32,
+
+    /// If true, the text specified by the Contents or RC entries shall be replicated
+    /// as a caption in the appearance of the line
+    ///
+    /// Default value: false
+    cap: bool,
+
+    /// A non-negative number that shall represent the length of the leader line
+    /// offset, which is the amount of empty space between the endpoints of the
+    /// annotation and the beginning of the leader lines
+    ///
+    /// Default value: 0
+    llo: f32,
+
+    /// The annotation's caption positioning
+    ///
+    /// Default value: Inline
+    cp: CaptionPositioning,
+
+    /// A measure dictionary that shall specify the scale and units that apply to
+    /// the line annotation
+    measure: Option<Measure>,
+
+    /// The offset of the caption text from its normal position, horizontally along
+    /// the annotation line and vertically perpendicular to it
+    ///
+    /// Default value: [0 0] (no offset from normal positioning)
+    co: [f32; 2],
+}
+
+impl LineAnnotation {
+    pub fn from_dict<'a>(
+        dict: &mut Dictionary<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let l = dict.expect("L", resolver)?;
+        let bs = dict.get("BS", resolver)?;
+        let le = dict.get("LE", resolver)?.unwrap_or_default();
+        let ic = dict.get("IC", resolver)?;
+        let ll = dict.get_number("LL", resolver)?.unwrap_or(0.0);
+        let lle = dict.get_number("LLE", resolver)?.unwrap_or(0.0);
+        let cap = dict.get_bool("Cap", resolver)?.unwrap_or(false);
+        let llo = dict.get_number("LLO", resolver)?.unwrap_or(0.0);
+        let cp = dict.get("CP", resolver)?.unwrap_or_default();
+        let measure = dict.get("Measure", resolver)?;
+        let co = dict.get("CO", resolver)?.unwrap_or([0.0, 0.0]);
+
+        Ok(Self {
+            l,
+            bs,
+            le,
+            ic,
+            ll,
+            lle,
+            cap,
+            llo,
+            cp,
+            measure,
+            co,
+        })
+    }
+}
+
+/// Polygon annotations display closed polygons on the page, and polyline
+/// annotations display open polylines, which are similar to polygons except
+/// that their first and last vertices are not implicitly connected
+#[derive(Debug)]
+pub(crate) struct PolygonAnnotation {
+    /// An array of numbers specifying the alternating horizontal and vertical
+    /// coordinates, respectively, of each vertex, in default user space
+    vertices: Option<Vec<f32>>,
+
+    /// (PDF 2.0) An array of n arrays, each supplying the operands for a path
+    /// building operator (m, l or c), used in place of Vertices to draw curves
+    path: Option<Vec<Vec<f32>>>,
+
+    /// For polyline annotations, the line ending styles that shall be used in
+    /// drawing the first and last segments
+    ///
+    /// Default value: [/None /None]
+    le: [LineEnding; 2],
+
+    /// A border style dictionary specifying the width and dash pattern that shall
+    /// be used in drawing the line
+    bs: Option<BorderStyle>,
+
+    /// The interior colour with which to fill the polygon or the polyline's line
+    /// endings
+    ic: Option<Vec<f32>>,
+
+    /// A border effect dictionary describing an effect applied to the border
+    /// described by the BS entry
+    be: Option<BorderEffect>,
+
+    /// A measure dictionary that shall specify the scale and units that apply to
+    /// the annotation
+    measure: Option<Measure>,
+}
+
+impl PolygonAnnotation {
+    pub fn from_dict<'a>(
+        dict: &mut Dictionary<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let vertices = dict.get("Vertices", resolver)?;
+        let path = dict.get("Path", resolver)?;
+
+        anyhow::ensure!(
+            vertices.is_some() || path.is_some(),
+            "polygon annotation must have either Vertices or Path"
+        );
+
+        let le = dict.get("LE", resolver)?.unwrap_or_default();
+        let bs = dict.get("BS", resolver)?;
+        let ic = dict.get("IC", resolver)?;
+        let be = dict.get("BE", resolver)?;
+        let measure = dict.get("Measure", resolver)?;
+
+        Ok(Self {
+            vertices,
+            path,
+            le,
+            bs,
+            ic,
+            be,
+            measure,
+        })
+    }
+}
+
+/// The style of the shape drawn at an end of a line
+#[derive(Debug, Default)]
+pub(crate) enum LineEnding {
+    /// A square filled with the annotation's interior colour, if any
+    Square,
+
+    /// A circle filled with the annotation's interior colour, if any
+    Circle,
+
+    /// A diamond shape filled with the annotation's interior colour, if any
+    Diamond,
+
+    /// Two short lines meeting in an acute angle to form an open arrowhead
+    OpenArrow,
+
+    /// Two short lines meeting in an acute angle as in the OpenArrow style and
+    /// connected by a third line to form a triangular closed arrowhead filled with
+    /// the annotation's interior colour, if any
+    ClosedArrow,
+
+    /// No line ending
+    #[default]
+    None,
+
+    /// A short line at the endpoint perpendicular to the line itself
+    Butt,
+
+    /// Two short lines in the reverse direction from OpenArrow
+    ReverseOpenArrow,
+
+    /// A triangular closed arrowhead in the reverse direction from ClosedArrow
+    ReverseClosedArrow,
+
+    /// A short line at the endpoint approximately 30 degrees clockwise from
+    /// perpendicular to the line itself
+    Slash,
+
+    Other(String),
+}
+
+impl LineEnding {
+    pub fn from_str(s: String) -> Self {
+        match s.as_ref() {
+            "Square" => Self::Square,
+            "Circle" => Self::Circle,
+            "Diamond" => Self::Diamond,
+            "OpenArrow" => Self::OpenArrow,
+            "ClosedArrow" => Self::ClosedArrow,
+            "None" => Self::None,
+            "Butt" => Self::Butt,
+            "ROpenArrow" => Self::ReverseOpenArrow,
+            "RClosedArrow" => Self::ReverseClosedArrow,
+            "Slash" => Self::Slash,
+            _ => Self::Other(s),
+        }
+    }
+}
+
+impl<'a> FromObj<'a> for LineEnding {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        Ok(LineEnding::from_str(resolver.assert_name(obj)?))
+    }
+}
+
+/// The positioning of a line annotation's caption
+#[pdf_enum]
+#[derive(Default)]
+pub(crate) enum CaptionPositioning {
+    /// The caption shall be centered inside the line
+    #[default]
+    Inline = "Inline",
+
+    /// The caption shall be on top of the line
+    Top = "Top",
+}
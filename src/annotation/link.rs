@@ -1,5 +1,6 @@
 use crate::{
-    actions::{Actions, UriAction},
+    actions::{ActionType, Actions, UriAction},
+    data_structures::Rectangle,
     destination::Destination,
 };
 
@@ -55,6 +56,61 @@ pub(crate) struct LinkAnnotation<'a> {
     bs: Option<BorderStyle>,
 }
 
+impl<'a> LinkAnnotation<'a> {
+    /// The destination displayed when the link is activated, given either
+    /// directly or by a go-to action
+    pub(crate) fn destination(&self) -> Option<&Destination> {
+        self.dest
+            .as_ref()
+            .or_else(|| self.a.as_ref()?.destination())
+    }
+
+    /// The URI resolved when the link is activated
+    pub(crate) fn uri(&self) -> Option<&str> {
+        self.a.as_ref()?.uri()
+    }
+
+    /// The type of the action performed when the link is activated, if any
+    pub(crate) fn action_type(&self) -> Option<ActionType> {
+        self.a.as_ref().map(Actions::action_type)
+    }
+}
+
+/// A link on a page, with the target which is displayed or resolved when it is
+/// activated
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    /// The region of the page, in default user space, in which the link is
+    /// activated
+    pub rect: Rectangle,
+    pub target: LinkTarget,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkTarget {
+    /// A location within this document
+    Page {
+        /// The zero-based index of the page
+        index: usize,
+
+        /// The region of the page, in default user space, that should be
+        /// displayed
+        view: Rectangle,
+    },
+
+    /// A URI, such as that of a web page
+    Uri(String),
+
+    /// An action other than going to a destination in this document or
+    /// resolving a URI, such as opening another document or running a script
+    Action(ActionType),
+
+    /// A destination which does not exist, because it refers to an object
+    /// which is not a page of this document or to a named destination which is
+    /// not defined
+    Missing,
+}
+
 #[pdf_enum]
 #[derive(Default)]
 pub enum HighlightingMode {
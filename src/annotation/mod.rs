@@ -1,23 +1,48 @@
 use crate::{
-    data_structures::Rectangle,
+    data_structures::{Matrix, Rectangle},
     date::Date,
     error::{ParseError, PdfResult},
     objects::{Dictionary, Object, Reference},
     optional_content::OptionalContent,
+    page::PageObject,
     resources::graphics_state_parameters::LineDashPattern,
-    FromObj, Resolve,
+    xobject::FormXObject,
+    FromObj, Parser, Resolve,
 };
 
+use appearance::{appearance_to_rect, fixed_orientation, AppearanceDictionary};
+use link::LinkAnnotation;
+use redact::RedactAnnotation;
 use subtype::{AnnotationSubType, AnnotationSubTypeKind};
+use text_markup::TextMarkupAnnotation;
 
+pub use self::{
+    link::{Link, LinkTarget},
+    prepress::{PrepressMark, PrinterMark, TrapNetwork},
+    text_markup::{TextMarkup, TextMarkupKind},
+};
+pub(crate) use self::{prepress::collect_prepress_marks, text_markup::collect_text_markup};
+
+mod appearance;
+mod file_attachment;
+mod free_text;
+mod line;
 mod link;
+mod multimedia;
+mod popup;
+mod prepress;
+mod redact;
+mod shape;
+mod stamp;
 mod state;
 mod subtype;
 mod text;
+mod text_markup;
+mod widget;
 
 #[derive(Debug)]
 pub struct Annotation<'a> {
-    base: BaseAnnotation,
+    base: BaseAnnotation<'a>,
     sub_type: AnnotationSubType<'a>,
 }
 
@@ -32,8 +57,141 @@ impl<'a> FromObj<'a> for Annotation<'a> {
     }
 }
 
+impl<'a> Annotation<'a> {
+    pub(crate) fn flags(&self) -> AnnotationFlags {
+        self.base.flags
+    }
+
+    pub(crate) fn optional_content(&self) -> Option<&OptionalContent> {
+        self.base.oc.as_ref()
+    }
+
+    /// The location of the annotation on the page, in default user space
+    pub(crate) fn rect(&self) -> Rectangle {
+        self.base.rect
+    }
+
+    pub(crate) fn link(&self) -> Option<&LinkAnnotation<'a>> {
+        match &self.sub_type {
+            AnnotationSubType::Link(link) => Some(link),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn redaction(&self) -> Option<&RedactAnnotation<'a>> {
+        match &self.sub_type {
+            AnnotationSubType::Redact(redact) => Some(redact),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn is_widget(&self) -> bool {
+        matches!(self.sub_type, AnnotationSubType::Widget(..))
+    }
+
+    /// For a pop-up annotation, the annotation whose text it displays
+    pub(crate) fn popup_parent(&self) -> Option<Reference> {
+        match &self.sub_type {
+            AnnotationSubType::Popup(popup) => popup.parent(),
+            _ => None,
+        }
+    }
+
+    fn text_markup(&self) -> Option<(TextMarkupKind, &TextMarkupAnnotation)> {
+        match &self.sub_type {
+            AnnotationSubType::Highlight(markup) => Some((TextMarkupKind::Highlight, markup)),
+            AnnotationSubType::Underline(markup) => Some((TextMarkupKind::Underline, markup)),
+            AnnotationSubType::Squiggly(markup) => Some((TextMarkupKind::Squiggly, markup)),
+            AnnotationSubType::StrikeOut(markup) => Some((TextMarkupKind::StrikeOut, markup)),
+            _ => None,
+        }
+    }
+
+    /// The comment of a markup annotation, whose dictionary is `reference`
+    fn comment(&self, reference: Reference) -> Option<Comment> {
+        let markup = self.base.markup_dict.as_ref()?;
+
+        Some(Comment {
+            reference,
+            contents: self.base.contents.clone(),
+            author: markup.t.clone(),
+            subject: markup.subj.clone(),
+            creation_date: markup.creation_date.clone(),
+            modified: self.base.last_modified.clone(),
+            in_reply_to: markup.irt,
+        })
+    }
+
+    /// The annotation to which this one is a reply, as opposed to one with
+    /// which it is grouped
+    fn replies_to(&self) -> Option<Reference> {
+        let markup = self.base.markup_dict.as_ref()?;
+
+        match markup.rt {
+            None | Some(ReplyType::R) => markup.irt,
+            Some(ReplyType::Group) => None,
+        }
+    }
+
+    /// The appearance stream used when the annotation is not interacting with
+    /// the user, selected by the annotation's appearance state if it has more
+    /// than one
+    pub(crate) fn normal_appearance(&self) -> Option<&FormXObject<'a>> {
+        self.base
+            .ap
+            .as_ref()?
+            .normal
+            .get(self.base.appearance_stream_name.as_deref())
+    }
+
+    /// The transformation from the form space of the normal appearance stream
+    /// to default user space, excluding the form matrix of the appearance
+    /// stream, or `None` if the annotation has no normal appearance which can
+    /// be mapped onto its rectangle
+    ///
+    /// `page_rotation` is the clockwise rotation of the page in degrees and
+    /// `zoom` the magnification at which it is displayed, which the
+    /// appearance counteracts if the NoRotate or NoZoom flags are set.
+    ///
+    /// Writers generally record the rotation of a widget (the R entry of its
+    /// appearance characteristics dictionary) in the form matrix of its
+    /// appearance stream. Where the form matrix is the identity, the appearance
+    /// was drawn unrotated and the rotation is applied here instead.
+    pub(crate) fn appearance_matrix(&self, page_rotation: i32, zoom: f32) -> Option<Matrix> {
+        let form = self.normal_appearance()?;
+
+        let widget_rotation = match &self.sub_type {
+            AnnotationSubType::Widget(widget) if form.matrix == Matrix::identity() => {
+                widget.mk.as_ref().map_or(0, |mk| mk.r)
+            }
+            _ => 0,
+        };
+
+        let rotation = Matrix::new_rotation(widget_rotation as f32);
+
+        let mut matrix =
+            rotation * appearance_to_rect(self.base.rect, form.bbox, form.matrix * rotation)?;
+
+        // Text annotations behave as if NoZoom and NoRotate were always set
+        let is_text = self.base.subtype == AnnotationSubTypeKind::Text;
+
+        let no_rotate = (self.base.flags.is_no_rotate() || is_text) && page_rotation % 360 != 0;
+        let no_zoom = (self.base.flags.is_no_zoom() || is_text) && zoom != 1.0;
+
+        if no_rotate || no_zoom {
+            matrix *= fixed_orientation(
+                self.base.rect,
+                no_rotate.then_some(page_rotation),
+                no_zoom.then_some(zoom),
+            );
+        }
+
+        Some(matrix)
+    }
+}
+
 #[derive(Debug)]
-pub(crate) struct BaseAnnotation {
+pub(crate) struct BaseAnnotation<'a> {
     subtype: AnnotationSubTypeKind,
 
     /// The annotation rectangle, defining the location of the
@@ -71,7 +229,7 @@ pub(crate) struct BaseAnnotation {
     /// An appearance dictionary specifying how the annotation shall be presented
     /// visually on the page. Individual annotation handlers may ignore this entry
     /// and provide their own appearances.
-    ap: Option<Appearance>,
+    ap: Option<AppearanceDictionary<'a>>,
 
     /// The annotation's appearance state, which selects the applicable appearance
     /// stream from an appearance subdictionary
@@ -199,6 +357,29 @@ struct MarkupAnnotation {
     ex_data: Option<ExternalDataDictionary>,
 }
 
+/// The text and authorship of a markup annotation, such as a review comment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// The annotation dictionary
+    pub reference: Reference,
+
+    pub contents: Option<String>,
+
+    /// The user who added the annotation
+    pub author: Option<String>,
+
+    pub subject: Option<String>,
+
+    pub creation_date: Option<Date>,
+
+    /// The date and time when the annotation was most recently modified, which
+    /// should be, but is not always, a PDF date string
+    pub modified: Option<String>,
+
+    /// The annotation to which this one replies, or with which it is grouped
+    pub in_reply_to: Option<Reference>,
+}
+
 // todo: this seems to only be used for 3d stuff
 #[derive(Debug, FromObj)]
 #[obj_type("ExData")]
@@ -245,13 +426,10 @@ impl MarkupAnnotation {
     }
 }
 
-impl BaseAnnotation {
+impl<'a> BaseAnnotation<'a> {
     const TYPE: &'static str = "Annot";
 
-    pub fn from_dict<'a>(
-        dict: &mut Dictionary<'a>,
-        resolver: &mut dyn Resolve<'a>,
-    ) -> PdfResult<Self> {
+    pub fn from_dict(dict: &mut Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
         dict.expect_type(Self::TYPE, resolver, false)?;
 
         let subtype = AnnotationSubTypeKind::from_str(&dict.expect_name("Subtype", resolver)?)?;
@@ -265,7 +443,7 @@ impl BaseAnnotation {
             .get_integer("F", resolver)?
             .map(AnnotationFlags::from_integer)
             .unwrap_or_default();
-        let ap = None;
+        let ap = dict.get("AP", resolver)?;
         let appearance_stream_name = dict.get_name("AS", resolver)?;
         let border = dict
             .get_arr("Border", resolver)?
@@ -419,8 +597,6 @@ impl AnnotationFlags {
     }
 }
 
-#[derive(Debug)]
-struct Appearance;
 #[derive(Debug)]
 struct RichTextString;
 
@@ -503,6 +679,37 @@ impl BorderStyleKind {
     }
 }
 
+/// A border effect dictionary specifies an effect that shall be applied to the
+/// border of the annotation
+#[derive(Debug, FromObj)]
+pub struct BorderEffect {
+    /// The border effect to apply
+    ///
+    /// Default value: S
+    #[field("S", default = BorderEffectKind::default())]
+    s: BorderEffectKind,
+
+    /// The intensity of the effect, in the range 0 to 2, which is only
+    /// significant for the cloudy border effect
+    ///
+    /// Default value: 0
+    #[field("I", default = 0.0)]
+    i: f32,
+}
+
+#[pdf_enum]
+#[derive(Default)]
+enum BorderEffectKind {
+    /// No effect: the border shall be as described by the annotation
+    /// dictionary's BS entry
+    #[default]
+    None = "S",
+
+    /// The border should appear "cloudy". The width and dash array specified by
+    /// BS shall be honoured.
+    Cloudy = "C",
+}
+
 #[derive(Debug)]
 struct Border {
     horizontal_corner_radius: u32,
@@ -553,3 +760,98 @@ impl Default for Border {
         }
     }
 }
+
+impl<'a> Parser<'a> {
+    /// The links on a page, with each destination within this document resolved
+    /// to the index of its page and the region of that page which it displays
+    ///
+    /// Link annotations which neither have a destination nor perform an
+    /// action are omitted.
+    pub fn page_links(&mut self, page: &PageObject<'a>) -> PdfResult<Vec<Link>> {
+        let annotations = match self.page_annotations(page)? {
+            Some(annotations) => annotations,
+            None => return Ok(Vec::new()),
+        };
+
+        let pages = self.pages();
+        let page_indices = self.page_indices();
+
+        let mut links = Vec::new();
+
+        for annotation in &annotations {
+            let link = match annotation.link() {
+                Some(link) => link,
+                None => continue,
+            };
+
+            let target = if let Some(dest) = link.destination() {
+                match self.resolve_explicit_destination(dest)? {
+                    Some(dest) => match page_indices.get(&dest.page_ref()) {
+                        Some(&index) => {
+                            let page_box = match pages[index].crop_box() {
+                                Some(crop_box) => crop_box,
+                                None => match pages[index].media_box() {
+                                    Some(media_box) => media_box,
+                                    None => anyhow::bail!("page {} has no media box", index),
+                                },
+                            };
+
+                            LinkTarget::Page {
+                                index,
+                                view: dest.view(page_box),
+                            }
+                        }
+                        None => LinkTarget::Missing,
+                    },
+                    None => LinkTarget::Missing,
+                }
+            } else if let Some(uri) = link.uri() {
+                LinkTarget::Uri(uri.to_owned())
+            } else if let Some(action_type) = link.action_type() {
+                LinkTarget::Action(action_type)
+            } else {
+                continue;
+            };
+
+            links.push(Link {
+                rect: annotation.rect(),
+                target,
+            });
+        }
+
+        Ok(links)
+    }
+
+    /// The highlight, underline, squiggly underline and strikeout annotations
+    /// of a page, each with the comments which reply to it
+    pub fn page_text_markup(&mut self, page: &PageObject<'a>) -> PdfResult<Vec<TextMarkup>> {
+        let annotations = page
+            .annots
+            .iter()
+            .flatten()
+            .map(|&annot| {
+                let obj = self.lexer.lex_object_from_reference(annot)?;
+
+                Ok((annot, Annotation::from_obj(obj, &mut self.lexer)?))
+            })
+            .collect::<PdfResult<Vec<_>>>()?;
+
+        Ok(collect_text_markup(&annotations))
+    }
+
+    /// The printer's marks and trap network annotations of a page
+    pub fn page_prepress_marks(&mut self, page: &PageObject<'a>) -> PdfResult<Vec<PrepressMark>> {
+        let annotations = page
+            .annots
+            .iter()
+            .flatten()
+            .map(|&annot| {
+                let obj = self.lexer.lex_object_from_reference(annot)?;
+
+                Ok((annot, Annotation::from_obj(obj, &mut self.lexer)?))
+            })
+            .collect::<PdfResult<Vec<_>>>()?;
+
+        Ok(collect_prepress_marks(&annotations))
+    }
+}
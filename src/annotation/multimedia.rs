@@ -0,0 +1,132 @@
+use crate::{
+    actions::{Actions, AnnotationAdditionalActions},
+    data_structures::Rectangle,
+    error::PdfResult,
+    objects::{Dictionary, Object},
+    Resolve,
+};
+
+use super::appearance::AppearanceCharacteristics;
+
+/// A movie annotation contains animated graphics and sound to be presented on
+/// the computer screen and through the speakers
+///
+/// Deprecated in PDF 2.0, in favour of screen annotations with rendition actions.
+#[derive(Debug)]
+pub(crate) struct MovieAnnotation<'a> {
+    /// The title of the movie annotation. Movie actions may use this title to
+    /// reference the movie annotation.
+    t: Option<String>,
+
+    /// A movie dictionary that shall describe the movie's static characteristics
+    movie: Dictionary<'a>,
+
+    /// A flag or dictionary specifying whether and how to play the movie when the
+    /// annotation is activated. If this value is a dictionary, it shall be a movie
+    /// activation dictionary specifying how to play the movie. If the value is the
+    /// boolean true, the movie shall be played using default activation parameters.
+    ///
+    /// Default value: true
+    a: Option<Object<'a>>,
+}
+
+impl<'a> MovieAnnotation<'a> {
+    pub fn from_dict(dict: &mut Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let t = dict.get_string("T", resolver)?;
+        let movie = dict.expect_dict("Movie", resolver)?;
+        let a = dict.get_object("A", resolver)?;
+
+        Ok(Self { t, movie, a })
+    }
+}
+
+/// A screen annotation specifies a region of a page upon which media clips may be
+/// played. It also serves as an object from which actions can be triggered.
+#[derive(Debug)]
+pub(crate) struct ScreenAnnotation<'a> {
+    /// The title of the screen annotation
+    t: Option<String>,
+
+    /// An appearance characteristics dictionary
+    mk: Option<AppearanceCharacteristics<'a>>,
+
+    /// An action that shall be performed when the annotation is activated
+    a: Option<Actions<'a>>,
+
+    /// An additional-actions dictionary defining the annotation's behaviour in
+    /// response to various trigger events
+    aa: Option<AnnotationAdditionalActions<'a>>,
+}
+
+impl<'a> ScreenAnnotation<'a> {
+    pub fn from_dict(dict: &mut Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let t = dict.get_string("T", resolver)?;
+        let mk = dict.get("MK", resolver)?;
+        let a = dict.get("A", resolver)?;
+        let aa = dict.get("AA", resolver)?;
+
+        Ok(Self { t, mk, a, aa })
+    }
+}
+
+/// A 3D annotation is the means by which 3D artwork shall be represented in a PDF
+/// document
+#[derive(Debug)]
+pub(crate) struct ThreeDAnnotation<'a> {
+    /// A 3D stream or 3D reference dictionary that specifies the 3D artwork to be
+    /// shown
+    dd: Object<'a>,
+
+    /// An object that specifies the default initial view of the 3D artwork that
+    /// shall be used when the annotation is activated
+    dv: Option<Object<'a>>,
+
+    /// An activation dictionary that defines the times at which the annotation
+    /// shall be activated and deactivated and the state of the 3D artwork
+    /// instance at those times
+    a: Option<Dictionary<'a>>,
+
+    /// The primary use of the 3D annotation. If true, it is intended to be
+    /// interactive; if false, it is intended to be manipulated programmatically
+    ///
+    /// Default value: true
+    i: bool,
+
+    /// The 3D view box, which is the rectangular area in which the 3D artwork
+    /// shall be drawn, in the annotation's target coordinate system
+    b: Option<Rectangle>,
+}
+
+impl<'a> ThreeDAnnotation<'a> {
+    pub fn from_dict(dict: &mut Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let dd = dict.expect_object("3DD", resolver)?;
+        let dv = dict.get_object("3DV", resolver)?;
+        let a = dict.get_dict("3DA", resolver)?;
+        let i = dict.get_bool("3DI", resolver)?.unwrap_or(true);
+        let b = dict.get("3DB", resolver)?;
+
+        Ok(Self { dd, dv, a, i, b })
+    }
+}
+
+/// (PDF 2.0) A RichMedia annotation presents interactive media, such as video or
+/// 3D content, described by its content and settings dictionaries
+#[derive(Debug)]
+pub(crate) struct RichMediaAnnotation<'a> {
+    /// A RichMedia content dictionary that stores the media and the configurations
+    /// with which it may be presented
+    content: Dictionary<'a>,
+
+    /// A RichMedia settings dictionary that stores the conditions under which the
+    /// annotation is activated and deactivated
+    settings: Option<Dictionary<'a>>,
+}
+
+impl<'a> RichMediaAnnotation<'a> {
+    pub fn from_dict(dict: &mut Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let content = dict.expect_dict("RichMediaContent", resolver)?;
+        let settings = dict.get_dict("RichMediaSettings", resolver)?;
+
+        Ok(Self { content, settings })
+    }
+}
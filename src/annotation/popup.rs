@@ -0,0 +1,37 @@
+use crate::{
+    error::PdfResult,
+    objects::{Dictionary, Reference},
+    Resolve,
+};
+
+/// A pop-up annotation displays text in a pop-up window for entry and editing.
+/// It shall not appear alone but is associated with a markup annotation, its
+/// parent annotation, and shall be used for editing the parent's text.
+#[derive(Debug)]
+pub(crate) struct PopupAnnotation {
+    /// The parent annotation with which this pop-up annotation shall be
+    /// associated
+    parent: Option<Reference>,
+
+    /// A flag specifying whether the pop-up annotation shall initially be
+    /// displayed open
+    ///
+    /// Default value: false (closed)
+    is_open: bool,
+}
+
+impl PopupAnnotation {
+    pub fn from_dict<'a>(
+        dict: &mut Dictionary<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let parent = dict.get_reference("Parent")?;
+        let is_open = dict.get_bool("Open", resolver)?.unwrap_or(false);
+
+        Ok(Self { parent, is_open })
+    }
+
+    pub fn parent(&self) -> Option<Reference> {
+        self.parent
+    }
+}
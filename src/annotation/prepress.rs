@@ -0,0 +1,210 @@
+use crate::{
+    data_structures::{Matrix, Rectangle},
+    error::PdfResult,
+    objects::{Dictionary, Object, Reference},
+    Resolve,
+};
+
+use super::{subtype::AnnotationSubType, Annotation};
+
+/// A printer's mark or trap network of a page, the annotations used in
+/// preparing a document for print production
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrepressMark {
+    PrinterMark(PrinterMark),
+    TrapNetwork(TrapNetwork),
+}
+
+/// A graphic symbol or text added to a page for production personnel, such as
+/// a registration target or colour bar
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrinterMark {
+    /// The annotation dictionary
+    pub reference: Reference,
+
+    /// The location of the mark on the page, in default user space
+    pub rect: Rectangle,
+
+    /// The kind of mark, such as ColorBar or RegistrationTarget
+    pub name: Option<String>,
+}
+
+/// The trapping of a page, which overlaps adjoining colours to hide gaps
+/// caused by misregistration of the printing plates
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrapNetwork {
+    /// The annotation dictionary
+    pub reference: Reference,
+
+    pub rect: Rectangle,
+
+    /// The date and time when the trap network was most recently modified,
+    /// which should be a PDF date string
+    pub last_modified: Option<String>,
+
+    /// The objects of the page description which, if changed, could affect
+    /// the trapping, and so invalidate the trap network
+    pub version: Vec<Reference>,
+
+    /// The appearance state of each annotation of the page when the trap
+    /// network was generated, in the order of the page's Annots array
+    pub annotation_states: Vec<Option<String>>,
+
+    /// The fonts which were replaced by substitutes when generating the trap
+    /// network
+    pub faux_fonts: Vec<Reference>,
+}
+
+/// The printer's marks and trap networks among the annotations of a page, in
+/// the order in which they appear
+pub(crate) fn collect_prepress_marks(annotations: &[(Reference, Annotation)]) -> Vec<PrepressMark> {
+    annotations
+        .iter()
+        .filter_map(|(reference, annotation)| match &annotation.sub_type {
+            AnnotationSubType::PrinterMark(mark) => Some(PrepressMark::PrinterMark(PrinterMark {
+                reference: *reference,
+                rect: annotation.rect(),
+                name: mark.mn.clone(),
+            })),
+            AnnotationSubType::TrapNet(trap_net) => Some(PrepressMark::TrapNetwork(TrapNetwork {
+                reference: *reference,
+                rect: annotation.rect(),
+                last_modified: trap_net.last_modified.clone(),
+                version: trap_net.version.clone().unwrap_or_default(),
+                annotation_states: trap_net.annotation_states(),
+                faux_fonts: trap_net.font_fauxing.clone().unwrap_or_default(),
+            })),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A printer's mark annotation represents a graphic symbol or text added to a page
+/// to assist production personnel in identifying components of a multiple-plate
+/// job and maintaining consistent output during production
+#[derive(Debug)]
+pub(crate) struct PrinterMarkAnnotation {
+    /// An arbitrary name identifying the type of printer's mark, such as
+    /// ColorBar or RegistrationTarget
+    mn: Option<String>,
+}
+
+impl PrinterMarkAnnotation {
+    pub fn from_dict<'a>(
+        dict: &mut Dictionary<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let mn = dict.get_name("MN", resolver)?;
+
+        Ok(Self { mn })
+    }
+}
+
+/// A trap network annotation defines the trapping characteristics for a page of
+/// a PDF document
+///
+/// Deprecated in PDF 2.0.
+#[derive(Debug)]
+pub(crate) struct TrapNetAnnotation<'a> {
+    /// The date and time when the trap network was most recently modified
+    ///
+    /// This entry is required if Version and AnnotStates are absent.
+    last_modified: Option<String>,
+
+    /// An unordered array of all objects present in the page description at the
+    /// time the trap networks were generated and that, if changed, could affect
+    /// the appearance of the page
+    ///
+    /// This entry shall be present if AnnotStates is present.
+    version: Option<Vec<Reference>>,
+
+    /// An array of name objects representing the appearance states (value of the
+    /// AS entry) for annotations associated with the page. The appearance states
+    /// shall be listed in the same order as the annotations in the page's Annots
+    /// array, with null for annotations that have no AS entry.
+    annot_states: Option<Vec<Object<'a>>>,
+
+    /// An array of font dictionaries representing fonts that were fauxed
+    /// (replaced by substitute fonts) during the generation of trap networks for
+    /// the page
+    font_fauxing: Option<Vec<Reference>>,
+}
+
+impl<'a> TrapNetAnnotation<'a> {
+    pub fn from_dict(dict: &mut Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let last_modified = dict.get_string("LastModified", resolver)?;
+        let version = dict.get("Version", resolver)?;
+        let annot_states = dict.get("AnnotStates", resolver)?;
+        let font_fauxing = dict.get("FontFauxing", resolver)?;
+
+        anyhow::ensure!(
+            annot_states.is_none() || version.is_some(),
+            "trap network annotation with AnnotStates must have a Version"
+        );
+
+        Ok(Self {
+            last_modified,
+            version,
+            annot_states,
+            font_fauxing,
+        })
+    }
+
+    /// The AnnotStates entry, with `None` for annotations without an
+    /// appearance state
+    fn annotation_states(&self) -> Vec<Option<String>> {
+        self.annot_states
+            .iter()
+            .flatten()
+            .map(|state| match state {
+                Object::Name(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// A watermark annotation shall be used to represent graphics that shall be
+/// printed at a fixed size and position on a page, regardless of the dimensions
+/// of the printed page
+#[derive(Debug)]
+pub(crate) struct WatermarkAnnotation {
+    /// A fixed print dictionary that specifies how this annotation shall be drawn
+    /// relative to the dimensions of the target media
+    fixed_print: Option<FixedPrint>,
+}
+
+impl WatermarkAnnotation {
+    pub fn from_dict<'a>(
+        dict: &mut Dictionary<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let fixed_print = dict.get("FixedPrint", resolver)?;
+
+        Ok(Self { fixed_print })
+    }
+}
+
+#[derive(Debug, FromObj)]
+#[obj_type("FixedPrint")]
+struct FixedPrint {
+    /// The matrix used to transform the annotation's rectangle before rendering
+    ///
+    /// Default value: the identity matrix
+    #[field("Matrix", default = Matrix::identity())]
+    matrix: Matrix,
+
+    /// The amount to translate the associated content horizontally, as a
+    /// percentage of the width of the target media
+    ///
+    /// Default value: 0
+    #[field("H", default = 0.0)]
+    h: f32,
+
+    /// The amount to translate the associated content vertically, as a
+    /// percentage of the height of the target media
+    ///
+    /// Default value: 0
+    #[field("V", default = 0.0)]
+    v: f32,
+}
@@ -0,0 +1,283 @@
+use crate::{
+    data_structures::Rectangle,
+    error::PdfResult,
+    objects::{decode_text_string, Dictionary},
+    stream::Stream,
+    writer::ContentBuilder,
+    Resolve,
+};
+
+use super::free_text::Justification;
+
+/// The widths of the characters from space to tilde in Helvetica, in
+/// thousandths of an em, with the quote and grave accent of WinAnsiEncoding
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// A redaction annotation identifies content that is intended to be removed from
+/// the document
+///
+/// Redaction is a two-step process: content is first marked for redaction with
+/// this annotation, and then the content is removed, after which the annotation
+/// shall be removed as well.
+#[derive(Debug)]
+pub(crate) struct RedactAnnotation<'a> {
+    /// An array of 8 * n numbers specifying the coordinates of n quadrilaterals
+    /// in default user space, which denote the content region that is intended
+    /// to be removed. If this entry is not present, the Rect entry denotes the
+    /// content region.
+    quad_points: Option<Vec<f32>>,
+
+    /// The colour that shall be used to fill the region after the affected
+    /// content has been removed
+    ic: Option<Vec<f32>>,
+
+    /// A form XObject specifying the overlay appearance for this redaction
+    /// annotation, drawn after the affected content has been removed
+    ro: Option<Stream<'a>>,
+
+    /// A text string specifying the overlay text that should be drawn over the
+    /// redacted region after the affected content has been removed
+    overlay_text: Option<String>,
+
+    /// If true, then the text specified by OverlayText should be repeated to fill
+    /// the redacted region after the affected content has been removed
+    ///
+    /// Default value: false
+    repeat: bool,
+
+    /// The appearance string that shall be used in formatting the overlay text
+    /// when it is drawn after the affected content has been removed
+    da: Option<String>,
+
+    /// The justification that shall be used in displaying the overlay text
+    ///
+    /// Default value: left-justified
+    q: Justification,
+}
+
+impl<'a> RedactAnnotation<'a> {
+    pub fn from_dict(dict: &mut Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let quad_points = dict.get("QuadPoints", resolver)?;
+        let ic = dict.get("IC", resolver)?;
+        let ro = dict.get("RO", resolver)?;
+        let overlay_text = dict.get_string("OverlayText", resolver)?;
+        let repeat = dict.get_bool("Repeat", resolver)?.unwrap_or(false);
+        let da = dict.get_string("DA", resolver)?;
+        let q = dict.get("Q", resolver)?.unwrap_or_default();
+
+        Ok(Self {
+            quad_points,
+            ic,
+            repeat,
+            ro,
+            overlay_text,
+            da,
+            q,
+        })
+    }
+
+    /// The regions of the page whose content is to be removed: the bounding box
+    /// of each quadrilateral of QuadPoints, or otherwise `rect`
+    pub fn regions(&self, rect: Rectangle) -> Vec<Rectangle> {
+        let quad_points = match &self.quad_points {
+            Some(quad_points) if quad_points.len() >= 8 => quad_points,
+            _ => return vec![rect],
+        };
+
+        quad_points
+            .chunks_exact(8)
+            .map(|quad| {
+                let xs = [quad[0], quad[2], quad[4], quad[6]];
+                let ys = [quad[1], quad[3], quad[5], quad[7]];
+
+                Rectangle::new(
+                    xs.into_iter().fold(f32::INFINITY, f32::min),
+                    ys.into_iter().fold(f32::INFINITY, f32::min),
+                    xs.into_iter().fold(f32::NEG_INFINITY, f32::max),
+                    ys.into_iter().fold(f32::NEG_INFINITY, f32::max),
+                )
+            })
+            .collect()
+    }
+
+    /// Paint the overlay shown in place of the removed content over each of
+    /// `regions`: the interior colour, followed by the overlay text shown in
+    /// Helvetica through the font resource `font`
+    ///
+    /// Returns whether the font resource is used.
+    // todo: the RO overlay appearance
+    pub fn draw_overlay(
+        &self,
+        regions: &[Rectangle],
+        content: &mut ContentBuilder,
+        font: &str,
+    ) -> bool {
+        let text = self
+            .overlay_text
+            .as_deref()
+            .map(decode_text_string)
+            .filter(|text| !text.trim().is_empty());
+
+        let (font_size, text_color) = default_appearance(self.da.as_deref().unwrap_or(""));
+
+        for region in regions {
+            let [llx, lly, urx, ury] = bounds(region);
+            let (width, height) = (urx - llx, ury - lly);
+
+            content.with_saved_state(|content| {
+                if let Some(ic) = &self.ic {
+                    if set_fill_color(content, ic) {
+                        content.rectangle(llx, lly, width, height).fill();
+                    }
+                }
+
+                let text = match &text {
+                    Some(text) => text,
+                    None => return,
+                };
+
+                // a font size of zero is auto-sized, to fit the text within the
+                // region
+                let font_size = match font_size {
+                    Some(size) if size > 0.0 => size,
+                    _ => (width / helvetica_width(text)).min(height),
+                };
+
+                if font_size <= 0.0 {
+                    return;
+                }
+
+                let mut line = text.clone();
+
+                if self.repeat {
+                    while helvetica_width(&line) * font_size < width {
+                        line.push(' ');
+                        line.push_str(text);
+                    }
+                }
+
+                let x = match self.q {
+                    Justification::Left => llx,
+                    Justification::Centered => {
+                        llx + (width - helvetica_width(&line) * font_size) / 2.0
+                    }
+                    Justification::Right => urx - helvetica_width(&line) * font_size,
+                };
+
+                let rows = if self.repeat {
+                    (height / font_size).ceil().max(1.0) as usize
+                } else {
+                    1
+                };
+
+                content.rectangle(llx, lly, width, height).clip().end_path();
+
+                if let Some(color) = &text_color {
+                    set_fill_color(content, color);
+                }
+
+                content.begin_text().set_font(font, font_size);
+
+                for row in 1..=rows {
+                    let y = ury - row as f32 * font_size;
+
+                    content
+                        .set_text_matrix(1.0, 0.0, 0.0, 1.0, x, y)
+                        .show_text(&line);
+                }
+
+                content.end_text();
+            });
+        }
+
+        text.is_some()
+    }
+}
+
+/// The lower left and upper right coordinates of `rect`, in that order
+fn bounds(rect: &Rectangle) -> [f32; 4] {
+    let [lower_left, _, upper_right, _] = rect.corners();
+
+    [
+        lower_left.x.min(upper_right.x),
+        lower_left.y.min(upper_right.y),
+        lower_left.x.max(upper_right.x),
+        lower_left.y.max(upper_right.y),
+    ]
+}
+
+/// The width of `text` shown in Helvetica, in ems
+fn helvetica_width(text: &str) -> f32 {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' => f32::from(HELVETICA_WIDTHS[c as usize - 32]),
+            _ => 556.0,
+        })
+        .sum::<f32>()
+        / 1000.0
+}
+
+/// The font size and fill colour of a default appearance string, such as
+/// `/Helv 12 Tf 1 0 0 rg`
+fn default_appearance(da: &str) -> (Option<f32>, Option<Vec<f32>>) {
+    let mut operands = Vec::new();
+    let mut font_size = None;
+    let mut color = None;
+
+    for token in da.split_whitespace() {
+        if let Ok(n) = token.parse::<f32>() {
+            operands.push(n);
+            continue;
+        }
+
+        match token {
+            "Tf" => font_size = operands.last().copied(),
+            "g" | "rg" | "k" => color = Some(operands.clone()),
+            // operands such as the font name are not numbers
+            _ if token.starts_with('/') => continue,
+            _ => {}
+        }
+
+        operands.clear();
+    }
+
+    (font_size, color)
+}
+
+/// Set the fill colour to `color`, in the device colour space with as many
+/// components, returning false if there is none, such as for an empty array
+/// which denotes no colour
+fn set_fill_color(content: &mut ContentBuilder, color: &[f32]) -> bool {
+    match *color {
+        [gray] => content.set_fill_gray(gray),
+        [r, g, b] => content.set_fill_rgb(r, g, b),
+        [c, m, y, k] => content.set_fill_cmyk(c, m, y, k),
+        _ => return false,
+    };
+
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::default_appearance;
+
+    #[test]
+    fn parse_default_appearance() {
+        assert_eq!(
+            default_appearance("/Helv 12 Tf 1 0 0 rg"),
+            (Some(12.0), Some(vec![1.0, 0.0, 0.0]))
+        );
+        assert_eq!(
+            default_appearance("0 g /Helv 0 Tf"),
+            (Some(0.0), Some(vec![0.0]))
+        );
+        assert_eq!(default_appearance(""), (None, None));
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{error::PdfResult, objects::Dictionary, Resolve};
+
+use super::{BorderEffect, BorderStyle};
+
+/// Square and circle annotations display, respectively, a rectangle or an ellipse
+/// on the page, inscribed within the annotation rectangle
+#[derive(Debug)]
+pub(crate) struct SquareCircleAnnotation {
+    /// A border style dictionary specifying the line width and dash pattern that
+    /// shall be used in drawing the rectangle or ellipse
+    bs: Option<BorderStyle>,
+
+    /// The interior colour with which to fill the annotation's rectangle or ellipse
+    ic: Option<Vec<f32>>,
+
+    /// A border effect dictionary describing an effect applied to the border
+    /// described by the BS entry
+    be: Option<BorderEffect>,
+
+    /// A set of four numbers describing the numerical differences between two
+    /// rectangles: the Rect entry of the annotation and the actual boundaries of
+    /// the underlying square or circle
+    rd: Option<[f32; 4]>,
+}
+
+impl SquareCircleAnnotation {
+    pub fn from_dict<'a>(
+        dict: &mut Dictionary<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let bs = dict.get("BS", resolver)?;
+        let ic = dict.get("IC", resolver)?;
+        let be = dict.get("BE", resolver)?;
+        let rd = dict.get("RD", resolver)?;
+
+        Ok(Self { bs, ic, be, rd })
+    }
+}
+
+/// An ink annotation represents a freehand "scribble" composed of one or more
+/// disjoint paths
+#[derive(Debug)]
+pub(crate) struct InkAnnotation {
+    /// An array of n arrays, each representing a stroked path. Each array shall
+    /// be a series of alternating horizontal and vertical coordinates in default
+    /// user space, specifying points along the path
+    ink_list: Option<Vec<Vec<f32>>>,
+
+    /// (PDF 2.0) An array of n arrays, each supplying the operands for a path
+    /// building operator (m, l or c), used in place of InkList to draw curves
+    path: Option<Vec<Vec<f32>>>,
+
+    /// A border style dictionary specifying the line width and dash pattern that
+    /// shall be used in drawing the paths
+    bs: Option<BorderStyle>,
+}
+
+impl InkAnnotation {
+    pub fn from_dict<'a>(
+        dict: &mut Dictionary<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let ink_list = dict.get("InkList", resolver)?;
+        let path = dict.get("Path", resolver)?;
+
+        anyhow::ensure!(
+            ink_list.is_some() || path.is_some(),
+            "ink annotation must have either InkList or Path"
+        );
+
+        let bs = dict.get("BS", resolver)?;
+
+        Ok(Self { ink_list, path, bs })
+    }
+}
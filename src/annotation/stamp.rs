@@ -0,0 +1,68 @@
+use crate::{error::PdfResult, objects::Dictionary, Resolve};
+
+/// A rubber stamp annotation displays text or graphics intended to look as if
+/// they were stamped on the page with a rubber stamp. When opened, it shall
+/// display a pop-up window containing the text of the associated note.
+#[derive(Debug)]
+pub(crate) struct StampAnnotation {
+    /// The name of an icon that shall be used in displaying the annotation
+    ///
+    /// Default value: Draft
+    name: StampName,
+}
+
+impl StampAnnotation {
+    pub fn from_dict<'a>(
+        dict: &mut Dictionary<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let name = dict
+            .get_name("Name", resolver)?
+            .map(StampName::from_str)
+            .unwrap_or_default();
+
+        Ok(Self { name })
+    }
+}
+
+#[derive(Debug, Default)]
+enum StampName {
+    Approved,
+    Experimental,
+    NotApproved,
+    AsIs,
+    Expired,
+    NotForPublicRelease,
+    Confidential,
+    Final,
+    Sold,
+    Departmental,
+    ForComment,
+    TopSecret,
+    #[default]
+    Draft,
+    ForPublicRelease,
+    Other(String),
+}
+
+impl StampName {
+    pub fn from_str(s: String) -> Self {
+        match s.as_ref() {
+            "Approved" => Self::Approved,
+            "Experimental" => Self::Experimental,
+            "NotApproved" => Self::NotApproved,
+            "AsIs" => Self::AsIs,
+            "Expired" => Self::Expired,
+            "NotForPublicRelease" => Self::NotForPublicRelease,
+            "Confidential" => Self::Confidential,
+            "Final" => Self::Final,
+            "Sold" => Self::Sold,
+            "Departmental" => Self::Departmental,
+            "ForComment" => Self::ForComment,
+            "TopSecret" => Self::TopSecret,
+            "Draft" => Self::Draft,
+            "ForPublicRelease" => Self::ForPublicRelease,
+            _ => Self::Other(s),
+        }
+    }
+}
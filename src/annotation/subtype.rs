@@ -4,18 +4,59 @@ use crate::{
     FromObj, Resolve,
 };
 
-use super::{link::LinkAnnotation, text::TextAnnotation, BaseAnnotation};
+use super::{
+    file_attachment::{FileAttachmentAnnotation, SoundAnnotation},
+    free_text::FreeTextAnnotation,
+    line::{LineAnnotation, PolygonAnnotation},
+    link::LinkAnnotation,
+    multimedia::{MovieAnnotation, RichMediaAnnotation, ScreenAnnotation, ThreeDAnnotation},
+    popup::PopupAnnotation,
+    prepress::{PrinterMarkAnnotation, TrapNetAnnotation, WatermarkAnnotation},
+    redact::RedactAnnotation,
+    shape::{InkAnnotation, SquareCircleAnnotation},
+    stamp::StampAnnotation,
+    text::TextAnnotation,
+    text_markup::{CaretAnnotation, TextMarkupAnnotation},
+    widget::WidgetAnnotation,
+    BaseAnnotation,
+};
 
 #[derive(Debug)]
 pub(crate) enum AnnotationSubType<'a> {
     Text(TextAnnotation),
     Link(LinkAnnotation<'a>),
+    FreeText(FreeTextAnnotation),
+    Line(LineAnnotation),
+    Square(SquareCircleAnnotation),
+    Circle(SquareCircleAnnotation),
+    Polygon(PolygonAnnotation),
+    PolyLine(PolygonAnnotation),
+    Highlight(TextMarkupAnnotation),
+    Underline(TextMarkupAnnotation),
+    Squiggly(TextMarkupAnnotation),
+    StrikeOut(TextMarkupAnnotation),
+    Stamp(StampAnnotation),
+    Caret(CaretAnnotation),
+    Ink(InkAnnotation),
+    Popup(PopupAnnotation),
+    FileAttachment(FileAttachmentAnnotation<'a>),
+    Sound(SoundAnnotation<'a>),
+    Movie(MovieAnnotation<'a>),
+    Widget(WidgetAnnotation<'a>),
+    Screen(ScreenAnnotation<'a>),
+    PrinterMark(PrinterMarkAnnotation),
+    TrapNet(TrapNetAnnotation<'a>),
+    Watermark(WatermarkAnnotation),
+    ThreeD(ThreeDAnnotation<'a>),
+    Redact(RedactAnnotation<'a>),
+    Projection,
+    RichMedia(RichMediaAnnotation<'a>),
 }
 
 impl<'a> AnnotationSubType<'a> {
     pub(crate) fn from_dict(
         mut dict: Dictionary<'a>,
-        base: &BaseAnnotation,
+        base: &BaseAnnotation<'a>,
         resolver: &mut dyn Resolve<'a>,
     ) -> PdfResult<Self> {
         Ok(match base.subtype {
@@ -26,7 +67,83 @@ impl<'a> AnnotationSubType<'a> {
                 Object::Dictionary(dict),
                 resolver,
             )?),
-            _ => todo!(),
+            AnnotationSubTypeKind::Widget => AnnotationSubType::Widget(WidgetAnnotation::from_obj(
+                Object::Dictionary(dict),
+                resolver,
+            )?),
+            AnnotationSubTypeKind::FreeText => {
+                AnnotationSubType::FreeText(FreeTextAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Line => {
+                AnnotationSubType::Line(LineAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Square => {
+                AnnotationSubType::Square(SquareCircleAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Circle => {
+                AnnotationSubType::Circle(SquareCircleAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Polygon => {
+                AnnotationSubType::Polygon(PolygonAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::PolyLine => {
+                AnnotationSubType::PolyLine(PolygonAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Highlight => {
+                AnnotationSubType::Highlight(TextMarkupAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Underline => {
+                AnnotationSubType::Underline(TextMarkupAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Squiggly => {
+                AnnotationSubType::Squiggly(TextMarkupAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::StrikeOut => {
+                AnnotationSubType::StrikeOut(TextMarkupAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Stamp => {
+                AnnotationSubType::Stamp(StampAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Caret => {
+                AnnotationSubType::Caret(CaretAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Ink => {
+                AnnotationSubType::Ink(InkAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Popup => {
+                AnnotationSubType::Popup(PopupAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::FileAttachment => AnnotationSubType::FileAttachment(
+                FileAttachmentAnnotation::from_dict(&mut dict, resolver)?,
+            ),
+            AnnotationSubTypeKind::Sound => {
+                AnnotationSubType::Sound(SoundAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Movie => {
+                AnnotationSubType::Movie(MovieAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Screen => {
+                AnnotationSubType::Screen(ScreenAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::PrinterMark => AnnotationSubType::PrinterMark(
+                PrinterMarkAnnotation::from_dict(&mut dict, resolver)?,
+            ),
+            AnnotationSubTypeKind::TrapNet => {
+                AnnotationSubType::TrapNet(TrapNetAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Watermark => {
+                AnnotationSubType::Watermark(WatermarkAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::ThreeD => {
+                AnnotationSubType::ThreeD(ThreeDAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Redact => {
+                AnnotationSubType::Redact(RedactAnnotation::from_dict(&mut dict, resolver)?)
+            }
+            AnnotationSubTypeKind::Projection => AnnotationSubType::Projection,
+            AnnotationSubTypeKind::RichMedia => {
+                AnnotationSubType::RichMedia(RichMediaAnnotation::from_dict(&mut dict, resolver)?)
+            }
         })
     }
 }
@@ -59,6 +176,8 @@ pub(super) enum AnnotationSubTypeKind {
     Watermark = "Watermark",
     ThreeD = "3D",
     Redact = "Redact",
+    Projection = "Projection",
+    RichMedia = "RichMedia",
 }
 
 impl AnnotationSubTypeKind {
@@ -82,6 +201,100 @@ impl AnnotationSubTypeKind {
                 | Self::FileAttachment
                 | Self::Sound
                 | Self::Redact
+                | Self::Projection
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{
+        annotation::Annotation,
+        objects::{Dictionary, Object},
+        version::PdfVersion,
+        writer::PdfWriter,
+        FromObj,
+    };
+
+    use super::AnnotationSubType;
+
+    fn numbers(numbers: &[i32]) -> Object<'static> {
+        Object::Array(numbers.iter().map(|&n| Object::Integer(n)).collect())
+    }
+
+    fn annotation(subtype: &str, entries: Vec<(&str, Object<'static>)>) -> Annotation<'static> {
+        let mut dict = HashMap::from([
+            ("Type".to_owned(), Object::Name("Annot".to_owned())),
+            ("Subtype".to_owned(), Object::Name(subtype.to_owned())),
+            ("Rect".to_owned(), numbers(&[0, 0, 100, 100])),
+        ]);
+
+        dict.extend(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value)),
+        );
+
+        Annotation::from_obj(
+            Object::Dictionary(Dictionary::new(dict)),
+            &mut PdfWriter::new(PdfVersion::V1_7),
         )
+        .unwrap()
+    }
+
+    #[test]
+    fn annotation_subtypes() {
+        let highlight = annotation(
+            "Highlight",
+            vec![
+                ("QuadPoints", numbers(&[0, 10, 50, 10, 0, 0, 50, 0])),
+                ("T", Object::String("reviewer".to_owned())),
+            ],
+        );
+        assert!(matches!(
+            highlight.sub_type,
+            AnnotationSubType::Highlight(..)
+        ));
+
+        let line = annotation(
+            "Line",
+            vec![
+                ("L", numbers(&[0, 0, 100, 100])),
+                (
+                    "LE",
+                    Object::Array(vec![
+                        Object::Name("OpenArrow".to_owned()),
+                        Object::Name("None".to_owned()),
+                    ]),
+                ),
+            ],
+        );
+        assert!(matches!(line.sub_type, AnnotationSubType::Line(..)));
+
+        let square = annotation("Square", vec![("IC", numbers(&[1, 0, 0]))]);
+        assert!(matches!(square.sub_type, AnnotationSubType::Square(..)));
+
+        let free_text = annotation(
+            "FreeText",
+            vec![
+                ("DA", Object::String("/Helv 12 Tf 0 g".to_owned())),
+                ("Q", Object::Integer(1)),
+            ],
+        );
+        assert!(matches!(
+            free_text.sub_type,
+            AnnotationSubType::FreeText(..)
+        ));
+
+        let redact = annotation(
+            "Redact",
+            vec![("OverlayText", Object::String("REDACTED".to_owned()))],
+        );
+        assert!(matches!(redact.sub_type, AnnotationSubType::Redact(..)));
+
+        let popup = annotation("Popup", vec![("Open", Object::True)]);
+        assert!(matches!(popup.sub_type, AnnotationSubType::Popup(..)));
     }
 }
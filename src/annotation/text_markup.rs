@@ -0,0 +1,295 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    error::PdfResult,
+    objects::{Dictionary, Reference},
+    Resolve,
+};
+
+use super::{Annotation, Comment};
+
+/// Text markup annotations appear as highlights, underlines, strikeouts, or
+/// jagged ("squiggly") underlines in the text of a document. When opened, they
+/// shall display a pop-up window containing the text of the associated note.
+#[derive(Debug)]
+pub(crate) struct TextMarkupAnnotation {
+    /// An array of 8 * n numbers specifying the coordinates of n quadrilaterals in
+    /// default user space. Each quadrilateral shall encompass a word or group of
+    /// contiguous words in the text underlying the annotation.
+    quad_points: Vec<f32>,
+}
+
+impl TextMarkupAnnotation {
+    pub fn from_dict<'a>(
+        dict: &mut Dictionary<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let quad_points: Vec<f32> = dict.expect("QuadPoints", resolver)?;
+
+        anyhow::ensure!(
+            quad_points.len() % 8 == 0,
+            "expected a multiple of 8 QuadPoints, found {}",
+            quad_points.len()
+        );
+
+        Ok(Self { quad_points })
+    }
+
+    /// The quadrilaterals given by QuadPoints, each as its four vertices
+    fn quads(&self) -> Vec<[[f32; 2]; 4]> {
+        self.quad_points
+            .chunks_exact(8)
+            .map(|quad| {
+                [
+                    [quad[0], quad[1]],
+                    [quad[2], quad[3]],
+                    [quad[4], quad[5]],
+                    [quad[6], quad[7]],
+                ]
+            })
+            .collect()
+    }
+}
+
+/// A highlight, underline, squiggly underline or strikeout of text on a page,
+/// with the comments made on it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextMarkup {
+    pub kind: TextMarkupKind,
+
+    /// The quadrilaterals enclosing the marked-up text, each given by its four
+    /// vertices in default user space, in the order in which they appear in
+    /// the annotation's QuadPoints
+    pub quads: Vec<[[f32; 2]; 4]>,
+
+    pub comment: Comment,
+
+    /// The comments replying to the annotation or to one of its replies, in
+    /// depth-first order, so that each is followed by its own replies
+    pub replies: Vec<Comment>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextMarkupKind {
+    Highlight,
+    Underline,
+    Squiggly,
+    StrikeOut,
+}
+
+/// The text markup annotations among the annotations of a page, each given
+/// along with the reference to its dictionary
+pub(crate) fn collect_text_markup(annotations: &[(Reference, Annotation)]) -> Vec<TextMarkup> {
+    let mut replies = HashMap::<Reference, Vec<Comment>>::new();
+
+    for (reference, annotation) in annotations {
+        if let (Some(irt), Some(comment)) =
+            (annotation.replies_to(), annotation.comment(*reference))
+        {
+            replies.entry(irt).or_default().push(comment);
+        }
+    }
+
+    annotations
+        .iter()
+        .filter_map(|(reference, annotation)| {
+            let (kind, markup) = annotation.text_markup()?;
+
+            let mut thread = Vec::new();
+            reply_thread(
+                *reference,
+                &replies,
+                &mut HashSet::from([*reference]),
+                &mut thread,
+            );
+
+            Some(TextMarkup {
+                kind,
+                quads: markup.quads(),
+                comment: annotation.comment(*reference)?,
+                replies: thread,
+            })
+        })
+        .collect()
+}
+
+/// Add the replies to `reference` to `thread`, each followed by its own
+/// replies
+///
+/// Replies already in `visited` are skipped, so that malformed documents with
+/// cycles of replies terminate.
+fn reply_thread(
+    reference: Reference,
+    replies: &HashMap<Reference, Vec<Comment>>,
+    visited: &mut HashSet<Reference>,
+    thread: &mut Vec<Comment>,
+) {
+    for reply in replies.get(&reference).into_iter().flatten() {
+        if visited.insert(reply.reference) {
+            thread.push(reply.clone());
+            reply_thread(reply.reference, replies, visited, thread);
+        }
+    }
+}
+
+/// A caret annotation is a visual symbol that indicates the presence of text
+/// edits
+#[derive(Debug)]
+pub(crate) struct CaretAnnotation {
+    /// A set of four numbers describing the numerical differences between two
+    /// rectangles: the Rect entry of the annotation and the actual boundaries of
+    /// the underlying caret
+    rd: Option<[f32; 4]>,
+
+    /// A name specifying a symbol that shall be associated with the caret
+    ///
+    /// Default value: None
+    sy: CaretSymbol,
+}
+
+impl CaretAnnotation {
+    pub fn from_dict<'a>(
+        dict: &mut Dictionary<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let rd = dict.get("RD", resolver)?;
+        let sy = dict.get("Sy", resolver)?.unwrap_or_default();
+
+        Ok(Self { rd, sy })
+    }
+}
+
+#[pdf_enum]
+#[derive(Default)]
+pub(crate) enum CaretSymbol {
+    /// A new paragraph symbol (¶) shall be associated with the caret
+    Paragraph = "P",
+
+    /// No symbol shall be associated with the caret
+    #[default]
+    None = "None",
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{
+        annotation::Annotation,
+        objects::{Dictionary, Object, Reference},
+        version::PdfVersion,
+        writer::PdfWriter,
+        FromObj,
+    };
+
+    use super::{collect_text_markup, TextMarkupKind};
+
+    fn reference(object_number: usize) -> Reference {
+        Reference {
+            object_number,
+            generation: 0,
+        }
+    }
+
+    fn annotation(
+        object_number: usize,
+        subtype: &str,
+        entries: Vec<(&str, Object<'static>)>,
+    ) -> (Reference, Annotation<'static>) {
+        let mut dict = HashMap::from([
+            ("Subtype".to_owned(), Object::Name(subtype.to_owned())),
+            (
+                "Rect".to_owned(),
+                Object::Array([0, 0, 100, 100].map(Object::Integer).to_vec()),
+            ),
+        ]);
+
+        dict.extend(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value)),
+        );
+
+        let annotation = Annotation::from_obj(
+            Object::Dictionary(Dictionary::new(dict)),
+            &mut PdfWriter::new(PdfVersion::V1_7),
+        )
+        .unwrap();
+
+        (reference(object_number), annotation)
+    }
+
+    fn string(s: &str) -> Object<'static> {
+        Object::String(s.to_owned())
+    }
+
+    #[test]
+    fn text_markup_threads() {
+        let quad_points = [10, 20, 50, 20, 10, 10, 50, 10]
+            .map(Object::Integer)
+            .to_vec();
+
+        let annotations = vec![
+            annotation(
+                1,
+                "Highlight",
+                vec![
+                    ("QuadPoints", Object::Array(quad_points)),
+                    ("Contents", string("typo")),
+                    ("T", string("alice")),
+                ],
+            ),
+            annotation(
+                2,
+                "Text",
+                vec![
+                    ("IRT", Object::Reference(reference(1))),
+                    ("Contents", string("fixed")),
+                    ("T", string("bob")),
+                ],
+            ),
+            annotation(
+                3,
+                "Text",
+                vec![
+                    ("IRT", Object::Reference(reference(2))),
+                    ("Contents", string("thanks")),
+                ],
+            ),
+            // grouped with, rather than replying to, the highlight
+            annotation(
+                4,
+                "Text",
+                vec![
+                    ("IRT", Object::Reference(reference(1))),
+                    ("RT", Object::Name("Group".to_owned())),
+                ],
+            ),
+        ];
+
+        let markup = collect_text_markup(&annotations);
+
+        assert_eq!(markup.len(), 1);
+        assert_eq!(markup[0].kind, TextMarkupKind::Highlight);
+        assert_eq!(
+            markup[0].quads,
+            vec![[[10.0, 20.0], [50.0, 20.0], [10.0, 10.0], [50.0, 10.0]]]
+        );
+        assert_eq!(markup[0].comment.contents.as_deref(), Some("typo"));
+        assert_eq!(markup[0].comment.author.as_deref(), Some("alice"));
+
+        let replies = markup[0]
+            .replies
+            .iter()
+            .map(|reply| (reply.reference, reply.contents.as_deref()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            replies,
+            vec![
+                (reference(2), Some("fixed")),
+                (reference(3), Some("thanks"))
+            ]
+        );
+    }
+}
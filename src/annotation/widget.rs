@@ -0,0 +1,54 @@
+use crate::{
+    actions::{Actions, AnnotationAdditionalActions},
+    objects::{Dictionary, Reference},
+};
+
+use super::{appearance::AppearanceCharacteristics, link::HighlightingMode, BorderStyle};
+
+/// Interactive forms use widget annotations to represent the appearance of
+/// fields and to manage user interactions
+///
+/// When a field has only a single associated widget annotation, the contents
+/// of the field dictionary and the annotation dictionary may be merged into a
+/// single dictionary containing entries that pertain to both a field and an
+/// annotation.
+#[derive(Debug, FromObj)]
+pub(crate) struct WidgetAnnotation<'a> {
+    /// The annotation's highlighting mode, the visual effect that shall be used
+    /// when the mouse button is pressed or held down inside its active area
+    ///
+    /// Default value: I
+    #[field("H", default = HighlightingMode::default())]
+    h: HighlightingMode,
+
+    /// An appearance characteristics dictionary that shall be used in
+    /// constructing a dynamic appearance stream specifying the annotation's
+    /// visual presentation on the page
+    #[field("MK")]
+    pub mk: Option<AppearanceCharacteristics<'a>>,
+
+    /// An action that shall be performed when the annotation is activated
+    #[field("A")]
+    a: Option<Actions<'a>>,
+
+    /// An additional-actions dictionary defining the annotation's behaviour in
+    /// response to various trigger events
+    #[field("AA")]
+    aa: Option<AnnotationAdditionalActions<'a>>,
+
+    /// A border style dictionary specifying the width and dash pattern that
+    /// shall be used in drawing the annotation's border
+    #[field("BS")]
+    bs: Option<BorderStyle>,
+
+    /// An indirect reference to the widget annotation's parent field. A widget
+    /// annotation may have at most one parent; that is, it can be included in
+    /// the Kids array of at most one field
+    #[field("Parent")]
+    parent: Option<Reference>,
+
+    /// The entries of the field dictionary, if it is merged with this
+    /// annotation
+    #[field]
+    other: Dictionary<'a>,
+}
@@ -0,0 +1,205 @@
+use crate::{
+    date::Date,
+    error::PdfResult,
+    file_specification::{EmbeddedFileStream, FileSpecification},
+    filter::decode_stream,
+    objects::Object,
+    FromObj, Parser, Resolve,
+};
+
+/// A file embedded in the document, listed in the EmbeddedFiles name tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    /// The key of the file in the EmbeddedFiles name tree
+    pub name: String,
+
+    /// The file name given by the file specification, if any
+    pub file_name: Option<String>,
+
+    pub description: Option<String>,
+
+    /// The MIME type of the file, such as `text/plain`
+    pub mime_type: Option<String>,
+
+    pub creation_date: Option<Date>,
+    pub modification_date: Option<Date>,
+
+    /// The MD5 digest of the uncompressed file, as recorded in the document.
+    /// This is not verified against `data`
+    pub checksum: Option<[u8; 16]>,
+
+    /// The uncompressed contents of the file
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    /// The attachment described by the file specification `obj`, or `None` if
+    /// the file specification does not contain an embedded file stream
+    pub(crate) fn from_file_specification<'a>(
+        name: &str,
+        obj: Object<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Option<Self>> {
+        let spec = match FileSpecification::from_obj(obj, resolver)? {
+            FileSpecification::Full(spec) => spec,
+            FileSpecification::Simple(..) => return Ok(None),
+        };
+
+        let EmbeddedFileStream {
+            subtype,
+            params,
+            stream,
+        } = match spec.ef.and_then(|ef| ef.uf.or(ef.f)) {
+            Some(file) => file,
+            None => return Ok(None),
+        };
+
+        let data = decode_stream(&stream.stream, &stream.dict, resolver)?.into_owned();
+
+        let file_name = spec
+            .unicode_file_specification_string
+            .or(spec.file_specification_string)
+            .map(|file_name| file_name.as_str().to_owned());
+
+        let (creation_date, modification_date, checksum) = match params {
+            Some(params) => (
+                params.creation_date,
+                params.mod_date,
+                params.check_sum.and_then(|check_sum| {
+                    check_sum
+                        .chars()
+                        .map(|c| c as u8)
+                        .collect::<Vec<u8>>()
+                        .try_into()
+                        .ok()
+                }),
+            ),
+            None => (None, None, None),
+        };
+
+        Ok(Some(Self {
+            name: name.to_owned(),
+            file_name,
+            description: spec.description,
+            mime_type: subtype.map(|subtype| subtype.0),
+            creation_date,
+            modification_date,
+            checksum,
+            data,
+        }))
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Every file embedded in the document through the EmbeddedFiles name tree,
+    /// in order of their names
+    ///
+    /// File specifications in the tree which do not contain an embedded file
+    /// stream are skipped.
+    pub fn attachments(&mut self) -> PdfResult<Vec<Attachment>> {
+        let names = match &self.catalog.names {
+            Some(names) => names.get_ref(&mut self.lexer)?.into_owned(),
+            None => return Ok(Vec::new()),
+        };
+
+        let embedded_files = match &names.embedded_files {
+            Some(embedded_files) => embedded_files,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut attachments = Vec::new();
+
+        for (name, obj) in embedded_files.entries() {
+            if let Some(attachment) =
+                Attachment::from_file_specification(name, obj.clone(), &mut self.lexer)?
+            {
+                attachments.push(attachment);
+            }
+        }
+
+        Ok(attachments)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test::{compress, TestFile},
+        ParseOptions,
+    };
+
+    #[test]
+    fn attachments() {
+        let notes = compress(b"notes");
+
+        let mut file = TestFile::new();
+        file.object(1, b"<< /Type /Catalog /Pages 2 0 R /Names 3 0 R >>");
+        file.object(2, b"<< /Type /Pages /Kids [] /Count 0 >>");
+        file.object(3, b"<< /EmbeddedFiles 4 0 R >>");
+
+        // a name tree of two leaves, the second of which lists file
+        // specifications without embedded files
+        file.object(4, b"<< /Kids [5 0 R 6 0 R] >>");
+        file.object(
+            5,
+            b"<< /Limits [(data.csv) (notes.txt)] /Names [(data.csv) 7 0 R (notes.txt) 8 0 R] >>",
+        );
+        file.object(
+            6,
+            b"<< /Limits [(remote) (simple)] /Names [(remote) << /Type /Filespec /F (remote.txt) >> \
+              (simple) (simple.txt)] >>",
+        );
+        file.object(
+            7,
+            b"<< /Type /Filespec /F (data.csv) /UF (data \\(1\\).csv) /Desc (Figures) \
+              /EF << /F 9 0 R /UF 10 0 R >> >>",
+        );
+        file.object(
+            8,
+            b"<< /Type /Filespec /F (notes.txt) /EF << /F 11 0 R >> >>",
+        );
+        file.stream(9, "<< /Type /EmbeddedFile /Length 3 >>", b"old");
+        file.stream(
+            10,
+            "<< /Type /EmbeddedFile /Subtype /text#2Fcsv /Length 3 \
+              /Params << /Size 3 /ModDate (D:20240102) /CheckSum <000102030405060708090a0b0c0d0e0f> >> >>",
+            b"1,2",
+        );
+        file.stream(
+            11,
+            &format!(
+                "<< /Type /EmbeddedFile /Filter /FlateDecode /Length {} >>",
+                notes.len()
+            ),
+            &notes,
+        );
+
+        let mut parser =
+            Parser::from_bytes(file.with_xref_table(), ParseOptions::default()).unwrap();
+
+        let attachments = parser.attachments().unwrap();
+
+        assert_eq!(attachments.len(), 2);
+
+        // the UF entries are preferred to F
+        assert_eq!(
+            attachments[0],
+            Attachment {
+                name: "data.csv".to_owned(),
+                file_name: Some("data (1).csv".to_owned()),
+                description: Some("Figures".to_owned()),
+                mime_type: Some("text/csv".to_owned()),
+                creation_date: None,
+                modification_date: Some(Date::from_str("D:20240102").unwrap()),
+                checksum: Some([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]),
+                data: b"1,2".to_vec(),
+            }
+        );
+
+        assert_eq!(attachments[1].name, "notes.txt");
+        assert_eq!(attachments[1].file_name.as_deref(), Some("notes.txt"));
+        assert_eq!(attachments[1].mime_type, None);
+        assert_eq!(attachments[1].data, b"notes");
+    }
+}
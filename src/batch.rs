@@ -0,0 +1,442 @@
+/*!
+ * Editing the metadata of many documents at once, such as setting the Title and
+ * Author of every file in a directory
+ */
+
+use std::borrow::Cow;
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    catalog::InformationDictionary,
+    date::Date,
+    error::PdfResult,
+    objects::{encode_text_string, Dictionary, Object},
+    page_labels::{NumberingStyle, PageLabels},
+    writer,
+    writer::{add_metadata, IncrementalUpdate},
+    FromObj, Lexer, ParseOptions, Parser, Resolve, ToObj,
+};
+
+/// A transformation of the document information dictionary, viewer
+/// preferences, and page labels of a document
+///
+/// Fields which are `None` are left unchanged.
+#[derive(Debug, Default, Clone)]
+pub struct MetadataEdit {
+    title: Option<String>,
+    author: Option<String>,
+    subject: Option<String>,
+    keywords: Option<String>,
+    creator: Option<String>,
+    producer: Option<String>,
+
+    /// Whether the window's title bar should display the document title rather
+    /// than the file name
+    display_doc_title: Option<bool>,
+
+    /// Changes to the page labels of the document, applied in order
+    page_labels: Vec<PageLabelEdit>,
+}
+
+/// A change to the page labels of a document
+#[derive(Debug, Clone)]
+enum PageLabelEdit {
+    /// Replace every labelling range
+    Set(PageLabels),
+
+    /// Begin a labelling range at the given page, replacing any which begins
+    /// there
+    SetRange {
+        first_page: usize,
+        style: Option<NumberingStyle>,
+        prefix: Option<String>,
+        start: i32,
+    },
+
+    /// Remove the labelling range which begins at the given page
+    RemoveRange(usize),
+}
+
+impl MetadataEdit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    pub fn keywords(mut self, keywords: impl Into<String>) -> Self {
+        self.keywords = Some(keywords.into());
+        self
+    }
+
+    pub fn creator(mut self, creator: impl Into<String>) -> Self {
+        self.creator = Some(creator.into());
+        self
+    }
+
+    pub fn producer(mut self, producer: impl Into<String>) -> Self {
+        self.producer = Some(producer.into());
+        self
+    }
+
+    pub fn display_doc_title(mut self, display_doc_title: bool) -> Self {
+        self.display_doc_title = Some(display_doc_title);
+        self
+    }
+
+    /// Remove the page labels of the document, so that pages are labelled by
+    /// their page number alone
+    pub fn remove_page_labels(mut self) -> Self {
+        self.page_labels.push(PageLabelEdit::Set(PageLabels::new()));
+        self
+    }
+
+    /// Replace the page labels of the document with `labels`
+    pub fn set_page_labels(mut self, labels: PageLabels) -> Self {
+        self.page_labels.push(PageLabelEdit::Set(labels));
+        self
+    }
+
+    /// Begin a labelling range at the zero-based index `first_page`, as by
+    /// [`PageLabels::set_range`], keeping the other ranges of the document
+    pub fn set_page_label_range(
+        mut self,
+        first_page: usize,
+        style: Option<NumberingStyle>,
+        prefix: Option<&str>,
+        start: i32,
+    ) -> Self {
+        self.page_labels.push(PageLabelEdit::SetRange {
+            first_page,
+            style,
+            prefix: prefix.map(str::to_owned),
+            start,
+        });
+        self
+    }
+
+    /// Remove the labelling range which begins at `first_page`, so that its
+    /// pages continue the range before it
+    pub fn remove_page_label_range(mut self, first_page: usize) -> Self {
+        self.page_labels
+            .push(PageLabelEdit::RemoveRange(first_page));
+        self
+    }
+
+    /// Whether the edit changes the document information dictionary, and so
+    /// the XMP metadata which mirrors it
+    fn edits_info(&self) -> bool {
+        self.title.is_some()
+            || self.author.is_some()
+            || self.subject.is_some()
+            || self.keywords.is_some()
+            || self.creator.is_some()
+            || self.producer.is_some()
+    }
+
+    /// Whether the edit changes anything outside of the document information
+    /// dictionary, and so requires the document catalog to be rewritten
+    fn edits_catalog(&self) -> bool {
+        self.display_doc_title.is_some() || !self.page_labels.is_empty()
+    }
+
+    /// The page labels after the edit has been applied to `labels`
+    fn apply_to_page_labels(&self, mut labels: PageLabels) -> PageLabels {
+        for edit in &self.page_labels {
+            match edit {
+                PageLabelEdit::Set(replacement) => labels = replacement.clone(),
+                PageLabelEdit::SetRange {
+                    first_page,
+                    style,
+                    prefix,
+                    start,
+                } => labels.set_range(*first_page, *style, prefix.as_deref(), *start),
+                PageLabelEdit::RemoveRange(first_page) => labels.remove_range(*first_page),
+            }
+        }
+
+        labels
+    }
+
+    /// Apply the edit to each document in `paths`, writing the changes as an
+    /// incremental update to the original file
+    ///
+    /// Documents are processed one at a time, and only the cross-reference
+    /// table, trailer, and the objects being edited are read, so that memory
+    /// use is bounded by the largest document rather than the size of the
+    /// batch, and page trees and content streams are never parsed.
+    ///
+    /// A document which fails to be edited does not stop the batch; the result
+    /// for each path is returned in the order given.
+    pub fn apply_to_all(
+        &self,
+        paths: impl IntoIterator<Item = impl AsRef<Path>>,
+    ) -> Vec<(PathBuf, PdfResult<()>)> {
+        paths
+            .into_iter()
+            .map(|path| {
+                let path = path.as_ref().to_path_buf();
+                let result = self.apply(&path);
+
+                (path, result)
+            })
+            .collect()
+    }
+
+    /// Apply the edit to the document at `path`, writing the changes as an
+    /// incremental update to the original file
+    pub fn apply(&self, path: impl AsRef<Path>) -> PdfResult<()> {
+        if !self.edits_info() && !self.edits_catalog() {
+            return Ok(());
+        }
+
+        let file = std::fs::read(path.as_ref())?;
+
+        // Values which are not edited are written back exactly as they were
+        let options = ParseOptions {
+            preserve_lexical_form: true,
+            preserve_key_order: true,
+            ..ParseOptions::default()
+        };
+
+        let (mut lexer, trailer) = Lexer::open(file, options)?;
+
+        let mut update = IncrementalUpdate::new(trailer.size);
+        let mut new_trailer = IncrementalUpdate::trailer(&trailer)?;
+        let mut catalog = lexer.assert_dict(Object::Reference(trailer.root))?;
+        let mut edits_catalog = self.edits_catalog();
+
+        if self.edits_info() {
+            let info = match &trailer.info {
+                Some(info) => Some(info.get_ref(&mut lexer)?.into_owned()),
+                None => None,
+            };
+
+            let info = self.apply_to_info(info).unwrap_or_default();
+
+            edits_catalog |= add_metadata(
+                &mut update,
+                &mut new_trailer,
+                &trailer,
+                &mut catalog,
+                &info,
+                &mut lexer,
+            )?;
+        }
+
+        if let Some(display_doc_title) = self.display_doc_title {
+            let display_doc_title = display_doc_title.to_obj()?;
+
+            // viewer preferences held in an object of their own are replaced
+            // by the update, rather than the catalog which refers to them
+            match catalog.peek("ViewerPreferences").cloned() {
+                Some(Object::Reference(reference)) => {
+                    let mut preferences = lexer.assert_dict(Object::Reference(reference))?;
+                    preferences.insert("DisplayDocTitle", display_doc_title);
+
+                    update.insert(reference, Object::Dictionary(preferences));
+                }
+                Some(Object::Dictionary(mut preferences)) => {
+                    preferences.insert("DisplayDocTitle", display_doc_title);
+                    catalog.insert("ViewerPreferences", Object::Dictionary(preferences));
+                }
+                _ => {
+                    let mut preferences = Dictionary::empty();
+                    preferences.insert("DisplayDocTitle", display_doc_title);
+                    catalog.insert("ViewerPreferences", Object::Dictionary(preferences));
+                }
+            }
+        }
+
+        if !self.page_labels.is_empty() {
+            let labels = match catalog.peek("PageLabels").cloned() {
+                Some(labels) => PageLabels::from_obj(labels, &mut lexer)?,
+                None => PageLabels::new(),
+            };
+
+            let labels = self.apply_to_page_labels(labels);
+
+            if labels.is_empty() {
+                catalog.remove("PageLabels");
+            } else {
+                let labels = update.add(labels.to_obj()?);
+                catalog.insert("PageLabels", Object::Reference(labels));
+            }
+        }
+
+        if edits_catalog {
+            update.insert(trailer.root, Object::Dictionary(catalog));
+        }
+
+        let file = update.write(&lexer.file, new_trailer)?;
+        std::fs::write(path, file)?;
+
+        Ok(())
+    }
+
+    /// The document information dictionary after the edit has been applied, or
+    /// `None` if the document has no information dictionary and the edit does
+    /// not create one
+    pub(crate) fn apply_to_info<'a>(
+        &self,
+        info: Option<InformationDictionary<'a>>,
+    ) -> Option<InformationDictionary<'a>> {
+        if !self.edits_info() {
+            return info;
+        }
+
+        let mut info = info.unwrap_or_default();
+
+        let fields = [
+            (&mut info.title, &self.title),
+            (&mut info.author, &self.author),
+            (&mut info.subject, &self.subject),
+            (&mut info.keywords, &self.keywords),
+            (&mut info.creator, &self.creator),
+            (&mut info.producer, &self.producer),
+        ];
+
+        for (field, edit) in fields {
+            if let Some(value) = edit {
+                *field = Some(encode_text_string(value));
+            }
+        }
+
+        info.mod_date = Some(Date::now());
+
+        Some(info)
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Apply `edit` to the document information dictionary, and update the XMP
+    /// metadata to match, returning the file with an incremental update
+    /// appended
+    ///
+    /// The XMP packet is updated within its padding where it fits, so that the
+    /// metadata stream keeps its length. Documents without XMP metadata are
+    /// given a packet.
+    pub fn update_metadata(&mut self, edit: &MetadataEdit) -> PdfResult<Vec<u8>> {
+        let info = self.info()?.map(Cow::into_owned);
+        let info = edit.apply_to_info(info).unwrap_or_default();
+        writer::update_metadata(&self.trailer, &info, &mut self.lexer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        version::PdfVersion,
+        writer::{DocumentBuilder, PageBuilder},
+        Parser,
+    };
+
+    #[test]
+    fn edit_leaves_other_fields_unchanged() {
+        let info = InformationDictionary {
+            author: Some("Original".to_owned()),
+            subject: Some("Subject".to_owned()),
+            ..InformationDictionary::default()
+        };
+
+        let info = MetadataEdit::new()
+            .author("Edited")
+            .apply_to_info(Some(info))
+            .unwrap();
+
+        assert_eq!(info.author.as_deref(), Some("Edited"));
+        assert_eq!(info.subject.as_deref(), Some("Subject"));
+        assert_eq!(info.title, None);
+    }
+
+    #[test]
+    fn write_edited_info() {
+        let info = MetadataEdit::new()
+            .title("Title")
+            .apply_to_info(None)
+            .unwrap();
+
+        let dict = match info.to_obj().unwrap() {
+            Object::Dictionary(dict) => dict,
+            obj => panic!("expected dictionary, found {:?}", obj),
+        };
+
+        let mut keys = dict.iter().map(|(key, _)| key).collect::<Vec<_>>();
+        keys.sort_unstable();
+
+        // the default value of Trapped is left out, and the modification date
+        // is set to the time of the edit
+        assert_eq!(keys, ["ModDate", "Title"]);
+        assert_eq!(
+            dict.peek("Title"),
+            Some(&Object::String("Title".to_owned()))
+        );
+    }
+
+    #[test]
+    fn apply_edit_to_file() {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+        builder.author("Original");
+        builder.add_page(PageBuilder::new(612.0, 792.0)).unwrap();
+
+        let path = std::env::temp_dir().join("pdf-batch-edit.pdf");
+        builder.save(&path).unwrap();
+
+        let original = std::fs::read(&path).unwrap();
+
+        let results = MetadataEdit::new()
+            .title("Edited")
+            .display_doc_title(true)
+            .set_page_label_range(0, Some(NumberingStyle::LowerRoman), None, 1)
+            .set_page_label_range(1, Some(NumberingStyle::Decimal), Some("P-"), 1)
+            .remove_page_label_range(1)
+            .apply_to_all([&path]);
+
+        assert!(results[0].1.is_ok());
+
+        let file = std::fs::read(&path).unwrap();
+        assert!(file.starts_with(&original));
+
+        let mut parser = Parser::from_bytes(file, ParseOptions::default()).unwrap();
+
+        let info = parser.info().unwrap().unwrap().into_owned();
+        assert_eq!(info.title.as_deref(), Some("Edited"));
+        assert_eq!(info.author.as_deref(), Some("Original"));
+        assert!(info.mod_date.is_some());
+
+        let labels = parser.page_labels().unwrap().unwrap();
+        assert_eq!(labels.label_for_page(0), "i");
+
+        let root = parser.trailer.root;
+        let catalog = parser.lexer.assert_dict(Object::Reference(root)).unwrap();
+
+        assert!(catalog.peek("Metadata").is_some());
+
+        match catalog.peek("ViewerPreferences") {
+            Some(Object::Dictionary(preferences)) => {
+                assert_eq!(preferences.peek("DisplayDocTitle"), Some(&Object::True))
+            }
+            preferences => panic!("expected viewer preferences, found {:?}", preferences),
+        }
+    }
+
+    #[test]
+    fn empty_edit_creates_no_info() {
+        assert!(MetadataEdit::new().apply_to_info(None).is_none());
+    }
+}
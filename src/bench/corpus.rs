@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+
+use crate::PdfResult;
+
+use super::Stage;
+
+/// A set of documents to measure, read from a manifest
+///
+/// A manifest lists one document per line, as a path relative to the manifest
+/// itself, followed by any number of options separated by whitespace:
+///
+/// ```text
+/// # blank lines and lines beginning with '#' are ignored
+/// reports/annual.pdf
+/// scans/letter.pdf stages=parse,decode
+/// secure/contract.pdf password=hunter2
+/// ```
+///
+/// The `stages` option restricts which stages are measured, such as for
+/// documents which cannot yet be rendered. Paths and passwords may not contain
+/// whitespace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Corpus {
+    pub documents: Vec<CorpusEntry>,
+}
+
+/// A document of a corpus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorpusEntry {
+    pub path: PathBuf,
+
+    /// The password with which to open the document, if it is encrypted
+    pub password: Option<String>,
+
+    /// The stages to measure, in the order in which they are performed
+    pub stages: Vec<Stage>,
+}
+
+impl CorpusEntry {
+    /// The name of the document within the corpus, for labelling results
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap_or(self.path.as_os_str())
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+impl Corpus {
+    /// Read the manifest at `path`
+    pub fn load(path: impl AsRef<Path>) -> PdfResult<Self> {
+        let path = path.as_ref();
+        let manifest = std::fs::read_to_string(path)?;
+
+        Self::parse(&manifest, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// Parse a manifest, resolving the paths of documents relative to
+    /// `base_dir`
+    pub fn parse(manifest: &str, base_dir: &Path) -> PdfResult<Self> {
+        let mut documents = Vec::new();
+
+        for (idx, line) in manifest.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+
+            let mut entry = CorpusEntry {
+                path: base_dir.join(fields.next().unwrap()),
+                password: None,
+                stages: Stage::ALL.to_vec(),
+            };
+
+            for option in fields {
+                match option.split_once('=') {
+                    Some(("password", password)) => entry.password = Some(password.to_owned()),
+                    Some(("stages", stages)) => {
+                        entry.stages = stages
+                            .split(',')
+                            .map(|name| {
+                                Stage::from_name(name).ok_or_else(|| {
+                                    anyhow::anyhow!("unknown stage {:?} on line {}", name, idx + 1)
+                                })
+                            })
+                            .collect::<PdfResult<Vec<Stage>>>()?;
+
+                        entry.stages.sort_by_key(|stage| *stage as u8);
+                    }
+                    _ => anyhow::bail!("unknown option {:?} on line {}", option, idx + 1),
+                }
+            }
+
+            documents.push(entry);
+        }
+
+        Ok(Self { documents })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_manifest() {
+        let manifest = "
+            # documents
+            a.pdf
+            b.pdf   stages=render,parse password=secret
+        ";
+
+        let corpus = Corpus::parse(manifest, Path::new("corpus")).unwrap();
+
+        assert_eq!(
+            corpus.documents,
+            vec![
+                CorpusEntry {
+                    path: PathBuf::from("corpus/a.pdf"),
+                    password: None,
+                    stages: Stage::ALL.to_vec(),
+                },
+                CorpusEntry {
+                    path: PathBuf::from("corpus/b.pdf"),
+                    password: Some("secret".to_owned()),
+                    stages: vec![Stage::Parse, Stage::Render],
+                },
+            ]
+        );
+
+        assert!(Corpus::parse("a.pdf stages=typeset", Path::new("")).is_err());
+        assert!(Corpus::parse("a.pdf level=3", Path::new("")).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use criterion::Criterion;
+
+use crate::{outline::TextLine, ParseOptions, Parser, PdfResult};
+
+use super::{stage_counters, Corpus, CorpusEntry, Stage, StageCounters};
+
+/// The time taken by each stage of processing a document, measured once
+#[derive(Debug, Clone)]
+pub struct DocumentTimings {
+    pub path: PathBuf,
+
+    /// The wall-clock time of each stage that was measured, in order
+    pub stages: Vec<(Stage, Duration)>,
+
+    /// The library's counters over all stages, which break down where the
+    /// time within each stage went
+    pub counters: StageCounters,
+}
+
+/// Measure each stage of every document in the corpus once
+///
+/// Each stage is performed on every page of the document, and stages after
+/// parsing reuse the parsed document.
+pub fn run(corpus: &Corpus) -> PdfResult<Vec<DocumentTimings>> {
+    let mut timings = Vec::with_capacity(corpus.documents.len());
+
+    for entry in &corpus.documents {
+        let before = stage_counters();
+        let mut stages = Vec::with_capacity(entry.stages.len());
+
+        let start = Instant::now();
+        let mut parser = open(entry)?;
+
+        if entry.stages.contains(&Stage::Parse) {
+            stages.push((Stage::Parse, start.elapsed()));
+        }
+
+        for &stage in &entry.stages {
+            if stage == Stage::Parse {
+                continue;
+            }
+
+            let start = Instant::now();
+            perform(stage, &mut parser)?;
+            stages.push((stage, start.elapsed()));
+        }
+
+        timings.push(DocumentTimings {
+            path: entry.path.clone(),
+            stages,
+            counters: stage_counters().since(&before),
+        });
+    }
+
+    Ok(timings)
+}
+
+/// Register a criterion benchmark for each stage of every document in the
+/// corpus, grouped by document
+///
+/// Each stage is performed once up front, so that documents which fail are
+/// reported as errors rather than panicking part way through a benchmark.
+pub fn criterion_benchmarks(criterion: &mut Criterion, corpus: &Corpus) -> PdfResult<()> {
+    for entry in &corpus.documents {
+        let mut group = criterion.benchmark_group(entry.name());
+        let mut parser = open(entry)?;
+
+        for &stage in &entry.stages {
+            if stage == Stage::Parse {
+                group.bench_function(stage.as_str(), |b| b.iter(|| open(entry).unwrap()));
+                continue;
+            }
+
+            perform(stage, &mut parser)?;
+
+            group.bench_function(stage.as_str(), |b| {
+                b.iter(|| perform(stage, &mut parser).unwrap())
+            });
+        }
+
+        group.finish();
+    }
+
+    Ok(())
+}
+
+fn open(entry: &CorpusEntry) -> PdfResult<Parser<'static>> {
+    Parser::with_options(
+        &entry.path,
+        ParseOptions {
+            password: entry.password.clone(),
+            ..ParseOptions::default()
+        },
+    )
+}
+
+/// Perform a stage after parsing on every page of the document
+fn perform(stage: Stage, parser: &mut Parser) -> PdfResult<()> {
+    for page in parser.pages() {
+        match stage {
+            Stage::Parse => unreachable!("documents are parsed when they are opened"),
+            Stage::Decode => {
+                parser.page_contents(&page)?;
+            }
+            Stage::Extract => {
+                let content = parser.page_contents(&page)?;
+                TextLine::from_content(content, &mut parser.lexer)?;
+            }
+            Stage::Render => {
+                parser.rendered_text_lines(&page)?;
+            }
+        }
+    }
+
+    Ok(())
+}
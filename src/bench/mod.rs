@@ -0,0 +1,154 @@
+//! Timing of each stage of processing a document, for measuring performance
+//! against a corpus of documents
+//!
+//! Whenever the `bench` feature is enabled, the library counts the calls to,
+//! and the time spent in, each [`Stage`]. The counters are kept per thread and
+//! are read with [`stage_counters`]. Stages nest: streams decoded while
+//! extracting text count toward both decoding and extraction.
+//!
+//! A [`Corpus`] lists the documents to measure, and [`run`] measures each stage
+//! once for each document. With [`criterion_benchmarks`], each stage of each
+//! document becomes a criterion benchmark, for statistically sound comparisons
+//! between runs.
+
+use std::{
+    cell::RefCell,
+    time::{Duration, Instant},
+};
+
+pub use corpus::{Corpus, CorpusEntry};
+pub use harness::{criterion_benchmarks, run, DocumentTimings};
+
+mod corpus;
+mod harness;
+
+/// A stage of processing a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// Reading the cross-reference table, trailer, catalog, and page tree
+    Parse,
+
+    /// Applying the filters of streams
+    Decode,
+
+    /// Collecting the text shown by content streams
+    Extract,
+
+    /// Interpreting content streams to paint pages
+    Render,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [Stage::Parse, Stage::Decode, Stage::Extract, Stage::Render];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Stage::Parse => "parse",
+            Stage::Decode => "decode",
+            Stage::Extract => "extract",
+            Stage::Render => "render",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|stage| stage.as_str() == s)
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The number of times a stage was entered, and the total time spent in it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageCounter {
+    pub calls: u64,
+    pub elapsed: Duration,
+}
+
+/// The counters of every stage, on the current thread
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageCounters([StageCounter; 4]);
+
+impl StageCounters {
+    pub fn get(&self, stage: Stage) -> StageCounter {
+        self.0[stage.index()]
+    }
+
+    /// The calls to and time spent in each stage since `earlier` was taken
+    pub fn since(&self, earlier: &StageCounters) -> StageCounters {
+        let mut counters = *self;
+
+        for (counter, earlier) in counters.0.iter_mut().zip(earlier.0) {
+            counter.calls -= earlier.calls;
+            counter.elapsed -= earlier.elapsed;
+        }
+
+        counters
+    }
+}
+
+thread_local! {
+    static COUNTERS: RefCell<StageCounters> = RefCell::new(StageCounters::default());
+}
+
+/// The counters of every stage, on the current thread
+pub fn stage_counters() -> StageCounters {
+    COUNTERS.with(|counters| *counters.borrow())
+}
+
+/// Reset the counters of every stage, on the current thread, to zero
+pub fn reset_stage_counters() {
+    COUNTERS.with(|counters| *counters.borrow_mut() = StageCounters::default());
+}
+
+/// Counts the time until it is dropped toward a stage
+pub(crate) struct StageTimer {
+    stage: Stage,
+    start: Instant,
+}
+
+impl StageTimer {
+    pub fn start(stage: Stage) -> Self {
+        Self {
+            stage,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for StageTimer {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+
+        COUNTERS.with(|counters| {
+            let counter = &mut counters.borrow_mut().0[self.stage.index()];
+
+            counter.calls += 1;
+            counter.elapsed += elapsed;
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn count_nested_stages() {
+        reset_stage_counters();
+
+        {
+            let _extract = StageTimer::start(Stage::Extract);
+            let _decode = StageTimer::start(Stage::Decode);
+        }
+
+        drop(StageTimer::start(Stage::Decode));
+
+        let counters = stage_counters();
+
+        assert_eq!(counters.get(Stage::Extract).calls, 1);
+        assert_eq!(counters.get(Stage::Decode).calls, 2);
+        assert_eq!(counters.get(Stage::Render).calls, 0);
+    }
+}
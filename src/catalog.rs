@@ -12,18 +12,24 @@ use std::{collections::HashMap, rc::Rc};
 
 use crate::{
     acro_form::AcroForm,
-    actions::Actions,
+    actions::{Actions, DocumentAdditionalActions},
+    assert_empty,
+    collection::Collection,
     color::ColorSpace,
-    data_structures::{NameTree, NumberTree},
+    data_structures::NameTree,
     date::Date,
     destination::Destination,
+    filter::decode_stream,
     job_ticket::JobTicket,
-    objects::{Name, TypedReference},
+    objects::{decode_text_string, Name, TypedReference},
     optional_content::OptionalContentProperties,
+    outline::DocumentOutline,
+    page_labels::PageLabels,
     stream::Stream,
     structure::StructTreeRoot,
+    version::PdfVersion,
     viewer_preferences::{PageMode, ViewerPreferences},
-    Dictionary, FromObj, Object, PdfResult, Reference, Resolve,
+    Dictionary, FromObj, Object, Parser, PdfResult, Reference, Resolve,
 };
 
 // todo: remove
@@ -45,12 +51,12 @@ pub struct DocumentCatalog<'a> {
     /// therefore shall be preceded by a SOLIDUS (2Fh) character (/) when written
     /// in the PDF file (for example, /1.4).
     #[field("Version")]
-    version: Option<Name>,
+    pub(crate) version: Option<PdfVersion>,
 
     /// An extensions dictionary containing developer prefix identification and
     /// version numbers for developer extensions that occur in this document
     #[field("Extensions")]
-    extensions: Option<Extensions>,
+    pub(crate) extensions: Option<Extensions>,
 
     /// The page tree node that shall be the root of the document's
     /// page tree
@@ -63,15 +69,15 @@ pub struct DocumentCatalog<'a> {
     /// labelling range to which the specified page label dictionary applies. The
     /// tree shall include a value for page index 0.
     #[field("PageLabels")]
-    page_labels: Option<TypedReference<'a, NumberTree<'a>>>,
+    pub(crate) page_labels: Option<TypedReference<'a, PageLabels>>,
 
     /// The document's name dictionary
     #[field("Names")]
-    names: Option<TypedReference<'a, NameDictionary<'a>>>,
+    pub(crate) names: Option<TypedReference<'a, NameDictionary<'a>>>,
 
     /// A dictionary of names and corresponding destinations
     #[field("Dests")]
-    dests: Option<Reference>,
+    pub(crate) dests: Option<Reference>,
 
     /// A viewer preferences dictionary specifying the way the document shall
     /// be displayed on the screen. If this entry is absent, conforming readers
@@ -93,14 +99,14 @@ pub struct DocumentCatalog<'a> {
     ///
     /// Shall be an indirect reference
     #[field("Outlines")]
-    outlines: Option<TypedReference<'a, DocumentOutline>>,
+    pub(crate) outlines: Option<TypedReference<'a, DocumentOutline>>,
 
     /// An array of thread dictionaries that shall represent the document’s
     /// article threads
     ///
     /// Shall be an indirect reference
     #[field("Threads")]
-    threads: Option<TypedReference<'a, ThreadDictionary>>,
+    pub(crate) threads: Option<Vec<Reference>>,
 
     /// A value specifying a destination that shall be displayed or an action
     /// that shall be performed when the document is opened. The value shall be
@@ -110,12 +116,12 @@ pub struct DocumentCatalog<'a> {
     /// If this entry is absent, the document shall be opened to the top of the
     /// first page at the default magnification factor.
     #[field("OpenAction")]
-    open_action: Option<OpenAction<'a>>,
+    pub(crate) open_action: Option<OpenAction<'a>>,
 
     /// An additional-actions dictionary defining the actions that shall be taken
     /// in response to various trigger events affecting the document as a whole
     #[field("AA")]
-    aa: Option<AdditionalActions>,
+    pub(crate) aa: Option<DocumentAdditionalActions<'a>>,
 
     /// A URI dictionary containing document-level information for URI actions
     #[field("URI")]
@@ -123,22 +129,22 @@ pub struct DocumentCatalog<'a> {
 
     /// The document’s interactive form (AcroForm) dictionary
     #[field("AcroForm")]
-    acro_form: Option<TypedReference<'a, AcroForm<'a>>>,
+    pub(crate) acro_form: Option<TypedReference<'a, AcroForm<'a>>>,
 
     /// A metadata stream that shall contain metadata for the document
     ///
     /// Shall be an indirect reference
     #[field("Metadata")]
-    metadata: Option<Reference>,
+    pub(crate) metadata: Option<Reference>,
 
     /// The document’s structure tree root dictionary
     #[field("StructTreeRoot")]
-    struct_tree_root: Option<TypedReference<'a, StructTreeRoot<'a>>>,
+    pub(crate) struct_tree_root: Option<TypedReference<'a, StructTreeRoot<'a>>>,
 
     /// A mark information dictionary that shall contain information about the
     /// document's usage of Tagged PDF conventions
     #[field("MarkInfo")]
-    mark_info: Option<MarkInformationDictionary>,
+    pub(crate) mark_info: Option<MarkInformationDictionary>,
 
     /// A language identifier that shall specify the natural language for all
     /// text in the document except where overridden by language specifications
@@ -146,7 +152,7 @@ pub struct DocumentCatalog<'a> {
     ///
     /// If this entry is absent, the language shall be considered unknown.
     #[field("Lang")]
-    lang: Option<String>,
+    pub(crate) lang: Option<String>,
 
     /// A Web Capture information dictionary that shall contain state information
     /// used by any Web Capture extension
@@ -166,27 +172,27 @@ pub struct DocumentCatalog<'a> {
     ///
     /// Required if a document contains optional content
     #[field("OCProperties")]
-    oc_properties: Option<OptionalContentProperties<'a>>,
+    pub(crate) oc_properties: Option<OptionalContentProperties<'a>>,
 
     /// A permissions dictionary that shall specify user access permissions for
     /// the document.
     #[field("Perms")]
-    perms: Option<Permissions>,
+    pub(crate) perms: Option<PermissionsDictionary>,
 
     /// A dictionary that shall contain attestations regarding the content of a
     /// PDF document, as it relates to the legality of digital signatures
     #[field("Legal")]
-    legal: Option<Legal>,
+    pub(crate) legal: Option<LegalAttestation>,
 
     /// An array of requirement dictionaries that shall represent requirements
     /// for the document
     #[field("Requirements")]
-    requirements: Option<Vec<Requirement>>,
+    pub(crate) requirements: Option<Vec<Requirement>>,
 
     /// A collection dictionary that a conforming reader shall use to enhance
     /// the presentation of file attachments stored in the PDF document.
     #[field("Collection")]
-    collection: Option<Collection>,
+    pub(crate) collection: Option<Collection>,
 
     /// A flag used to expedite the display of PDF documents containing XFA forms.
     /// It specifies whether the document shall be regenerated when the document
@@ -206,40 +212,62 @@ pub struct DocumentCatalog<'a> {
     /// reference in the catalog
     #[field("Info")]
     info: Option<TypedReference<'a, InformationDictionary<'a>>>,
+
+    /// The document security store, which shall contain the validation data
+    /// of the document's signatures
+    #[field("DSS")]
+    pub(crate) dss: Option<Object<'a>>,
 }
 
-#[derive(Debug, Clone, FromObj)]
+#[derive(Debug, Clone, FromObj, ToObj)]
 pub struct InformationDictionary<'a> {
     #[field("Title")]
-    title: Option<String>,
+    pub(crate) title: Option<String>,
     #[field("Author")]
-    author: Option<String>,
+    pub(crate) author: Option<String>,
     #[field("Subject")]
-    subject: Option<String>,
+    pub(crate) subject: Option<String>,
     #[field("Keywords")]
-    keywords: Option<String>,
+    pub(crate) keywords: Option<String>,
 
     /// If the document was converted to PDF from another format, the name of the
     /// conforming product that created the original document from which it was
     /// converted
     #[field("Creator")]
-    creator: Option<String>,
+    pub(crate) creator: Option<String>,
 
     /// If the document was converted to PDF from another format, the name of
     /// the conforming product that converted it to PDF
     #[field("Producer")]
-    producer: Option<String>,
+    pub(crate) producer: Option<String>,
 
     #[field("CreationDate")]
-    creation_date: Option<Date>,
+    pub(crate) creation_date: Option<Date>,
     #[field("ModDate")]
-    mod_date: Option<Date>,
+    pub(crate) mod_date: Option<Date>,
     #[field("Trapped", default = Trapped::default())]
-    trapped: Trapped,
+    pub(crate) trapped: Trapped,
 
     // todo: "other" field
     #[field]
-    other: Dictionary<'a>,
+    pub(crate) other: Dictionary<'a>,
+}
+
+impl Default for InformationDictionary<'_> {
+    fn default() -> Self {
+        Self {
+            title: None,
+            author: None,
+            subject: None,
+            keywords: None,
+            creator: None,
+            producer: None,
+            creation_date: None,
+            mod_date: None,
+            trapped: Trapped::default(),
+            other: Dictionary::empty(),
+        }
+    }
 }
 
 /// A name object indicating whether the document has been modified to include
@@ -262,45 +290,110 @@ pub enum Trapped {
     Unknown = "Unknown",
 }
 
-#[derive(Debug)]
-pub struct Extensions(HashMap<String, DeveloperExtensions>);
+/// The developer extensions used by the document, by the prefix registered
+/// for their developer, such as `ADBE` for Adobe
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Extensions(HashMap<String, Vec<DeveloperExtensions>>);
 
 impl<'a> FromObj<'a> for Extensions {
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
         let mut dict = resolver.assert_dict(obj)?;
         dict.expect_type("Extensions", resolver, false)?;
-        Ok(Self(<HashMap<String, DeveloperExtensions>>::from_obj(
-            Object::Dictionary(dict),
-            resolver,
-        )?))
+
+        let mut extensions = HashMap::new();
+
+        for (prefix, obj) in dict.entries() {
+            // PDF 2.0 allows a developer to declare several extensions as an
+            // array
+            let developer_extensions = match resolver.resolve(obj)? {
+                Object::Array(arr) => arr
+                    .into_iter()
+                    .map(|obj| DeveloperExtensions::from_obj(obj, resolver))
+                    .collect::<PdfResult<Vec<DeveloperExtensions>>>()?,
+                obj => vec![DeveloperExtensions::from_obj(obj, resolver)?],
+            };
+
+            extensions.insert(prefix, developer_extensions);
+        }
+
+        Ok(Self(extensions))
     }
 }
 
-#[derive(Debug, FromObj)]
+impl Extensions {
+    /// The prefix registered by Adobe
+    pub const ADOBE: &'static str = "ADBE";
+
+    /// The extensions declared by the developer with `prefix`
+    pub fn get(&self, prefix: &str) -> &[DeveloperExtensions] {
+        self.0.get(prefix).map_or(&[], Vec::as_slice)
+    }
+
+    /// The prefixes of the developers whose extensions are used
+    pub fn prefixes(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    /// The highest level of the extensions declared by the developer with
+    /// `prefix` to `base_version`
+    pub fn level(&self, prefix: &str, base_version: PdfVersion) -> Option<i32> {
+        self.get(prefix)
+            .iter()
+            .filter(|extension| extension.base_version == base_version)
+            .map(|extension| extension.extension_level)
+            .max()
+    }
+
+    /// The Adobe Extension Level of the document, an extension of PDF 1.7
+    ///
+    /// Level 3 adds AES-256 encryption, and level 5 adds portfolio navigators
+    /// and folders.
+    pub fn adobe_extension_level(&self) -> Option<i32> {
+        self.level(Self::ADOBE, PdfVersion::V1_7)
+    }
+}
+
+/// A developer extension, identifying a version of PDF and the changes to it
+/// made by the developer
+#[derive(Debug, Clone, PartialEq, FromObj)]
 #[obj_type("DeveloperExtensions")]
 pub struct DeveloperExtensions {
     /// The name of the PDF version to which this extension applies. The name shall
     /// be consistent with the syntax used for the Version entry of the catalog
     /// dictionary
     #[field("BaseVersion")]
-    base_version: Name,
+    pub base_version: PdfVersion,
 
     /// An integer defined by the developer to denote the extension being used. If
     /// the developer introduces more than one extension to a given BaseVersion
     /// the extension level numbers assigned by that developer shall increase
     /// over time.
     #[field("ExtensionLevel")]
-    extension_level: i32,
+    pub extension_level: i32,
+
+    /// (PDF 2.0) A URL to the documentation of the extension
+    #[field("URL")]
+    pub url: Option<String>,
+
+    /// (PDF 2.0) Additional revision information of the extension level
+    #[field("ExtensionRevision")]
+    extension_revision: Option<String>,
+}
+
+impl DeveloperExtensions {
+    pub fn extension_revision(&self) -> Option<String> {
+        self.extension_revision.as_deref().map(decode_text_string)
+    }
 }
 
 #[derive(Debug, FromObj)]
 struct Language;
 
-#[derive(Debug, FromObj)]
+#[derive(Debug, Clone, FromObj)]
 pub struct NameDictionary<'a> {
     /// A name tree mapping name strings to destinations
     #[field("Dests")]
-    dests: Option<NameTree<'a>>,
+    pub(crate) dests: Option<NameTree<'a>>,
 
     /// A name tree mapping name strings to annotation appearance streams
     #[field("AP")]
@@ -308,7 +401,7 @@ pub struct NameDictionary<'a> {
 
     /// A name tree mapping name strings to document-level JavaScript actions
     #[field("JavaScript")]
-    java_script: Option<NameTree<'a>>,
+    pub(crate) java_script: Option<NameTree<'a>>,
 
     /// A name tree mapping name strings to visible pages for use in interactive
     /// forms
@@ -332,11 +425,11 @@ pub struct NameDictionary<'a> {
     /// A name tree mapping name strings to file specifications for embedded file
     /// streams
     #[field("EmbeddedFiles")]
-    embedded_files: Option<NameTree<'a>>,
+    pub(crate) embedded_files: Option<NameTree<'a>>,
 
     /// A name tree mapping name strings to alternate presentations
     #[field("AlternatePresentations")]
-    alternate_presentations: Option<NameTree<'a>>,
+    pub(crate) alternate_presentations: Option<NameTree<'a>>,
 
     /// A name tree mapping name strings (which shall have Unicode encoding) to
     /// rendition objects
@@ -346,10 +439,6 @@ pub struct NameDictionary<'a> {
 
 #[derive(Debug, FromObj)]
 pub struct NamedDestinations;
-#[derive(Debug, FromObj)]
-pub struct DocumentOutline;
-#[derive(Debug, FromObj)]
-pub struct ThreadDictionary;
 
 #[derive(Debug)]
 pub enum OpenAction<'a> {
@@ -369,8 +458,6 @@ impl<'a> FromObj<'a> for OpenAction<'a> {
     }
 }
 
-#[derive(Debug, FromObj)]
-pub struct AdditionalActions;
 #[derive(Debug, FromObj)]
 pub struct UriDict;
 
@@ -383,6 +470,15 @@ pub struct MetadataStream<'a> {
     stream: Stream<'a>,
 }
 
+impl<'a> MetadataStream<'a> {
+    /// The XMP packet held by the stream, which is encoded as UTF-8
+    pub fn decode(&self, resolver: &mut dyn Resolve<'a>) -> PdfResult<String> {
+        let data = decode_stream(&self.stream.stream, &self.stream.dict, resolver)?;
+
+        Ok(String::from_utf8_lossy(&data).into_owned())
+    }
+}
+
 #[pdf_enum]
 enum MetadataStreamSubtype {
     Xml = "XML",
@@ -398,7 +494,7 @@ pub struct MarkInformationDictionary {
     ///
     /// Default value: false
     #[field("Marked", default = false)]
-    marked: bool,
+    pub(crate) marked: bool,
 
     /// A flag indicating the presence of structure elements that contain user
     /// properties attributes
@@ -483,14 +579,180 @@ impl<'a> FromObj<'a> for PagePiece<'a> {
     }
 }
 
-#[derive(Debug, FromObj)]
-pub struct Permissions;
-#[derive(Debug, FromObj)]
-pub struct Legal;
-#[derive(Debug, FromObj)]
-pub struct Requirement;
+/// The signatures which grant permissions to the users of the document
+#[derive(Debug, Clone, PartialEq, FromObj)]
+pub struct PermissionsDictionary {
+    /// The certification signature, whose DocMDP transform gives the changes
+    /// permitted to the document after it was certified
+    #[field("DocMDP")]
+    pub doc_mdp: Option<Reference>,
+
+    /// A usage rights signature, which enables additional features of a
+    /// viewer
+    #[field("UR3")]
+    pub ur3: Option<Reference>,
+}
+
+/// The legal attestation dictionary, in which the author of a signed document
+/// declares the content which could make it appear differently when viewed,
+/// and so affect what was signed
+///
+/// Each count is the number of instances of that content in the document.
+#[derive(Debug, Clone, PartialEq, FromObj)]
+pub struct LegalAttestation {
+    #[field("JavaScriptActions")]
+    pub javascript_actions: Option<u32>,
+    #[field("LaunchActions")]
+    pub launch_actions: Option<u32>,
+    #[field("URIActions")]
+    pub uri_actions: Option<u32>,
+    #[field("MovieActions")]
+    pub movie_actions: Option<u32>,
+    #[field("SoundActions")]
+    pub sound_actions: Option<u32>,
+    #[field("HideAnnotationActions")]
+    pub hide_annotation_actions: Option<u32>,
+    #[field("GoToRemoteActions")]
+    pub go_to_remote_actions: Option<u32>,
+
+    /// Image XObjects with Alternates entries
+    #[field("AlternateImages")]
+    pub alternate_images: Option<u32>,
+
+    /// Streams whose data is held in an external file
+    #[field("ExternalStreams")]
+    pub external_streams: Option<u32>,
+    #[field("TrueTypeFonts")]
+    pub true_type_fonts: Option<u32>,
+
+    /// Reference XObjects, which import content from another document
+    #[field("ExternalRefXobjects")]
+    pub external_ref_xobjects: Option<u32>,
+
+    /// OPI dictionaries, which refer to images held outside the document
+    #[field("ExternalOPIdicts")]
+    pub external_opi_dicts: Option<u32>,
+    #[field("NonEmbeddedFonts")]
+    pub non_embedded_fonts: Option<u32>,
+
+    /// Graphics state parameter dictionaries with the device-dependent OP,
+    /// HT, TR, UCR, BG and FL entries
+    #[field("DevDepGS_OP")]
+    pub dev_dep_gs_op: Option<u32>,
+    #[field("DevDepGS_HT")]
+    pub dev_dep_gs_ht: Option<u32>,
+    #[field("DevDepGS_TR")]
+    pub dev_dep_gs_tr: Option<u32>,
+    #[field("DevDepGS_UCR")]
+    pub dev_dep_gs_ucr: Option<u32>,
+    #[field("DevDepGS_BG")]
+    pub dev_dep_gs_bg: Option<u32>,
+    #[field("DevDepGS_FL")]
+    pub dev_dep_gs_fl: Option<u32>,
+
+    /// Annotations, other than links, whose appearance is not defined by an
+    /// appearance stream
+    #[field("Annotations")]
+    pub annotations: Option<u32>,
+
+    /// Whether the document contains optional content
+    #[field("OptionalContent")]
+    pub optional_content: Option<bool>,
+
+    /// An attestation from the author about the content of the document
+    #[field("Attestation")]
+    attestation: Option<String>,
+}
+
+impl LegalAttestation {
+    /// The author's attestation about the content of the document, as text
+    pub fn attestation(&self) -> Option<String> {
+        self.attestation.as_deref().map(decode_text_string)
+    }
+}
+
+/// A feature which a reader must support to process the document as intended
+#[derive(Debug, Clone, PartialEq)]
+pub struct Requirement {
+    /// The kind of feature required, such as `EnableJavaScripts`
+    pub requirement_type: String,
+
+    /// The version of the feature required, if any
+    pub version: Option<String>,
+
+    /// The handlers which check whether a reader meets the requirement, in
+    /// order of preference
+    pub handlers: Vec<RequirementHandler>,
+
+    /// How severely a reader which does not meet the requirement is penalized
+    /// when choosing among alternates, from 0 to 100
+    ///
+    /// Default value: 100
+    pub penalty: i32,
+
+    /// (PDF 2.0) The developer extensions which define the requirement
+    pub extensions: Option<Extensions>,
+}
+
+impl<'a> FromObj<'a> for Requirement {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        dict.expect_type("Requirement", resolver, false)?;
+
+        let requirement_type = dict.expect_name("S", resolver)?;
+        let version = dict.get_name("V", resolver)?;
+        let handlers = match dict.get_object("RH", resolver)? {
+            Some(Object::Array(arr)) => arr
+                .into_iter()
+                .map(|obj| RequirementHandler::from_obj(obj, resolver))
+                .collect::<PdfResult<Vec<RequirementHandler>>>()?,
+            Some(obj) => vec![RequirementHandler::from_obj(obj, resolver)?],
+            None => Vec::new(),
+        };
+        let penalty = dict.get_integer("Penalty", resolver)?.unwrap_or(100);
+
+        let extensions = dict.get::<Extensions>("Extensions", resolver)?;
+
+        assert_empty(dict);
+
+        Ok(Self {
+            requirement_type,
+            version,
+            handlers,
+            penalty,
+            extensions,
+        })
+    }
+}
+
+/// A means of checking whether a reader meets a requirement
 #[derive(Debug, Clone, PartialEq, FromObj)]
-pub struct Collection;
+#[obj_type("ReqHandler")]
+pub struct RequirementHandler {
+    #[field("S")]
+    pub handler_type: RequirementHandlerType,
+
+    /// The name of the document-level JavaScript action which checks the
+    /// requirement, for JavaScript handlers
+    #[field("Script")]
+    script: Option<String>,
+}
+
+impl RequirementHandler {
+    pub fn script(&self) -> Option<String> {
+        self.script.as_deref().map(decode_text_string)
+    }
+}
+
+#[pdf_enum]
+pub enum RequirementHandlerType {
+    /// A document-level JavaScript action checks the requirement
+    JavaScript = "JS",
+
+    /// The requirement is always met
+    NoOp = "NoOp",
+}
 #[derive(Debug, FromObj)]
 pub struct BoxColorInfo;
 
@@ -572,17 +834,6 @@ pub struct GroupAttributes<'a> {
     is_knockout: bool,
 }
 
-#[derive(Debug, FromObj)]
-pub struct Transitions;
-#[derive(Debug, FromObj)]
-pub struct SeparationInfo;
-#[derive(Debug, FromObj)]
-pub struct NavigationNode;
-#[derive(Debug, FromObj)]
-pub struct Viewport;
-#[derive(Debug, FromObj)]
-pub struct PropertyList;
-
 /// Specifies the page layout when the document is opened
 #[pdf_enum]
 #[derive(Default)]
@@ -606,3 +857,165 @@ enum PageLayout {
     /// Display the pages two at a time, with odd-numbered pages on the right
     TwoPageRight = "TwoPageRight",
 }
+
+impl<'a> Parser<'a> {
+    /// The developer extensions of the PDF specification used by the document
+    pub fn extensions(&self) -> Option<&Extensions> {
+        self.catalog.extensions.as_ref()
+    }
+
+    /// Whether the document may use AES-256 encryption, which was added to
+    /// PDF 1.7 by Adobe Extension Level 3 and is standard from PDF 2.0
+    pub fn supports_aes_256(&self) -> bool {
+        self.version >= PdfVersion::V2_0
+            || self
+                .extensions()
+                .and_then(Extensions::adobe_extension_level)
+                .map_or(false, |level| level >= 3)
+    }
+
+    /// The permissions dictionary of the catalog, which refers to the
+    /// certification and usage rights signatures of the document
+    pub fn permission_signatures(&self) -> Option<&PermissionsDictionary> {
+        self.catalog.perms.as_ref()
+    }
+
+    /// The author's declaration of the content of a signed document which may
+    /// affect how it appears, such as scripts and fonts which are not embedded
+    pub fn legal_attestation(&self) -> Option<&LegalAttestation> {
+        self.catalog.legal.as_ref()
+    }
+
+    /// The features which a reader must support to process the document as
+    /// intended
+    pub fn requirements(&self) -> &[Requirement] {
+        self.catalog.requirements.as_deref().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    fn parse<T: FromObj<'static>>(obj: &'static [u8]) -> PdfResult<T> {
+        let mut tokenizer = Tokenizer::new(obj);
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+
+        T::from_obj(obj, &mut tokenizer)
+    }
+
+    #[test]
+    fn requirements() {
+        let requirements = parse::<Vec<Requirement>>(
+            b"[<< /Type /Requirement /S /EnableJavaScripts /V /1.5 \
+                /RH << /Type /ReqHandler /S /JS /Script (Check\\(\\)) >> >> \
+               << /S /DigSigValidation /Penalty 0 \
+                /RH [<< /S /NoOp >> << /S /JS >>] >>]",
+        )
+        .unwrap();
+
+        assert_eq!(requirements.len(), 2);
+
+        assert_eq!(requirements[0].requirement_type, "EnableJavaScripts");
+        assert_eq!(requirements[0].version.as_deref(), Some("1.5"));
+        assert_eq!(requirements[0].penalty, 100);
+        assert_eq!(
+            requirements[0].handlers[0].handler_type,
+            RequirementHandlerType::JavaScript
+        );
+        assert_eq!(
+            requirements[0].handlers[0].script().as_deref(),
+            Some("Check()")
+        );
+
+        assert_eq!(requirements[1].version, None);
+        assert_eq!(requirements[1].penalty, 0);
+        assert_eq!(
+            requirements[1]
+                .handlers
+                .iter()
+                .map(|handler| handler.handler_type)
+                .collect::<Vec<_>>(),
+            [
+                RequirementHandlerType::NoOp,
+                RequirementHandlerType::JavaScript
+            ]
+        );
+
+        assert!(parse::<Requirement>(b"<< /Type /Requirement >>").is_err());
+    }
+
+    #[test]
+    fn developer_extensions() {
+        let extensions = parse::<Extensions>(
+            b"<< /Type /Extensions \
+                /ADBE << /Type /DeveloperExtensions /BaseVersion /1.7 /ExtensionLevel 8 >> \
+                /GLGR [<< /BaseVersion /1.7 /ExtensionLevel 1002 >> \
+                       << /BaseVersion /2.0 /ExtensionLevel 1 /URL (https://example.com/ext) \
+                          /ExtensionRevision (rev. 2) >> \
+                       << /BaseVersion /1.7 /ExtensionLevel 1008 >>] >>",
+        )
+        .unwrap();
+
+        let mut prefixes = extensions.prefixes().collect::<Vec<_>>();
+        prefixes.sort_unstable();
+        assert_eq!(prefixes, ["ADBE", "GLGR"]);
+
+        assert_eq!(extensions.adobe_extension_level(), Some(8));
+        assert_eq!(extensions.level("GLGR", PdfVersion::V1_7), Some(1008));
+        assert_eq!(extensions.level("GLGR", PdfVersion::V2_0), Some(1));
+        assert_eq!(extensions.level("ISO_", PdfVersion::V2_0), None);
+        assert!(extensions.get("ISO_").is_empty());
+
+        let glgr = &extensions.get("GLGR")[1];
+        assert_eq!(glgr.base_version, PdfVersion::V2_0);
+        assert_eq!(glgr.url.as_deref(), Some("https://example.com/ext"));
+        assert_eq!(glgr.extension_revision().as_deref(), Some("rev. 2"));
+        assert_eq!(extensions.get("ADBE")[0].extension_revision(), None);
+
+        let requirement = parse::<Requirement>(
+            b"<< /S /OCInteract /Extensions << /ADBE << /BaseVersion /1.7 /ExtensionLevel 3 >> >> >>",
+        )
+        .unwrap();
+        assert_eq!(
+            requirement.extensions.unwrap().adobe_extension_level(),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn legal_attestation() {
+        let legal = parse::<LegalAttestation>(
+            b"<< /JavaScriptActions 2 /NonEmbeddedFonts 1 /DevDepGS_OP 3 \
+                /OptionalContent true /Attestation (No scripts run on open) >>",
+        )
+        .unwrap();
+
+        assert_eq!(legal.javascript_actions, Some(2));
+        assert_eq!(legal.non_embedded_fonts, Some(1));
+        assert_eq!(legal.dev_dep_gs_op, Some(3));
+        assert_eq!(legal.launch_actions, None);
+        assert_eq!(legal.optional_content, Some(true));
+        assert_eq!(
+            legal.attestation().as_deref(),
+            Some("No scripts run on open")
+        );
+    }
+
+    #[test]
+    fn permissions_dictionary() {
+        let perms = parse::<PermissionsDictionary>(b"<< /DocMDP 12 0 R >>").unwrap();
+
+        assert_eq!(
+            perms,
+            PermissionsDictionary {
+                doc_mdp: Some(Reference {
+                    object_number: 12,
+                    generation: 0,
+                }),
+                ur3: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,458 @@
+/*!
+ * Portable collections, also known as PDF portfolios
+ *
+ * A collection presents the files embedded in a document as its contents. The
+ * collection dictionary describes how a reader lays them out, and the collection
+ * item dictionary of each file specification holds the values displayed for
+ * that file, such as its size or an author.
+ */
+
+use std::collections::BTreeMap;
+
+use crate::{
+    assert_empty,
+    attachment::Attachment,
+    collection,
+    date::Date,
+    error::PdfResult,
+    objects::{decode_text_string, Dictionary, Object},
+    writer, FromObj, Parser, Resolve,
+};
+
+/// A collection dictionary, which makes the document a portfolio of its
+/// embedded files
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collection {
+    /// The fields of the collection items, in ascending order of their O entry
+    pub schema: Vec<CollectionField>,
+
+    /// The name in the EmbeddedFiles name tree of the file initially presented
+    /// in the user interface, rather than the document itself
+    pub initial_document: Option<String>,
+
+    pub view: CollectionView,
+
+    /// The fields by which the files are ordered, each with whether it is in
+    /// ascending order
+    pub sort: Vec<(String, bool)>,
+
+    pub navigator: Option<CollectionNavigator>,
+}
+
+impl<'a> FromObj<'a> for Collection {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        dict.expect_type("Collection", resolver, false)?;
+
+        let mut schema = match dict.get_dict("Schema", resolver)? {
+            Some(schema) => collection_schema(schema, resolver)?,
+            None => Vec::new(),
+        };
+        schema.sort_by_key(|field| field.order);
+
+        let initial_document = dict.get_string("D", resolver)?;
+        let view = match dict.get_name("View", resolver)? {
+            Some(view) => CollectionView::from_str(&view)?,
+            None => CollectionView::Details,
+        };
+        let sort = match dict.get_dict("Sort", resolver)? {
+            Some(sort) => collection_sort(sort, resolver)?,
+            None => Vec::new(),
+        };
+        let navigator = dict
+            .get_object("Navigator", resolver)?
+            .map(|navigator| CollectionNavigator::from_obj(navigator, resolver))
+            .transpose()?;
+
+        // todo: the colors, folders and split of the user interface
+        dict.get_object("Colors", resolver)?;
+        dict.get_object("Folders", resolver)?;
+        dict.get_object("Split", resolver)?;
+
+        assert_empty(dict);
+
+        Ok(Self {
+            schema,
+            initial_document,
+            view,
+            sort,
+            navigator,
+        })
+    }
+}
+
+impl Collection {
+    /// The field of the schema whose key in the collection item dictionaries is
+    /// `key`
+    pub fn field(&self, key: &str) -> Option<&CollectionField> {
+        self.schema.iter().find(|field| field.key == key)
+    }
+}
+
+/// A field of the collection schema, one column of the details view
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionField {
+    /// The key of the field in the collection schema and item dictionaries
+    pub key: String,
+
+    /// The name of the field presented to the user
+    pub name: String,
+
+    pub field_type: CollectionFieldType,
+
+    /// The relative order of the field in the user interface
+    pub order: Option<i32>,
+
+    pub is_visible: bool,
+    pub is_editable: bool,
+}
+
+impl<'a> FromObj<'a> for CollectionField {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        dict.expect_type("CollectionField", resolver, false)?;
+
+        let field_type = CollectionFieldType::from_str(&dict.expect_name("Subtype", resolver)?)?;
+        let name = decode_text_string(&dict.expect_string("N", resolver)?);
+        let order = dict.get_integer("O", resolver)?;
+        let is_visible = dict.get_bool("V", resolver)?.unwrap_or(true);
+        let is_editable = dict.get_bool("E", resolver)?.unwrap_or(false);
+
+        assert_empty(dict);
+
+        Ok(Self {
+            key: String::new(),
+            name,
+            field_type,
+            order,
+            is_visible,
+            is_editable,
+        })
+    }
+}
+
+/// The kind of data of a collection field
+///
+/// Fields other than text, dates and numbers take their values from the file
+/// specification or embedded file stream instead of the collection item.
+#[pdf_enum]
+pub enum CollectionFieldType {
+    Text = "S",
+    Date = "D",
+    Number = "N",
+    FileName = "F",
+    Description = "Desc",
+    ModificationDate = "ModDate",
+    CreationDate = "CreationDate",
+    Size = "Size",
+
+    /// PDF 2.0
+    CompressedSize = "CompressedSize",
+}
+
+/// How the files of a collection are initially presented
+#[pdf_enum]
+pub enum CollectionView {
+    /// A list of the files, with a column for each field of the schema
+    Details = "D",
+
+    /// A grid of icons or thumbnails of the files
+    Tile = "T",
+
+    /// The collection is hidden, and the files are listed by the reader's
+    /// attachments view
+    Hidden = "H",
+
+    /// PDF 2.0: presented by the navigator
+    Custom = "C",
+}
+
+/// A navigator, which presents the files of the collection in a custom layout
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionNavigator {
+    /// The layouts supported by the navigator, such as `D`, `T`, `H`, or an
+    /// extended layout like `FilmStrip`, in order of preference
+    pub layouts: Vec<String>,
+}
+
+impl<'a> FromObj<'a> for CollectionNavigator {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        dict.expect_type("Navigator", resolver, false)?;
+
+        let layouts = match dict.get_object("Layout", resolver)? {
+            Some(Object::Array(layouts)) => layouts
+                .into_iter()
+                .map(|layout| resolver.assert_name(layout))
+                .collect::<PdfResult<Vec<String>>>()?,
+            Some(layout) => vec![resolver.assert_name(layout)?],
+            None => Vec::new(),
+        };
+
+        // other entries, such as those of Flash-based navigators, are not used
+        Ok(Self { layouts })
+    }
+}
+
+/// A value of a collection item
+#[derive(Debug, Clone, PartialEq)]
+pub enum CollectionValue {
+    Text(String),
+    Date(Date),
+    Number(f32),
+}
+
+/// The value of one field of a collection item
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionItemValue {
+    pub value: CollectionValue,
+
+    /// Text displayed before the value, which is ignored when sorting
+    pub prefix: Option<String>,
+}
+
+/// An embedded file of a portfolio, with the values of its collection item
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioMember {
+    pub attachment: Attachment,
+
+    /// The values of the collection item, by the key of their field
+    pub fields: BTreeMap<String, CollectionItemValue>,
+}
+
+impl PortfolioMember {
+    /// Whether the member is itself a PDF document, which may be opened with
+    /// [`crate::Parser::from_bytes`]
+    pub fn is_pdf(&self) -> bool {
+        self.attachment.mime_type.as_deref() == Some("application/pdf")
+            || self.attachment.data.starts_with(b"%PDF-")
+    }
+}
+
+fn collection_schema<'a>(
+    schema: Dictionary<'a>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Vec<CollectionField>> {
+    let mut fields = Vec::new();
+
+    for (key, obj) in schema.entries() {
+        if key == "Type" {
+            continue;
+        }
+
+        let mut field = CollectionField::from_obj(obj, resolver)?;
+        field.key = key;
+
+        fields.push(field);
+    }
+
+    Ok(fields)
+}
+
+fn collection_sort<'a>(
+    mut sort: Dictionary<'a>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Vec<(String, bool)>> {
+    sort.expect_type("CollectionSort", resolver, false)?;
+
+    let keys = match sort.expect_object("S", resolver)? {
+        Object::Array(keys) => keys
+            .into_iter()
+            .map(|key| resolver.assert_name(key))
+            .collect::<PdfResult<Vec<String>>>()?,
+        key => vec![resolver.assert_name(key)?],
+    };
+
+    // a single boolean applies to the first key, and each key without one is
+    // in ascending order
+    let ascending = match sort.get_object("A", resolver)? {
+        Some(Object::Array(ascending)) => ascending
+            .into_iter()
+            .map(|ascending| resolver.assert_bool(ascending))
+            .collect::<PdfResult<Vec<bool>>>()?,
+        Some(ascending) => vec![resolver.assert_bool(ascending)?],
+        None => Vec::new(),
+    };
+
+    assert_empty(sort);
+
+    Ok(keys
+        .into_iter()
+        .enumerate()
+        .map(|(idx, key)| (key, ascending.get(idx).copied().unwrap_or(true)))
+        .collect())
+}
+
+/// The values of the collection item dictionary `obj`
+///
+/// Strings are dates where `collection` declares a date field for them, and
+/// text otherwise.
+pub(crate) fn collection_item<'a>(
+    obj: Object<'a>,
+    collection: Option<&Collection>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<BTreeMap<String, CollectionItemValue>> {
+    let mut fields = BTreeMap::new();
+
+    for (key, obj) in resolver.assert_dict(obj)?.entries() {
+        if key == "Type" {
+            continue;
+        }
+
+        let is_date = collection
+            .and_then(|collection| collection.field(&key))
+            .map_or(false, |field| field.field_type == CollectionFieldType::Date);
+
+        let (value, prefix) = match resolver.resolve(obj)? {
+            Object::Dictionary(mut subitem) => {
+                subitem.expect_type("CollectionSubitem", resolver, false)?;
+
+                let value = subitem.get_object("D", resolver)?;
+                let prefix = subitem
+                    .get_string("P", resolver)?
+                    .map(|prefix| decode_text_string(&prefix));
+
+                assert_empty(subitem);
+
+                match value {
+                    Some(value) => (value, prefix),
+                    None => continue,
+                }
+            }
+            obj => (obj, None),
+        };
+
+        let value = match value {
+            Object::Integer(n) => CollectionValue::Number(n as f32),
+            Object::Real(n) => CollectionValue::Number(n),
+            Object::String(s) if is_date => match Date::from_str(&s) {
+                Ok(date) => CollectionValue::Date(date),
+                Err(..) => CollectionValue::Text(decode_text_string(&s)),
+            },
+            Object::String(s) => CollectionValue::Text(decode_text_string(&s)),
+            found => anyhow::bail!("expected collection item value, found {:?}", found),
+        };
+
+        fields.insert(key, CollectionItemValue { value, prefix });
+    }
+
+    Ok(fields)
+}
+
+impl<'a> Parser<'a> {
+    /// The collection dictionary of the document, if it is a portfolio
+    pub fn collection(&self) -> Option<&Collection> {
+        self.catalog.collection.as_ref()
+    }
+
+    /// Every embedded file of the document, with the values of its collection
+    /// item
+    ///
+    /// The contents of each member are extracted into its attachment. Files
+    /// without a collection item have no fields.
+    pub fn portfolio(&mut self) -> PdfResult<Vec<PortfolioMember>> {
+        let names = match &self.catalog.names {
+            Some(names) => names.get_ref(&mut self.lexer)?.into_owned(),
+            None => return Ok(Vec::new()),
+        };
+
+        let embedded_files = match &names.embedded_files {
+            Some(embedded_files) => embedded_files,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut members = Vec::new();
+
+        for (name, obj) in embedded_files.entries() {
+            let collection_item = match self.lexer.resolve(obj.clone())? {
+                Object::Dictionary(spec) => spec.peek("CI").cloned(),
+                _ => None,
+            };
+
+            let attachment =
+                match Attachment::from_file_specification(name, obj.clone(), &mut self.lexer)? {
+                    Some(attachment) => attachment,
+                    None => continue,
+                };
+
+            let fields = match collection_item {
+                Some(item) => collection::collection_item(
+                    item,
+                    self.catalog.collection.as_ref(),
+                    &mut self.lexer,
+                )?,
+                None => BTreeMap::new(),
+            };
+
+            members.push(PortfolioMember { attachment, fields });
+        }
+
+        Ok(members)
+    }
+
+    /// Embed the file of `member` with the values of its collection item,
+    /// returning the file with an incremental update appended
+    ///
+    /// A member with the same name is replaced. Documents which are not yet
+    /// portfolios are given a collection dictionary.
+    pub fn add_portfolio_member(&mut self, member: &PortfolioMember) -> PdfResult<Vec<u8>> {
+        writer::add_portfolio_member(&self.trailer, member, &mut self.lexer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{version::PdfVersion, writer::PdfWriter};
+
+    #[test]
+    fn collection_item_values() {
+        let schema = Collection {
+            schema: vec![CollectionField {
+                key: "Received".to_owned(),
+                name: "Received".to_owned(),
+                field_type: CollectionFieldType::Date,
+                order: None,
+                is_visible: true,
+                is_editable: false,
+            }],
+            initial_document: None,
+            view: CollectionView::Details,
+            sort: Vec::new(),
+            navigator: None,
+        };
+
+        let mut subitem = Dictionary::empty();
+        subitem.insert("D", Object::Integer(3));
+        subitem.insert("P", Object::String("Chapter ".to_owned()));
+
+        let mut item = Dictionary::empty();
+        item.insert("Type", Object::Name("CollectionItem".to_owned()));
+        item.insert("Received", Object::String("D:20230102".to_owned()));
+        item.insert("From", Object::String("Alice".to_owned()));
+        item.insert("Chapter", Object::Dictionary(subitem));
+
+        let mut resolver = PdfWriter::new(PdfVersion::V1_7);
+
+        let fields =
+            collection_item(Object::Dictionary(item), Some(&schema), &mut resolver).unwrap();
+
+        assert_eq!(fields.len(), 3);
+        assert!(matches!(
+            fields["Received"].value,
+            CollectionValue::Date(Date {
+                year: Some(2023),
+                ..
+            })
+        ));
+        assert_eq!(
+            fields["From"].value,
+            CollectionValue::Text("Alice".to_owned())
+        );
+        assert_eq!(fields["Chapter"].value, CollectionValue::Number(3.0));
+        assert_eq!(fields["Chapter"].prefix.as_deref(), Some("Chapter "));
+    }
+}
@@ -0,0 +1,278 @@
+use crate::icc_profile::{xyz_to_srgb, D65};
+
+/// A CIE-based colour space with a single transformation stage, whose
+/// component A represents the gray component of a calibrated gray space
+#[derive(Debug, Clone, FromObj)]
+pub struct CalGrayColorSpace {
+    /// An array of three numbers [XW YW ZW] that shall specify the tristimulus
+    /// value, in the CIE 1931 XYZ space, of the diffuse white point; see
+    /// 8.6.5.3, "CalRGB Colour Spaces", for further discussion. The numbers XW
+    /// and ZW shall be positive, and YW shall be equal to 1.0.
+    #[field("WhitePoint")]
+    pub white_point: [f32; 3],
+
+    /// An array of three numbers [XB YB ZB] that shall specify the tristimulus
+    /// value, in the CIE 1931 XYZ space, of the diffuse black point
+    ///
+    /// Default value: [0.0 0.0 0.0]
+    #[field("BlackPoint", default = [0.0, 0.0, 0.0])]
+    pub black_point: [f32; 3],
+
+    /// A number G defining the gamma for the gray (A) component. G shall be
+    /// positive and is generally greater than or equal to 1.
+    ///
+    /// Default value: 1
+    #[field("Gamma", default = 1.0)]
+    pub gamma: f32,
+}
+
+impl Default for CalGrayColorSpace {
+    fn default() -> Self {
+        Self {
+            white_point: D65,
+            black_point: [0.0, 0.0, 0.0],
+            gamma: 1.0,
+        }
+    }
+}
+
+impl CalGrayColorSpace {
+    /// Convert the gray component to sRGB
+    ///
+    /// The black point is not taken into account, as it is not by the
+    /// conversions described by the spec.
+    pub fn to_rgb(&self, a: f32) -> [f32; 3] {
+        let luminance = a.clamp(0.0, 1.0).powf(self.gamma);
+
+        xyz_to_srgb(self.white_point.map(|w| w * luminance), self.white_point)
+    }
+}
+
+/// A CIE-based colour space with a single transformation stage, whose
+/// components A, B and C represent calibrated red, green and blue
+#[derive(Debug, Clone, FromObj)]
+pub struct CalRgbColorSpace {
+    /// An array of three numbers [XW YW ZW] that shall specify the tristimulus
+    /// value, in the CIE 1931 XYZ space, of the diffuse white point. The
+    /// numbers XW and ZW shall be positive, and YW shall be equal to 1.0.
+    #[field("WhitePoint")]
+    pub white_point: [f32; 3],
+
+    /// An array of three numbers [XB YB ZB] that shall specify the tristimulus
+    /// value, in the CIE 1931 XYZ space, of the diffuse black point
+    ///
+    /// Default value: [0.0 0.0 0.0]
+    #[field("BlackPoint", default = [0.0, 0.0, 0.0])]
+    pub black_point: [f32; 3],
+
+    /// An array of three numbers [GR GG GB] specifying the gamma for the red,
+    /// green, and blue (A, B, and C) components of the colour space
+    ///
+    /// Default value: [1.0 1.0 1.0]
+    #[field("Gamma", default = [1.0, 1.0, 1.0])]
+    pub gamma: [f32; 3],
+
+    /// An array of nine numbers [XA YA ZA XB YB ZB XC YC ZC] specifying the
+    /// linear interpretation of the decoded A, B, and C components of the
+    /// colour space with respect to the final XYZ representation
+    ///
+    /// Default value: the identity matrix [1 0 0 0 1 0 0 0 1]
+    #[field("Matrix", default = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0])]
+    pub matrix: [f32; 9],
+}
+
+impl Default for CalRgbColorSpace {
+    fn default() -> Self {
+        Self {
+            white_point: D65,
+            black_point: [0.0, 0.0, 0.0],
+            gamma: [1.0, 1.0, 1.0],
+            matrix: [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+        }
+    }
+}
+
+impl CalRgbColorSpace {
+    /// Convert the red, green and blue components to sRGB
+    pub fn to_rgb(&self, components: [f32; 3]) -> [f32; 3] {
+        let [a, b, c] = [0, 1, 2].map(|idx| components[idx].clamp(0.0, 1.0).powf(self.gamma[idx]));
+
+        // each group of three entries of the matrix is the contribution of a
+        // component to X, Y and Z
+        let xyz = [0, 1, 2]
+            .map(|idx| self.matrix[idx] * a + self.matrix[idx + 3] * b + self.matrix[idx + 6] * c);
+
+        xyz_to_srgb(xyz, self.white_point)
+    }
+}
+
+/// A CIE 1976 L*a*b* colour space, whose components are the lightness L*
+/// and the chromaticity coordinates a* and b*
+#[derive(Debug, Clone, FromObj)]
+pub struct LabColorSpace {
+    /// An array of three numbers [XW YW ZW] that shall specify the tristimulus
+    /// value, in the CIE 1931 XYZ space, of the diffuse white point. The
+    /// numbers XW and ZW shall be positive, and YW shall be equal to 1.0.
+    #[field("WhitePoint")]
+    pub white_point: [f32; 3],
+
+    /// An array of three numbers [XB YB ZB] that shall specify the tristimulus
+    /// value, in the CIE 1931 XYZ space, of the diffuse black point
+    ///
+    /// Default value: [0.0 0.0 0.0]
+    #[field("BlackPoint", default = [0.0, 0.0, 0.0])]
+    pub black_point: [f32; 3],
+
+    /// An array of four numbers [amin amax bmin bmax] that shall specify the
+    /// range of valid values for the a* and b* (B and C) components of the
+    /// colour space
+    ///
+    /// Component values falling outside the specified range shall be adjusted
+    /// to the nearest valid value without error indication.
+    ///
+    /// Default value: [-100 100 -100 100].
+    #[field("Range", default = [-100.0, 100.0, -100.0, 100.0])]
+    pub range: [f32; 4],
+}
+
+impl Default for LabColorSpace {
+    fn default() -> Self {
+        Self {
+            white_point: D65,
+            black_point: [0.0, 0.0, 0.0],
+            range: [-100.0, 100.0, -100.0, 100.0],
+        }
+    }
+}
+
+impl LabColorSpace {
+    /// Convert the L*, a* and b* components to sRGB
+    pub fn to_rgb(&self, [l, a, b]: [f32; 3]) -> [f32; 3] {
+        let l = l.clamp(0.0, 100.0);
+        let a = a.clamp(self.range[0], self.range[1]);
+        let b = b.clamp(self.range[2], self.range[3]);
+
+        let m = (l + 16.0) / 116.0;
+        let components = [m + a / 500.0, m, m - b / 200.0];
+
+        let g = |x: f32| {
+            if x >= 6.0 / 29.0 {
+                x * x * x
+            } else {
+                108.0 / 841.0 * (x - 4.0 / 29.0)
+            }
+        };
+
+        let xyz = [0, 1, 2].map(|idx| self.white_point[idx] * g(components[idx]));
+
+        xyz_to_srgb(xyz, self.white_point)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{
+        color::{ColorSpace, ColorSpaceName},
+        objects::{Dictionary, Object},
+        version::PdfVersion,
+        writer::PdfWriter,
+        FromObj,
+    };
+
+    fn color_space<'a>(name: &str, entries: Vec<(&str, &[f32])>) -> ColorSpace<'a> {
+        let dict = entries
+            .into_iter()
+            .map(|(key, values)| {
+                let values = values.iter().map(|&v| Object::Real(v)).collect();
+
+                (key.to_owned(), Object::Array(values))
+            })
+            .collect::<HashMap<_, _>>();
+
+        ColorSpace::from_obj(
+            Object::Array(vec![
+                Object::Name(name.to_owned()),
+                Object::Dictionary(Dictionary::new(dict)),
+            ]),
+            &mut PdfWriter::new(PdfVersion::V1_7),
+        )
+        .unwrap()
+    }
+
+    fn assert_close(found: [f32; 3], expected: [f32; 3]) {
+        for (found, expected) in found.into_iter().zip(expected) {
+            assert!(
+                (found - expected).abs() < 0.01,
+                "{:?} != {:?}",
+                found,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn cie_based_colors() {
+        const D50: &[f32] = &[0.9642, 1.0, 0.8249];
+
+        // the primaries and white point of sRGB, with an approximation of its
+        // transfer function
+        let cal_rgb = color_space(
+            "CalRGB",
+            vec![
+                ("WhitePoint", &[0.9505, 1.0, 1.089]),
+                ("Gamma", &[2.2, 2.2, 2.2]),
+                (
+                    "Matrix",
+                    &[
+                        0.4124, 0.2126, 0.0193, 0.3576, 0.7152, 0.1192, 0.1805, 0.0722, 0.9505,
+                    ],
+                ),
+            ],
+        );
+
+        assert_close(cal_rgb.to_rgb(&[1.0, 1.0, 1.0]).unwrap(), [1.0, 1.0, 1.0]);
+        assert_close(cal_rgb.to_rgb(&[0.0, 1.0, 0.0]).unwrap(), [0.0, 1.0, 0.0]);
+        assert_close(cal_rgb.to_rgb(&[0.5, 0.5, 0.5]).unwrap(), [0.5; 3]);
+
+        // white is displayed as white, whatever the white point
+        let cal_gray = color_space("CalGray", vec![("WhitePoint", D50)]);
+
+        assert_close(cal_gray.to_rgb(&[1.0]).unwrap(), [1.0; 3]);
+        assert_close(cal_gray.to_rgb(&[0.0]).unwrap(), [0.0; 3]);
+
+        let lab = color_space(
+            "Lab",
+            vec![("WhitePoint", D50), ("Range", &[0.0, 50.0, -50.0, 0.0])],
+        );
+
+        assert_eq!(lab.default_decode(8), [0.0, 100.0, 0.0, 50.0, -50.0, 0.0]);
+        assert_close(lab.to_rgb(&[100.0, 0.0, 0.0]).unwrap(), [1.0; 3]);
+        assert_close(lab.to_rgb(&[0.0, 0.0, 0.0]).unwrap(), [0.0; 3]);
+        // a* and b* are limited to the range
+        assert_eq!(
+            lab.to_rgb(&[50.0, 80.0, 20.0]).unwrap(),
+            lab.to_rgb(&[50.0, 50.0, 0.0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn default_cie_based_colors() {
+        let cal_gray = ColorSpace::init(ColorSpaceName::CalGray).unwrap();
+        assert_close(cal_gray.to_rgb(&[1.0]).unwrap(), [1.0; 3]);
+
+        let cal_rgb = ColorSpace::init(ColorSpaceName::CalRGB).unwrap();
+        assert_close(cal_rgb.to_rgb(&[0.0, 0.0, 0.0]).unwrap(), [0.0; 3]);
+
+        let lab = ColorSpace::init(ColorSpaceName::Lab).unwrap();
+        assert_close(lab.to_rgb(&[100.0, 0.0, 0.0]).unwrap(), [1.0; 3]);
+
+        assert!(ColorSpace::init(ColorSpaceName::ICCBased).is_err());
+        assert!(ColorSpace::from_obj(
+            Object::Name("Indexed".to_owned()),
+            &mut PdfWriter::new(PdfVersion::V1_7)
+        )
+        .is_err());
+    }
+}
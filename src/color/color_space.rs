@@ -12,6 +12,7 @@ use crate::{
 };
 
 use super::{
+    cie::{CalGrayColorSpace, CalRgbColorSpace, LabColorSpace},
     device_n::{DeviceNColorSpace, DeviceNColorSpaceAttributes},
     icc::IccStream,
     indexed::{IndexedColorSpace, IndexedLookupTable},
@@ -44,19 +45,25 @@ pub enum ColorSpace<'a> {
     // CIE-based
     CalGray {
         a: f32,
+        space: Rc<CalGrayColorSpace>,
     },
     CalRGB {
         a: f32,
         b: f32,
         c: f32,
+        space: Rc<CalRgbColorSpace>,
     },
     Lab {
         a: f32,
         b: f32,
         c: f32,
+        space: Rc<LabColorSpace>,
     },
     IccBased {
         stream: Rc<IccStream<'a>>,
+
+        /// The profile of the stream, if it could be parsed
+        profile: Option<Rc<IccProfile>>,
         channels: Vec<f32>,
     },
 
@@ -71,8 +78,13 @@ pub enum ColorSpace<'a> {
 }
 
 impl<'a> ColorSpace<'a> {
-    pub fn init(name: ColorSpaceName) -> Self {
-        match name {
+    /// The colour space of the family `name`, with its initial colour
+    ///
+    /// CIE-based colour spaces are given a D65 white point and otherwise
+    /// default parameters. The other families which take parameters cannot be
+    /// made from their name alone.
+    pub fn init(name: ColorSpaceName) -> PdfResult<Self> {
+        Ok(match name {
             ColorSpaceName::DeviceGray => ColorSpace::DeviceGray(0.0),
             ColorSpaceName::DeviceRGB => ColorSpace::DeviceRGB {
                 red: 0.0,
@@ -85,19 +97,32 @@ impl<'a> ColorSpace<'a> {
                 yellow: 0.0,
                 key: 1.0,
             },
-            ColorSpaceName::CalGray => ColorSpace::CalGray { a: 0.0 },
+            ColorSpaceName::CalGray => ColorSpace::CalGray {
+                a: 0.0,
+                space: Rc::new(CalGrayColorSpace::default()),
+            },
             ColorSpaceName::CalRGB => ColorSpace::CalRGB {
                 a: 0.0,
                 b: 0.0,
                 c: 0.0,
+                space: Rc::new(CalRgbColorSpace::default()),
+            },
+            // the initial a* and b* components of 0.0 are within the default
+            // range
+            ColorSpaceName::Lab => ColorSpace::Lab {
+                a: 0.0,
+                b: 0.0,
+                c: 0.0,
+                space: Rc::new(LabColorSpace::default()),
             },
-            ColorSpaceName::Lab => todo!(),
-            ColorSpaceName::ICCBased => todo!(),
-            ColorSpaceName::Indexed => todo!(),
             ColorSpaceName::Pattern => ColorSpace::Pattern(None),
-            ColorSpaceName::Separation => todo!(),
-            ColorSpaceName::DeviceN => todo!(),
-        }
+            ColorSpaceName::ICCBased
+            | ColorSpaceName::Indexed
+            | ColorSpaceName::Separation
+            | ColorSpaceName::DeviceN => {
+                anyhow::bail!("the {} colour space requires parameters", name.as_str())
+            }
+        })
     }
 
     pub fn name(&self) -> ColorSpaceName {
@@ -116,6 +141,129 @@ impl<'a> ColorSpace<'a> {
         }
     }
 
+    /// The number of colour components in the colour space
+    pub fn components(&self) -> usize {
+        match self {
+            ColorSpace::DeviceGray(..)
+            | ColorSpace::CalGray { .. }
+            | ColorSpace::Indexed { .. }
+            | ColorSpace::Pattern(..)
+            | ColorSpace::Separation(..) => 1,
+            ColorSpace::DeviceRGB { .. } | ColorSpace::CalRGB { .. } | ColorSpace::Lab { .. } => 3,
+            ColorSpace::DeviceCMYK { .. } => 4,
+            ColorSpace::IccBased { stream, .. } => stream.num_of_color_components as usize,
+            ColorSpace::DeviceN(space) => space.names.len(),
+        }
+    }
+
+    /// The range of each component, as the Decode array of an image in the
+    /// colour space with samples of `bits` bits which has none
+    pub(crate) fn default_decode(&self, bits: u32) -> Vec<f32> {
+        match self {
+            ColorSpace::Indexed { .. } => vec![0.0, ((1_u32 << bits) - 1) as f32],
+            ColorSpace::Lab { space, .. } => {
+                vec![
+                    0.0,
+                    100.0,
+                    space.range[0],
+                    space.range[1],
+                    space.range[2],
+                    space.range[3],
+                ]
+            }
+            ColorSpace::IccBased { stream, .. } => stream.range.clone(),
+            _ => [0.0, 1.0]
+                .into_iter()
+                .cycle()
+                .take(self.components() * 2)
+                .collect(),
+        }
+    }
+
+    /// The components of the current colour of the colour space
+    fn color(&self) -> Vec<f32> {
+        match self {
+            &ColorSpace::DeviceGray(gray) => vec![gray],
+            &ColorSpace::DeviceRGB { red, green, blue } => vec![red, green, blue],
+            &ColorSpace::DeviceCMYK {
+                cyan,
+                magenta,
+                yellow,
+                key,
+            } => vec![cyan, magenta, yellow, key],
+            &ColorSpace::CalGray { a, .. } => vec![a],
+            &ColorSpace::CalRGB { a, b, c, .. } | &ColorSpace::Lab { a, b, c, .. } => vec![a, b, c],
+            ColorSpace::IccBased { channels, .. } => channels.clone(),
+            &ColorSpace::Indexed { index, .. } => vec![index as f32],
+            ColorSpace::Separation(space) => vec![space.tint],
+            ColorSpace::DeviceN(..) | ColorSpace::Pattern(..) => Vec::new(),
+        }
+    }
+
+    /// Convert the components of a colour in this colour space to red, green
+    /// and blue, each in the range 0.0 to 1.0
+    pub fn to_rgb(&self, components: &[f32]) -> PdfResult<[f32; 3]> {
+        let component = |idx: usize| components.get(idx).copied().unwrap_or(0.0);
+
+        Ok(match self {
+            ColorSpace::DeviceGray(..) => [component(0); 3],
+            ColorSpace::DeviceRGB { .. } => [component(0), component(1), component(2)],
+            ColorSpace::CalGray { space, .. } => space.to_rgb(component(0)),
+            ColorSpace::CalRGB { space, .. } => {
+                space.to_rgb([component(0), component(1), component(2)])
+            }
+            ColorSpace::Lab { space, .. } => {
+                space.to_rgb([component(0), component(1), component(2)])
+            }
+            ColorSpace::DeviceCMYK { .. } => {
+                let key = 1.0 - component(3);
+
+                [
+                    (1.0 - component(0)) * key,
+                    (1.0 - component(1)) * key,
+                    (1.0 - component(2)) * key,
+                ]
+            }
+            // colours are converted through the profile where it is
+            // supported, and otherwise through the alternate space, or the
+            // device space with as many components
+            ColorSpace::IccBased {
+                stream, profile, ..
+            } => match profile
+                .as_ref()
+                .and_then(|profile| profile.to_rgb(components))
+            {
+                Some(rgb) => rgb,
+                None => match (&stream.alternate, stream.num_of_color_components) {
+                    (Some(alternate), _) => alternate.to_rgb(components)?,
+                    (None, 1) => {
+                        ColorSpace::init(ColorSpaceName::DeviceGray)?.to_rgb(components)?
+                    }
+                    (None, 3) => ColorSpace::init(ColorSpaceName::DeviceRGB)?.to_rgb(components)?,
+                    (None, 4) => {
+                        ColorSpace::init(ColorSpaceName::DeviceCMYK)?.to_rgb(components)?
+                    }
+                    (None, n) => anyhow::bail!(
+                        "ICC profile of {} components has no alternate colour space",
+                        n
+                    ),
+                },
+            },
+            ColorSpace::Indexed { space, .. } => {
+                let index = component(0).round().max(0.0) as u32;
+
+                space.base.to_rgb(&space.color(index))?
+            }
+            ColorSpace::Separation(space) => space
+                .alternate_space
+                .to_rgb(&space.tint_transform.evaluate(&[component(0)])?)?,
+            ColorSpace::DeviceN(space) => space
+                .alternate_space
+                .to_rgb(&space.tint_transform.evaluate(components)?)?,
+            ColorSpace::Pattern(..) => anyhow::bail!("patterns have no colour components"),
+        })
+    }
+
     #[allow(unused)]
     fn blend(&self, background: Self) -> Self {
         todo!()
@@ -127,7 +275,7 @@ impl<'a> ColorSpace<'a> {
     pub fn as_u32(&self) -> u32 {
         match self {
             &Self::DeviceGray(n) => {
-                let n = n.round() as u32;
+                let n = (n * 255.0).round() as u32;
 
                 (0xff << 24) | (n << 16) | (n << 8) | n
             }
@@ -158,14 +306,17 @@ impl<'a> ColorSpace<'a> {
 
                 (0xff << 24) | (b << 16) | (g << 8) | r
             }
-            Self::IccBased { stream, channels } => {
-                // ensure we don't silently render colors we don't support
-                assert_eq!(stream.num_of_color_components, 3);
-                assert_eq!(channels.len(), 3);
+            Self::CalGray { .. }
+            | Self::CalRGB { .. }
+            | Self::Lab { .. }
+            | Self::IccBased { .. }
+            | Self::Indexed { .. } => {
+                // colours which cannot be converted are rendered black
+                let [red, green, blue] = self.to_rgb(&self.color()).unwrap_or([0.0; 3]);
 
-                let r = (channels[0] * 255.0) as u32;
-                let g = (channels[1] * 255.0) as u32;
-                let b = (channels[2] * 255.0) as u32;
+                let r = (red * 255.0).round() as u32;
+                let g = (green * 255.0).round() as u32;
+                let b = (blue * 255.0).round() as u32;
 
                 (0xff << 24) | (b << 16) | (g << 8) | r
             }
@@ -180,7 +331,7 @@ impl<'a> ColorSpace<'a> {
 impl<'a> FromObj<'a> for ColorSpace<'a> {
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
         match resolver.resolve(obj)? {
-            Object::Name(name) => Ok(ColorSpace::init(ColorSpaceName::from_str(&name)?)),
+            Object::Name(name) => ColorSpace::init(ColorSpaceName::from_str(&name)?),
             Object::Array(arr) => {
                 let name = resolver.assert_name(arr[0].clone())?;
 
@@ -188,9 +339,39 @@ impl<'a> FromObj<'a> for ColorSpace<'a> {
                     ColorSpaceName::DeviceGray => todo!(),
                     ColorSpaceName::DeviceRGB => todo!(),
                     ColorSpaceName::DeviceCMYK => todo!(),
-                    ColorSpaceName::CalGray => todo!(),
-                    ColorSpaceName::CalRGB => todo!(),
-                    ColorSpaceName::Lab => todo!(),
+                    ColorSpaceName::CalGray => {
+                        assert_len(&arr, 2)?;
+
+                        let space = Rc::new(CalGrayColorSpace::from_obj(arr[1].clone(), resolver)?);
+
+                        Ok(ColorSpace::CalGray { a: 0.0, space })
+                    }
+                    ColorSpaceName::CalRGB => {
+                        assert_len(&arr, 2)?;
+
+                        let space = Rc::new(CalRgbColorSpace::from_obj(arr[1].clone(), resolver)?);
+
+                        Ok(ColorSpace::CalRGB {
+                            a: 0.0,
+                            b: 0.0,
+                            c: 0.0,
+                            space,
+                        })
+                    }
+                    ColorSpaceName::Lab => {
+                        assert_len(&arr, 2)?;
+
+                        let space = Rc::new(LabColorSpace::from_obj(arr[1].clone(), resolver)?);
+
+                        // the initial colour is the nearest to all components
+                        // being 0.0 within the range of a* and b*
+                        Ok(ColorSpace::Lab {
+                            a: 0.0,
+                            b: 0.0_f32.clamp(space.range[0], space.range[1]),
+                            c: 0.0_f32.clamp(space.range[2], space.range[3]),
+                            space,
+                        })
+                    }
                     ColorSpaceName::ICCBased => {
                         assert_len(&arr, 2)?;
 
@@ -202,14 +383,17 @@ impl<'a> FromObj<'a> for ColorSpace<'a> {
                             resolver,
                         )?;
 
-                        let icc_profile = IccProfile::new(&stream)?;
-                        assert!(
-                            matches!(&icc_profile.header.colour_space.0, b"RGB " | b"GRAY"),
-                            "unimplemented ICC color profile: {:?}",
-                            icc_profile.header.colour_space
-                        );
+                        // a profile which cannot be parsed, or which does not
+                        // match N, is treated as one which is not supported,
+                        // and colours are converted through the alternate
+                        // space instead
+                        let profile = IccProfile::new(&stream).ok().filter(|profile| {
+                            profile.components()
+                                == usize::try_from(icc_stream.num_of_color_components).ok()
+                        });
 
                         Ok(ColorSpace::IccBased {
+                            profile: profile.map(Rc::new),
                             // todo: should actually be the lower bound of the
                             // Range for each channel instead of 0.0
                             channels: vec![0.0; icc_stream.num_of_color_components as usize],
@@ -223,6 +407,12 @@ impl<'a> FromObj<'a> for ColorSpace<'a> {
                         let hival = u8::try_from(u32::from_obj(arr[2].clone(), resolver)?)?;
                         let lookup = IndexedLookupTable::from_obj(arr[3].clone(), resolver)?;
 
+                        anyhow::ensure!(
+                            !matches!(base, ColorSpace::Pattern(..) | ColorSpace::Indexed { .. }),
+                            "the base of an indexed colour space may not be {:?}",
+                            base.name()
+                        );
+
                         let space = Rc::new(IndexedColorSpace {
                             base,
                             hival,
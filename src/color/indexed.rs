@@ -15,6 +15,29 @@ pub struct IndexedColorSpace<'a> {
     pub lookup: IndexedLookupTable,
 }
 
+impl<'a> IndexedColorSpace<'a> {
+    /// The components in the base colour space of the colour at `index`,
+    /// which is limited to hival
+    ///
+    /// Each component is given by a byte of the lookup table, mapped to the
+    /// range of that component.
+    pub fn color(&self, index: u32) -> Vec<f32> {
+        let components = self.base.components();
+        let start = index.min(u32::from(self.hival)) as usize * components;
+
+        self.base
+            .default_decode(8)
+            .chunks_exact(2)
+            .enumerate()
+            .map(|(idx, range)| {
+                let byte = self.lookup.buffer.get(start + idx).copied().unwrap_or(0);
+
+                range[0] + f32::from(byte) / 255.0 * (range[1] - range[0])
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct IndexedLookupTable {
     buffer: Vec<u8>,
@@ -23,7 +46,8 @@ pub struct IndexedLookupTable {
 impl<'a> FromObj<'a> for IndexedLookupTable {
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
         let buffer = match resolver.resolve(obj)? {
-            Object::String(s) => s.into_bytes(),
+            // strings hold each byte as a `char`
+            Object::String(s) => s.chars().map(|c| c as u8).collect(),
             Object::Stream(stream) => {
                 decode_stream(&stream.stream, &stream.dict, resolver)?.into_owned()
             }
@@ -36,3 +60,75 @@ impl<'a> FromObj<'a> for IndexedLookupTable {
         Ok(Self { buffer })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{
+        objects::Dictionary, stream::Stream, version::PdfVersion, writer::PdfWriter, FromObj,
+    };
+
+    use super::*;
+
+    fn indexed<'a>(base: &str, lookup: Object<'a>) -> Vec<Object<'a>> {
+        vec![
+            Object::Name("Indexed".to_owned()),
+            Object::Name(base.to_owned()),
+            Object::Integer(1),
+            lookup,
+        ]
+    }
+
+    #[test]
+    fn indexed_colors() {
+        let mut resolver = PdfWriter::new(PdfVersion::V1_7);
+
+        // the lookup table is a string of a red and a green entry
+        let space = ColorSpace::from_obj(
+            Object::Array(indexed(
+                "DeviceRGB",
+                Object::String("\u{ff}\0\0\0\u{ff}\0".to_owned()),
+            )),
+            &mut resolver,
+        )
+        .unwrap();
+
+        let space = match space {
+            ColorSpace::Indexed { space, .. } => space,
+            space => panic!("expected indexed colour space, found {:?}", space),
+        };
+
+        let color = |index| ColorSpace::Indexed {
+            index,
+            space: space.clone(),
+        };
+
+        assert_eq!(color(0).as_u32(), 0xff00_00ff);
+        assert_eq!(color(1).as_u32(), 0xff00_ff00);
+        // indices beyond hival are limited to it
+        assert_eq!(color(7).as_u32(), 0xff00_ff00);
+
+        // or a stream, in which a gray base space is given
+        let lookup = Object::Stream(Stream::new(
+            vec![0x00, 0x80],
+            Dictionary::new(HashMap::new()),
+        ));
+
+        let space =
+            ColorSpace::from_obj(Object::Array(indexed("DeviceGray", lookup)), &mut resolver)
+                .unwrap();
+
+        assert_eq!(space.to_rgb(&[1.0]).unwrap(), [128.0 / 255.0; 3]);
+
+        // the base may not be another indexed colour space
+        let nested = Object::Array(vec![
+            Object::Name("Indexed".to_owned()),
+            Object::Array(indexed("DeviceGray", Object::String("\0\0".to_owned()))),
+            Object::Integer(0),
+            Object::String("\0".to_owned()),
+        ]);
+
+        assert!(ColorSpace::from_obj(nested, &mut resolver).is_err());
+    }
+}
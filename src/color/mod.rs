@@ -1,9 +1,16 @@
+mod cie;
 mod color_space;
 mod device_n;
 mod icc;
 mod indexed;
+mod process;
+mod separation;
+mod spot;
 
 pub use color_space::{ColorSpace, ColorSpaceName};
+pub use process::SpotColorConversion;
+pub use separation::{ColorSeparation, SeparationInfo};
+pub use spot::{SpotColorLocation, SpotColorUsage, SpotColorant};
 
 pub struct Color;
 
@@ -0,0 +1,560 @@
+/*!
+ * Conversion of spot colours (Separation and DeviceN colour spaces) to process
+ * colour, for output devices without plates for the named colorants
+ */
+
+use std::collections::HashMap;
+
+use crate::{
+    content::{ContentToken, PdfGraphicsOperator},
+    error::PdfResult,
+    objects::Object,
+    xobject::read_sample,
+};
+
+use super::{device_n::DeviceNColorSpace, ColorSpace, ColorSpaceName};
+
+/// Options for replacing spot colours with their DeviceCMYK equivalents, as
+/// given by each colour space's tint transform
+#[derive(Debug, Default, Clone)]
+pub struct SpotColorConversion {
+    /// Colorants which are kept as spot colours, such as a die line named
+    /// "Cutline" which must remain on its own plate
+    preserve: Vec<String>,
+}
+
+impl SpotColorConversion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leave usages of the colorant `name` unconverted
+    pub fn preserve(mut self, name: impl Into<String>) -> Self {
+        self.preserve.push(name.into());
+        self
+    }
+
+    /// Whether usages of `space` are converted to process colour
+    ///
+    /// A DeviceN colour space is kept if any of its colorants are preserved,
+    /// since converting it would remove that colorant's plate. The special
+    /// colorant None never produces marks, so it cannot be represented in
+    /// process colour and is always kept.
+    pub(crate) fn converts(&self, space: &ColorSpace) -> bool {
+        match space {
+            ColorSpace::Separation(separation) => {
+                separation.name.0 != "None" && !self.is_preserved(&separation.name.0)
+            }
+            ColorSpace::DeviceN(DeviceNColorSpace { names, .. }) => {
+                !names.iter().any(|name| self.is_preserved(&name.0))
+            }
+            _ => false,
+        }
+    }
+
+    fn is_preserved(&self, name: &str) -> bool {
+        self.preserve.iter().any(|preserved| preserved == name)
+    }
+
+    /// Rewrite a content stream so that colours set in converted spot colour
+    /// spaces are instead set with the `K` and `k` operators
+    ///
+    /// `color_spaces` is the ColorSpace entry of the resource dictionary of the
+    /// content stream. Colour spaces set by `CS` and `cs` are part of the
+    /// graphics state, so they are saved and restored by `q` and `Q`.
+    ///
+    /// Inline images which name a converted colour space are refused, since
+    /// their samples would have to be converted in place. Images, shadings
+    /// and form XObjects are resources of their own, and are converted
+    /// separately.
+    pub(crate) fn rewrite_content<'a>(
+        &self,
+        tokens: impl Iterator<Item = PdfResult<ContentToken<'a>>>,
+        color_spaces: &HashMap<String, ColorSpace<'a>>,
+    ) -> PdfResult<Vec<ContentToken<'a>>> {
+        let mut output = Vec::new();
+        let mut operands = Vec::new();
+
+        let mut state = ConvertedSpaces::default();
+        let mut saved_states = Vec::new();
+
+        for token in tokens {
+            let operator = match token? {
+                ContentToken::Object(obj) => {
+                    operands.push(obj);
+                    continue;
+                }
+                ContentToken::Operator(operator) => operator,
+                // inline images may only use device colour spaces, or those
+                // of the resource dictionary by name
+                ContentToken::InlineImage(image) => {
+                    let space = match image.expanded_dict().peek("ColorSpace") {
+                        Some(Object::Name(name)) => color_spaces.get(name),
+                        _ => None,
+                    };
+
+                    if let Some(space) = space.filter(|space| self.converts(space)) {
+                        anyhow::bail!(
+                            "cannot convert the spot colours of an inline image in {:?}",
+                            space.name()
+                        );
+                    }
+
+                    output.extend(operands.drain(..).map(ContentToken::Object));
+                    output.push(ContentToken::InlineImage(image));
+                    continue;
+                }
+            };
+
+            let replacement = match operator {
+                PdfGraphicsOperator::q => {
+                    saved_states.push(state.clone());
+                    None
+                }
+                PdfGraphicsOperator::Q => {
+                    state = saved_states.pop().unwrap_or_default();
+                    None
+                }
+                PdfGraphicsOperator::CS | PdfGraphicsOperator::cs => {
+                    let space = match operands.last() {
+                        Some(Object::Name(name)) if ColorSpaceName::from_str(name).is_err() => {
+                            color_spaces.get(name).filter(|space| self.converts(space))
+                        }
+                        _ => None,
+                    };
+
+                    let slot = state.slot(operator == PdfGraphicsOperator::CS);
+
+                    *slot = space.cloned();
+
+                    match space {
+                        Some(space) => Some(to_process_cmyk(space, &initial_components(space))?),
+                        None => None,
+                    }
+                }
+                PdfGraphicsOperator::SC
+                | PdfGraphicsOperator::SCN
+                | PdfGraphicsOperator::sc
+                | PdfGraphicsOperator::scn => {
+                    match state.slot(matches!(
+                        operator,
+                        PdfGraphicsOperator::SC | PdfGraphicsOperator::SCN
+                    )) {
+                        Some(space) => {
+                            let components = operands
+                                .iter()
+                                .map(|obj| match obj {
+                                    Object::Integer(n) => Ok(*n as f32),
+                                    Object::Real(n) => Ok(*n),
+                                    obj => anyhow::bail!("expected tint, found {:?}", obj),
+                                })
+                                .collect::<PdfResult<Vec<f32>>>()?;
+
+                            Some(to_process_cmyk(space, &components)?)
+                        }
+                        None => None,
+                    }
+                }
+                PdfGraphicsOperator::G | PdfGraphicsOperator::RG | PdfGraphicsOperator::K => {
+                    state.stroking = None;
+                    None
+                }
+                PdfGraphicsOperator::g | PdfGraphicsOperator::rg | PdfGraphicsOperator::k => {
+                    state.nonstroking = None;
+                    None
+                }
+                _ => None,
+            };
+
+            match replacement {
+                Some(cmyk) => {
+                    operands.clear();
+
+                    output.extend(cmyk.iter().map(|&n| ContentToken::Object(Object::Real(n))));
+
+                    output.push(ContentToken::Operator(
+                        if matches!(
+                            operator,
+                            PdfGraphicsOperator::CS
+                                | PdfGraphicsOperator::SC
+                                | PdfGraphicsOperator::SCN
+                        ) {
+                            PdfGraphicsOperator::K
+                        } else {
+                            PdfGraphicsOperator::k
+                        },
+                    ));
+                }
+                None => {
+                    output.extend(operands.drain(..).map(ContentToken::Object));
+                    output.push(ContentToken::Operator(operator));
+                }
+            }
+        }
+
+        output.extend(operands.into_iter().map(ContentToken::Object));
+
+        Ok(output)
+    }
+
+    /// Convert the samples of an image in the spot colour space `space` to
+    /// 8-bit DeviceCMYK samples
+    ///
+    /// `data` holds the decoded samples of `bits` bits, and `decode` is the
+    /// Decode array of the image, if it has one.
+    pub(crate) fn convert_image(
+        &self,
+        space: &ColorSpace,
+        data: &[u8],
+        [width, height]: [usize; 2],
+        bits: u32,
+        decode: Option<&[f32]>,
+    ) -> PdfResult<Vec<u8>> {
+        let components = space.components();
+        let default_decode = space.default_decode(bits);
+        let decode = decode.unwrap_or(&default_decode);
+
+        let max = ((1_u32 << bits) - 1) as f32;
+
+        // each row begins on a byte boundary
+        let row_len = (width * components * bits as usize).div_ceil(8);
+
+        // images tend to repeat colours, and tint transforms may be slow to
+        // evaluate
+        let mut converted = HashMap::<Vec<u32>, [u8; 4]>::new();
+
+        let mut cmyk = Vec::with_capacity(width * height * 4);
+
+        for y in 0..height {
+            for x in 0..width {
+                let samples = (0..components)
+                    .map(|idx| {
+                        read_sample(
+                            data,
+                            y * row_len * 8 + (x * components + idx) * bits as usize,
+                            bits,
+                        )
+                    })
+                    .collect::<Vec<u32>>();
+
+                if let Some(pixel) = converted.get(&samples) {
+                    cmyk.extend_from_slice(pixel);
+                    continue;
+                }
+
+                let values = samples
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, &sample)| {
+                        let (min, max_value) = match decode.get(idx * 2..idx * 2 + 2) {
+                            Some(&[min, max_value]) => (min, max_value),
+                            _ => (0.0, 1.0),
+                        };
+
+                        min + sample as f32 * (max_value - min) / max
+                    })
+                    .collect::<Vec<f32>>();
+
+                let pixel = to_process_cmyk(space, &values)?
+                    .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+
+                cmyk.extend_from_slice(&pixel);
+                converted.insert(samples, pixel);
+            }
+        }
+
+        Ok(cmyk)
+    }
+}
+
+/// The converted spot colour spaces currently selected for stroking and
+/// nonstroking operations
+#[derive(Debug, Default, Clone)]
+struct ConvertedSpaces<'a> {
+    stroking: Option<ColorSpace<'a>>,
+    nonstroking: Option<ColorSpace<'a>>,
+}
+
+impl<'a> ConvertedSpaces<'a> {
+    fn slot(&mut self, is_stroking: bool) -> &mut Option<ColorSpace<'a>> {
+        if is_stroking {
+            &mut self.stroking
+        } else {
+            &mut self.nonstroking
+        }
+    }
+}
+
+/// The colour selected when a Separation or DeviceN colour space is set, in
+/// which every component has a tint of 1.0
+fn initial_components(space: &ColorSpace) -> Vec<f32> {
+    match space {
+        ColorSpace::DeviceN(space) => vec![1.0; space.names.len()],
+        _ => vec![1.0],
+    }
+}
+
+/// Convert the components of a colour in a Separation or DeviceN colour space
+/// to process CMYK, by applying its tint transform and converting the result
+/// from the alternate colour space
+///
+/// The special colorant All is applied to every plate, so it is converted to
+/// equal amounts of each process colorant.
+pub(crate) fn to_process_cmyk(space: &ColorSpace, components: &[f32]) -> PdfResult<[f32; 4]> {
+    let (alternate_space, tint_transform) = match space {
+        ColorSpace::Separation(separation) if separation.name.0 == "All" => {
+            let tint = components.first().copied().unwrap_or(1.0);
+
+            return Ok([tint; 4]);
+        }
+        ColorSpace::Separation(separation) => {
+            (&*separation.alternate_space, &separation.tint_transform)
+        }
+        ColorSpace::DeviceN(device_n) => (&*device_n.alternate_space, &device_n.tint_transform),
+        space => anyhow::bail!("expected spot colour space, found {:?}", space.name()),
+    };
+
+    let alternate = tint_transform.evaluate(components)?;
+
+    anyhow::ensure!(
+        !alternate.is_empty(),
+        "tint transform produced no output components"
+    );
+
+    match alternate_space.name() {
+        ColorSpaceName::DeviceCMYK => {
+            anyhow::ensure!(
+                alternate.len() == 4,
+                "expected 4 CMYK components, found {}",
+                alternate.len()
+            );
+
+            Ok([alternate[0], alternate[1], alternate[2], alternate[3]])
+        }
+        ColorSpaceName::DeviceGray => Ok([0.0, 0.0, 0.0, 1.0 - alternate[0]]),
+        ColorSpaceName::DeviceRGB => {
+            anyhow::ensure!(
+                alternate.len() == 3,
+                "expected 3 RGB components, found {}",
+                alternate.len()
+            );
+
+            Ok(rgb_to_cmyk([alternate[0], alternate[1], alternate[2]]))
+        }
+        // components in a four-component ICC profile are already process
+        // colour, in the characterised printing condition
+        ColorSpaceName::ICCBased if alternate_space.components() == 4 => {
+            anyhow::ensure!(
+                alternate.len() == 4,
+                "expected 4 CMYK components, found {}",
+                alternate.len()
+            );
+
+            Ok([alternate[0], alternate[1], alternate[2], alternate[3]])
+        }
+        // other CIE-based colours are converted through their profile or
+        // white point to RGB
+        ColorSpaceName::CalGray
+        | ColorSpaceName::CalRGB
+        | ColorSpaceName::Lab
+        | ColorSpaceName::ICCBased => Ok(rgb_to_cmyk(alternate_space.to_rgb(&alternate)?)),
+        name => anyhow::bail!(
+            "{:?} cannot be the alternate colour space of a spot colour space",
+            name
+        ),
+    }
+}
+
+/// Convert an RGB colour to CMYK with full black generation, taking as much
+/// of the colour as possible from the black plate
+fn rgb_to_cmyk([red, green, blue]: [f32; 3]) -> [f32; 4] {
+    let cyan = 1.0 - red;
+    let magenta = 1.0 - green;
+    let yellow = 1.0 - blue;
+    let key = cyan.min(magenta).min(yellow);
+
+    if key >= 1.0 {
+        [0.0, 0.0, 0.0, 1.0]
+    } else {
+        [
+            (cyan - key) / (1.0 - key),
+            (magenta - key) / (1.0 - key),
+            (yellow - key) / (1.0 - key),
+            key,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::*;
+    use crate::{content::ContentLexer, tokenizer::Tokenizer, FromObj};
+
+    fn color_space(obj: &'static [u8]) -> ColorSpace<'static> {
+        let mut tokenizer = Tokenizer::new(obj);
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+
+        ColorSpace::from_obj(obj, &mut tokenizer).unwrap()
+    }
+
+    /// A Separation colour space whose tint transform interpolates linearly
+    /// from no colour to `c1` in the alternate colour space
+    fn separation(name: &str, alternate: &str, c1: &str) -> String {
+        let c0 = vec!["0"; c1.split_whitespace().count()].join(" ");
+
+        format!(
+            "[/Separation /{} /{} << /FunctionType 2 /Domain [0 1] /N 1 /C0 [{}] /C1 [{}] >>]",
+            name, alternate, c0, c1
+        )
+    }
+
+    fn leaked(s: String) -> &'static [u8] {
+        s.into_bytes().leak()
+    }
+
+    #[test]
+    fn tint_transform_to_cmyk() {
+        let cmyk = color_space(leaked(separation("Orange", "DeviceCMYK", "0 0.5 1 0")));
+        assert_eq!(
+            to_process_cmyk(&cmyk, &[0.5]).unwrap(),
+            [0.0, 0.25, 0.5, 0.0]
+        );
+
+        // tints are clipped to the domain of the tint transform
+        assert_eq!(
+            to_process_cmyk(&cmyk, &[2.0]).unwrap(),
+            [0.0, 0.5, 1.0, 0.0]
+        );
+
+        let gray = color_space(leaked(separation("Black", "DeviceGray", "0")));
+        assert_eq!(
+            to_process_cmyk(&gray, &[1.0]).unwrap(),
+            [0.0, 0.0, 0.0, 1.0]
+        );
+
+        let rgb = color_space(leaked(separation("Red", "DeviceRGB", "1 0 0")));
+        assert_eq!(to_process_cmyk(&rgb, &[1.0]).unwrap(), [0.0, 1.0, 1.0, 0.0]);
+
+        let all = color_space(leaked(separation("All", "DeviceGray", "0")));
+        assert_eq!(to_process_cmyk(&all, &[0.4]).unwrap(), [0.4; 4]);
+
+        assert!(to_process_cmyk(
+            &ColorSpace::init(ColorSpaceName::DeviceRGB).unwrap(),
+            &[1.0]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn cie_based_alternate_to_cmyk() {
+        let lab = color_space(leaked(format!(
+            "[/Separation /Paper [/Lab << /WhitePoint [0.9505 1 1.089] >>] {}]",
+            "<< /FunctionType 2 /Domain [0 1] /N 1 /C0 [0 0 0] /C1 [100 0 0] >>"
+        )));
+
+        let close = |a: [f32; 4], b: [f32; 4]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 0.01);
+
+        assert!(close(
+            to_process_cmyk(&lab, &[0.0]).unwrap(),
+            [0.0, 0.0, 0.0, 1.0]
+        ));
+        assert!(close(
+            to_process_cmyk(&lab, &[1.0]).unwrap(),
+            [0.0, 0.0, 0.0, 0.0]
+        ));
+    }
+
+    #[test]
+    fn convert_image_samples() {
+        let orange = color_space(leaked(separation("Orange", "DeviceCMYK", "0 0.5 1 0")));
+
+        // two 4-bit samples packed into one byte per row
+        let cmyk = SpotColorConversion::new()
+            .convert_image(&orange, &[0x0F, 0xF0], [2, 2], 4, None)
+            .unwrap();
+
+        assert_eq!(
+            cmyk,
+            [
+                [0, 0, 0, 0],
+                [0, 128, 255, 0],
+                [0, 128, 255, 0],
+                [0, 0, 0, 0]
+            ]
+            .concat()
+        );
+
+        // the Decode array inverts the tints
+        let cmyk = SpotColorConversion::new()
+            .convert_image(&orange, &[0xFF], [1, 1], 8, Some(&[1.0, 0.0]))
+            .unwrap();
+
+        assert_eq!(cmyk, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn converted_colorants() {
+        let conversion = SpotColorConversion::new().preserve("Cutline");
+
+        let converts = |name: &str| {
+            conversion.converts(&color_space(leaked(separation(name, "DeviceGray", "0"))))
+        };
+
+        assert!(converts("Orange"));
+        assert!(!converts("Cutline"));
+        assert!(!converts("None"));
+
+        let device_n = |names: &str| {
+            color_space(leaked(format!(
+                "[/DeviceN [{}] /DeviceGray << /FunctionType 2 /Domain [0 1 0 1] /N 1 >>]",
+                names
+            )))
+        };
+
+        assert!(conversion.converts(&device_n("/Orange /Green")));
+        assert!(!conversion.converts(&device_n("/Orange /Cutline")));
+        assert!(!conversion.converts(&ColorSpace::init(ColorSpaceName::DeviceCMYK).unwrap()));
+    }
+
+    #[test]
+    fn rewrite_saved_color_spaces() {
+        let color_spaces = HashMap::from([
+            (
+                "Spot".to_owned(),
+                color_space(leaked(separation("Orange", "DeviceCMYK", "0 0.5 1 0"))),
+            ),
+            (
+                "Cut".to_owned(),
+                color_space(leaked(separation("Cutline", "DeviceGray", "0"))),
+            ),
+        ]);
+
+        let content: &[u8] = b"/Spot CS q 0.5 SCN 0 g 1 sc Q 0.5 SC /Cut cs 1 scn";
+
+        let tokens = SpotColorConversion::new()
+            .preserve("Cutline")
+            .rewrite_content(ContentLexer::new(Cow::Borrowed(content)), &color_spaces)
+            .unwrap();
+
+        let written = tokens
+            .iter()
+            .map(|token| match token {
+                ContentToken::Object(Object::Integer(n)) => n.to_string(),
+                ContentToken::Object(Object::Real(n)) => n.to_string(),
+                ContentToken::Object(Object::Name(name)) => format!("/{}", name),
+                ContentToken::Operator(operator) => format!("{:?}", operator),
+                token => panic!("unexpected token: {:?}", token),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        // the stroking space set before q is restored by Q, while the
+        // nonstroking colour set by g is left as it is
+        assert_eq!(
+            written,
+            "0 0.5 1 0 K q 0 0.25 0.5 0 K 0 g 1 sc Q 0 0.25 0.5 0 K /Cut cs 1 scn"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use crate::{
+    assert_empty,
+    error::{ParseError, PdfResult},
+    objects::{decode_text_string, Object, ObjectType, Reference},
+    FromObj, Parser, Resolve,
+};
+
+use super::{spot::colorant_names, ColorSpace};
+
+/// A separation dictionary, which marks a page as one colour separation of a
+/// page of the original document
+#[derive(Debug, Clone)]
+pub struct SeparationInfo<'a> {
+    /// The pages which are separations of the same page of the original
+    /// document, including this one
+    pub pages: Vec<Reference>,
+
+    /// The name of the device colorant used to render this separation, such
+    /// as Cyan or PANTONE 35 CV
+    pub device_colorant: String,
+
+    /// The Separation or DeviceN colour space which relates the colorant to
+    /// the colours of the page, when it is a spot colorant
+    pub color_space: Option<ColorSpace<'a>>,
+}
+
+impl<'a> FromObj<'a> for SeparationInfo<'a> {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        let pages = dict.expect("Pages", resolver)?;
+        let device_colorant = match dict.expect_object("DeviceColorant", resolver)? {
+            Object::Name(name) => name,
+            Object::String(name) => decode_text_string(&name),
+            _ => anyhow::bail!(ParseError::MismatchedObjectTypeAny {
+                expected: &[ObjectType::Name, ObjectType::String],
+            }),
+        };
+        let color_space = dict.get("ColorSpace", resolver)?;
+
+        assert_empty(dict);
+
+        Ok(Self {
+            pages,
+            device_colorant,
+            color_space,
+        })
+    }
+}
+
+/// A page which is one colour separation of a page of the original document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorSeparation {
+    /// The zero-based index of the page
+    pub page: usize,
+
+    /// The name of the device colorant of the separation
+    pub colorant: String,
+
+    /// The spot colorants named by the colour space of the separation
+    pub spot_colorants: Vec<String>,
+
+    /// The zero-based indices of the pages which are separations of the same
+    /// page of the original document, including this one. Pages which are not
+    /// part of the document are left out
+    pub pages: Vec<usize>,
+}
+
+impl ColorSeparation {
+    pub(crate) fn new(
+        page: usize,
+        info: &SeparationInfo,
+        page_indices: &HashMap<Reference, usize>,
+    ) -> Self {
+        Self {
+            page,
+            colorant: info.device_colorant.clone(),
+            spot_colorants: info
+                .color_space
+                .iter()
+                .flat_map(colorant_names)
+                .map(str::to_owned)
+                .collect(),
+            pages: info
+                .pages
+                .iter()
+                .filter_map(|page| page_indices.get(page).copied())
+                .collect(),
+        }
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// The pages which are colour separations of the original document, with
+    /// the colorant of each
+    pub fn color_separations(&self) -> Vec<ColorSeparation> {
+        let page_indices = self.page_indices();
+
+        self.pages()
+            .iter()
+            .enumerate()
+            .filter_map(|(page, page_object)| {
+                let info = page_object.separation_info.as_ref()?;
+
+                Some(ColorSeparation::new(page, info, &page_indices))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    fn reference(object_number: usize) -> Reference {
+        Reference {
+            object_number,
+            generation: 0,
+        }
+    }
+
+    #[test]
+    fn separation_of_pages() {
+        let mut tokenizer =
+            Tokenizer::new(b"<< /Pages [ 4 0 R 5 0 R 9 0 R ] /DeviceColorant /Magenta >>");
+
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+        let info = SeparationInfo::from_obj(obj, &mut tokenizer).unwrap();
+
+        assert_eq!(info.pages, vec![reference(4), reference(5), reference(9)]);
+
+        let page_indices = HashMap::from([(reference(4), 0), (reference(5), 1)]);
+
+        assert_eq!(
+            ColorSeparation::new(1, &info, &page_indices),
+            ColorSeparation {
+                page: 1,
+                colorant: "Magenta".to_owned(),
+                spot_colorants: Vec::new(),
+                pages: vec![0, 1],
+            }
+        );
+    }
+}
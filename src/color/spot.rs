@@ -0,0 +1,273 @@
+/*!
+ * An inventory of the spot colorants (those of Separation and DeviceN colour
+ * spaces) used by a document, for checking its plates before printing
+ */
+
+use std::rc::Rc;
+
+use crate::{
+    page::PageObject,
+    resources::{pattern::Pattern, Resources},
+    xobject::XObject,
+    Parser,
+};
+
+use super::{device_n::DeviceNColorSpace, ColorSpace};
+
+/// A colorant named by a Separation or DeviceN colour space, and each place
+/// in which a colour space naming it is used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpotColorant {
+    pub name: String,
+
+    /// The uses of colour spaces naming the colorant, in the order of the
+    /// pages on which they occur
+    pub usages: Vec<SpotColorUsage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpotColorUsage {
+    /// The zero-based index of the page
+    pub page: usize,
+
+    /// The names of the form XObjects, from outermost to innermost, within
+    /// whose resources the colour space is used, or empty if it is used by
+    /// the resources of the page itself
+    pub forms: Vec<String>,
+
+    pub location: SpotColorLocation,
+}
+
+/// The resource which uses a spot colour space, by its name in the resource
+/// dictionary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpotColorLocation {
+    /// An entry of the ColorSpace subdictionary, which may be selected by the
+    /// `CS` and `cs` operators
+    ColorSpace(String),
+
+    /// An image XObject, whose samples are in the colour space
+    Image(String),
+
+    /// A shading, painted by the `sh` operator
+    Shading(String),
+
+    /// A shading pattern
+    Pattern(String),
+}
+
+/// Every spot colorant used by the resources of `pages`, in the order in which
+/// they are first used
+///
+/// The colorant None is skipped, as it never produces marks. Colour spaces
+/// are found through the resources of each page and of the form XObjects they
+/// contain, including the base of Indexed colour spaces.
+// todo: inline images, tiling patterns, and annotation appearance streams
+fn spot_colors(pages: &[Rc<PageObject>]) -> Vec<SpotColorant> {
+    let mut colorants = Vec::new();
+
+    for (idx, page) in pages.iter().enumerate() {
+        if let Some(resources) = page.resources() {
+            collect_resources(&resources, idx, &mut Vec::new(), &mut colorants);
+        }
+    }
+
+    colorants
+}
+
+fn collect_resources(
+    resources: &Resources,
+    page: usize,
+    forms: &mut Vec<String>,
+    colorants: &mut Vec<SpotColorant>,
+) {
+    let mut add = |color_space: &ColorSpace, location: SpotColorLocation| {
+        for name in colorant_names(color_space) {
+            let usage = SpotColorUsage {
+                page,
+                forms: forms.clone(),
+                location: location.clone(),
+            };
+
+            match colorants.iter_mut().find(|colorant| colorant.name == name) {
+                Some(colorant) => colorant.usages.push(usage),
+                None => colorants.push(SpotColorant {
+                    name: name.to_owned(),
+                    usages: vec![usage],
+                }),
+            }
+        }
+    };
+
+    // resources are visited in the order of their names, so that the
+    // inventory of a document is always the same
+    if let Some(color_spaces) = &resources.color_space {
+        for (name, color_space) in sorted(color_spaces.iter()) {
+            add(color_space, SpotColorLocation::ColorSpace(name.clone()));
+        }
+    }
+
+    if let Some(shadings) = &resources.shading {
+        for (name, shading) in sorted(shadings.iter()) {
+            add(
+                shading.color_space(),
+                SpotColorLocation::Shading(name.clone()),
+            );
+        }
+    }
+
+    if let Some(patterns) = &resources.pattern {
+        for (name, pattern) in sorted(patterns.iter()) {
+            if let Pattern::Shading(pattern) = &**pattern {
+                add(
+                    pattern.shading().color_space(),
+                    SpotColorLocation::Pattern(name.clone()),
+                );
+            }
+        }
+    }
+
+    let xobjects = match &resources.xobject {
+        Some(xobjects) => sorted(xobjects.iter()),
+        None => return,
+    };
+
+    for (name, xobject) in &xobjects {
+        if let XObject::Image(image) = xobject {
+            if let Some(color_space) = &image.color_space {
+                add(color_space, SpotColorLocation::Image((*name).clone()));
+            }
+        }
+    }
+
+    for (name, xobject) in xobjects {
+        if let XObject::Form(form) = xobject {
+            if let Some(resources) = &form.resources {
+                forms.push(name.clone());
+                collect_resources(resources, page, forms, colorants);
+                forms.pop();
+            }
+        }
+    }
+}
+
+fn sorted<'b, T>(entries: impl Iterator<Item = (&'b String, T)>) -> Vec<(&'b String, T)> {
+    let mut entries = entries.collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    entries
+}
+
+/// The spot colorants named by a colour space
+pub(super) fn colorant_names<'b>(color_space: &'b ColorSpace) -> Vec<&'b str> {
+    match color_space {
+        ColorSpace::Separation(separation) if separation.name.0 != "None" => {
+            vec![separation.name.0.as_str()]
+        }
+        ColorSpace::DeviceN(DeviceNColorSpace { names, .. }) => names
+            .iter()
+            .map(|name| name.0.as_str())
+            .filter(|&name| name != "None")
+            .collect(),
+        ColorSpace::Indexed { space, .. } => colorant_names(&space.base),
+        _ => Vec::new(),
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Every spot colorant named by the Separation and DeviceN colour spaces
+    /// of the document, with each place in which they are used
+    pub fn spot_colors(&self) -> Vec<SpotColorant> {
+        spot_colors(&self.pages())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{
+        objects::{Dictionary, Object},
+        version::PdfVersion,
+        writer::PdfWriter,
+        FromObj,
+    };
+
+    use super::*;
+
+    fn name(name: &str) -> Object<'static> {
+        Object::Name(name.to_owned())
+    }
+
+    fn color_space(arr: Vec<Object<'static>>) -> ColorSpace<'static> {
+        let tint_transform = Dictionary::new(HashMap::from([
+            ("FunctionType".to_owned(), Object::Integer(2)),
+            (
+                "Domain".to_owned(),
+                Object::Array(vec![Object::Integer(0), Object::Integer(1)]),
+            ),
+            ("N".to_owned(), Object::Integer(1)),
+        ]));
+
+        let arr = [
+            arr,
+            vec![name("DeviceGray"), Object::Dictionary(tint_transform)],
+        ]
+        .concat();
+
+        ColorSpace::from_obj(Object::Array(arr), &mut PdfWriter::new(PdfVersion::V1_7)).unwrap()
+    }
+
+    #[test]
+    fn spot_color_inventory() {
+        let resources = Resources {
+            ext_g_state: None,
+            color_space: Some(HashMap::from([
+                (
+                    "CS1".to_owned(),
+                    color_space(vec![
+                        name("DeviceN"),
+                        Object::Array(vec![name("Varnish"), name("None")]),
+                    ]),
+                ),
+                (
+                    "CS0".to_owned(),
+                    color_space(vec![name("Separation"), name("Cutline")]),
+                ),
+                (
+                    "CS2".to_owned(),
+                    color_space(vec![name("Separation"), name("Varnish")]),
+                ),
+            ])),
+            pattern: None,
+            shading: None,
+            xobject: None,
+            font: None,
+            proc_set: None,
+            properties: None,
+        };
+
+        let mut colorants = Vec::new();
+        collect_resources(&resources, 3, &mut Vec::new(), &mut colorants);
+
+        let usage = |name: &str| SpotColorUsage {
+            page: 3,
+            forms: Vec::new(),
+            location: SpotColorLocation::ColorSpace(name.to_owned()),
+        };
+
+        assert_eq!(
+            colorants,
+            [
+                SpotColorant {
+                    name: "Cutline".to_owned(),
+                    usages: vec![usage("CS0")],
+                },
+                SpotColorant {
+                    name: "Varnish".to_owned(),
+                    usages: vec![usage("CS1"), usage("CS2")],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{
+    color::{ColorSpace, ColorSpaceName},
+    error::PdfResult,
+    objects::{Dictionary, Object},
+    stream::{Stream, StreamDict},
+    xobject::ImageXObject,
+    FromObj, Resolve,
+};
+
+/// The abbreviations of keys which may be used in the dictionary of an inline
+/// image
+const KEYS: [(&str, &str); 10] = [
+    ("BPC", "BitsPerComponent"),
+    ("CS", "ColorSpace"),
+    ("D", "Decode"),
+    ("DP", "DecodeParms"),
+    ("F", "Filter"),
+    ("H", "Height"),
+    ("IM", "ImageMask"),
+    ("I", "Interpolate"),
+    ("L", "Length"),
+    ("W", "Width"),
+];
+
+const FILTERS: [(&str, &str); 7] = [
+    ("AHx", "ASCIIHexDecode"),
+    ("A85", "ASCII85Decode"),
+    ("LZW", "LZWDecode"),
+    ("Fl", "FlateDecode"),
+    ("RL", "RunLengthDecode"),
+    ("CCF", "CCITTFaxDecode"),
+    ("DCT", "DCTDecode"),
+];
+
+const COLOR_SPACES: [(&str, &str); 4] = [
+    ("G", "DeviceGray"),
+    ("RGB", "DeviceRGB"),
+    ("CMYK", "DeviceCMYK"),
+    ("I", "Indexed"),
+];
+
+/// An image whose dictionary and data appear within a content stream, between
+/// the `BI`, `ID` and `EI` operators
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineImage<'a> {
+    /// The entries of the image dictionary as they were written, which may
+    /// use abbreviated keys and values
+    pub dict: Dictionary<'a>,
+
+    /// The data of the image, still encoded by its filters
+    pub data: Vec<u8>,
+}
+
+impl<'a> InlineImage<'a> {
+    /// The dictionary of the image, with its abbreviated keys, filter names
+    /// and colour space names replaced by those used by image XObjects
+    pub fn expanded_dict(&self) -> Dictionary<'a> {
+        let mut dict = Dictionary::empty();
+
+        for (key, value) in self.dict.iter() {
+            let key = expand(key, &KEYS);
+
+            let value = match (key, value) {
+                ("Filter", value) => expand_names(value.clone(), &FILTERS),
+                ("ColorSpace", Object::Array(arr)) => {
+                    let mut arr = arr.clone();
+
+                    // the base colour space of an Indexed colour space may
+                    // also be abbreviated
+                    for obj in arr.iter_mut().take(2) {
+                        *obj = expand_names(obj.clone(), &COLOR_SPACES);
+                    }
+
+                    Object::Array(arr)
+                }
+                ("ColorSpace", value) => expand_names(value.clone(), &COLOR_SPACES),
+                (_, value) => value.clone(),
+            };
+
+            dict.insert(key, value);
+        }
+
+        dict
+    }
+
+    /// The image as an image XObject, whose data is decoded by its filters
+    /// as that of any other image
+    ///
+    /// The colour space of the image may name an entry of `color_spaces`,
+    /// the ColorSpace entry of the resource dictionary of the content stream.
+    pub fn to_xobject(
+        &self,
+        color_spaces: Option<&HashMap<String, ColorSpace<'a>>>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<ImageXObject<'a>> {
+        let mut dict = self.expanded_dict();
+
+        let named_color_space = match dict.peek("ColorSpace") {
+            Some(Object::Name(name)) if ColorSpaceName::from_str(name).is_err() => {
+                match color_spaces.and_then(|color_spaces| color_spaces.get(name)) {
+                    Some(color_space) => Some(color_space.clone()),
+                    None => anyhow::bail!("unknown inline image colour space {:?}", name),
+                }
+            }
+            _ => None,
+        };
+
+        if named_color_space.is_some() {
+            dict.remove("ColorSpace");
+        }
+
+        dict.insert("Length", Object::Integer(i32::try_from(self.data.len())?));
+
+        let stream = Stream {
+            dict: StreamDict::from_dict(dict, resolver)?,
+            stream: Cow::Owned(self.data.clone()),
+        };
+
+        let mut image = ImageXObject::from_obj(Object::Stream(stream), resolver)?;
+
+        if named_color_space.is_some() {
+            image.color_space = named_color_space;
+        }
+
+        Ok(image)
+    }
+}
+
+fn expand<'b>(name: &'b str, abbreviations: &[(&str, &'b str)]) -> &'b str {
+    abbreviations
+        .iter()
+        .find(|(abbreviation, _)| *abbreviation == name)
+        .map_or(name, |(_, expanded)| expanded)
+}
+
+/// Expand a name, or each name of an array
+fn expand_names<'a>(obj: Object<'a>, abbreviations: &[(&str, &str)]) -> Object<'a> {
+    match obj {
+        Object::Name(name) => Object::Name(expand(&name, abbreviations).to_owned()),
+        Object::Array(arr) => Object::Array(
+            arr.into_iter()
+                .map(|obj| expand_names(obj, abbreviations))
+                .collect(),
+        ),
+        obj => obj,
+    }
+}
+
+/// The length of the data of an inline image, if it can be known from its
+/// expanded dictionary, or from the end of data marker of its first filter
+pub(super) fn data_len(dict: &Dictionary, data: &[u8]) -> Option<usize> {
+    let integer = |key: &str| match dict.peek(key) {
+        Some(&Object::Integer(n)) => usize::try_from(n).ok(),
+        _ => None,
+    };
+
+    if let Some(len) = integer("Length") {
+        return Some(len);
+    }
+
+    let first_filter = match dict.peek("Filter") {
+        Some(Object::Name(name)) => Some(name.as_str()),
+        Some(Object::Array(arr)) => match arr.first() {
+            Some(Object::Name(name)) => Some(name.as_str()),
+            Some(..) => return None,
+            None => None,
+        },
+        Some(..) => return None,
+        None => None,
+    };
+
+    match first_filter {
+        Some("ASCIIHexDecode") => return data.iter().position(|&b| b == b'>').map(|end| end + 1),
+        Some("ASCII85Decode") => {
+            return data
+                .windows(2)
+                .position(|window| window == b"~>")
+                .map(|end| end + 2)
+        }
+        Some(..) => return None,
+        None => {}
+    }
+
+    let (bits, components) = match dict.peek("ImageMask") {
+        Some(Object::True) => (1, 1),
+        _ => {
+            let components = match dict.peek("ColorSpace")? {
+                Object::Name(name) => match name.as_str() {
+                    "DeviceGray" => 1,
+                    "DeviceRGB" => 3,
+                    "DeviceCMYK" => 4,
+                    _ => return None,
+                },
+                Object::Array(arr) if matches!(arr.first(), Some(obj) if obj.name_is("Indexed")) => {
+                    1
+                }
+                _ => return None,
+            };
+
+            (integer("BitsPerComponent")?, components)
+        }
+    };
+
+    // each row begins on a byte boundary
+    Some((integer("Width")? * components * bits).div_ceil(8) * integer("Height")?)
+}
@@ -1,15 +1,24 @@
 use std::borrow::{Borrow, Cow};
+use std::rc::Rc;
 
 use crate::{
     error::PdfResult,
     lex::{LexBase, LexObject},
-    objects::Object,
+    objects::{Dictionary, Object},
+    page::PageObject,
+    render::Renderer,
+    Parser,
 };
 
+pub use inline_image::InlineImage;
+pub use property_list::PropertyList;
+
 pub(crate) use operator::PdfGraphicsOperator;
 pub(crate) use stream::ContentStream;
 
+mod inline_image;
 mod operator;
+mod property_list;
 mod stream;
 
 pub struct ContentLexer<'a> {
@@ -21,18 +30,83 @@ pub struct ContentLexer<'a> {
     /// Set to true when encountering a `BX` operator, and to false
     /// when an `EX` operator is encountered
     in_compatibility_mode: u128,
+
+    /// The offset of the first byte of the most recently lexed token
+    token_start: usize,
 }
 
 impl<'a> ContentLexer<'a> {
     pub fn as_slice(&'a self) -> &'a [u8] {
         &self.buffer[..]
     }
+
+    /// The offset of the first byte of the most recently lexed token
+    pub fn token_start(&self) -> usize {
+        self.token_start
+    }
+
+    /// The source text of the most recently lexed token
+    pub fn last_token(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.buffer[self.token_start..self.cursor])
+    }
+}
+
+/// A recoverable error encountered while interpreting a content stream
+///
+/// Content streams are interpreted on a best-effort basis: an operator which
+/// cannot be lexed or applied is skipped, along with its operands, and the
+/// rest of the stream is still interpreted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentDiagnostic {
+    /// The offset of the offending token within the decoded content stream
+    pub offset: usize,
+
+    /// The operator that failed, or `None` if the content could not be lexed
+    pub operator: Option<String>,
+
+    pub message: String,
+}
+
+/// A marked-content sequence of a content stream, from a BMC or BDC operator
+/// through its balancing EMC operator
+#[derive(Debug, Clone)]
+pub struct MarkedContentSequence<'a> {
+    /// The role or significance of the sequence, such as `Span`, `Artifact`
+    /// or, for optional content, `OC`
+    pub tag: String,
+
+    /// The property list of a sequence begun by a BDC operator
+    pub properties: Option<PropertyList<'a>>,
+
+    /// The number of sequences enclosing this one
+    pub depth: usize,
+
+    /// Whether the content of the sequence is visible, given the optional
+    /// content state and the optional content of this and every enclosing
+    /// sequence
+    pub is_visible: bool,
+
+    /// The text shown within the sequence, including within the sequences
+    /// nested in it
+    pub text: String,
+}
+
+impl MarkedContentSequence<'_> {
+    /// The marked-content identifier of the sequence, by which it is referred
+    /// to from the structure tree
+    pub fn mcid(&self) -> Option<i32> {
+        self.properties.as_ref()?.mcid
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub enum ContentToken<'a> {
     Object(Object<'a>),
     Operator(PdfGraphicsOperator),
+
+    /// An inline image, lexed as a whole from its `BI` operator to its `EI`
+    /// operator
+    InlineImage(InlineImage<'a>),
 }
 
 #[derive(Debug)]
@@ -51,10 +125,13 @@ impl<'a> Iterator for ContentLexer<'a> {
                 if self.in_compatibility_mode() {
                     self.next()
                 } else {
-                    todo!("Unknown operator: {:?}", s)
+                    Some(Err(anyhow::anyhow!("unknown operator {:?}", s)))
                 }
             }
-            Some(Err(e)) => Some(Err(e)),
+            Some(Err(e)) => {
+                self.recover();
+                Some(Err(e))
+            }
             None => None,
         }
     }
@@ -66,9 +143,126 @@ impl<'a> ContentLexer<'a> {
             buffer,
             cursor: 0,
             in_compatibility_mode: 0,
+            token_start: 0,
+        }
+    }
+
+    /// Skip to the next point from which lexing may resume after an error:
+    /// past at least one byte, and past the rest of a partially lexed token
+    fn recover(&mut self) {
+        if self.cursor == self.token_start {
+            self.next_byte();
+        }
+
+        while let Some(b) = self.peek_byte() {
+            if !Self::is_regular(b) {
+                break;
+            }
+
+            self.next_byte();
         }
     }
 
+    /// Lex an inline image following its `BI` operator: the entries of its
+    /// dictionary up to the `ID` operator, and then its data up to the `EI`
+    /// operator
+    fn lex_inline_image(&mut self) -> PdfResult<InlineImage<'a>> {
+        let mut dict = Dictionary::empty();
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek_byte() {
+                Some(b'/') => {
+                    let key = self.lex_name()?;
+                    let value = self.lex_object()?;
+
+                    dict.insert(key, value);
+                }
+                Some(b'I') if self.peek_byte_offset(1) == Some(b'D') => {
+                    self.cursor += 2;
+                    break;
+                }
+                Some(b) => {
+                    anyhow::bail!("unexpected byte {:?} in inline image dictionary", b as char)
+                }
+                None => anyhow::bail!("content stream ends within inline image dictionary"),
+            }
+        }
+
+        let image = InlineImage {
+            data: Vec::new(),
+            dict,
+        };
+
+        let data = self.lex_inline_image_data(&image.expanded_dict())?;
+
+        Ok(InlineImage { data, ..image })
+    }
+
+    /// Lex the data of an inline image, leaving the cursor after its closing
+    /// `EI` operator
+    ///
+    /// Where the length of the data cannot be known from the dictionary, the
+    /// data is taken to end at the first `EI` surrounded by whitespace which
+    /// is followed only by text, as binary data is unlikely to be.
+    fn lex_inline_image_data(&mut self, dict: &Dictionary) -> PdfResult<Vec<u8>> {
+        // a single whitespace character follows the `ID` operator
+        self.next_byte();
+
+        let data = &self.buffer[self.cursor..];
+
+        let is_ei_at = |idx: usize| {
+            data[idx..].starts_with(b"EI")
+                && data.get(idx + 2).map_or(true, |&b| !Self::is_regular(b))
+        };
+
+        let known_len = inline_image::data_len(dict, data).filter(|&len| {
+            match data
+                .get(len..)
+                .and_then(|rest| rest.iter().position(|&b| !Self::is_whitespace(b)))
+            {
+                Some(offset) => is_ei_at(len + offset),
+                None => false,
+            }
+        });
+
+        let (len, ei) = match known_len {
+            Some(len) => {
+                let offset = data[len..]
+                    .iter()
+                    .position(|&b| !Self::is_whitespace(b))
+                    .unwrap_or(0);
+
+                (len, len + offset)
+            }
+            None => {
+                let ei = (1..data.len()).find(|&idx| {
+                    Self::is_whitespace(data[idx - 1])
+                        && is_ei_at(idx)
+                        && data[idx + 2..]
+                            .iter()
+                            .take(64)
+                            .all(|&b| b.is_ascii_graphic() || Self::is_whitespace(b))
+                });
+
+                match ei {
+                    Some(ei) => (ei - 1, ei),
+                    None => {
+                        self.cursor = self.buffer.len();
+                        anyhow::bail!("inline image data is not terminated by EI")
+                    }
+                }
+            }
+        };
+
+        let image_data = data[..len].to_vec();
+
+        self.cursor += ei + 2;
+
+        Ok(image_data)
+    }
+
     fn enter_compatibility_mode(&mut self) {
         self.in_compatibility_mode <<= 1;
         self.in_compatibility_mode |= 1;
@@ -128,8 +322,23 @@ impl<'a> ContentLexer<'a> {
 
     fn next_token(&mut self) -> Option<PdfResult<ContentTokenOrUnknownOperator<'a>>> {
         self.skip_whitespace();
+        self.token_start = self.cursor;
+
         match self.peek_byte() {
-            Some(b'"' | b'\'' | b'a'..=b'z' | b'A'..=b'Z') => Some(self.try_lex_operator()),
+            Some(b'"' | b'\'' | b'a'..=b'z' | b'A'..=b'Z') => {
+                let token = self.try_lex_operator();
+
+                if let Ok(ContentTokenOrUnknownOperator::Token(ContentToken::Operator(
+                    PdfGraphicsOperator::BI,
+                ))) = token
+                {
+                    return Some(self.lex_inline_image().map(|image| {
+                        ContentTokenOrUnknownOperator::Token(ContentToken::InlineImage(image))
+                    }));
+                }
+
+                Some(token)
+            }
             Some(..) => Some(Ok(ContentTokenOrUnknownOperator::Token(
                 ContentToken::Object(match self.lex_object() {
                     Ok(v) => v,
@@ -161,9 +370,56 @@ impl<'a> LexObject<'a> for ContentLexer<'_> {
     }
 }
 
+impl<'a> Parser<'a> {
+    /// Interpret the content of `page`, returning the errors which were
+    /// skipped over
+    ///
+    /// Rendering and text extraction continue past malformed operators, such
+    /// as those with too few operands, unknown operators, and broken inline
+    /// images. This reports where that happened.
+    pub fn content_diagnostics(
+        &mut self,
+        page: &Rc<PageObject<'a>>,
+    ) -> PdfResult<Vec<ContentDiagnostic>> {
+        let mut content = self.page_contents(page)?;
+        let optional_content = self.optional_content_state();
+        let mut diagnostics = Vec::new();
+
+        Renderer::new(&mut content, &mut self.lexer, Rc::clone(page))
+            .with_optional_content(optional_content)
+            .with_diagnostics(&mut diagnostics)
+            .render_text_lines()?;
+
+        Ok(diagnostics)
+    }
+
+    /// The marked-content sequences of `page`, in the order in which they
+    /// begin, with their property lists, the text shown within them and
+    /// whether their content is visible under the default optional content
+    /// configuration
+    pub fn marked_content(
+        &mut self,
+        page: &Rc<PageObject<'a>>,
+    ) -> PdfResult<Vec<MarkedContentSequence<'a>>> {
+        let mut content = self.page_contents(page)?;
+        let optional_content = self.optional_content_state();
+        let mut sequences = Vec::new();
+
+        Renderer::new(&mut content, &mut self.lexer, Rc::clone(page))
+            .with_optional_content(optional_content)
+            .with_marked_content(&mut sequences)
+            .render_text_lines()?;
+
+        Ok(sequences)
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use std::collections::HashMap;
+
     use super::*;
+    use crate::{test::TestFile, ParseOptions};
 
     #[test]
     fn rg_operator_is_not_parsed_as_reference() {
@@ -229,4 +485,145 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn recover_from_errors() {
+        let buffer = b"1 g } 2 xyz 3 G BI /W 1 ID \xff\x00 EI q";
+
+        let tokens = ContentLexer::new(Cow::Borrowed(buffer))
+            .map(|token| token.ok())
+            .collect::<Vec<Option<ContentToken>>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Some(ContentToken::Object(Object::Integer(1))),
+                Some(ContentToken::Operator(PdfGraphicsOperator::g)),
+                None,
+                Some(ContentToken::Object(Object::Integer(2))),
+                None,
+                Some(ContentToken::Object(Object::Integer(3))),
+                Some(ContentToken::Operator(PdfGraphicsOperator::G)),
+                Some(ContentToken::InlineImage(InlineImage {
+                    dict: Dictionary::new(HashMap::from([("W".to_owned(), Object::Integer(1))])),
+                    data: vec![0xff, 0x00],
+                })),
+                Some(ContentToken::Operator(PdfGraphicsOperator::q)),
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_image_data() {
+        // the data of an image of known length may contain ` EI `
+        let buffer = b"BI /W 4 /H 1 /CS /G /BPC 8 ID \x00 EI EI Q";
+
+        let tokens = ContentLexer::new(Cow::Borrowed(buffer))
+            .collect::<PdfResult<Vec<ContentToken>>>()
+            .unwrap();
+
+        let image = match &tokens[0] {
+            ContentToken::InlineImage(image) => image,
+            token => panic!("expected inline image, found {:?}", token),
+        };
+
+        assert_eq!(image.data, b"\x00 EI");
+        assert_eq!(
+            tokens[1..],
+            [ContentToken::Operator(PdfGraphicsOperator::Q)]
+        );
+
+        // otherwise it ends at an `EI` followed only by text
+        let buffer = b"BI /W 4 /H 1 /CS /G /BPC 8 /F /Fl ID \x00 EI\xff EI Q";
+
+        let tokens = ContentLexer::new(Cow::Borrowed(buffer))
+            .collect::<PdfResult<Vec<ContentToken>>>()
+            .unwrap();
+
+        match &tokens[0] {
+            ContentToken::InlineImage(image) => assert_eq!(image.data, b"\x00 EI\xff"),
+            token => panic!("expected inline image, found {:?}", token),
+        }
+
+        assert_eq!(tokens.len(), 2);
+    }
+
+    #[test]
+    fn marked_content() {
+        // the final EMC operator is unbalanced, and is ignored
+        let content = b"/Span << /ActualText (fi) /Lang (en-GB) >> BDC \
+            BT /F1 12 Tf 10 10 Td <0061> Tj /P << /MCID 3 >> BDC <0062> Tj EMC ET EMC \
+            /OC /L1 BDC BT /F1 12 Tf <0063> Tj ET /Artifact BMC EMC EMC EMC";
+
+        let mut file = TestFile::new();
+        file.object(
+            1,
+            b"<< /Type /Catalog /Pages 2 0 R \
+              /OCProperties << /OCGs [5 0 R] /D << /OFF [5 0 R] >> >> >>",
+        );
+        file.object(2, b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>");
+        file.object(
+            3,
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 60 20] /Contents 4 0 R \
+              /Resources << /Properties << /L1 5 0 R >> /Font << /F1 6 0 R >> >> >>",
+        );
+        file.stream(4, &format!("<< /Length {} >>", content.len()), content);
+        file.object(5, b"<< /Type /OCG /Name (Hidden) >>");
+
+        // a composite font whose program is not embedded, so that glyphs are
+        // positioned and their text extracted without being painted
+        file.object(
+            6,
+            b"<< /Type /Font /Subtype /Type0 /BaseFont /Missing /Encoding /Identity-H \
+              /DescendantFonts [7 0 R] /ToUnicode 8 0 R >>",
+        );
+        file.object(
+            7,
+            b"<< /Type /Font /Subtype /CIDFontType2 /BaseFont /Missing \
+              /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+              /FontDescriptor << /Type /FontDescriptor /FontName /Missing /Flags 32 \
+              /FontBBox [0 0 1000 1000] /ItalicAngle 0 /Ascent 800 /Descent -200 \
+              /CapHeight 700 /StemV 80 >> >>",
+        );
+
+        let to_unicode = b"begincmap 1 begincodespacerange <0000> <FFFF> endcodespacerange \
+            1 beginbfrange <0061> <0063> <0061> endbfrange endcmap";
+        file.stream(
+            8,
+            &format!("<< /Length {} >>", to_unicode.len()),
+            to_unicode,
+        );
+
+        let mut parser =
+            Parser::from_bytes(file.with_xref_table(), ParseOptions::default()).unwrap();
+
+        let page = parser.pages().remove(0);
+        let sequences = parser.marked_content(&page).unwrap();
+
+        assert_eq!(
+            sequences
+                .iter()
+                .map(|sequence| (
+                    sequence.tag.as_str(),
+                    sequence.depth,
+                    sequence.is_visible,
+                    sequence.text.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            [
+                ("Span", 0, true, "ab"),
+                ("P", 1, true, "b"),
+                ("OC", 0, false, "c"),
+                ("Artifact", 1, false, ""),
+            ]
+        );
+
+        let span = sequences[0].properties.as_ref().unwrap();
+        assert_eq!(span.actual_text.as_deref(), Some("fi"));
+        assert_eq!(span.lang.as_deref(), Some("en-GB"));
+
+        assert_eq!(sequences[0].mcid(), None);
+        assert_eq!(sequences[1].mcid(), Some(3));
+        assert!(sequences[3].properties.is_none());
+    }
 }
@@ -0,0 +1,100 @@
+use crate::{
+    error::PdfResult,
+    objects::{decode_text_string, Dictionary, Object},
+    optional_content::OptionalContent,
+    resources::Resources,
+    FromObj, Resolve,
+};
+
+/// A property list, associating additional data with a marked-content sequence
+/// or point, as given by the operand of a BDC or DP operator
+///
+/// The operand is either an inline dictionary, or the name of a property list
+/// in the Properties subdictionary of the current resource dictionary.
+#[derive(Debug, Clone)]
+pub struct PropertyList<'a> {
+    /// The optional content group or membership dictionary controlling the
+    /// visibility of content tagged OC
+    pub(crate) optional_content: Option<OptionalContent>,
+
+    /// The marked-content identifier, by which the sequence is referred to from
+    /// the structure tree
+    pub mcid: Option<i32>,
+
+    /// A language identifier specifying the natural language of the text
+    /// within the sequence
+    pub lang: Option<String>,
+
+    /// Text that is an exact replacement for the content of the sequence, such
+    /// as the spelled-out form of a ligature or hyphenated word
+    pub actual_text: Option<String>,
+
+    /// An alternate description of the content of the sequence, such as the
+    /// description of an illustration
+    pub alt: Option<String>,
+
+    /// The expanded form of an abbreviation or acronym
+    pub expansion: Option<String>,
+
+    /// Entries which are private to the producer of the document
+    other: Dictionary<'a>,
+}
+
+impl<'a> FromObj<'a> for PropertyList<'a> {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj.clone())?;
+
+        // Optional content groups and membership dictionaries are used directly
+        // as the property lists of optional content
+        if matches!(
+            dict.get_name("Type", resolver)?.as_deref(),
+            Some("OCG" | "OCMD")
+        ) {
+            return Ok(Self {
+                optional_content: Some(OptionalContent::from_obj(obj, resolver)?),
+                mcid: None,
+                lang: None,
+                actual_text: None,
+                alt: None,
+                expansion: None,
+                other: Dictionary::empty(),
+            });
+        }
+
+        let mut text = |key: &str| -> PdfResult<Option<String>> {
+            Ok(dict
+                .get_string(key, resolver)?
+                .as_deref()
+                .map(decode_text_string))
+        };
+
+        Ok(Self {
+            optional_content: None,
+            lang: text("Lang")?,
+            actual_text: text("ActualText")?,
+            alt: text("Alt")?,
+            expansion: text("E")?,
+            mcid: dict.get_integer("MCID", resolver)?,
+            other: dict,
+        })
+    }
+}
+
+impl<'a> PropertyList<'a> {
+    /// The property list given by the operand of a BDC or DP operator,
+    /// looking up named property lists in `resources`
+    pub(crate) fn from_operand(
+        operand: Object<'a>,
+        resources: Option<&Resources<'a>>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        match operand {
+            Object::Name(name) => resources
+                .and_then(|resources| resources.properties.as_ref())
+                .and_then(|properties| properties.get(&name))
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("unable to find property list {:?}", name)),
+            obj => Self::from_obj(obj, resolver),
+        }
+    }
+}
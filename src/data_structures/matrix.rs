@@ -1,7 +1,11 @@
 use std::ops::{Mul, MulAssign};
 
 use crate::{
-    catalog::assert_len, error::PdfResult, geometry::Point, objects::Object, FromObj, Resolve,
+    catalog::assert_len,
+    error::PdfResult,
+    geometry::Point,
+    objects::{Object, ToObj},
+    FromObj, Resolve,
 };
 
 /// A 3x3 matrix
@@ -13,7 +17,7 @@ use crate::{
 /// [a b 0]
 /// [c d 0]
 /// [e f 1]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Matrix {
     a: f32,
     b: f32,
@@ -32,10 +36,8 @@ impl Mul<Matrix> for Matrix {
         let b = self.a * other.b + self.b * other.d;
         let d = self.c * other.b + self.d * other.d;
 
-        // todo: this is almost certainly wrong, but it works for the test case
-        // i'm using
-        let e = self.e + other.e;
-        let f = self.f + other.f;
+        let e = self.e * other.a + self.f * other.c + other.e;
+        let f = self.e * other.b + self.f * other.d + other.f;
 
         Matrix::new(a, b, c, d, e, f)
     }
@@ -124,3 +126,9 @@ impl<'a> FromObj<'a> for Matrix {
         Ok(Self { a, b, c, d, e, f })
     }
 }
+
+impl<'a> ToObj<'a> for Matrix {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        [self.a, self.b, self.c, self.d, self.e, self.f].to_obj()
+    }
+}
@@ -1,7 +1,7 @@
 pub(crate) use matrix::Matrix;
 pub(crate) use name_tree::NameTree;
 pub(crate) use number_tree::NumberTree;
-pub(crate) use rectangle::Rectangle;
+pub use rectangle::Rectangle;
 
 mod matrix;
 mod name_tree;
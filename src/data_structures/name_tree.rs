@@ -11,7 +11,7 @@ use crate::{
 // todo: add docs
 // todo: this is just a copy of the number tree. would be nice to fix it up a bit or
 // unify the two somehow
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NameTree<'a> {
     root: NameTreeRoot<'a>,
 }
@@ -35,21 +35,60 @@ fn get_names<'a>(
         .transpose()
 }
 
+fn get_kids<'a>(
+    dict: &mut Dictionary<'a>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Option<Vec<Rc<NameTreeNode<'a>>>>> {
+    dict.get_arr("Kids", resolver)?
+        .map(|kids| {
+            kids.into_iter()
+                .map(|kid| Ok(Rc::new(NameTreeNode::from_obj(kid, resolver)?)))
+                .collect::<PdfResult<Vec<Rc<NameTreeNode>>>>()
+        })
+        .transpose()
+}
+
 impl<'a> FromObj<'a> for NameTree<'a> {
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
         let mut dict = resolver.assert_dict(obj)?;
 
         let names = get_names(&mut dict, resolver)?;
+        let kids = get_kids(&mut dict, resolver)?;
 
         assert_empty(dict);
 
         Ok(Self {
-            root: NameTreeRoot { names, kids: None },
+            root: NameTreeRoot { names, kids },
         })
     }
 }
 
-#[derive(Debug)]
+impl<'a> NameTree<'a> {
+    /// Every key of the tree and its associated object, in order of their keys
+    pub fn entries(&self) -> Vec<(&str, &Object<'a>)> {
+        let mut entries = Vec::new();
+
+        if let Some(names) = &self.root.names {
+            entries.extend(names.iter().map(|(key, value)| (key.as_str(), value)));
+        }
+
+        for kid in self.root.kids.iter().flatten() {
+            kid.collect_entries(&mut entries);
+        }
+
+        entries
+    }
+
+    /// The object associated with `key`, if any
+    pub fn get(&self, key: &str) -> Option<&Object<'a>> {
+        self.entries()
+            .into_iter()
+            .find(|&(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+}
+
+#[derive(Debug, Clone)]
 struct NameTreeRoot<'a> {
     /// Shall be an array of indirect references to the immediate children of this
     /// node. The children may be intermediate or leaf nodes.
@@ -66,25 +105,60 @@ struct NameTreeRoot<'a> {
     names: Option<BTreeMap<String, Object<'a>>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum NameTreeNode<'a> {
     Intermediate(NameTreeIntermediateNode<'a>),
     Leaf(NameTreeLeaf<'a>),
 }
 
-#[derive(Debug)]
+impl<'a> NameTreeNode<'a> {
+    fn collect_entries<'b>(&'b self, entries: &mut Vec<(&'b str, &'b Object<'a>)>) {
+        match self {
+            NameTreeNode::Intermediate(node) => {
+                for kid in &node.kids {
+                    kid.collect_entries(entries);
+                }
+            }
+            NameTreeNode::Leaf(leaf) => {
+                entries.extend(leaf.names.iter().map(|(key, value)| (key.as_str(), value)))
+            }
+        }
+    }
+}
+
+impl<'a> FromObj<'a> for NameTreeNode<'a> {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        let limit = Limit::from_arr(dict.expect_arr("Limits", resolver)?, resolver)?;
+
+        let node = match get_kids(&mut dict, resolver)? {
+            Some(kids) => NameTreeNode::Intermediate(NameTreeIntermediateNode { kids, limit }),
+            None => NameTreeNode::Leaf(NameTreeLeaf {
+                names: get_names(&mut dict, resolver)?.unwrap_or_default(),
+                limit,
+            }),
+        };
+
+        assert_empty(dict);
+
+        Ok(node)
+    }
+}
+
+#[derive(Debug, Clone)]
 struct NameTreeIntermediateNode<'a> {
     kids: Vec<Rc<NameTreeNode<'a>>>,
     limit: Limit,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct NameTreeLeaf<'a> {
     names: BTreeMap<String, Object<'a>>,
     limit: Limit,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Limit {
     max: String,
     min: String,
@@ -11,7 +11,7 @@ use crate::{
 /// A number tree is similar to a name tree, except that its keys shall be integers instead of
 /// strings and shall be sorted in ascending numerical order. The entries in the leaf (or root)
 /// nodes containing the key-value pairs shall be named Nums instead of Names as in a name tree
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NumberTree<'a> {
     root: NumberTreeRoot<'a>,
 }
@@ -34,21 +34,53 @@ fn get_nums<'a>(
         .transpose()
 }
 
+fn get_kids<'a>(
+    dict: &mut Dictionary<'a>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Option<Vec<Rc<NumberTreeNode<'a>>>>> {
+    dict.get_arr("Kids", resolver)?
+        .map(|kids| {
+            kids.into_iter()
+                .map(|kid| Ok(Rc::new(NumberTreeNode::from_obj(kid, resolver)?)))
+                .collect::<PdfResult<Vec<Rc<NumberTreeNode>>>>()
+        })
+        .transpose()
+}
+
 impl<'a> FromObj<'a> for NumberTree<'a> {
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
         let mut dict = resolver.assert_dict(obj)?;
 
         let nums = get_nums(&mut dict, resolver)?;
+        let kids = get_kids(&mut dict, resolver)?;
 
         assert_empty(dict);
 
         Ok(Self {
-            root: NumberTreeRoot { nums, kids: None },
+            root: NumberTreeRoot { nums, kids },
         })
     }
 }
 
-#[derive(Debug)]
+impl<'a> NumberTree<'a> {
+    /// Every key of the tree and its associated object, in ascending order of
+    /// their keys
+    pub fn entries(&self) -> Vec<(i32, &Object<'a>)> {
+        let mut entries = Vec::new();
+
+        if let Some(nums) = &self.root.nums {
+            entries.extend(nums.iter().map(|(&key, value)| (key, value)));
+        }
+
+        for kid in self.root.kids.iter().flatten() {
+            kid.collect_entries(&mut entries);
+        }
+
+        entries
+    }
+}
+
+#[derive(Debug, Clone)]
 struct NumberTreeRoot<'a> {
     /// Shall be an array of indirect references to the immediate children of this
     /// node. The children may be intermediate or leaf nodes.
@@ -65,25 +97,60 @@ struct NumberTreeRoot<'a> {
     nums: Option<BTreeMap<i32, Object<'a>>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum NumberTreeNode<'a> {
     Intermediate(NumberTreeIntermediateNode<'a>),
     Leaf(NumberTreeLeaf<'a>),
 }
 
-#[derive(Debug)]
+impl<'a> NumberTreeNode<'a> {
+    fn collect_entries<'b>(&'b self, entries: &mut Vec<(i32, &'b Object<'a>)>) {
+        match self {
+            NumberTreeNode::Intermediate(node) => {
+                for kid in &node.kids {
+                    kid.collect_entries(entries);
+                }
+            }
+            NumberTreeNode::Leaf(leaf) => {
+                entries.extend(leaf.nums.iter().map(|(&key, value)| (key, value)))
+            }
+        }
+    }
+}
+
+impl<'a> FromObj<'a> for NumberTreeNode<'a> {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        let limit = Limit::from_arr(dict.expect_arr("Limits", resolver)?, resolver)?;
+
+        let node = match get_kids(&mut dict, resolver)? {
+            Some(kids) => NumberTreeNode::Intermediate(NumberTreeIntermediateNode { kids, limit }),
+            None => NumberTreeNode::Leaf(NumberTreeLeaf {
+                nums: get_nums(&mut dict, resolver)?.unwrap_or_default(),
+                limit,
+            }),
+        };
+
+        assert_empty(dict);
+
+        Ok(node)
+    }
+}
+
+#[derive(Debug, Clone)]
 struct NumberTreeIntermediateNode<'a> {
     kids: Vec<Rc<NumberTreeNode<'a>>>,
     limit: Limit,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct NumberTreeLeaf<'a> {
     nums: BTreeMap<i32, Object<'a>>,
     limit: Limit,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Limit {
     max: i32,
     min: i32,
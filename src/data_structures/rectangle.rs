@@ -1,12 +1,12 @@
 use crate::{
     catalog::assert_len,
     error::PdfResult,
-    geometry::{path_builder::PathBuilder, Path},
-    objects::Object,
+    geometry::{path_builder::PathBuilder, Path, Point},
+    objects::{Object, ToObj},
     FromObj, Resolve,
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Rectangle {
     lower_left_x: f32,
     lower_left_y: f32,
@@ -34,7 +34,74 @@ impl<'a> FromObj<'a> for Rectangle {
     }
 }
 
+impl<'a> ToObj<'a> for Rectangle {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        [
+            self.lower_left_x,
+            self.lower_left_y,
+            self.upper_right_x,
+            self.upper_right_y,
+        ]
+        .to_obj()
+    }
+}
+
 impl Rectangle {
+    pub const fn new(
+        lower_left_x: f32,
+        lower_left_y: f32,
+        upper_right_x: f32,
+        upper_right_y: f32,
+    ) -> Self {
+        Self {
+            lower_left_x,
+            lower_left_y,
+            upper_right_x,
+            upper_right_y,
+        }
+    }
+
+    /// The four corners of the rectangle, counterclockwise from the lower left
+    pub fn corners(&self) -> [Point; 4] {
+        [
+            Point::new(self.lower_left_x, self.lower_left_y),
+            Point::new(self.upper_right_x, self.lower_left_y),
+            Point::new(self.upper_right_x, self.upper_right_y),
+            Point::new(self.lower_left_x, self.upper_right_y),
+        ]
+    }
+
+    /// Whether `other` lies entirely within this rectangle, whichever of
+    /// their corners each is given by
+    pub fn contains(&self, other: &Rectangle) -> bool {
+        let (outer, inner) = (self.normalized(), other.normalized());
+
+        outer.lower_left_x <= inner.lower_left_x
+            && outer.lower_left_y <= inner.lower_left_y
+            && inner.upper_right_x <= outer.upper_right_x
+            && inner.upper_right_y <= outer.upper_right_y
+    }
+
+    /// Whether the two rectangles share any area
+    pub fn intersects(&self, other: &Rectangle) -> bool {
+        let (a, b) = (self.normalized(), other.normalized());
+
+        a.lower_left_x < b.upper_right_x
+            && b.lower_left_x < a.upper_right_x
+            && a.lower_left_y < b.upper_right_y
+            && b.lower_left_y < a.upper_right_y
+    }
+
+    /// The same rectangle, given by its lower left and upper right corners
+    fn normalized(&self) -> Self {
+        Self::new(
+            self.lower_left_x.min(self.upper_right_x),
+            self.lower_left_y.min(self.upper_right_y),
+            self.lower_left_x.max(self.upper_right_x),
+            self.lower_left_y.max(self.upper_right_y),
+        )
+    }
+
     pub fn width(&self) -> f32 {
         self.upper_right_x - self.lower_left_x
     }
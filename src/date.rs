@@ -112,6 +112,126 @@ impl Date {
 
         Ok(date)
     }
+
+    /// The current time, in UTC
+    pub(crate) fn now() -> Self {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        Self::from_unix_time(seconds)
+    }
+
+    /// The time `seconds` after the Unix epoch, in UTC
+    pub(crate) fn from_unix_time(seconds: u64) -> Self {
+        let days = (seconds / 86_400) as i64;
+        let time = seconds % 86_400;
+
+        // the civil date of a count of days, from Howard Hinnant's
+        // `civil_from_days`, counting years from March so that leap days fall
+        // at the end of each year
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        Date {
+            year: Some(year as u16),
+            month: Some(month as u16),
+            day: Some(day as u16),
+            hour: Some((time / 3600) as u16),
+            minute: Some((time / 60 % 60) as u16),
+            second: Some((time % 60) as u16),
+            ut_relationship: Some(UtRelationship::Equal),
+            ut_hour_offset: None,
+            ut_minute_offset: None,
+        }
+    }
+
+    /// The date as it would be written, of the form `D:YYYYMMDDHHmmSSOHH'mm'`
+    ///
+    /// Components after the first which is missing are omitted.
+    pub fn to_pdf_string(&self) -> String {
+        let mut s = String::from("D:");
+
+        let units = [
+            (self.year, 4),
+            (self.month, 2),
+            (self.day, 2),
+            (self.hour, 2),
+            (self.minute, 2),
+            (self.second, 2),
+        ];
+
+        for (unit, len) in units {
+            match unit {
+                Some(unit) => s.push_str(&format!("{:0len$}", unit, len = len)),
+                None => return s,
+            }
+        }
+
+        match &self.ut_relationship {
+            Some(ut_relationship) => s.push(ut_relationship.as_byte() as char),
+            None => return s,
+        }
+
+        if let Some(hour) = self.ut_hour_offset {
+            s.push_str(&format!("{:02}'", hour));
+
+            if let Some(minute) = self.ut_minute_offset {
+                s.push_str(&format!("{:02}'", minute));
+            }
+        }
+
+        s
+    }
+
+    /// The date in the ISO 8601 form used by XMP, `YYYY-MM-DDThh:mm:ssTZD`
+    ///
+    /// As with [`Date::to_pdf_string`], components after the first which is
+    /// missing are omitted.
+    pub(crate) fn to_xmp_string(&self) -> String {
+        let mut s = match self.year {
+            Some(year) => format!("{:04}", year),
+            None => return String::new(),
+        };
+
+        for unit in [self.month, self.day] {
+            match unit {
+                Some(unit) => s.push_str(&format!("-{:02}", unit)),
+                None => return s,
+            }
+        }
+
+        // a time shall have at least hours and minutes
+        match self.hour {
+            Some(hour) => s.push_str(&format!("T{:02}:{:02}", hour, self.minute.unwrap_or(0))),
+            None => return s,
+        }
+
+        if let (Some(_), Some(second)) = (self.minute, self.second) {
+            s.push_str(&format!(":{:02}", second));
+        }
+
+        match &self.ut_relationship {
+            Some(UtRelationship::Equal) => s.push('Z'),
+            Some(ut_relationship) => s.push_str(&format!(
+                "{}{:02}:{:02}",
+                ut_relationship.as_byte() as char,
+                self.ut_hour_offset.unwrap_or(0),
+                self.ut_minute_offset.unwrap_or(0)
+            )),
+            None => {}
+        }
+
+        s
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -132,6 +252,14 @@ impl UtRelationship {
             }
         })
     }
+
+    pub fn as_byte(&self) -> u8 {
+        match self {
+            Self::Plus => b'+',
+            Self::Minus => b'-',
+            Self::Equal => b'Z',
+        }
+    }
 }
 
 #[cfg(test)]
@@ -175,6 +303,29 @@ mod test {
         )
     }
 
+    #[test]
+    fn write_date() {
+        for s in [
+            "D:20201203184827-08'00'",
+            "D:20201125021108Z",
+            "D:2008061116",
+        ] {
+            assert_eq!(Date::from_str(s).unwrap().to_pdf_string(), s);
+        }
+    }
+
+    #[test]
+    fn write_xmp_date() {
+        for (pdf, xmp) in [
+            ("D:20201203184827-08'00'", "2020-12-03T18:48:27-08:00"),
+            ("D:20201125021108Z", "2020-11-25T02:11:08Z"),
+            ("D:2008061116", "2008-06-11T16:00"),
+            ("D:200806", "2008-06"),
+        ] {
+            assert_eq!(Date::from_str(pdf).unwrap().to_xmp_string(), xmp);
+        }
+    }
+
     #[test]
     fn no_ut_set() {
         assert_eq!(
@@ -192,4 +343,17 @@ mod test {
             Date::from_str("D:20080611165603").unwrap()
         )
     }
+
+    #[test]
+    fn from_unix_time() {
+        assert_eq!(Date::from_unix_time(0).to_pdf_string(), "D:19700101000000Z");
+        assert_eq!(
+            Date::from_unix_time(951_782_400).to_pdf_string(),
+            "D:20000229000000Z"
+        );
+        assert_eq!(
+            Date::from_unix_time(1_792_108_799).to_pdf_string(),
+            "D:20261015235959Z"
+        );
+    }
 }
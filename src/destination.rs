@@ -1,9 +1,10 @@
 use crate::{
     assert_reference,
     catalog::assert_len,
+    data_structures::Rectangle,
     error::{ParseError, PdfResult},
     objects::{Object, ObjectType, Reference},
-    FromObj, Resolve,
+    FromObj, Parser, Resolve,
 };
 
 /// A destination defines a particular view of a document, consisting of the following items:
@@ -37,7 +38,7 @@ impl<'a> FromObj<'a> for Destination {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExplicitDestination {
     kind: DestinationKind,
     page_ref: Reference,
@@ -117,9 +118,62 @@ impl ExplicitDestination {
 
         Ok(ExplicitDestination { kind, page_ref })
     }
+
+    /// The page object of the page to be displayed
+    pub(crate) fn page_ref(&self) -> Reference {
+        self.page_ref
+    }
+
+    /// The vertical coordinate that shall be positioned at the top edge of the
+    /// window, if the destination specifies one
+    pub(crate) fn top(&self) -> Option<f32> {
+        match self.kind {
+            DestinationKind::Xyz { top, .. }
+            | DestinationKind::FitH { top }
+            | DestinationKind::FitR { top, .. }
+            | DestinationKind::FitBh { top } => top,
+            DestinationKind::Fit
+            | DestinationKind::FitV { .. }
+            | DestinationKind::FitB
+            | DestinationKind::FitBv { .. } => None,
+        }
+    }
+
+    /// The region of the page, in default user space, that shall be displayed,
+    /// where `page` is the crop box of the page
+    ///
+    /// Coordinates which retain their current value are taken from the page,
+    /// as is the bounding box of the page's contents.
+    pub(crate) fn view(&self, page: Rectangle) -> Rectangle {
+        let [lower_left, _, upper_right, _] = page.corners();
+
+        let (left, bottom, right, top) = match self.kind {
+            DestinationKind::Xyz { left, top, .. } => (left, None, None, top),
+            DestinationKind::Fit | DestinationKind::FitB => (None, None, None, None),
+            DestinationKind::FitH { top } | DestinationKind::FitBh { top } => {
+                (None, None, None, top)
+            }
+            DestinationKind::FitV { left } | DestinationKind::FitBv { left } => {
+                (left, None, None, None)
+            }
+            DestinationKind::FitR {
+                left,
+                bottom,
+                right,
+                top,
+            } => (left, bottom, right, top),
+        };
+
+        Rectangle::new(
+            left.unwrap_or(lower_left.x),
+            bottom.unwrap_or(lower_left.y),
+            right.unwrap_or(upper_right.x),
+            top.unwrap_or(upper_right.y),
+        )
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum DestinationKind {
     /// Display the page designated by page, with the coordinates (left, top) positioned
     /// at the upper-left corner of the window and the contents of the page magnified by
@@ -180,3 +234,108 @@ enum DestinationKind {
     /// current value of that parameter shall be retained unchanged.
     FitBv { left: Option<f32> },
 }
+
+impl<'a> Parser<'a> {
+    /// The page object and top coordinate of a destination, looking up named
+    /// destinations in the Dests name tree and the Dests dictionary of the
+    /// catalog
+    ///
+    /// Returns `None` if a named destination is not defined.
+    pub(crate) fn resolve_destination(
+        &mut self,
+        dest: &Destination,
+    ) -> PdfResult<Option<(Reference, Option<f32>)>> {
+        Ok(self
+            .resolve_explicit_destination(dest)?
+            .map(|dest| (dest.page_ref(), dest.top())))
+    }
+
+    /// The explicit destination to which a destination refers, or `None` if it
+    /// is a named destination which is not defined
+    pub(crate) fn resolve_explicit_destination(
+        &mut self,
+        dest: &Destination,
+    ) -> PdfResult<Option<ExplicitDestination>> {
+        let name = match dest {
+            Destination::Explicit(dest) => return Ok(Some(dest.clone())),
+            Destination::Named(name) => name,
+        };
+
+        let mut obj = match &self.catalog.names {
+            Some(names) => names
+                .get_ref(&mut self.lexer)?
+                .dests
+                .as_ref()
+                .and_then(|dests| dests.get(name))
+                .cloned(),
+            None => None,
+        };
+
+        if obj.is_none() {
+            if let Some(dests) = self.catalog.dests {
+                let mut dests = self.lexer.assert_dict(Object::Reference(dests))?;
+
+                obj = dests.get_object(name, &mut self.lexer)?;
+            }
+        }
+
+        let obj = match obj {
+            Some(obj) => self.lexer.resolve(obj)?,
+            None => return Ok(None),
+        };
+
+        // The value may instead be a dictionary whose D entry is the destination
+        let obj = match obj {
+            Object::Dictionary(mut dict) => dict.expect_object("D", &mut self.lexer)?,
+            obj => obj,
+        };
+
+        Ok(match Destination::from_obj(obj, &mut self.lexer)? {
+            Destination::Explicit(dest) => Some(dest),
+            Destination::Named(..) => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        data_structures::Rectangle,
+        objects::{Object, Reference},
+        version::PdfVersion,
+        writer::PdfWriter,
+    };
+
+    use super::ExplicitDestination;
+
+    fn destination(kind: &str, mut values: Vec<Object<'static>>) -> ExplicitDestination {
+        let page = Reference {
+            object_number: 3,
+            generation: 0,
+        };
+
+        let mut arr = vec![Object::Reference(page), Object::Name(kind.to_owned())];
+        arr.append(&mut values);
+
+        ExplicitDestination::from_arr(arr, &mut PdfWriter::new(PdfVersion::V1_7)).unwrap()
+    }
+
+    #[test]
+    fn destination_views() {
+        let page = Rectangle::new(0.0, 0.0, 612.0, 792.0);
+
+        let dest = destination("Fit", Vec::new());
+        assert_eq!(dest.view(page), page);
+
+        let values = vec![Object::Integer(72), Object::Real(500.0), Object::Null];
+        let dest = destination("XYZ", values);
+        assert_eq!(dest.view(page), Rectangle::new(72.0, 0.0, 612.0, 500.0));
+
+        let dest = destination("FitH", vec![Object::Null]);
+        assert_eq!(dest.view(page), page);
+
+        let values = [10, 20, 300, 400].map(Object::Integer).to_vec();
+        let dest = destination("FitR", values);
+        assert_eq!(dest.view(page), Rectangle::new(10.0, 20.0, 300.0, 400.0));
+    }
+}
@@ -1,22 +1,24 @@
 #![allow(warnings)]
 
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, fmt};
 
 use aes::cipher::{
     block_padding::{NoPadding, Pkcs7},
     generic_array::GenericArray,
-    BlockDecrypt, BlockDecryptMut, KeyInit, KeyIvInit,
+    BlockDecrypt, BlockDecryptMut, BlockEncrypt, BlockEncryptMut, KeyInit, KeyIvInit,
 };
+use sha2::{Digest, Sha256, Sha384, Sha512};
 
 use crate::{
     file_specification::FileIdentifier,
+    filter::FilterKind,
     objects::{Dictionary, Name, Object, Reference},
     resolve::Resolve,
-    stream::Stream,
-    FromObj, PdfResult,
+    stream::{Stream, StreamDict},
+    FromObj, Parser, PdfResult, ToObj,
 };
 
-#[derive(Debug, Clone, FromObj)]
+#[derive(Debug, Clone, FromObj, ToObj)]
 pub struct Encryption<'a> {
     /// The name of the preferred security handler for this document. It shall
     /// be the name of the security handler that was used to encrypt the
@@ -56,8 +58,8 @@ pub struct Encryption<'a> {
     /// The conforming reader shall ignore entries in CF dictionary with the
     /// keys equal to those listed in Table 26 and use properties of the
     /// respective standard crypt filters.
-    #[field("CF")]
-    crypt_filters: Option<HashMap<String, CryptFilter>>,
+    #[field("CF", default = CryptFilterMap::default())]
+    crypt_filters: CryptFilterMap,
 
     /// The name of the crypt filter that shall be used by default when decrypting
     /// streams
@@ -92,8 +94,8 @@ pub struct Encryption<'a> {
     /// If this entry is not present, and the embedded file stream does not
     /// contain a crypt filter specifier, the stream shall be encrypted
     /// using the default stream crypt filter specified by StmF.
-    #[field("EFF", default = Name("Identity".to_owned()))]
-    embedded_file_filter: Name,
+    #[field("EFF")]
+    embedded_file_filter: Option<Name>,
 
     // todo: below fields should only be in standard security handler, not all
     /// A number specifying which revision of the standard security handler shall
@@ -109,24 +111,49 @@ pub struct Encryption<'a> {
     /// to 0
     ///
     /// 4 => if the document is encrypted with a V value of 4
+    ///
+    /// 6 => if the document is encrypted with a V value of 5 (PDF 2.0)
     #[field("R")]
     revision_number: i32,
 
     /// A 32-byte string, based on both the owner and user passwords, that shall
     /// be used in computing the encryption key and in determining whether
     /// a valid owner password was entered
+    ///
+    /// For revision 6, a 48-byte string consisting of a hash of the owner
+    /// password, followed by an 8-byte validation salt and an 8-byte key salt
     #[field("O")]
     owner: String,
 
-    /// A set of flags specifying which operations shall be permitted when the
-    /// document is opened with user access
+    /// A 32-byte string, based on the user password, that shall be used in
+    /// determining whether to prompt the user for a password and, if so,
+    /// whether a valid user or owner password was entered
+    ///
+    /// For revision 6, a 48-byte string consisting of a hash of the user
+    /// password, followed by an 8-byte validation salt and an 8-byte key salt
     #[field("U")]
     user: String,
 
+    /// (Required if R is 6) A 32-byte string, based on the owner and user
+    /// passwords, that shall be used in computing the file encryption key
+    #[field("OE")]
+    owner_encryption_key: Option<String>,
+
+    /// (Required if R is 6) A 32-byte string, based on the user password, that
+    /// shall be used in computing the file encryption key
+    #[field("UE")]
+    user_encryption_key: Option<String>,
+
+    /// (Required if R is 6) A 16-byte string, encrypted with the file
+    /// encryption key, that contains an encrypted copy of the permissions
+    /// flags
+    #[field("Perms")]
+    perms: Option<String>,
+
     /// A set of flags specifying which operations shall be permitted when the
     /// document is opened with user access
     #[field("P")]
-    user_permission_flags: UserAccessPermissions,
+    user_permission_flags: Permissions,
 
     /// Indicates whether the document-level metadata stream shall be encrypted
     #[field("EncryptMetadata", default = true)]
@@ -158,9 +185,57 @@ pub enum EncryptionAlgorithm {
     /// document, using the rules specified by the CF, StmF, and StrF
     /// entries.
     BasedOnOtherEntries = 4,
+
+    /// (PDF 2.0) The security handler defines the use of encryption and
+    /// decryption in the document, using the rules specified by the CF, StmF,
+    /// StrF and EFF entries, with a 256-bit file encryption key
+    Aes256 = 5,
+}
+
+/// The crypt filters of a document, from the CF entry of its encryption
+/// dictionary, by name
+#[derive(Debug, Clone, Default)]
+pub struct CryptFilterMap(HashMap<String, CryptFilter>);
+
+impl<'a> FromObj<'a> for CryptFilterMap {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        Ok(Self(HashMap::from_obj(obj, resolver)?))
+    }
 }
 
-#[derive(Debug, Clone, FromObj)]
+impl<'a> ToObj<'a> for CryptFilterMap {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        self.0.to_obj()
+    }
+}
+
+impl CryptFilterMap {
+    /// The crypt filter with the given name
+    ///
+    /// The standard Identity filter cannot be redefined, so entries with that
+    /// name are ignored.
+    pub fn get(&self, name: &str) -> Option<&CryptFilter> {
+        if name == "Identity" {
+            return None;
+        }
+
+        self.0.get(name)
+    }
+
+    /// The cipher applied by the crypt filter with the given name
+    fn cipher(&self, name: &str) -> PdfResult<Cipher> {
+        if name == "Identity" {
+            return Ok(Cipher::Identity);
+        }
+
+        match self.get(name) {
+            Some(filter) => Ok(filter.cipher()),
+            None => anyhow::bail!("undefined crypt filter {:?}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromObj, ToObj)]
 #[obj_type("CryptFilter")]
 pub struct CryptFilter {
     /// The method used, if any, by the conforming reader to decrypt data.
@@ -189,9 +264,34 @@ pub struct CryptFilter {
     length: Option<i32>,
 }
 
+impl CryptFilter {
+    pub fn method(&self) -> CryptFilterMethod {
+        self.crypt_filter_method
+    }
+
+    pub fn auth_event(&self) -> AuthEvent {
+        self.auth_event.unwrap_or_default()
+    }
+
+    pub fn length(&self) -> Option<i32> {
+        self.length
+    }
+
+    fn cipher(&self) -> Cipher {
+        match self.crypt_filter_method {
+            CryptFilterMethod::V2 => Cipher::Rc4,
+            CryptFilterMethod::AesV2 => Cipher::AesV2,
+            CryptFilterMethod::AesV3 => Cipher::AesV3,
+            // the standard security handler has no decryption of its own
+            // beyond that of the other methods
+            CryptFilterMethod::None => Cipher::Identity,
+        }
+    }
+}
+
 #[pdf_enum]
 #[derive(Default)]
-enum AuthEvent {
+pub enum AuthEvent {
     /// Authorization shall be required when a document is opened
     #[default]
     DocOpen = "DocOpen",
@@ -202,7 +302,7 @@ enum AuthEvent {
 
 #[pdf_enum]
 #[derive(Default)]
-enum CryptFilterMethod {
+pub enum CryptFilterMethod {
     /// The application shall not decrypt data but shall direct the input stream
     /// to the security handler for decryption.
     #[default]
@@ -220,118 +320,1605 @@ enum CryptFilterMethod {
     /// initialization vector that shall be randomly generated and placed
     /// as the first 16 bytes in the stream or string.
     AesV2 = "AESV2",
+
+    /// (PDF 2.0) The application shall ask the security handler for the file
+    /// encryption key and shall implicitly decrypt data with "Algorithm 1.A:
+    /// Encryption of data using the AES algorithms", using the AES-256
+    /// algorithm in Cipher Block Chaining (CBC) with padding mode with a
+    /// 16-byte block size and an initialization vector that is randomly
+    /// generated and placed as the first 16 bytes in the stream or string.
+    AesV3 = "AESV3",
 }
 
-#[derive(Debug, Copy, Clone)]
-struct UserAccessPermissions(i32);
+/// The operations permitted on an encrypted document, from the P entry of its
+/// encryption dictionary
+///
+/// Bit positions are numbered from 1, the low-order bit, as in the PDF
+/// specification. Conforming readers are expected to respect these, but
+/// nothing about the encryption itself enforces them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Permissions(i32);
 
-impl<'a> FromObj<'a> for UserAccessPermissions {
+impl Permissions {
+    const PRINT: i32 = 1 << 2;
+    const MODIFY: i32 = 1 << 3;
+    const COPY: i32 = 1 << 4;
+    const ANNOTATE: i32 = 1 << 5;
+    const FILL_FORMS: i32 = 1 << 8;
+    const EXTRACT_FOR_ACCESSIBILITY: i32 = 1 << 9;
+    const ASSEMBLE: i32 = 1 << 10;
+    const PRINT_HIGH_RESOLUTION: i32 = 1 << 11;
+
+    /// Every operation permitted, as for unencrypted documents and documents
+    /// opened with the owner password
+    pub fn all() -> Self {
+        Self(-1)
+    }
+
+    pub fn from_integer(i: i32) -> Self {
+        Self(i)
+    }
+
+    /// The P entry, as a signed 32-bit integer
+    pub fn bits(&self) -> i32 {
+        self.0
+    }
+
+    /// (Bit 3) Print the document, possibly at a degraded level of quality if
+    /// bit 12 is clear
+    pub fn can_print(&self) -> bool {
+        self.0 & Self::PRINT != 0
+    }
+
+    /// (Bit 4) Modify the contents of the document by operations other than
+    /// those controlled by bits 6, 9, and 11
+    pub fn can_modify(&self) -> bool {
+        self.0 & Self::MODIFY != 0
+    }
+
+    /// (Bit 5) Copy or otherwise extract text and graphics from the document
+    pub fn can_copy(&self) -> bool {
+        self.0 & Self::COPY != 0
+    }
+
+    /// (Bit 6) Add or modify text annotations, fill in interactive form
+    /// fields, and, if bit 4 is also set, create or modify interactive form
+    /// fields
+    pub fn can_annotate(&self) -> bool {
+        self.0 & Self::ANNOTATE != 0
+    }
+
+    /// (Bit 9) Fill in existing interactive form fields, including signature
+    /// fields, even if bit 6 is clear
+    pub fn can_fill_forms(&self) -> bool {
+        self.0 & (Self::FILL_FORMS | Self::ANNOTATE) != 0
+    }
+
+    /// (Bit 10) Extract text and graphics in support of accessibility to users
+    /// with disabilities or for other purposes
+    pub fn can_extract_for_accessibility(&self) -> bool {
+        self.0 & Self::EXTRACT_FOR_ACCESSIBILITY != 0
+    }
+
+    /// (Bit 11) Assemble the document, by inserting, rotating, or deleting
+    /// pages and creating document outline items or thumbnail images, even if
+    /// bit 4 is clear
+    pub fn can_assemble(&self) -> bool {
+        self.0 & (Self::ASSEMBLE | Self::MODIFY) != 0
+    }
+
+    /// (Bit 12) Print the document to a representation from which a faithful
+    /// digital copy of the PDF content could be generated
+    ///
+    /// When this bit is clear and bit 3 is set, printing is limited to a
+    /// low-level representation of the appearance, possibly of degraded
+    /// quality.
+    pub fn can_print_high_resolution(&self) -> bool {
+        self.can_print() && self.0 & Self::PRINT_HIGH_RESOLUTION != 0
+    }
+
+    /// Security handlers of revision 2 do not use bits 9 to 12, and instead
+    /// grant the operations they control along with those of bits 3 to 6
+    fn for_revision_2(self) -> Self {
+        let mut bits = self.0;
+
+        for (from, to) in [
+            (Self::PRINT, Self::PRINT_HIGH_RESOLUTION),
+            (Self::MODIFY, Self::ASSEMBLE),
+            (Self::COPY, Self::EXTRACT_FOR_ACCESSIBILITY),
+            (Self::ANNOTATE, Self::FILL_FORMS),
+        ] {
+            bits &= !to;
+
+            if self.0 & from != 0 {
+                bits |= to;
+            }
+        }
+
+        Self(bits)
+    }
+}
+
+impl<'a> FromObj<'a> for Permissions {
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
         Ok(Self(resolver.assert_integer(obj)?))
     }
 }
 
+impl<'a> ToObj<'a> for Permissions {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(Object::Integer(self.0))
+    }
+}
+
+/// How a document is encrypted with the standard security handler when it is
+/// written
+#[derive(Debug, Clone)]
+pub struct EncryptionOptions {
+    /// The password required to open the document, which may be empty
+    pub user_password: String,
+
+    /// The password which grants full access to the document, regardless of
+    /// `permissions`
+    ///
+    /// If this is empty, the user password is used in its place.
+    pub owner_password: String,
+
+    /// The operations permitted when the document is opened with the user
+    /// password
+    pub permissions: Permissions,
+
+    /// The cipher applied to strings and streams: V2 for 128-bit RC4, AESV2
+    /// for AES-128, or AESV3 for AES-256
+    pub method: CryptFilterMethod,
+}
+
+/// Which of the passwords of an encrypted document was supplied
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PasswordKind {
+    /// The user password, which grants the access given by the document's
+    /// permissions
+    User,
+
+    /// The owner password, which grants full access to the document
+    Owner,
+}
+
 const PADDING: [u8; 32] = [
     0x28, 0xBF, 0x4E, 0x5E, 0x4E, 0x75, 0x8A, 0x41, 0x64, 0x00, 0x4E, 0x56, 0xFF, 0xFA, 0x01, 0x08,
     0x2E, 0x2E, 0x00, 0xB6, 0xD0, 0x68, 0x3E, 0x80, 0x2F, 0x0C, 0xA9, 0xFE, 0x64, 0x53, 0x69, 0x7A,
 ];
 
 /// Manages encryption for entire document
+#[derive(Clone)]
 pub struct SecurityHandler<'a> {
     encryption: Encryption<'a>,
     file_identifier: FileIdentifier,
+
+    /// The file encryption key, computed from the password the document was
+    /// opened with
+    key: Vec<u8>,
+
+    /// Whether the document was opened with the user or the owner password
+    password_kind: PasswordKind,
+}
+
+// the file encryption key is left out, so that it does not end up in logs
+impl fmt::Debug for SecurityHandler<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecurityHandler")
+            .field("encryption", &self.encryption)
+            .field("file_identifier", &self.file_identifier)
+            .field("password_kind", &self.password_kind)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The cipher applied to strings or streams
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cipher {
+    Identity,
+    Rc4,
+    AesV2,
+    AesV3,
+}
+
+/// Whether objects are being encrypted, for writing, or decrypted, after
+/// reading
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Encrypt,
+    Decrypt,
 }
 
 impl<'a> SecurityHandler<'a> {
-    pub fn new(encryption: Encryption<'a>, file_identifier: FileIdentifier) -> Self {
-        assert_eq!(encryption.length % 8, 0);
+    /// Authenticate `password` as either the user or the owner password of the
+    /// document, computing the file encryption key
+    ///
+    /// The empty password is the user password of documents which may be
+    /// opened without one.
+    pub fn new(
+        encryption: Encryption<'a>,
+        file_identifier: FileIdentifier,
+        password: &str,
+    ) -> PdfResult<Self> {
+        if encryption.filter.0 != "Standard" {
+            anyhow::bail!("unsupported security handler {:?}", encryption.filter.0);
+        }
 
-        Self {
+        let mut handler = Self {
             encryption,
             file_identifier,
+            key: Vec::new(),
+            password_kind: PasswordKind::User,
+        };
+
+        handler.authenticate(password)?;
+
+        Ok(handler)
+    }
+
+    /// A handler which encrypts a document with the given identifier as
+    /// described by `options`
+    ///
+    /// The entries of the encryption dictionary are computed from the
+    /// passwords, and the handler is then opened with the owner password as a
+    /// reader would be.
+    pub(crate) fn for_writing(
+        options: &EncryptionOptions,
+        file_identifier: FileIdentifier,
+    ) -> PdfResult<Self> {
+        let (v, revision_number, length) = match options.method {
+            CryptFilterMethod::V2 => (EncryptionAlgorithm::Rc4OrAesGt40Bits, 3, 128),
+            CryptFilterMethod::AesV2 => (EncryptionAlgorithm::BasedOnOtherEntries, 4, 128),
+            CryptFilterMethod::AesV3 => (EncryptionAlgorithm::Aes256, 6, 256),
+            CryptFilterMethod::None => {
+                anyhow::bail!("documents cannot be encrypted without a cipher")
+            }
+        };
+
+        let mut crypt_filters = CryptFilterMap::default();
+
+        let filter_name = if revision_number >= 4 {
+            crypt_filters.0.insert(
+                "StdCF".to_owned(),
+                CryptFilter {
+                    crypt_filter_method: options.method,
+                    auth_event: Some(AuthEvent::DocOpen),
+                    length: Some(length / 8),
+                },
+            );
+
+            "StdCF"
+        } else {
+            "Identity"
+        };
+
+        // bits 1 and 2 of the permissions shall be 0
+        let permissions = Permissions(options.permissions.0 & !0b11);
+
+        let mut handler = Self {
+            encryption: Encryption {
+                filter: Name("Standard".to_owned()),
+                sub_filter: None,
+                v: Some(v),
+                length,
+                crypt_filters,
+                stream_filter: Name(filter_name.to_owned()),
+                string_filter: Name(filter_name.to_owned()),
+                embedded_file_filter: None,
+                revision_number,
+                owner: String::new(),
+                user: String::new(),
+                owner_encryption_key: None,
+                user_encryption_key: None,
+                perms: None,
+                user_permission_flags: permissions,
+                encrypt_metadata: true,
+                other: Dictionary::empty(),
+            },
+            file_identifier,
+            key: Vec::new(),
+            password_kind: PasswordKind::Owner,
+        };
+
+        let owner_password = if options.owner_password.is_empty() {
+            &options.user_password
+        } else {
+            &options.owner_password
+        };
+
+        if revision_number >= 5 {
+            handler.set_aes_256_passwords(&options.user_password, owner_password)?;
+        } else {
+            handler.set_rc4_passwords(&options.user_password, owner_password);
         }
+
+        handler.authenticate(owner_password)?;
+
+        Ok(handler)
     }
 
-    fn compute_encryption_key(&self, password: &[u8]) -> Vec<u8> {
-        let padded_password = if password.len() >= 32 {
-            password[..32].to_owned()
+    /// The encryption dictionary, to be referred to by the Encrypt entry of the
+    /// trailer
+    pub(crate) fn encryption_dictionary(&self) -> PdfResult<Object<'a>> {
+        self.encryption.to_obj()
+    }
+
+    /// Algorithms 3 and 5: Computing the O and U entries (revisions 3 and 4)
+    fn set_rc4_passwords(&mut self, user_password: &str, owner_password: &str) {
+        let user_password = string_bytes(user_password);
+
+        let mut hash = md5::compute(pad_password(&string_bytes(owner_password)));
+
+        for _ in 0..50 {
+            hash = md5::compute(hash.0);
+        }
+
+        let key = &hash[..self.key_length()];
+
+        let mut owner = pad_password(&user_password);
+
+        for i in 0..20_u8 {
+            let key = key.iter().map(|b| b ^ i).collect::<Vec<u8>>();
+            rc4(&key, &mut owner);
+        }
+
+        self.encryption.owner = bytes_string(&owner);
+
+        let key = self.compute_encryption_key(&user_password);
+
+        let mut hash = md5::Context::new();
+        hash.consume(PADDING);
+        hash.consume(string_bytes(&self.file_identifier.0[0]));
+
+        let mut user = hash.compute().0;
+
+        for i in 0..20_u8 {
+            let key = key.iter().map(|b| b ^ i).collect::<Vec<u8>>();
+            rc4(&key, &mut user);
+        }
+
+        // the remaining 16 bytes of U are arbitrary padding
+        self.encryption.user = bytes_string(&[&user[..], &PADDING[..16]].concat());
+    }
+
+    /// Algorithms 8 to 10: Computing the U, UE, O, OE and Perms entries
+    /// (revision 6)
+    fn set_aes_256_passwords(
+        &mut self,
+        user_password: &str,
+        owner_password: &str,
+    ) -> PdfResult<()> {
+        let user_password = &prepare_password(user_password)?;
+        let owner_password = &prepare_password(owner_password)?;
+
+        let key = random_bytes::<32>()?;
+
+        // each hash is followed by its validation salt and its key salt
+        let salts = random_bytes::<16>()?;
+        let user = [
+            &self.hash_r6(user_password, &salts[..8], &[])[..],
+            &salts[..],
+        ]
+        .concat();
+        let user_encryption_key =
+            aes_256_encrypt_key(&self.hash_r6(user_password, &salts[8..], &[]), &key);
+
+        let salts = random_bytes::<16>()?;
+        let owner = [
+            &self.hash_r6(owner_password, &salts[..8], &user)[..],
+            &salts[..],
+        ]
+        .concat();
+        let owner_encryption_key =
+            aes_256_encrypt_key(&self.hash_r6(owner_password, &salts[8..], &user), &key);
+
+        let encrypt_metadata = if self.encryption.encrypt_metadata {
+            b'T'
+        } else {
+            b'F'
+        };
+
+        let mut perms = [0; 16];
+        perms[..4].copy_from_slice(&self.encryption.user_permission_flags.0.to_le_bytes());
+        perms[4..8].copy_from_slice(&[0xFF; 4]);
+        perms[8] = encrypt_metadata;
+        perms[9..12].copy_from_slice(b"adb");
+        perms[12..].copy_from_slice(&random_bytes::<4>()?);
+
+        aes::Aes256::new_from_slice(&key)
+            .unwrap()
+            .encrypt_block(GenericArray::from_mut_slice(&mut perms));
+
+        self.encryption.user = bytes_string(&user);
+        self.encryption.user_encryption_key = Some(bytes_string(&user_encryption_key));
+        self.encryption.owner = bytes_string(&owner);
+        self.encryption.owner_encryption_key = Some(bytes_string(&owner_encryption_key));
+        self.encryption.perms = Some(bytes_string(&perms));
+
+        Ok(())
+    }
+
+    /// Authenticate `password` as either the user or the owner password,
+    /// replacing the password the document was opened with if it is correct
+    ///
+    /// Both passwords give the same file encryption key, so objects already
+    /// decrypted remain valid.
+    pub fn authenticate(&mut self, password: &str) -> PdfResult<PasswordKind> {
+        let (key, password_kind) = if self.encryption.revision_number >= 5 {
+            self.authenticate_aes_256(password)?
+        } else {
+            self.authenticate_rc4(password)?
+        };
+
+        self.key = key;
+        self.password_kind = password_kind;
+
+        Ok(password_kind)
+    }
+
+    /// Whether the document was opened with the user or the owner password
+    pub fn password_kind(&self) -> PasswordKind {
+        self.password_kind
+    }
+
+    /// Whether the document-level metadata stream is encrypted along with the
+    /// rest of the document
+    pub fn encrypts_metadata(&self) -> bool {
+        self.encryption.encrypt_metadata
+    }
+
+    /// The operations permitted by the P entry, regardless of the password the
+    /// document was opened with
+    pub fn permissions(&self) -> Permissions {
+        if self.encryption.revision_number == 2 {
+            self.encryption.user_permission_flags.for_revision_2()
+        } else {
+            self.encryption.user_permission_flags
+        }
+    }
+
+    /// Algorithms 6 and 7: Authenticating the user and owner passwords
+    /// (revision 4 and earlier)
+    ///
+    /// The password is tried as the user password first, then as the owner
+    /// password.
+    fn authenticate_rc4(&self, password: &str) -> PdfResult<(Vec<u8>, PasswordKind)> {
+        if self.encryption.length % 8 != 0 || !(40..=128).contains(&self.encryption.length) {
+            anyhow::bail!("invalid encryption key length {}", self.encryption.length);
+        }
+
+        // passwords are encoded in PDFDocEncoding, which is close enough to
+        // Latin-1
+        let password = &string_bytes(password);
+
+        if let Some(key) = self.authenticate_user_password(password) {
+            return Ok((key, PasswordKind::User));
+        }
+
+        let user_password = self.user_password_from_owner_password(password);
+
+        match self.authenticate_user_password(&user_password) {
+            Some(key) => Ok((key, PasswordKind::Owner)),
+            None => anyhow::bail!("incorrect password"),
+        }
+    }
+
+    /// Algorithm 2.A: Retrieving the file encryption key from an encrypted
+    /// document in order to decrypt it (revision 6 and later)
+    ///
+    /// The password is tried as the owner password first, then as the user
+    /// password.
+    fn authenticate_aes_256(&self, password: &str) -> PdfResult<(Vec<u8>, PasswordKind)> {
+        let password = &prepare_password(password)?;
+
+        let owner = string_bytes(&self.encryption.owner);
+        let user = string_bytes(&self.encryption.user);
+
+        if owner.len() < 48 || user.len() < 48 {
+            anyhow::bail!("O and U entries must be 48 bytes long for revision 6");
+        }
+
+        let (encrypted_key, intermediate_key, password_kind) =
+            if self.hash_r6(password, &owner[32..40], &user[..48]) == owner[..32] {
+                (
+                    &self.encryption.owner_encryption_key,
+                    self.hash_r6(password, &owner[40..48], &user[..48]),
+                    PasswordKind::Owner,
+                )
+            } else if self.hash_r6(password, &user[32..40], &[]) == user[..32] {
+                (
+                    &self.encryption.user_encryption_key,
+                    self.hash_r6(password, &user[40..48], &[]),
+                    PasswordKind::User,
+                )
+            } else {
+                anyhow::bail!("incorrect password");
+            };
+
+        let mut key = match encrypted_key.as_deref().map(string_bytes) {
+            Some(key) if key.len() == 32 => key,
+            _ => anyhow::bail!("OE and UE entries must be 32 bytes long for revision 6"),
+        };
+
+        cbc::Decryptor::<aes::Aes256>::new_from_slices(&intermediate_key, &[0; 16])
+            .unwrap()
+            .decrypt_padded_mut::<NoPadding>(&mut key)
+            .unwrap();
+
+        self.check_perms(&key)?;
+
+        Ok((key, password_kind))
+    }
+
+    /// Algorithm 2.B: Computing a hash (revision 6 and later)
+    ///
+    /// `user_key` is the U entry when computing hashes for the owner password,
+    /// and empty otherwise. Revision 5 uses only the initial SHA-256 hash.
+    fn hash_r6(&self, password: &[u8], salt: &[u8], user_key: &[u8]) -> Vec<u8> {
+        let mut k = Sha256::new()
+            .chain_update(password)
+            .chain_update(salt)
+            .chain_update(user_key)
+            .finalize()
+            .to_vec();
+
+        if self.encryption.revision_number == 5 {
+            return k;
+        }
+
+        let mut round = 0;
+
+        loop {
+            let k1 = [password, &k[..], user_key].concat().repeat(64);
+
+            let mut e = k1.clone();
+            let len = e.len();
+
+            cbc::Encryptor::<aes::Aes128>::new_from_slices(&k[..16], &k[16..32])
+                .unwrap()
+                .encrypt_padded_mut::<NoPadding>(&mut e, len)
+                .unwrap();
+
+            // the first 16 bytes of E, as a big-endian integer, modulo 3, which
+            // is equal to the sum of the bytes modulo 3
+            k = match e[..16].iter().map(|&b| u32::from(b)).sum::<u32>() % 3 {
+                0 => Sha256::digest(&e).to_vec(),
+                1 => Sha384::digest(&e).to_vec(),
+                _ => Sha512::digest(&e).to_vec(),
+            };
+
+            round += 1;
+
+            if round >= 64 && u32::from(*e.last().unwrap()) <= round - 32 {
+                break;
+            }
+        }
+
+        k.truncate(32);
+        k
+    }
+
+    /// Algorithm 13: Validating the permissions (revision 6 and later)
+    ///
+    /// The Perms entry holds the P entry and the EncryptMetadata flag,
+    /// encrypted with the file encryption key, which detects tampering with
+    /// the unencrypted copies.
+    fn check_perms(&self, key: &[u8]) -> PdfResult<()> {
+        let mut perms = match self.encryption.perms.as_deref().map(string_bytes) {
+            Some(perms) if perms.len() == 16 => perms,
+            _ => anyhow::bail!("Perms entry must be 16 bytes long for revision 6"),
+        };
+
+        aes::Aes256::new_from_slice(key)
+            .unwrap()
+            .decrypt_block(GenericArray::from_mut_slice(&mut perms));
+
+        let encrypt_metadata = if self.encryption.encrypt_metadata {
+            b'T'
         } else {
-            let mut b = password.to_owned();
-            b.extend_from_slice(&PADDING[..32 - password.len()]);
-            b
+            b'F'
         };
 
-        assert_eq!(padded_password.len(), 32);
+        if &perms[9..12] != b"adb"
+            || perms[..4] != self.encryption.user_permission_flags.0.to_le_bytes()
+            || perms[8] != encrypt_metadata
+        {
+            anyhow::bail!("Perms entry does not match the encryption dictionary");
+        }
 
+        Ok(())
+    }
+
+    /// The length of the file encryption key, in bytes
+    fn key_length(&self) -> usize {
+        if self.encryption.revision_number == 2 {
+            5
+        } else if self.encryption.v == Some(EncryptionAlgorithm::BasedOnOtherEntries) {
+            // the Length entry only applies to V values of 2 and 3; crypt
+            // filters use 128-bit keys
+            16
+        } else {
+            self.encryption.length as usize / 8
+        }
+    }
+
+    /// Algorithm 2: Computing an encryption key
+    fn compute_encryption_key(&self, password: &[u8]) -> Vec<u8> {
         let mut hash = md5::Context::new();
 
-        hash.consume(&padded_password);
-        hash.consume(&self.encryption.owner.as_bytes());
-        hash.consume(&self.encryption.user_permission_flags.0.to_le_bytes());
-        hash.consume(&self.file_identifier.0[0].as_bytes());
+        hash.consume(pad_password(password));
+        hash.consume(string_bytes(&self.encryption.owner));
+        hash.consume(self.encryption.user_permission_flags.0.to_le_bytes());
+        hash.consume(string_bytes(&self.file_identifier.0[0]));
 
         if self.encryption.revision_number >= 4 && !self.encryption.encrypt_metadata {
-            hash.consume(&[0xFF, 0xFF, 0xFF, 0xFF]);
+            hash.consume([0xFF, 0xFF, 0xFF, 0xFF]);
         }
 
         let mut hash = hash.compute();
 
+        let n = self.key_length();
+
         if self.encryption.revision_number >= 3 {
-            let n = self.encryption.length / 8;
             for _ in 0..50 {
-                hash = md5::compute(&hash[..n as usize]);
+                hash = md5::compute(&hash[..n]);
             }
         }
 
-        let n = if self.encryption.revision_number == 2 {
-            5
+        hash[..n].to_vec()
+    }
+
+    /// Algorithms 4 to 6: Computing the U value with the encryption key derived
+    /// from `password`, and comparing it to the U entry of the encryption
+    /// dictionary
+    ///
+    /// Returns the encryption key if the password is correct.
+    fn authenticate_user_password(&self, password: &[u8]) -> Option<Vec<u8>> {
+        let key = self.compute_encryption_key(password);
+        let expected = string_bytes(&self.encryption.user);
+
+        if self.encryption.revision_number == 2 {
+            let mut u = PADDING;
+            rc4(&key, &mut u);
+
+            return (expected == u).then_some(key);
+        }
+
+        let mut hash = md5::Context::new();
+        hash.consume(PADDING);
+        hash.consume(string_bytes(&self.file_identifier.0[0]));
+
+        let mut u = hash.compute().0;
+
+        for i in 0..20_u8 {
+            let key = key.iter().map(|b| b ^ i).collect::<Vec<u8>>();
+            rc4(&key, &mut u);
+        }
+
+        // only the first 16 bytes of U are significant; the rest is arbitrary
+        // padding
+        (expected.get(..16)? == u).then_some(key)
+    }
+
+    /// Algorithm 7: Recovering the user password from the O entry of the
+    /// encryption dictionary by treating `password` as the owner password
+    fn user_password_from_owner_password(&self, password: &[u8]) -> Vec<u8> {
+        let mut hash = md5::compute(pad_password(password));
+
+        let n = self.key_length();
+
+        if self.encryption.revision_number >= 3 {
+            for _ in 0..50 {
+                hash = md5::compute(hash.0);
+            }
+        }
+
+        let key = &hash[..n];
+
+        let mut user_password = string_bytes(&self.encryption.owner);
+
+        if self.encryption.revision_number == 2 {
+            rc4(key, &mut user_password);
         } else {
-            self.encryption.length / 8
+            for i in (0..20_u8).rev() {
+                let key = key.iter().map(|b| b ^ i).collect::<Vec<u8>>();
+                rc4(&key, &mut user_password);
+            }
+        }
+
+        user_password
+    }
+
+    /// The cipher used for strings or streams, as determined by the crypt filter
+    /// named by the StrF, StmF, or EFF entry for documents using crypt filters
+    fn cipher(&self, filter_name: &Name) -> PdfResult<Cipher> {
+        if !matches!(
+            self.encryption.v,
+            Some(EncryptionAlgorithm::BasedOnOtherEntries | EncryptionAlgorithm::Aes256)
+        ) {
+            return Ok(Cipher::Rc4);
+        }
+
+        self.encryption.crypt_filters.cipher(&filter_name.0)
+    }
+
+    /// The crypt filters of the document
+    pub fn crypt_filters(&self) -> &CryptFilterMap {
+        &self.encryption.crypt_filters
+    }
+
+    /// Algorithm 1: The key used to encrypt the strings and streams of the
+    /// object with the given reference
+    fn object_key(&self, reference: Reference, cipher: Cipher) -> Vec<u8> {
+        let mut key = self.key.clone();
+
+        key.extend_from_slice(&reference.object_number.to_le_bytes()[..3]);
+        key.extend_from_slice(&reference.generation.to_le_bytes()[..2]);
+
+        if cipher == Cipher::AesV2 {
+            key.extend_from_slice(b"sAlT");
+        }
+
+        let n = (self.key.len() + 5).min(16);
+
+        md5::compute(&key)[..n].to_vec()
+    }
+
+    fn decrypt(&self, data: Vec<u8>, reference: Reference, cipher: Cipher) -> PdfResult<Vec<u8>> {
+        self.transform(data, reference, cipher, Direction::Decrypt)
+    }
+
+    fn transform(
+        &self,
+        mut data: Vec<u8>,
+        reference: Reference,
+        cipher: Cipher,
+        direction: Direction,
+    ) -> PdfResult<Vec<u8>> {
+        let key = match cipher {
+            Cipher::Identity => return Ok(data),
+            Cipher::Rc4 => {
+                rc4(&self.object_key(reference, cipher), &mut data);
+                return Ok(data);
+            }
+            Cipher::AesV2 => self.object_key(reference, cipher),
+            // Algorithm 1.A: the file encryption key is used directly
+            Cipher::AesV3 => self.key.clone(),
         };
 
-        hash[..n as usize].to_vec()
+        match direction {
+            Direction::Encrypt => aes_cbc_encrypt(&key, data),
+            Direction::Decrypt => aes_cbc_decrypt(&key, data),
+        }
     }
 
-    pub fn decrypt_string(&mut self, s: Vec<u8>) -> PdfResult<String> {
-        todo!()
+    /// Decrypt a string of the object with the given reference, whose bytes are
+    /// held as `char`s
+    pub fn decrypt_string(&self, s: &str, reference: Reference) -> PdfResult<String> {
+        let cipher = self.cipher(&self.encryption.string_filter)?;
+
+        let decrypted = self.decrypt(string_bytes(s), reference, cipher)?;
+
+        Ok(decrypted.into_iter().map(char::from).collect())
+    }
+
+    pub fn decrypt_stream(&self, stream: Vec<u8>, reference: Reference) -> PdfResult<Vec<u8>> {
+        let cipher = self.cipher(&self.encryption.stream_filter)?;
+
+        self.decrypt(stream, reference, cipher)
     }
 
-    pub fn decrypt_stream(&self, mut stream: Vec<u8>, reference: Reference) -> PdfResult<Vec<u8>> {
-        let filter_name = &self.encryption.stream_filter.0;
-        let filter = self
+    /// Decrypt an embedded file stream, which uses the crypt filter named by
+    /// the EFF entry, or by StmF if there is none
+    pub fn decrypt_embedded_file(
+        &self,
+        stream: Vec<u8>,
+        reference: Reference,
+    ) -> PdfResult<Vec<u8>> {
+        let filter_name = self
             .encryption
-            .crypt_filters
+            .embedded_file_filter
             .as_ref()
+            .unwrap_or(&self.encryption.stream_filter);
+
+        let cipher = self.cipher(filter_name)?;
+
+        self.decrypt(stream, reference, cipher)
+    }
+
+    /// The cipher applied to the data of a stream with the given dictionary
+    ///
+    /// Cross-reference streams are never encrypted, and streams whose first
+    /// filter is a Crypt filter name the crypt filter to use in its decode
+    /// parameters, rather than using the document's default.
+    fn stream_cipher(&self, dict: &StreamDict) -> PdfResult<Cipher> {
+        let type_is = |name: &str| {
+            dict.other
+                .iter()
+                .any(|(key, value)| key == "Type" && value.name_is(name))
+        };
+
+        if type_is("XRef") {
+            return Ok(Cipher::Identity);
+        }
+
+        if let Some(FilterKind::Crypt) = dict.filter.as_ref().and_then(|filters| filters.first()) {
+            let name = dict
+                .decode_parms
+                .as_ref()
+                .and_then(|params| params.get(0))
+                .and_then(|params| params.iter().find(|(key, _)| *key == "Name"))
+                .map(|(_, value)| value);
+
+            return match name {
+                Some(Object::Name(name)) => self.cipher(&Name(name.clone())),
+                Some(..) => anyhow::bail!("expected name of crypt filter"),
+                None => Ok(Cipher::Identity),
+            };
+        }
+
+        if type_is("EmbeddedFile") {
+            let filter_name = self
+                .encryption
+                .embedded_file_filter
+                .as_ref()
+                .unwrap_or(&self.encryption.stream_filter);
+
+            return self.cipher(filter_name);
+        }
+
+        self.cipher(&self.encryption.stream_filter)
+    }
+
+    /// Decrypt every string and stream within the indirect object with the
+    /// given reference
+    pub fn decrypt_object(&self, obj: &mut Object<'a>, reference: Reference) -> PdfResult<()> {
+        self.transform_object(obj, reference, Direction::Decrypt)
+    }
+
+    /// Encrypt every string and stream within the indirect object with the
+    /// given reference, the inverse of `decrypt_object`
+    pub(crate) fn encrypt_object(
+        &self,
+        obj: &mut Object<'a>,
+        reference: Reference,
+    ) -> PdfResult<()> {
+        self.transform_object(obj, reference, Direction::Encrypt)
+    }
+
+    fn transform_object(
+        &self,
+        obj: &mut Object<'a>,
+        reference: Reference,
+        direction: Direction,
+    ) -> PdfResult<()> {
+        match obj {
+            Object::String(s) => {
+                let cipher = self.cipher(&self.encryption.string_filter)?;
+                let data = self.transform(string_bytes(s), reference, cipher, direction)?;
+
+                *s = bytes_string(&data);
+            }
+            Object::Array(arr) => {
+                for obj in arr {
+                    self.transform_object(obj, reference, direction)?;
+                }
+            }
+            Object::Dictionary(dict) => {
+                // the signature value of a signature dictionary is not
+                // encrypted, as it is calculated over the encrypted file
+                let contents = match dict.peek("ByteRange") {
+                    Some(..) => dict.remove("Contents"),
+                    None => None,
+                };
+
+                for obj in dict.values_mut() {
+                    self.transform_object(obj, reference, direction)?;
+                }
+
+                if let Some(contents) = contents {
+                    dict.insert("Contents", contents);
+                }
+            }
+            Object::Stream(stream) => {
+                for obj in stream.dict.other.values_mut() {
+                    self.transform_object(obj, reference, direction)?;
+                }
+
+                let cipher = self.stream_cipher(&stream.dict)?;
+                let data = std::mem::take(&mut stream.stream).into_owned();
+
+                stream.stream = Cow::Owned(self.transform(data, reference, cipher, direction)?);
+            }
+            Object::Null
+            | Object::True
+            | Object::False
+            | Object::Integer(..)
+            | Object::Real(..)
+            | Object::Name(..)
+            | Object::Reference(..) => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Pad or truncate a password to exactly 32 bytes
+fn pad_password(password: &[u8]) -> [u8; 32] {
+    let mut padded = PADDING;
+    let len = password.len().min(32);
+
+    padded[..len].copy_from_slice(&password[..len]);
+    padded[len..].copy_from_slice(&PADDING[..32 - len]);
+
+    padded
+}
+
+/// The bytes of a string, which holds each byte as a `char`
+fn string_bytes(s: &str) -> Vec<u8> {
+    s.chars().map(|c| c as u8).collect()
+}
+
+/// The bytes hashed for a revision 6 password: the UTF-8 encoding of the
+/// password after it has been normalized with SASLprep (RFC 4013),
+/// truncated to 127 bytes
+fn prepare_password(password: &str) -> PdfResult<Vec<u8>> {
+    let password = match stringprep::saslprep(password) {
+        Ok(password) => password,
+        Err(err) => anyhow::bail!("invalid password: {}", err),
+    };
+
+    Ok(password.as_bytes()[..password.len().min(127)].to_vec())
+}
+
+/// A string holding each of `bytes` as a `char`
+fn bytes_string(bytes: &[u8]) -> String {
+    bytes.iter().copied().map(char::from).collect()
+}
+
+/// `N` bytes from the operating system's random number generator
+fn random_bytes<const N: usize>() -> PdfResult<[u8; N]> {
+    let mut bytes = [0; N];
+
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| anyhow::anyhow!("failed to generate random bytes: {}", e))?;
+
+    Ok(bytes)
+}
+
+/// Encrypt the 32-byte file encryption key for the OE or UE entry, with
+/// AES-256 in cipher block chaining mode, no padding and an initialization
+/// vector of zero
+fn aes_256_encrypt_key(intermediate_key: &[u8], file_key: &[u8; 32]) -> [u8; 32] {
+    let mut encrypted = *file_key;
+
+    cbc::Encryptor::<aes::Aes256>::new_from_slices(intermediate_key, &[0; 16])
+        .unwrap()
+        .encrypt_padded_mut::<NoPadding>(&mut encrypted, 32)
+        .unwrap();
+
+    encrypted
+}
+
+/// Encrypt `data` with AES-128 or AES-256, the inverse of `aes_cbc_decrypt`,
+/// with a random initialization vector
+fn aes_cbc_encrypt(key: &[u8], data: Vec<u8>) -> PdfResult<Vec<u8>> {
+    let iv = random_bytes::<16>()?;
+
+    let len = data.len();
+
+    // room for the initialization vector and at least one byte of padding
+    let mut buffer = Vec::with_capacity(16 + len + 16 - len % 16);
+    buffer.extend_from_slice(&iv);
+    buffer.extend_from_slice(&data);
+    buffer.resize(16 + len + 16 - len % 16, 0);
+
+    let ciphertext = &mut buffer[16..];
+
+    match key.len() {
+        16 => cbc::Encryptor::<aes::Aes128>::new_from_slices(key, &iv)
             .unwrap()
-            .get(filter_name)
+            .encrypt_padded_mut::<Pkcs7>(ciphertext, len)
+            .unwrap(),
+        32 => cbc::Encryptor::<aes::Aes256>::new_from_slices(key, &iv)
+            .unwrap()
+            .encrypt_padded_mut::<Pkcs7>(ciphertext, len)
+            .unwrap(),
+        len => anyhow::bail!("invalid AES key length {}", len),
+    };
+
+    Ok(buffer)
+}
+
+/// Decrypt `data` with AES-128 or AES-256, depending on the length of the key,
+/// in cipher block chaining mode, where the initialization vector is stored as
+/// the first 16 bytes of the data and the plaintext is padded as described in
+/// RFC 2898
+fn aes_cbc_decrypt(key: &[u8], mut data: Vec<u8>) -> PdfResult<Vec<u8>> {
+    if data.len() < 32 || data.len() % 16 != 0 {
+        anyhow::bail!(
+            "AES-encrypted data has invalid length {}, expected a multiple of the block size",
+            data.len()
+        );
+    }
+
+    let (iv, ciphertext) = data.split_at_mut(16);
+
+    let plaintext = match key.len() {
+        16 => cbc::Decryptor::<aes::Aes128>::new_from_slices(key, iv)
+            .unwrap()
+            .decrypt_padded_mut::<Pkcs7>(ciphertext),
+        32 => cbc::Decryptor::<aes::Aes256>::new_from_slices(key, iv)
+            .unwrap()
+            .decrypt_padded_mut::<Pkcs7>(ciphertext),
+        len => anyhow::bail!("invalid AES key length {}", len),
+    };
+
+    let len = plaintext
+        .map_err(|_| anyhow::anyhow!("invalid padding in AES-encrypted data"))?
+        .len();
+
+    data.drain(..16);
+    data.truncate(len);
+
+    Ok(data)
+}
+
+/// Encrypt or decrypt `data` in place with the RC4 stream cipher, which is its
+/// own inverse
+fn rc4(key: &[u8], data: &mut [u8]) {
+    let mut state: [u8; 256] = std::array::from_fn(|i| i as u8);
+
+    let mut j = 0_u8;
+
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, usize::from(j));
+    }
+
+    let mut i = 0_u8;
+    let mut j = 0_u8;
+
+    for b in data {
+        i = i.wrapping_add(1);
+        j = j.wrapping_add(state[usize::from(i)]);
+        state.swap(usize::from(i), usize::from(j));
+
+        *b ^= state[usize::from(state[usize::from(i)].wrapping_add(state[usize::from(j)]))];
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Authenticate `password` as the user or owner password of an encrypted
+    /// document, which was opened with the password given in its
+    /// [`ParseOptions`]
+    ///
+    /// Supplying the owner password after opening a document with the user
+    /// password grants full access to it.
+    pub fn authenticate(&mut self, password: &str) -> PdfResult<PasswordKind> {
+        match &mut self.lexer.security_handler {
+            Some(security_handler) => security_handler.authenticate(password),
+            None => anyhow::bail!("document is not encrypted"),
+        }
+    }
+
+    /// The operations permitted with the password the document was opened
+    /// with, or every operation if the document is not encrypted
+    pub fn permissions(&self) -> Permissions {
+        match &self.lexer.security_handler {
+            Some(security_handler) if security_handler.password_kind() == PasswordKind::User => {
+                security_handler.permissions()
+            }
+            _ => Permissions::all(),
+        }
+    }
+
+    /// The crypt filters of an encrypted document, which select how its
+    /// strings, streams, and embedded files are decrypted
+    pub fn crypt_filters(&self) -> Option<&CryptFilterMap> {
+        self.lexer
+            .security_handler
+            .as_ref()
+            .map(SecurityHandler::crypt_filters)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aes::cipher::BlockEncrypt;
+
+    use crate::{
+        tokenizer::Tokenizer,
+        version::PdfVersion,
+        writer::{PdfWriter, StreamCompression},
+        ParseOptions, Parser,
+    };
+
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    /// A revision 3 handler for 128-bit RC4 with the owner password "owner"
+    /// and the user password "user"
+    fn rc4_handler() -> SecurityHandler<'static> {
+        let mut owner_key = md5::compute(pad_password(b"owner"));
+
+        for _ in 0..50 {
+            owner_key = md5::compute(owner_key.0);
+        }
+
+        // Algorithm 3: Computing the O value
+        let mut owner = pad_password(b"user");
+
+        for i in 0..20_u8 {
+            let key = owner_key.iter().map(|b| b ^ i).collect::<Vec<u8>>();
+            rc4(&key, &mut owner);
+        }
+
+        // the handler borrows from the dictionary for the rest of the test
+        let dict = format!(
+            "<< /Filter /Standard /V 2 /R 3 /Length 128 /P -4 /O <{}> /U <{}> >>",
+            hex(&owner),
+            hex(&[0; 32])
+        )
+        .leak();
+
+        let mut tokenizer = Tokenizer::new(dict.as_bytes());
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+        let encryption = Encryption::from_obj(obj, &mut tokenizer).unwrap();
+
+        let mut handler = SecurityHandler {
+            encryption,
+            file_identifier: FileIdentifier(["0123456789abcdef".to_owned(), String::new()]),
+            key: Vec::new(),
+            password_kind: PasswordKind::User,
+        };
+
+        // Algorithm 5: Computing the U value
+        let key = handler.compute_encryption_key(b"user");
+
+        let mut hash = md5::Context::new();
+        hash.consume(PADDING);
+        hash.consume(b"0123456789abcdef");
+
+        let mut user = hash.compute().0.to_vec();
+
+        for i in 0..20_u8 {
+            let key = key.iter().map(|b| b ^ i).collect::<Vec<u8>>();
+            rc4(&key, &mut user);
+        }
+
+        user.resize(32, 0);
+
+        handler.encryption.user = user.into_iter().map(char::from).collect();
+        handler.key = key;
+
+        handler
+    }
+
+    #[test]
+    fn authenticate_user_and_owner_passwords() {
+        let handler = rc4_handler();
+
+        assert_eq!(
+            handler.authenticate_user_password(b"user"),
+            Some(handler.key.clone())
+        );
+        assert_eq!(handler.authenticate_user_password(b"owner"), None);
+        assert_eq!(
+            handler.user_password_from_owner_password(b"owner"),
+            pad_password(b"user")
+        );
+    }
+
+    #[test]
+    fn distinguish_user_and_owner_passwords() {
+        let mut handler = rc4_handler();
+        let key = handler.key.clone();
+
+        assert_eq!(handler.authenticate("user").unwrap(), PasswordKind::User);
+        assert_eq!(handler.authenticate("owner").unwrap(), PasswordKind::Owner);
+        assert_eq!(handler.password_kind(), PasswordKind::Owner);
+        assert_eq!(handler.key, key);
+
+        assert!(handler.authenticate("wrong").is_err());
+        assert_eq!(handler.password_kind(), PasswordKind::Owner);
+    }
+
+    #[test]
+    fn permission_flags() {
+        // printing and copying, but not modification or annotation
+        let permissions = Permissions(-3904 | 1 << 2 | 1 << 4);
+
+        assert!(permissions.can_print());
+        assert!(permissions.can_copy());
+        assert!(!permissions.can_modify());
+        assert!(!permissions.can_annotate());
+        assert!(!permissions.can_fill_forms());
+        assert!(!permissions.can_extract_for_accessibility());
+        assert!(!permissions.can_assemble());
+        assert!(!permissions.can_print_high_resolution());
+
+        let revision_2 = permissions.for_revision_2();
+
+        assert!(revision_2.can_extract_for_accessibility());
+        assert!(revision_2.can_print_high_resolution());
+        assert!(!revision_2.can_assemble());
+    }
+
+    #[test]
+    fn decrypt_aes_v2_string_and_stream() {
+        type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
+
+        let dict = format!(
+            "<< /Filter /Standard /V 4 /R 4 /P -4 /O <{0}> /U <{0}> \
+                /CF << /StdCF << /Type /CryptFilter /CFM /AESV2 /Length 16 >> >> \
+                /StmF /StdCF /StrF /StdCF >>",
+            hex(&[0; 32])
+        );
+
+        let mut tokenizer = Tokenizer::new(dict.as_bytes());
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+
+        let handler = SecurityHandler {
+            encryption: Encryption::from_obj(obj, &mut tokenizer).unwrap(),
+            file_identifier: FileIdentifier([String::new(), String::new()]),
+            key: (0..16).collect(),
+            password_kind: PasswordKind::User,
+        };
+
+        let reference = Reference {
+            object_number: 7,
+            generation: 1,
+        };
+
+        let key = handler.object_key(reference, Cipher::AesV2);
+        let iv = [0x2A; 16];
+
+        let mut encrypted = iv.to_vec();
+        let mut buffer = [0; 32];
+        buffer[..13].copy_from_slice(b"secret (text)");
+
+        let ciphertext = Aes128CbcEnc::new_from_slices(&key, &iv)
+            .unwrap()
+            .encrypt_padded_mut::<Pkcs7>(&mut buffer, 13)
             .unwrap();
 
-        assert_eq!(filter.crypt_filter_method, CryptFilterMethod::AesV2);
+        encrypted.extend_from_slice(ciphertext);
 
-        dbg!(&self.encryption);
+        assert_eq!(
+            handler
+                .decrypt_stream(encrypted.clone(), reference)
+                .unwrap(),
+            b"secret (text)"
+        );
 
-        let mut key = self.compute_encryption_key(&[]);
-        let key_len = key.len();
+        let encrypted = encrypted.into_iter().map(char::from).collect::<String>();
 
-        key.extend_from_slice(&reference.object_number.to_le_bytes()[..3]);
-        key.extend_from_slice(&reference.generation.to_le_bytes()[..2]);
-        key.extend_from_slice(b"sAlT");
+        assert_eq!(
+            handler.decrypt_string(&encrypted, reference).unwrap(),
+            "secret (text)"
+        );
+    }
+
+    #[test]
+    fn authenticate_aes_256_passwords() {
+        fn encrypt_key(intermediate_key: &[u8], file_key: &[u8]) -> String {
+            let mut encrypted = file_key.to_vec();
+
+            cbc::Encryptor::<aes::Aes256>::new_from_slices(intermediate_key, &[0; 16])
+                .unwrap()
+                .encrypt_padded_mut::<NoPadding>(&mut encrypted, 32)
+                .unwrap();
+
+            encrypted.into_iter().map(char::from).collect()
+        }
+
+        let dict = format!(
+            "<< /Filter /Standard /V 5 /R 6 /Length 256 /P -1028 /O <{0}> /U <{0}> \
+                /CF << /StdCF << /CFM /AESV3 /Length 32 >> >> /StmF /StdCF /StrF /StdCF >>",
+            hex(&[0; 48])
+        );
+
+        let mut tokenizer = Tokenizer::new(dict.as_bytes());
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+
+        let mut handler = SecurityHandler {
+            encryption: Encryption::from_obj(obj, &mut tokenizer).unwrap(),
+            file_identifier: FileIdentifier([String::new(), String::new()]),
+            key: Vec::new(),
+            password_kind: PasswordKind::User,
+        };
+
+        let file_key = (0..32).collect::<Vec<u8>>();
+
+        // Algorithm 8: Computing the encryption dictionary's U and UE values
+        let mut user = handler.hash_r6("user".as_bytes(), &[1; 8], &[]);
+        user.extend_from_slice(&[1; 8]);
+        user.extend_from_slice(&[2; 8]);
+
+        let user_key = handler.hash_r6("user".as_bytes(), &[2; 8], &[]);
+
+        // Algorithm 9: Computing the encryption dictionary's O and OE values
+        let mut owner = handler.hash_r6("owner".as_bytes(), &[3; 8], &user);
+        owner.extend_from_slice(&[3; 8]);
+        owner.extend_from_slice(&[4; 8]);
 
-        let key = &md5::compute(&key)[..key_len];
+        let owner_key = handler.hash_r6("owner".as_bytes(), &[4; 8], &user);
 
-        let mut dec = aes::Aes128Dec::new_from_slice(&key).unwrap();
+        // Algorithm 10: Computing the encryption dictionary's Perms value
+        let mut perms = [0; 16];
+        perms[..4].copy_from_slice(&(-1028_i32).to_le_bytes());
+        perms[4..8].copy_from_slice(&[0xFF; 4]);
+        perms[8..12].copy_from_slice(b"Tadb");
 
-        let key = &[0; 32];
+        aes::Aes256::new_from_slice(&file_key)
+            .unwrap()
+            .encrypt_block(GenericArray::from_mut_slice(&mut perms));
+
+        handler.encryption.user = user.into_iter().map(char::from).collect();
+        handler.encryption.owner = owner.into_iter().map(char::from).collect();
+        handler.encryption.user_encryption_key = Some(encrypt_key(&user_key, &file_key));
+        handler.encryption.owner_encryption_key = Some(encrypt_key(&owner_key, &file_key));
+        handler.encryption.perms = Some(perms.into_iter().map(char::from).collect());
+
+        assert_eq!(
+            handler.authenticate_aes_256("user").unwrap(),
+            (file_key.clone(), PasswordKind::User)
+        );
+        assert_eq!(
+            handler.authenticate_aes_256("owner").unwrap(),
+            (file_key, PasswordKind::Owner)
+        );
+        assert!(handler.authenticate_aes_256("wrong").is_err());
+
+        // a soft hyphen is mapped to nothing by SASLprep
+        assert_eq!(
+            handler.authenticate_aes_256("us\u{AD}er").unwrap().1,
+            PasswordKind::User
+        );
+        assert!(handler.authenticate_aes_256("user\u{7}").is_err());
+
+        // the roman numeral nine is normalized to "IX"
+        handler.set_aes_256_passwords("\u{2168}", "owner").unwrap();
+
+        assert_eq!(
+            handler.authenticate_aes_256("IX").unwrap().1,
+            PasswordKind::User
+        );
+
+        handler.encryption.user_permission_flags = Permissions(-4);
+
+        assert!(handler.authenticate_aes_256("IX").is_err());
+    }
+
+    /// A revision 4 handler with an AESV2 filter for streams and an RC4 filter
+    /// for embedded files
+    fn crypt_filter_handler() -> SecurityHandler<'static> {
+        let dict = format!(
+            "<< /Filter /Standard /V 4 /R 4 /P -4 /O <{0}> /U <{0}> \
+                /CF << /StdCF << /CFM /AESV2 /Length 16 >> \
+                    /EFCF << /CFM /V2 /AuthEvent /EFOpen >> /Identity << /CFM /V2 >> >> \
+                /StmF /StdCF /StrF /Identity /EFF /EFCF >>",
+            hex(&[0; 32])
+        )
+        .leak();
+
+        let mut tokenizer = Tokenizer::new(dict.as_bytes());
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+
+        SecurityHandler {
+            encryption: Encryption::from_obj(obj, &mut tokenizer).unwrap(),
+            file_identifier: FileIdentifier([String::new(), String::new()]),
+            key: (0..16).collect(),
+            password_kind: PasswordKind::User,
+        }
+    }
 
-        assert_eq!(stream.len() % 16, 0);
+    #[test]
+    fn crypt_filter_lookup() {
+        let handler = crypt_filter_handler();
+        let filters = handler.crypt_filters();
 
-        type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
+        assert_eq!(
+            filters.get("StdCF").unwrap().method(),
+            CryptFilterMethod::AesV2
+        );
+        assert_eq!(filters.get("StdCF").unwrap().length(), Some(16));
+        assert_eq!(filters.get("EFCF").unwrap().auth_event(), AuthEvent::EFOpen);
+        assert!(filters.get("Identity").is_none());
 
-        let (iv, ciphertext) = stream.split_at_mut(16);
-        let cipher = Aes128CbcDec::new_from_slices(key, iv).unwrap();
+        assert_eq!(filters.cipher("StdCF").unwrap(), Cipher::AesV2);
+        assert_eq!(filters.cipher("EFCF").unwrap(), Cipher::Rc4);
+        assert_eq!(filters.cipher("Identity").unwrap(), Cipher::Identity);
+        assert!(filters.cipher("Undefined").is_err());
+
+        let reference = Reference {
+            object_number: 3,
+            generation: 0,
+        };
 
-        cipher.decrypt_padded_mut::<Pkcs7>(ciphertext).unwrap();
+        let mut encrypted = b"attachment".to_vec();
+        rc4(&handler.object_key(reference, Cipher::Rc4), &mut encrypted);
+
+        assert_eq!(
+            handler.decrypt_embedded_file(encrypted, reference).unwrap(),
+            b"attachment"
+        );
+    }
+
+    #[test]
+    fn stream_ciphers() {
+        let handler = crypt_filter_handler();
+
+        let cipher = |dict: &str| {
+            let mut tokenizer = Tokenizer::new(dict.as_bytes());
+            let obj = tokenizer.next_object().unwrap().unwrap().value;
+            let dict = tokenizer.assert_dict(obj).unwrap();
+
+            handler.stream_cipher(&StreamDict::from_dict(dict, &mut tokenizer).unwrap())
+        };
+
+        assert_eq!(cipher("<< /Length 0 >>").unwrap(), Cipher::AesV2);
+        assert_eq!(
+            cipher("<< /Length 0 /Type /XRef >>").unwrap(),
+            Cipher::Identity
+        );
+        assert_eq!(
+            cipher("<< /Length 0 /Type /EmbeddedFile >>").unwrap(),
+            Cipher::Rc4
+        );
+        assert_eq!(
+            cipher("<< /Length 0 /Filter [/Crypt /FlateDecode] >>").unwrap(),
+            Cipher::Identity
+        );
+        assert_eq!(
+            cipher("<< /Length 0 /Filter /Crypt /DecodeParms << /Name /EFCF >> >>").unwrap(),
+            Cipher::Rc4
+        );
+        assert!(
+            cipher("<< /Length 0 /Filter /Crypt /DecodeParms << /Name /Missing >> >>").is_err()
+        );
+    }
+
+    #[test]
+    fn decrypt_rc4_string() {
+        let handler = rc4_handler();
+
+        let reference = Reference {
+            object_number: 12,
+            generation: 0,
+        };
+
+        let mut encrypted = b"secret (text)".to_vec();
+        rc4(&handler.object_key(reference, Cipher::Rc4), &mut encrypted);
+
+        let encrypted = encrypted.into_iter().map(char::from).collect::<String>();
+
+        assert_eq!(
+            handler.decrypt_string(&encrypted, reference).unwrap(),
+            "secret (text)"
+        );
+    }
+
+    #[test]
+    fn rc4_test_vectors() {
+        let mut data = b"Plaintext".to_vec();
+        rc4(b"Key", &mut data);
+
+        assert_eq!(data, [0xBB, 0xF3, 0x16, 0xE8, 0xD9, 0x40, 0xAF, 0x0A, 0xD3]);
+
+        rc4(b"Key", &mut data);
+
+        assert_eq!(data, b"Plaintext");
+    }
+
+    #[test]
+    fn unencrypted_metadata_stream() {
+        let encryption_dict = |user: &[u8]| {
+            format!(
+                "<< /Filter /Standard /V 4 /R 4 /P -4 /O <{}> /U <{}> \
+                    /CF << /StdCF << /CFM /V2 /Length 16 >> >> /StmF /StdCF /StrF /StdCF \
+                    /EncryptMetadata false >>",
+                hex(&[0x11; 32]),
+                hex(user)
+            )
+            .leak()
+        };
+
+        let mut tokenizer = Tokenizer::new(encryption_dict(&[0; 32]).as_bytes());
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+
+        let mut handler = SecurityHandler {
+            encryption: Encryption::from_obj(obj, &mut tokenizer).unwrap(),
+            file_identifier: FileIdentifier(["0123456789abcdef".to_owned(), String::new()]),
+            key: Vec::new(),
+            password_kind: PasswordKind::User,
+        };
+
+        // the document may be opened with the empty user password
+        handler.key = handler.compute_encryption_key(b"");
+
+        // Algorithm 5: Computing the U value
+        let mut hash = md5::Context::new();
+        hash.consume(PADDING);
+        hash.consume(b"0123456789abcdef");
+
+        let mut user = hash.compute().0.to_vec();
+
+        for i in 0..20_u8 {
+            let key = handler.key.iter().map(|b| b ^ i).collect::<Vec<u8>>();
+            rc4(&key, &mut user);
+        }
+
+        user.resize(32, 0);
+
+        let reference = |object_number| Reference {
+            object_number,
+            generation: 0,
+        };
+
+        let mut content = b"0 0 m 10 10 l S".to_vec();
+        rc4(&handler.object_key(reference(2), Cipher::Rc4), &mut content);
+
+        // the writer does not encrypt, so the content stream is written as it
+        // is, and no object streams are written
+        let mut writer = PdfWriter::new(PdfVersion::V1_7);
+        writer.set_object_streams(false);
+        writer.set_stream_compression(StreamCompression::Keep);
+
+        let mut metadata = Dictionary::empty();
+        metadata.insert("Type", Object::Name("Metadata".to_owned()));
+        metadata.insert("Subtype", Object::Name("XML".to_owned()));
+
+        writer.add(Object::Stream(Stream::new(
+            b"<x:xmpmeta/>".as_slice(),
+            metadata,
+        )));
+        writer.add(Object::Stream(Stream::new(content, Dictionary::empty())));
+
+        for obj in [
+            "<< /Type /Pages /Kids [] /Count 0 >>",
+            "<< /Type /Catalog /Pages 3 0 R /Metadata 1 0 R >>",
+            encryption_dict(&user),
+        ] {
+            writer.add(
+                Tokenizer::new(obj.as_bytes())
+                    .next_object()
+                    .unwrap()
+                    .unwrap()
+                    .value,
+            );
+        }
+
+        writer.set_root(reference(4));
+        writer.set_trailer_entry("Encrypt", Object::Reference(reference(5)));
+        writer.set_trailer_entry(
+            "ID",
+            Object::Array(vec![
+                Object::String("0123456789abcdef".to_owned()),
+                Object::String(String::new()),
+            ]),
+        );
+
+        let mut parser =
+            Parser::from_bytes(writer.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        assert_eq!(
+            parser.xmp_metadata().unwrap().as_deref(),
+            Some("<x:xmpmeta/>")
+        );
+
+        // other streams are still decrypted
+        let stream = parser
+            .lexer
+            .assert_stream(Object::Reference(reference(2)))
+            .unwrap();
 
-        Ok(stream)
+        assert_eq!(stream.stream.as_ref(), b"0 0 m 10 10 l S");
     }
 }
@@ -1,8 +1,9 @@
 use crate::{
-    catalog::Collection,
+    date::Date,
     error::PdfResult,
-    objects::{Dictionary, Object},
-    FromObj, Resolve,
+    objects::{Dictionary, Name, Object},
+    stream::Stream,
+    FromObj, Resolve, ToObj,
 };
 
 #[derive(Debug, Clone, PartialEq)]
@@ -24,7 +25,7 @@ impl<'a> FromObj<'a> for FileSpecification<'a> {
 }
 
 #[derive(Debug, Clone, PartialEq, FromObj)]
-#[obj_type("Typespec")]
+#[obj_type("Filespec")]
 pub struct FullFileSpecification<'a> {
     /// The name of the file system that shall be used to interpret this file
     /// specification.
@@ -44,7 +45,7 @@ pub struct FullFileSpecification<'a> {
     /// cross-platform and cross-language compatibility and the F entry provides backwards
     /// compatibility.
     #[field("F")]
-    file_specification_string: Option<FileSpecificationString>,
+    pub(crate) file_specification_string: Option<FileSpecificationString>,
 
     /// A Unicode text string that provides file specification. This is a text string
     /// encoded using PDFDocEncoding or UTF-16BE with a leading byte-order marker.
@@ -52,7 +53,7 @@ pub struct FullFileSpecification<'a> {
     /// The F entry should be included along with this entry for backwards compatibility
     /// reasons.
     #[field("UF")]
-    unicode_file_specification_string: Option<FileSpecificationString>,
+    pub(crate) unicode_file_specification_string: Option<FileSpecificationString>,
 
     /// A file specification string representing a DOS file name.
     ///
@@ -100,7 +101,7 @@ pub struct FullFileSpecification<'a> {
     /// file. If this entry is present, the Type entry is required and the file specification
     /// dictionary shall be indirectly referenced.
     #[field("EF")]
-    ef: Option<Dictionary<'a>>,
+    pub(crate) ef: Option<Box<EmbeddedFiles<'a>>>,
 
     /// A dictionary with the same structure as the EF dictionary, which shall be present.
     ///
@@ -117,12 +118,12 @@ pub struct FullFileSpecification<'a> {
     ///
     /// It shall be used for files in the EmbeddedFiles name tree
     #[field("Desc")]
-    description: Option<String>,
+    pub(crate) description: Option<String>,
 
     /// A collection item dictionary, which shall be used to create the user interface for
     /// portable collections
     #[field("CI")]
-    collection_item_dict: Option<Collection>,
+    collection_item: Option<Dictionary<'a>>,
 }
 
 /// The standard format for representing a simple file specification in string form divides
@@ -139,6 +140,10 @@ impl FileSpecificationString {
     pub fn new(s: String) -> Self {
         Self(s)
     }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
 impl<'a> FromObj<'a> for FileSpecificationString {
@@ -147,8 +152,57 @@ impl<'a> FromObj<'a> for FileSpecificationString {
     }
 }
 
-#[derive(Debug, Clone)]
-struct EmbeddedFileStream;
+/// The embedded file streams of a file specification, keyed by the file
+/// specification string they correspond to
+#[derive(Debug, Clone, PartialEq, FromObj)]
+pub(crate) struct EmbeddedFiles<'a> {
+    #[field("F")]
+    pub f: Option<EmbeddedFileStream<'a>>,
+
+    #[field("UF")]
+    pub uf: Option<EmbeddedFileStream<'a>>,
+}
+
+/// The contents of a file embedded within the PDF file, which allows the file
+/// to be referenced from within the document without depending on an external
+/// file
+#[derive(Debug, Clone, PartialEq, FromObj)]
+#[obj_type("EmbeddedFile")]
+pub(crate) struct EmbeddedFileStream<'a> {
+    /// The subtype of the embedded file, which shall conform to the MIME media
+    /// type names defined in Internet RFC 2046
+    #[field("Subtype")]
+    pub subtype: Option<Name>,
+
+    /// An embedded file parameter dictionary that shall contain additional
+    /// file-specific information
+    #[field("Params")]
+    pub params: Option<EmbeddedFileParameters>,
+
+    #[field]
+    pub stream: Stream<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq, FromObj)]
+pub(crate) struct EmbeddedFileParameters {
+    /// The size of the uncompressed embedded file, in bytes
+    #[field("Size")]
+    pub size: Option<u32>,
+
+    /// The date and time when the embedded file was created
+    #[field("CreationDate")]
+    pub creation_date: Option<Date>,
+
+    /// The date and time when the embedded file was last modified
+    #[field("ModDate")]
+    pub mod_date: Option<Date>,
+
+    /// A 16-byte string that is the checksum of the bytes of the uncompressed
+    /// embedded file. The checksum shall be calculated by applying the standard
+    /// MD5 message-digest algorithm to the bytes of the embedded file stream
+    #[field("CheckSum")]
+    pub check_sum: Option<String>,
+}
 #[derive(Debug, Clone)]
 struct RelatedFilesArray;
 
@@ -161,3 +215,9 @@ impl<'a> FromObj<'a> for FileIdentifier {
         Ok(FileIdentifier(<[String; 2]>::from_obj(obj, resolver)?))
     }
 }
+
+impl<'a> ToObj<'a> for FileIdentifier {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        self.0.to_obj()
+    }
+}
@@ -1,3 +1,5 @@
+use std::io::{self, BufReader, Read};
+
 pub(crate) fn decode_ascii_hex(stream: &[u8]) -> Vec<u8> {
     let mut buffer = Vec::with_capacity(stream.len() / 2);
 
@@ -10,21 +12,40 @@ pub(crate) fn decode_ascii_hex(stream: &[u8]) -> Vec<u8> {
             Some(&c @ b'a'..=b'f') => c - b'a' + 10,
             Some(b'>') | None => break,
             Some(..) => todo!(),
-        } as u16;
+        };
 
         n *= 16;
 
+        // a final odd digit is followed by an implicit 0
         n += match iter.next() {
             Some(&c @ b'0'..=b'9') => c - b'0',
             Some(&c @ b'A'..=b'F') => c - b'A' + 10,
             Some(&c @ b'a'..=b'f') => c - b'a' + 10,
-            Some(b'>') | None => break,
+            Some(b'>') | None => {
+                buffer.push(n);
+                break;
+            }
             Some(..) => todo!(),
-        } as u16;
+        };
 
-        buffer.extend_from_slice(&n.to_be_bytes());
+        buffer.push(n);
+    }
+
+    buffer
+}
+
+pub(crate) fn encode_ascii_hex(data: &[u8]) -> Vec<u8> {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut buffer = Vec::with_capacity(data.len() * 2 + 1);
+
+    for &byte in data {
+        buffer.push(DIGITS[usize::from(byte >> 4)]);
+        buffer.push(DIGITS[usize::from(byte & 0xf)]);
     }
 
+    buffer.push(b'>');
+
     buffer
 }
 
@@ -33,7 +54,9 @@ fn decode_ascii_85_digit(digit: u8, n: &mut u32, count: &mut u8, result: &mut Ve
 
     if digit == b'z' {
         if *count == 0 {
+            // a whole group of zeros, which does not begin the next group
             result.extend_from_slice(&[0, 0, 0, 0]);
+            return;
         } else {
             todo!()
         }
@@ -88,9 +111,222 @@ pub(crate) fn decode_ascii_85(mut stream: &[u8]) -> Vec<u8> {
     buffer
 }
 
+pub(crate) fn encode_ascii_85(data: &[u8]) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity((data.len() / 4) * 5 + 7);
+
+    for group in data.chunks(4) {
+        // a final partial group is padded with zeros, and written as one
+        // more digit than it has bytes
+        let mut bytes = [0; 4];
+        bytes[..group.len()].copy_from_slice(group);
+
+        let mut n = u32::from_be_bytes(bytes);
+
+        if n == 0 && group.len() == 4 {
+            buffer.push(b'z');
+            continue;
+        }
+
+        let mut digits = [0; 5];
+
+        for digit in digits.iter_mut().rev() {
+            *digit = (n % 85) as u8 + b'!';
+            n /= 85;
+        }
+
+        buffer.extend_from_slice(&digits[..=group.len()]);
+    }
+
+    buffer.extend_from_slice(b"~>");
+
+    buffer
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Decodes ASCII hexadecimal data as it is read
+pub struct AsciiHexReader<R> {
+    bytes: io::Bytes<BufReader<R>>,
+    done: bool,
+}
+
+impl<R: Read> AsciiHexReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            bytes: BufReader::new(inner).bytes(),
+            done: false,
+        }
+    }
+
+    /// The value of the next digit, skipping whitespace, or `None` at the
+    /// end of the data
+    fn next_digit(&mut self) -> io::Result<Option<u8>> {
+        for byte in self.bytes.by_ref() {
+            match byte? {
+                c @ b'0'..=b'9' => return Ok(Some(c - b'0')),
+                c @ b'A'..=b'F' => return Ok(Some(c - b'A' + 10)),
+                c @ b'a'..=b'f' => return Ok(Some(c - b'a' + 10)),
+                b'>' => break,
+                c if c.is_ascii_whitespace() => {}
+                c => {
+                    return Err(invalid_data(format!(
+                        "invalid character in ASCII hexadecimal data: {:?}",
+                        c as char
+                    )))
+                }
+            }
+        }
+
+        self.done = true;
+
+        Ok(None)
+    }
+}
+
+impl<R: Read> Read for AsciiHexReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut len = 0;
+
+        while len < buf.len() && !self.done {
+            let high = match self.next_digit()? {
+                Some(high) => high,
+                None => break,
+            };
+
+            // a final odd digit is followed by an implicit 0
+            let low = self.next_digit()?.unwrap_or(0);
+
+            buf[len] = high << 4 | low;
+            len += 1;
+        }
+
+        Ok(len)
+    }
+}
+
+/// Decodes ASCII base-85 data as it is read
+pub struct Ascii85Reader<R> {
+    bytes: io::Bytes<BufReader<R>>,
+
+    /// The bytes of the last group which have not yet been read
+    group: [u8; 4],
+    len: usize,
+    pos: usize,
+
+    /// Whether any digits have been read, before which the `<~` which may
+    /// begin the data is skipped
+    started: bool,
+
+    done: bool,
+}
+
+impl<R: Read> Ascii85Reader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            bytes: BufReader::new(inner).bytes(),
+            group: [0; 4],
+            len: 0,
+            pos: 0,
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Decode the next group of up to five digits, returning whether there
+    /// was one
+    fn next_group(&mut self) -> io::Result<bool> {
+        let mut n: u64 = 0;
+        let mut count = 0;
+
+        while !self.done {
+            let digit = match self.bytes.next() {
+                Some(digit) => digit?,
+                None => break,
+            };
+
+            match digit {
+                b'<' if !self.started => match self.bytes.next().transpose()? {
+                    Some(b'~') => self.started = true,
+                    _ => {
+                        return Err(invalid_data(
+                            "ASCII base-85 data begins with '<'".to_owned(),
+                        ))
+                    }
+                },
+                b'z' if count == 0 => {
+                    self.started = true;
+                    self.group = [0; 4];
+                    self.len = 4;
+                    self.pos = 0;
+
+                    return Ok(true);
+                }
+                b'~' => self.done = true,
+                b'!'..=b'u' => {
+                    self.started = true;
+
+                    n = n * 85 + u64::from(digit - b'!');
+                    count += 1;
+
+                    if count == 5 {
+                        self.group = (n as u32).to_be_bytes();
+                        self.len = 4;
+                        self.pos = 0;
+
+                        return Ok(true);
+                    }
+                }
+                c if c.is_ascii_whitespace() => {}
+                c => {
+                    return Err(invalid_data(format!(
+                        "invalid character in ASCII base-85 data: {:?}",
+                        c as char
+                    )))
+                }
+            }
+        }
+
+        self.done = true;
+
+        // a final partial group of n digits is padded with 'u' and decodes to
+        // n - 1 bytes
+        if count < 2 {
+            return Ok(false);
+        }
+
+        for _ in count..5 {
+            n = n * 85 + 84;
+        }
+
+        self.group = (n as u32).to_be_bytes();
+        self.len = count - 1;
+        self.pos = 0;
+
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for Ascii85Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.len {
+            if !self.next_group()? {
+                return Ok(0);
+            }
+        }
+
+        let len = buf.len().min(self.len - self.pos);
+        buf[..len].copy_from_slice(&self.group[self.pos..self.pos + len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::decode_ascii_85;
+    use super::{decode_ascii_85, decode_ascii_hex, encode_ascii_85, encode_ascii_hex};
 
     #[test]
     fn ascii_85() {
@@ -111,4 +347,19 @@ mod test {
             r#"Man is distinguished, not only by his reason, but by this singular passion from other animals, which is a lust of the mind, that by a perseverance of delight in the continued and indefatigable generation of knowledge, exceeds the short vehemence of any carnal pleasure."#
         );
     }
+
+    #[test]
+    fn encode_ascii() {
+        assert_eq!(encode_ascii_hex(&[0x0f, 0xa0, 0xff]), b"0FA0FF>");
+        assert_eq!(decode_ascii_hex(b"0FA0FF>"), [0x0f, 0xa0, 0xff]);
+
+        assert_eq!(
+            encode_ascii_85(&[77, 97, 110, 32, 115, 117, 114, 101, 46]),
+            b"9jqo^F*2M7/c~>"
+        );
+
+        let data = [0, 0, 0, 0, 1, 2, 3, 4, 255, 0, 0];
+        assert_eq!(decode_ascii_85(&encode_ascii_85(&data)), data);
+        assert!(encode_ascii_85(&data).starts_with(b"z"));
+    }
 }
@@ -0,0 +1,677 @@
+use crate::error::PdfResult;
+
+/// Parameters of the CCITTFaxDecode filter
+#[derive(Debug, FromObj)]
+pub struct CcittFaxDecoderParams {
+    /// The encoding scheme: negative for pure two-dimensional (Group 4)
+    /// encoding, 0 for pure one-dimensional (Group 3, 1-D) encoding, and
+    /// positive for mixed encoding, in which each line may be either
+    #[field("K", default = 0)]
+    k: i32,
+
+    /// Whether each encoded line, or the end-of-line pattern before it, is
+    /// padded with zero bits to end on a byte boundary
+    #[field("EncodedByteAlign", default = false)]
+    encoded_byte_align: bool,
+
+    /// The width of the image in pixels
+    #[field("Columns", default = 1728)]
+    columns: u32,
+
+    /// The height of the image in lines, or 0 if it is determined by the
+    /// end of the encoded data
+    #[field("Rows", default = 0)]
+    rows: u32,
+
+    /// Whether 1 bits are black pixels, rather than the default of 0 bits
+    #[field("BlackIs1", default = false)]
+    black_is_1: bool,
+}
+
+/// Decodes image data encoded with the CCITT facsimile standards, ITU-T T.4
+/// (Group 3) and T.6 (Group 4), into rows of one bit per pixel, each padded to
+/// a whole byte
+///
+/// Decoding stops at the end-of-block pattern, once `Rows` lines have been
+/// decoded, or at the end of the data, whichever comes first.
+pub struct CcittFaxDecoder<'a> {
+    reader: BitReader<'a>,
+    params: CcittFaxDecoderParams,
+}
+
+/// The coding mode of an element of a two-dimensionally encoded line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Pass,
+    Horizontal,
+
+    /// The distance of the next changing element from that of the reference
+    /// line above it
+    Vertical(isize),
+}
+
+const MODE_CODES: [(u16, u8, Mode); 9] = [
+    (0b1, 1, Mode::Vertical(0)),
+    (0b011, 3, Mode::Vertical(1)),
+    (0b010, 3, Mode::Vertical(-1)),
+    (0b001, 3, Mode::Horizontal),
+    (0b0001, 4, Mode::Pass),
+    (0b000011, 6, Mode::Vertical(2)),
+    (0b000010, 6, Mode::Vertical(-2)),
+    (0b0000011, 7, Mode::Vertical(3)),
+    (0b0000010, 7, Mode::Vertical(-3)),
+];
+
+/// The end-of-line pattern, eleven zero bits followed by a one bit
+const EOL: u32 = 0b000000000001;
+
+impl<'a> CcittFaxDecoder<'a> {
+    pub fn new(buffer: &'a [u8], params: CcittFaxDecoderParams) -> Self {
+        Self {
+            reader: BitReader {
+                data: buffer,
+                pos: 0,
+            },
+            params,
+        }
+    }
+
+    pub fn decode(mut self) -> PdfResult<Vec<u8>> {
+        let columns = self.params.columns as usize;
+
+        if columns == 0 {
+            anyhow::bail!("CCITT encoded images must have at least one column");
+        }
+
+        let mut out = Vec::new();
+
+        // the reference line of the first line is entirely white
+        let mut reference = Vec::new();
+        let mut rows = 0;
+
+        while self.params.rows == 0 || rows < self.params.rows {
+            let two_dimensional = match self.begin_line() {
+                Some(two_dimensional) => two_dimensional,
+                None => break,
+            };
+
+            let line = if two_dimensional {
+                self.decode_2d_line(&reference)?
+            } else {
+                self.decode_1d_line()?
+            };
+
+            // a line cut short by the end of the data is dropped
+            let line = match line {
+                Some(line) => line,
+                None => break,
+            };
+
+            self.write_line(&line, &mut out);
+
+            reference = line;
+            rows += 1;
+        }
+
+        Ok(out)
+    }
+
+    /// Skip any end-of-line pattern or fill bits before the next line, and
+    /// determine whether it is encoded two-dimensionally, or `None` if the
+    /// end of the data has been reached
+    fn begin_line(&mut self) -> Option<bool> {
+        if self.params.k < 0 {
+            if self.params.encoded_byte_align {
+                self.reader.align();
+            }
+
+            // the end-of-block pattern is two end-of-line patterns
+            if self.reader.peek(12) == Some(EOL) || self.reader.is_exhausted() {
+                return None;
+            }
+
+            return Some(true);
+        }
+
+        if self.skip_eol() {
+            // the return-to-control pattern is a run of end-of-line patterns,
+            // each followed by a tag bit in mixed encoding
+            let rtc = match self.params.k {
+                0 => self.reader.peek(12) == Some(EOL),
+                _ => self.reader.peek(13) == Some(1 << 12 | EOL),
+            };
+
+            if rtc {
+                return None;
+            }
+        } else if self.params.encoded_byte_align {
+            self.reader.align();
+        }
+
+        if self.reader.is_exhausted() {
+            return None;
+        }
+
+        match self.params.k {
+            0 => Some(false),
+            // a tag bit of 0 marks a two-dimensionally encoded line
+            _ => self.reader.read_bit().map(|tag| tag == 0),
+        }
+    }
+
+    /// Skip an end-of-line pattern, along with any fill bits before it,
+    /// returning whether there was one
+    fn skip_eol(&mut self) -> bool {
+        let zeros = self.reader.leading_zeros();
+        let end = self.reader.pos + zeros + 1;
+
+        if zeros < 11
+            || end > self.reader.data.len() * 8
+            || (self.params.encoded_byte_align && end % 8 != 0)
+        {
+            return false;
+        }
+
+        self.reader.pos = end;
+
+        true
+    }
+
+    /// The changing elements of a line encoded as alternating runs of white and
+    /// black pixels, beginning with white
+    fn decode_1d_line(&mut self) -> PdfResult<Option<Vec<usize>>> {
+        let columns = self.params.columns as usize;
+
+        let mut changes = Vec::new();
+        let mut pos = 0;
+        let mut white = true;
+
+        while pos < columns {
+            let run = match self.read_run(white)? {
+                Some(run) => run,
+                None => return Ok(None),
+            };
+
+            pos = (pos + run).min(columns);
+            changes.push(pos);
+            white = !white;
+        }
+
+        Ok(Some(changes))
+    }
+
+    /// The changing elements of a line encoded relative to the changing
+    /// elements of the line above it, `reference`
+    fn decode_2d_line(&mut self, reference: &[usize]) -> PdfResult<Option<Vec<usize>>> {
+        let columns = self.params.columns as usize;
+
+        let mut changes = Vec::new();
+
+        // the line begins with an imaginary white pixel before its first
+        let mut a0 = -1_isize;
+        let mut white = true;
+
+        while a0 < columns as isize {
+            let (b1, b2) = reference_changes(reference, a0, white, columns);
+
+            let mode = match self.read_mode()? {
+                Some(mode) => mode,
+                None => return Ok(None),
+            };
+
+            match mode {
+                Mode::Pass => a0 = b2 as isize,
+                Mode::Horizontal => {
+                    let (first, second) = match (self.read_run(white)?, self.read_run(!white)?) {
+                        (Some(first), Some(second)) => (first, second),
+                        _ => return Ok(None),
+                    };
+
+                    let a1 = (a0.max(0) as usize + first).min(columns);
+                    let a2 = (a1 + second).min(columns);
+
+                    changes.extend([a1, a2]);
+                    a0 = a2 as isize;
+                }
+                Mode::Vertical(offset) => {
+                    let a1 = b1 as isize + offset;
+
+                    if a1 < a0.max(0) {
+                        anyhow::bail!("CCITT encoded line has a changing element out of order");
+                    }
+
+                    let a1 = (a1 as usize).min(columns);
+
+                    changes.push(a1);
+                    a0 = a1 as isize;
+                    white = !white;
+                }
+            }
+        }
+
+        Ok(Some(changes))
+    }
+
+    /// The length of a run of pixels of one colour, made up of any number of
+    /// make-up codes followed by a terminating code
+    fn read_run(&mut self, white: bool) -> PdfResult<Option<usize>> {
+        let codes: &[(u16, u8, u16)] = if white { &WHITE_CODES } else { &BLACK_CODES };
+
+        let mut run = 0;
+
+        loop {
+            let mut code = 0;
+            let mut length = None;
+
+            for len in 1..=13 {
+                code = code << 1
+                    | match self.reader.read_bit() {
+                        Some(bit) => bit,
+                        None => return Ok(None),
+                    };
+
+                length = codes
+                    .iter()
+                    .chain(&EXTENDED_MAKEUP_CODES)
+                    .find(|&&(c, l, _)| l == len && c == code)
+                    .map(|&(_, _, length)| length as usize);
+
+                if length.is_some() {
+                    break;
+                }
+            }
+
+            match length {
+                Some(length) if length < 64 => return Ok(Some(run + length)),
+                Some(length) => run += length,
+                None => anyhow::bail!("invalid CCITT run length code"),
+            }
+        }
+    }
+
+    fn read_mode(&mut self) -> PdfResult<Option<Mode>> {
+        let mut code = 0;
+
+        for len in 1..=7 {
+            code = code << 1
+                | match self.reader.read_bit() {
+                    Some(bit) => bit,
+                    None => return Ok(None),
+                };
+
+            if let Some(&(_, _, mode)) = MODE_CODES.iter().find(|&&(c, l, _)| l == len && c == code)
+            {
+                return Ok(Some(mode));
+            }
+        }
+
+        // todo: uncompressed mode
+        anyhow::bail!("unsupported CCITT coding mode")
+    }
+
+    /// Write a line as one bit per pixel, padded to a whole byte
+    fn write_line(&self, changes: &[usize], out: &mut Vec<u8>) {
+        let columns = self.params.columns as usize;
+        let black_is_1 = self.params.black_is_1;
+
+        let mut line = vec![if black_is_1 { 0x00 } else { 0xff }; columns.div_ceil(8)];
+
+        // changing elements at even indices begin black runs
+        for run in changes.chunks(2) {
+            let end = run.get(1).copied().unwrap_or(columns).min(columns);
+
+            for x in run[0]..end {
+                if black_is_1 {
+                    line[x / 8] |= 0x80 >> (x % 8);
+                } else {
+                    line[x / 8] &= !(0x80 >> (x % 8));
+                }
+            }
+        }
+
+        out.extend_from_slice(&line);
+    }
+}
+
+/// The first changing element of the reference line after `a0` which begins a
+/// run of the opposite colour to that at `a0`, and the changing element after
+/// it, either being the end of the line if there is none
+fn reference_changes(
+    reference: &[usize],
+    a0: isize,
+    white: bool,
+    columns: usize,
+) -> (usize, usize) {
+    let b1 = reference
+        .iter()
+        .enumerate()
+        .position(|(idx, &pos)| pos as isize > a0 && (idx % 2 == 0) == white);
+
+    match b1 {
+        Some(idx) => (
+            reference[idx],
+            reference.get(idx + 1).copied().unwrap_or(columns),
+        ),
+        None => (columns, columns),
+    }
+}
+
+/// Reads bits from most to least significant within each byte
+struct BitReader<'a> {
+    data: &'a [u8],
+
+    /// The index of the next bit
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn read_bit(&mut self) -> Option<u16> {
+        let byte = self.data.get(self.pos / 8)?;
+        let bit = (byte >> (7 - self.pos % 8)) & 1;
+
+        self.pos += 1;
+
+        Some(bit as u16)
+    }
+
+    /// The next `n` bits, without consuming them
+    fn peek(&self, n: usize) -> Option<u32> {
+        if self.pos + n > self.data.len() * 8 {
+            return None;
+        }
+
+        Some((self.pos..self.pos + n).fold(0, |bits, pos| {
+            bits << 1 | (self.data[pos / 8] >> (7 - pos % 8)) as u32 & 1
+        }))
+    }
+
+    /// The number of zero bits before the next one bit, or the end of the data
+    fn leading_zeros(&self) -> usize {
+        (self.pos..self.data.len() * 8)
+            .take_while(|&pos| self.data[pos / 8] & (0x80 >> (pos % 8)) == 0)
+            .count()
+    }
+
+    /// Whether only zero bits, such as padding, remain
+    fn is_exhausted(&self) -> bool {
+        self.pos + self.leading_zeros() >= self.data.len() * 8
+    }
+
+    fn align(&mut self) {
+        self.pos = self.pos.next_multiple_of(8);
+    }
+}
+
+/// Run lengths of white pixels, with their codes and the lengths of their
+/// codes, from the terminating codes of 0 to 63 pixels, followed by the
+/// make-up codes of multiples of 64 pixels
+const WHITE_CODES: [(u16, u8, u16); 91] = [
+    (0b00110101, 8, 0),
+    (0b000111, 6, 1),
+    (0b0111, 4, 2),
+    (0b1000, 4, 3),
+    (0b1011, 4, 4),
+    (0b1100, 4, 5),
+    (0b1110, 4, 6),
+    (0b1111, 4, 7),
+    (0b10011, 5, 8),
+    (0b10100, 5, 9),
+    (0b00111, 5, 10),
+    (0b01000, 5, 11),
+    (0b001000, 6, 12),
+    (0b000011, 6, 13),
+    (0b110100, 6, 14),
+    (0b110101, 6, 15),
+    (0b101010, 6, 16),
+    (0b101011, 6, 17),
+    (0b0100111, 7, 18),
+    (0b0001100, 7, 19),
+    (0b0001000, 7, 20),
+    (0b0010111, 7, 21),
+    (0b0000011, 7, 22),
+    (0b0000100, 7, 23),
+    (0b0101000, 7, 24),
+    (0b0101011, 7, 25),
+    (0b0010011, 7, 26),
+    (0b0100100, 7, 27),
+    (0b0011000, 7, 28),
+    (0b00000010, 8, 29),
+    (0b00000011, 8, 30),
+    (0b00011010, 8, 31),
+    (0b00011011, 8, 32),
+    (0b00010010, 8, 33),
+    (0b00010011, 8, 34),
+    (0b00010100, 8, 35),
+    (0b00010101, 8, 36),
+    (0b00010110, 8, 37),
+    (0b00010111, 8, 38),
+    (0b00101000, 8, 39),
+    (0b00101001, 8, 40),
+    (0b00101010, 8, 41),
+    (0b00101011, 8, 42),
+    (0b00101100, 8, 43),
+    (0b00101101, 8, 44),
+    (0b00000100, 8, 45),
+    (0b00000101, 8, 46),
+    (0b00001010, 8, 47),
+    (0b00001011, 8, 48),
+    (0b01010010, 8, 49),
+    (0b01010011, 8, 50),
+    (0b01010100, 8, 51),
+    (0b01010101, 8, 52),
+    (0b00100100, 8, 53),
+    (0b00100101, 8, 54),
+    (0b01011000, 8, 55),
+    (0b01011001, 8, 56),
+    (0b01011010, 8, 57),
+    (0b01011011, 8, 58),
+    (0b01001010, 8, 59),
+    (0b01001011, 8, 60),
+    (0b00110010, 8, 61),
+    (0b00110011, 8, 62),
+    (0b00110100, 8, 63),
+    (0b11011, 5, 64),
+    (0b10010, 5, 128),
+    (0b010111, 6, 192),
+    (0b0110111, 7, 256),
+    (0b00110110, 8, 320),
+    (0b00110111, 8, 384),
+    (0b01100100, 8, 448),
+    (0b01100101, 8, 512),
+    (0b01101000, 8, 576),
+    (0b01100111, 8, 640),
+    (0b011001100, 9, 704),
+    (0b011001101, 9, 768),
+    (0b011010010, 9, 832),
+    (0b011010011, 9, 896),
+    (0b011010100, 9, 960),
+    (0b011010101, 9, 1024),
+    (0b011010110, 9, 1088),
+    (0b011010111, 9, 1152),
+    (0b011011000, 9, 1216),
+    (0b011011001, 9, 1280),
+    (0b011011010, 9, 1344),
+    (0b011011011, 9, 1408),
+    (0b010011000, 9, 1472),
+    (0b010011001, 9, 1536),
+    (0b010011010, 9, 1600),
+    (0b011000, 6, 1664),
+    (0b010011011, 9, 1728),
+];
+
+/// Run lengths of black pixels, as for white pixels
+const BLACK_CODES: [(u16, u8, u16); 91] = [
+    (0b0000110111, 10, 0),
+    (0b010, 3, 1),
+    (0b11, 2, 2),
+    (0b10, 2, 3),
+    (0b011, 3, 4),
+    (0b0011, 4, 5),
+    (0b0010, 4, 6),
+    (0b00011, 5, 7),
+    (0b000101, 6, 8),
+    (0b000100, 6, 9),
+    (0b0000100, 7, 10),
+    (0b0000101, 7, 11),
+    (0b0000111, 7, 12),
+    (0b00000100, 8, 13),
+    (0b00000111, 8, 14),
+    (0b000011000, 9, 15),
+    (0b0000010111, 10, 16),
+    (0b0000011000, 10, 17),
+    (0b0000001000, 10, 18),
+    (0b00001100111, 11, 19),
+    (0b00001101000, 11, 20),
+    (0b00001101100, 11, 21),
+    (0b00000110111, 11, 22),
+    (0b00000101000, 11, 23),
+    (0b00000010111, 11, 24),
+    (0b00000011000, 11, 25),
+    (0b000011001010, 12, 26),
+    (0b000011001011, 12, 27),
+    (0b000011001100, 12, 28),
+    (0b000011001101, 12, 29),
+    (0b000001101000, 12, 30),
+    (0b000001101001, 12, 31),
+    (0b000001101010, 12, 32),
+    (0b000001101011, 12, 33),
+    (0b000011010010, 12, 34),
+    (0b000011010011, 12, 35),
+    (0b000011010100, 12, 36),
+    (0b000011010101, 12, 37),
+    (0b000011010110, 12, 38),
+    (0b000011010111, 12, 39),
+    (0b000001101100, 12, 40),
+    (0b000001101101, 12, 41),
+    (0b000011011010, 12, 42),
+    (0b000011011011, 12, 43),
+    (0b000001010100, 12, 44),
+    (0b000001010101, 12, 45),
+    (0b000001010110, 12, 46),
+    (0b000001010111, 12, 47),
+    (0b000001100100, 12, 48),
+    (0b000001100101, 12, 49),
+    (0b000001010010, 12, 50),
+    (0b000001010011, 12, 51),
+    (0b000000100100, 12, 52),
+    (0b000000110111, 12, 53),
+    (0b000000111000, 12, 54),
+    (0b000000100111, 12, 55),
+    (0b000000101000, 12, 56),
+    (0b000001011000, 12, 57),
+    (0b000001011001, 12, 58),
+    (0b000000101011, 12, 59),
+    (0b000000101100, 12, 60),
+    (0b000001011010, 12, 61),
+    (0b000001100110, 12, 62),
+    (0b000001100111, 12, 63),
+    (0b0000001111, 10, 64),
+    (0b000011001000, 12, 128),
+    (0b000011001001, 12, 192),
+    (0b000001011011, 12, 256),
+    (0b000000110011, 12, 320),
+    (0b000000110100, 12, 384),
+    (0b000000110101, 12, 448),
+    (0b0000001101100, 13, 512),
+    (0b0000001101101, 13, 576),
+    (0b0000001001010, 13, 640),
+    (0b0000001001011, 13, 704),
+    (0b0000001001100, 13, 768),
+    (0b0000001001101, 13, 832),
+    (0b0000001110010, 13, 896),
+    (0b0000001110011, 13, 960),
+    (0b0000001110100, 13, 1024),
+    (0b0000001110101, 13, 1088),
+    (0b0000001110110, 13, 1152),
+    (0b0000001110111, 13, 1216),
+    (0b0000001010010, 13, 1280),
+    (0b0000001010011, 13, 1344),
+    (0b0000001010100, 13, 1408),
+    (0b0000001010101, 13, 1472),
+    (0b0000001011010, 13, 1536),
+    (0b0000001011011, 13, 1600),
+    (0b0000001100100, 13, 1664),
+    (0b0000001100101, 13, 1728),
+];
+
+/// Make-up codes of 1792 to 2560 pixels, which are shared by both colours
+const EXTENDED_MAKEUP_CODES: [(u16, u8, u16); 13] = [
+    (0b00000001000, 11, 1792),
+    (0b00000001100, 11, 1856),
+    (0b00000001101, 11, 1920),
+    (0b000000010010, 12, 1984),
+    (0b000000010011, 12, 2048),
+    (0b000000010100, 12, 2112),
+    (0b000000010101, 12, 2176),
+    (0b000000010110, 12, 2240),
+    (0b000000010111, 12, 2304),
+    (0b000000011100, 12, 2368),
+    (0b000000011101, 12, 2432),
+    (0b000000011110, 12, 2496),
+    (0b000000011111, 12, 2560),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn params(k: i32, columns: u32, black_is_1: bool) -> CcittFaxDecoderParams {
+        CcittFaxDecoderParams {
+            k,
+            encoded_byte_align: false,
+            columns,
+            rows: 0,
+            black_is_1,
+        }
+    }
+
+    #[test]
+    fn decode_group_3() {
+        // white 2, black 3, white 3 on each of two lines, each line followed
+        // by an end-of-line pattern, and then the return-to-control pattern
+        let data = [
+            0b0111_1010,
+            0b0000_0000,
+            0b0000_0101,
+            0b1110_1000,
+            0b0000_0000,
+            0b0001_0000,
+            0b0000_0001,
+        ];
+
+        let decoded = CcittFaxDecoder::new(&data, params(0, 8, false))
+            .decode()
+            .unwrap();
+        assert_eq!(decoded, [0b1100_0111, 0b1100_0111]);
+
+        let decoded = CcittFaxDecoder::new(&data, params(0, 8, true))
+            .decode()
+            .unwrap();
+        assert_eq!(decoded, [0b0011_1000, 0b0011_1000]);
+    }
+
+    #[test]
+    fn decode_group_4() {
+        // the first line is encoded horizontally as white 2 and black 3, then
+        // vertically to the end of the line, and the second line vertically
+        // from the first, followed by the end-of-block pattern
+        let data = [
+            0b0010_1111,
+            0b0111_1000,
+            0b0000_0000,
+            0b1000_0000,
+            0b0000_1000,
+        ];
+
+        let decoded = CcittFaxDecoder::new(&data, params(-1, 8, false))
+            .decode()
+            .unwrap();
+        assert_eq!(decoded, [0b1100_0111, 0b1100_0111]);
+
+        // entirely white lines, each encoded as a single vertical mode code
+        let decoded = CcittFaxDecoder::new(&[0xff], params(-1, 1728, false))
+            .decode()
+            .unwrap();
+        assert_eq!(decoded, vec![0xff; 8 * 1728 / 8]);
+    }
+}
@@ -1,12 +1,30 @@
+#[cfg(feature = "jpeg")]
 use std::{
     borrow::Cow,
     io::{BufReader, Cursor},
 };
 
+#[cfg(feature = "jpeg")]
+use jpeg_decoder::PixelFormat;
+
+/// Decodes JPEG baseline and progressive image data into 8-bit samples,
+/// interleaved by pixel, with each row following the one above it
+///
+/// Images of three components are converted from YCbCr to RGB unless an Adobe
+/// APP14 marker says they were not transformed, and images of four components
+/// from YCCK to CMYK if the marker says they were.
+///
+/// Adobe applications write CMYK samples inverted, with 255 being no ink, and
+/// PDF producers embedding such images give them a Decode array of
+/// `[1 0 1 0 1 0 1 0]` to invert them again. The samples are therefore
+/// delivered as they were written, and not corrected here, so that the Decode
+/// array does not invert them twice.
+#[cfg(feature = "jpeg")]
 pub struct DctDecoder<'a> {
     buffer: Cow<'a, [u8]>,
 }
 
+#[cfg(feature = "jpeg")]
 impl<'a> DctDecoder<'a> {
     pub fn new(buffer: Cow<'a, [u8]>) -> Self {
         Self { buffer }
@@ -15,7 +33,42 @@ impl<'a> DctDecoder<'a> {
     pub fn decode(self) -> anyhow::Result<Vec<u8>> {
         let mut decoder = jpeg_decoder::Decoder::new(BufReader::new(Cursor::new(self.buffer)));
 
-        Ok(decoder.decode()?)
+        let mut data = decoder.decode()?;
+
+        let info = match decoder.info() {
+            Some(info) => info,
+            None => anyhow::bail!("JPEG image has no frame header"),
+        };
+
+        // jpeg-decoder inverts the components of four-component images,
+        // which it only accepts with an Adobe marker, so they are inverted
+        // back to the samples as written
+        if info.pixel_format == PixelFormat::CMYK32 {
+            for sample in &mut data {
+                *sample = 255 - *sample;
+            }
+        }
+
+        let components = match info.pixel_format {
+            PixelFormat::L8 => 1,
+            PixelFormat::RGB24 => 3,
+            PixelFormat::CMYK32 => 4,
+        };
+
+        let expected = info.width as usize * info.height as usize * components;
+
+        if data.len() != expected {
+            anyhow::bail!(
+                "JPEG image decoded to {} bytes, but its {}x{} pixels of {} components need {}",
+                data.len(),
+                info.width,
+                info.height,
+                components,
+                expected
+            );
+        }
+
+        Ok(data)
     }
 }
 
@@ -31,3 +84,125 @@ pub enum ColorTransform {
     /// image has one or two colour components.
     Yuv = 1,
 }
+
+#[cfg(all(test, feature = "jpeg"))]
+mod test {
+    use super::*;
+
+    fn segment(marker: u8, contents: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xFF, marker];
+        data.extend_from_slice(&u16::try_from(contents.len() + 2).unwrap().to_be_bytes());
+        data.extend_from_slice(contents);
+        data
+    }
+
+    /// A baseline JPEG of a single 8x8 block, in which every pixel has the
+    /// given sample for each component
+    ///
+    /// The quantization table is all ones and only DC coefficients are coded,
+    /// so each sample is given exactly by its coefficient.
+    fn jpeg(samples: &[u8], adobe_transform: Option<u8>) -> Vec<u8> {
+        let components = u8::try_from(samples.len()).unwrap();
+
+        let mut frame = vec![8, 0, 8, 0, 8, components];
+        let mut scan = vec![components];
+
+        for id in 1..=components {
+            frame.extend_from_slice(&[id, 0x11, 0]);
+            scan.extend_from_slice(&[id, 0x00]);
+        }
+
+        scan.extend_from_slice(&[0, 63, 0]);
+
+        // DC categories 0 to 11 are each coded in 4 bits, and the only AC code
+        // is end of block, coded as a single 0 bit
+        let mut dc_table = vec![0x00, 0, 0, 0, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        dc_table.extend(0..12);
+        let ac_table = [0x10, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x00];
+
+        let mut bits = Vec::new();
+
+        for &sample in samples {
+            let diff = (i32::from(sample) - 128) * 8;
+            let category = 32 - diff.unsigned_abs().leading_zeros();
+            let value = if diff < 0 { diff - 1 } else { diff };
+
+            bits.extend((0..4).rev().map(|i| (category >> i) & 1 == 1));
+            bits.extend((0..category).rev().map(|i| (value >> i) & 1 == 1));
+            bits.push(false);
+        }
+
+        // the final byte is padded with 1 bits, and 0xFF bytes are stuffed
+        bits.resize(bits.len().div_ceil(8) * 8, true);
+
+        let mut entropy_coded = Vec::new();
+
+        for byte in bits.chunks(8) {
+            let byte = byte.iter().fold(0_u8, |byte, &bit| byte << 1 | bit as u8);
+
+            entropy_coded.push(byte);
+
+            if byte == 0xFF {
+                entropy_coded.push(0);
+            }
+        }
+
+        let mut quantization_table = vec![0];
+        quantization_table.extend_from_slice(&[1; 64]);
+
+        let mut data = vec![0xFF, 0xD8];
+
+        if let Some(transform) = adobe_transform {
+            let mut app14 = b"Adobe".to_vec();
+            app14.extend_from_slice(&[0, 100, 0, 0, 0, 0, transform]);
+
+            data.extend(segment(0xEE, &app14));
+        }
+
+        data.extend(segment(0xDB, &quantization_table));
+        data.extend(segment(0xC0, &frame));
+        data.extend(segment(0xC4, &dc_table));
+        data.extend(segment(0xC4, &ac_table));
+        data.extend(segment(0xDA, &scan));
+        data.extend(entropy_coded);
+        data.extend_from_slice(&[0xFF, 0xD9]);
+
+        data
+    }
+
+    fn decode(data: Vec<u8>) -> Vec<u8> {
+        DctDecoder::new(Cow::Owned(data)).decode().unwrap()
+    }
+
+    #[test]
+    fn decode_gray() {
+        assert_eq!(decode(jpeg(&[200], None)), [200; 64]);
+    }
+
+    #[test]
+    fn decode_untransformed_rgb() {
+        let pixels = decode(jpeg(&[10, 60, 200], Some(0)));
+
+        assert_eq!(pixels.len(), 64 * 3);
+        assert!(pixels.chunks_exact(3).all(|pixel| pixel == [10, 60, 200]));
+    }
+
+    #[test]
+    fn cmyk_samples_as_written() {
+        // samples written by Adobe applications are inverted, and are left so
+        let pixels = decode(jpeg(&[245, 195, 55, 5], Some(0)));
+
+        assert_eq!(pixels.len(), 64 * 4);
+        assert!(pixels
+            .chunks_exact(4)
+            .all(|pixel| pixel == [245, 195, 55, 5]));
+    }
+
+    #[test]
+    fn reject_truncated_image() {
+        let mut data = jpeg(&[200], None);
+        data.truncate(20);
+
+        assert!(DctDecoder::new(Cow::Owned(data)).decode().is_err());
+    }
+}
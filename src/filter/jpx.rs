@@ -0,0 +1,399 @@
+use crate::{color::ColorSpaceName, error::PdfResult};
+
+/// The start of a JPEG 2000 codestream, the SOC marker followed by the SIZ
+/// marker
+const CODESTREAM_START: [u8; 4] = [0xff, 0x4f, 0xff, 0x51];
+
+/// The colour space of a JPEG 2000 image, as given by the colour
+/// specification box of a JP2 file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JpxColorSpace {
+    Gray,
+    Rgb,
+
+    /// YCbCr, which is converted to RGB when the image is decoded
+    Ycc,
+
+    Cmyk,
+
+    /// An ICC profile of `components` colour components
+    Icc {
+        profile: Vec<u8>,
+        components: u16,
+    },
+}
+
+impl JpxColorSpace {
+    /// The number of colour components of each pixel once decoded
+    pub fn components(&self) -> u16 {
+        match self {
+            Self::Gray => 1,
+            Self::Rgb | Self::Ycc => 3,
+            Self::Cmyk => 4,
+            Self::Icc { components, .. } => *components,
+        }
+    }
+
+    /// The device colour space equivalent to this one, by which the decoded
+    /// samples may be painted
+    pub fn device_equivalent(&self) -> Option<ColorSpaceName> {
+        device_color_space(self.components())
+    }
+}
+
+/// What the header of a JPEG 2000 image says about the samples it decodes to
+///
+/// The data of a JPXDecode stream may be either a JP2 file, whose header boxes
+/// describe the image, or a bare codestream, whose SIZ marker gives only the
+/// number of components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JpxHeader {
+    pub width: u32,
+    pub height: u32,
+
+    /// The number of components of each pixel once decoded, after any
+    /// palette has been applied, including any alpha channel
+    pub components: u16,
+
+    /// The colour space given by the JP2 header, which is absent for bare
+    /// codestreams and for colour spaces which are not supported
+    pub color_space: Option<JpxColorSpace>,
+}
+
+impl JpxHeader {
+    pub fn read(data: &[u8]) -> PdfResult<Self> {
+        if data.starts_with(&CODESTREAM_START) {
+            return Self::read_codestream(data);
+        }
+
+        let jp2h = match find_box(data, b"jp2h") {
+            Some(jp2h) => jp2h,
+            None => anyhow::bail!("JPEG 2000 image has neither a JP2 header nor a codestream"),
+        };
+
+        let (width, height, mut components) = match find_box(jp2h, b"ihdr") {
+            Some(&[h0, h1, h2, h3, w0, w1, w2, w3, c0, c1, ..]) => (
+                u32::from_be_bytes([w0, w1, w2, w3]),
+                u32::from_be_bytes([h0, h1, h2, h3]),
+                u16::from_be_bytes([c0, c1]),
+            ),
+            _ => anyhow::bail!("JPEG 2000 image has no valid image header box"),
+        };
+
+        // a palette maps one component to as many as it has columns
+        if let Some(&[_, _, columns, ..]) = find_box(jp2h, b"pclr") {
+            components = u16::from(columns);
+        }
+
+        let color_space = match find_box(jp2h, b"colr") {
+            // an enumerated colour space
+            Some(&[1, _, _, e0, e1, e2, e3, ..]) => match u32::from_be_bytes([e0, e1, e2, e3]) {
+                12 => Some(JpxColorSpace::Cmyk),
+                16 => Some(JpxColorSpace::Rgb),
+                17 => Some(JpxColorSpace::Gray),
+                18 => Some(JpxColorSpace::Ycc),
+                _ => None,
+            },
+            // a restricted ICC profile, or any ICC profile
+            Some([2 | 3, _, _, profile @ ..]) => Some(JpxColorSpace::Icc {
+                profile: profile.to_vec(),
+                components: icc_components(profile).unwrap_or(components),
+            }),
+            _ => None,
+        };
+
+        Ok(Self {
+            width,
+            height,
+            components,
+            color_space,
+        })
+    }
+
+    fn read_codestream(data: &[u8]) -> PdfResult<Self> {
+        // the SIZ marker segment: its length, the capabilities, then the
+        // reference grid and image offset, the tile sizes and offsets, and the
+        // number of components
+        let siz = match data.get(4..42) {
+            Some(siz) => siz,
+            None => anyhow::bail!("JPEG 2000 codestream ends within its SIZ marker"),
+        };
+
+        let int = |offset: usize| {
+            u32::from_be_bytes([
+                siz[offset],
+                siz[offset + 1],
+                siz[offset + 2],
+                siz[offset + 3],
+            ])
+        };
+
+        Ok(Self {
+            width: int(4).saturating_sub(int(12)),
+            height: int(8).saturating_sub(int(16)),
+            components: u16::from_be_bytes([siz[36], siz[37]]),
+            color_space: None,
+        })
+    }
+
+    /// The device colour space in which the decoded samples are to be painted
+    /// when the image dictionary has no ColorSpace entry
+    pub fn device_color_space(&self) -> Option<ColorSpaceName> {
+        match &self.color_space {
+            Some(color_space) => color_space.device_equivalent(),
+            None => device_color_space(self.components),
+        }
+    }
+
+    /// The number of colour components of each pixel, excluding any alpha
+    /// channel
+    pub fn color_components(&self) -> u16 {
+        match &self.color_space {
+            Some(color_space) => color_space.components().min(self.components),
+            None => self.components,
+        }
+    }
+}
+
+fn device_color_space(components: u16) -> Option<ColorSpaceName> {
+    match components {
+        1 => Some(ColorSpaceName::DeviceGray),
+        3 => Some(ColorSpaceName::DeviceRGB),
+        4 => Some(ColorSpaceName::DeviceCMYK),
+        _ => None,
+    }
+}
+
+/// The number of components of the data colour space of an ICC profile
+fn icc_components(profile: &[u8]) -> Option<u16> {
+    match profile.get(16..20)? {
+        b"GRAY" => Some(1),
+        b"RGB " | b"Lab " | b"YCbr" => Some(3),
+        b"CMYK" => Some(4),
+        _ => None,
+    }
+}
+
+/// The contents of the first box of type `ty` within `data`, a sequence of
+/// boxes
+fn find_box<'a>(mut data: &'a [u8], ty: &[u8; 4]) -> Option<&'a [u8]> {
+    while data.len() >= 8 {
+        let len = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let box_type = &data[4..8];
+
+        let (header_len, len) = match len {
+            // the box extends to the end of the data
+            0 => (8, data.len()),
+            // the length follows the type, as a 64-bit integer
+            1 => {
+                let len = u64::from_be_bytes(data.get(8..16)?.try_into().ok()?);
+                (16, usize::try_from(len).ok()?)
+            }
+            len => (8, len),
+        };
+
+        if len < header_len || len > data.len() {
+            return None;
+        }
+
+        if box_type == ty {
+            return Some(&data[header_len..len]);
+        }
+
+        data = &data[len..];
+    }
+
+    None
+}
+
+/// Decodes JPEG 2000 image data, either a JP2 file or a bare codestream, into
+/// 8-bit samples, interleaved by pixel, with each row following the one above
+/// it
+///
+/// Samples of other bit depths are scaled to 8 bits, components which are
+/// subsampled are scaled to the size of the image, and YCbCr images are
+/// converted to RGB. Only the colour components are kept, dropping any alpha
+/// channel.
+#[cfg(feature = "jpx")]
+pub struct JpxDecoder<'a> {
+    buffer: &'a [u8],
+}
+
+/// A decoded JPEG 2000 image
+#[cfg(feature = "jpx")]
+#[derive(Debug, Clone)]
+pub struct JpxImage {
+    pub header: JpxHeader,
+    pub data: Vec<u8>,
+}
+
+#[cfg(feature = "jpx")]
+impl<'a> JpxDecoder<'a> {
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self { buffer }
+    }
+
+    pub fn decode(self) -> PdfResult<JpxImage> {
+        let header = JpxHeader::read(self.buffer)?;
+        let image = jpeg2k::Image::from_bytes(self.buffer)?;
+
+        let components = image.components();
+        let channels = usize::from(header.color_components());
+
+        if components.len() < channels {
+            anyhow::bail!(
+                "JPEG 2000 image decoded to {} components, but its colour space has {}",
+                components.len(),
+                channels
+            );
+        }
+
+        let width = image.width() as usize;
+        let height = image.height() as usize;
+
+        let mut data = Vec::with_capacity(width * height * channels);
+
+        for y in 0..height {
+            for x in 0..width {
+                for component in &components[..channels] {
+                    let component_width = component.width() as usize;
+                    let component_height = component.height() as usize;
+
+                    let cx = (x * component_width / width).min(component_width - 1);
+                    let cy = (y * component_height / height).min(component_height - 1);
+
+                    let sample = component.data()[cy * component_width + cx];
+
+                    data.push(to_8_bits(
+                        sample,
+                        component.precision(),
+                        component.is_signed(),
+                    ));
+                }
+            }
+        }
+
+        if header.color_space == Some(JpxColorSpace::Ycc) {
+            for pixel in data.chunks_exact_mut(3) {
+                let [r, g, b] = ycc_to_rgb(pixel[0], pixel[1], pixel[2]);
+                pixel.copy_from_slice(&[r, g, b]);
+            }
+        }
+
+        Ok(JpxImage {
+            header: JpxHeader {
+                width: width as u32,
+                height: height as u32,
+                ..header
+            },
+            data,
+        })
+    }
+}
+
+/// Scale a sample of `precision` bits to 8 bits, offsetting signed samples to
+/// be unsigned
+#[cfg(feature = "jpx")]
+fn to_8_bits(sample: i32, precision: u32, signed: bool) -> u8 {
+    let precision = precision.clamp(1, 31);
+
+    let sample = if signed {
+        i64::from(sample) + (1 << (precision - 1))
+    } else {
+        i64::from(sample)
+    };
+
+    let max = (1_i64 << precision) - 1;
+
+    (sample.clamp(0, max) * 255 / max) as u8
+}
+
+#[cfg(feature = "jpx")]
+fn ycc_to_rgb(y: u8, cb: u8, cr: u8) -> [u8; 3] {
+    let y = f32::from(y);
+    let cb = f32::from(cb) - 128.0;
+    let cr = f32::from(cr) - 128.0;
+
+    [
+        (y + 1.402 * cr).round().clamp(0.0, 255.0) as u8,
+        (y - 0.344_136 * cb - 0.714_136 * cr)
+            .round()
+            .clamp(0.0, 255.0) as u8,
+        (y + 1.772 * cb).round().clamp(0.0, 255.0) as u8,
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn jp2_box(ty: &[u8; 4], contents: &[u8]) -> Vec<u8> {
+        let mut data = u32::try_from(contents.len() + 8)
+            .unwrap()
+            .to_be_bytes()
+            .to_vec();
+        data.extend_from_slice(ty);
+        data.extend_from_slice(contents);
+        data
+    }
+
+    #[test]
+    fn read_jp2_header() {
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&20_u32.to_be_bytes());
+        ihdr.extend_from_slice(&10_u32.to_be_bytes());
+        ihdr.extend_from_slice(&[0, 3, 7, 7, 0, 0]);
+
+        let mut colr = vec![1, 0, 0];
+        colr.extend_from_slice(&18_u32.to_be_bytes());
+
+        let jp2h = [jp2_box(b"ihdr", &ihdr), jp2_box(b"colr", &colr)].concat();
+
+        let data = [
+            jp2_box(b"jP  ", &[0x0d, 0x0a, 0x87, 0x0a]),
+            jp2_box(b"ftyp", b"jp2 \0\0\0\0jp2 "),
+            jp2_box(b"jp2h", &jp2h),
+            // the contents of the codestream box extend to the end of the file
+            vec![0, 0, 0, 0],
+            b"jp2c".to_vec(),
+            CODESTREAM_START.to_vec(),
+        ]
+        .concat();
+
+        let header = JpxHeader::read(&data).unwrap();
+
+        assert_eq!(
+            (header.width, header.height, header.components),
+            (10, 20, 3)
+        );
+        assert_eq!(header.color_space, Some(JpxColorSpace::Ycc));
+        assert_eq!(header.device_color_space(), Some(ColorSpaceName::DeviceRGB));
+    }
+
+    #[test]
+    fn read_codestream_header() {
+        let mut data = CODESTREAM_START.to_vec();
+
+        // the length of the segment and the capabilities
+        data.extend_from_slice(&[0, 41, 0, 0]);
+
+        // the reference grid, then the image offset, the tile size and the
+        // tile offset
+        for value in [100_u32, 50, 4, 2, 100, 50, 0, 0] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+
+        data.extend_from_slice(&[0, 1]);
+
+        let header = JpxHeader::read(&data).unwrap();
+
+        assert_eq!(
+            (header.width, header.height, header.components),
+            (96, 48, 1)
+        );
+        assert_eq!(
+            header.device_color_space(),
+            Some(ColorSpaceName::DeviceGray)
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use std::io::{self, BufReader, Read};
+
+use crate::error::PdfResult;
+
+/// Resets the table to its initial entries
+const CLEAR_TABLE: usize = 256;
+
+const END_OF_DATA: usize = 257;
+
+/// The first code which is not a single byte or a control code
+const FIRST_ENTRY: usize = 258;
+
+/// Codes are at most 12 bits long
+const MAX_CODES: usize = 4096;
+
+/// Decompresses data encoded using the LZW (Lempel-Ziv-Welch) adaptive
+/// compression method, before any predictor is reversed
+pub struct LzwDecoder<'a> {
+    buffer: &'a [u8],
+    early_change: bool,
+}
+
+impl<'a> LzwDecoder<'a> {
+    pub fn new(buffer: &'a [u8], early_change: bool) -> Self {
+        Self {
+            buffer,
+            early_change,
+        }
+    }
+
+    pub fn decode(self) -> PdfResult<Vec<u8>> {
+        let mut out = Vec::new();
+        LzwReader::new(self.buffer, self.early_change).read_to_end(&mut out)?;
+
+        Ok(out)
+    }
+}
+
+/// Decompresses LZW data as it is read, a code at a time
+///
+/// Codes begin 9 bits long, and grow by a bit each time the table fills, up
+/// to 12 bits.
+pub struct LzwReader<R> {
+    bytes: io::Bytes<BufReader<R>>,
+
+    /// Whether codes grow a code early, as most encoders do, which is given
+    /// by the EarlyChange parameter
+    early_change: bool,
+
+    /// Bits read which are not yet part of a code
+    bits: u32,
+    bit_count: u32,
+
+    /// The code before each entry after the single bytes, and the byte which
+    /// follows it
+    table: Vec<(u16, u8)>,
+
+    previous: Option<u16>,
+
+    /// The bytes of the last code which have not yet been read
+    pending: Vec<u8>,
+    pos: usize,
+
+    done: bool,
+}
+
+impl<R: Read> LzwReader<R> {
+    pub fn new(inner: R, early_change: bool) -> Self {
+        Self {
+            bytes: BufReader::new(inner).bytes(),
+            early_change,
+            bits: 0,
+            bit_count: 0,
+            table: Vec::new(),
+            previous: None,
+            pending: Vec::new(),
+            pos: 0,
+            done: false,
+        }
+    }
+
+    fn code_len(&self) -> u32 {
+        match FIRST_ENTRY + self.table.len() + usize::from(self.early_change) {
+            ..=511 => 9,
+            512..=1023 => 10,
+            1024..=2047 => 11,
+            _ => 12,
+        }
+    }
+
+    /// The next code, or `None` at the end of the data
+    fn read_code(&mut self) -> io::Result<Option<usize>> {
+        let len = self.code_len();
+
+        while self.bit_count < len {
+            match self.bytes.next() {
+                Some(byte) => {
+                    self.bits = self.bits << 8 | u32::from(byte?);
+                    self.bit_count += 8;
+                }
+                None => return Ok(None),
+            }
+        }
+
+        self.bit_count -= len;
+
+        let code = self.bits >> self.bit_count;
+        self.bits &= (1 << self.bit_count) - 1;
+
+        Ok(Some(code as usize))
+    }
+
+    /// Append the bytes of `code` to the pending bytes
+    fn push_entry(&mut self, mut code: usize) {
+        let start = self.pending.len();
+
+        while code >= FIRST_ENTRY {
+            let (prefix, byte) = self.table[code - FIRST_ENTRY];
+            self.pending.push(byte);
+            code = usize::from(prefix);
+        }
+
+        self.pending.push(code as u8);
+        self.pending[start..].reverse();
+    }
+
+    /// Decode the next code other than a control code, returning whether
+    /// there was one
+    fn next_code(&mut self) -> io::Result<bool> {
+        loop {
+            let code = match self.read_code()? {
+                Some(code) => code,
+                None => return Ok(false),
+            };
+
+            match code {
+                CLEAR_TABLE => {
+                    self.table.clear();
+                    self.previous = None;
+                    continue;
+                }
+                END_OF_DATA => return Ok(false),
+                _ => {}
+            }
+
+            self.pending.clear();
+            self.pos = 0;
+
+            if code < FIRST_ENTRY + self.table.len() {
+                self.push_entry(code);
+            } else {
+                match self.previous {
+                    // the code being defined by this one, which is the bytes
+                    // of the previous code followed by its own first byte
+                    Some(previous) if code == FIRST_ENTRY + self.table.len() => {
+                        self.push_entry(usize::from(previous));
+                        self.pending.push(self.pending[0]);
+                    }
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("invalid LZW code {}", code),
+                        ))
+                    }
+                }
+            }
+
+            if let Some(previous) = self.previous {
+                if FIRST_ENTRY + self.table.len() < MAX_CODES {
+                    self.table.push((previous, self.pending[0]));
+                }
+            }
+
+            self.previous = Some(code as u16);
+
+            return Ok(true);
+        }
+    }
+}
+
+impl<R: Read> Read for LzwReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.pending.len() {
+            if self.done || !self.next_code()? {
+                self.done = true;
+                return Ok(0);
+            }
+        }
+
+        let len = buf.len().min(self.pending.len() - self.pos);
+        buf[..len].copy_from_slice(&self.pending[self.pos..self.pos + len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_lzw() {
+        // the example from the PDF specification
+        let data = [0x80, 0x0b, 0x60, 0x50, 0x22, 0x0c, 0x0c, 0x85, 0x01];
+
+        assert_eq!(
+            LzwDecoder::new(&data, true).decode().unwrap(),
+            [45, 45, 45, 45, 45, 65, 45, 45, 45, 66]
+        );
+    }
+}
@@ -1,24 +1,43 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    io::{Cursor, Read},
+};
+
+use flate2::{read::ZlibDecoder, Compression};
 
+#[cfg(feature = "jpeg")]
+use crate::filter::dct::DctDecoder;
+#[cfg(feature = "jpx")]
+use crate::filter::jpx::JpxDecoder;
 use crate::{
     error::PdfResult,
-    filter::dct::DctDecoder,
     objects::{Dictionary, Object},
     stream::StreamDict,
     FromObj, Resolve,
 };
 
-use flate::{FlateDecoder, FlateDecoderParams};
+use ccitt::{CcittFaxDecoder, CcittFaxDecoderParams};
+use flate::FlateDecoder;
+use lzw::{LzwDecoder, LzwReader};
+use predictor::{PredictorParams, PredictorReader};
 
 pub mod ascii;
+pub mod ccitt;
 pub mod dct;
 pub mod flate;
+pub mod jpx;
+pub mod lzw;
+pub mod predictor;
+pub mod run_length;
 
 pub(crate) fn decode_stream<'a, 'b>(
     stream: &'b [u8],
     stream_dict: &StreamDict<'a>,
     resolver: &mut dyn Resolve<'a>,
 ) -> PdfResult<Cow<'b, [u8]>> {
+    #[cfg(feature = "bench")]
+    let _timer = crate::bench::StageTimer::start(crate::bench::Stage::Decode);
+
     if let Some(filters) = &stream_dict.filter {
         if filters.is_empty() {
             return Ok(Cow::Borrowed(stream));
@@ -26,40 +45,171 @@ pub(crate) fn decode_stream<'a, 'b>(
 
         let mut stream = stream.to_vec();
 
-        let decode_params = stream_dict.decode_parms.as_ref();
-
         for (idx, filter) in filters.iter().enumerate() {
-            let decode_params = decode_params
-                .and_then(|params| params.get(idx).cloned())
-                .unwrap_or_else(Dictionary::empty);
+            stream = decode_filter(stream, filter, filter_params(stream_dict, idx), resolver)?;
+        }
 
-            match filter {
-                FilterKind::AsciiHex => {
-                    stream = ascii::decode_ascii_hex(&stream);
-                }
-                FilterKind::Ascii85 => {
-                    stream = ascii::decode_ascii_85(&stream);
-                }
-                FilterKind::Lzw => todo!(),
-                FilterKind::Flate => {
-                    let decoder_params =
-                        FlateDecoderParams::from_obj(Object::Dictionary(decode_params), resolver)?;
+        return Ok(Cow::Owned(stream));
+    }
 
-                    stream = FlateDecoder::new(Cow::Owned(stream), decoder_params)?.decode();
-                }
-                FilterKind::RunLength => todo!(),
-                FilterKind::CcittFax => todo!(),
-                FilterKind::Jbig2 => todo!(),
-                FilterKind::Dct => stream = DctDecoder::new(Cow::Owned(stream)).decode()?,
-                FilterKind::Jpx => todo!(),
-                FilterKind::Crypt => todo!(),
+    Ok(Cow::Borrowed(stream))
+}
+
+/// The data of a stream, decoded as it is read
+///
+/// Flate, LZW, RunLength, ASCIIHex and ASCII85 data, along with any
+/// predictor, is decoded a little at a time, so that streams of any size may
+/// be read in bounded memory. The image filters, CCITTFax, DCT and JPX, need
+/// all of their data at once, so they are applied when the reader is made, to
+/// all of the data read through the filters before them, and the decoded
+/// image is held in memory.
+pub(crate) fn decode_stream_reader<'a, 'b>(
+    stream: &'b [u8],
+    stream_dict: &StreamDict<'a>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Box<dyn Read + 'b>> {
+    let mut reader: Box<dyn Read + 'b> = Box::new(stream);
+
+    for (idx, filter) in stream_dict.filter.iter().flatten().enumerate() {
+        let mut decode_params = filter_params(stream_dict, idx);
+
+        reader = match filter {
+            FilterKind::AsciiHex => Box::new(ascii::AsciiHexReader::new(reader)),
+            FilterKind::Ascii85 => Box::new(ascii::Ascii85Reader::new(reader)),
+            FilterKind::RunLength => Box::new(run_length::RunLengthReader::new(reader)),
+            FilterKind::Lzw => {
+                let early_change = decode_params
+                    .get::<i32>("EarlyChange", resolver)?
+                    .unwrap_or(1);
+
+                let predictor =
+                    PredictorParams::from_obj(Object::Dictionary(decode_params), resolver)?;
+
+                predicted(
+                    Box::new(LzwReader::new(reader, early_change != 0)),
+                    predictor,
+                )
             }
+            FilterKind::Flate => {
+                let predictor =
+                    PredictorParams::from_obj(Object::Dictionary(decode_params), resolver)?;
+
+                predicted(Box::new(ZlibDecoder::new(reader)), predictor)
+            }
+            FilterKind::Crypt => reader,
+            filter => {
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data)?;
+
+                Box::new(Cursor::new(decode_filter(
+                    data,
+                    filter,
+                    decode_params,
+                    resolver,
+                )?))
+            }
+        };
+    }
+
+    Ok(reader)
+}
+
+fn predicted<'b>(reader: Box<dyn Read + 'b>, predictor: PredictorParams) -> Box<dyn Read + 'b> {
+    if predictor.predicts() {
+        Box::new(PredictorReader::new(reader, predictor))
+    } else {
+        reader
+    }
+}
+
+/// The parameters of the filter at `idx`
+fn filter_params<'a>(stream_dict: &StreamDict<'a>, idx: usize) -> Dictionary<'a> {
+    stream_dict
+        .decode_parms
+        .as_ref()
+        .and_then(|params| params.get(idx).cloned())
+        .unwrap_or_else(Dictionary::empty)
+}
+
+fn decode_filter<'a>(
+    stream: Vec<u8>,
+    filter: &FilterKind,
+    mut decode_params: Dictionary<'a>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Vec<u8>> {
+    Ok(match filter {
+        FilterKind::AsciiHex => ascii::decode_ascii_hex(&stream),
+        FilterKind::Ascii85 => ascii::decode_ascii_85(&stream),
+        FilterKind::Lzw => {
+            // taken from the parameters before the remaining ones are those
+            // of the predictor
+            let early_change = decode_params
+                .get::<i32>("EarlyChange", resolver)?
+                .unwrap_or(1);
+
+            let predictor = PredictorParams::from_obj(Object::Dictionary(decode_params), resolver)?;
+
+            predictor.decode(LzwDecoder::new(&stream, early_change != 0).decode()?)?
         }
+        FilterKind::Flate => {
+            let predictor = PredictorParams::from_obj(Object::Dictionary(decode_params), resolver)?;
 
-        return Ok(Cow::Owned(stream));
+            predictor.decode(FlateDecoder::new(Cow::Owned(stream))?.decode())?
+        }
+        FilterKind::RunLength => run_length::decode_run_length(&stream)?,
+        FilterKind::CcittFax => {
+            let decoder_params =
+                CcittFaxDecoderParams::from_obj(Object::Dictionary(decode_params), resolver)?;
+
+            CcittFaxDecoder::new(&stream, decoder_params).decode()?
+        }
+        FilterKind::Jbig2 => anyhow::bail!("decoding JBIG2Decode streams is not supported"),
+        #[cfg(feature = "jpeg")]
+        FilterKind::Dct => DctDecoder::new(Cow::Owned(stream)).decode()?,
+        #[cfg(not(feature = "jpeg"))]
+        FilterKind::Dct => anyhow::bail!("decoding DCTDecode streams requires the jpeg feature"),
+        #[cfg(feature = "jpx")]
+        FilterKind::Jpx => JpxDecoder::new(&stream).decode()?.data,
+        #[cfg(not(feature = "jpx"))]
+        FilterKind::Jpx => anyhow::bail!("decoding JPXDecode streams requires the jpx feature"),
+        // the security handler applies crypt filters when the stream is
+        // resolved
+        FilterKind::Crypt => stream,
+    })
+}
+
+/// Encode data with the given filters, so that decoding it applies them in the
+/// order given
+///
+/// Flate, ASCIIHex, ASCII85 and RunLength data is encoded here. DCT data is
+/// passed through as it is, and must already be a JPEG image, as no encoder
+/// for images is provided.
+pub(crate) fn encode_stream(data: &[u8], filters: &[FilterKind]) -> PdfResult<Vec<u8>> {
+    let mut data = data.to_vec();
+
+    for filter in filters.iter().rev() {
+        data = match filter {
+            FilterKind::AsciiHex => ascii::encode_ascii_hex(&data),
+            FilterKind::Ascii85 => ascii::encode_ascii_85(&data),
+            FilterKind::Flate => flate::encode_flate(&data, Compression::default())?,
+            FilterKind::RunLength => run_length::encode_run_length(&data),
+            FilterKind::Dct => {
+                // every JPEG image begins with a start of image marker
+                if !data.starts_with(&[0xff, 0xd8]) {
+                    anyhow::bail!("DCTDecode data is not a JPEG image");
+                }
+
+                data
+            }
+            FilterKind::Lzw
+            | FilterKind::CcittFax
+            | FilterKind::Jbig2
+            | FilterKind::Jpx
+            | FilterKind::Crypt => anyhow::bail!("encoding {:?} streams is not supported", filter),
+        };
     }
 
-    Ok(Cow::Borrowed(stream))
+    Ok(data)
 }
 
 #[pdf_enum]
@@ -106,3 +256,96 @@ pub enum FilterKind {
     /// was before encryption
     Crypt = "Crypt",
 }
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, io::Write};
+
+    use flate2::{write::ZlibEncoder, Compression};
+
+    use super::*;
+    use crate::{stream::Stream, version::PdfVersion, writer::PdfWriter};
+
+    #[test]
+    fn read_decoded_stream() {
+        // rows of 4 bytes, each predicted from the row above
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&[2, 1, 2, 3, 4, 2, 1, 1, 1, 1, 2, 0, 0, 0, 1])
+            .unwrap();
+
+        let hex = encoder
+            .finish()
+            .unwrap()
+            .iter()
+            .map(|byte| format!("{:02x} ", byte))
+            .collect::<String>()
+            + ">";
+
+        let mut resolver = PdfWriter::new(PdfVersion::V1_7);
+
+        let mut stream = Stream::new(hex.into_bytes(), Dictionary::empty());
+        stream.dict.filter = Some(vec![FilterKind::AsciiHex, FilterKind::Flate]);
+        stream.dict.decode_parms = Some(
+            FromObj::from_obj(
+                Object::Array(vec![
+                    Object::Null,
+                    Object::Dictionary(Dictionary::new(HashMap::from([
+                        ("Predictor".to_owned(), Object::Integer(12)),
+                        ("Columns".to_owned(), Object::Integer(4)),
+                    ]))),
+                ]),
+                &mut resolver,
+            )
+            .unwrap(),
+        );
+
+        let decoded = decode_stream(&stream.stream, &stream.dict, &mut resolver).unwrap();
+        assert_eq!(&*decoded, [1, 2, 3, 4, 2, 3, 4, 5, 2, 3, 4, 6]);
+
+        let mut reader = decode_stream_reader(&stream.stream, &stream.dict, &mut resolver).unwrap();
+
+        // read a few bytes at a time, across the rows
+        let mut read = Vec::new();
+        let mut buf = [0; 5];
+
+        loop {
+            match reader.read(&mut buf).unwrap() {
+                0 => break,
+                n => read.extend_from_slice(&buf[..n]),
+            }
+        }
+
+        assert_eq!(read, &*decoded);
+
+        stream.dict.filter = Some(vec![FilterKind::Jbig2]);
+        stream.dict.decode_parms = None;
+
+        assert!(decode_stream(&stream.stream, &stream.dict, &mut resolver).is_err());
+        assert!(decode_stream_reader(&stream.stream, &stream.dict, &mut resolver).is_err());
+    }
+
+    #[test]
+    fn encode_filters() {
+        let data = b"BT /F1 12 Tf (aaaaaaaa) Tj ET".repeat(4);
+        let filters = vec![
+            FilterKind::Ascii85,
+            FilterKind::AsciiHex,
+            FilterKind::Flate,
+            FilterKind::RunLength,
+        ];
+
+        let mut resolver = PdfWriter::new(PdfVersion::V1_7);
+
+        let stream = Stream::encoded(&data, filters, Dictionary::empty()).unwrap();
+        assert!(stream.stream.iter().all(u8::is_ascii));
+
+        let decoded = decode_stream(&stream.stream, &stream.dict, &mut resolver).unwrap();
+        assert_eq!(&*decoded, data);
+
+        // JPEG data is written as it is
+        let jpeg = [0xff, 0xd8, 0xff, 0xd9];
+        assert_eq!(encode_stream(&jpeg, &[FilterKind::Dct]).unwrap(), jpeg);
+        assert!(encode_stream(&data, &[FilterKind::Dct]).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use std::io::{self, BufReader, Read};
+
+use crate::error::PdfResult;
+
+/// Marks the end of the data
+const END_OF_DATA: u8 = 128;
+
+/// Runs and literal sequences are at most 128 bytes long
+const MAX_RUN: usize = 128;
+
+/// Decompresses data in which each run of bytes is preceded by a length byte
+///
+/// A length byte from 0 to 127 is followed by that many bytes plus one, which
+/// are copied literally, and one from 129 to 255 by a single byte which is
+/// repeated 257 minus the length times.
+pub(crate) fn decode_run_length(stream: &[u8]) -> PdfResult<Vec<u8>> {
+    let mut buffer = Vec::with_capacity(stream.len());
+
+    RunLengthReader::new(stream).read_to_end(&mut buffer)?;
+
+    Ok(buffer)
+}
+
+/// The kind of run being decoded
+#[derive(Debug, Clone, Copy)]
+enum Run {
+    /// Bytes which are copied literally from the data
+    Literal,
+
+    /// A single byte which is repeated
+    Repeat(u8),
+}
+
+/// Decodes run length data as it is read
+pub struct RunLengthReader<R> {
+    bytes: io::Bytes<BufReader<R>>,
+
+    /// The run being decoded, and how many of its bytes have not yet been read
+    run: Run,
+    remaining: usize,
+
+    done: bool,
+}
+
+impl<R: Read> RunLengthReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            bytes: BufReader::new(inner).bytes(),
+            run: Run::Literal,
+            remaining: 0,
+            done: false,
+        }
+    }
+
+    /// Begin the next run, returning whether there was one
+    fn next_run(&mut self) -> io::Result<bool> {
+        if self.done {
+            return Ok(false);
+        }
+
+        match self.bytes.next().transpose()? {
+            None | Some(END_OF_DATA) => {
+                self.done = true;
+
+                Ok(false)
+            }
+            Some(len @ 0..=127) => {
+                self.run = Run::Literal;
+                self.remaining = usize::from(len) + 1;
+
+                Ok(true)
+            }
+            Some(len) => match self.bytes.next().transpose()? {
+                Some(byte) => {
+                    self.run = Run::Repeat(byte);
+                    self.remaining = 257 - usize::from(len);
+
+                    Ok(true)
+                }
+                None => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "run length data ends before a repeated byte",
+                )),
+            },
+        }
+    }
+}
+
+impl<R: Read> Read for RunLengthReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut len = 0;
+
+        while len < buf.len() {
+            if self.remaining == 0 && !self.next_run()? {
+                break;
+            }
+
+            match self.run {
+                Run::Repeat(byte) => {
+                    let count = self.remaining.min(buf.len() - len);
+                    buf[len..len + count].fill(byte);
+
+                    len += count;
+                    self.remaining -= count;
+                }
+                Run::Literal => match self.bytes.next().transpose()? {
+                    Some(byte) => {
+                        buf[len] = byte;
+
+                        len += 1;
+                        self.remaining -= 1;
+                    }
+                    None => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "run length data ends within a literal sequence",
+                        ))
+                    }
+                },
+            }
+        }
+
+        Ok(len)
+    }
+}
+
+pub(crate) fn encode_run_length(data: &[u8]) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(data.len() + data.len() / MAX_RUN + 2);
+
+    let mut idx = 0;
+
+    while idx < data.len() {
+        let run = data[idx..]
+            .iter()
+            .take(MAX_RUN)
+            .take_while(|&&byte| byte == data[idx])
+            .count();
+
+        if run > 1 {
+            buffer.push((257 - run) as u8);
+            buffer.push(data[idx]);
+            idx += run;
+            continue;
+        }
+
+        // bytes are written literally up to the next run of at least two
+        let mut end = idx + 1;
+
+        while end < data.len() && end - idx < MAX_RUN && data.get(end + 1) != Some(&data[end]) {
+            end += 1;
+        }
+
+        buffer.push((end - idx - 1) as u8);
+        buffer.extend_from_slice(&data[idx..end]);
+        idx = end;
+    }
+
+    buffer.push(END_OF_DATA);
+
+    buffer
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn run_length() {
+        let data = [1, 2, 3, 3, 3, 3, 4];
+        let encoded = encode_run_length(&data);
+
+        assert_eq!(encoded, [1, 1, 2, 253, 3, 0, 4, 128]);
+        assert_eq!(decode_run_length(&encoded).unwrap(), data);
+
+        let data = [[7; 300].as_slice(), &(0..=255).collect::<Vec<u8>>()].concat();
+        assert_eq!(decode_run_length(&encode_run_length(&data)).unwrap(), data);
+
+        assert!(decode_run_length(&[4, 1, 2]).is_err());
+        assert!(decode_run_length(&[200]).is_err());
+
+        // reading a byte at a time gives the same data
+        let mut reader = RunLengthReader::new(&encoded[..]);
+        let mut decoded = Vec::new();
+        let mut byte = [0];
+
+        while reader.read(&mut byte).unwrap() == 1 {
+            decoded.push(byte[0]);
+        }
+
+        assert_eq!(decoded, [1, 2, 3, 3, 3, 3, 4]);
+    }
+}
@@ -8,6 +8,55 @@ pub enum CffCharset {
     Two(Vec<CharsetRangeTwo>),
 }
 
+impl CffCharset {
+    /// The glyph ID of the glyph named by the string ID `sid`, if the font
+    /// contains one
+    pub fn glyph_id(&self, sid: u16) -> Option<u16> {
+        // .notdef is always the first glyph, and is omitted from the charset
+        if sid == 0 {
+            return Some(0);
+        }
+
+        match self {
+            // in the ISOAdobe charset, glyph IDs and string IDs coincide
+            Self::IsoAdobe => (sid <= 228).then_some(sid),
+            // todo: predefined expert charsets
+            Self::Expert | Self::ExpertSubset => None,
+            Self::Zero { name_ids } => name_ids
+                .iter()
+                .position(|&id| id == sid)
+                .map(|idx| idx as u16 + 1),
+            Self::One(ranges) => {
+                let mut gid = 1;
+
+                for range in ranges {
+                    if (range.first..=range.first.saturating_add(range.count as u16)).contains(&sid)
+                    {
+                        return Some(gid + sid - range.first);
+                    }
+
+                    gid += range.count as u16 + 1;
+                }
+
+                None
+            }
+            Self::Two(ranges) => {
+                let mut gid = 1;
+
+                for range in ranges {
+                    if (range.first..=range.first.saturating_add(range.count)).contains(&sid) {
+                        return Some(gid + sid - range.first);
+                    }
+
+                    gid += range.count + 1;
+                }
+
+                None
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct CharsetRangeOne {
     pub first: u16,
@@ -21,3 +70,46 @@ pub struct CharsetRangeTwo {
     /// Number of glyphs after `first`
     pub count: u16,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn glyph_id_of_string_id() {
+        assert_eq!(CffCharset::IsoAdobe.glyph_id(34), Some(34));
+        assert_eq!(CffCharset::IsoAdobe.glyph_id(229), None);
+
+        let charset = CffCharset::Zero {
+            name_ids: vec![400, 34, 391],
+        };
+
+        assert_eq!(charset.glyph_id(0), Some(0));
+        assert_eq!(charset.glyph_id(34), Some(2));
+        assert_eq!(charset.glyph_id(35), None);
+
+        // glyphs 1 to 3, then 4 to 5
+        let charset = CffCharset::One(vec![
+            CharsetRangeOne {
+                first: 100,
+                count: 2,
+            },
+            CharsetRangeOne {
+                first: 10,
+                count: 1,
+            },
+        ]);
+
+        assert_eq!(charset.glyph_id(102), Some(3));
+        assert_eq!(charset.glyph_id(11), Some(5));
+        assert_eq!(charset.glyph_id(103), None);
+
+        let charset = CffCharset::Two(vec![CharsetRangeTwo {
+            first: 1,
+            count: 300,
+        }]);
+
+        assert_eq!(charset.glyph_id(301), Some(301));
+        assert_eq!(charset.glyph_id(302), None);
+    }
+}
@@ -1,48 +1,91 @@
 use std::collections::VecDeque;
 
-use crate::{font::Glyph, geometry::path_builder::PathBuilder, parse_binary::BinaryParser};
+use crate::{
+    data_structures::Matrix,
+    font::Glyph,
+    geometry::{path_builder::PathBuilder, Point},
+    parse_binary::BinaryParser,
+};
+
+use super::{consts::STANDARD_ENCODING, dict::PrivateDict, CffFile, CffIndex};
 
 const WARN_ON_UNIMPLEMENTED_HINT: bool = false;
 
+/// The maximum depth of nested subroutine calls
+const MAX_SUBR_DEPTH: usize = 10;
+
+/// The number of elements in the transient array used by put and get
+const TRANSIENT_ARRAY_SIZE: usize = 32;
+
 /// Evaluation engine for Type 2 charstrings
 ///
 /// https://adobe-type-tools.github.io/font-tech-notes/pdfs/5177.Type2.pdf
 pub struct CffCharStringInterpreter<'a> {
     buffer: &'a [u8],
     cursor: usize,
+    font: &'a CffFile<'a>,
+    private_dict: &'a PrivateDict,
+    local_subrs: Option<&'a CffIndex<'a>>,
+
+    /// The buffer and cursor to return to at the end of each subroutine
+    /// currently being executed
+    call_stack: Vec<(&'a [u8], usize)>,
     operand_stack: VecDeque<f32>,
+    transient_array: [f32; TRANSIENT_ARRAY_SIZE],
+    random_state: u32,
     path_builder: PathBuilder,
     width: Option<f32>,
     first_stack_clearing_op: bool,
 
+    /// Whether this charstring is the base or accent of an accented character,
+    /// in which case it may not itself be an accented character
+    is_seac_component: bool,
+
     // todo: track actual hints, not just count
     hint_count: usize,
 }
 
 impl<'a> CffCharStringInterpreter<'a> {
-    fn new(buffer: &'a [u8]) -> Self {
+    fn new(buffer: &'a [u8], font: &'a CffFile<'a>) -> Self {
         Self {
             buffer,
             cursor: 0,
+            font,
+            private_dict: &font.private_dict,
+            local_subrs: font.local_subrs.as_ref(),
+            call_stack: Vec::new(),
             operand_stack: VecDeque::new(),
+            transient_array: [0.0; TRANSIENT_ARRAY_SIZE],
+            random_state: 0x2545_f491,
             path_builder: PathBuilder::new(),
             width: None,
             first_stack_clearing_op: true,
+            is_seac_component: false,
             hint_count: 0,
         }
     }
 
-    fn maybe_calculate_width(&mut self) -> anyhow::Result<()> {
-        if self.first_stack_clearing_op && self.operand_stack.len() == 1 {
-            let width = self.pop()?;
-            self.width = Some(width);
+    /// The first stack-clearing operator of a charstring may take an
+    /// additional argument, preceding all others, which gives the advance
+    /// width of the glyph as a difference from nominalWidthX. If it is absent,
+    /// the width is defaultWidthX
+    ///
+    /// `has_width` indicates whether the operand stack contains this argument.
+    fn maybe_take_width(&mut self, has_width: bool) -> anyhow::Result<()> {
+        if !self.first_stack_clearing_op {
+            return Ok(());
         }
 
         self.first_stack_clearing_op = false;
 
-        anyhow::ensure!(self.operand_stack.is_empty());
+        let width = if has_width {
+            self.private_dict.nominal_width_x + self.pop_front()?
+        } else {
+            self.private_dict.default_width_x
+        };
 
-        self.operand_stack.clear();
+        self.width = Some(width);
+        self.path_builder.width_vector = Point::new(width, 0.0);
 
         Ok(())
     }
@@ -106,9 +149,10 @@ impl<'a> CffCharStringInterpreter<'a> {
                 let n = -(b0 - 251) * 256 - b1 - 108;
                 self.push(n as f32);
             }
+            // a 16.16 fixed point number
             255 => {
                 let n = self.parse_i32()?;
-                self.push(n as f32);
+                self.push(n as f32 / 65536.0);
             }
             _ => anyhow::bail!("invalid charstring operator: {:?}", b0),
         }
@@ -116,124 +160,287 @@ impl<'a> CffCharStringInterpreter<'a> {
         Ok(())
     }
 
-    pub fn evaluate(buffer: &'a [u8]) -> anyhow::Result<Glyph> {
-        let mut parser = Self::new(buffer);
+    pub fn evaluate(buffer: &'a [u8], font: &'a CffFile<'a>) -> anyhow::Result<Glyph> {
+        let mut parser = Self::new(buffer, font);
+
+        parser.run()?;
+
+        Ok(Glyph {
+            outline: parser.path_builder.outline.clone(),
+            width_vector: parser.path_builder.width_vector,
+        })
+    }
+
+    /// Evaluate the charstring of the glyph `gid`. In a CID-keyed font, this
+    /// uses the Private DICT and local subrs of the Font DICT selected for the
+    /// glyph by FDSelect
+    pub fn evaluate_glyph(gid: u16, font: &'a CffFile<'a>) -> anyhow::Result<Glyph> {
+        let buffer = match font.charstring_index.get(gid as usize) {
+            Some(buffer) => buffer,
+            None => return Ok(Glyph::empty()),
+        };
+
+        let mut parser = Self::new(buffer, font);
+
+        (parser.private_dict, parser.local_subrs) = font.glyph_private_dict(gid);
 
-        while parser.peek().is_some() {
-            match parser.next()? {
+        parser.run()?;
+
+        Ok(Glyph {
+            outline: parser.path_builder.outline.clone(),
+            width_vector: parser.path_builder.width_vector,
+        })
+    }
+
+    fn run(&mut self) -> anyhow::Result<()> {
+        loop {
+            if self.peek().is_none() {
+                // a subroutine may end without a return if it ends in endchar,
+                // but we are lenient and treat the end of any subroutine as a
+                // return
+                if self.call_stack.is_empty() {
+                    break;
+                }
+
+                self.return_from_subr()?;
+                continue;
+            }
+
+            match self.next()? {
                 // y dy {dya dyb}* hstem (1)
-                1 => parser.hstem()?,
+                1 => self.hstem()?,
                 // x dx {dxa dxb}* vstem (3)
-                3 => parser.vstem()?,
+                3 => self.vstem()?,
                 // dy1 vmoveto (4)
-                4 => parser.vmoveto()?,
+                4 => self.vmoveto()?,
                 // {dxa dya}+ rlineto (5)
-                5 => parser.rlineto()?,
+                5 => self.rlineto()?,
                 // dx1 {dya dxb}* hlineto (6)
                 // {dxa dyb}+ hlineto (6)
-                6 => parser.hlineto()?,
+                6 => self.hlineto()?,
                 // dy1 {dxa dyb}* vlineto (7)
                 // {dya dxb}+ vlineto (7)
-                7 => parser.vlineto()?,
+                7 => self.vlineto()?,
                 // {dxa dya dxb dyb dxc dyc}+ rrcurveto (8)
-                8 => parser.rrcurveto()?,
+                8 => self.rrcurveto()?,
                 // subr# callsubr (10) –
-                10 => parser.callsubr()?,
+                10 => self.callsubr()?,
                 // – return (11) –
-                11 => todo!(),
-                12 => match parser.next()? {
+                11 => self.return_from_subr()?,
+                12 => match self.next()? {
                     // num1 num2 and (12 3) 1_or_0
-                    3 => todo!(),
+                    3 => self.binary_op(|a, b| bool_to_num(a != 0.0 && b != 0.0))?,
                     // num1 num2 or (12 4) 1_or_0
-                    4 => todo!(),
+                    4 => self.binary_op(|a, b| bool_to_num(a != 0.0 || b != 0.0))?,
                     // num1 not (12 5) 1_or_0
-                    5 => todo!(),
+                    5 => self.unary_op(|a| bool_to_num(a == 0.0))?,
                     // num abs (12 9) num2
-                    9 => todo!(),
+                    9 => self.unary_op(f32::abs)?,
                     // num1 num2 add (12 10) sum
-                    10 => todo!(),
+                    10 => self.binary_op(|a, b| a + b)?,
                     // num1 num2 sub (12 11) difference
-                    11 => todo!(),
+                    11 => self.binary_op(|a, b| a - b)?,
                     // num1 num2 div (12 12) quotient
-                    12 => todo!(),
+                    12 => self.binary_op(|a, b| a / b)?,
                     // num neg (12 14) num2
-                    14 => todo!(),
+                    14 => self.unary_op(|a| -a)?,
                     // num1 num2 eq (12 15) 1_or_0
-                    15 => todo!(),
+                    15 => self.binary_op(|a, b| bool_to_num(a == b))?,
                     // num drop (12 18)
-                    18 => todo!(),
+                    18 => {
+                        self.pop()?;
+                    }
                     // val i put (12 20)
-                    20 => todo!(),
+                    20 => self.put()?,
                     // i get (12 21) val
-                    21 => todo!(),
+                    21 => self.get()?,
                     // s1 s2 v1 v2 ifelse (12 22) s1_or_s2
-                    22 => todo!(),
+                    22 => self.ifelse()?,
                     // random (12 23) num2
-                    23 => todo!(),
+                    23 => {
+                        let n = self.random();
+                        self.push(n);
+                    }
                     // num1 num2 mul (12 24) product
-                    24 => todo!(),
+                    24 => self.binary_op(|a, b| a * b)?,
                     // num sqrt (12 26) num2
-                    26 => todo!(),
+                    26 => self.unary_op(f32::sqrt)?,
                     // any dup (12 27) any any
-                    27 => todo!(),
+                    27 => {
+                        let n = self.pop()?;
+                        self.push(n);
+                        self.push(n);
+                    }
                     // num1 num2 exch (12 28) num2 num1
-                    28 => todo!(),
+                    28 => {
+                        let num2 = self.pop()?;
+                        let num1 = self.pop()?;
+                        self.push(num2);
+                        self.push(num1);
+                    }
                     // numX ... num0 i index (12 29) numX ... num0 num
-                    29 => todo!(),
+                    29 => self.index()?,
                     // num(N–1) ... num0 N J roll (12 30) num((J–1) mod N) ... num0
                     // num(N–1) ... num(J mod N)
-                    30 => todo!(),
+                    30 => self.roll()?,
                     // dx1 dx2 dy2 dx3 dx4 dx5 dx6 hflex (12 34)
-                    34 => parser.hflex()?,
+                    34 => self.hflex()?,
                     // dx1 dy1 dx2 dy2 dx3 dy3 dx4 dy4 dx5 dy5 dx6 dy6 fd flex (12 35)
-                    35 => parser.flex()?,
+                    35 => self.flex()?,
                     // dx1 dy1 dx2 dy2 dx3 dx4 dx5 dy5 dx6 hflex1 (12 36)
-                    36 => parser.hflex1()?,
+                    36 => self.hflex1()?,
                     // dx1 dy1 dx2 dy2 dx3 dy3 dx4 dy4 dx5 dy5 d6 flex1 (12 37)
-                    37 => parser.flex1()?,
+                    37 => self.flex1()?,
                     b => anyhow::bail!("invalid top dict operator: 12 {:?}", b),
                 },
                 // – endchar (14)
-                14 => parser.end_char()?,
+                14 => {
+                    self.end_char()?;
+                    break;
+                }
                 // y dy {dya dyb}* hstemhm (18)
-                18 => parser.hstemhm()?,
+                18 => self.hstemhm()?,
                 // hintmask (19 + mask)
-                19 => parser.hintmask()?,
+                19 => self.hintmask()?,
                 // cntrmask (20 + mask)
-                20 => parser.cntrmask()?,
+                20 => self.cntrmask()?,
                 // dx1 dy1 rmoveto (21)
-                21 => parser.rmoveto()?,
+                21 => self.rmoveto()?,
                 // dx1 hmoveto (22)
-                22 => parser.hmoveto()?,
+                22 => self.hmoveto()?,
                 // x dx {dxa dxb}* vstemhm (23)
-                23 => parser.vstemhm()?,
+                23 => self.vstemhm()?,
                 // {dxa dya dxb dyb dxc dyc}+ dxd dyd rcurveline (24)
-                24 => parser.rcurveline()?,
+                24 => self.rcurveline()?,
                 // {dxa dya}+ dxb dyb dxc dyc dxd dyd rlinecurve (25)
-                25 => parser.rlinecurve()?,
+                25 => self.rlinecurve()?,
                 // dx1? {dya dxb dyb dyc}+ vvcurveto (26)
-                26 => parser.vvcurveto()?,
+                26 => self.vvcurveto()?,
                 // dy1? {dxa dxb dyb dxc}+ hhcurveto (27)
-                27 => parser.hhcurveto()?,
+                27 => self.hhcurveto()?,
                 // globalsubr# callgsubr (29) –
-                29 => parser.callgsubr()?,
+                29 => self.callgsubr()?,
                 // dy1 dx2 dy2 dx3 {dxa dxb dyb dyc dyd dxe dye dxf}* dyf? vhcurveto (30)
                 // {dya dxb dyb dxc dxd dxe dye dyf}+ dxf? vhcurveto (30)
-                30 => parser.vhcurveto()?,
+                30 => self.vhcurveto()?,
                 // dx1 dx2 dy2 dy3 {dya dxb dyb dxc dxd dxe dye dyf}* dxf? hvcurveto (31)
                 // {dxa dxb dyb dyc dyd dxe dye dxf}+ dyf? hvcurveto (31)
-                31 => parser.hvcurveto()?,
+                31 => self.hvcurveto()?,
+                28 => self.parse_number(28)?,
                 b @ 0..=31 => anyhow::bail!("unimplemented operator: {:?}", b),
-                b0 => parser.parse_number(b0)?,
+                b0 => self.parse_number(b0)?,
             }
         }
 
-        anyhow::ensure!(parser.operand_stack.is_empty());
+        anyhow::ensure!(self.operand_stack.is_empty());
 
-        Ok(Glyph {
-            outline: parser.path_builder.outline.clone(),
-            width_vector: parser.path_builder.width_vector,
-        })
+        Ok(())
+    }
+
+    fn unary_op(&mut self, op: impl FnOnce(f32) -> f32) -> anyhow::Result<()> {
+        let a = self.pop()?;
+        self.push(op(a));
+        Ok(())
+    }
+
+    fn binary_op(&mut self, op: impl FnOnce(f32, f32) -> f32) -> anyhow::Result<()> {
+        let b = self.pop()?;
+        let a = self.pop()?;
+        self.push(op(a, b));
+        Ok(())
+    }
+
+    fn transient_array_idx(&mut self) -> anyhow::Result<usize> {
+        let i = self.pop()?;
+
+        anyhow::ensure!(
+            i >= 0.0 && (i as usize) < TRANSIENT_ARRAY_SIZE,
+            "transient array index out of bounds: {}",
+            i
+        );
+
+        Ok(i as usize)
+    }
+
+    /// stores val into the transient array at the location given by i
+    fn put(&mut self) -> anyhow::Result<()> {
+        let i = self.transient_array_idx()?;
+        let val = self.pop()?;
+
+        self.transient_array[i] = val;
+
+        Ok(())
+    }
+
+    /// retrieves the value stored in the transient array at the location given
+    /// by i and pushes the value onto the argument stack
+    fn get(&mut self) -> anyhow::Result<()> {
+        let i = self.transient_array_idx()?;
+
+        self.push(self.transient_array[i]);
+
+        Ok(())
+    }
+
+    /// leaves the value s1 on the stack if v1 <= v2, or leaves s2 on the stack
+    /// if v1 > v2
+    fn ifelse(&mut self) -> anyhow::Result<()> {
+        let v2 = self.pop()?;
+        let v1 = self.pop()?;
+        let s2 = self.pop()?;
+        let s1 = self.pop()?;
+
+        self.push(if v1 <= v2 { s1 } else { s2 });
+
+        Ok(())
+    }
+
+    /// returns a pseudo random number num2 in the range (0,1], that is, greater
+    /// than zero and less than or equal to one
+    fn random(&mut self) -> f32 {
+        // xorshift32
+        self.random_state ^= self.random_state << 13;
+        self.random_state ^= self.random_state >> 17;
+        self.random_state ^= self.random_state << 5;
+
+        (self.random_state as f32 / u32::MAX as f32).max(f32::EPSILON)
+    }
+
+    /// retrieves the element i from the top of the argument stack and pushes a
+    /// copy of that element onto that stack. If i is negative, the top element
+    /// is copied
+    fn index(&mut self) -> anyhow::Result<()> {
+        let i = self.pop()?.max(0.0) as usize;
+
+        let n = match self.operand_stack.len().checked_sub(i + 1) {
+            Some(idx) => self.operand_stack[idx],
+            None => anyhow::bail!("stack underflow"),
+        };
+
+        self.push(n);
+
+        Ok(())
+    }
+
+    /// performs a circular shift of the elements num(N–1) ... num0 on the
+    /// argument stack by the amount J. Positive J indicates upward motion of the
+    /// stack; negative J indicates downward motion
+    fn roll(&mut self) -> anyhow::Result<()> {
+        let j = self.pop()? as i32;
+        let n = self.pop()? as i32;
+
+        anyhow::ensure!(n >= 0, "invalid roll count: {}", n);
+
+        let start = match self.operand_stack.len().checked_sub(n as usize) {
+            Some(start) => start,
+            None => anyhow::bail!("stack underflow"),
+        };
+
+        if n > 0 {
+            self.operand_stack.make_contiguous()[start..].rotate_right(j.rem_euclid(n) as usize);
+        }
+
+        Ok(())
     }
 
     /// specifies one or more horizontal stem hints. This allows multiple pairs
@@ -257,6 +464,8 @@ impl<'a> CffCharStringInterpreter<'a> {
     /// of non-overlapping hints.
     #[allow(unused_variables)]
     fn hstem(&mut self) -> anyhow::Result<()> {
+        self.maybe_take_width(self.operand_stack.len() % 2 == 1)?;
+
         let y = self.pop_front()?;
         let dy = self.pop_front()?;
         self.hint_count += 1;
@@ -270,7 +479,7 @@ impl<'a> CffCharStringInterpreter<'a> {
         if WARN_ON_UNIMPLEMENTED_HINT {
             println!("unimplemented cff charstring hinting op: hstem");
         }
-        self.maybe_calculate_width()?;
+        self.operand_stack.clear();
         Ok(())
     }
 
@@ -278,6 +487,8 @@ impl<'a> CffCharStringInterpreter<'a> {
     /// hstem if the charstring contains one or more hintmask operators
     #[allow(unused_variables)]
     fn hstemhm(&mut self) -> anyhow::Result<()> {
+        self.maybe_take_width(self.operand_stack.len() % 2 == 1)?;
+
         let y = self.pop_front()?;
         let dy = self.pop_front()?;
         self.hint_count += 1;
@@ -291,7 +502,7 @@ impl<'a> CffCharStringInterpreter<'a> {
         if WARN_ON_UNIMPLEMENTED_HINT {
             println!("unimplemented cff charstring hinting op: hstemhm");
         }
-        self.maybe_calculate_width()?;
+        self.operand_stack.clear();
         Ok(())
     }
 
@@ -299,6 +510,8 @@ impl<'a> CffCharStringInterpreter<'a> {
     /// vstem if the charstring contains one or more hintmask operators
     #[allow(unused_variables)]
     fn vstemhm(&mut self) -> anyhow::Result<()> {
+        self.maybe_take_width(self.operand_stack.len() % 2 == 1)?;
+
         let x = self.pop_front()?;
         let dx = self.pop_front()?;
         self.hint_count += 1;
@@ -312,7 +525,7 @@ impl<'a> CffCharStringInterpreter<'a> {
         if WARN_ON_UNIMPLEMENTED_HINT {
             println!("unimplemented cff charstring hinting op: vstemhm");
         }
-        self.maybe_calculate_width()?;
+        self.operand_stack.clear();
         Ok(())
     }
 
@@ -355,7 +568,7 @@ impl<'a> CffCharStringInterpreter<'a> {
         if WARN_ON_UNIMPLEMENTED_HINT {
             println!("unimplemented cff charstring hinting op: hintmask");
         }
-        self.maybe_calculate_width()?;
+        self.maybe_take_width(false)?;
         Ok(())
     }
 
@@ -367,6 +580,9 @@ impl<'a> CffCharStringInterpreter<'a> {
     /// stem hints. Bits set to 1 in the first cntrmask command have top
     /// priority; subsequent cntrmask commands specify lower priority counters
     fn cntrmask(&mut self) -> anyhow::Result<()> {
+        if !self.operand_stack.is_empty() {
+            self.vstem()?;
+        }
         let num_bytes = (self.hint_count as f32 / 8.0).ceil() as u32;
         // println!("parser.cntrmask()?;");
         for _ in 0..num_bytes {
@@ -375,7 +591,7 @@ impl<'a> CffCharStringInterpreter<'a> {
         if WARN_ON_UNIMPLEMENTED_HINT {
             println!("unimplemented cff charstring hinting op: cntrmask");
         }
-        self.maybe_calculate_width()?;
+        self.maybe_take_width(false)?;
         Ok(())
     }
 
@@ -399,6 +615,8 @@ impl<'a> CffCharStringInterpreter<'a> {
     /// hints
     #[allow(unused_variables)]
     fn vstem(&mut self) -> anyhow::Result<()> {
+        self.maybe_take_width(self.operand_stack.len() % 2 == 1)?;
+
         let x = self.pop_front()?;
         let dx = self.pop_front()?;
         self.hint_count += 1;
@@ -412,7 +630,7 @@ impl<'a> CffCharStringInterpreter<'a> {
         if WARN_ON_UNIMPLEMENTED_HINT {
             println!("unimplemented cff charstring hinting op: vstem");
         }
-        self.maybe_calculate_width()?;
+        self.operand_stack.clear();
         Ok(())
     }
 
@@ -452,14 +670,18 @@ impl<'a> CffCharStringInterpreter<'a> {
         let dy5 = self.pop_front()?;
         let d6 = self.pop_front()?;
 
-        // todo: should be condition here
-        let (dx6, dy6) = (d6, d6);
+        let dx = dx1 + dx2 + dx3 + dx4 + dx5;
+        let dy = dy1 + dy2 + dy3 + dy4 + dy5;
+
+        let (dx6, dy6) = if dx.abs() > dy.abs() {
+            (d6, -dy)
+        } else {
+            (-dx, d6)
+        };
 
         self.path_builder
             .relative_relative_curve_to(dx4, dy4, dx5, dy5, dx6, dy6);
 
-        println!("unimplemented cff charstring op: flex1");
-
         // println!("parser.flex1()?;");
 
         self.operand_stack.clear();
@@ -696,10 +918,12 @@ impl<'a> CffCharStringInterpreter<'a> {
 
     /// moves the current point dy1 units in the vertical direction
     fn vmoveto(&mut self) -> anyhow::Result<()> {
+        self.maybe_take_width(self.operand_stack.len() > 1)?;
+
         let dy1 = self.pop_front()?;
         self.path_builder.relative_move_to(0.0, dy1);
 
-        self.maybe_calculate_width()?;
+        self.operand_stack.clear();
         Ok(())
     }
 
@@ -780,23 +1004,27 @@ impl<'a> CffCharStringInterpreter<'a> {
 
     /// moves the current point dx1 units in the horizontal direction
     fn hmoveto(&mut self) -> anyhow::Result<()> {
+        self.maybe_take_width(self.operand_stack.len() > 1)?;
+
         let dx1 = self.pop_front()?;
 
         self.path_builder.relative_move_to(dx1, 0.0);
 
-        self.maybe_calculate_width()?;
+        self.operand_stack.clear();
         Ok(())
     }
 
     /// moves the current point to a position at the relative coordinates (dx1, dy1).
     fn rmoveto(&mut self) -> anyhow::Result<()> {
         // println!("parser.rmoveto()?;");
+        self.maybe_take_width(self.operand_stack.len() > 2)?;
+
         let dx = self.pop_front()?;
         let dy = self.pop_front()?;
 
         self.path_builder.relative_move_to(dx, dy);
 
-        self.maybe_calculate_width()?;
+        self.operand_stack.clear();
         Ok(())
     }
 
@@ -1025,13 +1253,53 @@ impl<'a> CffCharStringInterpreter<'a> {
     /// that are repeated throughout the font program, for example, serif outline
     /// sequences
     fn callsubr(&mut self) -> anyhow::Result<()> {
-        todo!()
+        match self.local_subrs {
+            Some(subrs) => self.call_subr(subrs),
+            None => anyhow::bail!("callsubr used without local subrs"),
+        }
     }
 
     /// operates in the same manner as callsubr except that it calls a global
     /// subroutine
     fn callgsubr(&mut self) -> anyhow::Result<()> {
-        todo!()
+        let font = self.font;
+
+        self.call_subr(&font.global_subrs)
+    }
+
+    fn call_subr(&mut self, subrs: &'a CffIndex<'a>) -> anyhow::Result<()> {
+        let idx = self.pop()? as i32 + subr_bias(subrs);
+
+        let subr = match usize::try_from(idx).ok().and_then(|idx| subrs.get(idx)) {
+            Some(subr) => subr,
+            None => anyhow::bail!("invalid subr index: {}", idx),
+        };
+
+        anyhow::ensure!(
+            self.call_stack.len() < MAX_SUBR_DEPTH,
+            "subr nesting limit exceeded"
+        );
+
+        self.call_stack.push((self.buffer, self.cursor));
+
+        self.buffer = subr;
+        self.cursor = 0;
+
+        Ok(())
+    }
+
+    /// returns from either a local or global charstring subroutine, and
+    /// continues execution after the corresponding call(g)subr
+    fn return_from_subr(&mut self) -> anyhow::Result<()> {
+        match self.call_stack.pop() {
+            Some((buffer, cursor)) => {
+                self.buffer = buffer;
+                self.cursor = cursor;
+            }
+            None => anyhow::bail!("return used outside of subr"),
+        }
+
+        Ok(())
     }
 
     /// appends one or more Bézier curves, as described by the dxa...dxc set of
@@ -1080,18 +1348,99 @@ impl<'a> CffCharStringInterpreter<'a> {
 
     /// finishes a charstring outline definition, and must be the last operator in a
     /// character's outline
+    ///
+    /// With four arguments, adx ady bchar achar, endchar instead builds an
+    /// accented character, in the manner of the Type 1 seac operator
     fn end_char(&mut self) -> anyhow::Result<()> {
+        self.maybe_take_width(matches!(self.operand_stack.len(), 1 | 5))?;
+
         if !self.path_builder.current_path.subpaths.is_empty() {
             self.path_builder.close_path();
         }
         // println!("parser.end_char()?;");
 
-        self.maybe_calculate_width()?;
+        if self.operand_stack.len() == 4 {
+            let adx = self.pop_front()?;
+            let ady = self.pop_front()?;
+            let bchar = self.pop_front()?;
+            let achar = self.pop_front()?;
 
-        anyhow::ensure!(self.peek().is_none(), "end char should be last op");
+            self.seac(adx, ady, bchar, achar)?;
+        }
+
+        anyhow::ensure!(self.operand_stack.is_empty());
 
         Ok(())
     }
+
+    /// Build an accented character from two glyphs of the font, identified by
+    /// their codes in the Adobe StandardEncoding, placing the origin of the
+    /// accent at (adx, ady) relative to the origin of the base
+    fn seac(&mut self, adx: f32, ady: f32, bchar: f32, achar: f32) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            !self.is_seac_component,
+            "accented character used as a component of an accented character"
+        );
+
+        let base = self.standard_encoding_glyph(bchar)?;
+        let mut accent = self.standard_encoding_glyph(achar)?;
+
+        accent
+            .outline
+            .apply_transform(Matrix::new_translation(adx, ady));
+
+        self.path_builder.outline.paths.extend(base.outline.paths);
+        self.path_builder.outline.paths.extend(accent.outline.paths);
+
+        Ok(())
+    }
+
+    fn standard_encoding_glyph(&self, code: f32) -> anyhow::Result<Glyph> {
+        let sid = match STANDARD_ENCODING.get(code as usize) {
+            Some(&sid) if sid != 0 => sid,
+            _ => anyhow::bail!("invalid standard encoding code: {}", code),
+        };
+
+        let cs = match self
+            .font
+            .charset
+            .glyph_id(sid)
+            .and_then(|gid| self.font.charstring_index.get(gid as usize))
+        {
+            Some(cs) => cs,
+            None => anyhow::bail!("no glyph found for standard encoding code: {}", code),
+        };
+
+        let mut parser = Self::new(cs, self.font);
+        parser.is_seac_component = true;
+        parser.run()?;
+
+        Ok(Glyph {
+            outline: parser.path_builder.outline,
+            width_vector: parser.path_builder.width_vector,
+        })
+    }
+}
+
+/// Subroutine numbers are biased by an amount depending on the number of
+/// subroutines, allowing the most frequently used ones to be called with the
+/// smallest encodings
+fn subr_bias(subrs: &CffIndex) -> i32 {
+    if subrs.count < 1240 {
+        107
+    } else if subrs.count < 33900 {
+        1131
+    } else {
+        32768
+    }
+}
+
+fn bool_to_num(b: bool) -> f32 {
+    if b {
+        1.0
+    } else {
+        0.0
+    }
 }
 
 impl<'a> BinaryParser for CffCharStringInterpreter<'a> {
@@ -1105,3 +1454,100 @@ impl<'a> BinaryParser for CffCharStringInterpreter<'a> {
         &mut self.cursor
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        font::cff::{dict::TopDict, CffCharset, CffEncoding},
+        geometry::Subpath,
+    };
+
+    /// An INDEX of consecutive items of `data` with the given lengths
+    fn index<'a>(data: &'a [u8], lengths: &[u32]) -> CffIndex<'a> {
+        let mut offset = vec![1];
+
+        for length in lengths {
+            offset.push(offset.last().unwrap() + length);
+        }
+
+        CffIndex {
+            count: lengths.len() as u16,
+            offsize: 1,
+            offset,
+            data,
+        }
+    }
+
+    #[test]
+    fn subroutines_and_arithmetic() {
+        let charstrings = [
+            // 50 10 20 rmoveto -107 callsubr -107 callgsubr
+            189, 149, 159, 21, 32, 10, 32, 29, //
+            // 60 40 add neg hlineto endchar
+            199, 179, 12, 10, 12, 14, 6, 14, //
+            // endchar
+            14,
+        ];
+
+        // 100 0 rlineto return
+        let local_subrs = [239, 139, 5, 11];
+
+        // 100 vlineto return
+        let global_subrs = [239, 7, 11];
+
+        let font = CffFile {
+            name_index: index(&[], &[]),
+            top_dict: TopDict::default(),
+            string_index: index(&[], &[]),
+            global_subrs: index(&global_subrs, &[3]),
+            private_dict: PrivateDict {
+                default_width_x: 500.0,
+                nominal_width_x: 100.0,
+                ..PrivateDict::default()
+            },
+            local_subrs: Some(index(&local_subrs, &[4])),
+            charstring_index: index(&charstrings, &[16, 1]),
+            encoding: CffEncoding::Standard,
+            charset: CffCharset::IsoAdobe,
+            fd_array: Vec::new(),
+            fd_select: None,
+        };
+
+        let glyph = CffCharStringInterpreter::evaluate_glyph(0, &font).unwrap();
+
+        // the width is relative to nominalWidthX
+        assert_eq!(glyph.width_vector, Point::new(150.0, 0.0));
+
+        let end_points = glyph.outline.paths[0]
+            .subpaths
+            .iter()
+            .map(|subpath| match subpath {
+                Subpath::Line(line) => line.end,
+                subpath => panic!("expected a line, found {:?}", subpath),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(glyph.outline.paths.len(), 1);
+        assert_eq!(
+            end_points,
+            [
+                Point::new(110.0, 20.0),
+                Point::new(110.0, 120.0),
+                Point::new(10.0, 120.0),
+                Point::new(10.0, 20.0),
+            ]
+        );
+
+        let glyph = CffCharStringInterpreter::evaluate_glyph(1, &font).unwrap();
+
+        assert_eq!(glyph.width_vector, Point::new(500.0, 0.0));
+        assert!(glyph.outline.paths.is_empty());
+
+        assert!(CffCharStringInterpreter::evaluate_glyph(2, &font)
+            .unwrap()
+            .outline
+            .paths
+            .is_empty());
+    }
+}
@@ -28,6 +28,24 @@ pub(crate) struct TopDict {
     pub post_script: Option<u16>,
     pub base_font_name: Option<u16>,
     pub base_font_blend: Option<Vec<f32>>,
+
+    /// The Registry, Ordering, and Supplement of a CID-keyed font. The
+    /// registry and ordering are SIDs
+    pub ros: Option<[f32; 3]>,
+    pub cid_font_version: f32,
+    pub cid_font_revision: f32,
+    pub cid_font_type: f32,
+    pub cid_count: u32,
+    pub uid_base: Option<f32>,
+
+    /// Offset (0) of the Font DICT INDEX of a CID-keyed font
+    pub fd_array: Option<u32>,
+
+    /// Offset (0) of the FDSelect structure of a CID-keyed font
+    pub fd_select: Option<u32>,
+
+    /// The name of a Font DICT within a CID-keyed font
+    pub font_name: Option<u16>,
 }
 
 impl Default for TopDict {
@@ -58,12 +76,21 @@ impl Default for TopDict {
             post_script: None,
             base_font_name: None,
             base_font_blend: None,
+            ros: None,
+            cid_font_version: 0.0,
+            cid_font_revision: 0.0,
+            cid_font_type: 0.0,
+            cid_count: 8720,
+            uid_base: None,
+            fd_array: None,
+            fd_select: None,
+            font_name: None,
         }
     }
 }
 
 #[derive(Debug)]
-pub(super) struct PrivateDict {
+pub(crate) struct PrivateDict {
     pub blue_values: Option<Vec<f32>>,
     pub other_blues: Option<Vec<f32>>,
     pub family_blues: Option<Vec<f32>>,
@@ -138,8 +165,18 @@ impl<'a> CffDictInterpreter<'a> {
         std::mem::take(&mut self.operand_stack)
     }
 
+    /// An array of numbers, where the first is absolute and each subsequent
+    /// number is relative to the previous one
     fn pop_delta(&mut self) -> Vec<f32> {
-        todo!()
+        let mut value = 0.0;
+
+        self.pop_arr()
+            .into_iter()
+            .map(|delta| {
+                value += delta;
+                value
+            })
+            .collect()
     }
 
     fn pop_bool(&mut self) -> anyhow::Result<bool> {
@@ -200,12 +237,39 @@ impl<'a> CffDictInterpreter<'a> {
                 let n = (b1 << 24) | (b2 << 16) | (b3 << 8) | b4;
                 self.push(n as f32);
             }
+            30 => {
+                let n = self.parse_real()?;
+                self.push(n);
+            }
             _ => anyhow::bail!("invalid dict operator: {:?}", b0),
         }
 
         Ok(())
     }
 
+    /// A real number operand is encoded as a sequence of nibbles, each
+    /// representing a digit, decimal point, exponent, or minus sign, and
+    /// terminated by the nibble 0xf
+    fn parse_real(&mut self) -> anyhow::Result<f32> {
+        let mut s = String::new();
+
+        loop {
+            let b = self.next()?;
+
+            for nibble in [b >> 4, b & 0xf] {
+                match nibble {
+                    0..=9 => s.push((b'0' + nibble) as char),
+                    0xa => s.push('.'),
+                    0xb => s.push('E'),
+                    0xc => s.push_str("E-"),
+                    0xe => s.push('-'),
+                    0xf => return Ok(s.parse()?),
+                    _ => anyhow::bail!("invalid real number nibble: {:?}", nibble),
+                }
+            }
+        }
+    }
+
     pub fn parse_top_dict(buffer: &'a [u8]) -> anyhow::Result<TopDict> {
         let mut parser = Self::new(buffer);
         let mut dict = TopDict::default();
@@ -232,9 +296,15 @@ impl<'a> CffDictInterpreter<'a> {
                     21 => dict.post_script = Some(parser.pop_u16()?),
                     22 => dict.base_font_name = Some(parser.pop_u16()?),
                     23 => dict.base_font_blend = Some(parser.pop_delta()),
-                    b @ 30..=36 => {
-                        anyhow::bail!("unimplemented CID CFF top dict operator: 12 {:?}", b)
-                    }
+                    30 => dict.ros = Some(parser.pop_arr().try_into().unwrap()),
+                    31 => dict.cid_font_version = parser.pop()?,
+                    32 => dict.cid_font_revision = parser.pop()?,
+                    33 => dict.cid_font_type = parser.pop()?,
+                    34 => dict.cid_count = parser.pop_u32()?,
+                    35 => dict.uid_base = Some(parser.pop()?),
+                    36 => dict.fd_array = Some(parser.pop_u32()?),
+                    37 => dict.fd_select = Some(parser.pop_u32()?),
+                    38 => dict.font_name = Some(parser.pop_u16()?),
                     b => anyhow::bail!("invalid top dict operator: 12 {:?}", b),
                 },
                 13 => dict.unique_id = Some(parser.pop()?),
@@ -270,9 +340,17 @@ impl<'a> CffDictInterpreter<'a> {
                     9 => dict.blue_scale = parser.pop()?,
                     10 => dict.blue_shift = parser.pop()?,
                     11 => dict.blue_fuzz = parser.pop()?,
+                    12 => dict.stem_snap_h = Some(parser.pop_delta()),
+                    13 => dict.stem_snap_v = Some(parser.pop_delta()),
+                    14 => dict.force_bold = parser.pop_bool()?,
+                    17 => dict.language_group = parser.pop()?,
+                    18 => dict.expansion_factor = parser.pop()?,
+                    19 => dict.initial_random_seed = parser.pop()?,
                     b => anyhow::bail!("invalid private dict operator: 12 {:?}", b),
                 },
                 19 => dict.subrs = Some(parser.pop()?),
+                20 => dict.default_width_x = parser.pop()?,
+                21 => dict.nominal_width_x = parser.pop()?,
                 b0 => parser.parse_number(b0)?,
             }
         }
@@ -294,3 +372,36 @@ impl<'a> BinaryParser for CffDictInterpreter<'a> {
         &mut self.cursor
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn private_dict() {
+        let dict = CffDictInterpreter::parse_private_dict(&[
+            // -10 0 500 10 BlueValues
+            129, 139, 248, 136, 149, 6, //
+            // 80 10 StemSnapH
+            219, 149, 12, 12, //
+            // 1 ForceBold
+            140, 12, 14, //
+            // 2.5E-3 ExpansionFactor
+            30, 0x2a, 0x5c, 0x3f, 12, 18, //
+            // 500 defaultWidthX
+            248, 136, 20, //
+            // -12.5 nominalWidthX
+            30, 0xe1, 0x2a, 0x5f, 21,
+        ])
+        .unwrap();
+
+        // arrays of deltas are made absolute
+        assert_eq!(dict.blue_values, Some(vec![-10.0, -10.0, 490.0, 500.0]));
+        assert_eq!(dict.stem_snap_h, Some(vec![80.0, 90.0]));
+
+        assert!(dict.force_bold);
+        assert_eq!(dict.expansion_factor, 0.0025);
+        assert_eq!(dict.default_width_x, 500.0);
+        assert_eq!(dict.nominal_width_x, -12.5);
+    }
+}
@@ -0,0 +1,40 @@
+/// Associates each glyph of a CID-keyed font with one of the Font DICTs in its
+/// FDArray
+#[derive(Debug)]
+pub enum FdSelect {
+    /// The index of the Font DICT of each glyph, in glyph order
+    Zero { fds: Vec<u8> },
+    Three {
+        ranges: Vec<FdSelectRange>,
+
+        /// One greater than the last glyph ID covered by the final range
+        sentinel: u16,
+    },
+}
+
+impl FdSelect {
+    /// The index in the FDArray of the Font DICT used by the glyph `gid`
+    pub fn font_dict_index(&self, gid: u16) -> Option<u8> {
+        match self {
+            Self::Zero { fds } => fds.get(gid as usize).copied(),
+            Self::Three { ranges, sentinel } => {
+                if gid >= *sentinel {
+                    return None;
+                }
+
+                ranges
+                    .iter()
+                    .take_while(|range| range.first <= gid)
+                    .last()
+                    .map(|range| range.fd)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FdSelectRange {
+    /// The first glyph ID of the range
+    pub first: u16,
+    pub fd: u8,
+}
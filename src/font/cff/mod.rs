@@ -10,16 +10,18 @@ mod charstring;
 mod consts;
 mod dict;
 mod encoding;
+mod fd_select;
 mod index;
 mod parse;
 
 pub use charset::*;
 pub use charstring::CffCharStringInterpreter;
 pub use encoding::*;
+pub use fd_select::*;
 pub use index::*;
 pub use parse::CffParser;
 
-use self::dict::TopDict;
+use self::dict::{PrivateDict, TopDict};
 
 type Offsize = u8;
 
@@ -28,9 +30,46 @@ pub struct CffFile<'a> {
     pub(crate) name_index: CffIndex<'a>,
     pub(crate) top_dict: TopDict,
     pub(crate) string_index: CffIndex<'a>,
+    pub(crate) global_subrs: CffIndex<'a>,
+    pub(crate) private_dict: PrivateDict,
+    pub(crate) local_subrs: Option<CffIndex<'a>>,
     pub(crate) charstring_index: CffIndex<'a>,
     pub(crate) encoding: CffEncoding<'a>,
     pub(crate) charset: CffCharset,
+
+    /// The Font DICTs of a CID-keyed font, each of which has its own Private
+    /// DICT and local subrs
+    pub(crate) fd_array: Vec<CffFontDict<'a>>,
+    pub(crate) fd_select: Option<FdSelect>,
+}
+
+impl<'a> CffFile<'a> {
+    /// Whether glyphs are selected by CID rather than by name. In a CID-keyed
+    /// font, the charset maps glyph IDs to CIDs rather than to SIDs
+    pub(crate) fn is_cid_keyed(&self) -> bool {
+        self.top_dict.ros.is_some()
+    }
+
+    /// The Private DICT and local subrs used by the glyph `gid`
+    pub(crate) fn glyph_private_dict(&self, gid: u16) -> (&PrivateDict, Option<&CffIndex<'a>>) {
+        let font_dict = self
+            .fd_select
+            .as_ref()
+            .and_then(|fd_select| fd_select.font_dict_index(gid))
+            .and_then(|idx| self.fd_array.get(idx as usize));
+
+        match font_dict {
+            Some(font_dict) => (&font_dict.private_dict, font_dict.local_subrs.as_ref()),
+            None => (&self.private_dict, self.local_subrs.as_ref()),
+        }
+    }
+}
+
+/// A Font DICT of a CID-keyed font
+#[derive(Debug)]
+pub(crate) struct CffFontDict<'a> {
+    pub private_dict: PrivateDict,
+    pub local_subrs: Option<CffIndex<'a>>,
 }
 
 #[derive(Debug)]
@@ -1,11 +1,14 @@
 use crate::{
-    font::{cff::dict::CffDictInterpreter, CffFile},
+    font::{
+        cff::dict::{CffDictInterpreter, PrivateDict},
+        CffFile,
+    },
     parse_binary::BinaryParser,
 };
 
 use super::{
-    CffCharset, CffEncoding, CffHeader, CffIndex, CharsetRangeOne, CharsetRangeTwo,
-    EncodingRangeOne,
+    CffCharset, CffEncoding, CffFontDict, CffHeader, CffIndex, CharsetRangeOne, CharsetRangeTwo,
+    EncodingRangeOne, FdSelect, FdSelectRange,
 };
 
 pub struct CffParser<'a> {
@@ -29,6 +32,9 @@ impl<'a> CffParser<'a> {
         );
         let top_dict = CffDictInterpreter::parse_top_dict(top_dict_index.data)?;
         let string_index = self.parse_index()?;
+        let global_subrs = self.parse_index()?;
+
+        let (private_dict, local_subrs) = self.parse_private(top_dict.private)?;
 
         self.cursor = top_dict.char_strings.unwrap() as usize;
         let charstring_index = self.parse_index()?;
@@ -52,20 +58,121 @@ impl<'a> CffParser<'a> {
             }
         };
 
+        let fd_array = match top_dict.fd_array {
+            Some(offset) => {
+                self.cursor = offset as usize;
+
+                let font_dict_index = self.parse_index()?;
+                let mut fd_array = Vec::with_capacity(font_dict_index.count as usize);
+
+                for font_dict in font_dict_index.iter() {
+                    let font_dict = CffDictInterpreter::parse_top_dict(font_dict)?;
+                    let (private_dict, local_subrs) = self.parse_private(font_dict.private)?;
+
+                    fd_array.push(CffFontDict {
+                        private_dict,
+                        local_subrs,
+                    });
+                }
+
+                fd_array
+            }
+            None => Vec::new(),
+        };
+
+        let fd_select = match top_dict.fd_select {
+            Some(offset) => {
+                self.cursor = offset as usize;
+                Some(self.parse_fd_select(charstring_index.count)?)
+            }
+            None => None,
+        };
+
         Ok(CffFile {
             name_index,
             top_dict,
             string_index,
+            global_subrs,
+            private_dict,
+            local_subrs,
             charstring_index,
             encoding,
             charset,
+            fd_array,
+            fd_select,
+        })
+    }
+
+    /// Parse the Private DICT at the given size and offset, along with the
+    /// local subrs it refers to
+    fn parse_private(
+        &mut self,
+        private: Option<[f32; 2]>,
+    ) -> anyhow::Result<(PrivateDict, Option<CffIndex<'a>>)> {
+        let [size, offset] = match private {
+            Some(private) => private,
+            None => return Ok((PrivateDict::default(), None)),
+        };
+
+        let start = offset as usize;
+        let end = start + size as usize;
+
+        let private_dict = match self.buffer.get(start..end) {
+            Some(buffer) => CffDictInterpreter::parse_private_dict(buffer)?,
+            None => anyhow::bail!("private dict out of bounds"),
+        };
+
+        // the offset to the local subrs is relative to the start of the
+        // private dict
+        let local_subrs = match private_dict.subrs {
+            Some(subrs) => {
+                self.cursor = start + subrs as usize;
+                Some(self.parse_index()?)
+            }
+            None => None,
+        };
+
+        Ok((private_dict, local_subrs))
+    }
+
+    fn parse_fd_select(&mut self, n_glyphs: u16) -> anyhow::Result<FdSelect> {
+        Ok(match self.next()? {
+            0 => {
+                let fds = match self
+                    .buffer
+                    .get(self.cursor..self.cursor + n_glyphs as usize)
+                {
+                    Some(fds) => fds.to_vec(),
+                    None => anyhow::bail!("FDSelect out of bounds"),
+                };
+
+                FdSelect::Zero { fds }
+            }
+            3 => {
+                let n_ranges = self.parse_u16()?;
+                let mut ranges = Vec::with_capacity(n_ranges as usize);
+
+                for _ in 0..n_ranges {
+                    let first = self.parse_u16()?;
+                    let fd = self.next()?;
+
+                    ranges.push(FdSelectRange { first, fd });
+                }
+
+                let sentinel = self.parse_u16()?;
+
+                FdSelect::Three { ranges, sentinel }
+            }
+            format => anyhow::bail!("invalid FDSelect format: {}", format),
         })
     }
 
     fn parse_charset(&mut self, mut n_glyphs: u16) -> anyhow::Result<CffCharset> {
         Ok(match self.next()? {
             0 => {
-                let name_ids = self.buffer[self.cursor..self.cursor + n_glyphs as usize * 2 - 1]
+                // .notdef is omitted, as it is always the first glyph
+                let len = (n_glyphs as usize).saturating_sub(1) * 2;
+                let name_ids = self.buffer[self.cursor..self.cursor + len]
                     .chunks_exact(2)
                     .map(|b| u16::from_be_bytes([b[0], b[1]]))
                     .collect();
@@ -150,6 +257,17 @@ impl<'a> CffParser<'a> {
 
     fn parse_index(&mut self) -> anyhow::Result<CffIndex<'a>> {
         let count = self.parse_u16()?;
+
+        // an empty INDEX consists of only the count field
+        if count == 0 {
+            return Ok(CffIndex {
+                count,
+                offsize: 0,
+                offset: Vec::new(),
+                data: &[],
+            });
+        }
+
         let offsize = self.next()?;
         let mut offset = Vec::new();
 
@@ -2,8 +2,8 @@ use std::collections::BTreeMap;
 
 use crate::{
     error::{ParseError, PdfResult},
+    filter::decode_stream,
     objects::{Name, Object, ObjectType},
-    stream::Stream,
     FromObj, Resolve,
 };
 
@@ -77,7 +77,7 @@ pub struct CidFontDictionary<'a> {
     ///       widths for consecutive CIDs or one width for a range of CIDs
     ///
     /// Default value: none (the DW value shall be used for all glyphs)
-    #[field("W", default = CidFontWidths::with_default(default_width))]
+    #[field("W", default = CidFontWidths::default())]
     pub widths: CidFontWidths,
 
     /// An array of two numbers specifying the default metrics for vertical writing
@@ -89,8 +89,8 @@ pub struct CidFontDictionary<'a> {
     /// A description of the metrics for vertical writing for the glyphs in the CIDFont
     ///
     /// Default value: none (the DW2 value shall be used for all glyphs)
-    #[field("W2")]
-    pub w2: Option<Vec<Object<'a>>>,
+    #[field("W2", default = CidFontVerticalMetrics::default())]
+    pub vertical_metrics: CidFontVerticalMetrics,
 
     /// A specification of the mapping from CIDs to glyph indices. If the value is a
     /// stream, the bytes in the stream shall contain the mapping from CIDs to glyph
@@ -104,20 +104,65 @@ pub struct CidFontDictionary<'a> {
     /// This entry may appear only in a Type 2 CIDFont whose associated TrueType font program
     /// is embedded in the PDF file
     #[field("CIDToGIDMap", default = CidToGidMap::Identity)]
-    pub cid_to_gid_map: CidToGidMap<'a>,
+    pub cid_to_gid_map: CidToGidMap,
+}
+
+impl<'a> CidFontDictionary<'a> {
+    /// The width of the glyph for `cid`, in thousandths of a unit of text space
+    pub fn width(&self, cid: u32) -> f32 {
+        self.widths.get(cid).unwrap_or(self.default_width as f32)
+    }
+
+    /// The metrics of the glyph for `cid` in vertical writing mode
+    ///
+    /// Glyphs without an entry in W2 have the vertical displacement given by DW2,
+    /// and a position vector placing the vertical origin halfway across the
+    /// glyph's width, at the height given by DW2.
+    pub fn vertical_metric(&self, cid: u32) -> VerticalMetric {
+        let [vy, w1y] = self.dw2;
+
+        self.vertical_metrics
+            .get(cid)
+            .unwrap_or_else(|| VerticalMetric {
+                w1y,
+                vx: self.width(cid) / 2.0,
+                vy,
+            })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum CidToGidMap<'a> {
+pub enum CidToGidMap {
     Identity,
-    Stream(Stream<'a>),
+
+    /// The glyph index of each CID, decoded from the stream
+    Table(Vec<u16>),
 }
 
-impl<'a> FromObj<'a> for CidToGidMap<'a> {
+impl CidToGidMap {
+    /// The glyph index for `cid`. CIDs beyond the end of the table map to glyph 0
+    pub fn glyph_index(&self, cid: u32) -> u32 {
+        match self {
+            Self::Identity => cid,
+            Self::Table(table) => table.get(cid as usize).copied().unwrap_or(0) as u32,
+        }
+    }
+}
+
+impl<'a> FromObj<'a> for CidToGidMap {
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
         Ok(match resolver.resolve(obj)? {
             Object::Name(ref name) if name == "Identity" => Self::Identity,
-            Object::Stream(stream) => Self::Stream(stream),
+            Object::Stream(stream) => {
+                let buffer = decode_stream(&stream.stream, &stream.dict, resolver)?;
+
+                Self::Table(
+                    buffer
+                        .chunks_exact(2)
+                        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                        .collect(),
+                )
+            }
             _ => {
                 anyhow::bail!(ParseError::MismatchedObjectTypeAny {
                     expected: &[ObjectType::Name, ObjectType::Stream],
@@ -127,56 +172,202 @@ impl<'a> FromObj<'a> for CidToGidMap<'a> {
     }
 }
 
-#[derive(Debug, Clone)]
+/// The widths of the glyphs of a CIDFont, in thousandths of a unit of text
+/// space, keyed by CID
+///
+/// Each element of the W array is either of the form `c [w1 w2 ... wn]`,
+/// giving the widths of consecutive CIDs starting at `c`, or of the form
+/// `cfirst clast w`, giving the same width to every CID from `cfirst` to
+/// `clast` inclusive
+#[derive(Debug, Default, Clone)]
 pub struct CidFontWidths {
-    pub map: BTreeMap<i32, f32>,
-    pub default: i32,
+    pub map: BTreeMap<u32, f32>,
 }
 
 impl CidFontWidths {
-    pub fn with_default(default: i32) -> Self {
-        Self {
-            map: BTreeMap::new(),
-            default,
-        }
+    pub fn get(&self, cid: u32) -> Option<f32> {
+        self.map.get(&cid).copied()
     }
 }
 
 impl<'a> FromObj<'a> for CidFontWidths {
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
         let mut map = BTreeMap::new();
-        let arr = resolver.assert_arr(obj)?;
 
-        let mut idx = 0;
+        let mut elements = resolver.assert_arr(obj)?.into_iter();
 
-        while idx < arr.len() {
-            let mut first = resolver.assert_integer(arr[idx].clone())?;
+        while let Some(first) = elements.next() {
+            let mut cid = to_cid(resolver.assert_integer(first)?)?;
 
-            idx += 1;
+            let second = match elements.next() {
+                Some(obj) => resolver.resolve(obj)?,
+                None => anyhow::bail!("expected array or integer following CID"),
+            };
 
-            match resolver.resolve(arr[idx].clone())? {
+            match second {
                 arr @ Object::Array(..) => {
-                    let arr = <Vec<f32>>::from_obj(arr, resolver)?;
-
-                    for width in arr {
-                        map.insert(first, width);
-                        first += 1;
+                    for width in <Vec<f32>>::from_obj(arr, resolver)? {
+                        map.insert(cid, width);
+                        cid += 1;
                     }
                 }
                 Object::Integer(last) => {
-                    idx += 1;
-                    let width = resolver.assert_number(arr[idx].clone())?;
+                    let width = match elements.next() {
+                        Some(width) => resolver.assert_number(width)?,
+                        None => anyhow::bail!("expected width for CID range"),
+                    };
 
-                    for i in first..last {
-                        map.insert(i, width);
+                    for cid in cid..=to_cid(last)? {
+                        map.insert(cid, width);
                     }
                 }
                 obj => anyhow::bail!("expected array or integer, found {:?}", obj),
             }
+        }
+
+        Ok(Self { map })
+    }
+}
+
+/// The metrics of a glyph in vertical writing mode, in thousandths of a unit of
+/// text space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VerticalMetric {
+    /// The vertical displacement of the glyph, usually negative
+    pub w1y: f32,
+
+    /// The position vector, which is the displacement from the glyph's
+    /// horizontal origin to its vertical origin
+    pub vx: f32,
+    pub vy: f32,
+}
+
+/// The vertical metrics of the glyphs of a CIDFont, keyed by CID
+///
+/// Each element of the W2 array is either of the form
+/// `c [w1y v1x v1y w2y v2x v2y ...]`, giving the metrics of consecutive CIDs
+/// starting at `c`, or of the form `cfirst clast w1y v1x v1y`, giving the same
+/// metrics to every CID from `cfirst` to `clast` inclusive
+#[derive(Debug, Default, Clone)]
+pub struct CidFontVerticalMetrics {
+    pub map: BTreeMap<u32, VerticalMetric>,
+}
+
+impl CidFontVerticalMetrics {
+    pub fn get(&self, cid: u32) -> Option<VerticalMetric> {
+        self.map.get(&cid).copied()
+    }
+}
+
+impl<'a> FromObj<'a> for CidFontVerticalMetrics {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut map = BTreeMap::new();
 
-            idx += 1;
+        let mut elements = resolver.assert_arr(obj)?.into_iter();
+
+        while let Some(first) = elements.next() {
+            let mut cid = to_cid(resolver.assert_integer(first)?)?;
+
+            let second = match elements.next() {
+                Some(obj) => resolver.resolve(obj)?,
+                None => anyhow::bail!("expected array or integer following CID"),
+            };
+
+            match second {
+                arr @ Object::Array(..) => {
+                    for metric in <Vec<f32>>::from_obj(arr, resolver)?.chunks_exact(3) {
+                        map.insert(
+                            cid,
+                            VerticalMetric {
+                                w1y: metric[0],
+                                vx: metric[1],
+                                vy: metric[2],
+                            },
+                        );
+                        cid += 1;
+                    }
+                }
+                Object::Integer(last) => {
+                    let mut metric = [0.0; 3];
+
+                    for n in &mut metric {
+                        *n = match elements.next() {
+                            Some(obj) => resolver.assert_number(obj)?,
+                            None => anyhow::bail!("expected metrics for CID range"),
+                        };
+                    }
+
+                    let [w1y, vx, vy] = metric;
+
+                    for cid in cid..=to_cid(last)? {
+                        map.insert(cid, VerticalMetric { w1y, vx, vy });
+                    }
+                }
+                obj => anyhow::bail!("expected array or integer, found {:?}", obj),
+            }
         }
 
-        Ok(Self { map, default: 1000 })
+        Ok(Self { map })
+    }
+}
+
+fn to_cid(n: i32) -> PdfResult<u32> {
+    u32::try_from(n).map_err(|_| anyhow::anyhow!("invalid CID: {}", n))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::xref::XrefParser;
+
+    use super::*;
+
+    fn integers(arr: &[i32]) -> Vec<Object<'static>> {
+        arr.iter().map(|&n| Object::Integer(n)).collect()
+    }
+
+    #[test]
+    fn widths() {
+        let mut arr = integers(&[1]);
+        arr.push(Object::Array(integers(&[500, 600])));
+        arr.extend(integers(&[10, 12, 250]));
+
+        let widths =
+            CidFontWidths::from_obj(Object::Array(arr), &mut XrefParser::new(Vec::new())).unwrap();
+
+        assert_eq!(widths.get(0), None);
+        assert_eq!(widths.get(1), Some(500.0));
+        assert_eq!(widths.get(2), Some(600.0));
+        assert_eq!(widths.get(10), Some(250.0));
+        assert_eq!(widths.get(12), Some(250.0));
+        assert_eq!(widths.get(13), None);
+    }
+
+    #[test]
+    fn vertical_metrics() {
+        let mut arr = integers(&[120]);
+        arr.push(Object::Array(integers(&[-1000, 250, 772, -500, 250, 772])));
+        arr.extend(integers(&[7080, 7085, -1000, 500, 880]));
+
+        let metrics =
+            CidFontVerticalMetrics::from_obj(Object::Array(arr), &mut XrefParser::new(Vec::new()))
+                .unwrap();
+
+        assert_eq!(
+            metrics.get(121),
+            Some(VerticalMetric {
+                w1y: -500.0,
+                vx: 250.0,
+                vy: 772.0
+            })
+        );
+        assert_eq!(
+            metrics.get(7085),
+            Some(VerticalMetric {
+                w1y: -1000.0,
+                vx: 500.0,
+                vy: 880.0
+            })
+        );
+        assert_eq!(metrics.get(122), None);
     }
 }
@@ -179,7 +179,7 @@ pub(crate) enum PredefinedCjkCmapName {
     UniKsUtf16H = "UniKS-UTF16-H",
 
     /// Vertical version of UniKS-UTF16-H
-    UniKsUtf16V = "UniKS-UTF16-",
+    UniKsUtf16V = "UniKS-UTF16-V",
 
     // generic
     /// The horizontal identity mapping for 2-byte CIDs; may be used with CIDFonts using any Registry, Ordering, and Supplement values. It maps 2-byte character codes ranging from 0 to 65,535 to the same 2-byte CID value, interpreted high-order byte first.
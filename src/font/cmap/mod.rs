@@ -0,0 +1,327 @@
+/*!
+ * CMaps, which map character codes to CIDs (the Encoding of a Type 0 font) or to
+ * Unicode values (a ToUnicode CMap)
+ *
+ * https://adobe-type-tools.github.io/font-tech-notes/pdfs/5014.CIDFont_Spec.pdf
+ * https://adobe-type-tools.github.io/font-tech-notes/pdfs/5099.CMapResources.pdf
+ */
+
+use crate::{
+    error::{ParseError, PdfResult},
+    filter::decode_stream,
+    objects::{Object, ObjectType},
+    FromObj, Resolve,
+};
+
+use super::cjk::PredefinedCjkCmapName;
+
+mod parse;
+
+/// The largest number of bytes in a single character code
+const MAX_CODE_LEN: usize = 4;
+
+/// A stream containing a CMap file that maps character codes to Unicode values
+#[derive(Debug)]
+pub struct ToUnicodeCmapStream {
+    pub(crate) cmap: CMap,
+}
+
+impl<'a> FromObj<'a> for ToUnicodeCmapStream {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let stream = resolver.assert_stream(obj)?;
+        let buffer = decode_stream(&stream.stream, &stream.dict, resolver)?;
+
+        Ok(Self {
+            cmap: CMap::parse(&buffer)?,
+        })
+    }
+}
+
+/// The direction in which successive glyphs of a font are laid out
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WritingMode {
+    #[default]
+    Horizontal,
+    Vertical,
+}
+
+/// A character code read from a string, along with the number of bytes it
+/// occupied. Codes of different lengths are distinct, even if they have the
+/// same numeric value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CharCode {
+    pub code: u32,
+    pub len: u8,
+}
+
+impl CharCode {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self {
+            code: bytes.iter().fold(0, |code, &b| (code << 8) | b as u32),
+            len: bytes.len() as u8,
+        }
+    }
+}
+
+/// The set of valid codes of a single length, given as a range for each byte
+#[derive(Debug, Clone, PartialEq)]
+struct CodespaceRange {
+    low: Vec<u8>,
+    high: Vec<u8>,
+}
+
+impl CodespaceRange {
+    fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() == self.low.len()
+            && bytes
+                .iter()
+                .zip(self.low.iter().zip(&self.high))
+                .all(|(b, (low, high))| (low..=high).contains(&b))
+    }
+}
+
+/// Consecutive codes of the same length, mapped to consecutive CIDs starting
+/// at `cid`
+#[derive(Debug, Clone, PartialEq)]
+struct CidRange {
+    low: u32,
+    high: u32,
+    len: u8,
+    cid: u32,
+}
+
+impl CidRange {
+    fn contains(&self, code: CharCode) -> bool {
+        code.len == self.len && (self.low..=self.high).contains(&code.code)
+    }
+
+    fn lookup(&self, code: CharCode) -> Option<u32> {
+        if self.contains(code) {
+            Some(self.cid + (code.code - self.low))
+        } else {
+            None
+        }
+    }
+}
+
+/// Consecutive codes of the same length, mapped to consecutive Unicode values
+/// starting at `dst`, a sequence of UTF-16 code units. Successive codes
+/// increment the last code unit
+#[derive(Debug, Clone, PartialEq)]
+struct UnicodeRange {
+    low: u32,
+    high: u32,
+    len: u8,
+    dst: Vec<u16>,
+}
+
+impl UnicodeRange {
+    fn lookup(&self, code: CharCode) -> Option<String> {
+        if code.len != self.len || !(self.low..=self.high).contains(&code.code) {
+            return None;
+        }
+
+        let mut dst = self.dst.clone();
+
+        if let Some(last) = dst.last_mut() {
+            *last = last.wrapping_add((code.code - self.low) as u16);
+        }
+
+        Some(char::decode_utf16(dst).filter_map(Result::ok).collect())
+    }
+}
+
+/// A CMap, specifying the codespace of a font's character codes and the
+/// mapping from those codes to CIDs or to Unicode values
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CMap {
+    pub(crate) name: Option<String>,
+    pub(crate) writing_mode: WritingMode,
+    codespace_ranges: Vec<CodespaceRange>,
+    cid_ranges: Vec<CidRange>,
+    notdef_ranges: Vec<CidRange>,
+    unicode_ranges: Vec<UnicodeRange>,
+}
+
+impl CMap {
+    pub(crate) fn parse(buffer: &[u8]) -> PdfResult<Self> {
+        parse::CMapParser::new(buffer).parse()
+    }
+
+    /// The Identity-H and Identity-V CMaps, which map 2-byte codes to the same
+    /// CID
+    pub(crate) fn identity(writing_mode: WritingMode) -> Self {
+        Self {
+            name: Some(
+                match writing_mode {
+                    WritingMode::Horizontal => "Identity-H",
+                    WritingMode::Vertical => "Identity-V",
+                }
+                .to_owned(),
+            ),
+            writing_mode,
+            codespace_ranges: vec![CodespaceRange {
+                low: vec![0x00, 0x00],
+                high: vec![0xff, 0xff],
+            }],
+            cid_ranges: vec![CidRange {
+                low: 0x0000,
+                high: 0xffff,
+                len: 2,
+                cid: 0,
+            }],
+            notdef_ranges: Vec::new(),
+            unicode_ranges: Vec::new(),
+        }
+    }
+
+    /// One of the predefined CMaps, which may be referred to by name rather
+    /// than embedded
+    pub(crate) fn predefined(name: &str) -> PdfResult<Self> {
+        let writing_mode = if name.ends_with("-V") {
+            WritingMode::Vertical
+        } else {
+            WritingMode::Horizontal
+        };
+
+        Ok(match PredefinedCjkCmapName::from_str(name)? {
+            PredefinedCjkCmapName::IdentityH | PredefinedCjkCmapName::IdentityV => {
+                Self::identity(writing_mode)
+            }
+            // todo: the CMap files for the predefined CJK encodings are not yet
+            // bundled, so every code maps to CID 0. The codespace is assumed to
+            // be two bytes, which is correct for the UCS-2 encodings
+            _ => Self {
+                name: Some(name.to_owned()),
+                writing_mode,
+                codespace_ranges: vec![CodespaceRange {
+                    low: vec![0x00, 0x00],
+                    high: vec![0xff, 0xff],
+                }],
+                ..Self::default()
+            },
+        })
+    }
+
+    /// Incorporate the mappings of the CMap referred to by `usecmap`, which are
+    /// overridden by any mappings of this CMap
+    fn use_cmap(&mut self, mut parent: CMap) {
+        parent.codespace_ranges.append(&mut self.codespace_ranges);
+        parent.cid_ranges.append(&mut self.cid_ranges);
+        parent.notdef_ranges.append(&mut self.notdef_ranges);
+        parent.unicode_ranges.append(&mut self.unicode_ranges);
+
+        self.codespace_ranges = parent.codespace_ranges;
+        self.cid_ranges = parent.cid_ranges;
+        self.notdef_ranges = parent.notdef_ranges;
+        self.unicode_ranges = parent.unicode_ranges;
+    }
+
+    /// Read the character code at the start of `bytes`, which must not be
+    /// empty
+    ///
+    /// A code that does not match any codespace range is as long as the
+    /// shortest codespace range that matches its first byte, and is mapped
+    /// to CID 0.
+    fn next_code(&self, bytes: &[u8]) -> CharCode {
+        if self.codespace_ranges.is_empty() {
+            return CharCode::from_bytes(&bytes[..1]);
+        }
+
+        for len in 1..=MAX_CODE_LEN.min(bytes.len()) {
+            if self
+                .codespace_ranges
+                .iter()
+                .any(|range| range.matches(&bytes[..len]))
+            {
+                return CharCode::from_bytes(&bytes[..len]);
+            }
+        }
+
+        let partial_match = self
+            .codespace_ranges
+            .iter()
+            .filter(|range| (range.low[0]..=range.high[0]).contains(&bytes[0]))
+            .map(|range| range.low.len())
+            .min();
+
+        let len = partial_match
+            .or_else(|| {
+                self.codespace_ranges
+                    .iter()
+                    .map(|range| range.low.len())
+                    .min()
+            })
+            .unwrap_or(1)
+            .min(bytes.len());
+
+        CharCode::from_bytes(&bytes[..len])
+    }
+
+    /// Split `bytes` into character codes according to the codespace ranges
+    pub(crate) fn codes<'b>(&'b self, mut bytes: &'b [u8]) -> impl Iterator<Item = CharCode> + 'b {
+        std::iter::from_fn(move || {
+            if bytes.is_empty() {
+                return None;
+            }
+
+            let code = self.next_code(bytes);
+
+            bytes = &bytes[code.len as usize..];
+
+            Some(code)
+        })
+    }
+
+    /// The CID selected by `code`. Codes without a mapping select the notdef
+    /// CID of their range, which is the same for every code in it, or CID 0
+    pub(crate) fn cid(&self, code: CharCode) -> u32 {
+        self.cid_ranges
+            .iter()
+            .rev()
+            .find_map(|range| range.lookup(code))
+            .or_else(|| {
+                self.notdef_ranges
+                    .iter()
+                    .rev()
+                    .find(|range| range.contains(code))
+                    .map(|range| range.cid)
+            })
+            .unwrap_or(0)
+    }
+
+    /// The Unicode value of `code`, if it has one
+    pub(crate) fn unicode(&self, code: CharCode) -> Option<String> {
+        self.unicode_ranges
+            .iter()
+            .rev()
+            .find_map(|range| range.lookup(code))
+    }
+}
+
+/// The Encoding entry of a Type 0 font is either the name of a predefined CMap,
+/// or a stream containing an embedded CMap
+impl<'a> FromObj<'a> for CMap {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        match resolver.resolve(obj)? {
+            Object::Name(name) => Self::predefined(&name),
+            Object::Stream(mut stream) => {
+                let buffer = decode_stream(&stream.stream, &stream.dict, resolver)?;
+                let mut cmap = Self::parse(&buffer)?;
+
+                if let Some(parent) = stream.dict.other.get::<CMap>("UseCMap", resolver)? {
+                    cmap.use_cmap(parent);
+                }
+
+                if let Some(1) = stream.dict.other.get_integer("WMode", resolver)? {
+                    cmap.writing_mode = WritingMode::Vertical;
+                }
+
+                Ok(cmap)
+            }
+            _ => anyhow::bail!(ParseError::MismatchedObjectTypeAny {
+                expected: &[ObjectType::Stream, ObjectType::Name],
+            }),
+        }
+    }
+}
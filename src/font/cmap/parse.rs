@@ -0,0 +1,441 @@
+use crate::{error::PdfResult, lex::LexBase};
+
+use super::{CMap, CharCode, CidRange, CodespaceRange, UnicodeRange, WritingMode, MAX_CODE_LEN};
+
+#[derive(Debug, Clone, PartialEq)]
+enum CMapToken {
+    Integer(i32),
+    HexString(Vec<u8>),
+    Name(String),
+    ArrayStart,
+    ArrayEnd,
+    Operator(String),
+
+    /// Strings, reals, dictionaries, and procedures are not needed to build a
+    /// CMap, and so are not distinguished
+    Other,
+}
+
+/// An operand of a CMap operator. Arrays are collected into a single operand
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Integer(i32),
+    HexString(Vec<u8>),
+    Name(String),
+    Array(Vec<Operand>),
+    Other,
+}
+
+/// Parses the subset of PostScript used by CMap files. Operators other than
+/// those defining mappings, `usecmap`, and `def` are ignored
+pub(super) struct CMapParser<'a> {
+    buffer: &'a [u8],
+    cursor: usize,
+    operands: Vec<Operand>,
+
+    /// The elements of each array currently being collected
+    arrays: Vec<Vec<Operand>>,
+    cmap: CMap,
+}
+
+impl<'a> LexBase<'a> for CMapParser<'a> {
+    fn buffer(&self) -> &[u8] {
+        self.buffer
+    }
+
+    fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    fn cursor_mut(&mut self) -> &mut usize {
+        &mut self.cursor
+    }
+}
+
+impl<'a> CMapParser<'a> {
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self {
+            buffer,
+            cursor: 0,
+            operands: Vec::new(),
+            arrays: Vec::new(),
+            cmap: CMap::default(),
+        }
+    }
+
+    pub fn parse(mut self) -> PdfResult<CMap> {
+        while let Some(token) = self.next_token()? {
+            let operand = match token {
+                CMapToken::Integer(n) => Operand::Integer(n),
+                CMapToken::HexString(s) => Operand::HexString(s),
+                CMapToken::Name(name) => Operand::Name(name),
+                CMapToken::Other => Operand::Other,
+                CMapToken::ArrayStart => {
+                    self.arrays.push(Vec::new());
+                    continue;
+                }
+                CMapToken::ArrayEnd => match self.arrays.pop() {
+                    Some(arr) => Operand::Array(arr),
+                    None => anyhow::bail!("unbalanced array in CMap"),
+                },
+                CMapToken::Operator(op) => {
+                    self.arrays.clear();
+                    self.operator(&op)?;
+                    continue;
+                }
+            };
+
+            match self.arrays.last_mut() {
+                Some(arr) => arr.push(operand),
+                None => self.operands.push(operand),
+            }
+        }
+
+        Ok(self.cmap)
+    }
+
+    fn operator(&mut self, op: &str) -> PdfResult<()> {
+        let operands = std::mem::take(&mut self.operands);
+
+        match op {
+            "endcodespacerange" => {
+                for pair in operands.chunks_exact(2) {
+                    if let [Operand::HexString(low), Operand::HexString(high)] = pair {
+                        anyhow::ensure!(
+                            low.len() == high.len() && (1..=MAX_CODE_LEN).contains(&low.len()),
+                            "invalid codespace range"
+                        );
+
+                        self.cmap.codespace_ranges.push(CodespaceRange {
+                            low: low.clone(),
+                            high: high.clone(),
+                        });
+                    }
+                }
+            }
+            "endcidrange" | "endnotdefrange" => {
+                let ranges = operands.chunks_exact(3).filter_map(|triple| match triple {
+                    [Operand::HexString(low), Operand::HexString(high), Operand::Integer(cid)] => {
+                        cid_range(low, high, *cid)
+                    }
+                    _ => None,
+                });
+
+                if op == "endcidrange" {
+                    self.cmap.cid_ranges.extend(ranges);
+                } else {
+                    self.cmap.notdef_ranges.extend(ranges);
+                }
+            }
+            "endcidchar" | "endnotdefchar" => {
+                let ranges = operands.chunks_exact(2).filter_map(|pair| match pair {
+                    [Operand::HexString(code), Operand::Integer(cid)] => {
+                        cid_range(code, code, *cid)
+                    }
+                    _ => None,
+                });
+
+                if op == "endcidchar" {
+                    self.cmap.cid_ranges.extend(ranges);
+                } else {
+                    self.cmap.notdef_ranges.extend(ranges);
+                }
+            }
+            "endbfchar" => {
+                for pair in operands.chunks_exact(2) {
+                    // todo: destinations given as glyph names
+                    if let [Operand::HexString(code), Operand::HexString(dst)] = pair {
+                        self.push_unicode_range(code, code, dst);
+                    }
+                }
+            }
+            "endbfrange" => {
+                for triple in operands.chunks_exact(3) {
+                    match triple {
+                        [Operand::HexString(low), Operand::HexString(high), Operand::HexString(dst)] =>
+                        {
+                            self.push_unicode_range(low, high, dst);
+                        }
+                        [Operand::HexString(low), Operand::HexString(high), Operand::Array(dsts)] =>
+                        {
+                            let low = CharCode::from_bytes(low);
+                            let high = CharCode::from_bytes(high);
+
+                            for (code, dst) in (low.code..=high.code).zip(dsts) {
+                                if let Operand::HexString(dst) = dst {
+                                    self.cmap.unicode_ranges.push(UnicodeRange {
+                                        low: code,
+                                        high: code,
+                                        len: low.len,
+                                        dst: utf16_code_units(dst),
+                                    });
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "usecmap" => {
+                if let Some(Operand::Name(name)) = operands.last() {
+                    let parent = CMap::predefined(name)?;
+                    self.cmap.use_cmap(parent);
+                }
+            }
+            "def" => match operands.as_slice() {
+                [.., Operand::Name(key), Operand::Integer(1)] if key == "WMode" => {
+                    self.cmap.writing_mode = WritingMode::Vertical;
+                }
+                [.., Operand::Name(key), Operand::Name(name)] if key == "CMapName" => {
+                    self.cmap.name = Some(name.clone());
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn push_unicode_range(&mut self, low: &[u8], high: &[u8], dst: &[u8]) {
+        if low.len() != high.len() {
+            return;
+        }
+
+        let low = CharCode::from_bytes(low);
+        let high = CharCode::from_bytes(high);
+
+        self.cmap.unicode_ranges.push(UnicodeRange {
+            low: low.code,
+            high: high.code,
+            len: low.len,
+            dst: utf16_code_units(dst),
+        });
+    }
+
+    fn next_token(&mut self) -> PdfResult<Option<CMapToken>> {
+        self.skip_whitespace();
+
+        let b = match self.peek_byte() {
+            Some(b) => b,
+            None => return Ok(None),
+        };
+
+        Ok(Some(match b {
+            b'/' => CMapToken::Name(self.lex_name()?),
+            b'(' => {
+                self.lex_string()?;
+                CMapToken::Other
+            }
+            b'[' => {
+                self.next_byte();
+                CMapToken::ArrayStart
+            }
+            b']' => {
+                self.next_byte();
+                CMapToken::ArrayEnd
+            }
+            b'<' | b'>' if self.peek_byte_offset(1) == Some(b) => {
+                self.cursor += 2;
+                CMapToken::Other
+            }
+            b'<' => CMapToken::HexString(self.lex_hex_bytes()?),
+            b'+' | b'-' | b'.' | b'0'..=b'9' => {
+                let token = self.lex_regular();
+
+                match token.parse() {
+                    Ok(n) => CMapToken::Integer(n),
+                    Err(..) => CMapToken::Other,
+                }
+            }
+            _ if Self::is_regular(b) => CMapToken::Operator(self.lex_regular()),
+            // procedure delimiters, and stray closing delimiters
+            _ => {
+                self.next_byte();
+                CMapToken::Other
+            }
+        }))
+    }
+
+    fn lex_regular(&mut self) -> String {
+        let start = self.cursor;
+
+        while let Some(b) = self.peek_byte() {
+            if !Self::is_regular(b) {
+                break;
+            }
+
+            self.next_byte();
+        }
+
+        String::from_utf8_lossy(&self.buffer[start..self.cursor]).into_owned()
+    }
+
+    /// Lex a hexadecimal string as raw bytes. A final odd digit is assumed to
+    /// be followed by 0
+    fn lex_hex_bytes(&mut self) -> PdfResult<Vec<u8>> {
+        self.expect_byte(b'<')?;
+
+        let mut digits = Vec::new();
+
+        loop {
+            match self.next_byte() {
+                Some(b'>') => break,
+                Some(b) if b.is_ascii_hexdigit() => digits.push(hex_digit(b)),
+                Some(b) if Self::is_whitespace(b) => {}
+                Some(b) => anyhow::bail!("invalid byte in hex string: {:?}", b as char),
+                None => anyhow::bail!("unterminated hex string"),
+            }
+        }
+
+        Ok(digits
+            .chunks(2)
+            .map(|pair| (pair[0] << 4) | pair.get(1).copied().unwrap_or(0))
+            .collect())
+    }
+}
+
+fn hex_digit(b: u8) -> u8 {
+    match b {
+        b'0'..=b'9' => b - b'0',
+        b'a'..=b'f' => b - b'a' + 10,
+        b'A'..=b'F' => b - b'A' + 10,
+        _ => unreachable!(),
+    }
+}
+
+fn cid_range(low: &[u8], high: &[u8], cid: i32) -> Option<CidRange> {
+    if low.len() != high.len() || cid < 0 {
+        return None;
+    }
+
+    let low = CharCode::from_bytes(low);
+    let high = CharCode::from_bytes(high);
+
+    Some(CidRange {
+        low: low.code,
+        high: high.code,
+        len: low.len,
+        cid: cid as u32,
+    })
+}
+
+/// Interpret the bytes of a hex string as big-endian UTF-16
+fn utf16_code_units(bytes: &[u8]) -> Vec<u16> {
+    bytes
+        .chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair.get(1).copied().unwrap_or(0)]))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn codes_and_cids(cmap: &CMap, bytes: &[u8]) -> Vec<(CharCode, u32)> {
+        cmap.codes(bytes)
+            .map(|code| (code, cmap.cid(code)))
+            .collect()
+    }
+
+    #[test]
+    fn mixed_length_codespace() {
+        let cmap = CMap::parse(
+            b"%!PS-Adobe-3.0 Resource-CMap
+            /CIDInit /ProcSet findresource begin
+            12 dict begin
+            begincmap
+            /CIDSystemInfo << /Registry (Adobe) /Ordering (Japan1) /Supplement 2 >> def
+            /CMapName /Test-H def
+            2 begincodespacerange
+            <00> <80>
+            <8140> <9ffc>
+            endcodespacerange
+            1 begincidrange
+            <20> <7e> 231
+            endcidrange
+            2 begincidchar
+            <8140> 633
+            <8141> 634
+            endcidchar
+            1 beginnotdefrange
+            <00> <1f> 231
+            endnotdefrange
+            endcmap
+            CMapName currentdict /CMap defineresource pop
+            end
+            end",
+        )
+        .unwrap();
+
+        assert_eq!(cmap.name.as_deref(), Some("Test-H"));
+        assert_eq!(cmap.writing_mode, WritingMode::Horizontal);
+
+        assert_eq!(
+            codes_and_cids(&cmap, b"A\x81\x41\x05\x81"),
+            vec![
+                (CharCode { code: 0x41, len: 1 }, 264),
+                (
+                    CharCode {
+                        code: 0x8141,
+                        len: 2
+                    },
+                    634
+                ),
+                (CharCode { code: 0x05, len: 1 }, 231),
+                // an incomplete code at the end of the string
+                (CharCode { code: 0x81, len: 1 }, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn use_identity_cmap() {
+        let cmap = CMap::parse(
+            b"/CMapName /Test-V def /WMode 1 def /Identity-V usecmap
+            1 begincidchar <0041> 10 endcidchar",
+        )
+        .unwrap();
+
+        assert_eq!(cmap.writing_mode, WritingMode::Vertical);
+
+        assert_eq!(
+            codes_and_cids(&cmap, b"\x00\x41\x12\x34"),
+            vec![
+                (CharCode { code: 0x41, len: 2 }, 10),
+                (
+                    CharCode {
+                        code: 0x1234,
+                        len: 2
+                    },
+                    0x1234
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn to_unicode_mappings() {
+        let cmap = CMap::parse(
+            b"1 begincodespacerange <0000> <FFFF> endcodespacerange
+            2 beginbfchar
+            <0003> <0020>
+            <0010> <D835DC00>
+            endbfchar
+            2 beginbfrange
+            <0024> <0026> <0041>
+            <0030> <0031> [<0066006C> <00E9>]
+            endbfrange",
+        )
+        .unwrap();
+
+        let unicode = |code| cmap.unicode(CharCode { code, len: 2 });
+
+        assert_eq!(unicode(0x03).as_deref(), Some(" "));
+        assert_eq!(unicode(0x10).as_deref(), Some("\u{1d400}"));
+        assert_eq!(unicode(0x26).as_deref(), Some("C"));
+        assert_eq!(unicode(0x30).as_deref(), Some("fl"));
+        assert_eq!(unicode(0x31).as_deref(), Some("é"));
+        assert_eq!(unicode(0x32), None);
+    }
+}
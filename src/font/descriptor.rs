@@ -20,7 +20,7 @@ pub struct FontDescriptor<'a> {
 
     /// A byte string specifying the preferred font family name
     #[field("FontFamily")]
-    font_family: Option<String>,
+    pub(crate) font_family: Option<String>,
 
     /// The font stretch value
     ///
@@ -33,11 +33,11 @@ pub struct FontDescriptor<'a> {
     ///
     /// The specific interpretation of these values varies from font to font
     #[field("FontWeight")]
-    font_weight: Option<f32>,
+    pub(crate) font_weight: Option<f32>,
 
     /// A collection of flags defining various characteristics of the font
     #[field("Flags")]
-    flags: FontDescriptorFlags,
+    pub(crate) flags: FontDescriptorFlags,
 
     /// A rectangle, expressed in the glyph coordinate system, that shall specify
     /// the font bounding box. This should be the smallest rectangle enclosing the
@@ -142,7 +142,7 @@ pub struct FontDescriptor<'a> {
     ///
     /// Meaningful only in CID fonts
     #[field("Style")]
-    style: Option<Dictionary<'a>>,
+    pub(crate) style: Option<FontStyle>,
 
     /// A name specifying the language of the font, which may be used for encodings
     /// where the language is not implied by the encoding itself.
@@ -177,6 +177,50 @@ pub struct FontDescriptor<'a> {
     cid_set: Option<TypedReference<'a, Stream<'a>>>,
 }
 
+impl FontDescriptor<'_> {
+    /// Whether the font program is embedded in the document, in any of the
+    /// FontFile, FontFile2, or FontFile3 entries
+    pub(crate) fn is_embedded(&self) -> bool {
+        self.font_file.is_some() || self.font_file_two.is_some() || self.font_file_three.is_some()
+    }
+
+    /// The ascent, descent, and x height of the font, in ems, falling back to
+    /// typical proportions of Latin fonts for metrics which are not given
+    pub(crate) fn vertical_metrics(&self) -> (f32, f32, f32) {
+        let ascent = self.ascent.filter(|&ascent| ascent != 0.0).unwrap_or(800.0);
+        let descent = self
+            .descent
+            .filter(|&descent| descent != 0.0)
+            .unwrap_or(-200.0);
+
+        let x_height = if self.x_height != 0.0 {
+            self.x_height
+        } else {
+            500.0
+        };
+
+        (ascent / 1000.0, descent / 1000.0, x_height / 1000.0)
+    }
+}
+
+/// Entries describing the style of the glyphs of a CIDFont
+#[derive(Debug, FromObj)]
+pub(crate) struct FontStyle {
+    /// A 12-byte string consisting of the font's family class ID and subclass
+    /// ID, followed by the 10-byte PANOSE classification number for the font
+    #[field("Panose")]
+    panose: String,
+}
+
+impl FontStyle {
+    /// The 10-byte PANOSE classification number, if the string is well formed
+    pub(crate) fn panose(&self) -> Option<[u8; 10]> {
+        let bytes = self.panose.chars().map(|c| c as u8).collect::<Vec<u8>>();
+
+        bytes.get(2..12)?.try_into().ok()
+    }
+}
+
 #[derive(Debug)]
 struct CidFontDescriptor<'a> {
     base: FontDescriptor<'a>,
@@ -184,7 +228,7 @@ struct CidFontDescriptor<'a> {
 
 // todo: derive FromObj for tuple structs
 #[derive(Clone, Copy)]
-struct FontDescriptorFlags(u32);
+pub(crate) struct FontDescriptorFlags(u32);
 
 impl fmt::Debug for FontDescriptorFlags {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -264,6 +308,12 @@ impl FontDescriptorFlags {
     pub const fn is_small_cap(&self) -> bool {
         self.0 & Self::SMALL_CAP != 0
     }
+
+    /// Bold glyphs shall be painted with extra pixels even at very small text
+    /// sizes
+    pub const fn is_force_bold(&self) -> bool {
+        self.0 & Self::FORCE_BOLD != 0
+    }
 }
 
 #[pdf_enum]
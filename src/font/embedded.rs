@@ -99,8 +99,8 @@ pub struct CompactType1FontFile<'a> {
 /// font descriptor for a CIDFontType0 CIDFont dictionary
 #[derive(Debug, Clone)]
 pub struct CompactType0CidFontFile<'a> {
-    dict: EmbeddedFontDictionary<'a>,
-    stream: Stream<'a>,
+    pub dict: EmbeddedFontDictionary<'a>,
+    pub stream: Stream<'a>,
 }
 
 /// OpenType® font program, as described in the OpenType Specification v.1.4. OpenType is an
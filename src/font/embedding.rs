@@ -0,0 +1,80 @@
+/*!
+ * Finding the fonts of a document whose font programs are not embedded, which
+ * must be supplied by the system of whoever displays or prints the document
+ */
+
+use std::rc::Rc;
+
+use crate::{page::PageObject, Parser};
+
+use super::substitute::find_substitute;
+
+/// A font used by the document whose font program is not embedded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingFont {
+    /// The PostScript name of the font, as given by the BaseFont entry of its
+    /// font dictionary (or that of the descendant CIDFont, for composite fonts)
+    pub base_font: String,
+
+    /// The zero-based indices of the pages whose resources use the font, in
+    /// ascending order
+    pub pages: Vec<usize>,
+
+    /// Whether a matching installed system font was found, which can be
+    /// embedded in place of the missing font program
+    pub has_substitute: bool,
+}
+
+/// Every font used by `pages` whose font program is not embedded, in the order
+/// in which they are first used
+///
+/// Fonts are identified by their PostScript name, so that the same font
+/// referenced from the resources of several pages is reported once.
+fn missing_fonts(pages: &[Rc<PageObject>]) -> Vec<MissingFont> {
+    let mut missing: Vec<MissingFont> = Vec::new();
+
+    for (idx, page) in pages.iter().enumerate() {
+        let resources = match page.resources() {
+            Some(resources) => resources,
+            None => continue,
+        };
+
+        // todo: fonts used by form xobjects, patterns, and annotation
+        // appearance streams
+        let fonts = match &resources.font {
+            Some(fonts) => fonts,
+            None => continue,
+        };
+
+        for font in fonts.values() {
+            let (base_font, descriptor) = match font.missing_font_program() {
+                Some(missing_font) => missing_font,
+                None => continue,
+            };
+
+            match missing.iter_mut().find(|font| font.base_font == base_font) {
+                Some(font) => {
+                    if font.pages.last() != Some(&idx) {
+                        font.pages.push(idx);
+                    }
+                }
+                None => missing.push(MissingFont {
+                    base_font: base_font.to_owned(),
+                    pages: vec![idx],
+                    has_substitute: find_substitute(base_font, descriptor).is_some(),
+                }),
+            }
+        }
+    }
+
+    missing
+}
+
+impl<'a> Parser<'a> {
+    /// Every font used by the document whose font program is not embedded, and
+    /// so must be supplied by the system on which the document is displayed or
+    /// printed
+    pub fn missing_fonts(&self) -> Vec<MissingFont> {
+        missing_fonts(&self.pages())
+    }
+}
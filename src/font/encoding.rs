@@ -1,4 +1,4 @@
-use std::{collections::HashMap, convert::TryFrom, mem};
+use std::{collections::HashMap, convert::TryFrom};
 
 use crate::{
     error::{ParseError, PdfResult},
@@ -6,6 +6,8 @@ use crate::{
     FromObj, Resolve,
 };
 
+use super::latin::{glyph_name_unicode, mac_roman_unicode, standard_unicode, win_ansi_unicode};
+
 #[derive(Debug)]
 pub enum FontEncoding {
     Base(BaseFontEncoding),
@@ -75,30 +77,30 @@ pub struct FontEncodingDict {
     differences: Option<FontDifferences>,
 }
 
+/// The glyph names of the codes which differ from the base encoding
 #[derive(Debug)]
-struct FontDifferences(HashMap<u32, Vec<String>>);
+struct FontDifferences(HashMap<u32, String>);
 
 impl<'a> FromObj<'a> for FontDifferences {
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
-        let mut arr = resolver.assert_arr(obj)?;
-
-        if arr.is_empty() {
-            return Ok(FontDifferences(HashMap::new()));
-        }
+        let arr = resolver.assert_arr(obj)?;
 
         let mut map = HashMap::new();
 
-        let mut code_point = resolver.assert_unsigned_integer(arr.remove(0))?;
-        let mut names = Vec::new();
+        // each integer gives the code of the first of the names which follow
+        // it, and the names are assigned consecutive codes
+        let mut code_point = None;
 
-        for obj in arr.into_iter().skip(1) {
+        for obj in arr {
             match resolver.resolve(obj)? {
-                Object::Integer(i) => {
-                    map.insert(code_point, mem::take(&mut names));
-                    names.clear();
-                    code_point = u32::try_from(i)?;
-                }
-                Object::Name(name) => names.push(name),
+                Object::Integer(i) => code_point = Some(u32::try_from(i)?),
+                Object::Name(name) => match code_point.as_mut() {
+                    Some(code) => {
+                        map.insert(*code, name);
+                        *code += 1;
+                    }
+                    None => anyhow::bail!("differences array does not start with a code"),
+                },
                 _ => {
                     anyhow::bail!(ParseError::MismatchedObjectTypeAny {
                         expected: &[ObjectType::Name, ObjectType::Integer],
@@ -110,3 +112,63 @@ impl<'a> FromObj<'a> for FontDifferences {
         Ok(Self(map))
     }
 }
+
+impl FontEncoding {
+    /// The character of a code in a nonsymbolic font with this encoding, whose
+    /// implicit base encoding is StandardEncoding
+    pub(crate) fn unicode(&self, code: u8) -> Option<char> {
+        let dict = match self {
+            Self::Base(base) => return base.unicode(code),
+            Self::Dictionary(dict) => dict,
+        };
+
+        if let Some(name) = dict
+            .differences
+            .as_ref()
+            .and_then(|differences| differences.0.get(&u32::from(code)))
+        {
+            return glyph_name_unicode(name);
+        }
+
+        match dict.base_encoding {
+            Some(base) => base.unicode(code),
+            None => standard_unicode(code),
+        }
+    }
+}
+
+impl BaseFontEncoding {
+    /// The character of a code in this encoding
+    ///
+    /// The expert character set has no equivalent in Unicode for most of its
+    /// glyphs, such as old-style figures and small capitals, so no code of
+    /// MacExpertEncoding has a character.
+    pub(crate) fn unicode(self, code: u8) -> Option<char> {
+        match self {
+            Self::MacRomanEncoding => mac_roman_unicode(code),
+            Self::MacExpertEncoding => None,
+            Self::WinAnsiEncoding => win_ansi_unicode(code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Tokenizer;
+
+    #[test]
+    fn differences() {
+        let mut tokenizer = Tokenizer::new(
+            b"<< /BaseEncoding /WinAnsiEncoding /Differences [39 /quoteright /foo 128 /Euro] >>",
+        );
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+        let encoding = FontEncoding::from_obj(obj, &mut tokenizer).unwrap();
+
+        assert_eq!(encoding.unicode(b'A'), Some('A'));
+        assert_eq!(encoding.unicode(39), Some('\u{2019}'));
+        assert_eq!(encoding.unicode(40), None);
+        assert_eq!(encoding.unicode(0x80), Some('\u{20ac}'));
+        assert_eq!(encoding.unicode(0x93), Some('\u{201c}'));
+    }
+}
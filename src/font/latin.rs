@@ -0,0 +1,308 @@
+/*!
+ * The characters of the standard Latin character set, by glyph name and by
+ * their codes in the predefined simple font encodings (Annex D)
+ */
+
+use crate::postscript::STANDARD_ENCODING;
+
+/// The glyph names of the standard Latin character set, other than the
+/// single letters, sorted by name
+#[rustfmt::skip]
+static GLYPH_NAMES: &[(&str, char)] = &[
+    ("AE", '\u{c6}'),
+    ("Aacute", '\u{c1}'),
+    ("Acircumflex", '\u{c2}'),
+    ("Adieresis", '\u{c4}'),
+    ("Agrave", '\u{c0}'),
+    ("Aring", '\u{c5}'),
+    ("Atilde", '\u{c3}'),
+    ("Ccedilla", '\u{c7}'),
+    ("Eacute", '\u{c9}'),
+    ("Ecircumflex", '\u{ca}'),
+    ("Edieresis", '\u{cb}'),
+    ("Egrave", '\u{c8}'),
+    ("Eth", '\u{d0}'),
+    ("Euro", '\u{20ac}'),
+    ("Iacute", '\u{cd}'),
+    ("Icircumflex", '\u{ce}'),
+    ("Idieresis", '\u{cf}'),
+    ("Igrave", '\u{cc}'),
+    ("Lslash", '\u{141}'),
+    ("Ntilde", '\u{d1}'),
+    ("OE", '\u{152}'),
+    ("Oacute", '\u{d3}'),
+    ("Ocircumflex", '\u{d4}'),
+    ("Odieresis", '\u{d6}'),
+    ("Ograve", '\u{d2}'),
+    ("Oslash", '\u{d8}'),
+    ("Otilde", '\u{d5}'),
+    ("Scaron", '\u{160}'),
+    ("Thorn", '\u{de}'),
+    ("Uacute", '\u{da}'),
+    ("Ucircumflex", '\u{db}'),
+    ("Udieresis", '\u{dc}'),
+    ("Ugrave", '\u{d9}'),
+    ("Yacute", '\u{dd}'),
+    ("Ydieresis", '\u{178}'),
+    ("Zcaron", '\u{17d}'),
+    ("aacute", '\u{e1}'),
+    ("acircumflex", '\u{e2}'),
+    ("acute", '\u{b4}'),
+    ("adieresis", '\u{e4}'),
+    ("ae", '\u{e6}'),
+    ("agrave", '\u{e0}'),
+    ("ampersand", '\u{26}'),
+    ("aring", '\u{e5}'),
+    ("asciicircum", '\u{5e}'),
+    ("asciitilde", '\u{7e}'),
+    ("asterisk", '\u{2a}'),
+    ("at", '\u{40}'),
+    ("atilde", '\u{e3}'),
+    ("backslash", '\u{5c}'),
+    ("bar", '\u{7c}'),
+    ("braceleft", '\u{7b}'),
+    ("braceright", '\u{7d}'),
+    ("bracketleft", '\u{5b}'),
+    ("bracketright", '\u{5d}'),
+    ("breve", '\u{2d8}'),
+    ("brokenbar", '\u{a6}'),
+    ("bullet", '\u{2022}'),
+    ("caron", '\u{2c7}'),
+    ("ccedilla", '\u{e7}'),
+    ("cedilla", '\u{b8}'),
+    ("cent", '\u{a2}'),
+    ("circumflex", '\u{2c6}'),
+    ("colon", '\u{3a}'),
+    ("comma", '\u{2c}'),
+    ("copyright", '\u{a9}'),
+    ("currency", '\u{a4}'),
+    ("dagger", '\u{2020}'),
+    ("daggerdbl", '\u{2021}'),
+    ("degree", '\u{b0}'),
+    ("dieresis", '\u{a8}'),
+    ("divide", '\u{f7}'),
+    ("dollar", '\u{24}'),
+    ("dotaccent", '\u{2d9}'),
+    ("dotlessi", '\u{131}'),
+    ("eacute", '\u{e9}'),
+    ("ecircumflex", '\u{ea}'),
+    ("edieresis", '\u{eb}'),
+    ("egrave", '\u{e8}'),
+    ("eight", '\u{38}'),
+    ("ellipsis", '\u{2026}'),
+    ("emdash", '\u{2014}'),
+    ("endash", '\u{2013}'),
+    ("equal", '\u{3d}'),
+    ("eth", '\u{f0}'),
+    ("exclam", '\u{21}'),
+    ("exclamdown", '\u{a1}'),
+    ("fi", '\u{fb01}'),
+    ("five", '\u{35}'),
+    ("fl", '\u{fb02}'),
+    ("florin", '\u{192}'),
+    ("four", '\u{34}'),
+    ("fraction", '\u{2044}'),
+    ("germandbls", '\u{df}'),
+    ("grave", '\u{60}'),
+    ("greater", '\u{3e}'),
+    ("guillemotleft", '\u{ab}'),
+    ("guillemotright", '\u{bb}'),
+    ("guilsinglleft", '\u{2039}'),
+    ("guilsinglright", '\u{203a}'),
+    ("hungarumlaut", '\u{2dd}'),
+    ("hyphen", '\u{2d}'),
+    ("iacute", '\u{ed}'),
+    ("icircumflex", '\u{ee}'),
+    ("idieresis", '\u{ef}'),
+    ("igrave", '\u{ec}'),
+    ("less", '\u{3c}'),
+    ("logicalnot", '\u{ac}'),
+    ("lslash", '\u{142}'),
+    ("macron", '\u{af}'),
+    ("minus", '\u{2212}'),
+    ("mu", '\u{b5}'),
+    ("multiply", '\u{d7}'),
+    ("nine", '\u{39}'),
+    ("ntilde", '\u{f1}'),
+    ("numbersign", '\u{23}'),
+    ("oacute", '\u{f3}'),
+    ("ocircumflex", '\u{f4}'),
+    ("odieresis", '\u{f6}'),
+    ("oe", '\u{153}'),
+    ("ogonek", '\u{2db}'),
+    ("ograve", '\u{f2}'),
+    ("one", '\u{31}'),
+    ("onehalf", '\u{bd}'),
+    ("onequarter", '\u{bc}'),
+    ("onesuperior", '\u{b9}'),
+    ("ordfeminine", '\u{aa}'),
+    ("ordmasculine", '\u{ba}'),
+    ("oslash", '\u{f8}'),
+    ("otilde", '\u{f5}'),
+    ("paragraph", '\u{b6}'),
+    ("parenleft", '\u{28}'),
+    ("parenright", '\u{29}'),
+    ("percent", '\u{25}'),
+    ("period", '\u{2e}'),
+    ("periodcentered", '\u{b7}'),
+    ("perthousand", '\u{2030}'),
+    ("plus", '\u{2b}'),
+    ("plusminus", '\u{b1}'),
+    ("question", '\u{3f}'),
+    ("questiondown", '\u{bf}'),
+    ("quotedbl", '\u{22}'),
+    ("quotedblbase", '\u{201e}'),
+    ("quotedblleft", '\u{201c}'),
+    ("quotedblright", '\u{201d}'),
+    ("quoteleft", '\u{2018}'),
+    ("quoteright", '\u{2019}'),
+    ("quotesinglbase", '\u{201a}'),
+    ("quotesingle", '\u{27}'),
+    ("registered", '\u{ae}'),
+    ("ring", '\u{2da}'),
+    ("scaron", '\u{161}'),
+    ("section", '\u{a7}'),
+    ("semicolon", '\u{3b}'),
+    ("seven", '\u{37}'),
+    ("six", '\u{36}'),
+    ("slash", '\u{2f}'),
+    ("space", '\u{20}'),
+    ("sterling", '\u{a3}'),
+    ("thorn", '\u{fe}'),
+    ("three", '\u{33}'),
+    ("threequarters", '\u{be}'),
+    ("threesuperior", '\u{b3}'),
+    ("tilde", '\u{2dc}'),
+    ("trademark", '\u{2122}'),
+    ("two", '\u{32}'),
+    ("twosuperior", '\u{b2}'),
+    ("uacute", '\u{fa}'),
+    ("ucircumflex", '\u{fb}'),
+    ("udieresis", '\u{fc}'),
+    ("ugrave", '\u{f9}'),
+    ("underscore", '\u{5f}'),
+    ("yacute", '\u{fd}'),
+    ("ydieresis", '\u{ff}'),
+    ("yen", '\u{a5}'),
+    ("zcaron", '\u{17e}'),
+    ("zero", '\u{30}'),
+];
+
+/// Codes 0x80 to 0x9F of WinAnsiEncoding, which differ from ISO Latin-1
+#[rustfmt::skip]
+static WIN_ANSI_HIGH: [Option<char>; 32] = [
+    Some('\u{20ac}'), None, Some('\u{201a}'), Some('\u{192}'),
+    Some('\u{201e}'), Some('\u{2026}'), Some('\u{2020}'), Some('\u{2021}'),
+    Some('\u{2c6}'), Some('\u{2030}'), Some('\u{160}'), Some('\u{2039}'),
+    Some('\u{152}'), None, Some('\u{17d}'), None,
+    None, Some('\u{2018}'), Some('\u{2019}'), Some('\u{201c}'),
+    Some('\u{201d}'), Some('\u{2022}'), Some('\u{2013}'), Some('\u{2014}'),
+    Some('\u{2dc}'), Some('\u{2122}'), Some('\u{161}'), Some('\u{203a}'),
+    Some('\u{153}'), None, Some('\u{17e}'), Some('\u{178}'),
+];
+
+/// Codes 0x80 to 0xFF of MacRomanEncoding. Codes which are not part of the
+/// standard Latin character set are given their Mac OS Roman characters
+static MAC_ROMAN_HIGH: &str = "ÄÅÇÉÑÖÜáàâäãåçéèêëíìîïñóòôöõúùûü†°¢£§•¶ß®©™´¨≠ÆØ∞±≤≥¥µ∂∑∏π∫ªºΩæø\
+    ¿¡¬√ƒ≈∆«»…\u{a0}ÀÃÕŒœ–—“”‘’÷◊ÿŸ⁄¤‹›ﬁﬂ‡·‚„‰ÂÊÁËÈÍÎÏÌÓÔ\u{f8ff}ÒÚÛÙıˆ˜¯˘˙˚¸˝˛ˇ";
+
+/// The character named by a glyph name, either one of the standard Latin
+/// character set or of the form `uniXXXX` or `uXXXX[XX]`
+pub(crate) fn glyph_name_unicode(name: &str) -> Option<char> {
+    if let [letter @ (b'A'..=b'Z' | b'a'..=b'z')] = name.as_bytes() {
+        return Some(char::from(*letter));
+    }
+
+    if let Ok(idx) = GLYPH_NAMES.binary_search_by_key(&name, |&(name, _)| name) {
+        return Some(GLYPH_NAMES[idx].1);
+    }
+
+    let hex = match name.strip_prefix("uni") {
+        Some(hex) if hex.len() == 4 => hex,
+        _ => match name.strip_prefix('u') {
+            Some(hex) if (4..=6).contains(&hex.len()) => hex,
+            _ => return None,
+        },
+    };
+
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+}
+
+/// The glyph name of a character, as used in the Differences array of an
+/// encoding dictionary
+pub(crate) fn unicode_glyph_name(c: char) -> String {
+    if c.is_ascii_alphabetic() {
+        return c.to_string();
+    }
+
+    match GLYPH_NAMES.iter().find(|&&(_, named)| named == c) {
+        Some((name, _)) => (*name).to_owned(),
+        None if c <= '\u{ffff}' => format!("uni{:04X}", u32::from(c)),
+        None => format!("u{:X}", u32::from(c)),
+    }
+}
+
+/// The character of a code in StandardEncoding, the built-in encoding of
+/// Type 1 fonts with the standard Latin character set
+pub(crate) fn standard_unicode(code: u8) -> Option<char> {
+    STANDARD_ENCODING
+        .get(usize::from(code))
+        .copied()
+        .flatten()
+        .and_then(glyph_name_unicode)
+}
+
+/// The character of a code in WinAnsiEncoding
+pub(crate) fn win_ansi_unicode(code: u8) -> Option<char> {
+    match code {
+        0x20..=0x7e | 0xa0..=0xff => Some(char::from(code)),
+        0x80..=0x9f => WIN_ANSI_HIGH[usize::from(code - 0x80)],
+        _ => None,
+    }
+}
+
+/// The character of a code in MacRomanEncoding
+pub(crate) fn mac_roman_unicode(code: u8) -> Option<char> {
+    match code {
+        0x20..=0x7e => Some(char::from(code)),
+        0x80..=0xff => MAC_ROMAN_HIGH.chars().nth(usize::from(code - 0x80)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn glyph_names() {
+        assert!(GLYPH_NAMES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(MAC_ROMAN_HIGH.chars().count(), 128);
+
+        assert_eq!(glyph_name_unicode("A"), Some('A'));
+        assert_eq!(glyph_name_unicode("quoteright"), Some('\u{2019}'));
+        assert_eq!(glyph_name_unicode("uni20AC"), Some('\u{20ac}'));
+        assert_eq!(glyph_name_unicode("u1F600"), Some('\u{1f600}'));
+        assert_eq!(glyph_name_unicode("union"), None);
+        assert_eq!(glyph_name_unicode("g123"), None);
+
+        assert_eq!(unicode_glyph_name('\u{e9}'), "eacute");
+        assert_eq!(unicode_glyph_name('\u{3b1}'), "uni03B1");
+    }
+
+    #[test]
+    fn encodings() {
+        assert_eq!(standard_unicode(0x27), Some('\u{2019}'));
+        assert_eq!(standard_unicode(0xe1), Some('\u{c6}'));
+        assert_eq!(win_ansi_unicode(0x27), Some('\''));
+        assert_eq!(win_ansi_unicode(0x80), Some('\u{20ac}'));
+        assert_eq!(win_ansi_unicode(0x81), None);
+        assert_eq!(win_ansi_unicode(0xe9), Some('\u{e9}'));
+        assert_eq!(mac_roman_unicode(0x8e), Some('\u{e9}'));
+        assert_eq!(mac_roman_unicode(0xde), Some('\u{fb01}'));
+    }
+}
@@ -15,9 +15,11 @@ use crate::{
 
 pub use self::{
     cff::{CffCharStringInterpreter, CffFile, CffParser},
-    cid::{CidFontSubtype, CidFontWidths, CidToGidMap},
+    cid::{CidFontDictionary, CidFontSubtype, VerticalMetric},
+    cmap::{CharCode, WritingMode},
     descriptor::FontDescriptor,
     embedded::Type3FontFile,
+    embedding::MissingFont,
     glyph::Glyph,
     true_type::TrueTypeFont,
     type0::Type0Font,
@@ -25,6 +27,12 @@ pub use self::{
     type3::Type3Font,
 };
 
+pub(crate) use self::{
+    encoding::FontEncoding,
+    latin::{standard_unicode, unicode_glyph_name, win_ansi_unicode},
+    substitute::find_substitute,
+};
+
 mod cff;
 mod cid;
 mod cid_font_type0;
@@ -33,8 +41,11 @@ mod cjk;
 mod cmap;
 mod descriptor;
 mod embedded;
+mod embedding;
 mod encoding;
 mod glyph;
+mod latin;
+mod substitute;
 pub mod true_type;
 mod type0;
 mod type1;
@@ -106,6 +117,52 @@ pub(crate) static BASE_14_FONTS: Lazy<BTreeMap<&'static str, Arc<RwLock<Type1Pos
 
 impl<'a> Font<'a> {
     const TYPE: &'static str = "Font";
+
+    /// The font descriptor of the font, or of the descendant CIDFont of a
+    /// composite font
+    pub(crate) fn descriptor(&self) -> Option<&FontDescriptor<'a>> {
+        match self {
+            Font::Type1(font) => font.base.font_descriptor.as_ref(),
+            Font::MmType1(font) => font.type1.base.font_descriptor.as_ref(),
+            Font::TrueType(font) => font.base.font_descriptor.as_ref(),
+            Font::Type3(..) => None,
+            Font::Type0(font) => font
+                .descendant_font
+                .first()
+                .map(|descendant| &descendant.font_descriptor),
+        }
+    }
+
+    /// The PostScript name and font descriptor of the font, if its font program
+    /// is not embedded in the document
+    ///
+    /// Type 3 fonts define their glyphs in content streams, and so are always
+    /// embedded. For composite fonts, the font program is that of the
+    /// descendant CIDFont.
+    pub(crate) fn missing_font_program(&self) -> Option<(&str, Option<&FontDescriptor<'a>>)> {
+        let (base_font, descriptor) = match self {
+            Font::Type1(font) => (font.base_font.as_str(), font.base.font_descriptor.as_ref()),
+            Font::MmType1(font) => (
+                font.type1.base_font.as_str(),
+                font.type1.base.font_descriptor.as_ref(),
+            ),
+            Font::TrueType(font) => (font.base_font.as_str(), font.base.font_descriptor.as_ref()),
+            Font::Type3(..) => return None,
+            Font::Type0(font) => {
+                let descendant = font.descendant_font.first()?;
+
+                (
+                    descendant.base_font.0.as_str(),
+                    Some(&descendant.font_descriptor),
+                )
+            }
+        };
+
+        match descriptor {
+            Some(descriptor) if descriptor.is_embedded() => None,
+            descriptor => Some((base_font, descriptor)),
+        }
+    }
 }
 
 impl<'a> FromObj<'a> for Font<'a> {
@@ -0,0 +1,238 @@
+/*!
+ * Substitution of installed system fonts for fonts which are neither embedded
+ * nor one of the standard 14
+ */
+
+use super::descriptor::FontDescriptor;
+
+/// The characteristics of a non-embedded font used to select a substitute
+#[derive(Debug, Clone, PartialEq)]
+struct SubstituteQuery {
+    /// The PostScript name, without any subset tag or style suffix
+    family: String,
+
+    /// The PostScript name, without any subset tag
+    post_script_name: String,
+    is_italic: bool,
+    is_fixed_pitch: bool,
+    weight: u16,
+    panose: Option<[u8; 10]>,
+}
+
+impl SubstituteQuery {
+    fn new(base_font: &str, descriptor: Option<&FontDescriptor>) -> Self {
+        let post_script_name = strip_subset_tag(base_font);
+
+        let (family, style) = match post_script_name.find([',', '-']) {
+            Some(idx) => (&post_script_name[..idx], &post_script_name[idx + 1..]),
+            None => (post_script_name, ""),
+        };
+
+        let family = descriptor
+            .and_then(|descriptor| descriptor.font_family.as_deref())
+            .unwrap_or(family);
+
+        let flags = descriptor.map(|descriptor| descriptor.flags);
+
+        let is_bold = style.contains("Bold") || flags.map_or(false, |flags| flags.is_force_bold());
+
+        let weight = descriptor
+            .and_then(|descriptor| descriptor.font_weight)
+            .map(|weight| weight as u16)
+            .unwrap_or(if is_bold { 700 } else { 400 });
+
+        Self {
+            family: normalize(family),
+            post_script_name: normalize(post_script_name),
+            is_italic: style.contains("Italic")
+                || style.contains("Oblique")
+                || flags.map_or(false, |flags| flags.is_italic()),
+            is_fixed_pitch: flags.map_or(false, |flags| flags.is_fixed_pitch()),
+            weight,
+            panose: descriptor
+                .and_then(|descriptor| descriptor.style.as_ref())
+                .and_then(|style| style.panose()),
+        }
+    }
+
+    /// How closely a font with the given characteristics matches the query.
+    /// Higher scores are better matches
+    fn score(
+        &self,
+        post_script_name: &str,
+        families: &[&str],
+        is_italic: bool,
+        is_fixed_pitch: bool,
+        weight: u16,
+        panose: Option<[u8; 10]>,
+    ) -> i32 {
+        let mut score = 0;
+
+        if normalize(post_script_name) == self.post_script_name {
+            score += 100;
+        }
+
+        if families
+            .iter()
+            .any(|family| normalize(family) == self.family)
+        {
+            score += 50;
+        }
+
+        if is_italic == self.is_italic {
+            score += 10;
+        }
+
+        if is_fixed_pitch == self.is_fixed_pitch {
+            score += 10;
+        }
+
+        score -= (i32::from(weight) - i32::from(self.weight)).abs() / 100;
+
+        // PANOSE digits of 0 and 1 mean "any" and "no fit", and so carry no
+        // information
+        if let (Some(a), Some(b)) = (self.panose, panose) {
+            score += 2 * a.iter().zip(&b).filter(|&(&a, &b)| a > 1 && a == b).count() as i32;
+        }
+
+        score
+    }
+}
+
+/// Remove the tag identifying a font subset, which consists of six uppercase
+/// letters followed by a plus sign
+fn strip_subset_tag(name: &str) -> &str {
+    match name.split_once('+') {
+        Some((tag, name)) if tag.len() == 6 && tag.bytes().all(|b| b.is_ascii_uppercase()) => name,
+        _ => name,
+    }
+}
+
+/// Lowercase `name` and remove the spaces and punctuation which differ between
+/// PostScript names and family names, so that e.g. "Times New Roman" and
+/// "TimesNewRoman" compare equal
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// The PANOSE classification of a TrueType font, read from its OS/2 table
+fn os2_panose(data: &[u8]) -> Option<[u8; 10]> {
+    let read_u16 = |offset: usize| -> Option<u16> {
+        Some(u16::from_be_bytes(
+            data.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    };
+
+    let read_u32 = |offset: usize| -> Option<u32> {
+        Some(u32::from_be_bytes(
+            data.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+
+    let num_tables = read_u16(4)? as usize;
+
+    (0..num_tables).find_map(|idx| {
+        let record = 12 + idx * 16;
+
+        if data.get(record..record + 4)? != b"OS/2" {
+            return None;
+        }
+
+        let offset = read_u32(record + 8)? as usize;
+
+        data.get(offset + 32..offset + 42)?.try_into().ok()
+    })
+}
+
+/// Whether `data` is a single TrueType font with glyf outlines, as opposed to
+/// a font collection or an OpenType font with CFF outlines
+fn is_true_type_outline_font(data: &[u8]) -> bool {
+    matches!(data.get(..4), Some([0x00, 0x01, 0x00, 0x00] | b"true"))
+}
+
+/// The contents of the installed system font which best matches a font that is
+/// neither embedded nor one of the standard 14
+///
+/// Candidates are matched by PostScript and family name, then by the italic and
+/// fixed pitch flags, weight, and PANOSE classification of the font
+/// descriptor, if any.
+#[cfg(feature = "system-fonts")]
+pub(crate) fn find_substitute(
+    base_font: &str,
+    descriptor: Option<&FontDescriptor>,
+) -> Option<Vec<u8>> {
+    use once_cell::sync::Lazy;
+
+    static SYSTEM_FONTS: Lazy<fontdb::Database> = Lazy::new(|| {
+        let mut db = fontdb::Database::new();
+        db.load_system_fonts();
+        db
+    });
+
+    let query = SubstituteQuery::new(base_font, descriptor);
+
+    SYSTEM_FONTS
+        .faces()
+        .filter_map(|face| {
+            let panose = SYSTEM_FONTS.with_face_data(face.id, |data, _| {
+                is_true_type_outline_font(data).then(|| os2_panose(data))
+            })??;
+
+            let families = face
+                .families
+                .iter()
+                .map(|(family, _)| family.as_str())
+                .collect::<Vec<&str>>();
+
+            let score = query.score(
+                &face.post_script_name,
+                &families,
+                face.style != fontdb::Style::Normal,
+                face.monospaced,
+                face.weight.0,
+                panose,
+            );
+
+            Some((score, face.id))
+        })
+        .max_by_key(|&(score, _)| score)
+        .and_then(|(_, id)| SYSTEM_FONTS.with_face_data(id, |data, _| data.to_vec()))
+}
+
+/// Without the `system-fonts` feature, no substitutes are available
+#[cfg(not(feature = "system-fonts"))]
+pub(crate) fn find_substitute(
+    _base_font: &str,
+    _descriptor: Option<&FontDescriptor>,
+) -> Option<Vec<u8>> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn query_from_base_font() {
+        let query = SubstituteQuery::new("ABCDEF+Garamond,BoldItalic", None);
+
+        assert_eq!(query.family, "garamond");
+        assert_eq!(query.post_script_name, "garamondbolditalic");
+        assert!(query.is_italic);
+        assert!(!query.is_fixed_pitch);
+        assert_eq!(query.weight, 700);
+    }
+
+    #[test]
+    fn family_outranks_style() {
+        let query = SubstituteQuery::new("Garamond-Bold", None);
+
+        let garamond = query.score("Garamond", &["Garamond"], false, false, 400, None);
+        let other = query.score("Other-Bold", &["Other"], false, false, 700, None);
+
+        assert!(garamond > other);
+    }
+}
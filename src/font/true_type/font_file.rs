@@ -1,3 +1,8 @@
+use crate::{
+    data_structures::Matrix,
+    geometry::{Outline, Path},
+};
+
 use super::{
     parse::TrueTypeParser,
     table::{
@@ -19,6 +24,10 @@ pub struct ParsedTrueTypeFontFile<'a> {
 }
 
 impl<'a> ParsedTrueTypeFontFile<'a> {
+    /// The maximum nesting of compound glyphs we will follow, regardless of the value
+    /// claimed by the maxp table
+    const MAX_COMPONENT_DEPTH: u16 = 16;
+
     pub fn new(buffer: &'a [u8]) -> anyhow::Result<Self> {
         let mut parser = TrueTypeParser::new(buffer);
 
@@ -95,15 +104,82 @@ impl<'a> ParsedTrueTypeFontFile<'a> {
         parser.read_loca_table(entry.offset as usize, entry.length as usize, loca_format)
     }
 
-    pub fn glyph(&mut self, char_code: u32) -> anyhow::Result<TrueTypeGlyph> {
-        let glyph_index = if let Some(cmap) = &self.cmap {
-            // todo: be smarter about subtable selection
-            cmap.subtables[0].lookup_char_code(char_code)
-        } else {
-            char_code
+    /// The glyph index of a character code in a simple (non-CID) TrueType font
+    ///
+    /// A TrueType font program embedded in a PDF may contain several cmap subtables,
+    /// and the font dictionary gives no indication of which one applies. As for
+    /// conforming readers, we look first in the (3, 0) Microsoft Symbol subtable,
+    /// where codes may have been offset into the range 0xF000-0xF0FF (or a nearby
+    /// range), then the (1, 0) Macintosh Roman subtable, and then the (3, 1)
+    /// Microsoft Unicode subtable.
+    ///
+    /// If the font has no cmap table, the character code is used as the glyph index.
+    pub fn glyph_index(&self, char_code: u32) -> u32 {
+        let cmap = match &self.cmap {
+            Some(cmap) => cmap,
+            None => return char_code,
         };
 
-        let glyf_entry = self.loca.get_glyf_entry(glyph_index).unwrap();
+        if let Some(symbol) = cmap.find_subtable(3, 0) {
+            for high_byte in [0x0000, 0xF000, 0xF100, 0xF200] {
+                let glyph_index = symbol.lookup_char_code(high_byte | char_code);
+
+                if glyph_index != 0 {
+                    return glyph_index;
+                }
+            }
+        }
+
+        for (platform_id, platform_specific_id) in [(1, 0), (3, 1)] {
+            if let Some(subtable) = cmap.find_subtable(platform_id, platform_specific_id) {
+                let glyph_index = subtable.lookup_char_code(char_code);
+
+                if glyph_index != 0 {
+                    return glyph_index;
+                }
+            }
+        }
+
+        // todo: be smarter about subtable selection
+        cmap.subtables
+            .first()
+            .map(|subtable| subtable.lookup_char_code(char_code))
+            .unwrap_or(0)
+    }
+
+    /// The glyph index of a character, looked up in the Unicode cmap subtables
+    ///
+    /// Returns 0, the .notdef glyph, if the font has no glyph for the character.
+    pub fn unicode_glyph_index(&self, c: char) -> u32 {
+        let cmap = match &self.cmap {
+            Some(cmap) => cmap,
+            None => return 0,
+        };
+
+        for (platform_id, platform_specific_id) in [(3, 10), (3, 1), (0, 4), (0, 3)] {
+            if let Some(subtable) = cmap.find_subtable(platform_id, platform_specific_id) {
+                let glyph_index = subtable.lookup_char_code(u32::from(c));
+
+                if glyph_index != 0 {
+                    return glyph_index;
+                }
+            }
+        }
+
+        0
+    }
+
+    pub fn glyph(&mut self, char_code: u32) -> anyhow::Result<TrueTypeGlyph> {
+        self.glyph_by_index(self.glyph_index(char_code))
+    }
+
+    pub fn glyph_by_index(&mut self, glyph_index: u32) -> anyhow::Result<TrueTypeGlyph> {
+        let glyf_entry = match self.loca.get_glyf_entry(glyph_index) {
+            Some(entry) if entry.len != 0 => entry,
+            // Glyphs without an outline, such as the space, and glyph indices
+            // outside of the font are both drawn as nothing
+            _ => return Ok(TrueTypeGlyph::Simple(SimpleGlyph::empty())),
+        };
 
         let glyf_table_offset = self
             .font_directory
@@ -112,12 +188,8 @@ impl<'a> ParsedTrueTypeFontFile<'a> {
 
         let glyf_offset = glyf_table_offset + glyf_entry.offset;
 
-        if glyf_entry.len == 0 {
-            return Ok(TrueTypeGlyph::Simple(SimpleGlyph::empty()));
-        }
-
         self.parser.cursor = glyf_offset as usize;
-        let glyf = self.parser.parse_glyph().unwrap();
+        let glyf = self.parser.parse_glyph()?;
 
         // todo: this should be true
         // assert_eq!(
@@ -128,6 +200,56 @@ impl<'a> ParsedTrueTypeFontFile<'a> {
         Ok(glyf)
     }
 
+    /// The outline of a glyph, scaled such that 1 unit is 1 em
+    ///
+    /// Glyph programs are not executed, so the outline is unhinted.
+    pub fn glyph_outline(&mut self, glyph_index: u32) -> anyhow::Result<Outline> {
+        let mut paths = Vec::new();
+
+        self.append_glyph_paths(glyph_index, Matrix::identity(), 0, &mut paths)?;
+
+        let mut outline = Outline { paths };
+
+        let scale = 1.0 / f32::from(self.head.units_per_em.max(1));
+        outline.apply_transform(Matrix::new_scale(scale, scale));
+
+        Ok(outline)
+    }
+
+    fn append_glyph_paths(
+        &mut self,
+        glyph_index: u32,
+        transform: Matrix,
+        depth: u16,
+        paths: &mut Vec<Path>,
+    ) -> anyhow::Result<()> {
+        // Guard against compound glyphs which (directly or indirectly) contain themselves
+        if depth > Self::MAX_COMPONENT_DEPTH {
+            anyhow::bail!("compound glyph {} nested too deeply", glyph_index);
+        }
+
+        match self.glyph_by_index(glyph_index)? {
+            TrueTypeGlyph::Simple(glyph) => {
+                for mut path in glyph.paths() {
+                    path.apply_transform(transform);
+                    paths.push(path);
+                }
+            }
+            TrueTypeGlyph::Compound(parts) => {
+                for part in parts {
+                    self.append_glyph_paths(
+                        u32::from(part.glyph_index),
+                        part.transform() * transform,
+                        depth + 1,
+                        paths,
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn glyphs(&mut self) -> Vec<TrueTypeGlyph> {
         let offset = self
             .font_directory
@@ -168,3 +290,142 @@ impl<'a> ParsedTrueTypeFontFile<'a> {
         self.parser.read_name_table(offset as usize)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        font::true_type::table::{CompoundGlyphComponentFlags, OutlineFlag},
+        geometry::BoundingBox,
+    };
+
+    fn u16s(values: &[u16]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect()
+    }
+
+    /// A font file whose table directory lists each of `tables`
+    fn font_file(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut file = vec![0, 1, 0, 0];
+        file.extend(u16s(&[tables.len() as u16, 0, 0, 0]));
+
+        let mut offset = file.len() + tables.len() * 16;
+        let mut data = Vec::new();
+
+        for (tag, table) in tables {
+            file.extend_from_slice(*tag);
+            file.extend_from_slice(&0_u32.to_be_bytes());
+            file.extend_from_slice(&(offset as u32).to_be_bytes());
+            file.extend_from_slice(&(table.len() as u32).to_be_bytes());
+
+            offset += table.len();
+            data.extend_from_slice(table);
+        }
+
+        file.extend(data);
+        file
+    }
+
+    /// A format 6 cmap subtable mapping `first_code` onwards to `glyphs`
+    fn trimmed_subtable(first_code: u16, glyphs: &[u16]) -> Vec<u8> {
+        let length = 10 + glyphs.len() as u16 * 2;
+
+        let mut subtable = u16s(&[6, length, 0, first_code, glyphs.len() as u16]);
+        subtable.extend(u16s(glyphs));
+        subtable
+    }
+
+    /// A font of a square, a compound glyph of that square scaled by half and
+    /// offset, and no .notdef outline. "A" maps to the square in the Macintosh
+    /// cmap subtable, and "B" to the compound glyph in the Microsoft Symbol
+    /// subtable, with its codes offset to 0xF000
+    fn font() -> Vec<u8> {
+        let mut head = vec![0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0x5f, 0x0f, 0x3c, 0xf5];
+        head.extend(u16s(&[0, 1000]));
+        head.extend([0; 24]);
+        head.extend(u16s(&[0, 0, 0, 1, 0]));
+
+        let mut maxp = vec![0, 1, 0, 0];
+        maxp.extend(u16s(&[3; 14]));
+
+        let mut square = u16s(&[1, 0, 0, 100, 100, 3, 0]);
+        square.extend([OutlineFlag::ON_CURVE; 4]);
+        square.extend(u16s(&[0, 100, 0, (-100_i16) as u16]));
+        square.extend(u16s(&[0, 0, 100, 0]));
+
+        let mut compound = u16s(&[(-1_i16) as u16, 0, 0, 50, 50]);
+        compound.extend(u16s(&[
+            CompoundGlyphComponentFlags::ARG_1_AND_2_ARE_WORDS
+                | CompoundGlyphComponentFlags::ARGS_ARE_XY_VALUES
+                | CompoundGlyphComponentFlags::WE_HAVE_A_SCALE,
+            1,
+            500,
+            0,
+            0x2000,
+        ]));
+
+        let loca = [0, 0, square.len(), square.len() + compound.len()]
+            .iter()
+            .flat_map(|&offset| (offset as u32).to_be_bytes())
+            .collect();
+
+        let mut glyf = square;
+        glyf.extend(compound);
+
+        let mac_roman = trimmed_subtable(b'A'.into(), &[1]);
+        let symbol = trimmed_subtable(0xf042, &[2]);
+
+        let mut cmap = u16s(&[0, 2, 1, 0, 0, 20, 3, 0, 0]);
+        cmap.extend(u16s(&[20 + mac_roman.len() as u16]));
+        cmap.extend(mac_roman);
+        cmap.extend(symbol);
+
+        font_file(&[
+            (b"cmap", cmap),
+            (b"glyf", glyf),
+            (b"head", head),
+            (b"loca", loca),
+            (b"maxp", maxp),
+        ])
+    }
+
+    fn assert_bounds(bbox: BoundingBox, [min_x, min_y, max_x, max_y]: [f32; 4]) {
+        let bounds = [bbox.min.x, bbox.min.y, bbox.max.x, bbox.max.y];
+
+        for (actual, expected) in bounds.iter().zip([min_x, min_y, max_x, max_y]) {
+            assert!((actual - expected).abs() < 1e-6, "{:?}", bounds);
+        }
+    }
+
+    #[test]
+    fn select_cmap_subtable() {
+        let font = font();
+        let font = ParsedTrueTypeFontFile::new(&font).unwrap();
+
+        assert_eq!(font.glyph_index(b'A'.into()), 1);
+        assert_eq!(font.glyph_index(b'B'.into()), 2);
+        assert_eq!(font.glyph_index(b'C'.into()), 0);
+    }
+
+    #[test]
+    fn compound_glyph_outline() {
+        let font = font();
+        let mut font = ParsedTrueTypeFontFile::new(&font).unwrap();
+
+        assert_bounds(
+            font.glyph_outline(1).unwrap().bounding_box(),
+            [0.0, 0.0, 0.1, 0.1],
+        );
+
+        let outline = font.glyph_outline(2).unwrap();
+        assert_eq!(outline.paths.len(), 1);
+
+        assert_bounds(outline.bounding_box(), [0.5, 0.0, 0.55, 0.05]);
+
+        // glyphs without an outline, and those beyond the end of the font
+        assert!(font.glyph_outline(0).unwrap().paths.is_empty());
+        assert!(font.glyph_outline(7).unwrap().paths.is_empty());
+    }
+}
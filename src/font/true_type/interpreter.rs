@@ -1,18 +1,12 @@
 #![allow(unused)]
 
-use std::collections::VecDeque;
-
 use anyhow::anyhow;
 
-use crate::{
-    font::{true_type::table::TrueTypeGlyph, Glyph},
-    geometry::{Outline, Path, Point},
-};
+use crate::{font::Glyph, geometry::Point};
 
 use super::{
     graphics_state::{RoundState, TrueTypeGraphicsState, Vector, Zone},
     instruction::TrueTypeInstruction,
-    table::OutlineFlag,
     F26Dot6, ParsedTrueTypeFontFile,
 };
 
@@ -169,94 +163,11 @@ pub struct TrueTypeInterpreter<'a> {
     original_positions: Vec<Point>,
     glyph_zone: Vec<Point>,
     twilight_zone: Vec<Point>,
-}
-
-struct PointIterator<'a> {
-    x_coords: &'a [i16],
-    y_coords: &'a [i16],
-    flags: &'a [u8],
-    cursor: usize,
-    queue: VecDeque<(Point, u8)>,
-    last_point: Point,
-    last_on_curve: bool,
-    first_point_on_curve: Option<Point>,
-}
-
-impl<'a> PointIterator<'a> {
-    pub fn new(x_coords: &'a [i16], y_coords: &'a [i16], flags: &'a [u8]) -> Self {
-        assert_eq!(x_coords.len(), y_coords.len());
-        assert_eq!(x_coords.len(), flags.len());
-
-        if flags.len() >= 2 {
-            // assert!(flags[0] & OutlineFlag::ON_CURVE != 0 || flags[1] & OutlineFlag::ON_CURVE != 0);
-        }
-
-        Self {
-            x_coords,
-            y_coords,
-            flags,
-            cursor: 0,
-            queue: VecDeque::new(),
-            last_point: Point::origin(),
-            last_on_curve: false,
-            first_point_on_curve: None,
-        }
-    }
-
-    fn next_point(&mut self) -> Option<Point> {
-        if let Some((point, flag)) = self.queue.pop_front() {
-            return Some(point);
-        }
-
-        let is_last = self.x_coords.get(self.cursor).is_none();
-
-        if is_last && !self.last_on_curve {
-            self.last_on_curve = true;
-            return self.first_point_on_curve;
-        }
-
-        let x = *self.x_coords.get(self.cursor)? as f32 / 2048.0;
-        let y = *self.y_coords.get(self.cursor)? as f32 / 2048.0;
-        let flag = *self.flags.get(self.cursor)?;
-
-        let mut point = Point { x, y };
-        let on_curve = flag & OutlineFlag::ON_CURVE != 0;
-
-        if self.cursor == 0 && !on_curve {
-            self.queue.push_back((point, flag));
-
-            self.last_on_curve = true;
-            self.cursor += 1;
-
-            self.first_point_on_curve = Some(point.midpoint(Point::origin()));
-
-            return Some(point.midpoint(Point::origin()));
-        } else {
-            self.first_point_on_curve.get_or_insert(point);
-        }
-
-        self.last_point = point;
-
-        if (on_curve && self.last_on_curve) || (!on_curve && !self.last_on_curve) {
-            self.queue.push_back((point, flag));
 
-            point = point.midpoint(self.last_point);
-        }
-
-        self.last_on_curve = on_curve;
-
-        self.cursor += 1;
-
-        Some(point)
-    }
-}
-
-impl<'a> Iterator for PointIterator<'a> {
-    type Item = Point;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.next_point()
-    }
+    /// Whether character codes are glyph indices, rather than codes to be mapped
+    /// through the font's cmap table. This is the case for CIDFontType2 fonts with
+    /// an identity CIDToGIDMap
+    char_codes_are_glyph_indices: bool,
 }
 
 impl<'a> TrueTypeInterpreter<'a> {
@@ -272,66 +183,29 @@ impl<'a> TrueTypeInterpreter<'a> {
             original_positions: Vec::new(),
             glyph_zone: Vec::new(),
             twilight_zone: vec![Point::origin(); max_twilight_points as usize],
+            char_codes_are_glyph_indices: false,
         }
     }
 
+    pub fn with_glyph_index_char_codes(mut self) -> Self {
+        self.char_codes_are_glyph_indices = true;
+        self
+    }
+
+    /// Rasterization is done with hinting off; glyph programs are not executed
     pub fn render_glyph(&mut self, char_code: u32) -> anyhow::Result<Glyph> {
         self.reset();
 
-        let ttf_glyph = self.ttf_file.glyph(char_code)?;
-
-        let mut relative = Point::origin();
-
-        let simple = match ttf_glyph {
-            TrueTypeGlyph::Simple(simple) => simple,
-            TrueTypeGlyph::Compound(_) => todo!(),
+        let glyph_index = if self.char_codes_are_glyph_indices {
+            char_code
+        } else {
+            self.ttf_file.glyph_index(char_code)
         };
 
-        let mut points = PointIterator::new(&simple.x_coords, &simple.y_coords, &simple.flags);
-
-        // todo: initialize glyph zone and initial positions
-        self.instruction_stream = InstructionStream::new(simple.instructions);
-
-        let mut paths = Vec::new();
-
-        let mut start = 0;
-        for contour_end in simple.end_points_of_contours {
-            let mut path = None;
-            let contour_end = contour_end as usize;
-
-            let x_coords = &simple.x_coords[start..contour_end];
-            let y_coords = &simple.y_coords[start..contour_end];
-            let flags = &simple.flags[start..contour_end];
-
-            let mut points = PointIterator::new(x_coords, y_coords, flags);
-            // assert_eq!(num_points.len() % 2, 1);
-
-            let p1 = points.next();
-
-            while let Some(c1) = points.next() {
-                let p2 = match points.next() {
-                    Some(v) => v,
-                    None => continue,
-                };
-                let path = path.get_or_insert_with(|| Path::new(p1.unwrap()));
-
-                path.quadratic_curve_to(c1, p2);
-            }
-
-            if let Some(mut path) = path.take() {
-                path.close_path();
-                paths.push(path);
-            }
-
-            start = contour_end;
-        }
+        let outline = self.ttf_file.glyph_outline(glyph_index)?;
 
         // self.execute().unwrap();
 
-        // assert!(self.interpreter_stack.is_empty());
-
-        let outline = Outline { paths };
-
         Ok(Glyph {
             width_vector: outline.bounding_box().max - outline.bounding_box().min,
             outline,
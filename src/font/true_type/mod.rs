@@ -35,7 +35,7 @@ pub use interpreter::TrueTypeInterpreter;
 pub struct TrueTypeFont<'a> {
     pub(crate) base: BaseFontDict<'a>,
 
-    base_font: String,
+    pub(crate) base_font: String,
 
     encoding: Option<FontEncoding>,
 }
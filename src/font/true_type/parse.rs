@@ -4,7 +4,8 @@ use crate::font::true_type::{table::Cmap8Group, Fixed};
 
 use super::{
     table::{
-        CmapSubtable, CmapTable, CompoundGlyphPartDescription, CvtTable, DirectoryTableEntry,
+        CmapEncodingRecord, CmapSubtable, CmapTable, CompoundGlyphComponentFlags,
+        CompoundGlyphPartDescription, CompoundTransformationOption, CvtTable, DirectoryTableEntry,
         FontDirectory, GlyfTable, Head, HeadFlags, LocaTable, MacStyle, MaxpTable, NameRecord,
         NameTable, OffsetSubtable, OutlineFlag, SimpleGlyph, TableDirectory, TableTag,
         TrueTypeGlyph,
@@ -101,6 +102,10 @@ impl<'a> TrueTypeParser<'a> {
         Ok(Fixed(i32::from_be_bytes(n.to_be_bytes())))
     }
 
+    fn read_f2dot14(&mut self) -> anyhow::Result<f32> {
+        Ok(self.read_i16()? as f32 / 16384.0)
+    }
+
     fn read_fword(&mut self) -> anyhow::Result<FWord> {
         Ok(FWord(self.read_i16()?))
     }
@@ -192,6 +197,10 @@ impl<'a> TrueTypeParser<'a> {
     }
 
     fn parse_simple_glyph(&mut self, number_of_contours: i16) -> anyhow::Result<SimpleGlyph> {
+        if number_of_contours == 0 {
+            return Ok(SimpleGlyph::empty());
+        }
+
         let mut end_points_of_contours = Vec::with_capacity(number_of_contours as usize);
 
         // todo: this should just reinterpret bytes
@@ -258,7 +267,63 @@ impl<'a> TrueTypeParser<'a> {
     }
 
     fn parse_compound_glyph(&mut self) -> anyhow::Result<Vec<CompoundGlyphPartDescription>> {
-        todo!("ttf compound glyph")
+        let mut parts = Vec::new();
+
+        loop {
+            let flags = self.read_u16()?;
+            let glyph_index = self.read_u16()?;
+
+            let are_words = flags & CompoundGlyphComponentFlags::ARG_1_AND_2_ARE_WORDS != 0;
+            let are_xy_values = flags & CompoundGlyphComponentFlags::ARGS_ARE_XY_VALUES != 0;
+
+            // Offsets are signed, while point numbers are unsigned
+            let (argument_one, argument_two) = match (are_words, are_xy_values) {
+                (true, true) => (self.read_i16()? as i32, self.read_i16()? as i32),
+                (true, false) => (self.read_u16()? as i32, self.read_u16()? as i32),
+                (false, true) => (self.next()? as i8 as i32, self.next()? as i8 as i32),
+                (false, false) => (self.next()? as i32, self.next()? as i32),
+            };
+
+            let transformation_option = if flags & CompoundGlyphComponentFlags::WE_HAVE_A_SCALE != 0
+            {
+                CompoundTransformationOption::Scale(self.read_f2dot14()?)
+            } else if flags & CompoundGlyphComponentFlags::WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                CompoundTransformationOption::XyScale {
+                    x_scale: self.read_f2dot14()?,
+                    y_scale: self.read_f2dot14()?,
+                }
+            } else if flags & CompoundGlyphComponentFlags::WE_HAVE_A_TWO_BY_TWO != 0 {
+                CompoundTransformationOption::TwoByTwo {
+                    x_scale: self.read_f2dot14()?,
+                    scale_01: self.read_f2dot14()?,
+                    scale_10: self.read_f2dot14()?,
+                    y_scale: self.read_f2dot14()?,
+                }
+            } else {
+                CompoundTransformationOption::Identity
+            };
+
+            parts.push(CompoundGlyphPartDescription {
+                flags: CompoundGlyphComponentFlags(flags),
+                glyph_index,
+                argument_one,
+                argument_two,
+                transformation_option,
+            });
+
+            if flags & CompoundGlyphComponentFlags::MORE_COMPONENTS == 0 {
+                // Instructions follow the last component. We don't execute glyph
+                // programs, so they are skipped
+                if flags & CompoundGlyphComponentFlags::WE_HAVE_INSTRUCTIONS != 0 {
+                    let instruction_length = self.read_u16()?;
+                    self.cursor += instruction_length as usize;
+                }
+
+                break;
+            }
+        }
+
+        Ok(parts)
     }
 
     pub fn parse_glyph(&mut self) -> anyhow::Result<TrueTypeGlyph> {
@@ -467,6 +532,7 @@ impl<'a> TrueTypeParser<'a> {
 
         let number_subtables = self.read_u16()?;
         let mut offsets = Vec::with_capacity(usize::from(number_subtables));
+        let mut encoding_records = Vec::with_capacity(usize::from(number_subtables));
 
         for _ in 0..number_subtables {
             let platform_id = self.read_u16()?;
@@ -486,6 +552,10 @@ impl<'a> TrueTypeParser<'a> {
             }
 
             offsets.push(offset as usize);
+            encoding_records.push(CmapEncodingRecord {
+                platform_id,
+                platform_specific_id,
+            });
         }
 
         let mut subtables = Vec::with_capacity(usize::from(number_subtables));
@@ -495,7 +565,11 @@ impl<'a> TrueTypeParser<'a> {
             subtables.push(self.parse_cmap_subtable()?);
         }
 
-        Ok(CmapTable { version, subtables })
+        Ok(CmapTable {
+            version,
+            encoding_records,
+            subtables,
+        })
     }
 
     fn parse_cmap_subtable(&mut self) -> anyhow::Result<CmapSubtable> {
@@ -508,9 +582,10 @@ impl<'a> TrueTypeParser<'a> {
             6 => self.parse_cmap_subtable_6(),
             8 => self.parse_cmap_subtable_8(),
             10 => todo!(),
-            12 => todo!(),
+            12 => self.parse_cmap_subtable_12(),
             13 => todo!(),
-            14 => todo!(),
+            // Unicode variation sequences don't affect the default glyph for a character
+            14 => Ok(CmapSubtable::Fourteen),
             _ => anyhow::bail!("invalid cmap subtable format: {:?}", format),
         }
     }
@@ -614,7 +689,9 @@ impl<'a> TrueTypeParser<'a> {
         assert_eq!(reserved, 0);
         let length = self.read_u32()?;
         let language = self.read_u32()?;
-        let is32 = self.get_byte_range(65536).to_vec();
+        // A packed bit array of 65536 bits
+        let is32 = self.get_byte_range(8192).to_vec();
+        self.cursor += 8192;
         let n_groups = self.read_u32()?;
 
         let mut groups = Vec::with_capacity(n_groups as usize);
@@ -639,4 +716,94 @@ impl<'a> TrueTypeParser<'a> {
             groups,
         })
     }
+
+    fn parse_cmap_subtable_12(&mut self) -> anyhow::Result<CmapSubtable> {
+        let start = self.cursor - 2;
+        let reserved = self.read_u16()?;
+        assert_eq!(reserved, 0);
+        let length = self.read_u32()?;
+        let language = self.read_u32()?;
+        let n_groups = self.read_u32()?;
+
+        let mut groups = Vec::with_capacity(n_groups as usize);
+
+        for _ in 0..n_groups {
+            let start_char_code = self.read_u32()?;
+            let end_char_code = self.read_u32()?;
+            let start_glyph_code = self.read_u32()?;
+
+            groups.push(Cmap8Group {
+                start_char_code,
+                end_char_code,
+                start_glyph_code,
+            })
+        }
+
+        assert_eq!(self.cursor, start + length as usize);
+
+        Ok(CmapSubtable::Twelve { language, groups })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compound_glyph_components() {
+        let mut glyph = Vec::new();
+
+        for value in [-1_i16, 0, 0, 100, 100] {
+            glyph.extend(value.to_be_bytes());
+        }
+
+        // offsets as bytes, scaled independently, followed by a second
+        // component positioned by point numbers
+        glyph.extend(
+            (CompoundGlyphComponentFlags::ARGS_ARE_XY_VALUES
+                | CompoundGlyphComponentFlags::WE_HAVE_AN_X_AND_Y_SCALE
+                | CompoundGlyphComponentFlags::MORE_COMPONENTS)
+                .to_be_bytes(),
+        );
+        glyph.extend(3_u16.to_be_bytes());
+        glyph.extend([0xfe, 0x05]);
+        glyph.extend(0x4000_u16.to_be_bytes());
+        glyph.extend(0x2000_u16.to_be_bytes());
+
+        glyph.extend(CompoundGlyphComponentFlags::WE_HAVE_INSTRUCTIONS.to_be_bytes());
+        glyph.extend(4_u16.to_be_bytes());
+        glyph.extend([200, 7]);
+
+        glyph.extend(2_u16.to_be_bytes());
+        glyph.extend([0xb0, 0x01]);
+
+        let mut parser = TrueTypeParser::new(&glyph);
+
+        let parts = match parser.parse_glyph().unwrap() {
+            TrueTypeGlyph::Compound(parts) => parts,
+            glyph => panic!("expected a compound glyph, found {:?}", glyph),
+        };
+
+        // the instructions after the last component are skipped
+        assert_eq!(parser.cursor, glyph.len());
+
+        assert_eq!(parts.len(), 2);
+
+        assert_eq!(parts[0].glyph_index, 3);
+        assert_eq!((parts[0].argument_one, parts[0].argument_two), (-2, 5));
+        assert!(matches!(
+            parts[0].transformation_option,
+            CompoundTransformationOption::XyScale {
+                x_scale,
+                y_scale,
+            } if x_scale == 1.0 && y_scale == 0.5
+        ));
+
+        assert_eq!(parts[1].glyph_index, 4);
+        assert_eq!((parts[1].argument_one, parts[1].argument_two), (200, 7));
+        assert!(matches!(
+            parts[1].transformation_option,
+            CompoundTransformationOption::Identity
+        ));
+    }
 }
@@ -2,9 +2,37 @@
 pub struct CmapTable {
     /// Version number (Set to zero)
     pub version: u16,
+
+    /// The platform and encoding of each subtable, in the same order as `subtables`
+    pub encoding_records: Vec<CmapEncodingRecord>,
     pub subtables: Vec<CmapSubtable>,
 }
 
+impl CmapTable {
+    pub fn find_subtable(
+        &self,
+        platform_id: u16,
+        platform_specific_id: u16,
+    ) -> Option<&CmapSubtable> {
+        self.encoding_records
+            .iter()
+            .position(|record| {
+                record.platform_id == platform_id
+                    && record.platform_specific_id == platform_specific_id
+            })
+            .and_then(|idx| self.subtables.get(idx))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CmapEncodingRecord {
+    /// Platform identifier. 0 is Unicode, 1 is Macintosh, and 3 is Microsoft
+    pub platform_id: u16,
+
+    /// Platform-specific encoding identifier
+    pub platform_specific_id: u16,
+}
+
 #[derive(Debug)]
 pub enum CmapSubtable {
     /// Format 0 is suitable for fonts whose character codes and glyph indices are
@@ -76,7 +104,16 @@ pub enum CmapSubtable {
         num_chars: u32,
         glyphs: Vec<u16>,
     },
-    Twelve,
+
+    /// Segmented coverage
+    ///
+    /// Format 12 is the standard character-to-glyph-index mapping table for fonts
+    /// supporting Unicode characters beyond the Basic Multilingual Plane. Its groups
+    /// have the same form as those of format 8
+    Twelve {
+        language: u32,
+        groups: Vec<Cmap8Group>,
+    },
     Thirteen,
     Fourteen,
 }
@@ -93,11 +130,13 @@ impl CmapSubtable {
                 glyph_index_array,
                 ..
             } => {
-                let (idx, _) = end_code
+                let idx = match end_code
                     .iter()
-                    .enumerate()
-                    .find(|(_, end_code)| **end_code as u32 >= char_code)
-                    .unwrap();
+                    .position(|&end_code| end_code as u32 >= char_code)
+                {
+                    Some(idx) => idx,
+                    None => return 0,
+                };
 
                 let start_code = start_code[idx];
                 if start_code as u32 > char_code {
@@ -107,36 +146,45 @@ impl CmapSubtable {
                 if id_range_offset[idx] == 0 {
                     (id_delta[idx] as i32 + char_code as i32) as u32 % 65536
                 } else {
-                    let addr = glyph_index_array[(idx as u16
-                        + id_range_offset[idx] / 2
-                        + (char_code as u16 - start_code)
-                        - *seg_count_x2 / 2)
-                        as usize];
+                    let glyph_index = (idx
+                        + id_range_offset[idx] as usize / 2
+                        + (char_code - start_code as u32) as usize)
+                        .checked_sub(*seg_count_x2 as usize / 2)
+                        .and_then(|addr| glyph_index_array.get(addr))
+                        .copied()
+                        .unwrap_or(0);
 
-                    (id_delta[idx] as i32 + addr as i32) as u32 % 65536
+                    if glyph_index == 0 {
+                        return 0;
+                    }
+
+                    (id_delta[idx] as i32 + glyph_index as i32) as u32 % 65536
                 }
             }
             CmapSubtable::Zero {
                 glyph_index_array, ..
-            } => {
-                assert!(char_code <= 256);
-                glyph_index_array[char_code as usize] as u32
-            }
+            } => glyph_index_array
+                .get(char_code as usize)
+                .copied()
+                .unwrap_or(0) as u32,
             CmapSubtable::Six {
                 glyph_index_array,
                 first_code,
                 ..
-            } => {
-                println!("likely incorrect ttf cmap subtable 6");
-                if char_code == 0 {
-                    return *first_code as u32;
-                }
-
-                glyph_index_array
-                    .get(char_code as usize - 1)
-                    .copied()
-                    .unwrap_or(0) as u32
-            }
+            } => char_code
+                .checked_sub(*first_code as u32)
+                .and_then(|idx| glyph_index_array.get(idx as usize))
+                .copied()
+                .unwrap_or(0) as u32,
+            CmapSubtable::Eight { groups, .. } | CmapSubtable::Twelve { groups, .. } => groups
+                .iter()
+                .find(|group| {
+                    group.start_char_code <= char_code && char_code <= group.end_char_code
+                })
+                .map(|group| group.start_glyph_code + (char_code - group.start_char_code))
+                .unwrap_or(0),
+            // Variation sequences only select alternates of glyphs found in another subtable
+            CmapSubtable::Fourteen => 0,
             _ => todo!("unimplemented cmap table lookup: {:#?}", self),
         }
     }
@@ -163,3 +211,87 @@ pub struct Cmap2SubHeader {
     id_delta: i16,
     id_range_offset: u16,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn segment_mapping() {
+        // a segment mapped by its delta, one mapped through the glyph index
+        // array, and the final segment required to end at 0xFFFF
+        let subtable = CmapSubtable::Four {
+            language: 0,
+            seg_count_x2: 6,
+            search_range: 4,
+            entry_selector: 1,
+            range_shift: 2,
+            end_code: vec![0x21, 0x42, 0xffff],
+            start_code: vec![0x20, 0x41, 0xffff],
+            id_delta: vec![5, 0, 1],
+            id_range_offset: vec![0, 4, 0],
+            glyph_index_array: vec![7, 0],
+        };
+
+        assert_eq!(subtable.lookup_char_code(0x20), 0x25);
+        assert_eq!(subtable.lookup_char_code(0x21), 0x26);
+        assert_eq!(subtable.lookup_char_code(0x41), 7);
+
+        // missing from the glyph index array, between segments, and beyond
+        // the last segment
+        assert_eq!(subtable.lookup_char_code(0x42), 0);
+        assert_eq!(subtable.lookup_char_code(0x30), 0);
+        assert_eq!(subtable.lookup_char_code(0x1_0000), 0);
+    }
+
+    #[test]
+    fn segmented_coverage() {
+        let subtable = CmapSubtable::Twelve {
+            language: 0,
+            groups: vec![Cmap8Group {
+                start_char_code: 0x1_f600,
+                end_char_code: 0x1_f601,
+                start_glyph_code: 10,
+            }],
+        };
+
+        assert_eq!(subtable.lookup_char_code(0x1_f601), 11);
+        assert_eq!(subtable.lookup_char_code(0x1_f602), 0);
+    }
+
+    #[test]
+    fn find_subtable_by_encoding() {
+        let cmap = CmapTable {
+            version: 0,
+            encoding_records: vec![
+                CmapEncodingRecord {
+                    platform_id: 1,
+                    platform_specific_id: 0,
+                },
+                CmapEncodingRecord {
+                    platform_id: 3,
+                    platform_specific_id: 1,
+                },
+            ],
+            subtables: vec![
+                CmapSubtable::Six {
+                    language: 0,
+                    first_code: 0x41,
+                    entry_count: 1,
+                    glyph_index_array: vec![3],
+                },
+                CmapSubtable::Fourteen,
+            ],
+        };
+
+        assert!(matches!(
+            cmap.find_subtable(3, 1),
+            Some(CmapSubtable::Fourteen)
+        ));
+        assert!(cmap.find_subtable(3, 0).is_none());
+
+        let mac_roman = cmap.find_subtable(1, 0).unwrap();
+        assert_eq!(mac_roman.lookup_char_code(0x41), 3);
+        assert_eq!(mac_roman.lookup_char_code(0x40), 0);
+    }
+}
@@ -1,3 +1,8 @@
+use crate::{
+    data_structures::Matrix,
+    geometry::{Path, Point},
+};
+
 use super::TableTag;
 
 #[derive(Debug)]
@@ -49,6 +54,78 @@ impl SimpleGlyph {
             y_coords: Vec::new(),
         }
     }
+
+    /// One closed path per contour, in font units
+    pub(crate) fn paths(&self) -> Vec<Path> {
+        let mut paths = Vec::with_capacity(self.end_points_of_contours.len());
+
+        let mut start = 0;
+        for &end in &self.end_points_of_contours {
+            let end = end as usize;
+
+            if end < start || end >= self.flags.len() {
+                break;
+            }
+
+            let points = (start..=end)
+                .map(|idx| {
+                    (
+                        Point::new(self.x_coords[idx] as f32, self.y_coords[idx] as f32),
+                        self.flags[idx] & OutlineFlag::ON_CURVE != 0,
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            if let Some(path) = contour_path(&points) {
+                paths.push(path);
+            }
+
+            start = end + 1;
+        }
+
+        paths
+    }
+}
+
+/// Build a path from the points of a single contour
+///
+/// Contours are made of quadratic curves. Two consecutive off-curve points imply an
+/// on-curve point at their midpoint, and a contour consisting only of off-curve points
+/// is permitted.
+fn contour_path(points: &[(Point, bool)]) -> Option<Path> {
+    let len = points.len();
+
+    let (start, offset, count) = match points.iter().position(|&(_, on_curve)| on_curve) {
+        Some(idx) => (points[idx].0, idx + 1, len - 1),
+        None => (points.last()?.0.midpoint(points[0].0), 0, len),
+    };
+
+    let mut path = Path::new(start);
+    let mut control_point = None;
+
+    for idx in 0..count {
+        let (point, on_curve) = points[(offset + idx) % len];
+
+        match (on_curve, control_point) {
+            (true, None) => path.line_to(point),
+            (true, Some(control)) => {
+                path.quadratic_curve_to(control, point);
+                control_point = None;
+            }
+            (false, None) => control_point = Some(point),
+            (false, Some(control)) => {
+                path.quadratic_curve_to(control, control.midpoint(point));
+                control_point = Some(point);
+            }
+        }
+    }
+
+    match control_point {
+        Some(control) => path.quadratic_curve_to(control, start),
+        None => path.close_path(),
+    }
+
+    Some(path)
 }
 
 #[derive(Debug)]
@@ -95,22 +172,51 @@ impl OutlineFlag {
 
 #[derive(Debug)]
 pub struct CompoundGlyphPartDescription {
-    flags: CompoundGlyphComponentFlags,
+    pub(crate) flags: CompoundGlyphComponentFlags,
 
     /// Glyph index of component
-    glyph_index: u16,
+    pub(crate) glyph_index: u16,
 
     /// X-offset for component or point number; type depends on bits 0 and 1 in component flags
-    argument_one: u16,
+    pub(crate) argument_one: i32,
 
     /// Y-offset for component or point number type depends on bits 0 and 1 in component flags
-    argument_two: u16,
+    pub(crate) argument_two: i32,
+
+    pub(crate) transformation_option: CompoundTransformationOption,
+}
 
-    transformation_option: CompoundTransformationOption,
+impl CompoundGlyphPartDescription {
+    /// The transformation from the glyph space of this component into that of the
+    /// compound glyph
+    pub(crate) fn transform(&self) -> Matrix {
+        let (a, b, c, d) = match self.transformation_option {
+            CompoundTransformationOption::Identity => (1.0, 0.0, 0.0, 1.0),
+            CompoundTransformationOption::Scale(scale) => (scale, 0.0, 0.0, scale),
+            CompoundTransformationOption::XyScale { x_scale, y_scale } => {
+                (x_scale, 0.0, 0.0, y_scale)
+            }
+            CompoundTransformationOption::TwoByTwo {
+                x_scale,
+                scale_01,
+                scale_10,
+                y_scale,
+            } => (x_scale, scale_01, scale_10, y_scale),
+        };
+
+        // todo: position components by matching points when the arguments are point numbers
+        let (e, f) = if self.flags.0 & CompoundGlyphComponentFlags::ARGS_ARE_XY_VALUES != 0 {
+            (self.argument_one as f32, self.argument_two as f32)
+        } else {
+            (0.0, 0.0)
+        };
+
+        Matrix::new(a, b, c, d, e, f)
+    }
 }
 
 #[derive(Debug)]
-pub struct CompoundGlyphComponentFlags(u16);
+pub struct CompoundGlyphComponentFlags(pub(crate) u16);
 
 impl CompoundGlyphComponentFlags {
     pub const ARG_1_AND_2_ARE_WORDS: u16 = 1 << 0;
@@ -126,9 +232,113 @@ impl CompoundGlyphComponentFlags {
     pub const OVERLAP_COMPOUND: u16 = 1 << 10;
 }
 
-#[derive(Debug)]
-enum CompoundTransformationOption {
-    One,
-    Two,
-    Three,
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum CompoundTransformationOption {
+    /// The component is not scaled
+    Identity,
+
+    /// The component is scaled uniformly
+    Scale(f32),
+
+    /// The x and y axes of the component are scaled independently
+    XyScale { x_scale: f32, y_scale: f32 },
+
+    /// The component is transformed by a 2x2 matrix, allowing for rotation and
+    /// skewing in addition to scaling
+    TwoByTwo {
+        x_scale: f32,
+        scale_01: f32,
+        scale_10: f32,
+        y_scale: f32,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::Subpath;
+
+    /// The end point and control point of each segment of `path`
+    fn segments(path: &Path) -> Vec<(Point, Option<Point>)> {
+        path.subpaths
+            .iter()
+            .map(|subpath| match subpath {
+                Subpath::Line(line) => (line.end, None),
+                Subpath::Quadratic(curve) => (curve.end, Some(curve.control_point)),
+                Subpath::Cubic(..) => panic!("TrueType outlines have no cubic curves"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn contour_starting_off_curve() {
+        let path = contour_path(&[
+            (Point::new(50.0, 100.0), false),
+            (Point::new(100.0, 0.0), true),
+            (Point::new(0.0, 0.0), true),
+        ])
+        .unwrap();
+
+        assert_eq!(path.start, Point::new(100.0, 0.0));
+        assert_eq!(
+            segments(&path),
+            [
+                (Point::new(0.0, 0.0), None),
+                (Point::new(100.0, 0.0), Some(Point::new(50.0, 100.0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn implied_on_curve_points() {
+        let path = contour_path(&[
+            (Point::new(0.0, 50.0), false),
+            (Point::new(50.0, 100.0), false),
+            (Point::new(100.0, 50.0), false),
+            (Point::new(50.0, 0.0), false),
+        ])
+        .unwrap();
+
+        // the contour begins midway between its last and first points
+        assert_eq!(path.start, Point::new(25.0, 25.0));
+        assert_eq!(
+            segments(&path),
+            [
+                (Point::new(25.0, 75.0), Some(Point::new(0.0, 50.0))),
+                (Point::new(75.0, 75.0), Some(Point::new(50.0, 100.0))),
+                (Point::new(75.0, 25.0), Some(Point::new(100.0, 50.0))),
+                (Point::new(25.0, 25.0), Some(Point::new(50.0, 0.0))),
+            ]
+        );
+
+        assert!(contour_path(&[]).is_none());
+    }
+
+    #[test]
+    fn compound_component_transform() {
+        let part = CompoundGlyphPartDescription {
+            flags: CompoundGlyphComponentFlags(CompoundGlyphComponentFlags::ARGS_ARE_XY_VALUES),
+            glyph_index: 1,
+            argument_one: 10,
+            argument_two: -20,
+            transformation_option: CompoundTransformationOption::XyScale {
+                x_scale: 2.0,
+                y_scale: 0.5,
+            },
+        };
+
+        assert_eq!(
+            part.transform(),
+            Matrix::new(2.0, 0.0, 0.0, 0.5, 10.0, -20.0)
+        );
+
+        // point numbers to be matched are not offsets
+        let part = CompoundGlyphPartDescription {
+            flags: CompoundGlyphComponentFlags(0),
+            transformation_option: CompoundTransformationOption::Identity,
+            ..part
+        };
+
+        assert_eq!(part.transform(), Matrix::identity());
+    }
 }
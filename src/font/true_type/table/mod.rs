@@ -11,14 +11,14 @@ mod name;
 mod table_name;
 mod tag;
 
-pub(super) use cmap::{Cmap8Group, CmapSubtable, CmapTable};
+pub(super) use cmap::{Cmap8Group, CmapEncodingRecord, CmapSubtable, CmapTable};
 pub(super) use cvt::CvtTable;
 pub(super) use font_directory::{
     DirectoryTableEntry, FontDirectory, OffsetSubtable, TableDirectory,
 };
 pub(super) use glyf::{
-    CompoundGlyphPartDescription, GlyfTable, GlyphDescription, OutlineFlag, SimpleGlyph,
-    TrueTypeGlyph,
+    CompoundGlyphComponentFlags, CompoundGlyphPartDescription, CompoundTransformationOption,
+    GlyfTable, GlyphDescription, OutlineFlag, SimpleGlyph, TrueTypeGlyph,
 };
 pub(super) use head::{Head, HeadFlags, MacStyle};
 pub(super) use loca::LocaTable;
@@ -1,32 +1,11 @@
-use crate::{
-    error::{ParseError, PdfResult},
-    objects::{Object, ObjectType},
-    stream::Stream,
-    FromObj, Resolve,
-};
+use crate::{error::PdfResult, objects::Object, FromObj, Resolve};
 
 use super::{
-    cid::CidFontDictionary, cjk::PredefinedCjkCmapName, cmap::ToUnicodeCmapStream, BaseFontDict,
+    cid::CidFontDictionary,
+    cmap::{CMap, CharCode, ToUnicodeCmapStream, WritingMode},
+    BaseFontDict,
 };
 
-#[derive(Debug)]
-enum Type0FontEncoding<'a> {
-    Predefined(PredefinedCjkCmapName),
-    Stream(Stream<'a>),
-}
-
-impl<'a> FromObj<'a> for Type0FontEncoding<'a> {
-    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
-        match resolver.resolve(obj)? {
-            Object::Name(name) => Ok(Self::Predefined(PredefinedCjkCmapName::from_str(&name)?)),
-            Object::Stream(stream) => Ok(Self::Stream(stream)),
-            _ => anyhow::bail!(ParseError::MismatchedObjectTypeAny {
-                expected: &[ObjectType::Stream, ObjectType::Name],
-            }),
-        }
-    }
-}
-
 /// A composite font, also called a Type 0 font, is one whose glyphs are obtained
 /// from a fontlike object called a CIDFont. A composite font shall be represented
 /// by a font dictionary whose Subtype value is Type0. The Type 0 font is known
@@ -51,14 +30,14 @@ pub struct Type0Font<'a> {
     /// maps character codes to font numbers and CIDs. If the descendant is
     /// a Type 2 CIDFont whose associated TrueType font program is not embedded
     /// in the PDF file, the Encoding entry shall be a predefined CMap name
-    encoding: Type0FontEncoding<'a>,
+    encoding: CMap,
 
     /// A one-element array specifying the CIDFont dictionary that is the descendant
     /// of this Type 0 font
     pub descendant_font: [CidFontDictionary<'a>; 1],
 
     /// A stream containing a CMap file that maps character codes to Unicode values
-    to_unicode: Option<ToUnicodeCmapStream<'a>>,
+    to_unicode: Option<ToUnicodeCmapStream>,
 }
 
 impl<'a> FromObj<'a> for Type0Font<'a> {
@@ -79,3 +58,42 @@ impl<'a> FromObj<'a> for Type0Font<'a> {
         })
     }
 }
+
+impl<'a> Type0Font<'a> {
+    /// Whether glyphs are laid out horizontally or vertically, as specified by
+    /// the font's CMap
+    pub fn writing_mode(&self) -> WritingMode {
+        self.encoding.writing_mode
+    }
+
+    /// Split a string shown with this font into character codes, along with the
+    /// CID each selects
+    pub(crate) fn cids<'b>(
+        &'b self,
+        bytes: &'b [u8],
+    ) -> impl Iterator<Item = (CharCode, u32)> + 'b {
+        self.encoding
+            .codes(bytes)
+            .map(|code| (code, self.encoding.cid(code)))
+    }
+
+    /// The Unicode text of a string shown with this font, as given by its
+    /// ToUnicode CMap
+    ///
+    /// Codes without a Unicode value, including every code if the font has no
+    /// ToUnicode CMap, become U+FFFD.
+    pub fn decode_text(&self, bytes: &[u8]) -> String {
+        self.encoding
+            .codes(bytes)
+            .map(|code| self.unicode(code))
+            .collect()
+    }
+
+    /// The Unicode text of a single code, or U+FFFD if it has none
+    pub(crate) fn unicode(&self, code: CharCode) -> String {
+        self.to_unicode
+            .as_ref()
+            .and_then(|to_unicode| to_unicode.cmap.unicode(code))
+            .unwrap_or_else(|| char::REPLACEMENT_CHARACTER.to_string())
+    }
+}
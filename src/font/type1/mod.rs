@@ -30,7 +30,7 @@ pub struct Type1Font<'a> {
     pub encoding: Option<FontEncoding>,
 
     /// A stream containing a CMap file that maps character codes to Unicode values
-    pub to_unicode: Option<ToUnicodeCmapStream<'a>>,
+    pub to_unicode: Option<ToUnicodeCmapStream>,
 }
 
 impl<'a> Type1Font<'a> {
@@ -55,7 +55,7 @@ impl<'a> Type1Font<'a> {
 /// in the font
 #[derive(Debug)]
 pub struct MmType1Font<'a> {
-    type1: Type1Font<'a>,
+    pub(crate) type1: Type1Font<'a>,
 }
 
 impl<'a> MmType1Font<'a> {
@@ -56,7 +56,7 @@ pub struct Type3Font<'a> {
     resources: Option<Resources<'a>>,
 
     /// A stream containing a CMap file that maps character codes to Unicode values
-    to_unicode: Option<ToUnicodeCmapStream<'a>>,
+    to_unicode: Option<ToUnicodeCmapStream>,
 }
 
 impl<'a> Type3Font<'a> {
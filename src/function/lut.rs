@@ -0,0 +1,162 @@
+use std::rc::Rc;
+
+use crate::error::PdfResult;
+
+use super::{interpolate, interpolate_table};
+
+/// The largest number of values a lookup table may hold, so that functions
+/// with many inputs aren't sampled at an unreasonable number of points
+const MAX_LUT_VALUES: usize = 1 << 22;
+
+/// The outputs of a function at evenly spaced points of its domain, from which
+/// the function is approximated by multilinear interpolation
+#[derive(Debug, Clone)]
+pub(super) struct FunctionLut {
+    /// The number of points along each input, repeated for each input
+    size: Vec<u32>,
+
+    /// The number of outputs at each point
+    outputs: usize,
+
+    /// The outputs at each point, in which the first input varies fastest
+    values: Rc<[f32]>,
+}
+
+impl FunctionLut {
+    /// Evaluate `f` at `resolution` points along each input, spanning `domain`
+    pub fn new(
+        resolution: usize,
+        domain: &[f32],
+        mut f: impl FnMut(&[f32]) -> PdfResult<Vec<f32>>,
+    ) -> PdfResult<Self> {
+        let m = domain.len() / 2;
+
+        anyhow::ensure!(
+            (2..=u32::MAX as usize).contains(&resolution),
+            "invalid function lookup table resolution {}",
+            resolution
+        );
+
+        let points = match resolution.checked_pow(m as u32) {
+            Some(points) if points <= MAX_LUT_VALUES => points,
+            _ => anyhow::bail!(
+                "function lookup table of resolution {} for {} inputs is too large",
+                resolution,
+                m
+            ),
+        };
+
+        let mut values = Vec::new();
+        let mut outputs = None;
+        let mut inputs = vec![0.0; m];
+
+        for point in 0..points {
+            let mut idx = point;
+
+            for (i, input) in inputs.iter_mut().enumerate() {
+                *input = interpolate(
+                    (idx % resolution) as f32,
+                    0.0,
+                    (resolution - 1) as f32,
+                    domain[2 * i],
+                    domain[2 * i + 1],
+                );
+                idx /= resolution;
+            }
+
+            let result = f(&inputs)?;
+
+            match outputs {
+                Some(n) => anyhow::ensure!(
+                    result.len() == n,
+                    "function has {} outputs at {:?}, expected {}",
+                    result.len(),
+                    inputs,
+                    n
+                ),
+                None => {
+                    anyhow::ensure!(
+                        points.saturating_mul(result.len()) <= MAX_LUT_VALUES,
+                        "function lookup table of resolution {} for {} inputs is too large",
+                        resolution,
+                        m
+                    );
+
+                    values.reserve_exact(points * result.len());
+                    outputs = Some(result.len());
+                }
+            }
+
+            values.extend(result);
+        }
+
+        Ok(Self {
+            size: vec![resolution as u32; m],
+            outputs: outputs.unwrap_or(0),
+            values: values.into(),
+        })
+    }
+
+    /// Interpolate between the points surrounding `inputs`, which have already
+    /// been clipped to `domain`
+    pub fn evaluate(&self, inputs: &[f32], domain: &[f32]) -> Vec<f32> {
+        let position = inputs
+            .iter()
+            .zip(&self.size)
+            .enumerate()
+            .map(|(i, (&x, &size))| {
+                interpolate(x, domain[2 * i], domain[2 * i + 1], 0.0, (size - 1) as f32)
+            })
+            .collect::<Vec<_>>();
+
+        interpolate_table(&position, &self.size, self.outputs, |idx| self.values[idx])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{
+        function::Function,
+        objects::{Dictionary, Object},
+        version::PdfVersion,
+        writer::PdfWriter,
+        FromObj,
+    };
+
+    #[test]
+    fn precomputed_functions() {
+        // y = x^2
+        let dict = HashMap::from([
+            ("FunctionType".to_owned(), Object::Integer(2)),
+            (
+                "Domain".to_owned(),
+                Object::Array(vec![Object::Integer(0), Object::Integer(1)]),
+            ),
+            ("N".to_owned(), Object::Integer(2)),
+        ]);
+
+        let mut f = Function::from_obj(
+            Object::Dictionary(Dictionary::new(dict)),
+            &mut PdfWriter::new(PdfVersion::V1_7),
+        )
+        .unwrap();
+
+        assert!(f.precompute(1).is_err());
+        assert!(f.precompute(usize::MAX).is_err());
+        assert!(!f.is_precomputed());
+
+        f.precompute(5).unwrap();
+        assert!(f.is_precomputed());
+
+        // points of the table are exact, and those between them are
+        // interpolated
+        assert_eq!(f.evaluate(&[0.5]).unwrap(), [0.25]);
+        assert_eq!(f.evaluate(&[0.375]).unwrap(), [0.15625]);
+        assert_eq!(f.evaluate(&[2.0]).unwrap(), [1.0]);
+
+        f.clear_precomputed();
+        assert_eq!(f.evaluate(&[0.375]).unwrap(), [0.140625]);
+    }
+}
@@ -1,3 +1,5 @@
+use std::marker::PhantomData;
+
 use crate::{
     error::PdfResult,
     objects::{Dictionary, Object},
@@ -6,12 +8,13 @@ use crate::{
 };
 
 use self::{
-    exponential_interpolation::ExponentialInterpolationFunction,
+    exponential_interpolation::ExponentialInterpolationFunction, lut::FunctionLut,
     postscript_calculator::PostScriptCalculatorFunction, sampled::SampledFunction,
     stitching::StitchingFunction,
 };
 
 mod exponential_interpolation;
+mod lut;
 mod postscript_calculator;
 mod sampled;
 mod stitching;
@@ -34,6 +37,14 @@ pub struct Function<'a> {
     range: Option<Vec<f32>>,
 
     subtype: FunctionSubtype<'a>,
+
+    /// A table of precomputed outputs, which is used in place of the function
+    /// if present
+    lut: Option<FunctionLut>,
+
+    /// Functions are parsed into owned data, but keep the lifetime of the
+    /// document so that they can be stored alongside the objects they came from
+    _t: PhantomData<&'a ()>,
 }
 
 #[derive(Debug)]
@@ -79,6 +90,148 @@ impl<'a> FromObj<'a> for StreamOrDict<'a> {
     }
 }
 
+impl<'a> Function<'a> {
+    /// Apply the function to `inputs`, clipping them to the domain and the
+    /// results to the range
+    pub fn evaluate(&self, inputs: &[f32]) -> PdfResult<Vec<f32>> {
+        anyhow::ensure!(
+            inputs.len() * 2 == self.domain.len(),
+            "expected {} function inputs, found {}",
+            self.domain.len() / 2,
+            inputs.len()
+        );
+
+        let inputs = clip(inputs, &self.domain);
+
+        match &self.lut {
+            Some(lut) => Ok(lut.evaluate(&inputs, &self.domain)),
+            None => self.evaluate_exact(&inputs),
+        }
+    }
+
+    /// Precompute the outputs of the function at `resolution` evenly spaced
+    /// points along each input, after which it is evaluated by interpolating
+    /// between them
+    ///
+    /// This is many times faster for functions which are evaluated often, such
+    /// as those of shadings and tint transforms, at the cost of accuracy
+    /// between the points. Functions with many inputs may not be precomputed
+    /// at high resolutions, as the table grows exponentially.
+    pub fn precompute(&mut self, resolution: usize) -> PdfResult<()> {
+        self.lut = Some(FunctionLut::new(resolution, &self.domain, |inputs| {
+            self.evaluate_exact(inputs)
+        })?);
+
+        Ok(())
+    }
+
+    /// Discard precomputed outputs, so that the function is evaluated exactly
+    pub fn clear_precomputed(&mut self) {
+        self.lut = None;
+    }
+
+    pub fn is_precomputed(&self) -> bool {
+        self.lut.is_some()
+    }
+
+    /// Apply the function to `inputs`, which have already been clipped to the
+    /// domain, without using precomputed outputs
+    fn evaluate_exact(&self, inputs: &[f32]) -> PdfResult<Vec<f32>> {
+        let outputs = match &self.subtype {
+            FunctionSubtype::ExponentialInterpolation(f) => f.evaluate(inputs[0]),
+            FunctionSubtype::Stitching(f) => f.evaluate(inputs[0], &self.domain)?,
+            FunctionSubtype::Sampled(f) => {
+                f.evaluate(inputs, &self.domain, self.range.as_deref())?
+            }
+            FunctionSubtype::PostScriptCalculator(f) => {
+                f.evaluate(inputs, self.range.as_deref())?
+            }
+        };
+
+        Ok(match &self.range {
+            Some(range) => clip(&outputs, range),
+            None => outputs,
+        })
+    }
+}
+
+/// Map `x` from the interval `x_min` to `x_max` onto the interval `y_min` to
+/// `y_max`
+fn interpolate(x: f32, x_min: f32, x_max: f32, y_min: f32, y_max: f32) -> f32 {
+    if x_max == x_min {
+        return y_min;
+    }
+
+    y_min + (x - x_min) * (y_max - y_min) / (x_max - x_min)
+}
+
+/// Multilinearly interpolate between the entries of an m-dimensional table with
+/// `size` entries in each dimension, the first of which varies fastest, and n
+/// components in each entry
+///
+/// Each coordinate of `position` is in units of entries, and shall be within
+/// the table. `entry` returns a component of the table by its index.
+fn interpolate_table(
+    position: &[f32],
+    size: &[u32],
+    n: usize,
+    entry: impl Fn(usize) -> f32,
+) -> Vec<f32> {
+    // the entry at or below the position in each dimension, and the fraction
+    // of the distance to the next entry
+    let mut lower = Vec::with_capacity(position.len());
+    let mut fractions = Vec::with_capacity(position.len());
+
+    for (&e, &size) in position.iter().zip(size) {
+        let idx = (e.floor().max(0.0) as u32).min(size.saturating_sub(2));
+
+        lower.push(idx as usize);
+        fractions.push(e - idx as f32);
+    }
+
+    let mut outputs = vec![0.0; n];
+
+    // each corner of the cell containing the position is weighted by its
+    // closeness along every dimension
+    for corner in 0..(1_usize << position.len()) {
+        let mut weight = 1.0;
+        let mut offset = 0;
+        let mut stride = 1;
+
+        for (i, &size) in size.iter().enumerate() {
+            let (idx, w) = if (corner >> i) & 1 == 1 {
+                (lower[i] + 1, fractions[i])
+            } else {
+                (lower[i], 1.0 - fractions[i])
+            };
+
+            weight *= w;
+            offset += idx * stride;
+            stride *= size as usize;
+        }
+
+        if weight == 0.0 {
+            continue;
+        }
+
+        for (j, output) in outputs.iter_mut().enumerate() {
+            *output += weight * entry(offset * n + j);
+        }
+    }
+
+    outputs
+}
+
+/// Clip each value to the corresponding interval of `bounds`, an array of 2 * n
+/// numbers
+fn clip(values: &[f32], bounds: &[f32]) -> Vec<f32> {
+    values
+        .iter()
+        .zip(bounds.chunks_exact(2))
+        .map(|(&value, bounds)| value.max(bounds[0]).min(bounds[1]))
+        .collect()
+}
+
 impl<'a> FromObj<'a> for Function<'a> {
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
         let mut stream_or_dict = StreamOrDict::from_obj(obj, resolver)?;
@@ -94,13 +247,15 @@ impl<'a> FromObj<'a> for Function<'a> {
             domain,
             range,
             subtype,
+            lut: None,
+            _t: PhantomData,
         })
     }
 }
 
 #[derive(Debug, Clone)]
 enum FunctionSubtype<'a> {
-    Sampled(SampledFunction<'a>),
+    Sampled(SampledFunction),
     ExponentialInterpolation(ExponentialInterpolationFunction),
     Stitching(StitchingFunction<'a>),
     PostScriptCalculator(PostScriptCalculatorFunction),
@@ -114,8 +269,8 @@ impl<'a> FromObj<'a> for FunctionSubtype<'a> {
         let subtype = FunctionType::from_integer(dict.expect_integer("FunctionType", resolver)?)?;
 
         Ok(match subtype {
-            FunctionType::Sampled => FunctionSubtype::Sampled(SampledFunction::from_obj(
-                Object::Stream(stream_or_dict.expect_stream()?),
+            FunctionType::Sampled => FunctionSubtype::Sampled(SampledFunction::from_stream(
+                stream_or_dict.expect_stream()?,
                 resolver,
             )?),
             FunctionType::ExponentialInterpolation => FunctionSubtype::ExponentialInterpolation(
@@ -148,12 +303,44 @@ pub enum SpotFunction<'a> {
     Function(Function<'a>),
 }
 
+impl<'a> SpotFunction<'a> {
+    /// The value of the spot function at (x, y) of a halftone cell, where both
+    /// coordinates range from -1.0 to 1.0
+    ///
+    /// Pixels with lower values are painted with full intensity first as the
+    /// level of a colour component increases.
+    pub fn evaluate(&self, x: f32, y: f32) -> PdfResult<f32> {
+        Ok(match self {
+            SpotFunction::Predefined(f) => f.evaluate(x, y),
+            SpotFunction::Function(f) => match f.evaluate(&[x, y])?.first() {
+                Some(&n) => n,
+                None => anyhow::bail!("spot function has no outputs"),
+            },
+        })
+    }
+}
+
 impl<'a> FromObj<'a> for SpotFunction<'a> {
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
-        Ok(if let Object::Name(ref name) = obj {
-            SpotFunction::Predefined(PredefinedSpotFunction::from_str(name)?)
-        } else {
-            SpotFunction::Function(Function::from_obj(obj, resolver)?)
+        Ok(match resolver.resolve(obj)? {
+            Object::Name(ref name) => {
+                SpotFunction::Predefined(PredefinedSpotFunction::from_str(name)?)
+            }
+            // an array of the names of predefined spot functions, in order of
+            // preference, of which the first supported is used
+            Object::Array(arr) => {
+                let spot_function = arr.into_iter().find_map(|obj| {
+                    let name = resolver.assert_name(obj).ok()?;
+
+                    PredefinedSpotFunction::from_str(&name).ok()
+                });
+
+                match spot_function {
+                    Some(spot_function) => SpotFunction::Predefined(spot_function),
+                    None => anyhow::bail!("no supported spot function"),
+                }
+            }
+            obj => SpotFunction::Function(Function::from_obj(obj, resolver)?),
         })
     }
 }
@@ -166,6 +353,7 @@ pub enum PredefinedSpotFunction {
     InvertedDoubleDot = "InvertedDoubleDot",
     CosineDot = "CosineDot",
     Double = "Double",
+    InvertedDouble = "InvertedDouble",
     Line = "Line",
     LineX = "LineX",
     LineY = "LineY",
@@ -182,6 +370,64 @@ pub enum PredefinedSpotFunction {
     Diamond = "Diamond",
 }
 
+impl PredefinedSpotFunction {
+    fn evaluate(self, x: f32, y: f32) -> f32 {
+        // angles are in degrees
+        let sin = |degrees: f32| degrees.to_radians().sin();
+        let cos = |degrees: f32| degrees.to_radians().cos();
+
+        let (ax, ay) = (x.abs(), y.abs());
+
+        match self {
+            Self::SimpleDot => 1.0 - (x * x + y * y),
+            Self::InvertedSimpleDot => x * x + y * y - 1.0,
+            Self::DoubleDot => (sin(x * 360.0) + sin(y * 360.0)) / 2.0,
+            Self::InvertedDoubleDot => -(sin(x * 360.0) + sin(y * 360.0)) / 2.0,
+            Self::CosineDot => (cos(x * 180.0) + cos(y * 180.0)) / 2.0,
+            Self::Double => (sin(x / 2.0 * 360.0) + sin(y * 360.0)) / 2.0,
+            Self::InvertedDouble => -(sin(x / 2.0 * 360.0) + sin(y * 360.0)) / 2.0,
+            Self::Line => -ay,
+            Self::LineX => x,
+            Self::LineY => y,
+            Self::Round => {
+                if ax + ay <= 1.0 {
+                    1.0 - (x * x + y * y)
+                } else {
+                    (ax - 1.0).powi(2) + (ay - 1.0).powi(2) - 1.0
+                }
+            }
+            Self::Ellipse => {
+                let w = 3.0 * ax + 4.0 * ay - 3.0;
+
+                if w < 0.0 {
+                    1.0 - (x * x + (ay / 0.75).powi(2)) / 4.0
+                } else if w > 1.0 {
+                    ((1.0 - ax).powi(2) + ((1.0 - ay) / 0.75).powi(2)) / 4.0 - 1.0
+                } else {
+                    0.5 - w
+                }
+            }
+            Self::EllipseA => 1.0 - (x * x + 0.9 * y * y),
+            Self::InvertedEllipseA => x * x + 0.9 * y * y - 1.0,
+            Self::EllipseB => 1.0 - (x * x + 0.625 * y * y).sqrt(),
+            Self::EllipseC => 1.0 - (0.9 * x * x + y * y),
+            Self::InvertedEllipseC => 0.9 * x * x + y * y - 1.0,
+            Self::Square => -ax.max(ay),
+            Self::Cross => -ax.min(ay),
+            Self::Rhomboid => (0.9 * ax + ay) / 2.0,
+            Self::Diamond => {
+                if ax + ay <= 0.75 {
+                    1.0 - (x * x + y * y)
+                } else if ax + ay <= 1.23 {
+                    1.0 - (0.85 * ax + ay)
+                } else {
+                    (ax - 1.0).powi(2) + (ay - 1.0).powi(2) - 1.0
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum TransferFunction<'a> {
     Identity,
@@ -196,13 +442,213 @@ pub enum TransferFunction<'a> {
 }
 
 impl<'a> FromObj<'a> for TransferFunction<'a> {
-    // todo: array, default
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
-        // todo: dont use this
-        Ok(if obj.name_is("Identity") {
-            TransferFunction::Identity
-        } else {
-            TransferFunction::Single(Function::from_obj(obj, resolver)?)
+        Ok(match resolver.resolve(obj)? {
+            Object::Name(name) if name == "Identity" => TransferFunction::Identity,
+            Object::Name(name) if name == "Default" => TransferFunction::Default,
+            Object::Array(arr) => {
+                let [a, b, c, d]: [Object<'a>; 4] = match arr.try_into() {
+                    Ok(arr) => arr,
+                    Err(arr) => anyhow::bail!("expected 4 transfer functions, found {}", arr.len()),
+                };
+
+                TransferFunction::Colorants {
+                    a: Function::from_obj(a, resolver)?,
+                    b: Function::from_obj(b, resolver)?,
+                    c: Function::from_obj(c, resolver)?,
+                    d: Function::from_obj(d, resolver)?,
+                }
+            }
+            obj => TransferFunction::Single(Function::from_obj(obj, resolver)?),
         })
     }
 }
+
+impl<'a> TransferFunction<'a> {
+    /// Sample the transfer function at each level of the components of an RGB
+    /// device, or `None` if it leaves them unchanged
+    ///
+    /// This device's default transfer function is the identity. Of an array of
+    /// four functions, the first three apply to the red, green and blue
+    /// components, and the fourth to gray, which the device has no component for.
+    pub(crate) fn table(&self) -> PdfResult<Option<TransferTable>> {
+        let functions = match self {
+            TransferFunction::Identity | TransferFunction::Default => return Ok(None),
+            TransferFunction::Single(f) => [f, f, f],
+            TransferFunction::Colorants { a, b, c, .. } => [a, b, c],
+        };
+
+        let mut channels = [[0; 256]; 3];
+
+        for (channel, f) in channels.iter_mut().zip(functions) {
+            for (level, transferred) in channel.iter_mut().enumerate() {
+                let y = match f.evaluate(&[level as f32 / 255.0])?.first() {
+                    Some(&y) => y,
+                    None => anyhow::bail!("transfer function has no outputs"),
+                };
+
+                *transferred = (y.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+
+        Ok(Some(TransferTable { channels }))
+    }
+
+    /// Sample the transfer function of a soft mask at each 8-bit level of the
+    /// mask values it is applied to, or `None` if it is the identity
+    pub(crate) fn soft_mask_table(&self) -> PdfResult<Option<[f32; 256]>> {
+        let f = match self {
+            TransferFunction::Identity | TransferFunction::Default => return Ok(None),
+            TransferFunction::Single(f) => f,
+            TransferFunction::Colorants { .. } => {
+                anyhow::bail!("the transfer function of a soft mask must be a single function")
+            }
+        };
+
+        let mut levels = [0.0; 256];
+
+        for (level, transferred) in levels.iter_mut().enumerate() {
+            *transferred = match f.evaluate(&[level as f32 / 255.0])?.first() {
+                Some(&y) => y.clamp(0.0, 1.0),
+                None => anyhow::bail!("transfer function has no outputs"),
+            };
+        }
+
+        Ok(Some(levels))
+    }
+}
+
+/// The transferred value of each 8-bit level of the red, green and blue
+/// components of a device colour
+#[derive(Debug)]
+pub(crate) struct TransferTable {
+    channels: [[u8; 256]; 3],
+}
+
+impl TransferTable {
+    pub fn apply(&self, color: u32) -> u32 {
+        let mut transferred = color & 0xff_00_00_00;
+
+        for (i, channel) in self.channels.iter().enumerate() {
+            let level = (color >> (8 * i)) & 0xff;
+
+            transferred |= u32::from(channel[level as usize]) << (8 * i);
+        }
+
+        transferred
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{
+        objects::{Dictionary, Object},
+        tokenizer::Tokenizer,
+        version::PdfVersion,
+        writer::PdfWriter,
+        FromObj,
+    };
+
+    use super::TransferFunction;
+
+    fn exponential(n: i32) -> Object<'static> {
+        Object::Dictionary(Dictionary::new(HashMap::from([
+            ("FunctionType".to_owned(), Object::Integer(2)),
+            (
+                "Domain".to_owned(),
+                Object::Array(vec![Object::Integer(0), Object::Integer(1)]),
+            ),
+            ("N".to_owned(), Object::Integer(n)),
+        ])))
+    }
+
+    #[test]
+    fn transfer_functions() {
+        let resolver = &mut PdfWriter::new(PdfVersion::V1_7);
+
+        let identity = TransferFunction::from_obj(Object::Name("Identity".to_owned()), resolver);
+        assert!(identity.unwrap().table().unwrap().is_none());
+
+        let default = TransferFunction::from_obj(Object::Name("Default".to_owned()), resolver);
+        assert!(default.unwrap().table().unwrap().is_none());
+
+        // y = x^2, applied to each component
+        let single = TransferFunction::from_obj(exponential(2), resolver).unwrap();
+        let table = single.table().unwrap().unwrap();
+        assert_eq!(table.apply(0xff_ff_80_00), 0xff_ff_40_00);
+
+        // the fourth function, for gray, is unused
+        let colorants = Object::Array(vec![
+            exponential(2),
+            exponential(1),
+            exponential(0),
+            exponential(3),
+        ]);
+        let colorants = TransferFunction::from_obj(colorants, resolver).unwrap();
+        let table = colorants.table().unwrap().unwrap();
+        assert_eq!(table.apply(0x80_80_80_80), 0x80_ff_80_40);
+
+        assert!(TransferFunction::from_obj(Object::Array(vec![exponential(2)]), resolver).is_err());
+    }
+
+    #[test]
+    fn soft_mask_transfer_functions() {
+        let resolver = &mut PdfWriter::new(PdfVersion::V1_7);
+
+        let identity = TransferFunction::from_obj(Object::Name("Identity".to_owned()), resolver);
+        assert!(identity.unwrap().soft_mask_table().unwrap().is_none());
+
+        // y = x^2
+        let single = TransferFunction::from_obj(exponential(2), resolver).unwrap();
+        let table = single.soft_mask_table().unwrap().unwrap();
+        assert_eq!(table[0], 0.0);
+        assert_eq!(table[255], 1.0);
+        assert!((table[128] - 0.252).abs() < 0.001);
+
+        let colorants = Object::Array(vec![exponential(1); 4]);
+        let colorants = TransferFunction::from_obj(colorants, resolver).unwrap();
+        assert!(colorants.soft_mask_table().is_err());
+    }
+
+    fn function(obj: &'static [u8]) -> super::Function<'static> {
+        let mut tokenizer = Tokenizer::new(obj);
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+
+        super::Function::from_obj(obj, &mut tokenizer).unwrap()
+    }
+
+    #[test]
+    fn evaluate_exponential() {
+        let function = function(
+            b"<< /FunctionType 2 /Domain [0 1] /Range [0 1 0 0.5] /C0 [0 1] /C1 [1 0] /N 2 >>",
+        );
+
+        assert_eq!(function.evaluate(&[0.5]).unwrap(), [0.25, 0.5]);
+
+        // inputs are clipped to the domain, and outputs to the range
+        assert_eq!(function.evaluate(&[-1.0]).unwrap(), [0.0, 0.5]);
+        assert_eq!(function.evaluate(&[3.0]).unwrap(), [1.0, 0.0]);
+
+        assert!(function.evaluate(&[0.5, 0.5]).is_err());
+    }
+
+    #[test]
+    fn evaluate_stitching() {
+        // rises from 0 to 1 over [0 0.5), then falls from 1 to 0 over [0.5 1],
+        // using the second function in reverse
+        let function = function(
+            b"<< /FunctionType 3 /Domain [0 1] /Bounds [0.5] /Encode [0 1 1 0] \
+               /Functions [ \
+                 << /FunctionType 2 /Domain [0 1] /N 1 >> \
+                 << /FunctionType 2 /Domain [0 1] /C0 [0] /C1 [1] /N 1 >> \
+               ] >>",
+        );
+
+        assert_eq!(function.evaluate(&[0.25]).unwrap(), [0.5]);
+        assert_eq!(function.evaluate(&[0.5]).unwrap(), [1.0]);
+        assert_eq!(function.evaluate(&[0.75]).unwrap(), [0.5]);
+        assert_eq!(function.evaluate(&[1.0]).unwrap(), [0.0]);
+    }
+}
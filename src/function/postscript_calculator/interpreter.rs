@@ -0,0 +1,408 @@
+use crate::postscript::{PostScriptError, PostScriptResult};
+
+use super::{lexer::PostScriptFunctionOperator, Instruction};
+
+/// The operand stack of a PostScript calculator function shall be able to hold
+/// at least 100 entries
+const MAX_STACK_DEPTH: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operand {
+    Integer(i32),
+    Real(f32),
+    Bool(bool),
+}
+
+impl Operand {
+    fn as_number(self) -> PostScriptResult<f32> {
+        match self {
+            Self::Integer(n) => Ok(n as f32),
+            Self::Real(n) => Ok(n),
+            Self::Bool(..) => anyhow::bail!(PostScriptError::TypeCheck),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct OperandStack {
+    operands: Vec<Operand>,
+}
+
+impl OperandStack {
+    pub fn new() -> Self {
+        Self {
+            operands: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.operands.len()
+    }
+
+    pub fn push_real(&mut self, n: f32) -> PostScriptResult<()> {
+        self.push(Operand::Real(n))
+    }
+
+    /// The numbers on the stack, from the bottom up
+    pub fn into_numbers(self) -> PostScriptResult<Vec<f32>> {
+        self.operands.into_iter().map(Operand::as_number).collect()
+    }
+
+    fn push(&mut self, operand: Operand) -> PostScriptResult<()> {
+        if self.operands.len() >= MAX_STACK_DEPTH {
+            anyhow::bail!(PostScriptError::StackOverflow);
+        }
+
+        self.operands.push(operand);
+
+        Ok(())
+    }
+
+    /// Push the result of an integer operation, which becomes a real if it
+    /// cannot be represented as an integer
+    fn push_integer_or_real(&mut self, n: Option<i32>, real: f32) -> PostScriptResult<()> {
+        match n {
+            Some(n) => self.push(Operand::Integer(n)),
+            None => self.push(Operand::Real(real)),
+        }
+    }
+
+    fn pop(&mut self) -> PostScriptResult<Operand> {
+        match self.operands.pop() {
+            Some(operand) => Ok(operand),
+            None => anyhow::bail!(PostScriptError::StackUnderflow),
+        }
+    }
+
+    fn pop_number(&mut self) -> PostScriptResult<f32> {
+        self.pop()?.as_number()
+    }
+
+    fn pop_integer(&mut self) -> PostScriptResult<i32> {
+        match self.pop()? {
+            Operand::Integer(n) => Ok(n),
+            _ => anyhow::bail!(PostScriptError::TypeCheck),
+        }
+    }
+
+    fn pop_bool(&mut self) -> PostScriptResult<bool> {
+        match self.pop()? {
+            Operand::Bool(b) => Ok(b),
+            _ => anyhow::bail!(PostScriptError::TypeCheck),
+        }
+    }
+
+    /// A count or index operand, which shall be a non-negative integer no
+    /// greater than the depth of the stack
+    fn pop_depth(&mut self) -> PostScriptResult<usize> {
+        let n = self.pop_integer()?;
+
+        if n < 0 || n as usize > self.operands.len() {
+            anyhow::bail!(PostScriptError::RangeCheck);
+        }
+
+        Ok(n as usize)
+    }
+
+    fn pop_real_result(&mut self, f: impl FnOnce(f32) -> f32) -> PostScriptResult<()> {
+        let n = self.pop_number()?;
+
+        self.push(Operand::Real(f(n)))
+    }
+
+    /// Apply an operation which yields an integer if both operands are integers
+    fn binary_arithmetic(
+        &mut self,
+        integer: impl FnOnce(i32, i32) -> Option<i32>,
+        real: impl FnOnce(f32, f32) -> f32,
+    ) -> PostScriptResult<()> {
+        let b = self.pop()?;
+        let a = self.pop()?;
+
+        match (a, b) {
+            (Operand::Integer(a), Operand::Integer(b)) => {
+                self.push_integer_or_real(integer(a, b), real(a as f32, b as f32))
+            }
+            (a, b) => self.push(Operand::Real(real(a.as_number()?, b.as_number()?))),
+        }
+    }
+
+    /// Apply an operation to two booleans or two integers
+    fn bitwise(
+        &mut self,
+        boolean: impl FnOnce(bool, bool) -> bool,
+        integer: impl FnOnce(i32, i32) -> i32,
+    ) -> PostScriptResult<()> {
+        let b = self.pop()?;
+        let a = self.pop()?;
+
+        match (a, b) {
+            (Operand::Bool(a), Operand::Bool(b)) => self.push(Operand::Bool(boolean(a, b))),
+            (Operand::Integer(a), Operand::Integer(b)) => {
+                self.push(Operand::Integer(integer(a, b)))
+            }
+            _ => anyhow::bail!(PostScriptError::TypeCheck),
+        }
+    }
+
+    fn compare(&mut self, f: impl FnOnce(f32, f32) -> bool) -> PostScriptResult<()> {
+        let b = self.pop_number()?;
+        let a = self.pop_number()?;
+
+        self.push(Operand::Bool(f(a, b)))
+    }
+
+    fn equal(&mut self) -> PostScriptResult<bool> {
+        let b = self.pop()?;
+        let a = self.pop()?;
+
+        Ok(match (a, b) {
+            (Operand::Bool(a), Operand::Bool(b)) => a == b,
+            (Operand::Bool(..), _) | (_, Operand::Bool(..)) => false,
+            // integers and reals are compared by their mathematical value
+            (a, b) => a.as_number()? == b.as_number()?,
+        })
+    }
+}
+
+/// Run each instruction of `program` in turn
+pub(super) fn execute(program: &[Instruction], stack: &mut OperandStack) -> PostScriptResult<()> {
+    for instruction in program {
+        match instruction {
+            Instruction::Integer(n) => stack.push(Operand::Integer(*n))?,
+            Instruction::Real(n) => stack.push(Operand::Real(*n))?,
+            Instruction::If(procedure) => {
+                if stack.pop_bool()? {
+                    execute(procedure, stack)?;
+                }
+            }
+            Instruction::IfElse(if_procedure, else_procedure) => {
+                if stack.pop_bool()? {
+                    execute(if_procedure, stack)?;
+                } else {
+                    execute(else_procedure, stack)?;
+                }
+            }
+            Instruction::Operator(op) => execute_operator(*op, stack)?,
+        }
+    }
+
+    Ok(())
+}
+
+fn execute_operator(
+    op: PostScriptFunctionOperator,
+    stack: &mut OperandStack,
+) -> PostScriptResult<()> {
+    match op {
+        PostScriptFunctionOperator::Abs => match stack.pop()? {
+            Operand::Integer(n) => stack.push_integer_or_real(n.checked_abs(), (n as f32).abs())?,
+            Operand::Real(n) => stack.push(Operand::Real(n.abs()))?,
+            Operand::Bool(..) => anyhow::bail!(PostScriptError::TypeCheck),
+        },
+        PostScriptFunctionOperator::Add => {
+            stack.binary_arithmetic(i32::checked_add, |a, b| a + b)?
+        }
+        PostScriptFunctionOperator::Sub => {
+            stack.binary_arithmetic(i32::checked_sub, |a, b| a - b)?
+        }
+        PostScriptFunctionOperator::Mul => {
+            stack.binary_arithmetic(i32::checked_mul, |a, b| a * b)?
+        }
+        PostScriptFunctionOperator::Neg => match stack.pop()? {
+            Operand::Integer(n) => stack.push_integer_or_real(n.checked_neg(), -(n as f32))?,
+            Operand::Real(n) => stack.push(Operand::Real(-n))?,
+            Operand::Bool(..) => anyhow::bail!(PostScriptError::TypeCheck),
+        },
+        PostScriptFunctionOperator::Div => {
+            let b = stack.pop_number()?;
+            let a = stack.pop_number()?;
+
+            if b == 0.0 {
+                anyhow::bail!(PostScriptError::RangeCheck);
+            }
+
+            stack.push(Operand::Real(a / b))?;
+        }
+        PostScriptFunctionOperator::Idiv | PostScriptFunctionOperator::Mod => {
+            let b = stack.pop_integer()?;
+            let a = stack.pop_integer()?;
+
+            if b == 0 {
+                anyhow::bail!(PostScriptError::RangeCheck);
+            }
+
+            // the result of both takes the sign of the dividend
+            stack.push(Operand::Integer(match op {
+                PostScriptFunctionOperator::Idiv => a.wrapping_div(b),
+                _ => a.wrapping_rem(b),
+            }))?;
+        }
+        PostScriptFunctionOperator::Atan => {
+            let den = stack.pop_number()?;
+            let num = stack.pop_number()?;
+
+            if num == 0.0 && den == 0.0 {
+                anyhow::bail!(PostScriptError::RangeCheck);
+            }
+
+            // the angle is in degrees, between 0 and 360
+            let angle = num.atan2(den).to_degrees();
+
+            stack.push(Operand::Real(if angle < 0.0 {
+                angle + 360.0
+            } else {
+                angle
+            }))?;
+        }
+        PostScriptFunctionOperator::Cos => stack.pop_real_result(|n| n.to_radians().cos())?,
+        PostScriptFunctionOperator::Sin => stack.pop_real_result(|n| n.to_radians().sin())?,
+        PostScriptFunctionOperator::Exp => {
+            let exponent = stack.pop_number()?;
+            let base = stack.pop_number()?;
+
+            let n = base.powf(exponent);
+
+            if !n.is_finite() {
+                anyhow::bail!(PostScriptError::RangeCheck);
+            }
+
+            stack.push(Operand::Real(n))?;
+        }
+        PostScriptFunctionOperator::Ln | PostScriptFunctionOperator::Log => {
+            let n = stack.pop_number()?;
+
+            if n <= 0.0 {
+                anyhow::bail!(PostScriptError::RangeCheck);
+            }
+
+            stack.push(Operand::Real(match op {
+                PostScriptFunctionOperator::Ln => n.ln(),
+                _ => n.log10(),
+            }))?;
+        }
+        PostScriptFunctionOperator::Sqrt => {
+            let n = stack.pop_number()?;
+
+            if n < 0.0 {
+                anyhow::bail!(PostScriptError::RangeCheck);
+            }
+
+            stack.push(Operand::Real(n.sqrt()))?;
+        }
+        PostScriptFunctionOperator::Ceiling
+        | PostScriptFunctionOperator::Floor
+        | PostScriptFunctionOperator::Round
+        | PostScriptFunctionOperator::Truncate => match stack.pop()? {
+            // integers are returned unchanged
+            Operand::Integer(n) => stack.push(Operand::Integer(n))?,
+            Operand::Real(n) => stack.push(Operand::Real(match op {
+                PostScriptFunctionOperator::Ceiling => n.ceil(),
+                PostScriptFunctionOperator::Floor => n.floor(),
+                // halfway values are rounded up, rather than away from 0
+                PostScriptFunctionOperator::Round => (n + 0.5).floor(),
+                _ => n.trunc(),
+            }))?,
+            Operand::Bool(..) => anyhow::bail!(PostScriptError::TypeCheck),
+        },
+        PostScriptFunctionOperator::Cvi => {
+            let n = stack.pop_number()?.trunc();
+
+            if n < i32::MIN as f32 || n >= i32::MAX as f32 {
+                anyhow::bail!(PostScriptError::RangeCheck);
+            }
+
+            stack.push(Operand::Integer(n as i32))?;
+        }
+        PostScriptFunctionOperator::Cvr => stack.pop_real_result(|n| n)?,
+
+        PostScriptFunctionOperator::And => stack.bitwise(|a, b| a && b, |a, b| a & b)?,
+        PostScriptFunctionOperator::Or => stack.bitwise(|a, b| a || b, |a, b| a | b)?,
+        PostScriptFunctionOperator::Xor => stack.bitwise(|a, b| a ^ b, |a, b| a ^ b)?,
+        PostScriptFunctionOperator::Not => match stack.pop()? {
+            Operand::Bool(b) => stack.push(Operand::Bool(!b))?,
+            Operand::Integer(n) => stack.push(Operand::Integer(!n))?,
+            Operand::Real(..) => anyhow::bail!(PostScriptError::TypeCheck),
+        },
+        PostScriptFunctionOperator::Bitshift => {
+            let shift = stack.pop_integer()?;
+            let n = stack.pop_integer()? as u32;
+
+            // positive shifts are to the left, and bits shifted in are 0
+            let n = if shift >= 0 {
+                n.checked_shl(shift as u32).unwrap_or(0)
+            } else {
+                n.checked_shr(shift.unsigned_abs()).unwrap_or(0)
+            };
+
+            stack.push(Operand::Integer(n as i32))?;
+        }
+        PostScriptFunctionOperator::True => stack.push(Operand::Bool(true))?,
+        PostScriptFunctionOperator::False => stack.push(Operand::Bool(false))?,
+        PostScriptFunctionOperator::Eq => {
+            let eq = stack.equal()?;
+            stack.push(Operand::Bool(eq))?;
+        }
+        PostScriptFunctionOperator::Ne => {
+            let eq = stack.equal()?;
+            stack.push(Operand::Bool(!eq))?;
+        }
+        PostScriptFunctionOperator::Ge => stack.compare(|a, b| a >= b)?,
+        PostScriptFunctionOperator::Gt => stack.compare(|a, b| a > b)?,
+        PostScriptFunctionOperator::Le => stack.compare(|a, b| a <= b)?,
+        PostScriptFunctionOperator::Lt => stack.compare(|a, b| a < b)?,
+
+        // `if` and `ifelse` are joined with their procedures when the function
+        // is parsed
+        PostScriptFunctionOperator::If | PostScriptFunctionOperator::Ifelse => {
+            anyhow::bail!(PostScriptError::TypeCheck)
+        }
+
+        PostScriptFunctionOperator::Copy => {
+            let n = stack.pop_depth()?;
+            let start = stack.operands.len() - n;
+
+            for idx in start..start + n {
+                stack.push(stack.operands[idx])?;
+            }
+        }
+        PostScriptFunctionOperator::Dup => {
+            let operand = stack.pop()?;
+
+            stack.push(operand)?;
+            stack.push(operand)?;
+        }
+        PostScriptFunctionOperator::Exch => {
+            let b = stack.pop()?;
+            let a = stack.pop()?;
+
+            stack.push(b)?;
+            stack.push(a)?;
+        }
+        PostScriptFunctionOperator::Index => {
+            let n = stack.pop_depth()?;
+
+            if n >= stack.operands.len() {
+                anyhow::bail!(PostScriptError::RangeCheck);
+            }
+
+            stack.push(stack.operands[stack.operands.len() - 1 - n])?;
+        }
+        PostScriptFunctionOperator::Pop => {
+            stack.pop()?;
+        }
+        PostScriptFunctionOperator::Roll => {
+            let j = stack.pop_integer()?;
+            let n = stack.pop_depth()?;
+
+            if n > 0 {
+                let start = stack.operands.len() - n;
+
+                // positive amounts roll towards the top of the stack
+                stack.operands[start..].rotate_right(j.rem_euclid(n as i32) as usize);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -140,9 +140,11 @@ impl PostScriptFunctionLexer {
 
     fn skip_whitespace(&mut self) {
         while let Some(b) = self.peek_byte() {
-            if b.is_ascii_whitespace() {
-                self.next_byte();
+            if !b.is_ascii_whitespace() && b != b'\0' {
+                break;
             }
+
+            self.next_byte();
         }
     }
 
@@ -169,7 +171,7 @@ impl PostScriptFunctionLexer {
             self.next_byte();
         }
 
-        ident_token_from_bytes(&self.buffer[start..=self.cursor])
+        ident_token_from_bytes(&self.buffer[start..self.cursor])
     }
 
     fn lex_whole_number(&mut self) {
@@ -193,43 +195,72 @@ impl PostScriptFunctionLexer {
 
     fn lex_number(&mut self) -> PostScriptResult<PostScriptFunctionToken> {
         let start = self.cursor;
-        self.consume_if_next_byte_is(b'-');
+
+        if !self.consume_if_next_byte_is(b'-') {
+            self.consume_if_next_byte_is(b'+');
+        }
+
         self.lex_whole_number();
 
+        let mut is_real = false;
+
         if self.consume_if_next_byte_is(b'.') {
+            is_real = true;
             self.lex_whole_number();
+        }
+
+        if self.consume_if_next_byte_is(b'e') || self.consume_if_next_byte_is(b'E') {
+            is_real = true;
+
+            if !self.consume_if_next_byte_is(b'-') {
+                self.consume_if_next_byte_is(b'+');
+            }
 
-            return Ok(PostScriptFunctionToken::Real(
-                std::str::from_utf8(&self.buffer[start..self.cursor])?.parse::<f32>()?,
-            ));
+            self.lex_whole_number();
         }
 
-        Ok(PostScriptFunctionToken::Integer(parse_integer(
-            &self.buffer[start..self.cursor],
-        )))
+        let number = std::str::from_utf8(&self.buffer[start..self.cursor])?;
+
+        if !is_real {
+            // integers which cannot be represented are converted to reals
+            if let Ok(n) = number.parse::<i32>() {
+                return Ok(PostScriptFunctionToken::Integer(n));
+            }
+        }
+
+        match number.parse::<f32>() {
+            Ok(n) => Ok(PostScriptFunctionToken::Real(n)),
+            Err(..) => anyhow::bail!(PostScriptError::ParseError(Cow::Owned(format!(
+                "invalid number: {:?}",
+                number
+            )))),
+        }
     }
 
     fn next_token(&mut self) -> Option<PostScriptResult<PostScriptFunctionToken>> {
+        self.skip_whitespace();
+
         Some(match self.peek_byte()? {
-            b'0'..=b'9' | b'-' | b'.' => self.lex_number(),
+            b'0'..=b'9' | b'-' | b'+' | b'.' => self.lex_number(),
             b'a'..=b'z' | b'A'..=b'Z' => self.lex_ident(),
-            b'{' => Ok(PostScriptFunctionToken::OpenCurlyBrace),
-            b'}' => Ok(PostScriptFunctionToken::CloseCurlyBrace),
-            b => todo!("unexpected token start {:?}", b),
-        })
-    }
-}
-
-// todo: overflow
-fn parse_integer(bytes: &[u8]) -> i32 {
-    let mut n = 0;
+            b'{' => {
+                self.next_byte();
+                Ok(PostScriptFunctionToken::OpenCurlyBrace)
+            }
+            b'}' => {
+                self.next_byte();
+                Ok(PostScriptFunctionToken::CloseCurlyBrace)
+            }
+            b => {
+                // skip the byte, so that the lexer can't be stuck on it
+                self.next_byte();
 
-    for b in bytes {
-        n *= 10;
-        n += *b as i32;
+                Err(anyhow::anyhow!(PostScriptError::ParseError(Cow::Owned(
+                    format!("unexpected byte in function: {:?}", b as char)
+                ))))
+            }
+        })
     }
-
-    n
 }
 
 impl Iterator for PostScriptFunctionLexer {
@@ -1,23 +1,236 @@
-use crate::{error::PdfResult, filter::decode_stream, stream::Stream, Resolve};
+use std::borrow::Cow;
 
-use self::lexer::PostScriptFunctionLexer;
+use crate::{
+    error::PdfResult,
+    filter::decode_stream,
+    postscript::{PostScriptError, PostScriptResult},
+    stream::Stream,
+    Resolve,
+};
 
+use self::{
+    interpreter::{execute, OperandStack},
+    lexer::{PostScriptFunctionLexer, PostScriptFunctionOperator, PostScriptFunctionToken},
+};
+
+mod interpreter;
 mod lexer;
 
 /// A type 4 function, also called a PostScript calculator function, shall be
 /// represented as a stream containing code written in a small subset of the PostScript language
 #[derive(Debug, Clone)]
 pub struct PostScriptCalculatorFunction {
-    // todo: probably want to actually store the AST instead of just the lexer
-    tokens: PostScriptFunctionLexer,
+    program: Vec<Instruction>,
+}
+
+/// A single step of a PostScript calculator function, in which the procedures
+/// operated on by `if` and `ifelse` are joined with their operator
+#[derive(Debug, Clone)]
+enum Instruction {
+    Integer(i32),
+    Real(f32),
+    Operator(PostScriptFunctionOperator),
+    If(Vec<Instruction>),
+    IfElse(Vec<Instruction>, Vec<Instruction>),
 }
 
 impl PostScriptCalculatorFunction {
     pub fn from_stream<'a>(stream: Stream<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
         let buffer = decode_stream(&stream.stream, &stream.dict, resolver)?;
 
-        Ok(Self {
-            tokens: PostScriptFunctionLexer::new(buffer.into_owned().into_boxed_slice()),
-        })
+        let mut tokens = PostScriptFunctionLexer::new(buffer.into_owned().into_boxed_slice());
+
+        // the function is a single procedure, enclosed in braces
+        match tokens.next().transpose()? {
+            Some(PostScriptFunctionToken::OpenCurlyBrace) => {}
+            token => anyhow::bail!(parse_error(format!(
+                "expected function to begin with `{{`, found {:?}",
+                token
+            ))),
+        }
+
+        let program = parse_procedure(&mut tokens)?;
+
+        if let Some(token) = tokens.next().transpose()? {
+            anyhow::bail!(parse_error(format!(
+                "unexpected token after end of function: {:?}",
+                token
+            )));
+        }
+
+        Ok(Self { program })
+    }
+
+    /// Run the function with `inputs` on the operand stack, which shall be
+    /// left with one number for each output in `range`
+    pub fn evaluate(&self, inputs: &[f32], range: Option<&[f32]>) -> PdfResult<Vec<f32>> {
+        let range = match range {
+            Some(range) => range,
+            None => anyhow::bail!("postscript calculator function has no Range"),
+        };
+
+        let mut stack = OperandStack::new();
+
+        for &input in inputs {
+            stack.push_real(input)?;
+        }
+
+        execute(&self.program, &mut stack)?;
+
+        anyhow::ensure!(
+            stack.len() == range.len() / 2,
+            "expected {} postscript calculator function outputs, found {}",
+            range.len() / 2,
+            stack.len()
+        );
+
+        stack.into_numbers()
+    }
+}
+
+fn parse_error(message: String) -> PostScriptError {
+    PostScriptError::ParseError(Cow::Owned(message))
+}
+
+/// Parse the body of a procedure, whose opening brace has already been
+/// consumed, up to and including its closing brace
+fn parse_procedure(tokens: &mut PostScriptFunctionLexer) -> PostScriptResult<Vec<Instruction>> {
+    let mut instructions = Vec::new();
+
+    // procedures may only appear as the operands of `if` and `ifelse`
+    let mut procedures: Vec<Vec<Instruction>> = Vec::new();
+
+    loop {
+        let token = match tokens.next().transpose()? {
+            Some(token) => token,
+            None => anyhow::bail!(parse_error("unterminated procedure".to_owned())),
+        };
+
+        let instruction = match token {
+            PostScriptFunctionToken::OpenCurlyBrace => {
+                procedures.push(parse_procedure(tokens)?);
+                continue;
+            }
+            PostScriptFunctionToken::CloseCurlyBrace => break,
+            PostScriptFunctionToken::Operator(PostScriptFunctionOperator::If) => {
+                match (procedures.pop(), procedures.is_empty()) {
+                    (Some(procedure), true) => Instruction::If(procedure),
+                    _ => anyhow::bail!(parse_error(
+                        "`if` shall be preceded by exactly one procedure".to_owned()
+                    )),
+                }
+            }
+            PostScriptFunctionToken::Operator(PostScriptFunctionOperator::Ifelse) => {
+                match (procedures.pop(), procedures.pop(), procedures.is_empty()) {
+                    (Some(else_procedure), Some(if_procedure), true) => {
+                        Instruction::IfElse(if_procedure, else_procedure)
+                    }
+                    _ => anyhow::bail!(parse_error(
+                        "`ifelse` shall be preceded by exactly two procedures".to_owned()
+                    )),
+                }
+            }
+            PostScriptFunctionToken::Operator(op) => Instruction::Operator(op),
+            PostScriptFunctionToken::Integer(n) => Instruction::Integer(n),
+            PostScriptFunctionToken::Real(n) => Instruction::Real(n),
+        };
+
+        anyhow::ensure!(
+            procedures.is_empty()
+                || matches!(instruction, Instruction::If(..) | Instruction::IfElse(..)),
+            parse_error("procedure not followed by `if` or `ifelse`".to_owned())
+        );
+
+        instructions.push(instruction);
+    }
+
+    anyhow::ensure!(
+        procedures.is_empty(),
+        parse_error("procedure not followed by `if` or `ifelse`".to_owned())
+    );
+
+    Ok(instructions)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{
+        function::Function,
+        objects::{Dictionary, Object},
+        version::PdfVersion,
+        writer::PdfWriter,
+        FromObj,
+    };
+
+    use super::*;
+
+    fn calculator(code: &str, domain: usize, range: usize) -> PdfResult<Function<'static>> {
+        let interval =
+            |n| Object::Array([0, 1].repeat(n).into_iter().map(Object::Integer).collect());
+
+        let dict = HashMap::from([
+            ("FunctionType".to_owned(), Object::Integer(4)),
+            ("Domain".to_owned(), interval(domain)),
+            ("Range".to_owned(), interval(range)),
+        ]);
+
+        Function::from_obj(
+            Object::Stream(Stream::new(code.as_bytes().to_vec(), Dictionary::new(dict))),
+            &mut PdfWriter::new(PdfVersion::V1_7),
+        )
+    }
+
+    fn evaluate(code: &str, inputs: &[f32], outputs: usize) -> Vec<f32> {
+        calculator(code, inputs.len(), outputs)
+            .unwrap()
+            .evaluate(inputs)
+            .unwrap()
+    }
+
+    #[test]
+    fn evaluate_calculator_functions() {
+        assert_eq!(
+            evaluate("{ dup mul exch 2 div add }", &[0.5, 0.25], 1),
+            [0.3125]
+        );
+        assert_eq!(evaluate("{ 0.5 gt { 1 } { 0 } ifelse }", &[0.75], 1), [1.0]);
+        assert_eq!(evaluate("{ dup 0.5 le { pop 0 } if }", &[0.75], 1), [0.75]);
+
+        // a tint transform from one colorant to CMYK
+        assert_eq!(
+            evaluate("{ 0 exch dup 0.5 mul 0 }", &[1.0], 4),
+            [0.0, 1.0, 0.5, 0.0]
+        );
+        assert_eq!(
+            evaluate("{ pop 0.1 0.2 0.3 3 1 roll }", &[0.0], 3),
+            [0.3, 0.1, 0.2]
+        );
+
+        // angles are in degrees, and results are clipped to the range
+        assert_eq!(evaluate("{ 180 mul cos }", &[1.0], 1), [0.0]);
+        assert_eq!(
+            evaluate("{ 0 exch 1 index atan 360 div }", &[1.0], 2),
+            [0.0, 0.25]
+        );
+        assert_eq!(
+            evaluate(
+                "{ 7 2 idiv 1 3 bitshift add 11 eq { 1 } { 0 } ifelse }",
+                &[],
+                1
+            ),
+            [1.0]
+        );
+
+        assert!(calculator("{ 1 add", 1, 1).is_err());
+        assert!(calculator("{ { 1 } add }", 1, 1).is_err());
+        assert!(calculator("{ 1 foo }", 1, 1).is_err());
+
+        let underflow = calculator("{ pop pop }", 1, 1).unwrap();
+        assert!(underflow.evaluate(&[0.5]).is_err());
+
+        let too_many_outputs = calculator("{ dup }", 1, 1).unwrap();
+        assert!(too_many_outputs.evaluate(&[0.5]).is_err());
     }
 }
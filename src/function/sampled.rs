@@ -1,47 +1,160 @@
-use crate::stream::Stream;
+use crate::{error::PdfResult, filter::decode_stream, stream::Stream, Resolve};
+
+use super::{interpolate, interpolate_table};
 
 /// Type 0 functions use a sequence of sample values (contained in a stream) to provide an
 /// approximation for functions whose domains and ranges are bounded. The samples are organized
 /// as an m-dimensional table in which each entry has n components.
-#[derive(Debug, Clone, FromObj)]
-pub struct SampledFunction<'a> {
+#[derive(Debug, Clone)]
+pub struct SampledFunction {
     /// An array of m positive integers that shall specify the number of samples in each
     /// input dimension of the sample table
-    #[field("Size")]
     size: Vec<u32>,
 
     /// The number of bits that shall represent each sample. (If the function has multiple
     /// output values, each one shall occupy BitsPerSample bits.)
-    #[field("BitsPerSample")]
     bits_per_sample: BitsPerSample,
 
     /// The order of interpolation between samples. Valid values shall be 1 and 3, specifying
     /// linear and cubic spline interpolation, respectively
     ///
     /// Default value: 1
-    #[field("Order", default = InterpolationOrder::default())]
     order: InterpolationOrder,
 
     /// An array of 2 * m numbers specifying the linear mapping of input values into the domain
     /// of the function's sample table.
     ///
     /// Default value: [0 (Size0 - 1) 0 (Size1 - 1) ...]
-    #[field(
-        "Encode",
-        default = size.iter().flat_map(|&i| vec![0.0, (i as f32) - 1.0]).collect()
-    )]
     encode: Vec<f32>,
 
     /// An array of 2 * n numbers specifying the linear mapping of sample values into the range
     /// appropriate for the function's output values
     ///
     /// Default value: same as the value of Range
-    // todo: doesn't have to be Option because of default
-    #[field("Decode")]
     decode: Option<Vec<f32>>,
 
-    #[field]
-    stream: Stream<'a>,
+    /// The decoded sample table, in which the first dimension varies fastest
+    samples: Vec<u8>,
+}
+
+impl SampledFunction {
+    pub fn from_stream<'a>(stream: Stream<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let samples = decode_stream(&stream.stream, &stream.dict, resolver)?.into_owned();
+
+        let mut dict = stream.dict.other;
+
+        let size: Vec<u32> = dict.expect("Size", resolver)?;
+        let bits_per_sample = dict.expect("BitsPerSample", resolver)?;
+        let order = dict
+            .get("Order", resolver)?
+            .unwrap_or_else(InterpolationOrder::default);
+        let encode: Vec<f32> = dict
+            .get("Encode", resolver)?
+            .unwrap_or_else(|| size.iter().flat_map(|&i| [0.0, (i as f32) - 1.0]).collect());
+        let decode = dict.get("Decode", resolver)?;
+
+        anyhow::ensure!(
+            !size.is_empty() && size.iter().all(|&i| i > 0),
+            "invalid sampled function size {:?}",
+            size
+        );
+        anyhow::ensure!(
+            encode.len() == size.len() * 2,
+            "expected {} Encode values for sampled function, found {}",
+            size.len() * 2,
+            encode.len()
+        );
+
+        Ok(Self {
+            size,
+            bits_per_sample,
+            order,
+            encode,
+            decode,
+            samples,
+        })
+    }
+
+    /// Interpolate between the samples surrounding the position of `inputs`
+    /// in the sample table, which have already been clipped to `domain`
+    ///
+    /// Interpolation is multilinear. Cubic spline interpolation, requested by
+    /// an Order of 3, is approximated by it.
+    pub fn evaluate(
+        &self,
+        inputs: &[f32],
+        domain: &[f32],
+        range: Option<&[f32]>,
+    ) -> PdfResult<Vec<f32>> {
+        let range = match range {
+            Some(range) => range,
+            None => anyhow::bail!("sampled function has no Range"),
+        };
+
+        anyhow::ensure!(
+            inputs.len() == self.size.len(),
+            "expected {} sampled function inputs, found {}",
+            self.size.len(),
+            inputs.len()
+        );
+
+        let decode = self.decode.as_deref().unwrap_or(range);
+        let n = range.len() / 2;
+
+        anyhow::ensure!(
+            decode.len() >= n * 2,
+            "expected {} Decode values for sampled function, found {}",
+            n * 2,
+            decode.len()
+        );
+
+        // the position of the inputs in the sample table
+        let position = inputs
+            .iter()
+            .zip(&self.size)
+            .enumerate()
+            .map(|(i, (&x, &size))| {
+                interpolate(
+                    x,
+                    domain[2 * i],
+                    domain[2 * i + 1],
+                    self.encode[2 * i],
+                    self.encode[2 * i + 1],
+                )
+                .clamp(0.0, (size - 1) as f32)
+            })
+            .collect::<Vec<_>>();
+
+        let outputs = interpolate_table(&position, &self.size, n, |idx| self.sample(idx) as f32);
+
+        let max_sample = ((1_u64 << self.bits_per_sample as u32) - 1) as f32;
+
+        Ok(outputs
+            .into_iter()
+            .enumerate()
+            .map(|(j, r)| interpolate(r, 0.0, max_sample, decode[2 * j], decode[2 * j + 1]))
+            .collect())
+    }
+
+    /// The sample at `idx` in the table, which is 0 beyond the end of the data
+    ///
+    /// Samples are packed without padding, with the high-order bits of each
+    /// byte first.
+    fn sample(&self, idx: usize) -> u32 {
+        let bits = self.bits_per_sample as usize;
+        let start = idx * bits;
+
+        let first_byte = start / 8;
+        let last_byte = (start + bits - 1) / 8;
+
+        let value = (first_byte..=last_byte).fold(0_u64, |value, byte| {
+            value << 8 | u64::from(self.samples.get(byte).copied().unwrap_or(0))
+        });
+
+        let shift = (last_byte - first_byte + 1) * 8 - start % 8 - bits;
+
+        ((value >> shift) & ((1 << bits) - 1)) as u32
+    }
 }
 
 #[pdf_enum(Integer)]
@@ -63,3 +176,110 @@ enum BitsPerSample {
     TwentyFour = 24,
     ThirtyTwo = 32,
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{
+        function::Function,
+        objects::{Dictionary, Object},
+        version::PdfVersion,
+        writer::PdfWriter,
+        FromObj,
+    };
+
+    use super::*;
+
+    fn numbers(values: &[f32]) -> Object<'static> {
+        Object::Array(values.iter().map(|&v| Object::Real(v)).collect())
+    }
+
+    fn integers(values: &[i32]) -> Object<'static> {
+        Object::Array(values.iter().map(|&v| Object::Integer(v)).collect())
+    }
+
+    fn sampled(samples: Vec<u8>, entries: Vec<(&str, Object<'static>)>) -> Function<'static> {
+        let mut dict = HashMap::from([
+            ("FunctionType".to_owned(), Object::Integer(0)),
+            ("BitsPerSample".to_owned(), Object::Integer(8)),
+        ]);
+
+        dict.extend(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value)),
+        );
+
+        Function::from_obj(
+            Object::Stream(Stream::new(samples, Dictionary::new(dict))),
+            &mut PdfWriter::new(PdfVersion::V1_7),
+        )
+        .unwrap()
+    }
+
+    fn assert_close(found: Vec<f32>, expected: &[f32]) {
+        assert_eq!(found.len(), expected.len());
+
+        for (found, expected) in found.into_iter().zip(expected) {
+            assert!(
+                (found - expected).abs() < 0.001,
+                "{} != {}",
+                found,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn evaluate_sampled_functions() {
+        let peak = sampled(
+            vec![0, 255, 0],
+            vec![
+                ("Size", integers(&[3])),
+                ("Domain", numbers(&[0.0, 1.0])),
+                ("Range", numbers(&[0.0, 1.0])),
+            ],
+        );
+
+        assert_close(peak.evaluate(&[0.25]).unwrap(), &[0.5]);
+        assert_close(peak.evaluate(&[0.5]).unwrap(), &[1.0]);
+        assert_close(peak.evaluate(&[1.0]).unwrap(), &[0.0]);
+        // inputs are clipped to the domain
+        assert_close(peak.evaluate(&[-3.0]).unwrap(), &[0.0]);
+
+        // the first input varies fastest, and each entry of the table has two
+        // outputs, the second of which is inverted by the Decode array
+        let bilinear = sampled(
+            vec![0, 0, 255, 0, 255, 0, 0, 0],
+            vec![
+                ("Size", integers(&[2, 2])),
+                ("Domain", numbers(&[0.0, 1.0, 0.0, 1.0])),
+                ("Range", numbers(&[0.0, 1.0, 0.0, 1.0])),
+                ("Decode", numbers(&[0.0, 1.0, 1.0, 0.0])),
+            ],
+        );
+
+        assert_close(bilinear.evaluate(&[1.0, 0.0]).unwrap(), &[1.0, 1.0]);
+        assert_close(bilinear.evaluate(&[0.0, 1.0]).unwrap(), &[1.0, 1.0]);
+        assert_close(bilinear.evaluate(&[1.0, 1.0]).unwrap(), &[0.0, 1.0]);
+        assert_close(bilinear.evaluate(&[0.5, 0.5]).unwrap(), &[0.5, 1.0]);
+        assert_close(bilinear.evaluate(&[0.25, 0.0]).unwrap(), &[0.25, 1.0]);
+
+        // 12-bit samples span byte boundaries, and Encode maps the domain onto
+        // part of the table
+        let twelve_bit = sampled(
+            vec![0x00, 0x08, 0x00, 0xff, 0xf0],
+            vec![
+                ("Size", integers(&[3])),
+                ("BitsPerSample", Object::Integer(12)),
+                ("Domain", numbers(&[0.0, 1.0])),
+                ("Encode", numbers(&[1.0, 2.0])),
+                ("Range", numbers(&[0.0, 1.0])),
+            ],
+        );
+
+        assert_close(twelve_bit.evaluate(&[0.0]).unwrap(), &[2048.0 / 4095.0]);
+        assert_close(twelve_bit.evaluate(&[1.0]).unwrap(), &[1.0]);
+    }
+}
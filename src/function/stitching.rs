@@ -37,4 +37,38 @@ impl<'a> StitchingFunction<'a> {
             encode,
         })
     }
+
+    /// Apply the function covering the subdomain containing `x`. Each subdomain
+    /// includes its lower bound, and the last also includes the upper bound of
+    /// `domain`
+    pub fn evaluate(&self, x: f32, domain: &[f32]) -> PdfResult<Vec<f32>> {
+        let k = self.bounds.iter().take_while(|&&bound| x >= bound).count();
+
+        let function = match self.functions.get(k) {
+            Some(function) => function,
+            None => anyhow::bail!(
+                "stitching function has {} functions, expected at least {}",
+                self.functions.len(),
+                k + 1
+            ),
+        };
+
+        let low = if k == 0 {
+            domain[0]
+        } else {
+            self.bounds[k - 1]
+        };
+        let high = self.bounds.get(k).copied().unwrap_or(domain[1]);
+
+        let e0 = self.encode[2 * k];
+        let e1 = self.encode[2 * k + 1];
+
+        let x = if high == low {
+            e0
+        } else {
+            e0 + (x - low) * (e1 - e0) / (high - low)
+        };
+
+        function.evaluate(&[x])
+    }
 }
@@ -46,6 +46,15 @@ impl BoundingBox {
                 || (self.max.y > other.min.y && self.max.y < other.max.y))
     }
 
+    /// Whether the two boxes share any area, including where one contains the
+    /// other
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x < other.max.x
+            && other.min.x < self.max.x
+            && self.min.y < other.max.y
+            && other.min.y < self.max.y
+    }
+
     pub fn width(&self) -> f32 {
         self.max.x - self.min.x
     }
@@ -79,6 +79,11 @@ impl PathBuilder {
         self.width_vector = Point::new(width_vector_x_coord, 0.0);
     }
 
+    pub fn sbw(&mut self, sbx: f32, sby: f32, wx: f32, wy: f32) {
+        self.current_path = Path::new(Point::new(sbx, sby));
+        self.width_vector = Point::new(wx, wy);
+    }
+
     #[allow(unused)]
     pub fn horizontal_stem(&mut self, y: f32, dy: f32) {}
     #[allow(unused)]
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+
+use crate::{
+    error::PdfResult,
+    filter::decode_stream,
+    function::{SpotFunction, TransferFunction},
+    objects::{Dictionary, Object},
+    stream::Stream,
+    FromObj, Resolve,
+};
+
+pub(crate) use screen::HalftoneScreen;
+
+mod screen;
+
+#[derive(Debug, Clone)]
+pub enum Halftones<'a> {
+    Default,
+    Dictionary(HalftoneDictionary<'a>),
+    Stream(HalftoneStream<'a>),
+}
+
+impl<'a> FromObj<'a> for Halftones<'a> {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        Ok(match resolver.resolve(obj)? {
+            Object::Name(name) if name == "Default" => Halftones::Default,
+            Object::Stream(stream) => {
+                Halftones::Stream(HalftoneStream::from_stream(stream, resolver)?)
+            }
+            Object::Dictionary(dict) => {
+                Halftones::Dictionary(HalftoneDictionary::from_dict(dict, resolver)?)
+            }
+            obj => anyhow::bail!("expected name, stream, or dictionary; found {:#?}", obj),
+        })
+    }
+}
+
+/// Halftones defined by a threshold array, which is held in the stream data
+#[derive(Debug, Clone)]
+pub enum HalftoneStream<'a> {
+    Six(HalftoneSix<'a>),
+    Ten(HalftoneTen<'a>),
+    Sixteen(HalftoneSixteen<'a>),
+}
+
+#[derive(Debug, Clone)]
+pub enum HalftoneDictionary<'a> {
+    One(HalftoneOne<'a>),
+    Five(HalftoneFive<'a>),
+}
+
+// todo: pdf_enum?
+#[pdf_enum(Integer)]
+enum HalftoneType {
+    /// Defines a single halftone screen by a frequency, angle, and spot function
+    One = 1,
+
+    /// Defines an arbitrary number of halftone screens, one for each colorant or colour
+    /// component (including both primary and spot colorants). The keys in this dictionary
+    /// are names of colorants; the values are halftone dictionaries of other types, each
+    /// defining the halftone screen for a single colorant.
+    Five = 5,
+
+    /// Defines a single halftone screen by a threshold array containing 8-bit sample values.
+    Six = 6,
+
+    /// Defines a single halftone screen by a threshold array containing 8-bit sample values,
+    /// representing a halftone cell that may have a nonzero screen angle
+    Ten = 10,
+
+    /// Defines a single halftone screen by a threshold array containing 16-bit sample values,
+    /// representing a halftone cell that may have a nonzero screen angle.
+    Sixteen = 16,
+}
+
+#[derive(Debug, Clone, FromObj)]
+pub struct HalftoneOne<'a> {
+    /// The screen frequency, measured in halftone cells per inch in device space
+    #[field("Frequency")]
+    frequency: f32,
+
+    /// The screen angle, in degrees of rotation counterclockwise with respect to
+    /// the device coordinate system
+    #[field("Angle")]
+    angle: f32,
+
+    /// A function object defining the order in which device pixels within a screen
+    /// cell shall be adjusted for different gray levels, or the name of one of the
+    /// predefined spot functions
+    #[field("SpotFunction")]
+    spot_function: SpotFunction<'a>,
+
+    /// A flag specifying whether to invoke a special halftone algorithm that is extremely
+    /// precise but computationally expensive; see Note 1 for further discussion.
+    ///
+    /// Default value: false
+    #[field("AccurateScreens")]
+    accurate_screens: Option<bool>,
+
+    /// A transfer function, which overrides the current transfer function in the graphics
+    /// state for the same component.
+    ///
+    /// This entry shall be present if the dictionary is a component of a type 5 halftone and
+    /// represents either a nonprimary or nonstandard primary colour component.
+    ///
+    /// The name Identity may be used to specify the identity function
+    #[field("TransferFunction")]
+    transfer_function: Option<TransferFunction<'a>>,
+
+    #[field("HalftoneName")]
+    halftone_name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HalftoneFive<'a> {
+    /// The halftones for each colorant, keyed by the name of the colorant
+    colorants: HashMap<String, Halftones<'a>>,
+
+    /// The halftone for colorants which have no entry of their own
+    default: Box<Halftones<'a>>,
+
+    halftone_name: Option<String>,
+}
+
+impl<'a> HalftoneFive<'a> {
+    fn from_dict(mut dict: Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let halftone_name = dict.get("HalftoneName", resolver)?;
+
+        let mut colorants = HashMap::new();
+        let mut default = None;
+
+        for (colorant, obj) in dict.entries() {
+            let halftone = Halftones::from_obj(obj, resolver)?;
+
+            anyhow::ensure!(
+                !matches!(
+                    halftone,
+                    Halftones::Dictionary(HalftoneDictionary::Five(..))
+                ),
+                "type 5 halftone for colorant {:?} may not itself be a type 5 halftone",
+                colorant
+            );
+
+            if colorant == "Default" {
+                default = Some(Box::new(halftone));
+            } else {
+                colorants.insert(colorant, halftone);
+            }
+        }
+
+        let default = match default {
+            Some(default) => default,
+            None => anyhow::bail!("type 5 halftone is missing its Default entry"),
+        };
+
+        Ok(Self {
+            colorants,
+            default,
+            halftone_name,
+        })
+    }
+
+    /// The halftone for the colorant with the given name
+    pub fn colorant(&self, colorant: &str) -> &Halftones<'a> {
+        self.colorants.get(colorant).unwrap_or(&self.default)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HalftoneSix<'a> {
+    /// The width of the threshold array, in device pixels
+    width: u32,
+
+    /// The height of the threshold array, in device pixels
+    height: u32,
+
+    transfer_function: Option<TransferFunction<'a>>,
+
+    halftone_name: Option<String>,
+
+    /// The 8-bit threshold values, row by row from the top left of the
+    /// threshold array
+    thresholds: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HalftoneTen<'a> {
+    /// The side of square X, in device pixels
+    x_square: u32,
+
+    /// The side of square Y, in device pixels
+    y_square: u32,
+
+    transfer_function: Option<TransferFunction<'a>>,
+
+    halftone_name: Option<String>,
+
+    /// The 8-bit threshold values of square X followed by those of square Y,
+    /// each row by row from its top left
+    thresholds: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HalftoneSixteen<'a> {
+    /// The width of the first (or only) rectangle in the threshold array, in device pixels.
+    width: u32,
+
+    /// The height of the first (or only) rectangle in the threshold array, in device pixels.
+    height: u32,
+
+    /// The width of the optional second rectangle in the threshold array, in device pixels.
+    ///
+    /// If this entry is present, the Height2 entry shall be present as well.
+    /// If this entry is absent, the Height2 entry shall also be absent, and the threshold array has
+    /// only one rectangle
+    width_two: Option<u32>,
+
+    /// The height of the optional second rectangle in the threshold array, in device pixels
+    height_two: Option<u32>,
+
+    transfer_function: Option<TransferFunction<'a>>,
+
+    halftone_name: Option<String>,
+
+    /// The 16-bit threshold values of the first rectangle followed by those of
+    /// the second, each row by row from its top left
+    thresholds: Vec<u16>,
+}
+
+impl<'a> HalftoneStream<'a> {
+    pub fn from_stream(stream: Stream<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let data = decode_stream(&stream.stream, &stream.dict, resolver)?.into_owned();
+
+        let mut dict = stream.dict.other;
+
+        dict.expect_type("Halftone", resolver, false)?;
+
+        let halftone_type = dict.expect::<HalftoneType>("HalftoneType", resolver)?;
+
+        let transfer_function = dict.get("TransferFunction", resolver)?;
+        let halftone_name = dict.get("HalftoneName", resolver)?;
+
+        Ok(match halftone_type {
+            HalftoneType::Six => {
+                let width = dict.expect("Width", resolver)?;
+                let height = dict.expect("Height", resolver)?;
+
+                HalftoneStream::Six(HalftoneSix {
+                    width,
+                    height,
+                    transfer_function,
+                    halftone_name,
+                    thresholds: threshold_array(data, width.saturating_mul(height))?,
+                })
+            }
+            HalftoneType::Ten => {
+                let x_square: u32 = dict.expect("Xsquare", resolver)?;
+                let y_square: u32 = dict.expect("Ysquare", resolver)?;
+
+                let len = x_square
+                    .saturating_mul(x_square)
+                    .saturating_add(y_square.saturating_mul(y_square));
+
+                HalftoneStream::Ten(HalftoneTen {
+                    x_square,
+                    y_square,
+                    transfer_function,
+                    halftone_name,
+                    thresholds: threshold_array(data, len)?,
+                })
+            }
+            HalftoneType::Sixteen => {
+                let width: u32 = dict.expect("Width", resolver)?;
+                let height: u32 = dict.expect("Height", resolver)?;
+                let width_two: Option<u32> = dict.get("Width2", resolver)?;
+                let height_two: Option<u32> = dict.get("Height2", resolver)?;
+
+                anyhow::ensure!(
+                    width > 0 && height > 0,
+                    "invalid type 16 halftone dimensions {}x{}",
+                    width,
+                    height
+                );
+                anyhow::ensure!(
+                    width_two.is_some() == height_two.is_some(),
+                    "type 16 halftone must have both or neither of Width2 and Height2"
+                );
+
+                let len = width.saturating_mul(height).saturating_add(
+                    width_two
+                        .unwrap_or(0)
+                        .saturating_mul(height_two.unwrap_or(0)),
+                );
+
+                let thresholds = threshold_array(data, len.saturating_mul(2))?
+                    .chunks_exact(2)
+                    .map(|n| u16::from_be_bytes([n[0], n[1]]))
+                    .collect();
+
+                HalftoneStream::Sixteen(HalftoneSixteen {
+                    width,
+                    height,
+                    width_two,
+                    height_two,
+                    transfer_function,
+                    halftone_name,
+                    thresholds,
+                })
+            }
+            HalftoneType::One | HalftoneType::Five => {
+                anyhow::bail!(
+                    "type {} halftone must be a dictionary",
+                    halftone_type as u32
+                )
+            }
+        })
+    }
+}
+
+/// Check that the threshold array holds `len` bytes, ignoring any data
+/// following them
+fn threshold_array(mut data: Vec<u8>, len: u32) -> PdfResult<Vec<u8>> {
+    let len = len as usize;
+
+    anyhow::ensure!(
+        len > 0 && data.len() >= len,
+        "expected threshold array of {} bytes, found {}",
+        len,
+        data.len()
+    );
+
+    data.truncate(len);
+
+    Ok(data)
+}
+
+impl<'a> HalftoneDictionary<'a> {
+    pub fn from_dict(mut dict: Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        dict.expect_type("Halftone", resolver, false)?;
+
+        let halftone_type = dict.expect::<HalftoneType>("HalftoneType", resolver)?;
+
+        Ok(match halftone_type {
+            HalftoneType::One => {
+                HalftoneDictionary::One(HalftoneOne::from_obj(Object::Dictionary(dict), resolver)?)
+            }
+            HalftoneType::Five => {
+                HalftoneDictionary::Five(HalftoneFive::from_dict(dict, resolver)?)
+            }
+            HalftoneType::Six | HalftoneType::Ten | HalftoneType::Sixteen => {
+                anyhow::bail!("type {} halftone must be a stream", halftone_type as u32)
+            }
+        })
+    }
+}
@@ -0,0 +1,353 @@
+use std::rc::Rc;
+
+use crate::error::PdfResult;
+
+use super::{HalftoneDictionary, HalftoneOne, HalftoneStream, Halftones};
+
+/// The largest side of a halftone cell derived from a spot function, in device
+/// pixels
+const MAX_CELL_SIZE: u32 = 64;
+
+/// The order in which the pixels of an 8x8 cell are painted with full
+/// intensity, used as the device's default halftone
+#[rustfmt::skip]
+const BAYER: [u8; 64] = [
+     0, 32,  8, 40,  2, 34, 10, 42,
+    48, 16, 56, 24, 50, 18, 58, 26,
+    12, 44,  4, 36, 14, 46,  6, 38,
+    60, 28, 52, 20, 62, 30, 54, 22,
+     3, 35, 11, 43,  1, 33,  9, 41,
+    51, 19, 59, 27, 49, 17, 57, 25,
+    15, 47,  7, 39, 13, 45,  5, 37,
+    63, 31, 55, 23, 61, 29, 53, 21,
+];
+
+/// Threshold values in the range 0.0 to 1.0 covering device space, against
+/// which a colour component is compared at each device pixel
+#[derive(Debug)]
+enum ThresholdScreen {
+    /// A rectangle of thresholds repeated horizontally and vertically
+    Tile {
+        width: u32,
+        height: u32,
+        thresholds: Vec<f32>,
+    },
+
+    /// A halftone cell made up of square X, with square Y adjacent to the
+    /// right of its top edge, repeated so as to produce a nonzero screen angle
+    Angled {
+        x_square: u32,
+        y_square: u32,
+        thresholds: Vec<f32>,
+    },
+
+    /// A square cell whose thresholds are ordered by a spot function, rotated
+    /// by the screen angle
+    Rotated {
+        size: u32,
+        sin: f32,
+        cos: f32,
+        thresholds: Vec<f32>,
+    },
+}
+
+/// A threshold value from a threshold array, where a value of 0 is treated as 1
+fn normalize(value: u16, max: u16) -> f32 {
+    f32::from(value.max(1)) / f32::from(max)
+}
+
+impl ThresholdScreen {
+    /// The screen for a colour component, which, for type 5 halftones, is that
+    /// of the first of `colorants` with a halftone of its own
+    fn new(halftones: &Halftones, colorants: &[&str], resolution: f32) -> PdfResult<Self> {
+        Ok(match halftones {
+            Halftones::Default => ThresholdScreen::Tile {
+                width: 8,
+                height: 8,
+                thresholds: BAYER.iter().map(|&n| f32::from(n + 1) / 65.0).collect(),
+            },
+            Halftones::Dictionary(HalftoneDictionary::One(one)) => {
+                Self::from_spot_function(one, resolution)?
+            }
+            Halftones::Dictionary(HalftoneDictionary::Five(five)) => {
+                let halftones = colorants
+                    .iter()
+                    .find_map(|&colorant| five.colorants.get(colorant))
+                    .unwrap_or(&five.default);
+
+                // type 5 halftones may not be nested, so this recurses at most once
+                Self::new(halftones, colorants, resolution)?
+            }
+            Halftones::Stream(HalftoneStream::Six(six)) => ThresholdScreen::Tile {
+                width: six.width,
+                height: six.height,
+                thresholds: six
+                    .thresholds
+                    .iter()
+                    .map(|&n| normalize(u16::from(n), 0xff))
+                    .collect(),
+            },
+            Halftones::Stream(HalftoneStream::Ten(ten)) => ThresholdScreen::Angled {
+                x_square: ten.x_square,
+                y_square: ten.y_square,
+                thresholds: ten
+                    .thresholds
+                    .iter()
+                    .map(|&n| normalize(u16::from(n), 0xff))
+                    .collect(),
+            },
+            // todo: tile the optional second rectangle alongside the first
+            Halftones::Stream(HalftoneStream::Sixteen(sixteen)) => ThresholdScreen::Tile {
+                width: sixteen.width,
+                height: sixteen.height,
+                thresholds: sixteen.thresholds[..(sixteen.width * sixteen.height) as usize]
+                    .iter()
+                    .map(|&n| normalize(n, 0xffff))
+                    .collect(),
+            },
+        })
+    }
+
+    /// Order the pixels of a halftone cell by the value of the spot function at
+    /// their centres, with pixels of lower value painted with full intensity at
+    /// lower levels
+    fn from_spot_function(halftone: &HalftoneOne, resolution: f32) -> PdfResult<Self> {
+        anyhow::ensure!(
+            halftone.frequency > 0.0,
+            "invalid halftone screen frequency {}",
+            halftone.frequency
+        );
+
+        let size = (resolution / halftone.frequency)
+            .round()
+            .clamp(2.0, MAX_CELL_SIZE as f32) as u32;
+
+        let centre = |i: u32| (i as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+
+        let mut values = Vec::with_capacity((size * size) as usize);
+
+        // device space rows run from top to bottom, whereas the spot function's
+        // y coordinates increase upwards
+        for row in 0..size {
+            for col in 0..size {
+                values.push(halftone.spot_function.evaluate(centre(col), -centre(row))?);
+            }
+        }
+
+        let mut order = (0..values.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+
+        let mut thresholds = vec![0.0; values.len()];
+
+        for (rank, idx) in order.into_iter().enumerate() {
+            thresholds[idx] = (rank + 1) as f32 / (values.len() + 1) as f32;
+        }
+
+        let (sin, cos) = halftone.angle.to_radians().sin_cos();
+
+        Ok(ThresholdScreen::Rotated {
+            size,
+            sin,
+            cos,
+            thresholds,
+        })
+    }
+
+    fn threshold(&self, x: usize, y: usize) -> f32 {
+        match self {
+            ThresholdScreen::Tile {
+                width,
+                height,
+                thresholds,
+            } => {
+                let (width, height) = (*width as usize, *height as usize);
+
+                thresholds[(y % height) * width + x % width]
+            }
+            ThresholdScreen::Angled {
+                x_square,
+                y_square,
+                thresholds,
+            } => {
+                let (a, b) = (i64::from(*x_square), i64::from(*y_square));
+                let (x, y) = (x as i64, y as i64);
+
+                // cells repeat along the lattice vectors (a, b) and (-b, a), so
+                // find the cell containing the pixel starting from the nearest
+                // lattice point
+                let det = a * a + b * b;
+                let i = (x * a + y * b).div_euclid(det);
+                let j = (y * a - x * b).div_euclid(det);
+
+                for di in -2..=2 {
+                    for dj in -2..=2 {
+                        let (i, j) = (i + di, j + dj);
+
+                        let cell_x = x - (i * a - j * b);
+                        let cell_y = y - (i * b + j * a);
+
+                        if (0..a).contains(&cell_x) && (0..a).contains(&cell_y) {
+                            return thresholds[(cell_y * a + cell_x) as usize];
+                        }
+
+                        if (a..a + b).contains(&cell_x) && (0..b).contains(&cell_y) {
+                            return thresholds[(a * a + cell_y * b + cell_x - a) as usize];
+                        }
+                    }
+                }
+
+                unreachable!("halftone cells cover device space")
+            }
+            ThresholdScreen::Rotated {
+                size,
+                sin,
+                cos,
+                thresholds,
+            } => {
+                let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+
+                let size = *size as usize;
+                let col = (x * cos + y * sin).rem_euclid(size as f32) as usize;
+                let row = (y * cos - x * sin).rem_euclid(size as f32) as usize;
+
+                thresholds[row.min(size - 1) * size + col.min(size - 1)]
+            }
+        }
+    }
+}
+
+/// The halftone screens with which the red, green and blue components of each
+/// pixel are rendered, approximating the output of a device which can only
+/// paint each component with either no or full intensity
+#[derive(Debug)]
+pub(crate) struct HalftoneScreen {
+    channels: [Rc<ThresholdScreen>; 3],
+}
+
+impl HalftoneScreen {
+    /// The screens for a device of `resolution` pixels per inch
+    pub fn new(halftones: &Halftones, resolution: f32) -> PdfResult<Self> {
+        let channels = match halftones {
+            // subtractive colorants are used for the additive components they
+            // absorb
+            Halftones::Dictionary(HalftoneDictionary::Five(..)) => [
+                Rc::new(ThresholdScreen::new(
+                    halftones,
+                    &["Red", "Cyan", "Gray"],
+                    resolution,
+                )?),
+                Rc::new(ThresholdScreen::new(
+                    halftones,
+                    &["Green", "Magenta", "Gray"],
+                    resolution,
+                )?),
+                Rc::new(ThresholdScreen::new(
+                    halftones,
+                    &["Blue", "Yellow", "Gray"],
+                    resolution,
+                )?),
+            ],
+            _ => {
+                let screen = Rc::new(ThresholdScreen::new(halftones, &[], resolution)?);
+
+                [Rc::clone(&screen), Rc::clone(&screen), screen]
+            }
+        };
+
+        Ok(Self { channels })
+    }
+
+    /// Screen the colour of the device pixel at (`x`, `y`), counted from the
+    /// top left, painting each component with full intensity if its level is
+    /// at least the threshold at that pixel
+    pub fn apply(&self, x: usize, y: usize, color: u32) -> u32 {
+        let mut screened = color & 0xff_00_00_00;
+
+        for (i, screen) in self.channels.iter().enumerate() {
+            let level = ((color >> (8 * i)) & 0xff) as f32 / 255.0;
+
+            if level >= screen.threshold(x, y) {
+                screened |= 0xff << (8 * i);
+            }
+        }
+
+        screened
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{
+        error::PdfResult,
+        halftones::Halftones,
+        objects::{Dictionary, Object},
+        stream::Stream,
+        version::PdfVersion,
+        writer::PdfWriter,
+        FromObj,
+    };
+
+    use super::HalftoneScreen;
+
+    fn halftone_stream(
+        entries: Vec<(&str, i32)>,
+        thresholds: Vec<u8>,
+    ) -> PdfResult<Halftones<'static>> {
+        let dict = entries
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), Object::Integer(value)))
+            .collect::<HashMap<_, _>>();
+
+        Halftones::from_obj(
+            Object::Stream(Stream::new(thresholds, Dictionary::new(dict))),
+            &mut PdfWriter::new(PdfVersion::V1_7),
+        )
+    }
+
+    #[test]
+    fn threshold_array_screens() {
+        let gray = 0xff_80_80_80;
+
+        let halftones = halftone_stream(
+            vec![("HalftoneType", 6), ("Width", 2), ("Height", 2)],
+            vec![64, 128, 192, 0],
+        )
+        .unwrap();
+        let screen = HalftoneScreen::new(&halftones, 72.0).unwrap();
+
+        assert_eq!(screen.apply(0, 0, gray), 0xff_ff_ff_ff);
+        assert_eq!(screen.apply(1, 0, gray), 0xff_ff_ff_ff);
+        assert_eq!(screen.apply(0, 1, gray), 0xff_00_00_00);
+        assert_eq!(screen.apply(1, 1, gray), 0xff_ff_ff_ff);
+
+        // the threshold array repeats across device space
+        assert_eq!(screen.apply(2, 3, gray), 0xff_00_00_00);
+
+        // square X is 2x2 and square Y is 1x1
+        let halftones = halftone_stream(
+            vec![("HalftoneType", 10), ("Xsquare", 2), ("Ysquare", 1)],
+            vec![255, 255, 255, 255, 1],
+        )
+        .unwrap();
+        let screen = HalftoneScreen::new(&halftones, 72.0).unwrap();
+
+        let painted = (0..5).map(|x| screen.apply(x, 0, gray)).collect::<Vec<_>>();
+
+        assert_eq!(
+            painted,
+            [
+                0xff_00_00_00,
+                0xff_00_00_00,
+                0xff_ff_ff_ff,
+                0xff_00_00_00,
+                0xff_00_00_00
+            ]
+        );
+
+        // the threshold array is too short
+        let entries = vec![("HalftoneType", 6), ("Width", 3), ("Height", 3)];
+        assert!(halftone_stream(entries, vec![0; 4]).is_err());
+    }
+}
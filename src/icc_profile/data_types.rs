@@ -1,8 +1,8 @@
-use fixed::types::extra::{U1, U15, U8};
+use fixed::types::extra::{U15, U16, U8};
 
-pub(super) type F15Dot16 = fixed::FixedI32<U15>;
-pub(super) type F16Dot16 = fixed::FixedU32<U15>;
-pub(super) type F1Dot15 = fixed::FixedI16<U1>;
+pub(super) type F15Dot16 = fixed::FixedI32<U16>;
+pub(super) type F16Dot16 = fixed::FixedU32<U16>;
+pub(super) type F1Dot15 = fixed::FixedU16<U15>;
 pub(super) type F8Dot8 = fixed::FixedU16<U8>;
 
 #[derive(Debug)]
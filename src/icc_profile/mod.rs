@@ -14,17 +14,25 @@ Additionally:
 
 use std::fmt::{self, Write};
 
-use crate::{date::Date, error::PdfResult};
+use crate::{date::Date, error::PdfResult, resources::graphics_state_parameters::RenderingIntent};
 
-use self::{data_types::XyzNumber, parse::IccProfileParser};
+use self::{data_types::XyzNumber, parse::IccProfileParser, transform::IccTransform};
+
+pub(crate) use self::transform::{xyz_to_srgb, D65};
 
 mod data_types;
 mod parse;
+mod transform;
 
 #[derive(Debug)]
 pub struct IccProfile {
     pub header: IccProfileHeader,
     pub tag_table: IccTagTable,
+
+    /// The conversion to the profile connection space, if this is a monochrome
+    /// or matrix/TRC profile. Profiles which rely on lookup tables are not
+    /// supported
+    transform: Option<IccTransform>,
 }
 
 impl IccProfile {
@@ -33,6 +41,55 @@ impl IccProfile {
 
         parser.parse()
     }
+
+    /// The number of components of the colour space of the profile, if it is
+    /// known
+    pub fn components(&self) -> Option<usize> {
+        Some(match &self.header.colour_space.0 {
+            b"GRAY" => 1,
+            b"2CLR" => 2,
+            b"RGB " | b"XYZ " | b"Lab " | b"Luv " | b"YCbr" | b"Yxy " | b"HSV " | b"HLS "
+            | b"CMY " | b"3CLR" => 3,
+            b"CMYK" | b"4CLR" => 4,
+            _ => return None,
+        })
+    }
+
+    /// The rendering intent given by the header of the profile
+    pub fn rendering_intent(&self) -> Option<RenderingIntent> {
+        Some(match self.header.rendering_intent {
+            0 => RenderingIntent::Perceptual,
+            1 => RenderingIntent::RelativeColorimetric,
+            2 => RenderingIntent::Saturation,
+            3 => RenderingIntent::AbsoluteColorimetric,
+            _ => return None,
+        })
+    }
+
+    /// Whether the profile has a tag for the conversion of colours with the
+    /// given rendering intent
+    ///
+    /// The colorimetric intents share the `A2B1` tag. Monochrome and
+    /// matrix/TRC profiles have the same conversion for every intent.
+    pub fn has_intent(&self, intent: RenderingIntent) -> bool {
+        let signature = match intent {
+            RenderingIntent::Perceptual => b"A2B0",
+            RenderingIntent::RelativeColorimetric | RenderingIntent::AbsoluteColorimetric => {
+                b"A2B1"
+            }
+            RenderingIntent::Saturation => b"A2B2",
+        };
+
+        self.transform.is_some() || self.tag_table.get(signature).is_some()
+    }
+
+    /// Convert the components of a colour to sRGB, each in the range 0.0 to
+    /// 1.0, or `None` if the profile is not one which is supported
+    pub fn to_rgb(&self, components: &[f32]) -> Option<[f32; 3]> {
+        self.transform
+            .as_ref()
+            .map(|transform| transform.to_srgb(components))
+    }
 }
 
 #[derive(Debug)]
@@ -79,6 +136,15 @@ pub struct IccTagTable {
     entries: Vec<TagTableEntry>,
 }
 
+impl IccTagTable {
+    pub(super) fn get(&self, signature: &[u8; 4]) -> Option<TagTableEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.signature == u32::from_be_bytes(*signature))
+            .copied()
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub(super) struct TagTableEntry {
     pub(super) signature: u32,
@@ -107,3 +173,104 @@ impl fmt::Debug for IccTagSignature {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn profile(colour_space: &[u8; 4], tags: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut header = vec![0; 128];
+        header[12..16].copy_from_slice(b"mntr");
+        header[16..20].copy_from_slice(colour_space);
+        header[20..24].copy_from_slice(b"XYZ ");
+        header[36..40].copy_from_slice(b"acsp");
+
+        let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+        let mut data = Vec::new();
+        let mut offset = 128 + 4 + 12 * tags.len();
+
+        for (signature, tag) in tags {
+            table.extend_from_slice(*signature);
+            table.extend_from_slice(&(offset as u32).to_be_bytes());
+            table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+            data.extend_from_slice(tag);
+            offset += tag.len();
+        }
+
+        let mut profile = [header, table, data].concat();
+        let len = (profile.len() as u32).to_be_bytes();
+        profile[..4].copy_from_slice(&len);
+
+        profile
+    }
+
+    fn fixed(values: &[f32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|v| ((v * 65536.0).round() as i32).to_be_bytes())
+            .collect()
+    }
+
+    fn xyz(x: f32, y: f32, z: f32) -> Vec<u8> {
+        [b"XYZ \0\0\0\0".to_vec(), fixed(&[x, y, z])].concat()
+    }
+
+    fn assert_close(found: [f32; 3], expected: [f32; 3]) {
+        for (found, expected) in found.into_iter().zip(expected) {
+            assert!(
+                (found - expected).abs() < 0.01,
+                "{:?} != {:?}",
+                found,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn matrix_trc_profiles() {
+        // the sRGB transfer function, as a parametric curve of type 3
+        let srgb_curve = [
+            b"para\0\0\0\0\0\x03\0\0".to_vec(),
+            fixed(&[2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045]),
+        ]
+        .concat();
+
+        let buffer = profile(
+            b"RGB ",
+            &[
+                (b"rXYZ", xyz(0.436_075, 0.222_504, 0.013_932)),
+                (b"gXYZ", xyz(0.385_065, 0.716_879, 0.097_105)),
+                (b"bXYZ", xyz(0.143_080, 0.060_617, 0.714_173)),
+                (b"rTRC", srgb_curve.clone()),
+                (b"gTRC", srgb_curve.clone()),
+                (b"bTRC", srgb_curve),
+            ],
+        );
+
+        let rgb = IccProfile::new(&buffer).unwrap();
+
+        assert_eq!(rgb.components(), Some(3));
+        assert_eq!(rgb.rendering_intent(), Some(RenderingIntent::Perceptual));
+        assert!(rgb.has_intent(RenderingIntent::Saturation));
+        assert_close(rgb.to_rgb(&[1.0, 1.0, 1.0]).unwrap(), [1.0, 1.0, 1.0]);
+        assert_close(rgb.to_rgb(&[1.0, 0.0, 0.0]).unwrap(), [1.0, 0.0, 0.0]);
+        assert_close(rgb.to_rgb(&[0.2, 0.5, 0.8]).unwrap(), [0.2, 0.5, 0.8]);
+
+        // a gamma of 2.2, stored as a u8Fixed8Number
+        let buffer = profile(
+            b"GRAY",
+            &[(b"kTRC", b"curv\0\0\0\0\0\0\0\x01\x02\x33".to_vec())],
+        );
+        let gray = IccProfile::new(&buffer).unwrap();
+
+        assert_close(gray.to_rgb(&[0.5]).unwrap(), [0.5; 3]);
+        assert_close(gray.to_rgb(&[0.0]).unwrap(), [0.0; 3]);
+
+        // lookup table profiles fall back to the alternate colour space
+        let cmyk = IccProfile::new(&profile(b"CMYK", &[])).unwrap();
+
+        assert_eq!(cmyk.components(), Some(4));
+        assert_eq!(cmyk.to_rgb(&[0.0, 0.0, 0.0, 1.0]), None);
+        assert!(!cmyk.has_intent(RenderingIntent::Perceptual));
+    }
+}
@@ -5,7 +5,8 @@ use crate::{
 };
 
 use super::{
-    data_types::{F15Dot16, XyzNumber},
+    data_types::{F15Dot16, F8Dot8, XyzNumber},
+    transform::{IccTransform, ToneCurve},
     IccProfile, IccTagTable, TagTableEntry,
 };
 
@@ -94,7 +95,10 @@ impl<'a> IccProfileParser<'a> {
     }
 
     fn get_byte_range(&mut self, len: usize) -> anyhow::Result<&[u8]> {
-        let buffer = &self.buffer[self.cursor..(self.cursor + len)];
+        let buffer = self
+            .buffer
+            .get(self.cursor..(self.cursor + len))
+            .ok_or(anyhow::anyhow!("unexpected eof"))?;
         self.cursor += len;
         Ok(buffer)
     }
@@ -110,8 +114,61 @@ impl<'a> IccProfileParser<'a> {
     pub fn parse(&mut self) -> PdfResult<IccProfile> {
         let header = self.parse_header()?;
         let tag_table: IccTagTable = self.parse_tag_table()?;
+        let transform = self.parse_transform(&header, &tag_table)?;
 
-        Ok(IccProfile { header, tag_table })
+        Ok(IccProfile {
+            header,
+            tag_table,
+            transform,
+        })
+    }
+
+    /// Build the conversion of a monochrome or matrix/TRC profile, if it has
+    /// all of the tags needed for one
+    fn parse_transform(
+        &mut self,
+        header: &IccProfileHeader,
+        tag_table: &IccTagTable,
+    ) -> PdfResult<Option<IccTransform>> {
+        // colorant tags are only meaningful in the XYZ connection space
+        if header.profile_connection_space.0 != *b"XYZ " {
+            return Ok(None);
+        }
+
+        match &header.colour_space.0 {
+            b"GRAY" => match tag_table.get(b"kTRC") {
+                Some(entry) => Ok(Some(IccTransform::Gray(self.parse_tone_curve(entry)?))),
+                None => Ok(None),
+            },
+            b"RGB " => {
+                let entries = [b"rTRC", b"gTRC", b"bTRC", b"rXYZ", b"gXYZ", b"bXYZ"]
+                    .map(|signature| tag_table.get(signature))
+                    .into_iter()
+                    .collect::<Option<Vec<_>>>();
+
+                let entries = match entries {
+                    Some(entries) => entries,
+                    None => return Ok(None),
+                };
+
+                let curves = [
+                    self.parse_tone_curve(entries[0])?,
+                    self.parse_tone_curve(entries[1])?,
+                    self.parse_tone_curve(entries[2])?,
+                ];
+
+                let columns = [
+                    self.parse_colorant(entries[3])?,
+                    self.parse_colorant(entries[4])?,
+                    self.parse_colorant(entries[5])?,
+                ];
+
+                let matrix = [0, 1, 2].map(|row| columns.map(|column| column[row]));
+
+                Ok(Some(IccTransform::MatrixTrc { curves, matrix }))
+            }
+            _ => Ok(None),
+        }
     }
 
     fn parse_f15dot16(&mut self) -> PdfResult<F15Dot16> {
@@ -266,6 +323,55 @@ impl<'a> IccProfileParser<'a> {
         Ok(CurveTag { reserved, values })
     }
 
+    /// The single XYZ value of a colorant tag
+    fn parse_colorant(&mut self, entry: TagTableEntry) -> PdfResult<[f32; 3]> {
+        let tag = self.parse_xyz_tag(entry)?;
+
+        match tag.values.first() {
+            Some(xyz) => Ok([xyz.cie_x.to_num(), xyz.cie_y.to_num(), xyz.cie_z.to_num()]),
+            None => anyhow::bail!("empty XYZ colorant tag"),
+        }
+    }
+
+    /// A tone reproduction curve, which may be either a `curv` or a `para` tag
+    fn parse_tone_curve(&mut self, entry: TagTableEntry) -> PdfResult<ToneCurve> {
+        self.cursor = entry.offset as usize;
+
+        match &self.parse_array::<4>()? {
+            b"curv" => {
+                let tag = self.parse_curve_tag(entry)?;
+
+                Ok(match tag.values.as_slice() {
+                    [] => ToneCurve::Identity,
+                    &[gamma] => ToneCurve::Gamma(F8Dot8::from_bits(gamma).to_num()),
+                    _ => ToneCurve::Table(tag.values),
+                })
+            }
+            b"para" => {
+                let _reserved = self.parse_array::<4>()?;
+                let function_type = self.parse_u16()?;
+                let _reserved = self.parse_array::<2>()?;
+
+                let num_of_params = match function_type {
+                    0 => 1,
+                    1 => 3,
+                    2 => 4,
+                    3 => 5,
+                    4 => 7,
+                    _ => anyhow::bail!("unknown parametric curve function type {}", function_type),
+                };
+
+                let mut params = Vec::with_capacity(num_of_params);
+                for _ in 0..num_of_params {
+                    params.push(self.parse_f15dot16()?.to_num::<f32>());
+                }
+
+                Ok(ToneCurve::parametric(function_type, &params))
+            }
+            found => anyhow::bail!("expected tone curve, found {:?}", IccTagSignature(*found)),
+        }
+    }
+
     fn parse_signature_tag(&mut self, entry: TagTableEntry) -> PdfResult<SignatureTag> {
         self.cursor = entry.offset as usize;
         self.expect_tag(IccTagSignature(*b"sig "))?;
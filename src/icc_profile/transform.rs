@@ -0,0 +1,164 @@
+/// The white point of the profile connection space
+const D50: [f32; 3] = [0.9642, 1.0, 0.8249];
+
+/// The white point of sRGB
+pub(crate) const D65: [f32; 3] = [0.950_47, 1.0, 1.088_83];
+
+/// Converts XYZ to the cone responses in which white points are adapted by
+/// the Bradford transform
+const BRADFORD: [[f32; 3]; 3] = [
+    [0.8951, 0.2664, -0.1614],
+    [-0.7502, 1.7135, 0.0367],
+    [0.0389, -0.0685, 1.0296],
+];
+
+const INVERSE_BRADFORD: [[f32; 3]; 3] = [
+    [0.986_993, -0.147_054, 0.159_963],
+    [0.432_305, 0.518_360, 0.049_291],
+    [-0.008_529, 0.040_043, 0.968_487],
+];
+
+/// Converts linear XYZ, relative to D65, to linear sRGB
+const XYZ_TO_LINEAR_SRGB: [[f32; 3]; 3] = [
+    [3.240_454, -1.537_139, -0.498_531],
+    [-0.969_266, 1.876_011, 0.041_556],
+    [0.055_643, -0.204_026, 1.057_225],
+];
+
+/// The function by which a device component is mapped to a linear value, as
+/// described by a `curv` or `para` tag
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum ToneCurve {
+    /// A `curv` tag with no entries
+    Identity,
+
+    /// A `curv` tag with a single entry, or a `para` tag of function type 0
+    Gamma(f32),
+
+    /// A `curv` tag with more than one entry, sampled at equal intervals over
+    /// the domain 0.0 to 1.0, between which values are interpolated linearly
+    Table(Vec<u16>),
+
+    /// A `para` tag of function types 1 to 4, each of which is expressed as
+    /// `[g, a, b, c, d, e, f]`
+    Parametric([f32; 7]),
+}
+
+impl ToneCurve {
+    /// Build the parametric curve of `function_type` from its parameters,
+    /// filling in the remaining ones such that the general form of type 4
+    /// gives the same result
+    pub(super) fn parametric(function_type: u16, params: &[f32]) -> Self {
+        let param = |idx: usize| params.get(idx).copied().unwrap_or(0.0);
+
+        let (g, a, b) = (param(0), param(1), param(2));
+
+        match function_type {
+            0 => ToneCurve::Gamma(g),
+            // Y = (aX + b)^g for X >= -b/a, otherwise 0
+            1 => ToneCurve::Parametric([g, a, b, 0.0, -b / a, 0.0, 0.0]),
+            // Y = (aX + b)^g + c for X >= -b/a, otherwise c
+            2 => ToneCurve::Parametric([g, a, b, 0.0, -b / a, param(3), param(3)]),
+            // Y = (aX + b)^g for X >= d, otherwise cX
+            3 => ToneCurve::Parametric([g, a, b, param(3), param(4), 0.0, 0.0]),
+            // Y = (aX + b)^g + e for X >= d, otherwise cX + f
+            _ => ToneCurve::Parametric([g, a, b, param(3), param(4), param(5), param(6)]),
+        }
+    }
+
+    pub(super) fn apply(&self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+
+        match self {
+            ToneCurve::Identity => x,
+            ToneCurve::Gamma(gamma) => x.powf(*gamma),
+            ToneCurve::Table(table) => {
+                let pos = x * (table.len() - 1) as f32;
+                let idx = (pos.floor() as usize).min(table.len() - 2);
+                let fract = pos - idx as f32;
+
+                let low = f32::from(table[idx]);
+                let high = f32::from(table[idx + 1]);
+
+                (low + (high - low) * fract) / f32::from(u16::MAX)
+            }
+            &ToneCurve::Parametric([g, a, b, c, d, e, f]) => {
+                if x >= d {
+                    (a * x + b).max(0.0).powf(g) + e
+                } else {
+                    c * x + f
+                }
+            }
+        }
+    }
+}
+
+/// The conversion from device components to the profile connection space
+/// which can be built from the tags of monochrome and three-component
+/// matrix/TRC profiles
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum IccTransform {
+    /// The `kTRC` tag, which gives the luminance of a gray component
+    Gray(ToneCurve),
+
+    /// The `rTRC`, `gTRC` and `bTRC` tags, and the `rXYZ`, `gXYZ` and `bXYZ`
+    /// colorant tags, which form the columns of `matrix`
+    MatrixTrc {
+        curves: [ToneCurve; 3],
+        matrix: [[f32; 3]; 3],
+    },
+}
+
+impl IccTransform {
+    /// Convert device components to sRGB, each in the range 0.0 to 1.0
+    pub(super) fn to_srgb(&self, components: &[f32]) -> [f32; 3] {
+        let component = |idx: usize| components.get(idx).copied().unwrap_or(0.0);
+
+        match self {
+            IccTransform::Gray(curve) => {
+                // luminance is unaffected by chromatic adaptation, so gray
+                // is gray in either white point
+                [encode_srgb(curve.apply(component(0)).clamp(0.0, 1.0)); 3]
+            }
+            IccTransform::MatrixTrc { curves, matrix } => {
+                let linear = [
+                    curves[0].apply(component(0)),
+                    curves[1].apply(component(1)),
+                    curves[2].apply(component(2)),
+                ];
+
+                xyz_to_srgb(mul(matrix, linear), D50)
+            }
+        }
+    }
+}
+
+/// Convert a colour in XYZ, whose white is `white_point`, to sRGB, each
+/// component of which is in the range 0.0 to 1.0
+///
+/// The colour is adapted to the white point of sRGB, such that the white of
+/// the source is displayed as white.
+pub(crate) fn xyz_to_srgb(xyz: [f32; 3], white_point: [f32; 3]) -> [f32; 3] {
+    let source = mul(&BRADFORD, white_point);
+    let destination = mul(&BRADFORD, D65);
+
+    let cone = mul(&BRADFORD, xyz);
+    let adapted = [0, 1, 2].map(|idx| cone[idx] * destination[idx] / source[idx]);
+
+    let linear = mul(&XYZ_TO_LINEAR_SRGB, mul(&INVERSE_BRADFORD, adapted));
+
+    linear.map(|c| encode_srgb(c.clamp(0.0, 1.0)))
+}
+
+fn mul(matrix: &[[f32; 3]; 3], vector: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+}
+
+/// Apply the transfer function of sRGB to a linear value
+fn encode_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
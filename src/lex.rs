@@ -304,9 +304,50 @@ pub(crate) trait LexBase<'a> {
     }
 }
 
+/// The original bytes of the numbers and strings of each indirect object
+///
+/// Parsing loses distinctions such as `1.0` versus `1.00`, or literal versus
+/// hexadecimal strings. Keeping the original bytes allows an object which is
+/// rewritten to reproduce the values it did not change byte-for-byte, which
+/// keeps incremental updates minimal.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LexicalForms {
+    forms: HashMap<Reference, Vec<(Object<'static>, Vec<u8>)>>,
+}
+
+impl LexicalForms {
+    /// Record the bytes of a number or string within the object `reference`.
+    /// Only the first lexical form of each value is kept
+    pub fn record(&mut self, reference: Reference, obj: &Object, bytes: &[u8]) {
+        let value = match obj {
+            Object::Integer(n) => Object::Integer(*n),
+            Object::Real(n) => Object::Real(*n),
+            Object::String(s) => Object::String(s.clone()),
+            _ => return,
+        };
+
+        let forms = self.forms.entry(reference).or_default();
+
+        if !forms.iter().any(|(v, _)| *v == value) {
+            forms.push((value, bytes.to_vec()));
+        }
+    }
+
+    /// The original bytes of `obj` within the object `reference`, if it was
+    /// lexed from the file with this value
+    pub fn get(&self, reference: Reference, obj: &Object) -> Option<&[u8]> {
+        self.forms
+            .get(&reference)?
+            .iter()
+            .find(|(value, _)| value == obj)
+            .map(|(_, bytes)| bytes.as_slice())
+    }
+}
+
 pub(crate) trait LexObject<'a>: LexBase<'a> {
     fn lex_object(&mut self) -> PdfResult<Object<'a>> {
         self.skip_whitespace();
+        let start = self.cursor();
         let obj = match self.peek_byte() {
             Some(b't') => self.lex_true(),
             Some(b'f') => self.lex_false(),
@@ -316,17 +357,29 @@ pub(crate) trait LexObject<'a>: LexBase<'a> {
             Some(b'(') => Ok(Object::String(self.lex_string()?)),
             Some(b'/') => Ok(Object::Name(self.lex_name()?)),
             Some(b'[') => self.lex_array(),
-            Some(b) => todo!(
+            Some(b) => anyhow::bail!(
                 "unexpected object start {:?} at line {}",
                 b as char,
                 self.line_number()
             ),
-            None => todo!(),
+            None => anyhow::bail!(ParseError::UnexpectedEof),
         }?;
+
+        if matches!(
+            obj,
+            Object::Integer(..) | Object::Real(..) | Object::String(..)
+        ) {
+            self.record_lexical_form(&obj, start);
+        }
+
         self.skip_whitespace();
         Ok(obj)
     }
 
+    /// Called after lexing each number or string, which began at `start` and
+    /// ends at the cursor, so that implementors may keep its original bytes
+    fn record_lexical_form(&mut self, _obj: &Object<'a>, _start: usize) {}
+
     /// Assumes leading 't' has not been consumed
     fn lex_true(&mut self) -> PdfResult<Object<'a>> {
         self.expect_bytes(b"true")?;
@@ -358,8 +411,8 @@ pub(crate) trait LexObject<'a>: LexBase<'a> {
                 self.next_byte();
                 Ok(Object::String(String::new()))
             }
-            Some(b) => todo!("{}", b),
-            None => todo!(),
+            Some(b) => anyhow::bail!("unexpected byte {:?} after '<'", b as char),
+            None => anyhow::bail!(ParseError::UnexpectedEof),
         }
     }
 
@@ -369,6 +422,7 @@ pub(crate) trait LexObject<'a>: LexBase<'a> {
         self.skip_whitespace();
 
         let mut dict = HashMap::new();
+        let mut key_order = Vec::new();
 
         while let Some(b) = self.peek_byte() {
             if b == b'>' {
@@ -380,14 +434,27 @@ pub(crate) trait LexObject<'a>: LexBase<'a> {
             let name = self.lex_name()?;
             let value = self.lex_object()?;
             self.skip_whitespace();
-            dict.insert(name, value);
+
+            // a repeated key keeps the position of its first occurrence
+            if dict.insert(name.clone(), value).is_none() && self.preserve_key_order() {
+                key_order.push(name);
+            }
         }
 
         self.skip_whitespace();
 
+        if self.preserve_key_order() {
+            return Ok(Dictionary::with_key_order(dict, key_order));
+        }
+
         Ok(Dictionary::new(dict))
     }
 
+    /// Whether dictionaries should remember the original order of their keys
+    fn preserve_key_order(&self) -> bool {
+        false
+    }
+
     fn lex_dict(&mut self) -> PdfResult<Object<'a>>;
 
     // utf-16 <FEFF0043006F006C006C00610062006F007200610020004F0066006600690063006500200036002E0034>
@@ -489,10 +556,12 @@ pub(crate) trait LexObject<'a>: LexBase<'a> {
                     generation: generation.parse::<usize>()?,
                 }));
             }
-
-            *self.cursor_mut() = whole_end_pos;
         }
 
+        // the whitespace skipped while looking for a reference is not part of
+        // the number
+        *self.cursor_mut() = whole_end_pos;
+
         Ok(Object::Integer(whole_number.parse::<i32>()? * negative))
     }
 
@@ -541,16 +610,21 @@ pub(crate) trait LexObject<'a>: LexBase<'a> {
         }
     }
 
-    fn read_obj_prelude(&mut self) -> PdfResult<()> {
+    /// Read the object number, generation number, and `obj` keyword which
+    /// begin an indirect object
+    fn read_obj_prelude(&mut self) -> PdfResult<Reference> {
         self.skip_whitespace();
-        self.lex_whole_number();
+        let object_number = self.lex_whole_number().parse()?;
         self.skip_whitespace();
-        self.lex_whole_number();
+        let generation = self.lex_whole_number().parse()?;
         self.skip_whitespace();
         self.expect_bytes(b"obj")?;
         self.skip_whitespace();
 
-        Ok(())
+        Ok(Reference {
+            object_number,
+            generation,
+        })
     }
 
     fn read_obj_trailer(&mut self) -> PdfResult<()> {
@@ -565,9 +639,13 @@ pub(crate) trait LexObject<'a>: LexBase<'a> {
 mod test {
     use std::{collections::HashMap, rc::Rc};
 
-    use crate::{objects::Object, xref::Xref, Lexer};
+    use crate::{
+        objects::{Object, Reference},
+        xref::Xref,
+        Lexer,
+    };
 
-    use super::LexObject;
+    use super::{LexObject, LexicalForms};
 
     macro_rules! lex_obj {
         ($input:expr, $output:expr) => {
@@ -669,4 +747,35 @@ mod test {
             Object::String("abc".to_owned())
         );
     }
+
+    #[test]
+    fn preserves_lexical_form() {
+        let reference = Reference {
+            object_number: 1,
+            generation: 0,
+        };
+
+        let mut lexer = Lexer::new(
+            b"[1.00 +5 <414243> (ABC)]".to_vec(),
+            Rc::new(Xref {
+                objects: HashMap::new(),
+            }),
+        )
+        .unwrap();
+
+        lexer.lexical_forms = Some(LexicalForms::default());
+        lexer.current_reference = Some(reference);
+
+        lexer.lex_object().unwrap();
+
+        let forms = lexer.lexical_forms.unwrap();
+
+        assert_eq!(forms.get(reference, &Object::Real(1.0)), Some(&b"1.00"[..]));
+        assert_eq!(forms.get(reference, &Object::Integer(5)), Some(&b"+5"[..]));
+        assert_eq!(
+            forms.get(reference, &Object::String("ABC".to_owned())),
+            Some(&b"<414243>"[..])
+        );
+        assert_eq!(forms.get(reference, &Object::Integer(1)), None);
+    }
 }
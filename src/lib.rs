@@ -15,7 +15,12 @@ extern crate pdf_macro;
 mod acro_form;
 mod actions;
 mod annotation;
+mod attachment;
+mod batch;
+#[cfg(feature = "bench")]
+pub mod bench;
 mod catalog;
+mod collection;
 mod color;
 pub mod content;
 mod data_structures;
@@ -32,44 +37,131 @@ mod halftones;
 mod icc_profile;
 mod job_ticket;
 mod lex;
+mod measure;
 mod object_stream;
 pub mod objects;
 mod optional_content;
+mod outline;
 pub mod page;
+mod page_labels;
 mod parse_binary;
+mod pipeline;
 mod postscript;
+mod preflight;
+mod presentation;
 mod render;
 mod resolve;
 mod resources;
 mod shading;
+mod signature;
 mod stream;
 mod structure;
+mod thread;
+mod tokenizer;
 mod trailer;
+mod transition;
+mod version;
 mod viewer_preferences;
+mod writer;
+mod xml;
+mod xmp;
 mod xobject;
 mod xref;
 
-use std::{borrow::Cow, cell::RefCell, collections::HashMap, io, rc::Rc};
+use std::{borrow::Cow, cell::RefCell, collections::HashMap, io, ops::Range, rc::Rc};
 
 use encryption::SecurityHandler;
 
-pub(crate) use crate::{objects::FromObj, resolve::Resolve};
+pub(crate) use crate::{
+    objects::{FromObj, ToObj},
+    resolve::Resolve,
+};
 
 use crate::{
     annotation::Annotation,
     catalog::{DocumentCatalog, InformationDictionary},
     error::ParseError,
     filter::decode_stream,
-    lex::{LexBase, LexObject},
+    lex::{LexBase, LexObject, LexicalForms},
     object_stream::{ObjectStream, ObjectStreamDict, ObjectStreamParser},
-    objects::{Dictionary, Object, Reference},
+    objects::{Dictionary, Object, Reference, TypedReference},
     page::{InheritablePageFields, PageNode, PageObject, PageTree, PageTreeNode},
     stream::StreamDict,
     trailer::Trailer,
     xref::{ByteOffset, TrailerOrOffset, Xref, XrefParser},
 };
 
-pub use crate::{content::ContentLexer, error::PdfResult, render::Renderer};
+pub use crate::{
+    acro_form::{
+        Fdf, FdfField, Field, FieldType, FieldValue, FormFill, Xfa, XfaPacket, Xfdf, XfdfAnnotation,
+    },
+    actions::{
+        Action, ActionTarget, ActionType, Actions, EmbeddedTarget, GoTo3DViewAction, GoToAction,
+        GoToEmbeddedAction, GoToRemoteAction, HideAction, ImportDataAction, JavaScriptAction,
+        LaunchAction, MovieAction, MovieOperation, NamedAction, OcgStateChange, PageAction,
+        PageEvent, RenditionAction, ResetFormAction, Script, ScriptLocation, SetOcgStateAction,
+        SoundAction, SubmitFormAction, TargetRelationship, ThreadAction, TransitionAction,
+        TriggerEvent, UriAction, WindowsLaunchParameters,
+    },
+    annotation::{
+        Comment, Link, LinkTarget, PrepressMark, PrinterMark, TextMarkup, TextMarkupKind,
+        TrapNetwork,
+    },
+    attachment::Attachment,
+    batch::MetadataEdit,
+    catalog::{
+        DeveloperExtensions, Extensions, LegalAttestation, PermissionsDictionary, Requirement,
+        RequirementHandler, RequirementHandlerType,
+    },
+    collection::{
+        Collection, CollectionField, CollectionFieldType, CollectionItemValue, CollectionNavigator,
+        CollectionValue, CollectionView, PortfolioMember,
+    },
+    color::{
+        ColorSeparation, SeparationInfo, SpotColorConversion, SpotColorLocation, SpotColorUsage,
+        SpotColorant,
+    },
+    content::{ContentDiagnostic, ContentLexer, InlineImage, MarkedContentSequence, PropertyList},
+    data_structures::Rectangle,
+    date::Date,
+    encryption::{
+        AuthEvent, CryptFilter, CryptFilterMap, CryptFilterMethod, EncryptionOptions, PasswordKind,
+        Permissions,
+    },
+    error::PdfResult,
+    font::MissingFont,
+    measure::{
+        CoordinateSystem, CoordinateSystemKind, FractionFormat, GeoPoint, GeospatialMeasure,
+        LabelPosition, Measure, NumberFormat, PageViewport, RectilinearMeasure, Viewport,
+    },
+    optional_content::{ExportedLayer, Intent, Layer, OptionalContentState},
+    outline::{OutlineIssue, OutlineItem, OutlineProblem},
+    page_labels::{NumberingStyle, PageLabels},
+    pipeline::Pipeline,
+    preflight::{PreflightFinding, PreflightReport, Severity},
+    presentation::{AlternatePresentation, NavigationNode, NavigationStep},
+    render::{text_lines::RenderedLine, Renderer},
+    signature::{
+        Change, ChangedObject, DisallowedChange, DocumentSecurityStore, FieldMdpAction, Signature,
+        SignatureProblem, SignatureReference, SignatureSubFilter, SignatureVerification, Timestamp,
+        TransformMethod, ValidationData,
+    },
+    stream::Stream,
+    structure::{
+        AccessibilityIssue, AccessibilityProblem, List, ListItem, ListNumbering, StructureBlock,
+        Table, TableCell, TableOfContents, TextBlock, TextOrder, TocItem,
+    },
+    thread::{ArticleBead, ArticleThread},
+    tokenizer::{IndirectObject, Spanned, Token, Tokenizer},
+    transition::{
+        PageTransition, Transition, TransitionDimension, TransitionDirection, TransitionMotion,
+        TransitionStyle,
+    },
+    version::{PdfFeatures, PdfVersion},
+    writer::{ContentBuilder, DocumentBuilder, PageBuilder, PdfWriter, StreamCompression},
+    xobject::{ImageXObject, RgbaImage},
+    xref::ObjectSpan,
+};
 
 /// Assert that the dictionary has no keys
 ///
@@ -126,6 +218,21 @@ impl<'a> LexObject<'a> for Lexer<'a> {
 
         Ok(Object::Dictionary(dict))
     }
+
+    fn record_lexical_form(&mut self, obj: &Object<'a>, start: usize) {
+        // the bytes of strings in encrypted files are ciphertext
+        if self.security_handler.is_some() {
+            return;
+        }
+
+        if let (Some(forms), Some(reference)) = (&mut self.lexical_forms, self.current_reference) {
+            forms.record(reference, obj, &self.file[start..self.pos]);
+        }
+    }
+
+    fn preserve_key_order(&self) -> bool {
+        self.preserve_key_order
+    }
 }
 
 pub struct Lexer<'a> {
@@ -134,31 +241,122 @@ pub struct Lexer<'a> {
     xref: Rc<Xref>,
     /// None if file isn't encrypted
     security_handler: Option<SecurityHandler<'a>>,
+
+    /// Indirect objects whose strings and streams are not encrypted, even in
+    /// encrypted documents: the encryption dictionary, and the document's
+    /// metadata stream if EncryptMetadata is false
+    unencrypted_references: Vec<Reference>,
     cached_object_streams: HashMap<usize, ObjectStreamParser<'a>>,
+
+    /// None unless the original lexical form of objects is being preserved
+    // todo: objects within object streams are not yet recorded
+    lexical_forms: Option<LexicalForms>,
+
+    // todo: dictionaries within object streams do not yet preserve their key order
+    preserve_key_order: bool,
+
+    /// The indirect object currently being lexed
+    current_reference: Option<Reference>,
 }
 
 impl<'a> Lexer<'a> {
+    /// Read the cross-reference table and trailer of `file`, following any
+    /// previous cross-reference sections, without reading the document catalog
+    /// or page tree
+    pub(crate) fn open(file: Vec<u8>, options: ParseOptions) -> PdfResult<(Self, Trailer<'a>)> {
+        let mut xref_parser = XrefParser::new(file.clone());
+        let xref_and_trailer = xref_parser.read_xref()?;
+        let xref = Rc::new(xref_and_trailer.xref);
+        let mut lexer = Lexer::new(file, xref)?;
+
+        if options.preserve_lexical_form {
+            lexer.lexical_forms = Some(LexicalForms::default());
+        }
+
+        lexer.preserve_key_order = options.preserve_key_order;
+
+        let trailer = match xref_and_trailer.trailer_or_offset {
+            TrailerOrOffset::Offset(offset) => {
+                let trailer = lexer.lex_trailer(offset, false)?;
+                let mut xref = (*lexer.xref).clone();
+
+                let mut prev = trailer.prev;
+                while let Some(prev_offset) = prev {
+                    let xref_and_trailer = xref_parser.parse_xref_at_offset(prev_offset)?;
+
+                    xref.merge_with_previous(xref_and_trailer.xref);
+
+                    // todo: superfluous clone(?)
+                    lexer.xref = Rc::new(xref.clone());
+
+                    let prev_trailer = match xref_and_trailer.trailer_or_offset {
+                        TrailerOrOffset::Trailer(trailer) => trailer,
+                        TrailerOrOffset::Offset(offset) => lexer.lex_trailer(offset, true)?,
+                    };
+
+                    prev = prev_trailer.prev;
+                }
+
+                trailer
+            }
+            TrailerOrOffset::Trailer(trailer) => trailer,
+        };
+
+        lexer.security_handler = if let Some(encryption) = &trailer.encryption {
+            let file_identifier = trailer
+                .id
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("encrypted document has no file identifier"))?;
+
+            let handler = SecurityHandler::new(
+                encryption.get_ref(&mut lexer)?.into_owned(),
+                file_identifier,
+                options.password.as_deref().unwrap_or_default(),
+            )?;
+
+            if let TypedReference::Indirect { reference, .. } = encryption {
+                lexer.unencrypted_references.push(*reference);
+            }
+
+            Some(handler)
+        } else {
+            None
+        };
+
+        Ok((lexer, trailer))
+    }
+
     pub fn new(file: Vec<u8>, xref: Rc<Xref>) -> io::Result<Self> {
         Ok(Self {
             file,
             xref,
             pos: 0,
             security_handler: None,
+            unencrypted_references: Vec::new(),
             cached_object_streams: HashMap::new(),
+            lexical_forms: None,
+            preserve_key_order: false,
+            current_reference: None,
         })
     }
 
     fn lex_object_stream(&mut self, byte_offset: usize) -> PdfResult<ObjectStream<'a>> {
         self.pos = byte_offset;
-        self.read_obj_prelude()?;
+        let reference = self.read_obj_prelude()?;
 
         let object_stream_dict = ObjectStreamDict::from_dict(self.lex_dict_ignore_stream()?, self)?;
-        let stream = self
+        let mut stream = self
             .lex_stream(object_stream_dict.stream_dict.clone())?
             .stream;
 
         self.read_obj_trailer()?;
 
+        // the objects within an object stream are encrypted only as part of it,
+        // with the key of the object stream
+        if let Some(security_handler) = &self.security_handler {
+            stream = Cow::Owned(security_handler.decrypt_stream(stream.into_owned(), reference)?);
+        }
+
         Ok(ObjectStream {
             stream,
             dict: object_stream_dict,
@@ -176,6 +374,49 @@ impl<'a> Lexer<'a> {
         Ok(trailer)
     }
 
+    fn lex_indirect_object(&mut self, reference: Reference) -> PdfResult<Object<'a>> {
+        self.pos = match Rc::clone(&self.xref).get_offset(reference)? {
+            Some(ByteOffset::MainFile(p)) => p,
+            Some(ByteOffset::ObjectStream { byte_offset, .. }) => {
+                return self.lex_object_from_object_stream(byte_offset, reference);
+            }
+            None => return Ok(Object::Null),
+        };
+
+        self.read_obj_prelude()?;
+
+        self.current_reference = Some(reference);
+
+        let obj = self.lex_object()?;
+
+        self.read_obj_trailer()?;
+
+        Ok(obj)
+    }
+
+    /// Decrypt the strings and streams of an object resolved from `reference`
+    ///
+    /// Objects within object streams are decrypted along with the object
+    /// stream itself.
+    fn decrypt_resolved(&self, mut obj: Object<'a>, reference: Reference) -> PdfResult<Object<'a>> {
+        let security_handler = match &self.security_handler {
+            Some(security_handler) => security_handler,
+            None => return Ok(obj),
+        };
+
+        if self.unencrypted_references.contains(&reference) {
+            return Ok(obj);
+        }
+
+        if let Some(ByteOffset::ObjectStream { .. }) = self.xref.get_offset(reference)? {
+            return Ok(obj);
+        }
+
+        security_handler.decrypt_object(&mut obj, reference)?;
+
+        Ok(obj)
+    }
+
     fn lex_object_from_object_stream(
         &mut self,
         byte_offset: usize,
@@ -186,15 +427,6 @@ impl<'a> Lexer<'a> {
             None => {
                 let ObjectStream { stream, dict } = self.lex_object_stream(byte_offset)?;
 
-                let stream = match &self.security_handler {
-                    Some(security_handler) => {
-                        let stream =
-                            security_handler.decrypt_stream(stream.into_owned(), reference)?;
-                        Cow::Owned(stream)
-                    }
-                    None => stream,
-                };
-
                 let decoded_stream = decode_stream(&stream, &dict.stream_dict, self)?;
 
                 let parser = ObjectStreamParser::new(decoded_stream.into_owned(), dict)?;
@@ -208,6 +440,26 @@ impl<'a> Lexer<'a> {
         parser.parse_object(reference)
     }
 
+    /// The bytes occupied by the indirect object beginning at `byte_offset`
+    fn object_span_at(&mut self, byte_offset: usize) -> PdfResult<Range<usize>> {
+        let init_pos = self.pos;
+
+        self.pos = byte_offset;
+        self.skip_whitespace();
+
+        let start = self.pos;
+
+        self.read_obj_prelude()?;
+        self.lex_object()?;
+        self.read_obj_trailer()?;
+
+        let end = self.pos;
+
+        self.pos = init_pos;
+
+        Ok(start..end)
+    }
+
     fn lex_page_tree(&mut self, xref: &Xref, root_reference: Reference) -> PdfResult<PageNode<'a>> {
         if xref.get_offset(root_reference)?.is_none() {
             return Ok(PageNode::Root(Rc::new(RefCell::new(PageTree {
@@ -394,26 +646,25 @@ impl<'a> Lexer<'a> {
 }
 
 impl<'a> Resolve<'a> for Lexer<'a> {
+    /// References are resolved in the middle of lexing other objects, such as
+    /// the indirect Length of a stream, so the position is restored afterwards
+    /// whether or not the object could be lexed
     fn lex_object_from_reference(&mut self, reference: Reference) -> PdfResult<Object<'a>> {
         let init_pos = self.pos;
+        let init_reference = self.current_reference;
 
-        self.pos = match Rc::clone(&self.xref).get_offset(reference)? {
-            Some(ByteOffset::MainFile(p)) => p,
-            Some(ByteOffset::ObjectStream { byte_offset, .. }) => {
-                return self.lex_object_from_object_stream(byte_offset, reference);
-            }
-            None => return Ok(Object::Null),
-        };
-
-        self.read_obj_prelude()?;
-
-        let obj = self.lex_object()?;
-
-        self.read_obj_trailer()?;
+        let obj = self
+            .lex_indirect_object(reference)
+            .and_then(|obj| self.decrypt_resolved(obj, reference));
 
         self.pos = init_pos;
+        self.current_reference = init_reference;
 
-        Ok(obj)
+        obj
+    }
+
+    fn security_handler(&self) -> Option<&SecurityHandler<'a>> {
+        self.security_handler.as_ref()
     }
 
     fn reference_exists(&mut self, reference: Reference) -> PdfResult<bool> {
@@ -427,68 +678,101 @@ pub struct Parser<'a> {
     trailer: Trailer<'a>,
     catalog: DocumentCatalog<'a>,
     page_tree: PageNode<'a>,
+    version: PdfVersion,
+
+    /// The version declared by the `%PDF-x.y` header, or `None` if the header
+    /// is missing or garbled
+    header_version: Option<PdfVersion>,
+}
+
+/// Options controlling how a document is parsed
+#[derive(Debug, Default, Clone)]
+pub struct ParseOptions {
+    /// Keep the original bytes of every number and string, so that objects
+    /// which are rewritten emit the values they did not change byte-for-byte,
+    /// minimizing the differences introduced by incremental updates
+    pub preserve_lexical_form: bool,
+
+    /// Remember the order in which the keys of each dictionary appear, so that
+    /// [`Dictionary::keys`] and [`Dictionary::entries`] reproduce the layout of
+    /// the producer rather than an arbitrary one
+    pub preserve_key_order: bool,
+
+    /// The user or owner password of an encrypted document
+    ///
+    /// Documents which may be opened without a password have an empty user
+    /// password, which is tried if this is `None`.
+    pub password: Option<String>,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(p: impl AsRef<std::path::Path>) -> PdfResult<Self> {
-        let file = std::fs::read(p)?;
-
-        let mut xref_parser = XrefParser::new(file.clone());
-        let xref_and_trailer = xref_parser.read_xref()?;
-        let mut xref = Rc::new(xref_and_trailer.xref);
-        let mut lexer = Lexer::new(file, Rc::clone(&xref))?;
-
-        let trailer = match xref_and_trailer.trailer_or_offset {
-            TrailerOrOffset::Offset(offset) => {
-                let trailer = lexer.lex_trailer(offset, false)?;
-                let mut xref = (*xref).clone();
+        Self::with_options(p, ParseOptions::default())
+    }
 
-                let mut prev = trailer.prev;
-                while let Some(prev_offset) = prev {
-                    let xref_and_trailer = xref_parser.parse_xref_at_offset(prev_offset)?;
+    pub fn with_options(p: impl AsRef<std::path::Path>, options: ParseOptions) -> PdfResult<Self> {
+        Self::from_bytes(std::fs::read(p)?, options)
+    }
 
-                    xref.merge_with_previous(xref_and_trailer.xref);
+    /// Parse a document held in memory, such as one which was just written
+    pub fn from_bytes(file: Vec<u8>, options: ParseOptions) -> PdfResult<Self> {
+        #[cfg(feature = "bench")]
+        let _timer = crate::bench::StageTimer::start(crate::bench::Stage::Parse);
 
-                    // todo: superfluous clone(?)
-                    lexer.xref = Rc::new(xref.clone());
+        // a missing or garbled header does not stop the file from being read
+        let header_version = PdfVersion::from_header(&file).ok();
 
-                    let prev_trailer = match xref_and_trailer.trailer_or_offset {
-                        TrailerOrOffset::Trailer(trailer) => trailer,
-                        TrailerOrOffset::Offset(offset) => lexer.lex_trailer(offset, true)?,
-                    };
+        let (mut lexer, trailer) = Lexer::open(file, options)?;
+        let xref = Rc::clone(&lexer.xref);
 
-                    prev = prev_trailer.prev;
-                }
+        let catalog = DocumentCatalog::from_obj(Object::Reference(trailer.root), &mut lexer)?;
 
-                trailer
+        if let (Some(security_handler), Some(metadata)) =
+            (&lexer.security_handler, catalog.metadata)
+        {
+            if !security_handler.encrypts_metadata() {
+                lexer.unencrypted_references.push(metadata);
             }
-            TrailerOrOffset::Trailer(trailer) => trailer,
-        };
-
-        xref = Rc::clone(&lexer.xref);
-        lexer.security_handler = if let Some(encryption) = &trailer.encryption {
-            let handler = SecurityHandler::new(
-                encryption.get_ref(&mut lexer)?.into_owned(),
-                trailer.id.clone().unwrap(),
-            );
-            Some(handler)
-        } else {
-            None
-        };
-
-        let catalog = DocumentCatalog::from_obj(Object::Reference(trailer.root), &mut lexer)?;
+        }
 
         let page_tree = lexer.lex_page_tree(&xref, catalog.pages)?;
 
+        let version =
+            PdfVersion::reconcile(header_version.unwrap_or(PdfVersion::V1_7), catalog.version);
+
         Ok(Self {
             lexer,
             xref,
             trailer,
             catalog,
             page_tree,
+            version,
+            header_version,
         })
     }
 
+    /// The version of the PDF specification to which this document conforms,
+    /// taking into account both the file header and the catalog Version entry
+    ///
+    /// A document whose header is missing or garbled is assumed to be PDF 1.7,
+    /// unless its catalog states otherwise.
+    pub fn version(&self) -> PdfVersion {
+        self.version
+    }
+
+    /// The version declared by the `%PDF-x.y` comment in the file header, or
+    /// `None` if the header is missing or garbled
+    pub fn header_version(&self) -> Option<PdfVersion> {
+        self.header_version
+    }
+
+    /// The original bytes of `obj`, a number or string within the object
+    /// `reference`, if lexical forms are being preserved and the value was read
+    /// from the file unchanged
+    pub(crate) fn lexical_form(&self, reference: Reference, obj: &Object) -> Option<&[u8]> {
+        self.lexer.lexical_forms.as_ref()?.get(reference, obj)
+    }
+
     pub fn info(&mut self) -> PdfResult<Option<Cow<InformationDictionary<'a>>>> {
         Ok(match &self.trailer.info {
             Some(v) => Some(v.get_ref(&mut self.lexer)?),
@@ -503,7 +787,42 @@ impl<'a> Parser<'a> {
         leaves
     }
 
-    pub fn page_annotations(&mut self, page: &PageObject) -> PdfResult<Option<Vec<Annotation>>> {
+    /// Every page of the document, along with the reference to its page object
+    fn page_references(&self) -> Vec<(Reference, Rc<PageObject<'a>>)> {
+        let root = match &self.page_tree {
+            PageNode::Root(root) => root,
+            _ => unreachable!(),
+        };
+
+        root.borrow()
+            .pages
+            .iter()
+            .filter_map(|(&reference, node)| match node {
+                PageNode::Leaf(page) => Some((reference, Rc::clone(page))),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The index of each page of the document, keyed by the reference to its
+    /// page object
+    fn page_indices(&self) -> HashMap<Reference, usize> {
+        let pages = self.pages();
+
+        self.page_references()
+            .into_iter()
+            .filter_map(|(reference, page)| {
+                let idx = pages.iter().position(|p| Rc::ptr_eq(p, &page))?;
+
+                Some((reference, idx))
+            })
+            .collect()
+    }
+
+    pub fn page_annotations(
+        &mut self,
+        page: &PageObject,
+    ) -> PdfResult<Option<Vec<Annotation<'a>>>> {
         if let Some(annots) = &page.annots {
             let annotations = annots
                 .iter()
@@ -512,7 +831,7 @@ impl<'a> Parser<'a> {
 
                     Annotation::from_obj(obj, &mut self.lexer)
                 })
-                .collect::<PdfResult<Vec<Annotation>>>()?;
+                .collect::<PdfResult<Vec<Annotation<'a>>>>()?;
 
             return Ok(Some(annotations));
         }
@@ -532,3 +851,300 @@ impl<'a> Parser<'a> {
         )))
     }
 }
+
+/// Files whose stream parameters are given as indirect references, as written
+/// by several producers
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use flate2::{write::ZlibEncoder, Compression};
+
+    use super::*;
+
+    pub(crate) struct TestFile {
+        pub(crate) bytes: Vec<u8>,
+        pub(crate) offsets: HashMap<usize, usize>,
+    }
+
+    impl TestFile {
+        pub(crate) fn new() -> Self {
+            Self {
+                bytes: b"%PDF-1.5\n".to_vec(),
+                offsets: HashMap::new(),
+            }
+        }
+
+        pub(crate) fn object(&mut self, object_number: usize, body: &[u8]) {
+            self.offsets.insert(object_number, self.bytes.len());
+            self.bytes
+                .extend_from_slice(format!("{} 0 obj\n", object_number).as_bytes());
+            self.bytes.extend_from_slice(body);
+            self.bytes.extend_from_slice(b"\nendobj\n");
+        }
+
+        pub(crate) fn stream(&mut self, object_number: usize, dict: &str, data: &[u8]) {
+            let mut body = format!("{}\nstream\n", dict).into_bytes();
+            body.extend_from_slice(data);
+            body.extend_from_slice(b"\nendstream");
+
+            self.object(object_number, &body);
+        }
+
+        pub(crate) fn with_xref_table(mut self) -> Vec<u8> {
+            let size = self.offsets.keys().max().unwrap() + 1;
+            let xref_offset = self.bytes.len();
+
+            let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", size);
+
+            for object_number in 1..size {
+                xref.push_str(&match self.offsets.get(&object_number) {
+                    Some(offset) => format!("{:010} 00000 n \n", offset),
+                    None => "0000000000 65535 f \n".to_owned(),
+                });
+            }
+
+            xref.push_str(&format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                size, xref_offset
+            ));
+
+            self.bytes.extend_from_slice(xref.as_bytes());
+            self.bytes
+        }
+
+        /// Finish the file with a cross-reference stream whose W and Length
+        /// entries are indirect, listing each `(object number, object stream,
+        /// index)` of `compressed`
+        pub(crate) fn with_xref_stream(mut self, compressed: &[(usize, usize, usize)]) -> Vec<u8> {
+            const W: usize = 100;
+            const LENGTH: usize = 101;
+            const XREF: usize = 102;
+            const SIZE: usize = XREF + 1;
+
+            self.object(W, b"[1 4 2]");
+            self.object(LENGTH, (SIZE * 7).to_string().as_bytes());
+
+            let xref_offset = self.bytes.len();
+            self.offsets.insert(XREF, xref_offset);
+
+            let mut rows = Vec::new();
+
+            for object_number in 0..SIZE {
+                if let Some(&(_, stream, idx)) = compressed.iter().find(|c| c.0 == object_number) {
+                    rows.push(2);
+                    rows.extend_from_slice(&(stream as u32).to_be_bytes());
+                    rows.extend_from_slice(&(idx as u16).to_be_bytes());
+                } else if let Some(&offset) = self.offsets.get(&object_number) {
+                    rows.push(1);
+                    rows.extend_from_slice(&(offset as u32).to_be_bytes());
+                    rows.extend_from_slice(&[0, 0]);
+                } else {
+                    rows.extend_from_slice(&[0, 0, 0, 0, 0, 0xff, 0xff]);
+                }
+            }
+
+            self.stream(
+                XREF,
+                &format!(
+                    "<< /Type /XRef /Size {} /W {} 0 R /Length {} 0 R /Root 1 0 R >>",
+                    SIZE, W, LENGTH
+                ),
+                &rows,
+            );
+
+            self.bytes
+                .extend_from_slice(format!("startxref\n{}\n%%EOF\n", xref_offset).as_bytes());
+            self.bytes
+        }
+    }
+
+    pub(crate) fn compress(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn decoded_stream(lexer: &mut Lexer, object_number: usize) -> Vec<u8> {
+        let stream = lexer
+            .assert_stream(Object::Reference(Reference {
+                object_number,
+                generation: 0,
+            }))
+            .unwrap();
+
+        decode_stream(&stream.stream, &stream.dict, lexer)
+            .unwrap()
+            .into_owned()
+    }
+
+    #[test]
+    fn indirect_length_filter_and_decode_parms() {
+        let data = compress(b"0 0 m 10 10 l S");
+
+        let mut file = TestFile::new();
+        file.object(1, b"<< /Type /Catalog >>");
+        file.stream(
+            2,
+            "<< /Length 3 0 R /Filter 4 0 R /DecodeParms 5 0 R >>",
+            &data,
+        );
+        file.object(3, data.len().to_string().as_bytes());
+        file.object(4, b"/FlateDecode");
+        file.object(5, b"<< /Predictor 6 0 R >>");
+        file.object(6, b"1");
+        file.stream(
+            7,
+            "<< /Length 3 0 R /Filter [4 0 R] /DecodeParms [5 0 R] >>",
+            &data,
+        );
+
+        let (mut lexer, _) = Lexer::open(file.with_xref_table(), ParseOptions::default()).unwrap();
+
+        assert_eq!(decoded_stream(&mut lexer, 2), b"0 0 m 10 10 l S");
+        assert_eq!(decoded_stream(&mut lexer, 7), b"0 0 m 10 10 l S");
+    }
+
+    #[test]
+    fn indirect_length_in_object_stream() {
+        let content = b"BT /F1 12 Tf (hi) Tj ET";
+        let compressed_objects = format!("4 0 {}", content.len());
+
+        let mut file = TestFile::new();
+        file.object(1, b"<< /Type /Catalog >>");
+        file.stream(2, "<< /Length 4 0 R >>", content);
+        file.stream(
+            3,
+            &format!(
+                "<< /Type /ObjStm /N 1 /First 4 /Length {} >>",
+                compressed_objects.len()
+            ),
+            compressed_objects.as_bytes(),
+        );
+
+        let (mut lexer, trailer) =
+            Lexer::open(file.with_xref_stream(&[(4, 3, 0)]), ParseOptions::default()).unwrap();
+
+        assert_eq!(trailer.size, 103);
+        assert_eq!(decoded_stream(&mut lexer, 2), content);
+    }
+
+    #[test]
+    fn compact_reachable_objects() {
+        let mut file = TestFile::new();
+        file.object(
+            1,
+            b"<< /Type /Catalog /Pages 3 0 R /Extra 4 0 R /Missing 9 0 R >>",
+        );
+        file.object(2, b"(unreachable)");
+        file.object(3, b"<< /Type /Pages /Kids [] /Count 0 >>");
+        file.stream(4, "<< /Length 5 0 R >>", b"data");
+        file.object(5, b"4");
+
+        // objects are renumbered in the order their references are reached
+        let options = ParseOptions {
+            preserve_key_order: true,
+            ..ParseOptions::default()
+        };
+
+        let (mut lexer, trailer) = Lexer::open(file.with_xref_table(), options).unwrap();
+
+        let mut compacted = writer::compact(&mut lexer, &trailer, PdfVersion::V1_5).unwrap();
+        compacted.set_object_streams(false);
+
+        let bytes = compacted.to_bytes().unwrap();
+
+        let (mut lexer, trailer) = Lexer::open(bytes, ParseOptions::default()).unwrap();
+
+        assert_eq!(trailer.size, 4);
+
+        let reference = |object_number| Reference {
+            object_number,
+            generation: 0,
+        };
+
+        let mut catalog = lexer.assert_dict(Object::Reference(trailer.root)).unwrap();
+
+        assert_eq!(catalog.get_reference("Pages").unwrap(), Some(reference(2)));
+        assert_eq!(catalog.get_reference("Extra").unwrap(), Some(reference(3)));
+        assert_eq!(
+            catalog.get_object("Missing", &mut lexer).unwrap(),
+            Some(Object::Null)
+        );
+        assert_eq!(decoded_stream(&mut lexer, 3), b"data");
+    }
+
+    #[test]
+    fn garbled_header() {
+        let mut document = writer::DocumentBuilder::new(PdfVersion::V1_4)
+            .build()
+            .unwrap();
+        document.set_object_streams(false);
+
+        let mut bytes = document.to_bytes().unwrap();
+        bytes[..5].copy_from_slice(b"%PFD-");
+
+        let parser = Parser::from_bytes(bytes, ParseOptions::default()).unwrap();
+
+        assert_eq!(parser.version(), PdfVersion::V1_7);
+        assert_eq!(parser.header_version(), None);
+    }
+
+    #[test]
+    fn preserve_key_order() {
+        let mut file = TestFile::new();
+        file.object(1, b"<< /Type /Catalog >>");
+        file.object(2, b"<< /Zeta 1 /Alpha 2 /Mid 3 /Alpha 4 /Beta 5 >>");
+        let bytes = file.with_xref_table();
+
+        let options = ParseOptions {
+            preserve_key_order: true,
+            ..ParseOptions::default()
+        };
+
+        let (mut lexer, _) = Lexer::open(bytes, options).unwrap();
+
+        let reference = Object::Reference(Reference {
+            object_number: 2,
+            generation: 0,
+        });
+
+        let mut dict = lexer.assert_dict(reference).unwrap();
+
+        assert_eq!(dict.keys(), vec!["Zeta", "Alpha", "Mid", "Beta"]);
+
+        dict.get_integer("Mid", &mut lexer).unwrap();
+
+        assert_eq!(
+            dict.entries().collect::<Vec<_>>(),
+            vec![
+                ("Zeta".to_owned(), Object::Integer(1)),
+                ("Alpha".to_owned(), Object::Integer(4)),
+                ("Beta".to_owned(), Object::Integer(5)),
+            ]
+        );
+    }
+
+    /// The colour of each pixel of the PNG image at `path`, from left to right
+    /// and top to bottom
+    pub(crate) fn rgb_pixels(path: &std::path::Path) -> Vec<[u8; 3]> {
+        let decoder = png::Decoder::new(std::fs::File::open(path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).unwrap();
+
+        buffer[..info.buffer_size()]
+            .chunks(4)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+            .collect()
+    }
+
+    /// The number of pixels of the PNG image at `path` which are not white
+    pub(crate) fn painted_pixels(path: &std::path::Path) -> usize {
+        rgb_pixels(path)
+            .into_iter()
+            .filter(|&pixel| pixel != [0xff, 0xff, 0xff])
+            .count()
+    }
+}
@@ -0,0 +1,786 @@
+/*!
+ * Viewports and measure dictionaries, which give regions of a page a
+ * coordinate system of their own, such as the scale of an engineering drawing
+ * or the geographic location of a map
+ *
+ * A rectilinear (RL) measure scales page distances into real-world units. A
+ * geospatial (GEO) measure instead ties points of the unit square over the
+ * viewport to latitudes and longitudes, from which the geographic location of
+ * any point within it may be interpolated.
+ */
+
+use crate::{
+    data_structures::Rectangle,
+    error::PdfResult,
+    objects::{decode_text_string, Dictionary, Object},
+    FromObj, Parser, Resolve,
+};
+
+/// A rectangular region of a page, with its own measurement coordinate system
+#[derive(Debug, Clone, FromObj)]
+#[obj_type("Viewport")]
+pub struct Viewport<'a> {
+    /// The location of the viewport on the page, in default user space
+    #[field("BBox")]
+    pub bbox: Rectangle,
+
+    /// A descriptive name of the viewport
+    #[field("Name")]
+    name: Option<String>,
+
+    /// The coordinate system that applies to the region of the viewport
+    #[field("Measure")]
+    pub measure: Option<Measure>,
+
+    /// (PDF 2.0) A point data dictionary, with extended geospatial data that
+    /// applies to the region
+    #[field("PtData")]
+    pt_data: Option<Dictionary<'a>>,
+}
+
+impl Viewport<'_> {
+    pub fn name(&self) -> Option<String> {
+        self.name.as_deref().map(decode_text_string)
+    }
+
+    /// Whether the point, in default user space, lies within the viewport
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        let [lower_left, _, upper_right, _] = self.bbox.corners();
+
+        let (min_x, max_x) = (
+            lower_left.x.min(upper_right.x),
+            lower_left.x.max(upper_right.x),
+        );
+        let (min_y, max_y) = (
+            lower_left.y.min(upper_right.y),
+            lower_left.y.max(upper_right.y),
+        );
+
+        min_x <= point[0] && point[0] <= max_x && min_y <= point[1] && point[1] <= max_y
+    }
+
+    /// The point, in default user space, in the measurement units of a
+    /// rectilinear measure, or `None` if the viewport has no such measure
+    pub fn to_measured(&self, point: [f32; 2]) -> Option<[f32; 2]> {
+        match &self.measure {
+            Some(Measure::Rectilinear(measure)) => Some(measure.to_measured(point)),
+            _ => None,
+        }
+    }
+
+    /// The latitude and longitude of the point, in default user space, under a
+    /// geospatial measure
+    ///
+    /// This is `None` if the viewport has no such measure, or if the point is
+    /// outside the region over which it applies.
+    pub fn to_geographic(&self, point: [f32; 2]) -> Option<GeoPoint> {
+        let measure = match &self.measure {
+            Some(Measure::Geospatial(measure)) => measure,
+            _ => return None,
+        };
+
+        let [lower_left, _, upper_right, _] = self.bbox.corners();
+
+        let unit_point = [
+            (point[0] - lower_left.x) / (upper_right.x - lower_left.x),
+            (point[1] - lower_left.y) / (upper_right.y - lower_left.y),
+        ];
+
+        measure.to_geographic(unit_point)
+    }
+}
+
+/// A measure dictionary, the coordinate system of a viewport or of the
+/// region measured by an annotation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Measure {
+    Rectilinear(RectilinearMeasure),
+    Geospatial(GeospatialMeasure),
+}
+
+impl<'a> FromObj<'a> for Measure {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        let subtype = dict.get_name("Subtype", resolver)?;
+
+        let obj = Object::Dictionary(dict);
+
+        Ok(match subtype.as_deref() {
+            None | Some("RL") => Measure::Rectilinear(RectilinearMeasure::from_obj(obj, resolver)?),
+            Some("GEO") => Measure::Geospatial(GeospatialMeasure::from_obj(obj, resolver)?),
+            Some(found) => anyhow::bail!("unknown measure subtype: {}", found),
+        })
+    }
+}
+
+/// A rectilinear coordinate system, in which the x and y axes may be scaled
+/// independently
+#[derive(Debug, Clone, PartialEq, FromObj)]
+#[obj_type("Measure")]
+pub struct RectilinearMeasure {
+    /// The scale ratio, such as "1 in = 0.1 mi"
+    #[field("R")]
+    ratio: String,
+
+    /// The units in which measurements along the x axis are displayed. The
+    /// conversion factor of the first converts from default user space units
+    #[field("X")]
+    pub x: Vec<NumberFormat>,
+
+    /// The units in which measurements along the y axis are displayed, when
+    /// they differ from those of the x axis
+    #[field("Y")]
+    pub y: Option<Vec<NumberFormat>>,
+
+    /// The units in which distances are displayed. The conversion factor of
+    /// the first converts from the largest units of the x axis
+    #[field("D")]
+    pub distance: Vec<NumberFormat>,
+
+    /// The units in which areas are displayed. The conversion factor of the
+    /// first converts from the square of the largest units of the x axis
+    #[field("A")]
+    pub area: Vec<NumberFormat>,
+
+    /// The units in which angles are displayed
+    #[field("T")]
+    pub angle: Option<Vec<NumberFormat>>,
+
+    /// The units in which the slopes of lines are displayed
+    #[field("S")]
+    pub slope: Option<Vec<NumberFormat>>,
+
+    /// The origin of the measurement coordinate system, in default user space
+    ///
+    /// Default value: [0 0]
+    #[field("O", default = [0.0, 0.0])]
+    pub origin: [f32; 2],
+
+    /// The factor which converts the largest units of the y axis into the
+    /// largest units of the x axis, when both are given
+    #[field("CYX")]
+    pub cyx: Option<f32>,
+}
+
+impl RectilinearMeasure {
+    pub fn ratio(&self) -> String {
+        decode_text_string(&self.ratio)
+    }
+
+    /// The point, in default user space, in the largest units of each axis,
+    /// relative to the origin
+    pub fn to_measured(&self, point: [f32; 2]) -> [f32; 2] {
+        [
+            (point[0] - self.origin[0]) * self.x_factor(),
+            (point[1] - self.origin[1]) * self.y_factor(),
+        ]
+    }
+
+    /// The distance between two points, in default user space, in the largest
+    /// units of the distance formats
+    pub fn distance(&self, start: [f32; 2], end: [f32; 2]) -> f32 {
+        let [x, y] = self.along_x([end[0] - start[0], end[1] - start[1]]);
+
+        x.hypot(y) * conversion_factor(&self.distance)
+    }
+
+    /// The area of the polygon with the given vertices, in default user space,
+    /// in the largest units of the area formats
+    pub fn area(&self, vertices: &[[f32; 2]]) -> f32 {
+        let vertices = vertices
+            .iter()
+            .map(|&point| self.along_x(point))
+            .collect::<Vec<[f32; 2]>>();
+
+        let twice_area = vertices
+            .iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+            .sum::<f32>();
+
+        (twice_area / 2.0).abs() * conversion_factor(&self.area)
+    }
+
+    /// The distance between two points, in default user space, as it is
+    /// displayed to the user, such as "1 mi 120 ft"
+    pub fn format_distance(&self, start: [f32; 2], end: [f32; 2]) -> String {
+        NumberFormat::format(self.distance(start, end), &self.distance)
+    }
+
+    /// The area of a polygon, as it is displayed to the user
+    pub fn format_area(&self, vertices: &[[f32; 2]]) -> String {
+        NumberFormat::format(self.area(vertices), &self.area)
+    }
+
+    fn x_factor(&self) -> f32 {
+        conversion_factor(&self.x)
+    }
+
+    fn y_factor(&self) -> f32 {
+        match &self.y {
+            Some(y) => conversion_factor(y),
+            None => self.x_factor(),
+        }
+    }
+
+    /// The vector, in default user space, with both of its components in the
+    /// largest units of the x axis
+    fn along_x(&self, vector: [f32; 2]) -> [f32; 2] {
+        let y = match self.y {
+            Some(..) => vector[1] * self.y_factor() * self.cyx.unwrap_or(1.0),
+            None => vector[1] * self.x_factor(),
+        };
+
+        [vector[0] * self.x_factor(), y]
+    }
+}
+
+/// The conversion factor into the largest units of a number format array
+fn conversion_factor(formats: &[NumberFormat]) -> f32 {
+    formats.first().map_or(1.0, |format| format.conversion)
+}
+
+/// How one unit of a measurement is displayed
+#[derive(Debug, Clone, PartialEq, FromObj)]
+#[obj_type("NumberFormat")]
+pub struct NumberFormat {
+    /// The label of the unit, such as "mi" or "ft"
+    #[field("U")]
+    unit: String,
+
+    /// The factor which converts into this unit, from the units of the
+    /// previous format in the array or, for the first, from those of the
+    /// measurement
+    #[field("C")]
+    pub conversion: f32,
+
+    /// How the fractional part of a value in this unit is displayed
+    ///
+    /// Default value: D
+    #[field("F", default = FractionFormat::Decimal)]
+    pub fraction_format: FractionFormat,
+
+    /// The precision of decimal values, as a power of 10, or the denominator
+    /// of fractional values
+    ///
+    /// Default value: 100
+    #[field("D", default = 100)]
+    pub precision: i32,
+
+    /// Whether fractions are displayed without being reduced, and decimals
+    /// without removing trailing zeros
+    ///
+    /// Default value: false
+    #[field("FD", default = false)]
+    pub keep_denominator: bool,
+
+    /// The text between orders of thousands
+    ///
+    /// Default value: (,)
+    #[field("RT", default = ",".to_owned())]
+    thousands_separator: String,
+
+    /// The text between the whole and fractional parts of decimal values
+    ///
+    /// Default value: (.)
+    #[field("RD", default = ".".to_owned())]
+    decimal_separator: String,
+
+    /// The text before the label
+    ///
+    /// Default value: ( )
+    #[field("PS", default = " ".to_owned())]
+    prefix_spacing: String,
+
+    /// The text after the label
+    ///
+    /// Default value: ( )
+    #[field("SS", default = " ".to_owned())]
+    suffix_spacing: String,
+
+    /// Whether the label comes before or after the value
+    ///
+    /// Default value: S
+    #[field("O", default = LabelPosition::Suffix)]
+    pub label_position: LabelPosition,
+}
+
+impl NumberFormat {
+    pub fn unit(&self) -> String {
+        decode_text_string(&self.unit)
+    }
+
+    /// The value, given in the largest units of the array, as it is displayed
+    /// to the user
+    ///
+    /// The whole part of the value is given in each unit but the last, with
+    /// what remains converted into the next.
+    pub fn format(value: f32, formats: &[NumberFormat]) -> String {
+        let (last, rest) = match formats.split_last() {
+            Some(formats) => formats,
+            None => return value.to_string(),
+        };
+
+        let sign = if value < 0.0 { "-" } else { "" };
+        let mut value = value.abs();
+        let mut parts = Vec::with_capacity(formats.len());
+
+        for (i, format) in rest.iter().enumerate() {
+            let whole = value.trunc();
+
+            value = (value - whole) * formats[i + 1].conversion;
+
+            if whole != 0.0 {
+                parts.push(format.label(&format.group_thousands(whole as u64)));
+            }
+        }
+
+        if value != 0.0 || parts.is_empty() {
+            parts.push(last.label(&last.format_value(value)));
+        }
+
+        format!("{}{}", sign, parts.join(" "))
+    }
+
+    /// The value, without its label, as it is displayed in the last unit of a
+    /// measurement
+    fn format_value(&self, value: f32) -> String {
+        match self.fraction_format {
+            FractionFormat::Round => self.group_thousands(value.round() as u64),
+            FractionFormat::Truncate => self.group_thousands(value.trunc() as u64),
+            FractionFormat::Decimal => {
+                let digits = (self.precision.max(1) as f32).log10().round() as usize;
+                let formatted = format!("{:.*}", digits, value);
+
+                let (whole, fraction) = formatted
+                    .split_once('.')
+                    .unwrap_or((formatted.as_str(), ""));
+
+                let fraction = if self.keep_denominator {
+                    fraction
+                } else {
+                    fraction.trim_end_matches('0')
+                };
+
+                let whole = self.group_thousands(whole.parse().unwrap_or(0));
+
+                if fraction.is_empty() {
+                    whole
+                } else {
+                    format!(
+                        "{}{}{}",
+                        whole,
+                        decode_text_string(&self.decimal_separator),
+                        fraction
+                    )
+                }
+            }
+            FractionFormat::Fraction => {
+                let denominator = self.precision.max(1) as u64;
+                let mut whole = value.trunc() as u64;
+                let mut numerator = (value.fract() * denominator as f32).round() as u64;
+
+                if numerator == denominator {
+                    whole += 1;
+                    numerator = 0;
+                }
+
+                let mut denominator = denominator;
+
+                if !self.keep_denominator {
+                    let divisor = gcd(numerator, denominator);
+                    numerator /= divisor;
+                    denominator /= divisor;
+                }
+
+                match (whole, numerator) {
+                    (whole, 0) => self.group_thousands(whole),
+                    (0, numerator) => format!("{}/{}", numerator, denominator),
+                    (whole, numerator) => format!(
+                        "{} {}/{}",
+                        self.group_thousands(whole),
+                        numerator,
+                        denominator
+                    ),
+                }
+            }
+        }
+    }
+
+    fn group_thousands(&self, value: u64) -> String {
+        let digits = value.to_string();
+        let separator = decode_text_string(&self.thousands_separator);
+
+        let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+
+        for (i, c) in digits.chars().enumerate() {
+            if i != 0 && (digits.len() - i) % 3 == 0 {
+                grouped.push_str(&separator);
+            }
+
+            grouped.push(c);
+        }
+
+        grouped
+    }
+
+    fn label(&self, value: &str) -> String {
+        match self.label_position {
+            LabelPosition::Suffix => format!(
+                "{}{}{}",
+                value,
+                decode_text_string(&self.prefix_spacing),
+                self.unit()
+            ),
+            LabelPosition::Prefix => format!(
+                "{}{}{}",
+                self.unit(),
+                decode_text_string(&self.suffix_spacing),
+                value
+            ),
+        }
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a.max(1)
+}
+
+#[pdf_enum]
+pub enum FractionFormat {
+    /// Show the value as a decimal, to the precision of the format
+    Decimal = "D",
+
+    /// Show the fractional part as a fraction, with the denominator of the
+    /// format
+    Fraction = "F",
+
+    /// Round the value to a whole number
+    Round = "R",
+
+    /// Truncate the value to a whole number
+    Truncate = "T",
+}
+
+#[pdf_enum]
+pub enum LabelPosition {
+    Suffix = "S",
+    Prefix = "P",
+}
+
+/// A geospatial coordinate system, which maps points of the unit square over a
+/// viewport to latitudes and longitudes
+#[derive(Debug, Clone, PartialEq, FromObj)]
+#[obj_type("Measure", subtype = "GEO")]
+pub struct GeospatialMeasure {
+    /// Pairs of numbers giving the vertices of the region of the unit square
+    /// over which the coordinate system applies
+    ///
+    /// Default value: [0 0 0 1 1 1 1 0]
+    #[field("Bounds", default = vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0])]
+    pub bounds: Vec<f32>,
+
+    /// The geographic or projected coordinate system of the points
+    #[field("GCS")]
+    pub gcs: CoordinateSystem,
+
+    /// The coordinate system in which locations are preferably displayed
+    #[field("DCS")]
+    pub dcs: Option<CoordinateSystem>,
+
+    /// The preferred linear, area and angular units of displayed measurements,
+    /// such as `KM`, `SQKM` and `DEG`
+    #[field("PDU")]
+    pub preferred_units: Option<[String; 3]>,
+
+    /// Pairs of numbers giving the latitude and longitude, in degrees, of each
+    /// point of the LPTS array
+    #[field("GPTS")]
+    pub geographic_points: Vec<f32>,
+
+    /// Pairs of numbers giving the points of the unit square which correspond
+    /// to those of the GPTS array. When absent, the vertices of the bounds are
+    /// used
+    #[field("LPTS")]
+    pub local_points: Option<Vec<f32>>,
+
+    /// (PDF 2.0) A matrix of 12 numbers which transforms projected coordinates
+    /// into three-dimensional model coordinates
+    #[field("PCSM")]
+    pub pcsm: Option<Vec<f32>>,
+}
+
+impl GeospatialMeasure {
+    /// The latitude and longitude of a point of the unit square over the
+    /// viewport, or `None` if it is outside the bounds or there are too few
+    /// points to map it
+    ///
+    /// The mapping is the affine transformation which best fits the local and
+    /// geographic points, by least squares.
+    pub fn to_geographic(&self, unit_point: [f32; 2]) -> Option<GeoPoint> {
+        if !polygon_contains(&self.bounds, unit_point) {
+            return None;
+        }
+
+        let local = self.local_points.as_deref().unwrap_or(&self.bounds[..]);
+
+        let pairs = local
+            .chunks_exact(2)
+            .zip(self.geographic_points.chunks_exact(2))
+            .map(|(local, geographic)| {
+                (
+                    [local[0] as f64, local[1] as f64],
+                    [geographic[0] as f64, geographic[1] as f64],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let latitude = fit_affine(&pairs, |geographic| geographic[0])?;
+        let longitude = fit_affine(&pairs, |geographic| geographic[1])?;
+
+        let [u, v] = [unit_point[0] as f64, unit_point[1] as f64];
+
+        Some(GeoPoint {
+            latitude: latitude[0] * u + latitude[1] * v + latitude[2],
+            longitude: longitude[0] * u + longitude[1] * v + longitude[2],
+        })
+    }
+}
+
+/// The coefficients `[a, b, c]` of the plane `a * u + b * v + c` which best
+/// fits the value of each point, by least squares
+fn fit_affine(pairs: &[([f64; 2], [f64; 2])], value: impl Fn([f64; 2]) -> f64) -> Option<[f64; 3]> {
+    if pairs.len() < 3 {
+        return None;
+    }
+
+    // the normal equations of the fit
+    let mut matrix = [[0.0; 3]; 3];
+    let mut rhs = [0.0; 3];
+
+    for &(local, geographic) in pairs {
+        let row = [local[0], local[1], 1.0];
+        let value = value(geographic);
+
+        for ((matrix_row, rhs), &a) in matrix.iter_mut().zip(rhs.iter_mut()).zip(row.iter()) {
+            for (cell, &b) in matrix_row.iter_mut().zip(row.iter()) {
+                *cell += a * b;
+            }
+
+            *rhs += a * value;
+        }
+    }
+
+    let full = determinant(matrix);
+
+    if full.abs() < f64::EPSILON {
+        return None;
+    }
+
+    // cramer's rule
+    let mut solution = [0.0; 3];
+
+    for (i, coefficient) in solution.iter_mut().enumerate() {
+        let mut replaced = matrix;
+
+        for (row, &value) in replaced.iter_mut().zip(rhs.iter()) {
+            row[i] = value;
+        }
+
+        *coefficient = determinant(replaced) / full;
+    }
+
+    Some(solution)
+}
+
+fn determinant(m: [[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+/// Whether the point lies within the polygon whose vertices are given by
+/// pairs of numbers
+fn polygon_contains(vertices: &[f32], point: [f32; 2]) -> bool {
+    let vertices = vertices.chunks_exact(2).collect::<Vec<&[f32]>>();
+
+    if vertices.len() < 3 {
+        return false;
+    }
+
+    let mut inside = false;
+
+    for (a, b) in vertices.iter().zip(vertices.iter().cycle().skip(1)) {
+        let on_edge = {
+            let cross = (b[0] - a[0]) * (point[1] - a[1]) - (b[1] - a[1]) * (point[0] - a[0]);
+            cross.abs() <= f32::EPSILON
+                && point[0] >= a[0].min(b[0])
+                && point[0] <= a[0].max(b[0])
+                && point[1] >= a[1].min(b[1])
+                && point[1] <= a[1].max(b[1])
+        };
+
+        if on_edge {
+            return true;
+        }
+
+        if (a[1] > point[1]) != (b[1] > point[1])
+            && point[0] < (b[0] - a[0]) * (point[1] - a[1]) / (b[1] - a[1]) + a[0]
+        {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
+
+/// A coordinate system, given by either its EPSG code or its well-known text
+#[derive(Debug, Clone, PartialEq, FromObj)]
+pub struct CoordinateSystem {
+    #[field("Type")]
+    pub kind: CoordinateSystemKind,
+
+    /// The code of the coordinate system in the EPSG registry
+    #[field("EPSG")]
+    pub epsg: Option<i32>,
+
+    /// A description of the coordinate system in the well-known text format
+    #[field("WKT")]
+    pub wkt: Option<String>,
+}
+
+#[pdf_enum]
+pub enum CoordinateSystemKind {
+    /// Locations are given by latitude and longitude
+    Geographic = "GEOGCS",
+
+    /// Locations are given by projecting the earth onto a plane
+    Projected = "PROJCS",
+}
+
+/// A location on the earth, in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPoint {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// A viewport of a page
+#[derive(Debug, Clone)]
+pub struct PageViewport<'a> {
+    /// The index of the page, starting from 0
+    pub page: usize,
+
+    pub viewport: Viewport<'a>,
+}
+
+impl<'a> Parser<'a> {
+    /// Every viewport of the document, with the page on which it appears
+    pub fn viewports(&self) -> Vec<PageViewport<'a>> {
+        self.pages()
+            .iter()
+            .enumerate()
+            .flat_map(|(page, page_object)| {
+                page_object
+                    .vp
+                    .iter()
+                    .flatten()
+                    .map(move |viewport| PageViewport {
+                        page,
+                        viewport: viewport.clone(),
+                    })
+            })
+            .collect()
+    }
+
+    /// The latitude and longitude of a point on a page, in default user space,
+    /// under the geospatial measure of the viewport which contains it
+    pub fn geographic_point(&self, page: usize, point: [f32; 2]) -> Option<GeoPoint> {
+        self.pages()
+            .get(page)?
+            .viewport_at(point)?
+            .to_geographic(point)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    fn measure(source: &[u8]) -> Measure {
+        let mut tokenizer = Tokenizer::new(source);
+
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+        Measure::from_obj(obj, &mut tokenizer).unwrap()
+    }
+
+    #[test]
+    fn rectilinear_distance_and_area() {
+        let measure = measure(
+            b"<< /Type /Measure /R (1 in = 10 ft) \
+               /X [ << /U (ft) /C 0.1388889 /D 100 >> << /U (in) /C 12 /F /R >> ] \
+               /D [ << /U (ft) /C 1 >> << /U (in) /C 12 /F /R >> ] \
+               /A [ << /U (sq ft) /C 1 >> ] /O [ 72 0 ] >>",
+        );
+
+        let measure = match measure {
+            Measure::Rectilinear(measure) => measure,
+            measure => panic!("expected rectilinear measure, found {:?}", measure),
+        };
+
+        let [x, y] = measure.to_measured([144.0, 72.0]);
+        assert!((x - 10.0).abs() < 1e-3);
+        assert!((y - 10.0).abs() < 1e-3);
+
+        assert_eq!(
+            measure.format_distance([0.0, 0.0], [75.6, 0.0]),
+            "10 ft 6 in"
+        );
+
+        let area = measure.area(&[[0.0, 0.0], [72.0, 0.0], [72.0, 72.0], [0.0, 72.0]]);
+        assert!((area - 100.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn number_format() {
+        let format = measure(
+            b"<< /R (1:1) /X [ << /U (mi) /C 1 /D 1000 /RT (,) >> ] /D [ << /U (mi) /C 1 >> ] \
+               /A [ << /U (sq mi) /C 1 >> ] >>",
+        );
+
+        let format = match format {
+            Measure::Rectilinear(measure) => measure.x,
+            measure => panic!("expected rectilinear measure, found {:?}", measure),
+        };
+
+        assert_eq!(NumberFormat::format(12345.5, &format), "12,345.5 mi");
+    }
+
+    #[test]
+    fn geospatial_point() {
+        let measure = measure(
+            b"<< /Type /Measure /Subtype /GEO /GCS << /Type /GEOGCS /EPSG 4326 >> \
+               /GPTS [ 40 -75 41 -75 41 -74 40 -74 ] /LPTS [ 0 0 0 1 1 1 1 0 ] >>",
+        );
+
+        let viewport = Viewport {
+            bbox: Rectangle::new(100.0, 100.0, 300.0, 500.0),
+            name: None,
+            measure: Some(measure),
+            pt_data: None,
+        };
+
+        let point = viewport.to_geographic([200.0, 400.0]).unwrap();
+        assert!((point.latitude - 40.75).abs() < 1e-6);
+        assert!((point.longitude - -74.5).abs() < 1e-6);
+
+        assert_eq!(viewport.to_geographic([50.0, 400.0]), None);
+        assert_eq!(viewport.to_measured([200.0, 400.0]), None);
+    }
+}
@@ -97,6 +97,15 @@ impl<'a> ObjectStreamParser<'a> {
         Ok(parser)
     }
 
+    /// The object numbers of the objects stored in the stream, in ascending
+    /// order
+    pub fn object_numbers(&self) -> Vec<usize> {
+        let mut object_numbers = self.offsets.keys().copied().collect::<Vec<usize>>();
+        object_numbers.sort_unstable();
+
+        object_numbers
+    }
+
     pub fn parse_object(&mut self, reference: Reference) -> PdfResult<Object<'a>> {
         let byte_offset = match self.offsets.get(&reference.object_number) {
             Some(&v) => v,
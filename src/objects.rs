@@ -50,6 +50,47 @@ impl<'a> Object<'a> {
     }
 }
 
+/// Decode a text string, which is encoded in UTF-16BE or (since PDF 2.0) UTF-8
+/// if it begins with the corresponding byte order marker, and otherwise in
+/// PDFDocEncoding
+///
+/// Strings hold each byte as a `char`, so strings in PDFDocEncoding are returned
+/// unchanged, which is correct for the characters it shares with Latin-1.
+pub(crate) fn decode_text_string(s: &str) -> String {
+    let bytes = s.chars().map(|c| c as u8).collect::<Vec<u8>>();
+
+    match bytes.as_slice() {
+        [0xfe, 0xff, rest @ ..] => String::from_utf16_lossy(
+            &rest
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect::<Vec<u16>>(),
+        ),
+        [0xef, 0xbb, 0xbf, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => s.to_owned(),
+    }
+}
+
+/// Encode a text string, the inverse of [`decode_text_string`]
+///
+/// Strings whose characters all fit in a single byte are returned unchanged,
+/// and all others are encoded in UTF-16BE with a byte order marker.
+pub(crate) fn encode_text_string(s: &str) -> String {
+    if s.chars().all(|c| (c as u32) < 256) {
+        return s.to_owned();
+    }
+
+    let mut encoded = String::from("\u{fe}\u{ff}");
+
+    for unit in s.encode_utf16() {
+        let [hi, lo] = unit.to_be_bytes();
+        encoded.push(hi as char);
+        encoded.push(lo as char);
+    }
+
+    encoded
+}
+
 /// A reference to a non-existing object is considered a `null`
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub struct Reference {
@@ -79,9 +120,22 @@ impl<'a, T: FromObj<'a> + Clone> TypedReference<'a, T> {
     }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone)]
 pub struct Dictionary<'a> {
     dict: HashMap<String, Object<'a>>,
+
+    /// The keys in the order they appeared in the file, if that order is being
+    /// preserved
+    ///
+    /// Keys which have since been removed are skipped when iterating.
+    key_order: Option<Vec<String>>,
+}
+
+/// Dictionaries are equal if their entries are, regardless of key order
+impl PartialEq for Dictionary<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.dict == other.dict
+    }
 }
 
 impl fmt::Debug for Dictionary<'_> {
@@ -93,7 +147,7 @@ impl fmt::Debug for Dictionary<'_> {
 
         let mut dictionary = f.debug_struct("Dictionary");
 
-        for (key, value) in &self.dict {
+        for (key, value) in self.iter() {
             dictionary.field(key, &value);
         }
 
@@ -103,17 +157,113 @@ impl fmt::Debug for Dictionary<'_> {
 
 impl<'a> Dictionary<'a> {
     pub fn new(dict: HashMap<String, Object<'a>>) -> Self {
-        Self { dict }
+        Self {
+            dict,
+            key_order: None,
+        }
     }
 
-    pub fn empty() -> Self {
+    /// Create a dictionary which remembers the original order of its keys
+    ///
+    /// `key_order` should contain each key of `dict` once.
+    pub fn with_key_order(dict: HashMap<String, Object<'a>>, key_order: Vec<String>) -> Self {
+        debug_assert_eq!(dict.len(), key_order.len());
+
         Self {
-            dict: HashMap::new(),
+            dict,
+            key_order: Some(key_order),
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(HashMap::new())
+    }
+
+    /// Whether the original order of the keys is known
+    pub fn preserves_key_order(&self) -> bool {
+        self.key_order.is_some()
+    }
+
+    /// The keys of the dictionary, in their original order if it was
+    /// preserved and in arbitrary order otherwise
+    pub fn keys(&self) -> Vec<&str> {
+        match &self.key_order {
+            Some(key_order) => key_order
+                .iter()
+                .filter(|key| self.dict.contains_key(*key))
+                .map(String::as_str)
+                .collect(),
+            None => self.dict.keys().map(String::as_str).collect(),
         }
     }
 
-    pub fn entries(self) -> impl Iterator<Item = (String, Object<'a>)> {
-        self.dict.into_iter()
+    /// Iterate over the entries without resolving or removing them, in their
+    /// original order if it was preserved
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Object<'a>)> {
+        self.keys()
+            .into_iter()
+            .map(move |key| (key, &self.dict[key]))
+    }
+
+    /// Set the value of `key`, returning its previous value
+    ///
+    /// Keys which are new to the dictionary come after all others in its key
+    /// order, if that order is preserved.
+    pub fn insert(&mut self, key: impl Into<String>, value: Object<'a>) -> Option<Object<'a>> {
+        let key = key.into();
+
+        if let Some(key_order) = &mut self.key_order {
+            if !key_order.contains(&key) {
+                key_order.push(key.clone());
+            }
+        }
+
+        self.dict.insert(key, value)
+    }
+
+    /// The value of `key`, without resolving or removing it
+    pub fn peek(&self, key: &str) -> Option<&Object<'a>> {
+        self.dict.get(key)
+    }
+
+    pub fn peek_mut(&mut self, key: &str) -> Option<&mut Object<'a>> {
+        self.dict.get_mut(key)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Object<'a>> {
+        self.dict.remove(key)
+    }
+
+    /// The values of the dictionary, in the original order of their keys if it
+    /// was preserved and in order of their keys otherwise, so that walks over
+    /// a document visit objects in the same order every time
+    pub(crate) fn values_mut(&mut self) -> impl Iterator<Item = &mut Object<'a>> {
+        let mut entries = self.dict.iter_mut().collect::<Vec<_>>();
+
+        match &self.key_order {
+            Some(key_order) => {
+                entries.sort_by_key(|(key, _)| key_order.iter().position(|k| k == *key))
+            }
+            None => entries.sort_by_key(|(key, _)| *key),
+        }
+
+        entries.into_iter().map(|(_, value)| value)
+    }
+
+    /// The remaining entries, in their original order if it was preserved
+    pub fn entries(mut self) -> impl Iterator<Item = (String, Object<'a>)> {
+        let ordered: Vec<(String, Object<'a>)> = match self.key_order.take() {
+            Some(key_order) => key_order
+                .into_iter()
+                .filter_map(|key| {
+                    let value = self.dict.remove(&key)?;
+                    Some((key, value))
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        ordered.into_iter().chain(self.dict)
     }
 
     pub fn get<T: FromObj<'a>>(
@@ -545,3 +695,133 @@ impl<'a, T: FromObj<'a> + Sync, const N: usize> FromObj<'a> for [T; N] {
         }
     }
 }
+
+/// Conversion of a value to the object it would be written as, the inverse of
+/// [`FromObj`]
+pub trait ToObj<'a> {
+    fn to_obj(&self) -> PdfResult<Object<'a>>;
+}
+
+impl<'a> ToObj<'a> for i32 {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(Object::Integer(*self))
+    }
+}
+
+impl<'a> ToObj<'a> for u32 {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(Object::Integer(i32::try_from(*self)?))
+    }
+}
+
+impl<'a> ToObj<'a> for usize {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(Object::Integer(i32::try_from(*self)?))
+    }
+}
+
+impl<'a> ToObj<'a> for f32 {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(Object::Real(*self))
+    }
+}
+
+impl<'a> ToObj<'a> for bool {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(if *self { Object::True } else { Object::False })
+    }
+}
+
+impl<'a> ToObj<'a> for Name {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(Object::Name(self.0.clone()))
+    }
+}
+
+impl<'a> ToObj<'a> for String {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(Object::String(self.clone()))
+    }
+}
+
+impl<'a> ToObj<'a> for Stream<'a> {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(Object::Stream(self.clone()))
+    }
+}
+
+impl<'a> ToObj<'a> for Object<'a> {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(self.clone())
+    }
+}
+
+impl<'a> ToObj<'a> for Reference {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(Object::Reference(*self))
+    }
+}
+
+impl<'a> ToObj<'a> for Dictionary<'a> {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(Object::Dictionary(self.clone()))
+    }
+}
+
+impl<'a, T: ToObj<'a>> ToObj<'a> for Vec<T> {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        self.as_slice().to_obj()
+    }
+}
+
+impl<'a, T: ToObj<'a>> ToObj<'a> for [T] {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(Object::Array(
+            self.iter().map(T::to_obj).collect::<PdfResult<_>>()?,
+        ))
+    }
+}
+
+impl<'a, T: ToObj<'a>, const N: usize> ToObj<'a> for [T; N] {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        self.as_slice().to_obj()
+    }
+}
+
+impl<'a, T: ToObj<'a>> ToObj<'a> for Rc<T> {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        T::to_obj(self)
+    }
+}
+
+impl<'a, T: ToObj<'a>> ToObj<'a> for Box<T> {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        T::to_obj(self)
+    }
+}
+
+impl<'a> ToObj<'a> for Date {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        Ok(Object::String(self.to_pdf_string()))
+    }
+}
+
+impl<'a, T: FromObj<'a> + ToObj<'a>> ToObj<'a> for TypedReference<'a, T> {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        match self {
+            TypedReference::Indirect { reference, .. } => Ok(Object::Reference(*reference)),
+            TypedReference::Direct(t) => t.to_obj(),
+        }
+    }
+}
+
+impl<'a, T: ToObj<'a>> ToObj<'a> for HashMap<String, T> {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        let dict = self
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), value.to_obj()?)))
+            .collect::<PdfResult<HashMap<String, Object<'a>>>>()?;
+
+        Ok(Object::Dictionary(Dictionary::new(dict)))
+    }
+}
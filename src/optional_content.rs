@@ -1,7 +1,231 @@
-use crate::objects::Object;
+use std::rc::Rc;
+use std::{collections::HashSet, path::PathBuf};
 
-#[derive(Debug, Clone, FromObj)]
-pub struct OptionalContent;
+use crate::{
+    assert_empty,
+    error::{ParseError, PdfResult},
+    objects::{Dictionary, Object, Reference},
+    page::PageObject,
+    render::Renderer,
+    writer, FromObj, Parser, Resolve,
+};
+
+/// An optional content group or optional content membership dictionary,
+/// specifying the optional content properties of some content
+#[derive(Debug, Clone)]
+pub enum OptionalContent {
+    /// Optional content groups are identified by their indirect reference, as
+    /// they shall always be indirect objects
+    Group(Reference),
+    Membership(OptionalContentMembership),
+}
+
+impl<'a> FromObj<'a> for OptionalContent {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let reference = match obj {
+            Object::Reference(reference) => Some(reference),
+            _ => None,
+        };
+
+        let mut dict = resolver.assert_dict(obj)?;
+
+        match dict.expect_name("Type", resolver)?.as_str() {
+            "OCG" => match reference {
+                Some(reference) => Ok(OptionalContent::Group(reference)),
+                None => anyhow::bail!("optional content group must be an indirect object"),
+            },
+            "OCMD" => Ok(OptionalContent::Membership(
+                OptionalContentMembership::from_dict(dict, resolver)?,
+            )),
+            found => anyhow::bail!(ParseError::MismatchedTypeKey {
+                expected: "OCG",
+                found: found.to_owned(),
+            }),
+        }
+    }
+}
+
+/// An optional content membership dictionary, used to express more complex
+/// visibility policies than a single optional content group
+#[derive(Debug, Clone)]
+pub struct OptionalContentMembership {
+    /// The optional content groups whose states shall determine the visibility
+    /// of content controlled by this membership dictionary
+    ///
+    /// Null values or references to deleted objects shall be ignored. If this
+    /// entry is not present, is an empty array, or contains references only to
+    /// null or deleted objects, the membership dictionary shall have no effect
+    /// on the visibility of any content.
+    pub groups: Vec<Reference>,
+
+    /// A name specifying the visibility policy for content belonging to this
+    /// membership dictionary
+    pub policy: VisibilityPolicy,
+}
+
+impl OptionalContentMembership {
+    fn from_dict<'a>(mut dict: Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let groups = match dict.get::<Object>("OCGs", resolver)? {
+            Some(Object::Array(arr)) => arr
+                .into_iter()
+                .filter_map(|obj| match obj {
+                    Object::Reference(reference) => Some(reference),
+                    _ => None,
+                })
+                .collect(),
+            Some(Object::Reference(reference)) => vec![reference],
+            Some(Object::Null) | None => Vec::new(),
+            Some(obj) => anyhow::bail!("expected optional content groups, found {:?}", obj),
+        };
+
+        let policy = dict.get("P", resolver)?.unwrap_or_default();
+
+        assert_empty(dict);
+
+        Ok(Self { groups, policy })
+    }
+}
+
+#[pdf_enum]
+#[derive(Default)]
+pub enum VisibilityPolicy {
+    /// Visible only if all of the entries in OCGs are ON
+    AllOn = "AllOn",
+
+    /// Visible if any of the entries in OCGs are ON
+    #[default]
+    AnyOn = "AnyOn",
+
+    /// Visible if any of the entries in OCGs are OFF
+    AnyOff = "AnyOff",
+
+    /// Visible only if all of the entries in OCGs are OFF
+    AllOff = "AllOff",
+}
+
+/// The ON/OFF state of the optional content groups in a document, used to
+/// determine whether optional content is painted
+#[derive(Debug, Clone)]
+pub struct OptionalContentState {
+    off: HashSet<Reference>,
+
+    /// Whether content which is not associated with any optional content group
+    /// shall be painted
+    pub(crate) show_unmarked: bool,
+}
+
+impl OptionalContentState {
+    /// Every optional content group is ON. This is equivalent to ignoring
+    /// optional content entirely
+    pub fn all_on() -> Self {
+        Self {
+            off: HashSet::new(),
+            show_unmarked: true,
+        }
+    }
+
+    /// The state described by the document's default viewing configuration
+    pub fn from_default_config(properties: &OptionalContentProperties) -> Self {
+        let config = &properties.default_config;
+
+        let mut off = match config.base_state.unwrap_or_default() {
+            OptionalContentBaseState::Off => {
+                properties.optional_content_groups.iter().copied().collect()
+            }
+            OptionalContentBaseState::On | OptionalContentBaseState::Unchanged => HashSet::new(),
+        };
+
+        for group in config.on.iter().flatten() {
+            off.remove(group);
+        }
+
+        off.extend(config.off.iter().flatten().copied());
+
+        Self {
+            off,
+            show_unmarked: true,
+        }
+    }
+
+    /// Only content belonging to `group` is painted. All other groups are OFF,
+    /// and content not associated with any group is hidden
+    pub fn only(group: Reference, properties: &OptionalContentProperties) -> Self {
+        Self {
+            off: properties
+                .optional_content_groups
+                .iter()
+                .copied()
+                .filter(|&other| other != group)
+                .collect(),
+            show_unmarked: false,
+        }
+    }
+
+    pub fn set(&mut self, group: Reference, on: bool) {
+        if on {
+            self.off.remove(&group);
+        } else {
+            self.off.insert(group);
+        }
+    }
+
+    pub fn is_on(&self, group: Reference) -> bool {
+        !self.off.contains(&group)
+    }
+
+    pub fn is_visible(&self, content: &OptionalContent) -> bool {
+        match content {
+            OptionalContent::Group(group) => self.is_on(*group),
+            OptionalContent::Membership(OptionalContentMembership { groups, policy }) => {
+                if groups.is_empty() {
+                    return true;
+                }
+
+                let mut states = groups.iter().map(|&group| self.is_on(group));
+
+                match policy {
+                    VisibilityPolicy::AllOn => states.all(|on| on),
+                    VisibilityPolicy::AnyOn => states.any(|on| on),
+                    VisibilityPolicy::AnyOff => states.any(|on| !on),
+                    VisibilityPolicy::AllOff => states.all(|on| !on),
+                }
+            }
+        }
+    }
+}
+
+/// An optional content group (layer) of the document, with its state under
+/// the default viewing configuration
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub group: Reference,
+
+    /// The name of the group, as presented in a viewer's user interface
+    pub name: String,
+
+    /// The intended use of the graphics in the group
+    pub intent: Vec<Intent>,
+
+    /// Whether the group is ON when the document is opened
+    pub is_on: bool,
+
+    /// Whether the default configuration prevents the state of the group from
+    /// being changed through a viewer's user interface
+    pub is_locked: bool,
+}
+
+/// A single optional content group of a page, rendered in isolation
+#[derive(Debug, Clone)]
+pub struct ExportedLayer {
+    /// The name of the optional content group
+    pub name: String,
+
+    /// The optional content group this layer was rendered from
+    pub group: Reference,
+
+    /// The location the rendered layer was written to
+    pub path: PathBuf,
+}
 
 #[derive(Debug, FromObj)]
 pub struct OptionalContentProperties<'a> {
@@ -9,19 +233,30 @@ pub struct OptionalContentProperties<'a> {
     /// document, in any order. Every optional content group shall be included
     /// in this array.
     #[field("OCGs")]
-    optional_content_groups: Vec<Object<'a>>,
+    pub(crate) optional_content_groups: Vec<Reference>,
 
     /// The default viewing optional content configuration dictionary
     #[field("D")]
-    default_config: OptionalContentConfiguration<'a>,
+    pub(crate) default_config: OptionalContentConfiguration<'a>,
 
     /// An array of alternate optional content configuration dictionaries
     #[field("Configs")]
     alternate_configs: Option<Vec<OptionalContentConfiguration<'a>>>,
 }
 
+impl OptionalContentProperties<'_> {
+    /// Whether the default configuration locks `group`
+    pub(crate) fn is_locked(&self, group: Reference) -> bool {
+        self.default_config
+            .locked
+            .iter()
+            .flatten()
+            .any(|obj| matches!(obj, Object::Reference(reference) if *reference == group))
+    }
+}
+
 #[derive(Debug, FromObj)]
-struct OptionalContentConfiguration<'a> {
+pub(crate) struct OptionalContentConfiguration<'a> {
     /// A name for the configuration, suitable for presentation in a user interface.
     #[field("Name")]
     name: Option<String>,
@@ -52,17 +287,15 @@ struct OptionalContentConfiguration<'a> {
     /// this configuration is applied.
     ///
     /// If the BaseState entry is ON, this entry is redundant.
-    // todo: Vec<OptionalContentGroup>
     #[field("ON")]
-    on: Option<Vec<Object<'a>>>,
+    on: Option<Vec<Reference>>,
 
     /// An array of optional content groups whose state shall be set to OFF when
     /// this configuration is applied.
     ///
     /// If the BaseState entry is OFF, this entry is redundant.
-    // todo: Vec<OptionalContentGroup>
     #[field("OFF")]
-    off: Option<Vec<Object<'a>>>,
+    off: Option<Vec<Reference>>,
 
     /// A single intent name or an array containing any combination of names. It
     /// shall be used to determine which optional content groups’ states to consider
@@ -153,8 +386,44 @@ struct OptionalContentConfiguration<'a> {
     locked: Option<Vec<Object<'a>>>,
 }
 
-#[derive(Debug, Clone, FromObj)]
-pub struct OptionalContentGroup;
+#[derive(Debug, Clone)]
+pub struct OptionalContentGroup {
+    /// The name of the optional content group, suitable for presentation in a
+    /// conforming reader's user interface
+    pub name: String,
+
+    /// A single intent name or an array containing any combination of names.
+    /// PDF defines two names, View and Design, that may indicate the intended
+    /// use of the graphics in the group.
+    ///
+    /// Default value: View
+    pub intent: Vec<Intent>,
+}
+
+impl<'a> FromObj<'a> for OptionalContentGroup {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        dict.expect_type("OCG", resolver, true)?;
+
+        let name = dict.expect_string("Name", resolver)?;
+        let intent = match dict.get_object("Intent", resolver)? {
+            Some(Object::Array(arr)) => arr
+                .into_iter()
+                .map(|obj| Intent::from_obj(obj, resolver))
+                .collect::<PdfResult<Vec<Intent>>>()?,
+            Some(obj) => vec![Intent::from_obj(obj, resolver)?],
+            None => vec![Intent::View],
+        };
+
+        // todo: parse usage dictionary
+        dict.get_dict("Usage", resolver)?;
+
+        assert_empty(dict);
+
+        Ok(Self { name, intent })
+    }
+}
 
 #[derive(Debug)]
 struct OptionalContentUsage;
@@ -184,7 +453,7 @@ enum OptionalContentBaseState {
 
 #[pdf_enum]
 #[derive(Default)]
-enum Intent {
+pub enum Intent {
     /// Used for interactive use by document consumers
     #[default]
     View = "View",
@@ -195,3 +464,318 @@ enum Intent {
     /// Indicates the set of all intents, including those not yet defined
     All = "All",
 }
+
+impl<'a> Parser<'a> {
+    /// The optional content state described by the document's default viewing
+    /// configuration, or every group ON if the document has no optional content
+    pub fn optional_content_state(&self) -> OptionalContentState {
+        match &self.catalog.oc_properties {
+            Some(properties) => OptionalContentState::from_default_config(properties),
+            None => OptionalContentState::all_on(),
+        }
+    }
+
+    /// The optional content groups (layers) of the document, with their names,
+    /// intents and states under the default viewing configuration
+    pub fn layers(&mut self) -> PdfResult<Vec<Layer>> {
+        let properties = match &self.catalog.oc_properties {
+            Some(properties) => properties,
+            None => return Ok(Vec::new()),
+        };
+
+        let state = OptionalContentState::from_default_config(properties);
+        let mut layers = Vec::with_capacity(properties.optional_content_groups.len());
+
+        for &group in &properties.optional_content_groups {
+            let OptionalContentGroup { name, intent } =
+                OptionalContentGroup::from_obj(Object::Reference(group), &mut self.lexer)?;
+
+            layers.push(Layer {
+                group,
+                name,
+                intent,
+                is_on: state.is_on(group),
+                is_locked: properties.is_locked(group),
+            });
+        }
+
+        Ok(layers)
+    }
+
+    /// Make `state` the default viewing configuration of the document's
+    /// optional content, returning the file with an incremental update
+    /// appended
+    ///
+    /// Start from [`Parser::optional_content_state`] and turn layers ON or OFF
+    /// with [`OptionalContentState::set`] to ship the document with them shown
+    /// or hidden.
+    pub fn set_default_layer_states(&mut self, state: &OptionalContentState) -> PdfResult<Vec<u8>> {
+        let groups = match &self.catalog.oc_properties {
+            Some(properties) => properties.optional_content_groups.clone(),
+            None => anyhow::bail!("document has no optional content"),
+        };
+
+        writer::set_default_layer_states(&self.trailer, &groups, state, &mut self.lexer)
+    }
+
+    /// Render each optional content group (layer) of `page` in isolation,
+    /// writing one PNG per group into `dir`
+    ///
+    /// Content which does not belong to any optional content group is omitted
+    /// from every layer.
+    pub fn export_layers(
+        &mut self,
+        page: &Rc<PageObject<'a>>,
+        dir: impl AsRef<std::path::Path>,
+    ) -> PdfResult<Vec<ExportedLayer>> {
+        let properties = match &self.catalog.oc_properties {
+            Some(properties) => properties,
+            None => return Ok(Vec::new()),
+        };
+
+        let states = properties
+            .optional_content_groups
+            .iter()
+            .map(|&group| (group, OptionalContentState::only(group, properties)))
+            .collect::<Vec<_>>();
+
+        let mut layers = Vec::with_capacity(states.len());
+
+        for (idx, (group, state)) in states.into_iter().enumerate() {
+            let name =
+                OptionalContentGroup::from_obj(Object::Reference(group), &mut self.lexer)?.name;
+
+            let file_name = name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect::<String>();
+
+            let path = dir.as_ref().join(format!("{:03}-{}.png", idx, file_name));
+
+            let mut content = self.page_contents(page)?;
+
+            Renderer::new(&mut content, &mut self.lexer, Rc::clone(page))
+                .with_optional_content(state)
+                .render_to_image(&path)?;
+
+            layers.push(ExportedLayer { name, group, path });
+        }
+
+        Ok(layers)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+    use crate::{
+        test::{painted_pixels, TestFile},
+        ParseOptions,
+    };
+
+    fn reference(object_number: usize) -> Reference {
+        Reference {
+            object_number,
+            generation: 0,
+        }
+    }
+
+    fn properties(dict: &'static [u8]) -> OptionalContentProperties<'static> {
+        let mut tokenizer = Tokenizer::new(dict);
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+
+        OptionalContentProperties::from_obj(obj, &mut tokenizer).unwrap()
+    }
+
+    fn membership(policy: VisibilityPolicy) -> OptionalContent {
+        OptionalContent::Membership(OptionalContentMembership {
+            groups: vec![reference(1), reference(2)],
+            policy,
+        })
+    }
+
+    #[test]
+    fn base_state_on() {
+        let state = OptionalContentState::from_default_config(&properties(
+            b"<< /OCGs [1 0 R 2 0 R 3 0 R] /D << /OFF [2 0 R] >> >>",
+        ));
+
+        assert!(state.is_on(reference(1)));
+        assert!(!state.is_on(reference(2)));
+        assert!(state.is_on(reference(3)));
+        assert!(state.show_unmarked);
+    }
+
+    #[test]
+    fn base_state_off() {
+        let state = OptionalContentState::from_default_config(&properties(
+            b"<< /OCGs [1 0 R 2 0 R 3 0 R] /D << /BaseState /OFF /ON [1 0 R 3 0 R] /OFF [3 0 R] >> >>",
+        ));
+
+        assert!(state.is_on(reference(1)));
+        assert!(!state.is_on(reference(2)));
+
+        // the OFF array is applied after the ON array
+        assert!(!state.is_on(reference(3)));
+    }
+
+    #[test]
+    fn only_one_group() {
+        let state = OptionalContentState::only(
+            reference(2),
+            &properties(b"<< /OCGs [1 0 R 2 0 R 3 0 R] /D << >> >>"),
+        );
+
+        assert!(!state.is_on(reference(1)));
+        assert!(state.is_on(reference(2)));
+        assert!(!state.is_on(reference(3)));
+        assert!(!state.show_unmarked);
+    }
+
+    #[test]
+    fn visibility_policies() {
+        let mut state = OptionalContentState::all_on();
+        state.set(reference(2), false);
+
+        assert!(state.is_visible(&OptionalContent::Group(reference(1))));
+        assert!(!state.is_visible(&OptionalContent::Group(reference(2))));
+
+        assert!(!state.is_visible(&membership(VisibilityPolicy::AllOn)));
+        assert!(state.is_visible(&membership(VisibilityPolicy::AnyOn)));
+        assert!(state.is_visible(&membership(VisibilityPolicy::AnyOff)));
+        assert!(!state.is_visible(&membership(VisibilityPolicy::AllOff)));
+
+        state.set(reference(1), false);
+
+        assert!(!state.is_visible(&membership(VisibilityPolicy::AnyOn)));
+        assert!(state.is_visible(&membership(VisibilityPolicy::AllOff)));
+
+        // a membership dictionary without groups has no effect
+        assert!(
+            state.is_visible(&OptionalContent::Membership(OptionalContentMembership {
+                groups: Vec::new(),
+                policy: VisibilityPolicy::AllOn,
+            }))
+        );
+    }
+
+    #[test]
+    fn export_layers() {
+        let content = b"/OC /L1 BDC 1 0 0 rg 0 0 10 10 re f EMC \
+            /OC /L2 BDC 0 0 1 rg 20 0 10 10 re f EMC \
+            0 g 40 0 10 10 re f";
+
+        let mut file = TestFile::new();
+        file.object(
+            1,
+            b"<< /Type /Catalog /Pages 2 0 R \
+              /OCProperties << /OCGs [5 0 R 6 0 R] /D << /BaseState /OFF >> >> >>",
+        );
+        file.object(2, b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>");
+        file.object(
+            3,
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 60 20] /Contents 4 0 R \
+              /Resources << /Properties << /L1 5 0 R /L2 6 0 R >> >> >>",
+        );
+        file.stream(4, &format!("<< /Length {} >>", content.len()), content);
+        file.object(5, b"<< /Type /OCG /Name (Red squares) >>");
+        file.object(6, b"<< /Type /OCG /Name (Blue) >>");
+
+        let mut parser =
+            Parser::from_bytes(file.with_xref_table(), ParseOptions::default()).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("pdf-export-layers-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let page = parser.pages().remove(0);
+        let layers = parser.export_layers(&page, &dir).unwrap();
+
+        assert_eq!(
+            layers
+                .iter()
+                .map(|layer| layer.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Red squares", "Blue"]
+        );
+        assert_eq!(layers[0].path, dir.join("000-Red_squares.png"));
+        assert_eq!(layers[1].path, dir.join("001-Blue.png"));
+
+        // each image shows only the 10x10 square of its own layer, even though
+        // the default configuration hides both layers, and not the unmarked one
+        assert_eq!(painted_pixels(&layers[0].path), 100);
+        assert_eq!(painted_pixels(&layers[1].path), 100);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn layers() {
+        let mut file = TestFile::new();
+        file.object(1, b"<< /Type /Catalog /Pages 2 0 R /OCProperties 3 0 R >>");
+        file.object(2, b"<< /Type /Pages /Kids [] /Count 0 >>");
+        file.object(
+            3,
+            b"<< /OCGs [4 0 R 5 0 R] /D << /Name (Default) /BaseState /OFF /ON [4 0 R] \
+              /Locked [5 0 R] >> >>",
+        );
+        file.object(4, b"<< /Type /OCG /Name (Text) /Intent /Design >>");
+        file.object(5, b"<< /Type /OCG /Name (Images) >>");
+
+        let mut parser =
+            Parser::from_bytes(file.with_xref_table(), ParseOptions::default()).unwrap();
+
+        let layers = |parser: &mut Parser| {
+            parser
+                .layers()
+                .unwrap()
+                .into_iter()
+                .map(|layer| (layer.name, layer.intent, layer.is_on, layer.is_locked))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            layers(&mut parser),
+            [
+                ("Text".to_owned(), vec![Intent::Design], true, false),
+                ("Images".to_owned(), vec![Intent::View], false, true),
+            ]
+        );
+
+        let reference = |object_number| Reference {
+            object_number,
+            generation: 0,
+        };
+
+        let mut state = parser.optional_content_state();
+        state.set(reference(4), false);
+        state.set(reference(5), true);
+
+        let updated = parser.set_default_layer_states(&state).unwrap();
+        let mut parser = Parser::from_bytes(updated, ParseOptions::default()).unwrap();
+
+        assert_eq!(
+            layers(&mut parser),
+            [
+                ("Text".to_owned(), vec![Intent::Design], false, false),
+                ("Images".to_owned(), vec![Intent::View], true, true),
+            ]
+        );
+
+        // the other entries of the configuration are kept
+        let mut properties = parser
+            .lexer
+            .assert_dict(Object::Reference(reference(3)))
+            .unwrap();
+        let mut config = properties.expect_dict("D", &mut parser.lexer).unwrap();
+
+        assert_eq!(
+            config.get_string("Name", &mut parser.lexer).unwrap(),
+            Some("Default".to_owned())
+        );
+        assert_eq!(
+            config.get_name("BaseState", &mut parser.lexer).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{actions::Actions, destination::Destination, objects::Reference};
+
+/// The outline dictionary, which is the root of the document's outline
+/// hierarchy
+#[derive(Debug, Clone, FromObj)]
+#[obj_type("Outlines")]
+pub(crate) struct DocumentOutline {
+    /// An indirect reference to the first top-level item in the outline
+    ///
+    /// Required if there are any open or closed outline entries
+    #[field("First")]
+    pub first: Option<Reference>,
+
+    /// An indirect reference to the last top-level item in the outline
+    ///
+    /// Required if there are any open or closed outline entries
+    #[field("Last")]
+    last: Option<Reference>,
+
+    /// Total number of visible outline items at all levels of the outline. The
+    /// value cannot be negative. This entry shall be omitted if there are no
+    /// open outline items
+    #[field("Count")]
+    pub count: Option<i32>,
+}
+
+/// An outline item dictionary, describing a single item of the outline
+/// hierarchy
+///
+/// The items at each level of the hierarchy form a linked list, chained
+/// together through their Prev and Next entries and accessed through the First
+/// and Last entries of the parent item or outline dictionary.
+#[derive(Debug, FromObj)]
+pub(crate) struct OutlineItemDictionary<'a> {
+    /// The text that shall be displayed on the screen for this item
+    #[field("Title")]
+    pub title: String,
+
+    /// The parent of this item in the outline hierarchy. The parent of a
+    /// top-level item shall be the outline dictionary itself
+    #[field("Parent")]
+    parent: Reference,
+
+    /// The previous item at this outline level
+    #[field("Prev")]
+    prev: Option<Reference>,
+
+    /// The next item at this outline level
+    #[field("Next")]
+    pub next: Option<Reference>,
+
+    /// The first of this item's immediate children in the outline hierarchy
+    ///
+    /// Required if the item has any descendants
+    #[field("First")]
+    pub first: Option<Reference>,
+
+    /// The last of this item's immediate children in the outline hierarchy
+    ///
+    /// Required if the item has any descendants
+    #[field("Last")]
+    last: Option<Reference>,
+
+    /// If the outline item is open, the sum of the number of visible
+    /// descendent outline items at all levels. If the outline item is closed,
+    /// a negative integer whose absolute value specifies how many descendants
+    /// would appear if the item were reopened
+    #[field("Count")]
+    pub count: Option<i32>,
+
+    /// The destination that shall be displayed when this item is activated
+    ///
+    /// This entry shall not be present if an A entry is present
+    #[field("Dest")]
+    pub dest: Option<Destination>,
+
+    /// The action that shall be performed when this item is activated
+    ///
+    /// This entry shall not be present if a Dest entry is present
+    #[field("A")]
+    pub a: Option<Actions<'a>>,
+
+    /// The structure element to which the item refers
+    #[field("SE")]
+    se: Option<Reference>,
+
+    /// An array of three numbers in the range 0.0 to 1.0, representing the
+    /// components in the DeviceRGB colour space of the colour that shall be
+    /// used for the outline entry's text
+    ///
+    /// Default value: [0.0 0.0 0.0]
+    #[field("C", default = [0.0, 0.0, 0.0])]
+    pub color: [f32; 3],
+
+    /// A set of flags specifying style characteristics for displaying the
+    /// outline item's text
+    ///
+    /// Default value: 0
+    #[field("F", default = 0)]
+    flags: u32,
+}
+
+impl OutlineItemDictionary<'_> {
+    const ITALIC: u32 = 1 << 0;
+    const BOLD: u32 = 1 << 1;
+
+    pub fn is_italic(&self) -> bool {
+        self.flags & Self::ITALIC != 0
+    }
+
+    pub fn is_bold(&self) -> bool {
+        self.flags & Self::BOLD != 0
+    }
+
+    /// The destination of the item, given either directly or by a go-to action
+    pub fn destination(&self) -> Option<&Destination> {
+        match (&self.dest, &self.a) {
+            (Some(dest), _) => Some(dest),
+            (None, Some(action)) => action.destination(),
+            (None, None) => None,
+        }
+    }
+}
@@ -0,0 +1,438 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    content::{ContentLexer, ContentToken, PdfGraphicsOperator},
+    data_structures::Matrix,
+    error::PdfResult,
+    geometry::Point,
+    objects::Object,
+    Resolve,
+};
+
+use super::OutlineItem;
+
+/// Lines shown at least this many times larger than body text are considered
+/// to be headings
+const HEADING_SIZE_RATIO: f32 = 1.15;
+
+/// The number of distinct heading sizes, and so the number of outline levels
+const MAX_HEADING_LEVELS: usize = 3;
+
+/// Longer lines are assumed to be emphasized text rather than headings
+const MAX_HEADING_LEN: usize = 200;
+
+/// Adjustments in a TJ array more negative than this, in thousandths of a unit
+/// of text space, are treated as a word break
+const WORD_BREAK_THRESHOLD: f32 = -200.0;
+
+/// A run of text shown on a single baseline at a single size
+///
+/// Strings are taken as-is and are not decoded through the font's encoding or
+/// ToUnicode CMap.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TextLine {
+    pub text: String,
+
+    /// The font size, scaled into default user space
+    pub size: f32,
+
+    /// The vertical coordinate of the baseline in default user space
+    pub baseline: f32,
+}
+
+impl TextLine {
+    /// Collect the lines of text shown by a content stream, in the order in
+    /// which they are shown
+    pub fn from_content<'a>(
+        content: ContentLexer<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Vec<Self>> {
+        #[cfg(feature = "bench")]
+        let _timer = crate::bench::StageTimer::start(crate::bench::Stage::Extract);
+
+        let mut collector = TextLineCollector::new();
+        let mut operands: Vec<Object<'a>> = Vec::new();
+
+        // malformed content is skipped, so that a single bad operator does
+        // not hide the rest of the page's text
+        for token in content {
+            let operator = match token {
+                Ok(ContentToken::Object(obj)) => {
+                    operands.push(obj);
+                    continue;
+                }
+                Ok(ContentToken::Operator(operator)) => operator,
+                Ok(ContentToken::InlineImage(..)) | Err(..) => continue,
+            };
+
+            let _ = collector.apply(operator, &mut operands, resolver);
+
+            operands.clear();
+        }
+
+        Ok(collector.lines)
+    }
+}
+
+/// Tracks just enough of the graphics and text state to know where, and at
+/// what size, text is shown
+struct TextLineCollector {
+    ctm: Matrix,
+    ctm_stack: Vec<Matrix>,
+    text_matrix: Matrix,
+    text_line_matrix: Matrix,
+    font_size: f32,
+    leading: f32,
+
+    /// The origin of the last string shown, in default user space
+    last_origin: Option<Point>,
+    lines: Vec<TextLine>,
+}
+
+impl TextLineCollector {
+    fn new() -> Self {
+        Self {
+            ctm: Matrix::identity(),
+            ctm_stack: Vec::new(),
+            text_matrix: Matrix::identity(),
+            text_line_matrix: Matrix::identity(),
+            font_size: 0.0,
+            leading: 0.0,
+            last_origin: None,
+            lines: Vec::new(),
+        }
+    }
+
+    fn apply<'a>(
+        &mut self,
+        operator: PdfGraphicsOperator,
+        operands: &mut Vec<Object<'a>>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<()> {
+        match operator {
+            PdfGraphicsOperator::q => self.ctm_stack.push(self.ctm),
+            PdfGraphicsOperator::Q => {
+                if let Some(ctm) = self.ctm_stack.pop() {
+                    self.ctm = ctm;
+                }
+            }
+            PdfGraphicsOperator::cm => {
+                let [a, b, c, d, e, f] = pop_numbers(operands, resolver)?;
+
+                self.ctm = Matrix::new(a, b, c, d, e, f) * self.ctm;
+            }
+            PdfGraphicsOperator::BT => {
+                self.text_matrix = Matrix::identity();
+                self.text_line_matrix = Matrix::identity();
+            }
+            PdfGraphicsOperator::Tf => {
+                let [size] = pop_numbers(operands, resolver)?;
+
+                self.font_size = size;
+            }
+            PdfGraphicsOperator::TL => {
+                let [leading] = pop_numbers(operands, resolver)?;
+
+                self.leading = leading;
+            }
+            PdfGraphicsOperator::Td => {
+                let [tx, ty] = pop_numbers(operands, resolver)?;
+
+                self.move_to_next_line(tx, ty);
+            }
+            PdfGraphicsOperator::TD => {
+                let [tx, ty] = pop_numbers(operands, resolver)?;
+
+                self.leading = -ty;
+                self.move_to_next_line(tx, ty);
+            }
+            PdfGraphicsOperator::Tm => {
+                let [a, b, c, d, e, f] = pop_numbers(operands, resolver)?;
+
+                self.text_matrix = Matrix::new(a, b, c, d, e, f);
+                self.text_line_matrix = self.text_matrix;
+            }
+            PdfGraphicsOperator::T_star => self.move_to_next_line(0.0, -self.leading),
+            PdfGraphicsOperator::Tj => {
+                if let Some(Object::String(s)) = operands.pop() {
+                    self.show(&s);
+                }
+            }
+            PdfGraphicsOperator::single_quote | PdfGraphicsOperator::double_quote => {
+                self.move_to_next_line(0.0, -self.leading);
+
+                if let Some(Object::String(s)) = operands.pop() {
+                    self.show(&s);
+                }
+            }
+            PdfGraphicsOperator::TJ => {
+                if let Some(Object::Array(arr)) = operands.pop() {
+                    let mut text = String::new();
+
+                    for obj in arr {
+                        match obj {
+                            Object::String(s) => text.push_str(&s),
+                            Object::Integer(n) if (n as f32) < WORD_BREAK_THRESHOLD => {
+                                text.push(' ')
+                            }
+                            Object::Real(n) if n < WORD_BREAK_THRESHOLD => text.push(' '),
+                            _ => {}
+                        }
+                    }
+
+                    self.show(&text);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn move_to_next_line(&mut self, tx: f32, ty: f32) {
+        self.text_line_matrix = Matrix::new_translation(tx, ty) * self.text_line_matrix;
+        self.text_matrix = self.text_line_matrix;
+    }
+
+    /// Glyph widths are not known, so the text position is not advanced after
+    /// showing a string. Consecutive strings shown from the same origin are
+    /// therefore assumed to be adjacent, and strings shown from a new origin on
+    /// the same baseline to be separate words.
+    fn show(&mut self, text: &str) {
+        let matrix = self.text_matrix * self.ctm;
+
+        let origin = matrix * Point::new(0.0, 0.0);
+        let up = matrix * Point::new(0.0, 1.0);
+
+        let scale = ((up.x - origin.x).powi(2) + (up.y - origin.y).powi(2)).sqrt();
+        let size = (self.font_size * scale).abs();
+
+        let is_new_origin = self.last_origin != Some(origin);
+        self.last_origin = Some(origin);
+
+        if let Some(line) = self.lines.last_mut() {
+            let is_same_line =
+                (line.baseline - origin.y).abs() <= size * 0.25 && (line.size - size).abs() < 0.5;
+
+            if is_same_line {
+                if is_new_origin && !line.text.ends_with(char::is_whitespace) {
+                    line.text.push(' ');
+                }
+
+                line.text.push_str(text);
+
+                return;
+            }
+        }
+
+        self.lines.push(TextLine {
+            text: text.to_owned(),
+            size,
+            baseline: origin.y,
+        });
+    }
+}
+
+fn pop_numbers<'a, const N: usize>(
+    operands: &mut Vec<Object<'a>>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<[f32; N]> {
+    let mut numbers = [0.0; N];
+
+    let start = match operands.len().checked_sub(N) {
+        Some(start) => start,
+        None => anyhow::bail!("expected {} operands, found {}", N, operands.len()),
+    };
+
+    for (number, obj) in numbers.iter_mut().zip(operands.drain(start..)) {
+        *number = resolver.assert_number(obj)?;
+    }
+
+    Ok(numbers)
+}
+
+/// Sizes are compared to the nearest half point
+fn size_key(size: f32) -> u32 {
+    (size * 2.0).round() as u32
+}
+
+/// Identify headings among the lines of text of each page by their size,
+/// returning each along with its level
+///
+/// The size of body text is taken to be the size at which the most characters
+/// are shown. Lines shown noticeably larger than body text are headings, with
+/// the largest size being level 1, the next largest level 2, and so on.
+/// Consecutive heading lines of the same level are joined, as they are
+/// usually a single heading wrapped onto several lines. Headings repeated on
+/// many pages are assumed to be running headers and ignored.
+pub(crate) fn headings_by_size(pages: &[Vec<TextLine>]) -> Vec<(u8, OutlineItem)> {
+    let mut chars_by_size: HashMap<u32, usize> = HashMap::new();
+
+    for line in pages.iter().flatten() {
+        *chars_by_size.entry(size_key(line.size)).or_default() +=
+            line.text.chars().filter(|c| !c.is_whitespace()).count();
+    }
+
+    let body_size = match chars_by_size
+        .iter()
+        .max_by_key(|&(&size, &count)| (count, std::cmp::Reverse(size)))
+    {
+        Some((&size, _)) => size,
+        None => return Vec::new(),
+    };
+
+    let mut heading_sizes = chars_by_size
+        .into_keys()
+        .filter(|&size| size as f32 >= body_size as f32 * HEADING_SIZE_RATIO)
+        .collect::<Vec<u32>>();
+
+    heading_sizes.sort_unstable_by(|a, b| b.cmp(a));
+    heading_sizes.truncate(MAX_HEADING_LEVELS);
+
+    let mut headings: Vec<(u8, OutlineItem)> = Vec::new();
+
+    for (page, lines) in pages.iter().enumerate() {
+        // the index and level of the previous line, if it was a heading
+        let mut previous: Option<(usize, u8)> = None;
+
+        for (idx, line) in lines.iter().enumerate() {
+            let level = match heading_sizes
+                .iter()
+                .position(|&size| size == size_key(line.size))
+            {
+                Some(level) => level as u8 + 1,
+                None => continue,
+            };
+
+            let title = line.text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+            if title.chars().count() > MAX_HEADING_LEN || !title.chars().any(char::is_alphabetic) {
+                continue;
+            }
+
+            match headings.last_mut() {
+                Some((_, heading)) if previous == Some((idx.wrapping_sub(1), level)) => {
+                    heading.title.push(' ');
+                    heading.title.push_str(&title);
+                }
+                _ => headings.push((
+                    level,
+                    OutlineItem::new(title, page, Some(line.baseline + line.size)),
+                )),
+            }
+
+            previous = Some((idx, level));
+        }
+    }
+
+    let mut pages_by_title: HashMap<&str, HashSet<Option<usize>>> = HashMap::new();
+
+    for (_, heading) in &headings {
+        pages_by_title
+            .entry(&heading.title)
+            .or_default()
+            .insert(heading.page);
+    }
+
+    let is_running_header = |title: &str| {
+        let count = pages_by_title[title].len();
+
+        count >= 3 && count * 4 >= pages.len()
+    };
+
+    let running_headers = headings
+        .iter()
+        .map(|(_, heading)| is_running_header(&heading.title))
+        .collect::<Vec<bool>>();
+
+    headings
+        .into_iter()
+        .zip(running_headers)
+        .filter(|(_, is_running_header)| !is_running_header)
+        .map(|(heading, _)| heading)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use crate::xref::XrefParser;
+
+    use super::*;
+
+    fn lines(buffer: &[u8]) -> Vec<TextLine> {
+        TextLine::from_content(
+            ContentLexer::new(Cow::Borrowed(buffer)),
+            &mut XrefParser::new(Vec::new()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn lines_with_size() {
+        let lines = lines(
+            b"BT /F1 12 Tf 72 700 Td (Intro) Tj (duction) Tj 0 -20 Td (Body) Tj ET \
+              q 2 0 0 2 0 0 cm BT /F1 10 Tf 1 0 0 1 36 300 Tm [(Big) -500 (text)] TJ ET Q",
+        );
+
+        assert_eq!(
+            lines,
+            vec![
+                TextLine {
+                    text: "Introduction".to_owned(),
+                    size: 12.0,
+                    baseline: 700.0,
+                },
+                TextLine {
+                    text: "Body".to_owned(),
+                    size: 12.0,
+                    baseline: 680.0,
+                },
+                TextLine {
+                    text: "Big text".to_owned(),
+                    size: 20.0,
+                    baseline: 600.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn headings_from_sizes() {
+        let line = |text: &str, size: f32| TextLine {
+            text: text.to_owned(),
+            size,
+            baseline: 700.0,
+        };
+
+        let pages = vec![
+            vec![
+                line("Chapter One", 24.0),
+                line("Background", 16.0),
+                line("A long paragraph of body text", 10.0),
+                line("Another long paragraph of body text", 10.0),
+            ],
+            vec![
+                line("Results and", 16.0),
+                line("Discussion", 16.0),
+                line("More body text", 10.0),
+                line("12", 16.0),
+            ],
+        ];
+
+        let headings = headings_by_size(&pages)
+            .into_iter()
+            .map(|(level, item)| (level, item.title, item.page))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            headings,
+            vec![
+                (1, "Chapter One".to_owned(), Some(0)),
+                (2, "Background".to_owned(), Some(0)),
+                (2, "Results and Discussion".to_owned(), Some(1)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,716 @@
+use crate::{
+    destination::Destination, error::PdfResult, objects::Reference, writer, FromObj, Parser,
+    Resolve,
+};
+use std::collections::{HashMap, HashSet};
+
+pub(crate) use document::{DocumentOutline, OutlineItemDictionary};
+pub(crate) use heuristic::{headings_by_size, TextLine};
+
+mod document;
+mod heuristic;
+
+/// An item of a document outline (a bookmark), referring to a location within
+/// the document
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineItem {
+    pub title: String,
+
+    /// The zero-based index of the page to which the item refers, or `None` if
+    /// the item does not refer to a page of this document, such as an item
+    /// which opens a URI
+    pub page: Option<usize>,
+
+    /// The vertical coordinate, in default user space, that should be
+    /// positioned at the top edge of the window, if known
+    pub top: Option<f32>,
+
+    /// The colour of the item's text, as RGB components in the range 0.0 to
+    /// 1.0
+    pub color: [f32; 3],
+    pub is_italic: bool,
+    pub is_bold: bool,
+
+    /// Whether the item's children are shown when the document is opened
+    pub is_open: bool,
+
+    /// Items immediately subordinate to this one in the outline hierarchy
+    pub children: Vec<OutlineItem>,
+}
+
+impl OutlineItem {
+    pub(crate) fn new(title: String, page: usize, top: Option<f32>) -> Self {
+        Self {
+            title,
+            page: Some(page),
+            top,
+            color: [0.0, 0.0, 0.0],
+            is_italic: false,
+            is_bold: false,
+            is_open: false,
+            children: Vec::new(),
+        }
+    }
+
+    /// This item and each of its descendants in depth-first order, along with
+    /// their depth relative to this item
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &OutlineItem)> {
+        let mut stack = vec![(0, self)];
+
+        std::iter::from_fn(move || {
+            let (depth, item) = stack.pop()?;
+
+            stack.extend(item.children.iter().rev().map(|child| (depth + 1, child)));
+
+            Some((depth, item))
+        })
+    }
+
+    /// Build an outline hierarchy from a flat sequence of items, each with the
+    /// level at which it appears
+    ///
+    /// Each item becomes a child of the closest preceding item of a lower
+    /// level. Levels need not be consecutive.
+    pub(crate) fn from_levels(items: impl IntoIterator<Item = (u8, OutlineItem)>) -> Vec<Self> {
+        let mut roots = Vec::new();
+        let mut open: Vec<(u8, OutlineItem)> = Vec::new();
+
+        for (level, item) in items {
+            while matches!(open.last(), Some(&(open_level, _)) if open_level >= level) {
+                close_last(&mut open, &mut roots);
+            }
+
+            open.push((level, item));
+        }
+
+        while !open.is_empty() {
+            close_last(&mut open, &mut roots);
+        }
+
+        roots
+    }
+}
+
+/// An outline item whose destination does not exist
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineIssue {
+    /// The outline item dictionary
+    pub reference: Reference,
+
+    pub title: String,
+
+    /// The index of the item among its siblings, preceded by that of each of
+    /// its ancestors, starting from the top level of the outline
+    pub path: Vec<usize>,
+
+    pub problem: OutlineProblem,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutlineProblem {
+    /// The destination refers to an object which is not a page of this
+    /// document, such as a page which has been removed
+    MissingPage(Reference),
+
+    /// The named destination is not defined in the Dests name tree or the
+    /// Dests dictionary of the catalog
+    UndefinedDestination(String),
+}
+
+impl OutlineItem {
+    /// Point the items of `issues` within the outline `items` at the page of
+    /// the nearest item that refers to a page, or at `fallback` if no item
+    /// does
+    pub(crate) fn retarget(
+        items: &mut [OutlineItem],
+        issues: &[OutlineIssue],
+        fallback: Option<usize>,
+    ) {
+        // the page of each item in depth-first order, along with its path
+        let mut flattened = Vec::new();
+
+        fn flatten(
+            items: &[OutlineItem],
+            path: &mut Vec<usize>,
+            out: &mut Vec<(Vec<usize>, Option<usize>)>,
+        ) {
+            for (idx, item) in items.iter().enumerate() {
+                path.push(idx);
+                out.push((path.clone(), item.page));
+                flatten(&item.children, path, out);
+                path.pop();
+            }
+        }
+
+        flatten(items, &mut Vec::new(), &mut flattened);
+
+        for issue in issues {
+            let position = match flattened.iter().position(|(path, _)| *path == issue.path) {
+                Some(position) => position,
+                None => continue,
+            };
+
+            let page = flattened[..position]
+                .iter()
+                .rev()
+                .chain(&flattened[position + 1..])
+                .find_map(|(_, page)| *page)
+                .or(fallback);
+
+            if let Some(item) = item_at_mut(items, &issue.path) {
+                item.page = page;
+                item.top = None;
+            }
+        }
+    }
+}
+
+fn item_at_mut<'a>(items: &'a mut [OutlineItem], path: &[usize]) -> Option<&'a mut OutlineItem> {
+    let (&first, rest) = path.split_first()?;
+    let item = items.get_mut(first)?;
+
+    if rest.is_empty() {
+        Some(item)
+    } else {
+        item_at_mut(&mut item.children, rest)
+    }
+}
+
+fn close_last(open: &mut Vec<(u8, OutlineItem)>, roots: &mut Vec<OutlineItem>) {
+    if let Some((_, item)) = open.pop() {
+        match open.last_mut() {
+            Some((_, parent)) => parent.children.push(item),
+            None => roots.push(item),
+        }
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// The document outline (bookmarks), with each item resolved to the index
+    /// of the page to which it refers
+    ///
+    /// Items whose destination cannot be resolved to a page of this document,
+    /// such as those which open a URI, have no page. Documents without an
+    /// outline have no items.
+    pub fn outline(&mut self) -> PdfResult<Vec<OutlineItem>> {
+        Ok(self.outline_with_issues()?.0)
+    }
+
+    /// The outline items whose destination does not exist, because it refers
+    /// to an object which is not a page of this document or to a named
+    /// destination which is not defined
+    ///
+    /// Documents which have been merged or had pages removed often accumulate
+    /// such dead bookmarks.
+    pub fn validate_outline(&mut self) -> PdfResult<Vec<OutlineIssue>> {
+        Ok(self.outline_with_issues()?.1)
+    }
+
+    /// Retarget each outline item whose destination does not exist to the
+    /// nearest valid page, returning the file with an incremental update
+    /// appended, along with the items which were retargeted
+    ///
+    /// The nearest valid page is that of the closest preceding item, in
+    /// depth-first order, which refers to a page of this document, or of the
+    /// closest following item if there is none, or otherwise the first page.
+    /// Only the retargeted items are rewritten, and the file is returned
+    /// unchanged if there are none.
+    pub fn repair_outline(&mut self) -> PdfResult<(Vec<u8>, Vec<OutlineIssue>)> {
+        let (mut items, issues) = self.outline_with_issues()?;
+
+        if issues.is_empty() {
+            return Ok((self.lexer.file.to_vec(), issues));
+        }
+
+        let pages = self.page_order();
+        let fallback = (!pages.is_empty()).then_some(0);
+
+        OutlineItem::retarget(&mut items, &issues, fallback);
+
+        let retargeted = issues
+            .iter()
+            .map(|issue| {
+                let page = item_at_mut(&mut items, &issue.path)
+                    .and_then(|item| item.page)
+                    .map(|page| pages[page]);
+
+                (issue.reference, page)
+            })
+            .collect::<Vec<(Reference, Option<Reference>)>>();
+
+        let file = writer::retarget_outline_items(&self.trailer, &retargeted, &mut self.lexer)?;
+
+        Ok((file, issues))
+    }
+
+    pub(crate) fn outline_with_issues(
+        &mut self,
+    ) -> PdfResult<(Vec<OutlineItem>, Vec<OutlineIssue>)> {
+        let outline = match &self.catalog.outlines {
+            Some(outline) => outline.get_ref(&mut self.lexer)?.into_owned(),
+            None => return Ok((Vec::new(), Vec::new())),
+        };
+
+        let page_indices = self.page_indices();
+        let mut issues = Vec::new();
+
+        let items = self.outline_items(
+            outline.first,
+            &page_indices,
+            &mut HashSet::new(),
+            &mut Vec::new(),
+            &mut issues,
+        )?;
+
+        Ok((items, issues))
+    }
+
+    /// The outline item `first` and its siblings, each with their descendants
+    ///
+    /// Items already in `visited` are skipped, so that malformed outlines with
+    /// cycles terminate. `path` is the position of the parent of `first`
+    /// within the outline, and items whose destination does not exist are
+    /// added to `issues`.
+    fn outline_items(
+        &mut self,
+        first: Option<Reference>,
+        page_indices: &HashMap<Reference, usize>,
+        visited: &mut HashSet<Reference>,
+        path: &mut Vec<usize>,
+        issues: &mut Vec<OutlineIssue>,
+    ) -> PdfResult<Vec<OutlineItem>> {
+        let mut items = Vec::new();
+        let mut next = first;
+
+        while let Some(reference) = next {
+            if !visited.insert(reference) {
+                break;
+            }
+
+            let obj = self.lexer.lex_object_from_reference(reference)?;
+            let item = OutlineItemDictionary::from_obj(obj, &mut self.lexer)?;
+
+            path.push(items.len());
+
+            let mut problem = None;
+
+            let (page, top) = match item.destination() {
+                Some(dest) => match self.resolve_destination(dest)? {
+                    Some((page_ref, top)) => match page_indices.get(&page_ref) {
+                        Some(&page) => (Some(page), top),
+                        None => {
+                            problem = Some(OutlineProblem::MissingPage(page_ref));
+                            (None, None)
+                        }
+                    },
+                    None => {
+                        if let Destination::Named(name) = dest {
+                            problem = Some(OutlineProblem::UndefinedDestination(name.clone()));
+                        }
+
+                        (None, None)
+                    }
+                },
+                None => (None, None),
+            };
+
+            if let Some(problem) = problem {
+                issues.push(OutlineIssue {
+                    reference,
+                    title: item.title.clone(),
+                    path: path.clone(),
+                    problem,
+                });
+            }
+
+            let children = self.outline_items(item.first, page_indices, visited, path, issues)?;
+
+            path.pop();
+
+            items.push(OutlineItem {
+                title: item.title.clone(),
+                page,
+                top,
+                color: item.color,
+                is_italic: item.is_italic(),
+                is_bold: item.is_bold(),
+                is_open: item.count.map_or(false, |count| count > 0),
+                children,
+            });
+
+            next = item.next;
+        }
+
+        Ok(items)
+    }
+
+    /// Replace the document outline with `items`, returning the file with an
+    /// incremental update appended
+    ///
+    /// Items refer to pages by index. Items without a page, such as those
+    /// which opened a URI, are written without a destination.
+    pub fn set_outline(&mut self, items: &[OutlineItem]) -> PdfResult<Vec<u8>> {
+        let pages = self.page_order();
+
+        writer::set_outline(&self.trailer, items, &pages, &mut self.lexer)
+    }
+
+    /// The page objects of the document, in order
+    fn page_order(&self) -> Vec<Reference> {
+        let mut pages = self
+            .page_indices()
+            .into_iter()
+            .map(|(reference, idx)| (idx, reference))
+            .collect::<Vec<(usize, Reference)>>();
+        pages.sort_unstable_by_key(|&(idx, _)| idx);
+
+        pages.into_iter().map(|(_, reference)| reference).collect()
+    }
+
+    /// Generate a document outline from the headings of the document, for
+    /// documents which lack bookmarks, and write it in place of any existing
+    /// outline
+    ///
+    /// Tagged documents use the H1 through H3 (and H) elements of their
+    /// structure tree. Otherwise, such as for scanned documents with a
+    /// recognized text layer, headings are identified as lines shown in a
+    /// larger font than body text.
+    ///
+    /// Returns the file with an incremental update appended.
+    pub fn generate_outline(&mut self) -> PdfResult<Vec<u8>> {
+        let items = self.heading_outline()?;
+
+        self.set_outline(&items)
+    }
+
+    /// The outline generated from the headings of the document
+    fn heading_outline(&mut self) -> PdfResult<Vec<OutlineItem>> {
+        let pages = self.pages();
+
+        if let Some(root) = &self.catalog.struct_tree_root {
+            let root = root.get_ref(&mut self.lexer)?.into_owned();
+            let headings = root.headings(&self.marked_content_text()?);
+
+            if !headings.is_empty() {
+                let page_indices = self.page_indices();
+
+                return Ok(OutlineItem::from_levels(headings.into_iter().filter_map(
+                    |heading| {
+                        let page = *page_indices.get(&heading.pg?)?;
+
+                        Some((heading.level, OutlineItem::new(heading.title, page, None)))
+                    },
+                )));
+            }
+        }
+
+        let mut lines = Vec::with_capacity(pages.len());
+
+        for page in &pages {
+            if page.contents.is_none() {
+                lines.push(Vec::new());
+                continue;
+            }
+
+            let content = self.page_contents(page)?;
+
+            lines.push(TextLine::from_content(content, &mut self.lexer)?);
+        }
+
+        Ok(OutlineItem::from_levels(headings_by_size(&lines)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        objects::Object,
+        test::TestFile,
+        version::PdfVersion,
+        writer::{DocumentBuilder, PageBuilder},
+        ParseOptions,
+    };
+
+    #[test]
+    fn retarget_to_nearest_valid_page() {
+        let mut broken = OutlineItem::new("Removed".to_owned(), 0, Some(10.0));
+        broken.page = None;
+
+        let mut chapter = OutlineItem::new("Chapter".to_owned(), 3, None);
+        chapter.children = vec![
+            broken.clone(),
+            OutlineItem::new("Section".to_owned(), 5, None),
+        ];
+
+        let mut items = vec![broken.clone(), chapter];
+
+        let issue = |path: Vec<usize>| OutlineIssue {
+            reference: Reference {
+                object_number: 1,
+                generation: 0,
+            },
+            title: "Removed".to_owned(),
+            path,
+            problem: OutlineProblem::UndefinedDestination("gone".to_owned()),
+        };
+
+        OutlineItem::retarget(&mut items, &[issue(vec![0]), issue(vec![1, 0])], Some(0));
+
+        // the first item has no preceding item, so the following one is used
+        assert_eq!(items[0].page, Some(3));
+        assert_eq!(items[0].top, None);
+        assert_eq!(items[1].children[0].page, Some(3));
+        assert_eq!(items[1].children[1].page, Some(5));
+    }
+
+    #[test]
+    fn outline() {
+        let mut file = TestFile::new();
+        file.object(
+            1,
+            b"<< /Type /Catalog /Pages 2 0 R /Outlines 5 0 R /Names << /Dests 11 0 R >> \
+              /Dests 12 0 R >>",
+        );
+        file.object(2, b"<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>");
+        file.object(
+            3,
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] >>",
+        );
+        file.object(
+            4,
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] >>",
+        );
+        file.object(
+            5,
+            b"<< /Type /Outlines /First 6 0 R /Last 8 0 R /Count 3 >>",
+        );
+
+        // an open item with a child, whose next sibling is reached again by the
+        // last item, which is a cycle
+        file.object(
+            6,
+            b"<< /Title (Chapter) /Parent 5 0 R /Next 7 0 R /First 9 0 R /Last 9 0 R /Count 1 \
+              /Dest [3 0 R /XYZ 0 700 0] /C [1 0 0] /F 2 >>",
+        );
+        file.object(
+            7,
+            b"<< /Title (Named) /Parent 5 0 R /Prev 6 0 R /Next 8 0 R \
+              /A << /S /GoTo /D (appendix) >> >>",
+        );
+        file.object(
+            8,
+            b"<< /Title (Legacy) /Parent 5 0 R /Prev 7 0 R /Next 7 0 R /Dest /Legacy /F 1 >>",
+        );
+        file.object(
+            9,
+            b"<< /Title (Website) /Parent 6 0 R /Next 10 0 R \
+              /A << /S /URI /URI (https://example.com) >> >>",
+        );
+        file.object(
+            10,
+            b"<< /Title (Missing) /Parent 6 0 R /Prev 9 0 R /Dest (missing) >>",
+        );
+        file.object(11, b"<< /Names [(appendix) << /D [4 0 R /FitH 300] >>] >>");
+        file.object(12, b"<< /Legacy [4 0 R /Fit] >>");
+
+        let mut parser =
+            Parser::from_bytes(file.with_xref_table(), ParseOptions::default()).unwrap();
+
+        let outline = parser.outline().unwrap();
+
+        let titles = outline
+            .iter()
+            .flat_map(OutlineItem::iter)
+            .map(|(depth, item)| (depth, item.title.as_str(), item.page, item.top))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            titles,
+            [
+                (0, "Chapter", Some(0), Some(700.0)),
+                (1, "Website", None, None),
+                (1, "Missing", None, None),
+                (0, "Named", Some(1), Some(300.0)),
+                (0, "Legacy", Some(1), None),
+            ]
+        );
+
+        assert_eq!(outline[0].color, [1.0, 0.0, 0.0]);
+        assert!(outline[0].is_bold && !outline[0].is_italic && outline[0].is_open);
+        assert!(outline[2].is_italic && !outline[2].is_open);
+
+        let issues = parser.validate_outline().unwrap();
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].title, "Missing");
+        assert_eq!(issues[0].path, [0, 1]);
+        assert_eq!(
+            issues[0].problem,
+            OutlineProblem::UndefinedDestination("missing".to_owned())
+        );
+    }
+
+    #[test]
+    fn generate_outline() {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+
+        for contents in [
+            &b"BT /F1 24 Tf 72 700 Td (Chapter One) Tj ET \
+               BT /F1 16 Tf 72 650 Td (Background) Tj ET \
+               BT /F1 10 Tf 72 600 Td (A long paragraph of body text) Tj ET \
+               BT /F1 10 Tf 72 580 Td (Another long paragraph of body text) Tj ET"[..],
+            b"BT /F1 16 Tf 72 700 Td (Results) Tj ET \
+              BT /F1 10 Tf 72 650 Td (More body text) Tj ET",
+        ] {
+            let mut page = PageBuilder::new(612.0, 792.0);
+            page.contents(contents);
+            builder.add_page(page).unwrap();
+        }
+
+        let bytes = builder.to_bytes().unwrap();
+        let mut parser = Parser::from_bytes(bytes, ParseOptions::default()).unwrap();
+
+        assert!(parser.outline().unwrap().is_empty());
+
+        let bytes = parser.generate_outline().unwrap();
+        let mut parser = Parser::from_bytes(bytes, ParseOptions::default()).unwrap();
+
+        let outline = parser.outline().unwrap();
+
+        let titles = outline
+            .iter()
+            .flat_map(OutlineItem::iter)
+            .map(|(depth, item)| (depth, item.title.as_str(), item.page))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            titles,
+            [
+                (0, "Chapter One", Some(0)),
+                (1, "Background", Some(0)),
+                (1, "Results", Some(1)),
+            ]
+        );
+
+        assert!(!outline[0].is_open);
+        assert!(parser.validate_outline().unwrap().is_empty());
+    }
+
+    #[test]
+    fn set_outline() {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_4);
+
+        for _ in 0..2 {
+            builder.add_page(PageBuilder::new(612.0, 792.0)).unwrap();
+        }
+
+        let bytes = builder.to_bytes().unwrap();
+        let mut parser = Parser::from_bytes(bytes, ParseOptions::default()).unwrap();
+
+        let mut chapter = OutlineItem::new("Chapter".to_owned(), 0, Some(700.0));
+        chapter.is_open = true;
+        chapter.is_bold = true;
+        chapter.color = [1.0, 0.0, 0.0];
+        chapter.children = vec![OutlineItem::new("Section".to_owned(), 1, None)];
+
+        let mut website = OutlineItem::new("Website".to_owned(), 0, None);
+        website.page = None;
+
+        let items = vec![chapter, website];
+
+        let bytes = parser.set_outline(&items).unwrap();
+        let mut parser = Parser::from_bytes(bytes, ParseOptions::default()).unwrap();
+
+        assert_eq!(parser.outline().unwrap(), items);
+
+        let outline = parser.catalog.outlines.as_ref().unwrap();
+        let outline = outline.get_ref(&mut parser.lexer).unwrap();
+        assert_eq!(outline.count, Some(3));
+
+        // pages are referred to by index
+        let mut beyond = items[1].clone();
+        beyond.page = Some(2);
+        assert!(parser.set_outline(&[beyond]).is_err());
+
+        let bytes = parser.set_outline(&[]).unwrap();
+        let mut parser = Parser::from_bytes(bytes, ParseOptions::default()).unwrap();
+
+        assert!(parser.outline().unwrap().is_empty());
+    }
+
+    #[test]
+    fn repair_outline() {
+        let objects: [&[u8]; 9] = [
+            b"<< /Type /Pages /Kids [2 0 R 3 0 R] /Count 2 >>",
+            b"<< /Type /Page /Parent 1 0 R /MediaBox [0 0 612 792] >>",
+            b"<< /Type /Page /Parent 1 0 R /MediaBox [0 0 612 792] >>",
+            b"<< /Type /Outlines /First 5 0 R /Last 7 0 R /Count 3 >>",
+            b"<< /Title (One) /Parent 4 0 R /Next 6 0 R /Dest [3 0 R /Fit] >>",
+            b"<< /Title (Two) /Parent 4 0 R /Prev 5 0 R /Next 7 0 R \
+              /A << /S /GoTo /D (missing) /Next << /S /Named /N /NextPage >> >> >>",
+            b"<< /Title (Three) /Parent 4 0 R /Prev 6 0 R /Dest [9 0 R /Fit] >>",
+            b"<< /Type /Catalog /Pages 1 0 R /Outlines 4 0 R >>",
+            b"<< /Removed true >>",
+        ];
+
+        let mut writer = writer::PdfWriter::new(PdfVersion::V1_7);
+
+        for obj in objects {
+            writer.add(
+                crate::Tokenizer::new(obj)
+                    .next_object()
+                    .unwrap()
+                    .unwrap()
+                    .value,
+            );
+        }
+
+        writer.set_root(Reference {
+            object_number: 8,
+            generation: 0,
+        });
+
+        let mut parser =
+            Parser::from_bytes(writer.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        let (bytes, issues) = parser.repair_outline().unwrap();
+        assert_eq!(
+            issues
+                .iter()
+                .map(|issue| issue.title.as_str())
+                .collect::<Vec<&str>>(),
+            ["Two", "Three"]
+        );
+
+        let mut parser = Parser::from_bytes(bytes.clone(), ParseOptions::default()).unwrap();
+
+        assert!(parser.validate_outline().unwrap().is_empty());
+        assert_eq!(
+            parser
+                .outline()
+                .unwrap()
+                .iter()
+                .map(|item| item.page)
+                .collect::<Vec<Option<usize>>>(),
+            [Some(1); 3]
+        );
+
+        // the action following the retargeted go-to action is kept
+        let mut two = parser
+            .lexer
+            .assert_dict(Object::Reference(Reference {
+                object_number: 6,
+                generation: 0,
+            }))
+            .unwrap();
+        let mut action = two.get_dict("A", &mut parser.lexer).unwrap().unwrap();
+        assert!(action
+            .get_dict("Next", &mut parser.lexer)
+            .unwrap()
+            .is_some());
+
+        // a document with nothing to repair is returned as it was
+        assert_eq!(parser.repair_outline().unwrap().0, bytes);
+    }
+}
@@ -1,17 +1,19 @@
 use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
 
 use crate::{
-    catalog::{
-        AdditionalActions, BoxColorInfo, GroupAttributes, MetadataStream, NavigationNode,
-        PagePiece, SeparationInfo, Transitions, Viewport,
-    },
+    actions::PageAdditionalActions,
+    catalog::{BoxColorInfo, GroupAttributes, MetadataStream, PagePiece},
+    color::SeparationInfo,
     content::ContentStream,
     data_structures::Rectangle,
     date::Date,
     error::PdfResult,
+    measure::Viewport,
     objects::{Dictionary, TypedReference},
+    presentation::NavigationNode,
     resources::Resources,
     stream::Stream,
+    transition::Transition,
     Reference, Resolve,
 };
 
@@ -72,6 +74,18 @@ impl<'a> PageNode<'a> {
         }
     }
 
+    pub fn rotate(&self) -> Option<i32> {
+        match self {
+            Self::Root(tree) => tree.borrow().inheritable_page_fields.rotate,
+            Self::Node(node) => node
+                .borrow()
+                .inheritable_page_fields
+                .rotate
+                .or_else(|| node.borrow().parent.rotate()),
+            Self::Leaf(leaf) => Some(leaf.rotation()),
+        }
+    }
+
     pub fn resources(&self) -> Option<Rc<Resources<'a>>> {
         match self {
             Self::Root(tree) => tree
@@ -274,7 +288,7 @@ pub struct PageObject<'a> {
     /// A transition dictionary describing the transition effect that shall
     /// be used when displaying the page during presentations
     // #[field("Trans")]
-    pub trans: Option<Transitions>,
+    pub trans: Option<Transition>,
 
     /// An array of annotation dictionaries that shall contain indirect
     /// references to all annotations associated with the page
@@ -284,7 +298,7 @@ pub struct PageObject<'a> {
     /// An additional-actions dictionary that shall define actions to
     /// be performed when the page is opened or closed
     // #[field("AA")]
-    pub aa: Option<AdditionalActions>,
+    pub aa: Option<PageAdditionalActions<'a>>,
 
     /// A metadata stream that shall contain metadata for the page
     // #[field("Metadata")]
@@ -310,7 +324,7 @@ pub struct PageObject<'a> {
     /// A separation dictionary that shall contain information needed to
     /// generate colour separations for the page
     // #[field("SeparationInfo")]
-    pub separation_info: Option<SeparationInfo>,
+    pub separation_info: Option<SeparationInfo<'a>>,
 
     /// A name specifying the tab order that shall be used for annotations on the page.
     ///
@@ -328,7 +342,7 @@ pub struct PageObject<'a> {
     /// A navigation node dictionary that shall represent the first
     /// node on the page
     // #[field("PresSteps")]
-    pub pres_steps: Option<NavigationNode>,
+    pub pres_steps: Option<NavigationNode<'a>>,
 
     /// A positive number that shall give the size of default user space units,
     /// in multiples of 1/72 inch. The range of supported values shall be
@@ -341,7 +355,7 @@ pub struct PageObject<'a> {
     /// An array of viewport dictionaries that shall specify rectangular
     /// regions of the page.
     // #[field("VP")]
-    pub vp: Option<Viewport>,
+    pub vp: Option<Vec<Viewport<'a>>>,
 }
 
 impl<'a> PageObject<'a> {
@@ -357,9 +371,27 @@ impl<'a> PageObject<'a> {
             .or(self.media_box)
     }
 
+    /// The number of degrees by which the page is rotated clockwise when
+    /// displayed
+    pub fn rotation(&self) -> i32 {
+        self.rotate.or_else(|| self.parent.rotate()).unwrap_or(0)
+    }
+
     pub fn resources(&self) -> Option<Rc<Resources<'a>>> {
         self.resources.as_ref().map(Rc::clone)
     }
+
+    /// The viewport whose coordinate system applies to the point, in default
+    /// user space
+    ///
+    /// Where viewports overlap, the last in the VP array is used.
+    pub fn viewport_at(&self, point: [f32; 2]) -> Option<&Viewport<'a>> {
+        self.vp
+            .as_deref()?
+            .iter()
+            .rev()
+            .find(|viewport| viewport.contains(point))
+    }
 }
 
 impl fmt::Debug for PageObject<'_> {
@@ -0,0 +1,305 @@
+/*!
+ * Page labels, used to number or otherwise identify pages when they are
+ * displayed, independently of their position in the document
+ *
+ * A document may be divided into labelling ranges, each a series of
+ * consecutive pages using the same numbering system. For example, the front
+ * matter may be numbered in lowercase roman numerals and the appendices
+ * prefixed with "A-".
+ */
+
+use crate::{
+    data_structures::NumberTree,
+    error::PdfResult,
+    objects::{Dictionary, Object},
+    FromObj, Parser, Resolve, ToObj,
+};
+
+/// The page labelling ranges of a document, as given by the PageLabels entry
+/// of the document catalog
+#[derive(Debug, Default, Clone)]
+pub struct PageLabels {
+    /// The index of the first page of each range and its labelling style, in
+    /// ascending order of page index
+    ranges: Vec<(usize, PageLabel)>,
+}
+
+impl<'a> FromObj<'a> for PageLabels {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let tree = NumberTree::from_obj(obj, resolver)?;
+
+        let mut ranges = tree
+            .entries()
+            .into_iter()
+            .filter(|&(start, _)| start >= 0)
+            .map(|(start, obj)| Ok((start as usize, PageLabel::from_obj(obj.clone(), resolver)?)))
+            .collect::<PdfResult<Vec<(usize, PageLabel)>>>()?;
+
+        ranges.sort_by_key(|&(start, _)| start);
+
+        Ok(Self { ranges })
+    }
+}
+
+/// Page labels are written as a number tree with a single node
+impl<'a> ToObj<'a> for PageLabels {
+    fn to_obj(&self) -> PdfResult<Object<'a>> {
+        let mut nums = Vec::with_capacity(self.ranges.len() * 2);
+
+        for (start, label) in &self.ranges {
+            nums.push(Object::Integer(i32::try_from(*start)?));
+            nums.push(label.to_obj()?);
+        }
+
+        let mut tree = Dictionary::empty();
+        tree.insert("Nums", Object::Array(nums));
+
+        Ok(Object::Dictionary(tree))
+    }
+}
+
+impl PageLabels {
+    /// Page labels with no ranges, under which pages are labelled by their
+    /// page number
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Begin a labelling range at the zero-based index `first_page`, replacing
+    /// any range which begins there
+    ///
+    /// Pages are numbered in `style` from `start`, after `prefix`. A range
+    /// with neither a style nor a prefix gives its pages empty labels.
+    pub fn set_range(
+        &mut self,
+        first_page: usize,
+        style: Option<NumberingStyle>,
+        prefix: Option<&str>,
+        start: i32,
+    ) {
+        let label = PageLabel {
+            style,
+            prefix: prefix.map(str::to_owned),
+            start: start.max(1),
+        };
+
+        match self
+            .ranges
+            .binary_search_by_key(&first_page, |&(start, _)| start)
+        {
+            Ok(idx) => self.ranges[idx].1 = label,
+            Err(idx) => self.ranges.insert(idx, (first_page, label)),
+        }
+    }
+
+    /// Remove the labelling range which begins at `first_page`, so that its
+    /// pages continue the range before it
+    pub fn remove_range(&mut self, first_page: usize) {
+        self.ranges.retain(|&(start, _)| start != first_page);
+    }
+
+    /// Whether the labels have no ranges
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The label of the page at the zero-based index `page`, such as "iv" or
+    /// "A-2"
+    ///
+    /// Pages before the first labelling range, which should not exist in a
+    /// well-formed document, are labelled with their page number.
+    pub fn label_for_page(&self, page: usize) -> String {
+        match self.ranges.iter().rev().find(|&&(start, _)| start <= page) {
+            Some((start, label)) => label.label(page - start),
+            None => (page + 1).to_string(),
+        }
+    }
+}
+
+/// A page label dictionary, defining the labelling characteristics for the
+/// pages in a labelling range
+#[derive(Debug, Clone, FromObj, ToObj)]
+#[obj_type("PageLabel")]
+struct PageLabel {
+    /// The numbering style that shall be used for the numeric portion of each
+    /// page label
+    ///
+    /// If this entry is absent, page labels shall consist solely of a label
+    /// prefix with no numeric portion.
+    #[field("S")]
+    style: Option<NumberingStyle>,
+
+    /// The label prefix for page labels in this range
+    #[field("P")]
+    prefix: Option<String>,
+
+    /// The value of the numeric portion for the first page label in the range.
+    /// Subsequent pages shall be numbered sequentially from this value, which
+    /// shall be greater than or equal to 1.
+    ///
+    /// Default value: 1
+    #[field("St", default = 1)]
+    start: i32,
+}
+
+impl PageLabel {
+    /// The label of the page `offset` pages after the first page of the range
+    fn label(&self, offset: usize) -> String {
+        let mut label = self.prefix.clone().unwrap_or_default();
+
+        if let Some(style) = self.style {
+            let n = self.start.max(1) as usize + offset;
+
+            label.push_str(&style.format(n));
+        }
+
+        label
+    }
+}
+
+/// The numbering system of the numeric portion of page labels
+#[pdf_enum]
+pub enum NumberingStyle {
+    /// Decimal arabic numerals
+    Decimal = "D",
+
+    /// Uppercase roman numerals
+    UpperRoman = "R",
+
+    /// Lowercase roman numerals
+    LowerRoman = "r",
+
+    /// Uppercase letters (A to Z for the first 26 pages, AA to ZZ for the next
+    /// 26, and so on)
+    UpperAlpha = "A",
+
+    /// Lowercase letters (a to z for the first 26 pages, aa to zz for the next
+    /// 26, and so on)
+    LowerAlpha = "a",
+}
+
+impl NumberingStyle {
+    fn format(self, n: usize) -> String {
+        match self {
+            NumberingStyle::Decimal => n.to_string(),
+            NumberingStyle::UpperRoman => roman(n),
+            NumberingStyle::LowerRoman => roman(n).to_ascii_lowercase(),
+            NumberingStyle::UpperAlpha => alpha(n),
+            NumberingStyle::LowerAlpha => alpha(n).to_ascii_lowercase(),
+        }
+    }
+}
+
+fn roman(mut n: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+
+    let mut s = String::new();
+
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            s.push_str(numeral);
+            n -= value;
+        }
+    }
+
+    s
+}
+
+fn alpha(n: usize) -> String {
+    let letter = (b'A' + ((n - 1) % 26) as u8) as char;
+
+    std::iter::repeat_n(letter, (n - 1) / 26 + 1).collect()
+}
+
+impl<'a> Parser<'a> {
+    /// The labelling ranges used to number the pages of the document when they
+    /// are displayed, if the document has page labels
+    pub fn page_labels(&mut self) -> PdfResult<Option<PageLabels>> {
+        Ok(match &self.catalog.page_labels {
+            Some(page_labels) => Some(page_labels.get_ref(&mut self.lexer)?.into_owned()),
+            None => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    fn range(style: Option<NumberingStyle>, prefix: Option<&str>, start: i32) -> PageLabel {
+        PageLabel {
+            style,
+            prefix: prefix.map(str::to_owned),
+            start,
+        }
+    }
+
+    #[test]
+    fn labels_across_ranges() {
+        let labels = PageLabels {
+            ranges: vec![
+                (0, range(Some(NumberingStyle::LowerRoman), None, 1)),
+                (4, range(Some(NumberingStyle::Decimal), None, 1)),
+                (10, range(Some(NumberingStyle::Decimal), Some("A-"), 8)),
+                (12, range(None, Some("Index"), 1)),
+            ],
+        };
+
+        assert_eq!(labels.label_for_page(0), "i");
+        assert_eq!(labels.label_for_page(3), "iv");
+        assert_eq!(labels.label_for_page(4), "1");
+        assert_eq!(labels.label_for_page(11), "A-9");
+        assert_eq!(labels.label_for_page(13), "Index");
+    }
+
+    #[test]
+    fn edit_ranges() {
+        let mut labels = PageLabels::new();
+        labels.set_range(4, Some(NumberingStyle::Decimal), None, 1);
+        labels.set_range(0, Some(NumberingStyle::LowerRoman), None, 1);
+        labels.set_range(6, None, Some("Index"), 1);
+
+        assert_eq!(labels.label_for_page(2), "iii");
+        assert_eq!(labels.label_for_page(5), "2");
+        assert_eq!(labels.label_for_page(6), "Index");
+
+        labels.set_range(4, Some(NumberingStyle::UpperAlpha), Some("A-"), 1);
+        labels.remove_range(6);
+
+        assert_eq!(labels.label_for_page(7), "A-D");
+
+        let obj = labels.to_obj().unwrap();
+        let written = PageLabels::from_obj(obj, &mut Tokenizer::new(b"")).unwrap();
+
+        assert_eq!(written.label_for_page(1), "ii");
+        assert_eq!(written.label_for_page(7), "A-D");
+    }
+
+    #[test]
+    fn alphabetic_labels_repeat_letters() {
+        assert_eq!(NumberingStyle::UpperAlpha.format(1), "A");
+        assert_eq!(NumberingStyle::UpperAlpha.format(26), "Z");
+        assert_eq!(NumberingStyle::LowerAlpha.format(28), "bb");
+    }
+
+    #[test]
+    fn roman_numerals() {
+        assert_eq!(roman(1994), "MCMXCIV");
+        assert_eq!(NumberingStyle::LowerRoman.format(14), "xiv");
+    }
+}
@@ -0,0 +1,253 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    color::SpotColorConversion,
+    encryption::EncryptionOptions,
+    error::PdfResult,
+    writer::{self, PdfWriter},
+    Parser,
+};
+
+/// A sequence of document manipulation operations, applied in order and
+/// written out once at the end
+///
+/// Documents are read once, and objects shared between steps are not
+/// re-parsed, so intermediate results never need to be written and read back.
+#[derive(Debug, Default, Clone)]
+pub struct Pipeline {
+    steps: Vec<PipelineStep>,
+
+    /// How the result is encrypted when it is written
+    encryption: Option<EncryptionOptions>,
+}
+
+#[derive(Debug, Clone)]
+enum PipelineStep {
+    /// Append every page of the document at the given path
+    Merge(PathBuf),
+
+    /// Rotate the page at the given 0-based index clockwise by a multiple of 90
+    /// degrees
+    Rotate { page: usize, degrees: i32 },
+
+    /// Draw the first page of the document at the given path over every page
+    Stamp(PathBuf),
+
+    /// Replace spot colours with their process colour equivalents
+    ConvertSpotColors(SpotColorConversion),
+
+    /// Embed a substitute for every font which is not embedded
+    EmbedFonts,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn merge(mut self, path: impl AsRef<Path>) -> Self {
+        self.steps
+            .push(PipelineStep::Merge(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Rotate the page at `page` clockwise by `degrees` from its current
+    /// rotation
+    pub fn rotate(mut self, page: usize, degrees: i32) -> Self {
+        self.steps.push(PipelineStep::Rotate { page, degrees });
+        self
+    }
+
+    pub fn stamp(mut self, path: impl AsRef<Path>) -> Self {
+        self.steps
+            .push(PipelineStep::Stamp(path.as_ref().to_path_buf()));
+        self
+    }
+
+    pub fn convert_spot_colors(mut self, options: SpotColorConversion) -> Self {
+        self.steps.push(PipelineStep::ConvertSpotColors(options));
+        self
+    }
+
+    /// Embed every font which is not already embedded, substituting the closest
+    /// matching installed system font
+    ///
+    /// Only the glyphs used by the document are embedded, and the font
+    /// dictionaries are rewritten to refer to the embedded font programs.
+    /// Fonts for which no substitute can be found cause the pipeline to fail.
+    pub fn embed_fonts(mut self) -> Self {
+        self.steps.push(PipelineStep::EmbedFonts);
+        self
+    }
+
+    /// Encrypt the result with the standard security handler when it is
+    /// written
+    ///
+    /// This applies to the document produced by every other step, regardless
+    /// of where it is added.
+    pub fn encrypt(mut self, options: EncryptionOptions) -> Self {
+        self.encryption = Some(options);
+        self
+    }
+
+    /// Apply every step and write the resulting document to `path`
+    ///
+    /// Encrypted documents are refused unless the pipeline encrypts the result,
+    /// since their encryption would otherwise be lost.
+    pub fn save(self, path: impl AsRef<Path>) -> PdfResult<()> {
+        // the objects of a compacted document borrow from the file it was read
+        // from, so every file is read before any step is applied, and kept
+        // until the result is written
+        let mut parsers = self
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                PipelineStep::Merge(path) | PipelineStep::Stamp(path) => Some(path),
+                _ => None,
+            })
+            .map(Parser::new)
+            .collect::<PdfResult<Vec<Parser>>>()?;
+
+        if self.encryption.is_none()
+            && parsers
+                .iter()
+                .any(|parser| parser.trailer.encryption.is_some())
+        {
+            anyhow::bail!("cannot save an encrypted document without encrypting the result");
+        }
+
+        let mut parsers = parsers.iter_mut();
+        let mut document: Option<PdfWriter> = None;
+
+        for step in self.steps {
+            document = Some(match (step, document) {
+                (PipelineStep::Merge(..), None) => parsers.next().unwrap().compacted()?,
+                (PipelineStep::Merge(..), Some(document)) => {
+                    let next = parsers.next().unwrap().compacted()?;
+                    let version = document.version().max(next.version());
+
+                    writer::merge(vec![document, next], version)?
+                }
+                (PipelineStep::Rotate { page, .. }, None) => {
+                    anyhow::bail!("cannot rotate page {} of an empty document", page)
+                }
+                (PipelineStep::Rotate { page, degrees }, Some(mut document)) => {
+                    let rotation = document.rotation(page)?;
+                    document.set_rotation(page, rotation + degrees)?;
+
+                    document
+                }
+                (PipelineStep::Stamp(..), None) => {
+                    anyhow::bail!("cannot stamp an empty document")
+                }
+                (PipelineStep::Stamp(..), Some(document)) => {
+                    writer::stamp(document, parsers.next().unwrap().compacted()?)?
+                }
+                (PipelineStep::ConvertSpotColors(..), None) => {
+                    anyhow::bail!("cannot convert the spot colours of an empty document")
+                }
+                (PipelineStep::ConvertSpotColors(conversion), Some(document)) => {
+                    writer::convert_spot_colors(document, &conversion)?
+                }
+                (PipelineStep::EmbedFonts, None) => {
+                    anyhow::bail!("cannot embed the fonts of an empty document")
+                }
+                (PipelineStep::EmbedFonts, Some(document)) => writer::embed_fonts(document)?,
+            });
+        }
+
+        match document {
+            Some(mut document) => {
+                if let Some(options) = &self.encryption {
+                    document.encrypt(options)?;
+                }
+
+                document.save(path)
+            }
+            None => anyhow::bail!("at least one document must be merged to save"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        encryption::{CryptFilterMethod, PasswordKind, Permissions},
+        version::PdfVersion,
+        writer::{DocumentBuilder, PageBuilder},
+        ParseOptions,
+    };
+
+    fn save_document(name: &str, pages: usize) -> PathBuf {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+
+        for _ in 0..pages {
+            builder.add_page(PageBuilder::new(612.0, 792.0)).unwrap();
+        }
+
+        let path = std::env::temp_dir().join(format!("pdf-pipeline-{}.pdf", name));
+        builder.save(&path).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn merge_and_rotate() {
+        let first = save_document("first", 2);
+        let second = save_document("second", 1);
+        let output = std::env::temp_dir().join("pdf-pipeline-output.pdf");
+
+        Pipeline::new()
+            .merge(&first)
+            .merge(&second)
+            .rotate(2, 90)
+            .rotate(2, 180)
+            .save(&output)
+            .unwrap();
+
+        let file = std::fs::read(&output).unwrap();
+        let mut parser = Parser::from_bytes(file, ParseOptions::default()).unwrap();
+        let mut document = parser.compacted().unwrap();
+
+        assert_eq!(document.pages().unwrap().len(), 3);
+        assert_eq!(document.rotation(0).unwrap(), 0);
+        assert_eq!(document.rotation(2).unwrap(), 270);
+    }
+
+    #[test]
+    fn encrypt_result() {
+        let input = save_document("unencrypted", 2);
+        let encrypted = std::env::temp_dir().join("pdf-pipeline-encrypted.pdf");
+
+        Pipeline::new()
+            .encrypt(EncryptionOptions {
+                user_password: String::new(),
+                owner_password: "owner".to_owned(),
+                permissions: Permissions::all(),
+                method: CryptFilterMethod::AesV2,
+            })
+            .merge(&input)
+            .save(&encrypted)
+            .unwrap();
+
+        let file = std::fs::read(&encrypted).unwrap();
+        let mut parser = Parser::from_bytes(file, ParseOptions::default()).unwrap();
+
+        assert!(parser.trailer.encryption.is_some());
+        assert_eq!(parser.authenticate("owner").unwrap(), PasswordKind::Owner);
+        assert_eq!(parser.compacted().unwrap().pages().unwrap().len(), 2);
+
+        // the encryption of the input would be lost
+        let output = std::env::temp_dir().join("pdf-pipeline-decrypted.pdf");
+
+        assert!(Pipeline::new().merge(&encrypted).save(&output).is_err());
+    }
+
+    #[test]
+    fn nothing_to_save() {
+        let output = std::env::temp_dir().join("pdf-pipeline-empty.pdf");
+
+        assert!(Pipeline::new().rotate(0, 90).save(output).is_err());
+    }
+}
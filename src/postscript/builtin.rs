@@ -122,7 +122,7 @@ pub(super) fn gen_system_dict() -> PostScriptDictionary {
 }
 
 #[rustfmt::skip]
-pub(super) static STANDARD_ENCODING: &[Option<&str>] = &[
+pub(crate) static STANDARD_ENCODING: &[Option<&str>] = &[
     /*\00x*/ None, None, None, None, None, None, None, None,
     /*\01x*/ None, None, None, None, None, None, None, None,
     /*\02x*/ None, None, None, None, None, None, None, None,
@@ -4,14 +4,19 @@ use std::{
     convert::TryInto,
 };
 
-use crate::{font::Glyph, geometry::path_builder::PathBuilder};
+use crate::{
+    data_structures::Matrix,
+    font::Glyph,
+    geometry::{path_builder::PathBuilder, Point},
+};
 
 use super::{
+    builtin::STANDARD_ENCODING,
     decode::decrypt_charstring,
     font::{Encoding, Type1PostscriptFont},
     graphics_operator::GraphicsOperator,
     interpreter::PostscriptInterpreter,
-    object::{PostScriptArray, PostScriptDictionary, PostScriptObject, PostScriptString},
+    object::{PostScriptDictionary, PostScriptObject, PostScriptString},
     PostScriptError, PostScriptResult,
 };
 
@@ -221,7 +226,7 @@ impl CharString {
                 255 => {
                     let bytes = &b[i..(i + 4)];
 
-                    i += 5;
+                    i += 4;
 
                     let int = i32::from_be_bytes(bytes.try_into().unwrap());
 
@@ -271,35 +276,63 @@ impl CharStrings {
     }
 }
 
+/// The state of a flex feature being drawn using the Flex mechanism of
+/// OtherSubrs 0 through 2
+///
+/// While a flex is in progress, rmoveto does not move the current point, but
+/// instead updates `current_point`, which OtherSubr 2 then records as the next
+/// of the seven points making up the two curves.
+#[derive(Debug)]
+struct Flex {
+    current_point: Point,
+    points: Vec<Point>,
+}
+
 pub(crate) struct CharStringPainter<'a> {
     path_builder: PathBuilder,
     has_current_point: bool,
     subroutines: &'a [CharString],
-    other_subroutines: &'a [PostScriptArray],
     operand_stack: CharStringStack,
-    interpreter: PostscriptInterpreter<'a>,
+
+    /// Results of the last callothersubr, to be transferred to the operand
+    /// stack using the pop command
+    other_subroutine_results: Vec<f32>,
+    flex: Option<Flex>,
+    subroutine_depth: usize,
+    is_finished: bool,
     encoding: &'a Encoding,
     char_strings: &'a CharStrings,
     gylph_cache: BTreeMap<u32, Glyph>,
 }
 
 impl<'a> CharStringPainter<'a> {
+    /// Implementations are not required to support more than 10 levels of
+    /// nested subroutine calls
+    const MAX_SUBROUTINE_DEPTH: usize = 10;
+
     pub fn new(font: &'a Type1PostscriptFont) -> Self {
         Self {
             path_builder: PathBuilder::new(),
             has_current_point: false,
             subroutines: font.private.subroutines.as_deref().unwrap_or(&[]),
-            other_subroutines: font.private.other_subroutines.as_deref().unwrap_or(&[]),
             encoding: &font.encoding,
             char_strings: &font.char_strings,
             operand_stack: CharStringStack::new(),
-            interpreter: PostscriptInterpreter::new(&[]),
+            other_subroutine_results: Vec::new(),
+            flex: None,
+            subroutine_depth: 0,
+            is_finished: false,
             gylph_cache: BTreeMap::new(),
         }
     }
 
     fn reinit(&mut self) {
         self.path_builder = PathBuilder::new();
+        self.operand_stack.clear();
+        self.other_subroutine_results.clear();
+        self.flex = None;
+        self.subroutine_depth = 0;
+        self.is_finished = false;
     }
 
     pub fn evaluate(&mut self, char_code: u32) -> PostScriptResult<Glyph> {
@@ -307,23 +340,51 @@ impl<'a> CharStringPainter<'a> {
             return Ok(glyph.clone());
         }
 
+        let charstring_name = self.encoding.get(char_code);
+
+        let glyph = match self.char_strings.get_by_name(charstring_name.borrow()) {
+            Some(charstring) => self.evaluate_charstring(charstring)?,
+            None => Glyph::empty(),
+        };
+
+        self.gylph_cache.insert(char_code, glyph.clone());
+
+        Ok(glyph)
+    }
+
+    fn evaluate_charstring(&mut self, charstring: &CharString) -> PostScriptResult<Glyph> {
         self.reinit();
 
-        let charstring_name = self.encoding.get(char_code);
+        self.evaluate_as_subroutine(charstring)?;
+
+        Ok(Glyph {
+            outline: self.path_builder.outline.clone(),
+            width_vector: self.path_builder.width_vector,
+        })
+    }
 
-        if let Some(charstring) = self.char_strings.get_by_name(charstring_name.borrow()) {
-            let glyph = self.evaluate_as_subroutine(charstring)?;
+    /// Evaluate the glyph for a character code in the Adobe StandardEncoding,
+    /// as used by the components of an accented character
+    fn evaluate_standard_encoding_char(&mut self, code: f32) -> PostScriptResult<Glyph> {
+        let name = match STANDARD_ENCODING.get(code as usize) {
+            Some(Some(name)) => name,
+            _ => anyhow::bail!(PostScriptError::RangeCheck),
+        };
 
-            self.gylph_cache.insert(char_code, glyph.clone());
+        let char_strings = self.char_strings;
 
-            Ok(glyph)
-        } else {
-            Ok(Glyph::empty())
+        match char_strings.get_by_name(&PostScriptString::from_bytes(name.as_bytes().to_vec())) {
+            Some(charstring) => self.evaluate_charstring(charstring),
+            None => Ok(Glyph::empty()),
         }
     }
 
-    fn evaluate_as_subroutine(&mut self, c: &CharString) -> PostScriptResult<Glyph> {
+    fn evaluate_as_subroutine(&mut self, c: &CharString) -> PostScriptResult<()> {
         for &elem in &c.0 {
+            if self.is_finished {
+                break;
+            }
+
             match elem {
                 CharStringElement::Int(n) => self.operand_stack.push(n as f32)?,
                 CharStringElement::Op(GraphicsOperator::HorizontalStem) => {
@@ -347,7 +408,7 @@ impl<'a> CharStringPainter<'a> {
 
                     self.operand_stack.clear();
 
-                    self.path_builder.relative_move_to(0.0, dy);
+                    self.relative_move_to(0.0, dy);
                 }
                 CharStringElement::Op(GraphicsOperator::RelativeLineTo) => {
                     let dx = self.operand_stack.pop_front()?;
@@ -393,7 +454,9 @@ impl<'a> CharStringPainter<'a> {
                     self.call_subroutine(subr_number)?;
                 }
                 CharStringElement::Op(GraphicsOperator::Return) => break,
-                CharStringElement::Op(GraphicsOperator::DotSection) => todo!(),
+                // hint replacement is not implemented, so there is nothing to
+                // suspend
+                CharStringElement::Op(GraphicsOperator::DotSection) => self.operand_stack.clear(),
                 CharStringElement::Op(GraphicsOperator::VerticalStem3) => {
                     let x0 = self.operand_stack.pop_front()?;
                     let dx0 = self.operand_stack.pop_front()?;
@@ -419,7 +482,6 @@ impl<'a> CharStringPainter<'a> {
                     self.path_builder
                         .horizontal_stem3(y0, dy0, y1, dy1, y2, dy2);
                 }
-                #[allow(unused)]
                 CharStringElement::Op(GraphicsOperator::StandardEncodingAccentedCharacter) => {
                     let asb = self.operand_stack.pop_front()?;
                     let adx = self.operand_stack.pop_front()?;
@@ -429,9 +491,8 @@ impl<'a> CharStringPainter<'a> {
 
                     self.operand_stack.clear();
 
-                    todo!()
+                    self.standard_encoding_accented_character(asb, adx, ady, bchar, achar)?;
                 }
-                #[allow(unused)]
                 CharStringElement::Op(GraphicsOperator::SideBearingWidth) => {
                     let sbx = self.operand_stack.pop_front()?;
                     let sby = self.operand_stack.pop_front()?;
@@ -440,11 +501,11 @@ impl<'a> CharStringPainter<'a> {
 
                     self.operand_stack.clear();
 
-                    todo!()
+                    self.path_builder.sbw(sbx, sby, wx, wy);
                 }
                 CharStringElement::Op(GraphicsOperator::Div) => {
-                    let num1 = self.operand_stack.stack[0];
-                    let num2 = self.operand_stack.stack[1];
+                    let num2 = self.operand_stack.pop()?;
+                    let num1 = self.operand_stack.pop()?;
 
                     self.operand_stack.push(num1 / num2)?;
                 }
@@ -460,12 +521,20 @@ impl<'a> CharStringPainter<'a> {
 
                     self.call_other_subroutine(other_subr_number as usize, args)?;
                 }
-                CharStringElement::Op(GraphicsOperator::Pop) => match self.interpreter.pop()? {
-                    PostScriptObject::Float(n) => self.operand_stack.push(n)?,
-                    PostScriptObject::Int(n) => self.operand_stack.push(n as f32)?,
-                    obj => todo!("unexpected operand: {:#?}", obj),
-                },
-                CharStringElement::Op(GraphicsOperator::SetCurrentPoint) => todo!(),
+                CharStringElement::Op(GraphicsOperator::Pop) => {
+                    match self.other_subroutine_results.pop() {
+                        Some(n) => self.operand_stack.push(n)?,
+                        None => anyhow::bail!(PostScriptError::StackUnderflow),
+                    }
+                }
+                CharStringElement::Op(GraphicsOperator::SetCurrentPoint) => {
+                    let x = self.operand_stack.pop_front()?;
+                    let y = self.operand_stack.pop_front()?;
+
+                    self.operand_stack.clear();
+
+                    self.path_builder.current_path.current_point = Point::new(x, y);
+                }
                 CharStringElement::Op(GraphicsOperator::HorizontalSideBearingWidth) => {
                     let side_bearing_x_coord = self.operand_stack.pop_front()?;
                     let width_vector_x_coord = self.operand_stack.pop_front()?;
@@ -476,20 +545,21 @@ impl<'a> CharStringPainter<'a> {
                     self.operand_stack.clear();
                 }
                 CharStringElement::Op(GraphicsOperator::EndChar) => {
-                    // todo: rest of this operator
                     if !self.path_builder.current_path.subpaths.is_empty() {
                         self.path_builder
                             .outline
                             .paths
                             .push(self.path_builder.current_path.clone());
                     }
-                    break;
+
+                    self.operand_stack.clear();
+                    self.is_finished = true;
                 }
                 CharStringElement::Op(GraphicsOperator::RelativeMoveTo) => {
                     let dx = self.operand_stack.pop_front()?;
                     let dy = self.operand_stack.pop_front()?;
 
-                    self.path_builder.relative_move_to(dx, dy);
+                    self.relative_move_to(dx, dy);
 
                     self.operand_stack.clear();
                 }
@@ -498,7 +568,7 @@ impl<'a> CharStringPainter<'a> {
 
                     self.operand_stack.clear();
 
-                    self.path_builder.relative_move_to(dx, 0.0);
+                    self.relative_move_to(dx, 0.0);
                 }
                 CharStringElement::Op(GraphicsOperator::VerticalHorizontalCurveTo) => {
                     let dy1 = self.operand_stack.pop_front()?;
@@ -525,47 +595,317 @@ impl<'a> CharStringPainter<'a> {
             }
         }
 
-        assert!(self.operand_stack.is_empty(), "{:?}", self.operand_stack);
+        Ok(())
+    }
 
-        Ok(Glyph {
-            outline: self.path_builder.outline.clone(),
-            width_vector: self.path_builder.width_vector,
-        })
+    /// Within a flex feature, moveto commands only supply the points of the
+    /// curves to OtherSubr 2 and do not move the current point
+    fn relative_move_to(&mut self, dx: f32, dy: f32) {
+        match &mut self.flex {
+            Some(flex) => {
+                flex.current_point.x += dx;
+                flex.current_point.y += dy;
+            }
+            None => self.path_builder.relative_move_to(dx, dy),
+        }
+    }
+
+    /// Build an accented character from two characters of the font, positioning
+    /// the accent such that its left sidebearing point is at (adx, ady) relative
+    /// to the origin of the base character
+    fn standard_encoding_accented_character(
+        &mut self,
+        asb: f32,
+        adx: f32,
+        ady: f32,
+        bchar: f32,
+        achar: f32,
+    ) -> PostScriptResult<()> {
+        let width_vector = self.path_builder.width_vector;
+
+        let base = self.evaluate_standard_encoding_char(bchar)?;
+        let mut accent = self.evaluate_standard_encoding_char(achar)?;
+
+        accent
+            .outline
+            .apply_transform(Matrix::new_translation(adx - asb, ady));
+
+        self.reinit();
+
+        self.path_builder.width_vector = width_vector;
+        self.path_builder.outline.paths = base.outline.paths;
+        self.path_builder
+            .outline
+            .paths
+            .append(&mut accent.outline.paths);
+
+        // seac takes the place of endchar
+        self.is_finished = true;
+
+        Ok(())
     }
 
     fn call_subroutine(&mut self, subr_number: usize) -> PostScriptResult<()> {
-        match subr_number {
-            0..=3 => todo!(),
-            _ => {
-                let subr = &self.subroutines[subr_number];
+        let subroutines = self.subroutines;
 
-                self.evaluate_as_subroutine(subr)?;
-            }
+        let subr = match subroutines.get(subr_number) {
+            Some(subr) => subr,
+            None => anyhow::bail!(PostScriptError::RangeCheck),
+        };
+
+        if self.subroutine_depth >= Self::MAX_SUBROUTINE_DEPTH {
+            anyhow::bail!(PostScriptError::ExecStackOverflow);
         }
 
+        self.subroutine_depth += 1;
+        self.evaluate_as_subroutine(subr)?;
+        self.subroutine_depth -= 1;
+
         Ok(())
     }
 
+    /// OtherSubrs are PostScript procedures, but their behavior is fixed by the
+    /// Type 1 specification, so rather than executing them, we implement them
+    /// directly
+    ///
+    /// `args` are in the order they were popped from the operand stack.
     fn call_other_subroutine(
         &mut self,
         other_subr_number: usize,
         args: Vec<f32>,
     ) -> PostScriptResult<()> {
+        self.other_subroutine_results.clear();
+
         match other_subr_number {
-            0..=3 => {
-                let subr = &self.other_subroutines[other_subr_number];
+            // flexheight x y 3 0 callothersubr
+            //
+            // Ends a flex feature, drawing its two curves. The end point is left
+            // to be popped for use by setcurrentpoint
+            0 => {
+                let flex = match self.flex.take() {
+                    Some(flex) if flex.points.len() == 7 && args.len() == 3 => flex,
+                    _ => anyhow::bail!(PostScriptError::RangeCheck),
+                };
+
+                let points = flex.points;
+
+                // the first point is the reference point, which only matters
+                // when the flex is to be drawn as a straight line
+                self.path_builder
+                    .current_path
+                    .cubic_curve_to(points[1], points[2], points[3]);
+                self.path_builder
+                    .current_path
+                    .cubic_curve_to(points[4], points[5], points[6]);
+
+                let (y, x) = (args[0], args[1]);
+
+                self.other_subroutine_results.extend([y, x]);
+            }
+            // 0 1 callothersubr
+            //
+            // Begins a flex feature
+            1 => {
+                self.flex = Some(Flex {
+                    current_point: self.path_builder.current_path.current_point,
+                    points: Vec::with_capacity(7),
+                });
+            }
+            // 0 2 callothersubr
+            //
+            // Adds the current point to the points of the flex feature
+            2 => match &mut self.flex {
+                Some(flex) => flex.points.push(flex.current_point),
+                None => anyhow::bail!(PostScriptError::RangeCheck),
+            },
+            // subr# 1 3 callothersubr
+            //
+            // Hint replacement. We don't use hints, so rather than calling the
+            // subroutine containing the new hints, we call subroutine 3, which
+            // does nothing
+            3 => self.other_subroutine_results.push(3.0),
+            // Reserved or font-specific OtherSubrs. Their arguments are left
+            // for any following pop commands, in their original order
+            _ => self.other_subroutine_results.extend(args),
+        }
 
-                for arg in args {
-                    self.interpreter.push(PostScriptObject::Float(arg));
-                }
+        Ok(())
+    }
+}
 
-                self.interpreter
-                    .execute_procedure(subr.clone().into_inner())
-                    .unwrap();
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::Subpath;
+
+    /// Encode and encrypt a charstring program written as numbers and operator
+    /// names, as it would appear in the CharStrings of a font
+    fn encrypted(program: &str) -> Vec<u8> {
+        let mut plain = vec![0; 4];
+
+        for token in program.split_whitespace() {
+            match token.parse::<i32>() {
+                Ok(n @ -107..=107) => plain.push((n + 139) as u8),
+                Ok(n) => {
+                    plain.push(255);
+                    plain.extend(n.to_be_bytes());
+                }
+                Err(..) => plain.extend_from_slice(match token {
+                    "hsbw" => &[13],
+                    "rmoveto" => &[21],
+                    "rlineto" => &[5],
+                    "closepath" => &[9],
+                    "endchar" => &[14],
+                    "seac" => &[12, 6],
+                    "callothersubr" => &[12, 16],
+                    "pop" => &[12, 17],
+                    "setcurrentpoint" => &[12, 33],
+                    op => panic!("unknown operator: {}", op),
+                }),
             }
-            _ => todo!("use of reserved other subroutine idx"),
         }
 
-        Ok(())
+        let mut r: u16 = 4330;
+
+        plain
+            .into_iter()
+            .map(|p| {
+                let c = p ^ (r >> 8) as u8;
+                r = (c as u16)
+                    .wrapping_add(r)
+                    .wrapping_mul(52845)
+                    .wrapping_add(22719);
+                c
+            })
+            .collect()
+    }
+
+    fn char_string(program: &str) -> CharString {
+        CharString::parse(&mut encrypted(program), false).unwrap()
+    }
+
+    fn painter<'a>(encoding: &'a Encoding, char_strings: &'a CharStrings) -> CharStringPainter<'a> {
+        CharStringPainter {
+            path_builder: PathBuilder::new(),
+            has_current_point: false,
+            subroutines: &[],
+            operand_stack: CharStringStack::new(),
+            other_subroutine_results: Vec::new(),
+            flex: None,
+            subroutine_depth: 0,
+            is_finished: false,
+            encoding,
+            char_strings,
+            gylph_cache: BTreeMap::new(),
+        }
+    }
+
+    fn name(name: &str) -> PostScriptString {
+        PostScriptString::from_bytes(name.as_bytes().to_vec())
+    }
+
+    /// The end point of each segment of each path of `glyph`
+    fn end_points(glyph: &Glyph) -> Vec<Vec<Point>> {
+        glyph
+            .outline
+            .paths
+            .iter()
+            .map(|path| {
+                path.subpaths
+                    .iter()
+                    .map(|subpath| match subpath {
+                        Subpath::Line(line) => line.end,
+                        Subpath::Quadratic(curve) => curve.end,
+                        Subpath::Cubic(curve) => curve.end,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_long_integers() {
+        let charstring = char_string("300 -1000 rlineto 5 endchar");
+
+        assert!(matches!(
+            charstring.0[..],
+            [
+                CharStringElement::Int(300),
+                CharStringElement::Int(-1000),
+                CharStringElement::Op(GraphicsOperator::RelativeLineTo),
+                CharStringElement::Int(5),
+                CharStringElement::Op(GraphicsOperator::EndChar),
+            ]
+        ));
+    }
+
+    #[test]
+    fn flex() {
+        let encoding = Encoding::new(Vec::new());
+        let char_strings = CharStrings(HashMap::new());
+
+        // the reference point, then the control and end points of both curves
+        let glyph = painter(&encoding, &char_strings)
+            .evaluate_charstring(&char_string(
+                "0 500 hsbw \
+                 0 1 callothersubr \
+                 50 10 rmoveto 0 2 callothersubr \
+                 -40 0 rmoveto 0 2 callothersubr \
+                 20 10 rmoveto 0 2 callothersubr \
+                 20 0 rmoveto 0 2 callothersubr \
+                 20 0 rmoveto 0 2 callothersubr \
+                 20 -10 rmoveto 0 2 callothersubr \
+                 10 -10 rmoveto 0 2 callothersubr \
+                 50 100 0 3 0 callothersubr pop pop setcurrentpoint \
+                 closepath endchar",
+            ))
+            .unwrap();
+
+        assert_eq!(glyph.width_vector, Point::new(500.0, 0.0));
+        assert_eq!(
+            end_points(&glyph),
+            [vec![
+                Point::new(50.0, 20.0),
+                Point::new(100.0, 0.0),
+                Point::new(0.0, 0.0),
+            ]]
+        );
+    }
+
+    #[test]
+    fn accented_character() {
+        let encoding = Encoding::new(vec![None; 256]);
+        let char_strings = CharStrings(HashMap::from([
+            (
+                name("A"),
+                char_string("0 600 hsbw 100 0 rlineto 0 100 rlineto closepath endchar"),
+            ),
+            (
+                name("acute"),
+                char_string("20 300 hsbw 10 20 rmoveto 5 0 rlineto 0 5 rlineto closepath endchar"),
+            ),
+        ]));
+
+        // the accent is placed with its sidebearing point at (200, 100)
+        let glyph = painter(&encoding, &char_strings)
+            .evaluate_charstring(&char_string("0 600 hsbw 20 200 100 65 194 seac"))
+            .unwrap();
+
+        assert_eq!(glyph.width_vector, Point::new(600.0, 0.0));
+        assert_eq!(
+            end_points(&glyph),
+            [
+                vec![
+                    Point::new(100.0, 0.0),
+                    Point::new(100.0, 100.0),
+                    Point::new(0.0, 0.0),
+                ],
+                vec![
+                    Point::new(215.0, 120.0),
+                    Point::new(215.0, 125.0),
+                    Point::new(210.0, 120.0),
+                ],
+            ]
+        );
     }
 }
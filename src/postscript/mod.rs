@@ -1,4 +1,5 @@
 pub(crate) use {
+    builtin::STANDARD_ENCODING,
     error::{PostScriptError, PostScriptResult},
     interpreter::PostscriptInterpreter,
 };
@@ -0,0 +1,363 @@
+/*!
+ * A common report for the findings of each of the document validators, such as
+ * the outline, accessibility and certification checks, which can be written
+ * as JSON for use by build pipelines
+ */
+
+use std::fmt::Write;
+
+use crate::{
+    content::ContentDiagnostic,
+    error::PdfResult,
+    font::MissingFont,
+    objects::Reference,
+    outline::{OutlineIssue, OutlineProblem},
+    signature::{Change, DisallowedChange},
+    structure::{AccessibilityIssue, AccessibilityProblem},
+    Parser,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The document may display or behave differently than intended, or
+    /// does not meet a standard it may be expected to
+    Warning,
+
+    /// The document is defective
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A single defect found in a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreflightFinding {
+    /// A stable identifier of the rule which the document breaks, such as
+    /// `outline.missing-page`
+    pub rule: &'static str,
+
+    pub severity: Severity,
+
+    /// The object concerned, if the finding concerns one
+    pub object: Option<Reference>,
+
+    /// The index of the page concerned, if known
+    pub page: Option<usize>,
+
+    /// A description of the defect, suitable for presenting to a person
+    pub message: String,
+}
+
+/// The findings of every validator run over a document
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PreflightReport {
+    pub findings: Vec<PreflightFinding>,
+}
+
+impl PreflightReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, finding: impl Into<PreflightFinding>) {
+        self.findings.push(finding.into());
+    }
+
+    pub fn extend<T: Into<PreflightFinding>>(&mut self, findings: impl IntoIterator<Item = T>) {
+        self.findings.extend(findings.into_iter().map(Into::into));
+    }
+
+    /// The number of findings of at least `severity`
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity >= severity)
+            .count()
+    }
+
+    /// Whether any finding is an error, such that a build should fail
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    /// The report as a JSON object, with the number of errors and warnings and
+    /// an array of findings
+    ///
+    /// Objects are written in the form `"12 0 R"`, and missing objects and
+    /// pages as `null`.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+
+        write!(
+            json,
+            "{{\"errors\":{},\"warnings\":{},\"findings\":[",
+            self.count(Severity::Error),
+            self.findings.len() - self.count(Severity::Error)
+        )
+        .unwrap();
+
+        for (idx, finding) in self.findings.iter().enumerate() {
+            if idx > 0 {
+                json.push(',');
+            }
+
+            let object = match finding.object {
+                Some(reference) => {
+                    format!("\"{} {} R\"", reference.object_number, reference.generation)
+                }
+                None => "null".to_owned(),
+            };
+
+            let page = match finding.page {
+                Some(page) => page.to_string(),
+                None => "null".to_owned(),
+            };
+
+            write!(
+                json,
+                "{{\"rule\":{},\"severity\":\"{}\",\"object\":{},\"page\":{},\"message\":{}}}",
+                json_string(finding.rule),
+                finding.severity.as_str(),
+                object,
+                page,
+                json_string(&finding.message)
+            )
+            .unwrap();
+        }
+
+        json.push_str("]}");
+
+        json
+    }
+}
+
+/// `s` as a quoted JSON string
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+
+    quoted.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+
+    quoted
+}
+
+impl From<OutlineIssue> for PreflightFinding {
+    fn from(issue: OutlineIssue) -> Self {
+        let (rule, message) = match &issue.problem {
+            OutlineProblem::MissingPage(page) => (
+                "outline.missing-page",
+                format!(
+                    "outline item {:?} refers to object {} {} R, which is not a page",
+                    issue.title, page.object_number, page.generation
+                ),
+            ),
+            OutlineProblem::UndefinedDestination(name) => (
+                "outline.undefined-destination",
+                format!(
+                    "outline item {:?} refers to the undefined destination {:?}",
+                    issue.title, name
+                ),
+            ),
+        };
+
+        Self {
+            rule,
+            severity: Severity::Warning,
+            object: Some(issue.reference),
+            page: None,
+            message,
+        }
+    }
+}
+
+impl From<AccessibilityIssue> for PreflightFinding {
+    fn from(issue: AccessibilityIssue) -> Self {
+        let element = issue.structure_type.as_deref().unwrap_or("structure");
+
+        let (rule, message) = match &issue.problem {
+            AccessibilityProblem::NotTagged => {
+                ("ua.not-tagged", "the document is not tagged".to_owned())
+            }
+            AccessibilityProblem::MissingLanguage => (
+                "ua.missing-language",
+                "the document does not specify its language".to_owned(),
+            ),
+            AccessibilityProblem::UntaggedContent(text) => (
+                "ua.untagged-content",
+                format!(
+                    "text {:?} is neither tagged nor marked as an artifact",
+                    text
+                ),
+            ),
+            AccessibilityProblem::UnreferencedContent(mcid) => (
+                "ua.unreferenced-content",
+                format!(
+                    "marked content {} is not referred to by any structure element",
+                    mcid
+                ),
+            ),
+            AccessibilityProblem::MissingAlternateText => (
+                "ua.missing-alt-text",
+                format!("{} element has no alternate text", element),
+            ),
+            AccessibilityProblem::SkippedHeadingLevel { level, previous: 0 } => (
+                "ua.skipped-heading-level",
+                format!("the first heading, {}, is of level {}", element, level),
+            ),
+            AccessibilityProblem::SkippedHeadingLevel { level, previous } => (
+                "ua.skipped-heading-level",
+                format!(
+                    "{} element of level {} follows a heading of level {}",
+                    element, level, previous
+                ),
+            ),
+        };
+
+        Self {
+            rule,
+            severity: Severity::Warning,
+            object: issue.element,
+            page: issue.page,
+            message,
+        }
+    }
+}
+
+impl From<DisallowedChange> for PreflightFinding {
+    fn from(change: DisallowedChange) -> Self {
+        let verb = match change.change {
+            Change::Added => "added",
+            Change::Modified => "modified",
+            Change::Removed => "removed",
+        };
+
+        Self {
+            rule: "signature.disallowed-change",
+            severity: Severity::Error,
+            object: Some(change.reference),
+            page: None,
+            message: format!(
+                "revision {} {} {:?} object, which the certification does not permit",
+                change.revision, verb, change.object
+            ),
+        }
+    }
+}
+
+impl From<MissingFont> for PreflightFinding {
+    fn from(font: MissingFont) -> Self {
+        Self {
+            rule: "font.not-embedded",
+            severity: Severity::Warning,
+            object: None,
+            page: font.pages.first().copied(),
+            message: format!("font {} is not embedded", font.base_font),
+        }
+    }
+}
+
+impl PreflightFinding {
+    /// A finding for an error skipped over while interpreting the content of
+    /// the page at index `page`
+    pub(crate) fn content(page: usize, diagnostic: ContentDiagnostic) -> Self {
+        let message = match &diagnostic.operator {
+            Some(operator) => format!(
+                "operator {} at offset {}: {}",
+                operator, diagnostic.offset, diagnostic.message
+            ),
+            None => format!("offset {}: {}", diagnostic.offset, diagnostic.message),
+        };
+
+        Self {
+            rule: "content.malformed",
+            severity: Severity::Error,
+            object: None,
+            page: Some(page),
+            message,
+        }
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Run each of the document validators, collecting their findings into a
+    /// single report
+    ///
+    /// This checks the outline, font embedding, the content of each page,
+    /// changes made since the document was certified, if it is, and PDF/UA
+    /// conformance. Malformed content and disallowed changes are errors, and
+    /// everything else a warning.
+    pub fn preflight(&mut self) -> PdfResult<PreflightReport> {
+        let mut report = PreflightReport::new();
+
+        report.extend(self.validate_outline()?);
+        report.extend(self.missing_fonts());
+
+        for (idx, page) in self.pages().into_iter().enumerate() {
+            if page.contents.is_none() {
+                continue;
+            }
+
+            let diagnostics = self.content_diagnostics(&page)?;
+
+            report.extend(
+                diagnostics
+                    .into_iter()
+                    .map(|diagnostic| PreflightFinding::content(idx, diagnostic)),
+            );
+        }
+
+        report.extend(self.check_certified_changes()?);
+        report.extend(self.check_accessibility()?);
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn report_to_json() {
+        let mut report = PreflightReport::new();
+
+        report.push(PreflightFinding {
+            rule: "ua.missing-alt-text",
+            severity: Severity::Warning,
+            object: Some(Reference {
+                object_number: 12,
+                generation: 0,
+            }),
+            page: Some(3),
+            message: "Figure \"logo\"\n".to_owned(),
+        });
+
+        assert!(!report.has_errors());
+        assert_eq!(
+            report.to_json(),
+            "{\"errors\":0,\"warnings\":1,\"findings\":[{\"rule\":\"ua.missing-alt-text\",\
+             \"severity\":\"warning\",\"object\":\"12 0 R\",\"page\":3,\
+             \"message\":\"Figure \\\"logo\\\"\\n\"}]}"
+        );
+    }
+}
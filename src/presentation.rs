@@ -0,0 +1,263 @@
+/*!
+ * Sub-page navigation and alternate presentations, for documents which are
+ * played back as slide shows
+ *
+ * The navigation nodes of a page form a doubly linked list, through their Next
+ * and Prev entries. Moving forward or back through a page performs the
+ * actions of the current node, which typically change the state of optional
+ * content to reveal or hide parts of the page, before moving on to the next
+ * page once the last node has been reached.
+ */
+
+use std::collections::HashSet;
+
+use crate::{
+    actions::{ActionType, Actions},
+    attachment::Attachment,
+    data_structures::NameTree,
+    error::PdfResult,
+    objects::{Object, Reference},
+    FromObj, Parser, Resolve,
+};
+
+/// A navigation node dictionary, one step of the sub-page navigation of a page
+#[derive(Debug, FromObj)]
+#[obj_type("NavNode")]
+pub struct NavigationNode<'a> {
+    /// The actions performed to move forward to the next node
+    #[field("NA")]
+    pub next_action: Option<Actions<'a>>,
+
+    /// The actions performed to move back to the previous node
+    #[field("PA")]
+    pub previous_action: Option<Actions<'a>>,
+
+    /// The next navigation node
+    #[field("Next")]
+    pub next: Option<Reference>,
+
+    /// The previous navigation node
+    #[field("Prev")]
+    pub prev: Option<Reference>,
+
+    /// The maximum time, in seconds, before the viewer moves forward to the
+    /// next node automatically
+    #[field("Dur")]
+    pub duration: Option<f32>,
+}
+
+/// One step of the sub-page navigation of a page
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavigationStep {
+    /// The index of the page, starting from 0
+    pub page: usize,
+
+    /// The navigation node dictionary, or `None` for the first node of a page
+    /// which it does not refer to indirectly
+    pub reference: Option<Reference>,
+
+    /// The maximum time, in seconds, before the viewer moves forward to the
+    /// next step automatically, or `None` if it does not
+    pub duration: Option<f32>,
+
+    /// The type of each action performed to move forward from this step
+    pub next_action_types: Vec<ActionType>,
+
+    /// The type of each action performed to move back from this step
+    pub previous_action_types: Vec<ActionType>,
+}
+
+impl NavigationStep {
+    fn new(page: usize, reference: Option<Reference>, node: &NavigationNode) -> Self {
+        Self {
+            page,
+            reference,
+            duration: node.duration,
+            next_action_types: node
+                .next_action
+                .as_ref()
+                .map(Actions::action_types)
+                .unwrap_or_default(),
+            previous_action_types: node
+                .previous_action
+                .as_ref()
+                .map(Actions::action_types)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The steps of the page at index `page`, starting from its first
+    /// navigation node and following their Next entries
+    ///
+    /// A node which has already been reached ends the steps, so that lists
+    /// which loop back on themselves are only walked once.
+    pub(crate) fn from_first_node<'a>(
+        page: usize,
+        first: &NavigationNode<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Vec<Self>> {
+        let mut steps = vec![NavigationStep::new(page, None, first)];
+        let mut visited = HashSet::new();
+        let mut next = first.next;
+
+        while let Some(reference) = next.filter(|node| visited.insert(*node)) {
+            let node =
+                NavigationNode::from_obj(resolver.lex_object_from_reference(reference)?, resolver)?;
+
+            // the first node is not known by its reference, but the second
+            // refers back to it
+            if steps.len() == 1 {
+                if let Some(prev) = node.prev {
+                    visited.insert(prev);
+                }
+            }
+
+            steps.push(NavigationStep::new(page, Some(reference), &node));
+            next = node.next;
+        }
+
+        Ok(steps)
+    }
+}
+
+/// A slide show dictionary, the only kind of alternate presentation
+#[derive(Debug, FromObj)]
+#[obj_type("SlideShow", subtype = "Embedded")]
+struct SlideShowDictionary<'a> {
+    /// A dictionary whose Names entry maps names to the file specifications
+    /// of the slides and other files used by the slide show
+    #[field("Resources")]
+    resources: NameTree<'a>,
+
+    /// The name of the first slide in the Resources name tree
+    #[field("StartResource")]
+    start_resource: String,
+}
+
+/// A slide show to be presented in place of the document, listed in the
+/// AlternatePresentations name tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlternatePresentation {
+    /// The key of the slide show in the AlternatePresentations name tree
+    pub name: String,
+
+    /// The name, among the resources, of the slide with which the slide show
+    /// starts
+    pub start_resource: String,
+
+    /// The slides and other files used by the slide show
+    pub resources: Vec<Attachment>,
+}
+
+impl AlternatePresentation {
+    pub(crate) fn from_slide_show<'a>(
+        name: &str,
+        obj: Object<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let slide_show = SlideShowDictionary::from_obj(obj, resolver)?;
+
+        let mut resources = Vec::new();
+
+        for (name, obj) in slide_show.resources.entries() {
+            if let Some(attachment) =
+                Attachment::from_file_specification(name, obj.clone(), resolver)?
+            {
+                resources.push(attachment);
+            }
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            start_resource: slide_show.start_resource,
+            resources,
+        })
+    }
+
+    /// The slide with which the slide show starts, if it is embedded in the
+    /// document
+    pub fn start(&self) -> Option<&Attachment> {
+        self.resources
+            .iter()
+            .find(|resource| resource.name == self.start_resource)
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// The sub-page navigation steps of every page which has them, in the
+    /// order in which they are presented
+    pub fn navigation_steps(&mut self) -> PdfResult<Vec<NavigationStep>> {
+        let mut steps = Vec::new();
+
+        for (page, page_object) in self.pages().iter().enumerate() {
+            if let Some(first) = &page_object.pres_steps {
+                steps.extend(NavigationStep::from_first_node(
+                    page,
+                    first,
+                    &mut self.lexer,
+                )?);
+            }
+        }
+
+        Ok(steps)
+    }
+
+    /// The slide shows to be presented in place of the document, listed in its
+    /// AlternatePresentations name tree
+    pub fn alternate_presentations(&mut self) -> PdfResult<Vec<AlternatePresentation>> {
+        let names = match &self.catalog.names {
+            Some(names) => names.get_ref(&mut self.lexer)?.into_owned(),
+            None => return Ok(Vec::new()),
+        };
+
+        let alternate_presentations = match &names.alternate_presentations {
+            Some(alternate_presentations) => alternate_presentations,
+            None => return Ok(Vec::new()),
+        };
+
+        alternate_presentations
+            .entries()
+            .into_iter()
+            .map(|(name, obj)| {
+                AlternatePresentation::from_slide_show(name, obj.clone(), &mut self.lexer)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    #[test]
+    fn navigation_node() {
+        let mut tokenizer = Tokenizer::new(
+            b"<< /Type /NavNode /NA << /S /Trans /Trans << /S /Dissolve >> >> \
+               /Next 12 0 R /Dur 2.5 >>",
+        );
+
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+        let node = NavigationNode::from_obj(obj, &mut tokenizer).unwrap();
+
+        assert_eq!(
+            node.next,
+            Some(Reference {
+                object_number: 12,
+                generation: 0,
+            })
+        );
+        assert_eq!(node.prev, None);
+
+        assert_eq!(
+            NavigationStep::new(3, None, &node),
+            NavigationStep {
+                page: 3,
+                reference: None,
+                duration: Some(2.5),
+                next_action_types: vec![ActionType::Trans],
+                previous_action_types: Vec::new(),
+            }
+        );
+    }
+}
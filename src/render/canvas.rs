@@ -1,14 +1,13 @@
-use std::{fs::File, io::BufWriter, mem, path::Path as FilePath};
-
-use bitvec::{prelude::Lsb0, slice::BitSlice};
+use std::{fs::File, io::BufWriter, mem, path::Path as FilePath, rc::Rc};
 
 use crate::{
-    color::{Color, ColorSpace, ColorSpaceName},
+    color::Color,
     error::PdfResult,
-    filter::{decode_stream, flate::BitsPerComponent},
+    function::TransferTable,
     geometry::{CubicBezierCurve, Line, Outline, Path, Point, QuadraticBezierCurve, Subpath},
+    halftones::HalftoneScreen,
     resolve::Resolve,
-    xobject::ImageXObject,
+    xobject::{ImageXObject, RgbaImage},
 };
 
 #[cfg(feature = "window")]
@@ -34,30 +33,56 @@ pub(super) struct Canvas {
     width: usize,
     height: usize,
     buffer: Vec<u32>,
+
+    /// The soft mask currently in effect, as a per-pixel opacity in the range
+    /// 0.0 to 1.0
+    mask: Option<Rc<[f32]>>,
+
+    /// The transfer function applied to colours before they are painted
+    transfer: Option<Rc<TransferTable>>,
+
+    /// The halftone screen with which painted pixels are rendered, if the
+    /// output of a bilevel device is being approximated
+    screen: Option<Rc<HalftoneScreen>>,
+
     #[cfg(feature = "window")]
-    window: Window,
+    window: Option<Window>,
 }
 
 fn parse_rgba(color: u32) -> [u8; 4] {
     let r = color & 0xff;
     let g = (color >> 8) & 0xff;
     let b = (color >> 16) & 0xff;
+    let a = (color >> 24) & 0xff;
 
-    [r as u8, g as u8, b as u8, 0xff]
+    [r as u8, g as u8, b as u8, a as u8]
 }
 
+/// Composite an opaque `color` with the given opacity over `background`, which
+/// may itself be partially transparent
 fn apply_opacity(color: u32, opacity: f32, background: u32) -> u32 {
     assert!(opacity >= 0.0 && opacity <= 1.0);
 
     let [red, green, blue, _] = parse_rgba(color);
-    let [bg_red, bg_green, bg_blue, _] = parse_rgba(background);
+    let [bg_red, bg_green, bg_blue, bg_alpha] = parse_rgba(background);
+
+    let bg_alpha = bg_alpha as f32 / 255.0;
+    let alpha = opacity + bg_alpha * (1.0 - opacity);
 
-    ColorSpace::DeviceRGB {
-        red: ((red as f32 * opacity) + bg_red as f32 * (1.0 - opacity)) / 255.0,
-        green: ((green as f32 * opacity) + bg_green as f32 * (1.0 - opacity)) / 255.0,
-        blue: ((blue as f32 * opacity) + bg_blue as f32 * (1.0 - opacity)) / 255.0,
+    if alpha == 0.0 {
+        return background;
     }
-    .as_u32()
+
+    let blend = |c: u8, bg: u8| {
+        ((c as f32 * opacity + bg as f32 * bg_alpha * (1.0 - opacity)) / alpha).round() as u32
+    };
+
+    let r = blend(red, bg_red);
+    let g = blend(green, bg_green);
+    let b = blend(blue, bg_blue);
+    let a = (alpha * 255.0).round() as u32;
+
+    (a << 24) | (b << 16) | (g << 8) | r
 }
 
 impl Canvas {
@@ -71,7 +96,10 @@ impl Canvas {
             width,
             height,
             buffer: vec![u32::MAX; width * height],
-            window,
+            mask: None,
+            transfer: None,
+            screen: None,
+            window: Some(window),
         }
     }
 
@@ -81,9 +109,64 @@ impl Canvas {
             width,
             height,
             buffer: vec![u32::MAX; width * height],
+            mask: None,
+            transfer: None,
+            screen: None,
         }
     }
 
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// A canvas of the same dimensions as this one, filled with `background`,
+    /// which is never displayed. Used to render transparency groups, such as
+    /// soft masks, before they are composited
+    pub fn offscreen(&self, background: u32) -> Self {
+        Self {
+            width: self.width,
+            height: self.height,
+            buffer: vec![background; self.width * self.height],
+            mask: None,
+            transfer: None,
+            screen: None,
+            #[cfg(feature = "window")]
+            window: None,
+        }
+    }
+
+    pub fn set_soft_mask(&mut self, mask: Option<Rc<[f32]>>) {
+        self.mask = mask;
+    }
+
+    pub fn set_transfer_table(&mut self, transfer: Option<Rc<TransferTable>>) {
+        self.transfer = transfer;
+    }
+
+    pub fn set_halftone_screen(&mut self, screen: Option<Rc<HalftoneScreen>>) {
+        self.screen = screen;
+    }
+
+    /// A soft mask derived from the luminosity of each pixel of this canvas
+    pub fn luminosity_mask(&self) -> Rc<[f32]> {
+        self.buffer
+            .iter()
+            .map(|&color| {
+                let [r, g, b, _] = parse_rgba(color);
+
+                (0.3 * r as f32 + 0.59 * g as f32 + 0.11 * b as f32) / 255.0
+            })
+            .collect()
+    }
+
+    /// A soft mask derived from the alpha of each pixel of this canvas
+    pub fn alpha_mask(&self) -> Rc<[f32]> {
+        self.buffer
+            .iter()
+            .map(|&color| parse_rgba(color)[3] as f32 / 255.0)
+            .collect()
+    }
+
     pub fn fill_path_non_zero_winding_number(&mut self, path: &Path, color: u32) {
         self.fill_path_even_odd(path, color)
     }
@@ -148,6 +231,50 @@ impl Canvas {
         }
     }
 
+    /// Fill the triangle with the given vertices, in device space, calling
+    /// `color` with the barycentric weights of the vertices at the centre of
+    /// each pixel it covers
+    pub fn fill_triangle(&mut self, vertices: [Point; 3], mut color: impl FnMut([f32; 3]) -> u32) {
+        // twice the signed area of the triangle formed by `a`, `b` and `p`
+        fn edge(a: Point, b: Point, p: Point) -> f32 {
+            (b.x - a.x) * (p.y - a.y) - (p.x - a.x) * (b.y - a.y)
+        }
+
+        let [a, b, c] = vertices;
+        let area = edge(a, b, c);
+
+        if area == 0.0 || !area.is_finite() {
+            return;
+        }
+
+        let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as usize;
+        let max_x = a.x.max(b.x).max(c.x).ceil().min(self.width as f32) as usize;
+        let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as usize;
+        let max_y = a.y.max(b.y).max(c.y).ceil().min(self.height as f32) as usize;
+
+        // pixels on an edge shared by two triangles are painted by both, so
+        // that no gaps appear between them
+        const TOLERANCE: f32 = -1e-4;
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = Point::new(x as f32 + 0.5, y as f32 + 0.5);
+
+                let wa = edge(b, c, p) / area;
+                let wb = edge(c, a, p) / area;
+                let wc = 1.0 - wa - wb;
+
+                if wa < TOLERANCE || wb < TOLERANCE || wc < TOLERANCE {
+                    continue;
+                }
+
+                let color = color([wa, wb, wc]);
+
+                self.paint_point(Point::new(x as f32, y as f32), color, 1.0);
+            }
+        }
+    }
+
     pub fn fill_outline_even_odd(&mut self, outline: &Outline, color: u32) {
         // todo: optimize to not require allocation or iteration
         let subpaths = outline
@@ -381,16 +508,36 @@ impl Canvas {
         }
 
         let idx = point.x as usize + (end - self.width) - point.y as usize * self.width;
+        let idx = idx.min(self.width * self.height - 1);
 
-        self.buffer[idx.min(self.width * self.height - 1)] = if opacity != 1.0 {
-            apply_opacity(
-                color,
-                opacity,
-                self.buffer[idx.min(self.width * self.height - 1)],
-            )
+        self.blend_pixel(idx, color, opacity);
+    }
+
+    fn blend_pixel(&mut self, idx: usize, color: u32, opacity: f32) {
+        let opacity = match &self.mask {
+            Some(mask) => opacity * mask[idx],
+            None => opacity,
+        };
+
+        if opacity <= 0.0 {
+            return;
+        }
+
+        let color = match &self.transfer {
+            Some(transfer) => transfer.apply(color),
+            None => color,
+        };
+
+        let color = if opacity < 1.0 {
+            apply_opacity(color, opacity, self.buffer[idx])
         } else {
             color
         };
+
+        self.buffer[idx] = match &self.screen {
+            Some(screen) => screen.apply(idx % self.width, idx / self.width, color),
+            None => color,
+        };
     }
 
     pub fn stroke_quadratic_bezier_curve(&mut self, curve: QuadraticBezierCurve, color: u32) {
@@ -422,119 +569,193 @@ impl Canvas {
         image: &ImageXObject<'a>,
         resolver: &mut dyn Resolve<'a>,
     ) -> PdfResult<()> {
-        let pixel_data = decode_stream(&image.stream.stream, &image.stream.dict, resolver)?;
-
-        let rgb_data = match image.color_space.as_ref().map(ColorSpace::name) {
-            Some(ColorSpaceName::DeviceGray) => match image.bits_per_component {
-                Some(BitsPerComponent::Eight) => pixel_data
-                    .iter()
-                    .map(|b| ColorSpace::DeviceGray(*b as f32).as_u32())
-                    .collect::<Vec<u32>>(),
-                Some(BitsPerComponent::One) => BitSlice::<u8, Lsb0>::from_slice(&pixel_data)
-                    .iter()
-                    .by_vals()
-                    .map(|b| ColorSpace::DeviceGray(b as u8 as f32).as_u32())
-                    .collect::<Vec<u32>>(),
-                _ => todo!(),
-            },
-            Some(ColorSpaceName::DeviceCMYK) => pixel_data
-                .chunks_exact(4)
-                .map(|chunk| {
-                    ColorSpace::DeviceCMYK {
-                        cyan: chunk[3] as f32,
-                        magenta: chunk[2] as f32,
-                        yellow: chunk[1] as f32,
-                        key: chunk[0] as f32,
-                    }
-                    .as_u32()
-                })
-                .collect::<Vec<u32>>(),
-            Some(ColorSpaceName::CalGray) => todo!(),
-            Some(ColorSpaceName::CalRGB) => todo!(),
-            Some(ColorSpaceName::Lab) => todo!(),
-            Some(ColorSpaceName::ICCBased) => todo!(),
-            Some(ColorSpaceName::Indexed) => todo!(),
-            Some(ColorSpaceName::Pattern) => todo!(),
-            Some(ColorSpaceName::Separation) => todo!(),
-            Some(ColorSpaceName::DeviceN) => todo!(),
-            Some(ColorSpaceName::DeviceRGB) | None => pixel_data
-                .chunks_exact(3)
-                .map(|chunk| {
-                    ColorSpace::DeviceRGB {
-                        red: chunk[2] as f32,
-                        green: chunk[1] as f32,
-                        blue: chunk[0] as f32,
-                    }
-                    .as_u32()
-                })
-                .collect::<Vec<u32>>(),
+        let rgba = image.decode_to_rgba(resolver)?;
+        self.draw_rgba(&rgba);
+
+        Ok(())
+    }
+
+    /// Paint the pixels of an image mask which it marks with `color`
+    pub fn draw_stencil<'a>(
+        &mut self,
+        image: &ImageXObject<'a>,
+        color: u32,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<()> {
+        let [red, green, blue, _] = parse_rgba(color);
+
+        let rgba = image.stencil_to_rgba([red, green, blue], resolver)?;
+        self.draw_rgba(&rgba);
+
+        Ok(())
+    }
+
+    fn draw_rgba(&mut self, rgba: &RgbaImage) {
+        let rgb_data = rgba
+            .data
+            .chunks_exact(4)
+            .map(|pixel| u32::from_le_bytes([pixel[0], pixel[1], pixel[2], 0xff]))
+            .collect::<Vec<u32>>();
+
+        let alpha = if rgba.data.chunks_exact(4).all(|pixel| pixel[3] == 0xff) {
+            None
+        } else {
+            Some(
+                rgba.data
+                    .chunks_exact(4)
+                    .map(|pixel| f32::from(pixel[3]) / 255.0)
+                    .collect::<Vec<f32>>(),
+            )
         };
 
-        assert_eq!(rgb_data.len() % image.width as usize, 0);
+        assert_eq!(rgb_data.len() % rgba.width as usize, 0);
 
         for i in 0..self.height {
             let start: usize = i * self.width;
-            let end = start + (image.width as usize).min(self.width);
+            let end = start + (rgba.width as usize).min(self.width);
 
             if end > self.width * self.height {
                 break;
             }
 
-            let image_start = i * image.width as usize;
+            let image_start = i * rgba.width as usize;
             let image_end = image_start + (end - start);
 
-            if image_end > image.width as usize * image.height as usize
-                || image_end >= rgb_data.len()
+            if image_end > rgba.width as usize * rgba.height as usize || image_end >= rgb_data.len()
             {
                 break;
             }
 
-            self.buffer
-                .get_mut(start..end)
-                .unwrap()
-                .copy_from_slice(&rgb_data[image_start..image_end]);
-        }
+            if alpha.is_none()
+                && self.mask.is_none()
+                && self.transfer.is_none()
+                && self.screen.is_none()
+            {
+                self.buffer
+                    .get_mut(start..end)
+                    .unwrap()
+                    .copy_from_slice(&rgb_data[image_start..image_end]);
 
-        Ok(())
+                continue;
+            }
+
+            for (offset, &color) in rgb_data[image_start..image_end].iter().enumerate() {
+                let opacity = alpha
+                    .as_ref()
+                    .map_or(1.0, |alpha| alpha[image_start + offset]);
+
+                self.blend_pixel(start + offset, color, opacity);
+            }
+        }
     }
 
-    fn render_to_image(&mut self, p: impl AsRef<FilePath>) {
-        let file = File::create(p).unwrap();
+    pub fn render_to_image(&mut self, p: impl AsRef<FilePath>) -> PdfResult<()> {
+        let file = File::create(p)?;
         let w = &mut BufWriter::new(file);
         let mut encoder = png::Encoder::new(w, self.width as u32, self.height as u32); // Width is 2 pixels and height is 1.
         encoder.set_color(png::ColorType::Rgba);
         encoder.set_depth(png::BitDepth::Eight);
 
-        let mut writer = encoder.write_header().unwrap();
+        let mut writer = encoder.write_header()?;
 
         let data = self
             .buffer
             .iter()
             .flat_map(|x| x.to_le_bytes())
             .collect::<Vec<_>>();
-        writer.write_image_data(&data).unwrap();
+        writer.write_image_data(&data)?;
+
+        Ok(())
     }
 
     pub fn draw(&mut self) {
         #[cfg(feature = "window")]
         {
-            while self.window.is_open() && !self.window.is_key_down(Key::Escape) {
+            while self.window.as_ref().map_or(false, |window| {
+                window.is_open() && !window.is_key_down(Key::Escape)
+            }) {
                 self.refresh();
             }
         }
 
         #[cfg(not(feature = "window"))]
         {
-            self.render_to_image("/root/pdf/foo.png");
+            self.render_to_image("/root/pdf/foo.png").unwrap();
         }
     }
 
     pub fn refresh(&mut self) {
         #[cfg(feature = "window")]
         {
-            self.window
-                .update_with_buffer(&self.buffer, self.width, self.height)
-                .unwrap();
+            if let Some(window) = self.window.as_mut() {
+                window
+                    .update_with_buffer(&self.buffer, self.width, self.height)
+                    .unwrap();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RED: u32 = 0xff00_00ff;
+    const BLACK: u32 = 0xff00_0000;
+    const WHITE: u32 = u32::MAX;
+
+    fn canvas(buffer: Vec<u32>) -> Canvas {
+        Canvas {
+            width: buffer.len(),
+            height: 1,
+            buffer,
+            mask: None,
+            transfer: None,
+            screen: None,
+            #[cfg(feature = "window")]
+            window: None,
+        }
+    }
+
+    #[test]
+    fn composite_over_transparent_background() {
+        assert_eq!(apply_opacity(RED, 0.5, WHITE), 0xff80_80ff);
+
+        // the colour is kept, and only its alpha is reduced
+        assert_eq!(apply_opacity(RED, 0.5, 0), 0x8000_00ff);
+        assert_eq!(apply_opacity(RED, 0.0, 0), 0);
+    }
+
+    #[test]
+    fn masks_from_canvas() {
+        // white, black, transparent white and fully transparent
+        let canvas = canvas(vec![WHITE, BLACK, 0x00ff_ffff, 0]);
+
+        let luminosity = canvas.luminosity_mask();
+        let alpha = canvas.alpha_mask();
+
+        for (mask, expected) in luminosity.iter().zip([1.0, 0.0, 1.0, 0.0]) {
+            assert!((mask - expected).abs() < 1e-6, "{} != {}", mask, expected);
         }
+
+        assert_eq!(alpha[..], [1.0, 1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn paint_through_soft_mask() {
+        let mut canvas = canvas(vec![WHITE; 3]);
+        canvas.set_soft_mask(Some(Rc::from([1.0, 0.5, 0.0])));
+
+        for idx in 0..3 {
+            canvas.blend_pixel(idx, RED, 1.0);
+        }
+
+        assert_eq!(canvas.buffer, [RED, 0xff80_80ff, WHITE]);
+
+        let mut offscreen = canvas.offscreen(0);
+        assert!(offscreen.mask.is_none());
+
+        offscreen.blend_pixel(0, RED, 0.25);
+        assert_eq!(offscreen.alpha_mask()[..], [64.0 / 255.0, 0.0, 0.0]);
     }
 }
@@ -1,9 +1,11 @@
+use std::rc::Rc;
+
 use crate::{
     color::ColorSpace,
     data_structures::Matrix,
-    function::{Function, TransferFunction},
+    function::{Function, TransferFunction, TransferTable},
     geometry::{Path, Point},
-    halftones::Halftones,
+    halftones::{HalftoneScreen, Halftones},
     resources::graphics_state_parameters::{
         BlendMode, LineCapStyle, LineDashPattern, LineJoinStyle, RenderingIntent, SoftMask,
     },
@@ -13,6 +15,17 @@ use crate::{
 pub(crate) struct GraphicsState<'a> {
     pub device_independent: DeviceIndependentGraphicsState<'a>,
     pub device_dependent: DeviceDependentGraphicsState<'a>,
+
+    /// The current soft mask, rendered to per-pixel opacities at the time it
+    /// was set in the graphics state
+    pub rendered_soft_mask: Option<Rc<[f32]>>,
+
+    /// The screen for the current halftone, if halftone screening is enabled
+    pub rendered_halftone_screen: Option<Rc<HalftoneScreen>>,
+
+    /// The current transfer function, sampled at each device colour level, or
+    /// `None` if it leaves colours unchanged
+    pub rendered_transfer: Option<Rc<TransferTable>>,
 }
 
 impl<'a> GraphicsState<'a> {
@@ -254,7 +267,7 @@ impl Default for DeviceDependentGraphicsState<'_> {
             overprint_mode: 0,
             black_generation: None,
             undercolor_removal: None,
-            transfer: TransferFunction::Identity,
+            transfer: TransferFunction::Default,
             halftones: Halftones::Default,
             flatness_tolerance: 1.0,
             smoothness_tolerance: 0.5,
@@ -1,6 +1,8 @@
 pub(crate) mod canvas;
 pub(super) mod error;
 pub(crate) mod graphics_state;
+pub(crate) mod redaction;
+pub(crate) mod text_lines;
 pub(crate) mod text_state;
 
 use std::{
@@ -10,27 +12,38 @@ use std::{
 };
 
 use crate::{
-    color::{ColorSpace, ColorSpaceName},
-    content::{ContentLexer, ContentToken, PdfGraphicsOperator},
-    data_structures::Matrix,
+    annotation::Annotation,
+    color::{Color, ColorSpace, ColorSpaceName},
+    content::{
+        ContentDiagnostic, ContentLexer, ContentToken, InlineImage, MarkedContentSequence,
+        PdfGraphicsOperator, PropertyList,
+    },
+    data_structures::{Matrix, Rectangle},
     error::PdfResult,
     filter::decode_stream,
     font::{
+        find_substitute,
         true_type::{ParsedTrueTypeFontFile, TrueTypeInterpreter},
-        CffCharStringInterpreter, CffFile, CffParser, CidFontSubtype, CidFontWidths, CidToGidMap,
-        Font, Glyph, TrueTypeFont, Type0Font, Type1Font, Type3FontFile, Widths, BASE_14_FONTS,
+        CffCharStringInterpreter, CffFile, CffParser, CharCode, CidFontDictionary, CidFontSubtype,
+        Font, Glyph, TrueTypeFont, Type0Font, Type1Font, Type3FontFile, VerticalMetric, Widths,
+        WritingMode, BASE_14_FONTS,
     },
     geometry::{Path, Point},
+    halftones::HalftoneScreen,
+    lex::LexBase,
     objects::Object,
+    optional_content::{OptionalContent, OptionalContentState},
     page::PageObject,
     postscript::{charstring::CharStringPainter, font::Type1PostscriptFont, PostscriptInterpreter},
     resources::{
         graphics_state_parameters::{
-            LineCapStyle, LineDashPattern, LineJoinStyle, RenderingIntent,
+            LineCapStyle, LineDashPattern, LineJoinStyle, RenderingIntent, SoftMask,
+            SoftMaskDictionary, SoftMaskSubtype,
         },
         Resources,
     },
-    xobject::{FormXObject, XObject},
+    shading::{MeshPatch, ShadingMesh, ShadingObject},
+    xobject::{FormXObject, ImageXObject, XObject},
     FromObj, Resolve,
 };
 
@@ -39,6 +52,8 @@ use canvas::Canvas;
 use self::{
     error::PdfRenderError,
     graphics_state::{ColorSpacePosition, GraphicsState},
+    redaction::{ContentEdit, RedactionScan},
+    text_lines::{PlacedGlyph, RenderedLine, RenderedLineBuilder},
     text_state::{TextRenderingMode, TextState},
 };
 
@@ -48,8 +63,35 @@ pub enum FillRule {
     NonZeroWindingNumber,
 }
 
+/// The number of points along each input at which the functions of shadings
+/// are precomputed
+const SHADING_FUNCTION_RESOLUTION: usize = 256;
+
+/// The number of rows and columns of triangles into which a patch is divided,
+/// so that each is a few pixels across in device space
+fn patch_divisions(patch: &MeshPatch, ctm: Matrix) -> usize {
+    let (mut min, mut max) = (
+        Point::new(f32::MAX, f32::MAX),
+        Point::new(f32::MIN, f32::MIN),
+    );
+
+    for &point in patch.points.iter().flatten() {
+        let point = ctm * point;
+
+        min = Point::new(min.x.min(point.x), min.y.min(point.y));
+        max = Point::new(max.x.max(point.x), max.y.max(point.y));
+    }
+
+    let size = (max.x - min.x).max(max.y - min.y);
+
+    ((size / 4.0).ceil() as usize).clamp(1, 64)
+}
+
 const SCALE: f32 = 1.0;
 
+/// The resolution of the rendered page, in pixels per inch
+const DEVICE_RESOLUTION: f32 = 72.0 * SCALE;
+
 pub struct Renderer<'a, 'b: 'a> {
     content: &'a mut ContentLexer<'b>,
     resolver: &'a mut dyn Resolve<'b>,
@@ -67,6 +109,31 @@ pub struct Renderer<'a, 'b: 'a> {
     current_path: Option<Path>,
     pending_clip: Option<FillRule>,
     marked_content_stack: Vec<MarkedContentMarker<'b>>,
+
+    /// The depth of the marked-content stack when the content stream being
+    /// interpreted began, beneath which its EMC operators do not reach
+    marked_content_floor: usize,
+
+    /// Where to record the marked-content sequences of the content
+    marked_content: Option<&'a mut Vec<MarkedContentSequence<'b>>>,
+
+    optional_content: OptionalContentState,
+
+    /// Annotations painted over the page content, in order
+    annotations: Vec<Annotation<'b>>,
+
+    /// The lines of text painted so far
+    rendered_lines: RenderedLineBuilder,
+
+    /// Where to record the errors skipped over while interpreting content
+    diagnostics: Option<&'a mut Vec<ContentDiagnostic>>,
+
+    /// Whether to render pixels through the current halftone screen
+    halftone_screening: bool,
+
+    /// The operators found to paint within the regions being redacted, when
+    /// redacting rather than rendering the page
+    redaction: Option<RedactionScan>,
 }
 
 impl<'a, 'b: 'a> Renderer<'a, 'b> {
@@ -137,7 +204,101 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
             current_path: None,
             pending_clip: None,
             marked_content_stack: Vec::new(),
+            marked_content_floor: 0,
+            marked_content: None,
+            optional_content: OptionalContentState::all_on(),
+            annotations: Vec::new(),
+            rendered_lines: RenderedLineBuilder::new(),
+            diagnostics: None,
+            halftone_screening: false,
+            redaction: None,
+        }
+    }
+
+    /// Render optional content according to the given group states, rather
+    /// than painting all content regardless of its visibility
+    pub fn with_optional_content(mut self, optional_content: OptionalContentState) -> Self {
+        self.optional_content = optional_content;
+        self
+    }
+
+    /// Record each error skipped over while interpreting the page content in
+    /// `diagnostics`, rather than discarding them
+    pub fn with_diagnostics(mut self, diagnostics: &'a mut Vec<ContentDiagnostic>) -> Self {
+        self.diagnostics = Some(diagnostics);
+        self
+    }
+
+    /// Record each marked-content sequence of the page content in `sequences`,
+    /// in the order in which they begin
+    pub fn with_marked_content(
+        mut self,
+        sequences: &'a mut Vec<MarkedContentSequence<'b>>,
+    ) -> Self {
+        self.marked_content = Some(sequences);
+        self
+    }
+
+    /// Paint the appearances of the given annotations over the page content
+    pub fn with_annotations(mut self, annotations: Vec<Annotation<'b>>) -> Self {
+        self.annotations = annotations;
+        self
+    }
+
+    /// Render each pixel through the halftone screens of the graphics state,
+    /// previewing the output of a printer which can only paint each colour
+    /// component with either no or full intensity
+    pub fn with_halftone_screening(mut self) -> Self {
+        self.halftone_screening = true;
+
+        // the default halftone is always valid
+        self.update_halftone_screen().unwrap();
+
+        self
+    }
+
+    /// Sample the current transfer function in the graphics state, and apply it
+    /// to subsequent painting
+    fn update_transfer_table(&mut self) -> PdfResult<()> {
+        let table = self.graphics_state.device_dependent.transfer.table()?;
+
+        self.graphics_state.rendered_transfer = table.map(Rc::new);
+        self.canvas
+            .set_transfer_table(self.graphics_state.rendered_transfer.clone());
+
+        Ok(())
+    }
+
+    /// Compute the screen for the current halftone in the graphics state, and
+    /// render subsequent painting through it
+    fn update_halftone_screen(&mut self) -> PdfResult<()> {
+        let screen = HalftoneScreen::new(
+            &self.graphics_state.device_dependent.halftones,
+            DEVICE_RESOLUTION,
+        )?;
+
+        self.graphics_state.rendered_halftone_screen = Some(Rc::new(screen));
+        self.canvas
+            .set_halftone_screen(self.graphics_state.rendered_halftone_screen.clone());
+
+        Ok(())
+    }
+
+    /// Whether content painted at this point in the content stream is visible,
+    /// taking into account all enclosing optional content
+    fn is_content_visible(&self, optional_content: Option<&OptionalContent>) -> bool {
+        let mut enclosing = self
+            .marked_content_stack
+            .iter()
+            .filter_map(|marker| marker.optional_content.as_ref())
+            .chain(optional_content)
+            .peekable();
+
+        if enclosing.peek().is_none() {
+            return self.optional_content.show_unmarked;
         }
+
+        enclosing.all(|oc| self.optional_content.is_visible(oc))
     }
 
     fn current_transformation_matrix(&self) -> Matrix {
@@ -147,6 +308,12 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
     }
 
     fn render_form_xobject(&mut self, mut form: FormXObject<'b>) -> PdfResult<()> {
+        if let Some(oc) = &form.oc {
+            if !self.optional_content.is_visible(oc) {
+                return Ok(());
+            }
+        }
+
         let content_buffer: Cow<'b, [u8]> = decode_stream(
             unsafe { &*(&*form.stream.stream as *const _) },
             &form.stream.dict,
@@ -160,9 +327,49 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
         std::mem::swap(&mut self.resources, &mut form.resources);
         self.graphics_state
             .device_independent
-            .current_transformation_matrix *= form.matrix;
+            .current_transformation_matrix = form.matrix
+            * self
+                .graphics_state
+                .device_independent
+                .current_transformation_matrix;
 
-        self.render_content_stream()?;
+        let has_optional_content = form.oc.is_some();
+
+        if let Some(scan) = &mut self.redaction {
+            scan.enter_form();
+        }
+
+        if let Some(oc) = form.oc.take() {
+            self.marked_content_stack.push(MarkedContentMarker {
+                tag: "OC".to_owned(),
+                properties: None,
+                optional_content: Some(oc),
+                sequence: None,
+            });
+        }
+
+        // sequences left open by the form are ended with it, and those it does
+        // not begin cannot be ended within it
+        let floor = std::mem::replace(
+            &mut self.marked_content_floor,
+            self.marked_content_stack.len(),
+        );
+
+        let result = self.render_content_stream();
+
+        self.marked_content_stack
+            .truncate(self.marked_content_floor);
+        self.marked_content_floor = floor;
+
+        if let Some(scan) = &mut self.redaction {
+            scan.exit_form();
+        }
+
+        result?;
+
+        if has_optional_content {
+            self.marked_content_stack.pop();
+        }
 
         std::mem::swap(self.content, &mut form_content);
         std::mem::swap(&mut self.resources, &mut form.resources);
@@ -172,6 +379,43 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
         Ok(())
     }
 
+    /// Paint the normal appearance of each visible annotation, mapping the
+    /// bounding box of its appearance stream onto its annotation rectangle
+    fn render_annotations(&mut self) -> PdfResult<()> {
+        let annotations = std::mem::take(&mut self.annotations);
+        let page_rotation = self.page.rotation();
+
+        for annotation in &annotations {
+            let flags = annotation.flags();
+
+            if flags.is_hidden()
+                || flags.is_no_view()
+                || !self.is_content_visible(annotation.optional_content())
+            {
+                continue;
+            }
+
+            let (form, matrix) = match (
+                annotation.normal_appearance(),
+                annotation.appearance_matrix(page_rotation, SCALE),
+            ) {
+                (Some(form), Some(matrix)) => (FormXObject::clone(form), matrix),
+                _ => continue,
+            };
+
+            self.save_graphics_state()?;
+            self.graphics_state
+                .device_independent
+                .current_transformation_matrix = matrix;
+
+            self.render_form_xobject(form)?;
+
+            self.restore_graphics_state()?;
+        }
+
+        Ok(())
+    }
+
     fn stroking_color(&self) -> &ColorSpace {
         &self.graphics_state.device_independent.color_space.stroking
     }
@@ -184,108 +428,245 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
             .nonstroking
     }
 
+    /// Interpret the content stream, skipping operators which cannot be lexed
+    /// or applied and recording a diagnostic for each
     fn render_content_stream(&mut self) -> PdfResult<()> {
-        while let Some(token) = self.content.next() {
-            let token = token?;
+        // the offset of the first operand of the next operator
+        let mut operands_start = None;
 
+        while let Some(token) = self.content.next() {
             match token {
-                ContentToken::Object(obj) => self.operand_stack.push(obj),
-                ContentToken::Operator(op) => match op {
-                    PdfGraphicsOperator::G => self.set_stroking_gray()?,
-                    PdfGraphicsOperator::g => self.set_nonstroking_gray()?,
-                    PdfGraphicsOperator::BT => self.begin_text()?,
-                    PdfGraphicsOperator::Tf => self.set_font_and_size()?,
-                    PdfGraphicsOperator::Td => self.move_text_position()?,
-                    PdfGraphicsOperator::TJ => self.draw_text_adjusted()?,
-                    PdfGraphicsOperator::Tj => self.draw_text_unadjusted()?,
-                    PdfGraphicsOperator::q => self.save_graphics_state()?,
-                    PdfGraphicsOperator::Q => self.restore_graphics_state()?,
-                    PdfGraphicsOperator::cm => self.transform_ctm()?,
-                    PdfGraphicsOperator::Do => self.draw_xobject()?,
-                    PdfGraphicsOperator::w => self.set_line_width()?,
-                    PdfGraphicsOperator::re => self.create_rectangle()?,
-                    PdfGraphicsOperator::W => self.set_clipping_path_non_zero_winding_number()?,
-                    PdfGraphicsOperator::W_star => self.set_clipping_path_even_odd()?,
-                    PdfGraphicsOperator::n => self.draw_path_nop()?,
-                    PdfGraphicsOperator::RG => self.set_stroking_rgb()?,
-                    PdfGraphicsOperator::rg => self.set_nonstroking_rgb()?,
-                    PdfGraphicsOperator::ET => self.end_text()?,
-                    PdfGraphicsOperator::BDC => {
-                        self.begin_marked_content_sequence_with_property_list()?
-                    }
-                    PdfGraphicsOperator::EMC => self.end_marked_content_sequence()?,
-                    PdfGraphicsOperator::Tm => self.set_text_matrix()?,
-                    PdfGraphicsOperator::gs => self.set_graphics_state_parameters()?,
-                    PdfGraphicsOperator::f | PdfGraphicsOperator::F => {
-                        self.fill_path(FillRule::NonZeroWindingNumber)?
-                    }
-                    PdfGraphicsOperator::f_star => self.fill_path(FillRule::EvenOdd)?,
-                    PdfGraphicsOperator::m => self.move_to()?,
-                    PdfGraphicsOperator::l => self.line_to()?,
-                    PdfGraphicsOperator::h => self.close_path()?,
-                    PdfGraphicsOperator::S => self.stroke_path()?,
-                    PdfGraphicsOperator::k => self.set_nonstroking_cmyk()?,
-                    PdfGraphicsOperator::K => self.set_stroking_cmyk()?,
-                    PdfGraphicsOperator::TL => self.set_text_leading()?,
-                    PdfGraphicsOperator::c => self.curve_to()?,
-                    PdfGraphicsOperator::v => self.curve_to_initial_replicated()?,
-                    PdfGraphicsOperator::y => self.curve_to_final_replicated()?,
-                    PdfGraphicsOperator::CS => self.set_stroking_color_space()?,
-                    PdfGraphicsOperator::cs => self.set_nonstroking_color_space()?,
-                    PdfGraphicsOperator::SC | PdfGraphicsOperator::SCN => {
-                        self.set_stroking_color()?
-                    }
-                    PdfGraphicsOperator::sc | PdfGraphicsOperator::scn => {
-                        self.set_nonstroking_color()?
+                Ok(ContentToken::Object(obj)) => {
+                    operands_start.get_or_insert(self.content.token_start());
+                    self.operand_stack.push(obj);
+                }
+                Ok(ContentToken::InlineImage(image)) => {
+                    self.begin_operator(operands_start.take());
+
+                    if let Err(e) = self.draw_inline_image(image) {
+                        self.record_diagnostic(Some("BI".to_owned()), e);
                     }
-                    PdfGraphicsOperator::i => self.set_flatness_tolerance()?,
-                    PdfGraphicsOperator::Tc => self.set_character_spacing()?,
-                    PdfGraphicsOperator::Tw => self.set_word_spacing()?,
-                    PdfGraphicsOperator::Ts => self.set_text_rise()?,
-                    PdfGraphicsOperator::Tz => self.set_horizontal_scaling()?,
-                    PdfGraphicsOperator::Tr => self.set_text_rendering_mode()?,
-                    PdfGraphicsOperator::TD => self.move_text_position_and_set_leading()?,
-                    PdfGraphicsOperator::T_star => self.move_to_next_line()?,
-                    PdfGraphicsOperator::BMC => self.begin_marked_content_sequence()?,
-                    PdfGraphicsOperator::J => self.set_line_cap_style()?,
-                    PdfGraphicsOperator::d => self.set_line_dash_pattern()?,
-                    PdfGraphicsOperator::j => self.set_line_join_style()?,
-                    PdfGraphicsOperator::B => {
-                        self.stroke_and_fill(FillRule::NonZeroWindingNumber)?
+
+                    self.operand_stack.clear();
+                }
+                Ok(ContentToken::Operator(op)) => {
+                    self.begin_operator(operands_start.take());
+
+                    if let Err(e) = self.apply_operator(op) {
+                        let operator = self.content.last_token().into_owned();
+                        self.record_diagnostic(Some(operator), e);
                     }
-                    PdfGraphicsOperator::B_star => self.stroke_and_fill(FillRule::EvenOdd)?,
-                    PdfGraphicsOperator::M => self.set_miter_limit()?,
-                    PdfGraphicsOperator::s => self.close_and_stroke_path()?,
-                    // compat section is handled in lexer
-                    PdfGraphicsOperator::BX | PdfGraphicsOperator::EX => {}
-                    PdfGraphicsOperator::sh => self.paint_using_shading_pattern()?,
-                    PdfGraphicsOperator::ri => self.set_color_rendering_intent()?,
-                    _ => todo!("unimplemented operator: {:?}", op),
-                },
+
+                    // operands left over by a failed or malformed operator
+                    // must not be used by the next one
+                    self.operand_stack.clear();
+                }
+                Err(e) => self.record_diagnostic(None, e),
             }
         }
 
         Ok(())
     }
 
+    /// Note the byte range of the operator about to be applied, including its
+    /// operands, so that it can be rewritten if it paints within a region being
+    /// redacted
+    fn begin_operator(&mut self, operands_start: Option<usize>) {
+        if let Some(scan) = &mut self.redaction {
+            let start = operands_start.unwrap_or_else(|| self.content.token_start());
+
+            scan.begin_operator(start..self.content.cursor());
+        }
+    }
+
+    fn record_diagnostic(&mut self, operator: Option<String>, error: anyhow::Error) {
+        let offset = self.content.token_start();
+
+        if let Some(diagnostics) = &mut self.diagnostics {
+            diagnostics.push(ContentDiagnostic {
+                offset,
+                operator,
+                message: error.to_string(),
+            });
+        }
+    }
+
+    fn apply_operator(&mut self, op: PdfGraphicsOperator) -> PdfResult<()> {
+        match op {
+            PdfGraphicsOperator::G => self.set_stroking_gray()?,
+            PdfGraphicsOperator::g => self.set_nonstroking_gray()?,
+            PdfGraphicsOperator::BT => self.begin_text()?,
+            PdfGraphicsOperator::Tf => self.set_font_and_size()?,
+            PdfGraphicsOperator::Td => self.move_text_position()?,
+            PdfGraphicsOperator::TJ => self.draw_text_adjusted()?,
+            PdfGraphicsOperator::Tj => self.draw_text_unadjusted()?,
+            PdfGraphicsOperator::q => self.save_graphics_state()?,
+            PdfGraphicsOperator::Q => self.restore_graphics_state()?,
+            PdfGraphicsOperator::cm => self.transform_ctm()?,
+            PdfGraphicsOperator::Do => self.draw_xobject()?,
+            PdfGraphicsOperator::w => self.set_line_width()?,
+            PdfGraphicsOperator::re => self.create_rectangle()?,
+            PdfGraphicsOperator::W => self.set_clipping_path_non_zero_winding_number()?,
+            PdfGraphicsOperator::W_star => self.set_clipping_path_even_odd()?,
+            PdfGraphicsOperator::n => self.draw_path_nop()?,
+            PdfGraphicsOperator::RG => self.set_stroking_rgb()?,
+            PdfGraphicsOperator::rg => self.set_nonstroking_rgb()?,
+            PdfGraphicsOperator::ET => self.end_text()?,
+            PdfGraphicsOperator::BDC => self.begin_marked_content_sequence_with_property_list()?,
+            PdfGraphicsOperator::EMC => self.end_marked_content_sequence()?,
+            PdfGraphicsOperator::Tm => self.set_text_matrix()?,
+            PdfGraphicsOperator::gs => self.set_graphics_state_parameters()?,
+            PdfGraphicsOperator::f | PdfGraphicsOperator::F => {
+                self.fill_path(FillRule::NonZeroWindingNumber)?
+            }
+            PdfGraphicsOperator::f_star => self.fill_path(FillRule::EvenOdd)?,
+            PdfGraphicsOperator::m => self.move_to()?,
+            PdfGraphicsOperator::l => self.line_to()?,
+            PdfGraphicsOperator::h => self.close_path()?,
+            PdfGraphicsOperator::S => self.stroke_path()?,
+            PdfGraphicsOperator::k => self.set_nonstroking_cmyk()?,
+            PdfGraphicsOperator::K => self.set_stroking_cmyk()?,
+            PdfGraphicsOperator::TL => self.set_text_leading()?,
+            PdfGraphicsOperator::c => self.curve_to()?,
+            PdfGraphicsOperator::v => self.curve_to_initial_replicated()?,
+            PdfGraphicsOperator::y => self.curve_to_final_replicated()?,
+            PdfGraphicsOperator::CS => self.set_stroking_color_space()?,
+            PdfGraphicsOperator::cs => self.set_nonstroking_color_space()?,
+            PdfGraphicsOperator::SC | PdfGraphicsOperator::SCN => self.set_stroking_color()?,
+            PdfGraphicsOperator::sc | PdfGraphicsOperator::scn => self.set_nonstroking_color()?,
+            PdfGraphicsOperator::i => self.set_flatness_tolerance()?,
+            PdfGraphicsOperator::Tc => self.set_character_spacing()?,
+            PdfGraphicsOperator::Tw => self.set_word_spacing()?,
+            PdfGraphicsOperator::Ts => self.set_text_rise()?,
+            PdfGraphicsOperator::Tz => self.set_horizontal_scaling()?,
+            PdfGraphicsOperator::Tr => self.set_text_rendering_mode()?,
+            PdfGraphicsOperator::TD => self.move_text_position_and_set_leading()?,
+            PdfGraphicsOperator::T_star => self.move_to_next_line()?,
+            PdfGraphicsOperator::BMC => self.begin_marked_content_sequence()?,
+            PdfGraphicsOperator::MP => self.designate_marked_content_point()?,
+            PdfGraphicsOperator::DP => self.designate_marked_content_point_with_property_list()?,
+            PdfGraphicsOperator::J => self.set_line_cap_style()?,
+            PdfGraphicsOperator::d => self.set_line_dash_pattern()?,
+            PdfGraphicsOperator::j => self.set_line_join_style()?,
+            PdfGraphicsOperator::B => self.stroke_and_fill(FillRule::NonZeroWindingNumber)?,
+            PdfGraphicsOperator::B_star => self.stroke_and_fill(FillRule::EvenOdd)?,
+            PdfGraphicsOperator::M => self.set_miter_limit()?,
+            PdfGraphicsOperator::s => self.close_and_stroke_path()?,
+            PdfGraphicsOperator::b => self.close_stroke_and_fill(FillRule::NonZeroWindingNumber)?,
+            PdfGraphicsOperator::b_star => self.close_stroke_and_fill(FillRule::EvenOdd)?,
+            PdfGraphicsOperator::single_quote => self.move_to_next_line_and_draw_text()?,
+            PdfGraphicsOperator::double_quote => self.set_spacing_and_draw_text()?,
+            // compat section is handled in lexer
+            PdfGraphicsOperator::BX | PdfGraphicsOperator::EX => {}
+            PdfGraphicsOperator::sh => self.paint_using_shading_pattern()?,
+            PdfGraphicsOperator::ri => self.set_color_rendering_intent()?,
+            // inline images are lexed as a whole, and painted by
+            // `draw_inline_image`
+            PdfGraphicsOperator::BI | PdfGraphicsOperator::ID | PdfGraphicsOperator::EI => {}
+            _ => anyhow::bail!("unimplemented operator: {:?}", op),
+        }
+
+        Ok(())
+    }
+
     fn paint_using_shading_pattern(&mut self) -> PdfResult<()> {
         let name = self.pop_name()?;
 
-        if let Some(resources) = &self.resources {
+        // the shading fills the current clipping path
+        if let Some(scan) = &mut self.redaction {
+            let clip = self
+                .graphics_state
+                .device_independent
+                .clipping_path
+                .bounding_box();
+
+            if scan.intersects(&clip) {
+                scan.redact(ContentEdit::Remove);
+            }
+        }
+
+        if let Some(resources) = self.resources.clone() {
             let shade = resources
                 .shading
                 .as_ref()
                 .and_then(|shading| shading.get(&name));
 
             match shade {
-                Some(shade) => todo!("{:#?}", shade),
-                None => todo!("unable to locate shade {:#?}", name),
+                Some(shade) => {
+                    if self.is_content_visible(None) {
+                        self.paint_shading(shade)?;
+                    }
+                }
+                None => anyhow::bail!("unable to locate shading {:?}", name),
             }
         }
 
         Ok(())
     }
 
+    /// Paint a mesh shading (types 4 to 7) by interpolating the colours of the
+    /// vertices of each of its triangles, into which patches are divided
+    // todo: function-based, axial and radial shadings, BBox, and clipping
+    fn paint_shading(&mut self, shading: &ShadingObject<'b>) -> PdfResult<()> {
+        let mesh = match shading.mesh(self.resolver)? {
+            Some(mesh) => mesh,
+            None => anyhow::bail!("unimplemented shading type {:?}", shading.shading_type()),
+        };
+
+        let ctm = self.current_transformation_matrix();
+        let color_space = shading.color_space();
+
+        // the function is evaluated at every pixel, so its outputs are
+        // precomputed where possible
+        let function = shading.function().map(|function| {
+            let mut function = function.clone();
+            let _ = function.precompute(SHADING_FUNCTION_RESOLUTION);
+
+            function
+        });
+
+        let triangles = match mesh {
+            ShadingMesh::Triangles(triangles) => triangles,
+            ShadingMesh::Patches(patches) => patches
+                .iter()
+                .flat_map(|patch| patch.triangulate(patch_divisions(patch, ctm)))
+                .collect(),
+        };
+
+        for triangle in triangles {
+            let [a, b, c] = &triangle.vertices;
+
+            self.canvas
+                .fill_triangle([ctm * a.point, ctm * b.point, ctm * c.point], |weights| {
+                    let value = (0..a.color.len())
+                        .map(|k| {
+                            weights[0] * a.color[k]
+                                + weights[1] * b.color[k]
+                                + weights[2] * c.color[k]
+                        })
+                        .collect::<Vec<_>>();
+
+                    let components = match &function {
+                        Some(function) => function.evaluate(&value),
+                        None => Ok(value),
+                    };
+
+                    // colours which cannot be converted are rendered black
+                    let [red, green, blue] = components
+                        .and_then(|components| color_space.to_rgb(&components))
+                        .unwrap_or([0.0; 3]);
+
+                    let r = (red * 255.0).round() as u32;
+                    let g = (green * 255.0).round() as u32;
+                    let b = (blue * 255.0).round() as u32;
+
+                    (0xff << 24) | (b << 16) | (g << 8) | r
+                });
+        }
+
+        Ok(())
+    }
+
     fn stroke_and_fill(&mut self, fill_rule: FillRule) -> PdfResult<()> {
         let stroke_color = self.stroking_color().as_u32();
         let fill_color = self.non_stroking_color().as_u32();
@@ -302,33 +683,138 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
             println!("unimplemented clipping path operator {:?}", clip);
         }
 
+        self.redact_path(&path, true);
+
+        if !self.is_content_visible(None) {
+            return Ok(());
+        }
+
         self.canvas.stroke_path(&path, stroke_color);
         self.canvas.fill_path(&path, fill_color, fill_rule);
 
         Ok(())
     }
 
+    /// Close, fill, and then stroke the path. This operator shall have the same
+    /// effect as the sequence `h B`.
+    fn close_stroke_and_fill(&mut self, fill_rule: FillRule) -> PdfResult<()> {
+        self.close_path()?;
+
+        self.stroke_and_fill(fill_rule)
+    }
+
+    /// Replace the operator painting `path`, which has been transformed to
+    /// device space, with `n` if the path lies within a region being redacted
+    fn redact_path(&mut self, path: &Path, is_stroked: bool) {
+        let scan = match &mut self.redaction {
+            Some(scan) => scan,
+            None => return,
+        };
+
+        let mut bbox = path.bounding_box();
+
+        // a stroke extends half the line width, in user space, to either side
+        // of the path
+        if is_stroked && !path.subpaths.is_empty() {
+            let ctm = self
+                .graphics_state
+                .device_independent
+                .current_transformation_matrix;
+            let half_width = self.graphics_state.device_independent.line_width / 2.0;
+
+            let origin = ctm * Point::origin();
+            let x = ctm * Point::new(half_width, 0.0) - origin;
+            let y = ctm * Point::new(0.0, half_width) - origin;
+
+            let dx = x.x.abs() + y.x.abs();
+            let dy = x.y.abs() + y.y.abs();
+
+            bbox.add_point(Point::new(bbox.min.x - dx, bbox.min.y - dy));
+            bbox.add_point(Point::new(bbox.max.x + dx, bbox.max.y + dy));
+        }
+
+        if scan.intersects(&bbox) {
+            scan.redact(ContentEdit::EndPath);
+        }
+    }
+
     pub fn render(mut self) -> PdfResult<()> {
+        #[cfg(feature = "bench")]
+        let _timer = crate::bench::StageTimer::start(crate::bench::Stage::Render);
+
         self.render_content_stream()?;
+        self.render_annotations()?;
 
         self.canvas.draw();
 
         Ok(())
     }
 
+    /// Render the page, returning the geometry of each line of text as
+    /// rendered
+    ///
+    /// Text shown with fonts which cannot be rendered, such as non-embedded
+    /// fonts without a substitute, is left out.
+    pub fn render_text_lines(mut self) -> PdfResult<Vec<RenderedLine>> {
+        #[cfg(feature = "bench")]
+        let _timer = crate::bench::StageTimer::start(crate::bench::Stage::Render);
+
+        self.render_content_stream()?;
+        self.render_annotations()?;
+
+        Ok(self.rendered_lines.finish(self.canvas.height() as f32))
+    }
+
+    /// Rewrite the page content stream so that none of its content is painted
+    /// within the given regions, in default user space
+    ///
+    /// Glyphs within a region are replaced by adjustments of the text position,
+    /// and paths are discarded without being painted. Images, shadings and form
+    /// XObjects which paint within a region are removed whole.
+    pub(crate) fn redact(mut self, regions: &[Rectangle]) -> PdfResult<Vec<u8>> {
+        self.redaction = Some(RedactionScan::new(regions));
+
+        self.render_content_stream()?;
+
+        let scan = self.redaction.take().unwrap();
+
+        Ok(scan.apply(&self.content.buffer))
+    }
+
+    /// Render the page and write the result to a PNG file at `path`
+    pub fn render_to_image(mut self, path: impl AsRef<std::path::Path>) -> PdfResult<()> {
+        #[cfg(feature = "bench")]
+        let _timer = crate::bench::StageTimer::start(crate::bench::Stage::Render);
+
+        self.render_content_stream()?;
+        self.render_annotations()?;
+
+        self.canvas.render_to_image(path)
+    }
+
     fn get_color_space(&mut self, pos: ColorSpacePosition) -> PdfResult<ColorSpace<'b>> {
         let color_space = self.graphics_state.get_color_space(pos);
 
         Ok(match color_space {
-            ColorSpace::IccBased { stream, .. } => {
+            ColorSpace::IccBased {
+                stream, profile, ..
+            } => {
                 let stream = Rc::clone(stream);
+                let profile = profile.clone();
 
                 let mut channels = Vec::new();
                 for _ in 0..stream.num_of_color_components {
                     channels.push(self.pop_number()?);
                 }
 
-                ColorSpace::IccBased { stream, channels }
+                // operands are popped from last to first
+                channels.reverse();
+
+                ColorSpace::IccBased {
+                    stream,
+                    profile,
+                    channels,
+                }
             }
             ColorSpace::Separation(space) => {
                 let mut space = space.clone();
@@ -358,13 +844,18 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
             }
             ColorSpace::Indexed { space, .. } => {
                 let space = Rc::clone(space);
-                let index = self.pop::<u32>()?;
+
+                // the index may be written as a real number, and indices
+                // beyond hival are limited to it when looked up
+                let index = self.pop_number()?.round().max(0.0) as u32;
 
                 ColorSpace::Indexed { index, space }
             }
-            ColorSpace::DeviceGray(..) | ColorSpace::CalGray { .. } => {
-                todo!()
-            }
+            ColorSpace::DeviceGray(..) => ColorSpace::DeviceGray(self.pop_number()?),
+            ColorSpace::CalGray { space, .. } => ColorSpace::CalGray {
+                space: Rc::clone(space),
+                a: self.pop_number()?,
+            },
             ColorSpace::DeviceRGB { .. } => {
                 let blue = self.pop_number()?;
                 let green = self.pop_number()?;
@@ -372,8 +863,23 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
 
                 ColorSpace::DeviceRGB { red, green, blue }
             }
-            ColorSpace::Lab { .. } | ColorSpace::CalRGB { .. } => {
-                todo!()
+            ColorSpace::CalRGB { space, .. } => {
+                let space = Rc::clone(space);
+
+                let c = self.pop_number()?;
+                let b = self.pop_number()?;
+                let a = self.pop_number()?;
+
+                ColorSpace::CalRGB { a, b, c, space }
+            }
+            ColorSpace::Lab { space, .. } => {
+                let space = Rc::clone(space);
+
+                let c = self.pop_number()?;
+                let b = self.pop_number()?;
+                let a = self.pop_number()?;
+
+                ColorSpace::Lab { a, b, c, space }
             }
             ColorSpace::DeviceCMYK { .. } => {
                 let key = self.pop_number()?;
@@ -391,6 +897,52 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
         })
     }
 
+    /// The colour space selected by the name of a colour space family
+    ///
+    /// A device colour space is remapped to the DefaultGray, DefaultRGB or
+    /// DefaultCMYK entry of the ColorSpace subdictionary of the current
+    /// resource dictionary, if it has one with as many components.
+    fn device_color_space(&self, name: ColorSpaceName) -> PdfResult<ColorSpace<'b>> {
+        let (key, components) = match name {
+            ColorSpaceName::DeviceGray => ("DefaultGray", 1),
+            ColorSpaceName::DeviceRGB => ("DefaultRGB", 3),
+            ColorSpaceName::DeviceCMYK => ("DefaultCMYK", 4),
+            _ => return ColorSpace::init(name),
+        };
+
+        self.resources
+            .as_ref()
+            .and_then(|resources| resources.color_space.as_ref())
+            .and_then(|color_spaces| color_spaces.get(key))
+            .filter(|color_space| color_space.components() == components)
+            .cloned()
+            .map_or_else(|| ColorSpace::init(name), Ok)
+    }
+
+    /// The image with its device colour space remapped to a default colour
+    /// space, if the current resource dictionary has one for it
+    fn with_default_color_space(&self, image: &ImageXObject<'b>) -> Option<ImageXObject<'b>> {
+        let name = match &image.color_space {
+            Some(
+                color_space @ (ColorSpace::DeviceGray(..)
+                | ColorSpace::DeviceRGB { .. }
+                | ColorSpace::DeviceCMYK { .. }),
+            ) => color_space.name(),
+            _ => return None,
+        };
+
+        let color_space = self.device_color_space(name).ok()?;
+
+        if color_space.name() == name {
+            return None;
+        }
+
+        let mut image = image.clone();
+        image.color_space = Some(color_space);
+
+        Some(image)
+    }
+
     fn set_nonstroking_color(&mut self) -> PdfResult<()> {
         let color_space = self.get_color_space(ColorSpacePosition::Nonstroking)?;
 
@@ -464,7 +1016,7 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
     fn set_stroking_color_space(&mut self) -> PdfResult<()> {
         let name = self.pop_name()?;
         let color_space = if let Ok(name) = ColorSpaceName::from_str(&name) {
-            ColorSpace::init(name)
+            self.device_color_space(name)?
         } else {
             let color_space = self
                 .resources
@@ -488,7 +1040,7 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
     fn set_nonstroking_color_space(&mut self) -> PdfResult<()> {
         let name = self.pop_name()?;
         let color_space = if let Ok(name) = ColorSpaceName::from_str(&name) {
-            ColorSpace::init(name)
+            self.device_color_space(name)?
         } else {
             let color_space = self
                 .resources
@@ -681,39 +1233,20 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
     /// concentration). The behaviour of this operator is affected by the overprint
     /// mode
     fn set_stroking_cmyk(&mut self) -> PdfResult<()> {
-        let key = self.pop_number()?;
-        let yellow = self.pop_number()?;
-        let magenta = self.pop_number()?;
-        let cyan = self.pop_number()?;
-
-        self.graphics_state.device_independent.color_space.stroking = ColorSpace::DeviceCMYK {
-            cyan,
-            magenta,
-            yellow,
-            key,
-        };
+        self.graphics_state.device_independent.color_space.stroking =
+            self.device_color_space(ColorSpaceName::DeviceCMYK)?;
 
-        Ok(())
+        self.set_stroking_color()
     }
 
     /// Same as [Renderer::set_stroking_cmyk], but used for nonstroking operations
     fn set_nonstroking_cmyk(&mut self) -> PdfResult<()> {
-        let key = self.pop_number()?;
-        let yellow = self.pop_number()?;
-        let magenta = self.pop_number()?;
-        let cyan = self.pop_number()?;
-
         self.graphics_state
             .device_independent
             .color_space
-            .nonstroking = ColorSpace::DeviceCMYK {
-            cyan,
-            magenta,
-            yellow,
-            key,
-        };
+            .nonstroking = self.device_color_space(ColorSpaceName::DeviceCMYK)?;
 
-        Ok(())
+        self.set_nonstroking_color()
     }
 
     /// Set the specified parameters in the graphics state. dictName shall be
@@ -722,20 +1255,105 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
     fn set_graphics_state_parameters(&mut self) -> PdfResult<()> {
         let dict_name = self.pop_name()?;
 
-        let graphics_state_parameters = self
-            .resources
+        let resources = self.resources.clone();
+
+        let graphics_state_parameters = resources
             .as_ref()
             .and_then(|res| res.ext_g_state.as_ref())
             .and_then(|state_map| state_map.get(&dict_name));
 
-        match graphics_state_parameters {
-            Some(gsp) => gsp.update_graphics_state(&mut self.graphics_state, &mut self.text_state),
-            None => todo!("{}", &dict_name),
+        let gsp = match graphics_state_parameters {
+            Some(gsp) => gsp,
+            None => anyhow::bail!("unable to find graphics state {:?}", dict_name),
+        };
+
+        gsp.update_graphics_state(&mut self.graphics_state, &mut self.text_state);
+
+        match gsp.soft_mask() {
+            Some(SoftMask::Dictionary(mask)) => {
+                let mask = self.render_soft_mask(mask.clone())?;
+                self.graphics_state.rendered_soft_mask = Some(mask);
+            }
+            Some(SoftMask::None) => self.graphics_state.rendered_soft_mask = None,
+            None => {}
+        }
+
+        self.canvas
+            .set_soft_mask(self.graphics_state.rendered_soft_mask.clone());
+
+        if gsp.has_transfer_function() {
+            self.update_transfer_table()?;
+        }
+
+        if self.halftone_screening && gsp.halftones().is_some() {
+            self.update_halftone_screen()?;
         }
 
         Ok(())
     }
 
+    /// Render the transparency group of a soft mask to an offscreen canvas, and
+    /// derive per-pixel mask values from either its luminosity or its alpha
+    ///
+    /// The mask's coordinate system is defined by the current transformation
+    /// matrix at the time the soft mask is set in the graphics state.
+    fn render_soft_mask(&mut self, mask: SoftMaskDictionary<'b>) -> PdfResult<Rc<[f32]>> {
+        let backdrop = match mask.subtype {
+            SoftMaskSubtype::Alpha => 0x00_00_00_00,
+            SoftMaskSubtype::Luminosity => match mask.backdrop_color.as_deref() {
+                Some(&[gray]) => ColorSpace::DeviceRGB {
+                    red: gray,
+                    green: gray,
+                    blue: gray,
+                }
+                .as_u32(),
+                Some(&[red, green, blue]) => ColorSpace::DeviceRGB { red, green, blue }.as_u32(),
+                Some(&[cyan, magenta, yellow, key]) => ColorSpace::DeviceCMYK {
+                    cyan,
+                    magenta,
+                    yellow,
+                    key,
+                }
+                .as_u32(),
+                Some(..) | None => Color::BLACK,
+            },
+        };
+
+        let transfer_table = mask.transfer_function.soft_mask_table()?;
+        let group = FormXObject::from_obj(Object::Stream(mask.transparency_group), self.resolver)?;
+
+        let offscreen = self.canvas.offscreen(backdrop);
+        let canvas = std::mem::replace(&mut self.canvas, offscreen);
+
+        // the group itself is rendered without any soft mask, and neither
+        // transferred nor screened, as it is never output
+        let soft_mask = self.graphics_state.rendered_soft_mask.take();
+        let transfer = self.graphics_state.rendered_transfer.take();
+        let screen = self.graphics_state.rendered_halftone_screen.take();
+
+        let result = self.render_form_xobject(group);
+
+        self.graphics_state.rendered_soft_mask = soft_mask;
+        self.graphics_state.rendered_transfer = transfer;
+        self.graphics_state.rendered_halftone_screen = screen;
+        let group_canvas = std::mem::replace(&mut self.canvas, canvas);
+
+        result?;
+
+        let values = match mask.subtype {
+            SoftMaskSubtype::Alpha => group_canvas.alpha_mask(),
+            SoftMaskSubtype::Luminosity => group_canvas.luminosity_mask(),
+        };
+
+        Ok(match transfer_table {
+            Some(table) => values
+                .iter()
+                .map(|&value| table[(value.clamp(0.0, 1.0) * 255.0).round() as usize])
+                .collect(),
+            None => values,
+        })
+    }
+
     /// Stroke the path.
     fn stroke_path(&mut self) -> PdfResult<()> {
         let color = self.stroking_color().as_u32();
@@ -752,6 +1370,12 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
             println!("unimplemented clipping path operator {:?}", clip);
         }
 
+        self.redact_path(&path, true);
+
+        if !self.is_content_visible(None) {
+            return Ok(());
+        }
+
         self.canvas.stroke_path(&path, color);
 
         Ok(())
@@ -776,6 +1400,12 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
             println!("unimplemented clipping path operator {:?}", clip);
         }
 
+        self.redact_path(&path, true);
+
+        if !self.is_content_visible(None) {
+            return Ok(());
+        }
+
         self.canvas.stroke_path(&path, color);
 
         Ok(())
@@ -838,10 +1468,10 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
     /// use for stroking operations. gray shall be a number between 0.0 (black)
     /// and 1.0 (white).
     fn set_stroking_gray(&mut self) -> PdfResult<()> {
-        let gray = self.pop_number()?;
-        self.graphics_state.device_independent.color_space.stroking = ColorSpace::DeviceGray(gray);
+        self.graphics_state.device_independent.color_space.stroking =
+            self.device_color_space(ColorSpaceName::DeviceGray)?;
 
-        Ok(())
+        self.set_stroking_color()
     }
 
     fn fill_path(&mut self, fill_rule: FillRule) -> PdfResult<()> {
@@ -864,6 +1494,12 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
             println!("unimplemented clipping path operator {:?}", clip);
         }
 
+        self.redact_path(&path, false);
+
+        if !self.is_content_visible(None) {
+            return Ok(());
+        }
+
         // todo: don't fill shapes we know to be completely off screen
         if false
             && !path.bounding_box().overlaps(
@@ -890,13 +1526,12 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
 
     /// Same as [Renderer::set_stroking_gray], but used for nonstroking operations
     fn set_nonstroking_gray(&mut self) -> PdfResult<()> {
-        let gray = self.pop_number()?;
         self.graphics_state
             .device_independent
             .color_space
-            .nonstroking = ColorSpace::DeviceGray(gray);
+            .nonstroking = self.device_color_space(ColorSpaceName::DeviceGray)?;
 
-        Ok(())
+        self.set_nonstroking_color()
     }
 
     /// Set the stroking colour space to DeviceRGB (or the DefaultRGB colour
@@ -904,28 +1539,20 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
     /// shall be a number between 0.0 (minimum intensity) and 1.0 (maximum
     /// intensity).
     fn set_stroking_rgb(&mut self) -> PdfResult<()> {
-        let blue = self.pop_number()?;
-        let green = self.pop_number()?;
-        let red = self.pop_number()?;
-
         self.graphics_state.device_independent.color_space.stroking =
-            ColorSpace::DeviceRGB { red, green, blue };
+            self.device_color_space(ColorSpaceName::DeviceRGB)?;
 
-        Ok(())
+        self.set_stroking_color()
     }
 
     /// Same as [Renderer::set_stroking_rgb] but used for nonstroking operations.
     fn set_nonstroking_rgb(&mut self) -> PdfResult<()> {
-        let blue = self.pop_number()?;
-        let green = self.pop_number()?;
-        let red = self.pop_number()?;
-
         self.graphics_state
             .device_independent
             .color_space
-            .nonstroking = ColorSpace::DeviceRGB { red, green, blue };
+            .nonstroking = self.device_color_space(ColorSpaceName::DeviceRGB)?;
 
-        Ok(())
+        self.set_nonstroking_color()
     }
 
     /// Begin a text object, initializing the text matrix, Tm, and the text line
@@ -966,7 +1593,7 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
                 self.text_state.font = Some(font);
                 self.text_state.font_size = size;
             }
-            None => todo!("could not find font with name {:?}", font_name),
+            None => anyhow::bail!("could not find font with name {:?}", font_name),
         }
 
         Ok(())
@@ -1012,14 +1639,20 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
         Ok(())
     }
 
-    fn draw_text(&mut self, arr: Vec<Object<'b>>) -> PdfResult<()> {
+    /// Show the strings of a text array, adjusting the text position by each
+    /// of its numbers
+    ///
+    /// When redacting, this returns the text array with each glyph within a
+    /// region being redacted replaced by an adjustment of the same width, if
+    /// any glyph was.
+    fn draw_text(&mut self, arr: Vec<Object<'b>>) -> PdfResult<Option<Vec<Object<'b>>>> {
         // todo: should actually get undefined/default width from the glyph itself
         let default_width = Widths::new(Some(Vec::new()), Some(0), Some(0), 1000.0).unwrap();
         let ffs: Cow<[u8]>;
         let stream: Cow<[u8]>;
-        let font: Arc<RwLock<dyn RenderableFont>>;
-        let cid_font_widths: CidFontWidths;
+        let font: Option<Arc<RwLock<dyn RenderableFont>>>;
         let widths: &dyn FontMetrics;
+        let mut composite_font: Option<&Type0Font> = None;
 
         match self.text_state.font.as_deref() {
             Some(Font::Type1(Type1Font {
@@ -1030,22 +1663,14 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
                     .as_ref()
                     .and_then(|desc| desc.font_file.clone());
 
-                if font_file.is_none() {
-                    if base.font_descriptor.is_none() {
-                        let base_font = BASE_14_FONTS.get(&base_font.as_ref()).unwrap();
+                let font_file_three = base
+                    .font_descriptor
+                    .as_ref()
+                    .and_then(|desc| desc.font_file_three.clone());
 
-                        font = Arc::<RwLock<Type1PostscriptFont>>::clone(base_font);
-                        widths = base.widths.as_ref().unwrap_or(&default_width);
-                    } else {
-                        match base
-                            .font_descriptor
-                            .as_ref()
-                            .unwrap()
-                            .font_file_three
-                            .as_ref()
-                            .unwrap()
-                            .clone()
-                        {
+                if font_file.is_none() {
+                    if let Some(font_file_three) = font_file_three {
+                        match font_file_three {
                             Type3FontFile::CompactType1(compact_type1) => {
                                 ffs = compact_type1.stream.stream;
                                 stream =
@@ -1053,19 +1678,35 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
 
                                 let cff_file = CffFile::load(&stream)?;
 
-                                font = Arc::new(RwLock::new(cff_file));
+                                font = Some(Arc::new(RwLock::new(cff_file)));
                                 widths = base.widths.as_ref().unwrap_or(&default_width);
                             }
                             Type3FontFile::CompactType0Cid(_) => todo!(),
                             Type3FontFile::OpenType(_) => todo!(),
                         }
+                    } else if let Some(base_font) = BASE_14_FONTS.get(&base_font.as_ref()) {
+                        font = Some(Arc::<RwLock<Type1PostscriptFont>>::clone(base_font));
+                        widths = base.widths.as_ref().unwrap_or(&default_width);
+                    } else {
+                        font = match find_substitute(base_font, base.font_descriptor.as_ref()) {
+                            Some(substitute) => {
+                                stream = Cow::Owned(substitute);
+
+                                Some(Arc::new(RwLock::new(TrueTypeInterpreter::load(&stream)?)))
+                            }
+                            None => {
+                                println!("skipping non-embedded font {:?}", base_font);
+                                None
+                            }
+                        };
+                        widths = base.widths.as_ref().unwrap_or(&default_width);
                     }
                 } else {
                     let font_file = font_file.unwrap();
                     ffs = font_file.stream.stream;
                     stream = decode_stream(&ffs, &font_file.stream.dict, self.resolver)?;
 
-                    font = Arc::new(RwLock::new(Type1PostscriptFont::load(&stream)?));
+                    font = Some(Arc::new(RwLock::new(Type1PostscriptFont::load(&stream)?)));
                     widths = base.widths.as_ref().unwrap();
                 }
             }
@@ -1078,89 +1719,189 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
                 widths = base.widths.as_ref().unwrap();
 
                 if font_file.is_none() {
-                    let base_font = BASE_14_FONTS
-                        .get(&base.font_descriptor.as_ref().unwrap().font_name.0.as_ref())
-                        .unwrap();
+                    let descriptor = base.font_descriptor.as_ref().unwrap();
+
+                    if let Some(base_font) = BASE_14_FONTS.get(&descriptor.font_name.0.as_ref()) {
+                        font = Some(Arc::<RwLock<Type1PostscriptFont>>::clone(base_font));
+                    } else {
+                        font = match find_substitute(&descriptor.font_name.0, Some(descriptor)) {
+                            Some(substitute) => {
+                                stream = Cow::Owned(substitute);
 
-                    font = Arc::<RwLock<Type1PostscriptFont>>::clone(base_font);
+                                Some(Arc::new(RwLock::new(TrueTypeInterpreter::load(&stream)?)))
+                            }
+                            None => {
+                                println!("skipping non-embedded font {:?}", descriptor.font_name.0);
+                                None
+                            }
+                        };
+                    }
                 } else {
                     let font_file = font_file.unwrap();
                     ffs = font_file.stream.stream;
                     stream = decode_stream(&ffs, &font_file.stream.dict, self.resolver)?;
 
-                    font = Arc::new(RwLock::new(TrueTypeInterpreter::load(&stream)?));
+                    font = Some(Arc::new(RwLock::new(TrueTypeInterpreter::load(&stream)?)));
                 }
             }
-            Some(Font::Type0(Type0Font {
-                descendant_font: [descendant_font],
-                ..
-            })) => {
-                assert_eq!(descendant_font.cid_to_gid_map, CidToGidMap::Identity);
+            Some(Font::Type0(type0)) => {
+                let [descendant_font] = &type0.descendant_font;
 
                 match descendant_font.subtype {
                     CidFontSubtype::CidFontType0 => {
-                        let font_file = descendant_font.font_descriptor.font_file.clone();
-                        if font_file.is_none() {
-                            println!("skipping unsupported type 0 cid font");
-                            return Ok(());
-                        }
+                        font = match &descendant_font.font_descriptor.font_file_three {
+                            Some(Type3FontFile::CompactType0Cid(font_file)) => {
+                                let font_file = font_file.clone();
+
+                                ffs = font_file.stream.stream;
+                                stream =
+                                    decode_stream(&ffs, &font_file.stream.dict, self.resolver)?;
 
-                        let font_file = font_file.unwrap();
-                        ffs = font_file.stream.stream;
-                        stream = decode_stream(&ffs, &font_file.stream.dict, self.resolver)?;
-                        font = Arc::new(RwLock::new(Type1PostscriptFont::load(&stream)?));
+                                Some(Arc::new(RwLock::new(CidFontType0Program::load(&stream)?)))
+                            }
+                            _ => {
+                                println!("skipping unsupported type 0 cid font");
+                                None
+                            }
+                        };
                     }
                     CidFontSubtype::CidFontType2 => {
-                        let font_file = descendant_font
-                            .font_descriptor
-                            .font_file_two
-                            .clone()
-                            .unwrap();
-
-                        ffs = font_file.stream.stream;
-
-                        stream = decode_stream(&ffs, &font_file.stream.dict, self.resolver)?;
-
-                        font = Arc::new(RwLock::new(TrueTypeInterpreter::load(&stream)?));
+                        font = match descendant_font.font_descriptor.font_file_two.clone() {
+                            Some(font_file) => {
+                                ffs = font_file.stream.stream;
+                                stream =
+                                    decode_stream(&ffs, &font_file.stream.dict, self.resolver)?;
+
+                                // CIDs are mapped to glyph indices through the
+                                // CIDToGIDMap before evaluating the glyph
+                                Some(Arc::new(RwLock::new(
+                                    TrueTypeInterpreter::load(&stream)?
+                                        .with_glyph_index_char_codes(),
+                                )))
+                            }
+                            None => {
+                                println!("skipping non-embedded type 2 cid font");
+                                None
+                            }
+                        };
                     }
                 }
 
-                if descendant_font.default_width != 1000 {
-                    cid_font_widths = CidFontWidths::with_default(descendant_font.default_width);
-                    widths = &cid_font_widths;
-                } else {
-                    widths = &descendant_font.widths;
-                }
+                widths = descendant_font;
+                composite_font = Some(type0);
             }
             Some(font @ Font::Type3(..)) => todo!("unimplemented type 3 font: {:#?}", font),
             Some(font @ Font::MmType1(_)) => todo!("unimplemented mm font: {:#?}", font),
-            None => todo!("no font selected in text state"),
+            None => anyhow::bail!("no font selected in text state"),
         };
 
-        assert_eq!(self.text_state.rendering_mode, TextRenderingMode::Fill);
+        let visible = self.is_content_visible(None);
+
+        // invisible text is positioned and extracted, but not painted
+        // todo: stroking and clipping text rendering modes
+        let painted = visible && self.text_state.rendering_mode != TextRenderingMode::Invisible;
+
+        // glyphs which are not painted are positioned as if glyph space were
+        // the usual 1/1000 of text space
+        let font_matrix = font
+            .as_ref()
+            .map_or(Matrix::new_scale(0.001, 0.001), |font| {
+                font.read().unwrap().font_matrix()
+            });
+
+        // the text array as rewritten to remove the glyphs within a region
+        // being redacted
+        let mut redacted_arr = Vec::new();
+        let mut is_redacted = false;
+
+        let (ascent, descent, x_height) = self
+            .text_state
+            .font
+            .as_deref()
+            .and_then(Font::descriptor)
+            .map_or((0.8, -0.2, 0.5), |descriptor| descriptor.vertical_metrics());
+
+        // The CIDFont of a composite font in vertical writing mode
+        let vertical_font = composite_font
+            .filter(|font| font.writing_mode() == WritingMode::Vertical)
+            .map(|font| &font.descendant_font[0]);
+
+        // the text of the shown glyphs, added to the open marked-content
+        // sequences once the font is no longer borrowed
+        let mut shown_text = String::new();
 
         for obj in arr {
             let obj = self.resolver.resolve(obj)?;
 
             let s = match obj {
                 Object::String(s) => s,
-                // todo: consolidate integer/float handling here
-                Object::Real(n) => {
-                    self.text_state.text_matrix *=
-                        Matrix::new_translation((-n * self.text_state.font_size) / 1000.0, 0.0);
+                Object::Real(..) | Object::Integer(..) => {
+                    redacted_arr.push(obj.clone());
+
+                    let adjustment =
+                        -self.resolver.assert_number(obj)? * self.text_state.font_size / 1000.0;
+
+                    let translation = match vertical_font {
+                        Some(..) => Matrix::new_translation(0.0, adjustment),
+                        None => Matrix::new_translation(adjustment, 0.0),
+                    };
+
+                    self.text_state.text_matrix = translation * self.text_state.text_matrix;
                     continue;
                 }
-                Object::Integer(n) => {
-                    self.text_state.text_matrix *= Matrix::new_translation(
-                        (-n as f32 * self.text_state.font_size) / 1000.0,
-                        0.0,
-                    );
-                    continue;
+                _ => anyhow::bail!("expected a string or number in text array"),
+            };
+
+            let shown_glyphs: Vec<ShownGlyph> = match composite_font {
+                Some(type0) => {
+                    let bytes = s.chars().map(|c| c as u8).collect::<Vec<u8>>();
+                    let [descendant_font] = &type0.descendant_font;
+
+                    type0
+                        .cids(&bytes)
+                        .map(|(code, cid)| ShownGlyph {
+                            text: type0.unicode(code),
+                            glyph: match descendant_font.subtype {
+                                CidFontSubtype::CidFontType0 => cid,
+                                CidFontSubtype::CidFontType2 => {
+                                    descendant_font.cid_to_gid_map.glyph_index(cid)
+                                }
+                            },
+                            metrics: cid,
+                            code: char_code_string(code),
+                            is_word_break: code == CharCode { code: 32, len: 1 },
+                        })
+                        .collect()
                 }
-                _ => todo!(),
+                None => s
+                    .chars()
+                    .map(|c| ShownGlyph {
+                        text: c.to_string(),
+                        glyph: c as u32,
+                        metrics: c as u32,
+                        code: c.to_string(),
+                        is_word_break: c == ' ',
+                    })
+                    .collect(),
             };
 
-            for c in s.chars() {
+            // the codes of the glyphs shown since the last one removed
+            let mut kept = String::new();
+
+            for shown_glyph in shown_glyphs {
+                let vertical_metric =
+                    vertical_font.map(|font| font.vertical_metric(shown_glyph.metrics));
+
+                // In vertical writing mode, the glyph is positioned such that its
+                // vertical origin is at the current text position
+                let origin = match vertical_metric {
+                    Some(VerticalMetric { vx, vy, .. }) => Matrix::new_translation(
+                        -vx * self.text_state.font_size / 1000.0,
+                        -vy * self.text_state.font_size / 1000.0,
+                    ),
+                    None => Matrix::identity(),
+                };
+
                 let trm = Matrix::new(
                     self.text_state.font_size * self.text_state.horizontal_scaling,
                     0.0,
@@ -1168,47 +1909,157 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
                     self.text_state.font_size,
                     0.0,
                     self.text_state.rise,
-                ) * font.read().unwrap().font_matrix()
+                ) * font_matrix
+                    * origin
                     * self.text_state.text_matrix
                     * self
                         .graphics_state
                         .device_independent
                         .current_transformation_matrix;
 
-                let mut glyph = font.write().unwrap().evaluate(c as u32)?;
+                // the glyph's advance, in text space units
+                let width = match vertical_metric {
+                    Some(VerticalMetric { w1y, .. }) => w1y / 1000.0,
+                    None => widths.get(shown_glyph.metrics),
+                };
 
-                glyph.outline.apply_transform(trm);
+                if self.is_glyph_redacted(vertical_metric.is_some(), width, ascent, descent) {
+                    let mut advance =
+                        width * self.text_state.font_size + self.text_state.character_spacing;
 
-                glyph
-                    .outline
-                    .apply_transform(Matrix::new_scale(SCALE, SCALE));
+                    if shown_glyph.is_word_break {
+                        advance += self.text_state.word_spacing;
+                    }
 
-                self.canvas.fill_outline_even_odd(
-                    &glyph.outline,
-                    self.graphics_state
-                        .device_independent
-                        .color_space
-                        .stroking
-                        .as_u32(),
-                );
+                    if !kept.is_empty() {
+                        redacted_arr.push(Object::String(std::mem::take(&mut kept)));
+                    }
+
+                    redacted_arr.push(Object::Real(-advance * 1000.0 / self.text_state.font_size));
+                    is_redacted = true;
+                } else {
+                    kept.push_str(&shown_glyph.code);
+                }
+
+                if let Some(font) = font.as_ref().filter(|_| painted) {
+                    let mut glyph = font.write().unwrap().evaluate(shown_glyph.glyph)?;
+
+                    glyph.outline.apply_transform(trm);
+
+                    glyph
+                        .outline
+                        .apply_transform(Matrix::new_scale(SCALE, SCALE));
+
+                    self.canvas.fill_outline_even_odd(
+                        &glyph.outline,
+                        self.graphics_state
+                            .device_independent
+                            .color_space
+                            .stroking
+                            .as_u32(),
+                    );
 
-                self.canvas.refresh();
+                    self.canvas.refresh();
+                }
+
+                shown_text.push_str(&shown_glyph.text);
 
-                let mut x_transform = widths.get(c as u32) * self.text_state.font_size
+                if let Some(vertical_metric) = vertical_metric {
+                    let mut y_transform = vertical_metric.w1y / 1000.0 * self.text_state.font_size
+                        + self.text_state.character_spacing;
+
+                    if shown_glyph.is_word_break {
+                        y_transform += self.text_state.word_spacing;
+                    }
+
+                    self.text_state.text_matrix =
+                        Matrix::new_translation(0.0, y_transform) * self.text_state.text_matrix;
+
+                    continue;
+                }
+
+                let mut x_transform = widths.get(shown_glyph.metrics) * self.text_state.font_size
                     + self.text_state.character_spacing;
 
-                // todo: this should actually consult the cmap
-                if c == ' ' {
+                if shown_glyph.is_word_break {
                     x_transform += self.text_state.word_spacing * SCALE;
                 }
 
                 x_transform *= self.text_state.horizontal_scaling;
 
-                self.text_state.text_matrix *= Matrix::new_translation(x_transform, 0.0);
+                let ctm = self
+                    .graphics_state
+                    .device_independent
+                    .current_transformation_matrix;
+
+                // the glyph's advance along the baseline, in canvas coordinates
+                let baseline = Point::new(0.0, self.text_state.rise);
+                let start_matrix = self.text_state.text_matrix * ctm;
+
+                self.text_state.text_matrix =
+                    Matrix::new_translation(x_transform, 0.0) * self.text_state.text_matrix;
+
+                if visible {
+                    let start = start_matrix * baseline;
+                    let top = start_matrix
+                        * Point::new(0.0, self.text_state.rise + self.text_state.font_size);
+                    let em = top - start;
+
+                    self.rendered_lines.push(PlacedGlyph {
+                        text: shown_glyph.text,
+                        start: start * SCALE,
+                        end: (self.text_state.text_matrix * ctm) * baseline * SCALE,
+                        em: (em.x * em.x + em.y * em.y).sqrt() * SCALE,
+                        ascent,
+                        descent,
+                        x_height,
+                    });
+                }
+            }
+
+            if !kept.is_empty() {
+                redacted_arr.push(Object::String(kept));
             }
         }
 
-        Ok(())
+        self.record_marked_content_text(&shown_text);
+
+        Ok(is_redacted.then_some(redacted_arr))
+    }
+
+    /// Whether the glyph about to be shown, advancing by `width` text space
+    /// units, lies within a region being redacted
+    fn is_glyph_redacted(&self, is_vertical: bool, width: f32, ascent: f32, descent: f32) -> bool {
+        let scan = match &self.redaction {
+            Some(scan) => scan,
+            None => return false,
+        };
+
+        let font_size = self.text_state.font_size;
+
+        // glyphs shown at a size of zero have no extent, and cannot be replaced
+        // by an adjustment
+        if font_size == 0.0 {
+            return false;
+        }
+
+        // vertical glyphs are centred on the text position, and advance down
+        let (x0, y0, x1, y1) = if is_vertical {
+            (-font_size / 2.0, width * font_size, font_size / 2.0, 0.0)
+        } else {
+            (
+                0.0,
+                self.text_state.rise + descent * font_size,
+                width * font_size * self.text_state.horizontal_scaling,
+                self.text_state.rise + ascent * font_size,
+            )
+        };
+
+        let matrix = self.text_state.text_matrix * self.current_transformation_matrix();
+        let corners =
+            [(x0, y0), (x1, y0), (x1, y1), (x0, y1)].map(|(x, y)| matrix * Point::new(x, y));
+
+        scan.intersects_points(&corners)
     }
 
     /// Show one or more text strings, allowing individual glyph positioning.
@@ -1225,15 +2076,50 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
     fn draw_text_adjusted(&mut self) -> PdfResult<()> {
         let arr = self.pop_arr()?;
 
-        self.draw_text(arr)?;
-
-        Ok(())
+        self.show_text(arr, "")
     }
 
     fn draw_text_unadjusted(&mut self) -> PdfResult<()> {
         let s = self.pop_string()?;
 
-        self.draw_text(vec![Object::String(s)])?;
+        self.show_text(vec![Object::String(s)], "")
+    }
+
+    /// Move to the next line and show a text string. This operator shall have
+    /// the same effect as the code `T* string Tj`.
+    fn move_to_next_line_and_draw_text(&mut self) -> PdfResult<()> {
+        let s = self.pop_string()?;
+
+        self.move_to_next_line()?;
+
+        self.show_text(vec![Object::String(s)], "T*\n")
+    }
+
+    /// Move to the next line and show a text string, using aw as the word
+    /// spacing and ac as the character spacing. This operator shall have the
+    /// same effect as the code `aw Tw ac Tc string '`.
+    fn set_spacing_and_draw_text(&mut self) -> PdfResult<()> {
+        let s = self.pop_string()?;
+        let character_spacing = self.pop_number()?;
+        let word_spacing = self.pop_number()?;
+
+        self.text_state.word_spacing = word_spacing;
+        self.text_state.character_spacing = character_spacing;
+        self.move_to_next_line()?;
+
+        let prefix = format!("{} Tw {} Tc T*\n", word_spacing, character_spacing);
+
+        self.show_text(vec![Object::String(s)], &prefix)
+    }
+
+    /// Show a text array, rewriting the operator showing it as `prefix`
+    /// followed by a `TJ` operator if any of its glyphs are redacted
+    fn show_text(&mut self, arr: Vec<Object<'b>>, prefix: &str) -> PdfResult<()> {
+        let redacted = self.draw_text(arr)?;
+
+        if let (Some(scan), Some(arr)) = (&mut self.redaction, redacted) {
+            scan.redact_text(prefix.as_bytes(), arr);
+        }
 
         Ok(())
     }
@@ -1248,6 +2134,12 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
     fn restore_graphics_state(&mut self) -> PdfResult<()> {
         if let Some(state) = self.graphics_state_stack.pop() {
             self.graphics_state = state;
+            self.canvas
+                .set_soft_mask(self.graphics_state.rendered_soft_mask.clone());
+            self.canvas
+                .set_transfer_table(self.graphics_state.rendered_transfer.clone());
+            self.canvas
+                .set_halftone_screen(self.graphics_state.rendered_halftone_screen.clone());
         }
 
         Ok(())
@@ -1268,7 +2160,11 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
 
         self.graphics_state
             .device_independent
-            .current_transformation_matrix *= matrix;
+            .current_transformation_matrix = matrix
+            * self
+                .graphics_state
+                .device_independent
+                .current_transformation_matrix;
 
         Ok(())
     }
@@ -1281,6 +2177,18 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
     fn draw_xobject(&mut self) -> PdfResult<()> {
         let name = self.pop_name()?;
 
+        let is_image = matches!(
+            self.resources
+                .as_ref()
+                .and_then(|resources| resources.xobject.as_ref())
+                .and_then(|xobject| xobject.get(&name)),
+            Some(XObject::Image(..))
+        );
+
+        if is_image {
+            self.redact_image();
+        }
+
         if let Some(resources) = &self.resources {
             let xobject = resources
                 .xobject
@@ -1288,7 +2196,17 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
                 .and_then(|xobject| xobject.get(&name));
 
             match xobject {
-                Some(XObject::Image(image)) => self.canvas.draw_image(image, self.resolver)?,
+                Some(XObject::Image(image)) if !self.is_content_visible(image.oc.as_ref()) => {}
+                // image masks are painted with the current fill colour
+                Some(XObject::Image(image)) if image.image_mask => {
+                    let fill_color = self.non_stroking_color().as_u32();
+
+                    self.canvas.draw_stencil(image, fill_color, self.resolver)?
+                }
+                Some(XObject::Image(image)) => match self.with_default_color_space(image) {
+                    Some(image) => self.canvas.draw_image(&image, self.resolver)?,
+                    None => self.canvas.draw_image(image, self.resolver)?,
+                },
                 Some(XObject::Form(form)) => {
                     let form: FormXObject<'b> = FormXObject::clone(form);
 
@@ -1297,13 +2215,50 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
                 Some(XObject::PostScript(ps_obj)) => {
                     todo!("unimplemented postscript xobject {:#?}", ps_obj)
                 }
-                None => todo!("unable to find xobject {:#?}", name),
+                None => anyhow::bail!("unable to find xobject {:?}", name),
             }
         }
 
         Ok(())
     }
 
+    /// Paint an inline image, as `Do` paints an image XObject
+    fn draw_inline_image(&mut self, image: InlineImage<'b>) -> PdfResult<()> {
+        self.redact_image();
+
+        let color_spaces = self
+            .resources
+            .as_ref()
+            .and_then(|resources| resources.color_space.as_ref());
+
+        let image = image.to_xobject(color_spaces, self.resolver)?;
+        let image = self.with_default_color_space(&image).unwrap_or(image);
+
+        // image masks are painted with the current fill colour
+        if image.image_mask {
+            let fill_color = self.non_stroking_color().as_u32();
+
+            self.canvas.draw_stencil(&image, fill_color, self.resolver)
+        } else {
+            self.canvas.draw_image(&image, self.resolver)
+        }
+    }
+
+    /// Remove the operator painting an image, which fills the unit square of
+    /// user space, if the image lies within a region being redacted
+    fn redact_image(&mut self) {
+        let ctm = self.current_transformation_matrix();
+
+        if let Some(scan) = &mut self.redaction {
+            let corners = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]
+                .map(|(x, y)| ctm * Point::new(x, y));
+
+            if scan.intersects_points(&corners) {
+                scan.redact(ContentEdit::Remove);
+            }
+        }
+    }
+
     /// Set the line width in the graphics state
     fn set_line_width(&mut self) -> PdfResult<()> {
         let line_width = self.pop_number()?;
@@ -1376,12 +2331,17 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
     /// tag shall be a name object indicating the role or significance of the
     /// sequence.
     fn begin_marked_content_sequence(&mut self) -> PdfResult<()> {
-        let tag = self.pop_name()?;
+        let tag = match self.pop_name() {
+            Ok(tag) => tag,
+            Err(err) => {
+                // begun regardless, so that the balancing EMC operator does not
+                // end an enclosing sequence
+                self.begin_marked_content(String::new(), None, None);
+                return Err(err);
+            }
+        };
 
-        self.marked_content_stack.push(MarkedContentMarker {
-            tag,
-            properties: None,
-        });
+        self.begin_marked_content(tag, None, None);
 
         Ok(())
     }
@@ -1393,24 +2353,116 @@ impl<'a, 'b: 'a> Renderer<'a, 'b> {
     /// associated with it in the Properties subdictionary of the current
     /// resource dictionary
     fn begin_marked_content_sequence_with_property_list(&mut self) -> PdfResult<()> {
-        let properties = self.pop::<Object<'b>>()?;
-        let tag = self.pop_name()?;
+        let (tag, properties) = match self.pop_marked_content_properties() {
+            Ok(operands) => operands,
+            Err(err) => {
+                // begun regardless, so that the balancing EMC operator does not
+                // end an enclosing sequence
+                self.begin_marked_content(String::new(), None, None);
+                return Err(err);
+            }
+        };
 
-        self.marked_content_stack.push(MarkedContentMarker {
-            tag,
-            properties: Some(properties),
-        });
+        // Optional content is marked using the tag OC, with its properties being
+        // an optional content group or membership dictionary
+        if tag == "OC" {
+            match properties.optional_content.clone() {
+                Some(optional_content) => {
+                    self.begin_marked_content(tag, Some(properties), Some(optional_content))
+                }
+                None => {
+                    let err = anyhow::anyhow!("expected optional content, found {:?}", properties);
+                    self.begin_marked_content(tag, Some(properties), None);
+                    return Err(err);
+                }
+            }
+        } else {
+            self.begin_marked_content(tag, Some(properties), None);
+        }
 
         Ok(())
     }
 
     /// End a marked-content sequence begun by a BMC or BDC operator.
     fn end_marked_content_sequence(&mut self) -> PdfResult<()> {
-        self.marked_content_stack.pop();
+        if self.marked_content_stack.len() > self.marked_content_floor {
+            self.marked_content_stack.pop();
+        }
 
         Ok(())
     }
 
+    /// Designate a marked-content point. tag shall be a name object indicating
+    /// the role or significance of the point.
+    fn designate_marked_content_point(&mut self) -> PdfResult<()> {
+        self.pop_name()?;
+
+        Ok(())
+    }
+
+    /// Designate a marked-content point with an associated property list. tag
+    /// and properties have the same meanings as for the BDC operator.
+    fn designate_marked_content_point_with_property_list(&mut self) -> PdfResult<()> {
+        self.pop_marked_content_properties()?;
+
+        Ok(())
+    }
+
+    /// Pop the tag and property list operands of a BDC or DP operator
+    fn pop_marked_content_properties(&mut self) -> PdfResult<(String, PropertyList<'b>)> {
+        let properties = self.pop::<Object<'b>>()?;
+        let tag = self.pop_name()?;
+
+        let properties =
+            PropertyList::from_operand(properties, self.resources.as_deref(), self.resolver)?;
+
+        Ok((tag, properties))
+    }
+
+    fn begin_marked_content(
+        &mut self,
+        tag: String,
+        properties: Option<PropertyList<'b>>,
+        optional_content: Option<OptionalContent>,
+    ) {
+        let depth = self.marked_content_stack.len();
+        let is_visible = self.is_content_visible(optional_content.as_ref());
+
+        let sequence = match &mut self.marked_content {
+            Some(sequences) => {
+                sequences.push(MarkedContentSequence {
+                    tag: tag.clone(),
+                    properties: properties.clone(),
+                    depth,
+                    is_visible,
+                    text: String::new(),
+                });
+
+                Some(sequences.len() - 1)
+            }
+            None => None,
+        };
+
+        self.marked_content_stack.push(MarkedContentMarker {
+            tag,
+            properties,
+            optional_content,
+            sequence,
+        });
+    }
+
+    /// Add the text of a glyph being shown to each marked-content sequence it
+    /// is within
+    fn record_marked_content_text(&mut self, text: &str) {
+        if let Some(sequences) = &mut self.marked_content {
+            for marker in &self.marked_content_stack {
+                if let Some(sequence) = marker.sequence {
+                    sequences[sequence].text.push_str(text);
+                }
+            }
+        }
+    }
+
     /// Set the text matrix, Tm, and the text line matrix, Tlm:
     ///
     /// T_m = T_lm = [a b 0, c d 0, e f 1]
@@ -1508,7 +2560,7 @@ impl<'a, 'b: 'a> RenderableFont<'a, 'b> for CffFile<'a> {
             }
         };
 
-        CffCharStringInterpreter::evaluate(cs)
+        CffCharStringInterpreter::evaluate(cs, self)
     }
 
     fn font_matrix(&self) -> Matrix {
@@ -1516,6 +2568,39 @@ impl<'a, 'b: 'a> RenderableFont<'a, 'b> for CffFile<'a> {
     }
 }
 
+/// The CFF font program of a CIDFontType0 CIDFont, whose glyphs are selected
+/// by CID
+///
+/// A font program without CIDFont operators is treated as if CIDs were glyph
+/// IDs.
+struct CidFontType0Program<'a>(CffFile<'a>);
+
+impl<'a, 'b: 'a> RenderableFont<'a, 'b> for CidFontType0Program<'a> {
+    fn load(stream: &'a [u8]) -> PdfResult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self(CffParser::new(stream).parse()?))
+    }
+
+    fn evaluate(&mut self, cid: u32) -> PdfResult<Glyph> {
+        let gid = if self.0.is_cid_keyed() {
+            u16::try_from(cid)
+                .ok()
+                .and_then(|cid| self.0.charset.glyph_id(cid))
+                .unwrap_or(0)
+        } else {
+            u16::try_from(cid).unwrap_or(0)
+        };
+
+        CffCharStringInterpreter::evaluate_glyph(gid, &self.0)
+    }
+
+    fn font_matrix(&self) -> Matrix {
+        Matrix::from_arr(self.0.top_dict.font_matrix)
+    }
+}
+
 trait FontMetrics {
     fn get(&self, codepoint: u32) -> f32;
 }
@@ -1526,19 +2611,109 @@ impl FontMetrics for Widths {
     }
 }
 
-impl FontMetrics for CidFontWidths {
-    fn get(&self, codepoint: u32) -> f32 {
-        self.map
-            .get(&(codepoint as i32))
-            .copied()
-            .unwrap_or(self.default as f32)
-            / 1000.0
+impl FontMetrics for CidFontDictionary<'_> {
+    fn get(&self, cid: u32) -> f32 {
+        self.width(cid) / 1000.0
     }
 }
 
+/// A glyph selected by a string shown with the current font
+/// The bytes of a character code, as a string of one `char` per byte
+fn char_code_string(code: CharCode) -> String {
+    (0..code.len)
+        .rev()
+        .map(|idx| char::from((code.code >> (8 * u32::from(idx))) as u8))
+        .collect()
+}
+
+struct ShownGlyph {
+    /// The Unicode text of the glyph, for a composite font, or otherwise the
+    /// character code as a `char`
+    text: String,
+
+    /// The value used to select the glyph from the font program
+    glyph: u32,
+
+    /// The value used to look up the glyph's metrics: the character code for
+    /// a simple font, or the CID for a composite font
+    metrics: u32,
+
+    /// The character code of the glyph, as a string of one `char` per byte
+    code: String,
+
+    /// Whether word spacing applies after the glyph, which is the case for the
+    /// single-byte code 32
+    is_word_break: bool,
+}
+
 #[derive(Debug, Clone)]
 struct MarkedContentMarker<'a> {
     tag: String,
-    // todo: type
-    properties: Option<Object<'a>>,
+    properties: Option<PropertyList<'a>>,
+    optional_content: Option<OptionalContent>,
+
+    /// The index of the sequence among those being recorded
+    sequence: Option<usize>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test::{rgb_pixels, TestFile},
+        ParseOptions, Parser,
+    };
+
+    #[test]
+    fn default_color_spaces() {
+        let content = b"q 10 0 0 10 0 0 cm /Im1 Do Q 1 g 10 0 10 10 re f \
+            0 0 1 rg 20 0 10 10 re f";
+
+        let mut file = TestFile::new();
+        file.object(1, b"<< /Type /Catalog /Pages 2 0 R >>");
+        file.object(2, b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>");
+
+        // DeviceGray is remapped to a palette of white and red, while the
+        // DefaultRGB entry is ignored, as it has the wrong number of components
+        file.object(
+            3,
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 30 10] /Contents 4 0 R \
+              /Resources << /ColorSpace << /DefaultRGB /DeviceGray \
+              /DefaultGray [/Indexed /DeviceRGB 1 <FFFFFFFF0000>] >> \
+              /XObject << /Im1 5 0 R >> >> >>",
+        );
+        file.stream(4, &format!("<< /Length {} >>", content.len()), content);
+        file.stream(
+            5,
+            "<< /Type /XObject /Subtype /Image /Width 10 /Height 10 /ColorSpace /DeviceGray \
+              /BitsPerComponent 8 /Length 100 >>",
+            &[1; 100],
+        );
+
+        let mut parser =
+            Parser::from_bytes(file.with_xref_table(), ParseOptions::default()).unwrap();
+
+        let path = std::env::temp_dir().join(format!(
+            "pdf-default-color-spaces-{}.png",
+            std::process::id()
+        ));
+
+        let page = parser.pages().remove(0);
+        let mut content = parser.page_contents(&page).unwrap();
+
+        Renderer::new(&mut content, &mut parser.lexer, page)
+            .render_to_image(&path)
+            .unwrap();
+
+        let pixels = rgb_pixels(&path);
+
+        assert_eq!(pixels.len(), 300);
+
+        // the centre of the gray image, the gray fill and the RGB fill
+        assert_eq!(pixels[155], [0xff, 0, 0]);
+        assert_eq!(pixels[165], [0xff, 0, 0]);
+        assert_eq!(pixels[175], [0, 0, 0xff]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,179 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use crate::{
+    data_structures::Rectangle,
+    geometry::{BoundingBox, Point},
+    objects::Object,
+    writer::write_object,
+};
+
+/// How an operator of the page content stream, along with its operands, is
+/// rewritten so that it no longer paints within the regions being redacted
+#[derive(Debug)]
+pub(crate) enum ContentEdit {
+    /// Remove the operator and its operands
+    Remove,
+
+    /// Replace a path-painting operator with `n`, so that the path is
+    /// discarded without being painted, but still modifies the clipping path
+    EndPath,
+
+    /// Replace the operator and its operands with the given content
+    Replace(Vec<u8>),
+}
+
+/// The operators of a page content stream which paint within the regions of
+/// the page being redacted
+#[derive(Debug)]
+pub(crate) struct RedactionScan {
+    /// The regions being redacted, in default user space
+    regions: Vec<BoundingBox>,
+
+    /// The byte range of the operator of the page content stream being
+    /// applied, starting at its first operand
+    operator: Range<usize>,
+
+    /// The number of form XObjects being painted. Forms may be shared with
+    /// other pages, so a form which paints within a region is removed as a
+    /// whole, rather than rewritten.
+    form_depth: usize,
+
+    /// The edits made to the page content stream, by the offset at which the
+    /// edited range begins
+    edits: BTreeMap<usize, (Range<usize>, ContentEdit)>,
+}
+
+impl RedactionScan {
+    pub fn new(regions: &[Rectangle]) -> Self {
+        let regions = regions
+            .iter()
+            .map(|rect| {
+                let mut bbox = BoundingBox::new();
+
+                for corner in rect.corners() {
+                    bbox.add_point(corner);
+                }
+
+                bbox
+            })
+            .collect();
+
+        Self {
+            regions,
+            operator: 0..0,
+            form_depth: 0,
+            edits: BTreeMap::new(),
+        }
+    }
+
+    /// Whether the content within `bbox`, in default user space, lies at least
+    /// partly within a region being redacted
+    pub fn intersects(&self, bbox: &BoundingBox) -> bool {
+        self.regions.iter().any(|region| region.intersects(bbox))
+    }
+
+    /// Whether the parallelogram with the given corners intersects a region
+    /// being redacted
+    pub fn intersects_points(&self, points: &[Point]) -> bool {
+        let mut bbox = BoundingBox::new();
+
+        for &point in points {
+            bbox.add_point(point);
+        }
+
+        self.intersects(&bbox)
+    }
+
+    /// Note the operator about to be applied, unless it is within a form
+    /// XObject
+    pub fn begin_operator(&mut self, operator: Range<usize>) {
+        if self.form_depth == 0 {
+            self.operator = operator;
+        }
+    }
+
+    pub fn enter_form(&mut self) {
+        self.form_depth += 1;
+    }
+
+    pub fn exit_form(&mut self) {
+        self.form_depth -= 1;
+    }
+
+    /// Rewrite the operator being applied, which paints within a region
+    ///
+    /// Within a form XObject, the operator which paints the form is removed
+    /// instead.
+    pub fn redact(&mut self, edit: ContentEdit) {
+        let edit = match self.form_depth {
+            0 => edit,
+            _ => ContentEdit::Remove,
+        };
+
+        self.edits
+            .insert(self.operator.start, (self.operator.clone(), edit));
+    }
+
+    /// Replace the text-showing operator being applied with `prefix`, followed
+    /// by a `TJ` operator showing the elements of `arr`
+    pub fn redact_text(&mut self, prefix: &[u8], arr: Vec<Object>) {
+        let mut content = prefix.to_vec();
+
+        write_object(&Object::Array(arr), &mut content).expect("content is written to memory");
+        content.extend_from_slice(b" TJ");
+
+        self.redact(ContentEdit::Replace(content));
+    }
+
+    /// The page content stream with each of its edits applied
+    pub fn apply(self, content: &[u8]) -> Vec<u8> {
+        let mut redacted = Vec::with_capacity(content.len());
+        let mut end = 0;
+
+        for (range, edit) in self.edits.into_values() {
+            redacted.extend_from_slice(&content[end..range.start]);
+
+            match edit {
+                ContentEdit::Remove => {}
+                ContentEdit::EndPath => redacted.push(b'n'),
+                ContentEdit::Replace(replacement) => redacted.extend_from_slice(&replacement),
+            }
+
+            end = range.end;
+        }
+
+        redacted.extend_from_slice(&content[end..]);
+
+        redacted
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{data_structures::Rectangle, objects::Object};
+
+    use super::{ContentEdit, RedactionScan};
+
+    #[test]
+    fn apply_content_edits() {
+        let content = b"0 0 10 10 re f BT (ab) Tj ET /Im1 Do";
+        let mut scan = RedactionScan::new(&[Rectangle::new(0.0, 0.0, 10.0, 10.0)]);
+
+        scan.begin_operator(13..14);
+        scan.redact(ContentEdit::EndPath);
+
+        scan.begin_operator(18..25);
+        scan.redact_text(
+            b"",
+            vec![Object::String("a".to_owned()), Object::Real(-500.0)],
+        );
+
+        scan.begin_operator(29..36);
+        scan.redact(ContentEdit::Remove);
+
+        assert_eq!(
+            String::from_utf8(scan.apply(content)).unwrap(),
+            "0 0 10 10 re n BT [(a) -500.0] TJ ET "
+        );
+    }
+}
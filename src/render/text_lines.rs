@@ -0,0 +1,264 @@
+use crate::{error::PdfResult, geometry::Point, page::PageObject, render::Renderer, Parser};
+use std::rc::Rc;
+
+/// Glyphs further than this many ems from the end of a line, along its
+/// baseline, begin a new line, such as in the next column
+const MAX_GAP: f32 = 2.0;
+
+/// Gaps wider than this many ems are treated as a word break
+const WORD_GAP: f32 = 0.25;
+
+/// A line of text as rendered, with its geometry in device coordinates: the
+/// pixels of the rendered page, with the origin at the top-left corner and the
+/// vertical axis pointing down
+///
+/// Viewers can draw selection carets along the baseline and highlights over
+/// the line box that line up with the rendered glyphs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedLine {
+    /// The text of the line, as given by the ToUnicode CMap of composite fonts
+    ///
+    /// The strings shown with simple fonts are taken as-is, as are their glyph
+    /// advances.
+    pub text: String,
+
+    /// The point on the baseline where the first glyph begins
+    pub baseline_start: [f32; 2],
+
+    /// The point on the baseline where the last glyph ends
+    pub baseline_end: [f32; 2],
+
+    /// The height of lowercase letters without ascenders above the baseline,
+    /// from the XHeight of the largest font in the line
+    pub x_height: f32,
+
+    /// The bounding box of the line, from the descent to the ascent of its
+    /// fonts, as `[left, top, right, bottom]`
+    pub line_box: [f32; 4],
+}
+
+/// A glyph painted by a text-showing operator, in the coordinates of the
+/// canvas, in which the vertical axis points up
+#[derive(Debug, Clone)]
+pub(super) struct PlacedGlyph {
+    pub text: String,
+
+    /// The start and end of the glyph's advance along the baseline
+    pub start: Point,
+    pub end: Point,
+
+    /// The length of one em perpendicular to the baseline
+    pub em: f32,
+
+    /// The ascent, descent, and x height of the font, in ems
+    pub ascent: f32,
+    pub descent: f32,
+    pub x_height: f32,
+}
+
+/// Joins the glyphs painted on a page into lines, in the order in which they
+/// are painted
+pub(super) struct RenderedLineBuilder {
+    lines: Vec<LineInProgress>,
+}
+
+struct LineInProgress {
+    text: String,
+    start: Point,
+    end: Point,
+
+    /// The unit vector along the baseline
+    direction: Point,
+
+    /// The size of the largest font in the line
+    em: f32,
+
+    /// The extents of the line, as distances from the baseline
+    ascent: f32,
+    descent: f32,
+    x_height: f32,
+}
+
+impl RenderedLineBuilder {
+    pub fn new() -> Self {
+        Self { lines: Vec::new() }
+    }
+
+    pub fn push(&mut self, glyph: PlacedGlyph) {
+        let advance = glyph.end - glyph.start;
+        let len = (advance.x * advance.x + advance.y * advance.y).sqrt();
+
+        if let Some(line) = self.lines.last_mut() {
+            if line.extend(&glyph) {
+                return;
+            }
+        }
+
+        // the direction of a line starting with a zero-width glyph is unknown,
+        // so it is assumed to be horizontal
+        let direction = if len > f32::EPSILON {
+            advance / len
+        } else {
+            Point::new(1.0, 0.0)
+        };
+
+        self.lines.push(LineInProgress {
+            text: glyph.text,
+            start: glyph.start,
+            end: glyph.end,
+            direction,
+            em: glyph.em,
+            ascent: glyph.ascent * glyph.em,
+            descent: glyph.descent * glyph.em,
+            x_height: glyph.x_height * glyph.em,
+        });
+    }
+
+    /// The lines of the page, converting canvas coordinates to device
+    /// coordinates for a page `height` pixels tall
+    pub fn finish(self, height: f32) -> Vec<RenderedLine> {
+        let to_device = |p: Point| [p.x, height - p.y];
+
+        self.lines
+            .into_iter()
+            .filter(|line| !line.text.trim().is_empty())
+            .map(|line| {
+                // perpendicular to the baseline, pointing towards the ascent
+                let up = Point::new(-line.direction.y, line.direction.x);
+
+                let ascent = up * line.ascent;
+                let descent = up * line.descent;
+
+                let corners = [
+                    line.start + ascent,
+                    line.end + ascent,
+                    line.start + descent,
+                    line.end + descent,
+                ]
+                .map(to_device);
+
+                let left = corners.iter().map(|c| c[0]).fold(f32::INFINITY, f32::min);
+                let right = corners
+                    .iter()
+                    .map(|c| c[0])
+                    .fold(f32::NEG_INFINITY, f32::max);
+                let top = corners.iter().map(|c| c[1]).fold(f32::INFINITY, f32::min);
+                let bottom = corners
+                    .iter()
+                    .map(|c| c[1])
+                    .fold(f32::NEG_INFINITY, f32::max);
+
+                RenderedLine {
+                    text: line.text,
+                    baseline_start: to_device(line.start),
+                    baseline_end: to_device(line.end),
+                    x_height: line.x_height,
+                    line_box: [left, top, right, bottom],
+                }
+            })
+            .collect()
+    }
+}
+
+impl LineInProgress {
+    /// Add the glyph to this line if it continues it, lying on the same
+    /// baseline shortly after its end
+    fn extend(&mut self, glyph: &PlacedGlyph) -> bool {
+        let offset = glyph.start - self.start;
+
+        let along = offset.x * self.direction.x + offset.y * self.direction.y;
+        let across = offset.y * self.direction.x - offset.x * self.direction.y;
+
+        let end = self.end - self.start;
+        let line_len = end.x * self.direction.x + end.y * self.direction.y;
+
+        let em = self.em.max(glyph.em);
+        let gap = along - line_len;
+
+        // superscripts and subscripts are raised or lowered by less than half
+        // an em
+        if across.abs() > em * 0.5 || gap < -em * 0.5 || gap > em * MAX_GAP {
+            return false;
+        }
+
+        if gap > em * WORD_GAP && !self.text.ends_with(char::is_whitespace) {
+            self.text.push(' ');
+        }
+
+        self.text.push_str(&glyph.text);
+
+        // the line ends at the furthest glyph along the baseline, projected
+        // onto the line's baseline
+        let glyph_end = glyph.end - self.start;
+        let glyph_len = glyph_end.x * self.direction.x + glyph_end.y * self.direction.y;
+
+        if glyph_len > line_len {
+            self.end = self.start + self.direction * glyph_len;
+        }
+
+        self.ascent = self.ascent.max(glyph.ascent * glyph.em);
+        self.descent = self.descent.min(glyph.descent * glyph.em);
+
+        if glyph.em > self.em {
+            self.em = glyph.em;
+            self.x_height = glyph.x_height * glyph.em;
+        }
+
+        true
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// The lines of text of `page` as rendered, with their baselines and line
+    /// boxes in the device coordinates of the rendered page, for drawing
+    /// selection carets and highlights that match the rendering
+    pub fn rendered_text_lines(
+        &mut self,
+        page: &Rc<PageObject<'a>>,
+    ) -> PdfResult<Vec<RenderedLine>> {
+        let mut content = self.page_contents(page)?;
+        let optional_content = self.optional_content_state();
+
+        Renderer::new(&mut content, &mut self.lexer, Rc::clone(page))
+            .with_optional_content(optional_content)
+            .render_text_lines()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn glyph(text: &str, x: f32, y: f32, width: f32, em: f32) -> PlacedGlyph {
+        PlacedGlyph {
+            text: text.to_owned(),
+            start: Point::new(x, y),
+            end: Point::new(x + width, y),
+            em,
+            ascent: 0.75,
+            descent: -0.25,
+            x_height: 0.5,
+        }
+    }
+
+    #[test]
+    fn join_glyphs_into_lines() {
+        let mut builder = RenderedLineBuilder::new();
+
+        builder.push(glyph("H", 10.0, 100.0, 6.0, 12.0));
+        builder.push(glyph("i", 16.0, 100.0, 3.0, 12.0));
+        builder.push(glyph("x", 25.0, 100.0, 6.0, 12.0));
+        builder.push(glyph("N", 10.0, 80.0, 6.0, 12.0));
+
+        let lines = builder.finish(200.0);
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].text, "Hi x");
+        assert_eq!(lines[0].baseline_start, [10.0, 100.0]);
+        assert_eq!(lines[0].baseline_end, [31.0, 100.0]);
+        assert_eq!(lines[0].x_height, 6.0);
+        assert_eq!(lines[0].line_box, [10.0, 91.0, 31.0, 103.0]);
+        assert_eq!(lines[1].text, "N");
+        assert_eq!(lines[1].baseline_start, [10.0, 120.0]);
+    }
+}
@@ -1,6 +1,7 @@
 use std::convert::TryFrom;
 
 use crate::{
+    encryption::SecurityHandler,
     error::PdfResult,
     objects::{Dictionary, Object, Reference},
     stream::Stream,
@@ -12,6 +13,15 @@ pub trait Resolve<'a> {
     /// Whether or not the reference points to an existing object
     fn reference_exists(&mut self, reference: Reference) -> PdfResult<bool>;
 
+    /// The security handler of an encrypted document
+    ///
+    /// Strings and streams are decrypted as the objects containing them are
+    /// resolved, so this is only needed to decrypt data which is not an
+    /// indirect object.
+    fn security_handler(&self) -> Option<&SecurityHandler<'a>> {
+        None
+    }
+
     fn assert_integer(&mut self, obj: Object) -> PdfResult<i32> {
         match obj {
             Object::Integer(i) => Ok(i),
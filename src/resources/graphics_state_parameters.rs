@@ -18,6 +18,20 @@ enum OrDefault<T> {
     Default,
 }
 
+/// The new value of a parameter with a second form that takes precedence over
+/// the first, where a second form of Default denotes the device's default value,
+/// `None`
+fn or_default<'b, T>(
+    first: &'b Option<T>,
+    second: &'b Option<OrDefault<T>>,
+) -> Option<Option<&'b T>> {
+    match (second, first) {
+        (Some(OrDefault::Value(value)), _) | (None, Some(value)) => Some(Some(value)),
+        (Some(OrDefault::Default), _) => Some(None),
+        (None, None) => None,
+    }
+}
+
 impl<'a, T: FromObj<'a>> FromObj<'a> for OrDefault<T> {
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
         let obj = resolver.resolve(obj)?;
@@ -262,14 +276,14 @@ pub struct SoftMaskDictionary<'a> {
     /// A subtype specifying the method to be used in deriving the mask values from the
     /// transparency group specified by the G entry
     #[field("S")]
-    subtype: SoftMaskSubtype,
+    pub(crate) subtype: SoftMaskSubtype,
 
     /// A transparency group XObject to be used as the source of alpha or colour values
     /// for deriving the mask. If the subtype S is Luminosity, the group attributes
     /// dictionary shall contain a CS entry defining the colour space in which the compositing
     /// computation is to be performed
     #[field("G")]
-    transparency_group: Stream<'a>,
+    pub(crate) transparency_group: Stream<'a>,
 
     /// An array of component values specifying the colour to be used as the backdrop against
     /// which to composite the transparency group XObject G. This entry shall be consulted only
@@ -278,9 +292,8 @@ pub struct SoftMaskDictionary<'a> {
     /// dictionary.
     ///
     /// Default value: the colour space's initial value, representing black.
-    // todo
     #[field("BC")]
-    backdrop_color: Option<Vec<Object<'a>>>,
+    pub(crate) backdrop_color: Option<Vec<f32>>,
 
     /// A function object specifying the transfer function to be used
     /// in deriving the mask values. The function shall accept one input, the computed
@@ -292,11 +305,11 @@ pub struct SoftMaskDictionary<'a> {
     ///
     /// Default value: Identity
     #[field("TR", default = TransferFunction::Identity)]
-    transfer_function: TransferFunction<'a>,
+    pub(crate) transfer_function: TransferFunction<'a>,
 }
 
 #[pdf_enum]
-enum SoftMaskSubtype {
+pub(crate) enum SoftMaskSubtype {
     /// The group's computed alpha shall be used, disregarding its colour
     Alpha = "Alpha",
 
@@ -366,6 +379,19 @@ impl<'a> FromObj<'a> for (Rc<Font<'a>>, f32) {
 }
 
 impl<'a> GraphicsStateParameters<'a> {
+    pub(crate) fn soft_mask(&self) -> Option<&SoftMask<'a>> {
+        self.soft_mask.as_ref()
+    }
+
+    pub(crate) fn halftones(&self) -> Option<&Halftones<'a>> {
+        self.halftones.as_ref()
+    }
+
+    /// Whether these parameters set the transfer function, with either TR or TR2
+    pub(crate) fn has_transfer_function(&self) -> bool {
+        self.transfer.is_some() || self.transfer_two.is_some()
+    }
+
     pub(crate) fn update_graphics_state(
         &self,
         graphics_state: &mut GraphicsState<'a>,
@@ -396,15 +422,30 @@ impl<'a> GraphicsStateParameters<'a> {
         update_field!(@clone line_dash_pattern, device_independent);
         update_field!(rendering_intent, device_independent);
         update_field!(should_overprint_stroking, device_dependent);
-        update_field!(should_overprint, device_dependent);
         update_field!(overprint_mode, device_dependent);
-        // todo: function fields
-        // update_field!(black_generation, device_dependent);
-        // update_field!(black_generation_two, device_dependent);
-        // update_field!(undercolor_removal, device_dependent);
-        // update_field!(undercolor_removal_two, device_dependent);
-        // update_field!(transfer, device_dependent);
-        // update_field!(transfer_two, device_dependent);
+
+        // OP sets both overprint parameters, unless op is also present
+        if let Some(should_overprint) = self.should_overprint.or(self.should_overprint_stroking) {
+            graphics_state.device_dependent.should_overprint = should_overprint;
+        }
+
+        if let Some(black_generation) =
+            or_default(&self.black_generation, &self.black_generation_two)
+        {
+            graphics_state.device_dependent.black_generation = black_generation.cloned();
+        }
+
+        if let Some(undercolor_removal) =
+            or_default(&self.undercolor_removal, &self.undercolor_removal_two)
+        {
+            graphics_state.device_dependent.undercolor_removal = undercolor_removal.cloned();
+        }
+
+        if let Some(transfer) = or_default(&self.transfer, &self.transfer_two) {
+            graphics_state.device_dependent.transfer =
+                transfer.cloned().unwrap_or(TransferFunction::Default);
+        }
+
         update_field!(@clone halftones, device_dependent);
         update_field!(flatness_tolerance, device_dependent);
         update_field!(smoothness_tolerance, device_dependent);
@@ -414,6 +455,10 @@ impl<'a> GraphicsStateParameters<'a> {
         update_field!(stroking_alpha_constant, device_independent);
         update_field!(nonstroking_alpha_constant, device_independent);
         update_field!(alpha_source, device_independent);
+
+        if let Some(knockout) = self.is_knockout {
+            text_state.knockout = knockout;
+        }
     }
 }
 
@@ -464,3 +509,72 @@ pub enum LineCapStyle {
     /// and shall be squared off.
     ProjectingSquare = 2,
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{
+        function::TransferFunction,
+        objects::{Dictionary, Object},
+        render::{graphics_state::GraphicsState, text_state::TextState},
+        version::PdfVersion,
+        writer::PdfWriter,
+        FromObj,
+    };
+
+    use super::GraphicsStateParameters;
+
+    fn apply(
+        entries: Vec<(&str, Object<'static>)>,
+    ) -> (GraphicsState<'static>, TextState<'static>) {
+        let dict = entries
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value))
+            .collect::<HashMap<_, _>>();
+
+        let parameters = GraphicsStateParameters::from_obj(
+            Object::Dictionary(Dictionary::new(dict)),
+            &mut PdfWriter::new(PdfVersion::V1_7),
+        )
+        .unwrap();
+
+        let mut graphics_state = GraphicsState::default();
+        let mut text_state = TextState::default();
+
+        parameters.update_graphics_state(&mut graphics_state, &mut text_state);
+
+        (graphics_state, text_state)
+    }
+
+    #[test]
+    fn graphics_state_parameters() {
+        // OP sets both overprint parameters, unless op is also present
+        let (graphics_state, _) = apply(vec![("OP", Object::True)]);
+        assert!(graphics_state.device_dependent.should_overprint_stroking);
+        assert!(graphics_state.device_dependent.should_overprint);
+
+        let (graphics_state, _) = apply(vec![("OP", Object::True), ("op", Object::False)]);
+        assert!(graphics_state.device_dependent.should_overprint_stroking);
+        assert!(!graphics_state.device_dependent.should_overprint);
+
+        // TR2 takes precedence over TR
+        let (graphics_state, _) = apply(vec![
+            ("TR", Object::Name("Identity".to_owned())),
+            ("TR2", Object::Name("Default".to_owned())),
+        ]);
+        assert!(matches!(
+            graphics_state.device_dependent.transfer,
+            TransferFunction::Default
+        ));
+
+        let (graphics_state, _) = apply(vec![("TR", Object::Name("Identity".to_owned()))]);
+        assert!(matches!(
+            graphics_state.device_dependent.transfer,
+            TransferFunction::Identity
+        ));
+
+        let (_, text_state) = apply(vec![("TK", Object::False)]);
+        assert!(!text_state.knockout);
+    }
+}
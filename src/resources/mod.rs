@@ -1,14 +1,8 @@
 use std::{collections::HashMap, rc::Rc};
 
 use crate::{
-    assert_empty,
-    color::ColorSpace,
-    error::PdfResult,
-    font::Font,
-    objects::{Dictionary, Object},
-    shading::ShadingObject,
-    xobject::XObject,
-    FromObj, Resolve,
+    assert_empty, color::ColorSpace, content::PropertyList, error::PdfResult, font::Font,
+    objects::Object, shading::ShadingObject, xobject::XObject, FromObj, Resolve,
 };
 
 use self::{graphics_state_parameters::GraphicsStateParameters, pattern::Pattern};
@@ -41,8 +35,9 @@ pub struct Resources<'a> {
     /// An array of predefined procedure set names
     pub proc_set: Option<Vec<ProcedureSet>>,
 
-    pub properties: Option<Dictionary<'a>>,
-    // properties: Option<HashMap<String, PropertyList>>,
+    /// A dictionary that maps resource names to property list dictionaries for
+    /// marked content
+    pub properties: Option<HashMap<String, PropertyList<'a>>>,
 }
 
 impl<'a> FromObj<'a> for Resources<'a> {
@@ -68,7 +63,7 @@ impl<'a> FromObj<'a> for Resources<'a> {
                     .collect::<PdfResult<Vec<ProcedureSet>>>()
             })
             .transpose()?;
-        let properties = dict.get_dict("Properties", resolver)?;
+        let properties = dict.get("Properties", resolver)?;
 
         assert_empty(dict);
 
@@ -117,6 +117,13 @@ pub struct ShadingPattern<'a> {
     ext_g_state: Option<GraphicsStateParameters<'a>>,
 }
 
+impl<'a> ShadingPattern<'a> {
+    /// The shading object defining the gradient fill of the pattern
+    pub fn shading(&self) -> &ShadingObject<'a> {
+        &self.shading
+    }
+}
+
 #[pdf_enum(Integer)]
 enum PatternType {
     Tiling = 1,
@@ -1,4 +1,6 @@
-use crate::{filter::flate::BitsPerComponent, function::Function, stream::Stream};
+use crate::{error::PdfResult, filter::decode_stream, function::Function, stream::Stream, Resolve};
+
+use super::mesh::{MeshReader, MeshTriangle};
 
 /// Type 4 shadings (free-form Gouraud-shaded triangle meshes) are commonly used to
 /// represent complex coloured and shaded three-dimensional shapes. The area to be
@@ -39,22 +41,63 @@ pub struct FreeformShading<'a> {
     decode: Vec<f32>,
 
     #[field("Function")]
-    function: Option<Function<'a>>,
+    pub(crate) function: Option<Function<'a>>,
 
     #[field]
     stream: Stream<'a>,
 }
 
+impl<'a> FreeformShading<'a> {
+    /// The triangles of the mesh, whose colours have a value for each of the
+    /// `components` of the shading's colour space, or a single parametric
+    /// value if the shading has a function
+    pub(super) fn triangles(
+        &self,
+        components: usize,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Vec<MeshTriangle>> {
+        let data = decode_stream(&self.stream.stream, &self.stream.dict, resolver)?;
+
+        let color_values = if self.function.is_some() {
+            1
+        } else {
+            components
+        };
+
+        MeshReader::new(
+            &data,
+            self.bits_per_coordinate as u32,
+            self.bits_per_component as u32,
+            self.bits_per_flag as u32,
+            &self.decode,
+            color_values,
+        )?
+        .free_form_triangles()
+    }
+}
+
 #[pdf_enum(Integer)]
 pub enum BitsPerCoordinate {
     One = 1,
     Two = 2,
     Four = 4,
     Eight = 8,
+    Twelve = 12,
     Sixteen = 16,
+    TwentyFour = 24,
     ThirtyTwo = 32,
 }
 
+#[pdf_enum(Integer)]
+pub enum BitsPerComponent {
+    One = 1,
+    Two = 2,
+    Four = 4,
+    Eight = 8,
+    Twelve = 12,
+    Sixteen = 16,
+}
+
 #[pdf_enum(Integer)]
 pub enum BitsPerFlag {
     Two = 2,
@@ -29,7 +29,7 @@ pub struct FunctionBasedShading<'a> {
     /// be a superset of that of the shading dictionary. If the value returned by the function for
     /// a given colour component is out of range, it shall be adjusted to the nearest valid value
     #[field("Function")]
-    function: Function<'a>,
+    pub(crate) function: Function<'a>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -1,6 +1,9 @@
-use crate::{filter::flate::BitsPerComponent, function::Function, stream::Stream};
+use crate::{error::PdfResult, filter::decode_stream, function::Function, stream::Stream, Resolve};
 
-use super::freeform::BitsPerCoordinate;
+use super::{
+    freeform::{BitsPerComponent, BitsPerCoordinate},
+    mesh::{MeshReader, MeshTriangle},
+};
 
 /// Type 5 shadings (lattice-form Gouraud-shaded triangle meshes) are similar to type
 /// 4, but instead of using freeform geometry, their vertices are arranged in a
@@ -22,7 +25,7 @@ pub struct LatticeformShading<'a> {
 
     /// The number of vertices in each row of the lattice; the value shall be
     /// greater than or equal to 2. The number of rows need not be specified.
-    #[field("BitsPerFlag")]
+    #[field("VerticesPerRow")]
     vertices_per_row: u32,
 
     /// An array of numbers specifying how to map vertex coordinates and colour
@@ -51,8 +54,37 @@ pub struct LatticeformShading<'a> {
     ///
     /// This entry shall not be used with an Indexed colour space.
     #[field("Function")]
-    function: Option<Function<'a>>,
+    pub(crate) function: Option<Function<'a>>,
 
     #[field]
     stream: Stream<'a>,
 }
+
+impl<'a> LatticeformShading<'a> {
+    /// The triangles of the mesh, whose colours have a value for each of the
+    /// `components` of the shading's colour space, or a single parametric
+    /// value if the shading has a function
+    pub(super) fn triangles(
+        &self,
+        components: usize,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Vec<MeshTriangle>> {
+        let data = decode_stream(&self.stream.stream, &self.stream.dict, resolver)?;
+
+        let color_values = if self.function.is_some() {
+            1
+        } else {
+            components
+        };
+
+        MeshReader::new(
+            &data,
+            self.bits_per_coordinate as u32,
+            self.bits_per_component as u32,
+            0,
+            &self.decode,
+            color_values,
+        )?
+        .lattice_form_triangles(self.vertices_per_row)
+    }
+}
@@ -0,0 +1,569 @@
+/*!
+ * Decoding of the vertex data of mesh shadings (types 4 to 7), which is
+ * packed into their streams as a sequence of bit fields
+ */
+
+use crate::{error::PdfResult, geometry::Point};
+
+/// A vertex of a mesh shading
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshVertex {
+    pub point: Point,
+
+    /// A component for each component of the shading's colour space or, if
+    /// the shading has a function, the single parametric value t to be passed
+    /// to it
+    pub color: Vec<f32>,
+}
+
+/// A triangle of a mesh shading, whose interior is coloured by interpolating
+/// between the colours of its vertices
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshTriangle {
+    pub vertices: [MeshVertex; 3],
+}
+
+/// A patch of a type 6 or type 7 shading, which is mapped from the unit square
+/// onto the area bounded by four cubic Bézier curves
+///
+/// Coons patches are represented by the tensor-product patch of the same
+/// shape, whose interior control points are derived from the boundary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshPatch {
+    /// The 16 control points, in which `points[i][j]` is the point pij of the
+    /// spec, at the ith position along u and the jth along v
+    pub points: [[Point; 4]; 4],
+
+    /// The colours of the corners p00, p03, p33 and p30, in that order
+    pub colors: [Vec<f32>; 4],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShadingMesh {
+    Triangles(Vec<MeshTriangle>),
+    Patches(Vec<MeshPatch>),
+}
+
+impl MeshPatch {
+    /// Build a patch from its 12 boundary points, in the order in which they
+    /// appear in the data stream, and its 4 interior points p11, p12, p22 and
+    /// p21 if it is a tensor-product patch
+    fn new(boundary: [Point; 12], interior: Option<[Point; 4]>, colors: [Vec<f32>; 4]) -> Self {
+        let [p00, p01, p02, p03, p13, p23, p33, p32, p31, p30, p20, p10] = boundary;
+
+        let [p11, p12, p22, p21] = match interior {
+            Some(interior) => interior,
+            None => {
+                // the implicit interior control points of a Coons patch
+                let interior = |corner: Point,
+                                adjacent: [Point; 2],
+                                opposite_ends: [Point; 2],
+                                far: [Point; 2],
+                                opposite: Point| {
+                    (-4.0 * corner
+                        + 6.0 * (adjacent[0] + adjacent[1])
+                        + -2.0 * (opposite_ends[0] + opposite_ends[1])
+                        + 3.0 * (far[0] + far[1])
+                        + -opposite)
+                        / 9.0
+                };
+
+                [
+                    interior(p00, [p01, p10], [p03, p30], [p31, p13], p33),
+                    interior(p03, [p02, p13], [p00, p33], [p32, p10], p30),
+                    interior(p33, [p32, p23], [p30, p03], [p02, p20], p00),
+                    interior(p30, [p31, p20], [p33, p00], [p01, p23], p03),
+                ]
+            }
+        };
+
+        Self {
+            points: [
+                [p00, p01, p02, p03],
+                [p10, p11, p12, p13],
+                [p20, p21, p22, p23],
+                [p30, p31, p32, p33],
+            ],
+            colors,
+        }
+    }
+
+    /// The point of the patch to which (u, v) of the unit square is mapped
+    pub fn point_at(&self, u: f32, v: f32) -> Point {
+        let bu = bernstein(u);
+        let bv = bernstein(v);
+
+        let mut point = Point::origin();
+
+        for (i, row) in self.points.iter().enumerate() {
+            for (j, &p) in row.iter().enumerate() {
+                point += p * (bu[i] * bv[j]);
+            }
+        }
+
+        point
+    }
+
+    /// The colour at (u, v) of the unit square, bilinearly interpolated
+    /// between the colours of the corners
+    pub fn color_at(&self, u: f32, v: f32) -> Vec<f32> {
+        let [c00, c03, c33, c30] = &self.colors;
+
+        (0..c00.len())
+            .map(|k| {
+                (1.0 - u) * (1.0 - v) * c00[k]
+                    + (1.0 - u) * v * c03[k]
+                    + u * v * c33[k]
+                    + u * (1.0 - v) * c30[k]
+            })
+            .collect()
+    }
+
+    /// Approximate the patch by triangles, dividing the unit square into a grid
+    /// of `divisions` by `divisions` cells
+    pub fn triangulate(&self, divisions: usize) -> Vec<MeshTriangle> {
+        let divisions = divisions.max(1);
+
+        let vertex = |i: usize, j: usize| {
+            let u = i as f32 / divisions as f32;
+            let v = j as f32 / divisions as f32;
+
+            MeshVertex {
+                point: self.point_at(u, v),
+                color: self.color_at(u, v),
+            }
+        };
+
+        let mut triangles = Vec::with_capacity(divisions * divisions * 2);
+
+        for i in 0..divisions {
+            for j in 0..divisions {
+                let [a, b, c, d] = [
+                    vertex(i, j),
+                    vertex(i + 1, j),
+                    vertex(i + 1, j + 1),
+                    vertex(i, j + 1),
+                ];
+
+                triangles.push(MeshTriangle {
+                    vertices: [a.clone(), b, c.clone()],
+                });
+                triangles.push(MeshTriangle {
+                    vertices: [a, c, d],
+                });
+            }
+        }
+
+        triangles
+    }
+}
+
+/// The cubic Bernstein polynomials at `t`
+fn bernstein(t: f32) -> [f32; 4] {
+    let s = 1.0 - t;
+
+    [s * s * s, 3.0 * t * s * s, 3.0 * t * t * s, t * t * t]
+}
+
+/// Reads the values of a mesh shading's data stream, each of which is packed
+/// with its most significant bit first
+pub(super) struct MeshReader<'d> {
+    data: &'d [u8],
+
+    /// The index of the next bit
+    pos: usize,
+
+    bits_per_coordinate: u32,
+    bits_per_component: u32,
+    bits_per_flag: u32,
+
+    /// The Decode array of the shading, which has been checked to hold a pair
+    /// of values for each colour value
+    decode: &'d [f32],
+}
+
+impl<'d> MeshReader<'d> {
+    /// `color_values` is the number of colour values of each vertex, which is
+    /// 1 if the shading has a function and otherwise the number of components
+    /// of its colour space
+    pub fn new(
+        data: &'d [u8],
+        bits_per_coordinate: u32,
+        bits_per_component: u32,
+        bits_per_flag: u32,
+        decode: &'d [f32],
+        color_values: usize,
+    ) -> PdfResult<Self> {
+        anyhow::ensure!(
+            decode.len() >= 4 + 2 * color_values,
+            "expected {} Decode values for mesh shading, found {}",
+            4 + 2 * color_values,
+            decode.len()
+        );
+
+        Ok(Self {
+            data,
+            pos: 0,
+            bits_per_coordinate,
+            bits_per_component,
+            bits_per_flag,
+            decode: &decode[..4 + 2 * color_values],
+        })
+    }
+
+    fn read_bits(&mut self, bits: u32) -> Option<u32> {
+        let end = self.pos + bits as usize;
+
+        if end > self.data.len() * 8 {
+            return None;
+        }
+
+        let value = (self.pos..end).fold(0_u64, |value, pos| {
+            value << 1 | u64::from((self.data[pos / 8] >> (7 - pos % 8)) & 1)
+        });
+
+        self.pos = end;
+
+        Some(value as u32)
+    }
+
+    /// Skip to the start of the next byte
+    pub fn align(&mut self) {
+        self.pos = self.pos.next_multiple_of(8);
+    }
+
+    /// The next edge flag, of which only the 2 least significant bits are used
+    pub fn read_flag(&mut self) -> Option<u8> {
+        Some((self.read_bits(self.bits_per_flag)? & 0b11) as u8)
+    }
+
+    fn read_value(&mut self, bits: u32, min: f32, max: f32) -> Option<f32> {
+        let value = self.read_bits(bits)?;
+        let max_value = ((1_u64 << bits) - 1) as f64;
+
+        Some((min as f64 + value as f64 * (max - min) as f64 / max_value) as f32)
+    }
+
+    pub fn read_point(&mut self) -> Option<Point> {
+        let x = self.read_value(self.bits_per_coordinate, self.decode[0], self.decode[1])?;
+        let y = self.read_value(self.bits_per_coordinate, self.decode[2], self.decode[3])?;
+
+        Some(Point::new(x, y))
+    }
+
+    pub fn read_color(&mut self) -> Option<Vec<f32>> {
+        let decode = self.decode;
+
+        decode[4..]
+            .chunks_exact(2)
+            .map(|range| self.read_value(self.bits_per_component, range[0], range[1]))
+            .collect()
+    }
+
+    fn read_vertex(&mut self) -> Option<MeshVertex> {
+        let point = self.read_point()?;
+        let color = self.read_color()?;
+
+        Some(MeshVertex { point, color })
+    }
+
+    /// The triangles of a free-form mesh, in which each vertex is preceded by
+    /// an edge flag and begins at a byte boundary
+    ///
+    /// An incomplete vertex at the end of the data, such as padding, is
+    /// ignored.
+    pub fn free_form_triangles(mut self) -> PdfResult<Vec<MeshTriangle>> {
+        let mut triangles: Vec<MeshTriangle> = Vec::new();
+
+        while let Some(flag) = self.read_flag() {
+            let vertex = match self.read_vertex() {
+                Some(vertex) => vertex,
+                None => break,
+            };
+
+            self.align();
+
+            let vertices = match flag {
+                // a new triangle, whose remaining vertices follow; their
+                // flags are ignored
+                0 => {
+                    let mut next_vertex = || {
+                        self.read_flag()?;
+                        let vertex = self.read_vertex()?;
+                        self.align();
+
+                        Some(vertex)
+                    };
+
+                    match (next_vertex(), next_vertex()) {
+                        (Some(b), Some(c)) => [vertex, b, c],
+                        _ => break,
+                    }
+                }
+                // a triangle sharing the edge vb vc (1) or va vc (2) of the
+                // previous one
+                1 | 2 => {
+                    let [va, vb, vc] = match triangles.last() {
+                        Some(triangle) => &triangle.vertices,
+                        None => {
+                            anyhow::bail!("free-form mesh shading begins with edge flag {}", flag)
+                        }
+                    };
+
+                    let shared = if flag == 1 { vb } else { va };
+
+                    [shared.clone(), vc.clone(), vertex]
+                }
+                _ => anyhow::bail!("invalid mesh shading edge flag {}", flag),
+            };
+
+            triangles.push(MeshTriangle { vertices });
+        }
+
+        Ok(triangles)
+    }
+
+    /// The triangles of a lattice-form mesh, whose vertices are arranged in
+    /// rows of `vertices_per_row`, each of which begins at a byte boundary
+    ///
+    /// An incomplete row at the end of the data is ignored.
+    pub fn lattice_form_triangles(mut self, vertices_per_row: u32) -> PdfResult<Vec<MeshTriangle>> {
+        anyhow::ensure!(
+            vertices_per_row >= 2,
+            "lattice-form mesh shading has {} vertices per row",
+            vertices_per_row
+        );
+
+        let vertices_per_row = vertices_per_row as usize;
+
+        let mut vertices = Vec::new();
+
+        while let Some(vertex) = self.read_vertex() {
+            vertices.push(vertex);
+            self.align();
+        }
+
+        let rows = vertices.chunks_exact(vertices_per_row).collect::<Vec<_>>();
+
+        let mut triangles = Vec::new();
+
+        for pair in rows.windows(2) {
+            let (top, bottom) = (pair[0], pair[1]);
+
+            for j in 0..vertices_per_row - 1 {
+                triangles.push(MeshTriangle {
+                    vertices: [top[j].clone(), top[j + 1].clone(), bottom[j].clone()],
+                });
+                triangles.push(MeshTriangle {
+                    vertices: [top[j + 1].clone(), bottom[j + 1].clone(), bottom[j].clone()],
+                });
+            }
+        }
+
+        Ok(triangles)
+    }
+
+    /// The patches of a Coons or, if `tensor_product` is set, tensor-product
+    /// patch mesh
+    ///
+    /// An incomplete patch at the end of the data is ignored.
+    pub fn patches(mut self, tensor_product: bool) -> PdfResult<Vec<MeshPatch>> {
+        let mut patches: Vec<MeshPatch> = Vec::new();
+
+        while let Some(flag) = self.read_flag() {
+            // the edge of the previous patch which is shared by this one, and
+            // the colours of its ends
+            let shared = match (flag, patches.last()) {
+                (0, _) => None,
+                (1..=3, Some(previous)) => {
+                    let p = &previous.points;
+                    let [c1, c2, c3, c4] = &previous.colors;
+
+                    Some(match flag {
+                        1 => ([p[0][3], p[1][3], p[2][3], p[3][3]], [c2, c3]),
+                        2 => ([p[3][3], p[3][2], p[3][1], p[3][0]], [c3, c4]),
+                        _ => ([p[3][0], p[2][0], p[1][0], p[0][0]], [c4, c1]),
+                    })
+                }
+                (_, None) => anyhow::bail!("patch mesh shading begins with edge flag {}", flag),
+                (_, Some(_)) => anyhow::bail!("invalid edge flag {} in patch mesh shading", flag),
+            };
+
+            let new_points = if shared.is_some() { 8 } else { 12 };
+            let new_colors = if shared.is_some() { 2 } else { 4 };
+
+            let points = (0..new_points + if tensor_product { 4 } else { 0 })
+                .map(|_| self.read_point())
+                .collect::<Option<Vec<_>>>();
+
+            let colors = (0..new_colors)
+                .map(|_| self.read_color())
+                .collect::<Option<Vec<_>>>();
+
+            let (mut points, mut colors) = match (points, colors) {
+                (Some(points), Some(colors)) => (points, colors),
+                _ => break,
+            };
+
+            if let Some((edge, edge_colors)) = shared {
+                points = edge.into_iter().chain(points).collect();
+                colors = edge_colors.into_iter().cloned().chain(colors).collect();
+            }
+
+            let boundary: [Point; 12] = points[..12].try_into().unwrap();
+            let interior = if tensor_product {
+                Some([points[12], points[13], points[14], points[15]])
+            } else {
+                None
+            };
+
+            patches.push(MeshPatch::new(
+                boundary,
+                interior,
+                colors.try_into().unwrap(),
+            ));
+        }
+
+        Ok(patches)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn color(c: f32) -> Vec<f32> {
+        vec![c]
+    }
+
+    #[test]
+    fn free_form_triangles() {
+        // 8-bit flags, coordinates and components, with coordinates decoded to
+        // 0..255 and colours to 0..1
+        let data = [
+            0, 0, 0, 0, //
+            0, 255, 0, 255, //
+            0, 0, 255, 0, //
+            1, 255, 255, 255, //
+            2, 128, 128, 0,
+        ];
+        let decode = [0.0, 255.0, 0.0, 255.0, 0.0, 1.0];
+
+        let triangles = MeshReader::new(&data, 8, 8, 8, &decode, 1)
+            .unwrap()
+            .free_form_triangles()
+            .unwrap();
+
+        let vertex = |x: f32, y: f32, c: f32| MeshVertex {
+            point: Point::new(x, y),
+            color: color(c),
+        };
+
+        assert_eq!(
+            triangles,
+            [
+                MeshTriangle {
+                    vertices: [
+                        vertex(0.0, 0.0, 0.0),
+                        vertex(255.0, 0.0, 1.0),
+                        vertex(0.0, 255.0, 0.0)
+                    ]
+                },
+                MeshTriangle {
+                    vertices: [
+                        vertex(255.0, 0.0, 1.0),
+                        vertex(0.0, 255.0, 0.0),
+                        vertex(255.0, 255.0, 1.0)
+                    ]
+                },
+                MeshTriangle {
+                    vertices: [
+                        vertex(255.0, 0.0, 1.0),
+                        vertex(255.0, 255.0, 1.0),
+                        vertex(128.0, 128.0, 0.0)
+                    ]
+                },
+            ]
+        );
+
+        // an edge flag of 1 can't begin a mesh
+        assert!(MeshReader::new(&data[12..], 8, 8, 8, &decode, 1)
+            .unwrap()
+            .free_form_triangles()
+            .is_err());
+    }
+
+    #[test]
+    fn lattice_form_triangles() {
+        // 4-bit coordinates and components, so that each vertex is padded to
+        // a byte boundary
+        let data = [0x00, 0x00, 0xf0, 0xf0, 0x0f, 0x00, 0xff, 0xf0];
+        let decode = [0.0, 15.0, 0.0, 15.0, 0.0, 15.0];
+
+        let triangles = MeshReader::new(&data, 4, 4, 8, &decode, 1)
+            .unwrap()
+            .lattice_form_triangles(2)
+            .unwrap();
+
+        assert_eq!(triangles.len(), 2);
+        assert_eq!(
+            triangles[1]
+                .vertices
+                .iter()
+                .map(|vertex| vertex.point)
+                .collect::<Vec<_>>(),
+            [
+                Point::new(15.0, 0.0),
+                Point::new(15.0, 15.0),
+                Point::new(0.0, 15.0)
+            ]
+        );
+        assert_eq!(triangles[1].vertices[1].color, [15.0]);
+    }
+
+    #[test]
+    fn coons_patches() {
+        // a patch whose boundary is the square from (0, 0) to (3, 3), and a
+        // second sharing its right-hand edge
+        let mut data = vec![0];
+        data.extend([
+            0, 0, 0, 1, 0, 2, 0, 3, 1, 3, 2, 3, 3, 3, 3, 2, 3, 1, 3, 0, 2, 0, 1, 0,
+        ]);
+        data.extend([0, 10, 20, 30]);
+        data.push(2);
+        data.extend([4, 3, 5, 3, 6, 3, 6, 2, 6, 1, 6, 0, 5, 0, 4, 0]);
+        data.extend([40, 50]);
+
+        let decode = [0.0, 255.0, 0.0, 255.0, 0.0, 255.0];
+
+        let patches = MeshReader::new(&data, 8, 8, 8, &decode, 1)
+            .unwrap()
+            .patches(false)
+            .unwrap();
+
+        assert_eq!(patches.len(), 2);
+
+        // the interior of a patch with straight, evenly divided sides is
+        // evenly divided too
+        assert_eq!(patches[0].points[1][2], Point::new(1.0, 2.0));
+        assert_eq!(patches[0].point_at(0.5, 0.5), Point::new(1.5, 1.5));
+        assert_eq!(patches[0].color_at(0.5, 0.5), color(15.0));
+
+        // the shared edge runs from p33 to p30 of the first patch
+        assert_eq!(
+            patches[1].points[0],
+            [
+                Point::new(3.0, 3.0),
+                Point::new(3.0, 2.0),
+                Point::new(3.0, 1.0),
+                Point::new(3.0, 0.0)
+            ]
+        );
+        assert_eq!(
+            patches[1].colors,
+            [color(20.0), color(30.0), color(40.0), color(50.0)]
+        );
+
+        assert_eq!(patches[0].triangulate(4).len(), 32);
+    }
+}
@@ -2,7 +2,7 @@ use crate::{
     color::ColorSpace,
     data_structures::Rectangle,
     error::PdfResult,
-    function::StreamOrDict,
+    function::{Function, StreamOrDict},
     objects::{Dictionary, Object},
     FromObj, Resolve,
 };
@@ -18,15 +18,67 @@ mod coons_patch_mesh;
 mod freeform;
 mod function_based;
 mod latticeform;
+mod mesh;
 mod radial;
 mod tensor_product_patch_mesh;
 
+pub use mesh::{MeshPatch, ShadingMesh};
+
 #[derive(Debug, Clone)]
 pub struct ShadingObject<'a> {
     base: BaseShadingDictionary<'a>,
     sub_type: SubtypeShadingDictionary<'a>,
 }
 
+impl<'a> ShadingObject<'a> {
+    /// The colour space in which the colours of the shading are expressed
+    pub fn color_space(&self) -> &ColorSpace<'a> {
+        &self.base.color_space
+    }
+
+    pub fn shading_type(&self) -> ShadingType {
+        self.base.shading_type
+    }
+
+    /// The function which maps the parametric values of the shading to the
+    /// components of its colour space, if it has one
+    pub fn function(&self) -> Option<&Function<'a>> {
+        match &self.sub_type {
+            SubtypeShadingDictionary::FunctionBased(shading) => Some(&shading.function),
+            SubtypeShadingDictionary::Axial(shading) => Some(&shading.function),
+            SubtypeShadingDictionary::Radial(shading) => Some(&shading.function),
+            SubtypeShadingDictionary::Freeform(shading) => shading.function.as_ref(),
+            SubtypeShadingDictionary::Latticeform(shading) => shading.function.as_ref(),
+            SubtypeShadingDictionary::CoonsPatchMesh(shading) => shading.function.as_ref(),
+            SubtypeShadingDictionary::TensorProductPatchMesh(shading) => shading.function.as_ref(),
+        }
+    }
+
+    /// The triangles or patches of a mesh shading (types 4 to 7), decoded from
+    /// its data stream, or `None` if it is not a mesh shading
+    pub fn mesh(&self, resolver: &mut dyn Resolve<'a>) -> PdfResult<Option<ShadingMesh>> {
+        let components = self.base.color_space.components();
+
+        Ok(Some(match &self.sub_type {
+            SubtypeShadingDictionary::Freeform(shading) => {
+                ShadingMesh::Triangles(shading.triangles(components, resolver)?)
+            }
+            SubtypeShadingDictionary::Latticeform(shading) => {
+                ShadingMesh::Triangles(shading.triangles(components, resolver)?)
+            }
+            SubtypeShadingDictionary::CoonsPatchMesh(shading) => {
+                ShadingMesh::Patches(shading.patches(components, resolver)?)
+            }
+            SubtypeShadingDictionary::TensorProductPatchMesh(shading) => {
+                ShadingMesh::Patches(shading.patches(components, resolver)?)
+            }
+            SubtypeShadingDictionary::FunctionBased(..)
+            | SubtypeShadingDictionary::Axial(..)
+            | SubtypeShadingDictionary::Radial(..) => return Ok(None),
+        }))
+    }
+}
+
 impl<'a> FromObj<'a> for ShadingObject<'a> {
     fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
         let mut stream_or_dict = StreamOrDict::from_obj(obj, resolver)?;
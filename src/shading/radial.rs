@@ -33,7 +33,7 @@ pub struct RadialShading<'a> {
     /// for a given colour component is out of range, it shall be adjusted to the nearest
     /// valid value.
     #[field("Function")]
-    function: Function<'a>,
+    pub(crate) function: Function<'a>,
 
     /// An array of two boolean values specifying whether to extend the shading beyond the
     /// starting and ending circles, respectively
@@ -1,6 +1,9 @@
-use crate::{filter::flate::BitsPerComponent, function::Function, stream::Stream};
+use crate::{error::PdfResult, filter::decode_stream, function::Function, stream::Stream, Resolve};
 
-use super::freeform::{BitsPerCoordinate, BitsPerFlag};
+use super::{
+    freeform::{BitsPerComponent, BitsPerCoordinate, BitsPerFlag},
+    mesh::{MeshPatch, MeshReader},
+};
 
 /// Type 7 shadings (tensor-product patch meshes) are identical to type 6, except that
 /// they are based on a bicubic tensor-product patch defined by 16 control points instead
@@ -52,8 +55,37 @@ pub struct TensorProductPatchMeshShading<'a> {
     ///
     /// This entry shall not be used with an Indexed colour space
     #[field("Function")]
-    function: Option<Function<'a>>,
+    pub(crate) function: Option<Function<'a>>,
 
     #[field]
     stream: Stream<'a>,
 }
+
+impl<'a> TensorProductPatchMeshShading<'a> {
+    /// The patches of the mesh, whose colours have a value for each of the
+    /// `components` of the shading's colour space, or a single parametric
+    /// value if the shading has a function
+    pub(super) fn patches(
+        &self,
+        components: usize,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Vec<MeshPatch>> {
+        let data = decode_stream(&self.stream.stream, &self.stream.dict, resolver)?;
+
+        let color_values = if self.function.is_some() {
+            1
+        } else {
+            components
+        };
+
+        MeshReader::new(
+            &data,
+            self.bits_per_coordinate as u32,
+            self.bits_per_component as u32,
+            self.bits_per_flag as u32,
+            &self.decode,
+            color_values,
+        )?
+        .patches(true)
+    }
+}
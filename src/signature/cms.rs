@@ -0,0 +1,124 @@
+use ::cms::{
+    cert::CertificateChoices,
+    content_info::ContentInfo,
+    signed_data::{SignerIdentifier, SignerInfo},
+};
+use der::{
+    asn1::ObjectIdentifier, oid::db::rfc5911::ID_SIGNED_DATA, Any, Decode, Encode, SliceReader,
+};
+use x509_cert::attr::Attributes;
+
+use crate::error::PdfResult;
+
+use super::x509::Certificate;
+
+/// A CMS signed-data object, as given by the Contents of a PKCS#7 or CAdES
+/// signature
+#[derive(Debug)]
+pub(super) struct SignedData {
+    /// The object identifier of the type of the signed content
+    pub content_type: ObjectIdentifier,
+
+    /// The content which is signed, if it is encapsulated rather than detached
+    pub encapsulated_content: Option<Vec<u8>>,
+
+    /// The certificates given for building a chain from the signer's
+    /// certificate to a trusted root
+    pub certificates: Vec<Certificate>,
+
+    /// The first signer of the content. Signatures in PDF have a single signer
+    pub signer: SignerInfo,
+}
+
+impl SignedData {
+    /// Parse the ContentInfo which wraps a signed-data object. Bytes after the
+    /// ContentInfo, such as the zeros which pad the Contents of a signature
+    /// dictionary, are ignored
+    pub fn from_der(bytes: &[u8]) -> PdfResult<Self> {
+        let mut reader = SliceReader::new(bytes)?;
+
+        Self::from_content_info(ContentInfo::decode(&mut reader)?)
+    }
+
+    /// Parse the ContentInfo which wraps a signed-data object, given as the
+    /// value of an attribute, such as a timestamp token
+    pub fn from_any(content_info: &Any) -> PdfResult<Self> {
+        Self::from_content_info(content_info.decode_as()?)
+    }
+
+    fn from_content_info(content_info: ContentInfo) -> PdfResult<Self> {
+        anyhow::ensure!(
+            content_info.content_type == ID_SIGNED_DATA,
+            "CMS content is not signed data"
+        );
+
+        let signed_data = content_info
+            .content
+            .decode_as::<::cms::signed_data::SignedData>()?;
+
+        let encapsulated_content = match &signed_data.encap_content_info.econtent {
+            Some(content) => Some(content.decode_as::<der::asn1::OctetString>()?.into_bytes()),
+            None => None,
+        };
+
+        // other certificate formats, such as attribute certificates, are
+        // skipped
+        let certificates = signed_data
+            .certificates
+            .map(|certificates| certificates.0.into_vec())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|choice| match choice {
+                CertificateChoices::Certificate(certificate) => Some(Certificate::new(certificate)),
+                CertificateChoices::Other(..) => None,
+            })
+            .collect();
+
+        // revocation information is read from the document security store
+        // rather than the signed data
+        let signer = match signed_data.signer_infos.0.into_vec().into_iter().next() {
+            Some(signer) => signer,
+            None => anyhow::bail!("CMS signed data has no signers"),
+        };
+
+        Ok(Self {
+            content_type: signed_data.encap_content_info.econtent_type,
+            encapsulated_content,
+            certificates,
+            signer,
+        })
+    }
+
+    /// The certificate of the signer, among those given by the signed data
+    pub fn signer_certificate(&self) -> Option<&Certificate> {
+        self.certificates
+            .iter()
+            .find(|certificate| match &self.signer.sid {
+                SignerIdentifier::IssuerAndSerialNumber(sid) => {
+                    certificate.tbs().issuer == sid.issuer
+                        && certificate.tbs().serial_number == sid.serial_number
+                }
+                SignerIdentifier::SubjectKeyIdentifier(identifier) => {
+                    certificate.subject_key_identifier().as_ref() == Some(identifier)
+                }
+            })
+    }
+}
+
+/// The DER encoding over which the signature of `signer` is calculated when
+/// there are signed attributes: the attributes with the tag of a set, rather
+/// than their implicit tag
+pub(super) fn signed_attributes_der(signer: &SignerInfo) -> PdfResult<Option<Vec<u8>>> {
+    Ok(match &signer.signed_attrs {
+        Some(attributes) => Some(attributes.to_der()?),
+        None => None,
+    })
+}
+
+/// The first value of the attribute identified by `oid` among `attributes`
+pub(super) fn attribute(attributes: Option<&Attributes>, oid: ObjectIdentifier) -> Option<&Any> {
+    attributes?
+        .iter()
+        .find(|attribute| attribute.oid == oid)
+        .and_then(|attribute| attribute.values.iter().next())
+}
@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    date::Date,
+    error::PdfResult,
+    filter::decode_stream,
+    objects::{Dictionary, Object},
+    Resolve,
+};
+
+/// The document security store, which holds the validation data needed to
+/// validate the document's signatures long after they were made, such as
+/// once the certificates of their signers have expired
+///
+/// The certificates, OCSP responses and CRLs of every signature are given by
+/// the store itself, and those used to validate a particular signature by
+/// its entry in `signatures`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocumentSecurityStore {
+    /// DER encoded X.509 certificates
+    pub certificates: Vec<Vec<u8>>,
+
+    /// DER encoded OCSP responses
+    pub ocsp_responses: Vec<Vec<u8>>,
+
+    /// DER encoded certificate revocation lists
+    pub crls: Vec<Vec<u8>>,
+
+    /// The validation data of individual signatures, given by the VRI
+    /// dictionary and keyed by [`Signature::vri_key`]
+    ///
+    /// [`Signature::vri_key`]: crate::Signature::vri_key
+    pub signatures: BTreeMap<String, ValidationData>,
+}
+
+impl DocumentSecurityStore {
+    pub(crate) fn from_obj<'a>(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = resolver.assert_dict(obj)?;
+
+        let certificates = streams(&mut dict, "Certs", resolver)?;
+        let ocsp_responses = streams(&mut dict, "OCSPs", resolver)?;
+        let crls = streams(&mut dict, "CRLs", resolver)?;
+
+        let mut signatures = BTreeMap::new();
+
+        if let Some(vri) = dict.get_dict("VRI", resolver)? {
+            for (key, obj) in vri.entries() {
+                let mut dict = resolver.assert_dict(obj)?;
+
+                // keys are written in upper case, though some writers use
+                // lower case
+                signatures.insert(
+                    key.to_ascii_uppercase(),
+                    ValidationData::from_dict(&mut dict, resolver)?,
+                );
+            }
+        }
+
+        Ok(Self {
+            certificates,
+            ocsp_responses,
+            crls,
+            signatures,
+        })
+    }
+}
+
+/// The validation data of a single signature
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationData {
+    /// DER encoded X.509 certificates
+    pub certificates: Vec<Vec<u8>>,
+
+    /// DER encoded OCSP responses
+    pub ocsp_responses: Vec<Vec<u8>>,
+
+    /// DER encoded certificate revocation lists
+    pub crls: Vec<Vec<u8>>,
+
+    /// The time at which the validation data was gathered
+    pub time: Option<Date>,
+
+    /// An RFC 3161 timestamp token of the validation data
+    pub timestamp: Option<Vec<u8>>,
+}
+
+impl ValidationData {
+    fn from_dict<'a>(dict: &mut Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let certificates = streams(dict, "Cert", resolver)?;
+        let ocsp_responses = streams(dict, "OCSP", resolver)?;
+        let crls = streams(dict, "CRL", resolver)?;
+        let time = dict.get::<Date>("TU", resolver)?;
+
+        let timestamp = match dict.get_stream("TS", resolver)? {
+            Some(stream) => {
+                Some(decode_stream(&stream.stream, &stream.dict, resolver)?.into_owned())
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            certificates,
+            ocsp_responses,
+            crls,
+            time,
+            timestamp,
+        })
+    }
+}
+
+/// The decoded data of each stream of the array `key`
+fn streams<'a>(
+    dict: &mut Dictionary<'a>,
+    key: &str,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Vec<Vec<u8>>> {
+    let arr = match dict.get_arr(key, resolver)? {
+        Some(arr) => arr,
+        None => return Ok(Vec::new()),
+    };
+
+    arr.into_iter()
+        .map(|obj| {
+            let stream = resolver.assert_stream(obj)?;
+            Ok(decode_stream(&stream.stream, &stream.dict, resolver)?.into_owned())
+        })
+        .collect()
+}
@@ -0,0 +1,407 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    error::PdfResult,
+    objects::{Object, Reference, TypedReference},
+    xref::XrefEntry,
+    ParseOptions, Parser, Resolve,
+};
+
+use super::{
+    verify::{revision_ends, trim_end},
+    Signature,
+};
+
+/// A change made by an incremental update to a certified document which the
+/// DocMDP permissions of its certification signature do not allow
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisallowedChange {
+    /// The revision which made the change, counting the original document as
+    /// 0 and each incremental update after it
+    pub revision: usize,
+
+    pub reference: Reference,
+    pub change: Change,
+    pub object: ChangedObject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Modified,
+    Removed,
+}
+
+/// The kind of an object changed by an incremental update, which determines
+/// the permissions under which the change is allowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangedObject {
+    Catalog,
+
+    /// The document information dictionary
+    Information,
+
+    /// A page or page tree node
+    Page,
+
+    /// The interactive form dictionary
+    AcroForm,
+
+    /// A form field or widget annotation
+    Field,
+
+    /// An annotation other than a widget annotation
+    Annotation,
+
+    Signature,
+    DocumentTimestamp,
+
+    /// The document security store or its VRI dictionary
+    SecurityStore,
+
+    Other,
+}
+
+/// Compare each revision made after the document was certified by
+/// `certification` with the revision before it, and find the changes which
+/// its permissions do not allow
+///
+/// As in PAdES, document timestamps and validation data added to the document
+/// security store are allowed whatever the permissions. Each revision is parsed
+/// anew, so documents which need a password to be opened cannot be checked.
+pub(crate) fn check_modifications(
+    certification: &Signature,
+    file: &[u8],
+) -> PdfResult<Vec<DisallowedChange>> {
+    let permissions = match certification.certification_permissions() {
+        Some(permissions) => permissions,
+        None => anyhow::bail!("signature is not a certification signature"),
+    };
+
+    let signed_end = certification
+        .byte_range
+        .last()
+        .map_or(0, |&(offset, len)| offset + len);
+
+    let ends = revision_ends(file);
+
+    let certified = match ends
+        .iter()
+        .position(|&end| trim_end(file, end) == trim_end(file, signed_end))
+    {
+        Some(certified) => certified,
+        None => anyhow::bail!("certification signature does not cover a whole revision"),
+    };
+
+    let mut changes = Vec::new();
+    let mut previous =
+        Parser::from_bytes(file[..ends[certified]].to_vec(), ParseOptions::default())?;
+
+    for (revision, &end) in ends.iter().enumerate().skip(certified + 1) {
+        let mut current = Parser::from_bytes(file[..end].to_vec(), ParseOptions::default())?;
+
+        changes.extend(compare_revisions(
+            &mut previous,
+            &mut current,
+            permissions,
+            revision,
+        )?);
+
+        previous = current;
+    }
+
+    Ok(changes)
+}
+
+/// The changes made by `current` to the revision before it, `previous`, which
+/// are not allowed by `permissions`
+fn compare_revisions<'a>(
+    previous: &mut Parser<'a>,
+    current: &mut Parser<'a>,
+    permissions: u8,
+    revision: usize,
+) -> PdfResult<Vec<DisallowedChange>> {
+    let object_numbers = previous
+        .xref
+        .objects
+        .keys()
+        .chain(current.xref.objects.keys())
+        .copied()
+        .collect::<BTreeSet<usize>>();
+
+    let info = match current.trailer.info {
+        Some(TypedReference::Indirect { reference, .. }) => Some(reference),
+        _ => None,
+    };
+
+    let mut changes = Vec::new();
+
+    for object_number in object_numbers {
+        let old_entry = previous.xref.objects.get(&object_number);
+        let new_entry = current.xref.objects.get(&object_number);
+
+        let (reference, change) = match (
+            in_use(object_number, old_entry),
+            in_use(object_number, new_entry),
+        ) {
+            (None, None) => continue,
+            (Some(reference), None) => (reference, Change::Removed),
+            (None, Some(reference)) => (reference, Change::Added),
+            (Some(_), Some(reference)) => {
+                if old_entry == new_entry {
+                    continue;
+                }
+
+                (reference, Change::Modified)
+            }
+        };
+
+        let old = match change {
+            Change::Added => None,
+            Change::Modified | Change::Removed => {
+                Some(previous.lexer.resolve(Object::Reference(reference))?)
+            }
+        };
+
+        let new = match change {
+            Change::Removed => None,
+            Change::Added | Change::Modified => {
+                Some(current.lexer.resolve(Object::Reference(reference))?)
+            }
+        };
+
+        // objects written again without changes, such as when an update packs
+        // them into a new object stream
+        if old.is_some() && old == new {
+            continue;
+        }
+
+        let object = match new.as_ref().or(old.as_ref()) {
+            Some(obj) if is_cross_reference_data(obj) => continue,
+            Some(_) if Some(reference) == info => ChangedObject::Information,
+            Some(obj) => classify(obj),
+            None => continue,
+        };
+
+        let allowed = match (object, change) {
+            (ChangedObject::SecurityStore, _)
+            | (ChangedObject::DocumentTimestamp, Change::Added)
+            | (ChangedObject::Information, Change::Added | Change::Modified) => true,
+
+            // new objects such as appearance streams and fonts only change the
+            // document through the objects which refer to them
+            (ChangedObject::Other, Change::Added) => true,
+
+            (ChangedObject::Signature, Change::Added)
+            | (ChangedObject::AcroForm, Change::Added | Change::Modified)
+            | (ChangedObject::Field, Change::Added | Change::Modified) => permissions >= 2,
+            (ChangedObject::Annotation, _) => permissions >= 3,
+            (ChangedObject::Catalog, Change::Modified) => changed_keys(&old, &new)
+                .into_iter()
+                .all(|key| key == "DSS" || (key == "AcroForm" && permissions >= 2)),
+            (ChangedObject::Page, Change::Modified) => {
+                let only_annotations = changed_keys(&old, &new)
+                    .into_iter()
+                    .all(|key| key == "Annots");
+
+                // annotations added to the page are checked as new objects,
+                // but those removed from it are not necessarily deleted
+                if only_annotations && permissions == 2 {
+                    for reference in removed_annotations(&old, &new, previous, current)? {
+                        changes.push(DisallowedChange {
+                            revision,
+                            reference,
+                            change: Change::Removed,
+                            object: ChangedObject::Annotation,
+                        });
+                    }
+                }
+
+                only_annotations && permissions >= 2
+            }
+            _ => false,
+        };
+
+        if !allowed {
+            changes.push(DisallowedChange {
+                revision,
+                reference,
+                change,
+                object,
+            });
+        }
+    }
+
+    Ok(changes)
+}
+
+/// The reference to an object if its cross-reference entry is in use
+fn in_use(object_number: usize, entry: Option<&XrefEntry>) -> Option<Reference> {
+    match entry? {
+        XrefEntry::InUse {
+            generation_number, ..
+        } => Some(Reference {
+            object_number,
+            generation: usize::from(*generation_number),
+        }),
+        XrefEntry::Compressed { .. } => Some(Reference {
+            object_number,
+            generation: 0,
+        }),
+        XrefEntry::Free { .. } | XrefEntry::Null => None,
+    }
+}
+
+/// Whether `obj` is a cross-reference or object stream, which every update
+/// writes in place of a cross-reference table
+fn is_cross_reference_data(obj: &Object) -> bool {
+    match obj {
+        Object::Stream(stream) => stream
+            .dict
+            .other
+            .peek("Type")
+            .map_or(false, |ty| ty.name_is("XRef") || ty.name_is("ObjStm")),
+        _ => false,
+    }
+}
+
+fn classify(obj: &Object) -> ChangedObject {
+    let dict = match obj {
+        Object::Dictionary(dict) => dict,
+        _ => return ChangedObject::Other,
+    };
+
+    let is = |key: &str, name: &str| dict.peek(key).map_or(false, |obj| obj.name_is(name));
+    let has = |key: &str| dict.peek(key).is_some();
+
+    if is("Type", "Catalog") {
+        ChangedObject::Catalog
+    } else if is("Type", "Page") || is("Type", "Pages") {
+        ChangedObject::Page
+    } else if is("Type", "Sig") {
+        ChangedObject::Signature
+    } else if is("Type", "DocTimeStamp") {
+        ChangedObject::DocumentTimestamp
+    } else if is("Type", "DSS") || has("VRI") || has("Certs") || has("OCSPs") || has("CRLs") {
+        ChangedObject::SecurityStore
+    } else if is("Subtype", "Widget") || has("FT") || has("T") {
+        ChangedObject::Field
+    } else if has("Subtype") && has("Rect") {
+        ChangedObject::Annotation
+    } else if has("Fields") {
+        ChangedObject::AcroForm
+    } else if has("Parent") && has("Kids") {
+        // a non-terminal field, which need not have a name of its own
+        ChangedObject::Field
+    } else {
+        ChangedObject::Other
+    }
+}
+
+/// The keys whose values differ between two versions of a dictionary
+fn changed_keys<'b>(old: &'b Option<Object>, new: &'b Option<Object>) -> Vec<&'b str> {
+    let (old, new) = match (old, new) {
+        (Some(Object::Dictionary(old)), Some(Object::Dictionary(new))) => (old, new),
+        _ => return Vec::new(),
+    };
+
+    let mut keys = old.keys();
+    keys.extend(new.keys());
+    keys.sort_unstable();
+    keys.dedup();
+
+    keys.retain(|key| old.peek(key) != new.peek(key));
+
+    keys
+}
+
+/// The annotations of the old version of a page which are not among those of
+/// its new version
+fn removed_annotations<'a>(
+    old: &Option<Object<'a>>,
+    new: &Option<Object<'a>>,
+    previous: &mut Parser<'a>,
+    current: &mut Parser<'a>,
+) -> PdfResult<Vec<Reference>> {
+    let old = annotations(old, previous)?;
+    let new = annotations(new, current)?;
+
+    Ok(old
+        .into_iter()
+        .filter(|annot| !new.contains(annot))
+        .collect())
+}
+
+fn annotations<'a>(
+    page: &Option<Object<'a>>,
+    parser: &mut Parser<'a>,
+) -> PdfResult<Vec<Reference>> {
+    let annots = match page {
+        Some(Object::Dictionary(page)) => page.peek("Annots").cloned(),
+        _ => None,
+    };
+
+    let annots = match annots {
+        Some(annots) => parser.lexer.assert_arr(annots)?,
+        None => return Ok(Vec::new()),
+    };
+
+    Ok(annots
+        .into_iter()
+        .filter_map(|annot| match annot {
+            Object::Reference(reference) => Some(reference),
+            _ => None,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::objects::Dictionary;
+
+    fn dict(entries: &[(&str, &str)]) -> Object<'static> {
+        Object::Dictionary(Dictionary::new(
+            entries
+                .iter()
+                .map(|&(key, name)| (key.to_owned(), Object::Name(name.to_owned())))
+                .collect::<HashMap<String, Object>>(),
+        ))
+    }
+
+    #[test]
+    fn classify_changed_objects() {
+        assert_eq!(
+            classify(&dict(&[
+                ("Type", "Annot"),
+                ("Subtype", "Widget"),
+                ("Rect", "")
+            ])),
+            ChangedObject::Field
+        );
+        assert_eq!(
+            classify(&dict(&[
+                ("Type", "Annot"),
+                ("Subtype", "Text"),
+                ("Rect", "")
+            ])),
+            ChangedObject::Annotation
+        );
+        assert_eq!(
+            classify(&dict(&[("VRI", "")])),
+            ChangedObject::SecurityStore
+        );
+        assert_eq!(classify(&dict(&[("Type", "Page")])), ChangedObject::Page);
+        assert_eq!(classify(&dict(&[])), ChangedObject::Other);
+    }
+
+    #[test]
+    fn find_changed_keys() {
+        let old = Some(dict(&[("Type", "Catalog"), ("PageMode", "UseNone")]));
+        let new = Some(dict(&[("Type", "Catalog"), ("DSS", "")]));
+
+        assert_eq!(changed_keys(&old, &new), vec!["DSS", "PageMode"]);
+    }
+}
@@ -0,0 +1,514 @@
+use der::{AnyRef, Decode, Reader, SliceReader};
+
+use crate::{
+    acro_form::collect_fields,
+    acro_form::FieldValue,
+    date::Date,
+    error::PdfResult,
+    objects::{decode_text_string, Dictionary, Object, Reference},
+    signature, writer, Parser, Resolve,
+};
+
+pub use dss::{DocumentSecurityStore, ValidationData};
+pub(crate) use mdp::check_modifications;
+pub use mdp::{Change, ChangedObject, DisallowedChange};
+pub use timestamp::Timestamp;
+pub(crate) use verify::{trust_roots, verify};
+pub use verify::{SignatureProblem, SignatureVerification};
+
+mod cms;
+mod digest;
+mod dss;
+mod mdp;
+mod timestamp;
+mod verify;
+mod x509;
+
+/// A digital signature, given by the signature dictionary which is the value
+/// of a signature field
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    /// The fully qualified name of the signature field
+    pub field_name: String,
+
+    /// The signature field
+    pub field: Reference,
+
+    /// The signature dictionary
+    pub reference: Reference,
+
+    /// Whether the dictionary is a document timestamp dictionary, whose
+    /// Contents are an RFC 3161 timestamp token rather than a signature
+    pub is_doc_timestamp: bool,
+
+    /// The name of the preferred signature handler to use when validating
+    /// this signature, such as `Adobe.PPKLite`
+    pub filter: String,
+
+    /// The encoding of the signature value and key information
+    pub sub_filter: Option<SignatureSubFilter>,
+
+    /// The signature value. For public-key signatures, this is either a DER
+    /// encoded PKCS#1 binary data object or a DER encoded CMS object
+    pub contents: Vec<u8>,
+
+    /// The X.509 certificates used when signing and verifying signatures that
+    /// use public-key cryptography, beginning with the signing certificate
+    ///
+    /// Only used with the `adbe.x509.rsa_sha1` encoding.
+    pub certificates: Vec<Vec<u8>>,
+
+    /// The byte ranges of the file covered by the digest, as pairs of a
+    /// starting offset and a length
+    pub byte_range: Vec<(usize, usize)>,
+
+    /// The signature reference dictionaries, which describe the object
+    /// digests and modification detection parameters of the signature
+    pub references: Vec<SignatureReference>,
+
+    /// The name of the person or authority signing the document
+    pub name: Option<String>,
+
+    /// The time of signing, which may instead be given by a timestamp within
+    /// the signature value
+    pub signing_time: Option<Date>,
+
+    /// The CPU host name or physical location of the signing
+    pub location: Option<String>,
+
+    /// The reason for the signing, such as "I agree…"
+    pub reason: Option<String>,
+
+    /// Information provided by the signer to enable a recipient to contact
+    /// the signer to verify the signature
+    pub contact_info: Option<String>,
+}
+
+impl Signature {
+    pub(crate) fn from_dict<'a>(
+        field_name: String,
+        field: Reference,
+        reference: Reference,
+        mut dict: Dictionary<'a>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let is_doc_timestamp = dict
+            .get_name("Type", resolver)?
+            .map_or(false, |ty| ty == "DocTimeStamp");
+
+        let filter = dict.expect_name("Filter", resolver)?;
+        let sub_filter = dict
+            .get_name("SubFilter", resolver)?
+            .map(SignatureSubFilter::from_name);
+
+        let contents = bytes(dict.expect_string("Contents", resolver)?);
+
+        let certificates = match dict.get_object("Cert", resolver)? {
+            Some(Object::Array(certs)) => certs
+                .into_iter()
+                .map(|cert| Ok(bytes(resolver.assert_string(cert)?)))
+                .collect::<PdfResult<Vec<Vec<u8>>>>()?,
+            Some(cert) => vec![bytes(resolver.assert_string(cert)?)],
+            None => Vec::new(),
+        };
+
+        let byte_range = match dict.get_arr("ByteRange", resolver)? {
+            Some(byte_range) => {
+                anyhow::ensure!(
+                    byte_range.len() % 2 == 0,
+                    "signature byte range has an odd number of entries"
+                );
+
+                let offsets = byte_range
+                    .into_iter()
+                    .map(|obj| Ok(resolver.assert_unsigned_integer(obj)? as usize))
+                    .collect::<PdfResult<Vec<usize>>>()?;
+
+                offsets
+                    .chunks_exact(2)
+                    .map(|range| (range[0], range[1]))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        let references = match dict.get_arr("Reference", resolver)? {
+            Some(references) => references
+                .into_iter()
+                .map(|obj| {
+                    let dict = resolver.assert_dict(obj)?;
+                    SignatureReference::from_dict(dict, resolver)
+                })
+                .collect::<PdfResult<Vec<SignatureReference>>>()?,
+            None => Vec::new(),
+        };
+
+        let name = dict.get_string("Name", resolver)?;
+        let signing_time = dict.get::<Date>("M", resolver)?;
+        let location = dict.get_string("Location", resolver)?;
+        let reason = dict.get_string("Reason", resolver)?;
+        let contact_info = dict.get_string("ContactInfo", resolver)?;
+
+        Ok(Self {
+            field_name,
+            field,
+            reference,
+            is_doc_timestamp,
+            filter,
+            sub_filter,
+            contents,
+            certificates,
+            byte_range,
+            references,
+            name: name.as_deref().map(decode_text_string),
+            signing_time,
+            location: location.as_deref().map(decode_text_string),
+            reason: reason.as_deref().map(decode_text_string),
+            contact_info: contact_info.as_deref().map(decode_text_string),
+        })
+    }
+
+    /// The DocMDP permissions of the signature, if it is a certification
+    /// signature
+    pub fn certification_permissions(&self) -> Option<u8> {
+        self.references
+            .iter()
+            .find_map(|reference| match reference.transform_method {
+                TransformMethod::DocMdp { permissions } => Some(permissions),
+                _ => None,
+            })
+    }
+
+    /// The key of the signature's validation data in the VRI dictionary of
+    /// the document security store: the upper case hexadecimal SHA-1 digest
+    /// of its Contents
+    ///
+    /// The digest of a document timestamp is of its timestamp token, without
+    /// the zeros which pad the Contents.
+    pub fn vri_key(&self) -> String {
+        let contents = match self.sub_filter {
+            Some(SignatureSubFilter::Rfc3161) => {
+                token_der(&self.contents).unwrap_or(&self.contents)
+            }
+            _ => &self.contents,
+        };
+
+        digest::DigestAlgorithm::Sha1
+            .digest(&[contents])
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect()
+    }
+}
+
+/// The encoding of the signature value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureSubFilter {
+    /// `adbe.x509.rsa_sha1`: a PKCS#1 signature of a SHA-1 digest, with the
+    /// certificates given by the Cert entry
+    X509RsaSha1,
+
+    /// `adbe.pkcs7.sha1`: a CMS signed-data object which encapsulates the
+    /// SHA-1 digest of the byte range
+    Pkcs7Sha1,
+
+    /// `adbe.pkcs7.detached`: a detached CMS signed-data object
+    Pkcs7Detached,
+
+    /// `ETSI.CAdES.detached`: a detached CMS signed-data object which
+    /// conforms to CAdES
+    CadesDetached,
+
+    /// `ETSI.RFC3161`: an RFC 3161 timestamp token, used by document
+    /// timestamps
+    Rfc3161,
+
+    Other(String),
+}
+
+impl SignatureSubFilter {
+    pub fn from_name(s: String) -> Self {
+        match s.as_ref() {
+            "adbe.x509.rsa_sha1" => Self::X509RsaSha1,
+            "adbe.pkcs7.sha1" => Self::Pkcs7Sha1,
+            "adbe.pkcs7.detached" => Self::Pkcs7Detached,
+            "ETSI.CAdES.detached" => Self::CadesDetached,
+            "ETSI.RFC3161" => Self::Rfc3161,
+            _ => Self::Other(s),
+        }
+    }
+}
+
+/// A signature reference dictionary, which describes a transform applied to
+/// the document before it is digested
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureReference {
+    pub transform_method: TransformMethod,
+
+    /// The algorithm used to calculate the digest of the transformed
+    /// document, such as `SHA256`
+    pub digest_method: Option<String>,
+}
+
+impl SignatureReference {
+    fn from_dict<'a>(mut dict: Dictionary<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let method = dict.expect_name("TransformMethod", resolver)?;
+        let mut params = dict
+            .get_dict("TransformParams", resolver)?
+            .unwrap_or_else(Dictionary::empty);
+
+        let transform_method = match method.as_ref() {
+            "DocMDP" => TransformMethod::DocMdp {
+                permissions: match params.get_unsigned_integer("P", resolver)? {
+                    Some(p @ 1..=3) => p as u8,
+                    Some(p) => anyhow::bail!("invalid DocMDP permissions: {}", p),
+                    None => 2,
+                },
+            },
+            "FieldMDP" => {
+                let action = params.expect_name("Action", resolver)?;
+                let fields = match params.get_arr("Fields", resolver)? {
+                    Some(fields) => fields
+                        .into_iter()
+                        .map(|obj| Ok(decode_text_string(&resolver.assert_string(obj)?)))
+                        .collect::<PdfResult<Vec<String>>>()?,
+                    None => Vec::new(),
+                };
+
+                TransformMethod::FieldMdp {
+                    action: match action.as_ref() {
+                        "All" => FieldMdpAction::All,
+                        "Include" => FieldMdpAction::Include(fields),
+                        "Exclude" => FieldMdpAction::Exclude(fields),
+                        _ => anyhow::bail!("invalid FieldMDP action: {}", action),
+                    },
+                }
+            }
+            "UR" | "UR3" => TransformMethod::UsageRights,
+            "Identity" => TransformMethod::Identity,
+            _ => TransformMethod::Other(method),
+        };
+
+        let digest_method = dict.get_name("DigestMethod", resolver)?;
+
+        Ok(Self {
+            transform_method,
+            digest_method,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransformMethod {
+    /// Detects modifications to the document which are not permitted by the
+    /// author of a certification signature
+    DocMdp {
+        /// The changes permitted to the document:
+        ///
+        /// 1. No changes
+        /// 2. Filling in forms, instantiating page templates and signing
+        /// 3. As 2, as well as annotation creation, deletion and modification
+        permissions: u8,
+    },
+
+    /// Detects modifications to form fields which invalidate the signature
+    FieldMdp {
+        action: FieldMdpAction,
+    },
+
+    /// Enables additional interactive features in conforming readers
+    UsageRights,
+
+    /// Detects modifications to the data given by the Data entry
+    Identity,
+
+    Other(String),
+}
+
+/// The form fields whose modification invalidates a signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldMdpAction {
+    /// All form fields
+    All,
+
+    /// Only the fields with these fully qualified names
+    Include(Vec<String>),
+
+    /// All but the fields with these fully qualified names
+    Exclude(Vec<String>),
+}
+
+/// The bytes of a string object, which holds one byte per character
+fn bytes(s: String) -> Vec<u8> {
+    s.chars().map(|c| c as u8).collect()
+}
+
+/// The DER encoded element at the start of `contents`, without the zeros
+/// which pad it
+fn token_der(contents: &[u8]) -> Option<&[u8]> {
+    let mut reader = SliceReader::new(contents).ok()?;
+    AnyRef::decode(&mut reader).ok()?;
+
+    let len = usize::try_from(reader.position()).ok()?;
+
+    Some(&contents[..len])
+}
+
+impl<'a> Parser<'a> {
+    /// Every signed signature field of the document's interactive form, with
+    /// its signature dictionary
+    ///
+    /// Unsigned signature fields have no signature dictionary, and are skipped.
+    pub fn signatures(&mut self) -> PdfResult<Vec<Signature>> {
+        let acro_form = match &self.catalog.acro_form {
+            Some(acro_form) => acro_form.get_ref(&mut self.lexer)?.into_owned(),
+            None => return Ok(Vec::new()),
+        };
+
+        let mut signatures = Vec::new();
+
+        for field in collect_fields(&acro_form.fields, &mut self.lexer)? {
+            let reference = match field.value {
+                Some(FieldValue::Signature(reference)) => reference,
+                _ => continue,
+            };
+
+            let dict = self.lexer.assert_dict(Object::Reference(reference))?;
+
+            signatures.push(Signature::from_dict(
+                field.name,
+                field.reference,
+                reference,
+                dict,
+                &mut self.lexer,
+            )?);
+        }
+
+        Ok(signatures)
+    }
+
+    /// Verify every signature of the document against the bytes it signs
+    ///
+    /// `trust_roots` are the DER encoded certificates trusted to issue,
+    /// directly or through intermediate certificates given by a signature,
+    /// the certificates of signers.
+    pub fn verify_signatures(
+        &mut self,
+        trust_roots: &[impl AsRef<[u8]>],
+    ) -> PdfResult<Vec<SignatureVerification>> {
+        let trust_roots = signature::trust_roots(trust_roots)?;
+
+        Ok(self
+            .signatures()?
+            .into_iter()
+            .map(|sig| signature::verify(sig, &self.lexer.file, &trust_roots))
+            .collect())
+    }
+
+    /// The document security store, which holds the certificates, OCSP
+    /// responses and CRLs needed for long-term validation of the document's
+    /// signatures
+    pub fn document_security_store(&mut self) -> PdfResult<Option<DocumentSecurityStore>> {
+        match self.catalog.dss.clone() {
+            Some(dss) => Ok(Some(DocumentSecurityStore::from_obj(dss, &mut self.lexer)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Add validation data for `signature` to the document security store,
+    /// returning the file with an incremental update appended
+    ///
+    /// The original bytes of the file are unchanged, so that existing
+    /// signatures remain valid.
+    pub fn add_validation_data(
+        &mut self,
+        signature: &Signature,
+        data: &ValidationData,
+    ) -> PdfResult<Vec<u8>> {
+        writer::add_validation_data(
+            &self.trailer,
+            self.catalog.dss.clone(),
+            signature.vri_key(),
+            data,
+            &mut self.lexer,
+        )
+    }
+
+    /// The certification signature of the document, if it is certified
+    ///
+    /// Its [`Signature::certification_permissions`] are the changes which may
+    /// be made to the document after it was certified: 1 for none, 2 for
+    /// filling in forms and signing, and 3 for annotating as well.
+    pub fn certification(&mut self) -> PdfResult<Option<Signature>> {
+        let doc_mdp = self.catalog.perms.as_ref().and_then(|perms| perms.doc_mdp);
+
+        Ok(self.signatures()?.into_iter().find(|sig| {
+            sig.certification_permissions().is_some()
+                && doc_mdp.map_or(true, |doc_mdp| doc_mdp == sig.reference)
+        }))
+    }
+
+    /// Find the changes made by incremental updates after the document was
+    /// certified which its certification does not permit
+    ///
+    /// Documents which are not certified have no disallowed changes.
+    pub fn check_certified_changes(&mut self) -> PdfResult<Vec<DisallowedChange>> {
+        match self.certification()? {
+            Some(certification) => signature::check_modifications(&certification, &self.lexer.file),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{objects::ToObj, version::PdfVersion, writer::PdfWriter};
+
+    #[test]
+    fn parse_certification_signature() {
+        let mut resolver = PdfWriter::new(PdfVersion::V1_7);
+
+        let mut params = Dictionary::empty();
+        params.insert("P", Object::Integer(1));
+
+        let mut reference = Dictionary::empty();
+        reference.insert("TransformMethod", Object::Name("DocMDP".to_owned()));
+        reference.insert("TransformParams", Object::Dictionary(params));
+
+        let mut dict = Dictionary::empty();
+        dict.insert("Type", Object::Name("Sig".to_owned()));
+        dict.insert("Filter", Object::Name("Adobe.PPKLite".to_owned()));
+        dict.insert("SubFilter", Object::Name("adbe.pkcs7.detached".to_owned()));
+        dict.insert("Contents", Object::String("\u{30}\u{82}".to_owned()));
+        dict.insert("ByteRange", [0, 100, 200, 50].to_obj().unwrap());
+        dict.insert(
+            "Reference",
+            Object::Array(vec![Object::Dictionary(reference)]),
+        );
+        dict.insert("M", Object::String("D:20240102030405Z".to_owned()));
+        dict.insert("Reason", Object::String("Approval".to_owned()));
+
+        let field = Reference {
+            object_number: 1,
+            generation: 0,
+        };
+        let sig = Reference {
+            object_number: 2,
+            generation: 0,
+        };
+
+        let signature =
+            Signature::from_dict("Sig1".to_owned(), field, sig, dict, &mut resolver).unwrap();
+
+        assert_eq!(
+            signature.sub_filter,
+            Some(SignatureSubFilter::Pkcs7Detached)
+        );
+        assert_eq!(signature.contents, vec![0x30, 0x82]);
+        assert_eq!(signature.byte_range, vec![(0, 100), (200, 50)]);
+        assert_eq!(signature.certification_permissions(), Some(1));
+        assert_eq!(signature.reason.as_deref(), Some("Approval"));
+        assert_eq!(signature.signing_time.unwrap().year, Some(2024));
+    }
+}
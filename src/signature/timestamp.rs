@@ -0,0 +1,146 @@
+use der::{
+    asn1::{GeneralizedTime, ObjectIdentifier, OctetString, UintRef},
+    AnyRef, DateTime, Decode, Reader, SliceReader, Tag, Tagged,
+};
+use x509_cert::spki::AlgorithmIdentifierOwned;
+
+use crate::{
+    date::{Date, UtRelationship},
+    error::PdfResult,
+};
+
+use super::{cms::SignedData, digest::DigestAlgorithm};
+
+const TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+
+/// The unsigned attribute of a CMS signer which holds a timestamp token of
+/// its signature value
+pub(super) const SIGNATURE_TIMESTAMP_TOKEN: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.2.14");
+
+/// An RFC 3161 timestamp, with which a time-stamping authority attests that
+/// data existed at a given time
+///
+/// Timestamps are given by document timestamp dictionaries, whose Contents
+/// are a timestamp token of the byte range, and by the signatures whose CMS
+/// signer carries a timestamp token of its signature value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timestamp {
+    /// The time at which the timestamp was created
+    pub time: Date,
+
+    /// The object identifier of the authority's policy under which the
+    /// timestamp was created
+    pub policy: String,
+
+    pub serial_number: Vec<u8>,
+
+    /// The common name of the authority's certificate
+    pub authority: Option<String>,
+
+    /// The digest of the timestamped data
+    pub message_imprint: Vec<u8>,
+
+    /// The algorithm with which `message_imprint` is calculated
+    pub(crate) digest_algorithm: DigestAlgorithm,
+}
+
+impl Timestamp {
+    /// Parse the TSTInfo encapsulated by the signed data of a timestamp
+    /// token
+    pub(super) fn from_signed_data(token: &SignedData) -> PdfResult<Self> {
+        let tst_info = match &token.encapsulated_content {
+            Some(tst_info) if token.content_type == TST_INFO => tst_info,
+            _ => anyhow::bail!("timestamp token does not contain timestamp info"),
+        };
+
+        let mut reader = SliceReader::new(tst_info)?;
+
+        let (policy, algorithm, message_imprint, serial_number, time) =
+            reader.sequence(|fields| {
+                u8::decode(fields)?;
+                let policy = ObjectIdentifier::decode(fields)?;
+
+                let (algorithm, message_imprint) = fields.sequence(|message_imprint| {
+                    Ok((
+                        AlgorithmIdentifierOwned::decode(message_imprint)?,
+                        OctetString::decode(message_imprint)?,
+                    ))
+                })?;
+
+                let serial_number = UintRef::decode(fields)?.as_bytes().to_vec();
+                let time = AnyRef::decode(fields)?;
+
+                // the accuracy, ordering, nonce, authority name and
+                // extensions which may follow are not needed
+                let rest = fields.remaining_len();
+                fields.read_slice(rest)?;
+
+                Ok((policy, algorithm, message_imprint, serial_number, time))
+            })?;
+
+        let digest_algorithm = match DigestAlgorithm::from_oid(&algorithm.oid) {
+            Some(digest_algorithm) => digest_algorithm,
+            None => anyhow::bail!("unsupported timestamp digest algorithm {}", algorithm.oid),
+        };
+
+        let authority = token
+            .signer_certificate()
+            .and_then(|certificate| certificate.common_name());
+
+        Ok(Self {
+            time: date(generalized_time(time)?),
+            policy: policy.to_string(),
+            serial_number,
+            authority,
+            message_imprint: message_imprint.into_bytes(),
+            digest_algorithm,
+        })
+    }
+
+    /// Whether the timestamp was created for the concatenation of `parts`
+    pub(super) fn is_for(&self, parts: &[&[u8]]) -> bool {
+        self.digest_algorithm.digest(parts) == self.message_imprint
+    }
+}
+
+/// The time of a timestamp, which unlike the times of certificates may give
+/// fractions of a second. These are ignored
+fn generalized_time(time: AnyRef) -> PdfResult<DateTime> {
+    let value = time.value();
+
+    let is_fraction = |fraction: &[u8]| match fraction.split_first() {
+        Some((b'.', digits)) => digits.iter().all(u8::is_ascii_digit),
+        _ => fraction.is_empty(),
+    };
+
+    match value.split_last() {
+        Some((b'Z', rest))
+            if time.tag() == Tag::GeneralizedTime
+                && rest.len() >= 14
+                && is_fraction(&rest[14..]) =>
+        {
+            let mut der = vec![Tag::GeneralizedTime.into(), 15];
+            der.extend_from_slice(&rest[..14]);
+            der.push(b'Z');
+
+            Ok(GeneralizedTime::from_der(&der)?.to_date_time())
+        }
+        _ => anyhow::bail!("invalid timestamp time"),
+    }
+}
+
+/// The date of a time given in UTC
+fn date(time: DateTime) -> Date {
+    Date {
+        year: Some(time.year()),
+        month: Some(u16::from(time.month())),
+        day: Some(u16::from(time.day())),
+        hour: Some(u16::from(time.hour())),
+        minute: Some(u16::from(time.minutes())),
+        second: Some(u16::from(time.seconds())),
+        ut_relationship: Some(UtRelationship::Equal),
+        ut_hour_offset: None,
+        ut_minute_offset: None,
+    }
+}
@@ -0,0 +1,866 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ::cms::signed_data::SignerInfo;
+use der::{
+    asn1::OctetString,
+    oid::db::rfc5911::{ID_MESSAGE_DIGEST, ID_SIGNING_TIME},
+    Decode, Encode,
+};
+use x509_cert::time::Time;
+
+use crate::{
+    date::{Date, UtRelationship},
+    error::PdfResult,
+};
+
+use super::{
+    cms::{attribute, signed_attributes_der, SignedData},
+    digest::DigestAlgorithm,
+    timestamp::{Timestamp, SIGNATURE_TIMESTAMP_TOKEN},
+    x509::{is_supported_signature, Certificate},
+    Signature, SignatureSubFilter,
+};
+
+/// The longest chain of intermediate certificates followed from a signer's
+/// certificate to a trusted root
+const MAX_CHAIN_LENGTH: usize = 16;
+
+/// The outcome of verifying a signature against the document it signs
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureVerification {
+    pub signature: Signature,
+
+    /// The common name of the signer's certificate
+    pub signer: Option<String>,
+
+    /// The timestamp of the signature, or of the document for a document
+    /// timestamp, which is only given if it is valid
+    pub timestamp: Option<Timestamp>,
+
+    /// The revision of the document covered by the signature, counting the
+    /// original document as 0 and each incremental update after it
+    pub revision: Option<usize>,
+
+    /// Whether the document was updated after it was signed. Such updates,
+    /// such as additional signatures, do not invalidate the signature, but
+    /// what they change is not covered by it
+    pub modified_after_signing: bool,
+
+    /// The reasons the signature is not valid, if any
+    pub problems: Vec<SignatureProblem>,
+}
+
+impl SignatureVerification {
+    /// Whether the signature is intact and made with a trusted certificate
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureProblem {
+    /// The byte range does not cover the whole of a revision of the document
+    /// apart from the signature value
+    InvalidByteRange,
+
+    /// The signature value is encoded in a way which is not verified, such as
+    /// `adbe.x509.rsa_sha1`
+    UnsupportedSubFilter,
+
+    /// The signature value could not be parsed
+    Malformed(String),
+
+    /// The signature is made with an algorithm which is not supported, given
+    /// by its object identifier
+    UnsupportedAlgorithm(String),
+
+    /// The signature value does not include the certificate of the signer
+    MissingSignerCertificate,
+
+    /// The signed digest does not match the digest of the byte range, so that
+    /// the document was modified after it was signed
+    DigestMismatch,
+
+    /// The signature was not made with the key of the signer's certificate
+    InvalidSignature,
+
+    /// The signer's certificate was not issued by a trusted root
+    UntrustedCertificate,
+
+    /// The signer's certificate was not valid at the time of signing
+    CertificateExpired,
+
+    /// The timestamp of the signature is not for its signature value, or was
+    /// not issued by a trusted authority
+    InvalidTimestamp,
+}
+
+/// Verify `signature` against `file`, the bytes of the document in which it
+/// is found
+pub(crate) fn verify(
+    signature: Signature,
+    file: &[u8],
+    trust_roots: &[Certificate],
+) -> SignatureVerification {
+    let mut verification = SignatureVerification {
+        signature,
+        signer: None,
+        timestamp: None,
+        revision: None,
+        modified_after_signing: false,
+        problems: Vec::new(),
+    };
+
+    let signed = match signed_bytes(&verification.signature.byte_range, file) {
+        Some(signed) => signed,
+        None => {
+            verification
+                .problems
+                .push(SignatureProblem::InvalidByteRange);
+            return verification;
+        }
+    };
+
+    let signed_end = verification
+        .signature
+        .byte_range
+        .last()
+        .map_or(0, |&(offset, len)| offset + len);
+
+    verification.revision = revision_ends(file)
+        .into_iter()
+        .position(|end| trim_end(file, end) == trim_end(file, signed_end));
+
+    verification.modified_after_signing =
+        file[signed_end..].iter().any(|b| !b.is_ascii_whitespace());
+
+    if verification.revision.is_none() {
+        verification
+            .problems
+            .push(SignatureProblem::InvalidByteRange);
+    }
+
+    if let Err(problem) = verify_signed_data(&mut verification, &signed, trust_roots) {
+        verification.problems.push(problem);
+    }
+
+    verification
+}
+
+/// Verify the CMS signed data of a signature, returning the first problem
+/// which prevents further verification
+fn verify_signed_data(
+    verification: &mut SignatureVerification,
+    signed: &[&[u8]],
+    trust_roots: &[Certificate],
+) -> Result<(), SignatureProblem> {
+    let sub_filter = match &verification.signature.sub_filter {
+        Some(
+            sub_filter @ (SignatureSubFilter::Pkcs7Detached
+            | SignatureSubFilter::CadesDetached
+            | SignatureSubFilter::Pkcs7Sha1
+            | SignatureSubFilter::Rfc3161),
+        ) => sub_filter.clone(),
+        _ => return Err(SignatureProblem::UnsupportedSubFilter),
+    };
+
+    let signed_data = SignedData::from_der(&verification.signature.contents).map_err(malformed)?;
+
+    // the content signed by a document timestamp is its timestamp info, which
+    // holds the digest of the byte range, and with adbe.pkcs7.sha1 it is the
+    // SHA-1 digest of the byte range
+    let content = match sub_filter {
+        SignatureSubFilter::Rfc3161 => {
+            let timestamp = Timestamp::from_signed_data(&signed_data).map_err(malformed)?;
+
+            if !timestamp.is_for(signed) {
+                return Err(SignatureProblem::DigestMismatch);
+            }
+
+            verification.timestamp = Some(timestamp);
+
+            signed_data
+                .encapsulated_content
+                .as_deref()
+                .into_iter()
+                .collect()
+        }
+        SignatureSubFilter::Pkcs7Sha1 => match signed_data.encapsulated_content.as_deref() {
+            Some(content) if content == DigestAlgorithm::Sha1.digest(signed) => vec![content],
+            _ => return Err(SignatureProblem::DigestMismatch),
+        },
+        _ => signed.to_vec(),
+    };
+
+    let certificate = verify_signer(&signed_data, &content)?;
+
+    verification.signer = certificate.common_name();
+
+    let signer = &signed_data.signer;
+
+    if sub_filter != SignatureSubFilter::Rfc3161 {
+        verification.timestamp = match signature_timestamp(signer, trust_roots) {
+            Ok(timestamp) => timestamp,
+            Err(problem) => {
+                verification.problems.push(problem);
+                None
+            }
+        };
+    }
+
+    // the time of signing is best given by a timestamp, and otherwise by the
+    // signer's claim
+    let signing_time = match &verification.timestamp {
+        Some(timestamp) => unix_time(&timestamp.time),
+        None => match attribute(signer.signed_attrs.as_ref(), ID_SIGNING_TIME)
+            .and_then(|time| Time::from_der(&time.to_der().ok()?).ok())
+        {
+            Some(time) => Some(time.to_unix_duration().as_secs() as i64),
+            None => verification
+                .signature
+                .signing_time
+                .as_ref()
+                .and_then(unix_time),
+        },
+    };
+
+    if let Some(signing_time) = signing_time {
+        if !certificate.is_valid_at(signing_time) {
+            verification
+                .problems
+                .push(SignatureProblem::CertificateExpired);
+        }
+    }
+
+    // without a time of signing, the chain must be valid now
+    let chain_time = signing_time.unwrap_or_else(now);
+
+    if !is_trusted(
+        certificate,
+        &signed_data.certificates,
+        trust_roots,
+        chain_time,
+    ) {
+        return Err(SignatureProblem::UntrustedCertificate);
+    }
+
+    Ok(())
+}
+
+/// Verify that the signer of `signed_data` signed `content`, returning the
+/// signer's certificate
+fn verify_signer<'c>(
+    signed_data: &'c SignedData,
+    content: &[&[u8]],
+) -> Result<&'c Certificate, SignatureProblem> {
+    let signer = &signed_data.signer;
+
+    let digest_algorithm = DigestAlgorithm::from_oid(&signer.digest_alg.oid)
+        .ok_or_else(|| SignatureProblem::UnsupportedAlgorithm(signer.digest_alg.oid.to_string()))?;
+
+    let content_digest = digest_algorithm.digest(content);
+
+    let signed_digest = match signed_attributes_der(signer).map_err(malformed)? {
+        Some(signed_attributes) => {
+            let message_digest = attribute(signer.signed_attrs.as_ref(), ID_MESSAGE_DIGEST)
+                .and_then(|message_digest| message_digest.decode_as::<OctetString>().ok());
+
+            match message_digest {
+                Some(message_digest) if message_digest.as_bytes() == content_digest => {}
+                _ => return Err(SignatureProblem::DigestMismatch),
+            }
+
+            digest_algorithm.digest(&[&signed_attributes])
+        }
+        None => content_digest,
+    };
+
+    let certificate = signed_data
+        .signer_certificate()
+        .ok_or(SignatureProblem::MissingSignerCertificate)?;
+
+    if !is_supported_signature(&signer.signature_algorithm) {
+        return Err(SignatureProblem::UnsupportedAlgorithm(
+            signer.signature_algorithm.oid.to_string(),
+        ));
+    }
+
+    match certificate.verify(
+        &signer.signature_algorithm,
+        signer.signature.as_bytes(),
+        digest_algorithm,
+        &signed_digest,
+    ) {
+        Ok(true) => Ok(certificate),
+        Ok(false) => Err(SignatureProblem::InvalidSignature),
+        Err(err) => Err(malformed(err)),
+    }
+}
+
+/// The timestamp of a signer's signature value, if it has one, verified to
+/// be issued by a trusted authority for the signature value
+fn signature_timestamp(
+    signer: &SignerInfo,
+    trust_roots: &[Certificate],
+) -> Result<Option<Timestamp>, SignatureProblem> {
+    let token = match attribute(signer.unsigned_attrs.as_ref(), SIGNATURE_TIMESTAMP_TOKEN) {
+        Some(token) => token,
+        None => return Ok(None),
+    };
+
+    let token = SignedData::from_any(token).map_err(|_| SignatureProblem::InvalidTimestamp)?;
+    let timestamp =
+        Timestamp::from_signed_data(&token).map_err(|_| SignatureProblem::InvalidTimestamp)?;
+
+    let is_valid = timestamp.is_for(&[signer.signature.as_bytes()])
+        && match verify_signer(
+            &token,
+            &token
+                .encapsulated_content
+                .as_deref()
+                .into_iter()
+                .collect::<Vec<_>>(),
+        ) {
+            Ok(authority) => unix_time(&timestamp.time).map_or(false, |time| {
+                authority.is_valid_at(time)
+                    && is_trusted(authority, &token.certificates, trust_roots, time)
+            }),
+            Err(..) => false,
+        };
+
+    if is_valid {
+        Ok(Some(timestamp))
+    } else {
+        Err(SignatureProblem::InvalidTimestamp)
+    }
+}
+
+fn malformed(err: anyhow::Error) -> SignatureProblem {
+    SignatureProblem::Malformed(err.to_string())
+}
+
+/// Whether a chain of certificates leads from `certificate` to one of
+/// `trust_roots`, through the certificate authorities among `intermediates`
+/// which are valid at `time`
+fn is_trusted(
+    certificate: &Certificate,
+    intermediates: &[Certificate],
+    trust_roots: &[Certificate],
+    time: i64,
+) -> bool {
+    let mut certificate = certificate;
+
+    for _ in 0..MAX_CHAIN_LENGTH {
+        let is_root = trust_roots
+            .iter()
+            .any(|root| root == certificate || certificate.is_issued_by(root).unwrap_or(false));
+
+        if is_root {
+            return true;
+        }
+
+        certificate = match intermediates.iter().find(|issuer| {
+            *issuer != certificate
+                && issuer.is_ca()
+                && issuer.is_valid_at(time)
+                && certificate.is_issued_by(issuer).unwrap_or(false)
+        }) {
+            Some(issuer) => issuer,
+            None => return false,
+        };
+    }
+
+    false
+}
+
+/// The number of seconds between the Unix epoch and now
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs() as i64)
+}
+
+/// The number of seconds between the Unix epoch and `date`, if it is given
+/// at least to the year
+fn unix_time(date: &Date) -> Option<i64> {
+    let year = i64::from(date.year?);
+    let month = i64::from(date.month.unwrap_or(1));
+    let day = i64::from(date.day.unwrap_or(1));
+
+    // days from civil, counting years from March so that the leap day is last
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let local = days * 86400
+        + i64::from(date.hour.unwrap_or(0)) * 3600
+        + i64::from(date.minute.unwrap_or(0)) * 60
+        + i64::from(date.second.unwrap_or(0));
+
+    let offset = i64::from(date.ut_hour_offset.unwrap_or(0)) * 3600
+        + i64::from(date.ut_minute_offset.unwrap_or(0)) * 60;
+
+    Some(match date.ut_relationship {
+        Some(UtRelationship::Plus) => local - offset,
+        Some(UtRelationship::Minus) => local + offset,
+        Some(UtRelationship::Equal) | None => local,
+    })
+}
+
+/// The ranges of `file` given by a signature's byte range, if they begin at
+/// the start of the file, lie within it, and leave gaps which contain only
+/// the hexadecimal signature value
+fn signed_bytes<'b>(byte_range: &[(usize, usize)], file: &'b [u8]) -> Option<Vec<&'b [u8]>> {
+    if byte_range.first()?.0 != 0 {
+        return None;
+    }
+
+    for window in byte_range.windows(2) {
+        let gap = file.get(window[0].0 + window[0].1..window[1].0)?;
+
+        if gap.first() != Some(&b'<') || gap.last() != Some(&b'>') {
+            return None;
+        }
+    }
+
+    byte_range
+        .iter()
+        .map(|&(offset, len)| file.get(offset..offset.checked_add(len)?))
+        .collect()
+}
+
+/// The offsets at which each revision of the document ends: after each
+/// end-of-file marker and the end-of-line following it
+pub(super) fn revision_ends(file: &[u8]) -> Vec<usize> {
+    let mut ends = Vec::new();
+    let mut pos = 0;
+
+    while let Some(idx) = file[pos..].windows(5).position(|window| window == b"%%EOF") {
+        pos += idx + 5;
+
+        match file.get(pos..pos + 2) {
+            Some(b"\r\n") => pos += 2,
+            _ if matches!(file.get(pos), Some(b'\r' | b'\n')) => pos += 1,
+            _ => {}
+        }
+
+        ends.push(pos);
+    }
+
+    ends
+}
+
+/// The offset `end`, moved back over any whitespace which precedes it
+pub(super) fn trim_end(file: &[u8], end: usize) -> usize {
+    file[..end.min(file.len())]
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(0, |idx| idx + 1)
+}
+
+/// Parse the DER encoded certificates of trusted roots
+pub(crate) fn trust_roots(certificates: &[impl AsRef<[u8]>]) -> PdfResult<Vec<Certificate>> {
+    certificates
+        .iter()
+        .map(|certificate| Certificate::from_der(certificate.as_ref()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{str::FromStr, time::Duration};
+
+    use ::cms::{
+        cert::{CertificateChoices, IssuerAndSerialNumber},
+        content_info::{CmsVersion, ContentInfo},
+        signed_data::{
+            CertificateSet, EncapsulatedContentInfo, SignedData, SignerIdentifier, SignerInfos,
+        },
+    };
+    use der::{
+        asn1::{BitString, SetOfVec, UtcTime},
+        oid::{
+            db::{
+                rfc5911::{ID_CONTENT_TYPE, ID_DATA, ID_SIGNED_DATA},
+                rfc5912::{
+                    ECDSA_WITH_SHA_256, ECDSA_WITH_SHA_384, ID_EC_PUBLIC_KEY, ID_RSASSA_PSS,
+                    ID_SHA_384, RSA_ENCRYPTION, SECP_256_R_1, SECP_384_R_1,
+                },
+            },
+            AssociatedOid, ObjectIdentifier,
+        },
+        Any,
+    };
+    use p256::ecdsa::signature::hazmat::PrehashSigner;
+    use rsa::{
+        pkcs1::{EncodeRsaPublicKey, RsaPssParams},
+        rand_core::{CryptoRng, RngCore},
+        Pss, RsaPrivateKey,
+    };
+    use sha2::{Digest, Sha256};
+    use x509_cert::{
+        attr::Attribute,
+        certificate::{TbsCertificate, Version},
+        ext::{pkix::BasicConstraints, Extension},
+        name::Name,
+        serial_number::SerialNumber,
+        spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned},
+        time::Validity,
+    };
+
+    use super::*;
+    use crate::objects::Reference;
+
+    /// A deterministic source of the randomness needed to generate and use
+    /// RSA keys
+    struct TestRng(u64);
+
+    impl RngCore for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for b in dest {
+                *b = self.next_u64() as u8;
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rsa::rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for TestRng {}
+
+    /// 2020-01-01, 2023-01-01, 2024-03-01 and 2030-01-01
+    const YEAR_2020: u64 = 1577836800;
+    const YEAR_2023: u64 = 1672531200;
+    const SIGNING_TIME: u64 = 1709251200;
+    const YEAR_2030: u64 = 1893456000;
+
+    fn time(seconds: u64) -> Time {
+        Time::UtcTime(UtcTime::from_unix_duration(Duration::from_secs(seconds)).unwrap())
+    }
+
+    fn algorithm(oid: ObjectIdentifier, parameters: Option<Any>) -> AlgorithmIdentifierOwned {
+        AlgorithmIdentifierOwned { oid, parameters }
+    }
+
+    fn ec_key_info(curve: ObjectIdentifier, point: &[u8]) -> SubjectPublicKeyInfoOwned {
+        SubjectPublicKeyInfoOwned {
+            algorithm: algorithm(ID_EC_PUBLIC_KEY, Some(Any::encode_from(&curve).unwrap())),
+            subject_public_key: BitString::from_bytes(point).unwrap(),
+        }
+    }
+
+    /// A certificate for `subject`, signed by `sign` with `signature_algorithm`
+    fn certificate(
+        serial_number: u8,
+        (issuer, subject): (&str, &str),
+        not_after: u64,
+        subject_public_key_info: SubjectPublicKeyInfoOwned,
+        signature_algorithm: AlgorithmIdentifierOwned,
+        sign: impl FnOnce(&[u8]) -> Vec<u8>,
+    ) -> x509_cert::Certificate {
+        let constraints = BasicConstraints {
+            ca: subject != "CN=Signer",
+            path_len_constraint: None,
+        };
+
+        let tbs_certificate = TbsCertificate {
+            version: Version::V3,
+            serial_number: SerialNumber::new(&[serial_number]).unwrap(),
+            signature: signature_algorithm.clone(),
+            issuer: Name::from_str(issuer).unwrap(),
+            validity: Validity {
+                not_before: time(YEAR_2020),
+                not_after: time(not_after),
+            },
+            subject: Name::from_str(subject).unwrap(),
+            subject_public_key_info,
+            issuer_unique_id: None,
+            subject_unique_id: None,
+            extensions: Some(vec![Extension {
+                extn_id: BasicConstraints::OID,
+                critical: true,
+                extn_value: OctetString::new(constraints.to_der().unwrap()).unwrap(),
+            }]),
+        };
+
+        let signature = sign(&tbs_certificate.to_der().unwrap());
+
+        x509_cert::Certificate {
+            tbs_certificate,
+            signature_algorithm,
+            signature: BitString::from_bytes(&signature).unwrap(),
+        }
+    }
+
+    /// A P-256 root, which issues an RSA intermediate whose validity ends at
+    /// `intermediate_not_after`, which issues a P-384 signer with RSA-PSS
+    fn chain(
+        intermediate_not_after: u64,
+    ) -> (Vec<x509_cert::Certificate>, p384::ecdsa::SigningKey) {
+        let mut rng = TestRng(0x2545f4914f6cdd1d);
+
+        let root_key = p256::ecdsa::SigningKey::from_slice(&[1; 32]).unwrap();
+        let intermediate_key = RsaPrivateKey::new(&mut rng, 1024).unwrap();
+        let signer_key = p384::ecdsa::SigningKey::from_slice(&[2; 48]).unwrap();
+
+        let sign_with_root = |tbs: &[u8]| {
+            let signature: p256::ecdsa::Signature =
+                root_key.sign_prehash(&Sha256::digest(tbs)).unwrap();
+            signature.to_der().as_bytes().to_vec()
+        };
+
+        let root = certificate(
+            1,
+            ("CN=Root", "CN=Root"),
+            YEAR_2030,
+            ec_key_info(
+                SECP_256_R_1,
+                root_key.verifying_key().to_encoded_point(false).as_bytes(),
+            ),
+            algorithm(ECDSA_WITH_SHA_256, None),
+            sign_with_root,
+        );
+
+        let intermediate = certificate(
+            2,
+            ("CN=Root", "CN=Intermediate"),
+            intermediate_not_after,
+            SubjectPublicKeyInfoOwned {
+                algorithm: algorithm(RSA_ENCRYPTION, Some(Any::null())),
+                subject_public_key: BitString::from_bytes(
+                    intermediate_key
+                        .to_public_key()
+                        .to_pkcs1_der()
+                        .unwrap()
+                        .as_bytes(),
+                )
+                .unwrap(),
+            },
+            algorithm(ECDSA_WITH_SHA_256, None),
+            sign_with_root,
+        );
+
+        let signer = certificate(
+            3,
+            ("CN=Intermediate", "CN=Signer"),
+            YEAR_2030,
+            ec_key_info(
+                SECP_384_R_1,
+                signer_key
+                    .verifying_key()
+                    .to_encoded_point(false)
+                    .as_bytes(),
+            ),
+            algorithm(
+                ID_RSASSA_PSS,
+                Some(Any::encode_from(&RsaPssParams::new::<Sha256>(32)).unwrap()),
+            ),
+            |tbs| {
+                intermediate_key
+                    .sign_with_rng(
+                        &mut rng,
+                        Pss::new_with_salt::<Sha256>(32),
+                        &Sha256::digest(tbs),
+                    )
+                    .unwrap()
+            },
+        );
+
+        (vec![root, intermediate, signer], signer_key)
+    }
+
+    fn attribute(oid: ObjectIdentifier, value: Any) -> Attribute {
+        Attribute {
+            oid,
+            values: SetOfVec::try_from(vec![value]).unwrap(),
+        }
+    }
+
+    /// A document with a single detached CMS signature of its byte range,
+    /// made with the key of the last of `certificates`
+    fn signed_document(
+        certificates: &[x509_cert::Certificate],
+        key: &p384::ecdsa::SigningKey,
+    ) -> (Vec<u8>, Signature) {
+        let head = b"%PDF-1.7\n1 0 obj\n<< /Type /Sig /Contents ".to_vec();
+        let tail = b" >>\nendobj\n%%EOF\n".to_vec();
+
+        let digest = DigestAlgorithm::Sha384.digest(&[&head, &tail]);
+
+        let signed_attrs = SetOfVec::try_from(vec![
+            attribute(ID_CONTENT_TYPE, Any::encode_from(&ID_DATA).unwrap()),
+            attribute(
+                ID_MESSAGE_DIGEST,
+                Any::encode_from(&OctetString::new(digest).unwrap()).unwrap(),
+            ),
+            attribute(
+                ID_SIGNING_TIME,
+                Any::encode_from(&time(SIGNING_TIME)).unwrap(),
+            ),
+        ])
+        .unwrap();
+
+        let signature: p384::ecdsa::Signature = key
+            .sign_prehash(&DigestAlgorithm::Sha384.digest(&[&signed_attrs.to_der().unwrap()]))
+            .unwrap();
+
+        let signer_certificate = &certificates.last().unwrap().tbs_certificate;
+
+        let signer = SignerInfo {
+            version: CmsVersion::V1,
+            sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+                issuer: signer_certificate.issuer.clone(),
+                serial_number: signer_certificate.serial_number.clone(),
+            }),
+            digest_alg: algorithm(ID_SHA_384, None),
+            signed_attrs: Some(signed_attrs),
+            signature_algorithm: algorithm(ECDSA_WITH_SHA_384, None),
+            signature: OctetString::new(signature.to_der().as_bytes()).unwrap(),
+            unsigned_attrs: None,
+        };
+
+        let signed_data = SignedData {
+            version: CmsVersion::V1,
+            digest_algorithms: SetOfVec::try_from(vec![algorithm(ID_SHA_384, None)]).unwrap(),
+            encap_content_info: EncapsulatedContentInfo {
+                econtent_type: ID_DATA,
+                econtent: None,
+            },
+            certificates: Some(CertificateSet(
+                SetOfVec::try_from(
+                    certificates
+                        .iter()
+                        .cloned()
+                        .map(CertificateChoices::Certificate)
+                        .collect::<Vec<_>>(),
+                )
+                .unwrap(),
+            )),
+            crls: None,
+            signer_infos: SignerInfos(SetOfVec::try_from(vec![signer]).unwrap()),
+        };
+
+        let mut contents = ContentInfo {
+            content_type: ID_SIGNED_DATA,
+            content: Any::encode_from(&signed_data).unwrap(),
+        }
+        .to_der()
+        .unwrap();
+
+        // the signature value is padded with zeros
+        contents.resize(contents.len() + 64, 0);
+
+        let mut file = head.clone();
+        file.push(b'<');
+        file.extend(
+            contents
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+                .bytes(),
+        );
+        file.push(b'>');
+
+        let byte_range = vec![(0, head.len()), (file.len(), tail.len())];
+        file.extend(tail);
+
+        let reference = Reference {
+            object_number: 1,
+            generation: 0,
+        };
+
+        let signature = Signature {
+            field_name: "Signature1".to_owned(),
+            field: reference,
+            reference,
+            is_doc_timestamp: false,
+            filter: "Adobe.PPKLite".to_owned(),
+            sub_filter: Some(SignatureSubFilter::Pkcs7Detached),
+            contents,
+            certificates: Vec::new(),
+            byte_range,
+            references: Vec::new(),
+            name: None,
+            signing_time: None,
+            location: None,
+            reason: None,
+            contact_info: None,
+        };
+
+        (file, signature)
+    }
+
+    #[test]
+    fn verify_certificate_chain() {
+        let (certificates, key) = chain(YEAR_2030);
+        let trust_roots = vec![Certificate::new(certificates[0].clone())];
+
+        let (file, signature) = signed_document(&certificates, &key);
+        let verification = verify(signature.clone(), &file, &trust_roots);
+
+        assert_eq!(verification.problems, Vec::new());
+        assert_eq!(verification.signer.as_deref(), Some("Signer"));
+        assert_eq!(verification.revision, Some(0));
+        assert!(!verification.modified_after_signing);
+
+        let mut modified = file.clone();
+        modified[1] = b'Q';
+
+        assert_eq!(
+            verify(signature, &modified, &trust_roots).problems,
+            vec![SignatureProblem::DigestMismatch]
+        );
+
+        // the intermediate certificate expired before the document was signed
+        let (certificates, key) = chain(YEAR_2023);
+        let (file, signature) = signed_document(&certificates, &key);
+
+        assert_eq!(
+            verify(signature, &file, &trust_roots).problems,
+            vec![SignatureProblem::UntrustedCertificate]
+        );
+    }
+
+    #[test]
+    fn unix_times() {
+        let date = Date::from_str("D:20240229120000+01'30'").unwrap();
+        assert_eq!(unix_time(&date), Some(1709208000 - 5400));
+
+        let date = Date {
+            year: Some(2024),
+            month: Some(2),
+            day: Some(29),
+            hour: Some(12),
+            minute: Some(0),
+            second: Some(0),
+            ut_relationship: Some(UtRelationship::Equal),
+            ut_hour_offset: None,
+            ut_minute_offset: None,
+        };
+        assert_eq!(unix_time(&date), Some(1709208000));
+    }
+
+    #[test]
+    fn find_revisions() {
+        let file = b"%PDF-1.7\n%%EOF\nxref\n%%EOF\r\n";
+
+        assert_eq!(revision_ends(file), vec![15, 27]);
+        assert_eq!(trim_end(file, 27), 25);
+    }
+}
@@ -0,0 +1,340 @@
+use der::{
+    asn1::ObjectIdentifier,
+    oid::db::{
+        rfc4519::CN,
+        rfc5912::{
+            ECDSA_WITH_SHA_256, ECDSA_WITH_SHA_384, ECDSA_WITH_SHA_512, ID_EC_PUBLIC_KEY, ID_MGF_1,
+            ID_RSASSA_PSS, RSA_ENCRYPTION, SECP_256_R_1, SECP_384_R_1, SHA_1_WITH_RSA_ENCRYPTION,
+            SHA_256_WITH_RSA_ENCRYPTION, SHA_384_WITH_RSA_ENCRYPTION, SHA_512_WITH_RSA_ENCRYPTION,
+        },
+    },
+    Any, Decode, Encode, Tag, Tagged,
+};
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use rsa::{
+    pkcs1::{DecodeRsaPublicKey, RsaPssParams},
+    Pkcs1v15Sign, Pss, RsaPublicKey,
+};
+use sha1::Sha1;
+use sha2::{Sha256, Sha384, Sha512};
+use x509_cert::{
+    certificate::TbsCertificate,
+    ext::pkix::{BasicConstraints, SubjectKeyIdentifier},
+    spki::AlgorithmIdentifierOwned,
+};
+
+use crate::error::PdfResult;
+
+use super::digest::DigestAlgorithm;
+
+/// ECDSA with SHA-1, which is not among the object identifiers of RFC 5912
+const ECDSA_WITH_SHA_1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.1");
+
+/// An X.509 certificate, with the fields needed to build and verify a chain
+/// of certificates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Certificate(x509_cert::Certificate);
+
+/// The way in which a signature is made with a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SignatureScheme {
+    /// RSASSA-PKCS1-v1_5
+    Pkcs1,
+
+    /// RSASSA-PSS, with a salt of `salt_len` bytes and MGF1 with the digest
+    /// algorithm of the signature
+    Pss {
+        salt_len: usize,
+    },
+
+    Ecdsa,
+}
+
+impl Certificate {
+    pub fn from_der(der: &[u8]) -> PdfResult<Self> {
+        Ok(Self(x509_cert::Certificate::from_der(der)?))
+    }
+
+    pub(super) fn new(certificate: x509_cert::Certificate) -> Self {
+        Self(certificate)
+    }
+
+    pub(super) fn tbs(&self) -> &TbsCertificate {
+        &self.0.tbs_certificate
+    }
+
+    pub fn subject_key_identifier(&self) -> Option<SubjectKeyIdentifier> {
+        match self.tbs().get::<SubjectKeyIdentifier>() {
+            Ok(Some((_, identifier))) => Some(identifier),
+            _ => None,
+        }
+    }
+
+    /// Whether the basic constraints extension allows the certificate to
+    /// issue other certificates
+    pub fn is_ca(&self) -> bool {
+        matches!(
+            self.tbs().get::<BasicConstraints>(),
+            Ok(Some((_, constraints))) if constraints.ca
+        )
+    }
+
+    /// Whether `time`, in seconds since the Unix epoch, lies within the
+    /// validity period of the certificate
+    pub fn is_valid_at(&self, time: i64) -> bool {
+        let validity = &self.tbs().validity;
+        let seconds = |time: x509_cert::time::Time| time.to_unix_duration().as_secs() as i64;
+
+        seconds(validity.not_before) <= time && time <= seconds(validity.not_after)
+    }
+
+    /// The common name of the certificate's subject, such as the name of the
+    /// signer
+    pub fn common_name(&self) -> Option<String> {
+        self.tbs()
+            .subject
+            .0
+            .iter()
+            .flat_map(|names| names.0.iter())
+            .find(|attribute| attribute.oid == CN)
+            .and_then(|attribute| directory_string(&attribute.value))
+    }
+
+    /// Whether the certificate was issued by `issuer`, whose public key
+    /// verifies the certificate's signature
+    pub fn is_issued_by(&self, issuer: &Certificate) -> PdfResult<bool> {
+        if self.tbs().issuer != issuer.tbs().subject {
+            return Ok(false);
+        }
+
+        let algorithm = &self.0.signature_algorithm;
+
+        let digest_algorithm = match signature_scheme(algorithm) {
+            Some((_, Some(digest_algorithm))) => digest_algorithm,
+            _ => anyhow::bail!(
+                "unsupported certificate signature algorithm {}",
+                algorithm.oid
+            ),
+        };
+
+        let signature = match self.0.signature.as_bytes() {
+            Some(signature) => signature,
+            None => anyhow::bail!("certificate signature has unused bits"),
+        };
+
+        issuer.verify(
+            algorithm,
+            signature,
+            digest_algorithm,
+            &digest_algorithm.digest(&[&self.tbs().to_der()?]),
+        )
+    }
+
+    /// Whether `signature` is a signature of `digest`, calculated with
+    /// `digest_algorithm`, made with the certificate's key as given by
+    /// `signature_algorithm`
+    pub fn verify(
+        &self,
+        signature_algorithm: &AlgorithmIdentifierOwned,
+        signature: &[u8],
+        digest_algorithm: DigestAlgorithm,
+        digest: &[u8],
+    ) -> PdfResult<bool> {
+        let (scheme, signed_digest_algorithm) = match signature_scheme(signature_algorithm) {
+            Some(scheme) => scheme,
+            None => anyhow::bail!(
+                "unsupported signature algorithm {}",
+                signature_algorithm.oid
+            ),
+        };
+
+        // the digest named by the signature algorithm is the one signed
+        if signed_digest_algorithm.map_or(false, |signed| signed != digest_algorithm) {
+            return Ok(false);
+        }
+
+        let key_info = &self.tbs().subject_public_key_info;
+
+        let key = match key_info.subject_public_key.as_bytes() {
+            Some(key) => key,
+            None => anyhow::bail!("certificate public key has unused bits"),
+        };
+
+        match (scheme, key_info.algorithm.oid) {
+            (SignatureScheme::Pkcs1, RSA_ENCRYPTION | ID_RSASSA_PSS) => {
+                let scheme = match digest_algorithm {
+                    DigestAlgorithm::Sha1 => Pkcs1v15Sign::new::<Sha1>(),
+                    DigestAlgorithm::Sha256 => Pkcs1v15Sign::new::<Sha256>(),
+                    DigestAlgorithm::Sha384 => Pkcs1v15Sign::new::<Sha384>(),
+                    DigestAlgorithm::Sha512 => Pkcs1v15Sign::new::<Sha512>(),
+                };
+
+                Ok(RsaPublicKey::from_pkcs1_der(key)?
+                    .verify(scheme, digest, signature)
+                    .is_ok())
+            }
+            (SignatureScheme::Pss { salt_len }, RSA_ENCRYPTION | ID_RSASSA_PSS) => {
+                let scheme = match digest_algorithm {
+                    DigestAlgorithm::Sha1 => Pss::new_with_salt::<Sha1>(salt_len),
+                    DigestAlgorithm::Sha256 => Pss::new_with_salt::<Sha256>(salt_len),
+                    DigestAlgorithm::Sha384 => Pss::new_with_salt::<Sha384>(salt_len),
+                    DigestAlgorithm::Sha512 => Pss::new_with_salt::<Sha512>(salt_len),
+                };
+
+                Ok(RsaPublicKey::from_pkcs1_der(key)?
+                    .verify(scheme, digest, signature)
+                    .is_ok())
+            }
+            (SignatureScheme::Ecdsa, ID_EC_PUBLIC_KEY) => {
+                let curve = match &key_info.algorithm.parameters {
+                    Some(curve) => curve.decode_as::<ObjectIdentifier>()?,
+                    None => anyhow::bail!("elliptic curve public key has no curve"),
+                };
+
+                // digests longer than the order of the curve are truncated
+                Ok(match curve {
+                    SECP_256_R_1 => {
+                        let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(key)?;
+
+                        p256::ecdsa::Signature::from_der(signature).map_or(false, |signature| {
+                            key.verify_prehash(digest, &signature).is_ok()
+                        })
+                    }
+                    SECP_384_R_1 => {
+                        let key = p384::ecdsa::VerifyingKey::from_sec1_bytes(key)?;
+
+                        p384::ecdsa::Signature::from_der(signature).map_or(false, |signature| {
+                            key.verify_prehash(digest, &signature).is_ok()
+                        })
+                    }
+                    curve => anyhow::bail!("unsupported elliptic curve {}", curve),
+                })
+            }
+            (_, key_algorithm) => anyhow::bail!(
+                "signature algorithm {} cannot be used with a key of algorithm {}",
+                signature_algorithm.oid,
+                key_algorithm
+            ),
+        }
+    }
+}
+
+/// Whether `algorithm`, the signature algorithm of a CMS signer or of a
+/// certificate, is one with which signatures can be verified
+pub(super) fn is_supported_signature(algorithm: &AlgorithmIdentifierOwned) -> bool {
+    signature_scheme(algorithm).is_some()
+}
+
+/// The scheme of the signature algorithm `algorithm`, along with the digest
+/// algorithm it names, if it names one, or `None` if it is not supported
+///
+/// An RSA or elliptic curve key algorithm, which CMS signers may give in
+/// place of a signature algorithm, signs with the digest algorithm of the
+/// signer.
+fn signature_scheme(
+    algorithm: &AlgorithmIdentifierOwned,
+) -> Option<(SignatureScheme, Option<DigestAlgorithm>)> {
+    Some(match algorithm.oid {
+        RSA_ENCRYPTION => (SignatureScheme::Pkcs1, None),
+        SHA_1_WITH_RSA_ENCRYPTION => (SignatureScheme::Pkcs1, Some(DigestAlgorithm::Sha1)),
+        SHA_256_WITH_RSA_ENCRYPTION => (SignatureScheme::Pkcs1, Some(DigestAlgorithm::Sha256)),
+        SHA_384_WITH_RSA_ENCRYPTION => (SignatureScheme::Pkcs1, Some(DigestAlgorithm::Sha384)),
+        SHA_512_WITH_RSA_ENCRYPTION => (SignatureScheme::Pkcs1, Some(DigestAlgorithm::Sha512)),
+        ID_RSASSA_PSS => {
+            // the parameters are required, though each of them has a default
+            let params = algorithm
+                .parameters
+                .as_ref()?
+                .decode_as::<RsaPssParams>()
+                .ok()?;
+            let digest_algorithm = DigestAlgorithm::from_oid(&params.hash.oid)?;
+
+            // the mask generation function uses the same digest algorithm as
+            // the signature
+            let mask_digest = params.mask_gen.parameters?;
+
+            if params.mask_gen.oid != ID_MGF_1 || mask_digest.oid != params.hash.oid {
+                return None;
+            }
+
+            (
+                SignatureScheme::Pss {
+                    salt_len: usize::from(params.salt_len),
+                },
+                Some(digest_algorithm),
+            )
+        }
+        ID_EC_PUBLIC_KEY => (SignatureScheme::Ecdsa, None),
+        ECDSA_WITH_SHA_1 => (SignatureScheme::Ecdsa, Some(DigestAlgorithm::Sha1)),
+        ECDSA_WITH_SHA_256 => (SignatureScheme::Ecdsa, Some(DigestAlgorithm::Sha256)),
+        ECDSA_WITH_SHA_384 => (SignatureScheme::Ecdsa, Some(DigestAlgorithm::Sha384)),
+        ECDSA_WITH_SHA_512 => (SignatureScheme::Ecdsa, Some(DigestAlgorithm::Sha512)),
+        _ => return None,
+    })
+}
+
+/// The text of an attribute value of a distinguished name
+fn directory_string(value: &Any) -> Option<String> {
+    match value.tag() {
+        Tag::Utf8String | Tag::PrintableString | Tag::Ia5String => {
+            Some(String::from_utf8_lossy(value.value()).into_owned())
+        }
+        Tag::TeletexString => Some(value.value().iter().map(|&b| b as char).collect()),
+        Tag::BmpString => Some(String::from_utf16_lossy(
+            &value
+                .value()
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect::<Vec<u16>>(),
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use der::asn1::Utf8StringRef;
+
+    use super::*;
+
+    #[test]
+    fn signature_schemes() {
+        let algorithm = |oid| AlgorithmIdentifierOwned {
+            oid,
+            parameters: None,
+        };
+
+        assert_eq!(
+            signature_scheme(&algorithm(SHA_256_WITH_RSA_ENCRYPTION)),
+            Some((SignatureScheme::Pkcs1, Some(DigestAlgorithm::Sha256)))
+        );
+        assert_eq!(
+            signature_scheme(&algorithm(ECDSA_WITH_SHA_1)),
+            Some((SignatureScheme::Ecdsa, Some(DigestAlgorithm::Sha1)))
+        );
+        // RSASSA-PSS requires its parameters
+        assert_eq!(signature_scheme(&algorithm(ID_RSASSA_PSS)), None);
+
+        let params = RsaPssParams::new::<Sha384>(48);
+
+        assert_eq!(
+            signature_scheme(&AlgorithmIdentifierOwned {
+                oid: ID_RSASSA_PSS,
+                parameters: Some(Any::encode_from(&params).unwrap()),
+            }),
+            Some((
+                SignatureScheme::Pss { salt_len: 48 },
+                Some(DigestAlgorithm::Sha384)
+            ))
+        );
+    }
+
+    #[test]
+    fn directory_strings() {
+        let name = Any::encode_from(&Utf8StringRef::new("Signer").unwrap()).unwrap();
+        assert_eq!(directory_string(&name).as_deref(), Some("Signer"));
+
+        let name = Any::new(Tag::BmpString, [0x00, 0x41, 0x00, 0xe9]).unwrap();
+        assert_eq!(directory_string(&name).as_deref(), Some("Aé"));
+    }
+}
@@ -1,9 +1,9 @@
-use std::{borrow::Cow, fmt};
+use std::{borrow::Cow, fmt, io::Read};
 
 use crate::{
     error::{ParseError, PdfResult},
     file_specification::FileSpecification,
-    filter::FilterKind,
+    filter::{decode_stream_reader, encode_stream, FilterKind},
     objects::{Dictionary, Object, ObjectType},
     FromObj, Resolve,
 };
@@ -14,6 +14,50 @@ pub struct Stream<'a> {
     pub(crate) stream: Cow<'a, [u8]>,
 }
 
+impl<'a> Stream<'a> {
+    /// A stream of unfiltered data, whose dictionary holds the given entries
+    /// along with its Length
+    pub fn new(data: impl Into<Cow<'a, [u8]>>, dict: Dictionary<'a>) -> Self {
+        let stream = data.into();
+
+        Self {
+            dict: StreamDict {
+                len: stream.len(),
+                filter: None,
+                decode_parms: None,
+                f: None,
+                f_filter: None,
+                f_decode_parms: None,
+                decoded_len: None,
+                other: dict,
+            },
+            stream,
+        }
+    }
+
+    /// A stream of `data` encoded with the given filters, which are applied in
+    /// reverse, so that they decode it in the order given
+    pub fn encoded(data: &[u8], filters: Vec<FilterKind>, dict: Dictionary<'a>) -> PdfResult<Self> {
+        let mut stream = Self::new(encode_stream(data, &filters)?, dict);
+
+        if !filters.is_empty() {
+            stream.dict.filter = Some(filters);
+        }
+
+        Ok(stream)
+    }
+
+    /// The data of the stream, decoded as it is read rather than all at once
+    ///
+    /// Only the Flate, LZW, RunLength, ASCIIHex and ASCII85 filters decode
+    /// incrementally. Streams with a CCITTFax, DCT or JPX filter are buffered
+    /// in full, up to and including that filter, when the reader is made, and
+    /// JBIG2 streams cannot be decoded.
+    pub fn decoded_reader(&self, resolver: &mut dyn Resolve<'a>) -> PdfResult<Box<dyn Read + '_>> {
+        decode_stream_reader(&self.stream, &self.dict, resolver)
+    }
+}
+
 impl fmt::Debug for Stream<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Stream")
@@ -32,6 +76,27 @@ impl<'a> DecodeParams<'a> {
     pub fn get(&self, idx: usize) -> Option<&Dictionary<'a>> {
         self.params.get(idx).and_then(|d| d.as_ref())
     }
+
+    pub(crate) fn values_mut(&mut self) -> impl Iterator<Item = &mut Object<'a>> {
+        self.params
+            .iter_mut()
+            .flatten()
+            .flat_map(|dict| dict.values_mut())
+    }
+
+    /// The parameters as they would be written, as a single dictionary if
+    /// there is only one filter
+    pub fn to_obj(&self) -> Object<'a> {
+        let obj = |params: &Option<Dictionary<'a>>| match params {
+            Some(dict) => Object::Dictionary(dict.clone()),
+            None => Object::Null,
+        };
+
+        match self.params.as_slice() {
+            [params] => obj(params),
+            params => Object::Array(params.iter().map(obj).collect()),
+        }
+    }
 }
 
 impl<'a> FromObj<'a> for DecodeParams<'a> {
@@ -150,4 +215,80 @@ impl<'a> StreamDict<'a> {
             other: dict,
         })
     }
+
+    /// The values of the dictionary, including those of the filter parameters
+    pub(crate) fn values_mut(&mut self) -> impl Iterator<Item = &mut Object<'a>> {
+        self.other
+            .values_mut()
+            .chain(self.decode_parms.iter_mut().flat_map(|p| p.values_mut()))
+            .chain(self.f_decode_parms.iter_mut().flat_map(|p| p.values_mut()))
+    }
+
+    /// Remove the Crypt filters, along with their parameters, such as when the
+    /// data has been decrypted
+    pub(crate) fn remove_crypt_filters(&mut self) {
+        let filters = match &mut self.filter {
+            Some(filters) => filters,
+            None => return,
+        };
+
+        let mut idx = 0;
+
+        while idx < filters.len() {
+            if filters[idx] != FilterKind::Crypt {
+                idx += 1;
+                continue;
+            }
+
+            filters.remove(idx);
+
+            if let Some(decode_parms) = &mut self.decode_parms {
+                if idx < decode_parms.params.len() {
+                    decode_parms.params.remove(idx);
+                }
+            }
+        }
+
+        if filters.is_empty() {
+            self.filter = None;
+            self.decode_parms = None;
+        }
+    }
+
+    /// The stream dictionary as it would be written, for `len` bytes of
+    /// stream data
+    pub(crate) fn to_dict(&self, len: usize) -> PdfResult<Dictionary<'a>> {
+        let filters = |filters: &[FilterKind]| match filters {
+            [filter] => Object::Name(filter.as_str().to_owned()),
+            filters => Object::Array(
+                filters
+                    .iter()
+                    .map(|filter| Object::Name(filter.as_str().to_owned()))
+                    .collect(),
+            ),
+        };
+
+        if self.f.is_some() {
+            // todo: write file specifications
+            anyhow::bail!("streams with external data cannot yet be written");
+        }
+
+        let mut dict = self.other.clone();
+
+        dict.insert("Length", Object::Integer(i32::try_from(len)?));
+
+        if let Some(filter) = &self.filter {
+            dict.insert("Filter", filters(filter));
+        }
+
+        if let Some(decode_parms) = &self.decode_parms {
+            dict.insert("DecodeParms", decode_parms.to_obj());
+        }
+
+        if let Some(decoded_len) = self.decoded_len {
+            dict.insert("DL", Object::Integer(i32::try_from(decoded_len)?));
+        }
+
+        Ok(dict)
+    }
 }
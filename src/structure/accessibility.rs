@@ -0,0 +1,323 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    content::{ContentLexer, ContentToken, PdfGraphicsOperator, PropertyList},
+    error::PdfResult,
+    objects::{Object, Reference},
+    resources::Resources,
+    Resolve,
+};
+
+use super::{
+    heading::first_content_page, StandardStructureType, StructTreeRoot, StructureElement,
+    StructureElementChild,
+};
+
+/// A failure of a document to meet a requirement of PDF/UA (ISO 14289)
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessibilityIssue {
+    /// The structure element which fails the requirement, if the problem
+    /// concerns one
+    pub element: Option<Reference>,
+
+    /// The structure type of the element, as named by the document
+    pub structure_type: Option<String>,
+
+    /// The index of the page on which the problem occurs, if known
+    pub page: Option<usize>,
+
+    pub problem: AccessibilityProblem,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccessibilityProblem {
+    /// The document has no structure tree, or its mark information dictionary
+    /// does not declare that it is tagged
+    NotTagged,
+
+    /// The document catalog does not specify the natural language of the
+    /// document
+    MissingLanguage,
+
+    /// Text is shown on the page which is neither within the structure tree
+    /// nor marked as an artifact
+    UntaggedContent(String),
+
+    /// A marked-content sequence of the page has a marked-content identifier,
+    /// but no structure element refers to it
+    UnreferencedContent(i32),
+
+    /// A figure or formula has neither alternate text nor replacement text
+    MissingAlternateText,
+
+    /// A numbered heading is more than one level deeper than the heading
+    /// before it, or the first heading is not H1
+    SkippedHeadingLevel {
+        level: u8,
+
+        /// The level of the heading before it, or 0 for the first heading
+        previous: u8,
+    },
+}
+
+/// How the content of a page is tagged: the marked-content identifiers of its
+/// sequences, and the text shown outside of both tagged content and artifacts
+///
+/// As with [`super::MarkedContentText`], strings are taken as-is.
+#[derive(Debug, Default)]
+pub(crate) struct ContentTagging {
+    mcids: HashSet<i32>,
+    untagged_text: String,
+}
+
+impl ContentTagging {
+    pub fn from_content<'a>(
+        content: ContentLexer<'a>,
+        resources: Option<&Resources<'a>>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let mut tagging = Self::default();
+
+        // whether each open marked-content sequence is an artifact or has a
+        // marked-content identifier
+        let mut stack: Vec<bool> = Vec::new();
+        let mut operands: Vec<Object<'a>> = Vec::new();
+
+        for token in content {
+            let operator = match token {
+                Ok(ContentToken::Object(obj)) => {
+                    operands.push(obj);
+                    continue;
+                }
+                Ok(ContentToken::Operator(operator)) => operator,
+                Ok(ContentToken::InlineImage(..)) | Err(..) => continue,
+            };
+
+            let is_tagged = stack.iter().any(|&is_tagged| is_tagged);
+
+            match operator {
+                PdfGraphicsOperator::BMC => {
+                    let is_artifact =
+                        matches!(operands.pop(), Some(Object::Name(tag)) if tag == "Artifact");
+
+                    stack.push(is_artifact);
+                }
+                PdfGraphicsOperator::BDC => {
+                    let properties = operands
+                        .pop()
+                        .map(|operand| PropertyList::from_operand(operand, resources, resolver));
+                    let is_artifact =
+                        matches!(operands.pop(), Some(Object::Name(tag)) if tag == "Artifact");
+
+                    let mcid = match properties {
+                        Some(Ok(properties)) => properties.mcid,
+                        Some(Err(..)) | None => None,
+                    };
+
+                    if let Some(mcid) = mcid {
+                        tagging.mcids.insert(mcid);
+                    }
+
+                    stack.push(is_artifact || mcid.is_some());
+                }
+                PdfGraphicsOperator::EMC => {
+                    stack.pop();
+                }
+                PdfGraphicsOperator::Tj
+                | PdfGraphicsOperator::single_quote
+                | PdfGraphicsOperator::double_quote
+                | PdfGraphicsOperator::TJ
+                    if !is_tagged =>
+                {
+                    match operands.pop() {
+                        Some(Object::String(s)) => tagging.untagged_text.push_str(&s),
+                        Some(Object::Array(arr)) => {
+                            for obj in arr {
+                                if let Object::String(s) = obj {
+                                    tagging.untagged_text.push_str(&s);
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+
+            operands.clear();
+        }
+
+        Ok(tagging)
+    }
+
+    /// The text shown outside of tagged content and artifacts, if there is any
+    /// other than whitespace
+    pub fn untagged_text(&self) -> Option<&str> {
+        let text = self.untagged_text.trim();
+
+        (!text.is_empty()).then_some(text)
+    }
+}
+
+impl<'a> StructTreeRoot<'a> {
+    /// The problems with the structure elements of the tree, and with the
+    /// tagging of the content of each page, that PDF/UA does not allow
+    ///
+    /// `tagging` holds how the content of each page is tagged, and
+    /// `page_indices` the index of each page.
+    pub(crate) fn accessibility_issues(
+        &self,
+        tagging: &HashMap<Reference, ContentTagging>,
+        page_indices: &HashMap<Reference, usize>,
+    ) -> Vec<AccessibilityIssue> {
+        let mut checker = AccessibilityChecker {
+            root: self,
+            page_indices,
+            referenced: HashSet::new(),
+            previous_heading: None,
+            issues: Vec::new(),
+        };
+
+        for element in self.k.iter().flatten() {
+            checker.element(element, None);
+        }
+
+        let mut issues = checker.issues;
+
+        let mut pages = tagging.iter().collect::<Vec<_>>();
+        pages.sort_by_key(|(page, _)| page_indices.get(page));
+
+        for (&page, tagging) in pages {
+            let mut unreferenced = tagging
+                .mcids
+                .iter()
+                .filter(|&&mcid| !checker.referenced.contains(&(page, mcid)))
+                .collect::<Vec<&i32>>();
+            unreferenced.sort_unstable();
+
+            issues.extend(unreferenced.into_iter().map(|&mcid| AccessibilityIssue {
+                element: None,
+                structure_type: None,
+                page: page_indices.get(&page).copied(),
+                problem: AccessibilityProblem::UnreferencedContent(mcid),
+            }));
+        }
+
+        issues
+    }
+}
+
+struct AccessibilityChecker<'r, 'a> {
+    root: &'r StructTreeRoot<'a>,
+    page_indices: &'r HashMap<Reference, usize>,
+
+    /// The page and marked-content identifier of each sequence which a
+    /// structure element refers to
+    referenced: HashSet<(Reference, i32)>,
+
+    /// The level of the last numbered heading, in logical order
+    previous_heading: Option<u8>,
+
+    issues: Vec<AccessibilityIssue>,
+}
+
+impl<'r, 'a> AccessibilityChecker<'r, 'a> {
+    fn element(&mut self, element: &StructureElement<'a>, pg: Option<Reference>) {
+        let pg = element.pg.or(pg);
+
+        let heading_level = match self.root.standard_type(&element.s) {
+            Some(StandardStructureType::Heading1) => Some(1),
+            Some(StandardStructureType::Heading2) => Some(2),
+            Some(StandardStructureType::Heading3) => Some(3),
+            Some(StandardStructureType::Heading4) => Some(4),
+            Some(StandardStructureType::Heading5) => Some(5),
+            Some(StandardStructureType::Heading6) => Some(6),
+            _ => None,
+        };
+
+        if let Some(level) = heading_level {
+            let previous = self.previous_heading.unwrap_or(0);
+
+            if level > previous + 1 {
+                self.report(
+                    element,
+                    pg,
+                    AccessibilityProblem::SkippedHeadingLevel { level, previous },
+                );
+            }
+
+            self.previous_heading = Some(level);
+        }
+
+        if let Some(StandardStructureType::Figure | StandardStructureType::Formula) =
+            self.root.standard_type(&element.s)
+        {
+            if element.alt.is_none() && element.actual_text.is_none() {
+                self.report(element, pg, AccessibilityProblem::MissingAlternateText);
+            }
+        }
+
+        for child in element.k.iter().flatten() {
+            match child {
+                StructureElementChild::StructureElement(child) => self.element(child, pg),
+                StructureElementChild::MarkedContentIdentifier(mcid) => {
+                    if let Some(pg) = pg {
+                        self.referenced.insert((pg, *mcid));
+                    }
+                }
+                // sequences in other content streams, such as those of form
+                // XObjects, are not checked
+                StructureElementChild::MarkedContentReferenceDictionary(mcr) => {
+                    if let (None, Some(pg)) = (mcr.stm, mcr.pg.or(pg)) {
+                        self.referenced.insert((pg, mcr.mcid));
+                    }
+                }
+                StructureElementChild::ObjectReferenceDictionary(..) => {}
+            }
+        }
+    }
+
+    fn report(
+        &mut self,
+        element: &StructureElement<'a>,
+        pg: Option<Reference>,
+        problem: AccessibilityProblem,
+    ) {
+        let page = pg
+            .or_else(|| first_content_page(element))
+            .and_then(|pg| self.page_indices.get(&pg).copied());
+
+        self.issues.push(AccessibilityIssue {
+            element: element.reference,
+            structure_type: Some(element.s.name().to_owned()),
+            page,
+            problem,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use crate::xref::XrefParser;
+
+    use super::*;
+
+    #[test]
+    fn untagged_text_outside_artifacts() {
+        let buffer = b"/P <</MCID 0>> BDC BT (Tagged) Tj ET EMC \
+            /Artifact BMC (page 1) Tj EMC \
+            /Span BMC [(Un) -20 (tagged)] TJ EMC";
+
+        let tagging = ContentTagging::from_content(
+            ContentLexer::new(Cow::Borrowed(buffer)),
+            None,
+            &mut XrefParser::new(Vec::new()),
+        )
+        .unwrap();
+
+        assert_eq!(tagging.mcids, HashSet::from([0]));
+        assert_eq!(tagging.untagged_text(), Some("Untagged"));
+    }
+}
@@ -0,0 +1,359 @@
+use crate::{
+    data_structures::Rectangle,
+    error::PdfResult,
+    objects::{Dictionary, Object},
+    FromObj, Resolve,
+};
+
+/// An attribute object, holding additional information about a structure
+/// element as interpreted by the application or plug-in extension named by
+/// its owner
+#[derive(Debug, Clone)]
+pub(crate) enum AttributeObject<'a> {
+    Layout(LayoutAttributes<'a>),
+    List(ListAttributes<'a>),
+    PrintField(PrintFieldAttributes<'a>),
+    Table(TableAttributes<'a>),
+
+    /// The attributes of any other owner, such as XML, HTML, CSS, or user
+    /// properties, which are kept uninterpreted
+    Other {
+        owner: String,
+        attributes: Dictionary<'a>,
+    },
+}
+
+impl<'a> FromObj<'a> for AttributeObject<'a> {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let mut dict = match resolver.resolve(obj)? {
+            Object::Dictionary(dict) => dict,
+            // the attributes of a stream are the entries of its dictionary, and
+            // its data is left to the owner to interpret
+            Object::Stream(stream) => stream.dict.other,
+            obj => anyhow::bail!("expected attribute object, found {:?}", obj),
+        };
+
+        let owner = dict.expect_name("O", resolver)?;
+        let obj = Object::Dictionary(dict);
+
+        Ok(match owner.as_str() {
+            "Layout" => AttributeObject::Layout(LayoutAttributes::from_obj(obj, resolver)?),
+            "List" => AttributeObject::List(ListAttributes::from_obj(obj, resolver)?),
+            "PrintField" => {
+                AttributeObject::PrintField(PrintFieldAttributes::from_obj(obj, resolver)?)
+            }
+            "Table" => AttributeObject::Table(TableAttributes::from_obj(obj, resolver)?),
+            _ => AttributeObject::Other {
+                owner,
+                attributes: resolver.assert_dict(obj)?,
+            },
+        })
+    }
+}
+
+/// One or more attribute objects, each of which may be followed by an integer
+/// revision number, as given by the A entry of a structure element or the
+/// values of the class map
+///
+/// Revision numbers are discarded, since attributes are never stale unless the
+/// structure tree is edited.
+pub(crate) fn attribute_objects<'a>(
+    obj: Object<'a>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Vec<AttributeObject<'a>>> {
+    Ok(match resolver.resolve(obj)? {
+        Object::Array(arr) => arr
+            .into_iter()
+            .filter(|obj| !matches!(obj, Object::Integer(..)))
+            .map(|obj| AttributeObject::from_obj(obj, resolver))
+            .collect::<PdfResult<Vec<AttributeObject>>>()?,
+        obj => vec![AttributeObject::from_obj(obj, resolver)?],
+    })
+}
+
+/// One or more attribute class names, each of which may be followed by an
+/// integer revision number, as given by the C entry of a structure element
+pub(crate) fn attribute_classes<'a>(
+    obj: Object<'a>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Vec<String>> {
+    Ok(match resolver.resolve(obj)? {
+        Object::Array(arr) => arr
+            .into_iter()
+            .filter(|obj| !matches!(obj, Object::Integer(..)))
+            .map(|obj| resolver.assert_name(obj))
+            .collect::<PdfResult<Vec<String>>>()?,
+        obj => vec![resolver.assert_name(obj)?],
+    })
+}
+
+/// Standard layout attributes, specifying parameters of the layout process
+/// used to produce the appearance described by a document's content
+// todo: WritingMode, colours, borders, padding, Width, Height, and the
+// inline-level and column attributes
+#[derive(Debug, Clone, FromObj)]
+pub(crate) struct LayoutAttributes<'a> {
+    /// The positioning of the element with respect to the enclosing reference
+    /// area and other content
+    ///
+    /// Default value: Inline
+    #[field("Placement", default = Placement::Inline)]
+    pub placement: Placement,
+
+    /// The amount of extra space preceding the before edge of a block-level
+    /// element, in the block-progression direction
+    ///
+    /// Default value: 0
+    #[field("SpaceBefore", default = 0.0)]
+    pub space_before: f32,
+
+    /// The amount of extra space following the after edge of a block-level
+    /// element, in the block-progression direction
+    ///
+    /// Default value: 0
+    #[field("SpaceAfter", default = 0.0)]
+    pub space_after: f32,
+
+    /// The distance from the start edge of the reference area to that of a
+    /// block-level element, in the inline-progression direction
+    ///
+    /// Default value: 0
+    #[field("StartIndent", default = 0.0)]
+    pub start_indent: f32,
+
+    /// The distance from the end edge of a block-level element to that of the
+    /// reference area, in the inline-progression direction
+    ///
+    /// Default value: 0
+    #[field("EndIndent", default = 0.0)]
+    pub end_indent: f32,
+
+    /// The additional distance from the start edge of a paragraph to that of
+    /// its first line, in the inline-progression direction
+    ///
+    /// Default value: 0
+    #[field("TextIndent", default = 0.0)]
+    pub text_indent: f32,
+
+    /// The alignment, in the inline-progression direction, of text and other
+    /// content within lines of a block-level element
+    ///
+    /// Default value: Start
+    #[field("TextAlign", default = TextAlign::Start)]
+    pub text_align: TextAlign,
+
+    /// The element's bounding box, in the default user space coordinates of
+    /// the page on which its first content appears
+    ///
+    /// Required for figures, formulas, and tables which are not inline
+    #[field("BBox")]
+    pub bbox: Option<Rectangle>,
+
+    #[field]
+    other: Dictionary<'a>,
+}
+
+#[pdf_enum]
+pub(crate) enum Placement {
+    /// Stacked in the block-progression direction within an enclosing reference
+    /// area or parent BLSE
+    Block = "Block",
+
+    /// Packed in the inline-progression direction within an enclosing BLSE
+    Inline = "Inline",
+
+    /// Placed so that the before edge of the element's allocation rectangle
+    /// coincides with that of the nearest enclosing reference area
+    Before = "Before",
+
+    /// Placed so that the start edge of the element's allocation rectangle
+    /// coincides with that of the nearest enclosing reference area
+    Start = "Start",
+
+    /// Placed so that the end edge of the element's allocation rectangle
+    /// coincides with that of the nearest enclosing reference area
+    End = "End",
+}
+
+#[pdf_enum]
+pub(crate) enum TextAlign {
+    /// Aligned with the start edge
+    Start = "Start",
+
+    /// Centred between the start and end edges
+    Center = "Center",
+
+    /// Aligned with the end edge
+    End = "End",
+
+    /// Aligned with both the start and end edges, with internal spacing within
+    /// each line expanded, if necessary, to achieve such alignment
+    Justify = "Justify",
+}
+
+/// Standard list attributes, which may appear in a structure element of type L
+#[derive(Debug, Clone, FromObj)]
+pub(crate) struct ListAttributes<'a> {
+    /// The numbering system used to generate the content of the Lbl elements
+    /// in an autonumbered list, or the symbol used to identify each item in an
+    /// unnumbered list
+    ///
+    /// Default value: None
+    #[field("ListNumbering", default = ListNumbering::None)]
+    pub list_numbering: ListNumbering,
+
+    #[field]
+    other: Dictionary<'a>,
+}
+
+/// The numbering system of a list
+#[pdf_enum]
+pub enum ListNumbering {
+    /// No autonumbering; Lbl elements, if present, contain arbitrary text not
+    /// subject to any numbering scheme
+    None = "None",
+
+    /// Solid circular bullet
+    Disc = "Disc",
+
+    /// Open circular bullet
+    Circle = "Circle",
+
+    /// Solid square bullet
+    Square = "Square",
+
+    /// Decimal arabic numerals (1–9, 10–99, …)
+    Decimal = "Decimal",
+
+    /// Uppercase roman numerals (I, II, III, IV, …)
+    UpperRoman = "UpperRoman",
+
+    /// Lowercase roman numerals (i, ii, iii, iv, …)
+    LowerRoman = "LowerRoman",
+
+    /// Uppercase letters (A, B, C, …)
+    UpperAlpha = "UpperAlpha",
+
+    /// Lowercase letters (a, b, c, …)
+    LowerAlpha = "LowerAlpha",
+}
+
+/// Standard table attributes, which may appear in the structure elements of a
+/// table, and in particular in its TH and TD cells
+#[derive(Debug, Clone, FromObj)]
+pub(crate) struct TableAttributes<'a> {
+    /// The number of rows in the enclosing table that shall be spanned by the
+    /// cell
+    ///
+    /// Default value: 1
+    #[field("RowSpan", default = 1)]
+    pub row_span: u32,
+
+    /// The number of columns in the enclosing table that shall be spanned by
+    /// the cell
+    ///
+    /// Default value: 1
+    #[field("ColSpan", default = 1)]
+    pub col_span: u32,
+
+    /// The element identifiers of the TH elements which are headers for the
+    /// cell, in addition to those implied by their Scope
+    ///
+    /// Default value: []
+    #[field("Headers", default = Vec::new())]
+    pub headers: Vec<String>,
+
+    /// Whether a header cell applies to the rest of the cells in its row, its
+    /// column, or both
+    #[field("Scope")]
+    pub scope: Option<TableScope>,
+
+    /// A summary of the table's purpose and structure, for use in non-visual
+    /// rendering
+    #[field("Summary")]
+    pub summary: Option<String>,
+
+    #[field]
+    other: Dictionary<'a>,
+}
+
+#[pdf_enum]
+pub(crate) enum TableScope {
+    Row = "Row",
+    Column = "Column",
+    Both = "Both",
+}
+
+/// Attributes of the structure elements of form fields which are not
+/// interactive, such as those of a printed form converted to PDF
+#[derive(Debug, Clone, FromObj)]
+pub(crate) struct PrintFieldAttributes<'a> {
+    /// The type of form field represented by the element
+    #[field("Role")]
+    pub role: Option<PrintFieldRole>,
+
+    /// The state of a radio button or check box field
+    ///
+    /// Default value: off
+    #[field("checked", default = CheckedState::Off)]
+    pub checked: CheckedState,
+
+    /// The alternate name of the field, similar to the TU entry of an
+    /// interactive form field
+    #[field("Desc")]
+    pub desc: Option<String>,
+
+    #[field]
+    other: Dictionary<'a>,
+}
+
+#[pdf_enum]
+pub(crate) enum PrintFieldRole {
+    RadioButton = "rb",
+    CheckBox = "cb",
+    PushButton = "pb",
+    TextValue = "tv",
+}
+
+#[pdf_enum]
+pub(crate) enum CheckedState {
+    On = "on",
+    Off = "off",
+    Neutral = "neutral",
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{tokenizer::Tokenizer, xref::XrefParser};
+
+    use super::*;
+
+    #[test]
+    fn attribute_objects_with_revisions() {
+        let obj = Tokenizer::new(
+            b"[<< /O /Table /ColSpan 2 /Headers [(h1)] >> 3 \
+                << /O /List /ListNumbering /LowerRoman >> \
+                << /O /CSS-2.00 /color (red) >> \
+                << /O /Layout /Placement /Block /Length 4 >> stream\ndata\nendstream]",
+        )
+        .next_object()
+        .unwrap()
+        .unwrap()
+        .value;
+
+        let attributes = attribute_objects(obj, &mut XrefParser::new(Vec::new())).unwrap();
+
+        match attributes.as_slice() {
+            [AttributeObject::Table(table), AttributeObject::List(list), AttributeObject::Other { owner, .. }, AttributeObject::Layout(layout)] =>
+            {
+                assert_eq!(table.col_span, 2);
+                assert_eq!(table.row_span, 1);
+                assert_eq!(table.headers, vec!["h1".to_owned()]);
+                assert_eq!(list.list_numbering, ListNumbering::LowerRoman);
+                assert_eq!(owner, "CSS-2.00");
+                assert_eq!(layout.placement, Placement::Block);
+            }
+            attributes => panic!("unexpected attributes {:?}", attributes),
+        }
+    }
+}
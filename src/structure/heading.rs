@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use crate::objects::Reference;
+
+use super::{
+    list::{BlockBuilder, Nested},
+    MarkedContentText, StandardStructureType, StructTreeRoot, StructureElement,
+    StructureElementChild,
+};
+
+/// Headings nested more deeply than this are not collected
+const MAX_HEADING_LEVEL: u8 = 3;
+
+/// A heading (structure type H or H1 through H3) of a tagged document
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StructureHeading {
+    /// The level of the heading, where 1 is the outermost
+    pub level: u8,
+    pub title: String,
+
+    /// The page on which the heading is shown, if known
+    pub pg: Option<Reference>,
+}
+
+impl<'a> StructTreeRoot<'a> {
+    /// Every heading in the structure tree, in logical order
+    ///
+    /// The level of a strongly structured heading (H) is the number of sections
+    /// (Sect) enclosing it.
+    pub(crate) fn headings(
+        &self,
+        text: &HashMap<Reference, MarkedContentText>,
+    ) -> Vec<StructureHeading> {
+        let builder = BlockBuilder::new(self, text);
+
+        let mut headings = Vec::new();
+
+        for element in self.k.iter().flatten() {
+            self.find_headings(&builder, element, None, 0, &mut headings);
+        }
+
+        headings
+    }
+
+    fn find_headings(
+        &self,
+        builder: &BlockBuilder<'_, 'a>,
+        element: &StructureElement<'a>,
+        pg: Option<Reference>,
+        mut section_depth: u8,
+        headings: &mut Vec<StructureHeading>,
+    ) {
+        let pg = element.pg.or(pg);
+
+        let level = match self.standard_type(&element.s) {
+            Some(StandardStructureType::Heading) => Some(section_depth.max(1)),
+            Some(StandardStructureType::Heading1) => Some(1),
+            Some(StandardStructureType::Heading2) => Some(2),
+            Some(StandardStructureType::Heading3) => Some(3),
+            Some(StandardStructureType::Section) => {
+                section_depth = section_depth.saturating_add(1);
+                None
+            }
+            _ => None,
+        };
+
+        if let Some(level) = level {
+            let title = builder.text(element, pg, &mut Nested::default());
+
+            if level <= MAX_HEADING_LEVEL && !title.is_empty() {
+                headings.push(StructureHeading {
+                    level,
+                    title,
+                    pg: pg.or_else(|| first_content_page(element)),
+                });
+            }
+
+            return;
+        }
+
+        for child in element.k.iter().flatten() {
+            if let StructureElementChild::StructureElement(child) = child {
+                self.find_headings(builder, child, pg, section_depth, headings);
+            }
+        }
+    }
+}
+
+/// The page of the first marked-content reference within `element` which
+/// specifies one
+pub(super) fn first_content_page(element: &StructureElement) -> Option<Reference> {
+    element.k.iter().flatten().find_map(|child| match child {
+        StructureElementChild::StructureElement(child) => {
+            child.pg.or_else(|| first_content_page(child))
+        }
+        StructureElementChild::MarkedContentReferenceDictionary(mcr) => mcr.pg,
+        StructureElementChild::ObjectReferenceDictionary(obj) => obj.pg,
+        StructureElementChild::MarkedContentIdentifier(..) => None,
+    })
+}
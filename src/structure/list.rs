@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+
+use crate::objects::Reference;
+
+use super::{
+    ListNumbering, MarkedContentText, StandardStructureType, StructTreeRoot, StructureElement,
+    StructureElementChild,
+};
+
+/// A list or table of contents reconstructed from the structure tree of a
+/// tagged document
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructureBlock {
+    List(List),
+    TableOfContents(TableOfContents),
+}
+
+/// A sequence of items of like meaning and importance (structure type L)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct List {
+    /// The text of the list's caption, if it has one
+    pub caption: Option<String>,
+
+    /// The numbering system of the list, if given by its list attributes
+    pub numbering: Option<ListNumbering>,
+    pub items: Vec<ListItem>,
+}
+
+/// An individual member of a list (structure type LI)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ListItem {
+    /// The bullet, name, or number identifying this item (structure type Lbl)
+    pub label: Option<String>,
+
+    /// The descriptive content of this item (structure type LBody), not
+    /// including any nested lists
+    pub body: String,
+
+    /// Lists nested within this item
+    pub sublists: Vec<List>,
+}
+
+/// A table of contents (structure type TOC)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TableOfContents {
+    pub items: Vec<TocItem>,
+}
+
+/// An individual member of a table of contents (structure type TOCI)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TocItem {
+    pub label: Option<String>,
+
+    /// The title of the referenced content, typically including the page
+    /// number at which it can be found
+    pub text: String,
+
+    /// Entries of any table of contents nested within this one
+    pub children: Vec<TocItem>,
+}
+
+/// Content that is gathered while collecting the text of an element, rather
+/// than becoming part of that text
+#[derive(Default)]
+pub(super) struct Nested {
+    lists: Vec<List>,
+    tables_of_contents: Vec<TableOfContents>,
+}
+
+/// Collects the text of structure elements from the marked-content text of
+/// each page
+pub(super) struct BlockBuilder<'r, 'a> {
+    pub root: &'r StructTreeRoot<'a>,
+    text: &'r HashMap<Reference, MarkedContentText>,
+}
+
+impl<'a> StructTreeRoot<'a> {
+    /// Every top-level list and table of contents in the structure tree, in
+    /// logical order
+    ///
+    /// `text` contains the marked-content text of each page of the document.
+    pub(crate) fn blocks(
+        &self,
+        text: &HashMap<Reference, MarkedContentText>,
+    ) -> Vec<StructureBlock> {
+        let builder = BlockBuilder::new(self, text);
+
+        let mut blocks = Vec::new();
+
+        for element in self.k.iter().flatten() {
+            builder.find_blocks(element, None, &mut blocks);
+        }
+
+        blocks
+    }
+}
+
+impl<'r, 'a> BlockBuilder<'r, 'a> {
+    pub(super) fn new(
+        root: &'r StructTreeRoot<'a>,
+        text: &'r HashMap<Reference, MarkedContentText>,
+    ) -> Self {
+        Self { root, text }
+    }
+
+    fn find_blocks(
+        &self,
+        element: &StructureElement<'a>,
+        pg: Option<Reference>,
+        blocks: &mut Vec<StructureBlock>,
+    ) {
+        let pg = element.pg.or(pg);
+
+        match self.root.standard_type(&element.s) {
+            Some(StandardStructureType::List) => {
+                blocks.push(StructureBlock::List(self.list(element, pg)));
+                return;
+            }
+            Some(StandardStructureType::TableOfContents) => {
+                blocks.push(StructureBlock::TableOfContents(
+                    self.table_of_contents(element, pg),
+                ));
+                return;
+            }
+            _ => {}
+        }
+
+        for child in element.k.iter().flatten() {
+            if let StructureElementChild::StructureElement(child) = child {
+                self.find_blocks(child, pg, blocks);
+            }
+        }
+    }
+
+    pub(super) fn list(&self, element: &StructureElement<'a>, pg: Option<Reference>) -> List {
+        let mut list = List {
+            numbering: self.root.list_numbering(element),
+            ..List::default()
+        };
+
+        for (child, pg) in child_elements(element, pg) {
+            match self.root.standard_type(&child.s) {
+                Some(StandardStructureType::ListItem) => {
+                    list.items.push(self.list_item(child, pg));
+                }
+                Some(StandardStructureType::Caption) => {
+                    list.caption = Some(self.text(child, pg, &mut Nested::default()));
+                }
+                // Not permitted by the spec, but commonly produced: a nested list as
+                // a sibling of the item it belongs to
+                Some(StandardStructureType::List) => {
+                    let sublist = self.list(child, pg);
+
+                    match list.items.last_mut() {
+                        Some(item) => item.sublists.push(sublist),
+                        None => list.items.push(ListItem {
+                            sublists: vec![sublist],
+                            ..ListItem::default()
+                        }),
+                    }
+                }
+                _ => {
+                    let mut nested = Nested::default();
+                    let body = self.text(child, pg, &mut nested);
+
+                    list.items.push(ListItem {
+                        label: None,
+                        body,
+                        sublists: nested.lists,
+                    });
+                }
+            }
+        }
+
+        list
+    }
+
+    fn list_item(&self, element: &StructureElement<'a>, pg: Option<Reference>) -> ListItem {
+        let mut label = None;
+        let mut nested = Nested::default();
+        let mut body = String::new();
+
+        for child in element.k.iter().flatten() {
+            let text = match child {
+                StructureElementChild::StructureElement(child) => {
+                    let pg = child.pg.or(pg);
+
+                    if let Some(StandardStructureType::Label) = self.root.standard_type(&child.s) {
+                        label = Some(self.text(child, pg, &mut nested));
+                        continue;
+                    }
+
+                    self.text(child, pg, &mut nested)
+                }
+                child => self.content_item_text(child, pg),
+            };
+
+            append(&mut body, &text);
+        }
+
+        ListItem {
+            label: label.filter(|label| !label.is_empty()),
+            body,
+            sublists: nested.lists,
+        }
+    }
+
+    pub(super) fn table_of_contents(
+        &self,
+        element: &StructureElement<'a>,
+        pg: Option<Reference>,
+    ) -> TableOfContents {
+        let mut toc = TableOfContents::default();
+
+        for (child, pg) in child_elements(element, pg) {
+            match self.root.standard_type(&child.s) {
+                Some(StandardStructureType::TableOfContentsItem) => {
+                    toc.items.push(self.toc_item(child, pg));
+                }
+                Some(StandardStructureType::TableOfContents) => {
+                    let mut nested = self.table_of_contents(child, pg).items;
+
+                    match toc.items.last_mut() {
+                        Some(item) => item.children.append(&mut nested),
+                        None => toc.items.append(&mut nested),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        toc
+    }
+
+    fn toc_item(&self, element: &StructureElement<'a>, pg: Option<Reference>) -> TocItem {
+        let mut label = None;
+        let mut nested = Nested::default();
+        let mut text = String::new();
+
+        for child in element.k.iter().flatten() {
+            let child_text = match child {
+                StructureElementChild::StructureElement(child) => {
+                    let pg = child.pg.or(pg);
+
+                    if let Some(StandardStructureType::Label) = self.root.standard_type(&child.s) {
+                        label = Some(self.text(child, pg, &mut nested));
+                        continue;
+                    }
+
+                    self.text(child, pg, &mut nested)
+                }
+                child => self.content_item_text(child, pg),
+            };
+
+            append(&mut text, &child_text);
+        }
+
+        TocItem {
+            label: label.filter(|label| !label.is_empty()),
+            text,
+            children: nested
+                .tables_of_contents
+                .into_iter()
+                .flat_map(|toc| toc.items)
+                .collect(),
+        }
+    }
+
+    /// The text content of `element` and its descendants, with whitespace
+    /// normalized
+    ///
+    /// Nested lists and tables of contents are moved into `nested` rather than
+    /// contributing to the text.
+    pub(super) fn text(
+        &self,
+        element: &StructureElement<'a>,
+        pg: Option<Reference>,
+        nested: &mut Nested,
+    ) -> String {
+        let pg = element.pg.or(pg);
+
+        match self.root.standard_type(&element.s) {
+            Some(StandardStructureType::List) => {
+                nested.lists.push(self.list(element, pg));
+                return String::new();
+            }
+            Some(StandardStructureType::TableOfContents) => {
+                nested
+                    .tables_of_contents
+                    .push(self.table_of_contents(element, pg));
+                return String::new();
+            }
+            _ => {}
+        }
+
+        if let Some(actual_text) = &element.actual_text {
+            return normalize_whitespace(actual_text);
+        }
+
+        let mut text = String::new();
+
+        for child in element.k.iter().flatten() {
+            let child_text = match child {
+                StructureElementChild::StructureElement(child) => self.text(child, pg, nested),
+                child => self.content_item_text(child, pg),
+            };
+
+            append(&mut text, &child_text);
+        }
+
+        text
+    }
+
+    pub(super) fn content_item_text(
+        &self,
+        child: &StructureElementChild,
+        pg: Option<Reference>,
+    ) -> String {
+        let (pg, mcid) = match child {
+            StructureElementChild::MarkedContentIdentifier(mcid) => (pg, *mcid),
+            StructureElementChild::MarkedContentReferenceDictionary(mcr) => {
+                // todo: marked content in streams other than the page's content stream
+                if mcr.stm.is_some() {
+                    return String::new();
+                }
+
+                (mcr.pg.or(pg), mcr.mcid)
+            }
+            StructureElementChild::ObjectReferenceDictionary(..)
+            | StructureElementChild::StructureElement(..) => return String::new(),
+        };
+
+        pg.and_then(|pg| self.text.get(&pg))
+            .and_then(|text| text.get(mcid))
+            .map(normalize_whitespace)
+            .unwrap_or_default()
+    }
+}
+
+/// The child structure elements of `element`, along with the page each should
+/// be considered to be on
+pub(super) fn child_elements<'e, 'a>(
+    element: &'e StructureElement<'a>,
+    pg: Option<Reference>,
+) -> impl Iterator<Item = (&'e StructureElement<'a>, Option<Reference>)> {
+    element
+        .k
+        .iter()
+        .flatten()
+        .filter_map(move |child| match child {
+            StructureElementChild::StructureElement(child) => Some((&**child, child.pg.or(pg))),
+            _ => None,
+        })
+}
+
+pub(super) fn normalize_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub(super) fn append(buffer: &mut String, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+
+    if !buffer.is_empty() {
+        buffer.push(' ');
+    }
+
+    buffer.push_str(text);
+}
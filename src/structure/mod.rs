@@ -1,12 +1,37 @@
+use std::collections::HashMap;
+
 use crate::{
     assert_empty,
     data_structures::{NameTree, NumberTree},
     error::{ParseError, PdfResult},
-    objects::{Dictionary, Object, ObjectType, Reference},
-    FromObj, Resolve,
+    objects::{Dictionary, Name, Object, ObjectType, Reference},
+    outline::TextLine,
+    FromObj, Parser, Resolve,
+};
+
+pub(crate) use accessibility::ContentTagging;
+pub use accessibility::{AccessibilityIssue, AccessibilityProblem};
+pub use attributes::ListNumbering;
+pub use list::{List, ListItem, StructureBlock, TableOfContents, TocItem};
+pub(crate) use reading::blocks_to_text;
+pub use reading::{TextBlock, TextOrder};
+pub use table::{Table, TableCell};
+pub(crate) use text::MarkedContentText;
+
+use attributes::{
+    attribute_classes, attribute_objects, AttributeObject, LayoutAttributes, PrintFieldAttributes,
+    TableAttributes,
 };
 
-#[derive(Debug)]
+mod accessibility;
+mod attributes;
+mod heading;
+mod list;
+mod reading;
+mod table;
+mod text;
+
+#[derive(Debug, Clone)]
 pub struct StructTreeRoot<'a> {
     /// The immediate child or children of the structure tree root in
     /// the structure hierarchy. The value may be either a dictionary
@@ -44,15 +69,102 @@ pub struct StructTreeRoot<'a> {
 
     /// A dictionary that shall map the names of structure types used in the document
     /// to their approximate equivalents in the set of standard structure types
-    role_map: Option<Dictionary<'a>>,
+    role_map: Option<HashMap<String, Name>>,
 
     /// A dictionary that shall map name objects designating attribute classes to the
     /// corresponding attribute objects or arrays of attribute objects
-    class_map: Option<Dictionary<'a>>,
+    class_map: Option<HashMap<String, Vec<AttributeObject<'a>>>>,
 }
 
 impl<'a> StructTreeRoot<'a> {
     const TYPE: &'static str = "StructTreeRoot";
+
+    /// The standard structure type that `ty` is equivalent to, following the role
+    /// map for nonstandard types
+    ///
+    /// A nonstandard type may be mapped to another nonstandard type, so long as
+    /// the chain eventually ends at a standard type. Cycles are not permitted, but
+    /// we don't trust the document to have followed this rule.
+    fn standard_type(&self, ty: &StructureType) -> Option<StandardStructureType> {
+        let mut name = match ty {
+            StructureType::Standard(standard) => return Some(*standard),
+            StructureType::Other(name) => name.as_str(),
+        };
+
+        let role_map = self.role_map.as_ref()?;
+
+        for _ in 0..=role_map.len() {
+            name = &role_map.get(name)?.0;
+
+            if let Ok(standard) = StandardStructureType::from_str(name) {
+                return Some(standard);
+            }
+        }
+
+        None
+    }
+
+    /// The attribute objects of `element`, both those given directly and those
+    /// of its attribute classes
+    ///
+    /// Attributes given directly come first, since they take precedence over
+    /// those of the same name specified by a class.
+    fn attributes<'r>(
+        &'r self,
+        element: &'r StructureElement<'a>,
+    ) -> impl Iterator<Item = &'r AttributeObject<'a>> {
+        let classes = element
+            .c
+            .iter()
+            .filter_map(|class| self.class_map.as_ref()?.get(class));
+
+        element.a.iter().chain(classes.flatten())
+    }
+
+    /// The standard layout attributes of `element`, if it has any
+    fn layout_attributes<'r>(
+        &'r self,
+        element: &'r StructureElement<'a>,
+    ) -> Option<&'r LayoutAttributes<'a>> {
+        self.attributes(element)
+            .find_map(|attributes| match attributes {
+                AttributeObject::Layout(layout) => Some(layout),
+                _ => None,
+            })
+    }
+
+    /// The standard table attributes of `element`, if it has any
+    fn table_attributes<'r>(
+        &'r self,
+        element: &'r StructureElement<'a>,
+    ) -> Option<&'r TableAttributes<'a>> {
+        self.attributes(element)
+            .find_map(|attributes| match attributes {
+                AttributeObject::Table(table) => Some(table),
+                _ => None,
+            })
+    }
+
+    /// The standard print field attributes of `element`, if it has any
+    fn print_field_attributes<'r>(
+        &'r self,
+        element: &'r StructureElement<'a>,
+    ) -> Option<&'r PrintFieldAttributes<'a>> {
+        self.attributes(element)
+            .find_map(|attributes| match attributes {
+                AttributeObject::PrintField(print_field) => Some(print_field),
+                _ => None,
+            })
+    }
+
+    /// The numbering system of a list element, if given by its list attributes
+    fn list_numbering(&self, element: &StructureElement<'a>) -> Option<ListNumbering> {
+        self.attributes(element)
+            .find_map(|attributes| match attributes {
+                AttributeObject::List(list) => Some(list.list_numbering),
+                _ => None,
+            })
+    }
 }
 
 impl<'a> FromObj<'a> for StructTreeRoot<'a> {
@@ -69,8 +181,16 @@ impl<'a> FromObj<'a> for StructTreeRoot<'a> {
         let id_tree = dict.get("IdTree", resolver)?;
         let parent_tree = dict.get("ParentTree", resolver)?;
         let parent_tree_next_key = dict.get_integer("ParentTreeNextKey", resolver)?;
-        let role_map = dict.get_dict("RoleMap", resolver)?;
-        let class_map = dict.get_dict("ClassMap", resolver)?;
+        let role_map = dict.get("RoleMap", resolver)?;
+        let class_map = dict
+            .get_dict("ClassMap", resolver)?
+            .map(|class_map| {
+                class_map
+                    .entries()
+                    .map(|(class, obj)| Ok((class, attribute_objects(obj, resolver)?)))
+                    .collect::<PdfResult<HashMap<String, Vec<AttributeObject>>>>()
+            })
+            .transpose()?;
 
         assert_empty(dict);
 
@@ -85,8 +205,11 @@ impl<'a> FromObj<'a> for StructTreeRoot<'a> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct StructureElement<'a> {
+    /// The structure element dictionary, if it is an indirect object
+    reference: Option<Reference>,
+
     /// The structure type, a name object identifying the nature of the structure
     /// element and its role within the document, such as a chapter, paragraph, or footnote
     s: StructureType,
@@ -122,8 +245,7 @@ struct StructureElement<'a> {
     /// element. Each attribute object shall be either a dictionary or a stream. If the value of
     /// this entry is an array, each attribute object in the array may be followed by an integer
     /// representing its revision number
-    // todo: what type is this
-    a: Option<Object<'a>>,
+    a: Vec<AttributeObject<'a>>,
 
     /// An attribute class name or array of class names associated with this structure element.
     ///
@@ -132,8 +254,7 @@ struct StructureElement<'a> {
     ///
     /// If both the A and C entries are present and a given attribute is specified by both, the one
     /// specified by the A entry shall take precedence
-    // todo: what type is this
-    c: Option<Object<'a>>,
+    c: Vec<String>,
 
     /// The current revision number of this structure element. The value shall be a non-negative
     /// integer.
@@ -169,12 +290,26 @@ impl<'a> StructureElement<'a> {
     const TYPE: &'static str = "StructElem";
 
     pub fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Vec<Self>> {
+        let reference = reference_of(&obj);
+
         Ok(match resolver.resolve(obj)? {
             Object::Array(arr) => arr
                 .into_iter()
-                .map(|obj| StructureElement::from_dict(resolver.assert_dict(obj)?, resolver))
+                .map(|obj| {
+                    let reference = reference_of(&obj);
+                    let mut element =
+                        StructureElement::from_dict(resolver.assert_dict(obj)?, resolver)?;
+                    element.reference = reference;
+
+                    Ok(element)
+                })
                 .collect::<PdfResult<Vec<StructureElement>>>()?,
-            Object::Dictionary(dict) => vec![StructureElement::from_dict(dict, resolver)?],
+            Object::Dictionary(dict) => {
+                let mut element = StructureElement::from_dict(dict, resolver)?;
+                element.reference = reference;
+
+                vec![element]
+            }
             _ => {
                 anyhow::bail!(ParseError::MismatchedObjectTypeAny {
                     expected: &[ObjectType::Array, ObjectType::Dictionary],
@@ -196,8 +331,17 @@ impl<'a> StructureElement<'a> {
             .map(|obj| StructureElementChild::from_obj(obj, resolver))
             .transpose()?;
 
-        let a = None;
-        let c = None;
+        let a = dict
+            .get_object("A", resolver)?
+            .map(|obj| attribute_objects(obj, resolver))
+            .transpose()?
+            .unwrap_or_default();
+
+        let c = dict
+            .get_object("C", resolver)?
+            .map(|obj| attribute_classes(obj, resolver))
+            .transpose()?
+            .unwrap_or_default();
 
         let r = dict.get_unsigned_integer("R", resolver)?.unwrap_or(0);
         let t = dict.get_string("T", resolver)?;
@@ -209,6 +353,7 @@ impl<'a> StructureElement<'a> {
         assert_empty(dict);
 
         Ok(Self {
+            reference: None,
             s,
             p,
             id,
@@ -226,7 +371,7 @@ impl<'a> StructureElement<'a> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum StructureElementChild<'a> {
     StructureElement(Box<StructureElement<'a>>),
     ObjectReferenceDictionary(ObjectReferenceDictionary),
@@ -236,11 +381,21 @@ enum StructureElementChild<'a> {
 
 impl<'a> StructureElementChild<'a> {
     pub fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Vec<Self>> {
+        let reference = reference_of(&obj);
+
         Ok(match resolver.resolve(obj)? {
             Object::Integer(identifier) => {
                 vec![StructureElementChild::MarkedContentIdentifier(identifier)]
             }
-            Object::Dictionary(dict) => vec![Self::from_dict(dict, resolver)?],
+            Object::Dictionary(dict) => {
+                let mut child = Self::from_dict(dict, resolver)?;
+
+                if let Self::StructureElement(element) = &mut child {
+                    element.reference = reference;
+                }
+
+                vec![child]
+            }
             Object::Array(arr) => arr
                 .into_iter()
                 .map(|obj| Self::from_obj(obj, resolver))
@@ -280,7 +435,15 @@ impl<'a> StructureElementChild<'a> {
     }
 }
 
-#[derive(Debug, FromObj)]
+/// The reference to an object, if it is an indirect object
+fn reference_of(obj: &Object) -> Option<Reference> {
+    match obj {
+        Object::Reference(reference) => Some(*reference),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, FromObj)]
 #[obj_type("OBJR")]
 struct ObjectReferenceDictionary {
     /// The page object of the page on which the object shall be rendered. This entry
@@ -294,7 +457,7 @@ struct ObjectReferenceDictionary {
     obj: Reference,
 }
 
-#[derive(Debug, FromObj)]
+#[derive(Debug, Clone, FromObj)]
 #[obj_type("MCR")]
 struct MarkedContentReferenceDictionary {
     /// The page object representing the page on which the graphics objects in the marked-content
@@ -322,7 +485,7 @@ struct MarkedContentReferenceDictionary {
     mcid: i32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum StructureType {
     Standard(StandardStructureType),
     Other(String),
@@ -336,6 +499,14 @@ impl StructureType {
             StructureType::Other(s)
         }
     }
+
+    /// The name of the type, as given by the S entry of the element
+    pub fn name(&self) -> &str {
+        match self {
+            StructureType::Standard(standard) => standard.as_str(),
+            StructureType::Other(name) => name,
+        }
+    }
 }
 
 #[pdf_enum]
@@ -404,5 +575,315 @@ enum StandardStructureType {
     /// entirely by the conforming writer. Neither the Private element nor any of its descendants
     /// shall be interpreted or exported to other document formats.
     Private = "Private",
-    // todo: rest of std structure types
+
+    /// A paragraph, a generic block-level element consisting of text
+    Paragraph = "P",
+
+    /// A heading. For documents which do not have a strongly hierarchical structure,
+    /// the level of the heading is given by its nesting within enclosing sections
+    Heading = "H",
+
+    /// A level 1 heading
+    Heading1 = "H1",
+
+    /// A level 2 heading
+    Heading2 = "H2",
+
+    /// A level 3 heading
+    Heading3 = "H3",
+
+    /// A level 4 heading
+    Heading4 = "H4",
+
+    /// A level 5 heading
+    Heading5 = "H5",
+
+    /// A level 6 heading
+    Heading6 = "H6",
+
+    /// A sequence of items of like meaning and importance. Its immediate children
+    /// should be an optional caption (structure type Caption) followed by one or more
+    /// list items (structure type LI)
+    List = "L",
+
+    /// An individual member of a list. Its children may be one or more labels, list
+    /// bodies, or both (structure types Lbl or LBody)
+    ListItem = "LI",
+
+    /// A label, a bullet, name, or number that identifies and distinguishes an element
+    /// from others in the same list or other grouping of related elements
+    Label = "Lbl",
+
+    /// The descriptive content of a list item. In a dictionary list, for example, it
+    /// contains the definition of the term. It may either contain the content directly
+    /// or have other block-level elements, including nested lists, as children
+    ListBody = "LBody",
+
+    /// A two-dimensional layout of rectangular data cells, possibly having a complex
+    /// substructure. It contains either one or more table rows (structure type TR) as
+    /// children; or an optional table head (THead) followed by one or more table body
+    /// elements (TBody) and an optional table footer (TFoot)
+    Table = "Table",
+
+    /// A row of headings or data in a table. It may contain table header cells and
+    /// table data cells (structure types TH and TD)
+    TableRow = "TR",
+
+    /// A table cell containing header text describing one or more rows or columns of
+    /// the table
+    TableHeaderCell = "TH",
+
+    /// A table cell containing data that is part of the table's content
+    TableDataCell = "TD",
+
+    /// A group of rows that constitute the header of a table
+    TableHeader = "THead",
+
+    /// A group of rows that constitute the main body portion of a table
+    TableBody = "TBody",
+
+    /// A group of rows that constitute the footer of a table
+    TableFooter = "TFoot",
+
+    /// A generic inline portion of text having no particular inherent characteristics.
+    /// It can be used, for example, to delimit a range of text with a given set of
+    /// styling attributes
+    Span = "Span",
+
+    /// An inline portion of text attributed to someone other than the author of the
+    /// surrounding text
+    Quote = "Quote",
+
+    /// An item of explanatory text, such as a footnote or an endnote, that is referred
+    /// to from within the body of the document
+    Note = "Note",
+
+    /// A citation to content elsewhere in the document
+    Reference = "Reference",
+
+    /// A reference identifying the external source of some cited content
+    BibliographyEntry = "BibEntry",
+
+    /// A fragment of computer program text
+    Code = "Code",
+
+    /// An association between a portion of the ILSE's content and a corresponding link
+    /// annotation or annotations
+    Link = "Link",
+
+    /// An association between a portion of the ILSE's content and a corresponding PDF
+    /// annotation
+    Annotation = "Annot",
+
+    /// A side-note (annotation) written in a smaller text size and placed adjacent to
+    /// the base text to which it refers
+    Ruby = "Ruby",
+
+    /// The full-size text to which the ruby annotation is applied
+    RubyBaseText = "RB",
+
+    /// The smaller-size text that is placed adjacent to the ruby base text
+    RubyAnnotationText = "RT",
+
+    /// Punctuation surrounding the ruby annotation text
+    RubyPunctuation = "RP",
+
+    /// A comment or annotation in a smaller text size and formatted onto two smaller
+    /// lines within the height of the containing text line and placed following the
+    /// base text to which it refers
+    Warichu = "Warichu",
+
+    /// The smaller-size text of a warichu comment that is formatted into two lines
+    WarichuText = "WT",
+
+    /// The punctuation that surrounds the warichu text
+    WarichuPunctuation = "WP",
+
+    /// An item of graphical content
+    Figure = "Figure",
+
+    /// A mathematical formula
+    Formula = "Formula",
+
+    /// A widget annotation representing an interactive form field
+    Form = "Form",
+}
+
+impl<'a> Parser<'a> {
+    /// The lists and tables of contents of a tagged document, reconstructed from
+    /// its structure tree
+    ///
+    /// Documents without a structure tree have no blocks.
+    pub fn structure_blocks(&mut self) -> PdfResult<Vec<StructureBlock>> {
+        let root = match &self.catalog.struct_tree_root {
+            Some(root) => root.get_ref(&mut self.lexer)?.into_owned(),
+            None => return Ok(Vec::new()),
+        };
+
+        let text = self.marked_content_text()?;
+
+        Ok(root.blocks(&text))
+    }
+
+    /// The tables of a tagged document, reconstructed from the Table, TR, TH and
+    /// TD elements of its structure tree
+    ///
+    /// Documents without a structure tree have no tables.
+    pub fn tables(&mut self) -> PdfResult<Vec<Table>> {
+        let root = match &self.catalog.struct_tree_root {
+            Some(root) => root.get_ref(&mut self.lexer)?.into_owned(),
+            None => return Ok(Vec::new()),
+        };
+
+        let text = self.marked_content_text()?;
+
+        Ok(root.tables(&text))
+    }
+
+    /// The text of a tagged document in logical reading order, as headings,
+    /// paragraphs, lists and tables reconstructed from its structure tree
+    ///
+    /// Documents without a structure tree have no blocks.
+    pub fn text_blocks(&mut self) -> PdfResult<Vec<TextBlock>> {
+        let root = match &self.catalog.struct_tree_root {
+            Some(root) => root.get_ref(&mut self.lexer)?.into_owned(),
+            None => return Ok(Vec::new()),
+        };
+
+        let text = self.marked_content_text()?;
+
+        Ok(root.text_blocks(&text))
+    }
+
+    /// The text of every page of the document
+    ///
+    /// In content order, each line of text is given a line of its own, and
+    /// pages are separated by blank lines. In logical order, the blocks of
+    /// [`Parser::text_blocks`] are separated by blank lines.
+    pub fn extract_text(&mut self, order: TextOrder) -> PdfResult<String> {
+        if order == TextOrder::Logical && self.catalog.struct_tree_root.is_some() {
+            return Ok(blocks_to_text(&self.text_blocks()?));
+        }
+
+        let mut pages = Vec::new();
+
+        for page in self.pages() {
+            if page.contents.is_none() {
+                pages.push(String::new());
+                continue;
+            }
+
+            let content = self.page_contents(&page)?;
+
+            let lines = TextLine::from_content(content, &mut self.lexer)?
+                .into_iter()
+                .map(|line| line.text)
+                .collect::<Vec<String>>();
+
+            pages.push(lines.join("\n"));
+        }
+
+        Ok(pages.join("\n\n"))
+    }
+
+    /// The marked-content text of each page, keyed by the reference to its page
+    /// object
+    pub(crate) fn marked_content_text(
+        &mut self,
+    ) -> PdfResult<HashMap<Reference, MarkedContentText>> {
+        let mut text = HashMap::new();
+
+        for (reference, page) in self.page_references() {
+            if page.contents.is_none() {
+                continue;
+            }
+
+            let content = self.page_contents(&page)?;
+
+            text.insert(
+                reference,
+                MarkedContentText::from_content(
+                    content,
+                    page.resources().as_deref(),
+                    &mut self.lexer,
+                )?,
+            );
+        }
+
+        Ok(text)
+    }
+
+    /// Check the document against the requirements of PDF/UA (ISO 14289) for
+    /// accessible documents, reporting each failure
+    ///
+    /// The document must be tagged and specify its language. The text of each
+    /// page must be tagged or marked as an artifact, and each marked-content
+    /// sequence with an identifier must belong to a structure element. Figures
+    /// and formulas must have alternate text, and numbered headings must not
+    /// skip levels. Problems with the structure tree are reported against the
+    /// structure element concerned.
+    pub fn check_accessibility(&mut self) -> PdfResult<Vec<AccessibilityIssue>> {
+        let mut issues = Vec::new();
+
+        let is_marked = self
+            .catalog
+            .mark_info
+            .as_ref()
+            .map_or(false, |mark_info| mark_info.marked);
+
+        let document_issue = |problem| AccessibilityIssue {
+            element: None,
+            structure_type: None,
+            page: None,
+            problem,
+        };
+
+        if !is_marked || self.catalog.struct_tree_root.is_none() {
+            issues.push(document_issue(AccessibilityProblem::NotTagged));
+        }
+
+        if self.catalog.lang.is_none() {
+            issues.push(document_issue(AccessibilityProblem::MissingLanguage));
+        }
+
+        let page_indices = self.page_indices();
+        let mut tagging = HashMap::new();
+
+        for (reference, page) in self.page_references() {
+            if page.contents.is_none() {
+                continue;
+            }
+
+            let content = self.page_contents(&page)?;
+
+            tagging.insert(
+                reference,
+                ContentTagging::from_content(
+                    content,
+                    page.resources().as_deref(),
+                    &mut self.lexer,
+                )?,
+            );
+        }
+
+        let mut pages = tagging.iter().collect::<Vec<_>>();
+        pages.sort_by_key(|(page, _)| page_indices.get(page));
+
+        for (page, tagging) in pages {
+            if let Some(text) = tagging.untagged_text() {
+                issues.push(AccessibilityIssue {
+                    page: page_indices.get(page).copied(),
+                    ..document_issue(AccessibilityProblem::UntaggedContent(text.to_owned()))
+                });
+            }
+        }
+
+        if let Some(root) = &self.catalog.struct_tree_root {
+            let root = root.get_ref(&mut self.lexer)?.into_owned();
+
+            issues.extend(root.accessibility_issues(&tagging, &page_indices));
+        }
+
+        Ok(issues)
+    }
 }
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use crate::objects::Reference;
+
+use super::{
+    list::{append, normalize_whitespace, BlockBuilder, Nested},
+    List, MarkedContentText, StandardStructureType, StructTreeRoot, StructureElement,
+    StructureElementChild, Table, TableOfContents, TocItem,
+};
+
+/// The order in which the text of a document is extracted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextOrder {
+    /// The order in which text is shown by the content stream of each page,
+    /// which for multi-column layouts may interleave the columns
+    #[default]
+    Content,
+
+    /// The logical reading order given by the structure tree of a tagged
+    /// document, falling back to content order for untagged documents
+    ///
+    /// Content which is not part of the structure tree, such as running
+    /// headers and page numbers marked as artifacts, is omitted.
+    Logical,
+}
+
+/// A block of text of a tagged document, in logical reading order
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextBlock {
+    Heading {
+        /// The level of the heading, where 1 is the outermost
+        level: u8,
+        text: String,
+    },
+
+    /// A paragraph, or other run of text which is not part of a more specific
+    /// block
+    Paragraph(String),
+
+    List(List),
+    TableOfContents(TableOfContents),
+
+    Table(Table),
+}
+
+impl<'a> StructTreeRoot<'a> {
+    /// The text of the structure tree as blocks, in logical order
+    ///
+    /// `text` contains the marked-content text of each page of the document.
+    pub(crate) fn text_blocks(
+        &self,
+        text: &HashMap<Reference, MarkedContentText>,
+    ) -> Vec<TextBlock> {
+        let mut reader = BlockReader {
+            builder: BlockBuilder::new(self, text),
+            blocks: Vec::new(),
+            paragraph: String::new(),
+        };
+
+        for element in self.k.iter().flatten() {
+            reader.element(element, None, 0);
+        }
+
+        reader.end_paragraph();
+
+        reader.blocks
+    }
+}
+
+/// Collects the blocks of the structure tree, gathering the text of inline
+/// elements and content items which are not within a paragraph into one
+struct BlockReader<'r, 'a> {
+    builder: BlockBuilder<'r, 'a>,
+    blocks: Vec<TextBlock>,
+
+    /// The text of inline content since the last block
+    paragraph: String,
+}
+
+impl<'r, 'a> BlockReader<'r, 'a> {
+    fn element(&mut self, element: &StructureElement<'a>, pg: Option<Reference>, depth: u8) {
+        let pg = element.pg.or(pg);
+        let root = self.builder.root;
+
+        let heading_level = match root.standard_type(&element.s) {
+            Some(StandardStructureType::Heading) => Some(depth.max(1)),
+            Some(StandardStructureType::Heading1) => Some(1),
+            Some(StandardStructureType::Heading2) => Some(2),
+            Some(StandardStructureType::Heading3) => Some(3),
+            Some(StandardStructureType::Heading4) => Some(4),
+            Some(StandardStructureType::Heading5) => Some(5),
+            Some(StandardStructureType::Heading6) => Some(6),
+            _ => None,
+        };
+
+        if let Some(level) = heading_level {
+            let text = self.text(element, pg);
+            self.push(TextBlock::Heading { level, text });
+            return;
+        }
+
+        match root.standard_type(&element.s) {
+            Some(StandardStructureType::Paragraph | StandardStructureType::Caption) => {
+                let text = self.text(element, pg);
+                self.push(TextBlock::Paragraph(text));
+            }
+            Some(StandardStructureType::Figure | StandardStructureType::Formula) => {
+                // represented by their alternate description, if they have no
+                // replacement text
+                let text = match (&element.actual_text, &element.alt) {
+                    (None, Some(alt)) => normalize_whitespace(alt),
+                    _ => self.text(element, pg),
+                };
+
+                self.push(TextBlock::Paragraph(text));
+            }
+            Some(StandardStructureType::List) => {
+                let list = self.builder.list(element, pg);
+                self.push(TextBlock::List(list));
+            }
+            Some(StandardStructureType::TableOfContents) => {
+                let toc = self.builder.table_of_contents(element, pg);
+                self.push(TextBlock::TableOfContents(toc));
+            }
+            Some(StandardStructureType::Table) => {
+                let table = self.builder.table(element, pg);
+                self.push(TextBlock::Table(table));
+            }
+            Some(StandardStructureType::Private) => {}
+            Some(
+                StandardStructureType::Span
+                | StandardStructureType::Quote
+                | StandardStructureType::Note
+                | StandardStructureType::Reference
+                | StandardStructureType::BibliographyEntry
+                | StandardStructureType::Code
+                | StandardStructureType::Link
+                | StandardStructureType::Annotation
+                | StandardStructureType::Ruby
+                | StandardStructureType::Warichu
+                | StandardStructureType::Label
+                | StandardStructureType::Form,
+            ) => {
+                let text = self.text(element, pg);
+                append(&mut self.paragraph, &text);
+            }
+            standard_type => {
+                // a grouping element, whose children are read in turn
+                let depth = match standard_type {
+                    Some(StandardStructureType::Section) => depth.saturating_add(1),
+                    _ => depth,
+                };
+
+                if let Some(actual_text) = &element.actual_text {
+                    self.push(TextBlock::Paragraph(normalize_whitespace(actual_text)));
+                    return;
+                }
+
+                for child in element.k.iter().flatten() {
+                    match child {
+                        StructureElementChild::StructureElement(child) => {
+                            self.element(child, pg, depth)
+                        }
+                        child => {
+                            let text = self.builder.content_item_text(child, pg);
+                            append(&mut self.paragraph, &text);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn text(&self, element: &StructureElement<'a>, pg: Option<Reference>) -> String {
+        self.builder.text(element, pg, &mut Nested::default())
+    }
+
+    fn push(&mut self, block: TextBlock) {
+        self.end_paragraph();
+
+        let is_empty = match &block {
+            TextBlock::Heading { text, .. } | TextBlock::Paragraph(text) => text.is_empty(),
+            TextBlock::List(list) => list.items.is_empty(),
+            TextBlock::TableOfContents(toc) => toc.items.is_empty(),
+            TextBlock::Table(table) => table.rows.is_empty(),
+        };
+
+        if !is_empty {
+            self.blocks.push(block);
+        }
+    }
+
+    fn end_paragraph(&mut self) {
+        if !self.paragraph.is_empty() {
+            let text = std::mem::take(&mut self.paragraph);
+            self.blocks.push(TextBlock::Paragraph(text));
+        }
+    }
+}
+
+/// The plain text of `blocks`, separated by blank lines
+///
+/// List items and table rows are given a line each, with nested lists and
+/// tables of contents indented beneath their items, and cells separated by
+/// tabs.
+pub(crate) fn blocks_to_text(blocks: &[TextBlock]) -> String {
+    let mut text = String::new();
+
+    for block in blocks {
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+
+        match block {
+            TextBlock::Heading { text: heading, .. } => text.push_str(heading),
+            TextBlock::Paragraph(paragraph) => text.push_str(paragraph),
+            TextBlock::List(list) => list_text(list, 0, &mut text),
+            TextBlock::TableOfContents(toc) => toc_text(&toc.items, 0, &mut text),
+            TextBlock::Table(table) => {
+                let rows = table
+                    .grid()
+                    .iter()
+                    .map(|row| row.join("\t"))
+                    .collect::<Vec<_>>();
+                text.push_str(&rows.join("\n"));
+            }
+        }
+    }
+
+    text
+}
+
+fn list_text(list: &List, indent: usize, text: &mut String) {
+    if let Some(caption) = &list.caption {
+        push_line(text, indent, caption);
+    }
+
+    for item in &list.items {
+        let line = match &item.label {
+            Some(label) => format!("{} {}", label, item.body),
+            None => item.body.clone(),
+        };
+
+        push_line(text, indent, &line);
+
+        for sublist in &item.sublists {
+            list_text(sublist, indent + 1, text);
+        }
+    }
+}
+
+fn toc_text(items: &[TocItem], indent: usize, text: &mut String) {
+    for item in items {
+        let line = match &item.label {
+            Some(label) => format!("{} {}", label, item.text),
+            None => item.text.clone(),
+        };
+
+        push_line(text, indent, &line);
+        toc_text(&item.children, indent + 1, text);
+    }
+}
+
+fn push_line(text: &mut String, indent: usize, line: &str) {
+    if !text.is_empty() && !text.ends_with("\n\n") {
+        text.push('\n');
+    }
+
+    for _ in 0..indent {
+        text.push_str("  ");
+    }
+
+    text.push_str(line);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::structure::ListItem;
+
+    #[test]
+    fn plain_text_of_blocks() {
+        let blocks = vec![
+            TextBlock::Heading {
+                level: 1,
+                text: "Results".to_owned(),
+            },
+            TextBlock::Paragraph("Two items were found.".to_owned()),
+            TextBlock::List(List {
+                items: vec![
+                    ListItem {
+                        label: Some("1.".to_owned()),
+                        body: "First".to_owned(),
+                        sublists: vec![List {
+                            items: vec![ListItem {
+                                body: "Detail".to_owned(),
+                                ..ListItem::default()
+                            }],
+                            ..List::default()
+                        }],
+                    },
+                    ListItem {
+                        label: Some("2.".to_owned()),
+                        body: "Second".to_owned(),
+                        sublists: Vec::new(),
+                    },
+                ],
+                ..List::default()
+            }),
+        ];
+
+        assert_eq!(
+            blocks_to_text(&blocks),
+            "Results\n\nTwo items were found.\n\n1. First\n  Detail\n2. Second"
+        );
+    }
+}
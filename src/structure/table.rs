@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+
+use crate::objects::Reference;
+
+use super::{
+    list::{child_elements, BlockBuilder, Nested},
+    MarkedContentText, StandardStructureType, StructTreeRoot, StructureElement,
+};
+
+/// A table (structure type Table) reconstructed from the structure tree of a
+/// tagged document into rows and columns
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Table {
+    /// The text of the table's caption, if it has one
+    pub caption: Option<String>,
+
+    /// A summary of the table's purpose and structure, given by its table
+    /// attributes
+    pub summary: Option<String>,
+
+    /// The cells of each row, in order
+    ///
+    /// A cell which spans several rows appears only in the first of them.
+    pub rows: Vec<Vec<TableCell>>,
+
+    /// The number of rows at the start of `rows` which form the header of the
+    /// table: those within a THead element or, if there is none, those made up
+    /// of header cells alone
+    pub header_rows: usize,
+
+    /// The number of columns of the table, taking spans into account
+    pub columns: usize,
+}
+
+/// A cell of a table (structure type TH or TD)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TableCell {
+    pub text: String,
+
+    /// Whether the cell is a header cell (TH), rather than a data cell (TD)
+    pub is_header: bool,
+
+    /// The row and column of the top left of the cell, counting from 0
+    pub row: usize,
+    pub column: usize,
+
+    /// The number of rows and columns spanned by the cell, from its RowSpan and
+    /// ColSpan attributes
+    pub row_span: usize,
+    pub col_span: usize,
+}
+
+impl Table {
+    /// The text of the cell at each row and column of the table
+    ///
+    /// The text of a cell which spans several rows or columns is given at its
+    /// top left, and the other positions it covers are empty, as are those
+    /// which no cell covers.
+    pub fn grid(&self) -> Vec<Vec<String>> {
+        let mut grid = vec![vec![String::new(); self.columns]; self.rows.len()];
+
+        for cell in self.rows.iter().flatten() {
+            grid[cell.row][cell.column] = cell.text.clone();
+        }
+
+        grid
+    }
+
+    /// The table as comma-separated values, following RFC 4180
+    ///
+    /// Each row of [`Table::grid`] is a record, terminated by CRLF. Fields
+    /// which contain commas, quotes or line breaks are quoted.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+
+        for row in self.grid() {
+            let fields = row.iter().map(|field| csv_field(field)).collect::<Vec<_>>();
+
+            csv.push_str(&fields.join(","));
+            csv.push_str("\r\n");
+        }
+
+        csv
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+impl<'a> StructTreeRoot<'a> {
+    /// Every table in the structure tree, in logical order
+    ///
+    /// `text` contains the marked-content text of each page of the document.
+    /// Tables nested within the cells of another table are not included.
+    pub(crate) fn tables(&self, text: &HashMap<Reference, MarkedContentText>) -> Vec<Table> {
+        let builder = BlockBuilder::new(self, text);
+
+        let mut tables = Vec::new();
+
+        for element in self.k.iter().flatten() {
+            builder.find_tables(element, None, &mut tables);
+        }
+
+        tables
+    }
+}
+
+/// A row of cells as they appear in the structure tree, before the cells are
+/// placed into columns
+struct RowElements<'e, 'a> {
+    cells: Vec<(&'e StructureElement<'a>, Option<Reference>)>,
+    is_header: bool,
+}
+
+impl<'r, 'a> BlockBuilder<'r, 'a> {
+    fn find_tables(
+        &self,
+        element: &StructureElement<'a>,
+        pg: Option<Reference>,
+        tables: &mut Vec<Table>,
+    ) {
+        let pg = element.pg.or(pg);
+
+        if let Some(StandardStructureType::Table) = self.root.standard_type(&element.s) {
+            tables.push(self.table(element, pg));
+            return;
+        }
+
+        for (child, pg) in child_elements(element, pg) {
+            self.find_tables(child, pg, tables);
+        }
+    }
+
+    pub(super) fn table(&self, element: &StructureElement<'a>, pg: Option<Reference>) -> Table {
+        let mut table = Table {
+            summary: self
+                .root
+                .table_attributes(element)
+                .and_then(|attributes| attributes.summary.clone()),
+            ..Table::default()
+        };
+
+        let mut rows = Vec::new();
+
+        for (child, pg) in child_elements(element, pg) {
+            match self.root.standard_type(&child.s) {
+                Some(StandardStructureType::Caption) => {
+                    table.caption = Some(self.text(child, pg, &mut Nested::default()));
+                }
+                Some(StandardStructureType::TableRow) => {
+                    rows.push(self.row_elements(child, pg, false))
+                }
+                Some(StandardStructureType::TableHeader) => {
+                    for (row, pg) in child_elements(child, pg) {
+                        rows.push(self.row_elements(row, pg, true));
+                    }
+                }
+                Some(StandardStructureType::TableBody | StandardStructureType::TableFooter) => {
+                    for (row, pg) in child_elements(child, pg) {
+                        rows.push(self.row_elements(row, pg, false));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        table.header_rows = if rows.iter().any(|row| row.is_header) {
+            rows.iter().take_while(|row| row.is_header).count()
+        } else {
+            rows.iter()
+                .take_while(|row| {
+                    !row.cells.is_empty()
+                        && row.cells.iter().all(|(cell, _)| {
+                            matches!(
+                                self.root.standard_type(&cell.s),
+                                Some(StandardStructureType::TableHeaderCell)
+                            )
+                        })
+                })
+                .count()
+        };
+
+        // the columns of each row which are covered by cells spanning down
+        // from the rows above it
+        let mut occupied: Vec<Vec<bool>> = vec![Vec::new(); rows.len()];
+
+        for (row_index, row) in rows.iter().enumerate() {
+            let mut column = 0;
+            let mut cells = Vec::with_capacity(row.cells.len());
+
+            for &(element, pg) in &row.cells {
+                while occupied[row_index].get(column).copied().unwrap_or(false) {
+                    column += 1;
+                }
+
+                let attributes = self.root.table_attributes(element);
+
+                // spans past the last row are clipped, as they are by viewers
+                let row_span = attributes
+                    .map_or(1, |attributes| attributes.row_span as usize)
+                    .clamp(1, rows.len() - row_index);
+                let col_span = attributes
+                    .map_or(1, |attributes| attributes.col_span as usize)
+                    .max(1);
+
+                for covered in &mut occupied[row_index..row_index + row_span] {
+                    if covered.len() < column + col_span {
+                        covered.resize(column + col_span, false);
+                    }
+
+                    covered[column..column + col_span].fill(true);
+                }
+
+                cells.push(TableCell {
+                    text: self.text(element, pg, &mut Nested::default()),
+                    is_header: matches!(
+                        self.root.standard_type(&element.s),
+                        Some(StandardStructureType::TableHeaderCell)
+                    ),
+                    row: row_index,
+                    column,
+                    row_span,
+                    col_span,
+                });
+
+                column += col_span;
+            }
+
+            table.rows.push(cells);
+        }
+
+        table.columns = occupied.iter().map(Vec::len).max().unwrap_or(0);
+
+        table
+    }
+
+    fn row_elements<'e>(
+        &self,
+        row: &'e StructureElement<'a>,
+        pg: Option<Reference>,
+        is_header: bool,
+    ) -> RowElements<'e, 'a> {
+        RowElements {
+            cells: child_elements(row, pg).collect(),
+            is_header,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cell(text: &str, row: usize, column: usize, row_span: usize, col_span: usize) -> TableCell {
+        TableCell {
+            text: text.to_owned(),
+            is_header: row == 0,
+            row,
+            column,
+            row_span,
+            col_span,
+        }
+    }
+
+    #[test]
+    fn spanned_cells_to_csv() {
+        let table = Table {
+            rows: vec![
+                vec![cell("Region", 0, 0, 2, 1), cell("Sales", 0, 1, 1, 2)],
+                vec![cell("2023", 1, 1, 1, 1), cell("2024", 1, 2, 1, 1)],
+                vec![
+                    cell("North, East", 2, 0, 1, 1),
+                    cell("1", 2, 1, 1, 1),
+                    cell("say \"2\"", 2, 2, 1, 1),
+                ],
+            ],
+            header_rows: 2,
+            columns: 3,
+            ..Table::default()
+        };
+
+        assert_eq!(
+            table.to_csv(),
+            "Region,Sales,\r\n,2023,2024\r\n\"North, East\",1,\"say \"\"2\"\"\"\r\n"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use crate::{
+    content::{ContentLexer, ContentToken, PdfGraphicsOperator, PropertyList},
+    error::PdfResult,
+    objects::Object,
+    resources::Resources,
+    Resolve,
+};
+
+/// The text shown within each marked-content sequence of a content stream,
+/// keyed by its marked-content identifier (MCID)
+///
+/// This is the glue between a structure tree, whose leaves refer to content by
+/// MCID, and the content stream itself. Strings are taken as-is and are not
+/// decoded through the font's encoding or ToUnicode CMap.
+///
+/// Where a marked-content sequence has an ActualText entry, that replaces the
+/// text shown within it.
+#[derive(Debug, Default)]
+pub(crate) struct MarkedContentText {
+    text: HashMap<i32, String>,
+}
+
+/// Adjustments in a TJ array more negative than this, in thousandths of a unit
+/// of text space, are treated as a word break
+const WORD_BREAK_THRESHOLD: f32 = -200.0;
+
+impl MarkedContentText {
+    pub fn from_content<'a>(
+        content: ContentLexer<'a>,
+        resources: Option<&Resources<'a>>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        #[cfg(feature = "bench")]
+        let _timer = crate::bench::StageTimer::start(crate::bench::Stage::Extract);
+
+        let mut text: HashMap<i32, String> = HashMap::new();
+
+        // The MCID of each open marked-content sequence, if it has one, and
+        // whether its text has been replaced by an ActualText entry
+        let mut stack: Vec<(Option<i32>, bool)> = Vec::new();
+        let mut operands: Vec<Object<'a>> = Vec::new();
+
+        // tokens which cannot be lexed are skipped, so that the text of the
+        // rest of the stream is still extracted
+        for token in content {
+            let operator = match token {
+                Ok(ContentToken::Object(obj)) => {
+                    operands.push(obj);
+                    continue;
+                }
+                Ok(ContentToken::Operator(operator)) => operator,
+                Ok(ContentToken::InlineImage(..)) | Err(..) => continue,
+            };
+
+            let mcid = match stack.iter().any(|&(_, is_replaced)| is_replaced) {
+                true => None,
+                false => stack.iter().rev().find_map(|&(mcid, _)| mcid),
+            };
+
+            match operator {
+                PdfGraphicsOperator::BMC => stack.push((None, false)),
+                PdfGraphicsOperator::BDC => {
+                    let properties = match operands
+                        .pop()
+                        .map(|operand| PropertyList::from_operand(operand, resources, resolver))
+                    {
+                        Some(Ok(properties)) => properties,
+                        // a malformed BDC still begins a sequence, which is
+                        // ended by the matching EMC
+                        Some(Err(..)) | None => {
+                            stack.push((None, false));
+                            operands.clear();
+                            continue;
+                        }
+                    };
+
+                    let own_mcid = properties.mcid;
+
+                    if let (Some(mcid), Some(actual_text)) =
+                        (own_mcid.or(mcid), &properties.actual_text)
+                    {
+                        text.entry(mcid).or_default().push_str(actual_text);
+                    }
+
+                    stack.push((own_mcid, properties.actual_text.is_some()));
+                }
+                PdfGraphicsOperator::EMC => {
+                    stack.pop();
+                }
+                PdfGraphicsOperator::Tj => {
+                    if let (Some(mcid), Some(Object::String(s))) = (mcid, operands.pop()) {
+                        text.entry(mcid).or_default().push_str(&s);
+                    }
+                }
+                PdfGraphicsOperator::single_quote | PdfGraphicsOperator::double_quote => {
+                    if let (Some(mcid), Some(Object::String(s))) = (mcid, operands.pop()) {
+                        let buffer = text.entry(mcid).or_default();
+
+                        push_space(buffer);
+                        buffer.push_str(&s);
+                    }
+                }
+                PdfGraphicsOperator::TJ => {
+                    if let (Some(mcid), Some(Object::Array(arr))) = (mcid, operands.pop()) {
+                        let buffer = text.entry(mcid).or_default();
+
+                        for obj in arr {
+                            match obj {
+                                Object::String(s) => buffer.push_str(&s),
+                                Object::Integer(n) if (n as f32) < WORD_BREAK_THRESHOLD => {
+                                    push_space(buffer)
+                                }
+                                Object::Real(n) if n < WORD_BREAK_THRESHOLD => push_space(buffer),
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                PdfGraphicsOperator::T_star | PdfGraphicsOperator::Td | PdfGraphicsOperator::TD => {
+                    if let Some(mcid) = mcid {
+                        push_space(text.entry(mcid).or_default());
+                    }
+                }
+                _ => {}
+            }
+
+            operands.clear();
+        }
+
+        Ok(Self { text })
+    }
+
+    pub fn get(&self, mcid: i32) -> Option<&str> {
+        self.text.get(&mcid).map(String::as_str)
+    }
+}
+
+fn push_space(buffer: &mut String) {
+    if !buffer.is_empty() && !buffer.ends_with(char::is_whitespace) {
+        buffer.push(' ');
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use crate::xref::XrefParser;
+
+    use super::*;
+
+    #[test]
+    fn text_by_mcid() {
+        let buffer = b"/P <</MCID 0>> BDC BT (Hello) Tj [(wor) -20 (ld) -500 (again)] TJ ET EMC \
+            /Artifact BMC (page 1) Tj EMC \
+            /LI <</MCID 1>> BDC (first) Tj T* (line) Tj EMC";
+
+        let text = MarkedContentText::from_content(
+            ContentLexer::new(Cow::Borrowed(buffer)),
+            None,
+            &mut XrefParser::new(Vec::new()),
+        )
+        .unwrap();
+
+        assert_eq!(text.get(0), Some("Helloworld again"));
+        assert_eq!(text.get(1), Some("first line"));
+        assert_eq!(text.get(2), None);
+    }
+
+    #[test]
+    fn actual_text_replaces_content() {
+        let buffer = b"/P <</MCID 0>> BDC (The ) Tj \
+            /Span <</ActualText (fi)>> BDC (\\014) Tj EMC (nal) Tj EMC";
+
+        let text = MarkedContentText::from_content(
+            ContentLexer::new(Cow::Borrowed(buffer)),
+            None,
+            &mut XrefParser::new(Vec::new()),
+        )
+        .unwrap();
+
+        assert_eq!(text.get(0), Some("The final"));
+    }
+}
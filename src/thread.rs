@@ -0,0 +1,325 @@
+/*!
+ * Article threads, which connect the regions of a document that make up a
+ * single flow of content, such as a newspaper article continued on a later
+ * page
+ *
+ * Each thread is a circular chain of beads, linked through their N (next) and
+ * V (previous) entries, each of which marks a rectangle on a page.
+ */
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    catalog::InformationDictionary,
+    data_structures::Rectangle,
+    error::PdfResult,
+    objects::{decode_text_string, Object, Reference},
+    FromObj, Parser, Resolve,
+};
+
+/// A thread dictionary
+#[derive(Debug, Clone, FromObj)]
+#[obj_type("Thread")]
+pub(crate) struct ThreadDictionary<'a> {
+    /// The first bead of the thread
+    #[field("F")]
+    pub first: Option<Reference>,
+
+    /// A thread information dictionary, with the same entries as the document
+    /// information dictionary
+    #[field("I")]
+    pub info: Option<InformationDictionary<'a>>,
+
+    /// A metadata stream for the thread
+    #[field("Metadata")]
+    metadata: Option<Reference>,
+}
+
+/// A bead dictionary, one region of an article thread
+#[derive(Debug, Clone, FromObj)]
+#[obj_type("Bead")]
+pub(crate) struct BeadDictionary {
+    /// The thread to which the bead belongs
+    ///
+    /// Required for the first bead of a thread, and optional for the others.
+    #[field("T")]
+    thread: Option<Reference>,
+
+    /// The next bead of the thread, which for the last bead is the first
+    #[field("N")]
+    pub next: Option<Reference>,
+
+    /// The previous bead of the thread, which for the first bead is the last
+    #[field("V")]
+    pub prev: Option<Reference>,
+
+    /// The page on which the bead appears
+    #[field("P")]
+    pub page: Option<Reference>,
+
+    /// The location of the bead on its page, in default user space
+    #[field("R")]
+    pub rect: Rectangle,
+}
+
+/// An article thread, with its beads in reading order
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArticleThread {
+    /// The thread dictionary
+    pub reference: Reference,
+
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub beads: Vec<ArticleBead>,
+}
+
+/// A region of an article thread
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArticleBead {
+    /// The bead dictionary
+    pub reference: Reference,
+
+    /// The zero-based index of the page on which the bead appears, or `None`
+    /// if it is not a page of this document
+    pub page: Option<usize>,
+
+    /// The location of the bead on its page, in default user space
+    pub rect: Rectangle,
+}
+
+impl ArticleThread {
+    /// The page and rectangle of each region of the article, in reading order,
+    /// skipping beads which are not on a page of this document
+    pub fn regions(&self) -> impl Iterator<Item = (usize, Rectangle)> + '_ {
+        self.beads
+            .iter()
+            .filter_map(|bead| Some((bead.page?, bead.rect)))
+    }
+
+    /// The thread `reference`, with each bead of its chain
+    ///
+    /// Beads are followed through their N entries until the chain returns to
+    /// the first bead. If the chain is broken, such as by a bead which no
+    /// longer exists, the beads before the first are found through the V
+    /// entries instead, and follow the others.
+    pub(crate) fn from_reference<'a>(
+        reference: Reference,
+        page_indices: &HashMap<Reference, usize>,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Self> {
+        let thread =
+            ThreadDictionary::from_obj(resolver.lex_object_from_reference(reference)?, resolver)?;
+
+        let (title, author) = match &thread.info {
+            Some(info) => (
+                info.title.as_deref().map(decode_text_string),
+                info.author.as_deref().map(decode_text_string),
+            ),
+            None => (None, None),
+        };
+
+        let mut beads = Vec::new();
+        let mut visited = HashSet::new();
+
+        let first = match thread.first {
+            Some(first) => first,
+            None => {
+                return Ok(Self {
+                    reference,
+                    title,
+                    author,
+                    beads,
+                })
+            }
+        };
+
+        let mut next = Some(first);
+
+        while let Some(reference) = next.filter(|bead| visited.insert(*bead)) {
+            let bead = match bead_dictionary(reference, resolver)? {
+                Some(bead) => bead,
+                None => break,
+            };
+
+            next = bead.next;
+            beads.push(article_bead(reference, &bead, page_indices));
+        }
+
+        // the chain did not return to the first bead, so those which could not
+        // be reached are found by walking back from it
+        if next != Some(first) && !beads.is_empty() {
+            let mut previous = Vec::new();
+            let mut prev = bead_dictionary(first, resolver)?.and_then(|bead| bead.prev);
+
+            while let Some(reference) = prev.filter(|bead| visited.insert(*bead)) {
+                let bead = match bead_dictionary(reference, resolver)? {
+                    Some(bead) => bead,
+                    None => break,
+                };
+
+                prev = bead.prev;
+                previous.push(article_bead(reference, &bead, page_indices));
+            }
+
+            beads.extend(previous.into_iter().rev());
+        }
+
+        Ok(Self {
+            reference,
+            title,
+            author,
+            beads,
+        })
+    }
+}
+
+/// The bead dictionary `reference`, or `None` if it does not exist
+fn bead_dictionary<'a>(
+    reference: Reference,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Option<BeadDictionary>> {
+    match resolver.lex_object_from_reference(reference)? {
+        Object::Null => Ok(None),
+        obj => Ok(Some(BeadDictionary::from_obj(obj, resolver)?)),
+    }
+}
+
+fn article_bead(
+    reference: Reference,
+    bead: &BeadDictionary,
+    page_indices: &HashMap<Reference, usize>,
+) -> ArticleBead {
+    ArticleBead {
+        reference,
+        page: bead.page.and_then(|page| page_indices.get(&page).copied()),
+        rect: bead.rect,
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// The article threads of the document, each with its beads in reading
+    /// order
+    ///
+    /// Threads connect the regions of an article which is split across columns
+    /// or pages, so that it can be read in sequence.
+    pub fn article_threads(&mut self) -> PdfResult<Vec<ArticleThread>> {
+        let threads = match &self.catalog.threads {
+            Some(threads) => threads.clone(),
+            None => return Ok(Vec::new()),
+        };
+
+        let page_indices = self.page_indices();
+
+        threads
+            .into_iter()
+            .map(|thread| ArticleThread::from_reference(thread, &page_indices, &mut self.lexer))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{tokenizer::Tokenizer, version::PdfVersion, writer::PdfWriter};
+
+    fn reference(object_number: usize) -> Reference {
+        Reference {
+            object_number,
+            generation: 0,
+        }
+    }
+
+    fn document(objects: &[(usize, &'static [u8])]) -> PdfWriter<'static> {
+        let mut writer = PdfWriter::new(PdfVersion::V1_7);
+
+        for &(object_number, obj) in objects {
+            writer.insert(
+                reference(object_number),
+                Tokenizer::new(obj).next_object().unwrap().unwrap().value,
+            );
+        }
+
+        writer
+    }
+
+    fn bead_references(thread: &ArticleThread) -> Vec<usize> {
+        thread
+            .beads
+            .iter()
+            .map(|bead| bead.reference.object_number)
+            .collect()
+    }
+
+    #[test]
+    fn circular_chain() {
+        let mut writer = document(&[
+            (
+                1,
+                b"<< /Type /Thread /F 2 0 R /I << /Title (Story) /Author (Ann) >> >>",
+            ),
+            (
+                2,
+                b"<< /Type /Bead /T 1 0 R /N 3 0 R /V 4 0 R /P 10 0 R /R [0 0 100 200] >>",
+            ),
+            (
+                3,
+                b"<< /Type /Bead /N 4 0 R /V 2 0 R /P 11 0 R /R [0 0 50 50] >>",
+            ),
+            (
+                4,
+                b"<< /Type /Bead /N 2 0 R /V 3 0 R /P 12 0 R /R [0 0 10 10] >>",
+            ),
+            (10, b"<< /Type /Page >>"),
+            (11, b"<< /Type /Page >>"),
+            (12, b"<< /Type /Page >>"),
+        ]);
+
+        let page_indices = HashMap::from([(reference(10), 0), (reference(11), 1)]);
+
+        let thread =
+            ArticleThread::from_reference(reference(1), &page_indices, &mut writer).unwrap();
+
+        assert_eq!(thread.title.as_deref(), Some("Story"));
+        assert_eq!(thread.author.as_deref(), Some("Ann"));
+        assert_eq!(bead_references(&thread), [2, 3, 4]);
+
+        // the last bead is not on a page of the document
+        assert_eq!(thread.beads[2].page, None);
+        assert_eq!(
+            thread.regions().collect::<Vec<_>>(),
+            [
+                (0, Rectangle::new(0.0, 0.0, 100.0, 200.0)),
+                (1, Rectangle::new(0.0, 0.0, 50.0, 50.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn broken_chain() {
+        // the second bead's next bead no longer exists, so the third is found
+        // through the previous bead of the first
+        let mut writer = document(&[
+            (1, b"<< /Type /Thread /F 2 0 R >>"),
+            (
+                2,
+                b"<< /Type /Bead /T 1 0 R /N 3 0 R /V 4 0 R /R [0 0 1 1] >>",
+            ),
+            (3, b"<< /Type /Bead /N 9 0 R /V 2 0 R /R [0 0 2 2] >>"),
+            (4, b"<< /Type /Bead /N 2 0 R /V 3 0 R /R [0 0 3 3] >>"),
+        ]);
+
+        let thread =
+            ArticleThread::from_reference(reference(1), &HashMap::new(), &mut writer).unwrap();
+
+        assert_eq!(thread.title, None);
+        assert_eq!(bead_references(&thread), [2, 3, 4]);
+        assert_eq!(thread.regions().count(), 0);
+
+        let mut writer = document(&[(1, b"<< /Type /Thread >>")]);
+
+        let thread =
+            ArticleThread::from_reference(reference(1), &HashMap::new(), &mut writer).unwrap();
+
+        assert!(thread.beads.is_empty());
+    }
+}
@@ -0,0 +1,530 @@
+/*!
+ * A standalone lexer for PDF syntax, for tools which inspect the bytes of a
+ * file directly rather than loading it as a document
+ *
+ * No cross-reference table is read and no references are resolved, so the
+ * tokenizer works on fragments of files and on files too damaged to load.
+ */
+
+use std::{collections::HashMap, ops::Range};
+
+use crate::{
+    error::PdfResult,
+    lex::{LexBase, LexObject},
+    objects::{Dictionary, Object, Reference},
+    stream::StreamDict,
+    Resolve,
+};
+
+/// A lexer over the bytes of a PDF file, producing tokens or whole objects
+/// along with the byte ranges they occupy
+#[derive(Debug, Clone)]
+pub struct Tokenizer<'a> {
+    buffer: &'a [u8],
+    cursor: usize,
+
+    /// Whether the last token was the `stream` keyword, and so the next is the
+    /// stream data
+    after_stream_keyword: bool,
+}
+
+/// The lexical units of PDF syntax
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token<'a> {
+    Integer(i32),
+    Real(f32),
+
+    /// A literal or hexadecimal string
+    String(String),
+    Name(String),
+    ArrayStart,
+    ArrayEnd,
+    DictionaryStart,
+    DictionaryEnd,
+
+    /// A sequence of regular characters which is not a number, such as `true`,
+    /// `obj`, `R`, or a content stream operator
+    Keyword(String),
+
+    /// The text of a comment, without the leading `%`
+    Comment(String),
+
+    /// The raw bytes of a stream, between the end of line following the
+    /// `stream` keyword and the `endstream` keyword
+    StreamData(&'a [u8]),
+}
+
+/// A value along with the bytes it was lexed from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Range<usize>,
+}
+
+/// An object defined by an `obj` ... `endobj` block
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndirectObject<'a> {
+    pub reference: Reference,
+    pub object: Object<'a>,
+
+    /// The bytes from the start of the object number through the end of the
+    /// `endobj` keyword
+    pub span: Range<usize>,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            buffer: bytes,
+            cursor: 0,
+            after_stream_keyword: false,
+        }
+    }
+
+    /// The byte offset at which the next token begins lexing
+    pub fn offset(&self) -> usize {
+        self.cursor
+    }
+
+    /// Continue lexing from the byte offset `offset`
+    pub fn seek(&mut self, offset: usize) {
+        self.cursor = offset.min(self.buffer.len());
+        self.after_stream_keyword = false;
+    }
+
+    /// The next token, or `None` at the end of the input
+    pub fn next_token(&mut self) -> PdfResult<Option<Spanned<Token<'a>>>> {
+        if self.after_stream_keyword {
+            self.after_stream_keyword = false;
+
+            let data = self.stream_data(self.cursor)?;
+            let span = self.cursor..data.end;
+            self.cursor = data.end;
+
+            return Ok(Some(Spanned {
+                value: Token::StreamData(&self.buffer[data]),
+                span,
+            }));
+        }
+
+        while self.next_is_whitespace() {
+            self.next_byte();
+        }
+
+        let start = self.cursor;
+
+        let token = match self.peek_byte() {
+            None => return Ok(None),
+            Some(b'%') => {
+                self.next_byte();
+                self.skip_comment();
+
+                Token::Comment(self.text(start + 1..self.cursor))
+            }
+            Some(b'/') => Token::Name(self.lex_name()?),
+            Some(b'(') => Token::String(self.lex_string()?),
+            Some(b'<') if self.peek_byte_offset(1) == Some(b'<') => {
+                self.cursor += 2;
+                Token::DictionaryStart
+            }
+            Some(b'<') => match self.lex_hex_string()? {
+                Object::String(s) => Token::String(s),
+                _ => unreachable!(),
+            },
+            Some(b'>') if self.peek_byte_offset(1) == Some(b'>') => {
+                self.cursor += 2;
+                Token::DictionaryEnd
+            }
+            Some(b'[') => {
+                self.next_byte();
+                Token::ArrayStart
+            }
+            Some(b']') => {
+                self.next_byte();
+                Token::ArrayEnd
+            }
+            // PostScript procedure delimiters, used by type 4 functions
+            Some(b @ (b'{' | b'}')) => {
+                self.next_byte();
+                Token::Keyword(char::from(b).to_string())
+            }
+            Some(b'+' | b'-' | b'.' | b'0'..=b'9') => self.lex_number_token()?,
+            Some(b) if Self::is_regular(b) => {
+                while self.peek_byte().map_or(false, Self::is_regular) {
+                    self.next_byte();
+                }
+
+                let keyword = self.text(start..self.cursor);
+                self.after_stream_keyword = keyword == "stream";
+
+                Token::Keyword(keyword)
+            }
+            Some(b) => anyhow::bail!(
+                "unexpected delimiter {:?} at offset {}",
+                char::from(b),
+                start
+            ),
+        };
+
+        Ok(Some(Spanned {
+            value: token,
+            span: start..self.cursor,
+        }))
+    }
+
+    /// The next complete object, such as a dictionary or a reference of the
+    /// form `12 0 R`, or `None` at the end of the input
+    ///
+    /// Streams whose Length is an indirect reference, or is not followed by the
+    /// `endstream` keyword, are assumed to end at the next `endstream`
+    /// keyword.
+    pub fn next_object(&mut self) -> PdfResult<Option<Spanned<Object<'a>>>> {
+        self.skip_whitespace();
+        self.after_stream_keyword = false;
+
+        let start = self.cursor;
+
+        match self.peek_byte() {
+            None => return Ok(None),
+            Some(
+                b't' | b'f' | b'n' | b'<' | b'+' | b'-' | b'0'..=b'9' | b'.' | b'(' | b'/' | b'[',
+            ) => {}
+            Some(b) => anyhow::bail!(
+                "unexpected object start {:?} at offset {}",
+                char::from(b),
+                start
+            ),
+        }
+
+        let object = self.lex_object()?;
+
+        // `lex_object` skips trailing whitespace, which is not part of the span
+        let mut end = self.cursor;
+        while end > start && Self::is_whitespace(self.buffer[end - 1]) {
+            end -= 1;
+        }
+
+        Ok(Some(Spanned {
+            value: object,
+            span: start..end,
+        }))
+    }
+
+    /// The next indirect object at or after the current offset, or `None` if
+    /// there are no more
+    ///
+    /// Bytes which cannot be lexed are skipped, so this finds the objects of
+    /// damaged files and of files whose cross-reference table is missing.
+    pub fn next_indirect_object(&mut self) -> PdfResult<Option<IndirectObject<'a>>> {
+        // The last two tokens, which are the object and generation numbers if
+        // the next token is the `obj` keyword
+        let mut previous: [Option<Spanned<Token<'a>>>; 2] = [None, None];
+
+        loop {
+            let token = match self.next_token() {
+                Ok(Some(token)) => token,
+                Ok(None) => return Ok(None),
+                Err(..) => {
+                    self.seek(self.cursor + 1);
+                    previous = [None, None];
+                    continue;
+                }
+            };
+
+            if let (
+                Token::Keyword(keyword),
+                [Some(Spanned {
+                    value: Token::Integer(object_number),
+                    span,
+                }), Some(Spanned {
+                    value: Token::Integer(generation),
+                    ..
+                })],
+            ) = (&token.value, &previous)
+            {
+                if keyword == "obj" && *object_number >= 0 && *generation >= 0 {
+                    let start = span.start;
+                    let after_obj = self.cursor;
+
+                    if let Some(object) = self.indirect_object_body(start)? {
+                        return Ok(Some(IndirectObject {
+                            reference: Reference {
+                                object_number: *object_number as usize,
+                                generation: *generation as usize,
+                            },
+                            object: object.value,
+                            span: object.span,
+                        }));
+                    }
+
+                    self.seek(after_obj);
+                }
+            }
+
+            previous = [previous[1].take(), Some(token)];
+        }
+    }
+
+    /// The object following an `obj` keyword and the span of the indirect
+    /// object beginning at `start`, or `None` if it is malformed
+    fn indirect_object_body(&mut self, start: usize) -> PdfResult<Option<Spanned<Object<'a>>>> {
+        let object = match self.next_object() {
+            Ok(Some(object)) => object.value,
+            Ok(None) | Err(..) => return Ok(None),
+        };
+
+        match self.next_token() {
+            Ok(Some(Spanned {
+                value: Token::Keyword(keyword),
+                span,
+            })) if keyword == "endobj" => Ok(Some(Spanned {
+                value: object,
+                span: start..span.end,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    fn lex_number_token(&mut self) -> PdfResult<Token<'a>> {
+        let start = self.cursor;
+
+        if matches!(self.peek_byte(), Some(b'+' | b'-')) {
+            self.next_byte();
+        }
+
+        let whole_number = self.lex_whole_number();
+
+        if self.peek_byte() != Some(b'.') {
+            anyhow::ensure!(
+                !whole_number.is_empty(),
+                "expected number at offset {}",
+                start
+            );
+
+            return Ok(Token::Integer(
+                self.text(start..self.cursor).parse::<i32>()?,
+            ));
+        }
+
+        self.next_byte();
+
+        let fraction = self.lex_whole_number();
+
+        anyhow::ensure!(
+            !whole_number.is_empty() || !fraction.is_empty(),
+            "expected number at offset {}",
+            start
+        );
+
+        Ok(Token::Real(self.text(start..self.cursor).parse::<f32>()?))
+    }
+
+    /// The bytes of a stream, given the offset just after its `stream` keyword
+    ///
+    /// The data is taken to end at the next `endstream` keyword, less the end
+    /// of line marker which precedes it.
+    fn stream_data(&self, after_keyword: usize) -> PdfResult<Range<usize>> {
+        let start = match &self.buffer[after_keyword..] {
+            [b'\r', b'\n', ..] => after_keyword + 2,
+            [b'\n' | b'\r', ..] => after_keyword + 1,
+            _ => anyhow::bail!(
+                "expected end of line after stream keyword at offset {}",
+                after_keyword
+            ),
+        };
+
+        let mut end = self.buffer[start..]
+            .windows(b"endstream".len())
+            .position(|window| window == b"endstream")
+            .map(|idx| start + idx)
+            .ok_or_else(|| anyhow::anyhow!("unterminated stream at offset {}", start))?;
+
+        if end > start && self.buffer[end - 1] == b'\n' {
+            end -= 1;
+        }
+
+        if end > start && self.buffer[end - 1] == b'\r' {
+            end -= 1;
+        }
+
+        Ok(start..end)
+    }
+
+    /// Whether the stream beginning at the cursor, with its `stream` keyword,
+    /// has `len` bytes of data followed by the `endstream` keyword
+    fn is_stream_length(&self, len: i32) -> bool {
+        let after_keyword = self.cursor + b"stream".len();
+
+        let start = match &self.buffer[after_keyword.min(self.buffer.len())..] {
+            [b'\r', b'\n', ..] => after_keyword + 2,
+            [b'\n' | b'\r', ..] => after_keyword + 1,
+            _ => return false,
+        };
+
+        let end = match usize::try_from(len) {
+            Ok(len) if start + len <= self.buffer.len() => start + len,
+            _ => return false,
+        };
+
+        let after_data = self.buffer[end..]
+            .iter()
+            .position(|&b| !Self::is_whitespace(b))
+            .map_or(self.buffer.len(), |idx| end + idx);
+
+        self.buffer[after_data..].starts_with(b"endstream")
+    }
+
+    fn text(&self, range: Range<usize>) -> String {
+        self.buffer[range].iter().map(|&b| char::from(b)).collect()
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = PdfResult<Spanned<Token<'a>>>;
+
+    /// The next token, skipping the byte at which an error occurred so that
+    /// iteration always makes progress
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.cursor;
+
+        match self.next_token() {
+            Ok(token) => token.map(Ok),
+            Err(e) => {
+                self.seek(start + 1);
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<'a> LexBase<'a> for Tokenizer<'a> {
+    fn buffer(&self) -> &[u8] {
+        self.buffer
+    }
+
+    fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    fn cursor_mut(&mut self) -> &mut usize {
+        &mut self.cursor
+    }
+}
+
+impl<'a> LexObject<'a> for Tokenizer<'a> {
+    fn lex_dict(&mut self) -> PdfResult<Object<'a>> {
+        let dict = self.lex_dict_ignore_stream()?;
+
+        if !self.next_matches(b"stream") {
+            return Ok(Object::Dictionary(dict));
+        }
+
+        let mut entries = dict.entries().collect::<HashMap<String, Object<'a>>>();
+
+        // Without a cross-reference table an indirect length cannot be
+        // resolved, so the length is found from the endstream keyword instead,
+        // as it is for lengths which are wrong
+        let has_length = match entries.get("Length") {
+            Some(&Object::Integer(len)) => self.is_stream_length(len),
+            _ => false,
+        };
+
+        if !has_length {
+            let data = self.stream_data(self.cursor + b"stream".len())?;
+
+            entries.insert(
+                "Length".to_owned(),
+                Object::Integer(i32::try_from(data.len())?),
+            );
+        }
+
+        let stream_dict = StreamDict::from_dict(Dictionary::new(entries), self)?;
+
+        Ok(Object::Stream(self.lex_stream(stream_dict)?))
+    }
+}
+
+impl<'a> Resolve<'a> for Tokenizer<'a> {
+    fn lex_object_from_reference(&mut self, reference: Reference) -> PdfResult<Object<'a>> {
+        anyhow::bail!(
+            "cannot resolve {:?} without a cross-reference table",
+            reference
+        )
+    }
+
+    fn reference_exists(&mut self, _reference: Reference) -> PdfResult<bool> {
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tokens_with_offsets() {
+        let tokens = Tokenizer::new(b"1 0 obj\n<< /Kids [2 0 R] >> % note\nendobj")
+            .map(|token| token.unwrap())
+            .map(|token| (token.value, token.span))
+            .collect::<Vec<(Token, Range<usize>)>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                (Token::Integer(1), 0..1),
+                (Token::Integer(0), 2..3),
+                (Token::Keyword("obj".to_owned()), 4..7),
+                (Token::DictionaryStart, 8..10),
+                (Token::Name("Kids".to_owned()), 11..16),
+                (Token::ArrayStart, 17..18),
+                (Token::Integer(2), 18..19),
+                (Token::Integer(0), 20..21),
+                (Token::Keyword("R".to_owned()), 22..23),
+                (Token::ArrayEnd, 23..24),
+                (Token::DictionaryEnd, 25..27),
+                (Token::Comment(" note".to_owned()), 28..34),
+                (Token::Keyword("endobj".to_owned()), 35..41),
+            ]
+        );
+    }
+
+    #[test]
+    fn stream_data_token() {
+        let mut tokenizer = Tokenizer::new(b"stream\r\nabc\nendstream");
+
+        tokenizer.next_token().unwrap();
+
+        assert_eq!(
+            tokenizer.next_token().unwrap().unwrap().value,
+            Token::StreamData(b"abc")
+        );
+        assert_eq!(
+            tokenizer.next_token().unwrap().unwrap().value,
+            Token::Keyword("endstream".to_owned())
+        );
+    }
+
+    #[test]
+    fn finds_indirect_objects_among_garbage() {
+        let input = b")) garbage 7 3 obj\n<< /Length 9 0 R >>\nstream\nxyz\nendstream\nendobj";
+
+        let object = Tokenizer::new(input)
+            .next_indirect_object()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            object.reference,
+            Reference {
+                object_number: 7,
+                generation: 3
+            }
+        );
+        assert_eq!(object.span, 11..input.len());
+
+        match object.object {
+            Object::Stream(stream) => assert_eq!(&*stream.stream, b"xyz"),
+            obj => panic!("expected stream, found {:?}", obj),
+        }
+    }
+}
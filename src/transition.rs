@@ -0,0 +1,220 @@
+/*!
+ * Page transitions, the visual effects with which a viewer moves from one
+ * page to the next when a document is presented as a slide show
+ */
+
+use crate::{
+    error::{ParseError, PdfResult},
+    objects::{Object, ObjectType},
+    FromObj, Parser, Resolve,
+};
+
+/// A transition dictionary, describing the effect used when moving to a page
+/// during presentations
+#[derive(Debug, Clone, Copy, PartialEq, FromObj)]
+#[obj_type("Trans")]
+pub struct Transition {
+    /// The transition style that shall be used when moving to this page from
+    /// another during a presentation
+    ///
+    /// Default value: R
+    #[field("S", default = TransitionStyle::Replace)]
+    pub style: TransitionStyle,
+
+    /// The duration of the transition effect, in seconds
+    ///
+    /// Default value: 1
+    #[field("D", default = 1.0)]
+    pub duration: f32,
+
+    /// The dimension in which the Split and Blinds effects occur
+    ///
+    /// Default value: H
+    #[field("Dm", default = TransitionDimension::Horizontal)]
+    pub dimension: TransitionDimension,
+
+    /// The direction of motion for the Split, Box and Fly effects
+    ///
+    /// Default value: I
+    #[field("M", default = TransitionMotion::Inward)]
+    pub motion: TransitionMotion,
+
+    /// The direction in which the Wipe, Glitter, Fly, Cover, Uncover and Push
+    /// effects move
+    ///
+    /// Default value: 0
+    #[field("Di", default = TransitionDirection::LeftToRight)]
+    pub direction: TransitionDirection,
+
+    /// (PDF 1.5) The starting or ending scale at which the changes are drawn,
+    /// for the Fly effect
+    ///
+    /// Default value: 1.0
+    #[field("SS", default = 1.0)]
+    pub scale: f32,
+
+    /// (PDF 1.5) Whether the area that is flown in is rectangular and opaque,
+    /// for the Fly effect
+    ///
+    /// Default value: false
+    #[field("B", default = false)]
+    pub is_opaque: bool,
+}
+
+#[pdf_enum]
+pub enum TransitionStyle {
+    /// Two lines sweep across the screen, revealing the new page
+    Split = "Split",
+
+    /// Multiple lines, evenly spaced across the screen, synchronously sweep
+    /// in the same direction to reveal the new page
+    Blinds = "Blinds",
+
+    /// A rectangular box sweeps inward from the edges of the page or outward
+    /// from the centre
+    Box = "Box",
+
+    /// A single line sweeps across the screen from one edge to the other
+    Wipe = "Wipe",
+
+    /// The old page dissolves gradually to reveal a new one
+    Dissolve = "Dissolve",
+
+    /// Similar to Dissolve, except that the effect sweeps across the page in
+    /// a wide band moving from one side of the screen to the other
+    Glitter = "Glitter",
+
+    /// The new page simply replaces the old one with no special transition
+    /// effect
+    Replace = "R",
+
+    /// (PDF 1.5) Changes are flown out or in, to or from a location that is
+    /// offscreen except when `direction` is None
+    Fly = "Fly",
+
+    /// (PDF 1.5) The old page slides off the screen while the new page slides
+    /// in, pushing the old page out
+    Push = "Push",
+
+    /// (PDF 1.5) The new page slides on to the screen, covering the old page
+    Cover = "Cover",
+
+    /// (PDF 1.5) The old page slides off the screen, uncovering the new page
+    Uncover = "Uncover",
+
+    /// (PDF 1.5) The new page gradually becomes visible through the old one
+    Fade = "Fade",
+}
+
+#[pdf_enum]
+pub enum TransitionDimension {
+    Horizontal = "H",
+    Vertical = "V",
+}
+
+#[pdf_enum]
+pub enum TransitionMotion {
+    /// From the edges of the page towards the centre
+    Inward = "I",
+
+    /// From the centre of the page towards the edges
+    Outward = "O",
+}
+
+/// The direction in which a transition effect moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionDirection {
+    LeftToRight,
+    BottomToTop,
+    RightToLeft,
+    TopToBottom,
+
+    /// Only for the Glitter effect
+    TopLeftToBottomRight,
+
+    /// Only for the Fly effect with a scale other than 1.0, which is then
+    /// drawn at the centre of the page
+    None,
+}
+
+impl<'a> FromObj<'a> for TransitionDirection {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        Ok(match resolver.resolve(obj)? {
+            Object::Name(name) if name == "None" => TransitionDirection::None,
+            obj @ (Object::Integer(..) | Object::Real(..)) => {
+                match resolver.assert_number(obj)? as i32 {
+                    0 => TransitionDirection::LeftToRight,
+                    90 => TransitionDirection::BottomToTop,
+                    180 => TransitionDirection::RightToLeft,
+                    270 => TransitionDirection::TopToBottom,
+                    315 => TransitionDirection::TopLeftToBottomRight,
+                    angle => anyhow::bail!("invalid transition direction: {}", angle),
+                }
+            }
+            _ => anyhow::bail!(ParseError::MismatchedObjectTypeAny {
+                expected: &[ObjectType::Integer, ObjectType::Name],
+            }),
+        })
+    }
+}
+
+/// The advance timing and transition of a page, when the document is
+/// presented as a slide show
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageTransition {
+    /// The index of the page, starting from 0
+    pub page: usize,
+
+    /// The maximum time, in seconds, for which the page is displayed before
+    /// the viewer advances to the next page automatically, or `None` if it
+    /// does not
+    pub duration: Option<f32>,
+
+    /// The effect used when moving to the page, or `None` to replace the
+    /// previous page without one
+    pub transition: Option<Transition>,
+}
+
+impl<'a> Parser<'a> {
+    /// The advance timing and transition of each page which has either, for
+    /// playing the document back as a presentation
+    pub fn page_transitions(&self) -> Vec<PageTransition> {
+        self.pages()
+            .iter()
+            .enumerate()
+            .filter(|(_, page)| page.dur.is_some() || page.trans.is_some())
+            .map(|(page, page_object)| PageTransition {
+                page,
+                duration: page_object.dur,
+                transition: page_object.trans,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    #[test]
+    fn fly_transition() {
+        let mut tokenizer = Tokenizer::new(b"<< /Type /Trans /S /Fly /D 0.5 /Di /None /SS 0.25 >>");
+
+        let obj = tokenizer.next_object().unwrap().unwrap().value;
+        let transition = Transition::from_obj(obj, &mut tokenizer).unwrap();
+
+        assert_eq!(
+            transition,
+            Transition {
+                style: TransitionStyle::Fly,
+                duration: 0.5,
+                dimension: TransitionDimension::Horizontal,
+                motion: TransitionMotion::Inward,
+                direction: TransitionDirection::None,
+                scale: 0.25,
+                is_opaque: false,
+            }
+        );
+    }
+}
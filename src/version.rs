@@ -0,0 +1,271 @@
+use std::fmt;
+
+use crate::{
+    error::{ParseError, PdfResult},
+    objects::{Dictionary, Object},
+    FromObj, Resolve,
+};
+
+/// The version of the PDF specification a document conforms to
+///
+/// The version is initially declared by the `%PDF-x.y` comment in the file
+/// header, and may be overridden by the Version entry of the document catalog
+/// if the latter specifies a later version. This allows a conforming writer to
+/// update the version using an incremental update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PdfVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl PdfVersion {
+    pub const V1_0: Self = Self::new(1, 0);
+    pub const V1_1: Self = Self::new(1, 1);
+    pub const V1_2: Self = Self::new(1, 2);
+    pub const V1_3: Self = Self::new(1, 3);
+    pub const V1_4: Self = Self::new(1, 4);
+    pub const V1_5: Self = Self::new(1, 5);
+    pub const V1_6: Self = Self::new(1, 6);
+    pub const V1_7: Self = Self::new(1, 7);
+    pub const V2_0: Self = Self::new(2, 0);
+
+    /// Conforming readers shall accept a header anywhere within the first 1024
+    /// bytes of the file
+    const HEADER_SEARCH_LIMIT: usize = 1024;
+
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    /// Parse a version of the form `x.y`, as found in the catalog Version
+    /// entry and in developer extensions dictionaries
+    pub(crate) fn from_str(s: &str) -> PdfResult<Self> {
+        let (major, minor) = match s.split_once('.') {
+            Some(v) => v,
+            None => anyhow::bail!(ParseError::UnrecognizedVariant {
+                found: s.to_owned(),
+                ty: "PdfVersion",
+            }),
+        };
+
+        Ok(Self::new(major.parse()?, minor.parse()?))
+    }
+
+    /// Find and parse the `%PDF-x.y` comment in the file header
+    pub(crate) fn from_header(file: &[u8]) -> PdfResult<Self> {
+        const MAGIC: &[u8] = b"%PDF-";
+
+        let search = &file[..file.len().min(Self::HEADER_SEARCH_LIMIT)];
+
+        let start = match search
+            .windows(MAGIC.len())
+            .position(|window| window == MAGIC)
+        {
+            Some(start) => start + MAGIC.len(),
+            None => anyhow::bail!(ParseError::MismatchedByte {
+                expected: b'%',
+                found: file.first().copied(),
+            }),
+        };
+
+        let len = file[start..]
+            .iter()
+            .position(|&b| !matches!(b, b'0'..=b'9' | b'.'))
+            .unwrap_or(file.len() - start);
+
+        Self::from_str(std::str::from_utf8(&file[start..start + len])?)
+    }
+
+    /// The effective version of a document, given the version in its header and
+    /// the (optional) Version entry of its catalog
+    pub(crate) fn reconcile(header: Self, catalog: Option<Self>) -> Self {
+        match catalog {
+            Some(catalog) => header.max(catalog),
+            None => header,
+        }
+    }
+
+    /// The form in which this version is written in a document catalog
+    pub(crate) fn as_name(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for PdfVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl<'a> FromObj<'a> for PdfVersion {
+    fn from_obj(obj: Object<'a>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        Self::from_str(&resolver.assert_name(obj)?)
+    }
+}
+
+/// Features used by a document which impose a lower bound on the version it
+/// may declare
+///
+/// This is used when writing a document to emit the smallest version that
+/// supports everything the document actually contains.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PdfFeatures {
+    /// Cross-reference streams and object streams (PDF 1.5)
+    pub xref_streams: bool,
+
+    /// Transparency groups, soft masks, and blend modes (PDF 1.4)
+    pub transparency: bool,
+
+    /// 128-bit RC4 encryption (PDF 1.4)
+    pub rc4_128: bool,
+
+    /// AES-128 encryption using the AESV2 crypt filter method (PDF 1.6)
+    pub aes_128: bool,
+
+    /// AES-256 encryption using the AESV3 crypt filter method (PDF 2.0)
+    pub aes_256: bool,
+
+    /// Optional content (PDF 1.5)
+    pub optional_content: bool,
+}
+
+impl PdfFeatures {
+    /// The features used by either `self` or `other`
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            xref_streams: self.xref_streams || other.xref_streams,
+            transparency: self.transparency || other.transparency,
+            rc4_128: self.rc4_128 || other.rc4_128,
+            aes_128: self.aes_128 || other.aes_128,
+            aes_256: self.aes_256 || other.aes_256,
+            optional_content: self.optional_content || other.optional_content,
+        }
+    }
+
+    /// The features used by `obj` and the direct objects within it
+    ///
+    /// Referenced objects are not followed, so every indirect object of a
+    /// document must be examined to find all of the features it uses.
+    pub(crate) fn of_object(obj: &Object) -> Self {
+        let mut features = Self::default();
+        features.detect(obj);
+        features
+    }
+
+    fn detect(&mut self, obj: &Object) {
+        match obj {
+            Object::Array(elements) => elements.iter().for_each(|obj| self.detect(obj)),
+            Object::Dictionary(dict) => self.detect_in_dictionary(dict),
+            Object::Stream(stream) => self.detect_in_dictionary(&stream.dict.other),
+            _ => {}
+        }
+    }
+
+    fn detect_in_dictionary(&mut self, dict: &Dictionary) {
+        for (key, value) in dict.iter() {
+            match (key, value) {
+                // soft masks of graphics states and images, and transparency
+                // group attributes of forms and pages
+                ("SMask", value) if !value.name_is("None") => self.transparency = true,
+                ("Group", _) => self.transparency = true,
+                ("CA" | "ca", Object::Integer(alpha)) if *alpha != 1 => self.transparency = true,
+                ("CA" | "ca", Object::Real(alpha)) if *alpha != 1.0 => self.transparency = true,
+                ("BM", value) if !value.name_is("Normal") && !value.name_is("Compatible") => {
+                    self.transparency = true
+                }
+                // the crypt filters of the standard security handler
+                ("CFM", value) if value.name_is("AESV2") => self.aes_128 = true,
+                ("CFM", value) if value.name_is("AESV3") => self.aes_256 = true,
+                ("Filter", value) if value.name_is("Standard") => {
+                    if let Some(Object::Integer(2..)) = dict.peek("V") {
+                        self.rc4_128 = true;
+                    }
+                }
+                ("OCProperties", _) => self.optional_content = true,
+                _ => {}
+            }
+
+            self.detect(value);
+        }
+    }
+
+    /// The earliest version which supports every feature used
+    pub fn minimum_version(&self) -> PdfVersion {
+        let mut version = PdfVersion::V1_0;
+
+        let mut require = |used: bool, required: PdfVersion| {
+            if used {
+                version = version.max(required);
+            }
+        };
+
+        require(self.transparency, PdfVersion::V1_4);
+        require(self.rc4_128, PdfVersion::V1_4);
+        require(self.xref_streams, PdfVersion::V1_5);
+        require(self.optional_content, PdfVersion::V1_5);
+        require(self.aes_128, PdfVersion::V1_6);
+        require(self.aes_256, PdfVersion::V2_0);
+
+        version
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn header() {
+        assert_eq!(
+            PdfVersion::from_header(b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n").unwrap(),
+            PdfVersion::V1_7
+        );
+    }
+
+    #[test]
+    fn header_with_leading_garbage() {
+        assert_eq!(
+            PdfVersion::from_header(b"\x00\x00junk%PDF-2.0\r\n").unwrap(),
+            PdfVersion::V2_0
+        );
+    }
+
+    #[test]
+    fn missing_header() {
+        assert!(PdfVersion::from_header(b"1 0 obj").is_err());
+    }
+
+    #[test]
+    fn catalog_overrides_earlier_header() {
+        assert_eq!(
+            PdfVersion::reconcile(PdfVersion::V1_4, Some(PdfVersion::V1_7)),
+            PdfVersion::V1_7
+        );
+        assert_eq!(
+            PdfVersion::reconcile(PdfVersion::V1_7, Some(PdfVersion::V1_4)),
+            PdfVersion::V1_7
+        );
+    }
+
+    #[test]
+    fn minimum_version() {
+        assert_eq!(PdfFeatures::default().minimum_version(), PdfVersion::V1_0);
+        assert_eq!(
+            PdfFeatures {
+                xref_streams: true,
+                transparency: true,
+                ..PdfFeatures::default()
+            }
+            .minimum_version(),
+            PdfVersion::V1_5
+        );
+        assert_eq!(
+            PdfFeatures {
+                aes_128: true,
+                ..PdfFeatures::default()
+            }
+            .minimum_version(),
+            PdfVersion::V1_6
+        );
+    }
+}
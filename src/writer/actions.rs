@@ -0,0 +1,121 @@
+use crate::{
+    actions::PageEvent,
+    error::PdfResult,
+    objects::{Dictionary, Object},
+};
+
+use super::{
+    merge::{resolved, set_entry},
+    PdfWriter,
+};
+
+/// The actions performed when pages are opened or closed
+impl<'a> PdfWriter<'a> {
+    /// Perform `action` when the page at `index` is opened or closed, in place
+    /// of any action the page already performs on that event
+    ///
+    /// `action` is an action dictionary, or a reference to one. The page is
+    /// left without an action for the event if it is `None`.
+    pub fn set_page_action(
+        &mut self,
+        index: usize,
+        event: PageEvent,
+        action: Option<Object<'a>>,
+    ) -> PdfResult<()> {
+        let (page, entries) = self.page_entries(index)?;
+
+        let mut actions = match entries
+            .peek("AA")
+            .and_then(|actions| resolved(self, actions))
+        {
+            Some(Object::Dictionary(actions)) => actions.clone(),
+            _ => Dictionary::empty(),
+        };
+
+        let key = match event {
+            PageEvent::Open => "O",
+            PageEvent::Close => "C",
+        };
+
+        match action {
+            Some(action) => {
+                actions.insert(key, action);
+            }
+            None => {
+                actions.remove(key);
+            }
+        }
+
+        if actions.is_empty() {
+            if let Some((_, Object::Dictionary(page))) = self.objects.get_mut(&page.object_number) {
+                page.remove("AA");
+            }
+        } else {
+            set_entry(self, page, "AA", Object::Dictionary(actions));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        actions::{ActionType, PageAction},
+        version::PdfVersion,
+        writer::{dictionary, DocumentBuilder, PageBuilder},
+        ParseOptions, Parser,
+    };
+
+    #[test]
+    fn page_actions_round_trip() {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+        builder.add_page(PageBuilder::new(612.0, 792.0)).unwrap();
+        builder.add_page(PageBuilder::new(612.0, 792.0)).unwrap();
+
+        let mut writer = builder.build().unwrap();
+
+        let script = writer.add(Object::Dictionary(dictionary([
+            ("S", Object::Name("JavaScript".to_owned())),
+            ("JS", Object::String("app.alert('open')".to_owned())),
+        ])));
+
+        writer
+            .set_page_action(1, PageEvent::Open, Some(Object::Reference(script)))
+            .unwrap();
+        writer
+            .set_page_action(
+                1,
+                PageEvent::Close,
+                Some(Object::Dictionary(dictionary([
+                    ("S", Object::Name("Named".to_owned())),
+                    ("N", Object::Name("FirstPage".to_owned())),
+                ]))),
+            )
+            .unwrap();
+        writer
+            .set_page_action(0, PageEvent::Close, Some(Object::Reference(script)))
+            .unwrap();
+        writer.set_page_action(0, PageEvent::Close, None).unwrap();
+
+        let parser =
+            Parser::from_bytes(writer.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        assert_eq!(
+            parser.page_actions(),
+            vec![
+                PageAction {
+                    page: 1,
+                    event: PageEvent::Open,
+                    action_types: vec![ActionType::JavaScript],
+                },
+                PageAction {
+                    page: 1,
+                    event: PageEvent::Close,
+                    action_types: vec![ActionType::Named],
+                },
+            ]
+        );
+    }
+}
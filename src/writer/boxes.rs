@@ -0,0 +1,193 @@
+use crate::{
+    data_structures::Rectangle,
+    error::PdfResult,
+    objects::{Dictionary, Object, Reference},
+    FromObj, Resolve, ToObj,
+};
+
+use super::{merge::set_entry, PdfWriter};
+
+/// Each page boundary which must lie within another, the crop box by default
+/// being the media box, and the bleed box the crop box
+const NESTED_BOXES: [(&str, &str); 4] = [
+    ("CropBox", "MediaBox"),
+    ("BleedBox", "CropBox"),
+    ("TrimBox", "BleedBox"),
+    ("ArtBox", "BleedBox"),
+];
+
+/// The rotation and boundaries of pages
+///
+/// Boundaries and rotations set on a page replace those it would inherit from
+/// the page tree.
+impl<'a> PdfWriter<'a> {
+    /// The number of degrees by which the page at `index` is rotated clockwise
+    /// when it is displayed or printed
+    pub fn rotation(&mut self, index: usize) -> PdfResult<i32> {
+        let (_, page) = self.page_entries(index)?;
+
+        Ok(match page.peek("Rotate").cloned() {
+            Some(rotate) => self.assert_integer(rotate)?.rem_euclid(360),
+            None => 0,
+        })
+    }
+
+    /// Rotate the page at `index` clockwise by `degrees` from its upright
+    /// position, which must be a multiple of 90
+    pub fn set_rotation(&mut self, index: usize, degrees: i32) -> PdfResult<()> {
+        if degrees % 90 != 0 {
+            anyhow::bail!(
+                "pages may only be rotated by multiples of 90 degrees, not {}",
+                degrees
+            );
+        }
+
+        let (page, _) = self.page_entries(index)?;
+
+        set_entry(
+            self,
+            page,
+            "Rotate",
+            Object::Integer(degrees.rem_euclid(360)),
+        );
+
+        Ok(())
+    }
+
+    /// Set the boundaries of the medium on which the page at `index` is to be
+    /// displayed or printed, which must contain all of its other boundaries
+    pub fn set_media_box(&mut self, index: usize, media_box: Rectangle) -> PdfResult<()> {
+        self.set_page_box(index, "MediaBox", media_box)
+    }
+
+    /// Set the region to which the page at `index` is clipped when it is
+    /// displayed or printed, which must lie within its media box
+    pub fn set_crop_box(&mut self, index: usize, crop_box: Rectangle) -> PdfResult<()> {
+        self.set_page_box(index, "CropBox", crop_box)
+    }
+
+    /// Set the region to which the page at `index` is clipped in a production
+    /// environment, which must lie within its crop box
+    pub fn set_bleed_box(&mut self, index: usize, bleed_box: Rectangle) -> PdfResult<()> {
+        self.set_page_box(index, "BleedBox", bleed_box)
+    }
+
+    /// Set the intended dimensions of the page at `index` after trimming,
+    /// which must lie within its bleed box
+    pub fn set_trim_box(&mut self, index: usize, trim_box: Rectangle) -> PdfResult<()> {
+        self.set_page_box(index, "TrimBox", trim_box)
+    }
+
+    /// Set the extent of the meaningful content of the page at `index`, which
+    /// must lie within its bleed box
+    pub fn set_art_box(&mut self, index: usize, art_box: Rectangle) -> PdfResult<()> {
+        self.set_page_box(index, "ArtBox", art_box)
+    }
+
+    fn set_page_box(&mut self, index: usize, key: &str, rect: Rectangle) -> PdfResult<()> {
+        let (page, mut entries) = self.page_entries(index)?;
+
+        entries.insert(key, rect.to_obj()?);
+        self.validate_boxes(&entries)?;
+
+        set_entry(self, page, key, rect.to_obj()?);
+
+        Ok(())
+    }
+
+    /// The page at `index`, with the entries it inherits
+    pub(super) fn page_entries(&self, index: usize) -> PdfResult<(Reference, Dictionary<'a>)> {
+        let mut pages = self.pages_with_inherited()?;
+
+        if index >= pages.len() {
+            anyhow::bail!("there is no page {} of {} pages", index, pages.len());
+        }
+
+        let (page, mut entries) = pages.swap_remove(index);
+
+        if let Some(Object::Dictionary(dict)) = self.get(page) {
+            for (key, value) in dict.iter() {
+                entries.insert(key, value.clone());
+            }
+        }
+
+        Ok((page, entries))
+    }
+
+    /// Check that each boundary of `page` lies within the one which contains
+    /// it
+    fn validate_boxes(&mut self, page: &Dictionary<'a>) -> PdfResult<()> {
+        let media_box = match page.peek("MediaBox") {
+            Some(media_box) => Rectangle::from_obj(media_box.clone(), self)?,
+            None => anyhow::bail!("the page has no media box"),
+        };
+
+        if media_box.width() == 0.0 || media_box.height() == 0.0 {
+            anyhow::bail!("the media box of the page is empty");
+        }
+
+        let mut boxes = vec![("MediaBox", media_box)];
+
+        for (inner, outer) in NESTED_BOXES {
+            let outer_box = match boxes.iter().find(|(key, _)| *key == outer) {
+                Some((_, outer_box)) => *outer_box,
+                None => media_box,
+            };
+
+            let inner_box = match page.peek(inner) {
+                Some(inner_box) => Rectangle::from_obj(inner_box.clone(), self)?,
+                None => outer_box,
+            };
+
+            if !outer_box.contains(&inner_box) {
+                anyhow::bail!(
+                    "the {} of the page does not lie within its {}",
+                    inner,
+                    outer
+                );
+            }
+
+            boxes.push((inner, inner_box));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        version::PdfVersion,
+        writer::{DocumentBuilder, PageBuilder},
+    };
+
+    #[test]
+    fn nest_page_boxes() {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+        builder.add_page(PageBuilder::new(612.0, 792.0)).unwrap();
+
+        let mut writer = builder.build().unwrap();
+
+        writer
+            .set_crop_box(0, Rectangle::new(36.0, 36.0, 576.0, 756.0))
+            .unwrap();
+        writer
+            .set_trim_box(0, Rectangle::new(72.0, 72.0, 540.0, 720.0))
+            .unwrap();
+
+        // outside of the crop box, which the bleed box defaults to
+        assert!(writer
+            .set_art_box(0, Rectangle::new(0.0, 0.0, 540.0, 720.0))
+            .is_err());
+
+        // would no longer contain the crop box
+        assert!(writer
+            .set_media_box(0, Rectangle::new(0.0, 0.0, 500.0, 792.0))
+            .is_err());
+
+        writer.set_rotation(0, -90).unwrap();
+        assert_eq!(writer.rotation(0).unwrap(), 270);
+        assert!(writer.set_rotation(0, 45).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{
+    catalog::InformationDictionary,
+    data_structures::Rectangle,
+    error::PdfResult,
+    objects::{encode_text_string, Dictionary, Object, Reference},
+    version::PdfVersion,
+    ToObj,
+};
+
+use super::{dictionary, flate_stream, PdfWriter};
+
+/// Creates a document from scratch, with a catalog, a page tree holding the
+/// pages in the order in which they are added, and an information dictionary
+///
+/// Objects shared between pages, such as fonts and images, are added with
+/// [`DocumentBuilder::add_object`] and referred to from the resources of each
+/// page.
+#[derive(Debug, Clone)]
+pub struct DocumentBuilder<'a> {
+    writer: PdfWriter<'a>,
+
+    /// The root of the page tree, whose number is reserved so that pages may
+    /// refer to it as their parent before it is written
+    page_tree: Reference,
+    pages: Vec<Reference>,
+    info: InformationDictionary<'a>,
+}
+
+/// A page to be added to a document, with its content stream and the
+/// resources used by it
+#[derive(Debug, Clone)]
+pub struct PageBuilder<'a> {
+    media_box: Rectangle,
+    contents: Vec<u8>,
+
+    /// Each named resource, by category, such as Font or XObject
+    resources: BTreeMap<String, BTreeMap<String, Object<'a>>>,
+}
+
+impl<'a> DocumentBuilder<'a> {
+    pub fn new(version: PdfVersion) -> Self {
+        let mut writer = PdfWriter::new(version);
+        let page_tree = writer.add(Object::Null);
+
+        Self {
+            writer,
+            page_tree,
+            pages: Vec::new(),
+            info: InformationDictionary::default(),
+        }
+    }
+
+    pub fn title(&mut self, title: &str) -> &mut Self {
+        self.info.title = Some(encode_text_string(title));
+        self
+    }
+
+    pub fn author(&mut self, author: &str) -> &mut Self {
+        self.info.author = Some(encode_text_string(author));
+        self
+    }
+
+    pub fn subject(&mut self, subject: &str) -> &mut Self {
+        self.info.subject = Some(encode_text_string(subject));
+        self
+    }
+
+    pub fn keywords(&mut self, keywords: &str) -> &mut Self {
+        self.info.keywords = Some(encode_text_string(keywords));
+        self
+    }
+
+    pub fn creator(&mut self, creator: &str) -> &mut Self {
+        self.info.creator = Some(encode_text_string(creator));
+        self
+    }
+
+    pub fn producer(&mut self, producer: &str) -> &mut Self {
+        self.info.producer = Some(encode_text_string(producer));
+        self
+    }
+
+    /// Add an indirect object, such as a font or image shared between pages
+    pub fn add_object(&mut self, obj: Object<'a>) -> Reference {
+        self.writer.add(obj)
+    }
+
+    /// Add a page after all others, along with its content stream
+    pub fn add_page(&mut self, page: PageBuilder<'a>) -> PdfResult<Reference> {
+        let contents = self.writer.add(Object::Stream(flate_stream(
+            &page.contents,
+            Dictionary::empty(),
+        )?));
+
+        let resources = page
+            .resources
+            .into_iter()
+            .map(|(category, resources)| {
+                (
+                    category,
+                    Object::Dictionary(Dictionary::new(resources.into_iter().collect())),
+                )
+            })
+            .collect();
+
+        let reference = self.writer.add(Object::Dictionary(dictionary([
+            ("Type", Object::Name("Page".to_owned())),
+            ("Parent", Object::Reference(self.page_tree)),
+            ("MediaBox", page.media_box.to_obj()?),
+            ("Resources", Object::Dictionary(Dictionary::new(resources))),
+            ("Contents", Object::Reference(contents)),
+        ])));
+
+        self.pages.push(reference);
+
+        Ok(reference)
+    }
+
+    /// The writer holding the complete document, to which further objects may
+    /// be added
+    pub fn build(mut self) -> PdfResult<PdfWriter<'a>> {
+        self.writer.insert(
+            self.page_tree,
+            Object::Dictionary(dictionary([
+                ("Type", Object::Name("Pages".to_owned())),
+                (
+                    "Kids",
+                    Object::Array(self.pages.iter().copied().map(Object::Reference).collect()),
+                ),
+                ("Count", self.pages.len().to_obj()?),
+            ])),
+        );
+
+        let catalog = self.writer.add(Object::Dictionary(dictionary([
+            ("Type", Object::Name("Catalog".to_owned())),
+            ("Pages", Object::Reference(self.page_tree)),
+        ])));
+
+        self.writer.set_root(catalog);
+
+        let info = self.info.to_obj()?;
+
+        if info != Object::Dictionary(Dictionary::empty()) {
+            let info = self.writer.add(info);
+            self.writer.set_info(info);
+        }
+
+        Ok(self.writer)
+    }
+
+    pub fn save(self, path: impl AsRef<Path>) -> PdfResult<()> {
+        self.build()?.save(path)
+    }
+
+    pub fn to_bytes(self) -> PdfResult<Vec<u8>> {
+        self.build()?.to_bytes()
+    }
+}
+
+impl<'a> PageBuilder<'a> {
+    /// A page of the given size, in default user space units of 1/72 inch
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            media_box: Rectangle::new(0.0, 0.0, width, height),
+            contents: Vec::new(),
+            resources: BTreeMap::new(),
+        }
+    }
+
+    /// Set the content stream of the page, the operators which paint it
+    pub fn contents(&mut self, contents: impl Into<Vec<u8>>) -> &mut Self {
+        self.contents = contents.into();
+        self
+    }
+
+    /// Make a resource available to the content stream under `name`, such as
+    /// a font in the Font category
+    pub fn resource(
+        &mut self,
+        category: &str,
+        name: impl Into<String>,
+        resource: Object<'a>,
+    ) -> &mut Self {
+        self.resources
+            .entry(category.to_owned())
+            .or_default()
+            .insert(name.into(), resource);
+        self
+    }
+
+    pub fn font(&mut self, name: impl Into<String>, font: Reference) -> &mut Self {
+        self.resource("Font", name, Object::Reference(font))
+    }
+
+    pub fn xobject(&mut self, name: impl Into<String>, xobject: Reference) -> &mut Self {
+        self.resource("XObject", name, Object::Reference(xobject))
+    }
+
+    pub fn ext_g_state(&mut self, name: impl Into<String>, ext_g_state: Reference) -> &mut Self {
+        self.resource("ExtGState", name, Object::Reference(ext_g_state))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{content::ContentToken, ParseOptions, Parser};
+
+    #[test]
+    fn build_document() {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+        builder.title("Report \u{2014} 2024").author("Author");
+
+        let font = builder.add_object(Object::Dictionary(dictionary([
+            ("Type", Object::Name("Font".to_owned())),
+            ("Subtype", Object::Name("Type1".to_owned())),
+            ("BaseFont", Object::Name("Helvetica".to_owned())),
+        ])));
+
+        for text in ["first", "second"] {
+            let mut page = PageBuilder::new(612.0, 792.0);
+            page.font("F1", font)
+                .contents(format!("BT /F1 12 Tf 72 720 Td ({}) Tj ET", text));
+
+            builder.add_page(page).unwrap();
+        }
+
+        let mut parser =
+            Parser::from_bytes(builder.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        let pages = parser.pages();
+        assert_eq!(pages.len(), 2);
+
+        let info = parser.info().unwrap().unwrap().into_owned();
+        assert_eq!(info.author.as_deref(), Some("Author"));
+
+        let mut shown = Vec::new();
+
+        for page in &pages {
+            for token in parser.page_contents(page).unwrap() {
+                if let ContentToken::Object(Object::String(s)) = token.unwrap() {
+                    shown.push(s);
+                }
+            }
+        }
+
+        assert_eq!(shown, vec!["first", "second"]);
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    error::PdfResult,
+    objects::{Object, Reference, TypedReference},
+    trailer::Trailer,
+    version::PdfVersion,
+    Resolve, ToObj,
+};
+
+use super::PdfWriter;
+
+/// Copy the objects reachable from the trailer of a document into a writer,
+/// dropping all others and renumbering them compactly in the order in which
+/// they are reached
+///
+/// Objects are read through `resolver`, so strings and streams are written
+/// decrypted, and the document is written without its Encrypt entry.
+/// References to objects which do not exist are replaced by null, which is
+/// how they are read.
+pub(crate) fn compact<'a>(
+    resolver: &mut dyn Resolve<'a>,
+    trailer: &Trailer<'a>,
+    version: PdfVersion,
+) -> PdfResult<PdfWriter<'a>> {
+    let mut walk = Walk::default();
+
+    walk.reach(trailer.root, resolver)?;
+
+    let mut info = match &trailer.info {
+        Some(TypedReference::Indirect { reference, .. }) => Object::Reference(*reference),
+        Some(TypedReference::Direct(info)) => info.to_obj()?,
+        None => Object::Null,
+    };
+
+    walk.reach_within(&mut info, resolver)?;
+
+    let mut objects = Vec::new();
+
+    while let Some(reference) = walk.queue.pop_front() {
+        let mut obj = resolver.lex_object_from_reference(reference)?;
+
+        // the data is written decrypted, so it no longer passes through the
+        // security handler
+        if let Object::Stream(stream) = &mut obj {
+            if resolver.security_handler().is_some() {
+                stream.dict.remove_crypt_filters();
+            }
+        }
+
+        walk.reach_within(&mut obj, resolver)?;
+
+        objects.push((walk.renumbered[&reference], obj));
+    }
+
+    let mut renumber = |obj: &mut Object<'a>| -> PdfResult<()> {
+        if let Object::Reference(reference) = obj {
+            *obj = match walk.renumbered.get(reference) {
+                Some(reference) => Object::Reference(*reference),
+                None => Object::Null,
+            };
+        }
+
+        Ok(())
+    };
+
+    let mut writer = PdfWriter::new(version);
+
+    for (reference, mut obj) in objects {
+        for_each_reference(&mut obj, &mut renumber)?;
+        writer.insert(reference, obj);
+    }
+
+    for_each_reference(&mut info, &mut renumber)?;
+
+    match walk.renumbered.get(&trailer.root) {
+        Some(root) => writer.set_root(*root),
+        None => anyhow::bail!("the document catalog does not exist"),
+    }
+
+    if info != Object::Null {
+        writer.set_trailer_entry("Info", info);
+    }
+
+    if let Some(id) = &trailer.id {
+        writer.set_trailer_entry("ID", id.to_obj()?);
+    }
+
+    Ok(writer)
+}
+
+/// The objects reached so far, with the references they will be written
+/// under, and those whose own references have yet to be followed
+#[derive(Default)]
+struct Walk {
+    renumbered: HashMap<Reference, Reference>,
+    queue: VecDeque<Reference>,
+}
+
+impl Walk {
+    fn reach(&mut self, reference: Reference, resolver: &mut dyn Resolve) -> PdfResult<()> {
+        if self.renumbered.contains_key(&reference) || !resolver.reference_exists(reference)? {
+            return Ok(());
+        }
+
+        let renumbered = Reference {
+            object_number: self.renumbered.len() + 1,
+            generation: 0,
+        };
+
+        self.renumbered.insert(reference, renumbered);
+        self.queue.push_back(reference);
+
+        Ok(())
+    }
+
+    fn reach_within(&mut self, obj: &mut Object, resolver: &mut dyn Resolve) -> PdfResult<()> {
+        for_each_reference(obj, &mut |obj| match obj {
+            Object::Reference(reference) => self.reach(*reference, resolver),
+            _ => Ok(()),
+        })
+    }
+}
+
+/// Call `f` with each reference within `obj`, including `obj` itself
+pub(super) fn for_each_reference<'a>(
+    obj: &mut Object<'a>,
+    f: &mut dyn FnMut(&mut Object<'a>) -> PdfResult<()>,
+) -> PdfResult<()> {
+    match obj {
+        Object::Reference(..) => f(obj)?,
+        Object::Array(arr) => {
+            for obj in arr {
+                for_each_reference(obj, f)?;
+            }
+        }
+        Object::Dictionary(dict) => {
+            for obj in dict.values_mut() {
+                for_each_reference(obj, f)?;
+            }
+        }
+        Object::Stream(stream) => {
+            for obj in stream.dict.values_mut() {
+                for_each_reference(obj, f)?;
+            }
+        }
+        Object::Null
+        | Object::True
+        | Object::False
+        | Object::Integer(..)
+        | Object::Real(..)
+        | Object::String(..)
+        | Object::Name(..) => {}
+    }
+
+    Ok(())
+}
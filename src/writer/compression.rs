@@ -0,0 +1,209 @@
+use std::borrow::Cow;
+
+use flate2::Compression;
+
+use crate::{
+    error::PdfResult,
+    filter::{decode_stream, flate::encode_flate, FilterKind},
+    objects::{Object, Reference},
+    stream::Stream,
+};
+
+use super::PdfWriter;
+
+/// How the data of a stream is filtered when it is written
+///
+/// Only streams whose filters can be reversed without loss, Flate, LZW,
+/// ASCIIHex, ASCII85 and RunLength, are decoded. Streams with any other filter, such as images
+/// compressed with DCTDecode, are always written as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamCompression {
+    /// Write streams with the filters they already have
+    Keep,
+
+    /// Write streams without filters
+    Decompress,
+
+    /// Compress streams with Flate at `level`, from 0 for no compression to 9
+    /// for the best compression, decoding them first if they are already
+    /// filtered
+    Flate { level: u32 },
+
+    /// Compress streams which have no filters with Flate at `level`, and write
+    /// all others with the filters they already have
+    ///
+    /// Metadata streams are left uncompressed, so that their XML can be read
+    /// by tools which do not understand PDF.
+    FlateUnfiltered { level: u32 },
+}
+
+impl Default for StreamCompression {
+    fn default() -> Self {
+        Self::FlateUnfiltered { level: 6 }
+    }
+}
+
+/// The filters applied to streams as they are written
+impl<'a> PdfWriter<'a> {
+    /// Choose how streams are filtered when they are written, which by default
+    /// compresses those which are uncompressed with Flate
+    pub fn set_stream_compression(&mut self, compression: StreamCompression) {
+        self.stream_compression = compression;
+    }
+
+    /// Choose how the stream `reference` is filtered when it is written, in
+    /// place of the choice made for all streams
+    pub fn set_stream_compression_for(
+        &mut self,
+        reference: Reference,
+        compression: StreamCompression,
+    ) {
+        self.stream_compressions.insert(reference, compression);
+    }
+
+    /// The object with the given reference as it is to be written, with its
+    /// data filtered as chosen if it is a stream
+    pub(super) fn prepared<'b>(
+        &self,
+        reference: Reference,
+        obj: &'b Object<'a>,
+    ) -> PdfResult<Cow<'b, Object<'a>>> {
+        let stream = match obj {
+            Object::Stream(stream) => stream,
+            obj => return Ok(Cow::Borrowed(obj)),
+        };
+
+        let compression = self.stream_compressions.get(&reference).copied();
+
+        let filters = stream.dict.filter.as_deref().unwrap_or_default();
+        let decodable = stream.dict.f.is_none()
+            && filters.iter().all(|filter| {
+                matches!(
+                    filter,
+                    FilterKind::Flate
+                        | FilterKind::Lzw
+                        | FilterKind::AsciiHex
+                        | FilterKind::Ascii85
+                        | FilterKind::RunLength
+                )
+            });
+
+        let level = match compression.unwrap_or(self.stream_compression) {
+            StreamCompression::Keep => return Ok(Cow::Borrowed(obj)),
+            StreamCompression::Decompress if filters.is_empty() || !decodable => {
+                return Ok(Cow::Borrowed(obj))
+            }
+            StreamCompression::Decompress => None,
+            StreamCompression::Flate { level } if decodable => Some(level),
+            StreamCompression::Flate { .. } => return Ok(Cow::Borrowed(obj)),
+            StreamCompression::FlateUnfiltered { level } => {
+                // an explicit choice for this stream overrides the exception
+                // for metadata
+                let metadata = compression.is_none()
+                    && stream
+                        .dict
+                        .other
+                        .peek("Type")
+                        .map_or(false, |ty| ty.name_is("Metadata"));
+
+                if !filters.is_empty() || stream.dict.f.is_some() || metadata {
+                    return Ok(Cow::Borrowed(obj));
+                }
+
+                Some(level)
+            }
+        };
+
+        let data = decode_stream(&stream.stream, &stream.dict, &mut &*self)?;
+
+        let mut prepared = Stream::new(data.into_owned(), stream.dict.other.clone());
+
+        if let Some(level) = level {
+            let compressed = encode_flate(&prepared.stream, Compression::new(level.min(9)))?;
+
+            // data which compression would not make any smaller, such as that
+            // of an empty stream, is kept as it is
+            if !filters.is_empty() || compressed.len() < prepared.stream.len() {
+                prepared.stream = Cow::Owned(compressed);
+                prepared.dict.filter = Some(vec![FilterKind::Flate]);
+            }
+        }
+
+        Ok(Cow::Owned(Object::Stream(prepared)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        objects::Dictionary, version::PdfVersion, writer::flate_stream, Lexer, ParseOptions,
+        Resolve,
+    };
+
+    #[test]
+    fn recompress_streams() {
+        let data = b"0 0 m 612 792 l S ".repeat(32);
+        let xml = b"<x:xmpmeta/>".repeat(8);
+
+        let mut writer = PdfWriter::new(PdfVersion::V1_4);
+
+        let uncompressed = writer.add(Object::Stream(Stream::new(
+            data.clone(),
+            Dictionary::empty(),
+        )));
+        let compressed = writer.add(Object::Stream(
+            flate_stream(&data, Dictionary::empty()).unwrap(),
+        ));
+
+        let mut metadata = Dictionary::empty();
+        metadata.insert("Type", Object::Name("Metadata".to_owned()));
+        metadata.insert("Subtype", Object::Name("XML".to_owned()));
+
+        let xmp = writer.add(Object::Stream(Stream::new(xml.clone(), metadata)));
+
+        let root = writer.add(Object::Null);
+        writer.set_root(root);
+
+        let written = |writer: &PdfWriter| {
+            let (mut lexer, _) =
+                Lexer::open(writer.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+            [uncompressed, compressed, xmp].map(|reference| {
+                let stream = lexer.assert_stream(Object::Reference(reference)).unwrap();
+                let decoded = decode_stream(&stream.stream, &stream.dict, &mut lexer)
+                    .unwrap()
+                    .into_owned();
+
+                (stream.dict.filter, decoded)
+            })
+        };
+
+        let flate = Some(vec![FilterKind::Flate]);
+
+        assert_eq!(
+            written(&writer),
+            [
+                (flate.clone(), data.clone()),
+                (flate.clone(), data.clone()),
+                (None, xml.clone()),
+            ]
+        );
+
+        writer.set_stream_compression(StreamCompression::Decompress);
+        writer.set_stream_compression_for(xmp, StreamCompression::Flate { level: 9 });
+
+        assert_eq!(
+            written(&writer),
+            [
+                (None, data.clone()),
+                (None, data.clone()),
+                (flate.clone(), xml.clone()),
+            ]
+        );
+
+        writer.set_stream_compression_for(compressed, StreamCompression::Keep);
+
+        assert_eq!(written(&writer)[1], (flate, data));
+    }
+}
@@ -0,0 +1,416 @@
+use crate::{
+    content::{ContentToken, PdfGraphicsOperator},
+    error::PdfResult,
+    filter::decode_stream,
+    objects::Object,
+    Resolve,
+};
+
+use super::{object::write_name, write_object, PdfWriter};
+
+/// Writes a content stream one operator at a time
+///
+/// Graphics states saved with [`ContentBuilder::save_state`] which are not
+/// restored, and any text object which is not ended, are closed by
+/// [`ContentBuilder::finish`], so that the stream is always balanced.
+///
+/// ```ignore
+/// let mut content = ContentBuilder::new();
+///
+/// content
+///     .with_saved_state(|content| {
+///         content.set_fill_rgb(1.0, 0.0, 0.0).rectangle(72.0, 72.0, 144.0, 72.0).fill();
+///     })
+///     .begin_text()
+///     .set_font("F1", 12.0)
+///     .move_text(72.0, 720.0)
+///     .show_text("Hello");
+///
+/// page.contents(content.finish());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ContentBuilder {
+    out: Vec<u8>,
+
+    /// The number of graphics states saved and not yet restored
+    saved_states: usize,
+    in_text: bool,
+}
+
+impl ContentBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The content stream, after restoring every saved graphics state and
+    /// ending the current text object, if any
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.in_text {
+            self.end_text();
+        }
+
+        while self.saved_states > 0 {
+            self.restore_state();
+        }
+
+        self.out
+    }
+
+    fn op(&mut self, operands: &[Object], operator: PdfGraphicsOperator) -> &mut Self {
+        for operand in operands {
+            write_object(operand, &mut self.out).expect("operands are written to memory");
+            self.out.push(b' ');
+        }
+
+        self.out.extend_from_slice(operator.as_str().as_bytes());
+        self.out.push(b'\n');
+
+        self
+    }
+
+    fn numbers<const N: usize>(
+        &mut self,
+        numbers: [f32; N],
+        operator: PdfGraphicsOperator,
+    ) -> &mut Self {
+        self.op(&numbers.map(number), operator)
+    }
+
+    /* Graphics state */
+
+    /// Save the graphics state, which is restored by the matching
+    /// [`ContentBuilder::restore_state`] or when the stream is finished
+    pub fn save_state(&mut self) -> &mut Self {
+        self.saved_states += 1;
+        self.op(&[], PdfGraphicsOperator::q)
+    }
+
+    /// Restore the most recently saved graphics state, if any is saved
+    pub fn restore_state(&mut self) -> &mut Self {
+        if self.saved_states == 0 {
+            return self;
+        }
+
+        self.saved_states -= 1;
+        self.op(&[], PdfGraphicsOperator::Q)
+    }
+
+    /// Paint with `f` between saving and restoring the graphics state, so that
+    /// changes it makes to the graphics state do not outlive it
+    pub fn with_saved_state(&mut self, f: impl FnOnce(&mut Self)) -> &mut Self {
+        let saved_states = self.saved_states;
+
+        self.save_state();
+        f(self);
+
+        while self.saved_states > saved_states {
+            self.restore_state();
+        }
+
+        self
+    }
+
+    /// Concatenate the matrix `[a b c d e f]` to the current transformation
+    /// matrix
+    pub fn transform(&mut self, a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> &mut Self {
+        self.numbers([a, b, c, d, e, f], PdfGraphicsOperator::cm)
+    }
+
+    pub fn translate(&mut self, x: f32, y: f32) -> &mut Self {
+        self.transform(1.0, 0.0, 0.0, 1.0, x, y)
+    }
+
+    pub fn scale(&mut self, x: f32, y: f32) -> &mut Self {
+        self.transform(x, 0.0, 0.0, y, 0.0, 0.0)
+    }
+
+    pub fn set_line_width(&mut self, width: f32) -> &mut Self {
+        self.numbers([width], PdfGraphicsOperator::w)
+    }
+
+    /// Set the dash pattern, as alternating lengths of dashes and gaps
+    /// starting `phase` units into the pattern
+    pub fn set_dash(&mut self, lengths: &[f32], phase: f32) -> &mut Self {
+        let lengths = Object::Array(lengths.iter().copied().map(number).collect());
+
+        self.op(&[lengths, number(phase)], PdfGraphicsOperator::d)
+    }
+
+    /// Set parameters from the graphics state parameter dictionary `name` of
+    /// the ExtGState resources
+    pub fn set_graphics_state(&mut self, name: &str) -> &mut Self {
+        self.op(&[Object::Name(name.to_owned())], PdfGraphicsOperator::gs)
+    }
+
+    /* Color */
+
+    pub fn set_fill_gray(&mut self, gray: f32) -> &mut Self {
+        self.numbers([gray], PdfGraphicsOperator::g)
+    }
+
+    pub fn set_stroke_gray(&mut self, gray: f32) -> &mut Self {
+        self.numbers([gray], PdfGraphicsOperator::G)
+    }
+
+    pub fn set_fill_rgb(&mut self, r: f32, g: f32, b: f32) -> &mut Self {
+        self.numbers([r, g, b], PdfGraphicsOperator::rg)
+    }
+
+    pub fn set_stroke_rgb(&mut self, r: f32, g: f32, b: f32) -> &mut Self {
+        self.numbers([r, g, b], PdfGraphicsOperator::RG)
+    }
+
+    pub fn set_fill_cmyk(&mut self, c: f32, m: f32, y: f32, k: f32) -> &mut Self {
+        self.numbers([c, m, y, k], PdfGraphicsOperator::k)
+    }
+
+    pub fn set_stroke_cmyk(&mut self, c: f32, m: f32, y: f32, k: f32) -> &mut Self {
+        self.numbers([c, m, y, k], PdfGraphicsOperator::K)
+    }
+
+    /* Path construction and painting */
+
+    pub fn move_to(&mut self, x: f32, y: f32) -> &mut Self {
+        self.numbers([x, y], PdfGraphicsOperator::m)
+    }
+
+    pub fn line_to(&mut self, x: f32, y: f32) -> &mut Self {
+        self.numbers([x, y], PdfGraphicsOperator::l)
+    }
+
+    /// Append a cubic Bézier curve to `(x3, y3)`, with the control points
+    /// `(x1, y1)` and `(x2, y2)`
+    pub fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32) -> &mut Self {
+        self.numbers([x1, y1, x2, y2, x3, y3], PdfGraphicsOperator::c)
+    }
+
+    pub fn rectangle(&mut self, x: f32, y: f32, width: f32, height: f32) -> &mut Self {
+        self.numbers([x, y, width, height], PdfGraphicsOperator::re)
+    }
+
+    pub fn close_path(&mut self) -> &mut Self {
+        self.op(&[], PdfGraphicsOperator::h)
+    }
+
+    pub fn stroke(&mut self) -> &mut Self {
+        self.op(&[], PdfGraphicsOperator::S)
+    }
+
+    /// Fill the path using the nonzero winding number rule
+    pub fn fill(&mut self) -> &mut Self {
+        self.op(&[], PdfGraphicsOperator::f)
+    }
+
+    /// Fill the path using the even-odd rule
+    pub fn fill_even_odd(&mut self) -> &mut Self {
+        self.op(&[], PdfGraphicsOperator::f_star)
+    }
+
+    pub fn fill_and_stroke(&mut self) -> &mut Self {
+        self.op(&[], PdfGraphicsOperator::B)
+    }
+
+    /// End the path without painting it, such as after setting it as the
+    /// clipping path
+    pub fn end_path(&mut self) -> &mut Self {
+        self.op(&[], PdfGraphicsOperator::n)
+    }
+
+    /// Intersect the clipping path with the current path, using the nonzero
+    /// winding number rule, once the path is painted or ended
+    pub fn clip(&mut self) -> &mut Self {
+        self.op(&[], PdfGraphicsOperator::W)
+    }
+
+    /* Text */
+
+    /// Begin a text object, which is ended by [`ContentBuilder::end_text`] or
+    /// when the stream is finished
+    pub fn begin_text(&mut self) -> &mut Self {
+        if self.in_text {
+            return self;
+        }
+
+        self.in_text = true;
+        self.op(&[], PdfGraphicsOperator::BT)
+    }
+
+    pub fn end_text(&mut self) -> &mut Self {
+        if !self.in_text {
+            return self;
+        }
+
+        self.in_text = false;
+        self.op(&[], PdfGraphicsOperator::ET)
+    }
+
+    /// Set the font to the font resource `name`, at `size` units
+    pub fn set_font(&mut self, name: &str, size: f32) -> &mut Self {
+        self.op(
+            &[Object::Name(name.to_owned()), number(size)],
+            PdfGraphicsOperator::Tf,
+        )
+    }
+
+    /// Move to the start of the next line, offset from the start of the
+    /// current line
+    pub fn move_text(&mut self, x: f32, y: f32) -> &mut Self {
+        self.numbers([x, y], PdfGraphicsOperator::Td)
+    }
+
+    pub fn set_text_matrix(&mut self, a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> &mut Self {
+        self.numbers([a, b, c, d, e, f], PdfGraphicsOperator::Tm)
+    }
+
+    pub fn set_leading(&mut self, leading: f32) -> &mut Self {
+        self.numbers([leading], PdfGraphicsOperator::TL)
+    }
+
+    pub fn next_line(&mut self) -> &mut Self {
+        self.op(&[], PdfGraphicsOperator::T_star)
+    }
+
+    pub fn set_character_spacing(&mut self, spacing: f32) -> &mut Self {
+        self.numbers([spacing], PdfGraphicsOperator::Tc)
+    }
+
+    pub fn set_word_spacing(&mut self, spacing: f32) -> &mut Self {
+        self.numbers([spacing], PdfGraphicsOperator::Tw)
+    }
+
+    /// Show `text` in the current font
+    ///
+    /// Each character is written as a single byte, the character code of a
+    /// simple font, so characters beyond U+00FF are replaced with `?`.
+    pub fn show_text(&mut self, text: &str) -> &mut Self {
+        let text = text
+            .chars()
+            .map(|c| if (c as u32) < 256 { c } else { '?' })
+            .collect();
+
+        self.op(&[Object::String(text)], PdfGraphicsOperator::Tj)
+    }
+
+    /* XObjects and marked content */
+
+    /// Paint the XObject resource `name`, such as an image or form
+    pub fn draw_xobject(&mut self, name: &str) -> &mut Self {
+        self.op(&[Object::Name(name.to_owned())], PdfGraphicsOperator::Do)
+    }
+
+    pub fn begin_marked_content(&mut self, tag: &str) -> &mut Self {
+        self.op(&[Object::Name(tag.to_owned())], PdfGraphicsOperator::BMC)
+    }
+
+    pub fn end_marked_content(&mut self) -> &mut Self {
+        self.op(&[], PdfGraphicsOperator::EMC)
+    }
+}
+
+impl From<ContentBuilder> for Vec<u8> {
+    fn from(content: ContentBuilder) -> Self {
+        content.finish()
+    }
+}
+
+/// A number as an operand, written as an integer if it has no fractional part
+fn number(n: f32) -> Object<'static> {
+    if n.fract() == 0.0 && n.abs() < i32::MAX as f32 {
+        Object::Integer(n as i32)
+    } else {
+        Object::Real(n)
+    }
+}
+
+/// The data of the page content `contents`, a stream or array of streams, each
+/// decoded and ending a line
+pub(super) fn decoded_content<'a>(
+    document: &mut PdfWriter<'a>,
+    contents: Option<Object<'a>>,
+) -> PdfResult<Vec<u8>> {
+    let streams = match contents {
+        Some(contents) => match document.resolve(contents)? {
+            Object::Array(streams) => streams,
+            contents => vec![contents],
+        },
+        None => Vec::new(),
+    };
+
+    let mut content = Vec::new();
+
+    for stream in streams {
+        let stream = match document.resolve(stream)? {
+            Object::Stream(stream) => stream,
+            _ => continue,
+        };
+
+        content.extend_from_slice(&decode_stream(&stream.stream, &stream.dict, document)?);
+        content.push(b'\n');
+    }
+
+    Ok(content)
+}
+
+/// Write the tokens of a content stream, such as those read by a
+/// [`ContentLexer`](crate::ContentLexer), with each operator ending a line
+pub(super) fn write_content(tokens: &[ContentToken], out: &mut Vec<u8>) -> PdfResult<()> {
+    for token in tokens {
+        match token {
+            ContentToken::Object(obj) => {
+                write_object(obj, out)?;
+                out.push(b' ');
+            }
+            ContentToken::Operator(operator) => {
+                out.extend_from_slice(operator.as_str().as_bytes());
+                out.push(b'\n');
+            }
+            ContentToken::InlineImage(image) => {
+                out.extend_from_slice(b"BI\n");
+
+                for (key, value) in image.dict.iter() {
+                    write_name(key, out)?;
+                    out.push(b' ');
+                    write_object(value, out)?;
+                    out.push(b'\n');
+                }
+
+                // the data begins after the single whitespace character
+                // following ID
+                out.extend_from_slice(b"ID\n");
+                out.extend_from_slice(&image.data);
+                out.extend_from_slice(b"\nEI\n");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn balance_saved_states_and_text() {
+        let mut content = ContentBuilder::new();
+
+        content
+            .with_saved_state(|content| {
+                content
+                    .save_state()
+                    .set_fill_rgb(1.0, 0.5, 0.0)
+                    .rectangle(0.0, 0.0, 10.0, 10.0)
+                    .fill();
+            })
+            .restore_state()
+            .save_state()
+            .begin_text()
+            .set_font("F1", 12.0)
+            .show_text("a(b)");
+
+        assert_eq!(
+            String::from_utf8(content.finish()).unwrap(),
+            "q\nq\n1 0.5 0 rg\n0 0 10 10 re\nf\nQ\nQ\nq\nBT\n/F1 12 Tf\n(a\\(b\\)) Tj\nET\nQ\n"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    error::PdfResult,
+    filter::decode_stream,
+    objects::{Dictionary, Object},
+    signature::ValidationData,
+    trailer::Trailer,
+    Lexer, Resolve,
+};
+
+use super::{dictionary, flate_stream, IncrementalUpdate};
+
+/// Append an incremental update to the file of `lexer` which adds `data` to
+/// the document security store, as the validation data of the signature whose
+/// VRI key is `key`
+///
+/// `dss` is the DSS entry of the document catalog, if the document already
+/// has a security store. Certificates, OCSP responses and CRLs which the store
+/// already holds are referred to rather than written again.
+pub(crate) fn add_validation_data<'a>(
+    trailer: &Trailer<'a>,
+    dss: Option<Object<'a>>,
+    key: String,
+    data: &ValidationData,
+    lexer: &mut Lexer<'a>,
+) -> PdfResult<Vec<u8>> {
+    // the streams of the update would have to be encrypted
+    anyhow::ensure!(
+        trailer.encryption.is_none(),
+        "cannot add validation data to an encrypted document"
+    );
+
+    let mut update = IncrementalUpdate::new(trailer.size);
+
+    let (dss_reference, mut dss) = match dss {
+        Some(Object::Reference(reference)) => (
+            Some(reference),
+            lexer.assert_dict(Object::Reference(reference))?,
+        ),
+        Some(obj) => (None, lexer.assert_dict(obj)?),
+        None => (None, dictionary([("Type", Object::Name("DSS".to_owned()))])),
+    };
+
+    let mut vri_entry = Dictionary::empty();
+
+    for (pool_key, vri_key, items) in [
+        ("Certs", "Cert", &data.certificates),
+        ("OCSPs", "OCSP", &data.ocsp_responses),
+        ("CRLs", "CRL", &data.crls),
+    ] {
+        if items.is_empty() {
+            continue;
+        }
+
+        let mut pool = PooledStreams::new(dss.get_arr(pool_key, lexer)?, lexer)?;
+
+        let objects = items
+            .iter()
+            .map(|item| pool.add(item, &mut update))
+            .collect::<PdfResult<Vec<Object>>>()?;
+
+        dss.insert(pool_key, Object::Array(pool.objects));
+        vri_entry.insert(vri_key, Object::Array(objects));
+    }
+
+    if let Some(time) = &data.time {
+        vri_entry.insert("TU", Object::String(time.to_pdf_string()));
+    }
+
+    if let Some(timestamp) = &data.timestamp {
+        let timestamp = update.add(Object::Stream(flate_stream(
+            timestamp,
+            Dictionary::empty(),
+        )?));
+
+        vri_entry.insert("TS", Object::Reference(timestamp));
+    }
+
+    let mut vri = dss
+        .get_dict("VRI", lexer)?
+        .unwrap_or_else(Dictionary::empty);
+    vri.insert(&key, Object::Dictionary(vri_entry));
+    dss.insert("VRI", Object::Dictionary(vri));
+
+    match dss_reference {
+        Some(reference) => update.insert(reference, Object::Dictionary(dss)),
+        None => {
+            let dss = update.add(Object::Dictionary(dss));
+
+            let mut catalog = lexer.assert_dict(Object::Reference(trailer.root))?;
+            catalog.insert("DSS", Object::Reference(dss));
+
+            update.insert(trailer.root, Object::Dictionary(catalog));
+        }
+    }
+
+    update.write(&lexer.file, IncrementalUpdate::trailer(trailer)?)
+}
+
+/// The streams of one of the arrays of the document security store, such as
+/// Certs, with their decoded data
+struct PooledStreams<'a> {
+    objects: Vec<Object<'a>>,
+    data: Vec<Vec<u8>>,
+}
+
+impl<'a> PooledStreams<'a> {
+    fn new(objects: Option<Vec<Object<'a>>>, resolver: &mut dyn Resolve<'a>) -> PdfResult<Self> {
+        let objects = objects.unwrap_or_default();
+
+        let data = objects
+            .iter()
+            .map(|obj| {
+                let stream = resolver.assert_stream(obj.clone())?;
+                Ok(decode_stream(&stream.stream, &stream.dict, resolver)?.into_owned())
+            })
+            .collect::<PdfResult<Vec<Vec<u8>>>>()?;
+
+        Ok(Self { objects, data })
+    }
+
+    /// The object of the stream holding `item`, which is added to the array
+    /// unless it is already present
+    fn add(&mut self, item: &[u8], update: &mut IncrementalUpdate<'a>) -> PdfResult<Object<'a>> {
+        if let Some(i) = self.data.iter().position(|data| data == item) {
+            return Ok(self.objects[i].clone());
+        }
+
+        let obj =
+            Object::Reference(update.add(Object::Stream(flate_stream(item, Dictionary::empty())?)));
+
+        self.objects.push(obj.clone());
+        self.data.push(item.to_vec());
+
+        Ok(obj)
+    }
+}
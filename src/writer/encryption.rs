@@ -0,0 +1,155 @@
+use std::borrow::Cow;
+
+use crate::{
+    encryption::{EncryptionOptions, SecurityHandler},
+    error::PdfResult,
+    file_specification::FileIdentifier,
+    objects::{Object, Reference},
+    FromObj, ToObj,
+};
+
+use super::PdfWriter;
+
+/// Encryption of the strings and streams of objects as they are written
+impl<'a> PdfWriter<'a> {
+    /// Encrypt the document with the standard security handler when it is
+    /// written, as described by `options`
+    ///
+    /// The objects held by the writer are left unencrypted, so they may still
+    /// be read and changed. The document is given a file identifier if it has
+    /// none, since the encryption key is derived from it.
+    pub fn encrypt(&mut self, options: &EncryptionOptions) -> PdfResult<()> {
+        let file_identifier = match self.trailer.peek("ID") {
+            Some(id) => FileIdentifier::from_obj(id.clone(), &mut &*self)?,
+            None => {
+                let mut id = [0; 16];
+
+                getrandom::getrandom(&mut id)
+                    .map_err(|e| anyhow::anyhow!("failed to generate file identifier: {}", e))?;
+
+                let id = id.into_iter().map(char::from).collect::<String>();
+
+                FileIdentifier([id.clone(), id])
+            }
+        };
+
+        let handler = SecurityHandler::for_writing(options, file_identifier.clone())?;
+
+        let encrypt = match self.trailer.peek("Encrypt") {
+            Some(Object::Reference(reference)) => *reference,
+            _ => self.add(Object::Null),
+        };
+
+        self.insert(encrypt, handler.encryption_dictionary()?);
+        self.set_trailer_entry("Encrypt", Object::Reference(encrypt));
+        self.set_trailer_entry("ID", file_identifier.to_obj()?);
+
+        self.security_handler = Some(handler);
+
+        Ok(())
+    }
+
+    /// The object with the given reference as it is to be written, with its
+    /// strings and streams encrypted if the document is
+    ///
+    /// The encryption dictionary is never encrypted.
+    pub(super) fn encrypted<'b>(
+        &self,
+        reference: Reference,
+        obj: Cow<'b, Object<'a>>,
+    ) -> PdfResult<Cow<'b, Object<'a>>> {
+        let handler = match &self.security_handler {
+            Some(handler) => handler,
+            None => return Ok(obj),
+        };
+
+        if let Some(Object::Reference(encrypt)) = self.trailer.peek("Encrypt") {
+            if *encrypt == reference {
+                return Ok(obj);
+            }
+        }
+
+        let mut obj = obj.into_owned();
+        handler.encrypt_object(&mut obj, reference)?;
+
+        Ok(Cow::Owned(obj))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        encryption::{CryptFilterMethod, PasswordKind, Permissions},
+        filter::decode_stream,
+        objects::Dictionary,
+        stream::Stream,
+        version::PdfVersion,
+        writer::{DocumentBuilder, PageBuilder},
+        ParseOptions, Parser, Resolve,
+    };
+
+    #[test]
+    fn encrypt_and_reopen() {
+        let data = b"0 0 m 10 10 l S".to_vec();
+
+        for (method, object_streams) in [
+            (CryptFilterMethod::V2, false),
+            (CryptFilterMethod::AesV2, true),
+            (CryptFilterMethod::AesV3, true),
+        ] {
+            let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+            builder.add_page(PageBuilder::new(612.0, 792.0)).unwrap();
+
+            let mut writer = builder.build().unwrap();
+            writer.set_object_streams(object_streams);
+
+            let stream = writer.add(Object::Stream(Stream::new(
+                data.clone(),
+                Dictionary::empty(),
+            )));
+            let string = writer.add(Object::String("top secret".to_owned()));
+
+            writer
+                .encrypt(&EncryptionOptions {
+                    user_password: "user".to_owned(),
+                    owner_password: "owner".to_owned(),
+                    permissions: Permissions::from_integer(!(1 << 4)),
+                    method,
+                })
+                .unwrap();
+
+            let bytes = writer.to_bytes().unwrap();
+
+            assert!(!bytes.windows(10).any(|w| w == b"top secret"));
+            assert!(Parser::from_bytes(bytes.clone(), ParseOptions::default()).is_err());
+
+            let options = ParseOptions {
+                password: Some("user".to_owned()),
+                ..ParseOptions::default()
+            };
+
+            let mut parser = Parser::from_bytes(bytes, options).unwrap();
+
+            let decrypted = parser
+                .lexer
+                .assert_stream(Object::Reference(stream))
+                .unwrap();
+            let decoded =
+                decode_stream(&decrypted.stream, &decrypted.dict, &mut parser.lexer).unwrap();
+
+            assert_eq!(decoded.as_ref(), data, "{:?}", method);
+            assert_eq!(
+                parser
+                    .lexer
+                    .assert_string(Object::Reference(string))
+                    .unwrap(),
+                "top secret"
+            );
+
+            assert!(!parser.permissions().can_copy());
+            assert!(parser.permissions().can_print());
+            assert_eq!(parser.authenticate("owner").unwrap(), PasswordKind::Owner);
+        }
+    }
+}
@@ -0,0 +1,442 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Range,
+};
+
+use crate::{
+    content::{ContentLexer, ContentToken},
+    error::PdfResult,
+    filter::{decode_stream, FilterKind},
+    objects::{Dictionary, Object, Reference},
+    Resolve,
+};
+
+use super::{
+    dictionary,
+    merge::{collect_pages, name_tree_entries, prune, resolved},
+    PdfWriter,
+};
+
+/// Copy the pages `range` of a compacted document into a document of their
+/// own, holding only the objects those pages use
+///
+/// Each page keeps the attributes it inherits from the page tree, but only
+/// the entries of its resources which its content streams name, such as the
+/// fonts it shows text in, so that resources shared with pages which are not
+/// extracted are dropped. Pages whose content cannot be decoded keep all of
+/// their resources.
+///
+/// Named destinations which go to the extracted pages are kept, and link
+/// annotations which go to pages which are not extracted are removed. Other
+/// document-level structures, such as the outline and interactive form, are
+/// dropped, and any remaining references to pages which are not extracted
+/// become null.
+pub(crate) fn extract_pages<'a>(
+    mut document: PdfWriter<'a>,
+    range: Range<usize>,
+) -> PdfResult<PdfWriter<'a>> {
+    let root = match document.trailer.peek("Root") {
+        Some(Object::Reference(root)) => *root,
+        _ => anyhow::bail!("the document catalog must be an indirect object"),
+    };
+
+    let catalog = match document.get(root) {
+        Some(Object::Dictionary(catalog)) => catalog.clone(),
+        _ => anyhow::bail!("the document catalog does not exist"),
+    };
+
+    let mut leaves = Vec::new();
+    let mut page_tree_nodes = HashSet::new();
+
+    if let Some(Object::Reference(pages)) = catalog.peek("Pages") {
+        collect_pages(
+            &document,
+            *pages,
+            &Dictionary::empty(),
+            &mut page_tree_nodes,
+            &mut leaves,
+        );
+    }
+
+    if range.start > range.end || range.end > leaves.len() {
+        anyhow::bail!(
+            "pages {}..{} are not within the {} pages of the document",
+            range.start,
+            range.end,
+            leaves.len()
+        );
+    }
+
+    let selected = leaves.drain(range).collect::<Vec<_>>();
+    let selected_pages = selected
+        .iter()
+        .map(|(page, _)| *page)
+        .collect::<HashSet<_>>();
+
+    let mut tree_dests = Vec::new();
+
+    if let Some(Object::Dictionary(names)) = catalog
+        .peek("Names")
+        .and_then(|names| resolved(&document, names))
+    {
+        if let Some(tree) = names.peek("Dests") {
+            name_tree_entries(&document, tree, &mut HashSet::new(), &mut tree_dests);
+        }
+    }
+
+    let tree_dests = tree_dests.into_iter().collect::<HashMap<_, _>>();
+
+    let old_dests = match catalog
+        .peek("Dests")
+        .and_then(|old_dests| resolved(&document, old_dests))
+    {
+        Some(Object::Dictionary(old_dests)) => old_dests.clone(),
+        _ => Dictionary::empty(),
+    };
+
+    let goes_to_selected = |document: &PdfWriter<'a>, dest: &Object<'a>| {
+        destination_page(document, dest).map_or(false, |page| selected_pages.contains(&page))
+    };
+
+    let page_tree = document.add(Object::Null);
+
+    for (page, inherited) in &selected {
+        let mut page_dict = match document.get(*page) {
+            Some(Object::Dictionary(page_dict)) => page_dict.clone(),
+            _ => continue,
+        };
+
+        for (key, value) in inherited.iter() {
+            if page_dict.peek(key).is_none() {
+                page_dict.insert(key, value.clone());
+            }
+        }
+
+        if let Some(Some(Object::Array(annots))) = page_dict
+            .peek("Annots")
+            .map(|annots| resolved(&document, annots))
+        {
+            let annots = annots
+                .iter()
+                .filter(|annot| match resolved(&document, annot) {
+                    Some(Object::Dictionary(annot)) => {
+                        match link_target(&document, annot, &tree_dests, &old_dests) {
+                            Some(dest) => goes_to_selected(&document, dest),
+                            None => true,
+                        }
+                    }
+                    _ => true,
+                })
+                .cloned()
+                .collect();
+
+            page_dict.insert("Annots", Object::Array(annots));
+        }
+
+        if let Some(names) = content_names(&mut document, page_dict.peek("Contents").cloned())? {
+            if let Some(Some(Object::Dictionary(resources))) = page_dict
+                .peek("Resources")
+                .map(|resources| resolved(&document, resources))
+            {
+                let resources = used_resources(&document, resources, &names);
+                page_dict.insert("Resources", Object::Dictionary(resources));
+            }
+        }
+
+        page_dict.insert("Parent", Object::Reference(page_tree));
+        document.insert(*page, Object::Dictionary(page_dict));
+    }
+
+    let tree_dests = tree_dests
+        .into_iter()
+        .filter(|(_, dest)| goes_to_selected(&document, dest))
+        .collect::<BTreeMap<_, _>>();
+
+    let old_dests = Dictionary::new(
+        old_dests
+            .iter()
+            .filter(|(_, dest)| goes_to_selected(&document, dest))
+            .map(|(name, dest)| (name.to_owned(), dest.clone()))
+            .collect(),
+    );
+
+    // the rest of the page tree, and the catalog which refers to it, are
+    // replaced
+    for node in page_tree_nodes.into_iter().chain([root]) {
+        if !selected_pages.contains(&node) {
+            document.objects.remove(&node.object_number);
+        }
+    }
+
+    document.insert(
+        page_tree,
+        Object::Dictionary(dictionary([
+            ("Type", Object::Name("Pages".to_owned())),
+            (
+                "Kids",
+                Object::Array(
+                    selected
+                        .iter()
+                        .map(|(page, _)| Object::Reference(*page))
+                        .collect(),
+                ),
+            ),
+            ("Count", Object::Integer(i32::try_from(selected.len())?)),
+        ])),
+    );
+
+    let mut catalog = dictionary([
+        ("Type", Object::Name("Catalog".to_owned())),
+        ("Pages", Object::Reference(page_tree)),
+    ]);
+
+    if !tree_dests.is_empty() {
+        let names = tree_dests
+            .into_iter()
+            .flat_map(|(name, dest)| [Object::String(name), dest])
+            .collect();
+
+        catalog.insert(
+            "Names",
+            Object::Dictionary(dictionary([(
+                "Dests",
+                Object::Dictionary(dictionary([("Names", Object::Array(names))])),
+            )])),
+        );
+    }
+
+    if !old_dests.is_empty() {
+        catalog.insert("Dests", Object::Dictionary(old_dests));
+    }
+
+    let root = document.add(Object::Dictionary(catalog));
+
+    // the file identifier and any other entries belong to the original
+    // document
+    let info = document.trailer.peek("Info").cloned();
+    document.trailer = Dictionary::empty();
+    document.set_root(root);
+
+    if let Some(info) = info {
+        document.set_trailer_entry("Info", info);
+    }
+
+    prune(document)
+}
+
+/// The page an explicit destination, or a destination dictionary, goes to
+fn destination_page(document: &PdfWriter, dest: &Object) -> Option<Reference> {
+    match resolved(document, dest)? {
+        Object::Array(dest) => match dest.first()? {
+            Object::Reference(page) => Some(*page),
+            _ => None,
+        },
+        Object::Dictionary(dest) => destination_page(document, dest.peek("D")?),
+        _ => None,
+    }
+}
+
+/// The destination of a link annotation, or of a link whose GoTo action goes
+/// to a location within the document, with named destinations looked up
+fn link_target<'b, 'a>(
+    document: &'b PdfWriter<'a>,
+    annot: &'b Dictionary<'a>,
+    tree_dests: &'b HashMap<String, Object<'a>>,
+    old_dests: &'b Dictionary<'a>,
+) -> Option<&'b Object<'a>> {
+    let dest = match annot.peek("Dest") {
+        Some(dest) => dest,
+        None => match annot
+            .peek("A")
+            .and_then(|action| resolved(document, action))
+        {
+            Some(Object::Dictionary(action))
+                if action.peek("S").map_or(false, |s| s.name_is("GoTo")) =>
+            {
+                action.peek("D")?
+            }
+            _ => return None,
+        },
+    };
+
+    match dest {
+        Object::String(name) => tree_dests.get(name),
+        Object::Name(name) => old_dests.peek(name),
+        dest => Some(dest),
+    }
+}
+
+/// The names used as operands within the content streams `contents`, such as
+/// the fonts passed to Tf, or `None` if they cannot all be decoded
+fn content_names<'a>(
+    document: &mut PdfWriter<'a>,
+    contents: Option<Object<'a>>,
+) -> PdfResult<Option<HashSet<String>>> {
+    let streams = match contents {
+        Some(contents) => match document.resolve(contents)? {
+            Object::Array(streams) => streams,
+            contents => vec![contents],
+        },
+        None => Vec::new(),
+    };
+
+    let mut names = HashSet::new();
+
+    for stream in streams {
+        let stream = match document.resolve(stream)? {
+            Object::Stream(stream) => stream,
+            _ => continue,
+        };
+
+        let decodable = stream.dict.filter.iter().flatten().all(|filter| {
+            matches!(
+                filter,
+                FilterKind::Flate | FilterKind::Lzw | FilterKind::AsciiHex | FilterKind::Ascii85
+            )
+        });
+
+        if !decodable {
+            return Ok(None);
+        }
+
+        let data = match decode_stream(&stream.stream, &stream.dict, document) {
+            Ok(data) => data.into_owned(),
+            Err(..) => return Ok(None),
+        };
+
+        if !insert_content_names(Cow::Owned(data), &mut names) {
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(names))
+}
+
+/// Insert the names used as operands within the content stream `data` into
+/// `names`, returning false if it cannot be lexed
+pub(super) fn insert_content_names(data: Cow<[u8]>, names: &mut HashSet<String>) -> bool {
+    for token in ContentLexer::new(data) {
+        match token {
+            Ok(ContentToken::Object(Object::Name(name))) => {
+                names.insert(name);
+            }
+            // the colour space of an inline image may name a resource
+            Ok(ContentToken::InlineImage(image)) => {
+                if let Some(Object::Name(name)) = image.expanded_dict().peek("ColorSpace") {
+                    names.insert(name.clone());
+                }
+            }
+            Ok(..) => {}
+            Err(..) => return false,
+        }
+    }
+
+    true
+}
+
+/// The entries of each category of `resources` which are named in `names`,
+/// dropping categories which have none
+///
+/// Entries which are not dictionaries of named resources, such as ProcSet,
+/// are kept as they are.
+pub(super) fn used_resources<'a>(
+    document: &PdfWriter<'a>,
+    resources: &Dictionary<'a>,
+    names: &HashSet<String>,
+) -> Dictionary<'a> {
+    let mut used = Dictionary::empty();
+
+    for (category, value) in resources.iter() {
+        match resolved(document, value) {
+            Some(Object::Dictionary(entries)) => {
+                let entries = entries
+                    .iter()
+                    .filter(|(name, _)| names.contains(*name))
+                    .map(|(name, resource)| (name.to_owned(), resource.clone()))
+                    .collect::<HashMap<_, _>>();
+
+                if !entries.is_empty() {
+                    used.insert(category, Object::Dictionary(Dictionary::new(entries)));
+                }
+            }
+            _ => {
+                used.insert(category, value.clone());
+            }
+        }
+    }
+
+    used
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        version::PdfVersion,
+        writer::{DocumentBuilder, PageBuilder},
+        ParseOptions, Parser,
+    };
+
+    #[test]
+    fn extract_page_with_used_resources() {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+
+        let font = |base_font: &str| {
+            Object::Dictionary(dictionary([
+                ("Type", Object::Name("Font".to_owned())),
+                ("Subtype", Object::Name("Type1".to_owned())),
+                ("BaseFont", Object::Name(base_font.to_owned())),
+            ]))
+        };
+
+        let helvetica = builder.add_object(font("Helvetica"));
+        let courier = builder.add_object(font("Courier"));
+
+        for text in ["first", "second", "third"] {
+            let mut page = PageBuilder::new(612.0, 792.0);
+            page.font("F1", helvetica)
+                .font("F2", courier)
+                .contents(format!("BT /F1 12 Tf ({}) Tj ET", text));
+
+            builder.add_page(page).unwrap();
+        }
+
+        let mut parser =
+            Parser::from_bytes(builder.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        let extracted = parser.extract_pages(1..2).unwrap();
+
+        let fonts = extracted
+            .objects
+            .values()
+            .filter_map(|(_, obj)| match obj {
+                Object::Dictionary(dict)
+                    if dict.peek("Type").map_or(false, |ty| ty.name_is("Font")) =>
+                {
+                    dict.peek("BaseFont").cloned()
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(fonts, vec![Object::Name("Helvetica".to_owned())]);
+
+        let mut parser =
+            Parser::from_bytes(extracted.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        let pages = parser.pages();
+        assert_eq!(pages.len(), 1);
+
+        let shown = parser
+            .page_contents(&pages[0])
+            .unwrap()
+            .filter_map(|token| match token.unwrap() {
+                ContentToken::Object(Object::String(s)) => Some(s),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(shown, vec!["second"]);
+    }
+}
@@ -0,0 +1,623 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeSet},
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    error::PdfResult,
+    font::{
+        find_substitute, standard_unicode, true_type::ParsedTrueTypeFontFile, unicode_glyph_name,
+        win_ansi_unicode, Font, FontDescriptor, FontEncoding,
+    },
+    objects::{Dictionary, Object, Reference},
+    FromObj,
+};
+
+use super::{dictionary, flate_stream, PdfWriter};
+
+/// The flags of a font descriptor which say whether the font uses characters
+/// outside the standard Latin character set
+const SYMBOLIC: i32 = 1 << 2;
+const NONSYMBOLIC: i32 = 1 << 5;
+const FIXED_PITCH: i32 = 1 << 0;
+const ITALIC: i32 = 1 << 6;
+
+/// Embed the closest matching installed system font in place of each font of
+/// a compacted document whose font program is not embedded
+///
+/// Substitutes are TrueType fonts, which are embedded as FontFile2 streams
+/// holding only the glyphs for the codes of the font dictionary, and the font
+/// dictionaries and their descriptors are rewritten to describe them. Fonts for
+/// which no substitute is installed, and composite fonts, whose CIDs cannot be
+/// mapped to the glyphs of a substitute, are refused.
+pub(crate) fn embed_fonts(document: PdfWriter) -> PdfResult<PdfWriter> {
+    embed_substitute_fonts(document, find_substitute)
+}
+
+fn embed_substitute_fonts<'a>(
+    mut document: PdfWriter<'a>,
+    find_substitute: impl Fn(&str, Option<&FontDescriptor>) -> Option<Vec<u8>>,
+) -> PdfResult<PdfWriter<'a>> {
+    let fonts = document
+        .objects
+        .iter()
+        .filter(|(_, (_, obj))| match obj {
+            Object::Dictionary(dict) => {
+                matches!(dict.peek("Type"), Some(Object::Name(name)) if name == "Font")
+            }
+            _ => false,
+        })
+        .map(|(&object_number, &(generation, _))| Reference {
+            object_number,
+            generation,
+        })
+        .collect::<Vec<Reference>>();
+
+    for reference in fonts {
+        let font = Font::from_obj(Object::Reference(reference), &mut document)?;
+
+        let (base_font, descriptor) = match font.missing_font_program() {
+            Some(missing_font) => missing_font,
+            None => continue,
+        };
+
+        if let Font::Type0(..) = font {
+            anyhow::bail!(
+                "cannot embed a substitute for the composite font {}",
+                base_font
+            );
+        }
+
+        let program = match find_substitute(base_font, descriptor) {
+            Some(program) => program,
+            None => anyhow::bail!("no installed font can be substituted for {}", base_font),
+        };
+
+        let is_symbolic = descriptor.map_or(false, |descriptor| {
+            descriptor.flags.is_symbolic() && !descriptor.flags.is_non_symbolic()
+        });
+
+        let base_font = base_font.to_owned();
+
+        embed_font(&mut document, reference, &base_font, is_symbolic, &program)?;
+    }
+
+    Ok(document)
+}
+
+/// Embed a subset of the TrueType font `data` as the font program of the
+/// simple font dictionary at `reference`
+fn embed_font(
+    document: &mut PdfWriter,
+    reference: Reference,
+    base_font: &str,
+    is_symbolic: bool,
+    data: &[u8],
+) -> PdfResult<()> {
+    let mut font = match document.get(reference) {
+        Some(Object::Dictionary(font)) => font.clone(),
+        _ => anyhow::bail!("expected font dictionary"),
+    };
+
+    let program = ParsedTrueTypeFontFile::new(data)?;
+
+    let first_char = code_entry(&font, "FirstChar", 0)?;
+    let last_char = code_entry(&font, "LastChar", 255)?;
+
+    let encoding = match font.peek("Encoding") {
+        Some(encoding) => Some(FontEncoding::from_obj(encoding.clone(), document)?),
+        None => None,
+    };
+
+    // symbolic fonts are looked up by code in the Microsoft Symbol or Macintosh
+    // cmap subtables, and nonsymbolic fonts by the character the code encodes
+    let mut characters = Vec::new();
+    let mut glyphs = BTreeSet::from([0]);
+
+    let code_glyphs = (first_char..=last_char)
+        .map(|code| {
+            if is_symbolic {
+                return program.glyph_index(u32::from(code));
+            }
+
+            let c = match &encoding {
+                Some(encoding) => encoding.unicode(code),
+                None => standard_unicode(code),
+            };
+
+            match c {
+                Some(c) => {
+                    characters.push((code, c));
+                    program.unicode_glyph_index(c)
+                }
+                None => 0,
+            }
+        })
+        .collect::<Vec<u32>>();
+
+    glyphs.extend(code_glyphs.iter().map(|&glyph| glyph as u16));
+
+    let glyphs = glyphs.into_iter().collect::<Vec<u16>>();
+
+    let subset = subsetter::subset(data, 0, subsetter::Profile::pdf(&glyphs))
+        .map_err(|e| anyhow::anyhow!("failed to subset the substitute for {}: {}", base_font, e))?;
+
+    let name = format!("{}+{}", subset_tag(base_font, &glyphs), base_font);
+    let metrics = Metrics::new(&program, data);
+
+    let font_file = document.add(Object::Stream(flate_stream(
+        &subset,
+        dictionary([("Length1", Object::Integer(subset.len() as i32))]),
+    )?));
+
+    let (descriptor_reference, descriptor) = match font.peek("FontDescriptor") {
+        Some(&Object::Reference(descriptor)) => (Some(descriptor), document.get(descriptor)),
+        descriptor => (None, descriptor),
+    };
+
+    let mut descriptor = match descriptor {
+        Some(Object::Dictionary(descriptor)) => descriptor.clone(),
+        _ => Dictionary::empty(),
+    };
+
+    let flags = match descriptor.peek("Flags") {
+        Some(&Object::Integer(flags)) => flags,
+        _ => metrics.flags,
+    };
+
+    descriptor.insert("Type", Object::Name("FontDescriptor".to_owned()));
+    descriptor.insert("FontName", Object::Name(name.clone()));
+    descriptor.insert(
+        "Flags",
+        Object::Integer(
+            flags & !(SYMBOLIC | NONSYMBOLIC) | if is_symbolic { SYMBOLIC } else { NONSYMBOLIC },
+        ),
+    );
+    descriptor.insert(
+        "FontBBox",
+        Object::Array(metrics.bbox.iter().copied().map(Object::Real).collect()),
+    );
+    descriptor.insert("Ascent", Object::Real(metrics.ascent));
+    descriptor.insert("Descent", Object::Real(metrics.descent));
+    descriptor.insert("CapHeight", Object::Real(metrics.cap_height));
+    descriptor.insert("FontFile2", Object::Reference(font_file));
+
+    if descriptor.peek("ItalicAngle").is_none() {
+        descriptor.insert("ItalicAngle", Object::Real(metrics.italic_angle));
+    }
+
+    if descriptor.peek("StemV").is_none() {
+        descriptor.insert("StemV", Object::Real(metrics.stem_v));
+    }
+
+    // the character set lists the glyph names of a Type 1 font program
+    descriptor.remove("CharSet");
+
+    let descriptor = match descriptor_reference {
+        Some(reference) => {
+            document.insert(reference, Object::Dictionary(descriptor));
+            reference
+        }
+        None => document.add(Object::Dictionary(descriptor)),
+    };
+
+    font.insert("Subtype", Object::Name("TrueType".to_owned()));
+    font.insert("BaseFont", Object::Name(name));
+    font.insert("FontDescriptor", Object::Reference(descriptor));
+
+    // an embedded program would otherwise supply the implicit base encoding,
+    // so the codes are given as differences from WinAnsiEncoding, whose glyph
+    // names are mapped to characters through the Unicode cmap of the program
+    if is_symbolic {
+        font.remove("Encoding");
+    } else {
+        font.insert("Encoding", win_ansi_differences(&characters));
+    }
+
+    if font.peek("Widths").is_none() {
+        font.insert("FirstChar", Object::Integer(i32::from(first_char)));
+        font.insert("LastChar", Object::Integer(i32::from(last_char)));
+        font.insert(
+            "Widths",
+            Object::Array(
+                code_glyphs
+                    .iter()
+                    .map(|&glyph| Object::Real(metrics.advance_width(data, glyph)))
+                    .collect(),
+            ),
+        );
+    }
+
+    document.insert(reference, Object::Dictionary(font));
+
+    Ok(())
+}
+
+/// The integer value of a code entry of a simple font dictionary
+fn code_entry(font: &Dictionary, key: &str, default: u8) -> PdfResult<u8> {
+    match font.peek(key) {
+        Some(&Object::Integer(code)) => Ok(u8::try_from(code)?),
+        Some(..) => anyhow::bail!("expected {} to be an integer", key),
+        None => Ok(default),
+    }
+}
+
+/// An encoding mapping each of `characters` from its code, as differences from
+/// WinAnsiEncoding
+fn win_ansi_differences<'a>(characters: &[(u8, char)]) -> Object<'a> {
+    let differences = characters
+        .iter()
+        .filter(|&&(code, c)| win_ansi_unicode(code) != Some(c))
+        .flat_map(|&(code, c)| {
+            [
+                Object::Integer(i32::from(code)),
+                Object::Name(unicode_glyph_name(c)),
+            ]
+        })
+        .collect::<Vec<Object>>();
+
+    let base_encoding = Object::Name("WinAnsiEncoding".to_owned());
+
+    if differences.is_empty() {
+        return base_encoding;
+    }
+
+    Object::Dictionary(dictionary([
+        ("Type", Object::Name("Encoding".to_owned())),
+        ("BaseEncoding", base_encoding),
+        ("Differences", Object::Array(differences)),
+    ]))
+}
+
+/// A tag of six uppercase letters identifying the subset of a font with the
+/// given glyphs, which is prefixed to its name
+fn subset_tag(base_font: &str, glyphs: &[u16]) -> String {
+    let mut hasher = DefaultHasher::new();
+    base_font.hash(&mut hasher);
+    glyphs.hash(&mut hasher);
+
+    let mut hash = hasher.finish();
+
+    (0..6)
+        .map(|_| {
+            let letter = char::from(b'A' + (hash % 26) as u8);
+            hash /= 26;
+            letter
+        })
+        .collect()
+}
+
+/// The table of a TrueType font with the given tag
+fn table<'b>(data: &'b [u8], tag: &[u8; 4]) -> Option<&'b [u8]> {
+    let num_tables = usize::from(read_u16(data, 4)?);
+
+    (0..num_tables).find_map(|idx| {
+        let record = data.get(12 + idx * 16..12 + (idx + 1) * 16)?;
+
+        if &record[..4] != tag {
+            return None;
+        }
+
+        let offset = u32::from_be_bytes(record[8..12].try_into().unwrap()) as usize;
+        let length = u32::from_be_bytes(record[12..16].try_into().unwrap()) as usize;
+
+        data.get(offset..offset + length)
+    })
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().unwrap(),
+    ))
+}
+
+fn read_i16(data: &[u8], offset: usize) -> Option<i16> {
+    read_u16(data, offset).map(|value| value as i16)
+}
+
+/// The metrics of a TrueType font given by its font descriptor, in thousandths
+/// of an em
+#[derive(Debug)]
+struct Metrics {
+    scale: f32,
+    bbox: [f32; 4],
+    ascent: f32,
+    descent: f32,
+    cap_height: f32,
+    italic_angle: f32,
+    stem_v: f32,
+
+    /// The flags of a descriptor for the font, other than those giving whether
+    /// it is symbolic
+    flags: i32,
+}
+
+impl Metrics {
+    fn new(program: &ParsedTrueTypeFontFile, data: &[u8]) -> Self {
+        let head = &program.head;
+        let scale = 1000.0 / f32::from(head.units_per_em.max(1));
+
+        let hhea = table(data, b"hhea");
+        let os2 = table(data, b"OS/2");
+        let post = table(data, b"post");
+
+        let ascent = hhea
+            .and_then(|hhea| read_i16(hhea, 4))
+            .unwrap_or(head.y_max.0);
+        let descent = hhea
+            .and_then(|hhea| read_i16(hhea, 6))
+            .unwrap_or(head.y_min.0);
+
+        // the cap height was added in version 2 of the OS/2 table
+        let cap_height = os2
+            .filter(|os2| read_u16(os2, 0).map_or(false, |version| version >= 2))
+            .and_then(|os2| read_i16(os2, 88))
+            .unwrap_or(ascent);
+
+        let weight = os2.and_then(|os2| read_u16(os2, 4)).unwrap_or(400);
+
+        let italic_angle = post.and_then(|post| post.get(4..8)).map_or(0.0, |angle| {
+            i32::from_be_bytes(angle.try_into().unwrap()) as f32 / 65536.0
+        });
+
+        let is_fixed_pitch = post
+            .and_then(|post| post.get(12..16))
+            .map_or(false, |fixed_pitch| fixed_pitch != [0; 4]);
+
+        let is_italic = head.mac_style.0 & (1 << 1) != 0 || italic_angle != 0.0;
+
+        let mut flags = 0;
+
+        if is_fixed_pitch {
+            flags |= FIXED_PITCH;
+        }
+
+        if is_italic {
+            flags |= ITALIC;
+        }
+
+        Self {
+            scale,
+            bbox: [head.x_min.0, head.y_min.0, head.x_max.0, head.y_max.0]
+                .map(|value| f32::from(value) * scale),
+            ascent: f32::from(ascent) * scale,
+            descent: f32::from(descent) * scale,
+            cap_height: f32::from(cap_height) * scale,
+            italic_angle,
+            // fonts give no stem width, so it is estimated from the weight
+            stem_v: 10.0 + 0.244 * (f32::from(weight) - 50.0),
+            flags,
+        }
+    }
+
+    /// The advance width of a glyph, from the hmtx table
+    fn advance_width(&self, data: &[u8], glyph: u32) -> f32 {
+        let num_h_metrics = table(data, b"hhea").and_then(|hhea| read_u16(hhea, 34));
+
+        let advance = match (table(data, b"hmtx"), num_h_metrics) {
+            // glyphs after the last long metric share its advance width
+            (Some(hmtx), Some(num_h_metrics)) if num_h_metrics > 0 => {
+                let idx = (glyph as usize).min(usize::from(num_h_metrics) - 1);
+                read_u16(hmtx, idx * 4).unwrap_or(0)
+            }
+            _ => 0,
+        };
+
+        f32::from(advance) * self.scale
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        filter::decode_stream,
+        version::PdfVersion,
+        writer::{DocumentBuilder, PageBuilder},
+        Resolve,
+    };
+
+    fn u16s(values: &[u16]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect()
+    }
+
+    /// A TrueType font of three squares of different sizes, the last two of
+    /// which are "A" and "B" in its Unicode cmap subtable, with 1000 units per
+    /// em and advance widths of 100, 500 and 600
+    fn font() -> Vec<u8> {
+        let mut head = vec![0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0x5f, 0x0f, 0x3c, 0xf5];
+        head.extend(u16s(&[0, 1000]));
+        head.extend([0; 16]);
+        head.extend(u16s(&[0, (-200_i16) as u16, 300, 800, 0, 0, 0, 1, 0]));
+
+        let mut hhea = vec![0, 1, 0, 0];
+        hhea.extend(u16s(&[800, (-200_i16) as u16]));
+        hhea.extend([0; 26]);
+        hhea.extend(u16s(&[3]));
+
+        let hmtx = u16s(&[100, 0, 500, 0, 600, 0]);
+
+        let mut maxp = vec![0, 1, 0, 0];
+        maxp.extend(u16s(&[3; 14]));
+
+        let square = |size: u16| {
+            let mut glyph = u16s(&[1, 0, 0, size, size, 3, 0]);
+            glyph.extend([1; 4]);
+            glyph.extend(u16s(&[0, size, 0, size.wrapping_neg()]));
+            glyph.extend(u16s(&[0, 0, size, 0]));
+            glyph
+        };
+
+        let mut glyf = Vec::new();
+        let mut loca = Vec::new();
+
+        for size in [100, 200, 300] {
+            loca.extend((glyf.len() as u32).to_be_bytes());
+            glyf.extend(square(size));
+        }
+
+        loca.extend((glyf.len() as u32).to_be_bytes());
+
+        let mut cmap = u16s(&[0, 1, 3, 1, 0, 12]);
+        cmap.extend(u16s(&[6, 14, 0, u16::from(b'A'), 2, 1, 2]));
+
+        let tables: [(&[u8; 4], Vec<u8>); 7] = [
+            (b"cmap", cmap),
+            (b"glyf", glyf),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"loca", loca),
+            (b"maxp", maxp),
+        ];
+
+        let mut file = vec![0, 1, 0, 0];
+        file.extend(u16s(&[tables.len() as u16, 0, 0, 0]));
+
+        let mut offset = file.len() + tables.len() * 16;
+        let mut data = Vec::new();
+
+        for (tag, mut table) in tables {
+            table.resize((table.len() + 3) & !3, 0);
+
+            file.extend_from_slice(tag);
+            file.extend_from_slice(&0_u32.to_be_bytes());
+            file.extend_from_slice(&(offset as u32).to_be_bytes());
+            file.extend_from_slice(&(table.len() as u32).to_be_bytes());
+
+            offset += table.len();
+            data.extend(table);
+        }
+
+        file.extend(data);
+        file
+    }
+
+    fn name<'b>(dict: &'b Dictionary, key: &str) -> &'b str {
+        match dict.peek(key) {
+            Some(Object::Name(name)) => name,
+            obj => panic!("expected {} to be a name, found {:?}", key, obj),
+        }
+    }
+
+    fn document(font: Dictionary<'static>) -> (PdfWriter<'static>, Reference) {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+        let font = builder.add_object(Object::Dictionary(font));
+
+        let mut page = PageBuilder::new(612.0, 792.0);
+        page.font("F1", font);
+        builder.add_page(page).unwrap();
+
+        (builder.build().unwrap(), font)
+    }
+
+    #[test]
+    fn embed_substitute_subset() {
+        let (document, font) = document(dictionary([
+            ("Type", Object::Name("Font".to_owned())),
+            ("Subtype", Object::Name("Type1".to_owned())),
+            ("BaseFont", Object::Name("Foo".to_owned())),
+            ("FirstChar", Object::Integer(0x41)),
+            ("LastChar", Object::Integer(0x41)),
+            ("Widths", Object::Array(vec![Object::Integer(500)])),
+        ]));
+
+        let mut document = embed_substitute_fonts(document, |base_font, descriptor| {
+            assert!(descriptor.is_none());
+            (base_font == "Foo").then(self::font)
+        })
+        .unwrap();
+
+        let font = document.assert_dict(Object::Reference(font)).unwrap();
+        let base_font = name(&font, "BaseFont");
+
+        assert_eq!(name(&font, "Subtype"), "TrueType");
+        assert_eq!(base_font.len(), "ABCDEF+Foo".len());
+        assert!(base_font.ends_with("+Foo"));
+        assert_eq!(name(&font, "Encoding"), "WinAnsiEncoding");
+
+        let descriptor = document
+            .assert_dict(font.peek("FontDescriptor").unwrap().clone())
+            .unwrap();
+
+        assert_eq!(name(&descriptor, "FontName"), base_font);
+        assert_eq!(
+            descriptor.peek("Flags"),
+            Some(&Object::Integer(NONSYMBOLIC))
+        );
+        assert_eq!(descriptor.peek("Ascent"), Some(&Object::Real(800.0)));
+        assert_eq!(descriptor.peek("Descent"), Some(&Object::Real(-200.0)));
+
+        let font_file = document
+            .assert_stream(descriptor.peek("FontFile2").unwrap().clone())
+            .unwrap();
+        let data = decode_stream(&font_file.stream, &font_file.dict, &mut document).unwrap();
+
+        let mut program = ParsedTrueTypeFontFile::new(&data).unwrap();
+
+        // only the glyph of "A" and the .notdef glyph are kept, and glyph
+        // indices are unchanged
+        assert_eq!(program.unicode_glyph_index('A'), 1);
+        assert!(!program.glyph_outline(0).unwrap().paths.is_empty());
+        assert!(!program.glyph_outline(1).unwrap().paths.is_empty());
+        assert!(program.glyph_outline(2).unwrap().paths.is_empty());
+    }
+
+    #[test]
+    fn widths_and_differences() {
+        let (document, font) = document(dictionary([
+            ("Type", Object::Name("Font".to_owned())),
+            ("Subtype", Object::Name("TrueType".to_owned())),
+            ("BaseFont", Object::Name("Foo".to_owned())),
+            ("FirstChar", Object::Integer(0x41)),
+            ("LastChar", Object::Integer(0x43)),
+            (
+                "Encoding",
+                Object::Dictionary(dictionary([(
+                    "Differences",
+                    Object::Array(vec![Object::Integer(0x43), Object::Name("A".to_owned())]),
+                )])),
+            ),
+        ]));
+
+        let mut document = embed_substitute_fonts(document, |_, _| Some(self::font())).unwrap();
+
+        let font = document.assert_dict(Object::Reference(font)).unwrap();
+
+        // the differences map C to the glyph of A
+        assert_eq!(
+            font.peek("Widths"),
+            Some(&Object::Array(vec![
+                Object::Real(500.0),
+                Object::Real(600.0),
+                Object::Real(500.0),
+            ]))
+        );
+
+        let encoding = document
+            .assert_dict(font.peek("Encoding").unwrap().clone())
+            .unwrap();
+
+        assert_eq!(
+            encoding.peek("Differences"),
+            Some(&Object::Array(vec![
+                Object::Integer(0x43),
+                Object::Name("A".to_owned()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn refuse_fonts_without_substitute() {
+        let (document, _) = document(dictionary([
+            ("Type", Object::Name("Font".to_owned())),
+            ("Subtype", Object::Name("Type1".to_owned())),
+            ("BaseFont", Object::Name("Foo".to_owned())),
+        ]));
+
+        assert!(embed_substitute_fonts(document, |_, _| None).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use std::{collections::BTreeMap, io::Write};
+
+use crate::{
+    error::PdfResult,
+    objects::{Dictionary, Object, Reference, ToObj, TypedReference},
+    trailer::Trailer,
+};
+
+use super::{
+    dictionary, flate_stream, write_dictionary, write_indirect_object,
+    xref::{write_xref_subsections, xref_stream_data, xref_subsections, XrefRow},
+};
+
+/// Changes to an existing file, written as an incremental update appended to
+/// the original bytes
+///
+/// The original bytes are never rewritten, so that signatures covering them
+/// remain valid. The update's cross-reference section lists only the objects
+/// it adds or replaces, and is a stream if the file's last section is.
+#[derive(Debug)]
+pub(crate) struct IncrementalUpdate<'a> {
+    objects: BTreeMap<usize, (usize, Object<'a>)>,
+
+    /// The Size of the original file's trailer, one greater than the highest
+    /// object number it uses
+    size: usize,
+}
+
+impl<'a> IncrementalUpdate<'a> {
+    pub fn new(size: usize) -> Self {
+        Self {
+            objects: BTreeMap::new(),
+            size,
+        }
+    }
+
+    /// Add an object under an object number unused by the original file
+    pub fn add(&mut self, obj: Object<'a>) -> Reference {
+        let reference = Reference {
+            object_number: self.next_object_number(),
+            generation: 0,
+        };
+
+        self.insert(reference, obj);
+
+        reference
+    }
+
+    /// Add an object, or replace an object of the original file
+    pub fn insert(&mut self, reference: Reference, obj: Object<'a>) {
+        self.objects
+            .insert(reference.object_number, (reference.generation, obj));
+    }
+
+    fn next_object_number(&self) -> usize {
+        self.objects
+            .keys()
+            .next_back()
+            .map_or(self.size, |n| (n + 1).max(self.size))
+    }
+
+    /// The entries of the trailer of an update to a file with `trailer` which
+    /// are carried over from it: Root, Info and ID
+    pub fn trailer(trailer: &Trailer<'a>) -> PdfResult<Dictionary<'a>> {
+        let mut new_trailer = dictionary([("Root", Object::Reference(trailer.root))]);
+
+        if let Some(TypedReference::Indirect { reference, .. }) = &trailer.info {
+            new_trailer.insert("Info", Object::Reference(*reference));
+        }
+
+        if let Some(id) = &trailer.id {
+            new_trailer.insert("ID", id.to_obj()?);
+        }
+
+        Ok(new_trailer)
+    }
+
+    /// The original `file` followed by the update
+    ///
+    /// `trailer` holds the entries of the new trailer other than Size and
+    /// Prev, such as Root, Info and ID, which are not inherited from the
+    /// trailer of the original file.
+    pub fn write(&self, file: &[u8], mut trailer: Dictionary<'a>) -> PdfResult<Vec<u8>> {
+        let prev = start_xref(file)?;
+        let is_xref_stream = !file
+            .get(prev..)
+            .map_or(false, |section| section.starts_with(b"xref"));
+
+        let mut out = file.to_vec();
+
+        if !out.ends_with(b"\n") && !out.ends_with(b"\r") {
+            out.push(b'\n');
+        }
+
+        let mut used = BTreeMap::new();
+
+        for (&object_number, (generation, obj)) in &self.objects {
+            used.insert(
+                object_number,
+                XrefRow::InFile {
+                    offset: out.len(),
+                    generation: *generation,
+                },
+            );
+
+            write_indirect_object(object_number, *generation, obj, &mut out)?;
+        }
+
+        let xref_offset = out.len();
+        let mut size = self.next_object_number();
+
+        trailer.insert("Prev", Object::Integer(i32::try_from(prev)?));
+
+        if is_xref_stream {
+            // the cross-reference stream lists itself
+            let xref_number = size;
+            size += 1;
+
+            used.insert(
+                xref_number,
+                XrefRow::InFile {
+                    offset: xref_offset,
+                    generation: 0,
+                },
+            );
+
+            let subsections = xref_subsections(&used);
+            let rows = subsections
+                .iter()
+                .flat_map(|(_, rows)| rows.iter().copied())
+                .collect::<Vec<XrefRow>>();
+
+            let (data, widths) = xref_stream_data(&rows);
+
+            let index = subsections
+                .iter()
+                .flat_map(|(first, rows)| [*first, rows.len()])
+                .map(|n| Ok(Object::Integer(i32::try_from(n)?)))
+                .collect::<PdfResult<Vec<Object>>>()?;
+
+            trailer.insert("Type", Object::Name("XRef".to_owned()));
+            trailer.insert("Size", Object::Integer(i32::try_from(size)?));
+            trailer.insert("Index", Object::Array(index));
+            trailer.insert(
+                "W",
+                Object::Array(
+                    widths
+                        .into_iter()
+                        .map(|width| Object::Integer(width as i32))
+                        .collect(),
+                ),
+            );
+
+            write_indirect_object(
+                xref_number,
+                0,
+                &Object::Stream(flate_stream(&data, trailer)?),
+                &mut out,
+            )?;
+        } else {
+            write_xref_subsections(&xref_subsections(&used), &mut out)?;
+
+            trailer.insert("Size", Object::Integer(i32::try_from(size)?));
+
+            out.extend_from_slice(b"trailer\n");
+            write_dictionary(&trailer, &mut out)?;
+            out.push(b'\n');
+        }
+
+        writeln!(out, "startxref\n{}\n%%EOF", xref_offset)?;
+
+        Ok(out)
+    }
+}
+
+/// The offset of the last cross-reference section of `file`, given after its
+/// final `startxref` keyword
+fn start_xref(file: &[u8]) -> PdfResult<usize> {
+    let keyword = match file
+        .windows(b"startxref".len())
+        .rposition(|window| window == b"startxref")
+    {
+        Some(keyword) => keyword,
+        None => anyhow::bail!("file has no startxref keyword"),
+    };
+
+    let offset = file[keyword + b"startxref".len()..]
+        .iter()
+        .skip_while(|b| b.is_ascii_whitespace())
+        .take_while(|b| b.is_ascii_digit())
+        .map(|&b| b as char)
+        .collect::<String>();
+
+    Ok(offset.parse()?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{version::PdfVersion, writer::DocumentBuilder, ParseOptions, Parser, Resolve};
+
+    #[test]
+    fn append_update() {
+        let builder = DocumentBuilder::new(PdfVersion::V1_7);
+        let mut document = builder.build().unwrap();
+        document.set_object_streams(false);
+
+        let file = document.to_bytes().unwrap();
+
+        let parser = Parser::from_bytes(file.clone(), ParseOptions::default()).unwrap();
+        let root = parser.trailer.root;
+
+        let mut update = IncrementalUpdate::new(parser.trailer.size);
+        let added = update.add(Object::Name("Added".to_owned()));
+
+        let mut trailer = Dictionary::empty();
+        trailer.insert("Root", Object::Reference(root));
+
+        let updated = update.write(&file, trailer).unwrap();
+
+        assert!(updated.starts_with(&file));
+
+        let mut parser = Parser::from_bytes(updated, ParseOptions::default()).unwrap();
+
+        assert_eq!(
+            parser.lexer.resolve(Object::Reference(added)).unwrap(),
+            Object::Name("Added".to_owned())
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{
+    error::PdfResult,
+    objects::{Dictionary, Object, Reference},
+    optional_content::OptionalContentState,
+    trailer::Trailer,
+    Lexer, Resolve,
+};
+
+use super::IncrementalUpdate;
+
+/// Append an incremental update to the file of `lexer` which makes `state`
+/// the default viewing configuration of the document's optional content
+///
+/// `groups` are the optional content groups of the document. The ON and OFF
+/// arrays of the default configuration dictionary are replaced, listing each
+/// group by its state, and its other entries are kept.
+pub(crate) fn set_default_layer_states<'a>(
+    trailer: &Trailer<'a>,
+    groups: &[Reference],
+    state: &OptionalContentState,
+    lexer: &mut Lexer<'a>,
+) -> PdfResult<Vec<u8>> {
+    // the strings of the configuration would have to be encrypted
+    anyhow::ensure!(
+        trailer.encryption.is_none(),
+        "cannot change the layers of an encrypted document"
+    );
+
+    let mut update = IncrementalUpdate::new(trailer.size);
+    let mut catalog = lexer.assert_dict(Object::Reference(trailer.root))?;
+
+    let (properties_reference, mut properties) = match catalog.peek("OCProperties").cloned() {
+        Some(Object::Reference(reference)) => (
+            Some(reference),
+            lexer.assert_dict(Object::Reference(reference))?,
+        ),
+        Some(obj) => (None, lexer.assert_dict(obj)?),
+        None => anyhow::bail!("document has no optional content"),
+    };
+
+    let (config_reference, mut config) = match properties.peek("D").cloned() {
+        Some(Object::Reference(reference)) => (
+            Some(reference),
+            lexer.assert_dict(Object::Reference(reference))?,
+        ),
+        Some(obj) => (None, lexer.assert_dict(obj)?),
+        None => (None, Dictionary::empty()),
+    };
+
+    let (on, off): (Vec<Reference>, Vec<Reference>) =
+        groups.iter().partition(|&&group| state.is_on(group));
+
+    // the base state of the default configuration shall be ON
+    config.remove("BaseState");
+    config.insert("ON", references(on));
+    config.insert("OFF", references(off));
+
+    match (config_reference, properties_reference) {
+        (Some(reference), _) => update.insert(reference, Object::Dictionary(config)),
+        (None, Some(reference)) => {
+            properties.insert("D", Object::Dictionary(config));
+            update.insert(reference, Object::Dictionary(properties));
+        }
+        (None, None) => {
+            properties.insert("D", Object::Dictionary(config));
+            catalog.insert("OCProperties", Object::Dictionary(properties));
+            update.insert(trailer.root, Object::Dictionary(catalog));
+        }
+    }
+
+    update.write(&lexer.file, IncrementalUpdate::trailer(trailer)?)
+}
+
+fn references<'a>(references: Vec<Reference>) -> Object<'a> {
+    Object::Array(references.into_iter().map(Object::Reference).collect())
+}
@@ -0,0 +1,1010 @@
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::{
+    error::PdfResult,
+    objects::{Dictionary, Object, Reference},
+    version::PdfVersion,
+};
+
+use super::{compact::for_each_reference, dictionary, write_object, PdfWriter};
+
+/// Entries of page tree nodes which are inherited by the pages beneath them
+/// that do not set them
+pub(super) const INHERITABLE: [&str; 4] = ["Resources", "MediaBox", "CropBox", "Rotate"];
+
+/// Entries of the catalog of the first document which are kept, as they
+/// describe how the merged document is opened rather than what it contains
+const KEPT_CATALOG_ENTRIES: [&str; 5] = [
+    "PageLayout",
+    "PageMode",
+    "ViewerPreferences",
+    "OpenAction",
+    "Lang",
+];
+
+/// The top-level items of the outline of one document
+struct Outline {
+    first: Reference,
+    last: Reference,
+}
+
+/// Combine compacted documents into one, with the pages of each following
+/// those of the documents before it
+///
+/// The objects of each document are renumbered to follow those of the
+/// documents before it, and the page tree of each is flattened into a single
+/// page tree, with inherited attributes copied into each page. The outline
+/// of each document follows that of the document before it, and named
+/// destinations are merged into a single Dests name tree, prefixing the names
+/// of later documents with their position (`2:chapter`) wherever they collide
+/// with a name already taken. References to renamed destinations from
+/// outline items, link annotations and GoTo actions are updated to match.
+/// The fields of each interactive form likewise follow those of the forms
+/// before it, with top-level fields renamed in the same way wherever their
+/// names collide, so that every fully qualified field name stays unique.
+///
+/// Objects which serialize identically, such as a font or image embedded in
+/// several documents, are written once. Pages and their annotations are never
+/// shared, even if identical.
+///
+/// Only the presentation entries of the catalog of the first document, such
+/// as its PageMode and OpenAction, are kept, along with its information
+/// dictionary. Other document-level structures, such as structure trees and
+/// name trees other than Dests, are dropped.
+pub(crate) fn merge<'a>(
+    documents: Vec<PdfWriter<'a>>,
+    version: PdfVersion,
+) -> PdfResult<PdfWriter<'a>> {
+    let mut merged = PdfWriter::new(version);
+    let page_tree = merged.add(Object::Null);
+
+    let mut pages = Vec::new();
+    let mut protected = HashSet::new();
+    let mut outlines = Vec::new();
+    let mut dests = BTreeMap::new();
+    let mut field_names = HashSet::new();
+    let mut acro_form = None;
+    let mut catalog = Dictionary::empty();
+    let mut info = None;
+
+    for (idx, mut document) in documents.into_iter().enumerate() {
+        let root = match document.trailer.peek("Root") {
+            Some(Object::Reference(root)) => *root,
+            _ => anyhow::bail!("the document catalog must be an indirect object"),
+        };
+
+        let document_catalog = match document.get(root) {
+            Some(Object::Dictionary(catalog)) => catalog.clone(),
+            _ => anyhow::bail!("the document catalog does not exist"),
+        };
+
+        let mut leaves = Vec::new();
+
+        if let Some(Object::Reference(pages)) = document_catalog.peek("Pages") {
+            collect_pages(
+                &document,
+                *pages,
+                &Dictionary::empty(),
+                &mut HashSet::new(),
+                &mut leaves,
+            );
+        }
+
+        let mut annotations = Vec::new();
+
+        for (page, inherited) in &leaves {
+            if let Some((_, Object::Dictionary(page))) =
+                document.objects.get_mut(&page.object_number)
+            {
+                for (key, value) in inherited.iter() {
+                    if page.peek(key).is_none() {
+                        page.insert(key, value.clone());
+                    }
+                }
+            }
+
+            if let Some(Some(Object::Array(annots))) = document
+                .get(*page)
+                .and_then(|page| match page {
+                    Object::Dictionary(page) => page.peek("Annots"),
+                    _ => None,
+                })
+                .map(|annots| resolved(&document, annots))
+            {
+                annotations.extend(annots.iter().filter_map(|annot| match annot {
+                    Object::Reference(annot) => Some(*annot),
+                    _ => None,
+                }));
+            }
+        }
+
+        let outline = match document_catalog
+            .peek("Outlines")
+            .and_then(|outlines| resolved(&document, outlines))
+        {
+            Some(Object::Dictionary(outlines)) => {
+                match (outlines.peek("First"), outlines.peek("Last")) {
+                    (Some(Object::Reference(first)), Some(Object::Reference(last))) => {
+                        Some(Outline {
+                            first: *first,
+                            last: *last,
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        // named destinations, both from the Dests name tree and from the
+        // Dests dictionary of PDF 1.1, which become entries of the merged
+        // name tree
+        let mut entries = Vec::new();
+
+        if let Some(Object::Dictionary(names)) = document_catalog
+            .peek("Names")
+            .and_then(|names| resolved(&document, names))
+        {
+            if let Some(tree) = names.peek("Dests") {
+                name_tree_entries(&document, tree, &mut HashSet::new(), &mut entries);
+            }
+        }
+
+        let form = match document_catalog
+            .peek("AcroForm")
+            .and_then(|form| resolved(&document, form))
+        {
+            Some(Object::Dictionary(form)) => Some(form_dictionary(&document, form)),
+            _ => None,
+        };
+
+        let mut renamed_strings = HashMap::new();
+        let mut renamed_names = HashMap::new();
+        let mut named = Vec::new();
+
+        for (name, dest) in entries {
+            let unique = unique_name(&name, idx, |name| dests.contains_key(name));
+            dests.insert(unique.clone(), dest);
+            named.push(unique.clone());
+            renamed_strings.insert(name, unique);
+        }
+
+        if let Some(Object::Dictionary(old_dests)) = document_catalog
+            .peek("Dests")
+            .and_then(|old_dests| resolved(&document, old_dests))
+        {
+            for (name, dest) in old_dests.iter() {
+                let unique = unique_name(name, idx, |name| dests.contains_key(name));
+                dests.insert(unique.clone(), dest.clone());
+                named.push(unique.clone());
+                renamed_names.insert(name.to_owned(), unique);
+            }
+        }
+
+        for (_, obj) in document.objects.values_mut() {
+            rename_destinations(obj, &renamed_strings, &renamed_names);
+        }
+
+        let mut fields = Vec::new();
+
+        if let Some(Some(Object::Array(top_level))) = form.as_ref().map(|form| form.peek("Fields"))
+        {
+            fields.extend(top_level.iter().filter_map(|field| match field {
+                Object::Reference(field) => Some(*field),
+                _ => None,
+            }));
+        }
+
+        // only names taken by earlier documents are avoided, as the fields of
+        // this document are renamed together
+        let mut names = Vec::new();
+
+        for field in &fields {
+            if let Some((_, Object::Dictionary(field))) =
+                document.objects.get_mut(&field.object_number)
+            {
+                if let Some(Object::String(name)) = field.peek("T") {
+                    let unique = unique_name(name, idx, |name| field_names.contains(name));
+                    field.insert("T", Object::String(unique.clone()));
+                    names.push(unique);
+                }
+            }
+        }
+
+        field_names.extend(names);
+
+        let offset = merged.next_object_number() - 1;
+
+        let shift = |reference: Reference| Reference {
+            object_number: reference.object_number + offset,
+            generation: reference.generation,
+        };
+
+        let mut renumber = |obj: &mut Object<'a>| -> PdfResult<()> {
+            if let Object::Reference(reference) = obj {
+                *reference = shift(*reference);
+            }
+
+            Ok(())
+        };
+
+        for name in named {
+            if let Some(dest) = dests.get_mut(&name) {
+                for_each_reference(dest, &mut renumber)?;
+            }
+        }
+
+        if idx == 0 {
+            let mut document_catalog = match document.get(root) {
+                Some(obj) => obj.clone(),
+                None => Object::Null,
+            };
+
+            for_each_reference(&mut document_catalog, &mut renumber)?;
+
+            if let Object::Dictionary(document_catalog) = document_catalog {
+                for key in KEPT_CATALOG_ENTRIES {
+                    if let Some(value) = document_catalog.peek(key) {
+                        catalog.insert(key, value.clone());
+                    }
+                }
+            }
+
+            info = document.trailer.peek("Info").cloned();
+
+            if let Some(info) = &mut info {
+                for_each_reference(info, &mut renumber)?;
+            }
+        }
+
+        for (object_number, (generation, mut obj)) in document.objects {
+            for_each_reference(&mut obj, &mut renumber)?;
+
+            merged.insert(
+                shift(Reference {
+                    object_number,
+                    generation,
+                }),
+                obj,
+            );
+        }
+
+        for (page, _) in leaves {
+            let page = shift(page);
+
+            set_entry(&mut merged, page, "Parent", Object::Reference(page_tree));
+
+            pages.push(page);
+            protected.insert(page);
+        }
+
+        protected.extend(annotations.into_iter().map(shift));
+        protected.extend(fields.into_iter().map(shift));
+
+        if let Some(outline) = outline {
+            outlines.push(Outline {
+                first: shift(outline.first),
+                last: shift(outline.last),
+            });
+        }
+
+        if let Some(form) = form {
+            let mut form = Object::Dictionary(form);
+            for_each_reference(&mut form, &mut renumber)?;
+
+            if let Object::Dictionary(form) = form {
+                match &mut acro_form {
+                    Some(acro_form) => merge_forms(acro_form, form),
+                    None => acro_form = Some(form),
+                }
+            }
+        }
+    }
+
+    merged.insert(
+        page_tree,
+        Object::Dictionary(dictionary([
+            ("Type", Object::Name("Pages".to_owned())),
+            (
+                "Kids",
+                Object::Array(pages.iter().copied().map(Object::Reference).collect()),
+            ),
+            ("Count", Object::Integer(i32::try_from(pages.len())?)),
+        ])),
+    );
+
+    if let (Some(first), Some(last)) = (outlines.first(), outlines.last()) {
+        let outline_root = merged.add(Object::Null);
+
+        for pair in outlines.windows(2) {
+            set_entry(
+                &mut merged,
+                pair[0].last,
+                "Next",
+                Object::Reference(pair[1].first),
+            );
+            set_entry(
+                &mut merged,
+                pair[1].first,
+                "Prev",
+                Object::Reference(pair[0].last),
+            );
+        }
+
+        // the top-level items of every document are now siblings beneath the
+        // merged outline, which counts each of them along with the visible
+        // descendants of those which are open
+        let mut next = Some(first.first);
+        let mut visited = HashSet::new();
+        let mut count = 0;
+
+        while let Some(item) = next.filter(|item| visited.insert(*item)) {
+            set_entry(&mut merged, item, "Parent", Object::Reference(outline_root));
+
+            (next, count) = match merged.get(item) {
+                Some(Object::Dictionary(item)) => (
+                    match item.peek("Next") {
+                        Some(Object::Reference(next)) => Some(*next),
+                        _ => None,
+                    },
+                    match item.peek("Count") {
+                        Some(Object::Integer(open)) => count + 1 + (*open).max(0),
+                        _ => count + 1,
+                    },
+                ),
+                _ => (None, count),
+            };
+        }
+
+        merged.insert(
+            outline_root,
+            Object::Dictionary(dictionary([
+                ("Type", Object::Name("Outlines".to_owned())),
+                ("First", Object::Reference(first.first)),
+                ("Last", Object::Reference(last.last)),
+                ("Count", Object::Integer(count)),
+            ])),
+        );
+
+        catalog.insert("Outlines", Object::Reference(outline_root));
+    }
+
+    if !dests.is_empty() {
+        // the keys of a name tree are sorted, as they are here
+        let names = dests
+            .into_iter()
+            .flat_map(|(name, dest)| [Object::String(name), dest])
+            .collect();
+
+        catalog.insert(
+            "Names",
+            Object::Dictionary(dictionary([(
+                "Dests",
+                Object::Dictionary(dictionary([("Names", Object::Array(names))])),
+            )])),
+        );
+    }
+
+    if let Some(acro_form) = acro_form {
+        catalog.insert("AcroForm", Object::Dictionary(acro_form));
+    }
+
+    catalog.insert("Type", Object::Name("Catalog".to_owned()));
+    catalog.insert("Pages", Object::Reference(page_tree));
+
+    let root = merged.add(Object::Dictionary(catalog));
+    merged.set_root(root);
+
+    if let Some(info) = info {
+        merged.set_trailer_entry("Info", info);
+    }
+
+    deduplicate(&mut merged, &protected)?;
+
+    prune(merged)
+}
+
+/// The object `obj` refers to, if it is a reference, or `obj` itself
+pub(super) fn resolved<'b, 'a>(
+    document: &'b PdfWriter<'a>,
+    obj: &'b Object<'a>,
+) -> Option<&'b Object<'a>> {
+    match obj {
+        Object::Reference(reference) => document.get(*reference),
+        obj => Some(obj),
+    }
+}
+
+pub(super) fn set_entry<'a>(
+    writer: &mut PdfWriter<'a>,
+    reference: Reference,
+    key: &str,
+    value: Object<'a>,
+) {
+    if let Some((_, Object::Dictionary(dict))) = writer.objects.get_mut(&reference.object_number) {
+        dict.insert(key, value);
+    }
+}
+
+/// The pages beneath the page tree node `node`, in order, each with the
+/// attributes it inherits from the nodes above it
+pub(super) fn collect_pages<'a>(
+    document: &PdfWriter<'a>,
+    node: Reference,
+    inherited: &Dictionary<'a>,
+    visited: &mut HashSet<Reference>,
+    pages: &mut Vec<(Reference, Dictionary<'a>)>,
+) {
+    if !visited.insert(node) {
+        return;
+    }
+
+    let dict = match document.get(node) {
+        Some(Object::Dictionary(dict)) => dict,
+        _ => return,
+    };
+
+    let mut inherited = inherited.clone();
+
+    for key in INHERITABLE {
+        if let Some(value) = dict.peek(key) {
+            inherited.insert(key, value.clone());
+        }
+    }
+
+    let is_page = dict.peek("Type").map_or(false, |ty| ty.name_is("Page"));
+
+    match dict.peek("Kids").and_then(|kids| resolved(document, kids)) {
+        Some(Object::Array(kids)) if !is_page => {
+            for kid in kids {
+                if let Object::Reference(kid) = kid {
+                    collect_pages(document, *kid, &inherited, visited, pages);
+                }
+            }
+        }
+        _ => pages.push((node, inherited)),
+    }
+}
+
+/// The keys and values of the name tree `node`, in order
+pub(super) fn name_tree_entries<'a>(
+    document: &PdfWriter<'a>,
+    node: &Object<'a>,
+    visited: &mut HashSet<Reference>,
+    entries: &mut Vec<(String, Object<'a>)>,
+) {
+    if let Object::Reference(reference) = node {
+        if !visited.insert(*reference) {
+            return;
+        }
+    }
+
+    let node = match resolved(document, node) {
+        Some(Object::Dictionary(node)) => node,
+        _ => return,
+    };
+
+    if let Some(Some(Object::Array(names))) =
+        node.peek("Names").map(|names| resolved(document, names))
+    {
+        for pair in names.chunks_exact(2) {
+            if let Object::String(name) = &pair[0] {
+                entries.push((name.clone(), pair[1].clone()));
+            }
+        }
+    }
+
+    if let Some(Some(Object::Array(kids))) = node.peek("Kids").map(|kids| resolved(document, kids))
+    {
+        for kid in kids {
+            name_tree_entries(document, kid, visited, entries);
+        }
+    }
+}
+
+/// The interactive form dictionary `form`, with its Fields array and the
+/// font resources of its default resources (DR) given directly, so that
+/// they can be merged with those of other forms
+fn form_dictionary<'a>(document: &PdfWriter<'a>, form: &Dictionary<'a>) -> Dictionary<'a> {
+    let mut form = form.clone();
+
+    if let Some(Some(fields)) = form.peek("Fields").map(|fields| resolved(document, fields)) {
+        let fields = fields.clone();
+        form.insert("Fields", fields);
+    }
+
+    if let Some(Some(Object::Dictionary(resources))) = form
+        .peek("DR")
+        .map(|resources| resolved(document, resources))
+    {
+        let mut resources = resources.clone();
+
+        if let Some(Some(fonts)) = resources
+            .peek("Font")
+            .map(|fonts| resolved(document, fonts))
+        {
+            let fonts = fonts.clone();
+            resources.insert("Font", fonts);
+        }
+
+        form.insert("DR", Object::Dictionary(resources));
+    }
+
+    form
+}
+
+/// Add the fields of the interactive form `form` to those of `merged`, along
+/// with the fonts of its default resources which `merged` does not name
+///
+/// The other entries of `merged`, such as its default appearance (DA), are
+/// kept, though appearances are regenerated if either form requires it.
+fn merge_forms<'a>(merged: &mut Dictionary<'a>, form: Dictionary<'a>) {
+    if let Some(Object::Array(fields)) = form.peek("Fields") {
+        match merged.peek_mut("Fields") {
+            Some(Object::Array(merged_fields)) => merged_fields.extend(fields.iter().cloned()),
+            _ => {
+                merged.insert("Fields", Object::Array(fields.clone()));
+            }
+        }
+    }
+
+    if let Some(Object::Dictionary(resources)) = form.peek("DR") {
+        if let Some(Object::Dictionary(fonts)) = resources.peek("Font") {
+            let mut merged_resources = match merged.peek("DR") {
+                Some(Object::Dictionary(resources)) => resources.clone(),
+                _ => Dictionary::empty(),
+            };
+            let mut merged_fonts = match merged_resources.peek("Font") {
+                Some(Object::Dictionary(fonts)) => fonts.clone(),
+                _ => Dictionary::empty(),
+            };
+
+            for (name, font) in fonts.iter() {
+                if merged_fonts.peek(name).is_none() {
+                    merged_fonts.insert(name, font.clone());
+                }
+            }
+
+            merged_resources.insert("Font", Object::Dictionary(merged_fonts));
+            merged.insert("DR", Object::Dictionary(merged_resources));
+        }
+    }
+
+    if matches!(form.peek("NeedAppearances"), Some(Object::True)) {
+        merged.insert("NeedAppearances", Object::True);
+    }
+}
+
+/// `name`, prefixed with the position of its document as many times as is
+/// needed to distinguish it from the names for which `taken` holds
+fn unique_name(name: &str, idx: usize, taken: impl Fn(&str) -> bool) -> String {
+    let mut unique = name.to_owned();
+
+    while taken(&unique) {
+        unique = format!("{}:{}", idx + 1, unique);
+    }
+
+    unique
+}
+
+/// Update the named destinations of outline items and link annotations (Dest)
+/// and of GoTo actions (D) within `obj`
+///
+/// Destinations named by name objects refer to the Dests dictionary, which
+/// is merged into the name tree, so they become strings.
+fn rename_destinations(
+    obj: &mut Object,
+    strings: &HashMap<String, String>,
+    names: &HashMap<String, String>,
+) {
+    match obj {
+        Object::Array(arr) => {
+            for obj in arr {
+                rename_destinations(obj, strings, names);
+            }
+        }
+        Object::Dictionary(dict) => {
+            let key = if dict.peek("S").map_or(false, |s| s.name_is("GoTo")) {
+                "D"
+            } else {
+                "Dest"
+            };
+
+            let renamed = match dict.peek(key) {
+                Some(Object::String(name)) => strings.get(name),
+                Some(Object::Name(name)) => names.get(name),
+                _ => None,
+            };
+
+            if let Some(renamed) = renamed.cloned() {
+                dict.insert(key, Object::String(renamed));
+            }
+
+            for obj in dict.values_mut() {
+                rename_destinations(obj, strings, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replace each object which serializes identically to an earlier one by a
+/// reference to the earlier one, other than those in `protected`
+///
+/// Objects which refer to duplicates may themselves become duplicates once
+/// their references are replaced, so this repeats until nothing changes.
+/// Objects within cycles, such as pages, which refer to their parent, are
+/// only deduplicated if their references are already identical.
+fn deduplicate<'a>(writer: &mut PdfWriter<'a>, protected: &HashSet<Reference>) -> PdfResult<()> {
+    loop {
+        let mut seen = HashMap::new();
+        let mut duplicates = HashMap::new();
+
+        for (&object_number, (generation, obj)) in &writer.objects {
+            let reference = Reference {
+                object_number,
+                generation: *generation,
+            };
+
+            if protected.contains(&reference) {
+                continue;
+            }
+
+            let mut serialized = Vec::new();
+            write_object(obj, &mut serialized)?;
+
+            match seen.entry(serialized) {
+                Entry::Occupied(original) => {
+                    duplicates.insert(reference, *original.get());
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(reference);
+                }
+            }
+        }
+
+        if duplicates.is_empty() {
+            return Ok(());
+        }
+
+        for duplicate in duplicates.keys() {
+            writer.objects.remove(&duplicate.object_number);
+        }
+
+        let mut replace = |obj: &mut Object<'a>| -> PdfResult<()> {
+            if let Object::Reference(reference) = obj {
+                if let Some(original) = duplicates.get(reference) {
+                    *reference = *original;
+                }
+            }
+
+            Ok(())
+        };
+
+        for (_, obj) in writer.objects.values_mut() {
+            for_each_reference(obj, &mut replace)?;
+        }
+
+        for obj in writer.trailer.values_mut() {
+            for_each_reference(obj, &mut replace)?;
+        }
+    }
+}
+
+/// Drop the objects which are not reachable from the trailer, such as the
+/// catalogs and page tree nodes of the merged documents, and renumber the
+/// others compactly in the order in which they are reached
+pub(super) fn prune<'a>(writer: PdfWriter<'a>) -> PdfResult<PdfWriter<'a>> {
+    let PdfWriter {
+        version,
+        mut objects,
+        mut trailer,
+        object_streams,
+        features,
+        stream_compression,
+        stream_compressions,
+        security_handler,
+    } = writer;
+
+    let mut queue = VecDeque::new();
+
+    for obj in trailer.values_mut() {
+        queue.extend(references_within(obj)?);
+    }
+
+    let mut renumbered = HashMap::new();
+    let mut reached = Vec::new();
+
+    while let Some(reference) = queue.pop_front() {
+        if renumbered.contains_key(&reference) {
+            continue;
+        }
+
+        let mut obj = match objects.remove(&reference.object_number) {
+            Some((generation, obj)) if generation == reference.generation => obj,
+            Some(entry) => {
+                objects.insert(reference.object_number, entry);
+                continue;
+            }
+            None => continue,
+        };
+
+        renumbered.insert(
+            reference,
+            Reference {
+                object_number: renumbered.len() + 1,
+                generation: 0,
+            },
+        );
+
+        queue.extend(references_within(&mut obj)?);
+        reached.push((reference, obj));
+    }
+
+    let mut renumber = |obj: &mut Object<'a>| -> PdfResult<()> {
+        if let Object::Reference(reference) = obj {
+            *obj = match renumbered.get(reference) {
+                Some(reference) => Object::Reference(*reference),
+                None => Object::Null,
+            };
+        }
+
+        Ok(())
+    };
+
+    let mut pruned = PdfWriter::new(version);
+    pruned.object_streams = object_streams;
+    pruned.features = features;
+    pruned.stream_compression = stream_compression;
+    pruned.security_handler = security_handler;
+    pruned.stream_compressions = stream_compressions
+        .into_iter()
+        .filter_map(|(reference, compression)| Some((*renumbered.get(&reference)?, compression)))
+        .collect();
+
+    for (reference, mut obj) in reached {
+        for_each_reference(&mut obj, &mut renumber)?;
+        pruned.insert(renumbered[&reference], obj);
+    }
+
+    for (key, mut value) in trailer.entries() {
+        for_each_reference(&mut value, &mut renumber)?;
+        pruned.set_trailer_entry(key, value);
+    }
+
+    Ok(pruned)
+}
+
+/// The references within `obj`, including `obj` itself
+fn references_within(obj: &mut Object) -> PdfResult<Vec<Reference>> {
+    let mut references = Vec::new();
+
+    for_each_reference(obj, &mut |obj| {
+        if let Object::Reference(reference) = obj {
+            references.push(*reference);
+        }
+
+        Ok(())
+    })?;
+
+    Ok(references)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ParseOptions, Parser};
+
+    /// A document with one page, whose font and media box are inherited from
+    /// the page tree, a closed outline item referring to the page through the
+    /// named destination "chapter", and a text field named "name"
+    fn document(title: &str) -> Parser<'static> {
+        let mut writer = PdfWriter::new(PdfVersion::V1_7);
+
+        let reference = |object_number| Reference {
+            object_number,
+            generation: 0,
+        };
+
+        let font = Object::Dictionary(dictionary([
+            ("Type", Object::Name("Font".to_owned())),
+            ("Subtype", Object::Name("Type1".to_owned())),
+            ("BaseFont", Object::Name("Helvetica".to_owned())),
+        ]));
+
+        writer.insert(
+            reference(1),
+            Object::Dictionary(dictionary([
+                ("Type", Object::Name("Catalog".to_owned())),
+                ("Pages", Object::Reference(reference(2))),
+                ("Outlines", Object::Reference(reference(5))),
+                (
+                    "AcroForm",
+                    Object::Dictionary(dictionary([
+                        (
+                            "Fields",
+                            Object::Array(vec![Object::Reference(reference(8))]),
+                        ),
+                        (
+                            "DR",
+                            Object::Dictionary(dictionary([(
+                                "Font",
+                                Object::Dictionary(dictionary([(
+                                    "Helv",
+                                    Object::Reference(reference(4)),
+                                )])),
+                            )])),
+                        ),
+                        ("DA", Object::String("/Helv 0 Tf 0 g".to_owned())),
+                    ])),
+                ),
+                (
+                    "Names",
+                    Object::Dictionary(dictionary([(
+                        "Dests",
+                        Object::Dictionary(dictionary([(
+                            "Names",
+                            Object::Array(vec![
+                                Object::String("chapter".to_owned()),
+                                Object::Array(vec![
+                                    Object::Reference(reference(3)),
+                                    Object::Name("XYZ".to_owned()),
+                                    Object::Null,
+                                    Object::Integer(700),
+                                    Object::Null,
+                                ]),
+                            ]),
+                        )])),
+                    )])),
+                ),
+            ])),
+        );
+        writer.insert(
+            reference(2),
+            Object::Dictionary(dictionary([
+                ("Type", Object::Name("Pages".to_owned())),
+                ("Kids", Object::Array(vec![Object::Reference(reference(3))])),
+                ("Count", Object::Integer(1)),
+                (
+                    "MediaBox",
+                    Object::Array(vec![
+                        Object::Integer(0),
+                        Object::Integer(0),
+                        Object::Integer(612),
+                        Object::Integer(792),
+                    ]),
+                ),
+                (
+                    "Resources",
+                    Object::Dictionary(dictionary([(
+                        "Font",
+                        Object::Dictionary(dictionary([("F1", Object::Reference(reference(4)))])),
+                    )])),
+                ),
+            ])),
+        );
+        writer.insert(
+            reference(3),
+            Object::Dictionary(dictionary([
+                ("Type", Object::Name("Page".to_owned())),
+                ("Parent", Object::Reference(reference(2))),
+                (
+                    "Annots",
+                    Object::Array(vec![Object::Reference(reference(8))]),
+                ),
+            ])),
+        );
+        writer.insert(reference(4), font);
+        writer.insert(
+            reference(5),
+            Object::Dictionary(dictionary([
+                ("Type", Object::Name("Outlines".to_owned())),
+                ("First", Object::Reference(reference(6))),
+                ("Last", Object::Reference(reference(6))),
+                ("Count", Object::Integer(1)),
+            ])),
+        );
+        writer.insert(
+            reference(6),
+            Object::Dictionary(dictionary([
+                ("Title", Object::String(title.to_owned())),
+                ("Parent", Object::Reference(reference(5))),
+                ("Dest", Object::String("chapter".to_owned())),
+                ("First", Object::Reference(reference(7))),
+                ("Last", Object::Reference(reference(7))),
+                ("Count", Object::Integer(-1)),
+            ])),
+        );
+        writer.insert(
+            reference(7),
+            Object::Dictionary(dictionary([
+                ("Title", Object::String("section".to_owned())),
+                ("Parent", Object::Reference(reference(6))),
+                ("Dest", Object::String("chapter".to_owned())),
+            ])),
+        );
+        writer.insert(
+            reference(8),
+            Object::Dictionary(dictionary([
+                ("Type", Object::Name("Annot".to_owned())),
+                ("Subtype", Object::Name("Widget".to_owned())),
+                ("FT", Object::Name("Tx".to_owned())),
+                ("T", Object::String("name".to_owned())),
+                ("P", Object::Reference(reference(3))),
+                (
+                    "Rect",
+                    Object::Array(vec![
+                        Object::Integer(72),
+                        Object::Integer(700),
+                        Object::Integer(272),
+                        Object::Integer(720),
+                    ]),
+                ),
+            ])),
+        );
+        writer.set_root(reference(1));
+
+        Parser::from_bytes(writer.to_bytes().unwrap(), ParseOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn merge_documents() {
+        let mut documents = [document("first"), document("second")];
+
+        let merged = Parser::merge(&mut documents).unwrap();
+
+        let fonts = merged
+            .objects
+            .values()
+            .filter(|(_, obj)| match obj {
+                Object::Dictionary(dict) => {
+                    dict.peek("Type").map_or(false, |ty| ty.name_is("Font"))
+                }
+                _ => false,
+            })
+            .count();
+
+        assert_eq!(fonts, 1);
+
+        let mut parser =
+            Parser::from_bytes(merged.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        assert_eq!(parser.pages().len(), 2);
+
+        let outline = parser
+            .outline()
+            .unwrap()
+            .into_iter()
+            .map(|item| (item.title, item.page))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            outline,
+            vec![
+                ("first".to_owned(), Some(0)),
+                ("second".to_owned(), Some(1)),
+            ]
+        );
+        assert!(parser.validate_outline().unwrap().is_empty());
+
+        // only the two closed top-level items are visible
+        let outline_root = parser
+            .catalog
+            .outlines
+            .clone()
+            .unwrap()
+            .get_ref(&mut parser.lexer)
+            .unwrap()
+            .into_owned();
+        assert_eq!(outline_root.count, Some(2));
+
+        let fields = parser
+            .form_fields()
+            .unwrap()
+            .into_iter()
+            .map(|field| field.name)
+            .collect::<Vec<String>>();
+
+        assert_eq!(fields, ["name", "2:name"]);
+    }
+}
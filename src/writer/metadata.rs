@@ -0,0 +1,155 @@
+use crate::{
+    catalog::InformationDictionary,
+    error::PdfResult,
+    filter::decode_stream,
+    objects::{Dictionary, Object, ToObj, TypedReference},
+    stream::Stream,
+    trailer::Trailer,
+    xmp::xmp_packet,
+    FromObj, Lexer, Resolve,
+};
+
+use super::{dictionary, IncrementalUpdate, PdfWriter};
+
+/// Append an incremental update to the file of `lexer` which replaces the
+/// document information dictionary with `info`, and updates the XMP packet of
+/// the document's metadata stream to match, or adds one
+pub(crate) fn update_metadata<'a>(
+    trailer: &Trailer<'a>,
+    info: &InformationDictionary<'a>,
+    lexer: &mut Lexer<'a>,
+) -> PdfResult<Vec<u8>> {
+    let mut update = IncrementalUpdate::new(trailer.size);
+    let mut new_trailer = IncrementalUpdate::trailer(trailer)?;
+    let mut catalog = lexer.assert_dict(Object::Reference(trailer.root))?;
+
+    if add_metadata(
+        &mut update,
+        &mut new_trailer,
+        trailer,
+        &mut catalog,
+        info,
+        lexer,
+    )? {
+        update.insert(trailer.root, Object::Dictionary(catalog));
+    }
+
+    update.write(&lexer.file, new_trailer)
+}
+
+/// Add the replacement of the document information dictionary with `info`
+/// to `update`, along with the XMP packet of the metadata stream of `catalog`
+/// updated to match
+///
+/// Documents without a metadata stream are given one, which is added to
+/// `catalog`. Returns whether `catalog` was changed, in which case it must be
+/// added to the update as well.
+pub(crate) fn add_metadata<'a>(
+    update: &mut IncrementalUpdate<'a>,
+    new_trailer: &mut Dictionary<'a>,
+    trailer: &Trailer<'a>,
+    catalog: &mut Dictionary<'a>,
+    info: &InformationDictionary<'a>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<bool> {
+    // the strings of the information dictionary would have to be encrypted
+    anyhow::ensure!(
+        trailer.encryption.is_none(),
+        "cannot update the metadata of an encrypted document"
+    );
+
+    match &trailer.info {
+        Some(TypedReference::Indirect { reference, .. }) => {
+            update.insert(*reference, info.to_obj()?)
+        }
+        _ => {
+            let reference = update.add(info.to_obj()?);
+            new_trailer.insert("Info", Object::Reference(reference));
+        }
+    }
+
+    match catalog.peek("Metadata").cloned() {
+        Some(Object::Reference(reference)) => {
+            let stream = resolver.assert_stream(Object::Reference(reference))?;
+            let stream = metadata_stream(Some(stream), info, resolver)?;
+
+            update.insert(reference, Object::Stream(stream));
+
+            Ok(false)
+        }
+        _ => {
+            let metadata = update.add(Object::Stream(metadata_stream(None, info, resolver)?));
+            catalog.insert("Metadata", Object::Reference(metadata));
+
+            Ok(true)
+        }
+    }
+}
+
+impl<'a> PdfWriter<'a> {
+    /// Update the XMP packet of the document's metadata stream to match its
+    /// information dictionary, adding a metadata stream if it has none
+    ///
+    /// PDF/A requires the two to agree. Properties of the packet which do not
+    /// mirror an entry of the information dictionary are kept.
+    pub fn sync_xmp_metadata(&mut self) -> PdfResult<()> {
+        let root = match self.trailer.peek("Root") {
+            Some(Object::Reference(root)) => *root,
+            _ => anyhow::bail!("the document catalog must be set before its metadata"),
+        };
+
+        let mut resolver = &*self;
+
+        let info = match resolver.trailer.peek("Info").cloned() {
+            Some(info) => InformationDictionary::from_obj(info, &mut resolver)?,
+            None => InformationDictionary::default(),
+        };
+
+        let mut catalog = resolver.assert_dict(Object::Reference(root))?;
+
+        match catalog.peek("Metadata").cloned() {
+            Some(Object::Reference(reference)) => {
+                let stream = resolver.assert_stream(Object::Reference(reference))?;
+                let stream = metadata_stream(Some(stream), &info, &mut resolver)?;
+
+                self.insert(reference, Object::Stream(stream));
+            }
+            _ => {
+                let stream = metadata_stream(None, &info, &mut resolver)?;
+                let metadata = self.add(Object::Stream(stream));
+
+                catalog.insert("Metadata", Object::Reference(metadata));
+                self.insert(root, Object::Dictionary(catalog));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A metadata stream holding the XMP packet for `info`, updating the packet of
+/// `existing` in place if there is one
+///
+/// The packet is written unfiltered, so that later updates can be made in
+/// place as well.
+fn metadata_stream<'a>(
+    existing: Option<Stream<'a>>,
+    info: &InformationDictionary,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<Stream<'a>> {
+    match existing {
+        Some(stream) => {
+            let data = decode_stream(&stream.stream, &stream.dict, resolver)?;
+            let packet = xmp_packet(Some(&String::from_utf8_lossy(&data)), info)?;
+
+            Ok(Stream::new(packet.into_bytes(), stream.dict.other))
+        }
+        None => Ok(Stream::new(
+            xmp_packet(None, info)?.into_bytes(),
+            dictionary([
+                ("Type", Object::Name("Metadata".to_owned())),
+                ("Subtype", Object::Name("XML".to_owned())),
+            ]),
+        )),
+    }
+}
@@ -0,0 +1,889 @@
+//! Serialization of objects, and of complete files from an in-memory set of
+//! objects
+
+use std::ops::{Bound, Range, RangeBounds};
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    io::Write,
+    path::Path,
+};
+
+use crate::{
+    encryption::{PasswordKind, SecurityHandler},
+    error::PdfResult,
+    filter::FilterKind,
+    objects::{Dictionary, Object, Reference},
+    stream::Stream,
+    version::{PdfFeatures, PdfVersion},
+    writer,
+    xref::ObjectSpan,
+    Parser, Resolve,
+};
+
+pub use builder::{DocumentBuilder, PageBuilder};
+pub(crate) use compact::compact;
+pub use compression::StreamCompression;
+pub use content::ContentBuilder;
+pub(crate) use dss::add_validation_data;
+pub(crate) use extract::extract_pages;
+pub(crate) use fonts::embed_fonts;
+pub(crate) use incremental::IncrementalUpdate;
+pub(crate) use layers::set_default_layer_states;
+pub(crate) use merge::merge;
+pub(crate) use metadata::{add_metadata, update_metadata};
+pub(crate) use object::write_object;
+pub(crate) use outline::{retarget_outline_items, set_outline};
+pub(crate) use portfolio::add_portfolio_member;
+pub(crate) use redact::redact;
+pub(crate) use repair::repair;
+pub(crate) use spot_colors::convert_spot_colors;
+pub(crate) use stamp::stamp;
+
+use object::write_dictionary;
+use xref::{write_xref_table, xref_rows, xref_stream_data, XrefRow};
+
+mod actions;
+mod boxes;
+mod builder;
+mod compact;
+mod compression;
+mod content;
+mod dss;
+mod encryption;
+mod extract;
+mod fonts;
+mod incremental;
+mod layers;
+mod merge;
+mod metadata;
+mod object;
+mod outline;
+mod pages;
+mod portfolio;
+mod redact;
+mod repair;
+mod spot_colors;
+mod stamp;
+mod xref;
+
+/// The number of objects packed into each object stream
+///
+/// Readers decode a whole object stream to read any object within it, so
+/// smaller streams make reading single objects cheaper.
+const OBJECTS_PER_STREAM: usize = 100;
+
+/// The bytes occupied by each object written, with its reference
+type ObjectSpans = Vec<(Reference, ObjectSpan)>;
+
+/// Writes a complete file, with a cross-reference table and trailer, from a
+/// set of indirect objects
+///
+/// Objects are written in order of their object number. Object numbers which
+/// are not used are listed as free in the cross-reference table.
+///
+/// Files of PDF 1.5 and later pack objects other than streams into compressed
+/// object streams, and list their objects in a cross-reference stream, which
+/// holds the entries of the trailer.
+#[derive(Debug, Clone)]
+pub struct PdfWriter<'a> {
+    version: PdfVersion,
+
+    /// Each indirect object, by object number, with its generation number
+    objects: BTreeMap<usize, (usize, Object<'a>)>,
+
+    /// Entries of the trailer other than Size, which is determined by the
+    /// objects written
+    trailer: Dictionary<'a>,
+
+    /// Whether to write object streams and a cross-reference stream
+    object_streams: bool,
+
+    /// The features used by the document, which may require a later version
+    /// than the one it was created with
+    features: PdfFeatures,
+
+    /// How the data of streams is filtered when they are written
+    stream_compression: StreamCompression,
+
+    /// Choices of filters for particular streams, in place of
+    /// `stream_compression`
+    stream_compressions: HashMap<Reference, StreamCompression>,
+
+    /// Encrypts the strings and streams of each object as it is written
+    security_handler: Option<SecurityHandler<'a>>,
+}
+
+impl<'a> PdfWriter<'a> {
+    pub fn new(version: PdfVersion) -> Self {
+        Self {
+            version,
+            objects: BTreeMap::new(),
+            trailer: Dictionary::empty(),
+            object_streams: version >= PdfVersion::V1_5,
+            features: PdfFeatures {
+                xref_streams: version >= PdfVersion::V1_5,
+                ..PdfFeatures::default()
+            },
+            stream_compression: StreamCompression::default(),
+            stream_compressions: HashMap::new(),
+            security_handler: None,
+        }
+    }
+
+    /// Whether to pack objects into object streams, and write a
+    /// cross-reference stream rather than a table
+    ///
+    /// This is enabled by default for PDF 1.5 and later, which introduced them,
+    /// and may be disabled for readers which do not support them. Enabling it
+    /// for an earlier version raises the version of the document to 1.5.
+    pub fn set_object_streams(&mut self, object_streams: bool) {
+        self.object_streams = object_streams;
+        self.features.xref_streams = object_streams;
+    }
+
+    /// Record features used by the document, raising its version to the first
+    /// which supports them
+    ///
+    /// Transparency, optional content and the crypt filters of the standard
+    /// security handler are found in the objects of the document when it is
+    /// written, so this is only needed for features which are not.
+    pub fn use_features(&mut self, features: PdfFeatures) {
+        self.features = self.features.union(features);
+    }
+
+    /// The version of the document: the version it was created with, or the
+    /// first version which supports the features it uses if that is later
+    pub fn version(&self) -> PdfVersion {
+        let features = self
+            .objects
+            .values()
+            .map(|(_, obj)| PdfFeatures::of_object(obj))
+            .fold(self.features, PdfFeatures::union);
+
+        self.version.max(features.minimum_version())
+    }
+
+    /// The version written in the header of the document
+    ///
+    /// This is the version the document was created with, unless it uses a
+    /// cross-reference stream, which must be found before the catalog can be
+    /// read. Later versions required by the objects of the document are given
+    /// by the Version entry of the catalog instead.
+    fn header_version(&self) -> PdfVersion {
+        let features = PdfFeatures {
+            xref_streams: self.features.xref_streams,
+            ..PdfFeatures::default()
+        };
+
+        self.version.max(features.minimum_version())
+    }
+
+    /// The objects to write, with a Version entry added to the catalog if the
+    /// document requires a later version than its header states
+    fn objects_to_write(&self) -> PdfResult<BTreeMap<usize, (usize, Cow<Object<'a>>)>> {
+        let mut objects = self
+            .objects
+            .iter()
+            .map(|(&object_number, (generation, obj))| {
+                (object_number, (*generation, Cow::Borrowed(obj)))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let version = self.version();
+
+        if version > self.header_version() {
+            let root = match self.trailer.peek("Root") {
+                Some(Object::Reference(root)) => *root,
+                _ => anyhow::bail!("the document catalog must be set before writing"),
+            };
+
+            let mut catalog = (&*self).assert_dict(Object::Reference(root))?;
+            catalog.insert("Version", Object::Name(version.as_name()));
+
+            objects.insert(
+                root.object_number,
+                (root.generation, Cow::Owned(Object::Dictionary(catalog))),
+            );
+        }
+
+        Ok(objects)
+    }
+
+    /// Add an object under the next unused object number
+    pub fn add(&mut self, obj: Object<'a>) -> Reference {
+        let reference = Reference {
+            object_number: self.next_object_number(),
+            generation: 0,
+        };
+
+        self.insert(reference, obj);
+
+        reference
+    }
+
+    /// Add or replace the object with the given reference
+    pub fn insert(&mut self, reference: Reference, obj: Object<'a>) -> Option<Object<'a>> {
+        self.objects
+            .insert(reference.object_number, (reference.generation, obj))
+            .map(|(_, obj)| obj)
+    }
+
+    pub fn get(&self, reference: Reference) -> Option<&Object<'a>> {
+        match self.objects.get(&reference.object_number) {
+            Some((generation, obj)) if *generation == reference.generation => Some(obj),
+            _ => None,
+        }
+    }
+
+    pub fn next_object_number(&self) -> usize {
+        self.objects.keys().next_back().map_or(1, |n| n + 1)
+    }
+
+    /// Set the document catalog, which must be set before the file is written
+    pub fn set_root(&mut self, root: Reference) {
+        self.trailer.insert("Root", Object::Reference(root));
+    }
+
+    /// Set the document information dictionary
+    pub fn set_info(&mut self, info: Reference) {
+        self.trailer.insert("Info", Object::Reference(info));
+    }
+
+    /// Set any other entry of the trailer, such as ID or Encrypt
+    pub fn set_trailer_entry(&mut self, key: impl Into<String>, value: Object<'a>) {
+        self.trailer.insert(key, value);
+    }
+
+    /// Write the complete file
+    pub fn write(&self, mut out: impl Write) -> PdfResult<()> {
+        out.write_all(&self.to_bytes()?)?;
+
+        Ok(())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> PdfResult<()> {
+        std::fs::write(path, self.to_bytes()?)?;
+
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> PdfResult<Vec<u8>> {
+        Ok(self.to_bytes_with_spans()?.0)
+    }
+
+    /// Write the complete file, along with the bytes occupied by each object
+    /// written, ordered by object number
+    ///
+    /// Spans are those `Parser::object_spans` finds when the file is read
+    /// back, so they include the object streams and cross-reference stream
+    /// the writer adds, and objects within an object stream are given the span
+    /// of that stream.
+    pub fn to_bytes_with_spans(&self) -> PdfResult<(Vec<u8>, ObjectSpans)> {
+        if self.trailer.iter().all(|(key, _)| key != "Root") {
+            anyhow::bail!("the document catalog must be set before writing");
+        }
+
+        // a comment of at least four bytes above 127 marks the file as binary
+        // to tools which would otherwise treat it as text
+        let mut out = format!("%PDF-{}\n%", self.header_version()).into_bytes();
+        out.extend_from_slice(&[0xE2, 0xE3, 0xCF, 0xD3]);
+        out.push(b'\n');
+
+        let mut spans = Vec::new();
+
+        if self.object_streams {
+            self.write_compressed(&mut out, &mut spans)?;
+        } else {
+            self.write_uncompressed(&mut out, &mut spans)?;
+        }
+
+        spans.sort_by_key(|(reference, _): &(Reference, ObjectSpan)| reference.object_number);
+
+        Ok((out, spans))
+    }
+
+    fn write_uncompressed(&self, out: &mut Vec<u8>, spans: &mut ObjectSpans) -> PdfResult<()> {
+        let mut used = BTreeMap::new();
+
+        for (&object_number, (generation, obj)) in &self.objects_to_write()? {
+            used.insert(
+                object_number,
+                XrefRow::InFile {
+                    offset: out.len(),
+                    generation: *generation,
+                },
+            );
+
+            let reference = Reference {
+                object_number,
+                generation: *generation,
+            };
+
+            let prepared = self.encrypted(reference, self.prepared(reference, obj)?)?;
+
+            spans.push((
+                reference,
+                ObjectSpan {
+                    range: write_indirect_object(object_number, *generation, &prepared, out)?,
+                    object_stream: None,
+                },
+            ));
+        }
+
+        let size = self.next_object_number();
+        let xref_offset = out.len();
+
+        write_xref_table(&xref_rows(&used, size), out)?;
+
+        let mut trailer = self.trailer.clone();
+        trailer.insert("Size", Object::Integer(i32::try_from(size)?));
+
+        out.extend_from_slice(b"trailer\n");
+        write_dictionary(&trailer, out)?;
+        writeln!(out, "\nstartxref\n{}\n%%EOF", xref_offset)?;
+
+        Ok(())
+    }
+
+    /// Write objects other than streams within object streams, following
+    /// all other objects, and finish with a cross-reference stream
+    fn write_compressed(&self, out: &mut Vec<u8>, spans: &mut ObjectSpans) -> PdfResult<()> {
+        let encrypt = self.trailer.iter().find_map(|(key, value)| match value {
+            Object::Reference(reference) if key == "Encrypt" => Some(reference.object_number),
+            _ => None,
+        });
+
+        // streams cannot be within object streams, nor objects with a
+        // generation other than 0, and the encryption dictionary must be
+        // readable before any object stream can be decrypted
+        let objects = self.objects_to_write()?;
+
+        let (compressible, uncompressed): (Vec<_>, Vec<_>) =
+            objects
+                .iter()
+                .partition(|&(&object_number, (generation, obj))| {
+                    *generation == 0
+                        && !matches!(&**obj, Object::Stream(..))
+                        && Some(object_number) != encrypt
+                });
+
+        let mut used = BTreeMap::new();
+
+        for (&object_number, (generation, obj)) in uncompressed {
+            used.insert(
+                object_number,
+                XrefRow::InFile {
+                    offset: out.len(),
+                    generation: *generation,
+                },
+            );
+
+            let reference = Reference {
+                object_number,
+                generation: *generation,
+            };
+
+            let prepared = self.encrypted(reference, self.prepared(reference, obj)?)?;
+
+            spans.push((
+                reference,
+                ObjectSpan {
+                    range: write_indirect_object(object_number, *generation, &prepared, out)?,
+                    object_stream: None,
+                },
+            ));
+        }
+
+        let mut next_object_number = self.next_object_number();
+
+        for objects in compressible.chunks(OBJECTS_PER_STREAM) {
+            let stream_number = next_object_number;
+            next_object_number += 1;
+
+            let stream_reference = Reference {
+                object_number: stream_number,
+                generation: 0,
+            };
+
+            let mut offsets = Vec::new();
+            let mut body = Vec::new();
+
+            for (index, &(&object_number, (_, obj))) in objects.iter().enumerate() {
+                write!(offsets, "{} {} ", object_number, body.len())?;
+                write_object(obj, &mut body)?;
+                body.push(b'\n');
+
+                used.insert(
+                    object_number,
+                    XrefRow::Compressed {
+                        stream: stream_number,
+                        index,
+                    },
+                );
+            }
+
+            let first = offsets.len();
+            offsets.extend_from_slice(&body);
+
+            let dict = dictionary([
+                ("Type", Object::Name("ObjStm".to_owned())),
+                ("N", Object::Integer(i32::try_from(objects.len())?)),
+                ("First", Object::Integer(i32::try_from(first)?)),
+            ]);
+
+            used.insert(
+                stream_number,
+                XrefRow::InFile {
+                    offset: out.len(),
+                    generation: 0,
+                },
+            );
+
+            // the objects within an object stream are encrypted along with
+            // the stream, rather than individually
+            let stream = self.encrypted(
+                stream_reference,
+                Cow::Owned(Object::Stream(flate_stream(&offsets, dict)?)),
+            )?;
+
+            let range = write_indirect_object(stream_number, 0, &stream, out)?;
+
+            spans.extend(objects.iter().map(|&(&object_number, _)| {
+                (
+                    Reference {
+                        object_number,
+                        generation: 0,
+                    },
+                    ObjectSpan {
+                        range: range.clone(),
+                        object_stream: Some(stream_reference),
+                    },
+                )
+            }));
+            spans.push((
+                stream_reference,
+                ObjectSpan {
+                    range,
+                    object_stream: None,
+                },
+            ));
+        }
+
+        // the cross-reference stream lists itself
+        let xref_number = next_object_number;
+        let size = xref_number + 1;
+        let xref_offset = out.len();
+
+        used.insert(
+            xref_number,
+            XrefRow::InFile {
+                offset: xref_offset,
+                generation: 0,
+            },
+        );
+
+        let (data, widths) = xref_stream_data(&xref_rows(&used, size));
+
+        let mut dict = self.trailer.clone();
+        dict.insert("Type", Object::Name("XRef".to_owned()));
+        dict.insert("Size", Object::Integer(i32::try_from(size)?));
+        dict.insert(
+            "W",
+            Object::Array(
+                widths
+                    .into_iter()
+                    .map(|width| Object::Integer(width as i32))
+                    .collect(),
+            ),
+        );
+
+        let range = write_indirect_object(
+            xref_number,
+            0,
+            &Object::Stream(flate_stream(&data, dict)?),
+            out,
+        )?;
+
+        spans.push((
+            Reference {
+                object_number: xref_number,
+                generation: 0,
+            },
+            ObjectSpan {
+                range,
+                object_stream: None,
+            },
+        ));
+        writeln!(out, "startxref\n{}\n%%EOF", xref_offset)?;
+
+        Ok(())
+    }
+}
+
+/// Objects are resolved from those held by the writer, so that they may be
+/// read, such as to decode a stream, before they are written
+impl<'a> Resolve<'a> for PdfWriter<'a> {
+    fn lex_object_from_reference(&mut self, reference: Reference) -> PdfResult<Object<'a>> {
+        Ok(self.get(reference).cloned().unwrap_or(Object::Null))
+    }
+
+    fn reference_exists(&mut self, reference: Reference) -> PdfResult<bool> {
+        Ok(self.get(reference).is_some())
+    }
+}
+
+impl<'a> Resolve<'a> for &PdfWriter<'a> {
+    fn lex_object_from_reference(&mut self, reference: Reference) -> PdfResult<Object<'a>> {
+        Ok(self.get(reference).cloned().unwrap_or(Object::Null))
+    }
+
+    fn reference_exists(&mut self, reference: Reference) -> PdfResult<bool> {
+        Ok(self.get(reference).is_some())
+    }
+}
+
+/// Write an indirect object, returning the bytes it occupies from the start of
+/// its object number through the end of its `endobj` keyword
+fn write_indirect_object(
+    object_number: usize,
+    generation: usize,
+    obj: &Object,
+    out: &mut Vec<u8>,
+) -> PdfResult<Range<usize>> {
+    let start = out.len();
+
+    writeln!(out, "{} {} obj", object_number, generation)?;
+    write_object(obj, out)?;
+    out.extend_from_slice(b"\nendobj");
+
+    let end = out.len();
+    out.push(b'\n');
+
+    Ok(start..end)
+}
+
+fn dictionary<'a, const N: usize>(entries: [(&str, Object<'a>); N]) -> Dictionary<'a> {
+    Dictionary::new(
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value))
+            .collect(),
+    )
+}
+
+/// A stream of `data` compressed with the Flate filter
+fn flate_stream<'a>(data: &[u8], dict: Dictionary<'a>) -> PdfResult<Stream<'a>> {
+    Stream::encoded(data, vec![FilterKind::Flate], dict)
+}
+
+impl<'a> Parser<'a> {
+    /// A writer holding only the objects reachable from the trailer, such as
+    /// the catalog and information dictionary, renumbered compactly
+    ///
+    /// Objects left behind by incremental updates, and object and
+    /// cross-reference streams, are dropped, so that the document can be
+    /// written as a single revision. Encrypted documents are written without
+    /// their encryption, which requires the owner password.
+    pub fn compacted(&mut self) -> PdfResult<PdfWriter<'a>> {
+        if let Some(security_handler) = &self.lexer.security_handler {
+            if security_handler.password_kind() != PasswordKind::Owner {
+                anyhow::bail!("removing the encryption of a document requires the owner password");
+            }
+        }
+
+        writer::compact(&mut self.lexer, &self.trailer, self.version)
+    }
+
+    /// Write the document to `path` as a single revision, without the objects
+    /// which are no longer reachable
+    ///
+    /// If the document has XMP metadata, its packet is updated to match the
+    /// document information dictionary.
+    pub fn save_as(&mut self, path: impl AsRef<std::path::Path>) -> PdfResult<()> {
+        let mut document = self.compacted()?;
+
+        if self.catalog.metadata.is_some() {
+            document.sync_xmp_metadata()?;
+        }
+
+        document.save(path)
+    }
+
+    /// A writer holding the pages of each of `documents`, in order, along with
+    /// their outlines and named destinations
+    ///
+    /// Each document is compacted as by [`Parser::compacted`], so encrypted
+    /// documents require their owner password. Resources shared between the
+    /// documents, such as identical fonts, are written once. Named
+    /// destinations of later documents which collide with those of earlier
+    /// ones are prefixed with the position of their document, as in
+    /// `2:chapter`. The merged document has the highest version of the
+    /// documents, and the information dictionary of the first.
+    pub fn merge(documents: &mut [Parser<'a>]) -> PdfResult<PdfWriter<'a>> {
+        let version = match documents.iter().map(Parser::version).max() {
+            Some(version) => version,
+            None => anyhow::bail!("at least one document is required to merge"),
+        };
+
+        let documents = documents
+            .iter_mut()
+            .map(Parser::compacted)
+            .collect::<PdfResult<Vec<_>>>()?;
+
+        writer::merge(documents, version)
+    }
+
+    /// A writer holding the document in `file`, rebuilt from the objects found
+    /// by scanning it, for documents too damaged to open
+    ///
+    /// The cross-reference table is ignored, so files whose table is missing
+    /// or points at the wrong offsets are recovered. Stream lengths are
+    /// corrected, the Count of each page tree node is made to match the pages
+    /// beneath it, and pages which have been cut off from the page tree are
+    /// gathered into a new one if no other pages remain. Encrypted documents
+    /// cannot be repaired.
+    pub fn repair(file: &'a [u8]) -> PdfResult<PdfWriter<'a>> {
+        writer::repair(file)
+    }
+
+    /// A writer holding a standalone document made of the pages `range` of
+    /// this document, with only the fonts, images and other resources they
+    /// use
+    ///
+    /// The document is compacted as by [`Parser::compacted`], so encrypted
+    /// documents require their owner password.
+    pub fn extract_pages(&mut self, range: impl RangeBounds<usize>) -> PdfResult<PdfWriter<'a>> {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.pages().len(),
+        };
+
+        writer::extract_pages(self.compacted()?, start..end)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{stream::Stream, Lexer, ParseOptions, Resolve};
+
+    #[test]
+    fn round_trip() {
+        let mut writer = PdfWriter::new(PdfVersion::V1_7);
+
+        let pages = Reference {
+            object_number: 2,
+            generation: 0,
+        };
+
+        let catalog = writer.add(Object::Dictionary(Dictionary::new(HashMap::from([
+            ("Type".to_owned(), Object::Name("Catalog".to_owned())),
+            ("Pages".to_owned(), Object::Reference(pages)),
+        ]))));
+
+        writer.insert(
+            pages,
+            Object::Dictionary(Dictionary::new(HashMap::from([
+                ("Type".to_owned(), Object::Name("Pages".to_owned())),
+                ("Kids".to_owned(), Object::Array(Vec::new())),
+                ("Count".to_owned(), Object::Integer(0)),
+            ]))),
+        );
+
+        // leave objects 3 and 4 free
+        let content = Reference {
+            object_number: 5,
+            generation: 0,
+        };
+
+        writer.insert(
+            content,
+            Object::Stream(Stream::new(
+                b"BT (a\\)b) Tj ET".to_vec(),
+                Dictionary::empty(),
+            )),
+        );
+
+        let string = writer.add(Object::String("\u{0}(\u{ff}".to_owned()));
+
+        writer.set_root(catalog);
+
+        // objects 1, 2, and 6 are packed into object stream 7, and object 8 is
+        // the cross-reference stream
+        for (object_streams, size) in [(false, 7), (true, 9)] {
+            writer.set_object_streams(object_streams);
+
+            let bytes = writer.to_bytes().unwrap();
+
+            assert!(bytes.starts_with(b"%PDF-1.7\n"));
+            assert!(bytes.ends_with(b"%%EOF\n"));
+            assert_eq!(bytes.windows(7).any(|w| w == b"/ObjStm"), object_streams);
+
+            let (mut lexer, trailer) = Lexer::open(bytes, ParseOptions::default()).unwrap();
+
+            assert_eq!(trailer.size, size);
+            assert_eq!(trailer.root, catalog);
+
+            let mut pages = lexer.assert_dict(Object::Reference(pages)).unwrap();
+            assert_eq!(pages.expect_integer("Count", &mut lexer).unwrap(), 0);
+
+            let stream = lexer.assert_stream(Object::Reference(content)).unwrap();
+            assert_eq!(&*stream.stream, b"BT (a\\)b) Tj ET");
+
+            assert_eq!(
+                lexer.assert_string(Object::Reference(string)).unwrap(),
+                "\u{0}(\u{ff}"
+            );
+        }
+    }
+
+    #[test]
+    fn version_raised_by_features() {
+        let mut writer = DocumentBuilder::new(PdfVersion::V1_4).build().unwrap();
+
+        assert!(writer.to_bytes().unwrap().starts_with(b"%PDF-1.4\n"));
+
+        writer.set_object_streams(true);
+
+        let bytes = writer.to_bytes().unwrap();
+        assert!(bytes.starts_with(b"%PDF-1.5\n"));
+        assert!(bytes.windows(5).any(|w| w == b"/XRef"));
+
+        writer.use_features(PdfFeatures {
+            aes_128: true,
+            ..PdfFeatures::default()
+        });
+
+        assert_eq!(writer.version(), PdfVersion::V1_6);
+
+        writer.set_object_streams(false);
+
+        assert_eq!(writer.version(), PdfVersion::V1_6);
+
+        // the header keeps the version the document was created with, and the
+        // catalog raises it
+        let bytes = writer.to_bytes().unwrap();
+        assert!(bytes.starts_with(b"%PDF-1.4\n"));
+
+        let parser = Parser::from_bytes(bytes, ParseOptions::default()).unwrap();
+        assert_eq!(parser.header_version(), Some(PdfVersion::V1_4));
+        assert_eq!(parser.version(), PdfVersion::V1_6);
+    }
+
+    #[test]
+    fn version_raised_by_written_objects() {
+        let mut writer = DocumentBuilder::new(PdfVersion::V1_3).build().unwrap();
+
+        let opaque = writer.add(Object::Dictionary(dictionary([
+            ("CA", Object::Integer(1)),
+            ("BM", Object::Name("Normal".to_owned())),
+            ("SMask", Object::Name("None".to_owned())),
+        ])));
+
+        assert_eq!(writer.version(), PdfVersion::V1_3);
+
+        let bytes = writer.to_bytes().unwrap();
+        assert!(!bytes.windows(8).any(|w| w == b"/Version"));
+
+        writer.insert(
+            opaque,
+            Object::Dictionary(dictionary([("ca", Object::Real(0.5))])),
+        );
+
+        assert_eq!(writer.version(), PdfVersion::V1_4);
+
+        writer.add(Object::Dictionary(dictionary([(
+            "StdCF",
+            Object::Dictionary(dictionary([("CFM", Object::Name("AESV2".to_owned()))])),
+        )])));
+
+        assert_eq!(writer.version(), PdfVersion::V1_6);
+
+        let bytes = writer.to_bytes().unwrap();
+        assert!(bytes.starts_with(b"%PDF-1.3\n"));
+
+        let parser = Parser::from_bytes(bytes, ParseOptions::default()).unwrap();
+        assert_eq!(parser.version(), PdfVersion::V1_6);
+        assert_eq!(parser.pages().len(), 0);
+    }
+
+    #[test]
+    fn spans_of_written_objects() {
+        for object_streams in [false, true] {
+            let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+            builder.add_page(PageBuilder::new(612.0, 792.0)).unwrap();
+            builder.add_object(Object::Stream(Stream::new(
+                b"0 0 m 10 10 l S".to_vec(),
+                Dictionary::empty(),
+            )));
+
+            let mut writer = builder.build().unwrap();
+            writer.set_object_streams(object_streams);
+
+            let (bytes, spans) = writer.to_bytes_with_spans().unwrap();
+
+            for (reference, span) in &spans {
+                let written = &bytes[span.range.clone()];
+                let object_number = span.object_stream.unwrap_or(*reference).object_number;
+
+                assert!(written.starts_with(format!("{} 0 obj", object_number).as_bytes()));
+                assert!(written.ends_with(b"endobj"));
+            }
+
+            assert_eq!(
+                spans.iter().any(|(_, span)| span.object_stream.is_some()),
+                object_streams
+            );
+
+            // the spans are those found when the file is read back
+            let mut parser = Parser::from_bytes(bytes, ParseOptions::default()).unwrap();
+
+            assert_eq!(parser.object_spans().unwrap(), spans);
+        }
+    }
+
+    #[test]
+    fn free_list() {
+        let mut writer = PdfWriter::new(PdfVersion::V1_4);
+
+        let catalog = Reference {
+            object_number: 2,
+            generation: 0,
+        };
+
+        writer.insert(catalog, Object::Null);
+        writer.insert(
+            Reference {
+                object_number: 4,
+                generation: 3,
+            },
+            Object::Null,
+        );
+        writer.set_root(catalog);
+
+        let bytes = writer.to_bytes().unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+
+        assert!(text.contains(
+            "xref\n0 5\n\
+             0000000001 65535 f \n\
+             0000000003 00001 f \n"
+        ));
+        assert!(text.contains("0000000000 00001 f \n"));
+        assert!(text.contains(" 00003 n \n"));
+        assert!(text.contains("trailer\n<</Root 2 0 R/Size 5>>"));
+
+        assert!(PdfWriter::new(PdfVersion::V1_4).to_bytes().is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use std::io::{self, Write};
+
+use crate::{
+    error::PdfResult,
+    objects::{Dictionary, Object},
+    stream::Stream,
+};
+
+/// Serialize a direct object
+///
+/// Dictionaries are written in their original key order if it was preserved,
+/// and sorted by key otherwise, so that the output is deterministic.
+pub fn write_object(obj: &Object, out: &mut impl Write) -> PdfResult<()> {
+    match obj {
+        Object::Null => out.write_all(b"null")?,
+        Object::True => out.write_all(b"true")?,
+        Object::False => out.write_all(b"false")?,
+        Object::Integer(i) => write!(out, "{}", i)?,
+        Object::Real(r) => write_real(*r, out)?,
+        Object::String(s) => write_string(s, out)?,
+        Object::Name(name) => write_name(name, out)?,
+        Object::Array(arr) => {
+            out.write_all(b"[")?;
+
+            for (idx, obj) in arr.iter().enumerate() {
+                if idx != 0 {
+                    out.write_all(b" ")?;
+                }
+
+                write_object(obj, out)?;
+            }
+
+            out.write_all(b"]")?;
+        }
+        Object::Dictionary(dict) => write_dictionary(dict, out)?,
+        Object::Stream(stream) => write_stream(stream, out)?,
+        Object::Reference(reference) => write!(
+            out,
+            "{} {} R",
+            reference.object_number, reference.generation
+        )?,
+    }
+
+    Ok(())
+}
+
+pub fn write_dictionary(dict: &Dictionary, out: &mut impl Write) -> PdfResult<()> {
+    let mut entries = dict.iter().collect::<Vec<_>>();
+
+    if !dict.preserves_key_order() {
+        entries.sort_by_key(|&(key, _)| key);
+    }
+
+    out.write_all(b"<<")?;
+
+    for (key, value) in entries {
+        write_name(key, out)?;
+        out.write_all(b" ")?;
+        write_object(value, out)?;
+    }
+
+    out.write_all(b">>")?;
+
+    Ok(())
+}
+
+/// Write a stream with its data as it is held, so that its Length and
+/// filters are those of the data in memory rather than in the original file
+fn write_stream(stream: &Stream, out: &mut impl Write) -> PdfResult<()> {
+    write_dictionary(&stream.dict.to_dict(stream.stream.len())?, out)?;
+
+    out.write_all(b"\nstream\n")?;
+    out.write_all(&stream.stream)?;
+    out.write_all(b"\nendstream")?;
+
+    Ok(())
+}
+
+/// Write a real number in decimal notation, which is the only notation PDF
+/// supports
+///
+/// Integral values keep a decimal point, so that they are read back as reals.
+fn write_real(r: f32, out: &mut impl Write) -> io::Result<()> {
+    if !r.is_finite() {
+        return out.write_all(b"0");
+    }
+
+    let s = r.to_string();
+
+    if s.contains('.') {
+        out.write_all(s.as_bytes())
+    } else {
+        write!(out, "{}.0", s)
+    }
+}
+
+/// Write a string, whose bytes are held as `char`s
+///
+/// Strings of printable text are written as literal strings, escaping their
+/// delimiters, and all others as hexadecimal strings. Strings which were read
+/// as UTF-16 hold characters beyond a single byte, and are written as UTF-16
+/// again, with a byte order marker.
+fn write_string(s: &str, out: &mut impl Write) -> io::Result<()> {
+    if s.chars().any(|c| c > '\u{ff}') {
+        out.write_all(b"<FEFF")?;
+
+        for unit in s.encode_utf16() {
+            write!(out, "{:04X}", unit)?;
+        }
+
+        return out.write_all(b">");
+    }
+
+    let is_text = s
+        .chars()
+        .all(|c| matches!(c, ' '..='~' | '\n' | '\r' | '\t'));
+
+    if !is_text {
+        out.write_all(b"<")?;
+
+        for c in s.chars() {
+            write!(out, "{:02X}", c as u32 as u8)?;
+        }
+
+        return out.write_all(b">");
+    }
+
+    out.write_all(b"(")?;
+
+    for c in s.chars() {
+        match c {
+            '(' | ')' | '\\' => write!(out, "\\{}", c)?,
+            // line endings within strings are otherwise normalized to '\n'
+            '\r' => out.write_all(b"\\r")?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+
+    out.write_all(b")")
+}
+
+/// Write a name, escaping bytes which are not regular characters as a number
+/// sign followed by their hexadecimal code
+pub(super) fn write_name(name: &str, out: &mut impl Write) -> io::Result<()> {
+    out.write_all(b"/")?;
+
+    for c in name.chars() {
+        match c {
+            '!'..='~'
+                if !matches!(
+                    c,
+                    '#' | '(' | ')' | '<' | '>' | '[' | ']' | '{' | '}' | '/' | '%'
+                ) =>
+            {
+                write!(out, "{}", c)?
+            }
+            c => write!(out, "#{:02X}", c as u32 as u8)?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::objects::Reference;
+
+    fn written(obj: &Object) -> String {
+        let mut out = Vec::new();
+        write_object(obj, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn write_direct_objects() {
+        assert_eq!(written(&Object::Real(1.0)), "1.0");
+        assert_eq!(written(&Object::Real(-0.25)), "-0.25");
+        assert_eq!(
+            written(&Object::String("a (b) \\c\r".to_owned())),
+            "(a \\(b\\) \\\\c\\r)"
+        );
+        assert_eq!(written(&Object::String("\u{0}\u{ff}".to_owned())), "<00FF>");
+        assert_eq!(written(&Object::String("\u{3b1}".to_owned())), "<FEFF03B1>");
+        assert_eq!(written(&Object::Name("A B#".to_owned())), "/A#20B#23");
+
+        let dict = Dictionary::new(HashMap::from([
+            ("Type".to_owned(), Object::Name("Page".to_owned())),
+            (
+                "Kids".to_owned(),
+                Object::Array(vec![
+                    Object::Reference(Reference {
+                        object_number: 4,
+                        generation: 0,
+                    }),
+                    Object::Null,
+                ]),
+            ),
+        ]));
+
+        assert_eq!(
+            written(&Object::Dictionary(dict)),
+            "<</Kids [4 0 R null]/Type /Page>>"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{
+    error::PdfResult,
+    objects::{encode_text_string, Object, Reference},
+    outline::OutlineItem,
+    trailer::Trailer,
+    Lexer, Resolve,
+};
+
+use super::{dictionary, IncrementalUpdate};
+
+/// Append an incremental update to the file of `lexer` which replaces the
+/// document outline with `items`
+///
+/// `pages` are the page objects of the document, in order, to which the items
+/// refer by index. Items which do not refer to a page are given no
+/// destination. A document given no items is left without an outline.
+pub(crate) fn set_outline<'a>(
+    trailer: &Trailer<'a>,
+    items: &[OutlineItem],
+    pages: &[Reference],
+    lexer: &mut Lexer<'a>,
+) -> PdfResult<Vec<u8>> {
+    // the titles of the items would have to be encrypted
+    anyhow::ensure!(
+        trailer.encryption.is_none(),
+        "cannot change the outline of an encrypted document"
+    );
+
+    let mut update = IncrementalUpdate::new(trailer.size);
+    let mut catalog = lexer.assert_dict(Object::Reference(trailer.root))?;
+
+    if items.is_empty() {
+        catalog.remove("Outlines");
+    } else {
+        let root = update.add(Object::Null);
+        let (first, last, visible) = add_items(&mut update, items, root, pages)?;
+
+        let mut outline = dictionary([
+            ("Type", Object::Name("Outlines".to_owned())),
+            ("First", Object::Reference(first)),
+            ("Last", Object::Reference(last)),
+        ]);
+
+        // the count of the outline dictionary is never negative, and is
+        // omitted if no item is visible
+        if visible > 0 {
+            outline.insert("Count", Object::Integer(visible));
+        }
+
+        update.insert(root, Object::Dictionary(outline));
+        catalog.insert("Outlines", Object::Reference(root));
+    }
+
+    update.insert(trailer.root, Object::Dictionary(catalog));
+
+    update.write(&lexer.file, IncrementalUpdate::trailer(trailer)?)
+}
+
+/// Append an incremental update to the file of `lexer` which points each of
+/// the outline items of `retargeted` at the given page object, or leaves the
+/// item without a destination if it is given none
+pub(crate) fn retarget_outline_items<'a>(
+    trailer: &Trailer<'a>,
+    retargeted: &[(Reference, Option<Reference>)],
+    lexer: &mut Lexer<'a>,
+) -> PdfResult<Vec<u8>> {
+    // the titles of the items would have to be encrypted
+    anyhow::ensure!(
+        trailer.encryption.is_none(),
+        "cannot change the outline of an encrypted document"
+    );
+
+    let mut update = IncrementalUpdate::new(trailer.size);
+
+    for &(item, page) in retargeted {
+        let mut dict = lexer.assert_dict(Object::Reference(item))?;
+
+        let dest = page.map(|page| {
+            Object::Array(vec![
+                Object::Reference(page),
+                Object::Name("Fit".to_owned()),
+            ])
+        });
+
+        match (dict.peek("Dest"), dict.peek("A").cloned()) {
+            // the destination of a go-to action is replaced within the action,
+            // so that any actions which follow it are kept
+            (None, Some(action)) => match dest {
+                Some(dest) => {
+                    let mut action = lexer.assert_dict(action)?;
+                    action.insert("D", dest);
+                    dict.insert("A", Object::Dictionary(action));
+                }
+                None => {
+                    dict.remove("A");
+                }
+            },
+            _ => match dest {
+                Some(dest) => {
+                    dict.insert("Dest", dest);
+                }
+                None => {
+                    dict.remove("Dest");
+                }
+            },
+        }
+
+        update.insert(item, Object::Dictionary(dict));
+    }
+
+    update.write(&lexer.file, IncrementalUpdate::trailer(trailer)?)
+}
+
+/// Add the non-empty `items`, and their descendants, as the children of
+/// `parent`
+///
+/// Returns the first and last of the items, and the number of items which are
+/// visible beneath `parent` when it is open.
+fn add_items<'a>(
+    update: &mut IncrementalUpdate<'a>,
+    items: &[OutlineItem],
+    parent: Reference,
+    pages: &[Reference],
+) -> PdfResult<(Reference, Reference, i32)> {
+    // siblings refer to each other, so each is numbered before any is written
+    let references = items
+        .iter()
+        .map(|_| update.add(Object::Null))
+        .collect::<Vec<Reference>>();
+
+    let mut visible = 0;
+
+    for (idx, item) in items.iter().enumerate() {
+        let mut dict = dictionary([
+            ("Title", Object::String(encode_text_string(&item.title))),
+            ("Parent", Object::Reference(parent)),
+        ]);
+
+        if idx > 0 {
+            dict.insert("Prev", Object::Reference(references[idx - 1]));
+        }
+
+        if let Some(&next) = references.get(idx + 1) {
+            dict.insert("Next", Object::Reference(next));
+        }
+
+        visible += 1;
+
+        if !item.children.is_empty() {
+            let (first, last, descendants) =
+                add_items(update, &item.children, references[idx], pages)?;
+
+            dict.insert("First", Object::Reference(first));
+            dict.insert("Last", Object::Reference(last));
+
+            // closed items give the number of descendants which would be
+            // visible if they were opened as a negative count
+            if item.is_open {
+                dict.insert("Count", Object::Integer(descendants));
+                visible += descendants;
+            } else {
+                dict.insert("Count", Object::Integer(-descendants));
+            }
+        }
+
+        if let Some(page) = item.page {
+            let page = match pages.get(page) {
+                Some(&page) => page,
+                None => anyhow::bail!(
+                    "outline item {:?} refers to page {} of a document with {} pages",
+                    item.title,
+                    page,
+                    pages.len()
+                ),
+            };
+
+            let dest = match item.top {
+                Some(top) => vec![
+                    Object::Reference(page),
+                    Object::Name("XYZ".to_owned()),
+                    Object::Null,
+                    Object::Real(top),
+                    Object::Null,
+                ],
+                None => vec![Object::Reference(page), Object::Name("Fit".to_owned())],
+            };
+
+            dict.insert("Dest", Object::Array(dest));
+        }
+
+        if item.color != [0.0, 0.0, 0.0] {
+            dict.insert(
+                "C",
+                Object::Array(item.color.iter().copied().map(Object::Real).collect()),
+            );
+        }
+
+        let flags = i32::from(item.is_italic) | i32::from(item.is_bold) << 1;
+
+        if flags != 0 {
+            dict.insert("F", Object::Integer(flags));
+        }
+
+        update.insert(references[idx], Object::Dictionary(dict));
+    }
+
+    Ok((references[0], references[references.len() - 1], visible))
+}
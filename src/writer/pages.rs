@@ -0,0 +1,518 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    error::PdfResult,
+    objects::{Dictionary, Object, Reference},
+};
+
+use super::{
+    merge::{collect_pages, resolved},
+    PdfWriter,
+};
+
+/// The ways an explicit destination may fit its page to the window, which
+/// follow the page in the destination array
+const FIT_TYPES: [&str; 8] = [
+    "XYZ", "Fit", "FitH", "FitV", "FitR", "FitB", "FitBH", "FitBV",
+];
+
+/// Operations on the page tree, which keep the Kids and Count of each node
+/// consistent with the Parent of each of its children
+///
+/// Explicit destinations refer to pages by reference, so they continue to go
+/// to pages which are moved. Destinations which refer to pages by index
+/// instead, as some producers write, are updated to the new index of their
+/// page.
+impl<'a> PdfWriter<'a> {
+    /// The page objects of the document, in order
+    pub fn pages(&self) -> PdfResult<Vec<Reference>> {
+        Ok(self
+            .pages_with_inherited()?
+            .into_iter()
+            .map(|(page, _)| page)
+            .collect())
+    }
+
+    /// Remove the page at `index`
+    ///
+    /// Link annotations which go to the page are removed, and all other
+    /// destinations which go to it, such as those of outline items and named
+    /// destinations, go instead to the page which takes its place, or the new
+    /// last page if it was the last.
+    pub fn remove_page(&mut self, index: usize) -> PdfResult<()> {
+        let old = self.pages()?;
+
+        let page = match old.get(index) {
+            Some(page) => *page,
+            None => anyhow::bail!("there is no page {} of {} pages", index, old.len()),
+        };
+
+        self.detach_page(page)?;
+        self.objects.remove(&page.object_number);
+
+        let new = self.pages()?;
+
+        self.remove_links_to(page, index, &new);
+
+        let replacement = new.get(index).or_else(|| new.last()).copied();
+
+        self.fix_destinations(&old, Some((page, replacement)))
+    }
+
+    /// Insert the page object `page`, which has been added to the writer but
+    /// is not yet part of the page tree, so that it is at `index`
+    ///
+    /// Pages are appended if `index` is the number of pages.
+    pub fn insert_page(&mut self, index: usize, page: Reference) -> PdfResult<()> {
+        let old = self.pages()?;
+
+        if old.contains(&page) {
+            anyhow::bail!("the page is already part of the page tree");
+        }
+
+        match self.get(page) {
+            Some(Object::Dictionary(dict))
+                if dict.peek("Type").map_or(false, |ty| ty.name_is("Page")) => {}
+            _ => anyhow::bail!("the page object does not exist"),
+        }
+
+        self.attach_page(page, index, &old)?;
+
+        self.fix_destinations(&old, None)
+    }
+
+    /// Move the page at `from` so that it is at `to`
+    ///
+    /// The attributes the page inherits are copied into it, as it may be moved
+    /// beneath a different node of the page tree.
+    pub fn move_page(&mut self, from: usize, to: usize) -> PdfResult<()> {
+        let old = self.pages_with_inherited()?;
+
+        let (page, inherited) = match old.get(from) {
+            Some(page) => page.clone(),
+            None => anyhow::bail!("there is no page {} of {} pages", from, old.len()),
+        };
+
+        if to >= old.len() {
+            anyhow::bail!("there is no page {} of {} pages", to, old.len());
+        }
+
+        if let Some((_, Object::Dictionary(dict))) = self.objects.get_mut(&page.object_number) {
+            for (key, value) in inherited.iter() {
+                if dict.peek(key).is_none() {
+                    dict.insert(key, value.clone());
+                }
+            }
+        }
+
+        self.detach_page(page)?;
+
+        let remaining = self.pages()?;
+        self.attach_page(page, to, &remaining)?;
+
+        let old = old.into_iter().map(|(page, _)| page).collect::<Vec<_>>();
+
+        self.fix_destinations(&old, None)
+    }
+
+    pub(super) fn pages_with_inherited(&self) -> PdfResult<Vec<(Reference, Dictionary<'a>)>> {
+        let mut pages = Vec::new();
+
+        collect_pages(
+            self,
+            self.page_tree_root()?,
+            &Dictionary::empty(),
+            &mut HashSet::new(),
+            &mut pages,
+        );
+
+        Ok(pages)
+    }
+
+    fn page_tree_root(&self) -> PdfResult<Reference> {
+        let catalog = match self.trailer.peek("Root") {
+            Some(Object::Reference(root)) => self.get(*root),
+            _ => None,
+        };
+
+        match catalog {
+            Some(Object::Dictionary(catalog)) => match catalog.peek("Pages") {
+                Some(Object::Reference(pages)) => Ok(*pages),
+                _ => anyhow::bail!("the document catalog has no page tree"),
+            },
+            _ => anyhow::bail!("the document catalog must be set"),
+        }
+    }
+
+    fn parent_of(&self, node: Reference) -> PdfResult<Reference> {
+        match self.get(node) {
+            Some(Object::Dictionary(node)) => match node.peek("Parent") {
+                Some(Object::Reference(parent)) => Ok(*parent),
+                _ => anyhow::bail!("the page has no parent"),
+            },
+            _ => anyhow::bail!("the page does not exist"),
+        }
+    }
+
+    /// The Kids array of the page tree node `node`, whether it is direct or
+    /// indirect
+    fn kids_mut(&mut self, node: Reference) -> Option<&mut Vec<Object<'a>>> {
+        let indirect = match self.get(node) {
+            Some(Object::Dictionary(node)) => match node.peek("Kids") {
+                Some(Object::Reference(kids)) => Some(*kids),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let kids = match indirect {
+            Some(kids) => &mut self.objects.get_mut(&kids.object_number)?.1,
+            None => match &mut self.objects.get_mut(&node.object_number)?.1 {
+                Object::Dictionary(node) => node.peek_mut("Kids")?,
+                _ => return None,
+            },
+        };
+
+        match kids {
+            Object::Array(kids) => Some(kids),
+            _ => None,
+        }
+    }
+
+    /// Add `delta` to the Count of `node` and each of its ancestors
+    fn adjust_counts(&mut self, node: Reference, delta: i32) {
+        let mut next = Some(node);
+        let mut visited = HashSet::new();
+
+        while let Some(node) = next.filter(|node| visited.insert(*node)) {
+            next = match self.objects.get_mut(&node.object_number) {
+                Some((_, Object::Dictionary(node))) => {
+                    let count = match node.peek("Count") {
+                        Some(Object::Integer(count)) => *count,
+                        _ => 0,
+                    };
+
+                    node.insert("Count", Object::Integer(count + delta));
+
+                    match node.peek("Parent") {
+                        Some(Object::Reference(parent)) => Some(*parent),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+        }
+    }
+
+    fn detach_page(&mut self, page: Reference) -> PdfResult<()> {
+        let parent = self.parent_of(page)?;
+
+        if let Some(kids) = self.kids_mut(parent) {
+            kids.retain(|kid| *kid != Object::Reference(page));
+        }
+
+        self.adjust_counts(parent, -1);
+
+        Ok(())
+    }
+
+    /// Add `page` to the page tree before the page at `index` of `pages`, or
+    /// after the last if `index` is the number of pages
+    fn attach_page(&mut self, page: Reference, index: usize, pages: &[Reference]) -> PdfResult<()> {
+        let (parent, sibling, after) = match (pages.get(index), pages.last()) {
+            (Some(next), _) => (self.parent_of(*next)?, Some(*next), false),
+            (None, Some(last)) if index == pages.len() => {
+                (self.parent_of(*last)?, Some(*last), true)
+            }
+            (None, None) if index == 0 => (self.page_tree_root()?, None, true),
+            _ => anyhow::bail!("there is no page {} of {} pages", index, pages.len()),
+        };
+
+        let kids = match self.kids_mut(parent) {
+            Some(kids) => kids,
+            None => anyhow::bail!("the page tree node has no kids"),
+        };
+
+        let position = match sibling {
+            Some(sibling) => match kids
+                .iter()
+                .position(|kid| *kid == Object::Reference(sibling))
+            {
+                Some(position) if after => position + 1,
+                Some(position) => position,
+                None => kids.len(),
+            },
+            None => kids.len(),
+        };
+
+        kids.insert(position, Object::Reference(page));
+
+        if let Some((_, Object::Dictionary(page))) = self.objects.get_mut(&page.object_number) {
+            page.insert("Parent", Object::Reference(parent));
+        }
+
+        self.adjust_counts(parent, 1);
+
+        Ok(())
+    }
+
+    /// Remove the link annotations of `pages` which go to the page `removed`,
+    /// which was at `index`
+    fn remove_links_to(&mut self, removed: Reference, index: usize, pages: &[Reference]) {
+        for &page in pages {
+            let annots = match self.get(page) {
+                Some(Object::Dictionary(page)) => match page
+                    .peek("Annots")
+                    .and_then(|annots| resolved(self, annots))
+                {
+                    Some(Object::Array(annots)) => annots,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+
+            if !annots
+                .iter()
+                .any(|annot| self.is_link_to(annot, removed, index))
+            {
+                continue;
+            }
+
+            let annots = annots
+                .iter()
+                .filter(|annot| !self.is_link_to(annot, removed, index))
+                .cloned()
+                .collect();
+
+            if let Some((_, Object::Dictionary(page))) = self.objects.get_mut(&page.object_number) {
+                page.insert("Annots", Object::Array(annots));
+            }
+        }
+    }
+
+    fn is_link_to(&self, annot: &Object<'a>, page: Reference, index: usize) -> bool {
+        let annot = match resolved(self, annot) {
+            Some(Object::Dictionary(annot))
+                if annot
+                    .peek("Subtype")
+                    .map_or(false, |subtype| subtype.name_is("Link")) =>
+            {
+                annot
+            }
+            _ => return false,
+        };
+
+        let dest = match annot.peek("Dest") {
+            Some(dest) => Some(dest),
+            None => match annot.peek("A").and_then(|action| resolved(self, action)) {
+                Some(Object::Dictionary(action))
+                    if action.peek("S").map_or(false, |s| s.name_is("GoTo")) =>
+                {
+                    action.peek("D")
+                }
+                _ => None,
+            },
+        };
+
+        match dest.and_then(|dest| resolved(self, dest)) {
+            Some(Object::Array(dest)) => match dest.first() {
+                Some(Object::Reference(reference)) => *reference == page,
+                Some(Object::Integer(idx)) => usize::try_from(*idx).ok() == Some(index),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Update explicit destinations after the order of pages has changed from
+    /// `old`, retargeting those which went to a removed page to its
+    /// replacement
+    fn fix_destinations(
+        &mut self,
+        old: &[Reference],
+        removed: Option<(Reference, Option<Reference>)>,
+    ) -> PdfResult<()> {
+        let new_indices = self
+            .pages()?
+            .into_iter()
+            .enumerate()
+            .map(|(idx, page)| (page, idx))
+            .collect::<HashMap<_, _>>();
+
+        let retarget = |page: Reference| match removed {
+            Some((removed, replacement)) if page == removed => replacement,
+            _ => Some(page),
+        };
+
+        let mut fix = |dest: &mut Vec<Object<'a>>| {
+            let fixed = match &dest[0] {
+                Object::Reference(page) => match retarget(*page) {
+                    Some(page) => Object::Reference(page),
+                    None => Object::Null,
+                },
+                Object::Integer(idx) => match usize::try_from(*idx)
+                    .ok()
+                    .and_then(|idx| old.get(idx))
+                    .and_then(|page| retarget(*page))
+                    .and_then(|page| new_indices.get(&page))
+                {
+                    Some(&idx) => Object::Integer(idx as i32),
+                    None => return,
+                },
+                _ => return,
+            };
+
+            dest[0] = fixed;
+        };
+
+        for (_, obj) in self.objects.values_mut() {
+            visit_destinations(obj, &mut fix);
+        }
+
+        Ok(())
+    }
+}
+
+/// Call `f` with each explicit destination within `obj`, other than those of
+/// actions which go to other documents
+fn visit_destinations<'a>(obj: &mut Object<'a>, f: &mut dyn FnMut(&mut Vec<Object<'a>>)) {
+    match obj {
+        Object::Array(arr) if is_explicit_destination(arr) => f(arr),
+        Object::Array(arr) => {
+            for obj in arr {
+                visit_destinations(obj, f);
+            }
+        }
+        Object::Dictionary(dict) => {
+            let is_remote = dict
+                .peek("S")
+                .map_or(false, |s| s.name_is("GoToR") || s.name_is("GoToE"));
+
+            // the page of a remote destination is within the other document
+            let remote_dest = if is_remote { dict.remove("D") } else { None };
+
+            for obj in dict.values_mut() {
+                visit_destinations(obj, f);
+            }
+
+            if let Some(dest) = remote_dest {
+                dict.insert("D", dest);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_explicit_destination(arr: &[Object]) -> bool {
+    match arr {
+        [Object::Reference(..) | Object::Integer(..), Object::Name(fit), ..] => {
+            FIT_TYPES.contains(&fit.as_str())
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        content::ContentToken,
+        version::PdfVersion,
+        writer::{dictionary, DocumentBuilder, PageBuilder},
+        ParseOptions, Parser,
+    };
+
+    fn dict<'b, 'a>(writer: &'b PdfWriter<'a>, reference: Reference) -> &'b Dictionary<'a> {
+        match writer.get(reference) {
+            Some(Object::Dictionary(dict)) => dict,
+            obj => panic!("expected a dictionary, found {:?}", obj),
+        }
+    }
+
+    #[test]
+    fn remove_and_move_pages() {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_4);
+
+        for text in ["first", "second", "third"] {
+            let mut page = PageBuilder::new(612.0, 792.0);
+            page.contents(format!("BT ({}) Tj ET", text));
+
+            builder.add_page(page).unwrap();
+        }
+
+        let mut writer = builder.build().unwrap();
+        let pages = writer.pages().unwrap();
+
+        // a link from the first page to the second, and destinations of the
+        // third page by reference and by index
+        writer.insert(
+            pages[0],
+            Object::Dictionary({
+                let mut page = dict(&writer, pages[0]).clone();
+                page.insert(
+                    "Annots",
+                    Object::Array(vec![Object::Dictionary(dictionary([
+                        ("Subtype", Object::Name("Link".to_owned())),
+                        (
+                            "Dest",
+                            Object::Array(vec![
+                                Object::Reference(pages[1]),
+                                Object::Name("Fit".to_owned()),
+                            ]),
+                        ),
+                    ]))]),
+                );
+                page
+            }),
+        );
+
+        let by_reference = writer.add(Object::Array(vec![
+            Object::Reference(pages[2]),
+            Object::Name("Fit".to_owned()),
+        ]));
+        let by_index = writer.add(Object::Array(vec![
+            Object::Integer(2),
+            Object::Name("Fit".to_owned()),
+        ]));
+
+        writer.move_page(2, 0).unwrap();
+        assert_eq!(writer.pages().unwrap(), vec![pages[2], pages[0], pages[1]]);
+
+        writer.remove_page(2).unwrap();
+        assert_eq!(writer.pages().unwrap(), vec![pages[2], pages[0]]);
+
+        assert_eq!(
+            writer.get(by_reference).unwrap(),
+            &Object::Array(vec![
+                Object::Reference(pages[2]),
+                Object::Name("Fit".to_owned())
+            ])
+        );
+        assert_eq!(
+            writer.get(by_index).unwrap(),
+            &Object::Array(vec![Object::Integer(0), Object::Name("Fit".to_owned())])
+        );
+        assert_eq!(
+            dict(&writer, pages[0]).peek("Annots"),
+            Some(&Object::Array(Vec::new()))
+        );
+
+        let root = writer.page_tree_root().unwrap();
+        assert_eq!(dict(&writer, root).peek("Count"), Some(&Object::Integer(2)));
+
+        let mut parser =
+            Parser::from_bytes(writer.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        let mut shown = Vec::new();
+
+        for page in &parser.pages() {
+            for token in parser.page_contents(page).unwrap() {
+                if let ContentToken::Object(Object::String(s)) = token.unwrap() {
+                    shown.push(s);
+                }
+            }
+        }
+
+        assert_eq!(shown, vec!["third", "first"]);
+    }
+}
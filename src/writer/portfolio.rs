@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{
+    collection::{CollectionFieldType, CollectionValue, PortfolioMember},
+    error::PdfResult,
+    objects::{encode_text_string, Dictionary, Object, Reference, ToObj},
+    stream::Stream,
+    trailer::Trailer,
+    Lexer, Resolve,
+};
+
+use super::{dictionary, IncrementalUpdate};
+
+/// Append an incremental update to the file of `lexer` which embeds the file
+/// of `member`, with the values of its collection item
+///
+/// A member of the same name is replaced. The document is made a portfolio
+/// if it has no collection dictionary, and fields of the collection item which
+/// the schema lacks are added to it, so that the values are shown.
+pub(crate) fn add_portfolio_member<'a>(
+    trailer: &Trailer<'a>,
+    member: &PortfolioMember,
+    lexer: &mut Lexer<'a>,
+) -> PdfResult<Vec<u8>> {
+    // the strings of the file specification would have to be encrypted
+    anyhow::ensure!(
+        trailer.encryption.is_none(),
+        "cannot add to the portfolio of an encrypted document"
+    );
+
+    let mut update = IncrementalUpdate::new(trailer.size);
+    let mut catalog = lexer.assert_dict(Object::Reference(trailer.root))?;
+
+    let file_specification = file_specification(member, &mut update)?;
+    let file_specification = update.add(Object::Dictionary(file_specification));
+
+    let (names_reference, mut names) = indirect_dictionary(catalog.peek("Names"), lexer)?;
+    let (tree_reference, tree) = indirect_dictionary(names.peek("EmbeddedFiles"), lexer)?;
+
+    let mut entries = BTreeMap::new();
+    name_tree_entries(
+        Object::Dictionary(tree),
+        lexer,
+        &mut HashSet::new(),
+        &mut entries,
+    )?;
+    entries.insert(
+        encode_text_string(&member.attachment.name),
+        Object::Reference(file_specification),
+    );
+
+    let tree = dictionary([(
+        "Names",
+        Object::Array(
+            entries
+                .into_iter()
+                .flat_map(|(name, obj)| [Object::String(name), obj])
+                .collect(),
+        ),
+    )]);
+
+    names.insert("EmbeddedFiles", store(&mut update, tree_reference, tree));
+    catalog.insert("Names", store(&mut update, names_reference, names));
+
+    let (collection_reference, mut collection) =
+        indirect_dictionary(catalog.peek("Collection"), lexer)?;
+    let (schema_reference, mut schema) = indirect_dictionary(collection.peek("Schema"), lexer)?;
+
+    collection.insert("Type", Object::Name("Collection".to_owned()));
+
+    for (key, value) in &member.fields {
+        if schema.peek(key).is_some() {
+            continue;
+        }
+
+        let field_type = match value.value {
+            CollectionValue::Text(..) => CollectionFieldType::Text,
+            CollectionValue::Date(..) => CollectionFieldType::Date,
+            CollectionValue::Number(..) => CollectionFieldType::Number,
+        };
+
+        schema.insert(
+            key.clone(),
+            Object::Dictionary(dictionary([
+                ("Type", Object::Name("CollectionField".to_owned())),
+                ("Subtype", Object::Name(field_type.as_str().to_owned())),
+                ("N", Object::String(encode_text_string(key))),
+            ])),
+        );
+    }
+
+    if !schema.is_empty() {
+        collection.insert("Schema", store(&mut update, schema_reference, schema));
+    }
+
+    catalog.insert(
+        "Collection",
+        store(&mut update, collection_reference, collection),
+    );
+
+    update.insert(trailer.root, Object::Dictionary(catalog));
+
+    update.write(&lexer.file, IncrementalUpdate::trailer(trailer)?)
+}
+
+/// The file specification of `member`, whose embedded file stream is added to
+/// `update`
+fn file_specification<'a>(
+    member: &PortfolioMember,
+    update: &mut IncrementalUpdate<'a>,
+) -> PdfResult<Dictionary<'a>> {
+    let attachment = &member.attachment;
+
+    let checksum = md5::compute(&attachment.data);
+
+    let mut params = dictionary([
+        (
+            "Size",
+            Object::Integer(i32::try_from(attachment.data.len())?),
+        ),
+        (
+            "CheckSum",
+            Object::String(checksum.0.iter().map(|&b| b as char).collect()),
+        ),
+    ]);
+
+    if let Some(creation_date) = &attachment.creation_date {
+        params.insert("CreationDate", creation_date.to_obj()?);
+    }
+
+    if let Some(modification_date) = &attachment.modification_date {
+        params.insert("ModDate", modification_date.to_obj()?);
+    }
+
+    let mut stream_dict = dictionary([
+        ("Type", Object::Name("EmbeddedFile".to_owned())),
+        ("Params", Object::Dictionary(params)),
+    ]);
+
+    if let Some(mime_type) = &attachment.mime_type {
+        stream_dict.insert("Subtype", Object::Name(mime_type.clone()));
+    }
+
+    let stream = update.add(Object::Stream(Stream::new(
+        attachment.data.clone(),
+        stream_dict,
+    )));
+
+    let file_name = attachment.file_name.as_ref().unwrap_or(&attachment.name);
+
+    let mut spec = dictionary([
+        ("Type", Object::Name("Filespec".to_owned())),
+        ("F", Object::String(encode_text_string(file_name))),
+        ("UF", Object::String(encode_text_string(file_name))),
+        (
+            "EF",
+            Object::Dictionary(dictionary([
+                ("F", Object::Reference(stream)),
+                ("UF", Object::Reference(stream)),
+            ])),
+        ),
+    ]);
+
+    if let Some(description) = &attachment.description {
+        spec.insert("Desc", Object::String(encode_text_string(description)));
+    }
+
+    let mut item = dictionary([("Type", Object::Name("CollectionItem".to_owned()))]);
+
+    for (key, value) in &member.fields {
+        let data = match &value.value {
+            CollectionValue::Text(text) => Object::String(encode_text_string(text)),
+            CollectionValue::Date(date) => date.to_obj()?,
+            CollectionValue::Number(n) if n.fract() == 0.0 => Object::Integer(*n as i32),
+            CollectionValue::Number(n) => Object::Real(*n),
+        };
+
+        let obj = match &value.prefix {
+            Some(prefix) => Object::Dictionary(dictionary([
+                ("Type", Object::Name("CollectionSubitem".to_owned())),
+                ("D", data),
+                ("P", Object::String(encode_text_string(prefix))),
+            ])),
+            None => data,
+        };
+
+        item.insert(key.clone(), obj);
+    }
+
+    spec.insert("CI", Object::Dictionary(item));
+
+    Ok(spec)
+}
+
+/// The dictionary `obj`, with its reference if it is indirect, or an empty
+/// dictionary if there is none
+fn indirect_dictionary<'a>(
+    obj: Option<&Object<'a>>,
+    resolver: &mut dyn Resolve<'a>,
+) -> PdfResult<(Option<Reference>, Dictionary<'a>)> {
+    Ok(match obj {
+        Some(&Object::Reference(reference)) => (
+            Some(reference),
+            resolver.assert_dict(Object::Reference(reference))?,
+        ),
+        Some(obj) => (None, resolver.assert_dict(obj.clone())?),
+        None => (None, Dictionary::empty()),
+    })
+}
+
+/// Replace the indirect dictionary `reference` with `dict`, returning the value
+/// with which its parent refers to it
+fn store<'a>(
+    update: &mut IncrementalUpdate<'a>,
+    reference: Option<Reference>,
+    dict: Dictionary<'a>,
+) -> Object<'a> {
+    match reference {
+        Some(reference) => {
+            update.insert(reference, Object::Dictionary(dict));
+            Object::Reference(reference)
+        }
+        None => Object::Dictionary(dict),
+    }
+}
+
+/// The leaves of the name tree `node`, keeping indirect values as references
+fn name_tree_entries<'a>(
+    node: Object<'a>,
+    resolver: &mut dyn Resolve<'a>,
+    visited: &mut HashSet<Reference>,
+    entries: &mut BTreeMap<String, Object<'a>>,
+) -> PdfResult<()> {
+    if let Object::Reference(reference) = node {
+        if !visited.insert(reference) {
+            return Ok(());
+        }
+    }
+
+    let mut node = resolver.assert_dict(node)?;
+
+    if let Some(names) = node.get_arr("Names", resolver)? {
+        for pair in names.chunks_exact(2) {
+            let name = resolver.assert_string(pair[0].clone())?;
+            entries.insert(name, pair[1].clone());
+        }
+    }
+
+    if let Some(kids) = node.get_arr("Kids", resolver)? {
+        for kid in kids {
+            name_tree_entries(kid, resolver, visited, entries)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use std::rc::Rc;
+use std::{borrow::Cow, collections::HashSet};
+
+use crate::{
+    annotation::Annotation,
+    data_structures::Rectangle,
+    error::PdfResult,
+    objects::Reference,
+    objects::{Dictionary, Object},
+    render::Renderer,
+    writer,
+    writer::ContentBuilder,
+    FromObj, Parser, Resolve,
+};
+
+use super::{
+    dictionary,
+    extract::{insert_content_names, used_resources},
+    flate_stream,
+    merge::{prune, resolved, set_entry},
+    PdfWriter,
+};
+
+/// The content and annotations of a page once its redactions are applied
+#[derive(Debug)]
+pub(crate) struct RedactedPage {
+    pub index: usize,
+
+    /// The page content, with the content within the redacted regions removed
+    pub content: Vec<u8>,
+
+    /// The content painted over the redacted regions, such as their interior
+    /// colour and overlay text
+    pub overlay: Vec<u8>,
+
+    /// The name of the font resource with which the overlay shows text, which
+    /// is added to the page's resources as Helvetica
+    pub font: Option<String>,
+
+    /// The positions within the page's Annots array of the annotations to
+    /// remove, such as the redaction annotations themselves
+    pub removed_annotations: Vec<usize>,
+}
+
+/// Rewrite each of `pages` of a compacted document with its redactions
+/// applied, then drop the objects which are no longer used, so that the
+/// removed content does not remain in the file
+///
+/// Each page's resources are reduced to those its new content names, so that
+/// images and forms which are no longer painted are dropped as well.
+pub(crate) fn redact<'a>(
+    mut document: PdfWriter<'a>,
+    pages: Vec<RedactedPage>,
+) -> PdfResult<PdfWriter<'a>> {
+    for page in pages {
+        redact_page(&mut document, page)?;
+    }
+
+    prune(document)
+}
+
+fn redact_page<'a>(document: &mut PdfWriter<'a>, redacted: RedactedPage) -> PdfResult<()> {
+    let (page, entries) = document.page_entries(redacted.index)?;
+
+    // the remaining content is painted in a graphics state of its own, so that
+    // the overlay is painted in the default one
+    let mut content = b"q\n".to_vec();
+    content.extend_from_slice(&redacted.content);
+    content.extend_from_slice(b"\nQ\n");
+    content.extend_from_slice(&redacted.overlay);
+
+    let mut resources = match entries
+        .peek("Resources")
+        .and_then(|resources| resolved(document, resources))
+    {
+        Some(Object::Dictionary(resources)) => resources.clone(),
+        _ => Dictionary::empty(),
+    };
+
+    let mut names = HashSet::new();
+
+    if insert_content_names(Cow::Borrowed(&content), &mut names) {
+        resources = used_resources(document, &resources, &names);
+    }
+
+    if let Some(font) = redacted.font {
+        let mut fonts = match resources
+            .peek("Font")
+            .and_then(|fonts| resolved(document, fonts))
+        {
+            Some(Object::Dictionary(fonts)) => fonts.clone(),
+            _ => Dictionary::empty(),
+        };
+
+        fonts.insert(
+            font,
+            Object::Dictionary(dictionary([
+                ("Type", Object::Name("Font".to_owned())),
+                ("Subtype", Object::Name("Type1".to_owned())),
+                ("BaseFont", Object::Name("Helvetica".to_owned())),
+                ("Encoding", Object::Name("WinAnsiEncoding".to_owned())),
+            ])),
+        );
+
+        resources.insert("Font", Object::Dictionary(fonts));
+    }
+
+    let contents = document.add(Object::Stream(flate_stream(&content, Dictionary::empty())?));
+
+    set_entry(document, page, "Contents", Object::Reference(contents));
+    set_entry(document, page, "Resources", Object::Dictionary(resources));
+
+    if redacted.removed_annotations.is_empty() {
+        return Ok(());
+    }
+
+    let annots = match entries
+        .peek("Annots")
+        .and_then(|annots| resolved(document, annots))
+    {
+        Some(Object::Array(annots)) => annots.clone(),
+        _ => Vec::new(),
+    };
+
+    let mut kept = Vec::new();
+
+    for (idx, annot) in annots.into_iter().enumerate() {
+        if !redacted.removed_annotations.contains(&idx) {
+            kept.push(annot);
+            continue;
+        }
+
+        // the annotation is deleted rather than only unlinked from the page,
+        // as it may still be referenced, such as from the structure tree
+        if let Object::Reference(reference) = annot {
+            document.objects.remove(&reference.object_number);
+        }
+    }
+
+    set_entry(document, page, "Annots", Object::Array(kept));
+
+    Ok(())
+}
+
+impl<'a> Parser<'a> {
+    /// A writer holding the document with its redaction annotations applied
+    ///
+    /// The content beneath each redaction annotation is removed from the page
+    /// content, not merely covered: glyphs are removed from the text shown,
+    /// paths are discarded, and images, shadings and form XObjects which paint
+    /// within a redacted region are removed whole. The overlay of each
+    /// redaction annotation is then painted in its place.
+    ///
+    /// The redaction annotations are removed, along with the other annotations
+    /// over the redacted regions and their pop-ups, but not form fields. The
+    /// document is compacted as by [`Parser::compacted`], so that the removed
+    /// content does not remain in the file.
+    pub fn apply_redactions(&mut self) -> PdfResult<PdfWriter<'a>> {
+        let document = self.compacted()?;
+        let mut redacted_pages = Vec::new();
+
+        for (index, page) in self.pages().iter().enumerate() {
+            let annotations = page
+                .annots
+                .iter()
+                .flatten()
+                .map(|&annot| {
+                    let obj = self.lexer.lex_object_from_reference(annot)?;
+
+                    Ok((annot, Annotation::from_obj(obj, &mut self.lexer)?))
+                })
+                .collect::<PdfResult<Vec<_>>>()?;
+
+            let regions = annotations
+                .iter()
+                .filter_map(|(_, annotation)| {
+                    Some(annotation.redaction()?.regions(annotation.rect()))
+                })
+                .flatten()
+                .collect::<Vec<Rectangle>>();
+
+            if regions.is_empty() {
+                continue;
+            }
+
+            let content = match &page.contents {
+                Some(..) => {
+                    let mut content = self.page_contents(page)?;
+
+                    Renderer::new(&mut content, &mut self.lexer, Rc::clone(page))
+                        .redact(&regions)?
+                }
+                None => Vec::new(),
+            };
+
+            let mut removed = annotations
+                .iter()
+                .filter(|(_, annotation)| {
+                    annotation.redaction().is_some()
+                        || (!annotation.is_widget()
+                            && regions
+                                .iter()
+                                .any(|region| region.intersects(&annotation.rect())))
+                })
+                .map(|&(reference, _)| reference)
+                .collect::<HashSet<Reference>>();
+
+            // pop-ups display the text of the annotations they belong to
+            for (reference, annotation) in &annotations {
+                if matches!(annotation.popup_parent(), Some(parent) if removed.contains(&parent)) {
+                    removed.insert(*reference);
+                }
+            }
+
+            // the overlay text is shown with a font resource of its own
+            let resources = page.resources();
+            let fonts = resources
+                .as_ref()
+                .and_then(|resources| resources.font.as_ref());
+
+            let font = (0..)
+                .map(|n| format!("Redact{}", n))
+                .find(|name| !fonts.map_or(false, |fonts| fonts.contains_key(name)))
+                .unwrap();
+
+            let mut overlay = ContentBuilder::new();
+            let mut uses_font = false;
+
+            for (_, annotation) in &annotations {
+                if let Some(redact) = annotation.redaction() {
+                    let regions = redact.regions(annotation.rect());
+
+                    uses_font |= redact.draw_overlay(&regions, &mut overlay, &font);
+                }
+            }
+
+            redacted_pages.push(RedactedPage {
+                index,
+                content,
+                overlay: overlay.finish(),
+                font: uses_font.then_some(font),
+                removed_annotations: annotations
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (reference, _))| removed.contains(reference))
+                    .map(|(idx, _)| idx)
+                    .collect(),
+            });
+        }
+
+        writer::redact(document, redacted_pages)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        objects::ToObj,
+        version::PdfVersion,
+        writer::{DocumentBuilder, PageBuilder},
+        ParseOptions, Parser,
+    };
+
+    #[test]
+    fn apply_redaction_annotation() {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+
+        let redact = builder.add_object(Object::Dictionary(dictionary([
+            ("Type", Object::Name("Annot".to_owned())),
+            ("Subtype", Object::Name("Redact".to_owned())),
+            ("Rect", [0.0, 0.0, 100.0, 100.0].to_obj().unwrap()),
+            ("IC", [0.0].to_obj().unwrap()),
+        ])));
+
+        let mut page = PageBuilder::new(612.0, 792.0);
+        page.contents("0 0 1 rg 10 10 50 50 re f 300 300 50 50 re f");
+
+        let page = builder.add_page(page).unwrap();
+        let mut document = builder.build().unwrap();
+
+        set_entry(
+            &mut document,
+            page,
+            "Annots",
+            Object::Array(vec![Object::Reference(redact)]),
+        );
+
+        let mut parser =
+            Parser::from_bytes(document.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        let redacted = parser.apply_redactions().unwrap();
+
+        let mut parser =
+            Parser::from_bytes(redacted.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        let pages = parser.pages();
+        assert!(pages[0].annots.iter().flatten().next().is_none());
+
+        let content = parser.page_contents(&pages[0]).unwrap();
+
+        assert_eq!(
+            String::from_utf8_lossy(&content.buffer),
+            "q\n0 0 1 rg 10 10 50 50 re n 300 300 50 50 re f\nQ\nq\n0 g\n0 0 100 100 re\nf\nQ\n"
+        );
+    }
+}
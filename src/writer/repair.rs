@@ -0,0 +1,413 @@
+use std::{collections::HashSet, marker::PhantomData};
+
+use crate::{
+    error::PdfResult,
+    file_specification::FileIdentifier,
+    filter::decode_stream,
+    object_stream::{ObjectStreamDict, ObjectStreamParser},
+    objects::{Dictionary, Object, Reference, TypedReference},
+    stream::Stream,
+    tokenizer::{Spanned, Tokenizer},
+    trailer::Trailer,
+    version::PdfVersion,
+    FromObj,
+};
+
+use super::{compact, dictionary, merge::INHERITABLE, PdfWriter};
+
+/// Rebuild the document held in `file` from the indirect objects found by
+/// scanning it, ignoring its cross-reference table
+///
+/// Where an object is defined more than once, as by incremental updates, the
+/// last definition is kept. Objects within object streams are recovered, and
+/// the data of streams whose Length is wrong is found from their `endstream`
+/// keyword. The Kids, Count and Parent of each page tree node are made
+/// consistent with the pages beneath it, and if the catalog or its page tree
+/// cannot be found, the pages of the file are gathered into a new one. The
+/// document is then compacted, as by [`compact`].
+pub(crate) fn repair(file: &[u8]) -> PdfResult<PdfWriter<'_>> {
+    let header_version = PdfVersion::from_header(file).unwrap_or(PdfVersion::V1_7);
+
+    let mut recovered = PdfWriter::new(header_version);
+
+    // the dictionaries of trailers and cross-reference streams, in the order
+    // in which they appear
+    let mut trailers = Vec::new();
+
+    let mut tokenizer = Tokenizer::new(file);
+
+    while let Some(object) = tokenizer.next_indirect_object()? {
+        if let Object::Stream(stream) = &object.object {
+            if has_type(&stream.dict.other, "XRef") {
+                trailers.push((object.span.start, stream.dict.other.clone()));
+            }
+
+            // object streams which cannot be decoded are skipped, as are
+            // other damaged objects
+            if has_type(&stream.dict.other, "ObjStm") {
+                if let Ok(objects) = unpack_object_stream(stream, &mut recovered) {
+                    for (reference, obj) in objects {
+                        recovered.insert(reference, obj);
+                    }
+                }
+            }
+        }
+
+        recovered.insert(object.reference, object.object);
+    }
+
+    let mut offset = 0;
+
+    while let Some(idx) = file[offset..]
+        .windows(b"trailer".len())
+        .position(|window| window == b"trailer")
+    {
+        let start = offset + idx;
+        offset = start + b"trailer".len();
+
+        tokenizer.seek(offset);
+
+        if let Ok(Some(Spanned {
+            value: Object::Dictionary(dict),
+            ..
+        })) = tokenizer.next_object()
+        {
+            trailers.push((start, dict));
+        }
+    }
+
+    trailers.sort_by_key(|(offset, _)| *offset);
+
+    if trailers
+        .iter()
+        .any(|(_, trailer)| trailer.peek("Encrypt").is_some())
+    {
+        anyhow::bail!("cannot repair an encrypted document");
+    }
+
+    // the latest trailer whose catalog survives, or else the latest catalog
+    let root = trailers
+        .iter()
+        .rev()
+        .find_map(|(_, trailer)| match trailer.peek("Root") {
+            Some(Object::Reference(root)) if is_dictionary(&recovered, *root, Some("Catalog")) => {
+                Some(*root)
+            }
+            _ => None,
+        })
+        .or_else(|| references_of_type(&recovered, "Catalog").pop());
+
+    let root = match root {
+        Some(root) => root,
+        None => recovered.add(Object::Dictionary(dictionary([(
+            "Type",
+            Object::Name("Catalog".to_owned()),
+        )]))),
+    };
+
+    reconcile_page_tree(&mut recovered, root)?;
+
+    let info = trailers
+        .iter()
+        .rev()
+        .find_map(|(_, trailer)| match trailer.peek("Info") {
+            Some(Object::Reference(info)) if is_dictionary(&recovered, *info, None) => Some(*info),
+            Some(info @ Object::Dictionary(..)) => Some(recovered.add(info.clone())),
+            _ => None,
+        });
+
+    let id = trailers
+        .iter()
+        .rev()
+        .find_map(|(_, trailer)| trailer.peek("ID").cloned())
+        .and_then(|id| FileIdentifier::from_obj(id, &mut recovered).ok());
+
+    let catalog_version = match recovered.get(root) {
+        Some(Object::Dictionary(catalog)) => match catalog.peek("Version") {
+            Some(Object::Name(version)) => PdfVersion::from_str(version).ok(),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let trailer = Trailer {
+        size: recovered.next_object_number(),
+        prev: None,
+        root,
+        encryption: None,
+        id,
+        info: info.map(|reference| TypedReference::Indirect {
+            reference,
+            _t: PhantomData,
+        }),
+        xref_stream: None,
+        doc_checksum: None,
+    };
+
+    compact(
+        &mut recovered,
+        &trailer,
+        PdfVersion::reconcile(header_version, catalog_version),
+    )
+}
+
+/// The objects stored in the object stream `stream`
+fn unpack_object_stream<'a>(
+    stream: &Stream<'a>,
+    resolver: &mut PdfWriter<'a>,
+) -> PdfResult<Vec<(Reference, Object<'a>)>> {
+    let (n, first) = match (stream.dict.other.peek("N"), stream.dict.other.peek("First")) {
+        (Some(&Object::Integer(n)), Some(&Object::Integer(first))) => {
+            (usize::try_from(n)?, usize::try_from(first)?)
+        }
+        _ => anyhow::bail!("object stream has no N or First"),
+    };
+
+    let decoded = decode_stream(&stream.stream, &stream.dict, resolver)?;
+
+    anyhow::ensure!(
+        first <= decoded.len(),
+        "object stream is shorter than its First"
+    );
+
+    let mut parser = ObjectStreamParser::new(
+        decoded.into_owned(),
+        ObjectStreamDict {
+            stream_dict: stream.dict.clone(),
+            n,
+            first,
+            extends: None,
+        },
+    )?;
+
+    parser
+        .object_numbers()
+        .into_iter()
+        .map(|object_number| {
+            let reference = Reference {
+                object_number,
+                generation: 0,
+            };
+
+            Ok((reference, parser.parse_object(reference)?))
+        })
+        .collect()
+}
+
+/// Make the page tree of the catalog `root` consistent, or give the catalog a
+/// new page tree holding every page of the document if it has none
+fn reconcile_page_tree(writer: &mut PdfWriter, root: Reference) -> PdfResult<()> {
+    let mut catalog = match writer.get(root) {
+        Some(Object::Dictionary(catalog)) => catalog.clone(),
+        _ => anyhow::bail!("the document catalog does not exist"),
+    };
+
+    let count = match catalog.peek("Pages") {
+        Some(&Object::Reference(pages)) => {
+            reconcile_node(writer, pages, None, &mut HashSet::new()).map(|count| (pages, count))
+        }
+        _ => None,
+    };
+
+    if let Some((_, 1..)) = count {
+        return Ok(());
+    }
+
+    let pages = references_of_type(writer, "Page");
+
+    anyhow::ensure!(!pages.is_empty(), "no pages were found");
+
+    let node = match count {
+        Some((node, _)) => node,
+        None => writer.add(Object::Null),
+    };
+
+    for &page in &pages {
+        let inherited = inherited_attributes(writer, page);
+
+        if let Some((_, Object::Dictionary(page))) = writer.objects.get_mut(&page.object_number) {
+            for (key, value) in inherited.entries() {
+                if page.peek(&key).is_none() {
+                    page.insert(key, value);
+                }
+            }
+
+            page.insert("Parent", Object::Reference(node));
+        }
+    }
+
+    writer.insert(
+        node,
+        Object::Dictionary(dictionary([
+            ("Type", Object::Name("Pages".to_owned())),
+            ("Count", Object::Integer(i32::try_from(pages.len())?)),
+            (
+                "Kids",
+                Object::Array(pages.into_iter().map(Object::Reference).collect()),
+            ),
+        ])),
+    );
+
+    catalog.insert("Pages", Object::Reference(node));
+    writer.insert(root, Object::Dictionary(catalog));
+
+    Ok(())
+}
+
+/// Set the Count of the page tree node `node`, and of those beneath it, to
+/// the number of pages beneath it, and the Parent of each to `parent`,
+/// returning the number of pages
+///
+/// Kids which do not exist, or have already been visited, are removed. The
+/// result is `None` if `node` is neither a page nor a page tree node.
+fn reconcile_node(
+    writer: &mut PdfWriter,
+    node: Reference,
+    parent: Option<Reference>,
+    visited: &mut HashSet<Reference>,
+) -> Option<usize> {
+    if !visited.insert(node) {
+        return None;
+    }
+
+    let mut dict = match writer.get(node) {
+        Some(Object::Dictionary(dict)) => dict.clone(),
+        _ => return None,
+    };
+
+    let kids = match dict.peek("Kids") {
+        Some(Object::Array(kids)) => Some(kids.clone()),
+        Some(&Object::Reference(kids)) => match writer.get(kids) {
+            Some(Object::Array(kids)) => Some(kids.clone()),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let count = match kids {
+        _ if has_type(&dict, "Page") => {
+            dict.remove("Kids");
+            1
+        }
+        None if dict.peek("Type").is_none() && dict.peek("Contents").is_some() => {
+            dict.insert("Type", Object::Name("Page".to_owned()));
+            1
+        }
+        Some(kids) => {
+            let mut count = 0;
+            let mut kept = Vec::new();
+
+            for kid in kids {
+                if let Object::Reference(kid) = kid {
+                    if let Some(n) = reconcile_node(writer, kid, Some(node), visited) {
+                        count += n;
+                        kept.push(Object::Reference(kid));
+                    }
+                }
+            }
+
+            dict.insert("Type", Object::Name("Pages".to_owned()));
+            dict.insert("Kids", Object::Array(kept));
+            dict.insert("Count", Object::Integer(i32::try_from(count).ok()?));
+
+            count
+        }
+        None => return None,
+    };
+
+    match parent {
+        Some(parent) => dict.insert("Parent", Object::Reference(parent)),
+        None => dict.remove("Parent"),
+    };
+
+    writer.insert(node, Object::Dictionary(dict));
+
+    Some(count)
+}
+
+/// The attributes `page` inherits from the nodes above it, following its
+/// Parent entries as far as they lead
+fn inherited_attributes<'a>(writer: &PdfWriter<'a>, page: Reference) -> Dictionary<'a> {
+    let mut inherited = Dictionary::empty();
+    let mut visited = HashSet::new();
+    let mut next = Some(page);
+
+    while let Some(node) = next.filter(|node| visited.insert(*node)) {
+        next = match writer.get(node) {
+            Some(Object::Dictionary(dict)) => {
+                for key in INHERITABLE {
+                    if let (None, Some(value)) = (inherited.peek(key), dict.peek(key)) {
+                        inherited.insert(key, value.clone());
+                    }
+                }
+
+                match dict.peek("Parent") {
+                    Some(Object::Reference(parent)) => Some(*parent),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+    }
+
+    inherited
+}
+
+/// The references of the dictionaries whose Type is `ty`, in order of object
+/// number
+fn references_of_type(writer: &PdfWriter, ty: &str) -> Vec<Reference> {
+    writer
+        .objects
+        .iter()
+        .filter(|(_, (_, obj))| match obj {
+            Object::Dictionary(dict) => has_type(dict, ty),
+            _ => false,
+        })
+        .map(|(&object_number, &(generation, _))| Reference {
+            object_number,
+            generation,
+        })
+        .collect()
+}
+
+/// Whether `reference` is a dictionary, of the type `ty` if one is given
+fn is_dictionary(writer: &PdfWriter, reference: Reference, ty: Option<&str>) -> bool {
+    match writer.get(reference) {
+        Some(Object::Dictionary(dict)) => ty.map_or(true, |ty| has_type(dict, ty)),
+        _ => false,
+    }
+}
+
+fn has_type(dict: &Dictionary, ty: &str) -> bool {
+    dict.peek("Type").map_or(false, |obj| obj.name_is(ty))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ParseOptions, Parser};
+
+    #[test]
+    fn repair_broken_file() {
+        // the cross-reference table is missing, the stream Length is too short,
+        // the Count is wrong, and a kid of the page tree does not exist
+        let file = b"%PDF-1.4\n\
+            1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n\
+            2 0 obj\n<< /Type /Pages /Kids [3 0 R 9 0 R] /Count 5 \
+            /MediaBox [0 0 612 792] >>\nendobj\n\
+            3 0 obj\n<< /Type /Page /Parent 2 0 R /Contents 4 0 R >>\nendobj\n\
+            4 0 obj\n<< /Length 2 >>\nstream\nBT (Repaired) Tj ET\nendstream\nendobj\n\
+            trailer\n<< /Size 5 /Root 1 0 R >>\n%%EOF\n";
+
+        let repaired = repair(file).unwrap().to_bytes().unwrap();
+
+        let mut parser = Parser::from_bytes(repaired, ParseOptions::default()).unwrap();
+        let pages = parser.pages();
+
+        assert_eq!(pages.len(), 1);
+
+        let content = parser.page_contents(&pages[0]).unwrap();
+
+        assert_eq!(&*content.buffer, b"BT (Repaired) Tj ET");
+    }
+}
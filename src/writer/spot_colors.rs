@@ -0,0 +1,490 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{
+    color::{ColorSpace, SpotColorConversion},
+    content::ContentLexer,
+    error::PdfResult,
+    filter::decode_stream,
+    objects::{Dictionary, Object, Reference},
+    stream::Stream,
+    FromObj,
+};
+
+use super::{
+    content::{decoded_content, write_content},
+    flate_stream,
+    merge::{prune, resolved, set_entry},
+    PdfWriter,
+};
+
+/// Rewrite the content of each page of a compacted document so that colours
+/// set in the spot colour spaces converted by `conversion` are set in
+/// DeviceCMYK instead, then drop the content streams which are replaced
+///
+/// The form XObjects and tiling patterns used by the pages are rewritten in
+/// the same way, and images are converted to DeviceCMYK samples. Shadings in
+/// converted colour spaces cannot be represented in process colour without
+/// resampling them, so documents which use them are refused.
+pub(crate) fn convert_spot_colors<'a>(
+    mut document: PdfWriter<'a>,
+    conversion: &SpotColorConversion,
+) -> PdfResult<PdfWriter<'a>> {
+    let mut converted = HashSet::new();
+
+    for (page, inherited) in document.pages_with_inherited()? {
+        let entries = match document.get(page) {
+            Some(Object::Dictionary(page)) => page.clone(),
+            _ => continue,
+        };
+
+        let resources = match entries
+            .peek("Resources")
+            .or_else(|| inherited.peek("Resources"))
+            .and_then(|resources| resolved(&document, resources))
+        {
+            Some(Object::Dictionary(resources)) => resources.clone(),
+            _ => continue,
+        };
+
+        convert_resources(&mut document, &resources, conversion, &mut converted)?;
+
+        // pages whose resources name no colour spaces are left as they are
+        let color_spaces = match resources.peek("ColorSpace") {
+            Some(color_spaces) => {
+                HashMap::<String, ColorSpace>::from_obj(color_spaces.clone(), &mut document)?
+            }
+            None => continue,
+        };
+
+        let content = decoded_content(&mut document, entries.peek("Contents").cloned())?;
+
+        let tokens =
+            conversion.rewrite_content(ContentLexer::new(Cow::Owned(content)), &color_spaces)?;
+
+        let mut content = Vec::new();
+        write_content(&tokens, &mut content)?;
+
+        let contents = document.add(Object::Stream(flate_stream(&content, Dictionary::empty())?));
+
+        set_entry(&mut document, page, "Contents", Object::Reference(contents));
+    }
+
+    prune(document)
+}
+
+/// Convert the form XObjects, images and tiling patterns of `resources`, and
+/// refuse any shading in a converted colour space
+///
+/// `converted` holds the streams which have already been converted, as they
+/// may be shared between pages and forms.
+fn convert_resources<'a>(
+    document: &mut PdfWriter<'a>,
+    resources: &Dictionary<'a>,
+    conversion: &SpotColorConversion,
+    converted: &mut HashSet<Reference>,
+) -> PdfResult<()> {
+    let entries = |document: &PdfWriter<'a>, category: &str| match resources
+        .peek(category)
+        .and_then(|entries| resolved(document, entries))
+    {
+        Some(Object::Dictionary(entries)) => entries
+            .iter()
+            .map(|(_, value)| value.clone())
+            .collect::<Vec<Object>>(),
+        _ => Vec::new(),
+    };
+
+    for shading in entries(document, "Shading") {
+        check_shading(document, &shading, conversion)?;
+    }
+
+    for obj in entries(document, "XObject")
+        .into_iter()
+        .chain(entries(document, "Pattern"))
+    {
+        let stream = match resolved(document, &obj) {
+            Some(Object::Stream(stream)) => stream.clone(),
+            // shading patterns are dictionaries
+            Some(Object::Dictionary(pattern)) => {
+                if let Some(shading) = pattern.peek("Shading").cloned() {
+                    check_shading(document, &shading, conversion)?;
+                }
+
+                continue;
+            }
+            _ => continue,
+        };
+
+        // streams are always indirect objects
+        let reference = match obj {
+            Object::Reference(reference) => reference,
+            _ => continue,
+        };
+
+        if !converted.insert(reference) {
+            continue;
+        }
+
+        let entry_is = |key: &str, name: &str| {
+            stream
+                .dict
+                .other
+                .peek(key)
+                .map_or(false, |value| value.name_is(name))
+        };
+
+        let converted_stream = if entry_is("Subtype", "Image") {
+            convert_image(document, &stream, conversion)?
+        } else if entry_is("Subtype", "Form")
+            || matches!(
+                stream.dict.other.peek("PatternType"),
+                Some(Object::Integer(1))
+            )
+        {
+            convert_content_stream(document, &stream, resources, conversion, converted)?
+        } else {
+            None
+        };
+
+        if let Some(converted_stream) = converted_stream {
+            document.insert(reference, Object::Stream(converted_stream));
+        }
+    }
+
+    Ok(())
+}
+
+/// Rewrite the content of a form XObject or tiling pattern, whose resources
+/// are `parent_resources` if it has none of its own
+fn convert_content_stream<'a>(
+    document: &mut PdfWriter<'a>,
+    stream: &Stream<'a>,
+    parent_resources: &Dictionary<'a>,
+    conversion: &SpotColorConversion,
+    converted: &mut HashSet<Reference>,
+) -> PdfResult<Option<Stream<'a>>> {
+    let resources = match stream
+        .dict
+        .other
+        .peek("Resources")
+        .and_then(|resources| resolved(document, resources))
+    {
+        Some(Object::Dictionary(resources)) => resources.clone(),
+        _ => parent_resources.clone(),
+    };
+
+    convert_resources(document, &resources, conversion, converted)?;
+
+    let color_spaces = match resources.peek("ColorSpace") {
+        Some(color_spaces) => {
+            HashMap::<String, ColorSpace>::from_obj(color_spaces.clone(), document)?
+        }
+        None => return Ok(None),
+    };
+
+    let content = decode_stream(&stream.stream, &stream.dict, document)?.into_owned();
+
+    let tokens =
+        conversion.rewrite_content(ContentLexer::new(Cow::Owned(content)), &color_spaces)?;
+
+    let mut content = Vec::new();
+    write_content(&tokens, &mut content)?;
+
+    Ok(Some(flate_stream(&content, stream.dict.other.clone())?))
+}
+
+/// Convert the samples of an image in a converted colour space to DeviceCMYK
+fn convert_image<'a>(
+    document: &mut PdfWriter<'a>,
+    stream: &Stream<'a>,
+    conversion: &SpotColorConversion,
+) -> PdfResult<Option<Stream<'a>>> {
+    let dict = &stream.dict.other;
+
+    let space = match dict.peek("ColorSpace") {
+        Some(space) => ColorSpace::from_obj(space.clone(), document)?,
+        // image masks have no colour space
+        None => return Ok(None),
+    };
+
+    if !conversion.converts(&space) {
+        return Ok(None);
+    }
+
+    // the ranges of a colour key mask are of the samples being replaced
+    if let Some(Object::Array(..)) = dict.peek("Mask") {
+        anyhow::bail!("cannot convert the spot colours of an image with a colour key mask");
+    }
+
+    let integer = |key: &str| match dict.peek(key) {
+        Some(&Object::Integer(n)) if n > 0 => Ok(n as usize),
+        _ => anyhow::bail!("expected positive integer {} of image", key),
+    };
+
+    let width = integer("Width")?;
+    let height = integer("Height")?;
+    let bits = integer("BitsPerComponent")? as u32;
+
+    let decode = match dict.peek("Decode") {
+        Some(decode) => Some(Vec::<f32>::from_obj(decode.clone(), document)?),
+        None => None,
+    };
+
+    let data = decode_stream(&stream.stream, &stream.dict, document)?;
+
+    let cmyk = conversion.convert_image(&space, &data, [width, height], bits, decode.as_deref())?;
+
+    let mut dict = dict.clone();
+    dict.remove("Decode");
+    dict.insert("ColorSpace", Object::Name("DeviceCMYK".to_owned()));
+    dict.insert("BitsPerComponent", Object::Integer(8));
+
+    Ok(Some(flate_stream(&cmyk, dict)?))
+}
+
+/// Refuse a shading, or shading pattern's shading, in a converted colour space
+fn check_shading<'a>(
+    document: &mut PdfWriter<'a>,
+    shading: &Object<'a>,
+    conversion: &SpotColorConversion,
+) -> PdfResult<()> {
+    let dict = match resolved(document, shading) {
+        Some(Object::Dictionary(dict)) => dict.clone(),
+        Some(Object::Stream(stream)) => stream.dict.other.clone(),
+        _ => return Ok(()),
+    };
+
+    let space = match dict.peek("ColorSpace") {
+        Some(space) => ColorSpace::from_obj(space.clone(), document)?,
+        None => return Ok(()),
+    };
+
+    if conversion.converts(&space) {
+        anyhow::bail!(
+            "cannot convert the spot colours of a shading in {:?}",
+            space.name()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        tokenizer::Tokenizer,
+        version::PdfVersion,
+        writer::{dictionary, DocumentBuilder, PageBuilder},
+        ParseOptions, Parser, Resolve,
+    };
+
+    fn object(s: &'static str) -> Object<'static> {
+        Tokenizer::new(s.as_bytes())
+            .next_object()
+            .unwrap()
+            .unwrap()
+            .value
+    }
+
+    const ORANGE: &str = "[/Separation /Orange /DeviceCMYK << /FunctionType 2 /Domain [0 1] \
+        /N 1 /C0 [0 0 0 0] /C1 [0 0.5 1 0] >>]";
+
+    #[test]
+    fn convert_separation() {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+
+        let mut page = PageBuilder::new(612.0, 792.0);
+        page.contents("/Spot cs 0.5 scn 0 0 10 10 re f /Cutline CS 1 SCN S")
+            .resource(
+                "ColorSpace",
+                "Spot",
+                Object::Array(vec![
+                    Object::Name("Separation".to_owned()),
+                    Object::Name("Orange".to_owned()),
+                    Object::Name("DeviceCMYK".to_owned()),
+                    Object::Dictionary(dictionary([
+                        ("FunctionType", Object::Integer(2)),
+                        (
+                            "Domain",
+                            Object::Array(vec![Object::Integer(0), Object::Integer(1)]),
+                        ),
+                        (
+                            "C0",
+                            Object::Array(vec![
+                                Object::Integer(0),
+                                Object::Integer(0),
+                                Object::Integer(0),
+                                Object::Integer(0),
+                            ]),
+                        ),
+                        (
+                            "C1",
+                            Object::Array(vec![
+                                Object::Integer(0),
+                                Object::Real(0.5),
+                                Object::Integer(1),
+                                Object::Integer(0),
+                            ]),
+                        ),
+                        ("N", Object::Integer(1)),
+                    ])),
+                ]),
+            )
+            .resource(
+                "ColorSpace",
+                "Cutline",
+                Object::Array(vec![
+                    Object::Name("Separation".to_owned()),
+                    Object::Name("Cutline".to_owned()),
+                    Object::Name("DeviceGray".to_owned()),
+                    Object::Dictionary(dictionary([
+                        ("FunctionType", Object::Integer(2)),
+                        (
+                            "Domain",
+                            Object::Array(vec![Object::Integer(0), Object::Integer(1)]),
+                        ),
+                        ("N", Object::Integer(1)),
+                    ])),
+                ]),
+            );
+
+        builder.add_page(page).unwrap();
+
+        let mut parser =
+            Parser::from_bytes(builder.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        let converted = convert_spot_colors(
+            parser.compacted().unwrap(),
+            &SpotColorConversion::new().preserve("Cutline"),
+        )
+        .unwrap();
+
+        let mut parser =
+            Parser::from_bytes(converted.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        let pages = parser.pages();
+        let content = parser.page_contents(&pages[0]).unwrap();
+
+        assert_eq!(
+            String::from_utf8_lossy(&content.buffer),
+            "0.0 0.5 1.0 0.0 k\n0.0 0.25 0.5 0.0 k\n0 0 10 10 re\nf\n/Cutline CS\n1 SCN\nS\n"
+        );
+    }
+
+    #[test]
+    fn convert_forms_and_images() {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+
+        let mut form = Dictionary::empty();
+        form.insert("Subtype", Object::Name("Form".to_owned()));
+        form.insert("BBox", object("[0 0 10 10]"));
+        form.insert(
+            "Resources",
+            Object::Dictionary(dictionary([(
+                "ColorSpace",
+                Object::Dictionary(dictionary([("Spot", object(ORANGE))])),
+            )])),
+        );
+
+        let form = builder.add_object(Object::Stream(Stream::new(
+            b"/Spot cs 1 scn 0 0 5 5 re f".to_vec(),
+            form,
+        )));
+
+        let image = builder.add_object(Object::Stream(Stream::new(
+            vec![0, 255],
+            dictionary([
+                ("Subtype", Object::Name("Image".to_owned())),
+                ("Width", Object::Integer(2)),
+                ("Height", Object::Integer(1)),
+                ("BitsPerComponent", Object::Integer(8)),
+                ("ColorSpace", object(ORANGE)),
+            ]),
+        )));
+
+        let mut page = PageBuilder::new(612.0, 792.0);
+        page.contents("/Fm Do /Im Do")
+            .xobject("Fm", form)
+            .xobject("Im", image);
+
+        builder.add_page(page).unwrap();
+
+        let mut parser =
+            Parser::from_bytes(builder.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        let converted =
+            convert_spot_colors(parser.compacted().unwrap(), &SpotColorConversion::new()).unwrap();
+
+        let mut resolver = &converted;
+
+        let page = resolver
+            .assert_dict(Object::Reference(converted.pages().unwrap()[0]))
+            .unwrap();
+        let resources = resolver
+            .assert_dict(page.peek("Resources").unwrap().clone())
+            .unwrap();
+        let xobjects = resolver
+            .assert_dict(resources.peek("XObject").unwrap().clone())
+            .unwrap();
+
+        let mut decoded = |name: &str| {
+            let stream = resolver
+                .assert_stream(xobjects.peek(name).unwrap().clone())
+                .unwrap();
+            let data = decode_stream(&stream.stream, &stream.dict, &mut resolver)
+                .unwrap()
+                .into_owned();
+
+            (stream.dict.other, data)
+        };
+
+        let (_, content) = decoded("Fm");
+
+        assert_eq!(
+            String::from_utf8_lossy(&content),
+            "0.0 0.5 1.0 0.0 k\n0.0 0.5 1.0 0.0 k\n0 0 5 5 re\nf\n"
+        );
+
+        let (dict, samples) = decoded("Im");
+
+        assert!(dict.peek("ColorSpace").unwrap().name_is("DeviceCMYK"));
+        assert_eq!(samples, [0, 0, 0, 0, 0, 128, 255, 0]);
+    }
+
+    #[test]
+    fn refuse_spot_shadings() {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+
+        let shading = dictionary([
+            ("ShadingType", Object::Integer(2)),
+            ("ColorSpace", object(ORANGE)),
+            ("Coords", object("[0 0 10 0]")),
+            (
+                "Function",
+                object("<< /FunctionType 2 /Domain [0 1] /N 1 >>"),
+            ),
+        ]);
+
+        let mut page = PageBuilder::new(612.0, 792.0);
+        page.contents("/Sh sh")
+            .resource("Shading", "Sh", Object::Dictionary(shading));
+
+        builder.add_page(page).unwrap();
+
+        let mut parser =
+            Parser::from_bytes(builder.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        assert!(
+            convert_spot_colors(parser.compacted().unwrap(), &SpotColorConversion::new()).is_err()
+        );
+        assert!(convert_spot_colors(
+            parser.compacted().unwrap(),
+            &SpotColorConversion::new().preserve("Orange")
+        )
+        .is_ok());
+    }
+}
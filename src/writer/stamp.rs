@@ -0,0 +1,211 @@
+use crate::{
+    error::PdfResult,
+    objects::{Dictionary, Object, Reference},
+};
+
+use super::{
+    content::decoded_content,
+    dictionary, flate_stream,
+    merge::{merge, prune, resolved, set_entry},
+    PdfWriter,
+};
+
+/// Draw the first page of the compacted document `stamp` over every page of
+/// the compacted document `document`, such as to add a watermark
+///
+/// The stamp becomes a form XObject shared by every page, and is drawn at the
+/// origin of each page without being scaled. The content of each page is
+/// wrapped in a graphics state of its own, so that the stamp is drawn in the
+/// default one. Only the first page of the stamp is kept, and not its outline
+/// or other document-level structures.
+pub(crate) fn stamp<'a>(
+    document: PdfWriter<'a>,
+    mut stamp: PdfWriter<'a>,
+) -> PdfResult<PdfWriter<'a>> {
+    let page_count = document.pages()?.len();
+
+    // only the pages of the stamp are merged, rather than its outline and
+    // named destinations as well
+    let root = match stamp.trailer.peek("Root") {
+        Some(Object::Reference(root)) => *root,
+        _ => anyhow::bail!("the document catalog must be an indirect object"),
+    };
+
+    let stamp_pages = match stamp.get(root) {
+        Some(Object::Dictionary(catalog)) => catalog.peek("Pages").cloned(),
+        _ => None,
+    };
+
+    stamp.insert(
+        root,
+        Object::Dictionary(dictionary([
+            ("Type", Object::Name("Catalog".to_owned())),
+            ("Pages", stamp_pages.unwrap_or(Object::Null)),
+        ])),
+    );
+
+    let version = document.version().max(stamp.version());
+    let mut document = merge(vec![document, stamp], version)?;
+
+    let pages = document.pages()?;
+
+    let stamp_page = match pages.get(page_count) {
+        Some(&page) => match document.get(page) {
+            Some(Object::Dictionary(page)) => page.clone(),
+            _ => anyhow::bail!("the first page of the stamp does not exist"),
+        },
+        None => anyhow::bail!("the stamp document has no pages"),
+    };
+
+    let form = form_xobject(&mut document, &stamp_page)?;
+
+    let begin = document.add(Object::Stream(flate_stream(b"q\n", Dictionary::empty())?));
+
+    for &page in &pages[..page_count] {
+        let entries = match document.get(page) {
+            Some(Object::Dictionary(page)) => page.clone(),
+            _ => continue,
+        };
+
+        let mut resources = match entries
+            .peek("Resources")
+            .and_then(|resources| resolved(&document, resources))
+        {
+            Some(Object::Dictionary(resources)) => resources.clone(),
+            _ => Dictionary::empty(),
+        };
+
+        let mut xobjects = match resources
+            .peek("XObject")
+            .and_then(|xobjects| resolved(&document, xobjects))
+        {
+            Some(Object::Dictionary(xobjects)) => xobjects.clone(),
+            _ => Dictionary::empty(),
+        };
+
+        let name = (0..)
+            .map(|n| format!("Stamp{}", n))
+            .find(|name| xobjects.peek(name).is_none())
+            .unwrap();
+
+        xobjects.insert(name.clone(), Object::Reference(form));
+        resources.insert("XObject", Object::Dictionary(xobjects));
+
+        let end = document.add(Object::Stream(flate_stream(
+            format!("\nQ\n/{} Do\n", name).as_bytes(),
+            Dictionary::empty(),
+        )?));
+
+        let mut contents = vec![Object::Reference(begin)];
+
+        match entries
+            .peek("Contents")
+            .and_then(|contents| resolved(&document, contents))
+        {
+            Some(Object::Array(streams)) => contents.extend(streams.iter().cloned()),
+            Some(..) => contents.extend(entries.peek("Contents").cloned()),
+            None => {}
+        }
+
+        contents.push(Object::Reference(end));
+
+        set_entry(&mut document, page, "Contents", Object::Array(contents));
+        set_entry(
+            &mut document,
+            page,
+            "Resources",
+            Object::Dictionary(resources),
+        );
+    }
+
+    for idx in (page_count..pages.len()).rev() {
+        document.remove_page(idx)?;
+    }
+
+    prune(document)
+}
+
+/// A form XObject drawing the content of `page`, clipped to its crop box
+fn form_xobject<'a>(document: &mut PdfWriter<'a>, page: &Dictionary<'a>) -> PdfResult<Reference> {
+    let content = decoded_content(document, page.peek("Contents").cloned())?;
+
+    let bbox = match page.peek("CropBox").or_else(|| page.peek("MediaBox")) {
+        Some(bbox) => bbox.clone(),
+        None => anyhow::bail!("the page of the stamp has no media box"),
+    };
+
+    let dict = dictionary([
+        ("Type", Object::Name("XObject".to_owned())),
+        ("Subtype", Object::Name("Form".to_owned())),
+        ("BBox", bbox),
+        (
+            "Resources",
+            page.peek("Resources")
+                .cloned()
+                .unwrap_or_else(|| Object::Dictionary(Dictionary::empty())),
+        ),
+    ]);
+
+    Ok(document.add(Object::Stream(flate_stream(&content, dict)?)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        filter::decode_stream,
+        version::PdfVersion,
+        writer::{DocumentBuilder, PageBuilder},
+        xobject::XObject,
+        ParseOptions, Parser,
+    };
+
+    fn document(contents: &[&str]) -> Parser<'static> {
+        let mut builder = DocumentBuilder::new(PdfVersion::V1_7);
+
+        for contents in contents {
+            let mut page = PageBuilder::new(612.0, 792.0);
+            page.contents(*contents);
+
+            builder.add_page(page).unwrap();
+        }
+
+        Parser::from_bytes(builder.to_bytes().unwrap(), ParseOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn stamp_every_page() {
+        let mut original = document(&["0 0 1 rg 10 10 50 50 re f", "0 1 0 rg 10 10 50 50 re f"]);
+        let mut watermark = document(&["1 0 0 rg 0 0 20 20 re f", "0 g 0 0 40 40 re f"]);
+
+        let stamped = stamp(
+            original.compacted().unwrap(),
+            watermark.compacted().unwrap(),
+        )
+        .unwrap();
+
+        let mut parser =
+            Parser::from_bytes(stamped.to_bytes().unwrap(), ParseOptions::default()).unwrap();
+
+        let pages = parser.pages();
+        assert_eq!(pages.len(), 2);
+
+        let content = parser.page_contents(&pages[1]).unwrap();
+
+        assert_eq!(
+            String::from_utf8_lossy(&content.buffer),
+            "q\n0 1 0 rg 10 10 50 50 re f\nQ\n/Stamp0 Do\n"
+        );
+
+        let resources = pages[0].resources().unwrap();
+
+        let form = match &resources.xobject.as_ref().unwrap()["Stamp0"] {
+            XObject::Form(form) => form,
+            xobject => panic!("expected a form XObject, found {:?}", xobject),
+        };
+
+        let content = decode_stream(&form.stream.stream, &form.stream.dict, &mut parser.lexer);
+
+        assert_eq!(content.unwrap().as_ref(), b"1 0 0 rg 0 0 20 20 re f\n");
+    }
+}
@@ -0,0 +1,184 @@
+use std::{collections::BTreeMap, io::Write};
+
+use crate::error::PdfResult;
+
+/// The generation number of the head of the list of free objects, which is
+/// never reused
+const FREE_LIST_HEAD_GENERATION: usize = 65535;
+
+/// An entry of a cross-reference table or stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum XrefRow {
+    /// An object number which is not in use, linking to the next free object
+    /// number
+    Free { next: usize, generation: usize },
+
+    /// An object written directly in the file, at the given byte offset
+    InFile { offset: usize, generation: usize },
+
+    /// An object at `index` within the object stream `stream`
+    Compressed { stream: usize, index: usize },
+}
+
+impl XrefRow {
+    /// The type and two fields of the row within a cross-reference stream
+    fn fields(self) -> [usize; 3] {
+        match self {
+            XrefRow::Free { next, generation } => [0, next, generation],
+            XrefRow::InFile { offset, generation } => [1, offset, generation],
+            XrefRow::Compressed { stream, index } => [2, stream, index],
+        }
+    }
+}
+
+/// The rows for object numbers up to `size`, given those which are in use
+///
+/// Unused object numbers form the linked list of free entries, each pointing
+/// to the next, starting and ending with object 0.
+pub(super) fn xref_rows(used: &BTreeMap<usize, XrefRow>, size: usize) -> Vec<XrefRow> {
+    let next_free = |object_number: usize| {
+        (object_number + 1..size)
+            .find(|n| !used.contains_key(n))
+            .unwrap_or(0)
+    };
+
+    (0..size)
+        .map(|object_number| match used.get(&object_number) {
+            Some(row) => *row,
+            None => XrefRow::Free {
+                next: next_free(object_number),
+                generation: if object_number == 0 {
+                    FREE_LIST_HEAD_GENERATION
+                } else {
+                    // the generation to use if the object number were reused
+                    1
+                },
+            },
+        })
+        .collect()
+}
+
+/// Write a cross-reference table with a single subsection
+///
+/// Tables cannot refer to objects within object streams.
+pub(super) fn write_xref_table(rows: &[XrefRow], out: &mut Vec<u8>) -> PdfResult<()> {
+    writeln!(out, "xref\n0 {}", rows.len())?;
+
+    write_xref_rows(rows, out)
+}
+
+fn write_xref_rows(rows: &[XrefRow], out: &mut Vec<u8>) -> PdfResult<()> {
+    for row in rows {
+        // each entry is exactly 20 bytes, ending with a space and a line feed
+        match *row {
+            XrefRow::Free { next, generation } => {
+                writeln!(out, "{:010} {:05} f ", next, generation)?;
+            }
+            XrefRow::InFile { offset, generation } => {
+                writeln!(out, "{:010} {:05} n ", offset, generation)?;
+            }
+            XrefRow::Compressed { .. } => {
+                anyhow::bail!("cross-reference tables cannot refer to compressed objects")
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The runs of consecutive object numbers among `used`, each as its first
+/// object number and its rows, as listed by the subsections of an
+/// incremental update
+pub(super) fn xref_subsections(used: &BTreeMap<usize, XrefRow>) -> Vec<(usize, Vec<XrefRow>)> {
+    let mut subsections: Vec<(usize, Vec<XrefRow>)> = Vec::new();
+
+    for (&object_number, &row) in used {
+        match subsections.last_mut() {
+            Some((first, rows)) if *first + rows.len() == object_number => rows.push(row),
+            _ => subsections.push((object_number, vec![row])),
+        }
+    }
+
+    subsections
+}
+
+/// Write a cross-reference table with the given subsections
+pub(super) fn write_xref_subsections(
+    subsections: &[(usize, Vec<XrefRow>)],
+    out: &mut Vec<u8>,
+) -> PdfResult<()> {
+    out.extend_from_slice(b"xref\n");
+
+    for (first, rows) in subsections {
+        writeln!(out, "{} {}", first, rows.len())?;
+        write_xref_rows(rows, out)?;
+    }
+
+    Ok(())
+}
+
+/// The unfiltered data of a cross-reference stream, along with its W entry,
+/// the width in bytes of each field
+pub(super) fn xref_stream_data(rows: &[XrefRow]) -> (Vec<u8>, [usize; 3]) {
+    let width = |field: usize| {
+        let max = rows
+            .iter()
+            .map(|row| row.fields()[field])
+            .max()
+            .unwrap_or(0);
+
+        // fields are big-endian, and at least one byte wide
+        (usize::BITS - max.leading_zeros()).div_ceil(8) as usize
+    };
+
+    let widths = [1, width(1).max(1), width(2).max(1)];
+
+    let mut data = Vec::with_capacity(rows.len() * widths.iter().sum::<usize>());
+
+    for row in rows {
+        for (field, width) in row.fields().into_iter().zip(widths) {
+            data.extend_from_slice(&field.to_be_bytes()[std::mem::size_of::<usize>() - width..]);
+        }
+    }
+
+    (data, widths)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_xref_stream() {
+        let used = BTreeMap::from([
+            (
+                1,
+                XrefRow::InFile {
+                    offset: 0x1234,
+                    generation: 0,
+                },
+            ),
+            (
+                3,
+                XrefRow::Compressed {
+                    stream: 1,
+                    index: 2,
+                },
+            ),
+        ]);
+
+        let rows = xref_rows(&used, 4);
+        let (data, widths) = xref_stream_data(&rows);
+
+        assert_eq!(widths, [1, 2, 2]);
+        assert_eq!(
+            data,
+            [
+                0, 0, 2, 0xff, 0xff, // 0: free, next 2
+                1, 0x12, 0x34, 0, 0, // 1: at 0x1234
+                0, 0, 0, 0, 1, // 2: free, last
+                2, 0, 1, 0, 2, // 3: index 2 of stream 1
+            ]
+        );
+    }
+}
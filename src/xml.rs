@@ -0,0 +1,361 @@
+/*!
+ * A minimal XML reader and writer, sufficient for the XML-based interchange
+ * formats embedded in and exchanged with PDF files
+ *
+ * Namespaces are not resolved, and document type declarations are skipped.
+ */
+
+use crate::error::PdfResult;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct XmlElement {
+    /// The qualified name of the element, including any namespace prefix
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<XmlNode>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum XmlNode {
+    Element(XmlElement),
+    Text(String),
+}
+
+impl XmlElement {
+    /// The name of the element without its namespace prefix
+    pub fn local_name(&self) -> &str {
+        self.name.rsplit(':').next().unwrap_or(&self.name)
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The child elements, skipping text
+    pub fn elements(&self) -> impl Iterator<Item = &XmlElement> {
+        self.children.iter().filter_map(|child| match child {
+            XmlNode::Element(element) => Some(element),
+            XmlNode::Text(..) => None,
+        })
+    }
+
+    /// The first child element with the given local name
+    pub fn element(&self, local_name: &str) -> Option<&XmlElement> {
+        self.elements()
+            .find(|element| element.local_name() == local_name)
+    }
+
+    /// The concatenated text of the element and its descendants
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+
+        for child in &self.children {
+            match child {
+                XmlNode::Element(element) => text.push_str(&element.text()),
+                XmlNode::Text(t) => text.push_str(t),
+            }
+        }
+
+        text
+    }
+
+    /// Write the element and its descendants as XML
+    pub fn write(&self, out: &mut String) {
+        out.push('<');
+        out.push_str(&self.name);
+
+        for (key, value) in &self.attributes {
+            out.push_str(&format!(" {}=\"{}\"", key, escape(value)));
+        }
+
+        if self.children.is_empty() {
+            out.push_str("/>");
+            return;
+        }
+
+        out.push('>');
+
+        for child in &self.children {
+            match child {
+                XmlNode::Element(element) => element.write(out),
+                XmlNode::Text(text) => out.push_str(&escape(text)),
+            }
+        }
+
+        out.push_str(&format!("</{}>", self.name));
+    }
+}
+
+/// Parse the root element of an XML document
+pub(crate) fn parse(xml: &str) -> PdfResult<XmlElement> {
+    let mut reader = XmlReader { xml, pos: 0 };
+
+    loop {
+        reader.skip_whitespace();
+
+        if reader.skip_markup()? {
+            continue;
+        }
+
+        return match reader.parse_element()? {
+            Some(element) => Ok(element),
+            None => anyhow::bail!("expected XML root element"),
+        };
+    }
+}
+
+/// Escape the characters of `s` which cannot appear literally in text or in
+/// attribute values
+pub(crate) fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\r' => escaped.push_str("&#13;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+struct XmlReader<'a> {
+    xml: &'a str,
+    pos: usize,
+}
+
+impl<'a> XmlReader<'a> {
+    fn rest(&self) -> &'a str {
+        &self.xml[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn skip_past(&mut self, terminator: &str) -> PdfResult<&'a str> {
+        let rest = self.rest();
+
+        match rest.find(terminator) {
+            Some(idx) => {
+                self.pos += idx + terminator.len();
+                Ok(&rest[..idx])
+            }
+            None => anyhow::bail!("unterminated XML markup, expected {:?}", terminator),
+        }
+    }
+
+    /// Skip a comment, processing instruction, or document type declaration,
+    /// returning whether there was one
+    fn skip_markup(&mut self) -> PdfResult<bool> {
+        let rest = self.rest();
+
+        if rest.starts_with("<!--") {
+            self.skip_past("-->")?;
+        } else if rest.starts_with("<?") {
+            self.skip_past("?>")?;
+        } else if rest.starts_with("<!") && !rest.starts_with("<![CDATA[") {
+            self.skip_past(">")?;
+        } else {
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    fn parse_name(&mut self) -> &'a str {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or(rest.len());
+
+        self.pos += len;
+
+        &rest[..len]
+    }
+
+    /// Parse the element starting at the current position, or return `None`
+    /// if there is no start tag
+    fn parse_element(&mut self) -> PdfResult<Option<XmlElement>> {
+        if !self.rest().starts_with('<') || self.rest().starts_with("</") {
+            return Ok(None);
+        }
+
+        self.pos += 1;
+
+        let name = self.parse_name().to_owned();
+        let mut attributes = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+
+            let rest = self.rest();
+
+            if rest.starts_with("/>") {
+                self.pos += 2;
+
+                return Ok(Some(XmlElement {
+                    name,
+                    attributes,
+                    children: Vec::new(),
+                }));
+            }
+
+            if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+
+            let key = self.parse_name().to_owned();
+
+            if key.is_empty() {
+                anyhow::bail!("malformed start tag of XML element {:?}", name);
+            }
+
+            self.skip_whitespace();
+
+            if !self.rest().starts_with('=') {
+                anyhow::bail!("expected value of XML attribute {:?}", key);
+            }
+
+            self.pos += 1;
+            self.skip_whitespace();
+
+            let quote = match self.rest().chars().next() {
+                Some(quote @ ('"' | '\'')) => quote,
+                _ => anyhow::bail!("expected quoted value of XML attribute {:?}", key),
+            };
+
+            self.pos += 1;
+
+            let value = self.skip_past(&quote.to_string())?;
+
+            attributes.push((key, unescape(value)));
+        }
+
+        let mut children = Vec::new();
+
+        loop {
+            let rest = self.rest();
+
+            if rest.is_empty() {
+                anyhow::bail!("unterminated XML element {:?}", name);
+            }
+
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let end = self.parse_name();
+
+                if end != name {
+                    anyhow::bail!("mismatched end tag {:?} for XML element {:?}", end, name);
+                }
+
+                self.skip_past(">")?;
+                break;
+            }
+
+            if rest.starts_with("<![CDATA[") {
+                self.pos += "<![CDATA[".len();
+                let text = self.skip_past("]]>")?;
+                children.push(XmlNode::Text(text.to_owned()));
+                continue;
+            }
+
+            if self.skip_markup()? {
+                continue;
+            }
+
+            if let Some(element) = self.parse_element()? {
+                children.push(XmlNode::Element(element));
+                continue;
+            }
+
+            let len = rest.find('<').unwrap_or(rest.len());
+            self.pos += len;
+            children.push(XmlNode::Text(unescape(&rest[..len])));
+        }
+
+        Ok(Some(XmlElement {
+            name,
+            attributes,
+            children,
+        }))
+    }
+}
+
+/// Replace the predefined entities and character references of `s`
+///
+/// Unrecognized entities are kept as they are.
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(idx) = rest.find('&') {
+        unescaped.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+
+        let c = entity.and_then(|(entity, _)| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => {
+                let code = match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => entity.strip_prefix('#')?.parse::<u32>().ok()?,
+                };
+
+                char::from_u32(code)
+            }
+        });
+
+        match (c, entity) {
+            (Some(c), Some((_, end))) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    unescaped.push_str(rest);
+
+    unescaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_elements_and_entities() {
+        let root = parse(
+            r#"<?xml version="1.0"?>
+<!DOCTYPE root>
+<!-- comment -->
+<root a='1 &amp; 2'><x:child>a &lt;b&gt; &#233;&#x41;</x:child><empty/><![CDATA[<raw>]]></root>"#,
+        )
+        .unwrap();
+
+        assert_eq!(root.attribute("a"), Some("1 & 2"));
+        assert_eq!(root.element("child").unwrap().text(), "a <b> éA");
+        assert_eq!(root.elements().count(), 2);
+        assert_eq!(root.text(), "a <b> éA<raw>");
+        assert_eq!(unescape(&escape("<\"&'>")), "<\"&'>");
+    }
+}
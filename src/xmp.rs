@@ -0,0 +1,278 @@
+/*!
+ * Writing XMP metadata packets, keeping them consistent with the document
+ * information dictionary as PDF/A requires
+ *
+ * As with the rest of the XML support, namespaces are not resolved, so
+ * properties are recognized by their conventional prefixes: `dc`, `pdf` and
+ * `xmp`.
+ */
+
+use crate::{
+    catalog::InformationDictionary,
+    catalog::MetadataStream,
+    date::Date,
+    error::PdfResult,
+    objects::decode_text_string,
+    objects::Object,
+    xml::{self, XmlElement, XmlNode},
+    FromObj, Parser,
+};
+
+/// The padding given to new packets, so that they can later be updated in
+/// place, as the XMP specification recommends
+const DEFAULT_PADDING: usize = 2048;
+
+const PACKET_HEADER: &str = "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n";
+const PACKET_TRAILER: &str = "<?xpacket end=\"w\"?>";
+
+/// The properties which mirror entries of the document information
+/// dictionary, and are rewritten whenever it changes
+const INFO_PROPERTIES: [&str; 9] = [
+    "dc:title",
+    "dc:creator",
+    "dc:description",
+    "pdf:Keywords",
+    "pdf:Producer",
+    "xmp:CreatorTool",
+    "xmp:CreateDate",
+    "xmp:ModifyDate",
+    "xmp:MetadataDate",
+];
+
+/// An XMP packet holding the entries of `info`
+///
+/// If `existing` is the document's current packet, its other properties are
+/// kept, and its padding is shrunk or grown so that the new packet is the same
+/// length, if it fits. Otherwise, the packet is given fresh padding.
+pub(crate) fn xmp_packet(
+    existing: Option<&str>,
+    info: &InformationDictionary,
+) -> PdfResult<String> {
+    let mut root = match existing {
+        Some(packet) => xml::parse(packet)?,
+        None => element(
+            "x:xmpmeta",
+            vec![("xmlns:x", "adobe:ns:meta/")],
+            vec![XmlNode::Element(element(
+                "rdf:RDF",
+                vec![("xmlns:rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#")],
+                Vec::new(),
+            ))],
+        ),
+    };
+
+    let rdf = match rdf_element(&mut root) {
+        Some(rdf) => rdf,
+        None => anyhow::bail!("XMP packet has no rdf:RDF element"),
+    };
+
+    for child in &mut rdf.children {
+        if let XmlNode::Element(description) = child {
+            description
+                .attributes
+                .retain(|(key, _)| !INFO_PROPERTIES.contains(&key.as_str()));
+            description.children.retain(|child| match child {
+                XmlNode::Element(property) => !INFO_PROPERTIES.contains(&property.name.as_str()),
+                XmlNode::Text(..) => true,
+            });
+        }
+    }
+
+    // descriptions which held only the properties being rewritten
+    rdf.children.retain(|child| match child {
+        XmlNode::Element(description) => {
+            description.elements().next().is_some()
+                || description
+                    .attributes
+                    .iter()
+                    .any(|(key, _)| !key.starts_with("xmlns:") && key != "rdf:about")
+        }
+        XmlNode::Text(..) => true,
+    });
+
+    let properties = info_properties(info);
+
+    if !properties.is_empty() {
+        rdf.children.push(XmlNode::Element(element(
+            "rdf:Description",
+            vec![
+                ("rdf:about", ""),
+                ("xmlns:dc", "http://purl.org/dc/elements/1.1/"),
+                ("xmlns:pdf", "http://ns.adobe.com/pdf/1.3/"),
+                ("xmlns:xmp", "http://ns.adobe.com/xap/1.0/"),
+            ],
+            properties.into_iter().map(XmlNode::Element).collect(),
+        )));
+    }
+
+    let mut packet = PACKET_HEADER.to_owned();
+    root.write(&mut packet);
+    packet.push('\n');
+
+    let unpadded = packet.len() + PACKET_TRAILER.len();
+
+    let padding = match existing {
+        Some(existing) if existing.len() >= unpadded => existing.len() - unpadded,
+        _ => DEFAULT_PADDING,
+    };
+
+    // lines of spaces, which may be overwritten by a later update in place
+    packet.extend((1..=padding).map(|n| if n % 100 == 0 { '\n' } else { ' ' }));
+    packet.push_str(PACKET_TRAILER);
+
+    Ok(packet)
+}
+
+/// The rdf:RDF element of a packet, which is usually within an x:xmpmeta
+/// element but may be its root
+fn rdf_element(root: &mut XmlElement) -> Option<&mut XmlElement> {
+    if root.local_name() == "RDF" {
+        return Some(root);
+    }
+
+    root.children.iter_mut().find_map(|child| match child {
+        XmlNode::Element(element) if element.local_name() == "RDF" => Some(element),
+        _ => None,
+    })
+}
+
+fn info_properties(info: &InformationDictionary) -> Vec<XmlElement> {
+    let mut properties = Vec::new();
+
+    // language alternatives and ordered arrays, as the Dublin Core schema
+    // defines these properties
+    if let Some(title) = &info.title {
+        properties.push(array("dc:title", "rdf:Alt", &decode_text_string(title)));
+    }
+
+    if let Some(author) = &info.author {
+        properties.push(array("dc:creator", "rdf:Seq", &decode_text_string(author)));
+    }
+
+    if let Some(subject) = &info.subject {
+        properties.push(array(
+            "dc:description",
+            "rdf:Alt",
+            &decode_text_string(subject),
+        ));
+    }
+
+    let text = |s: &Option<String>| s.as_deref().map(decode_text_string);
+
+    let simple = [
+        ("pdf:Keywords", text(&info.keywords)),
+        ("pdf:Producer", text(&info.producer)),
+        ("xmp:CreatorTool", text(&info.creator)),
+        (
+            "xmp:CreateDate",
+            info.creation_date.as_ref().map(Date::to_xmp_string),
+        ),
+        (
+            "xmp:ModifyDate",
+            info.mod_date.as_ref().map(Date::to_xmp_string),
+        ),
+        (
+            "xmp:MetadataDate",
+            info.mod_date.as_ref().map(Date::to_xmp_string),
+        ),
+    ];
+
+    for (name, value) in simple {
+        if let Some(value) = value {
+            properties.push(element(name, Vec::new(), vec![XmlNode::Text(value)]));
+        }
+    }
+
+    properties
+}
+
+/// A property whose value is an array with a single item, which for language
+/// alternatives is the default
+fn array(name: &str, kind: &str, value: &str) -> XmlElement {
+    let attributes = if kind == "rdf:Alt" {
+        vec![("xml:lang", "x-default")]
+    } else {
+        Vec::new()
+    };
+
+    let item = element("rdf:li", attributes, vec![XmlNode::Text(value.to_owned())]);
+    let array = element(kind, Vec::new(), vec![XmlNode::Element(item)]);
+
+    element(name, Vec::new(), vec![XmlNode::Element(array)])
+}
+
+fn element(name: &str, attributes: Vec<(&str, &str)>, children: Vec<XmlNode>) -> XmlElement {
+    XmlElement {
+        name: name.to_owned(),
+        attributes: attributes
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect(),
+        children,
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// The document's XMP metadata packet, from the metadata stream of its
+    /// catalog
+    ///
+    /// Encrypted documents may leave the metadata stream unencrypted, so that
+    /// it can be indexed without a password, in which case it is read as-is.
+    pub fn xmp_metadata(&mut self) -> PdfResult<Option<String>> {
+        let reference = match self.catalog.metadata {
+            Some(reference) => reference,
+            None => return Ok(None),
+        };
+
+        let metadata = MetadataStream::from_obj(Object::Reference(reference), &mut self.lexer)?;
+
+        Ok(Some(metadata.decode(&mut self.lexer)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn update_packet_in_place() {
+        let info = InformationDictionary {
+            title: Some("Report".to_owned()),
+            producer: Some("pdf-rs".to_owned()),
+            ..InformationDictionary::default()
+        };
+
+        let packet = xmp_packet(None, &info).unwrap();
+
+        let existing = packet.replacen(
+            "</rdf:RDF>",
+            "<rdf:Description rdf:about=\"\" xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\" \
+             pdfaid:part=\"2\" pdfaid:conformance=\"B\"/></rdf:RDF>",
+            1,
+        );
+
+        let info = InformationDictionary {
+            title: Some("Annual <Report>".to_owned()),
+            author: Some("A. Author".to_owned()),
+            ..InformationDictionary::default()
+        };
+
+        let updated = xmp_packet(Some(&existing), &info).unwrap();
+
+        assert_eq!(updated.len(), existing.len());
+        assert!(updated.ends_with(PACKET_TRAILER));
+        assert!(updated.contains("pdfaid:part=\"2\""));
+        assert!(!updated.contains("pdf:Producer"));
+
+        let root = xml::parse(&updated).unwrap();
+        let titles = root
+            .element("RDF")
+            .unwrap()
+            .elements()
+            .filter_map(|description| description.element("title"))
+            .map(XmlElement::text)
+            .collect::<Vec<_>>();
+
+        assert_eq!(titles, vec!["Annual <Report>"]);
+    }
+}
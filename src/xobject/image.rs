@@ -2,9 +2,11 @@ use crate::{
     catalog::MetadataStream,
     color::ColorSpace,
     error::PdfResult,
-    filter::{dct::ColorTransform, flate::BitsPerComponent},
+    filter::{
+        dct::ColorTransform, decode_stream, flate::BitsPerComponent, jpx::JpxHeader, FilterKind,
+    },
     objects::{Name, Object},
-    optional_content::{OptionalContent, OptionalContentGroup},
+    optional_content::OptionalContent,
     resources::graphics_state_parameters::RenderingIntent,
     stream::Stream,
     FromObj, Resolve,
@@ -172,6 +174,200 @@ pub struct ImageXObject<'a> {
     color_transform: Option<ColorTransform>,
 }
 
+/// The pixels of a decoded image, each of 8-bit red, green, blue and alpha
+/// samples, from left to right and top to bottom
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl<'a> ImageXObject<'a> {
+    /// Decode the image into pixels of red, green, blue and alpha
+    ///
+    /// Samples are unpacked from BitsPerComponent bits, mapped through the
+    /// Decode array, and converted from the colour space of the image. The
+    /// alpha of each pixel is taken from the soft mask of the image, and
+    /// pixels whose samples fall within the ranges of a colour key mask are
+    /// transparent. Pixels are otherwise opaque.
+    ///
+    /// Image masks have no colour of their own, and are painted black, the
+    /// initial fill colour.
+    pub fn decode_to_rgba(&self, resolver: &mut dyn Resolve<'a>) -> PdfResult<RgbaImage> {
+        if self.image_mask {
+            return self.stencil_to_rgba([0, 0, 0], resolver);
+        }
+
+        let data = decode_stream(&self.stream.stream, &self.stream.dict, resolver)?;
+
+        let jpx = self
+            .stream
+            .dict
+            .filter
+            .as_ref()
+            .map_or(false, |filters| filters.contains(&FilterKind::Jpx));
+
+        let header = if jpx {
+            Some(JpxHeader::read(&self.stream.stream)?)
+        } else {
+            None
+        };
+
+        // JPEG 2000 images are decoded to 8-bit samples, in the colour space
+        // given by their data unless the image dictionary has one, and their
+        // Decode arrays are ignored
+        let color_space = match (&self.color_space, &header) {
+            (Some(color_space), _) => color_space.clone(),
+            (None, Some(header)) => match header.device_color_space() {
+                Some(name) => ColorSpace::init(name)?,
+                None => anyhow::bail!("JPEG 2000 image has no colour space"),
+            },
+            (None, None) => anyhow::bail!("image has no colour space"),
+        };
+
+        let bits = match (self.bits_per_component, &header) {
+            (_, Some(..)) => 8,
+            (Some(bits), None) => bits as u32,
+            (None, None) => anyhow::bail!("image has no BitsPerComponent"),
+        };
+
+        let decode = match &self.decode {
+            Some(decode) if header.is_none() => decode.clone(),
+            _ => color_space.default_decode(bits),
+        };
+
+        let components = color_space.components();
+
+        // a JPEG 2000 image may also have samples of an alpha channel
+        let samples_per_pixel = header.as_ref().map_or(components, |header| {
+            usize::from(header.components).max(components)
+        });
+
+        let max = ((1_u32 << bits) - 1) as f32;
+        let width = self.width as usize;
+
+        // each row begins on a byte boundary
+        let row_len = (width * samples_per_pixel * bits as usize).div_ceil(8);
+
+        let alpha = match &self.s_mask {
+            Some(s_mask) => Some(s_mask.decode_alpha(self.width, self.height, resolver)?),
+            None => None,
+        };
+
+        // a soft mask takes the place of any other mask
+        let color_key = match (&self.mask, &alpha) {
+            (Some(ImageMask::ColorKey(ranges)), None) => Some(ranges),
+            _ => None,
+        };
+
+        let mut pixels = Vec::with_capacity(width * self.height as usize * 4);
+        let mut values = vec![0.0; components];
+
+        for y in 0..self.height as usize {
+            for x in 0..width {
+                let mut masked = color_key.is_some();
+
+                for (idx, value) in values.iter_mut().enumerate() {
+                    let bit_offset =
+                        y * row_len * 8 + (x * samples_per_pixel + idx) * bits as usize;
+                    let sample = read_sample(&data, bit_offset, bits) as f32;
+
+                    // the ranges of a colour key are of samples before they
+                    // are decoded
+                    if let Some(ranges) = color_key {
+                        masked &= match ranges.get(idx * 2..idx * 2 + 2) {
+                            Some(&[min, max_sample]) => min <= sample && sample <= max_sample,
+                            _ => false,
+                        };
+                    }
+
+                    let (min, max_value) = match decode.get(idx * 2..idx * 2 + 2) {
+                        Some(&[min, max_value]) => (min, max_value),
+                        _ => (0.0, 1.0),
+                    };
+
+                    *value = min + sample * (max_value - min) / max;
+                }
+
+                let rgb = color_space.to_rgb(&values)?;
+                pixels.extend(rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8));
+
+                pixels.push(match &alpha {
+                    Some(alpha) => (alpha[y * width + x] * 255.0).round() as u8,
+                    None if masked => 0,
+                    None => 255,
+                });
+            }
+        }
+
+        Ok(RgbaImage {
+            width: self.width,
+            height: self.height,
+            data: pixels,
+        })
+    }
+}
+
+impl<'a> ImageXObject<'a> {
+    /// Which pixels an image mask paints, from left to right and top to bottom
+    ///
+    /// Pixels whose sample is 0 are painted, unless the Decode array is
+    /// `[1 0]`, in which case those whose sample is 1 are painted instead.
+    pub fn decode_stencil(&self, resolver: &mut dyn Resolve<'a>) -> PdfResult<Vec<bool>> {
+        if !self.image_mask {
+            anyhow::bail!("image is not an image mask");
+        }
+
+        let data = decode_stream(&self.stream.stream, &self.stream.dict, resolver)?;
+
+        let inverted = matches!(self.decode.as_deref(), Some(&[min, max]) if min > max);
+
+        let width = self.width as usize;
+
+        // each row begins on a byte boundary
+        let row_len = width.div_ceil(8);
+
+        Ok((0..self.height as usize)
+            .flat_map(|y| (0..width).map(move |x| y * row_len * 8 + x))
+            .map(|bit_offset| (read_sample(&data, bit_offset, 1) == 1) == inverted)
+            .collect())
+    }
+
+    /// Paint an image mask with `color`, given as red, green and blue, leaving
+    /// the pixels it does not paint transparent
+    pub fn stencil_to_rgba(
+        &self,
+        color: [u8; 3],
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<RgbaImage> {
+        let [red, green, blue] = color;
+
+        let data = self
+            .decode_stencil(resolver)?
+            .into_iter()
+            .flat_map(|painted| [red, green, blue, if painted { 255 } else { 0 }])
+            .collect();
+
+        Ok(RgbaImage {
+            width: self.width,
+            height: self.height,
+            data,
+        })
+    }
+}
+
+/// The sample of `bits` bits which begins `bit_offset` bits into `data`,
+/// which is 0 beyond the end of the data
+pub(crate) fn read_sample(data: &[u8], bit_offset: usize, bits: u32) -> u32 {
+    let byte = |idx: usize| u32::from(data.get(idx).copied().unwrap_or(0));
+
+    match bits {
+        16 => byte(bit_offset / 8) << 8 | byte(bit_offset / 8 + 1),
+        _ => (byte(bit_offset / 8) >> (8 - bits - (bit_offset % 8) as u32)) & ((1 << bits) - 1),
+    }
+}
+
 #[derive(Debug, Clone, FromObj)]
 #[obj_type("XObject", subtype = "Image")]
 pub struct SoftMaskImage<'a> {
@@ -240,6 +436,46 @@ pub struct SoftMaskImage<'a> {
     stream: Stream<'a>,
 }
 
+impl<'a> SoftMaskImage<'a> {
+    /// Decode the samples of this soft mask into per-pixel opacities in the
+    /// range 0.0 to 1.0, resampled to the dimensions of the parent image
+    pub(crate) fn decode_alpha(
+        &self,
+        width: u32,
+        height: u32,
+        resolver: &mut dyn Resolve<'a>,
+    ) -> PdfResult<Vec<f32>> {
+        let data = decode_stream(&self.stream.stream, &self.stream.dict, resolver)?;
+
+        let bits = self.bits_per_component as u32;
+        let max = ((1_u32 << bits) - 1) as f32;
+        let (decode_min, decode_max) = (self.decode[0], self.decode[1]);
+
+        // each row begins on a byte boundary
+        let row_len = (self.width * bits).div_ceil(8);
+
+        let sample = |x: u32, y: u32| -> f32 {
+            let value = read_sample(&data, (y * row_len * 8 + x * bits) as usize, bits);
+
+            decode_min + value as f32 * (decode_max - decode_min) / max
+        };
+
+        let mut alpha = Vec::with_capacity(width as usize * height as usize);
+
+        for y in 0..height {
+            let mask_y = (y * self.height / height).min(self.height - 1);
+
+            for x in 0..width {
+                let mask_x = (x * self.width / width).min(self.width - 1);
+
+                alpha.push(sample(mask_x, mask_y).clamp(0.0, 1.0));
+            }
+        }
+
+        Ok(alpha)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AlternateImage<'a> {
     /// The image XObject for the alternate image
@@ -255,8 +491,7 @@ pub struct AlternateImage<'a> {
 
     /// An optional content group or optional content membership dictionary
     /// that facilitates the selection of which alternate image to use
-    // todo: optional content membership
-    oc: Option<OptionalContentGroup>,
+    oc: Option<OptionalContent>,
 }
 
 impl<'a> FromObj<'a> for AlternateImage<'a> {
@@ -314,3 +549,244 @@ impl<'a> FromObj<'a> for ImageMask<'a> {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{objects::Dictionary, version::PdfVersion, writer::PdfWriter};
+
+    fn image<'a>(data: Vec<u8>, entries: Vec<(&str, Object<'a>)>) -> ImageXObject<'a> {
+        let dict = Dictionary::new(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect::<HashMap<_, _>>(),
+        );
+
+        ImageXObject::from_obj(
+            Object::Stream(Stream::new(data, dict)),
+            &mut PdfWriter::new(PdfVersion::V1_7),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn decode_images_to_rgba() {
+        let mut resolver = PdfWriter::new(PdfVersion::V1_7);
+
+        // rows of three 1-bit samples, inverted by the Decode array
+        let gray = image(
+            vec![0b1010_0000, 0b0100_0000],
+            vec![
+                ("Width", Object::Integer(3)),
+                ("Height", Object::Integer(2)),
+                ("ColorSpace", Object::Name("DeviceGray".to_owned())),
+                ("BitsPerComponent", Object::Integer(1)),
+                (
+                    "Decode",
+                    Object::Array(vec![Object::Integer(1), Object::Integer(0)]),
+                ),
+            ],
+        );
+
+        assert_eq!(
+            gray.decode_to_rgba(&mut resolver).unwrap().data,
+            [
+                0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, //
+                255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255,
+            ]
+        );
+
+        // a palette of red and blue, with a 4-bit index
+        let indexed = image(
+            vec![0x10],
+            vec![
+                ("Width", Object::Integer(2)),
+                ("Height", Object::Integer(1)),
+                (
+                    "ColorSpace",
+                    Object::Array(vec![
+                        Object::Name("Indexed".to_owned()),
+                        Object::Name("DeviceRGB".to_owned()),
+                        Object::Integer(1),
+                        Object::String("\u{ff}\0\0\0\0\u{ff}".to_owned()),
+                    ]),
+                ),
+                ("BitsPerComponent", Object::Integer(4)),
+            ],
+        );
+
+        assert_eq!(
+            indexed.decode_to_rgba(&mut resolver).unwrap().data,
+            [0, 0, 255, 255, 255, 0, 0, 255]
+        );
+
+        let cmyk = image(
+            vec![0, 255, 255, 0, 0, 0, 0, 128],
+            vec![
+                ("Width", Object::Integer(2)),
+                ("Height", Object::Integer(1)),
+                ("ColorSpace", Object::Name("DeviceCMYK".to_owned())),
+                ("BitsPerComponent", Object::Integer(8)),
+            ],
+        );
+
+        assert_eq!(
+            cmyk.decode_to_rgba(&mut resolver).unwrap().data,
+            [255, 0, 0, 255, 127, 127, 127, 255]
+        );
+    }
+
+    #[test]
+    fn decode_inverted_cmyk() {
+        // CMYK samples as Adobe applications write them, with 255 being no
+        // ink, are inverted again by the Decode array
+        let cmyk = image(
+            vec![255, 0, 0, 255, 255, 255, 255, 0],
+            vec![
+                ("Width", Object::Integer(2)),
+                ("Height", Object::Integer(1)),
+                ("ColorSpace", Object::Name("DeviceCMYK".to_owned())),
+                ("BitsPerComponent", Object::Integer(8)),
+                (
+                    "Decode",
+                    Object::Array([1, 0, 1, 0, 1, 0, 1, 0].map(Object::Integer).to_vec()),
+                ),
+            ],
+        );
+
+        assert_eq!(
+            cmyk.decode_to_rgba(&mut PdfWriter::new(PdfVersion::V1_7))
+                .unwrap()
+                .data,
+            [255, 0, 0, 255, 0, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn decode_stencils() {
+        let mut resolver = PdfWriter::new(PdfVersion::V1_7);
+
+        let entries = |decode: Option<[i32; 2]>| {
+            let mut entries = vec![
+                ("Width", Object::Integer(3)),
+                ("Height", Object::Integer(2)),
+                ("ImageMask", Object::True),
+            ];
+
+            if let Some([min, max]) = decode {
+                entries.push((
+                    "Decode",
+                    Object::Array(vec![Object::Integer(min), Object::Integer(max)]),
+                ));
+            }
+
+            entries
+        };
+
+        let data = vec![0b1010_0000, 0b0110_0000];
+
+        let stencil = image(data.clone(), entries(None));
+        assert_eq!(
+            stencil.decode_stencil(&mut resolver).unwrap(),
+            [false, true, false, true, false, false]
+        );
+
+        let inverted = image(data, entries(Some([1, 0])));
+        assert_eq!(
+            inverted.decode_stencil(&mut resolver).unwrap(),
+            [true, false, true, false, true, true]
+        );
+
+        assert_eq!(
+            inverted
+                .stencil_to_rgba([255, 0, 0], &mut resolver)
+                .unwrap()
+                .data[..8],
+            [255, 0, 0, 255, 255, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn mask_color_key() {
+        let mut resolver = PdfWriter::new(PdfVersion::V1_7);
+
+        // pixels whose red is from 250 to 255 and green and blue are 0
+        let range = |min, max| [Object::Integer(min), Object::Integer(max)];
+
+        let image = image(
+            vec![255, 0, 0, 200, 0, 0, 252, 0, 0],
+            vec![
+                ("Width", Object::Integer(3)),
+                ("Height", Object::Integer(1)),
+                ("ColorSpace", Object::Name("DeviceRGB".to_owned())),
+                ("BitsPerComponent", Object::Integer(8)),
+                (
+                    "Mask",
+                    Object::Array([range(250, 255), range(0, 0), range(0, 0)].concat()),
+                ),
+            ],
+        );
+
+        let alpha = image
+            .decode_to_rgba(&mut resolver)
+            .unwrap()
+            .data
+            .chunks_exact(4)
+            .map(|pixel| pixel[3])
+            .collect::<Vec<u8>>();
+
+        assert_eq!(alpha, [0, 255, 0]);
+    }
+
+    #[test]
+    fn resample_soft_mask() {
+        let mut resolver = PdfWriter::new(PdfVersion::V1_7);
+
+        let soft_mask = |bits: i32, data: Vec<u8>, decode: Option<[i32; 2]>| {
+            let mut entries = vec![
+                ("Type".to_owned(), Object::Name("XObject".to_owned())),
+                ("Subtype".to_owned(), Object::Name("Image".to_owned())),
+                ("Width".to_owned(), Object::Integer(2)),
+                ("Height".to_owned(), Object::Integer(1)),
+                (
+                    "ColorSpace".to_owned(),
+                    Object::Name("DeviceGray".to_owned()),
+                ),
+                ("BitsPerComponent".to_owned(), Object::Integer(bits)),
+            ];
+
+            if let Some([min, max]) = decode {
+                entries.push((
+                    "Decode".to_owned(),
+                    Object::Array(vec![Object::Integer(min), Object::Integer(max)]),
+                ));
+            }
+
+            let dict = Dictionary::new(entries.into_iter().collect::<HashMap<_, _>>());
+
+            SoftMaskImage::from_obj(
+                Object::Stream(Stream::new(data, dict)),
+                &mut PdfWriter::new(PdfVersion::V1_7),
+            )
+            .unwrap()
+        };
+
+        // each sample of the mask covers two columns of a 4x2 image
+        let mask = soft_mask(8, vec![0, 255], None);
+
+        assert_eq!(
+            mask.decode_alpha(4, 2, &mut resolver).unwrap(),
+            [0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0]
+        );
+
+        let inverted = soft_mask(4, vec![0x0f], Some([1, 0]));
+
+        assert_eq!(
+            inverted.decode_alpha(2, 1, &mut resolver).unwrap(),
+            [1.0, 0.0]
+        );
+    }
+}
@@ -1,6 +1,12 @@
 use crate::{error::PdfResult, objects::Object, FromObj, Resolve};
 
-pub use self::{form::FormXObject, image::ImageXObject, postscript::PostScriptXObject};
+pub use self::{
+    form::FormXObject,
+    image::{ImageXObject, RgbaImage},
+    postscript::PostScriptXObject,
+};
+
+pub(crate) use self::image::read_sample;
 
 mod form;
 mod image;
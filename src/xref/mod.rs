@@ -1,6 +1,6 @@
-use std::{collections::HashMap, convert::TryFrom};
+use std::{collections::HashMap, convert::TryFrom, ops::Range};
 
-use crate::{PdfResult, Reference};
+use crate::{Parser, PdfResult, Reference};
 
 pub(crate) use parser::{TrailerOrOffset, XrefParser};
 
@@ -23,6 +23,19 @@ pub enum ByteOffset {
     ObjectStream { byte_offset: usize, index: usize },
 }
 
+/// The bytes occupied by an indirect object, from the start of its object
+/// number through the end of its `endobj` keyword
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectSpan {
+    pub range: Range<usize>,
+
+    /// The object stream containing the object, if it is compressed
+    ///
+    /// Objects within an object stream do not occupy their own bytes of the
+    /// file, so `range` is instead that of the object stream.
+    pub object_stream: Option<Reference>,
+}
+
 impl Xref {
     pub fn get_offset(&self, reference: Reference) -> PdfResult<Option<ByteOffset>> {
         Ok(
@@ -54,6 +67,25 @@ impl Xref {
         )
     }
 
+    /// The reference to each object in use, along with its generation number
+    pub(crate) fn references(&self) -> impl Iterator<Item = Reference> + '_ {
+        self.objects
+            .iter()
+            .filter_map(|(&object_number, entry)| match entry {
+                XrefEntry::InUse {
+                    generation_number, ..
+                } => Some(Reference {
+                    object_number,
+                    generation: *generation_number as usize,
+                }),
+                XrefEntry::Compressed { .. } => Some(Reference {
+                    object_number,
+                    generation: 0,
+                }),
+                XrefEntry::Free { .. } | XrefEntry::Null => None,
+            })
+    }
+
     pub fn merge_with_previous(&mut self, previous: Xref) {
         for (key, value) in previous.objects.into_iter() {
             self.objects.entry(key).or_insert(value);
@@ -61,7 +93,7 @@ impl Xref {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) enum XrefEntry {
     InUse {
         byte_offset: usize,
@@ -82,3 +114,139 @@ pub(crate) enum XrefEntry {
     },
     Null,
 }
+
+impl<'a> Parser<'a> {
+    /// The bytes occupied in the original file by the object `reference`, or
+    /// `None` if it is not in use
+    ///
+    /// Extents are found by lexing each object, so they are exact even where
+    /// objects are separated by comments or padding.
+    pub fn object_span(&mut self, reference: Reference) -> PdfResult<Option<ObjectSpan>> {
+        let span = match self.xref.get_offset(reference)? {
+            Some(ByteOffset::MainFile(byte_offset)) => ObjectSpan {
+                range: self.lexer.object_span_at(byte_offset)?,
+                object_stream: None,
+            },
+            Some(ByteOffset::ObjectStream { byte_offset, .. }) => {
+                let object_stream = match self.xref.objects.get(&reference.object_number) {
+                    Some(&XrefEntry::Compressed { object_number, .. }) => Reference {
+                        object_number: usize::try_from(object_number)?,
+                        generation: 0,
+                    },
+                    _ => unreachable!(),
+                };
+
+                ObjectSpan {
+                    range: self.lexer.object_span_at(byte_offset)?,
+                    object_stream: Some(object_stream),
+                }
+            }
+            None => return Ok(None),
+        };
+
+        Ok(Some(span))
+    }
+
+    /// The bytes occupied in the original file by every object in use, ordered
+    /// by object number
+    ///
+    /// The spans of a document being written are given by
+    /// `PdfWriter::to_bytes_with_spans`.
+    pub fn object_spans(&mut self) -> PdfResult<Vec<(Reference, ObjectSpan)>> {
+        let mut references = self.xref.references().collect::<Vec<Reference>>();
+        references.sort_by_key(|reference| reference.object_number);
+
+        let mut spans = Vec::with_capacity(references.len());
+
+        for reference in references {
+            if let Some(span) = self.object_span(reference)? {
+                spans.push((reference, span));
+            }
+        }
+
+        Ok(spans)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test::TestFile, ParseOptions};
+
+    #[test]
+    fn object_spans() {
+        let mut file = TestFile::new();
+        file.object(1, b"<< /Type /Catalog /Pages 2 0 R >>");
+
+        // comments and padding between objects are not part of either
+        file.bytes.extend_from_slice(b"% padding\n\n");
+        file.object(2, b"<< /Type /Pages /Kids [] /Count 0 >>");
+        file.bytes.extend_from_slice(b"   ");
+
+        let compressed_objects = b"4 0 (compressed)";
+        file.stream(
+            3,
+            &format!(
+                "<< /Type /ObjStm /N 1 /First 4 /Length {} >>",
+                compressed_objects.len()
+            ),
+            compressed_objects,
+        );
+
+        let span = |file: &TestFile, object_number: usize, body: &str| {
+            let start = file.offsets[&object_number];
+            start..start + format!("{} 0 obj\n{}\nendobj", object_number, body).len()
+        };
+
+        let catalog = span(&file, 1, "<< /Type /Catalog /Pages 2 0 R >>");
+        let pages = span(&file, 2, "<< /Type /Pages /Kids [] /Count 0 >>");
+
+        let bytes = file.with_xref_stream(&[(4, 3, 0)]);
+        let object_stream = {
+            let start = bytes.windows(7).position(|w| w == b"3 0 obj").unwrap();
+            let end = start
+                + bytes[start..]
+                    .windows(6)
+                    .position(|w| w == b"endobj")
+                    .unwrap();
+
+            start..end + 6
+        };
+
+        let mut parser = Parser::from_bytes(bytes, ParseOptions::default()).unwrap();
+
+        let reference = |object_number| Reference {
+            object_number,
+            generation: 0,
+        };
+
+        assert_eq!(
+            parser.object_span(reference(2)).unwrap(),
+            Some(ObjectSpan {
+                range: pages,
+                object_stream: None,
+            })
+        );
+
+        assert_eq!(
+            parser.object_span(reference(4)).unwrap(),
+            Some(ObjectSpan {
+                range: object_stream,
+                object_stream: Some(reference(3)),
+            })
+        );
+
+        assert_eq!(parser.object_span(reference(50)).unwrap(), None);
+
+        let spans = parser.object_spans().unwrap();
+
+        assert_eq!(
+            spans
+                .iter()
+                .map(|(reference, _)| reference.object_number)
+                .collect::<Vec<_>>(),
+            [1, 2, 3, 4, 100, 101, 102]
+        );
+        assert_eq!(spans[0].1.range, catalog);
+    }
+}
@@ -43,13 +43,31 @@ impl<'a> LexObject<'a> for XrefParser {
     }
 }
 
+/// The cross-reference table is not known while it is being parsed, so objects
+/// are found by searching the file for their headers instead.
+///
+/// The values of a cross-reference stream dictionary shall be direct, but some
+/// producers nevertheless write indirect references for entries such as Length
+/// and W.
 impl<'a> Resolve<'a> for XrefParser {
     fn lex_object_from_reference(&mut self, reference: Reference) -> PdfResult<Object<'a>> {
-        Ok(Object::Reference(reference))
+        let offset = match self.find_object(reference) {
+            Some(offset) => offset,
+            None => return Ok(Object::Null),
+        };
+
+        let init_pos = self.pos;
+
+        self.pos = offset;
+        let obj = self.read_obj_prelude().and_then(|_| self.lex_object());
+        self.pos = init_pos;
+
+        obj
     }
 
+    /// Objects in object streams have no header to search for, so every
+    /// reference is assumed to exist until the table has been parsed
     fn reference_exists(&mut self, _reference: Reference) -> PdfResult<bool> {
-        // todo: wrong! but i forget how xref parser works
         Ok(true)
     }
 }
@@ -72,6 +90,26 @@ impl<'a> XrefParser {
         Self { file, pos: 0 }
     }
 
+    /// The offset of the last header of the given object in the file, which is
+    /// its definition in the most recent update
+    fn find_object(&self, reference: Reference) -> Option<usize> {
+        let header = format!("{} {} obj", reference.object_number, reference.generation);
+
+        self.file
+            .windows(header.len())
+            .enumerate()
+            .rev()
+            .find(|&(idx, window)| {
+                window == header.as_bytes()
+                    && (idx == 0 || !self.file[idx - 1].is_ascii_digit())
+                    && self
+                        .file
+                        .get(idx + header.len())
+                        .map_or(true, |b| !b.is_ascii_alphanumeric())
+            })
+            .map(|(idx, _)| idx)
+    }
+
     /// We read backwards in 1024 byte chunks, looking for `"startxref"`
     pub fn read_xref(&mut self) -> PdfResult<XrefAndTrailer<'a>> {
         let mut pos = self.file.len().saturating_sub(1);
@@ -83,9 +121,10 @@ impl<'a> XrefParser {
 
             let next_pos = pos.saturating_sub(KILOBYTE - START_XREF_SIGNATURE.len());
             // todo: use rabin-karp or something similar
+            // the last keyword in the file points to the most recent update
             if let Some(start) = self.file[next_pos..=pos]
                 .windows(START_XREF_SIGNATURE.len())
-                .position(|window| window == START_XREF_SIGNATURE)
+                .rposition(|window| window == START_XREF_SIGNATURE)
             {
                 break start + next_pos;
             }
@@ -18,7 +18,8 @@ pub struct XrefStream<'a> {
 }
 
 #[derive(Debug)]
-/// Values in this dictionary must *not* be indirect references
+/// Values in this dictionary shall not be indirect references, though some
+/// producers write them anyway
 pub struct XrefStreamDict<'a> {
     pub(crate) stream_dict: StreamDict<'a>,
     pub(crate) trailer: Trailer<'a>,